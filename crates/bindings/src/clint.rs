@@ -0,0 +1,92 @@
+//! CLINT Python binding.
+//!
+//! Holds a `Py<PyCpu>` back-reference like `Plic`, so timer and IPI registers
+//! are read and written on the live device.
+
+use pyo3::exceptions::{PyIndexError, PyRuntimeError};
+use pyo3::prelude::*;
+use rvsim_core::soc::devices::Clint;
+
+use crate::cpu::PyCpu;
+
+/// Per-hart timer and software interrupt registers returned by ``cpu.clint``.
+///
+/// ``clint.set_mtimecmp(3, 1000)`` arms hart 3's timer; ``clint.mip(3)`` shows
+/// the interrupts that hart saw pending as of the last cycle.
+#[pyclass(name = "Clint")]
+pub struct PyClint {
+    pub cpu: Py<PyCpu>,
+}
+
+impl PyClint {
+    fn with_device<R>(&self, py: Python<'_>, f: impl FnOnce(&mut Clint) -> R) -> PyResult<R> {
+        let mut cpu = self.cpu.borrow_mut(py);
+        cpu.inner
+            .cpu
+            .bus
+            .bus
+            .clint_mut()
+            .map(f)
+            .ok_or_else(|| PyRuntimeError::new_err("no CLINT on the bus"))
+    }
+
+    /// Like `with_device`, but raises ``IndexError`` for a nonexistent hart.
+    fn with_hart<R>(
+        &self,
+        py: Python<'_>,
+        hart: usize,
+        f: impl FnOnce(&mut Clint) -> R,
+    ) -> PyResult<R> {
+        self.with_device(py, |clint| (hart < clint.num_harts()).then(|| f(clint)))?
+            .ok_or_else(|| PyIndexError::new_err(format!("hart {hart} does not exist")))
+    }
+}
+
+#[pymethods]
+impl PyClint {
+    /// Number of harts served.
+    #[getter]
+    fn num_harts(&self, py: Python<'_>) -> PyResult<usize> {
+        self.with_device(py, |clint| clint.num_harts())
+    }
+
+    /// Shared machine time counter.
+    #[getter]
+    fn mtime(&self, py: Python<'_>) -> PyResult<u64> {
+        self.with_device(py, |clint| clint.mtime())
+    }
+
+    /// Timer compare value of ``hart``.
+    fn mtimecmp(&self, py: Python<'_>, hart: usize) -> PyResult<u64> {
+        self.with_hart(py, hart, |clint| clint.mtimecmp(hart).unwrap_or(u64::MAX))
+    }
+
+    /// Sets the timer compare value of ``hart``.
+    fn set_mtimecmp(&self, py: Python<'_>, hart: usize, value: u64) -> PyResult<()> {
+        self.with_hart(py, hart, |clint| clint.set_mtimecmp(hart, value))
+    }
+
+    /// Whether ``hart``'s software interrupt (IPI) is pending.
+    fn msip(&self, py: Python<'_>, hart: usize) -> PyResult<bool> {
+        self.with_hart(py, hart, |clint| clint.hart_msip(hart))
+    }
+
+    /// Raises or clears ``hart``'s software interrupt.
+    fn set_msip(&self, py: Python<'_>, hart: usize, pending: bool) -> PyResult<()> {
+        self.with_hart(py, hart, |clint| clint.set_msip(hart, pending))
+    }
+
+    /// ``mip`` CSR of ``hart`` as of its last cycle.
+    fn mip(&self, py: Python<'_>, hart: usize) -> PyResult<u64> {
+        let cpu = self.cpu.borrow(py);
+        cpu.inner
+            .hart(hart)
+            .map(|h| h.csrs.mip)
+            .ok_or_else(|| PyIndexError::new_err(format!("hart {hart} does not exist")))
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        let (harts, mtime) = self.with_device(py, |clint| (clint.num_harts(), clint.mtime()))?;
+        Ok(format!("Clint(num_harts={harts}, mtime={mtime})"))
+    }
+}
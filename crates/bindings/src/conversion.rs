@@ -10,7 +10,8 @@ use serde_json;
 /// Converts a Python dict to a simulator `Config`.
 ///
 /// The dict is serialized to JSON and then deserialized into `Config`. Keys must match
-/// the Rust config structure (e.g., `general`, `system`, `memory`, `cache`, `pipeline`).
+/// the Rust config structure (e.g., `general`, `system`, `memory`, `cache`, `pipeline`,
+/// and the optional `initial_state`, which `Cpu` applies once the program is loaded).
 ///
 /// # Arguments
 ///
@@ -29,6 +30,17 @@ pub fn py_dict_to_config(py: Python<'_>, dict: &Bound<'_, PyAny>) -> PyResult<Co
     let config: Config = serde_json::from_str(&json_str).map_err(|e| {
         PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Invalid config: {e}"))
     })?;
+    config.validate_memory_map().map_err(|e| {
+        PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Invalid config: {e}"))
+    })?;
+    config.validate_devices().map_err(|e| {
+        PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Invalid config: {e}"))
+    })?;
+    if let Some(addr) = config.initial_state.csrs.keys().find(|&&addr| addr > 0xfff) {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "Invalid config: initial_state CSR address {addr:#x} out of range (0x000–0xfff)"
+        )));
+    }
 
     Ok(config)
 }
@@ -4,20 +4,47 @@
 //! and methods that users interact with live here — nothing leaks through a Python
 //! wrapper layer.
 
+use crate::clint::PyClint;
 use crate::conversion::py_dict_to_config;
+use crate::device::{DeviceError, PyCallbackDevice, PyDeviceHandle};
+use crate::disk::PyVirtioBlock;
+use crate::dma::PyDma;
+use crate::error::to_py_err;
 use crate::instruction::PyInstruction;
+use crate::mmio_trace::PyMmioTraceSink;
+use crate::net::PyVirtioNet;
+use crate::plic::PyPlic;
+use crate::run_status::PyRunStatus;
 use crate::snapshot::PyPipelineSnapshot;
 use crate::stats::PyStats;
+use crate::uart::PyUart;
 use crate::views::{Csrs, Memory, Registers, VirtualMemory};
-use pyo3::exceptions::PyRuntimeError;
+use pyo3::exceptions::{PyIndexError, PyRuntimeError, PyValueError};
 use pyo3::prelude::*;
-use rvsim_core::Simulator;
+use pyo3::types::PyDict;
+use rvsim_core::common::{CsrAddr, PhysAddr, RegIdx, SimError};
 use rvsim_core::core::arch::mode::PrivilegeMode;
+use rvsim_core::core::units::cache::CacheSim;
 use rvsim_core::sim::loader;
+use rvsim_core::sim::simulator::SystemEvent;
+use rvsim_core::soc::mmio_trace::MmioTraceFile;
+use rvsim_core::{Config, Simulator};
 use std::io::Write;
-use std::io::{BufReader, BufWriter, Read};
 
-// ── Formatting helper ────────────────────────────────────────────────────────
+/// Instructions `run_functional` and `run_detailed` run between checks for
+/// Python signals.
+const INST_CHUNK: u64 = 10_000;
+
+// ── Formatting helpers ───────────────────────────────────────────────────────
+
+/// Single-letter name of a privilege mode, as used throughout the Python API.
+const fn privilege_letter(mode: PrivilegeMode) -> &'static str {
+    match mode {
+        PrivilegeMode::Machine => "M",
+        PrivilegeMode::Supervisor => "S",
+        PrivilegeMode::User => "U",
+    }
+}
 
 fn fmt_commas(n: u64) -> String {
     let s = n.to_string();
@@ -39,17 +66,63 @@ fn fmt_commas(n: u64) -> String {
 #[pyclass(name = "Cpu")]
 pub struct PyCpu {
     pub inner: Simulator,
+    /// Python callable invoked as ``fn(pc)`` after every simulated cycle.
+    step_callback: Option<Py<PyAny>>,
+    /// Exit code of the most recent run that ended with the program exiting.
+    exit_code: Option<u64>,
+    /// Kernel image loaded at construction, reloaded on every reboot.
+    kernel: Option<KernelImage>,
+    /// Exception raised by a Python device handler during the current cycle.
+    device_error: DeviceError,
+}
+
+/// Kernel and device tree a `Cpu` was booted with.
+struct KernelImage {
+    config: Config,
+    kernel_path: String,
+    dtb_path: Option<String>,
+}
+
+impl From<Simulator> for PyCpu {
+    fn from(inner: Simulator) -> Self {
+        Self {
+            inner,
+            step_callback: None,
+            exit_code: None,
+            kernel: None,
+            device_error: DeviceError::default(),
+        }
+    }
 }
 
 // ── Private Rust helpers (not exposed to Python) ─────────────────────────────
 
 impl PyCpu {
-    pub(crate) const fn privilege_str(&self) -> &'static str {
-        match self.inner.cpu.privilege {
-            PrivilegeMode::Machine => "M",
-            PrivilegeMode::Supervisor => "S",
-            PrivilegeMode::User => "U",
+    /// Resolves ``"l1i"``, ``"l1d"``, ``"l2"`` or ``"l3"`` to that cache and the
+    /// latency charged when it writes a dirty victim back to the next level
+    /// (zero below L3, whose memory traffic the memory controller times).
+    fn cache_level(&mut self, level: &str) -> PyResult<(&mut CacheSim, u64)> {
+        let cpu = &mut self.inner.cpu;
+        let below = |c: &CacheSim| if c.enabled { c.latency } else { 0 };
+        let (cache, next_latency) = match level {
+            "l1i" => (&mut cpu.l1_i_cache, below(&cpu.l2_cache)),
+            "l1d" => (&mut cpu.l1_d_cache, below(&cpu.l2_cache)),
+            "l2" => (&mut cpu.l2_cache, below(&cpu.l3_cache)),
+            "l3" => (&mut cpu.l3_cache, 0),
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "unknown cache level {level:?} (expected \"l1i\", \"l1d\", \"l2\" or \"l3\")"
+                )));
+            }
+        };
+        if !cache.enabled {
+            return Err(PyValueError::new_err(format!("cache {level} is disabled")));
         }
+        Ok((cache, next_latency))
+    }
+
+    pub(crate) const fn privilege_str(&self) -> &'static str {
+        privilege_letter(self.inner.cpu.privilege)
     }
 
     pub(crate) fn read_csr_by_name(&self, name: &str) -> Option<u64> {
@@ -85,36 +158,206 @@ impl PyCpu {
         }
     }
 
+    /// Copies the statistics, sampling the host memory backing RAM.
+    fn stats_snapshot(&self) -> PyStats {
+        let mut stats = self.inner.cpu.stats.clone();
+        stats.ram_resident_bytes = self.inner.cpu.ram.resident_bytes() as u64;
+        PyStats::from(stats)
+    }
+
+    /// Validates a raw CSR number from Python (12-bit address space).
+    fn csr_addr(addr: i64) -> PyResult<CsrAddr> {
+        u16::try_from(addr).ok().filter(|&a| a <= 0xFFF).map(CsrAddr::new).ok_or_else(|| {
+            PyValueError::new_err(format!("CSR address {addr:#x} out of range (0x000–0xfff)"))
+        })
+    }
+
+    /// Validates a register index from Python (0–31).
+    pub(crate) fn reg_idx(idx: usize) -> PyResult<RegIdx> {
+        u8::try_from(idx).ok().filter(|&i| i < 32).map(RegIdx::new).ok_or_else(|| {
+            PyIndexError::new_err(format!("register index {idx} out of range (0–31)"))
+        })
+    }
+
+    /// Calls the step callback, if any, with the current PC. Returns `true`
+    /// if the callback asked to halt by returning a truthy value.
+    fn call_step_callback(&self, py: Python<'_>, cb: Option<&Py<PyAny>>) -> PyResult<bool> {
+        match cb {
+            Some(cb) => cb.call1(py, (self.inner.cpu.pc,))?.is_truthy(py),
+            None => Ok(false),
+        }
+    }
+
+    /// Advances one cycle, then raises the first exception a Python device
+    /// handler threw during it.
+    fn tick_once(&mut self) -> PyResult<()> {
+        let result = self.inner.tick();
+        self.check_device_error(result)
+    }
+
+    /// Raises the first exception a Python device handler threw since the
+    /// last check, or else converts the simulator's `result`.
+    fn check_device_error<T>(&self, result: Result<T, SimError>) -> PyResult<T> {
+        let handler_err =
+            self.device_error.lock().unwrap_or_else(std::sync::PoisonError::into_inner).take();
+        if let Some(err) = handler_err {
+            return Err(err);
+        }
+        result.map_err(|e| to_py_err(&e))
+    }
+
+    /// Runs `n` instructions functionally or in detail, in slices of
+    /// [`INST_CHUNK`] with Python signals checked between them.
+    fn run_insts(&mut self, py: Python<'_>, n: u64, detailed: bool) -> PyResult<PyRunStatus> {
+        let mut remaining = n;
+        let status = loop {
+            if remaining == 0 {
+                break PyRunStatus::InstLimit;
+            }
+            py.check_signals()?;
+            let chunk = remaining.min(INST_CHUNK);
+            let result = if detailed {
+                self.inner.run_detailed(chunk)
+            } else {
+                self.inner.run_functional(chunk)
+            };
+            let retired = self.check_device_error(result)?;
+            remaining = remaining.saturating_sub(retired);
+            if let Some(code) = self.take_exit_or_reboot()? {
+                self.exit_code = Some(code);
+                break PyRunStatus::Exited;
+            }
+        };
+        let _ = std::io::stdout().flush();
+        Ok(status)
+    }
+
+    /// Takes the power event raised by the last tick. Returns the exit code
+    /// if the program exited; on a reboot, loads the kernel and DTB again.
+    fn take_exit_or_reboot(&mut self) -> PyResult<Option<u64>> {
+        match self.inner.take_system_event() {
+            Some(SystemEvent::Exit(code)) => Ok(Some(code)),
+            Some(SystemEvent::Reboot(_)) => {
+                if let Some(k) = &self.kernel {
+                    loader::setup_kernel_load(
+                        &mut self.inner.cpu,
+                        &k.config,
+                        "",
+                        k.dtb_path.clone(),
+                        Some(k.kernel_path.clone()),
+                    )
+                    .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+                    self.inner.sync_arch_regs();
+                }
+                Ok(None)
+            }
+            None => Ok(None),
+        }
+    }
+
     /// Core run loop. Runs for up to `limit` cycles (or forever if `None`),
     /// checking Python signals every 10 000 cycles.
-    fn run_inner(&mut self, py: Python<'_>, limit: Option<u64>) -> PyResult<Option<u64>> {
+    ///
+    /// Also stops when an armed breakpoint is reached, and, with
+    /// `stop_on_trap`, when the primary hart takes a trap.
+    fn run_inner(
+        &mut self,
+        py: Python<'_>,
+        limit: Option<u64>,
+        stop_on_trap: bool,
+    ) -> PyResult<PyRunStatus> {
+        let cb = self.step_callback.as_ref().map(|cb| cb.clone_ref(py));
         let start = self.inner.cpu.stats.cycles;
-        loop {
+        let status = loop {
             if let Some(max) = limit
                 && self.inner.cpu.stats.cycles.saturating_sub(start) >= max
             {
-                let _ = std::io::stdout().flush();
-                return Ok(None);
+                break PyRunStatus::CycleLimit;
             }
             if self.inner.cpu.stats.cycles.is_multiple_of(10_000) {
                 py.check_signals()?;
                 let _ = std::io::stdout().flush();
             }
-            match self.inner.tick() {
-                Ok(()) => {
-                    if let Some(code) = self.inner.take_exit() {
-                        let _ = std::io::stdout().flush();
-                        return Ok(Some(code));
-                    }
-                }
-                Err(e) => return Err(PyRuntimeError::new_err(e.to_string())),
+            let traps_before = self.inner.cpu.stats.traps_taken;
+            self.tick_once()?;
+            if let Some(code) = self.take_exit_or_reboot()? {
+                self.exit_code = Some(code);
+                break PyRunStatus::Exited;
             }
-        }
+            if self.inner.take_breakpoint_hit() {
+                break PyRunStatus::PcReached;
+            }
+            if stop_on_trap && self.inner.cpu.stats.traps_taken != traps_before {
+                break PyRunStatus::Trap;
+            }
+            if self.call_step_callback(py, cb.as_ref())? {
+                break PyRunStatus::Halted;
+            }
+        };
+        let _ = std::io::stdout().flush();
+        Ok(status)
     }
 
     /// Run for exactly `cycles` cycles. Used by `run_until` and `sample`.
-    fn run_for_cycles(&mut self, py: Python<'_>, cycles: u64) -> PyResult<Option<u64>> {
-        self.run_inner(py, Some(cycles))
+    fn run_for_cycles(&mut self, py: Python<'_>, cycles: u64) -> PyResult<PyRunStatus> {
+        self.run_inner(py, Some(cycles), false)
+    }
+
+    /// The exit code if `status` reports that the program exited.
+    fn exit_code_for(&self, status: PyRunStatus) -> Option<u64> {
+        if status == PyRunStatus::Exited { self.exit_code } else { None }
+    }
+
+    /// `run_until` loop: runs `chunk`-cycle slices, checking the Python-side
+    /// conditions between slices. The breakpoint is armed by the caller.
+    fn run_until_inner(
+        slf: &Py<Self>,
+        py: Python<'_>,
+        limit: Option<u64>,
+        predicate: Option<Py<PyAny>>,
+        privilege: Option<String>,
+        stop_on_trap: bool,
+        chunk: u64,
+    ) -> PyResult<PyRunStatus> {
+        // Without Python-side conditions there is nothing to check between slices.
+        let chunk =
+            if predicate.is_none() && privilege.is_none() { u64::MAX } else { chunk.max(1) };
+        let mut cycles_run = 0u64;
+
+        loop {
+            let c = if let Some(max) = limit {
+                let remaining = max.saturating_sub(cycles_run);
+                if remaining == 0 {
+                    return Ok(PyRunStatus::CycleLimit);
+                }
+                chunk.min(remaining)
+            } else {
+                chunk
+            };
+
+            // Run a slice, release borrow before checking predicates.
+            let status = slf.borrow_mut(py).run_inner(py, Some(c), stop_on_trap)?;
+            cycles_run = cycles_run.saturating_add(c);
+
+            if status != PyRunStatus::CycleLimit {
+                return Ok(status);
+            }
+
+            // Check the privilege condition with an immutable borrow.
+            if privilege.as_deref().is_some_and(|p| slf.borrow(py).privilege_str() == p) {
+                return Ok(PyRunStatus::Condition);
+            }
+
+            // Call Python predicate, passing the Cpu object itself.
+            if let Some(ref pred) = predicate {
+                let result = pred.call1(py, (slf.clone_ref(py),))?;
+                if result.extract::<bool>(py)? {
+                    return Ok(PyRunStatus::Condition);
+                }
+            }
+
+            py.check_signals()?;
+        }
     }
 
     /// Run with stderr progress reporting every `progress` cycles.
@@ -138,13 +381,13 @@ impl PyCpu {
                 progress
             };
 
-            let exit = self.run_for_cycles(py, chunk)?;
+            let status = self.run_for_cycles(py, chunk)?;
             cycles_run += chunk;
 
-            if let Some(code) = exit {
+            if status != PyRunStatus::CycleLimit {
                 eprint!("\r\x1b[2K");
                 let _ = std::io::stderr().flush();
-                return Ok(Some(code));
+                return Ok(self.exit_code_for(status));
             }
 
             let s = &self.inner.cpu.stats;
@@ -166,10 +409,15 @@ impl PyCpu {
     ///
     /// This is the sole entry point for creating a Cpu. All system setup (ELF loading,
     /// HTIF registration, kernel loading) happens inside Rust — nothing leaks to Python.
+    /// Raises ``ValueError`` if the config is invalid, including devices whose address
+    /// ranges overlap, and ``RuntimeError`` if ``elf_data`` is an ELF that cannot be
+    /// loaded (not 64-bit RISC-V, or a segment outside the memory map).
     ///
     /// Args:
     ///     `config_dict`: The nested config dict (from ``Config.to_dict()``).
-    ///     `elf_data`: Raw bytes of an ELF binary (bare-metal mode). Optional.
+    ///     `elf_data`: Raw bytes of a bare-metal program: a 64-bit RISC-V ELF,
+    ///         loaded by segment, or a flat binary, loaded at the start of RAM.
+    ///         Optional.
     ///     `kernel_path`: Path to a kernel image (kernel mode). Optional.
     ///     `dtb_path`: Path to a DTB file (kernel mode). Optional.
     ///     `disk_path`: Path to a disk image. Optional.
@@ -184,52 +432,73 @@ impl PyCpu {
         disk_path: Option<String>,
     ) -> PyResult<Self> {
         let config = py_dict_to_config(py, config_dict)?;
+        let initial_state = config.initial_state.clone();
         let disk = disk_path.unwrap_or_default();
-        let mut system = rvsim_core::soc::System::new(&config, &disk);
+        let mut system =
+            rvsim_core::soc::System::try_new(&config, &disk).map_err(PyValueError::new_err)?;
 
-        // ELF loading (bare-metal mode)
-        let mut elf_entry: Option<u64> = None;
+        // Program loading (bare-metal mode): an ELF is loaded by segment, any
+        // other image is copied flat to the start of RAM.
+        let mut elf = None;
+        let mut flat_end = None;
         let mut tohost_addr: Option<u64> = None;
         if let Some(data) = elf_data {
-            if let Some(result) = loader::try_load_elf(&data, &mut system.bus) {
-                elf_entry = Some(result.entry);
+            if let Some(result) =
+                loader::try_load_elf(&data, &mut system.bus).map_err(|e| to_py_err(&e))?
+            {
                 if let Some(tohost) = result.tohost_addr {
-                    system.add_htif(tohost);
+                    system.add_htif(tohost, &config);
                     tohost_addr = Some(tohost);
                 }
+                elf = Some(result);
             } else {
-                return Err(PyRuntimeError::new_err(
-                    "Not a valid ELF file. Only ELF binaries are supported.",
-                ));
+                system.bus.load_binary_at(&data, PhysAddr::new(config.general.start_pc));
+                flat_end = Some(config.general.start_pc + data.len() as u64);
             }
         }
 
         let mut sim = Simulator::new(system, &config);
 
-        // Apply ELF entry point
-        if let Some(entry) = elf_entry {
-            sim.cpu.pc = entry;
+        // Apply ELF entry point; with syscall emulation also build the
+        // process stack and program break.
+        if let Some(result) = elf {
+            sim.cpu.pc = result.entry;
+            sim.cpu.start_process(result.entry, result.image_end, result.phdrs);
+            sim.symbols = result.symbols;
+        } else if let Some(end) = flat_end {
+            sim.cpu.start_process(config.general.start_pc, end, None);
         }
 
         // HTIF setup (bare-metal with tohost symbol)
-        if let Some(tohost) = tohost_addr {
+        if tohost_addr.is_some() {
             sim.cpu.direct_mode = false;
             sim.cpu.privilege = PrivilegeMode::Machine;
-            sim.cpu.htif_range = Some((tohost, tohost + 16));
         }
 
         // Kernel loading
-        if let Some(kpath) = kernel_path {
-            loader::setup_kernel_load(&mut sim.cpu, &config, "", dtb_path, Some(kpath))
-                .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        let kernel = if let Some(kpath) = kernel_path {
+            loader::setup_kernel_load(
+                &mut sim.cpu,
+                &config,
+                "",
+                dtb_path.clone(),
+                Some(kpath.clone()),
+            )
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
             sim.cpu.direct_mode = false;
-        }
+            Some(KernelImage { config, kernel_path: kpath, dtb_path })
+        } else {
+            None
+        };
+
+        // Configured start privilege and CSRs override whatever the loaders set.
+        sim.cpu.apply_initial_state(&initial_state);
 
         // Sync architectural registers (a0/a1/a2 from loader, sp from direct_mode)
         // into the O3 PRF. Must happen after all register initialization.
         sim.sync_arch_regs();
 
-        Ok(Self { inner: sim })
+        Ok(Self { kernel, ..Self::from(sim) })
     }
 
     // ── Properties ───────────────────────────────────────────────────────────
@@ -245,30 +514,97 @@ impl PyCpu {
         self.inner.cpu.pc = value;
     }
 
+    /// Number of boots so far, counting the first (read-only). Each
+    /// ``SysCon`` reboot restarts the harts and reloads the kernel.
+    #[getter]
+    const fn boots(&self) -> u64 {
+        self.inner.boots()
+    }
+
     /// Current privilege level: ``"M"``, ``"S"``, or ``"U"`` (read-only).
     #[getter]
     const fn privilege(&self) -> &'static str {
         self.privilege_str()
     }
 
-    /// Whether instruction tracing is enabled (read/write).
-    #[getter]
-    const fn trace(&self) -> bool {
-        self.inner.cpu.trace
+    /// Whether instruction tracing is enabled (read/write). With a trace
+    /// region configured, output is only produced inside it.
+    #[getter(trace)]
+    const fn trace_requested(&self) -> bool {
+        self.inner.cpu.trace_requested
     }
 
     #[setter]
     const fn set_trace(&mut self, value: bool) {
-        self.inner.cpu.trace = value;
+        self.inner.cpu.set_trace(value);
     }
 
     /// Performance statistics as a dict (read-only).
     #[getter]
     fn stats(&self, py: Python<'_>) -> PyResult<PyObject> {
-        let s = PyStats::from(self.inner.cpu.stats.clone());
+        let s = self.stats_snapshot();
         Ok(s.to_dict(py)?.into_bound(py).into_any().unbind())
     }
 
+    /// Non-retiring cycles charged to each stall cause, e.g.
+    /// ``cpu.stall_breakdown()["dcache_miss"]``.
+    fn stall_breakdown(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let s = self.stats_snapshot();
+        Ok(s.stall_breakdown(py)?.into_bound(py).into_any().unbind())
+    }
+
+    /// Device accesses made by this hart, per device name (RAM excluded), e.g.
+    /// ``cpu.mmio_accesses()["UART0"]["writes"]``.
+    fn mmio_accesses(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let s = self.stats_snapshot();
+        Ok(s.mmio_accesses(py)?.into_bound(py).into_any().unbind())
+    }
+
+    /// Per-cycle occupancy histograms, e.g. ``cpu.occupancy()["rob"][n]`` is
+    /// the number of cycles with ``n`` ROB entries in use.
+    fn occupancy(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let s = self.stats_snapshot();
+        Ok(s.occupancy(py)?.into_bound(py).into_any().unbind())
+    }
+
+    /// Most recent traps taken by the hart, oldest first, as a list of dicts
+    /// with ``cycle``, ``cause`` (as written to ``mcause``/``scause``),
+    /// ``interrupt``, ``epc``, ``tval``, ``privilege`` (mode trapped from) and
+    /// ``handler`` (mode that took it), privileges as ``"M"``, ``"S"`` or ``"U"``.
+    fn recent_traps<'py>(&self, py: Python<'py>) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        self.inner
+            .cpu
+            .recent_traps
+            .iter()
+            .map(|t| {
+                let d = PyDict::new(py);
+                d.set_item("cycle", t.cycle)?;
+                d.set_item("cause", t.cause)?;
+                d.set_item("interrupt", t.is_interrupt())?;
+                d.set_item("epc", t.epc)?;
+                d.set_item("tval", t.tval)?;
+                d.set_item("privilege", privilege_letter(t.privilege))?;
+                d.set_item("handler", privilege_letter(t.handler))?;
+                Ok(d)
+            })
+            .collect()
+    }
+
+    /// Hits, misses and hit rate per cache level, e.g.
+    /// ``cpu.cache_stats()["l1d"]["hit_rate"]``.
+    fn cache_stats(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let s = self.stats_snapshot();
+        Ok(s.cache_stats(py)?.into_bound(py).into_any().unbind())
+    }
+
+    /// Bytes moved on each memory hierarchy link per interval of
+    /// ``Config.bandwidth_interval`` cycles, e.g.
+    /// ``cpu.bandwidth_timeline()[0]["dram_gbps"]``.
+    fn bandwidth_timeline<'py>(&self, py: Python<'py>) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        let s = self.stats_snapshot();
+        s.bandwidth_timeline(py)
+    }
+
     /// Register file — ``cpu.regs[10]``, ``cpu.regs[10] = v``.
     #[getter]
     fn regs(slf: Bound<'_, Self>) -> Registers {
@@ -311,6 +647,64 @@ impl PyCpu {
         VirtualMemory { cpu: slf.unbind(), width: 64 }
     }
 
+    /// Host end of the ``VirtIO`` network device, or ``None`` when the
+    /// configuration has no network device.
+    #[getter]
+    fn net(slf: Bound<'_, Self>) -> Option<PyVirtioNet> {
+        let present = slf.borrow_mut().inner.cpu.bus.bus.virtio_net_mut().is_some();
+        present.then(|| PyVirtioNet { cpu: slf.unbind() })
+    }
+
+    /// Host view of the ``VirtIO`` block device, or ``None`` when the bus has none.
+    #[getter]
+    fn disk(slf: Bound<'_, Self>) -> Option<PyVirtioBlock> {
+        let present = slf.borrow_mut().inner.cpu.bus.bus.virtio_blk_mut().is_some();
+        present.then(|| PyVirtioBlock { cpu: slf.unbind() })
+    }
+
+    /// Host end of the serial console, or ``None`` when the bus has no UART.
+    #[getter]
+    fn uart(slf: Bound<'_, Self>) -> Option<PyUart> {
+        let present = slf.borrow_mut().inner.cpu.bus.bus.uart_mut().is_some();
+        present.then(|| PyUart { cpu: slf.unbind(), index: 0 })
+    }
+
+    /// Host ends of every UART on the bus, console first.
+    #[getter]
+    fn uarts(slf: Bound<'_, Self>) -> Vec<PyUart> {
+        let count = slf
+            .borrow_mut()
+            .inner
+            .cpu
+            .bus
+            .bus
+            .devices()
+            .filter(|d| d.name().starts_with("UART"))
+            .count();
+        (0..count).map(|index| PyUart { cpu: slf.clone().unbind(), index }).collect()
+    }
+
+    /// Interrupt controller state, or ``None`` when the bus has no PLIC.
+    #[getter]
+    fn plic(slf: Bound<'_, Self>) -> Option<PyPlic> {
+        let present = slf.borrow_mut().inner.cpu.bus.bus.plic_mut().is_some();
+        present.then(|| PyPlic { cpu: slf.unbind() })
+    }
+
+    /// Per-hart timer and IPI registers, or ``None`` when the bus has no CLINT.
+    #[getter]
+    fn clint(slf: Bound<'_, Self>) -> Option<PyClint> {
+        let present = slf.borrow_mut().inner.cpu.bus.bus.clint_mut().is_some();
+        present.then(|| PyClint { cpu: slf.unbind() })
+    }
+
+    /// DMA controller status, or ``None`` unless ``dma`` is enabled in the config.
+    #[getter]
+    fn dma(slf: Bound<'_, Self>) -> Option<PyDma> {
+        let present = slf.borrow_mut().inner.cpu.bus.bus.dma_mut().is_some();
+        present.then(|| PyDma { cpu: slf.unbind() })
+    }
+
     /// Committed PC trace from the pipeline as a list of ``(pc, raw_inst)`` pairs.
     #[getter]
     fn pc_trace(&self) -> Vec<(u64, u32)> {
@@ -329,9 +723,10 @@ impl PyCpu {
     /// Execute until one instruction commits.
     ///
     /// Returns an :class:`Instruction` or ``None`` if the simulation exited
-    /// before an instruction could commit.
+    /// (or the step callback halted it) before an instruction could commit.
     #[pyo3(signature = (max_cycles=100_000))]
     fn step(&mut self, py: Python<'_>, max_cycles: u64) -> PyResult<Option<PyInstruction>> {
+        let cb = self.step_callback.as_ref().map(|cb| cb.clone_ref(py));
         let before_last = self.inner.cpu.pc_trace.last().copied();
         let mut cycles_run: u64 = 0;
 
@@ -342,15 +737,13 @@ impl PyCpu {
             if cycles_run.is_multiple_of(10_000) {
                 py.check_signals()?;
             }
-            match self.inner.tick() {
-                Ok(()) => {
-                    if self.inner.take_exit().is_some() {
-                        return Ok(None);
-                    }
-                }
-                Err(e) => return Err(PyRuntimeError::new_err(e.to_string())),
+            self.tick_once()?;
+            if let Some(code) = self.take_exit_or_reboot()? {
+                self.exit_code = Some(code);
+                return Ok(None);
             }
             cycles_run += 1;
+            let halted = self.call_step_callback(py, cb.as_ref())?;
 
             let new_last = self.inner.cpu.pc_trace.last().copied();
             if new_last != before_last
@@ -364,6 +757,9 @@ impl PyCpu {
                     cycles: self.inner.cpu.stats.cycles,
                 }));
             }
+            if halted {
+                return Ok(None);
+            }
         }
     }
 
@@ -388,11 +784,12 @@ impl PyCpu {
         let exit = if progress > 0 {
             self.run_with_progress(py, limit, progress)?
         } else {
-            self.run_inner(py, limit)?
+            let status = self.run_inner(py, limit, false)?;
+            self.exit_code_for(status)
         };
 
         if let Some(sections) = stats_sections {
-            let s = PyStats::from(self.inner.cpu.stats.clone());
+            let s = self.stats_snapshot();
             if sections.is_empty() {
                 s.print();
             } else {
@@ -433,13 +830,13 @@ impl PyCpu {
                 every
             };
 
-            let exit = self.run_for_cycles(py, chunk)?;
+            let status = self.run_for_cycles(py, chunk)?;
             cycles_run += chunk;
 
-            let s = PyStats::from(self.inner.cpu.stats.clone());
+            let s = self.stats_snapshot();
             snapshots.push(s.to_dict(py)?.into_bound(py).into_any().unbind());
 
-            if exit.is_some() {
+            if status != PyRunStatus::CycleLimit {
                 break;
             }
         }
@@ -447,19 +844,27 @@ impl PyCpu {
         Ok(snapshots)
     }
 
-    /// Run until a predicate is satisfied or the simulation exits.
+    /// Run until a predicate is satisfied, the PC reaches a target, or the
+    /// run ends.
+    ///
+    /// The loop runs in Rust. ``pc`` is exact: the run stops just before the
+    /// instruction at ``pc`` retires, with every older instruction retired and
+    /// the pipeline flushed so ``Cpu.pc == pc``. If ``pc`` is already the next
+    /// instruction to commit, it retires once first.
     ///
     /// Args:
     ///     predicate: ``lambda cpu: bool`` — stop when it returns ``True``.
-    ///     pc: Stop when the program counter equals this value.
+    ///     pc: Stop when the instruction at this address is next to commit.
     ///     privilege: Stop when the privilege level equals this (``"M"``, ``"S"``, ``"U"``).
     ///     limit: Maximum total cycles (``None`` = unlimited).
-    ///     chunk: Cycles between predicate checks.
+    ///     `stop_on_trap`: Also stop when a trap (exception or interrupt) is taken.
+    ///     chunk: Cycles between ``predicate``/``privilege`` checks.
     ///
     /// Returns:
-    ///     Exit code if the simulation exited, or ``None`` if the condition was met
-    ///     or *limit* was reached.
-    #[pyo3(signature = (predicate=None, *, pc=None, privilege=None, limit=None, chunk=10_000))]
+    ///     The :class:`RunStatus` saying why the run stopped. On
+    ///     ``RunStatus.Exited`` the code is in ``Cpu.exit_code``.
+    #[pyo3(signature = (predicate=None, *, pc=None, privilege=None, limit=None, stop_on_trap=false, chunk=10_000))]
+    #[allow(clippy::too_many_arguments)]
     fn run_until(
         slf: Bound<'_, Self>,
         py: Python<'_>,
@@ -467,8 +872,9 @@ impl PyCpu {
         pc: Option<u64>,
         privilege: Option<String>,
         limit: Option<u64>,
+        stop_on_trap: bool,
         chunk: u64,
-    ) -> PyResult<Option<u64>> {
+    ) -> PyResult<PyRunStatus> {
         if predicate.is_none() && pc.is_none() && privilege.is_none() {
             return Err(PyRuntimeError::new_err(
                 "run_until() requires at least one of: predicate, pc=, or privilege=",
@@ -476,52 +882,217 @@ impl PyCpu {
         }
 
         let slf_py: Py<Self> = slf.unbind();
-        let mut cycles_run = 0u64;
+        slf_py.borrow_mut(py).inner.set_breakpoint(pc);
+        let status =
+            Self::run_until_inner(&slf_py, py, limit, predicate, privilege, stop_on_trap, chunk);
+        slf_py.borrow_mut(py).inner.set_breakpoint(None);
+        status
+    }
 
-        loop {
-            let c = if let Some(max) = limit {
-                let remaining = max.saturating_sub(cycles_run);
-                if remaining == 0 {
-                    return Ok(None);
-                }
-                chunk.min(remaining)
-            } else {
-                chunk
-            };
+    /// Run for exactly ``cycles`` cycles, or until the program exits or the
+    /// step callback halts the run.
+    ///
+    /// Returns:
+    ///     ``RunStatus.CycleLimit``, ``RunStatus.Exited`` or ``RunStatus.Halted``.
+    fn run_for(&mut self, py: Python<'_>, cycles: u64) -> PyResult<PyRunStatus> {
+        self.run_for_cycles(py, cycles)
+    }
 
-            // Run a chunk, release borrow before checking predicates.
-            let exit = slf_py.borrow_mut(py).run_for_cycles(py, c)?;
-            cycles_run += c;
+    /// Execute ``n`` instructions functionally: each instruction runs to
+    /// completion in one cycle, without the pipeline, to fast-forward to a
+    /// region of interest. TLBs fill as usual and the caches are warmed if
+    /// ``Config.fast_forward_warm`` is set; branch predictors are untouched.
+    ///
+    /// Instructions still in the pipeline commit first. Afterwards the
+    /// pipeline resumes at the next instruction, with the architectural
+    /// state a detailed run would have reached.
+    ///
+    /// Returns:
+    ///     ``RunStatus.InstLimit``, or ``RunStatus.Exited`` if the program
+    ///     exited first.
+    fn run_functional(&mut self, py: Python<'_>, n: u64) -> PyResult<PyRunStatus> {
+        self.run_insts(py, n, false)
+    }
 
-            if let Some(code) = exit {
-                return Ok(Some(code));
-            }
+    /// Simulate the pipeline in detail until ``n`` more instructions have
+    /// retired on hart 0, then drain it so the architectural state is
+    /// exactly that after the last one.
+    ///
+    /// Returns:
+    ///     ``RunStatus.InstLimit``, or ``RunStatus.Exited`` if the program
+    ///     exited first.
+    fn run_detailed(&mut self, py: Python<'_>, n: u64) -> PyResult<PyRunStatus> {
+        self.run_insts(py, n, true)
+    }
 
-            // Check simple predicates with an immutable borrow.
-            let stop = {
-                let cpu = slf_py.borrow(py);
-                pc.is_some_and(|p| cpu.inner.cpu.pc == p)
-                    || privilege.as_deref().is_some_and(|priv_str| cpu.privilege_str() == priv_str)
-            };
-            if stop {
-                return Ok(None);
-            }
+    /// Exit code of the most recent run that ended because the program exited,
+    /// or ``None``.
+    #[getter]
+    const fn exit_code(&self) -> Option<u64> {
+        self.exit_code
+    }
 
-            // Call Python predicate, passing the Cpu object itself.
-            if let Some(ref pred) = predicate {
-                let result = pred.call1(py, (slf_py.clone_ref(py),))?;
-                if result.extract::<bool>(py)? {
-                    return Ok(None);
-                }
+    /// Read integer register ``x{idx}``.
+    ///
+    /// Raises ``IndexError`` if `idx` is outside 0–31.
+    fn read_reg(&self, idx: usize) -> PyResult<u64> {
+        Ok(self.inner.cpu.regs.read(Self::reg_idx(idx)?))
+    }
+
+    /// Write integer register ``x{idx}``. Writes to ``x0`` are ignored.
+    ///
+    /// Raises ``IndexError`` if `idx` is outside 0–31.
+    fn write_reg(&mut self, idx: usize, value: u64) -> PyResult<()> {
+        self.inner.write_reg(Self::reg_idx(idx)?, value);
+        Ok(())
+    }
+
+    /// Read floating-point register ``f{idx}`` as raw (NaN-boxed) bits.
+    ///
+    /// Raises ``IndexError`` if `idx` is outside 0–31.
+    fn read_freg(&self, idx: usize) -> PyResult<u64> {
+        Ok(self.inner.cpu.regs.read_f(Self::reg_idx(idx)?))
+    }
+
+    /// Write floating-point register ``f{idx}`` from raw bits. Single-precision
+    /// values must already be NaN-boxed (upper 32 bits all ones).
+    ///
+    /// Raises ``IndexError`` if `idx` is outside 0–31.
+    fn write_freg(&mut self, idx: usize, value: u64) -> PyResult<()> {
+        self.inner.write_freg(Self::reg_idx(idx)?, value);
+        Ok(())
+    }
+
+    /// Read a CSR by its 12-bit address, with the same side-effect-free view
+    /// a ``csrr`` instruction would see.
+    ///
+    /// Raises ``ValueError`` if `addr` is outside ``0x000``–``0xfff``.
+    fn read_csr(&self, addr: i64) -> PyResult<u64> {
+        Ok(self.inner.cpu.csr_read(Self::csr_addr(addr)?))
+    }
+
+    /// Write a CSR by its 12-bit address, applying the same WARL masking and
+    /// aliasing (e.g. ``sstatus`` ↔ ``mstatus``) as a ``csrw`` instruction.
+    ///
+    /// Raises ``ValueError`` if `addr` is outside ``0x000``–``0xfff``.
+    fn write_csr(&mut self, addr: i64, value: u64) -> PyResult<()> {
+        self.inner.cpu.csr_write(Self::csr_addr(addr)?, value);
+        Ok(())
+    }
+
+    /// Disassemble the instruction at physical address `addr`, read through
+    /// the bus. A compressed instruction is read as 16 bits and shown as its
+    /// 32-bit expansion.
+    ///
+    /// Raises ``ValueError`` if any byte of the instruction is unmapped.
+    fn disasm(&mut self, addr: u64) -> PyResult<String> {
+        let bus = &mut self.inner.cpu.bus.bus;
+        let mut read_half = |at: u64| {
+            let unmapped =
+                (at..=at.saturating_add(1)).find(|&a| !bus.is_valid_address(PhysAddr::new(a)));
+            if let Some(bad) = unmapped {
+                return Err(PyValueError::new_err(format!("unmapped physical address {bad:#x}")));
             }
+            Ok(u32::from(bus.read_u16(PhysAddr::new(at))))
+        };
+        let low = read_half(addr)?;
+        let inst =
+            if low & 0x3 == 0x3 { low | (read_half(addr.wrapping_add(2))? << 16) } else { low };
+        Ok(rvsim_core::isa::disasm::disassemble(inst))
+    }
 
-            py.check_signals()?;
+    /// Advance one cycle.
+    ///
+    /// Returns ``True`` if the step callback asked to halt.
+    fn tick(&mut self, py: Python<'_>) -> PyResult<bool> {
+        self.tick_once()?;
+        let cb = self.step_callback.as_ref().map(|cb| cb.clone_ref(py));
+        self.call_step_callback(py, cb.as_ref())
+    }
+
+    /// Install ``fn(pc)`` to be called after every simulated cycle by
+    /// ``tick()``, ``step()``, ``run()``, ``sample()`` and ``run_until()``,
+    /// with the current program counter. Returning a truthy value halts the
+    /// run as if its cycle limit had been reached. Pass ``None`` to remove it.
+    ///
+    /// The callback runs while the Cpu is borrowed, so it must not call back
+    /// into this Cpu.
+    #[pyo3(signature = (callback))]
+    fn set_step_callback(&mut self, callback: Option<Py<PyAny>>) {
+        self.step_callback = callback;
+    }
+
+    /// Attach an MMIO device implemented in Python at ``[base, base + size)``.
+    ///
+    /// ``handlers`` provides ``read(offset, size) -> int`` and
+    /// ``write(offset, size, value)``, called on every guest access with the
+    /// offset into the device and the access size in bytes. With ``irq``, the
+    /// device drives that PLIC source through the returned handle. An
+    /// exception raised by a handler stops the run and propagates.
+    ///
+    /// Every access re-enters Python, so keep such devices off hot paths.
+    /// Handlers run while the Cpu is borrowed and must not call back into it.
+    ///
+    /// Raises ``ValueError`` if the range overlaps another device or ``irq``
+    /// is outside 1–63 or already in use, and ``RuntimeError`` once the
+    /// simulation has started.
+    #[pyo3(signature = (base, size, handlers, irq=None))]
+    fn add_device(
+        &mut self,
+        base: u64,
+        size: u64,
+        handlers: &Bound<'_, PyAny>,
+        irq: Option<u32>,
+    ) -> PyResult<PyDeviceHandle> {
+        if self.inner.cpu.stats.cycles != 0 {
+            return Err(PyRuntimeError::new_err("devices must be added before the first cycle"));
+        }
+        if size == 0 {
+            return Err(PyValueError::new_err("device size must be non-zero"));
         }
+        let bus = &mut self.inner.cpu.bus.bus;
+        if let Some(id) = irq {
+            if !(1..64).contains(&id) {
+                return Err(PyValueError::new_err(format!("irq {id} out of range (1–63)")));
+            }
+            if let Some(dev) = bus.devices().find(|d| d.get_irq_id().is_some_and(|i| i.val() == id))
+            {
+                return Err(PyValueError::new_err(format!("irq {id} is used by {}", dev.name())));
+            }
+        }
+        let read = handlers.getattr("read")?.unbind();
+        let write = handlers.getattr("write")?.unbind();
+        let (device, handle) =
+            PyCallbackDevice::new(base, size, read, write, irq, self.device_error.clone());
+        bus.try_add_device(Box::new(device)).map_err(PyValueError::new_err)?;
+        Ok(handle)
     }
 
-    /// Advance one cycle.
-    fn tick(&mut self) -> PyResult<()> {
-        self.inner.tick().map_err(|e| PyRuntimeError::new_err(e.to_string()))
+    /// Trace every bus access to a device other than RAM.
+    ///
+    /// ``target`` is a file path, written one access per line as
+    /// ``<cycle> <hart> <R|W> <addr> <device> <size> <value>``, or a callable
+    /// invoked as ``target(cycle, hart, kind, addr, device, size, value)`` with
+    /// ``kind`` ``"R"`` or ``"W"``. ``None`` stops tracing (and closes the file).
+    /// An exception raised by the callable stops the run and propagates.
+    ///
+    /// Raises ``RuntimeError`` if the file cannot be created.
+    #[pyo3(signature = (target))]
+    fn set_mmio_trace(&mut self, target: Option<&Bound<'_, PyAny>>) -> PyResult<()> {
+        let bus = &mut self.inner.cpu.bus.bus;
+        let Some(target) = target else {
+            bus.set_mmio_trace(None);
+            return Ok(());
+        };
+        if target.is_callable() {
+            let sink = PyMmioTraceSink::new(target.clone().unbind(), self.device_error.clone());
+            bus.set_mmio_trace(Some(Box::new(sink)));
+        } else {
+            let path: std::path::PathBuf = target.extract()?;
+            let file = MmioTraceFile::create(&path.to_string_lossy()).map_err(|e| to_py_err(&e))?;
+            bus.set_mmio_trace(Some(Box::new(file)));
+        }
+        Ok(())
     }
 
     /// Translate a virtual address to a physical address using the current page tables.
@@ -557,15 +1128,9 @@ impl PyCpu {
         paddr: u64,
         length: usize,
     ) -> Bound<'py, pyo3::types::PyBytes> {
-        let cpu = &self.inner.cpu;
-        if paddr >= cpu.ram_start && paddr + length as u64 <= cpu.ram_end && !cpu.ram_ptr.is_null()
-        {
-            let offset = (paddr - cpu.ram_start) as usize;
-            let slice = unsafe { std::slice::from_raw_parts(cpu.ram_ptr.add(offset), length) };
-            pyo3::types::PyBytes::new(py, slice)
-        } else {
+        let mut buf = vec![0u8; length];
+        if !self.inner.cpu.ram.read_bytes(paddr, &mut buf) {
             // Fallback: read byte-by-byte via the bus
-            let mut buf = vec![0u8; length];
             for (i, byte) in buf.iter_mut().enumerate() {
                 *byte = self
                     .inner
@@ -574,8 +1139,68 @@ impl PyCpu {
                     .bus
                     .read_u8(rvsim_core::common::PhysAddr::new(paddr + i as u64));
             }
-            pyo3::types::PyBytes::new(py, &buf)
         }
+        pyo3::types::PyBytes::new(py, &buf)
+    }
+
+    /// Flip bit ``bit`` (0–7) of the RAM byte at physical address ``addr``,
+    /// as a soft error would. With ``Config.ecc="SecDed"`` the next load of
+    /// the word corrects a single flipped bit and faults on two or more.
+    ///
+    /// Raises ``ValueError`` if ``addr`` is not in RAM or ``bit`` is not 0–7.
+    #[pyo3(signature = (addr, bit))]
+    fn inject_fault(&mut self, addr: u64, bit: u8) -> PyResult<()> {
+        let cpu = &mut self.inner.cpu;
+        if cpu.faults.inject(&cpu.ram, &mut cpu.stats, addr, bit) {
+            Ok(())
+        } else {
+            Err(PyValueError::new_err(format!("cannot flip bit {bit} of {addr:#x}")))
+        }
+    }
+
+    /// Address of the symbol ``name`` in the loaded ELF, or ``None``.
+    fn symbol(&self, name: &str) -> Option<u64> {
+        self.inner.symbols.lookup(name)
+    }
+
+    /// Reset every hart to its power-on state: PC at the reset vector
+    /// (``0x8000_0000``), machine mode, power-on CSRs and PMP, and empty
+    /// pipelines. Registers, memory, caches and statistics are kept.
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+
+    /// Drive one access through a single cache level, as if the pipeline had
+    /// made it, without executing instructions or touching memory contents.
+    ///
+    /// Updates replacement state, dirty bits and the level's prefetcher; other
+    /// levels are not consulted. Useful for warming caches from an address trace.
+    ///
+    /// Args:
+    ///     addr: Physical address.
+    ///     `is_write`: Mark the line dirty.
+    ///     level: ``"l1i"``, ``"l1d"``, ``"l2"`` or ``"l3"``.
+    ///
+    /// Returns:
+    ///     ``(hit, penalty)``, where ``penalty`` is the write-back cost of a
+    ///     dirty victim evicted by a miss.
+    ///
+    /// Raises ``ValueError`` for an unknown or disabled level.
+    #[pyo3(signature = (addr, is_write, level))]
+    fn cache_access(&mut self, addr: u64, is_write: bool, level: &str) -> PyResult<(bool, u64)> {
+        let (cache, next_latency) = self.cache_level(level)?;
+        Ok(cache.access(addr, is_write, next_latency))
+    }
+
+    /// Whether the line holding ``addr`` is present in cache ``level``
+    /// (``"l1i"``, ``"l1d"``, ``"l2"`` or ``"l3"``). Does not update
+    /// replacement state.
+    ///
+    /// Raises ``ValueError`` for an unknown or disabled level.
+    #[pyo3(signature = (addr, level))]
+    fn cache_contains(&mut self, addr: u64, level: &str) -> PyResult<bool> {
+        let (cache, _) = self.cache_level(level)?;
+        Ok(cache.contains(addr))
     }
 
     /// Capture a snapshot of the current pipeline state.
@@ -591,202 +1216,32 @@ impl PyCpu {
         PyPipelineSnapshot::new(self.inner.pipeline.snapshot(width))
     }
 
-    /// Save a checkpoint of the full simulation state to a file.
-    ///
-    /// The checkpoint includes PC, registers, CSRs, privilege mode, and RAM.
-    fn save(&self, path: &str) -> PyResult<()> {
-        let cpu = &self.inner.cpu;
-        let file = std::fs::File::create(path)
-            .map_err(|e| PyRuntimeError::new_err(format!("cannot create checkpoint file: {e}")))?;
-        let mut w = BufWriter::new(file);
-
-        let mut header = serde_json::Map::new();
-        let _ = header.insert("magic".into(), serde_json::Value::from("rvsim-checkpoint"));
-        let _ = header.insert("version".into(), serde_json::Value::from(1u64));
-        let _ = header.insert("pc".into(), serde_json::Value::from(cpu.pc));
-        let _ = header.insert("privilege".into(), serde_json::Value::from(cpu.privilege.to_u8()));
-        let _ = header.insert("direct_mode".into(), serde_json::Value::from(cpu.direct_mode));
-        let _ = header.insert("trace".into(), serde_json::Value::from(cpu.trace));
-        let _ = header.insert("wfi_waiting".into(), serde_json::Value::from(cpu.wfi_waiting));
-        let _ = header.insert("wfi_pc".into(), serde_json::Value::from(cpu.wfi_pc));
-        let _ = header.insert("ram_start".into(), serde_json::Value::from(cpu.ram_start));
-        let _ = header.insert("ram_end".into(), serde_json::Value::from(cpu.ram_end));
-
-        let gprs: Vec<serde_json::Value> = (0u8..32)
-            .map(|i| serde_json::Value::from(cpu.regs.read(rvsim_core::common::RegIdx::new(i))))
-            .collect();
-        let _ = header.insert("gpr".into(), serde_json::Value::Array(gprs));
-
-        let fprs: Vec<serde_json::Value> = (0u8..32)
-            .map(|i| serde_json::Value::from(cpu.regs.read_f(rvsim_core::common::RegIdx::new(i))))
-            .collect();
-        let _ = header.insert("fpr".into(), serde_json::Value::Array(fprs));
-
-        let c = &cpu.csrs;
-        let mut csrs = serde_json::Map::new();
-        let _ = csrs.insert("mstatus".into(), c.mstatus.into());
-        let _ = csrs.insert("misa".into(), c.misa.into());
-        let _ = csrs.insert("medeleg".into(), c.medeleg.into());
-        let _ = csrs.insert("mideleg".into(), c.mideleg.into());
-        let _ = csrs.insert("mie".into(), c.mie.into());
-        let _ = csrs.insert("mtvec".into(), c.mtvec.into());
-        let _ = csrs.insert("mscratch".into(), c.mscratch.into());
-        let _ = csrs.insert("mepc".into(), c.mepc.into());
-        let _ = csrs.insert("mcause".into(), c.mcause.into());
-        let _ = csrs.insert("mtval".into(), c.mtval.into());
-        let _ = csrs.insert("mip".into(), c.mip.into());
-        let _ = csrs.insert("sstatus".into(), c.sstatus.into());
-        let _ = csrs.insert("sie".into(), c.sie.into());
-        let _ = csrs.insert("stvec".into(), c.stvec.into());
-        let _ = csrs.insert("sscratch".into(), c.sscratch.into());
-        let _ = csrs.insert("sepc".into(), c.sepc.into());
-        let _ = csrs.insert("scause".into(), c.scause.into());
-        let _ = csrs.insert("stval".into(), c.stval.into());
-        let _ = csrs.insert("sip".into(), c.sip.into());
-        let _ = csrs.insert("satp".into(), c.satp.into());
-        let _ = csrs.insert("cycle".into(), c.cycle.into());
-        let _ = csrs.insert("time".into(), c.time.into());
-        let _ = csrs.insert("instret".into(), c.instret.into());
-        let _ = csrs.insert("mcycle".into(), c.mcycle.into());
-        let _ = csrs.insert("minstret".into(), c.minstret.into());
-        let _ = csrs.insert("stimecmp".into(), c.stimecmp.into());
-        let _ = csrs.insert("fflags".into(), c.fflags.into());
-        let _ = csrs.insert("frm".into(), c.frm.into());
-        let _ = csrs.insert("mcounteren".into(), c.mcounteren.into());
-        let _ = csrs.insert("scounteren".into(), c.scounteren.into());
-        let _ = header.insert("csrs".into(), serde_json::Value::Object(csrs));
-
-        let header_bytes = serde_json::to_vec(&serde_json::Value::Object(header))
-            .map_err(|e| PyRuntimeError::new_err(format!("serialization error: {e}")))?;
-        let header_len = header_bytes.len() as u64;
-        std::io::Write::write_all(&mut w, &header_len.to_le_bytes())
-            .map_err(|e| PyRuntimeError::new_err(format!("write error: {e}")))?;
-        std::io::Write::write_all(&mut w, &header_bytes)
-            .map_err(|e| PyRuntimeError::new_err(format!("write error: {e}")))?;
-
-        let ram_size = (cpu.ram_end - cpu.ram_start) as usize;
-        if !cpu.ram_ptr.is_null() && ram_size > 0 {
-            let ram_slice = unsafe { std::slice::from_raw_parts(cpu.ram_ptr, ram_size) };
-            std::io::Write::write_all(&mut w, ram_slice)
-                .map_err(|e| PyRuntimeError::new_err(format!("write error: {e}")))?;
-        }
+    /// Save a checkpoint of the whole simulated machine to a file.
+    ///
+    /// The checkpoint holds every hart's registers, CSRs, privilege mode and
+    /// LR/SC reservation, the device state (UART FIFOs, CLINT timers, PLIC,
+    /// `VirtIO` queues and disk writes) and the non-zero pages of every RAM
+    /// bank. With ``microarch=True`` cache lines, TLB entries and branch
+    /// predictor tables are saved too.
+    ///
+    /// Saving flushes the pipelines, as a breakpoint hit does; the run
+    /// continues from the next instruction to commit.
+    #[pyo3(signature = (path, microarch = false))]
+    fn checkpoint(&mut self, path: &str, microarch: bool) -> PyResult<()> {
+        self.inner.save_checkpoint(path, microarch).map_err(|e| to_py_err(&e))
+    }
 
-        std::io::Write::flush(&mut w)
-            .map_err(|e| PyRuntimeError::new_err(format!("flush error: {e}")))?;
-        Ok(())
+    /// Alias of :meth:`checkpoint`.
+    #[pyo3(signature = (path, microarch = false))]
+    fn save(&mut self, path: &str, microarch: bool) -> PyResult<()> {
+        self.checkpoint(path, microarch)
     }
 
     /// Restore simulation state from a checkpoint file.
     ///
-    /// The CPU must have been created with compatible RAM size.
+    /// The CPU must have been built from the same configuration and image as
+    /// the one that saved the checkpoint. Statistics restart from zero.
     fn restore(&mut self, path: &str) -> PyResult<()> {
-        let file = std::fs::File::open(path)
-            .map_err(|e| PyRuntimeError::new_err(format!("cannot open checkpoint file: {e}")))?;
-        let mut r = BufReader::new(file);
-
-        let mut len_buf = [0u8; 8];
-        Read::read_exact(&mut r, &mut len_buf)
-            .map_err(|e| PyRuntimeError::new_err(format!("read error: {e}")))?;
-        let header_len = u64::from_le_bytes(len_buf) as usize;
-
-        let mut header_bytes = vec![0u8; header_len];
-        Read::read_exact(&mut r, &mut header_bytes)
-            .map_err(|e| PyRuntimeError::new_err(format!("read error: {e}")))?;
-        let header: serde_json::Value = serde_json::from_slice(&header_bytes)
-            .map_err(|e| PyRuntimeError::new_err(format!("invalid checkpoint header: {e}")))?;
-
-        let magic = header.get("magic").and_then(|v| v.as_str()).unwrap_or("");
-        if magic != "rvsim-checkpoint" {
-            return Err(PyRuntimeError::new_err("not a valid rvsim checkpoint file"));
-        }
-
-        let cpu = &mut self.inner.cpu;
-
-        cpu.pc = header["pc"].as_u64().unwrap_or(0);
-        cpu.privilege = PrivilegeMode::from_u8(header["privilege"].as_u64().unwrap_or(3) as u8);
-        cpu.direct_mode = header["direct_mode"].as_bool().unwrap_or(false);
-        cpu.trace = header["trace"].as_bool().unwrap_or(false);
-        cpu.wfi_waiting = header["wfi_waiting"].as_bool().unwrap_or(false);
-        cpu.wfi_pc = header["wfi_pc"].as_u64().unwrap_or(0);
-
-        if let Some(gprs) = header["gpr"].as_array() {
-            for (i, v) in gprs.iter().enumerate().take(32) {
-                cpu.regs.write(rvsim_core::common::RegIdx::new(i as u8), v.as_u64().unwrap_or(0));
-            }
-        }
-
-        if let Some(fprs) = header["fpr"].as_array() {
-            for (i, v) in fprs.iter().enumerate().take(32) {
-                cpu.regs.write_f(rvsim_core::common::RegIdx::new(i as u8), v.as_u64().unwrap_or(0));
-            }
-        }
-
-        if let Some(csrs) = header.get("csrs") {
-            let c = &mut cpu.csrs;
-            macro_rules! restore_csr {
-                ($field:ident) => {
-                    if let Some(v) = csrs.get(stringify!($field)).and_then(|v| v.as_u64()) {
-                        c.$field = v;
-                    }
-                };
-            }
-            restore_csr!(mstatus);
-            restore_csr!(misa);
-            restore_csr!(medeleg);
-            restore_csr!(mideleg);
-            restore_csr!(mie);
-            restore_csr!(mtvec);
-            restore_csr!(mscratch);
-            restore_csr!(mepc);
-            restore_csr!(mcause);
-            restore_csr!(mtval);
-            restore_csr!(mip);
-            restore_csr!(sstatus);
-            restore_csr!(sie);
-            restore_csr!(stvec);
-            restore_csr!(sscratch);
-            restore_csr!(sepc);
-            restore_csr!(scause);
-            restore_csr!(stval);
-            restore_csr!(sip);
-            restore_csr!(satp);
-            restore_csr!(cycle);
-            restore_csr!(time);
-            restore_csr!(instret);
-            restore_csr!(mcycle);
-            restore_csr!(minstret);
-            restore_csr!(stimecmp);
-            restore_csr!(fflags);
-            restore_csr!(frm);
-            restore_csr!(mcounteren);
-            restore_csr!(scounteren);
-        }
-
-        let ckpt_ram_start = header["ram_start"].as_u64().unwrap_or(0);
-        let ckpt_ram_end = header["ram_end"].as_u64().unwrap_or(0);
-        let ckpt_ram_size = (ckpt_ram_end - ckpt_ram_start) as usize;
-        let cpu_ram_size = (cpu.ram_end - cpu.ram_start) as usize;
-
-        if ckpt_ram_size != cpu_ram_size {
-            return Err(PyRuntimeError::new_err(format!(
-                "RAM size mismatch: checkpoint has {ckpt_ram_size} bytes, CPU has {cpu_ram_size} bytes"
-            )));
-        }
-
-        if !cpu.ram_ptr.is_null() && ckpt_ram_size > 0 {
-            let ram_slice = unsafe { std::slice::from_raw_parts_mut(cpu.ram_ptr, ckpt_ram_size) };
-            Read::read_exact(&mut r, ram_slice)
-                .map_err(|e| PyRuntimeError::new_err(format!("read error restoring RAM: {e}")))?;
-        }
-
-        let _ = cpu.l1_i_cache.flush();
-        let _ = cpu.l1_d_cache.flush();
-        let _ = cpu.l2_cache.flush();
-        let _ = cpu.l3_cache.flush();
-        cpu.mmu.dtlb.flush();
-        cpu.mmu.itlb.flush();
-        cpu.mmu.l2_tlb.flush();
-
-        Ok(())
+        self.inner.load_checkpoint(path).map_err(|e| to_py_err(&e))
     }
 }
@@ -0,0 +1,186 @@
+//! Python-implemented MMIO device.
+//!
+//! `PyCallbackDevice` forwards every bus access in its range to Python
+//! ``read``/``write`` callables, so a device can be prototyped in Python
+//! before it is written in Rust. Each access takes the GIL and makes a Python
+//! call, which costs microseconds where a native device costs nanoseconds.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use rvsim_core::common::IrqId;
+use rvsim_core::soc::devices::Device;
+
+/// First exception raised by a device handler, held until the run loop
+/// can raise it.
+pub type DeviceError = Arc<Mutex<Option<PyErr>>>;
+
+/// Bus device whose registers are implemented by Python callables.
+pub struct PyCallbackDevice {
+    base: u64,
+    size: u64,
+    /// ``read(offset, size) -> int``.
+    read: Py<PyAny>,
+    /// ``write(offset, size, value)``.
+    write: Py<PyAny>,
+    /// PLIC source driven by `irq_level`.
+    irq: Option<u32>,
+    /// Interrupt line level, shared with the Python handle.
+    irq_level: Arc<AtomicBool>,
+    error: DeviceError,
+}
+
+impl PyCallbackDevice {
+    /// Creates the device and the handle returned to Python.
+    pub fn new(
+        base: u64,
+        size: u64,
+        read: Py<PyAny>,
+        write: Py<PyAny>,
+        irq: Option<u32>,
+        error: DeviceError,
+    ) -> (Self, PyDeviceHandle) {
+        let irq_level = Arc::new(AtomicBool::new(false));
+        let handle = PyDeviceHandle { base, size, irq, irq_level: Arc::clone(&irq_level) };
+        (Self { base, size, read, write, irq, irq_level, error }, handle)
+    }
+
+    fn call_read(&self, offset: u64, size: u64) -> u64 {
+        Python::with_gil(|py| self.read.call1(py, (offset, size))?.extract::<u64>(py))
+            .unwrap_or_else(|e| {
+                self.fail(e);
+                0
+            })
+    }
+
+    fn call_write(&self, offset: u64, size: u64, val: u64) {
+        if let Err(e) = Python::with_gil(|py| self.write.call1(py, (offset, size, val))) {
+            self.fail(e);
+        }
+    }
+
+    /// Keeps the first exception; later ones are usually its consequences.
+    fn fail(&self, err: PyErr) {
+        let mut slot = self.error.lock().unwrap_or_else(PoisonError::into_inner);
+        if slot.is_none() {
+            *slot = Some(err);
+        }
+    }
+}
+
+impl Device for PyCallbackDevice {
+    fn name(&self) -> &'static str {
+        "PyCallback"
+    }
+
+    fn address_range(&self) -> (u64, u64) {
+        (self.base, self.size)
+    }
+
+    fn read_u8(&mut self, offset: u64) -> u8 {
+        self.call_read(offset, 1) as u8
+    }
+
+    fn read_u16(&mut self, offset: u64) -> u16 {
+        self.call_read(offset, 2) as u16
+    }
+
+    fn read_u32(&mut self, offset: u64) -> u32 {
+        self.call_read(offset, 4) as u32
+    }
+
+    fn read_u64(&mut self, offset: u64) -> u64 {
+        self.call_read(offset, 8)
+    }
+
+    fn write_u8(&mut self, offset: u64, val: u8) {
+        self.call_write(offset, 1, val.into());
+    }
+
+    fn write_u16(&mut self, offset: u64, val: u16) {
+        self.call_write(offset, 2, val.into());
+    }
+
+    fn write_u32(&mut self, offset: u64, val: u32) {
+        self.call_write(offset, 4, val.into());
+    }
+
+    fn write_u64(&mut self, offset: u64, val: u64) {
+        self.call_write(offset, 8, val);
+    }
+
+    fn tick(&mut self) -> bool {
+        self.irq_level.load(Ordering::Relaxed)
+    }
+
+    fn get_irq_id(&self) -> Option<IrqId> {
+        self.irq.map(IrqId::new)
+    }
+}
+
+/// Handle to a Python device returned by ``cpu.add_device``.
+///
+/// The interrupt line is level-triggered: the PLIC sees the source pending
+/// from ``raise_irq()`` until ``lower_irq()``. Both may be called from inside
+/// the device's own handlers.
+#[pyclass(name = "CallbackDevice")]
+pub struct PyDeviceHandle {
+    base: u64,
+    size: u64,
+    irq: Option<u32>,
+    irq_level: Arc<AtomicBool>,
+}
+
+impl PyDeviceHandle {
+    fn set_irq(&self, level: bool) -> PyResult<()> {
+        if self.irq.is_none() {
+            return Err(PyRuntimeError::new_err("device was added without an irq"));
+        }
+        self.irq_level.store(level, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl PyDeviceHandle {
+    /// Physical base address.
+    #[getter]
+    const fn base(&self) -> u64 {
+        self.base
+    }
+
+    /// Size of the register window in bytes.
+    #[getter]
+    const fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// PLIC source the device drives, or ``None``.
+    #[getter]
+    const fn irq(&self) -> Option<u32> {
+        self.irq
+    }
+
+    /// Whether the interrupt line is raised.
+    #[getter]
+    fn irq_pending(&self) -> bool {
+        self.irq_level.load(Ordering::Relaxed)
+    }
+
+    /// Raises the interrupt line.
+    fn raise_irq(&self) -> PyResult<()> {
+        self.set_irq(true)
+    }
+
+    /// Lowers the interrupt line.
+    fn lower_irq(&self) -> PyResult<()> {
+        self.set_irq(false)
+    }
+
+    fn __repr__(&self) -> String {
+        let irq = self.irq.map_or_else(|| "None".to_owned(), |id| id.to_string());
+        format!("CallbackDevice(base={:#x}, size={:#x}, irq={irq})", self.base, self.size)
+    }
+}
@@ -0,0 +1,91 @@
+//! `VirtIO` block device Python binding.
+//!
+//! Holds a `Py<PyCpu>` back-reference like `VirtioNet`, so sectors are read
+//! from and written to the live device.
+
+use pyo3::exceptions::{PyOSError, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use rvsim_core::soc::devices::VirtioBlock;
+
+use crate::cpu::PyCpu;
+
+/// Host view of the ``VirtIO`` block device returned by ``cpu.disk``.
+///
+/// ``disk.read_sector(0)`` returns what the guest would read;
+/// ``disk.export_overlay(path)`` saves a copy-on-write overlay as a sparse diff.
+#[pyclass(name = "VirtioBlock")]
+pub struct PyVirtioBlock {
+    pub cpu: Py<PyCpu>,
+}
+
+impl PyVirtioBlock {
+    fn with_device<R>(&self, py: Python<'_>, f: impl FnOnce(&mut VirtioBlock) -> R) -> PyResult<R> {
+        let mut cpu = self.cpu.borrow_mut(py);
+        cpu.inner
+            .cpu
+            .bus
+            .bus
+            .virtio_blk_mut()
+            .map(f)
+            .ok_or_else(|| PyRuntimeError::new_err("no VirtIO block device on the bus"))
+    }
+}
+
+#[pymethods]
+impl PyVirtioBlock {
+    /// Whether writes go to an overlay instead of the loaded image.
+    #[getter]
+    fn copy_on_write(&self, py: Python<'_>) -> PyResult<bool> {
+        self.with_device(py, |disk| disk.copy_on_write())
+    }
+
+    /// Number of sectors in the copy-on-write overlay.
+    #[getter]
+    fn overlay_sectors(&self, py: Python<'_>) -> PyResult<usize> {
+        self.with_device(py, |disk| disk.overlay_sectors())
+    }
+
+    /// Number of sectors waiting in the write cache.
+    #[getter]
+    fn dirty_sectors(&self, py: Python<'_>) -> PyResult<usize> {
+        self.with_device(py, |disk| disk.dirty_sectors())
+    }
+
+    /// Contents of sector ``sector`` as the guest would read it; raises
+    /// ``ValueError`` past the end of the disk.
+    fn read_sector<'py>(&self, py: Python<'py>, sector: u64) -> PyResult<Bound<'py, PyBytes>> {
+        self.with_device(py, |disk| disk.read_sector(sector))?
+            .map(|data| PyBytes::new(py, &data))
+            .ok_or_else(|| {
+                PyValueError::new_err(format!("sector {sector} is past the end of the disk"))
+            })
+    }
+
+    /// Replace sector ``sector`` from the host, through the overlay in
+    /// copy-on-write mode; raises ``ValueError`` past the end of the disk or
+    /// if ``data`` is not one sector long.
+    fn write_sector(&self, py: Python<'_>, sector: u64, data: &[u8]) -> PyResult<()> {
+        if self.with_device(py, |disk| disk.write_sector(sector, data))? {
+            Ok(())
+        } else {
+            Err(PyValueError::new_err(format!(
+                "cannot write {} bytes to sector {sector}",
+                data.len()
+            )))
+        }
+    }
+
+    /// Save the copy-on-write overlay to ``path`` as a sparse diff file;
+    /// raises ``OSError`` if it cannot be written.
+    fn export_overlay(&self, py: Python<'_>, path: &str) -> PyResult<()> {
+        self.with_device(py, |disk| disk.export_overlay(path))?
+            .map_err(|e| PyOSError::new_err(format!("{path}: {e}")))
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        let (overlay, dirty) =
+            self.with_device(py, |disk| (disk.overlay_sectors(), disk.dirty_sectors()))?;
+        Ok(format!("VirtioBlock(overlay_sectors={overlay}, dirty_sectors={dirty})"))
+    }
+}
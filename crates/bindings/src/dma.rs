@@ -0,0 +1,72 @@
+//! DMA controller Python binding.
+//!
+//! Holds a `Py<PyCpu>` back-reference like `Clint`, so status is read from the
+//! live device while a transfer progresses.
+
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use rvsim_core::soc::devices::DmaController;
+use rvsim_core::soc::devices::dma::{STATUS_DONE, STATUS_ERROR};
+
+use crate::cpu::PyCpu;
+
+/// Memory-to-memory DMA controller status returned by ``cpu.dma``.
+///
+/// ``while cpu.dma.busy: cpu.run_for(100)`` polls a transfer the guest started;
+/// ``cpu.dma.bytes_done`` tracks its progress.
+#[pyclass(name = "Dma")]
+pub struct PyDma {
+    pub cpu: Py<PyCpu>,
+}
+
+impl PyDma {
+    fn with_device<R>(&self, py: Python<'_>, f: impl FnOnce(&DmaController) -> R) -> PyResult<R> {
+        let mut cpu = self.cpu.borrow_mut(py);
+        cpu.inner
+            .cpu
+            .bus
+            .bus
+            .dma_mut()
+            .map(|dma| f(dma))
+            .ok_or_else(|| PyRuntimeError::new_err("no DMA controller on the bus"))
+    }
+}
+
+#[pymethods]
+impl PyDma {
+    /// STATUS register: bit 0 BUSY, bit 1 DONE, bit 2 ERROR.
+    #[getter]
+    fn status(&self, py: Python<'_>) -> PyResult<u64> {
+        self.with_device(py, DmaController::status)
+    }
+
+    /// Whether a transfer is in progress.
+    #[getter]
+    fn busy(&self, py: Python<'_>) -> PyResult<bool> {
+        self.with_device(py, DmaController::busy)
+    }
+
+    /// Whether the last transfer completed (until the guest clears DONE).
+    #[getter]
+    fn done(&self, py: Python<'_>) -> PyResult<bool> {
+        self.with_device(py, |dma| dma.status() & STATUS_DONE != 0)
+    }
+
+    /// Whether the last transfer was rejected for a range outside RAM.
+    #[getter]
+    fn error(&self, py: Python<'_>) -> PyResult<bool> {
+        self.with_device(py, |dma| dma.status() & STATUS_ERROR != 0)
+    }
+
+    /// Bytes copied by the current or last transfer.
+    #[getter]
+    fn bytes_done(&self, py: Python<'_>) -> PyResult<u64> {
+        self.with_device(py, DmaController::copied)
+    }
+
+    /// Programmed ``(src, dst, len)`` registers.
+    #[getter]
+    fn transfer(&self, py: Python<'_>) -> PyResult<(u64, u64, u64)> {
+        self.with_device(py, DmaController::transfer)
+    }
+}
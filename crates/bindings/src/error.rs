@@ -0,0 +1,24 @@
+//! Simulator error mapping.
+//!
+//! Watchdog failures raise `HangError` so callers can tell a hung guest apart
+//! from other simulator errors, which raise `RuntimeError`.
+
+use pyo3::PyErr;
+use pyo3::exceptions::PyRuntimeError;
+use rvsim_core::SimError;
+
+pyo3::create_exception!(
+    rvsim,
+    HangError,
+    PyRuntimeError,
+    "The watchdog fired: the guest hung or ran past `max_cycles`."
+);
+
+/// Converts a simulator error into the matching Python exception.
+pub fn to_py_err(e: &SimError) -> PyErr {
+    if e.is_hang() {
+        HangError::new_err(e.to_string())
+    } else {
+        PyRuntimeError::new_err(e.to_string())
+    }
+}
@@ -3,7 +3,10 @@
 //! This crate exposes the simulator to Python via `PyO3`. It provides:
 //! 1. **CPU:** `Cpu` — the sole public entry point for simulation.
 //! 2. **Views:** `Instruction`, `Registers`, `Csrs`, `Memory` for CPU introspection.
-//! 3. **Utilities:** `version()` and `disassemble()`.
+//! 3. **Devices:** `VirtioNet` for exchanging frames with the guest network device,
+//!    `Uart` for scripted console input and output, `Plic` and `Clint` for interrupt state,
+//!    `Dma` for DMA controller status, and `CallbackDevice` for MMIO devices implemented in Python.
+//! 4. **Utilities:** `version()` and `disassemble()`.
 
 // PyO3 bindings — relax documentation and pedantic lints for binding-layer code.
 #![allow(
@@ -22,16 +25,36 @@
 
 use pyo3::prelude::*;
 
+/// CLINT binding (`PyClint` exposed as `Clint`).
+pub mod clint;
 /// Python dict to Rust `Config` conversion.
 pub mod conversion;
 /// CPU binding (`PyCpu` exposed as `Cpu`).
 pub mod cpu;
+/// Python-implemented MMIO device (`PyDeviceHandle` exposed as `CallbackDevice`).
+pub mod device;
+/// `VirtIO` block device binding (`PyVirtioBlock` exposed as `VirtioBlock`).
+pub mod disk;
+/// DMA controller binding (`PyDma` exposed as `Dma`).
+pub mod dma;
+/// Simulator error to Python exception mapping (`HangError`).
+pub mod error;
 /// Instruction binding (`PyInstruction` exposed as `Instruction`).
 pub mod instruction;
+/// MMIO trace delivered to a Python callable.
+pub mod mmio_trace;
+/// `VirtIO` network device binding (`PyVirtioNet` exposed as `VirtioNet`).
+pub mod net;
+/// PLIC binding (`PyPlic` exposed as `Plic`).
+pub mod plic;
+/// Run status binding (`PyRunStatus` exposed as `RunStatus`).
+pub mod run_status;
 /// Pipeline snapshot binding (`PyPipelineSnapshot` exposed as `PipelineSnapshot`).
 pub mod snapshot;
 /// Statistics (internal, not exposed to Python).
 pub mod stats;
+/// 16550 UART binding (`PyUart` exposed as `Uart`).
+pub mod uart;
 /// Utility functions (e.g., version).
 pub mod utils;
 /// Register, CSR, and memory view bindings.
@@ -42,11 +65,20 @@ pub fn register_emulator_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<cpu::PyCpu>()?;
 
     m.add_class::<instruction::PyInstruction>()?;
+    m.add_class::<run_status::PyRunStatus>()?;
     m.add_class::<snapshot::PyPipelineSnapshot>()?;
     m.add_class::<views::Registers>()?;
     m.add_class::<views::Csrs>()?;
     m.add_class::<views::Memory>()?;
     m.add_class::<views::VirtualMemory>()?;
+    m.add_class::<net::PyVirtioNet>()?;
+    m.add_class::<uart::PyUart>()?;
+    m.add_class::<plic::PyPlic>()?;
+    m.add_class::<clint::PyClint>()?;
+    m.add_class::<dma::PyDma>()?;
+    m.add_class::<disk::PyVirtioBlock>()?;
+    m.add_class::<device::PyDeviceHandle>()?;
+    m.add("HangError", m.py().get_type::<error::HangError>())?;
 
     m.add_function(wrap_pyfunction!(utils::version, m)?)?;
     m.add_function(wrap_pyfunction!(utils::disassemble, m)?)?;
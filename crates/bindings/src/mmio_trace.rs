@@ -0,0 +1,39 @@
+//! MMIO trace delivered to a Python callable.
+//!
+//! `PyMmioTraceSink` calls ``callback(cycle, hart, kind, addr, device, size, value)``
+//! for every device access the bus sees, with ``kind`` ``"R"`` or ``"W"`` as in
+//! the trace file format.
+
+use std::sync::PoisonError;
+
+use pyo3::prelude::*;
+use rvsim_core::soc::mmio_trace::{MmioAccess, MmioTraceSink};
+
+use crate::device::DeviceError;
+
+/// MMIO trace receiver that forwards each access to a Python callable.
+pub struct PyMmioTraceSink {
+    callback: Py<PyAny>,
+    /// Slot for the first exception raised by the callback.
+    error: DeviceError,
+}
+
+impl PyMmioTraceSink {
+    /// Creates a sink calling `callback`, storing its first exception in `error`.
+    pub const fn new(callback: Py<PyAny>, error: DeviceError) -> Self {
+        Self { callback, error }
+    }
+}
+
+impl MmioTraceSink for PyMmioTraceSink {
+    fn record(&mut self, a: &MmioAccess<'_>) {
+        let kind = if a.is_write { "W" } else { "R" };
+        let args = (a.cycle, a.hart, kind, a.paddr, a.device, a.size, a.value);
+        if let Err(e) = Python::with_gil(|py| self.callback.call1(py, args)) {
+            let mut slot = self.error.lock().unwrap_or_else(PoisonError::into_inner);
+            if slot.is_none() {
+                *slot = Some(e);
+            }
+        }
+    }
+}
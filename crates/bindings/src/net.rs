@@ -0,0 +1,64 @@
+//! `VirtIO` network device Python binding.
+//!
+//! Holds a `Py<PyCpu>` back-reference like the register and memory views, so
+//! frames go straight to and from the live device.
+
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use rvsim_core::soc::devices::VirtioNet;
+
+use crate::cpu::PyCpu;
+
+/// Host end of the ``VirtIO`` network device returned by ``cpu.net``.
+///
+/// ``net.inject(frame)`` queues an Ethernet frame for the guest;
+/// ``net.take_tx()`` returns the frames the guest has sent (capture backend).
+#[pyclass(name = "VirtioNet")]
+pub struct PyVirtioNet {
+    pub cpu: Py<PyCpu>,
+}
+
+impl PyVirtioNet {
+    fn with_device<R>(&self, py: Python<'_>, f: impl FnOnce(&mut VirtioNet) -> R) -> PyResult<R> {
+        let mut cpu = self.cpu.borrow_mut(py);
+        cpu.inner
+            .cpu
+            .bus
+            .bus
+            .virtio_net_mut()
+            .map(f)
+            .ok_or_else(|| PyRuntimeError::new_err("no VirtIO network device on the bus"))
+    }
+}
+
+#[pymethods]
+impl PyVirtioNet {
+    /// MAC address reported to the guest, as ``"52:54:00:12:34:56"``.
+    #[getter]
+    fn mac(&self, py: Python<'_>) -> PyResult<String> {
+        let mac = self.with_device(py, |net| net.mac())?;
+        Ok(mac.iter().map(|b| format!("{b:02x}")).collect::<Vec<_>>().join(":"))
+    }
+
+    /// Number of injected or received frames waiting for a guest RX buffer.
+    #[getter]
+    fn rx_pending(&self, py: Python<'_>) -> PyResult<usize> {
+        self.with_device(py, |net| net.rx_pending())
+    }
+
+    /// Queue an Ethernet frame (without the ``virtio_net_hdr``) for the guest.
+    fn inject(&self, py: Python<'_>, frame: &[u8]) -> PyResult<()> {
+        self.with_device(py, |net| net.inject_frame(frame.to_vec()))
+    }
+
+    /// Remove and return the frames the guest has transmitted, oldest first.
+    fn take_tx<'py>(&self, py: Python<'py>) -> PyResult<Vec<Bound<'py, PyBytes>>> {
+        let frames = self.with_device(py, VirtioNet::take_tx_frames)?;
+        Ok(frames.iter().map(|f| PyBytes::new(py, f)).collect())
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        Ok(format!("VirtioNet(mac={})", self.mac(py)?))
+    }
+}
@@ -0,0 +1,74 @@
+//! PLIC Python binding.
+//!
+//! Read-only view of the interrupt controller for tests; holds a
+//! `Py<PyCpu>` back-reference like `Uart` and `VirtioNet`.
+
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use rvsim_core::soc::devices::Plic;
+
+use crate::cpu::PyCpu;
+
+/// Interrupt controller state returned by ``cpu.plic``.
+///
+/// Contexts are numbered ``2 * hart`` (M-mode) and ``2 * hart + 1`` (S-mode).
+/// Inspecting state never claims or completes an interrupt.
+#[pyclass(name = "Plic")]
+pub struct PyPlic {
+    pub cpu: Py<PyCpu>,
+}
+
+impl PyPlic {
+    fn with_device<R>(&self, py: Python<'_>, f: impl FnOnce(&mut Plic) -> R) -> PyResult<R> {
+        let mut cpu = self.cpu.borrow_mut(py);
+        cpu.inner
+            .cpu
+            .bus
+            .bus
+            .plic_mut()
+            .map(f)
+            .ok_or_else(|| PyRuntimeError::new_err("no PLIC on the bus"))
+    }
+}
+
+#[pymethods]
+impl PyPlic {
+    /// Number of interrupt contexts (two per hart).
+    #[getter]
+    fn num_contexts(&self, py: Python<'_>) -> PyResult<usize> {
+        self.with_device(py, |plic| plic.num_contexts())
+    }
+
+    /// IDs of the pending sources, ascending.
+    #[getter]
+    fn pending(&self, py: Python<'_>) -> PyResult<Vec<u32>> {
+        self.with_device(py, |plic| plic.pending_sources())
+    }
+
+    /// IDs of the sources claimed but not yet completed, ascending.
+    #[getter]
+    fn claimed(&self, py: Python<'_>) -> PyResult<Vec<u32>> {
+        self.with_device(py, |plic| plic.claimed_sources())
+    }
+
+    /// IDs of the sources enabled for ``context``, ascending.
+    fn enabled(&self, py: Python<'_>, context: usize) -> PyResult<Vec<u32>> {
+        self.with_device(py, |plic| plic.enabled_sources(context))
+    }
+
+    /// Priority of ``source`` (0 = never interrupts).
+    fn priority(&self, py: Python<'_>, source: u32) -> PyResult<u32> {
+        self.with_device(py, |plic| plic.priority(source))
+    }
+
+    /// Priority threshold of ``context``.
+    fn threshold(&self, py: Python<'_>, context: usize) -> PyResult<u32> {
+        self.with_device(py, |plic| plic.threshold(context))
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        let (pending, claimed) =
+            self.with_device(py, |plic| (plic.pending_sources(), plic.claimed_sources()))?;
+        Ok(format!("Plic(pending={pending:?}, claimed={claimed:?})"))
+    }
+}
@@ -0,0 +1,26 @@
+//! Run status Python binding.
+//!
+//! Reports why `Cpu.run_until()`, `Cpu.run_for()`, `Cpu.run_functional()` or
+//! `Cpu.run_detailed()` returned.
+
+use pyo3::prelude::*;
+
+/// Why a run returned.
+#[pyclass(name = "RunStatus", eq, eq_int, frozen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PyRunStatus {
+    /// The target PC is the next instruction to commit.
+    PcReached,
+    /// A trap (exception or interrupt) was taken.
+    Trap,
+    /// The program exited; see ``Cpu.exit_code``.
+    Exited,
+    /// The cycle cap was reached.
+    CycleLimit,
+    /// The step callback returned a truthy value.
+    Halted,
+    /// The ``predicate`` or ``privilege`` condition was met.
+    Condition,
+    /// The instruction count was reached.
+    InstLimit,
+}
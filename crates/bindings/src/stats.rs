@@ -2,10 +2,17 @@
 //!
 //! Exposes simulation statistics to Python: getters for cycles, cache hits/misses,
 //! branch accuracy, and instruction mix; `print` / `print_sections` for human-readable
-//! output; `to_dict` for JSON-serializable export (multisim, scripting).
+//! output; `to_dict` for JSON-serializable export (multisim, scripting);
+//! `stall_breakdown` for the per-cause cycle accounting; `occupancy` for the raw
+//! ROB, store buffer, and issue queue occupancy histograms; `mmio_accesses` for the
+//! per-device MMIO access counts; `cache_stats` for the per-level cache hit and
+//! miss counts; `bandwidth_timeline` for the per-interval bytes on each link of
+//! the memory hierarchy.
 
 use pyo3::prelude::*;
-use rvsim_core::stats::SimStats;
+use rvsim_core::core::pipeline::backend::o3::fu_pool::{FuType, PortClass};
+use rvsim_core::core::units::cache::bandwidth::{Link, Traffic};
+use rvsim_core::stats::{SimStats, StallCause};
 
 /// Internal statistics wrapper — not exposed to Python.
 #[derive(Clone)]
@@ -24,12 +31,94 @@ impl PyStats {
         self.inner.print_sections(&sections);
     }
 
+    /// Export the non-retiring cycles charged to each stall cause as a dict.
+    pub fn stall_breakdown(&self, py: Python<'_>) -> pyo3::PyResult<pyo3::Py<pyo3::types::PyDict>> {
+        let d = pyo3::types::PyDict::new(py);
+        for cause in StallCause::ALL {
+            d.set_item(cause.name(), self.inner.stall_cycles[cause as usize])?;
+        }
+        Ok(d.into())
+    }
+
+    /// Export the per-cycle occupancy histograms as a dict of lists
+    /// (index = entries occupied, value = cycles).
+    pub fn occupancy(&self, py: Python<'_>) -> pyo3::PyResult<pyo3::Py<pyo3::types::PyDict>> {
+        let d = pyo3::types::PyDict::new(py);
+        let s = &self.inner;
+        d.set_item("rob", s.rob_occupancy.histogram())?;
+        d.set_item("store_buffer", s.store_buffer_occupancy.histogram())?;
+        d.set_item("issue_queue", s.issue_queue_occupancy.histogram())?;
+        Ok(d.into())
+    }
+
+    /// Export the MMIO access counts as a dict of dicts keyed by device name
+    /// (``reads``, ``writes``, ``bytes_read``, ``bytes_written``).
+    pub fn mmio_accesses(&self, py: Python<'_>) -> pyo3::PyResult<pyo3::Py<pyo3::types::PyDict>> {
+        let d = pyo3::types::PyDict::new(py);
+        for (name, a) in self.inner.mmio.iter() {
+            let dev = pyo3::types::PyDict::new(py);
+            dev.set_item("reads", a.reads)?;
+            dev.set_item("writes", a.writes)?;
+            dev.set_item("bytes_read", a.bytes_read)?;
+            dev.set_item("bytes_written", a.bytes_written)?;
+            d.set_item(name, dev)?;
+        }
+        Ok(d.into())
+    }
+
+    /// Export the cache hit and miss counts as a dict of dicts keyed by level
+    /// (``l1i``, ``l1d``, ``l2``, ``l3``), each with ``hits``, ``misses`` and
+    /// ``hit_rate`` (0.0 for a level with no accesses).
+    pub fn cache_stats(&self, py: Python<'_>) -> pyo3::PyResult<pyo3::Py<pyo3::types::PyDict>> {
+        let d = pyo3::types::PyDict::new(py);
+        let s = &self.inner;
+        for (level, hits, misses) in [
+            ("l1i", s.icache_hits, s.icache_misses),
+            ("l1d", s.dcache_hits, s.dcache_misses),
+            ("l2", s.l2_hits, s.l2_misses),
+            ("l3", s.l3_hits, s.l3_misses),
+        ] {
+            let total = hits + misses;
+            let cache = pyo3::types::PyDict::new(py);
+            cache.set_item("hits", hits)?;
+            cache.set_item("misses", misses)?;
+            cache.set_item("hit_rate", if total > 0 { hits as f64 / total as f64 } else { 0.0 })?;
+            d.set_item(level, cache)?;
+        }
+        Ok(d.into())
+    }
+
+    /// Export the bandwidth timeline as a list of dicts, one per interval:
+    /// ``cycle`` (the interval's first cycle) plus ``<link>_bytes`` and
+    /// ``<link>_gbps`` for each of ``l2``, ``l3`` and ``dram``.
+    pub fn bandwidth_timeline<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> pyo3::PyResult<Vec<Bound<'py, pyo3::types::PyDict>>> {
+        let bw = &self.inner.bandwidth;
+        bw.timeline()
+            .iter()
+            .enumerate()
+            .map(|(i, bytes)| {
+                let d = pyo3::types::PyDict::new(py);
+                d.set_item("cycle", bw.origin() + i as u64 * bw.interval())?;
+                for link in Link::ALL {
+                    let b = bytes[link as usize];
+                    d.set_item(format!("{}_bytes", link.name()), b)?;
+                    d.set_item(format!("{}_gbps", link.name()), bw.gb_per_sec(b, bw.interval()))?;
+                }
+                Ok(d)
+            })
+            .collect()
+    }
+
     /// Export all stats as a Python dict (JSON-serializable).
     pub fn to_dict(&self, py: Python<'_>) -> pyo3::PyResult<pyo3::Py<pyo3::types::PyDict>> {
         let d = pyo3::types::PyDict::new(py);
         let s = &self.inner;
-        d.set_item("cycles", s.cycles)?;
+        d.set_item("cycles", s.measured_cycles())?;
         d.set_item("instructions_retired", s.instructions_retired)?;
+        d.set_item("fused_pairs", s.fused_pairs)?;
         d.set_item("icache_hits", s.icache_hits)?;
         d.set_item("icache_misses", s.icache_misses)?;
         d.set_item("dcache_hits", s.dcache_hits)?;
@@ -42,22 +131,49 @@ impl PyStats {
         d.set_item("stalls_control", s.stalls_control)?;
         d.set_item("stalls_data", s.stalls_data)?;
         d.set_item("stalls_fu_structural", s.stalls_fu_structural)?;
+        for fu in FuType::ALL {
+            d.set_item(format!("fu_busy_{}", fu.name()), s.fu_busy_cycles[fu as usize])?;
+        }
+        for port in PortClass::ALL {
+            d.set_item(format!("port_issues_{}", port.name()), s.port_issues[port as usize])?;
+        }
+        d.set_item("stalls_issue_port", s.stalls_issue_port)?;
+        d.set_item("fetch_buffer_occupancy", s.fetch_buffer_occupancy)?;
+        d.set_item("fetch_buffer_peak", s.fetch_buffer_peak)?;
+        d.set_item("fetch_buffer_full_cycles", s.fetch_buffer_full_cycles)?;
+        d.set_item("fetch_line_crossings", s.fetch_line_crossings)?;
+        d.set_item("fetch_line_crossing_cycles", s.fetch_line_crossing_cycles)?;
         d.set_item("stalls_backpressure", s.stalls_backpressure)?;
         d.set_item("misprediction_penalty", s.misprediction_penalty)?;
         d.set_item("pipeline_flushes", s.pipeline_flushes)?;
         d.set_item("flushes_branch", s.flushes_branch)?;
         d.set_item("flushes_system", s.flushes_system)?;
         d.set_item("mem_ordering_violations", s.mem_ordering_violations)?;
+        d.set_item("store_forwards", s.store_forwards)?;
+        d.set_item("store_forward_stalls", s.store_forward_stalls)?;
         d.set_item("stalls_dispatch", s.stalls_dispatch)?;
+        d.set_item("stalls_store_buffer_full", s.stalls_store_buffer_full)?;
         d.set_item("stalls_checkpoint", s.stalls_checkpoint)?;
+        d.set_item("checkpoint_restores", s.checkpoint_restores)?;
+        d.set_item("recovery_walk_entries", s.recovery_walk_entries)?;
         d.set_item("stalls_squash", s.stalls_squash)?;
         d.set_item("stalls_rename_rebuild", s.stalls_rename_rebuild)?;
         d.set_item("stalls_mshr_full", s.stalls_mshr_full)?;
+        d.set_item("stalls_dcache_port", s.stalls_dcache_port)?;
+
+        for cause in StallCause::ALL {
+            d.set_item(format!("stall_{}", cause.name()), s.stall_cycles[cause as usize])?;
+        }
 
         d.set_item("cycles_user", s.cycles_user)?;
         d.set_item("cycles_kernel", s.cycles_kernel)?;
         d.set_item("cycles_machine", s.cycles_machine)?;
         d.set_item("traps_taken", s.traps_taken)?;
+        d.set_item("interrupts_taken", s.interrupts_taken)?;
+        d.set_item("interrupt_latency_cycles", s.interrupt_latency_cycles)?;
+        d.set_item("interrupt_latency_max", s.interrupt_latency_max)?;
+        d.set_item("syscalls", s.syscalls)?;
+        d.set_item("syscalls_unknown", s.syscalls_unknown)?;
 
         d.set_item("branch_predictions", s.committed_branch_predictions)?;
         d.set_item("branch_mispredictions", s.committed_branch_mispredictions)?;
@@ -79,7 +195,8 @@ impl PyStats {
             0.0
         };
         d.set_item("speculative_branch_accuracy_pct", spec_acc)?;
-        let ipc = if s.cycles > 0 { s.instructions_retired as f64 / s.cycles as f64 } else { 0.0 };
+        let cycles = s.measured_cycles();
+        let ipc = if cycles > 0 { s.instructions_retired as f64 / cycles as f64 } else { 0.0 };
         d.set_item("ipc", ipc)?;
 
         d.set_item("inst_load", s.inst_load)?;
@@ -104,6 +221,65 @@ impl PyStats {
         d.set_item("mdp_predictions_wait_all", s.mdp_predictions_wait_all)?;
         d.set_item("mdp_predictions_wait_for", s.mdp_predictions_wait_for)?;
         d.set_item("mdp_violations", s.mdp_violations)?;
+        d.set_item("mdp_avoided_violations", s.mdp_avoided_violations)?;
+        d.set_item("mdp_false_dependences", s.mdp_false_dependences)?;
+        d.set_item("mdp_accuracy_pct", s.mdp_accuracy_pct())?;
+
+        d.set_item("mem_requests", s.mem_traffic.requests())?;
+        d.set_item("mem_bytes", s.mem_traffic.bytes())?;
+        d.set_item("mem_avg_latency", s.mem_traffic.avg_latency())?;
+        d.set_item("mem_p99_latency", s.mem_traffic.p99_latency())?;
+        d.set_item("mem_peak_bandwidth", s.mem_traffic.peak_bandwidth())?;
+        d.set_item("mem_avg_queue_occupancy", s.mem_traffic.avg_queue_occupancy())?;
+        d.set_item("mem_reads", s.mem_traffic.reads())?;
+        d.set_item("mem_avg_read_latency", s.mem_traffic.avg_read_latency())?;
+        d.set_item("mem_p99_read_latency", s.mem_traffic.percentile_read_latency(99.0))?;
+        let row_buffer = s.mem_traffic.row_buffer();
+        d.set_item("mem_row_hits", row_buffer.iter().map(|rb| rb.hits).sum::<u64>())?;
+        d.set_item("mem_row_misses", row_buffer.iter().map(|rb| rb.misses).sum::<u64>())?;
+        d.set_item("mem_row_conflicts", row_buffer.iter().map(|rb| rb.conflicts).sum::<u64>())?;
+        for rb in row_buffer {
+            d.set_item(
+                format!("mem_row_hit_rate_ch{}_bg{}", rb.channel, rb.bank_group),
+                rb.hit_rate(),
+            )?;
+        }
+
+        for link in Link::ALL {
+            for traffic in Traffic::ALL {
+                d.set_item(
+                    format!("bw_{}_{}_bytes", link.name(), traffic.name()),
+                    s.bandwidth.bytes(link, traffic),
+                )?;
+            }
+            d.set_item(
+                format!("bw_{}_gbps", link.name()),
+                s.bandwidth.gb_per_sec(s.bandwidth.link_bytes(link), s.measured_cycles()),
+            )?;
+        }
+
+        let mmio = s.mmio.total();
+        d.set_item("ram_resident_bytes", s.ram_resident_bytes)?;
+        d.set_item("faults_injected", s.faults_injected)?;
+        d.set_item("ecc_corrected", s.ecc_corrected)?;
+        d.set_item("ecc_uncorrected", s.ecc_uncorrected)?;
+        d.set_item("mmio_reads", mmio.reads)?;
+        d.set_item("mmio_writes", mmio.writes)?;
+        d.set_item("mmio_bytes", mmio.bytes_read + mmio.bytes_written)?;
+        d.set_item("disk_cached_sectors", s.disk.cached_sectors)?;
+        d.set_item("disk_write_through_sectors", s.disk.write_through_sectors)?;
+        d.set_item("disk_flushed_sectors", s.disk.flushed_sectors)?;
+        d.set_item("disk_flushes", s.disk.flush_requests)?;
+
+        for (name, h) in [
+            ("rob", &s.rob_occupancy),
+            ("store_buffer", &s.store_buffer_occupancy),
+            ("issue_queue", &s.issue_queue_occupancy),
+        ] {
+            d.set_item(format!("{name}_occupancy_avg"), h.avg())?;
+            d.set_item(format!("{name}_occupancy_p99"), h.percentile(99.0))?;
+            d.set_item(format!("{name}_occupancy_max"), h.max())?;
+        }
 
         Ok(d.into())
     }
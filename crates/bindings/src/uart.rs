@@ -0,0 +1,88 @@
+//! 16550 UART Python binding.
+//!
+//! Holds a `Py<PyCpu>` back-reference like `VirtioNet`, so console bytes go
+//! straight to and from the live device, and links to UARTs of other `Cpu`
+//! objects are attached to the devices themselves.
+
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use rvsim_core::soc::devices::{SerialLink, Uart};
+
+use crate::cpu::PyCpu;
+
+/// Host end of a serial port, returned by ``cpu.uart`` (the console) and ``cpu.uarts``.
+///
+/// ``uart.write_input(b"ls\n")`` types bytes on the port;
+/// ``uart.read_output()`` returns what the guest has printed since the last call.
+/// ``uart.connect(other_cpu.uart)`` wires it to a UART of another ``Cpu``.
+#[pyclass(name = "Uart")]
+pub struct PyUart {
+    pub cpu: Py<PyCpu>,
+    /// Position among the system's UARTs (0 for the console).
+    pub index: usize,
+}
+
+impl PyUart {
+    fn with_device<R>(&self, py: Python<'_>, f: impl FnOnce(&mut Uart) -> R) -> PyResult<R> {
+        let mut cpu = self.cpu.borrow_mut(py);
+        cpu.inner
+            .cpu
+            .bus
+            .bus
+            .nth_uart_mut(self.index)
+            .map(f)
+            .ok_or_else(|| PyRuntimeError::new_err(format!("no UART{} on the bus", self.index)))
+    }
+}
+
+#[pymethods]
+impl PyUart {
+    /// Position among the system's UARTs; 0 is the console.
+    #[getter]
+    const fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Queue bytes for the guest; they enter the receive FIFO as it drains.
+    fn write_input(&self, py: Python<'_>, data: &[u8]) -> PyResult<()> {
+        self.with_device(py, |uart| uart.push_input(data))
+    }
+
+    /// Remove and return the bytes the guest has transmitted, oldest first.
+    fn read_output<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let out = self.with_device(py, Uart::take_output)?;
+        Ok(PyBytes::new(py, &out))
+    }
+
+    /// Whether a serial link to another UART is attached.
+    #[getter]
+    fn connected(&self, py: Python<'_>) -> PyResult<bool> {
+        self.with_device(py, |uart| uart.is_connected())
+    }
+
+    /// Wire this UART to `other`, usually on another ``Cpu``, as a serial cable.
+    ///
+    /// Each side's transmitted bytes are received by the other `latency`
+    /// cycles of the receiver's own stepping after it first sees them, raising
+    /// its receive interrupt. Any earlier link of either UART is replaced.
+    /// Raises ``ValueError`` if `other` is this UART.
+    #[pyo3(signature = (other, latency=0))]
+    fn connect(&self, py: Python<'_>, other: PyRef<'_, Self>, latency: u64) -> PyResult<()> {
+        if self.cpu.is(&other.cpu) && self.index == other.index {
+            return Err(PyValueError::new_err("cannot connect a UART to itself"));
+        }
+        let (ours, theirs) = SerialLink::pair(latency);
+        self.with_device(py, |uart| uart.connect(ours))?;
+        other.with_device(py, |uart| uart.connect(theirs))
+    }
+
+    /// Detach the serial link, if any; bytes still in flight to this UART are dropped.
+    fn disconnect(&self, py: Python<'_>) -> PyResult<()> {
+        self.with_device(py, Uart::disconnect)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Uart(index={})", self.index)
+    }
+}
@@ -24,13 +24,17 @@ pub fn version() -> String {
 ///
 /// * `inst` - Raw 32-bit instruction word (16-bit compressed instructions
 ///   should be zero-extended to 32 bits before passing).
+/// * `numeric` - Print base instructions with `xN` registers and hex CSR
+///   numbers instead of pseudo-instructions and ABI/CSR names.
 ///
 /// # Returns
 ///
 /// A human-readable disassembly string such as `"addi sp, sp, -16"`,
 /// or `"unknown (0x????????)"` for unrecognised encodings.
 #[pyfunction]
+#[pyo3(signature = (inst, numeric=false))]
 #[must_use]
-pub fn disassemble(inst: u32) -> String {
-    rvsim_core::isa::disasm::disassemble(inst)
+pub fn disassemble(inst: u32, numeric: bool) -> String {
+    use rvsim_core::isa::disasm::{Syntax, disassemble_with};
+    disassemble_with(inst, if numeric { Syntax::Numeric } else { Syntax::Canonical })
 }
@@ -3,9 +3,10 @@
 //! Each view holds a `Py<PyCpu>` back-reference so reads and writes go through
 //! the live CPU rather than a snapshot.
 
-use pyo3::exceptions::{PyIndexError, PyKeyError, PyTypeError};
+use pyo3::exceptions::{PyKeyError, PyTypeError, PyValueError};
 use pyo3::prelude::*;
-use rvsim_core::common::RegIdx;
+use pyo3::types::PyBytes;
+use rvsim_core::common::{PhysAddr, RegIdx};
 
 use crate::cpu::PyCpu;
 
@@ -56,17 +57,11 @@ pub struct Registers {
 #[pymethods]
 impl Registers {
     fn __getitem__(&self, py: Python<'_>, idx: usize) -> PyResult<u64> {
-        if idx >= 32 {
-            return Err(PyIndexError::new_err(format!("register index {idx} out of range (0–31)")));
-        }
-        Ok(self.cpu.borrow(py).inner.cpu.regs.read(RegIdx::new(idx as u8)))
+        Ok(self.cpu.borrow(py).inner.cpu.regs.read(PyCpu::reg_idx(idx)?))
     }
 
     fn __setitem__(&self, py: Python<'_>, idx: usize, value: u64) -> PyResult<()> {
-        if idx >= 32 {
-            return Err(PyIndexError::new_err(format!("register index {idx} out of range (0–31)")));
-        }
-        self.cpu.borrow_mut(py).inner.cpu.regs.write(RegIdx::new(idx as u8), value);
+        self.cpu.borrow_mut(py).inner.write_reg(PyCpu::reg_idx(idx)?, value);
         Ok(())
     }
 
@@ -113,6 +108,8 @@ impl Csrs {
 /// Subscript memory access returned by `cpu.mem32` or `cpu.mem64`.
 ///
 /// ``cpu.mem32[addr]`` reads a u32. ``cpu.mem64[addr]`` reads a u64.
+/// ``read(addr, len)`` and ``write(addr, data)`` move byte strings through the
+/// system bus, so both RAM and MMIO devices are reachable.
 /// These use **physical** addresses — no MMU translation.
 #[pyclass(name = "Memory")]
 pub struct Memory {
@@ -132,11 +129,47 @@ impl Memory {
         }
     }
 
+    /// Reads `len` bytes starting at physical address `addr`.
+    ///
+    /// Raises `ValueError` if any byte of the range is not backed by a device.
+    fn read<'py>(&self, py: Python<'py>, addr: u64, len: u64) -> PyResult<Bound<'py, PyBytes>> {
+        let mut cpu = self.cpu.borrow_mut(py);
+        let bus = &mut cpu.inner.cpu.bus.bus;
+        check_range(bus, addr, len)?;
+        let data: Vec<u8> = (0..len).map(|i| bus.read_u8(PhysAddr::new(addr + i))).collect();
+        Ok(PyBytes::new(py, &data))
+    }
+
+    /// Writes `data` starting at physical address `addr`.
+    ///
+    /// Raises `ValueError` if any byte of the range is not backed by a device;
+    /// nothing is written in that case.
+    fn write(&self, py: Python<'_>, addr: u64, data: &[u8]) -> PyResult<()> {
+        let mut cpu = self.cpu.borrow_mut(py);
+        let bus = &mut cpu.inner.cpu.bus.bus;
+        check_range(bus, addr, data.len() as u64)?;
+        for (i, &byte) in data.iter().enumerate() {
+            bus.write_u8(PhysAddr::new(addr + i as u64), byte);
+        }
+        Ok(())
+    }
+
     fn __repr__(&self) -> String {
         format!("Memory(u{})", self.width)
     }
 }
 
+/// Fails unless every byte of `[addr, addr + len)` is claimed by a bus device.
+fn check_range(bus: &rvsim_core::soc::interconnect::Bus, addr: u64, len: u64) -> PyResult<()> {
+    let end = addr
+        .checked_add(len)
+        .ok_or_else(|| PyValueError::new_err(format!("range {addr:#x}+{len:#x} overflows")))?;
+    if let Some(bad) = (addr..end).find(|&a| !bus.is_valid_address(PhysAddr::new(a))) {
+        return Err(PyValueError::new_err(format!("unmapped physical address {bad:#x}")));
+    }
+    Ok(())
+}
+
 /// Subscript memory access with virtual-to-physical translation via the MMU.
 ///
 /// ``cpu.vmem64[addr]`` translates `addr` through the current page tables
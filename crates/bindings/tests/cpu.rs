@@ -0,0 +1,839 @@
+//! `Cpu` binding tests, driven through the Python object model.
+
+#![allow(missing_docs, clippy::unwrap_used)]
+
+use _core::cpu::PyCpu;
+use _core::run_status::PyRunStatus;
+use pyo3::exceptions::{PyIndexError, PyRuntimeError, PyValueError, PyZeroDivisionError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict};
+use rvsim_core::Simulator;
+use rvsim_core::common::{PhysAddr, Trap};
+use rvsim_core::config::Config;
+use rvsim_core::core::arch::mode::PrivilegeMode;
+use rvsim_core::soc::System;
+
+const MTVEC: u32 = 0x305;
+const RAM_BASE: u64 = 0x8000_0000;
+
+fn with_cpu(f: impl FnOnce(&Bound<'_, PyCpu>)) {
+    with_config(&Config::default(), f);
+}
+
+fn with_config(config: &Config, f: impl FnOnce(&Bound<'_, PyCpu>)) {
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let inner = Simulator::new(System::new(config, ""), config);
+        let cpu = Bound::new(py, PyCpu::from(inner)).unwrap();
+        f(&cpu);
+    });
+}
+
+#[test]
+fn write_csr_reads_back_mtvec() {
+    with_cpu(|cpu| {
+        let _ = cpu.call_method1("write_csr", (MTVEC, 0x8000_0100_u64)).unwrap();
+        let mtvec: u64 = cpu.call_method1("read_csr", (MTVEC,)).unwrap().extract().unwrap();
+        assert_eq!(mtvec, 0x8000_0100);
+        assert_eq!(cpu.borrow().inner.cpu.csrs.mtvec, 0x8000_0100);
+    });
+}
+
+#[test]
+fn csr_address_out_of_range_raises_value_error() {
+    with_cpu(|cpu| {
+        let py = cpu.py();
+        for addr in [0x1000_i64, -1] {
+            let err = cpu.call_method1("read_csr", (addr,)).unwrap_err();
+            assert!(err.is_instance_of::<PyValueError>(py), "read_csr({addr:#x}): {err}");
+            let err = cpu.call_method1("write_csr", (addr, 0)).unwrap_err();
+            assert!(err.is_instance_of::<PyValueError>(py), "write_csr({addr:#x}): {err}");
+        }
+    });
+}
+
+#[test]
+fn write_reg_feeds_stepped_add() {
+    with_cpu(|cpu| {
+        {
+            let mut c = cpu.borrow_mut();
+            // add x7, x5, x6
+            c.inner.cpu.bus.bus.write_u32(PhysAddr::new(RAM_BASE), 0x0062_83B3);
+            c.inner.cpu.pc = RAM_BASE;
+        }
+        let _ = cpu.call_method1("write_reg", (5, 40)).unwrap();
+        let _ = cpu.call_method1("write_reg", (6, 2)).unwrap();
+        let inst = cpu.call_method0("step").unwrap();
+        assert!(!inst.is_none(), "add did not commit");
+
+        let x7: u64 = cpu.call_method1("read_reg", (7,)).unwrap().extract().unwrap();
+        assert_eq!(x7, 42);
+    });
+}
+
+#[test]
+fn write_reg_x0_is_ignored() {
+    with_cpu(|cpu| {
+        let _ = cpu.call_method1("write_reg", (0, 0xdead)).unwrap();
+        let x0: u64 = cpu.call_method1("read_reg", (0,)).unwrap().extract().unwrap();
+        assert_eq!(x0, 0);
+    });
+}
+
+#[test]
+fn freg_round_trips_raw_bits() {
+    with_cpu(|cpu| {
+        let bits = 1.5_f64.to_bits();
+        let _ = cpu.call_method1("write_freg", (0, bits)).unwrap();
+        let _ = cpu.call_method1("write_freg", (31, bits)).unwrap();
+        for idx in [0, 31] {
+            let v: u64 = cpu.call_method1("read_freg", (idx,)).unwrap().extract().unwrap();
+            assert_eq!(v, bits, "f{idx}");
+        }
+    });
+}
+
+#[test]
+fn register_index_out_of_range_raises_index_error() {
+    with_cpu(|cpu| {
+        let py = cpu.py();
+        for method in ["read_reg", "read_freg"] {
+            let err = cpu.call_method1(method, (32,)).unwrap_err();
+            assert!(err.is_instance_of::<PyIndexError>(py), "{method}(32): {err}");
+        }
+        for method in ["write_reg", "write_freg"] {
+            let err = cpu.call_method1(method, (32, 1)).unwrap_err();
+            assert!(err.is_instance_of::<PyIndexError>(py), "{method}(32, 1): {err}");
+        }
+    });
+}
+
+/// Defines a `Counter` class whose instances count calls and return `True`
+/// (halt) once `stop_after` calls have been made.
+fn counter(py: Python<'_>, stop_after: Option<u64>) -> Bound<'_, PyAny> {
+    let locals = PyDict::new(py);
+    py.run(
+        c"
+class Counter:
+    def __init__(self, stop_after):
+        self.calls = 0
+        self.stop_after = stop_after
+    def __call__(self, pc):
+        self.calls += 1
+        return self.stop_after is not None and self.calls >= self.stop_after
+",
+        None,
+        Some(&locals),
+    )
+    .unwrap();
+    locals.get_item("Counter").unwrap().unwrap().call1((stop_after,)).unwrap()
+}
+
+/// Points the CPU at a `j .` spin loop so runs never exit on their own.
+fn spin(cpu: &Bound<'_, PyCpu>) {
+    let mut c = cpu.borrow_mut();
+    c.inner.cpu.bus.bus.write_u32(PhysAddr::new(RAM_BASE), 0x0000_006F);
+    c.inner.cpu.pc = RAM_BASE;
+}
+
+#[test]
+fn step_callback_runs_once_per_cycle() {
+    with_cpu(|cpu| {
+        spin(cpu);
+        let cb = counter(cpu.py(), None);
+        let _ = cpu.call_method1("set_step_callback", (&cb,)).unwrap();
+        let start = cpu.borrow().inner.cpu.stats.cycles;
+        let exit = cpu.call_method1("run", (250,)).unwrap();
+        assert!(exit.is_none());
+
+        let cycles = cpu.borrow().inner.cpu.stats.cycles - start;
+        let calls: u64 = cb.getattr("calls").unwrap().extract().unwrap();
+        assert_eq!(cycles, 250);
+        assert_eq!(calls, 250);
+    });
+}
+
+#[test]
+fn step_callback_truthy_return_halts_run() {
+    with_cpu(|cpu| {
+        spin(cpu);
+        let cb = counter(cpu.py(), Some(10));
+        let _ = cpu.call_method1("set_step_callback", (&cb,)).unwrap();
+        let start = cpu.borrow().inner.cpu.stats.cycles;
+        let exit = cpu.call_method1("run", (1000,)).unwrap();
+        assert!(exit.is_none());
+        assert_eq!(cpu.borrow().inner.cpu.stats.cycles - start, 10);
+
+        let halted: bool = cpu.call_method0("tick").unwrap().extract().unwrap();
+        assert!(halted);
+
+        let _ = cpu.call_method1("set_step_callback", (cpu.py().None(),)).unwrap();
+        let _ = cpu.call_method1("run", (100,)).unwrap();
+        let calls: u64 = cb.getattr("calls").unwrap().extract().unwrap();
+        assert_eq!(calls, 11, "a removed callback is no longer called");
+    });
+}
+
+/// `li x5, 1; li x6, 2; li x7, 3; j .`
+fn load_counting_program(cpu: &Bound<'_, PyCpu>) {
+    let mut c = cpu.borrow_mut();
+    let program = [0x0010_0293, 0x0020_0313, 0x0030_0393, 0x0000_006F];
+    for (i, inst) in program.into_iter().enumerate() {
+        c.inner.cpu.bus.bus.write_u32(PhysAddr::new(RAM_BASE + 4 * i as u64), inst);
+    }
+    c.inner.cpu.pc = RAM_BASE;
+}
+
+fn reg(cpu: &Bound<'_, PyCpu>, idx: usize) -> u64 {
+    cpu.call_method1("read_reg", (idx,)).unwrap().extract().unwrap()
+}
+
+/// Calls `cpu.run_until(pc=pc, limit=limit, **extra)` and returns its status.
+fn run_until(cpu: &Bound<'_, PyCpu>, pc: u64, limit: u64, extra: &[(&str, bool)]) -> PyRunStatus {
+    let kwargs = PyDict::new(cpu.py());
+    kwargs.set_item("pc", pc).unwrap();
+    kwargs.set_item("limit", limit).unwrap();
+    for &(key, value) in extra {
+        kwargs.set_item(key, value).unwrap();
+    }
+    cpu.call_method("run_until", (), Some(&kwargs)).unwrap().extract().unwrap()
+}
+
+#[test]
+fn run_until_stops_before_target_pc() {
+    with_cpu(|cpu| {
+        load_counting_program(cpu);
+        let status = run_until(cpu, RAM_BASE + 8, 1000, &[]);
+        assert_eq!(status, PyRunStatus::PcReached);
+
+        let pc: u64 = cpu.getattr("pc").unwrap().extract().unwrap();
+        assert_eq!(pc, RAM_BASE + 8);
+        assert_eq!((reg(cpu, 5), reg(cpu, 6), reg(cpu, 7)), (1, 2, 0));
+
+        // Already at the target: it retires once and the run continues.
+        let status = run_until(cpu, RAM_BASE + 8, 200, &[]);
+        assert_eq!(status, PyRunStatus::CycleLimit);
+        assert_eq!(reg(cpu, 7), 3);
+    });
+}
+
+#[test]
+fn run_for_runs_fixed_cycles() {
+    with_cpu(|cpu| {
+        spin(cpu);
+        let start = cpu.borrow().inner.cpu.stats.cycles;
+        let status = cpu.call_method1("run_for", (123,)).unwrap();
+        assert_eq!(status.extract::<PyRunStatus>().unwrap(), PyRunStatus::CycleLimit);
+        assert_eq!(cpu.borrow().inner.cpu.stats.cycles - start, 123);
+    });
+}
+
+#[test]
+fn run_until_reports_exit_and_trap() {
+    with_cpu(|cpu| {
+        // Direct mode: the illegal all-zero word at RAM_BASE exits the run.
+        cpu.borrow_mut().inner.cpu.pc = RAM_BASE;
+        let status = run_until(cpu, RAM_BASE + 0x100, 1000, &[]);
+        assert_eq!(status, PyRunStatus::Exited);
+        let code: Option<u64> = cpu.getattr("exit_code").unwrap().extract().unwrap();
+        assert_eq!(code, Some(0));
+    });
+    // An ecall traps to a spin loop at mtvec; the run stops there only when
+    // asked to.
+    for (stop_on_trap, expected) in [(true, PyRunStatus::Trap), (false, PyRunStatus::CycleLimit)] {
+        with_cpu(|cpu| {
+            {
+                let mut c = cpu.borrow_mut();
+                c.inner.cpu.direct_mode = false;
+                c.inner.cpu.bus.bus.write_u32(PhysAddr::new(RAM_BASE), 0x0000_0073); // ecall
+                c.inner.cpu.bus.bus.write_u32(PhysAddr::new(RAM_BASE + 0x40), 0x0000_006F);
+                c.inner.cpu.pc = RAM_BASE;
+            }
+            let _ = cpu.call_method1("write_csr", (MTVEC, RAM_BASE + 0x40)).unwrap();
+            let status = run_until(cpu, RAM_BASE + 0x100, 1000, &[("stop_on_trap", stop_on_trap)]);
+            assert_eq!(status, expected);
+            let mcause = cpu.borrow().inner.cpu.csrs.mcause;
+            assert_eq!(mcause, 11, "ecall from M-mode");
+        });
+    }
+}
+
+#[test]
+fn memory_write_then_read_round_trips_bytes() {
+    with_cpu(|cpu| {
+        let mem = cpu.getattr("mem64").unwrap();
+        let data: Vec<u8> = (0..=255).collect();
+        let addr = RAM_BASE + 0x1003;
+        let _ = mem.call_method1("write", (addr, PyBytes::new(cpu.py(), &data))).unwrap();
+
+        let back: Vec<u8> =
+            mem.call_method1("read", (addr, data.len())).unwrap().extract().unwrap();
+        assert_eq!(back, data);
+        let word: u64 = mem.get_item(RAM_BASE + 0x1008).unwrap().extract().unwrap();
+        assert_eq!(word, u64::from_le_bytes([5, 6, 7, 8, 9, 10, 11, 12]));
+    });
+}
+
+#[test]
+fn memory_access_outside_devices_raises_value_error() {
+    with_cpu(|cpu| {
+        let py = cpu.py();
+        let mem = cpu.getattr("mem32").unwrap();
+        let err = mem.call_method1("read", (0_u64, 4)).unwrap_err();
+        assert!(err.is_instance_of::<PyValueError>(py), "{err}");
+        let err = mem.call_method1("read", (u64::MAX, 2)).unwrap_err();
+        assert!(err.is_instance_of::<PyValueError>(py), "{err}");
+
+        // A write straddling the end of RAM fails without touching the valid part.
+        let ram_end = RAM_BASE + Config::default().memory.ram_size as u64;
+        let err = mem.call_method1("write", (ram_end - 2, PyBytes::new(py, &[1; 4]))).unwrap_err();
+        assert!(err.is_instance_of::<PyValueError>(py), "{err}");
+        let tail: Vec<u8> = mem.call_method1("read", (ram_end - 2, 2)).unwrap().extract().unwrap();
+        assert_eq!(tail, [0, 0]);
+    });
+}
+
+const DEVICE_BASE: u64 = 0x2000_0000;
+
+/// Defines a "magic counter" device: every read returns the count and
+/// increments it, a write sets it. A read at offset 8 raises.
+fn magic_counter(py: Python<'_>) -> Bound<'_, PyAny> {
+    let locals = PyDict::new(py);
+    py.run(
+        c"
+class MagicCounter:
+    def __init__(self):
+        self.count = 0
+        self.writes = []
+    def read(self, offset, size):
+        if offset == 8:
+            raise ZeroDivisionError('bad register')
+        value = self.count
+        self.count += 1
+        return value
+    def write(self, offset, size, value):
+        self.writes.append((offset, size, value))
+        self.count = value
+",
+        None,
+        Some(&locals),
+    )
+    .unwrap();
+    locals.get_item("MagicCounter").unwrap().unwrap().call0().unwrap()
+}
+
+#[test]
+fn python_device_serves_guest_loads_and_stores() {
+    with_cpu(|cpu| {
+        let dev = magic_counter(cpu.py());
+        let _ = cpu.call_method1("add_device", (DEVICE_BASE, 0x1000, &dev)).unwrap();
+        {
+            // lui a0, 0x20000; lw t0, 0(a0); lw t1, 0(a0); li t2, 100;
+            // sw t2, 4(a0); lw s0, 0(a0); j .
+            let program =
+                [0x2000_0537, 0x0005_2283, 0x0005_2303, 0x0640_0393, 0x0075_2223, 0x0005_2403];
+            let mut c = cpu.borrow_mut();
+            for (i, inst) in program.into_iter().chain([0x0000_006F]).enumerate() {
+                c.inner.cpu.bus.bus.write_u32(PhysAddr::new(RAM_BASE + 4 * i as u64), inst);
+            }
+            c.inner.cpu.pc = RAM_BASE;
+        }
+        let status = run_until(cpu, RAM_BASE + 24, 1000, &[]);
+        assert_eq!(status, PyRunStatus::PcReached);
+
+        assert_eq!((reg(cpu, 5), reg(cpu, 6), reg(cpu, 8)), (0, 1, 100));
+        let writes: Vec<(u64, u64, u64)> = dev.getattr("writes").unwrap().extract().unwrap();
+        assert_eq!(writes, [(4, 4, 100)]);
+        let count: u64 = dev.getattr("count").unwrap().extract().unwrap();
+        assert_eq!(count, 101, "each guest load reads the device exactly once");
+    });
+}
+
+#[test]
+fn python_device_drives_its_plic_source() {
+    with_cpu(|cpu| {
+        let dev = magic_counter(cpu.py());
+        let handle = cpu.call_method1("add_device", (DEVICE_BASE, 0x1000, &dev, 20)).unwrap();
+        spin(cpu);
+        let plic = cpu.getattr("plic").unwrap();
+        let pending = || -> Vec<u32> { plic.getattr("pending").unwrap().extract().unwrap() };
+
+        let _ = handle.call_method0("raise_irq").unwrap();
+        let _ = cpu.call_method0("tick").unwrap();
+        assert_eq!(pending(), [20]);
+
+        let _ = handle.call_method0("lower_irq").unwrap();
+        let _ = cpu.call_method0("tick").unwrap();
+        assert!(pending().is_empty());
+    });
+}
+
+#[test]
+fn python_device_rejects_clashes_and_late_registration() {
+    with_cpu(|cpu| {
+        let py = cpu.py();
+        let dev = magic_counter(py);
+        let err = cpu.call_method1("add_device", (RAM_BASE + 0x1000, 16, &dev)).unwrap_err();
+        assert!(err.is_instance_of::<PyValueError>(py), "{err}");
+        assert!(err.to_string().contains("overlaps DRAM"), "{err}");
+        let err = cpu.call_method1("add_device", (DEVICE_BASE, 16, &dev, 10)).unwrap_err();
+        assert!(err.to_string().contains("used by UART0"), "{err}");
+
+        let handle = cpu.call_method1("add_device", (DEVICE_BASE, 16, &dev)).unwrap();
+        let err = handle.call_method0("raise_irq").unwrap_err();
+        assert!(err.is_instance_of::<PyRuntimeError>(py), "{err}");
+
+        spin(cpu);
+        let _ = cpu.call_method0("tick").unwrap();
+        let err = cpu.call_method1("add_device", (DEVICE_BASE + 0x100, 16, &dev)).unwrap_err();
+        assert!(err.is_instance_of::<PyRuntimeError>(py), "{err}");
+    });
+}
+
+#[test]
+fn python_device_handler_exception_stops_the_run() {
+    with_cpu(|cpu| {
+        let dev = magic_counter(cpu.py());
+        let _ = cpu.call_method1("add_device", (DEVICE_BASE, 16, &dev)).unwrap();
+        {
+            // lui a0, 0x20000; lw t0, 8(a0); j .
+            let mut c = cpu.borrow_mut();
+            for (i, inst) in [0x2000_0537, 0x0085_2283, 0x0000_006F].into_iter().enumerate() {
+                c.inner.cpu.bus.bus.write_u32(PhysAddr::new(RAM_BASE + 4 * i as u64), inst);
+            }
+            c.inner.cpu.pc = RAM_BASE;
+        }
+        let err = cpu.call_method1("run", (1000,)).unwrap_err();
+        assert!(err.is_instance_of::<PyZeroDivisionError>(cpu.py()), "{err}");
+        assert!(cpu.borrow().inner.cpu.stats.cycles < 1000);
+    });
+}
+
+#[test]
+fn mmio_trace_callback_sees_uart_writes_but_not_ram() {
+    with_cpu(|cpu| {
+        let py = cpu.py();
+        let log = pyo3::types::PyList::empty(py);
+        let locals = PyDict::new(py);
+        locals.set_item("log", &log).unwrap();
+        let record = py.eval(c"lambda *access: log.append(access)", Some(&locals), None).unwrap();
+        let _ = cpu.call_method1("set_mmio_trace", (record,)).unwrap();
+        {
+            // lui a0, 0x10000; li t0, 'H'; sb t0, 0(a0); auipc a1, 0;
+            // sw t0, 256(a1); j .
+            let program = [0x1000_0537, 0x0480_0293, 0x0055_0023, 0x0000_0597, 0x1055_A023];
+            let mut c = cpu.borrow_mut();
+            for (i, inst) in program.into_iter().chain([0x0000_006F]).enumerate() {
+                c.inner.cpu.bus.bus.write_u32(PhysAddr::new(RAM_BASE + 4 * i as u64), inst);
+            }
+            c.inner.cpu.pc = RAM_BASE;
+        }
+        let _ = run_until(cpu, RAM_BASE + 20, 1000, &[]);
+        assert_eq!(cpu.borrow_mut().inner.cpu.bus.bus.read_u32(PhysAddr::new(RAM_BASE + 268)), 72);
+
+        let log: Vec<(u64, usize, String, u64, String, u64, u64)> = log.extract().unwrap();
+        assert_eq!(log.len(), 1, "{log:?}");
+        let (cycle, hart, kind, addr, device, size, value) = &log[0];
+        assert!(*cycle > 0);
+        assert_eq!((*hart, kind.as_str(), *addr), (0, "W", 0x1000_0000));
+        assert_eq!((device.as_str(), *size, *value), ("UART0", 1, 72));
+
+        let counts = cpu.call_method0("mmio_accesses").unwrap();
+        let uart_writes: u64 =
+            counts.get_item("UART0").unwrap().get_item("writes").unwrap().extract().unwrap();
+        assert_eq!(uart_writes, 1);
+        assert!(!counts.contains("DRAM").unwrap());
+        let stats = cpu.getattr("stats").unwrap();
+        assert_eq!(stats.get_item("mmio_writes").unwrap().extract::<u64>().unwrap(), 1);
+
+        let _ = cpu.call_method1("set_mmio_trace", (py.None(),)).unwrap();
+        assert!(!cpu.borrow().inner.cpu.bus.bus.mmio_trace_enabled());
+    });
+}
+
+#[test]
+fn dma_status_is_polled_until_the_copy_completes() {
+    with_cpu(|cpu| assert!(cpu.getattr("dma").unwrap().is_none()));
+
+    let mut config = Config::default();
+    config.system.dma = true;
+    with_config(&config, |cpu| {
+        let dma_base = config.system.dma_base;
+        let (src, dst) = (RAM_BASE + 0x1000, RAM_BASE + 0x2000);
+        {
+            let mut c = cpu.borrow_mut();
+            let bus = &mut c.inner.cpu.bus.bus;
+            bus.write_u32(PhysAddr::new(RAM_BASE), 0x0000_006F); // j .
+            bus.write_u64(PhysAddr::new(src + 248), 0x1234_5678);
+            bus.write_u64(PhysAddr::new(dma_base), src);
+            bus.write_u64(PhysAddr::new(dma_base + 0x08), dst);
+            bus.write_u64(PhysAddr::new(dma_base + 0x10), 256);
+            bus.write_u64(PhysAddr::new(dma_base + 0x18), 1);
+            c.inner.cpu.pc = RAM_BASE;
+        }
+        let dma = cpu.getattr("dma").unwrap();
+        assert!(dma.getattr("busy").unwrap().extract::<bool>().unwrap());
+        let transfer: (u64, u64, u64) = dma.getattr("transfer").unwrap().extract().unwrap();
+        assert_eq!(transfer, (src, dst, 256));
+
+        let mut polls = 0;
+        while dma.getattr("busy").unwrap().extract::<bool>().unwrap() {
+            let _ = cpu.call_method1("run_for", (8,)).unwrap();
+            polls += 1;
+            assert!(polls < 100, "transfer never completed");
+        }
+        assert!(polls > 1, "256 bytes take more than one poll");
+        assert!(dma.getattr("done").unwrap().extract::<bool>().unwrap());
+        assert!(!dma.getattr("error").unwrap().extract::<bool>().unwrap());
+        assert_eq!(dma.getattr("bytes_done").unwrap().extract::<u64>().unwrap(), 256);
+        assert_eq!(
+            cpu.borrow_mut().inner.cpu.bus.bus.read_u64(PhysAddr::new(dst + 248)),
+            0x1234_5678
+        );
+    });
+}
+
+#[test]
+fn cache_access_warms_l1d_for_later_hits() {
+    let mut config = Config::default();
+    config.cache.l1_d.enabled = true;
+    with_config(&config, |cpu| {
+        let line = config.cache.l1_d.line_bytes as u64;
+        let trace: Vec<u64> = (0..8).map(|i| RAM_BASE + i * line).collect();
+        for &addr in &trace {
+            assert!(
+                !cpu.call_method1("cache_contains", (addr, "l1d"))
+                    .unwrap()
+                    .extract::<bool>()
+                    .unwrap()
+            );
+            let (hit, _): (bool, u64) =
+                cpu.call_method1("cache_access", (addr, false, "l1d")).unwrap().extract().unwrap();
+            assert!(!hit, "cold access to {addr:#x} hit");
+        }
+        for &addr in &trace {
+            assert!(
+                cpu.call_method1("cache_contains", (addr, "l1d"))
+                    .unwrap()
+                    .extract::<bool>()
+                    .unwrap()
+            );
+            let (hit, penalty): (bool, u64) = cpu
+                .call_method1("cache_access", (addr + 8, true, "l1d"))
+                .unwrap()
+                .extract()
+                .unwrap();
+            assert_eq!((hit, penalty), (true, 0), "warm access to {addr:#x}");
+        }
+        assert!(cpu.borrow().inner.cpu.l1_d_cache.contains(trace[0]));
+        assert_eq!(cpu.borrow().inner.cpu.stats.cycles, 0, "no instructions executed");
+
+        let py = cpu.py();
+        let err = cpu.call_method1("cache_access", (RAM_BASE, false, "l4")).unwrap_err();
+        assert!(err.is_instance_of::<PyValueError>(py));
+        let err = cpu.call_method1("cache_contains", (RAM_BASE, "l3")).unwrap_err();
+        assert!(err.is_instance_of::<PyValueError>(py), "L3 is disabled by default");
+    });
+}
+
+#[test]
+fn recent_traps_lists_taken_traps_as_dicts() {
+    with_cpu(|cpu| {
+        let traps = cpu.call_method0("recent_traps").unwrap();
+        assert!(traps.is_empty().unwrap());
+
+        {
+            let mut c = cpu.borrow_mut();
+            c.inner.cpu.direct_mode = false;
+            c.inner.cpu.privilege = PrivilegeMode::User;
+            c.inner.cpu.trap(&Trap::LoadPageFault(0x4000_0000), RAM_BASE + 0x40);
+        }
+        let traps = cpu.call_method0("recent_traps").unwrap();
+        assert_eq!(traps.len().unwrap(), 1);
+        let t = traps.get_item(0).unwrap();
+        let get = |k: &str| t.get_item(k).unwrap();
+        assert_eq!(get("cause").extract::<u64>().unwrap(), 13);
+        assert!(!get("interrupt").extract::<bool>().unwrap());
+        assert_eq!(get("epc").extract::<u64>().unwrap(), RAM_BASE + 0x40);
+        assert_eq!(get("tval").extract::<u64>().unwrap(), 0x4000_0000);
+        assert_eq!(get("privilege").extract::<String>().unwrap(), "U");
+        assert_eq!(get("handler").extract::<String>().unwrap(), "M");
+    });
+}
+
+#[test]
+fn copy_on_write_disks_share_a_base_file_without_modifying_it() {
+    let path = std::env::temp_dir().join(format!("rvsim-cow-{}.img", std::process::id()));
+    std::fs::write(&path, vec![0xEE; 512 * 8]).unwrap();
+    let mut config = Config::default();
+    config.system.disk_copy_on_write = true;
+
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let system = || {
+            let sim = Simulator::new(System::new(&config, path.to_str().unwrap()), &config);
+            Bound::new(py, PyCpu::from(sim)).unwrap()
+        };
+        let (a, b) = (system(), system());
+        let disk_a = a.getattr("disk").unwrap();
+        let disk_b = b.getattr("disk").unwrap();
+
+        let _ = disk_a.call_method1("write_sector", (0, PyBytes::new(py, &[0xAA; 512]))).unwrap();
+        let _ = disk_b.call_method1("write_sector", (0, PyBytes::new(py, &[0xBB; 512]))).unwrap();
+
+        let read = |disk: &Bound<'_, PyAny>, sector: u64| -> Vec<u8> {
+            disk.call_method1("read_sector", (sector,)).unwrap().extract().unwrap()
+        };
+        assert_eq!(read(&disk_a, 0), vec![0xAA; 512]);
+        assert_eq!(read(&disk_b, 0), vec![0xBB; 512]);
+        assert_eq!(read(&disk_a, 1), vec![0xEE; 512]);
+        let overlay: usize = disk_a.getattr("overlay_sectors").unwrap().extract().unwrap();
+        assert_eq!(overlay, 1);
+
+        let err = disk_a.call_method1("read_sector", (8,)).unwrap_err();
+        assert!(err.is_instance_of::<PyValueError>(py));
+        let err = disk_a.call_method1("write_sector", (0, PyBytes::new(py, &[0; 4]))).unwrap_err();
+        assert!(err.is_instance_of::<PyValueError>(py));
+    });
+
+    assert_eq!(std::fs::read(&path).unwrap(), vec![0xEE; 512 * 8], "base file modified");
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn cache_stats_match_the_raw_counters() {
+    let mut config = Config::default();
+    config.cache.l1_i.enabled = true;
+    config.cache.l1_d.enabled = true;
+    config.cache.l2.enabled = true;
+    with_config(&config, |cpu| {
+        {
+            let mut c = cpu.borrow_mut();
+            let bus = &mut c.inner.cpu.bus.bus;
+            // Load 64 consecutive doublewords, then spin.
+            let program = [
+                0x0002_B383, // ld   x7, 0(x5)
+                0x0082_8293, // addi x5, x5, 8
+                0xFFF3_0313, // addi x6, x6, -1
+                0xFE03_1AE3, // bne  x6, x0, -12
+                0x0000_006F, // j    .
+            ];
+            for (i, inst) in program.into_iter().enumerate() {
+                bus.write_u32(PhysAddr::new(RAM_BASE + 4 * i as u64), inst);
+            }
+            c.inner.cpu.pc = RAM_BASE;
+        }
+        let _ = cpu.call_method1("write_reg", (5, RAM_BASE + 0x1_0000)).unwrap();
+        let _ = cpu.call_method1("write_reg", (6, 64)).unwrap();
+        let _ = cpu.call_method1("run", (5_000,)).unwrap();
+
+        let caches = cpu.call_method0("cache_stats").unwrap();
+        let stats = cpu.borrow().inner.cpu.stats.clone();
+        assert!(stats.dcache_hits > 0 && stats.dcache_misses > 0, "workload missed L1D");
+        for (level, hits, misses) in [
+            ("l1i", stats.icache_hits, stats.icache_misses),
+            ("l1d", stats.dcache_hits, stats.dcache_misses),
+            ("l2", stats.l2_hits, stats.l2_misses),
+            ("l3", stats.l3_hits, stats.l3_misses),
+        ] {
+            let c = caches.get_item(level).unwrap();
+            assert_eq!(c.get_item("hits").unwrap().extract::<u64>().unwrap(), hits, "{level}");
+            assert_eq!(c.get_item("misses").unwrap().extract::<u64>().unwrap(), misses, "{level}");
+            let rate: f64 = c.get_item("hit_rate").unwrap().extract().unwrap();
+            let expected =
+                if hits + misses == 0 { 0.0 } else { hits as f64 / (hits + misses) as f64 };
+            assert!((rate - expected).abs() < 1e-12, "{level}: {rate} != {expected}");
+        }
+    });
+}
+
+#[test]
+fn device_list_from_dict_gives_one_uart_object_per_port() {
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let dict = py
+            .eval(
+                c"{'general': {}, 'memory': {}, 'pipeline': {},
+                  'cache': {'l1_i': {}, 'l1_d': {}, 'l2': {}, 'l3': {}},
+                  'system': {'devices': [
+                    {'type': 'Uart', 'base': 0x1000_0000, 'quiet': True},
+                    {'type': 'Uart', 'base': 0x1000_1000, 'irq': 12, 'quiet': True},
+                    {'type': 'Plic', 'base': 0x0c00_0000},
+                ]}}",
+                None,
+                None,
+            )
+            .unwrap();
+        let config = _core::conversion::py_dict_to_config(py, &dict).unwrap();
+        let inner = Simulator::new(System::new(&config, ""), &config);
+        let cpu = Bound::new(py, PyCpu::from(inner)).unwrap();
+
+        cpu.borrow_mut().inner.cpu.bus.bus.write_u8(PhysAddr::new(0x1000_1000), b'd');
+        let uarts = cpu.getattr("uarts").unwrap();
+        assert_eq!(uarts.len().unwrap(), 2);
+        let debug = uarts.get_item(1).unwrap();
+        assert_eq!(debug.getattr("index").unwrap().extract::<usize>().unwrap(), 1);
+        let out = debug.call_method0("read_output").unwrap();
+        assert_eq!(out.downcast::<PyBytes>().unwrap().as_bytes(), b"d");
+        let console = cpu.getattr("uart").unwrap().call_method0("read_output").unwrap();
+        assert!(console.downcast::<PyBytes>().unwrap().as_bytes().is_empty());
+
+        let two_plics = py.eval(c"[{'type': 'Plic', 'base': 0}] * 2", None, None).unwrap();
+        dict.get_item("system").unwrap().set_item("devices", two_plics).unwrap();
+        let err = _core::conversion::py_dict_to_config(py, &dict).unwrap_err();
+        assert!(err.is_instance_of::<PyValueError>(py), "{err}");
+    });
+}
+
+#[test]
+fn disasm_reads_and_decodes_instructions_in_memory() {
+    with_cpu(|cpu| {
+        {
+            let bus = &mut cpu.borrow_mut().inner.cpu.bus.bus;
+            // addi sp, sp, -16 ; c.addi a0, 1 ; add a0, a1, a2 (straddling a word)
+            bus.write_u32(PhysAddr::new(RAM_BASE), 0xff01_0113);
+            bus.write_u16(PhysAddr::new(RAM_BASE + 4), 0x0505);
+            bus.write_u16(PhysAddr::new(RAM_BASE + 6), 0x8533);
+            bus.write_u16(PhysAddr::new(RAM_BASE + 8), 0x00c5);
+        }
+        let disasm = |addr: u64| -> String {
+            cpu.call_method1("disasm", (addr,)).unwrap().extract().unwrap()
+        };
+        assert_eq!(disasm(RAM_BASE), "addi sp, sp, -16");
+        assert_eq!(disasm(RAM_BASE + 4), "addi a0, a0, 1");
+        assert_eq!(disasm(RAM_BASE + 6), "add a0, a1, a2");
+
+        let err = cpu.call_method1("disasm", (0x4000_0000_u64,)).unwrap_err();
+        assert!(err.is_instance_of::<PyValueError>(cpu.py()), "{err}");
+    });
+}
+
+#[test]
+fn connected_uarts_carry_bytes_between_cpus() {
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let config = Config::default();
+        let new_cpu = || {
+            let inner = Simulator::new(System::new(&config, ""), &config);
+            Bound::new(py, PyCpu::from(inner)).unwrap()
+        };
+        let (a, b) = (new_cpu(), new_cpu());
+        let a_uart = a.getattr("uart").unwrap();
+        let b_uart = b.getattr("uart").unwrap();
+        let _ = a_uart.call_method1("connect", (&b_uart, 3)).unwrap();
+        assert!(b_uart.getattr("connected").unwrap().extract::<bool>().unwrap());
+        let err = a_uart.call_method1("connect", (&a.getattr("uart").unwrap(),)).unwrap_err();
+        assert!(err.is_instance_of::<PyValueError>(py), "{err}");
+
+        let uart_base = config.system.uart_base;
+        a.borrow_mut().inner.cpu.bus.bus.write_u8(PhysAddr::new(uart_base), b'z');
+        let received = |cpu: &Bound<'_, PyCpu>| {
+            let out = cpu.borrow_mut().inner.cpu.bus.bus.read_u8(PhysAddr::new(uart_base + 5));
+            out & 1 != 0
+        };
+        for _ in 0..3 {
+            let _ = b.borrow_mut().inner.cpu.bus.tick();
+            assert!(!received(&b));
+        }
+        let _ = b.borrow_mut().inner.cpu.bus.tick();
+        assert!(received(&b));
+        assert_eq!(b.borrow_mut().inner.cpu.bus.bus.read_u8(PhysAddr::new(uart_base)), b'z');
+
+        let _ = b_uart.call_method0("disconnect").unwrap();
+        assert!(!b_uart.getattr("connected").unwrap().extract::<bool>().unwrap());
+    });
+}
+
+#[test]
+fn initial_state_starts_translated_in_supervisor_mode() {
+    const ROOT: u64 = 0x8020_0000;
+    const CODE: u64 = 0x8010_0000;
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let dict = py
+            .eval(
+                c"{'general': {}, 'system': {}, 'memory': {}, 'pipeline': {},
+                  'cache': {'l1_i': {}, 'l1_d': {}, 'l2': {}, 'l3': {}},
+                  'initial_state': {
+                    'privilege': 'Supervisor',
+                    'satp': (8 << 60) | (0x80200000 >> 12),
+                    'csrs': {0x3B0: (1 << 54) - 1, 0x3A0: 0x1F},
+                  }}",
+                None,
+                None,
+            )
+            .unwrap();
+        let cpu = py.get_type::<PyCpu>().call1((&dict,)).unwrap();
+        let cpu = cpu.downcast::<PyCpu>().unwrap();
+        {
+            let mut c = cpu.borrow_mut();
+            assert_eq!(c.inner.cpu.privilege, PrivilegeMode::Supervisor);
+            let bus = &mut c.inner.cpu.bus.bus;
+            // Sv39: VA 0x8000_0000 (VPN 2/0/0) -> PA CODE, executable.
+            bus.write_u64(PhysAddr::new(ROOT + 2 * 8), ((ROOT + 0x1000) >> 12) << 10 | 1);
+            bus.write_u64(PhysAddr::new(ROOT + 0x1000), ((ROOT + 0x2000) >> 12) << 10 | 1);
+            bus.write_u64(PhysAddr::new(ROOT + 0x2000), (CODE >> 12) << 10 | 0x4B);
+            // addi a0, zero, 42 at the physical page only.
+            bus.write_u32(PhysAddr::new(CODE), 0x02a0_0513);
+        }
+        assert_eq!(cpu.getattr("pc").unwrap().extract::<u64>().unwrap(), RAM_BASE);
+        let inst = cpu.call_method0("step").unwrap();
+        assert!(!inst.is_none(), "first instruction did not commit");
+        let a0: u64 = cpu.call_method1("read_reg", (10,)).unwrap().extract().unwrap();
+        assert_eq!(a0, 42, "fetch was not translated");
+        assert_eq!(cpu.getattr("privilege").unwrap().extract::<String>().unwrap(), "S");
+
+        let bad = py.eval(c"{'privilege': 'Supervisor', 'csrs': {0x1000: 0}}", None, None).unwrap();
+        dict.set_item("initial_state", bad).unwrap();
+        let err = py.get_type::<PyCpu>().call1((&dict,)).unwrap_err();
+        assert!(err.is_instance_of::<PyValueError>(py), "{err}");
+    });
+}
+
+#[test]
+fn checkpoint_restores_registers_and_disk_writes_into_a_new_cpu() {
+    let path = std::env::temp_dir().join(format!("rvsim-ckpt-{}.img", std::process::id()));
+    let ckpt = path.with_extension("ckpt");
+    std::fs::write(&path, vec![0xEE; 512 * 8]).unwrap();
+
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        for copy_on_write in [false, true] {
+            let mut config = Config::default();
+            config.system.disk_copy_on_write = copy_on_write;
+            let system = || {
+                let sim = Simulator::new(System::new(&config, path.to_str().unwrap()), &config);
+                Bound::new(py, PyCpu::from(sim)).unwrap()
+            };
+            let (a, b) = (system(), system());
+            let disk = a.getattr("disk").unwrap();
+            let _ = disk.call_method1("write_sector", (3, PyBytes::new(py, &[0xAA; 512]))).unwrap();
+            let _ = a.call_method1("write_reg", (9, 0x1234)).unwrap();
+            a.borrow_mut().inner.cpu.bus.bus.write_u64(PhysAddr::new(RAM_BASE + 0x2000), 77);
+            let _ = a.call_method1("checkpoint", (ckpt.to_str().unwrap(),)).unwrap();
+
+            let _ = b.call_method1("restore", (ckpt.to_str().unwrap(),)).unwrap();
+            let x9: u64 = b.call_method1("read_reg", (9,)).unwrap().extract().unwrap();
+            assert_eq!(x9, 0x1234);
+            assert_eq!(
+                b.borrow_mut().inner.cpu.bus.bus.read_u64(PhysAddr::new(RAM_BASE + 0x2000)),
+                77
+            );
+            let disk = b.getattr("disk").unwrap();
+            let read = |sector: u64| -> Vec<u8> {
+                disk.call_method1("read_sector", (sector,)).unwrap().extract().unwrap()
+            };
+            assert_eq!(read(3), vec![0xAA; 512], "copy_on_write={copy_on_write}");
+            assert_eq!(read(2), vec![0xEE; 512], "copy_on_write={copy_on_write}");
+        }
+
+        std::fs::write(&ckpt, b"not a checkpoint").unwrap();
+        with_cpu(|cpu| {
+            let err = cpu.call_method1("restore", (ckpt.to_str().unwrap(),)).unwrap_err();
+            assert!(err.is_instance_of::<PyRuntimeError>(py), "{err}");
+        });
+    });
+
+    std::fs::remove_file(&path).unwrap();
+    std::fs::remove_file(&ckpt).unwrap();
+}
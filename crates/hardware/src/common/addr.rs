@@ -6,11 +6,13 @@
 //! 2. **Address Manipulation:** Provides helper methods for extracting page offsets and raw values.
 //! 3. **MMU Integration:** Acts as the primary interface for memory translation operations.
 
+use serde::{Deserialize, Serialize};
+
 /// An Address Space Identifier (ASID) from SATP[59:44].
 ///
 /// Used by the TLB to distinguish translations belonging to different address spaces,
 /// enabling OS context switches without a full TLB flush.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
 pub struct Asid(u16);
 
 impl Asid {
@@ -51,14 +53,14 @@ impl IrqId {
 ///
 /// Represents the upper 27 bits of a 39-bit virtual address (bits 38:12),
 /// used as a TLB tag and page table index.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
 pub struct Vpn(u64);
 
 /// A Physical Page Number in the RISC-V address space.
 ///
 /// Represents the upper bits of a physical address (bits 55:12),
 /// used as TLB data and in page table entries.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
 pub struct Ppn(u64);
 
 impl Vpn {
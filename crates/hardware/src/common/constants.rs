@@ -60,7 +60,8 @@ pub const DELEG_STIP_BIT: u64 = 5;
 /// Bit mask indicating that a trap cause represents an interrupt.
 pub const CAUSE_INTERRUPT_BIT: u64 = 1 << 63;
 
-/// Maximum number of cycles before hang detection triggers.
+/// Suggested watchdog threshold (`general.hang_threshold`): cycles at one PC
+/// before a hang is reported.
 pub const HANG_DETECTION_THRESHOLD: u64 = 5000;
 
 /// Opcode for the Wait For Interrupt (WFI) instruction.
@@ -68,3 +69,7 @@ pub const WFI_INSTRUCTION: u32 = 0x10500073;
 
 /// Number of cycles between status update messages during simulation.
 pub const STATUS_UPDATE_INTERVAL: u64 = 5_000_000;
+
+/// Frequency in Hz of `mtime` as advertised in the device tree
+/// (`timebase-frequency`).
+pub const TIMEBASE_FREQ: u64 = 10_000_000;
@@ -76,6 +76,13 @@ impl CsrAddr {
     pub const fn is_read_only(self) -> bool {
         ((self.0 >> 10) & 0x3) == 0x3
     }
+
+    /// Returns `true` for the floating-point CSRs (`fflags`, `frm`, `fcsr`),
+    /// which are inaccessible while `mstatus.FS` is Off.
+    #[inline(always)]
+    pub const fn is_fp(self) -> bool {
+        matches!(self.0, 0x001..=0x003)
+    }
 }
 
 impl From<u16> for CsrAddr {
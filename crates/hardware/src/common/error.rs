@@ -316,6 +316,14 @@ pub enum LrScRecord {
         /// Physical address to check reservation against.
         paddr: crate::common::PhysAddr,
     },
+    /// AMOCAS: perform the compare-and-swap at commit.  The comparison
+    /// (`rd`) and swap (`rs2`) values are read from the architectural
+    /// register file, memory is updated only on a match, and `rd` (plus
+    /// `rd + 1` for `amocas.q`) receives the old memory value.
+    Cas {
+        /// Physical address of the compare-and-swap.
+        paddr: crate::common::PhysAddr,
+    },
 }
 
 /// Result of a virtual-to-physical address translation operation.
@@ -0,0 +1,42 @@
+//! Hex encoding of byte buffers for serialized state.
+//!
+//! Used with `#[serde(with = "crate::common::hex")]` on `Vec<u8>` fields so
+//! that checkpointed buffers (FIFOs, disk sectors, network frames) are
+//! written as one lowercase hex string instead of an array of numbers.
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serializer};
+use std::fmt::Write;
+
+/// Serializes `bytes` as a lowercase hex string.
+///
+/// # Errors
+///
+/// Returns the serializer's error if it cannot write a string.
+pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        let _ = write!(out, "{b:02x}");
+    }
+    serializer.serialize_str(&out)
+}
+
+/// Deserializes a hex string written by [`serialize`].
+///
+/// # Errors
+///
+/// Returns an error if the value is not a string of hex byte pairs.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    if !s.len().is_multiple_of(2) {
+        return Err(D::Error::custom("hex string has an odd length"));
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| {
+            s.get(i..i + 2)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| D::Error::custom(format!("invalid hex byte at offset {i}")))
+        })
+        .collect()
+}
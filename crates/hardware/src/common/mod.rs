@@ -26,12 +26,18 @@ pub mod data;
 /// Error types and trap definitions.
 pub mod error;
 
+/// Hex encoding of byte buffers for serialized state.
+pub mod hex;
+
 /// Architectural register index newtype (5-bit, 0–31).
 pub mod reg_idx;
 
 /// Register file implementation.
 pub mod reg;
 
+/// Seedable pseudo-random number generator.
+pub mod rng;
+
 /// Top-level simulator error type.
 pub mod sim_error;
 
@@ -15,7 +15,7 @@ use crate::core::arch::gpr::Gpr;
 ///
 /// This structure provides a single interface for accessing all processor registers,
 /// abstracting the underlying GPR and FPR implementations.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct RegisterFile {
     gpr: Gpr,
     fpr: Fpr,
@@ -23,14 +23,37 @@ pub enum SimError {
         source: std::io::Error,
     },
 
-    /// The simulator appears to be stuck at the same program counter for an
-    /// unusually large number of cycles.
+    /// An ELF image could not be loaded.
     ///
-    /// This is not necessarily fatal — it may be a legitimate WFI spin-wait —
-    /// but it is surfaced so callers can decide whether to abort.
+    /// The image must be a well-formed 64-bit RISC-V ELF whose loadable
+    /// segments all lie in the memory map.
+    #[error("cannot load ELF: {reason}")]
+    InvalidElf {
+        /// What is wrong with the image.
+        reason: String,
+    },
+
+    /// A checkpoint could not be written or restored.
+    ///
+    /// Restoring needs a simulator built from the same configuration (harts,
+    /// RAM banks and devices) as the one that saved the checkpoint.
+    #[error("checkpoint '{path}': {reason}")]
+    Checkpoint {
+        /// Path of the checkpoint file.
+        path: String,
+        /// What went wrong.
+        reason: String,
+    },
+
+    /// The watchdog saw the program counter stuck at the same value for
+    /// `general.hang_threshold` consecutive cycles.
+    ///
+    /// Cycles parked in WFI do not count. `report` holds the diagnostic dump:
+    /// privilege mode, pending interrupts, the last retired instructions and
+    /// the ROB and store buffer contents.
     #[error(
-        "potential hang: PC {pc:#x} unchanged for {cycle_count} cycles \
-         (instruction word: {inst:#010x})"
+        "hang detected: PC {pc:#x} unchanged for {cycle_count} cycles \
+         (instruction word: {inst:#010x})\n{report}"
     )]
     HangDetected {
         /// Program counter that has not changed.
@@ -39,6 +62,48 @@ pub enum SimError {
         inst: u32,
         /// Number of consecutive cycles spent at this PC.
         cycle_count: u64,
+        /// Diagnostic dump of the hart at the time of the hang.
+        report: String,
+    },
+
+    /// The watchdog saw the committed instructions confined to a small loop
+    /// of PCs, with no register value changed, no store, no system
+    /// instruction and no trap, for `general.hang_threshold` cycles.
+    ///
+    /// `report` lists the loop's instructions, disassembled, followed by the
+    /// same diagnostic dump as [`SimError::HangDetected`].
+    #[error(
+        "livelock detected: {} PCs looping without progress for {cycle_count} cycles\n{report}",
+        pcs.len()
+    )]
+    LivelockDetected {
+        /// PCs of the loop, in ascending order.
+        pcs: Vec<u64>,
+        /// Number of cycles spent in the loop.
+        cycle_count: u64,
+        /// The loop's disassembly and the diagnostic dump of the hart.
+        report: String,
+    },
+
+    /// The watchdog cycle budget `general.max_cycles` ran out before the
+    /// program exited.
+    ///
+    /// `report` holds the same diagnostic dump as [`SimError::HangDetected`].
+    #[error("hang detected: no exit after {cycles} cycles\n{report}")]
+    CycleLimitExceeded {
+        /// Cycle count at which the watchdog fired.
+        cycles: u64,
+        /// Diagnostic dump of the hart at the time of the hang.
+        report: String,
+    },
+
+    /// The guest asked to reboot more than `general.max_reboots` times.
+    #[error("reboot limit exceeded: guest asked for reboot {reboots} (limit {limit})")]
+    RebootLimitExceeded {
+        /// Number of reboots requested, including the refused one.
+        reboots: u64,
+        /// Configured `general.max_reboots`.
+        limit: u64,
     },
 
     /// A kernel panic was detected via the `tohost`/panic sentinel mechanism.
@@ -50,3 +115,16 @@ pub enum SimError {
         cycle: u64,
     },
 }
+
+impl SimError {
+    /// Returns `true` if the watchdog fired ([`SimError::HangDetected`],
+    /// [`SimError::LivelockDetected`] or [`SimError::CycleLimitExceeded`]).
+    pub const fn is_hang(&self) -> bool {
+        matches!(
+            self,
+            Self::HangDetected { .. }
+                | Self::LivelockDetected { .. }
+                | Self::CycleLimitExceeded { .. }
+        )
+    }
+}
@@ -8,9 +8,14 @@
 //!
 //! Configuration is supplied via JSON from the Python API (`SimConfig`) or use `Config::default()` for the CLI.
 
-use crate::core::pipeline::backend::o3::fu_pool::FuConfig;
+use crate::core::arch::mode::PrivilegeMode;
+use crate::core::pipeline::backend::o3::fu_pool::{FuConfig, LatencyTable, PortConfig};
 use crate::core::pipeline::engine::BackendType;
+use crate::core::pipeline::frontend::fusion::FusionPattern;
 use serde::Deserialize;
+use std::collections::BTreeMap;
+
+pub use defaults::MAX_UARTS;
 
 /// Default configuration constants for the simulator.
 ///
@@ -41,12 +46,33 @@ mod defaults {
     /// Base address of `VirtIO` block device MMIO region.
     pub const DISK_BASE: u64 = 0x9000_0000;
 
+    /// Base address of `VirtIO` network device MMIO region.
+    pub const NET_BASE: u64 = 0x9000_1000;
+
+    /// Base address of `VirtIO` entropy device MMIO region.
+    pub const RNG_BASE: u64 = 0x9000_2000;
+
+    /// Base address of the DMA controller MMIO region.
+    pub const DMA_BASE: u64 = 0x9000_3000;
+
+    /// Bytes the DMA controller moves per cycle.
+    pub const DMA_BYTES_PER_CYCLE: u64 = 8;
+
     /// Base address of CLINT (Core Local Interruptor) timer MMIO region.
     pub const CLINT_BASE: u64 = 0x0200_0000;
 
     /// Base address of system controller (power/reset) MMIO region.
     pub const SYSCON_BASE: u64 = 0x0010_0000;
 
+    /// Base address of PLIC (Platform-Level Interrupt Controller) MMIO region.
+    pub const PLIC_BASE: u64 = 0x0c00_0000;
+
+    /// Base address of Goldfish RTC MMIO region.
+    pub const RTC_BASE: u64 = 0x0010_1000;
+
+    /// Maximum number of UARTs on the bus.
+    pub const MAX_UARTS: usize = 4;
+
     /// System bus width in bytes (8 bytes = 64-bit bus).
     ///
     /// Determines the maximum transfer size per bus transaction.
@@ -62,16 +88,27 @@ mod defaults {
     /// Divides the simulation cycle counter to produce the machine timer value.
     pub const CLINT_DIVIDER: u64 = 10;
 
+    /// Number of harts (hardware threads) in the system.
+    ///
+    /// Single-hart by default; multi-hart systems share RAM and devices.
+    pub const NUM_HARTS: usize = 1;
+
     /// CAS (Column Access Strobe) latency in DRAM cycles.
     ///
     /// Time from column address assertion to data availability for reads.
     pub const T_CAS: u64 = 14;
 
-    /// RAS (Row Access Strobe) latency in DRAM cycles.
+    /// RAS (Row Access Strobe) time in DRAM cycles.
     ///
-    /// Time required to activate a DRAM row before column access.
+    /// Minimum time a row stays open after activation before it can be
+    /// precharged.
     pub const T_RAS: u64 = 14;
 
+    /// RCD (RAS to CAS Delay) in DRAM cycles.
+    ///
+    /// Time required to activate a DRAM row before column access.
+    pub const T_RCD: u64 = 14;
+
     /// Precharge latency in DRAM cycles.
     ///
     /// Time required to close an active row before opening a new one.
@@ -86,6 +123,12 @@ mod defaults {
     /// Number of DRAM banks per rank (default 8, typical DDR3/DDR4).
     pub const NUM_BANKS: usize = 8;
 
+    /// Number of DRAM channels.
+    pub const NUM_CHANNELS: usize = 1;
+
+    /// Number of bank groups per channel.
+    pub const NUM_BANK_GROUPS: usize = 1;
+
     /// Row-to-Row Delay (different bank) in DRAM cycles.
     ///
     /// Minimum time between ACT commands to different banks.
@@ -105,6 +148,14 @@ mod defaults {
     /// are unavailable.
     pub const T_RFC: u64 = 350;
 
+    /// Cycles a queued DRAM request may be bypassed by row hits before the
+    /// FR-FCFS scheduler serves it in arrival order.
+    pub const DRAM_STARVATION_LIMIT: u64 = 200;
+
+    /// Queued DRAM writes at which the scheduler stops favouring reads and
+    /// drains writes.
+    pub const DRAM_WRITE_DRAIN_THRESHOLD: usize = 8;
+
     /// Translation Lookaside Buffer entry count (L1).
     ///
     /// Number of virtual-to-physical address translations cached in each L1 TLB.
@@ -163,6 +214,12 @@ mod defaults {
     /// Default Reorder Buffer size (64 entries).
     pub const ROB_SIZE: usize = 64;
 
+    /// Default fetch target queue size (4 fetch blocks).
+    pub const FTQ_SIZE: usize = 4;
+
+    /// Default fetch buffer size (16 instructions).
+    pub const FETCH_BUFFER_SIZE: usize = 16;
+
     /// Default Store Buffer size (16 entries).
     pub const STORE_BUFFER_SIZE: usize = 16;
 
@@ -208,6 +265,12 @@ mod defaults {
 
     /// Default Tournament predictor local prediction table size (log2, 1024 entries).
     pub const TOURNAMENT_LOCAL_PRED_BITS: usize = 10;
+
+    /// Default core clock frequency (1 GHz), used to convert bandwidth to GB/s.
+    pub const CLOCK_MHZ: u64 = 1000;
+
+    /// Default length of a bandwidth timeline interval (10K cycles).
+    pub const BANDWIDTH_INTERVAL: u64 = 10_000;
 }
 
 /// Memory controller implementation types.
@@ -231,6 +294,85 @@ pub enum MemoryController {
     Dram,
 }
 
+/// Host storage behind guest RAM.
+///
+/// Selects how each RAM bank's `DramBuffer` is allocated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum RamBacking {
+    /// Anonymous demand-paged `mmap`: host pages are allocated only when
+    /// first touched. Falls back to `Heap` where `mmap` is unavailable.
+    #[default]
+    Anonymous,
+    /// `mmap` of the file at `memory.ram_file`, banks laid out one after
+    /// another at page-aligned offsets; guest writes reach the file.
+    File,
+    /// Zeroed heap allocation of the whole bank up front.
+    Heap,
+}
+
+/// Error-correcting code checked on RAM loads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum Ecc {
+    /// No ECC: flipped bits reach the load unnoticed.
+    #[default]
+    None,
+    /// SEC-DED per 64-bit word: a single flipped bit is corrected and
+    /// scrubbed, two or more raise a load access fault.
+    SecDed,
+}
+
+/// A bit flip scheduled for a given cycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct InjectedFault {
+    /// Physical address of the byte to corrupt
+    pub addr: u64,
+    /// Bit of the byte to flip (0-7)
+    pub bit: u8,
+    /// Cycle at which the bit flips
+    pub cycle: u64,
+}
+
+/// RAM fault injection and ECC modelling.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FaultInjectionConfig {
+    /// Probability that a RAM load flips one random bit of the bytes it
+    /// reads before ECC sees them (0 = never).
+    #[serde(default)]
+    pub rate: f64,
+
+    /// Seed of the generator drawing random flips; equal seeds give the
+    /// same flips.
+    #[serde(default)]
+    pub seed: u64,
+
+    /// Explicit bit flips.
+    #[serde(default)]
+    pub faults: Vec<InjectedFault>,
+
+    /// ECC checked on RAM loads. Default: `None`.
+    #[serde(default)]
+    pub ecc: Ecc,
+}
+
+/// DRAM request scheduling policy.
+///
+/// Selects how the DRAM controller orders the requests that are waiting
+/// for a bank.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum DramScheduler {
+    /// Requests are serviced as they arrive, waiting for their bank without
+    /// a queue.
+    #[default]
+    Immediate,
+    /// Queued requests are issued strictly in arrival order.
+    #[serde(alias = "FCFS")]
+    Fcfs,
+    /// First-Ready, First-Come-First-Served: a queued row hit may bypass
+    /// older requests to the same channel, up to `starvation_limit`.
+    #[serde(alias = "FR-FCFS", alias = "FRFCFS")]
+    FrFcfs,
+}
+
 /// Cache replacement policy algorithms.
 ///
 /// Specifies the algorithm used to select which cache line to evict
@@ -379,6 +521,92 @@ pub enum MemDepPredictor {
     StoreSet,
 }
 
+/// Result forwarding network of the in-order backend.
+///
+/// Controls how soon a consumer can read a result that has not yet reached
+/// the reorder buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub enum BypassModel {
+    /// No bypass network.
+    ///
+    /// Consumers wait until the producer has written back. This is the
+    /// default.
+    #[default]
+    None,
+    /// Full bypass network.
+    ///
+    /// Results are forwarded from the Memory1 and Memory2 latches, and a
+    /// single-cycle producer can feed a consumer issued in the same group.
+    /// A load's data is only forwarded once it leaves Memory2, so a
+    /// dependent instruction always waits at least one cycle behind it.
+    Full,
+}
+
+/// Output format of the pipeline visualization trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub enum PipeTraceFormat {
+    /// Konata's native Kanata 0004 log. This is the default.
+    #[default]
+    Konata,
+    /// gem5 `O3PipeView` records (also readable by Konata).
+    O3PipeView,
+}
+
+/// Output format of the physical memory access trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub enum AccessTraceFormat {
+    /// Delta- and varint-encoded binary records. This is the default.
+    #[default]
+    Binary,
+    /// One comma-separated line per record.
+    Csv,
+}
+
+/// What happens to the statistics when the guest reboots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub enum RebootStats {
+    /// Keep counting across boots. This is the default.
+    #[default]
+    Accumulate,
+    /// Zero every hart's statistics when a reboot starts the next boot.
+    Reset,
+}
+
+/// Handling of misaligned (non-naturally-aligned) loads and stores.
+///
+/// Atomics (LR/SC/AMO) always trap when misaligned, whatever the policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub enum MisalignedPolicy {
+    /// Raise `LoadAddressMisaligned` / `StoreAddressMisaligned` with `mtval`
+    /// set to the address, leaving emulation to software (spike's default).
+    /// This is the default.
+    #[default]
+    Trap,
+    /// Handle the access in hardware. An access crossing an 8-byte boundary
+    /// is split into aligned sub-accesses whose results are combined, at the
+    /// cost of extra cycles. Accesses spanning two pages that are not
+    /// physically contiguous still trap.
+    Emulate,
+}
+
+/// Host side of the `VirtIO` network device.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub enum NetBackend {
+    /// Frames sent by the guest are queued for the host to collect, and only
+    /// frames injected by the host are received. Fully deterministic.
+    Capture,
+    /// Every frame sent by the guest is received back by the guest.
+    Loopback,
+    /// Frames are exchanged with the named host TAP interface (Linux only).
+    Tap(String),
+}
+
 /// Root configuration structure containing all simulator settings.
 ///
 /// Configuration is supplied by the Python API (`SimConfig.to_dict()` → JSON) or
@@ -481,6 +709,186 @@ pub struct Config {
     pub cache: CacheHierarchyConfig,
     /// Pipeline and branch predictor configuration
     pub pipeline: PipelineConfig,
+    /// Hart state to start from, applied once the program is loaded
+    #[serde(default)]
+    pub initial_state: InitialStateConfig,
+}
+
+/// Initial hart state overriding the reset state, for tests that start
+/// outside Machine mode or with translation already enabled.
+///
+/// Applied with [`Cpu::apply_initial_state`](crate::core::Cpu::apply_initial_state)
+/// after the program is loaded. CSRs are written as by `csrw`, so WARL fields
+/// are masked.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct InitialStateConfig {
+    /// Privilege mode to start in (`None` = Machine)
+    #[serde(default)]
+    pub privilege: Option<PrivilegeMode>,
+    /// Initial `mstatus`
+    #[serde(default)]
+    pub mstatus: Option<u64>,
+    /// Initial `satp`, written last so that the other CSRs (e.g. PMP) are in
+    /// place when translation starts
+    #[serde(default)]
+    pub satp: Option<u64>,
+    /// Further CSRs to write, by 12-bit address, in ascending address order
+    #[serde(default)]
+    pub csrs: BTreeMap<u16, u64>,
+}
+
+impl Config {
+    /// Returns the RAM banks of the physical address map, in ascending
+    /// address order.
+    ///
+    /// Without explicit `memory.regions` this is a single bank of
+    /// `memory.ram_size` bytes at `system.ram_base`.
+    pub fn ram_regions(&self) -> Vec<MemoryRegion> {
+        let mut regions = if self.memory.regions.is_empty() {
+            vec![MemoryRegion { base: self.system.ram_base, size: self.memory.ram_size }]
+        } else {
+            self.memory.regions.clone()
+        };
+        regions.sort_by_key(|r| r.base);
+        regions
+    }
+
+    /// Returns the MMIO devices to build, in the order they are listed.
+    ///
+    /// Without explicit `system.devices` this is the standard set: UART,
+    /// `VirtIO` block and entropy devices, CLINT, PLIC, `SysCon` and Goldfish
+    /// RTC, plus the DMA controller if `system.dma` is set and the `VirtIO`
+    /// network device if `system.net` is, configured from the per-device
+    /// `system` fields.
+    pub fn devices(&self) -> Vec<DeviceConfig> {
+        let s = &self.system;
+        if !s.devices.is_empty() {
+            return s.devices.clone();
+        }
+        let device = |kind: DeviceKind, base: u64| DeviceConfig { kind, base, irq: None };
+        let mut devices = vec![
+            device(
+                DeviceKind::Uart { to_stderr: s.uart_to_stderr, quiet: s.uart_quiet },
+                s.uart_base,
+            ),
+            device(
+                DeviceKind::VirtioBlock {
+                    write_cache_sectors: s.disk_write_cache_sectors,
+                    write_latency: s.disk_write_latency,
+                    copy_on_write: s.disk_copy_on_write,
+                },
+                s.disk_base,
+            ),
+            device(DeviceKind::VirtioRng { seed: s.rng_seed }, s.rng_base),
+            device(DeviceKind::Clint, s.clint_base),
+            device(DeviceKind::Plic, defaults::PLIC_BASE),
+            device(DeviceKind::SysCon, s.syscon_base),
+            device(DeviceKind::GoldfishRtc, defaults::RTC_BASE),
+        ];
+        if s.dma {
+            devices.push(device(
+                DeviceKind::Dma { bytes_per_cycle: s.dma_bytes_per_cycle },
+                s.dma_base,
+            ));
+        }
+        if let Some(backend) = &s.net {
+            devices.push(device(DeviceKind::VirtioNet { backend: backend.clone() }, s.net_base));
+        }
+        devices
+    }
+
+    /// Checks that the device list has at most [`MAX_UARTS`] UARTs, at most
+    /// one device of every other type, and no two devices raising the same
+    /// PLIC source, which must be between 1 and 63.
+    ///
+    /// Overlapping address ranges are reported when the system is built (see
+    /// [`System::try_new`](crate::soc::System::try_new)), since only the devices
+    /// know their sizes.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the first problem found.
+    pub fn validate_devices(&self) -> Result<(), String> {
+        let devices = self.devices();
+        let uarts = devices.iter().filter(|d| matches!(d.kind, DeviceKind::Uart { .. })).count();
+        if uarts > MAX_UARTS {
+            return Err(format!("{uarts} UARTs listed; at most {MAX_UARTS} are supported"));
+        }
+        for (i, dev) in devices.iter().enumerate() {
+            let earlier = &devices[..i];
+            if !matches!(dev.kind, DeviceKind::Uart { .. })
+                && earlier.iter().any(|d| d.kind.name() == dev.kind.name())
+            {
+                return Err(format!("more than one {} device listed", dev.kind.name()));
+            }
+            let Some(irq) = dev.irq() else { continue };
+            if !(1..64).contains(&irq) {
+                return Err(format!(
+                    "{} at {:#x} uses interrupt {irq}; PLIC sources are 1 to 63",
+                    dev.kind.name(),
+                    dev.base
+                ));
+            }
+            if let Some(other) = earlier.iter().find(|d| d.irq() == Some(irq)) {
+                return Err(format!(
+                    "{} at {:#x} and {} at {:#x} both use interrupt {irq}",
+                    other.kind.name(),
+                    other.base,
+                    dev.kind.name(),
+                    dev.base
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the RAM banks are non-empty, do not overlap or wrap the
+    /// address space, that `system.ram_base` lies in one of them, that
+    /// file-backed RAM names its file, and that injected faults are valid
+    /// bit flips inside RAM.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the first problem found.
+    pub fn validate_memory_map(&self) -> Result<(), String> {
+        let regions = self.ram_regions();
+        for r in &regions {
+            if r.size == 0 {
+                return Err(format!("memory region at {:#x} is empty", r.base));
+            }
+            if r.base.checked_add(r.size as u64).is_none() {
+                return Err(format!("memory region at {:#x} wraps the address space", r.base));
+            }
+        }
+        for pair in regions.windows(2) {
+            if pair[0].end() > pair[1].base {
+                return Err(format!(
+                    "memory regions at {:#x} and {:#x} overlap",
+                    pair[0].base, pair[1].base
+                ));
+            }
+        }
+        let ram_base = self.system.ram_base;
+        if !regions.iter().any(|r| ram_base >= r.base && ram_base < r.end()) {
+            return Err(format!("ram_base {ram_base:#x} is not inside a memory region"));
+        }
+        if self.memory.ram_backing == RamBacking::File && self.memory.ram_file.is_none() {
+            return Err("ram_backing File needs a ram_file".to_string());
+        }
+        let faults = &self.memory.fault_injection;
+        if !(0.0..=1.0).contains(&faults.rate) {
+            return Err(format!("fault rate {} is not a probability", faults.rate));
+        }
+        for f in &faults.faults {
+            if f.bit > 7 {
+                return Err(format!("fault at {:#x} flips bit {}; bits are 0 to 7", f.addr, f.bit));
+            }
+            if !regions.iter().any(|r| f.addr >= r.base && f.addr < r.end()) {
+                return Err(format!("fault at {:#x} is not inside a memory region", f.addr));
+            }
+        }
+        Ok(())
+    }
 }
 
 /// General simulation settings and options.
@@ -488,11 +896,22 @@ pub struct Config {
 /// Contains high-level simulation configuration such as tracing,
 /// initial program counter, and direct (bare-metal) execution mode.
 #[derive(Debug, Clone, Deserialize)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct GeneralConfig {
     /// Enable instruction tracing to stderr and debug output (hang detection, status updates, mode switches)
     #[serde(default)]
     pub trace_instructions: bool,
 
+    /// Trace region start: tracing and the pipeline trace turn on when the
+    /// instruction at this PC commits. `None` starts the run inside the region.
+    #[serde(default)]
+    pub trace_start_pc: Option<u64>,
+
+    /// Trace region end: tracing and the pipeline trace turn off when the
+    /// instruction at this PC commits, before it is traced.
+    #[serde(default)]
+    pub trace_stop_pc: Option<u64>,
+
     /// Initial PC value (defaults to RAM base)
     #[serde(default = "GeneralConfig::default_start_pc")]
     pub start_pc: u64,
@@ -505,6 +924,106 @@ pub struct GeneralConfig {
     /// Initial stack pointer (only used when `direct_mode` is true). Defaults to `ram_base` + 16MiB if not set.
     #[serde(default)]
     pub initial_sp: Option<u64>,
+
+    /// Pipeline visualization trace file; every instruction's stage cycles are logged when set.
+    #[serde(default)]
+    pub pipe_trace: Option<String>,
+
+    /// Format of the pipeline visualization trace.
+    #[serde(default)]
+    pub pipe_trace_format: PipeTraceFormat,
+
+    /// MMIO trace file; every bus access to a device other than RAM is logged when set.
+    #[serde(default)]
+    pub mmio_trace: Option<String>,
+
+    /// Physical memory access trace file; committed loads and stores and
+    /// I-cache fetch misses are logged when set.
+    #[serde(default)]
+    pub access_trace: Option<String>,
+
+    /// Format of the physical memory access trace.
+    #[serde(default)]
+    pub access_trace_format: AccessTraceFormat,
+
+    /// Keep one access trace record in this many (1 = every access).
+    #[serde(default = "GeneralConfig::default_access_trace_sample")]
+    pub access_trace_sample: u64,
+
+    /// Sample ROB, store buffer, and issue queue occupancy histograms every cycle.
+    /// Disable for maximum simulation speed.
+    #[serde(default = "GeneralConfig::default_sample_occupancy")]
+    pub sample_occupancy: bool,
+
+    /// Watchdog: fail with a hang report once the PC has not changed for this
+    /// many consecutive cycles (cycles parked in WFI excluded), or the
+    /// committed instructions have looped over a few PCs without progress for
+    /// as long. `None` disables it.
+    #[serde(default)]
+    pub hang_threshold: Option<u64>,
+
+    /// Watchdog: fail with a hang report once this many cycles have been
+    /// simulated without the program exiting. `None` disables it.
+    #[serde(default)]
+    pub max_cycles: Option<u64>,
+
+    /// Fail once the guest asks to reboot more than this many times. `None`
+    /// allows any number of reboots.
+    #[serde(default)]
+    pub max_reboots: Option<u64>,
+
+    /// Whether statistics accumulate across reboots or restart each boot.
+    #[serde(default)]
+    pub reboot_stats: RebootStats,
+
+    /// Service RISC-V semihosting calls (`slli x0,x0,0x1f; ebreak; srai x0,x0,7`)
+    /// on the host instead of taking the breakpoint trap.
+    #[serde(default)]
+    pub semihosting: bool,
+
+    /// Directory that semihosting `SYS_OPEN` resolves file names in. `None`
+    /// refuses every file open.
+    #[serde(default)]
+    pub semihosting_root: Option<String>,
+
+    /// Syscall emulation: in direct mode, service an `ecall` as a Linux
+    /// system call on the host so statically linked user programs run
+    /// without a kernel.
+    #[serde(default)]
+    pub syscall_emulation: bool,
+
+    /// Directory that emulated `openat` resolves paths in, absolute paths
+    /// included. `None` refuses every file open.
+    #[serde(default)]
+    pub syscall_root: Option<String>,
+
+    /// Program arguments for syscall emulation, `argv[0]` first.
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    /// Core clock frequency in MHz; only used to report bandwidth in GB/s.
+    #[serde(default = "GeneralConfig::default_clock_mhz")]
+    pub clock_mhz: u64,
+
+    /// Cycles per interval of the cache/DRAM bandwidth timeline. 0 disables
+    /// the timeline; totals are always kept.
+    #[serde(default = "GeneralConfig::default_bandwidth_interval")]
+    pub bandwidth_interval: u64,
+
+    /// Fast-forward: execute this many instructions functionally, without
+    /// the pipeline, before detailed simulation starts. 0 disables it.
+    #[serde(default)]
+    pub fast_forward_insts: u64,
+
+    /// Fast-forward: switch to detailed simulation once hart 0 reaches this
+    /// PC, if that happens before `fast_forward_insts` instructions.
+    #[serde(default)]
+    pub switch_pc: Option<u64>,
+
+    /// Look up fast-forwarded fetches and data accesses in the caches, so
+    /// they are warm when detailed simulation starts. TLBs always fill.
+    #[serde(default)]
+    pub fast_forward_warm: bool,
 }
 
 impl GeneralConfig {
@@ -517,15 +1036,58 @@ impl GeneralConfig {
     const fn default_direct_mode() -> bool {
         true
     }
+
+    /// Occupancy sampling is on by default.
+    const fn default_sample_occupancy() -> bool {
+        true
+    }
+
+    /// The access trace keeps every record by default.
+    const fn default_access_trace_sample() -> u64 {
+        1
+    }
+
+    /// Returns the default core clock frequency in MHz.
+    const fn default_clock_mhz() -> u64 {
+        defaults::CLOCK_MHZ
+    }
+
+    /// Returns the default bandwidth timeline interval in cycles.
+    const fn default_bandwidth_interval() -> u64 {
+        defaults::BANDWIDTH_INTERVAL
+    }
 }
 
 impl Default for GeneralConfig {
     fn default() -> Self {
         Self {
             trace_instructions: false,
+            trace_start_pc: None,
+            trace_stop_pc: None,
             start_pc: defaults::RAM_BASE,
             direct_mode: true,
             initial_sp: None,
+            pipe_trace: None,
+            pipe_trace_format: PipeTraceFormat::Konata,
+            mmio_trace: None,
+            access_trace: None,
+            access_trace_format: AccessTraceFormat::Binary,
+            access_trace_sample: Self::default_access_trace_sample(),
+            sample_occupancy: true,
+            hang_threshold: None,
+            max_cycles: None,
+            max_reboots: None,
+            reboot_stats: RebootStats::Accumulate,
+            semihosting: false,
+            semihosting_root: None,
+            syscall_emulation: false,
+            syscall_root: None,
+            args: Vec::new(),
+            clock_mhz: defaults::CLOCK_MHZ,
+            bandwidth_interval: defaults::BANDWIDTH_INTERVAL,
+            fast_forward_insts: 0,
+            switch_pc: None,
+            fast_forward_warm: false,
         }
     }
 }
@@ -535,6 +1097,7 @@ impl Default for GeneralConfig {
 /// Defines memory-mapped I/O base addresses, RAM configuration,
 /// and system bus parameters.
 #[derive(Debug, Clone, Deserialize)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct SystemConfig {
     /// UART MMIO base address
     #[serde(default = "SystemConfig::default_uart_base")]
@@ -544,6 +1107,51 @@ pub struct SystemConfig {
     #[serde(default = "SystemConfig::default_disk_base")]
     pub disk_base: u64,
 
+    /// `VirtIO` disk write cache capacity in sectors; writes are acknowledged
+    /// once cached and written back on flush or when the cache is full
+    /// (0 = write-through)
+    #[serde(default)]
+    pub disk_write_cache_sectors: usize,
+
+    /// Cycles to write one sector to the `VirtIO` disk image
+    #[serde(default)]
+    pub disk_write_latency: u64,
+
+    /// Keep `VirtIO` disk writes in a per-simulation overlay instead of the
+    /// loaded image, which is then shared by every simulation in the process
+    /// that opens the same file
+    #[serde(default)]
+    pub disk_copy_on_write: bool,
+
+    /// `VirtIO` network device MMIO base address
+    #[serde(default = "SystemConfig::default_net_base")]
+    pub net_base: u64,
+
+    /// `VirtIO` network device backend (`None` = no network device)
+    #[serde(default)]
+    pub net: Option<NetBackend>,
+
+    /// `VirtIO` entropy device MMIO base address
+    #[serde(default = "SystemConfig::default_rng_base")]
+    pub rng_base: u64,
+
+    /// Seed of the `VirtIO` entropy device's generator; equal seeds give the
+    /// guest identical random bytes
+    #[serde(default)]
+    pub rng_seed: u64,
+
+    /// Attach the memory-to-memory DMA controller
+    #[serde(default)]
+    pub dma: bool,
+
+    /// DMA controller MMIO base address
+    #[serde(default = "SystemConfig::default_dma_base")]
+    pub dma_base: u64,
+
+    /// Bytes the DMA controller copies per cycle
+    #[serde(default = "SystemConfig::default_dma_bytes_per_cycle")]
+    pub dma_bytes_per_cycle: u64,
+
     /// Main RAM base address
     #[serde(default = "SystemConfig::default_ram_base")]
     pub ram_base: u64,
@@ -572,6 +1180,11 @@ pub struct SystemConfig {
     #[serde(default = "SystemConfig::default_clint_divider")]
     pub clint_divider: u64,
 
+    /// Number of harts; each has its own registers, CSRs and pipeline but
+    /// shares RAM and devices through the bus.
+    #[serde(default = "SystemConfig::default_num_harts")]
+    pub num_harts: usize,
+
     /// When true, UART output goes to stderr (for visibility when run from Python).
     #[serde(default)]
     pub uart_to_stderr: bool,
@@ -584,6 +1197,120 @@ pub struct SystemConfig {
     /// registered at this address to intercept riscv-tests pass/fail writes.
     #[serde(default)]
     pub tohost_addr: u64,
+
+    /// MMIO devices to put on the bus. Empty (the default) builds the standard
+    /// set from the per-device fields above; otherwise those fields are
+    /// ignored and exactly these devices are built.
+    #[serde(default)]
+    pub devices: Vec<DeviceConfig>,
+}
+
+/// An MMIO device on the system bus, as listed in [`SystemConfig::devices`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct DeviceConfig {
+    /// Device type (the `type` key) and its type-specific options.
+    #[serde(flatten)]
+    pub kind: DeviceKind,
+
+    /// MMIO base address
+    pub base: u64,
+
+    /// PLIC source the device raises (`None` = the type's default); ignored
+    /// by devices without an interrupt
+    #[serde(default)]
+    pub irq: Option<u32>,
+}
+
+impl DeviceConfig {
+    /// Returns the PLIC source the device raises, or `None` if it has no interrupt.
+    pub fn irq(&self) -> Option<u32> {
+        self.kind.default_irq().map(|default| self.irq.unwrap_or(default))
+    }
+}
+
+/// Type of a bus device, with its type-specific options.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type")]
+pub enum DeviceKind {
+    /// 16550 UART. The first one listed is the console (`UART0`), which
+    /// also reads host stdin; later ones are `UART1`, `UART2`, ...
+    Uart {
+        /// Write output to stderr instead of stdout
+        #[serde(default)]
+        to_stderr: bool,
+        /// Discard output instead of echoing it
+        #[serde(default)]
+        quiet: bool,
+    },
+    /// `VirtIO` block device, backed by the disk image.
+    VirtioBlock {
+        /// Write cache capacity in sectors (0 = write-through)
+        #[serde(default)]
+        write_cache_sectors: usize,
+        /// Cycles to write one sector to the image
+        #[serde(default)]
+        write_latency: u64,
+        /// Keep writes in a per-simulation overlay
+        #[serde(default)]
+        copy_on_write: bool,
+    },
+    /// `VirtIO` network device.
+    VirtioNet {
+        /// Host side of the device
+        backend: NetBackend,
+    },
+    /// `VirtIO` entropy device.
+    VirtioRng {
+        /// Seed of the generator
+        #[serde(default)]
+        seed: u64,
+    },
+    /// Core-local interruptor (timer and software interrupts); the timer
+    /// divider is `system.clint_divider`.
+    Clint,
+    /// Platform-level interrupt controller.
+    Plic,
+    /// System controller (poweroff and reboot).
+    SysCon,
+    /// Goldfish real-time clock.
+    GoldfishRtc,
+    /// Memory-to-memory DMA controller.
+    Dma {
+        /// Bytes copied per cycle
+        #[serde(default = "SystemConfig::default_dma_bytes_per_cycle")]
+        bytes_per_cycle: u64,
+    },
+}
+
+impl DeviceKind {
+    /// Returns the type name, as given in the `type` key.
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Uart { .. } => "Uart",
+            Self::VirtioBlock { .. } => "VirtioBlock",
+            Self::VirtioNet { .. } => "VirtioNet",
+            Self::VirtioRng { .. } => "VirtioRng",
+            Self::Clint => "Clint",
+            Self::Plic => "Plic",
+            Self::SysCon => "SysCon",
+            Self::GoldfishRtc => "GoldfishRtc",
+            Self::Dma { .. } => "Dma",
+        }
+    }
+
+    /// Returns the PLIC source the device raises by default, or `None` if it
+    /// has no interrupt.
+    pub const fn default_irq(&self) -> Option<u32> {
+        match self {
+            Self::VirtioBlock { .. } => Some(1),
+            Self::VirtioNet { .. } => Some(2),
+            Self::VirtioRng { .. } => Some(3),
+            Self::Dma { .. } => Some(4),
+            Self::Uart { .. } => Some(10),
+            Self::GoldfishRtc => Some(11),
+            Self::Clint | Self::Plic | Self::SysCon => None,
+        }
+    }
 }
 
 impl SystemConfig {
@@ -597,6 +1324,26 @@ impl SystemConfig {
         defaults::DISK_BASE
     }
 
+    /// Returns the default `VirtIO` network device MMIO base address.
+    const fn default_net_base() -> u64 {
+        defaults::NET_BASE
+    }
+
+    /// Returns the default `VirtIO` entropy device MMIO base address.
+    const fn default_rng_base() -> u64 {
+        defaults::RNG_BASE
+    }
+
+    /// Returns the default DMA controller MMIO base address.
+    const fn default_dma_base() -> u64 {
+        defaults::DMA_BASE
+    }
+
+    /// Returns the default DMA controller copy rate.
+    const fn default_dma_bytes_per_cycle() -> u64 {
+        defaults::DMA_BYTES_PER_CYCLE
+    }
+
     /// Returns the default RAM base address.
     const fn default_ram_base() -> u64 {
         defaults::RAM_BASE
@@ -631,6 +1378,11 @@ impl SystemConfig {
     const fn default_clint_divider() -> u64 {
         defaults::CLINT_DIVIDER
     }
+
+    /// Returns the default number of harts.
+    const fn default_num_harts() -> usize {
+        defaults::NUM_HARTS
+    }
 }
 
 impl Default for SystemConfig {
@@ -642,6 +1394,16 @@ impl Default for SystemConfig {
         Self {
             uart_base: defaults::UART_BASE,
             disk_base: defaults::DISK_BASE,
+            disk_write_cache_sectors: 0,
+            disk_write_latency: 0,
+            disk_copy_on_write: false,
+            net_base: defaults::NET_BASE,
+            net: None,
+            rng_base: defaults::RNG_BASE,
+            rng_seed: 0,
+            dma: false,
+            dma_base: defaults::DMA_BASE,
+            dma_bytes_per_cycle: defaults::DMA_BYTES_PER_CYCLE,
             ram_base: defaults::RAM_BASE,
             clint_base: defaults::CLINT_BASE,
             syscon_base: defaults::SYSCON_BASE,
@@ -649,13 +1411,31 @@ impl Default for SystemConfig {
             bus_width: defaults::BUS_WIDTH,
             bus_latency: defaults::BUS_LATENCY,
             clint_divider: defaults::CLINT_DIVIDER,
+            num_harts: defaults::NUM_HARTS,
             uart_to_stderr: false,
             uart_quiet: false,
             tohost_addr: 0,
+            devices: Vec::new(),
         }
     }
 }
 
+/// A bank of RAM in the physical address map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct MemoryRegion {
+    /// Physical base address
+    pub base: u64,
+    /// Size in bytes
+    pub size: usize,
+}
+
+impl MemoryRegion {
+    /// Returns the physical address one past the end of the region.
+    pub const fn end(&self) -> u64 {
+        self.base.wrapping_add(self.size as u64)
+    }
+}
+
 /// Main memory system configuration.
 ///
 /// Specifies RAM size, memory controller type, DRAM timing parameters,
@@ -666,6 +1446,26 @@ pub struct MemoryConfig {
     #[serde(default = "MemoryConfig::default_ram_size")]
     pub ram_size: usize,
 
+    /// RAM banks as `(base, size)` regions; addresses between them are
+    /// unmapped and fault on access. Empty (the default) maps one bank of
+    /// `ram_size` bytes at `system.ram_base`.
+    #[serde(default)]
+    pub regions: Vec<MemoryRegion>,
+
+    /// Route the CPU's direct RAM accesses through the bounds-checked
+    /// `DramBuffer` API instead of cached raw pointers. Slower; intended for
+    /// Miri, sanitizers, and fuzzing. Always on under Miri. Default: false.
+    #[serde(default)]
+    pub safe_ram: bool,
+
+    /// Host storage behind RAM. Default: anonymous demand-paged mapping.
+    #[serde(default)]
+    pub ram_backing: RamBacking,
+
+    /// File mapped as RAM when `ram_backing` is `File`; created if missing.
+    #[serde(default)]
+    pub ram_file: Option<String>,
+
     /// Memory controller type
     #[serde(default)]
     pub controller: MemoryController,
@@ -674,10 +1474,14 @@ pub struct MemoryConfig {
     #[serde(default = "MemoryConfig::default_t_cas")]
     pub t_cas: u64,
 
-    /// RAS latency (row access strobe)
+    /// RAS time (row access strobe): minimum cycles a row stays open
     #[serde(default = "MemoryConfig::default_t_ras")]
     pub t_ras: u64,
 
+    /// RAS to CAS delay: cycles from row activation to column access
+    #[serde(default = "MemoryConfig::default_t_rcd")]
+    pub t_rcd: u64,
+
     /// Precharge latency
     #[serde(default = "MemoryConfig::default_t_pre")]
     pub t_pre: u64,
@@ -686,10 +1490,18 @@ pub struct MemoryConfig {
     #[serde(default = "MemoryConfig::default_row_miss")]
     pub row_miss_latency: u64,
 
-    /// Number of DRAM banks per rank
+    /// Number of DRAM banks per channel
     #[serde(default = "MemoryConfig::default_num_banks")]
     pub num_banks: usize,
 
+    /// Number of DRAM channels; consecutive rows are interleaved across them
+    #[serde(default = "MemoryConfig::default_num_channels")]
+    pub num_channels: usize,
+
+    /// Number of bank groups per channel, for row-buffer statistics
+    #[serde(default = "MemoryConfig::default_num_bank_groups")]
+    pub num_bank_groups: usize,
+
     /// Row-to-Row Delay (different bank activation spacing)
     #[serde(default = "MemoryConfig::default_t_rrd")]
     pub t_rrd: u64,
@@ -706,6 +1518,19 @@ pub struct MemoryConfig {
     #[serde(default = "MemoryConfig::default_t_rfc")]
     pub t_rfc: u64,
 
+    /// DRAM request scheduling policy
+    #[serde(default)]
+    pub scheduler: DramScheduler,
+
+    /// Cycles after which a queued request is no longer bypassed (0 = never)
+    #[serde(default = "MemoryConfig::default_starvation_limit")]
+    pub starvation_limit: u64,
+
+    /// Queued writes that make the scheduler drain writes ahead of reads
+    /// (0 = no read priority)
+    #[serde(default = "MemoryConfig::default_write_drain_threshold")]
+    pub write_drain_threshold: usize,
+
     /// L1 TLB entry count
     #[serde(default = "MemoryConfig::default_tlb_size")]
     pub tlb_size: usize,
@@ -729,13 +1554,13 @@ pub struct MemoryConfig {
     #[serde(default = "MemoryConfig::default_software_ad_bits")]
     pub software_ad_bits: bool,
 
-    /// Trap on misaligned memory accesses instead of handling them natively.
-    /// When true, misaligned loads/stores raise `LoadAddressMisaligned` /
-    /// `StoreAddressMisaligned` exceptions (matching spike's default behavior).
-    /// When false, misaligned accesses are handled transparently with a latency
-    /// penalty (like many modern RISC-V cores). Default: true.
-    #[serde(default = "MemoryConfig::default_misaligned_access_trap")]
-    pub misaligned_access_trap: bool,
+    /// How the LSU handles misaligned loads and stores. Default: `Trap`.
+    #[serde(default)]
+    pub misaligned: MisalignedPolicy,
+
+    /// Bit flips injected into RAM and the ECC that catches them.
+    #[serde(default)]
+    pub fault_injection: FaultInjectionConfig,
 }
 
 impl MemoryConfig {
@@ -749,11 +1574,16 @@ impl MemoryConfig {
         defaults::T_CAS
     }
 
-    /// Returns the default RAS latency in DRAM cycles.
+    /// Returns the default RAS time in DRAM cycles.
     const fn default_t_ras() -> u64 {
         defaults::T_RAS
     }
 
+    /// Returns the default RAS to CAS delay in DRAM cycles.
+    const fn default_t_rcd() -> u64 {
+        defaults::T_RCD
+    }
+
     /// Returns the default precharge latency in DRAM cycles.
     const fn default_t_pre() -> u64 {
         defaults::T_PRE
@@ -769,6 +1599,16 @@ impl MemoryConfig {
         defaults::NUM_BANKS
     }
 
+    /// Returns the default number of DRAM channels.
+    const fn default_num_channels() -> usize {
+        defaults::NUM_CHANNELS
+    }
+
+    /// Returns the default number of bank groups.
+    const fn default_num_bank_groups() -> usize {
+        defaults::NUM_BANK_GROUPS
+    }
+
     /// Returns the default row-to-row delay in DRAM cycles.
     const fn default_t_rrd() -> u64 {
         defaults::T_RRD
@@ -789,6 +1629,16 @@ impl MemoryConfig {
         defaults::T_RFC
     }
 
+    /// Returns the default DRAM scheduler starvation limit in cycles.
+    const fn default_starvation_limit() -> u64 {
+        defaults::DRAM_STARVATION_LIMIT
+    }
+
+    /// Returns the default DRAM write drain threshold.
+    const fn default_write_drain_threshold() -> usize {
+        defaults::DRAM_WRITE_DRAIN_THRESHOLD
+    }
+
     /// Returns the default TLB entry count.
     const fn default_tlb_size() -> usize {
         defaults::TLB_SIZE
@@ -813,17 +1663,6 @@ impl MemoryConfig {
     const fn default_software_ad_bits() -> bool {
         true
     }
-
-    /// Returns the default value for misaligned access trap behavior.
-    ///
-    /// Default `true` matches spike and avoids the cross-page corruption bug:
-    /// Memory1 only translates the base virtual address, so misaligned
-    /// accesses that span two pages would read/write wrong physical memory
-    /// when handled in hardware.  Until a proper page-splitting LSU is
-    /// implemented, keep this `true`.
-    const fn default_misaligned_access_trap() -> bool {
-        true
-    }
 }
 
 impl Default for MemoryConfig {
@@ -834,22 +1673,33 @@ impl Default for MemoryConfig {
     fn default() -> Self {
         Self {
             ram_size: defaults::RAM_SIZE,
+            regions: Vec::new(),
+            safe_ram: false,
+            ram_backing: RamBacking::default(),
+            ram_file: None,
             controller: MemoryController::default(),
             t_cas: defaults::T_CAS,
             t_ras: defaults::T_RAS,
+            t_rcd: defaults::T_RCD,
             t_pre: defaults::T_PRE,
             row_miss_latency: defaults::ROW_MISS_LATENCY,
             num_banks: defaults::NUM_BANKS,
+            num_channels: defaults::NUM_CHANNELS,
+            num_bank_groups: defaults::NUM_BANK_GROUPS,
             t_rrd: defaults::T_RRD,
             row_size_bytes: defaults::ROW_SIZE_BYTES,
             t_refi: defaults::T_REFI,
             t_rfc: defaults::T_RFC,
+            scheduler: DramScheduler::default(),
+            starvation_limit: defaults::DRAM_STARVATION_LIMIT,
+            write_drain_threshold: defaults::DRAM_WRITE_DRAIN_THRESHOLD,
             tlb_size: defaults::TLB_SIZE,
             l2_tlb_size: defaults::L2_TLB_SIZE,
             l2_tlb_ways: defaults::L2_TLB_WAYS,
             l2_tlb_latency: defaults::L2_TLB_LATENCY,
             software_ad_bits: true,
-            misaligned_access_trap: true,
+            misaligned: MisalignedPolicy::Trap,
+            fault_injection: FaultInjectionConfig::default(),
         }
     }
 }
@@ -871,6 +1721,9 @@ pub struct CacheHierarchyConfig {
     /// Number of Write Combining Buffer entries (0 = disabled)
     #[serde(default)]
     pub wcb_entries: usize,
+    /// L1D accesses that can start per cycle (0 = unlimited)
+    #[serde(default)]
+    pub l1_d_ports: usize,
 }
 
 /// Individual cache level configuration.
@@ -997,6 +1850,16 @@ pub struct PipelineConfig {
     #[serde(default)]
     pub misa_override: Option<String>,
 
+    /// Enable the Zacas extension (`amocas.w/d/q`). When disabled the
+    /// encodings decode as illegal instructions.
+    #[serde(default)]
+    pub zacas: bool,
+
+    /// Enable the Zicond extension (`czero.eqz`, `czero.nez`). When disabled
+    /// the encodings decode as illegal instructions.
+    #[serde(default)]
+    pub zicond: bool,
+
     /// TAGE predictor configuration
     #[serde(default)]
     pub tage: TageConfig,
@@ -1017,6 +1880,27 @@ pub struct PipelineConfig {
     #[serde(default)]
     pub ittage: IttageConfig,
 
+    /// Fetch target queue size: predicted fetch blocks Fetch1 may run
+    /// ahead of Fetch2 (minimum 1).
+    #[serde(default = "PipelineConfig::default_ftq_size")]
+    pub ftq_size: usize,
+
+    /// Fetch buffer size: instructions Fetch2 may run ahead of Decode
+    /// (minimum one fetch block).
+    #[serde(default = "PipelineConfig::default_fetch_buffer_size")]
+    pub fetch_buffer_size: usize,
+
+    /// Instructions fetched per cycle: the largest fetch block Fetch1
+    /// predicts. Decode and the backend still take `width` per cycle.
+    /// 0 means `width`.
+    #[serde(default)]
+    pub fetch_width: usize,
+
+    /// Extra cycles Fetch1 stalls after a branch or jump misprediction is
+    /// detected in execute, on top of the frontend refill (0 = refill only).
+    #[serde(default)]
+    pub mispredict_penalty: u64,
+
     /// Backend type (`InOrder` or `OutOfOrder`)
     #[serde(default)]
     pub backend: BackendType,
@@ -1051,14 +1935,30 @@ pub struct PipelineConfig {
     #[serde(default = "PipelineConfig::default_load_ports")]
     pub load_ports: usize,
 
-    /// Number of store ports (stores issued per cycle, O3 backend).
+    /// Number of store ports: stores issued per cycle (O3 backend) and, unless
+    /// `store_buffer_drain_rate` is set, committed stores drained from the store
+    /// buffer per cycle (both backends).
     #[serde(default = "PipelineConfig::default_store_ports")]
     pub store_ports: usize,
 
+    /// Committed stores written from the store buffer to the cache per cycle
+    /// (both backends). 0 means `store_ports`.
+    #[serde(default)]
+    pub store_buffer_drain_rate: usize,
+
     /// Functional unit pool configuration (O3 backend).
     #[serde(default)]
     pub fu_config: FuConfig,
 
+    /// Latency and throughput per operation class (both backends). Classes
+    /// left out keep their unit's `fu_config` latency.
+    #[serde(default)]
+    pub latencies: LatencyTable,
+
+    /// Issue ports per functional unit class (both backends; 0 = width-limited).
+    #[serde(default)]
+    pub ports: PortConfig,
+
     /// Number of checkpoint slots for O(1) branch recovery (0 = disabled).
     #[serde(default = "PipelineConfig::default_checkpoint_count")]
     pub checkpoint_count: usize,
@@ -1070,6 +1970,14 @@ pub struct PipelineConfig {
     /// Store-set predictor configuration
     #[serde(default)]
     pub store_set: StoreSetConfig,
+
+    /// Result forwarding network (in-order backend)
+    #[serde(default)]
+    pub bypass: BypassModel,
+
+    /// Macro-op fusion patterns recognised at decode (empty = fusion disabled)
+    #[serde(default)]
+    pub fusion: Vec<FusionPattern>,
 }
 
 impl PipelineConfig {
@@ -1098,6 +2006,16 @@ impl PipelineConfig {
         defaults::ROB_SIZE
     }
 
+    /// Returns the default fetch target queue size.
+    const fn default_ftq_size() -> usize {
+        defaults::FTQ_SIZE
+    }
+
+    /// Returns the default fetch buffer size.
+    const fn default_fetch_buffer_size() -> usize {
+        defaults::FETCH_BUFFER_SIZE
+    }
+
     /// Returns the default store buffer size.
     const fn default_store_buffer_size() -> usize {
         defaults::STORE_BUFFER_SIZE
@@ -1152,11 +2070,17 @@ impl Default for PipelineConfig {
             btb_ways: defaults::BTB_WAYS,
             ras_size: defaults::RAS_SIZE,
             misa_override: None,
+            zacas: false,
+            zicond: false,
             tage: TageConfig::default(),
             perceptron: PerceptronConfig::default(),
             tournament: TournamentConfig::default(),
             sc: ScConfig::default(),
             ittage: IttageConfig::default(),
+            ftq_size: defaults::FTQ_SIZE,
+            fetch_buffer_size: defaults::FETCH_BUFFER_SIZE,
+            fetch_width: 0,
+            mispredict_penalty: 0,
             backend: BackendType::default(),
             rob_size: defaults::ROB_SIZE,
             store_buffer_size: defaults::STORE_BUFFER_SIZE,
@@ -1166,10 +2090,15 @@ impl Default for PipelineConfig {
             load_queue_size: defaults::LOAD_QUEUE_SIZE,
             load_ports: defaults::LOAD_PORTS,
             store_ports: defaults::STORE_PORTS,
+            store_buffer_drain_rate: 0,
             fu_config: FuConfig::default(),
+            latencies: LatencyTable::new(),
+            ports: PortConfig::default(),
             checkpoint_count: defaults::CHECKPOINT_COUNT,
             mem_dep_predictor: MemDepPredictor::default(),
             store_set: StoreSetConfig::default(),
+            bypass: BypassModel::default(),
+            fusion: Vec::new(),
         }
     }
 }
@@ -7,6 +7,7 @@
 //! 4. **Access Logic:** Standardized read and write operations for register interaction.
 
 use crate::common::CsrAddr;
+use serde::{Deserialize, Serialize};
 
 /// Floating-point accrued exceptions CSR address.
 pub const FFLAGS: CsrAddr = CsrAddr::from_u32(0x001);
@@ -53,9 +54,27 @@ pub const MCOUNTEREN: CsrAddr = CsrAddr::from_u32(0x306);
 /// Machine environment configuration register CSR address.
 pub const MENVCFG: CsrAddr = CsrAddr::from_u32(0x30A);
 
+/// Machine counter-inhibit register CSR address.
+pub const MCOUNTINHIBIT: CsrAddr = CsrAddr::from_u32(0x320);
+
+/// CY bit in mcountinhibit — stops `mcycle` from incrementing.
+pub const MCOUNTINHIBIT_CY: u64 = 1 << 0;
+
+/// IR bit in mcountinhibit — stops `minstret` from incrementing.
+pub const MCOUNTINHIBIT_IR: u64 = 1 << 2;
+
 /// STCE bit in menvcfg — enables Sstc (hardware stimecmp-based STIP) for S-mode.
 pub const MENVCFG_STCE: u64 = 1 << 63;
 
+/// CBIE field in menvcfg — `cbo.inval` below M-mode: 00 illegal, 01 flush, 11 invalidate.
+pub const MENVCFG_CBIE: u64 = 0b11 << 4;
+
+/// CBCFE bit in menvcfg — enables `cbo.clean` and `cbo.flush` below M-mode.
+pub const MENVCFG_CBCFE: u64 = 1 << 6;
+
+/// CBZE bit in menvcfg — enables `cbo.zero` below M-mode.
+pub const MENVCFG_CBZE: u64 = 1 << 7;
+
 /// Machine scratch register CSR address.
 pub const MSCRATCH: CsrAddr = CsrAddr::from_u32(0x340);
 
@@ -119,6 +138,128 @@ pub const MCYCLE: CsrAddr = CsrAddr::from_u32(0xB00);
 /// Machine instructions retired counter CSR address.
 pub const MINSTRET: CsrAddr = CsrAddr::from_u32(0xB02);
 
+/// Names of `pmpcfg0`–`pmpcfg15` (0x3A0–0x3AF).
+const PMPCFG_NAMES: [&str; 16] = [
+    "pmpcfg0", "pmpcfg1", "pmpcfg2", "pmpcfg3", "pmpcfg4", "pmpcfg5", "pmpcfg6", "pmpcfg7",
+    "pmpcfg8", "pmpcfg9", "pmpcfg10", "pmpcfg11", "pmpcfg12", "pmpcfg13", "pmpcfg14", "pmpcfg15",
+];
+
+/// Names of `pmpaddr0`–`pmpaddr63` (0x3B0–0x3EF).
+const PMPADDR_NAMES: [&str; 64] = [
+    "pmpaddr0",
+    "pmpaddr1",
+    "pmpaddr2",
+    "pmpaddr3",
+    "pmpaddr4",
+    "pmpaddr5",
+    "pmpaddr6",
+    "pmpaddr7",
+    "pmpaddr8",
+    "pmpaddr9",
+    "pmpaddr10",
+    "pmpaddr11",
+    "pmpaddr12",
+    "pmpaddr13",
+    "pmpaddr14",
+    "pmpaddr15",
+    "pmpaddr16",
+    "pmpaddr17",
+    "pmpaddr18",
+    "pmpaddr19",
+    "pmpaddr20",
+    "pmpaddr21",
+    "pmpaddr22",
+    "pmpaddr23",
+    "pmpaddr24",
+    "pmpaddr25",
+    "pmpaddr26",
+    "pmpaddr27",
+    "pmpaddr28",
+    "pmpaddr29",
+    "pmpaddr30",
+    "pmpaddr31",
+    "pmpaddr32",
+    "pmpaddr33",
+    "pmpaddr34",
+    "pmpaddr35",
+    "pmpaddr36",
+    "pmpaddr37",
+    "pmpaddr38",
+    "pmpaddr39",
+    "pmpaddr40",
+    "pmpaddr41",
+    "pmpaddr42",
+    "pmpaddr43",
+    "pmpaddr44",
+    "pmpaddr45",
+    "pmpaddr46",
+    "pmpaddr47",
+    "pmpaddr48",
+    "pmpaddr49",
+    "pmpaddr50",
+    "pmpaddr51",
+    "pmpaddr52",
+    "pmpaddr53",
+    "pmpaddr54",
+    "pmpaddr55",
+    "pmpaddr56",
+    "pmpaddr57",
+    "pmpaddr58",
+    "pmpaddr59",
+    "pmpaddr60",
+    "pmpaddr61",
+    "pmpaddr62",
+    "pmpaddr63",
+];
+
+/// Returns the standard lowercase name of a CSR address, if it is one the
+/// simulator implements or a PMP register (e.g. `"mstatus"` for 0x300).
+pub const fn csr_name(addr: CsrAddr) -> Option<&'static str> {
+    let raw = addr.as_u32();
+    Some(match raw {
+        0x001 => "fflags",
+        0x002 => "frm",
+        0x003 => "fcsr",
+        0x100 => "sstatus",
+        0x104 => "sie",
+        0x105 => "stvec",
+        0x106 => "scounteren",
+        0x140 => "sscratch",
+        0x141 => "sepc",
+        0x142 => "scause",
+        0x143 => "stval",
+        0x144 => "sip",
+        0x14D => "stimecmp",
+        0x180 => "satp",
+        0x300 => "mstatus",
+        0x301 => "misa",
+        0x302 => "medeleg",
+        0x303 => "mideleg",
+        0x304 => "mie",
+        0x305 => "mtvec",
+        0x306 => "mcounteren",
+        0x30A => "menvcfg",
+        0x320 => "mcountinhibit",
+        0x340 => "mscratch",
+        0x341 => "mepc",
+        0x342 => "mcause",
+        0x343 => "mtval",
+        0x344 => "mip",
+        0x3A0..=0x3AF => PMPCFG_NAMES[(raw - 0x3A0) as usize],
+        0x3B0..=0x3EF => PMPADDR_NAMES[(raw - 0x3B0) as usize],
+        0xB00 => "mcycle",
+        0xB02 => "minstret",
+        0xC00 => "cycle",
+        0xC01 => "time",
+        0xC02 => "instret",
+        0xF11 => "mvendorid",
+        0xF12 => "marchid",
+        0xF13 => "mimpid",
+        0xF14 => "mhartid",
+        _ => return None,
+    })
+}
+
 /// User interrupt enable bit in `mstatus` register.
 pub const MSTATUS_UIE: u64 = 1 << 0;
 
@@ -250,6 +391,30 @@ pub const MSTATUS_UXL: u64 = 3 << 32;
 /// Supervisor XLEN field in `mstatus` register (bits 35:34).
 pub const MSTATUS_SXL: u64 = 3 << 34;
 
+/// `mstatus` fields software can write. The rest are WPRI or hardwired:
+/// UXL/SXL always read 2 (RV64), SD is derived from FS on read, and the
+/// unimplemented XS, VS, MBE/SBE/UBE fields read zero.
+pub const MSTATUS_WRITABLE: u64 = MSTATUS_SIE
+    | MSTATUS_MIE
+    | MSTATUS_SPIE
+    | MSTATUS_MPIE
+    | MSTATUS_SPP
+    | MSTATUS_MPP
+    | MSTATUS_FS
+    | MSTATUS_MPRV
+    | MSTATUS_SUM
+    | MSTATUS_MXR
+    | MSTATUS_TVM
+    | MSTATUS_TW
+    | MSTATUS_TSR;
+
+/// `mstatus` fields visible through `sstatus` (SD is derived on read).
+pub const SSTATUS_VISIBLE: u64 =
+    MSTATUS_SIE | MSTATUS_SPIE | MSTATUS_SPP | MSTATUS_FS | MSTATUS_SUM | MSTATUS_MXR | MSTATUS_UXL;
+
+/// `sstatus` fields software can write; UXL is read-only.
+pub const SSTATUS_WRITABLE: u64 = SSTATUS_VISIBLE & !MSTATUS_UXL;
+
 /// Bit shift for address translation mode field in `satp` register.
 pub const SATP_MODE_SHIFT: u64 = 60;
 
@@ -295,6 +460,9 @@ pub const MISA_EXT_S: u64 = 1 << 18;
 /// MISA extension bit for user mode (U extension).
 pub const MISA_EXT_U: u64 = 1 << 20;
 
+/// MISA extension bits software may clear and set again: A, C, D, F and M.
+pub const MISA_TOGGLEABLE: u64 = MISA_EXT_A | MISA_EXT_C | MISA_EXT_D | MISA_EXT_F | MISA_EXT_M;
+
 /// MISA XLEN field value for 32-bit architecture.
 pub const MISA_XLEN_32: u64 = 1 << 62;
 
@@ -347,12 +515,15 @@ pub const fn csr_serialization_type(addr: CsrAddr) -> CsrSerializationType {
 ///
 /// Contains all machine-level and supervisor-level CSRs that control processor state,
 /// interrupt handling, memory management, and performance counters.
-#[derive(Clone, Default, Debug)]
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
 pub struct Csrs {
     /// Machine status register.
     pub mstatus: u64,
     /// Machine ISA register.
     pub misa: u64,
+    /// Bits of `misa` a write may change: the toggleable extensions the hart
+    /// was configured with.
+    pub misa_writable: u64,
     /// Machine exception delegation.
     pub medeleg: u64,
     /// Machine interrupt delegation.
@@ -411,6 +582,8 @@ pub struct Csrs {
     pub scounteren: u64,
     /// Machine environment configuration register.
     pub menvcfg: u64,
+    /// Machine counter-inhibit register.
+    pub mcountinhibit: u64,
 }
 
 impl Csrs {
@@ -462,6 +635,7 @@ impl Csrs {
             x if x == MCOUNTEREN.as_u32() => self.mcounteren,
             x if x == SCOUNTEREN.as_u32() => self.scounteren,
             x if x == MENVCFG.as_u32() => self.menvcfg,
+            x if x == MCOUNTINHIBIT.as_u32() => self.mcountinhibit,
             _ => 0,
         }
     }
@@ -480,8 +654,8 @@ impl Csrs {
                 self.fflags = val & 0x1F;
                 self.frm = (val >> 5) & 0x7;
             }
-            x if x == MSTATUS.as_u32() => self.mstatus = val,
-            x if x == MISA.as_u32() => self.misa = val,
+            x if x == MSTATUS.as_u32() => self.write_mstatus(val),
+            x if x == MISA.as_u32() => self.write_misa(val),
             x if x == MEDELEG.as_u32() => self.medeleg = val,
             x if x == MIDELEG.as_u32() => self.mideleg = val,
             x if x == MIE.as_u32() => self.mie = val,
@@ -491,7 +665,7 @@ impl Csrs {
             x if x == MCAUSE.as_u32() => self.mcause = val,
             x if x == MTVAL.as_u32() => self.mtval = val,
             x if x == MIP.as_u32() => self.mip = val,
-            x if x == SSTATUS.as_u32() => self.sstatus = val,
+            x if x == SSTATUS.as_u32() => self.write_sstatus(val),
             x if x == SIE.as_u32() => self.sie = val,
             x if x == STVEC.as_u32() => self.stvec = val,
             x if x == SSCRATCH.as_u32() => self.sscratch = val,
@@ -512,9 +686,57 @@ impl Csrs {
             x if x == MINSTRET.as_u32() => self.minstret = val,
             x if x == MCOUNTEREN.as_u32() => self.mcounteren = val,
             x if x == SCOUNTEREN.as_u32() => self.scounteren = val,
+            x if x == MCOUNTINHIBIT.as_u32() => self.mcountinhibit = val,
             _ => {}
         }
     }
+
+    /// Writes `mstatus` as a CSR instruction would: WPRI and read-only
+    /// fields are dropped, UXL/SXL stay RV64, the reserved MPP encoding 2
+    /// is legalized to U, and `sstatus` is kept in step.
+    pub const fn write_mstatus(&mut self, val: u64) {
+        let mut mstatus =
+            (val & MSTATUS_WRITABLE) | (MSTATUS_DEFAULT_RV64 & (MSTATUS_UXL | MSTATUS_SXL));
+        // Clamp to U rather than M so a bad write cannot escalate on MRET.
+        if (mstatus >> MSTATUS_MPP_SHIFT) & MSTATUS_MPP_MASK == 2 {
+            mstatus &= !MSTATUS_MPP;
+        }
+        self.mstatus = mstatus;
+        self.sstatus = mstatus & SSTATUS_VISIBLE;
+    }
+
+    /// Writes `misa` as a CSR instruction would: only the bits in
+    /// `misa_writable` change, and D is cleared along with F, which it
+    /// depends on.
+    pub const fn write_misa(&mut self, val: u64) {
+        let mut misa = (self.misa & !self.misa_writable) | (val & self.misa_writable);
+        if misa & MISA_EXT_F == 0 {
+            misa &= !MISA_EXT_D;
+        }
+        self.misa = misa;
+    }
+
+    /// Writes `sstatus` as a CSR instruction would: only the S-visible
+    /// writable fields of `mstatus` change.
+    pub const fn write_sstatus(&mut self, val: u64) {
+        self.mstatus = (self.mstatus & !SSTATUS_WRITABLE) | (val & SSTATUS_WRITABLE);
+        self.sstatus = self.mstatus & SSTATUS_VISIBLE;
+    }
+
+    /// Advances `mcycle` by one cycle unless `mcountinhibit.CY` is set.
+    pub const fn count_cycle(&mut self) {
+        if self.mcountinhibit & MCOUNTINHIBIT_CY == 0 {
+            self.mcycle = self.mcycle.wrapping_add(1);
+        }
+    }
+
+    /// Advances `minstret` by one retired instruction unless
+    /// `mcountinhibit.IR` is set.
+    pub const fn count_instret(&mut self) {
+        if self.mcountinhibit & MCOUNTINHIBIT_IR == 0 {
+            self.minstret = self.minstret.wrapping_add(1);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -538,11 +760,24 @@ mod tests {
     fn test_csrs_read_write() {
         let mut csrs = Csrs::default();
 
+        // Bits 2 and 4 are WPRI, VS (bit 9) is unimplemented, and MPP = 2
+        // is reserved and legalizes to U: only SPIE survives.
         csrs.write(MSTATUS, 0x1234);
-        assert_eq!(csrs.read(MSTATUS), 0x1234);
-
-        csrs.write(MISA, 0x5678);
-        assert_eq!(csrs.read(MISA), 0x5678);
+        assert_eq!(csrs.read(MSTATUS), 0x0020 | MSTATUS_DEFAULT_RV64);
+        assert_eq!(csrs.read(SSTATUS), 0x0020 | (MSTATUS_DEFAULT_RV64 & MSTATUS_UXL));
+
+        // Only implemented toggleable extensions change; D follows F.
+        csrs.misa = MISA_DEFAULT_RV64IMAFDC;
+        csrs.misa_writable = MISA_TOGGLEABLE;
+        csrs.write(MISA, 0);
+        assert_eq!(csrs.read(MISA), MISA_XLEN_64 | MISA_EXT_I | MISA_EXT_S | MISA_EXT_U);
+        csrs.write(MISA, MISA_EXT_D | MISA_EXT_M);
+        assert_eq!(
+            csrs.read(MISA),
+            MISA_XLEN_64 | MISA_EXT_I | MISA_EXT_M | MISA_EXT_S | MISA_EXT_U
+        );
+        csrs.write(MISA, u64::MAX);
+        assert_eq!(csrs.read(MISA), MISA_DEFAULT_RV64IMAFDC);
 
         csrs.write(SATP, SATP_MODE_SV39 << SATP_MODE_SHIFT | 0xabc);
         assert_eq!(csrs.read(SATP), SATP_MODE_SV39 << SATP_MODE_SHIFT | 0xabc);
@@ -12,7 +12,7 @@ use crate::common::RegIdx;
 ///
 /// Contains 32 floating-point registers used for arithmetic operations. Registers
 /// are stored as 64-bit double-precision values.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Fpr {
     fregs: [f64; 32],
 }
@@ -12,7 +12,7 @@ use crate::common::RegIdx;
 ///
 /// Contains 32 general-purpose registers used for integer operations. Register `x0`
 /// is hardwired to zero and cannot be modified.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Gpr {
     regs: [u64; 32],
 }
@@ -6,11 +6,13 @@
 //! 2. **Serialization:** Conversion between numeric representations and enum variants.
 //! 3. **Observability:** Human-readable naming and display formatting for privilege states.
 
+use serde::Deserialize;
+
 /// RISC-V privilege mode levels.
 ///
 /// RISC-V defines three privilege modes that control access to system resources
 /// and instructions. Machine mode is the highest privilege level.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
 pub enum PrivilegeMode {
     /// User mode (U-mode).
     ///
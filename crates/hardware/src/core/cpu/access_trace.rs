@@ -0,0 +1,262 @@
+//! Physical memory access trace for external cache simulators.
+//!
+//! When `general.access_trace` is set, the hart logs one [`AccessRecord`] for
+//! every committed load and store and for every instruction fetch that misses
+//! the L1 I-cache. `general.access_trace_sample` keeps only every Nth record
+//! to bound the file size. A fetch record carries the missing line's address
+//! and the line size. The file is written in one of two formats:
+//! 1. **Binary:** The magic `RVAT` and a version byte (1), followed by one
+//!    record after another. A record is a flags byte (bit 0 = write,
+//!    bit 1 = fetch) and four LEB128 varints: the cycle delta from the
+//!    previous record, the zigzag-encoded PC delta, the zigzag-encoded
+//!    physical address delta, and the access size in bytes. Deltas start from
+//!    zero and wrap, so any sequence round-trips.
+//! 2. **CSV:** A `cycle,pc,paddr,size,is_write,is_fetch` header line, then one
+//!    line per record with hex PC and address and `0`/`1` flags.
+//!
+//! [`read_access_trace`] loads either format back into records.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+
+use crate::common::SimError;
+use crate::config::AccessTraceFormat;
+
+/// Magic bytes at the start of a binary trace.
+const MAGIC: &[u8; 4] = b"RVAT";
+
+/// Binary format version written after the magic.
+const VERSION: u8 = 1;
+
+/// Header line of a CSV trace.
+const CSV_HEADER: &str = "cycle,pc,paddr,size,is_write,is_fetch";
+
+/// Flags-byte bit set for a write.
+const FLAG_WRITE: u8 = 1 << 0;
+
+/// Flags-byte bit set for an instruction fetch.
+const FLAG_FETCH: u8 = 1 << 1;
+
+/// One memory access in the trace.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AccessRecord {
+    /// Cycle the access was recorded in (commit for loads and stores).
+    pub cycle: u64,
+    /// PC of the instruction that made the access.
+    pub pc: u64,
+    /// Physical address accessed.
+    pub paddr: u64,
+    /// Access size in bytes.
+    pub size: u64,
+    /// `true` for a store.
+    pub is_write: bool,
+    /// `true` for an instruction fetch.
+    pub is_fetch: bool,
+}
+
+/// Writer of the access trace.
+#[derive(Debug)]
+pub struct AccessTrace {
+    out: BufWriter<File>,
+    format: AccessTraceFormat,
+    /// Keep one record in this many.
+    sample: u64,
+    /// Records offered since the last one kept.
+    skipped: u64,
+    /// Previous record written, the base of the binary deltas.
+    prev: AccessRecord,
+    /// Records written.
+    written: u64,
+}
+
+impl AccessTrace {
+    /// Creates (truncating) the trace file at `path`, keeping every
+    /// `sample`th record (0 is treated as 1).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SimError::FileRead`] if the file cannot be created.
+    pub fn create(path: &str, format: AccessTraceFormat, sample: u64) -> Result<Self, SimError> {
+        let file = File::create(path)
+            .map_err(|source| SimError::FileRead { path: path.to_owned(), source })?;
+        let mut out = BufWriter::with_capacity(1 << 16, file);
+        let header = match format {
+            AccessTraceFormat::Binary => {
+                out.write_all(MAGIC).and_then(|()| out.write_all(&[VERSION]))
+            }
+            AccessTraceFormat::Csv => writeln!(out, "{CSV_HEADER}"),
+        };
+        header.map_err(|source| SimError::FileRead { path: path.to_owned(), source })?;
+        Ok(Self {
+            out,
+            format,
+            sample: sample.max(1),
+            skipped: 0,
+            prev: AccessRecord::default(),
+            written: 0,
+        })
+    }
+
+    /// Returns the number of records written so far.
+    pub const fn written(&self) -> u64 {
+        self.written
+    }
+
+    /// Offers `rec` to the trace; it is written if the sampler keeps it.
+    pub fn record(&mut self, rec: &AccessRecord) {
+        self.skipped += 1;
+        if self.skipped < self.sample {
+            return;
+        }
+        self.skipped = 0;
+        self.written += 1;
+        match self.format {
+            AccessTraceFormat::Binary => {
+                let flags = if rec.is_write { FLAG_WRITE } else { 0 }
+                    | if rec.is_fetch { FLAG_FETCH } else { 0 };
+                let mut buf = [0u8; 1 + 4 * 10];
+                buf[0] = flags;
+                let mut len = 1;
+                len += put_varint(&mut buf[len..], rec.cycle.wrapping_sub(self.prev.cycle));
+                len += put_varint(&mut buf[len..], zigzag(rec.pc.wrapping_sub(self.prev.pc)));
+                len += put_varint(&mut buf[len..], zigzag(rec.paddr.wrapping_sub(self.prev.paddr)));
+                len += put_varint(&mut buf[len..], rec.size);
+                let _ = self.out.write_all(&buf[..len]);
+            }
+            AccessTraceFormat::Csv => {
+                let _ = writeln!(
+                    self.out,
+                    "{},{:#x},{:#x},{},{},{}",
+                    rec.cycle,
+                    rec.pc,
+                    rec.paddr,
+                    rec.size,
+                    u8::from(rec.is_write),
+                    u8::from(rec.is_fetch)
+                );
+            }
+        }
+        self.prev = *rec;
+    }
+}
+
+impl Drop for AccessTrace {
+    fn drop(&mut self) {
+        let _ = self.out.flush();
+    }
+}
+
+/// Reads an access trace written in either format.
+///
+/// # Errors
+///
+/// Returns [`SimError::FileRead`] if the file cannot be read or is not a
+/// well-formed trace.
+pub fn read_access_trace(path: &str) -> Result<Vec<AccessRecord>, SimError> {
+    let mut bytes = Vec::new();
+    let _ = File::open(path)
+        .and_then(|f| BufReader::new(f).read_to_end(&mut bytes))
+        .map_err(|source| SimError::FileRead { path: path.to_owned(), source })?;
+    let parsed = if bytes.starts_with(MAGIC) {
+        parse_binary(&bytes[MAGIC.len()..])
+    } else {
+        parse_csv(&bytes)
+    };
+    parsed.map_err(|msg| SimError::FileRead {
+        path: path.to_owned(),
+        source: io::Error::new(io::ErrorKind::InvalidData, msg),
+    })
+}
+
+/// Parses the binary records following the magic.
+fn parse_binary(bytes: &[u8]) -> Result<Vec<AccessRecord>, String> {
+    let Some((&version, mut rest)) = bytes.split_first() else {
+        return Err("truncated access trace header".to_owned());
+    };
+    if version != VERSION {
+        return Err(format!("unsupported access trace version {version}"));
+    }
+    let mut records = Vec::new();
+    let mut prev = AccessRecord::default();
+    while let Some((&flags, tail)) = rest.split_first() {
+        rest = tail;
+        let mut field = || take_varint(&mut rest).ok_or("truncated access trace record");
+        let rec = AccessRecord {
+            cycle: prev.cycle.wrapping_add(field()?),
+            pc: prev.pc.wrapping_add(unzigzag(field()?)),
+            paddr: prev.paddr.wrapping_add(unzigzag(field()?)),
+            size: field()?,
+            is_write: flags & FLAG_WRITE != 0,
+            is_fetch: flags & FLAG_FETCH != 0,
+        };
+        records.push(rec);
+        prev = rec;
+    }
+    Ok(records)
+}
+
+/// Parses a CSV trace, header line included.
+fn parse_csv(bytes: &[u8]) -> Result<Vec<AccessRecord>, String> {
+    let text = std::str::from_utf8(bytes).map_err(|e| e.to_string())?;
+    let mut lines = text.lines();
+    if lines.next() != Some(CSV_HEADER) {
+        return Err("not an access trace".to_owned());
+    }
+    let hex = |s: &str| u64::from_str_radix(s.trim_start_matches("0x"), 16);
+    lines
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let f: Vec<&str> = line.split(',').collect();
+            let parse = || -> Option<AccessRecord> {
+                let [cycle, pc, paddr, size, is_write, is_fetch] = f.as_slice() else {
+                    return None;
+                };
+                Some(AccessRecord {
+                    cycle: cycle.parse().ok()?,
+                    pc: hex(pc).ok()?,
+                    paddr: hex(paddr).ok()?,
+                    size: size.parse().ok()?,
+                    is_write: *is_write == "1",
+                    is_fetch: *is_fetch == "1",
+                })
+            };
+            parse().ok_or_else(|| format!("malformed access trace line '{line}'"))
+        })
+        .collect()
+}
+
+/// Writes `val` as a LEB128 varint into `buf`, returning the bytes used.
+fn put_varint(buf: &mut [u8], mut val: u64) -> usize {
+    let mut i = 0;
+    while val >= 0x80 {
+        buf[i] = (val as u8) | 0x80;
+        val >>= 7;
+        i += 1;
+    }
+    buf[i] = val as u8;
+    i + 1
+}
+
+/// Reads a LEB128 varint from the front of `bytes`, advancing it.
+fn take_varint(bytes: &mut &[u8]) -> Option<u64> {
+    let mut val = 0u64;
+    for (i, &b) in bytes.iter().enumerate().take(10) {
+        val |= u64::from(b & 0x7f) << (7 * i);
+        if b & 0x80 == 0 {
+            *bytes = &bytes[i + 1..];
+            return Some(val);
+        }
+    }
+    None
+}
+
+/// Maps a two's-complement delta to an unsigned value that is small for
+/// deltas near zero in either direction.
+const fn zigzag(delta: u64) -> u64 {
+    (delta << 1) ^ (((delta as i64) >> 63) as u64)
+}
+
+/// Inverse of [`zigzag`].
+const fn unzigzag(val: u64) -> u64 {
+    (val >> 1) ^ (val & 1).wrapping_neg()
+}
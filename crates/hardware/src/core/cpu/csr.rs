@@ -5,10 +5,14 @@
 //! 1. **Read Operations:** Retrieves CSR values while handling architectural side effects.
 //! 2. **Write Operations:** Updates CSR state and triggers necessary system updates (e.g., TLB flushes).
 //! 3. **Side Effect Management:** Handles interrupt inhibition and status bit synchronization.
+//! 4. **Access Checks:** Decides which privileged and CSR instructions are illegal at execute.
 
 use super::Cpu;
-use crate::common::{CsrAddr, Trap};
+use crate::common::{CsrAddr, RegIdx, Trap};
+use crate::config::InitialStateConfig;
 use crate::core::arch::csr;
+use crate::core::arch::mode::PrivilegeMode;
+use crate::core::pipeline::signals::{ControlSignals, CsrOp, SystemOp};
 
 impl Cpu {
     /// Reads a value from a Control and Status Register (CSR).
@@ -30,11 +34,11 @@ impl Cpu {
             }
             x if x == csr::MVENDORID.as_u32()
                 || x == csr::MARCHID.as_u32()
-                || x == csr::MIMPID.as_u32()
-                || x == csr::MHARTID.as_u32() =>
+                || x == csr::MIMPID.as_u32() =>
             {
                 0
             }
+            x if x == csr::MHARTID.as_u32() => self.hart_id as u64,
             x if x == csr::MSTATUS.as_u32() => {
                 let val = self.csrs.mstatus & !csr::MSTATUS_SD;
                 if val & csr::MSTATUS_FS == csr::MSTATUS_FS_DIRTY {
@@ -73,11 +77,10 @@ impl Cpu {
             x if x == csr::MCOUNTEREN.as_u32() => self.csrs.mcounteren,
             x if x == csr::SCOUNTEREN.as_u32() => self.csrs.scounteren,
             x if x == csr::MENVCFG.as_u32() => self.csrs.menvcfg,
-            x if x == csr::CYCLE.as_u32() || x == csr::MCYCLE.as_u32() => self.stats.cycles,
+            x if x == csr::MCOUNTINHIBIT.as_u32() => self.csrs.mcountinhibit,
+            x if x == csr::CYCLE.as_u32() || x == csr::MCYCLE.as_u32() => self.csrs.mcycle,
             x if x == csr::TIME.as_u32() => self.stats.cycles / self.clint_divider,
-            x if x == csr::INSTRET.as_u32() || x == csr::MINSTRET.as_u32() => {
-                self.stats.instructions_retired
-            }
+            x if x == csr::INSTRET.as_u32() || x == csr::MINSTRET.as_u32() => self.csrs.minstret,
             0x3A0 => {
                 self.pmp.get_cfg(0) as u64
                     | ((self.pmp.get_cfg(1) as u64) << 8)
@@ -131,41 +134,7 @@ impl Cpu {
             x if x == csr::CSR_SIM_PANIC.as_u32() => {
                 self.trap(&Trap::RequestedTrap(val), self.pc);
             }
-            x if x == csr::MSTATUS.as_u32() => {
-                // WARL: only defined writable bits are accepted; WPRI/SD/UXL/SXL ignored.
-                const MSTATUS_WRITABLE: u64 = csr::MSTATUS_SIE
-                    | csr::MSTATUS_MIE
-                    | csr::MSTATUS_SPIE
-                    | csr::MSTATUS_MPIE
-                    | csr::MSTATUS_SPP
-                    | csr::MSTATUS_MPP
-                    | csr::MSTATUS_FS
-                    | csr::MSTATUS_MPRV
-                    | csr::MSTATUS_SUM
-                    | csr::MSTATUS_MXR
-                    | csr::MSTATUS_TVM
-                    | csr::MSTATUS_TW
-                    | csr::MSTATUS_TSR;
-                // UXL and SXL are hardwired to 2 (RV64)
-                let preserved = self.csrs.mstatus & (csr::MSTATUS_UXL | csr::MSTATUS_SXL);
-                self.csrs.mstatus = (val & MSTATUS_WRITABLE) | preserved;
-
-                // WARL: MPP must encode a supported privilege mode (0=U, 1=S, 3=M).
-                // Value 2 is reserved; clamp to 0 (User) to prevent privilege escalation.
-                let mpp = (self.csrs.mstatus >> csr::MSTATUS_MPP_SHIFT) & csr::MSTATUS_MPP_MASK;
-                if mpp == 2 {
-                    self.csrs.mstatus &= !csr::MSTATUS_MPP;
-                }
-
-                let mask = csr::MSTATUS_SIE
-                    | csr::MSTATUS_SPIE
-                    | csr::MSTATUS_SPP
-                    | csr::MSTATUS_FS
-                    | csr::MSTATUS_SUM
-                    | csr::MSTATUS_MXR
-                    | csr::MSTATUS_UXL;
-                self.csrs.sstatus = self.csrs.mstatus & mask;
-            }
+            x if x == csr::MSTATUS.as_u32() => self.csrs.write_mstatus(val),
             x if x == csr::MEDELEG.as_u32() => {
                 // Bit 11 (ecall from M-mode) cannot be delegated
                 self.csrs.medeleg = val & !(1 << 11);
@@ -192,7 +161,14 @@ impl Cpu {
                 self.csrs.mtvec = if mode >= 2 { val & !3 } else { val };
             }
             x if x == csr::MISA.as_u32() => {
-                // MISA is WARL: writes are silently ignored (extensions are hardwired).
+                // WARL: only the configured A, C, D, F and M bits toggle.
+                // Clearing C is suppressed when the next instruction is not
+                // 4-byte aligned, since it could no longer be fetched.
+                let clears_c =
+                    self.csrs.misa & !val & self.csrs.misa_writable & csr::MISA_EXT_C != 0;
+                if !clears_c || self.committed_next_pc.is_multiple_of(4) {
+                    self.csrs.write_misa(val);
+                }
             }
             x if x == csr::MSCRATCH.as_u32() => self.csrs.mscratch = val,
             x if x == csr::MEPC.as_u32() => self.csrs.mepc = val & !1,
@@ -204,19 +180,7 @@ impl Cpu {
                 // Track software-written SEIP so pre_tick preserves it
                 self.sw_seip = (val & csr::MIP_SEIP) != 0;
             }
-            x if x == csr::SSTATUS.as_u32() => {
-                // UXL is read-only in sstatus (always reflects mstatus UXL)
-                let writable_mask = csr::MSTATUS_SIE
-                    | csr::MSTATUS_SPIE
-                    | csr::MSTATUS_SPP
-                    | csr::MSTATUS_FS
-                    | csr::MSTATUS_SUM
-                    | csr::MSTATUS_MXR;
-                let read_mask = writable_mask | csr::MSTATUS_UXL;
-
-                self.csrs.mstatus = (self.csrs.mstatus & !writable_mask) | (val & writable_mask);
-                self.csrs.sstatus = self.csrs.mstatus & read_mask;
-            }
+            x if x == csr::SSTATUS.as_u32() => self.csrs.write_sstatus(val),
             x if x == csr::SIE.as_u32() => {
                 let mask = self.csrs.mideleg;
                 self.csrs.mie = (self.csrs.mie & !mask) | (val & mask);
@@ -244,8 +208,12 @@ impl Cpu {
             x if x == csr::MENVCFG.as_u32() => {
                 self.csrs.menvcfg = val;
             }
-            x if x == csr::MCYCLE.as_u32() => self.stats.cycles = val,
-            x if x == csr::MINSTRET.as_u32() => self.stats.instructions_retired = val,
+            x if x == csr::MCOUNTINHIBIT.as_u32() => {
+                // Only CY(0) and IR(2) are implemented; TM(1) is hardwired to zero
+                self.csrs.mcountinhibit = val & (csr::MCOUNTINHIBIT_CY | csr::MCOUNTINHIBIT_IR);
+            }
+            x if x == csr::MCYCLE.as_u32() => self.csrs.mcycle = val,
+            x if x == csr::MINSTRET.as_u32() => self.csrs.minstret = val,
             0x3A0 => {
                 for i in 0..8 {
                     self.pmp.set_cfg(i, ((val >> (i * 8)) & 0xFF) as u8);
@@ -285,6 +253,97 @@ impl Cpu {
             _ => {}
         }
     }
+
+    /// Overrides the reset state with `state`: writes `mstatus`, then the
+    /// other listed CSRs, then `satp`, each as `csrw` would, and switches to
+    /// the requested privilege mode.
+    ///
+    /// Setting `satp` or the privilege also leaves direct mode, which would
+    /// otherwise bypass translation and emulate `ecall`.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The `initial_state` section of the configuration.
+    pub fn apply_initial_state(&mut self, state: &InitialStateConfig) {
+        if let Some(mstatus) = state.mstatus {
+            self.csr_write(csr::MSTATUS, mstatus);
+        }
+        for (&addr, &val) in &state.csrs {
+            self.csr_write(CsrAddr::new(addr), val);
+        }
+        if let Some(satp) = state.satp {
+            self.csr_write(csr::SATP, satp);
+            self.direct_mode = false;
+        }
+        if let Some(privilege) = state.privilege {
+            self.privilege = privilege;
+            self.direct_mode = false;
+        }
+    }
+    /// Returns `true` if an instruction must raise an illegal-instruction
+    /// exception when it executes in the current privilege mode.
+    ///
+    /// Covers the checks that depend on state a preceding CSR write may
+    /// still be changing when the instruction is decoded: `MRET`/`SRET`/
+    /// `WFI`/`SFENCE.VMA` privilege and `mstatus.TSR`/`TW`/`TVM`, CSR
+    /// privilege, the counter enables, read-only CSRs, and FP instructions
+    /// and CSRs while `mstatus.FS` is Off.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctrl` - The decoded control signals.
+    /// * `rs1` - The `rs1` field, which decides whether a CSR op writes.
+    pub fn is_illegal_at_execute(&self, ctrl: &ControlSignals, rs1: RegIdx) -> bool {
+        let supervisor = self.privilege == PrivilegeMode::Supervisor;
+        let mstatus = self.csrs.mstatus;
+        let fs_off = mstatus & csr::MSTATUS_FS == 0;
+        match ctrl.system_op {
+            SystemOp::Mret => self.privilege != PrivilegeMode::Machine,
+            SystemOp::Sret => {
+                self.privilege == PrivilegeMode::User
+                    || (supervisor && mstatus & csr::MSTATUS_TSR != 0)
+            }
+            SystemOp::Wfi => {
+                self.privilege == PrivilegeMode::User
+                    || (supervisor && mstatus & csr::MSTATUS_TW != 0)
+            }
+            SystemOp::SfenceVma => supervisor && mstatus & csr::MSTATUS_TVM != 0,
+            SystemOp::None | SystemOp::Fence => {
+                fs_off && (ctrl.fp_reg_write || ctrl.rs1_fp || ctrl.rs2_fp || ctrl.rs3_fp)
+            }
+            SystemOp::System if ctrl.csr_op != CsrOp::None => {
+                let addr = ctrl.csr_addr;
+                (addr == csr::SATP && supervisor && mstatus & csr::MSTATUS_TVM != 0)
+                    || self.counter_denied(addr)
+                    || (self.privilege.to_u8() as u32) < addr.privilege_level() as u32
+                    || (addr.is_fp() && fs_off)
+                    || (addr.is_read_only() && ctrl.csr_op.writes_csr(rs1))
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if `mcounteren`/`scounteren` deny the current mode
+    /// access to `addr` (`cycle`, `time` or `instret`).
+    fn counter_denied(&self, addr: CsrAddr) -> bool {
+        let bit = if addr == csr::CYCLE {
+            0
+        } else if addr == csr::TIME {
+            1
+        } else if addr == csr::INSTRET {
+            2
+        } else {
+            return false;
+        };
+        let mask = 1u64 << bit;
+        match self.privilege {
+            PrivilegeMode::Supervisor => self.csrs.mcounteren & mask == 0,
+            PrivilegeMode::User => {
+                self.csrs.mcounteren & mask == 0 || self.csrs.scounteren & mask == 0
+            }
+            PrivilegeMode::Machine => false,
+        }
+    }
 }
 
 #[cfg(test)]
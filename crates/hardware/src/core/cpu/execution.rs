@@ -7,11 +7,8 @@
 //! 4. **Observability:** Provides tracing and pipeline visualization for debugging.
 
 use super::Cpu;
-use crate::common::constants::{
-    HANG_DETECTION_THRESHOLD, PAGE_OFFSET_MASK, PAGE_SHIFT, STATUS_UPDATE_INTERVAL, VPN_MASK,
-    WFI_INSTRUCTION,
-};
-use crate::common::{Asid, SimError, Vpn};
+use crate::common::constants::{PAGE_OFFSET_MASK, PAGE_SHIFT, STATUS_UPDATE_INTERVAL, VPN_MASK};
+use crate::common::{Asid, PhysAddr, SimError, Vpn};
 use crate::core::arch::csr;
 use crate::core::arch::mode::PrivilegeMode;
 use crate::isa::abi;
@@ -27,6 +24,8 @@ impl Cpu {
     ///
     /// Returns [`SimError::KernelPanic`] when the bus panic sentinel fires.
     pub fn pre_tick(&mut self) -> Result<bool, SimError> {
+        self.store_log.clear();
+        self.bus.bus.set_access_context(self.stats.cycles, self.hart_id);
         if let Some(code) = self.bus.check_exit() {
             self.exit_code = Some(code);
             return Ok(true);
@@ -39,51 +38,35 @@ impl Cpu {
             }
         }
 
-        if self.pc == self.last_pc {
+        // A hart parked in WFI is waiting, not hung.
+        if self.pc == self.last_pc && !self.wfi_waiting {
             self.same_pc_count += 1;
-            if self.same_pc_count == HANG_DETECTION_THRESHOLD {
-                let asid = Asid::new(
-                    ((self.csrs.satp >> csr::SATP_ASID_SHIFT) & csr::SATP_ASID_MASK) as u16,
-                );
-                let inst = if let Some(hit) =
-                    self.mmu.dtlb.lookup(Vpn::new((self.pc >> PAGE_SHIFT) & VPN_MASK), asid)
-                {
-                    let paddr = crate::common::PhysAddr::new(
-                        hit.ppn.to_addr() | (self.pc & PAGE_OFFSET_MASK),
-                    );
-                    self.bus.bus.read_u32(paddr)
-                } else {
-                    let paddr = crate::common::PhysAddr::new(self.pc);
-                    if self.bus.bus.is_valid_address(paddr) {
-                        // No TLB entry — likely M-mode with paging disabled;
-                        // treat PC as a physical address.
-                        self.bus.bus.read_u32(paddr)
-                    } else {
-                        0
-                    }
-                };
-
-                if inst == WFI_INSTRUCTION {
-                    trace_trap!(self.trace;
-                        event = "wfi-wait",
-                        pc    = %crate::trace::Hex(self.pc),
-                        "CPU stuck in WFI — waiting for interrupt"
-                    );
-                } else {
-                    trace_trap!(self.trace;
-                        event = "potential-hang",
-                        pc    = %crate::trace::Hex(self.pc),
-                        inst  = inst,
-                        "CPU potential hang detected"
-                    );
-                }
-            }
         } else {
             self.last_pc = self.pc;
             self.same_pc_count = 0;
         }
+        if self.wfi_waiting {
+            self.loop_detector.restart(self.stats.cycles);
+        }
 
-        let (timer_irq, msip, meip, seip) = self.bus.tick();
+        // Devices advance once per cycle on hart 0; the other harts only
+        // sample the interrupt lines routed to them.
+        let (timer_irq, msip, meip, seip) =
+            if self.hart_id == 0 { self.bus.tick() } else { self.bus.bus.hart_irqs(self.hart_id) };
+        if self.hart_id == 0 {
+            self.bus.service_dma(self.stats.cycles, &mut self.stats.mem_traffic);
+            self.bus.tick_memory(self.stats.cycles, &mut self.stats.mem_traffic);
+        }
+        self.faults.tick(&self.ram, &mut self.stats);
+
+        // Device DMA writes bypass the store path, so they clear a
+        // reservation on the written granule here.
+        if self.load_reservation.is_some() {
+            for i in 0..self.bus.bus.dma_writes().len() {
+                let (addr, len) = self.bus.bus.dma_writes()[i];
+                self.invalidate_reservation(PhysAddr::new(addr), len);
+            }
+        }
 
         let mut mip = self.csrs.mip;
 
@@ -130,14 +113,53 @@ impl Cpu {
         self.csrs.mip = mip;
 
         self.stats.cycles += 1;
+        self.csrs.count_cycle();
         self.track_mode_cycles();
 
+        if mip & self.csrs.mie == 0 {
+            self.interrupt_pending_since = None;
+        } else {
+            let _ = self.interrupt_pending_since.get_or_insert(self.stats.cycles);
+        }
+
         Ok(false)
     }
 
-    /// Post-tick: zero x0, privilege tracing, status printing.
+    /// Translates virtual address `vaddr` for a host-side access (diagnostics,
+    /// semihosting) without a page walk.
+    ///
+    /// Translates through the D-TLB if it holds the page, otherwise treats
+    /// `vaddr` as physical (M-mode with paging off).
+    pub fn host_paddr(&mut self, vaddr: u64) -> PhysAddr {
+        let asid =
+            Asid::new(((self.csrs.satp >> csr::SATP_ASID_SHIFT) & csr::SATP_ASID_MASK) as u16);
+        let paddr = self
+            .mmu
+            .dtlb
+            .lookup(Vpn::new((vaddr >> PAGE_SHIFT) & VPN_MASK), asid)
+            .map_or(vaddr, |hit| hit.ppn.to_addr() | (vaddr & PAGE_OFFSET_MASK));
+        PhysAddr::new(paddr)
+    }
+
+    /// Reads the instruction word at virtual address `pc` for diagnostics.
+    ///
+    /// Translates as [`Self::host_paddr`] does. Returns 0 if unreadable.
+    pub fn inst_word_at(&mut self, pc: u64) -> u32 {
+        let paddr = self.host_paddr(pc);
+        if self.bus.bus.is_valid_address(paddr) { self.bus.bus.read_u32(paddr) } else { 0 }
+    }
+
+    /// Post-tick: zero x0, MMIO access and disk write counts, privilege tracing, status printing.
     pub fn post_tick(&mut self, prev_priv: PrivilegeMode) {
         self.regs.write(abi::REG_ZERO, 0);
+        if self.bus.bus.has_mmio_stats() {
+            self.stats.mmio.merge(&self.bus.bus.take_mmio_stats());
+        }
+        if let Some(disk) = self.bus.bus.virtio_blk_mut()
+            && disk.has_write_stats()
+        {
+            self.stats.disk.add(&disk.take_write_stats());
+        }
 
         if self.trace {
             if self.privilege != prev_priv {
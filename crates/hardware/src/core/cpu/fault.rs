@@ -0,0 +1,156 @@
+//! RAM Fault Injection and ECC.
+//!
+//! Flips bits in guest RAM and models the error-correcting code guarding it.
+//! Flips come from three sources:
+//! 1. **Scheduled:** `memory.fault_injection.faults` lists `(addr, bit, cycle)`
+//!    flips, applied as their cycle starts.
+//! 2. **Random:** with probability `memory.fault_injection.rate`, a RAM load
+//!    flips one bit of the bytes it reads, drawn from a generator seeded with
+//!    `memory.fault_injection.seed` so runs replay exactly.
+//! 3. **Host:** [`FaultInjector::inject`], e.g. `Cpu.inject_fault` in Python.
+//!
+//! A flip is written to RAM itself, and the injector remembers which bits of
+//! each 64-bit word are wrong. With SEC-DED ECC, a load over a word with one
+//! wrong bit reads the corrected value and the word is scrubbed; two or more
+//! wrong bits are uncorrectable and the load raises a load access fault.
+//! Without ECC the load sees the corrupted data. A store recomputes the check
+//! bits, so bytes it overwrites are no longer in error.
+//!
+//! Only data loads that hit RAM directly are checked; fetches, atomics and
+//! device DMA read RAM as it is.
+
+use super::ram::HostRam;
+use crate::common::rng::SplitMix64;
+use crate::config::{Ecc, FaultInjectionConfig, InjectedFault};
+use crate::stats::SimStats;
+use std::collections::HashMap;
+
+/// Injects bit flips into RAM and checks loads against them.
+#[derive(Debug, Clone)]
+pub struct FaultInjector {
+    /// ECC checked on loads.
+    ecc: Ecc,
+    /// Probability that a load flips a random bit.
+    rate: f64,
+    /// Generator drawing random flips.
+    rng: SplitMix64,
+    /// Scheduled flips not yet applied, latest cycle first.
+    pending: Vec<InjectedFault>,
+    /// Wrong bits of each corrupted word, keyed by the word's address.
+    flipped: HashMap<u64, u64>,
+}
+
+impl Default for FaultInjector {
+    /// Returns an injector with no flips and no ECC.
+    fn default() -> Self {
+        Self::new(&FaultInjectionConfig::default())
+    }
+}
+
+impl FaultInjector {
+    /// Creates an injector from the `memory.fault_injection` configuration.
+    pub fn new(config: &FaultInjectionConfig) -> Self {
+        let mut pending = config.faults.clone();
+        pending.sort_by_key(|f| std::cmp::Reverse(f.cycle));
+        Self {
+            ecc: config.ecc,
+            rate: config.rate,
+            rng: SplitMix64::new(config.seed),
+            pending,
+            flipped: HashMap::new(),
+        }
+    }
+
+    /// Returns `true` if loads must be checked: some bit is wrong or loads
+    /// may flip one.
+    pub fn is_armed(&self) -> bool {
+        self.rate > 0.0 || !self.flipped.is_empty()
+    }
+
+    /// Returns the number of bits currently wrong in RAM.
+    pub fn flipped_bits(&self) -> u64 {
+        self.flipped.values().map(|mask| u64::from(mask.count_ones())).sum()
+    }
+
+    /// Flips bit `bit` (0-7) of the RAM byte at `addr`.
+    ///
+    /// Returns `false`, flipping nothing, if `addr` is not in RAM or `bit`
+    /// is out of range.
+    pub fn inject(&mut self, ram: &HostRam, stats: &mut SimStats, addr: u64, bit: u8) -> bool {
+        if bit > 7 || !flip_ram(ram, addr, bit) {
+            return false;
+        }
+        let word = addr & !7;
+        let mask = self.flipped.entry(word).or_insert(0);
+        *mask ^= 1 << ((addr & 7) * 8 + u64::from(bit));
+        if *mask == 0 {
+            let _ = self.flipped.remove(&word);
+        }
+        stats.faults_injected += 1;
+        true
+    }
+
+    /// Applies the scheduled flips due by the current cycle.
+    pub fn tick(&mut self, ram: &HostRam, stats: &mut SimStats) {
+        while let Some(f) = self.pending.pop_if(|f| f.cycle <= stats.cycles) {
+            let _ = self.inject(ram, stats, f.addr, f.bit);
+        }
+    }
+
+    /// Checks a `size`-byte RAM load at `addr`, after possibly flipping a
+    /// random bit of it.
+    ///
+    /// Corrects and scrubs single-bit errors when ECC is on. Returns `false`
+    /// if a word the load reads is uncorrectable.
+    pub fn check_load(
+        &mut self,
+        ram: &HostRam,
+        stats: &mut SimStats,
+        addr: u64,
+        size: u64,
+    ) -> bool {
+        if self.rate > 0.0 && self.rng.next_f64() < self.rate {
+            let r = self.rng.next_u64();
+            let _ = self.inject(ram, stats, addr + r % size, (r >> 32) as u8 & 7);
+        }
+        if self.ecc == Ecc::None {
+            return true;
+        }
+        let mut word = addr & !7;
+        while word < addr + size {
+            if let Some(&mask) = self.flipped.get(&word) {
+                if mask.count_ones() > 1 {
+                    stats.ecc_uncorrected += 1;
+                    return false;
+                }
+                let bit = u64::from(mask.trailing_zeros());
+                let _ = flip_ram(ram, word + bit / 8, (bit % 8) as u8);
+                let _ = self.flipped.remove(&word);
+                stats.ecc_corrected += 1;
+            }
+            word += 8;
+        }
+        true
+    }
+
+    /// Clears the errors in the bytes a `size`-byte store to `addr` rewrote.
+    pub fn store(&mut self, addr: u64, size: u64) {
+        if self.flipped.is_empty() {
+            return;
+        }
+        for byte in addr..addr + size {
+            let word = byte & !7;
+            let Some(mask) = self.flipped.get_mut(&word) else { continue };
+            *mask &= !(0xff << ((byte & 7) * 8));
+            if *mask == 0 {
+                let _ = self.flipped.remove(&word);
+            }
+        }
+    }
+}
+
+/// Flips bit `bit` of the RAM byte at `addr`; returns `false` if `addr` is
+/// not in RAM.
+fn flip_ram(ram: &HostRam, addr: u64, bit: u8) -> bool {
+    ram.read(addr, 1).is_some_and(|byte| ram.write(addr, 1, byte ^ (1 << bit)))
+}
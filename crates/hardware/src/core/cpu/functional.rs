@@ -0,0 +1,500 @@
+//! Functional Execution.
+//!
+//! Executes one instruction at a time to completion, without the pipeline,
+//! for fast-forwarding to a region of interest (SimPoint-style sampling). It
+//! performs the following:
+//! 1. **Fetch and Decode:** Translates the PC, expands compressed encodings and
+//!    decodes with the pipeline's decoder, so the same encodings are legal.
+//! 2. **Execute:** Applies the privilege, CSR and `mstatus.FS` checks of the
+//!    execute stage and computes results with the same ALU/FPU code.
+//! 3. **Memory:** Performs loads, stores and atomics directly on memory,
+//!    with the alignment, translation and access-fault rules of Memory1.
+//! 4. **Retire:** Updates registers, CSRs, the PC, `minstret` and the
+//!    retirement statistics as commit does; traps and interrupts go through
+//!    [`Cpu::trap`].
+//!
+//! Every instruction takes one cycle. Translation fills the TLBs as usual;
+//! caches are only touched when warming is requested. Branch predictors are
+//! left alone.
+
+use super::{Cpu, PC_TRACE_MAX};
+use crate::common::constants::{
+    COMPRESSED_INSTRUCTION_MASK, COMPRESSED_INSTRUCTION_VALUE, OPCODE_MASK,
+};
+use crate::common::{AccessType, PhysAddr, PteUpdate, RegIdx, SfenceVmaInfo, Trap, VirtAddr};
+use crate::config::MisalignedPolicy;
+use crate::core::arch::csr;
+use crate::core::arch::mode::PrivilegeMode;
+use crate::core::pipeline::backend::inorder::execute::compute_alu;
+use crate::core::pipeline::backend::shared::cbo;
+use crate::core::pipeline::backend::shared::commit::{
+    cas_register_pair, compare_and_swap, sfence_vma_commit, update_instruction_stats,
+    write_store_to_memory,
+};
+use crate::core::pipeline::frontend::decode::decode_instruction;
+use crate::core::pipeline::signals::{
+    AtomicOp, ControlFlow, ControlSignals, CsrOp, MemWidth, OpASrc, OpBSrc, SystemOp,
+};
+use crate::core::units::lsu::{Lsu, unaligned};
+use crate::isa::decode::decode;
+use crate::isa::instruction::InstructionBits;
+use crate::isa::privileged::opcodes as sys_ops;
+use crate::isa::rv64i::{funct3, opcodes};
+use crate::isa::rvc::expand::expand;
+
+/// ADDI x0, x0, 0: eliminated at decode, so it never retires.
+const INSTRUCTION_NOP: u32 = 0x0000_0013;
+
+/// Bit position of the funct3 field.
+const FUNCT3_SHIFT: u32 = 12;
+
+/// Mask of the funct3 field after shifting.
+const FUNCT3_MASK: u32 = 0x7;
+
+/// Result of a memory access: the value for `rd` and a deferred PTE update.
+type MemResult = Result<(u64, Option<PteUpdate>), Trap>;
+
+impl Cpu {
+    /// Runs one cycle of functional execution: takes a pending interrupt,
+    /// waits in WFI, or executes the instruction at `pc` to completion.
+    ///
+    /// With `warm` set, the instruction fetch and data access are also
+    /// looked up in the caches so they hold the working set when detailed
+    /// simulation starts.
+    ///
+    /// Returns `true` if an instruction retired. Instructions that trap,
+    /// and NOPs (eliminated at decode by the pipeline), do not count.
+    pub fn step_functional(&mut self, warm: bool) -> bool {
+        if let Some(interrupt) = self.check_interrupts() {
+            let epc = if self.wfi_waiting { self.wfi_pc } else { self.pc };
+            self.wfi_waiting = false;
+            let since = self.interrupt_pending_since.take().unwrap_or(self.stats.cycles);
+            let latency = self.stats.cycles - since;
+            self.stats.interrupts_taken += 1;
+            self.stats.interrupt_latency_cycles += latency;
+            self.stats.interrupt_latency_max = self.stats.interrupt_latency_max.max(latency);
+            self.take_functional_trap(&interrupt, epc);
+            return false;
+        }
+        if self.wfi_waiting {
+            // A pending interrupt this mode does not take still wakes the hart.
+            if self.csrs.mip & self.csrs.mie == 0 {
+                self.stats.cycles_wfi += 1;
+            } else {
+                self.wfi_waiting = false;
+                self.pc = self.wfi_pc;
+                self.committed_next_pc = self.wfi_pc;
+            }
+            return false;
+        }
+
+        let pc = self.pc;
+        match self.execute_functional(pc, warm) {
+            Ok(retired) => retired,
+            Err(trap) => {
+                self.take_functional_trap(&trap, pc);
+                false
+            }
+        }
+    }
+
+    /// Takes `trap` at `epc` with nothing in flight.
+    fn take_functional_trap(&mut self, trap: &Trap, epc: u64) {
+        self.trap(trap, epc);
+        self.trap_reg_write = None;
+        self.committed_next_pc = self.pc;
+    }
+
+    /// Fetches, executes and retires the instruction at `pc`.
+    fn execute_functional(&mut self, pc: u64, warm: bool) -> Result<bool, Trap> {
+        let (inst, inst_size) = self.fetch_functional(pc, warm)?;
+        let next_pc = pc.wrapping_add(inst_size);
+        if inst == INSTRUCTION_NOP {
+            self.pc = next_pc;
+            self.committed_next_pc = next_pc;
+            return Ok(false);
+        }
+
+        let d = decode(inst);
+        let ctrl = decode_instruction(inst, pc, &d, self.csrs.misa, self.zacas, self.zicond)?;
+        let rv1 = if ctrl.rs1_fp { self.regs.read_f(d.rs1) } else { self.regs.read(d.rs1) };
+        let rv2 = if ctrl.rs2_fp { self.regs.read_f(d.rs2) } else { self.regs.read(d.rs2) };
+        let rv3 = if ctrl.rs3_fp { self.regs.read_f(inst.rs3()) } else { 0 };
+        let op_a = match ctrl.a_src {
+            OpASrc::Reg1 => rv1,
+            OpASrc::Pc => pc,
+            OpASrc::Zero => 0,
+        };
+        let op_b = match ctrl.b_src {
+            OpBSrc::Reg2 => rv2,
+            OpBSrc::Imm => d.imm as u64,
+            OpBSrc::Zero => 0,
+        };
+
+        self.check_system_op(inst, &ctrl, d.rs1)?;
+
+        let mut pte_update = None;
+        let mut cbo_paddr = None;
+        let mut csr_write = None;
+        let mut fp_flags = 0;
+        let mut target = next_pc;
+        let result = if let SystemOp::Cbo(op) = ctrl.system_op {
+            let tr = cbo::translate_block(self, op, inst, op_a);
+            if let Some(trap) = tr.trap {
+                return Err(trap);
+            }
+            pte_update = tr.pte_update;
+            cbo_paddr = Some((op, tr.paddr.val()));
+            0
+        } else if ctrl.csr_op != CsrOp::None {
+            let old = if ctrl.csr_op.reads_csr(d.rd) { self.csr_read(ctrl.csr_addr) } else { 0 };
+            let src = if ctrl.csr_op.is_imm() { d.rs1.as_usize() as u64 } else { rv1 };
+            let new = match ctrl.csr_op {
+                CsrOp::Rw | CsrOp::Rwi => src,
+                CsrOp::Rs | CsrOp::Rsi => old | src,
+                CsrOp::Rc | CsrOp::Rci => old & !src,
+                CsrOp::None => old,
+            };
+            if ctrl.csr_op.writes_csr(d.rs1) {
+                csr_write = Some(new);
+            }
+            old
+        } else if matches!(ctrl.system_op, SystemOp::None | SystemOp::Fence) {
+            let (alu_out, flags) = compute_alu(ctrl.alu, op_a, op_b, rv3, ctrl.is_rv32);
+            fp_flags = flags;
+            match ctrl.control_flow {
+                ControlFlow::Branch => {
+                    let taken = match (inst >> FUNCT3_SHIFT) & FUNCT3_MASK {
+                        funct3::BEQ => op_a == op_b,
+                        funct3::BNE => op_a != op_b,
+                        funct3::BLT => (op_a as i64) < (op_b as i64),
+                        funct3::BGE => (op_a as i64) >= (op_b as i64),
+                        funct3::BLTU => op_a < op_b,
+                        funct3::BGEU => op_a >= op_b,
+                        _ => false,
+                    };
+                    if taken {
+                        target = pc.wrapping_add(d.imm as u64);
+                    }
+                    alu_out
+                }
+                ControlFlow::Jump => {
+                    target = if inst & OPCODE_MASK == opcodes::OP_JALR {
+                        rv1.wrapping_add(d.imm as u64) & !1
+                    } else {
+                        pc.wrapping_add(d.imm as u64)
+                    };
+                    next_pc
+                }
+                ControlFlow::Sequential if ctrl.mem_read || ctrl.mem_write => {
+                    let (value, update) =
+                        self.access_functional(&ctrl, d.rd, d.rs2, alu_out, rv2, warm)?;
+                    pte_update = update;
+                    value
+                }
+                ControlFlow::Sequential => alu_out,
+            }
+        } else {
+            0
+        };
+
+        // Retire, in the order commit applies an instruction's effects.
+        self.update_trace_region(pc);
+        self.pc_trace.push((pc, inst));
+        while self.pc_trace.len() > PC_TRACE_MAX {
+            let _ = self.pc_trace.remove(0);
+        }
+        self.stats.instructions_retired += 1;
+        self.csrs.count_instret();
+        update_instruction_stats(self, &ctrl);
+
+        if self.hang_threshold.is_some() {
+            let changed = if ctrl.fp_reg_write {
+                self.regs.read_f(d.rd) != result
+            } else {
+                ctrl.reg_write && !d.rd.is_zero() && self.regs.read(d.rd) != result
+            };
+            let progress = changed || ctrl.mem_write || ctrl.system_op != SystemOp::None;
+            self.loop_detector.retire(pc, progress, self.stats.cycles);
+        }
+        if ctrl.fp_reg_write {
+            self.regs.write_f(d.rd, result);
+            self.set_fs_dirty();
+        } else if ctrl.reg_write && !d.rd.is_zero() {
+            self.regs.write(d.rd, result);
+        }
+        if let Some(upd) = pte_update {
+            write_store_to_memory(self, upd.pte_addr, upd.pte_value, MemWidth::Double);
+        }
+        if fp_flags != 0 {
+            self.csrs.fflags |= fp_flags as u64;
+            self.set_fs_dirty();
+        }
+        if let Some(new) = csr_write {
+            self.csr_write(ctrl.csr_addr, new);
+        }
+
+        self.pc = target;
+        match ctrl.system_op {
+            SystemOp::Mret => self.do_mret(),
+            SystemOp::Sret => self.do_sret(),
+            SystemOp::Wfi => {
+                if self.csrs.mie != 0 || self.csrs.mip != 0 {
+                    self.wfi_waiting = true;
+                    self.wfi_pc = next_pc;
+                }
+            }
+            SystemOp::FenceI => {
+                let _ = self.l1_i_cache.invalidate_all();
+            }
+            SystemOp::SfenceVma => {
+                let info =
+                    SfenceVmaInfo { rs1_idx: d.rs1, rs2_idx: d.rs2, rs1_val: rv1, rs2_val: rv2 };
+                sfence_vma_commit(self, &info);
+                self.clear_reservation();
+            }
+            SystemOp::Cbo(_) | SystemOp::None | SystemOp::Fence | SystemOp::System => {}
+        }
+        if let Some((op, paddr)) = cbo_paddr {
+            cbo::commit(self, op, paddr);
+        }
+        self.committed_next_pc = self.pc;
+        Ok(true)
+    }
+
+    /// Fetches the instruction at `pc` as Fetch1 and Fetch2 do.
+    ///
+    /// Returns the instruction, expanded if compressed, and its size.
+    fn fetch_functional(&mut self, pc: u64, warm: bool) -> Result<(u32, u64), Trap> {
+        let align_mask = if self.csrs.misa & csr::MISA_EXT_C != 0 { 1 } else { 3 };
+        if pc & align_mask != 0 {
+            return Err(Trap::InstructionAddressMisaligned(pc));
+        }
+        let tr = self.translate(VirtAddr::new(pc), AccessType::Fetch, 4);
+        if let Some(trap) = tr.trap {
+            return Err(trap);
+        }
+        if warm {
+            let _latency = self.simulate_memory_access(tr.paddr, AccessType::Fetch);
+        }
+        let half_word = self.read_half_functional(tr.paddr);
+        if half_word & COMPRESSED_INSTRUCTION_MASK != COMPRESSED_INSTRUCTION_VALUE {
+            let expanded = expand(half_word);
+            if expanded == 0 || self.csrs.misa & csr::MISA_EXT_C == 0 {
+                return Err(Trap::IllegalInstruction(half_word as u32));
+            }
+            return Ok((expanded, 2));
+        }
+        let upper_va = pc.wrapping_add(2);
+        let upper = if (pc >> 12) == (upper_va >> 12) {
+            PhysAddr::new(tr.paddr.val() + 2)
+        } else {
+            let hi = self.translate(VirtAddr::new(upper_va), AccessType::Fetch, 2);
+            if let Some(trap) = hi.trap {
+                return Err(trap);
+            }
+            hi.paddr
+        };
+        let upper_half = self.read_half_functional(upper);
+        Ok(((upper_half as u32) << 16 | half_word as u32, 4))
+    }
+
+    /// Reads an instruction half-word at `paddr`, from RAM when it is there.
+    fn read_half_functional(&mut self, paddr: PhysAddr) -> u16 {
+        self.ram.read(paddr.val(), 2).map_or_else(|| self.bus.bus.read_u16(paddr), |v| v as u16)
+    }
+
+    /// Applies the execute stage's privilege checks and raises ECALL.
+    fn check_system_op(&self, inst: u32, ctrl: &ControlSignals, rs1: RegIdx) -> Result<(), Trap> {
+        if self.is_illegal_at_execute(ctrl, rs1) {
+            return Err(Trap::IllegalInstruction(inst));
+        }
+        if ctrl.system_op == SystemOp::System && inst == sys_ops::ECALL {
+            return Err(match self.privilege {
+                PrivilegeMode::User => Trap::EnvironmentCallFromUMode,
+                PrivilegeMode::Supervisor => Trap::EnvironmentCallFromSMode,
+                PrivilegeMode::Machine => Trap::EnvironmentCallFromMMode,
+            });
+        }
+        Ok(())
+    }
+
+    /// Performs the load, store or atomic of an instruction at virtual
+    /// address `vaddr` and returns the value written to `rd`.
+    ///
+    /// `store_data` is the value of `rs2`; `rd` and `rs2` name the AMOCAS
+    /// operand registers.
+    fn access_functional(
+        &mut self,
+        ctrl: &ControlSignals,
+        rd: RegIdx,
+        rs2: RegIdx,
+        vaddr: u64,
+        store_data: u64,
+        warm: bool,
+    ) -> MemResult {
+        let (paddr, pte_update) = self.translate_data_functional(ctrl, vaddr)?;
+        let raw = paddr.val();
+        let size = unaligned::width_to_bytes(ctrl.width);
+        let access = if ctrl.mem_write { AccessType::Write } else { AccessType::Read };
+        if warm && raw >= self.cache_base {
+            let _latency = self.simulate_memory_access(paddr, access);
+        }
+        let sext_word = |v: u64| (v as u32 as i32) as i64 as u64;
+
+        let value = match ctrl.atomic_op {
+            AtomicOp::None if ctrl.mem_read => {
+                let is_ram = self.ram.contains(raw, size);
+                if is_ram
+                    && self.faults.is_armed()
+                    && !self.faults.check_load(&self.ram, &mut self.stats, raw, size)
+                {
+                    return Err(Trap::LoadAccessFault(vaddr));
+                }
+                let shift = 64 - 8 * size;
+                let extend = |v: u64| {
+                    if ctrl.signed_load { (((v << shift) as i64) >> shift) as u64 } else { v }
+                };
+                let mut ld = if is_ram {
+                    self.ram.read(raw, size).map_or(0, extend)
+                } else if unaligned::needs_split(raw, size) {
+                    extend(unaligned::split_load(raw, size, |a| {
+                        self.bus.bus.read_u8(PhysAddr::new(a))
+                    }))
+                } else {
+                    match ctrl.width {
+                        MemWidth::Byte => extend(self.bus.bus.read_u8(paddr) as u64),
+                        MemWidth::Half => extend(self.bus.bus.read_u16(paddr) as u64),
+                        MemWidth::Word => extend(self.bus.bus.read_u32(paddr) as u64),
+                        MemWidth::Double => self.bus.bus.read_u64(paddr),
+                        MemWidth::Quad | MemWidth::Nop => 0,
+                    }
+                };
+                // NaN-boxing for FP loads.
+                if ctrl.fp_reg_write && matches!(ctrl.width, MemWidth::Word) {
+                    ld |= 0xFFFF_FFFF_0000_0000;
+                }
+                ld
+            }
+            AtomicOp::None => {
+                write_store_to_memory(self, paddr, store_data, ctrl.width);
+                self.invalidate_reservation(paddr, size);
+                0
+            }
+            AtomicOp::Lr => {
+                let ld = match ctrl.width {
+                    MemWidth::Word => sext_word(self.bus.bus.read_u32(paddr) as u64),
+                    MemWidth::Double => self.bus.bus.read_u64(paddr),
+                    _ => 0,
+                };
+                self.set_reservation(paddr);
+                ld
+            }
+            AtomicOp::Sc => {
+                if self.check_reservation(paddr) {
+                    self.clear_reservation();
+                    write_store_to_memory(self, paddr, store_data, ctrl.width);
+                    0
+                } else {
+                    1
+                }
+            }
+            AtomicOp::Cas => {
+                let expected = cas_register_pair(self, rd);
+                let swap = cas_register_pair(self, rs2);
+                let (old_lo, old_hi) = compare_and_swap(self, paddr, ctrl.width, expected, swap);
+                if matches!(ctrl.width, MemWidth::Quad) && !rd.is_zero() {
+                    self.regs.write(RegIdx::new(rd.as_u8() + 1), old_hi);
+                }
+                old_lo
+            }
+            op if !self.ram.contains(raw, size)
+                || self.htif_range.is_some_and(|(lo, hi)| raw >= lo && raw < hi) =>
+            {
+                // Device registers see the read-modify-write as one bus access.
+                let width = ctrl.width;
+                let old = self.bus.bus.read_modify_write(paddr, size, |old| {
+                    let old = if size == 4 { sext_word(old) } else { old };
+                    Lsu::atomic_alu(op, old, store_data, width)
+                });
+                if size == 4 { sext_word(old) } else { old }
+            }
+            op => {
+                let old = match ctrl.width {
+                    MemWidth::Word => sext_word(self.bus.bus.read_u32(paddr) as u64),
+                    MemWidth::Double => self.bus.bus.read_u64(paddr),
+                    _ => 0,
+                };
+                let new = Lsu::atomic_alu(op, old, store_data, ctrl.width);
+                write_store_to_memory(self, paddr, new, ctrl.width);
+                self.invalidate_reservation(paddr, size);
+                old
+            }
+        };
+        Ok((value, pte_update))
+    }
+
+    /// Checks alignment and translates data address `vaddr` as Memory1 does,
+    /// including the second page of a split misaligned access and the
+    /// physical access-fault checks.
+    fn translate_data_functional(
+        &mut self,
+        ctrl: &ControlSignals,
+        vaddr: u64,
+    ) -> Result<(PhysAddr, Option<PteUpdate>), Trap> {
+        let size = unaligned::width_to_bytes(ctrl.width);
+        let misaligned_trap = || {
+            if ctrl.mem_write {
+                unaligned::store_misaligned_trap(vaddr)
+            } else {
+                unaligned::load_misaligned_trap(vaddr)
+            }
+        };
+        // Atomics always require natural alignment.
+        if !unaligned::is_aligned(vaddr, size)
+            && (self.misaligned == MisalignedPolicy::Trap || ctrl.atomic_op != AtomicOp::None)
+        {
+            return Err(misaligned_trap());
+        }
+
+        let access = if ctrl.mem_write { AccessType::Write } else { AccessType::Read };
+        let tr = self.translate(VirtAddr::new(vaddr), access, size);
+        if let Some(trap) = tr.trap {
+            return Err(trap);
+        }
+        let (paddr, mut pte_update) = (tr.paddr, tr.pte_update);
+        if let (_, Some((hi_vaddr, hi_len))) = unaligned::split_at_granule(vaddr, size)
+            && hi_vaddr % crate::common::constants::PAGE_SIZE == 0
+        {
+            let hi = self.translate(VirtAddr::new(hi_vaddr), access, hi_len);
+            if let Some(trap) = hi.trap {
+                return Err(trap);
+            }
+            if hi.paddr.val() != paddr.val().wrapping_add(hi_vaddr - vaddr)
+                || (pte_update.is_some() && hi.pte_update.is_some())
+            {
+                return Err(misaligned_trap());
+            }
+            pte_update = pte_update.or(hi.pte_update);
+        }
+
+        let past_bank_end = self.is_ram(paddr.val()) && !self.ram.contains(paddr.val(), size);
+        if past_bank_end
+            || (self.data_privilege() != PrivilegeMode::Machine
+                && !self.bus.bus.is_valid_address(paddr))
+        {
+            return Err(if ctrl.mem_write {
+                Trap::StoreAccessFault(vaddr)
+            } else {
+                Trap::LoadAccessFault(vaddr)
+            });
+        }
+        Ok((paddr, pte_update))
+    }
+
+    /// Marks the floating-point state dirty in `mstatus` and `sstatus`.
+    const fn set_fs_dirty(&mut self) {
+        self.csrs.mstatus = (self.csrs.mstatus & !csr::MSTATUS_FS) | csr::MSTATUS_FS_DIRTY;
+        self.csrs.sstatus = (self.csrs.sstatus & !csr::MSTATUS_FS) | csr::MSTATUS_FS_DIRTY;
+    }
+}
@@ -0,0 +1,60 @@
+//! Livelock Detection.
+//!
+//! The same-PC watchdog only catches a hart stuck on one instruction. Real
+//! hangs are usually small loops: a spin on a flag that never changes, or a
+//! retry that never succeeds. [`LoopDetector`] watches the committed
+//! instruction stream and tracks how long it has stayed within a set of two
+//! to [`LOOP_MAX_PCS`] distinct PCs without architectural progress; a single
+//! PC is left to the same-PC watchdog. An instruction makes progress if it
+//! writes a register with a new value, writes memory, or is a system
+//! instruction (CSR access, fence, `ecall`, ...); a trap, or a hart parked in
+//! WFI, also restarts the window.
+
+/// Largest set of distinct PCs still considered a tight loop.
+pub const LOOP_MAX_PCS: usize = 8;
+
+/// Tracks how long the committed PCs have stayed in a small set without
+/// architectural progress.
+#[derive(Clone, Debug, Default)]
+pub struct LoopDetector {
+    /// Distinct PCs committed since the window started.
+    pcs: Vec<u64>,
+    /// Cycle at which the window started.
+    since: u64,
+}
+
+impl LoopDetector {
+    /// Records an instruction committed at `pc` in `cycle`.
+    ///
+    /// Progress, or a PC that would grow the set past [`LOOP_MAX_PCS`],
+    /// starts a new window.
+    pub fn retire(&mut self, pc: u64, progress: bool, cycle: u64) {
+        if progress {
+            self.restart(cycle);
+        } else if !self.pcs.contains(&pc) {
+            if self.pcs.len() == LOOP_MAX_PCS {
+                self.restart(cycle);
+            }
+            self.pcs.push(pc);
+        }
+    }
+
+    /// Starts a new, empty window at `cycle`.
+    pub fn restart(&mut self, cycle: u64) {
+        self.pcs.clear();
+        self.since = cycle;
+    }
+
+    /// Returns the cycles spent in the current loop as of `cycle`, or 0 if
+    /// fewer than two PCs have committed since the window started.
+    pub const fn cycles_in_loop(&self, cycle: u64) -> u64 {
+        if self.pcs.len() < 2 { 0 } else { cycle.saturating_sub(self.since) }
+    }
+
+    /// Returns the loop's PCs in ascending order.
+    pub fn loop_pcs(&self) -> Vec<u64> {
+        let mut pcs = self.pcs.clone();
+        pcs.sort_unstable();
+        pcs
+    }
+}
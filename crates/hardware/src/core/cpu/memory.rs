@@ -5,13 +5,41 @@
 //! 1. **Address Translation:** Interfaces with the MMU to convert virtual to physical addresses.
 //! 2. **Cache Simulation:** Models the behavior of L1, L2, and L3 caches during memory access.
 //! 3. **Latency Modeling:** Calculates timing penalties for cache hits, misses, and bus transit.
+//! 4. **Bandwidth Accounting:** Counts the bytes each fill, prefetch and writeback moves
+//!    between levels.
 
 use super::Cpu;
 use crate::common::{AccessType, PhysAddr, TranslationResult, Trap, VirtAddr};
 use crate::config::InclusionPolicy;
+use crate::core::arch::csr::{MSTATUS_MPP_MASK, MSTATUS_MPP_SHIFT, MSTATUS_MPRV};
+use crate::core::arch::mode::PrivilegeMode;
+use crate::core::units::cache::EvictedLine;
+use crate::core::units::cache::bandwidth::{Link, Traffic};
 use crate::core::units::mmu::pmp::PmpResult;
 
+/// Size of a DRAM fill transfer (one cache line) in bytes.
+const DRAM_FILL_BYTES: usize = 64;
+
 impl Cpu {
+    /// Returns whether physical address `paddr` is in a RAM bank.
+    #[inline]
+    pub fn is_ram(&self, paddr: u64) -> bool {
+        self.ram.contains(paddr, 1)
+    }
+
+    /// Returns the privilege loads and stores are translated and checked at:
+    /// `mstatus.MPP` while `mstatus.MPRV` is set, otherwise the current mode.
+    /// Instruction fetches always use the current mode.
+    pub const fn data_privilege(&self) -> PrivilegeMode {
+        if self.csrs.mstatus & MSTATUS_MPRV == 0 {
+            self.privilege
+        } else {
+            PrivilegeMode::from_u8(
+                ((self.csrs.mstatus >> MSTATUS_MPP_SHIFT) & MSTATUS_MPP_MASK) as u8,
+            )
+        }
+    }
+
     /// Translates a virtual address to a physical address using the MMU.
     ///
     /// # Arguments
@@ -41,17 +69,9 @@ impl Cpu {
             return TranslationResult::success(paddr, 0);
         }
 
-        // MPRV: when set and access is not Fetch, use MPP as effective privilege.
-        let effective_priv = if access != AccessType::Fetch
-            && (self.csrs.mstatus & crate::core::arch::csr::MSTATUS_MPRV) != 0
-        {
-            use crate::core::arch::csr::{MSTATUS_MPP_MASK, MSTATUS_MPP_SHIFT};
-            use crate::core::arch::mode::PrivilegeMode;
-            let mpp = ((self.csrs.mstatus >> MSTATUS_MPP_SHIFT) & MSTATUS_MPP_MASK) as u8;
-            PrivilegeMode::from_u8(mpp)
-        } else {
-            self.privilege
-        };
+        // MPRV: loads and stores (not fetches) use MPP as effective privilege.
+        let effective_priv =
+            if access == AccessType::Fetch { self.privilege } else { self.data_privilege() };
 
         let result = self.mmu.translate_with_pmp(
             vaddr,
@@ -67,7 +87,7 @@ impl Cpu {
         // S/U-mode with no matching entry gets NoMatch (denied).
         if result.trap.is_none() {
             let paddr = result.paddr.val();
-            let is_machine = effective_priv == crate::core::arch::mode::PrivilegeMode::Machine;
+            let is_machine = effective_priv == PrivilegeMode::Machine;
             let pmp_result = self.pmp.check(
                 paddr,
                 size,
@@ -102,7 +122,14 @@ impl Cpu {
     ///   buffers) and do **not** add latency to the demand path.
     /// - The DRAM controller is only consulted when all caches miss, so its
     ///   stateful bank/row-buffer/refresh tracking reflects real traffic only.
-    pub fn simulate_l1d_miss_latency(&mut self, addr: PhysAddr, access: AccessType) -> u64 {
+    /// - If the controller queues requests, the miss is queued and its id
+    ///   returned alongside the latency, which then excludes the DRAM access
+    ///   itself; the MSHR adds it once the controller completes the request.
+    pub fn simulate_l1d_miss_latency(
+        &mut self,
+        addr: PhysAddr,
+        access: AccessType,
+    ) -> (u64, Option<u64>) {
         // Dirty writebacks are fire-and-forget into write buffers (gem5 WriteBuffer
         // queue model). They do not block the demand miss, so we pass 0 as the
         // next-level-latency used for dirty victim writeback costing.
@@ -111,6 +138,7 @@ impl Cpu {
         let raw_addr = addr.val();
         let is_write = matches!(access, AccessType::Write);
         let inclusion = self.inclusion_policy;
+        self.account_lines(1, Traffic::Demand, 1, self.l1_d_cache.line_bytes());
 
         if self.l2_cache.enabled {
             total_penalty += self.l2_cache.latency;
@@ -120,7 +148,11 @@ impl Cpu {
             // Filter and install L2 prefetch candidates through the shared filter
             let filtered =
                 self.prefetch_filter.filter_and_record(l2_prefetches, &mut self.stats.pf_dedup_l2);
-            let pf_evictions = self.l2_cache.install_prefetches(&filtered, WB_LAT);
+            let (prefetched, pf_evictions) = self.l2_cache.install_prefetches(&filtered, WB_LAT);
+            let line = self.l2_cache.line_bytes();
+            self.account_lines(2, Traffic::Prefetch, prefetched, line);
+            self.account_writebacks(2, &l2_evictions, line);
+            self.account_writebacks(2, &pf_evictions, line);
 
             // Inclusive policy: L2 eviction → back-invalidate matching L1D/L1I lines
             if inclusion == InclusionPolicy::Inclusive {
@@ -136,9 +168,10 @@ impl Cpu {
 
             if l2_hit {
                 self.stats.l2_hits += 1;
-                return total_penalty;
+                return (total_penalty, None);
             }
             self.stats.l2_misses += 1;
+            self.account_lines(2, Traffic::Demand, 1, line);
         }
 
         if self.l3_cache.enabled {
@@ -149,7 +182,11 @@ impl Cpu {
             // Filter and install L3 prefetch candidates
             let filtered =
                 self.prefetch_filter.filter_and_record(l3_prefetches, &mut self.stats.pf_dedup_l3);
-            let pf_evictions = self.l3_cache.install_prefetches(&filtered, WB_LAT);
+            let (prefetched, pf_evictions) = self.l3_cache.install_prefetches(&filtered, WB_LAT);
+            let line = self.l3_cache.line_bytes();
+            self.account_lines(3, Traffic::Prefetch, prefetched, line);
+            self.account_writebacks(3, &l3_evictions, line);
+            self.account_writebacks(3, &pf_evictions, line);
 
             // Inclusive policy: L3 eviction → back-invalidate L2, L1D, L1I
             if inclusion == InclusionPolicy::Inclusive {
@@ -166,17 +203,90 @@ impl Cpu {
 
             if l3_hit {
                 self.stats.l3_hits += 1;
-                return total_penalty;
+                return (total_penalty, None);
             }
             self.stats.l3_misses += 1;
+            self.account_lines(3, Traffic::Demand, 1, line);
         }
 
         // All caches missed — now query the DRAM controller (stateful).
-        let ram_latency = self.bus.mem_controller.access_latency(raw_addr, self.stats.cycles);
+        let cycle = self.stats.cycles;
+        let queued =
+            self.bus.mem_controller.enqueue(raw_addr, DRAM_FILL_BYTES as u64, is_write, cycle);
+        if queued.is_none() {
+            total_penalty += self.bus.mem_controller.request(
+                raw_addr,
+                DRAM_FILL_BYTES as u64,
+                cycle,
+                &mut self.stats.mem_traffic,
+            );
+        }
         total_penalty += self.bus.bus.calculate_transit_time(8);
-        total_penalty += ram_latency;
-        total_penalty += self.bus.bus.calculate_transit_time(64);
-        total_penalty
+        total_penalty += self.bus.bus.calculate_transit_time(DRAM_FILL_BYTES);
+        (total_penalty, queued)
+    }
+
+    /// Collects the completion times of queued DRAM requests that L1D MSHRs
+    /// are waiting on.
+    pub fn resolve_dram_requests(&mut self) {
+        let controller = &mut self.bus.mem_controller;
+        self.l1d_mshrs.resolve_dram(|id| controller.take_completion(id));
+    }
+
+    /// Probes L1D for a non-blocking access (see [`CacheSim::access_check`]),
+    /// accounting the traffic of any prefetches it triggers.
+    ///
+    /// [`CacheSim::access_check`]: crate::core::units::cache::CacheSim::access_check
+    pub fn l1d_access_check(&mut self, addr: u64, is_write: bool) -> bool {
+        let (hit, prefetched, evictions) = self.l1_d_cache.access_check(addr, is_write);
+        let line = self.l1_d_cache.line_bytes();
+        self.account_lines(1, Traffic::Prefetch, prefetched, line);
+        self.account_writebacks(1, &evictions, line);
+        hit
+    }
+
+    /// Installs the line an L1D MSHR fetched. Under the exclusive policy the
+    /// victim moves to L2; a dirty victim is accounted as a writeback.
+    pub fn install_l1d_fill(&mut self, line_addr: u64, is_write: bool) {
+        // Write-back penalty already accounted for in miss latency
+        let (_penalty, evicted) =
+            self.l1_d_cache.install_line_public_tracked(line_addr, is_write, 0);
+        let Some(ev) = evicted else { return };
+        self.account_writebacks(1, &[ev], self.l1_d_cache.line_bytes());
+
+        if self.inclusion_policy == InclusionPolicy::Exclusive && self.l2_cache.enabled {
+            let _ = self.l2_cache.install_or_replace(ev.addr, ev.dirty, 0);
+            self.stats.exclusive_l1_to_l2_swaps += 1;
+        }
+    }
+
+    /// Returns the link from cache `level` (1–3) to the next enabled level
+    /// below it.
+    const fn link_below(&self, level: u8) -> Link {
+        if level < 2 && self.l2_cache.enabled {
+            Link::L2
+        } else if level < 3 && self.l3_cache.enabled {
+            Link::L3
+        } else {
+            Link::Dram
+        }
+    }
+
+    /// Accounts `lines` lines of `line_bytes` crossing the link below cache
+    /// `level` as `traffic`.
+    fn account_lines(&mut self, level: u8, traffic: Traffic, lines: usize, line_bytes: usize) {
+        if lines > 0 {
+            let link = self.link_below(level);
+            let bytes = (lines * line_bytes) as u64;
+            self.stats.bandwidth.record(link, traffic, bytes, self.stats.cycles);
+        }
+    }
+
+    /// Accounts the dirty lines among `evictions` from cache `level` as
+    /// writebacks to the level below.
+    fn account_writebacks(&mut self, level: u8, evictions: &[EvictedLine], line_bytes: usize) {
+        let dirty = evictions.iter().filter(|ev| ev.dirty).count();
+        self.account_lines(level, Traffic::Writeback, dirty, line_bytes);
     }
 
     /// Simulates a memory access through the full cache hierarchy (L1 → L2 → L3 → DRAM).
@@ -208,10 +318,16 @@ impl Cpu {
 
         // If no cache level is enabled, every access goes directly to DRAM.
         if !l1_enabled && !self.l2_cache.enabled && !self.l3_cache.enabled {
-            let ram_latency = self.bus.mem_controller.access_latency(raw_addr, self.stats.cycles);
+            self.account_lines(3, Traffic::Demand, 1, DRAM_FILL_BYTES);
+            let ram_latency = self.bus.mem_controller.request(
+                raw_addr,
+                DRAM_FILL_BYTES as u64,
+                self.stats.cycles,
+                &mut self.stats.mem_traffic,
+            );
             return self.bus.bus.calculate_transit_time(8)
                 + ram_latency
-                + self.bus.bus.calculate_transit_time(64);
+                + self.bus.bus.calculate_transit_time(DRAM_FILL_BYTES);
         }
 
         // ── L1 ──────────────────────────────────────────────────────────────────
@@ -230,11 +346,16 @@ impl Cpu {
         // Filter L1 prefetch candidates through the shared filter, then install
         let filtered_l1 =
             self.prefetch_filter.filter_and_record(l1_prefetches, &mut self.stats.pf_dedup_l1);
-        let l1_pf_evictions = if is_inst {
+        let (l1_prefetched, l1_pf_evictions) = if is_inst {
             self.l1_i_cache.install_prefetches(&filtered_l1, WB_LAT)
         } else {
             self.l1_d_cache.install_prefetches(&filtered_l1, WB_LAT)
         };
+        let l1_line =
+            if is_inst { self.l1_i_cache.line_bytes() } else { self.l1_d_cache.line_bytes() };
+        self.account_lines(1, Traffic::Prefetch, l1_prefetched, l1_line);
+        self.account_writebacks(1, &l1_evictions, l1_line);
+        self.account_writebacks(1, &l1_pf_evictions, l1_line);
 
         // Exclusive policy: L1 eviction → install evicted line into L2
         if inclusion == InclusionPolicy::Exclusive && self.l2_cache.enabled {
@@ -250,12 +371,14 @@ impl Cpu {
                 return total_penalty;
             }
             self.stats.icache_misses += 1;
+            self.account_lines(1, Traffic::Demand, 1, l1_line);
         } else if !is_inst && self.l1_d_cache.enabled {
             if l1_hit {
                 self.stats.dcache_hits += 1;
                 return total_penalty;
             }
             self.stats.dcache_misses += 1;
+            self.account_lines(1, Traffic::Demand, 1, l1_line);
         }
 
         // ── L2 ──────────────────────────────────────────────────────────────────
@@ -267,7 +390,12 @@ impl Cpu {
             // Filter and install L2 prefetch candidates
             let filtered_l2 =
                 self.prefetch_filter.filter_and_record(l2_prefetches, &mut self.stats.pf_dedup_l2);
-            let l2_pf_evictions = self.l2_cache.install_prefetches(&filtered_l2, WB_LAT);
+            let (l2_prefetched, l2_pf_evictions) =
+                self.l2_cache.install_prefetches(&filtered_l2, WB_LAT);
+            let l2_line = self.l2_cache.line_bytes();
+            self.account_lines(2, Traffic::Prefetch, l2_prefetched, l2_line);
+            self.account_writebacks(2, &l2_evictions, l2_line);
+            self.account_writebacks(2, &l2_pf_evictions, l2_line);
 
             // Inclusive policy: L2 eviction → back-invalidate L1 lines
             if inclusion == InclusionPolicy::Inclusive {
@@ -291,6 +419,7 @@ impl Cpu {
                 return total_penalty;
             }
             self.stats.l2_misses += 1;
+            self.account_lines(2, Traffic::Demand, 1, l2_line);
         }
 
         // ── L3 ──────────────────────────────────────────────────────────────────
@@ -302,7 +431,12 @@ impl Cpu {
             // Filter and install L3 prefetch candidates
             let filtered_l3 =
                 self.prefetch_filter.filter_and_record(l3_prefetches, &mut self.stats.pf_dedup_l3);
-            let l3_pf_evictions = self.l3_cache.install_prefetches(&filtered_l3, WB_LAT);
+            let (l3_prefetched, l3_pf_evictions) =
+                self.l3_cache.install_prefetches(&filtered_l3, WB_LAT);
+            let l3_line = self.l3_cache.line_bytes();
+            self.account_lines(3, Traffic::Prefetch, l3_prefetched, l3_line);
+            self.account_writebacks(3, &l3_evictions, l3_line);
+            self.account_writebacks(3, &l3_pf_evictions, l3_line);
 
             // Inclusive policy: L3 eviction → back-invalidate L2, L1D, L1I
             if inclusion == InclusionPolicy::Inclusive {
@@ -322,15 +456,21 @@ impl Cpu {
                 return total_penalty;
             }
             self.stats.l3_misses += 1;
+            self.account_lines(3, Traffic::Demand, 1, l3_line);
         }
 
         // ── DRAM (all caches missed) ────────────────────────────────────────────
         // Only now do we consult the stateful DRAM controller, so its bank,
         // row-buffer, and refresh state reflects real memory traffic only.
-        let ram_latency = self.bus.mem_controller.access_latency(raw_addr, self.stats.cycles);
+        let ram_latency = self.bus.mem_controller.request(
+            raw_addr,
+            DRAM_FILL_BYTES as u64,
+            self.stats.cycles,
+            &mut self.stats.mem_traffic,
+        );
         total_penalty += self.bus.bus.calculate_transit_time(8);
         total_penalty += ram_latency;
-        total_penalty += self.bus.bus.calculate_transit_time(64);
+        total_penalty += self.bus.bus.calculate_transit_time(DRAM_FILL_BYTES);
         total_penalty
     }
 }
@@ -6,31 +6,66 @@
 //! 2. **Memory Hierarchy:** MMU, TLBs, and multi-level cache simulations.
 //! 3. **System Integration:** System bus, devices, and RAM.
 
+/// Physical memory access trace for external cache simulators.
+pub mod access_trace;
+
 /// Control and Status Register access and management.
 pub mod csr;
 
 /// Instruction execution orchestration and pipeline coordination.
 pub mod execution;
 
+/// Atomic instruction execution without the pipeline, for fast-forwarding.
+pub mod functional;
+
+/// RAM bit-flip injection and ECC checking on loads.
+pub mod fault;
+
+/// Detection of tight loops that make no architectural progress.
+pub mod livelock;
+
 /// Memory access handling and load/store operations.
 pub mod memory;
 
+/// Direct RAM access (raw-pointer fast path and bounds-checked safe path).
+pub mod ram;
+
+/// RISC-V semihosting calls serviced on the host.
+pub mod semihosting;
+
+/// Linux system calls serviced on the host (syscall emulation).
+pub mod syscall;
+
 /// Trap and exception handling logic.
 pub mod trap;
 
-use crate::common::{PhysAddr, RegisterFile};
-use crate::config::{Config, InclusionPolicy};
+use crate::common::{PhysAddr, RegIdx, RegisterFile};
+use crate::config::{
+    AccessTraceFormat, Config, InclusionPolicy, MisalignedPolicy, PipeTraceFormat,
+};
 use crate::core::arch::csr::Csrs;
 use crate::core::arch::mode::PrivilegeMode;
+use crate::core::cpu::access_trace::{AccessRecord, AccessTrace};
+use crate::core::cpu::livelock::LoopDetector;
+use crate::core::pipeline::frontend::fusion::FusionPattern;
+use crate::core::pipeline::pipe_trace::{PipeStage, PipeTrace};
+use crate::core::pipeline::rob::RobTag;
 use crate::core::pipeline::write_buffer::WriteCombiningBuffer;
 use crate::core::units::bru::BranchPredictorWrapper;
 use crate::core::units::cache::CacheSim;
+use crate::core::units::cache::bandwidth::BandwidthStats;
 use crate::core::units::cache::mshr::MshrFile;
 use crate::core::units::mmu::Mmu;
 use crate::core::units::mmu::pmp::Pmp;
 use crate::core::units::prefetch::PrefetchFilter;
 use crate::soc::System;
 use crate::stats::SimStats;
+use fault::FaultInjector;
+use ram::HostRam;
+use semihosting::Semihosting;
+use std::collections::VecDeque;
+use syscall::SyscallEmulation;
+use trap::TrapRecord;
 
 /// CPU architectural state: registers, caches, MMU, bus, and statistics.
 ///
@@ -43,12 +78,19 @@ pub struct Cpu {
     pub regs: RegisterFile,
     /// Program Counter.
     pub pc: u64,
+    /// PC the hart restarts from on [`Self::reset`] (`general.start_pc`).
+    pub reset_vector: u64,
     /// Control and Status Registers.
     pub csrs: Csrs,
     /// Current Privilege Mode (M, S, U).
     pub privilege: PrivilegeMode,
     /// Load Reservation address (for LR/SC).
     pub load_reservation: Option<PhysAddr>,
+    /// Memory writes (address, size) this hart made during the current cycle;
+    /// the simulator invalidates the other harts' reservations against them.
+    pub store_log: Vec<(PhysAddr, u64)>,
+    /// Index of this hart in the system (value of `mhartid`).
+    pub hart_id: usize,
 
     /// System Bus and Devices.
     pub bus: System,
@@ -70,6 +112,8 @@ pub struct Cpu {
     pub inclusion_policy: InclusionPolicy,
     /// Write Combining Buffer for store coalescing.
     pub wcb: WriteCombiningBuffer,
+    /// L1D accesses that can start per cycle in Memory1 (0 = unlimited).
+    pub l1_d_ports: usize,
     /// Shared prefetch filter to deduplicate prefetch requests across cache levels.
     pub prefetch_filter: PrefetchFilter,
     /// Base address of RAM — addresses at or above this go through the
@@ -86,8 +130,17 @@ pub struct Cpu {
     /// I-cache line size in bytes (for cache-line-aligned fetch).
     pub i_cache_line_bytes: usize,
 
-    /// Enable instruction tracing.
+    /// Instruction tracing guard: on while tracing is requested and commit is
+    /// inside the trace region.
     pub trace: bool,
+    /// Instruction tracing requested (`general.trace_instructions`).
+    pub trace_requested: bool,
+    /// Trace region start (`general.trace_start_pc`).
+    pub trace_start_pc: Option<u64>,
+    /// Trace region end (`general.trace_stop_pc`).
+    pub trace_stop_pc: Option<u64>,
+    /// Commit is inside the trace region.
+    pub trace_in_region: bool,
     /// Exit code if simulation finished.
     pub exit_code: Option<u64>,
     /// Performance statistics.
@@ -100,40 +153,59 @@ pub struct Cpu {
     pub last_pc: u64,
     /// Hang detection counter.
     pub same_pc_count: u64,
+    /// Watchdog same-PC and livelock threshold (`general.hang_threshold`).
+    pub hang_threshold: Option<u64>,
+    /// Livelock detector fed by commit while `hang_threshold` is set.
+    pub loop_detector: LoopDetector,
+    /// Watchdog cycle budget (`general.max_cycles`).
+    pub max_cycles: Option<u64>,
     /// WFI state.
     pub wfi_waiting: bool,
     /// PC when WFI was entered.
     pub wfi_pc: u64,
+    /// Cycle at which an enabled interrupt (`mip & mie`) became pending, for
+    /// interrupt latency accounting. Cleared when it is taken or withdrawn.
+    pub interrupt_pending_since: Option<u64>,
     /// The PC that the next committed instruction should start at.
     /// Updated after every commit to `entry.pc + entry.inst_size`.
     /// Used as the EPC for interrupts when the ROB is empty, because
     /// `cpu.pc` is the *fetch* PC, which can be far ahead of the commit point.
     pub committed_next_pc: u64,
-    /// Raw pointer to the start of simulated RAM.
-    ///
-    /// # Safety Invariants
-    ///
-    /// This pointer must maintain the following invariants at all times:
-    /// - Points to a valid, allocated memory region of size `(ram_end - ram_start)` bytes
-    /// - The memory region remains valid for the entire lifetime of the `Cpu` instance
-    /// - All accesses must verify: `ram_start <= address < ram_end` before dereferencing
-    /// - The pointer is valid for both reads and writes (memory is mutable)
-    /// - Memory is properly aligned for the underlying allocation (even if individual
-    ///   accesses use `read_unaligned`/`write_unaligned`)
-    /// - No other code may free or reallocate this memory while the CPU exists
-    /// - The pointer remains valid across CPU state changes and pipeline operations
-    pub ram_ptr: *mut u8,
-    /// Physical address where RAM starts.
-    pub ram_start: u64,
-    /// Physical address where RAM ends (exclusive).
-    pub ram_end: u64,
+    /// Commit stops just before the instruction at this PC retires.
+    /// Armed via `Simulator::set_breakpoint`.
+    pub break_pc: Option<u64>,
+    /// Let the instruction at `break_pc` retire once (it was already next to
+    /// commit when the breakpoint was armed).
+    pub break_skip: bool,
+    /// Set by commit when it stopped at `break_pc`.
+    pub break_hit: bool,
+    /// Commit stops once `stats.instructions_retired` reaches this count.
+    /// Armed via `Simulator::run_detailed`.
+    pub retire_limit: Option<u64>,
+    /// RAM banks accessed directly by fetch, loads, and stores, bypassing the bus.
+    pub ram: HostRam,
+    /// Bit flips injected into RAM and the ECC checking loads against them.
+    pub faults: FaultInjector,
 
     /// HTIF tohost address range (start, end). Stores in this range bypass the
     /// RAM fast-path and go through the bus so the HTIF device can intercept them.
     pub htif_range: Option<(u64, u64)>,
 
+    /// Semihosting state, present when `general.semihosting` is set.
+    pub semihosting: Option<Semihosting>,
+
+    /// Emulated process state, present when `general.syscall_emulation` is set.
+    pub syscall_emulation: Option<SyscallEmulation>,
+
+    /// Register result of a trap the simulator serviced itself (a semihosting
+    /// or emulated system call), for a backend that keeps its own copy of the register file.
+    /// `regs` already holds the value.
+    pub trap_reg_write: Option<(RegIdx, u64)>,
+
     /// Ring buffer of (pc, inst) for last N retired instructions (for invalid-PC debug trace).
     pub pc_trace: Vec<(u64, u32)>,
+    /// Ring buffer of the last [`TRAP_LOG_MAX`] traps taken, oldest first.
+    pub recent_traps: VecDeque<TrapRecord>,
     /// Last invalid PC we printed debug for (avoid duplicate dumps).
     pub last_invalid_pc_debug: Option<u64>,
 
@@ -143,12 +215,25 @@ pub struct Cpu {
     /// redirects when the target happens to equal the current fetch PC.
     pub redirect_pending: bool,
 
+    /// Set with `redirect_pending` when the redirect is a branch or jump
+    /// misprediction, so the pipeline can add the configured penalty.
+    pub mispredict_pending: bool,
+
     /// Software-managed A/D bits: PTW faults on A=0 or D=0 instead of
     /// auto-setting them (matches spike's behavior for log comparison).
     pub software_ad_bits: bool,
 
-    /// Trap on misaligned memory accesses instead of handling them natively.
-    pub misaligned_access_trap: bool,
+    /// How the LSU handles misaligned loads and stores.
+    pub misaligned: MisalignedPolicy,
+
+    /// Zacas extension enabled (`amocas.*` decode as illegal otherwise).
+    pub zacas: bool,
+
+    /// Zicond extension enabled (`czero.*` decode as illegal otherwise).
+    pub zicond: bool,
+
+    /// Macro-op fusion patterns enabled in the decode stage.
+    pub fusion: Vec<FusionPattern>,
 
     /// Cycle at which a kernel panic was first detected (None if not yet detected).
     /// The simulator runs for 100k more cycles after detection to allow the full
@@ -162,13 +247,30 @@ pub struct Cpu {
     /// Optional buffered writer for the commit log (enabled by the `commit-log` feature).
     #[cfg(feature = "commit-log")]
     pub commit_log: Option<std::io::BufWriter<std::fs::File>>,
+
+    /// Pipeline visualization trace, if `general.pipe_trace` is set.
+    pub pipe_trace: Option<PipeTrace>,
+
+    /// Physical memory access trace, if `general.access_trace` is set.
+    pub access_trace: Option<AccessTrace>,
+
+    /// Sample ROB, store buffer, and issue queue occupancy every cycle.
+    pub sample_occupancy: bool,
+
+    /// Committed stores drained from the store buffer to memory per cycle.
+    pub store_drain_rate: usize,
+
+    /// Sequence number assigned to the next fetched instruction.
+    pub next_seq: u64,
+    /// Sequence number of the latest retired instruction.
+    pub retired_seq: u64,
 }
 
 /// Maximum number of (pc, inst) entries kept for invalid-PC debug trace.
 pub const PC_TRACE_MAX: usize = 32;
 
-unsafe impl Send for Cpu {}
-unsafe impl Sync for Cpu {}
+/// Maximum number of entries kept in [`Cpu::recent_traps`].
+pub const TRAP_LOG_MAX: usize = 64;
 
 impl Cpu {
     /// Cache line size for reservation granularity (64 bytes)
@@ -202,6 +304,81 @@ impl Cpu {
         self.load_reservation = None;
     }
 
+    /// Invalidates the load reservation if a write of `size` bytes at `addr`
+    /// touches any byte of the reserved granule.
+    ///
+    /// Called for every committed store (including AMOs) so that an SC
+    /// paired with an earlier LR fails when the granule was written in
+    /// between (RISC-V spec §8.2). Also used for writes by other agents:
+    /// stores drained by other harts and device DMA, which may span many
+    /// granules.
+    #[inline]
+    pub const fn invalidate_reservation(&mut self, addr: PhysAddr, size: u64) {
+        if let Some(reserved) = self.load_reservation
+            && size > 0
+            && addr.0 < reserved.0.saturating_add(Self::RESERVATION_GRANULE)
+            && addr.0.saturating_add(size - 1) >= reserved.0
+        {
+            self.load_reservation = None;
+        }
+    }
+
+    /// Returns the CSRs at power-on for a hart implementing `misa`.
+    fn reset_csrs(misa: u64, direct_mode: bool) -> Csrs {
+        use crate::core::arch::csr::{
+            MISA_TOGGLEABLE, MSTATUS_DEFAULT_RV64, MSTATUS_FS_INIT, SSTATUS_VISIBLE,
+        };
+
+        // In direct (SE) mode, enable FP state so user programs can use
+        // floating-point instructions without an OS to set mstatus.FS.
+        // In full-system mode, firmware/OS is responsible for enabling FP.
+        let mstatus =
+            if direct_mode { MSTATUS_DEFAULT_RV64 | MSTATUS_FS_INIT } else { MSTATUS_DEFAULT_RV64 };
+
+        // Initialize sstatus as a view of mstatus (spec: sstatus is not a
+        // separate register, it's a restricted view of mstatus).
+        Csrs {
+            mstatus,
+            sstatus: mstatus & SSTATUS_VISIBLE,
+            misa,
+            misa_writable: misa & MISA_TOGGLEABLE,
+            stimecmp: u64::MAX,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the hart to its reset state without rebuilding the system.
+    ///
+    /// The PC is set to [`Self::reset_vector`] and the hart enters M-mode.
+    /// Every CSR takes its power-on value: `mstatus.MIE` and `mstatus.MPRV`
+    /// are clear, `mcause` is 0, `misa` regains every configured extension,
+    /// and the PMP entries are unlocked and off. TLBs, the LR/SC reservation
+    /// and any WFI sleep are cleared. Integer and FP registers, memory,
+    /// caches, predictors and statistics are kept; the RISC-V spec leaves
+    /// the registers unspecified at reset.
+    ///
+    /// In-flight instructions are not touched, so the pipeline must be
+    /// flushed first (see [`crate::Simulator::reset`]).
+    pub fn reset(&mut self) {
+        use crate::core::arch::csr::MISA_TOGGLEABLE;
+
+        // Bits outside `misa_writable` never change, so the configured
+        // extensions are the fixed bits plus the toggleable ones.
+        let misa = (self.csrs.misa & !MISA_TOGGLEABLE) | self.csrs.misa_writable;
+        self.csrs = Self::reset_csrs(misa, self.direct_mode);
+        self.pmp = Pmp::new();
+        self.pc = self.reset_vector;
+        self.committed_next_pc = self.reset_vector;
+        self.privilege = PrivilegeMode::Machine;
+        self.clear_reservation();
+        self.mmu.dtlb.flush();
+        self.mmu.itlb.flush();
+        self.wfi_waiting = false;
+        self.interrupt_pending_since = None;
+        self.trap_reg_write = None;
+        self.sw_seip = false;
+    }
+
     /// Creates a new CPU instance with the specified system and configuration.
     ///
     /// # Arguments
@@ -215,9 +392,7 @@ impl Cpu {
     pub fn new(mut system: System, config: &Config) -> Self {
         use crate::core::arch::csr::{
             MISA_DEFAULT_RV64IMAFDC, MISA_EXT_A, MISA_EXT_C, MISA_EXT_D, MISA_EXT_F, MISA_EXT_I,
-            MISA_EXT_M, MISA_EXT_S, MISA_EXT_U, MISA_XLEN_64, MSTATUS_DEFAULT_RV64, MSTATUS_FS,
-            MSTATUS_FS_INIT, MSTATUS_MXR, MSTATUS_SIE, MSTATUS_SPIE, MSTATUS_SPP, MSTATUS_SUM,
-            MSTATUS_UXL,
+            MISA_EXT_M, MISA_EXT_S, MISA_EXT_U, MISA_XLEN_64,
         };
         use crate::isa::abi;
 
@@ -241,34 +416,28 @@ impl Cpu {
 
         let direct_mode = config.general.direct_mode;
 
-        // In direct (SE) mode, enable FP state so user programs can use
-        // floating-point instructions without an OS to set mstatus.FS.
-        // In full-system mode, firmware/OS is responsible for enabling FP.
-        let mstatus =
-            if direct_mode { MSTATUS_DEFAULT_RV64 | MSTATUS_FS_INIT } else { MSTATUS_DEFAULT_RV64 };
-
-        // Initialize sstatus as a view of mstatus (spec: sstatus is not a
-        // separate register, it's a restricted view of mstatus).
-        let sstatus_mask = MSTATUS_SIE
-            | MSTATUS_SPIE
-            | MSTATUS_SPP
-            | MSTATUS_FS
-            | MSTATUS_SUM
-            | MSTATUS_MXR
-            | MSTATUS_UXL;
-        let sstatus = mstatus & sstatus_mask;
-        let csrs = Csrs {
-            mstatus,
-            sstatus,
-            misa: configured_misa,
-            stimecmp: u64::MAX,
-            ..Default::default()
-        };
+        // Stores to an HTIF device must reach it through the bus rather than
+        // the RAM it sits in.
+        let htif_range = system.bus.htif_mut().map(|htif| {
+            let (base, size) = crate::soc::devices::Device::address_range(htif);
+            (base, base + size)
+        });
+
+        let csrs = Self::reset_csrs(configured_misa, direct_mode);
+
+        // The emulated process stack starts at the top of the boot RAM bank.
+        let syscall_emulation = config.general.syscall_emulation.then(|| {
+            let ram_end = system.bus.get_ram_info().map_or(0, |(_, _, end)| end);
+            SyscallEmulation::new(
+                config.general.syscall_root.as_deref(),
+                &config.general.args,
+                config.general.initial_sp.unwrap_or(ram_end),
+            )
+        });
 
         let bp = BranchPredictorWrapper::new(config);
 
-        let (ram_ptr, ram_start, ram_end) =
-            system.bus.get_ram_info().unwrap_or((std::ptr::null_mut(), 0, 0));
+        let ram = HostRam::new(system.bus.ram_buffers(), config.memory.safe_ram || cfg!(miri));
         let regs = if direct_mode {
             let sp = config.general.initial_sp.unwrap_or(config.system.ram_base + 0x100_0000);
             let mut r = RegisterFile::new();
@@ -280,24 +449,33 @@ impl Cpu {
         // Always start in Machine mode. The riscv-tests switch to lower modes
         // via their own trap handlers; bare-metal binaries need M-mode too.
         let privilege = PrivilegeMode::Machine;
+        let mut stats = SimStats::default();
+        stats.bandwidth =
+            BandwidthStats::new(config.general.bandwidth_interval, config.general.clock_mhz);
 
         Self {
             regs,
             pc: config.general.start_pc,
-            trace: config.general.trace_instructions,
+            reset_vector: config.general.start_pc,
+            trace: config.general.trace_instructions && config.general.trace_start_pc.is_none(),
+            trace_requested: config.general.trace_instructions,
+            trace_start_pc: config.general.trace_start_pc,
+            trace_stop_pc: config.general.trace_stop_pc,
+            trace_in_region: config.general.trace_start_pc.is_none(),
             bus: system,
             exit_code: None,
             csrs,
             privilege,
             direct_mode,
             cache_base: config.system.ram_base,
-            stats: SimStats::default(),
+            stats,
             branch_predictor: bp,
             l1_i_cache: CacheSim::new(&config.cache.l1_i),
             l1_d_cache: CacheSim::new(&config.cache.l1_d),
             l1d_mshrs: MshrFile::new(config.cache.l1_d.mshr_count, config.cache.l1_d.line_bytes),
             inclusion_policy: config.cache.inclusion_policy,
             wcb: WriteCombiningBuffer::new(config.cache.wcb_entries, config.cache.l1_d.line_bytes),
+            l1_d_ports: config.cache.l1_d_ports,
             prefetch_filter: PrefetchFilter::new(
                 if config.cache.l1_d.prefetcher != crate::config::Prefetcher::None
                     || config.cache.l2.prefetcher != crate::config::Prefetcher::None
@@ -319,6 +497,8 @@ impl Cpu {
             ),
             pmp: Pmp::new(),
             load_reservation: None,
+            store_log: Vec::new(),
+            hart_id: 0,
             pipeline_width: config.pipeline.width,
             has_register_renaming: config.pipeline.backend
                 == crate::core::pipeline::engine::BackendType::OutOfOrder,
@@ -326,22 +506,49 @@ impl Cpu {
             clint_divider: config.system.clint_divider,
             last_pc: 0,
             same_pc_count: 0,
+            hang_threshold: config.general.hang_threshold,
+            loop_detector: LoopDetector::default(),
+            max_cycles: config.general.max_cycles,
             wfi_waiting: false,
             wfi_pc: 0,
+            interrupt_pending_since: None,
             committed_next_pc: config.general.start_pc,
-            ram_ptr,
-            ram_start,
-            ram_end,
-            htif_range: None,
+            break_pc: None,
+            break_skip: false,
+            break_hit: false,
+            retire_limit: None,
+            ram,
+            faults: FaultInjector::new(&config.memory.fault_injection),
+            htif_range,
+            semihosting: config
+                .general
+                .semihosting
+                .then(|| Semihosting::new(config.general.semihosting_root.as_deref())),
+            syscall_emulation,
+            trap_reg_write: None,
             pc_trace: Vec::with_capacity(PC_TRACE_MAX),
+            recent_traps: VecDeque::with_capacity(TRAP_LOG_MAX),
             last_invalid_pc_debug: None,
             redirect_pending: false,
+            mispredict_pending: false,
             software_ad_bits: config.memory.software_ad_bits,
-            misaligned_access_trap: config.memory.misaligned_access_trap,
+            misaligned: config.memory.misaligned,
+            zacas: config.pipeline.zacas,
+            zicond: config.pipeline.zicond,
+            fusion: config.pipeline.fusion.clone(),
             panic_detected_at_cycle: None,
             sw_seip: false,
             #[cfg(feature = "commit-log")]
             commit_log: None,
+            pipe_trace: None,
+            access_trace: None,
+            sample_occupancy: config.general.sample_occupancy,
+            store_drain_rate: match config.pipeline.store_buffer_drain_rate {
+                0 => config.pipeline.store_ports.max(1),
+                rate => rate,
+            },
+            next_seq: 0,
+            retired_seq: 0,
         }
     }
 
@@ -365,6 +572,111 @@ impl Cpu {
         Ok(())
     }
 
+    /// Opens a pipeline visualization trace in `format`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SimError::FileRead`] if the file cannot be created.
+    pub fn open_pipe_trace(
+        &mut self,
+        path: &str,
+        format: PipeTraceFormat,
+    ) -> Result<(), crate::common::SimError> {
+        let mut trace = PipeTrace::create(path, format)?;
+        trace.set_active(self.trace_in_region);
+        self.pipe_trace = Some(trace);
+        Ok(())
+    }
+
+    /// Opens a physical memory access trace in `format`, keeping one record
+    /// in `sample`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SimError::FileRead`] if the file cannot be created.
+    pub fn open_access_trace(
+        &mut self,
+        path: &str,
+        format: AccessTraceFormat,
+        sample: u64,
+    ) -> Result<(), crate::common::SimError> {
+        self.access_trace = Some(AccessTrace::create(path, format, sample)?);
+        Ok(())
+    }
+
+    /// Logs an access made by the instruction at `pc` to the access trace.
+    #[inline]
+    pub fn trace_access(
+        &mut self,
+        pc: u64,
+        paddr: PhysAddr,
+        size: u64,
+        is_write: bool,
+        is_fetch: bool,
+    ) {
+        if let Some(trace) = &mut self.access_trace {
+            trace.record(&AccessRecord {
+                cycle: self.stats.cycles,
+                pc,
+                paddr: paddr.val(),
+                size,
+                is_write,
+                is_fetch,
+            });
+        }
+    }
+
+    /// Requests instruction tracing on or off. Output is only produced while
+    /// commit is inside the trace region.
+    pub const fn set_trace(&mut self, on: bool) {
+        self.trace_requested = on;
+        self.trace = on && self.trace_in_region;
+    }
+
+    /// Enters or leaves the trace region as the instruction at `pc` commits:
+    /// `trace_start_pc` turns tracing on, `trace_stop_pc` turns it off.
+    #[inline]
+    pub fn update_trace_region(&mut self, pc: u64) {
+        let in_region = if self.trace_start_pc == Some(pc) {
+            true
+        } else if self.trace_stop_pc == Some(pc) {
+            false
+        } else {
+            return;
+        };
+        self.trace_in_region = in_region;
+        self.trace = self.trace_requested && in_region;
+        if let Some(trace) = &mut self.pipe_trace {
+            trace.set_active(in_region);
+        }
+    }
+
+    /// Assigns the next sequence number to an instruction fetched at `pc`.
+    pub fn alloc_seq(&mut self, pc: u64) -> u64 {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        if let Some(trace) = &mut self.pipe_trace {
+            trace.fetch(seq, pc, self.stats.cycles);
+        }
+        seq
+    }
+
+    /// Reports instruction `seq` entering `stage` to the pipeline trace.
+    #[inline]
+    pub fn pipe_event(&mut self, seq: u64, stage: PipeStage) {
+        if let Some(trace) = &mut self.pipe_trace {
+            trace.stage(seq, stage, self.stats.cycles);
+        }
+    }
+
+    /// Reports the instruction in ROB entry `tag` entering `stage`.
+    #[inline]
+    pub fn pipe_event_tag(&mut self, tag: RobTag, stage: PipeStage) {
+        if let Some(trace) = &mut self.pipe_trace {
+            trace.stage_tag(tag, stage, self.stats.cycles);
+        }
+    }
+
     /// Retrieves the exit code if the simulation has finished.
     ///
     /// # Returns
@@ -379,6 +691,18 @@ impl Cpu {
         println!("PC = {:#018x}", self.pc);
         self.regs.dump();
     }
+
+    /// Formats the recently retired instructions in `pc_trace`, oldest
+    /// first, one `pc: disassembly` line each.
+    pub fn format_pc_trace(&self) -> String {
+        use std::fmt::Write;
+
+        let mut out = String::new();
+        for &(pc, inst) in &self.pc_trace {
+            let _ = writeln!(out, "  {pc:#018x}: {}", crate::isa::disasm::disassemble(inst));
+        }
+        out
+    }
 }
 
 #[cfg(test)]
@@ -402,6 +726,26 @@ mod tests {
         assert!(!cpu.check_reservation(PhysAddr::new(0x1000)));
     }
 
+    #[test]
+    fn test_invalidate_reservation_by_store() {
+        let config = Config::default();
+        let system = System::new(&config, "");
+        let mut cpu = Cpu::new(system, &config);
+
+        cpu.set_reservation(PhysAddr::new(0x1000));
+        cpu.invalidate_reservation(PhysAddr::new(0x2000), 8);
+        assert!(cpu.check_reservation(PhysAddr::new(0x1000)));
+
+        // Misaligned store starting in the previous granule but spilling into it.
+        cpu.invalidate_reservation(PhysAddr::new(0x0FFC), 8);
+        assert!(!cpu.check_reservation(PhysAddr::new(0x1000)));
+
+        // DMA write covering the granule from well outside it on both sides.
+        cpu.set_reservation(PhysAddr::new(0x1000));
+        cpu.invalidate_reservation(PhysAddr::new(0x0F00), 0x200);
+        assert!(!cpu.check_reservation(PhysAddr::new(0x1000)));
+    }
+
     #[test]
     fn test_cpu_dump_state_no_panic() {
         let config = Config::default();
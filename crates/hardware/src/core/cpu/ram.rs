@@ -0,0 +1,245 @@
+//! Direct RAM Access.
+//!
+//! Loads, stores, and fetches that hit RAM bypass the bus and access the
+//! bank's storage directly. Two access paths are provided:
+//! 1. **Fast path (default):** Cached host pointers with unaligned reads and writes.
+//! 2. **Safe path (`memory.safe_ram`, always under Miri):** The bounds-checked
+//!    `DramBuffer` API, with no raw pointers. Slower, but clean under Miri and
+//!    sanitizers, and convenient for fuzzing.
+//!
+//! Both paths see the same bytes; only the cost of an access differs.
+
+// Guest accesses may be misaligned; the fast path's read_unaligned/write_unaligned is intentional.
+#![allow(clippy::cast_ptr_alignment)]
+
+use crate::soc::memory::buffer::DramBuffer;
+use std::sync::Arc;
+
+/// Host pointer to the start of a RAM bank, used only by the fast path.
+#[derive(Debug, Clone, Copy)]
+struct HostPtr(*mut u8);
+
+// SAFETY: the pointer targets the storage of the `DramBuffer` held alongside
+// it in the same `RamBank`, which keeps the allocation alive and is itself
+// `Send + Sync`. Concurrent harts access RAM exactly as they would through
+// the buffer's own API.
+unsafe impl Send for HostPtr {}
+unsafe impl Sync for HostPtr {}
+
+/// One RAM bank as seen by the CPU.
+#[derive(Debug, Clone)]
+pub struct RamBank {
+    /// Physical address where the bank starts.
+    start: u64,
+    /// Physical address where the bank ends (exclusive).
+    end: u64,
+    /// Backing storage, shared with the bus `Memory` device.
+    buffer: Arc<DramBuffer>,
+    /// Host pointer into `buffer`, or `None` on the safe path.
+    fast: Option<HostPtr>,
+}
+
+impl RamBank {
+    /// Returns the physical address where the bank starts.
+    pub const fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// Returns the physical address where the bank ends (exclusive).
+    pub const fn end(&self) -> u64 {
+        self.end
+    }
+
+    /// Returns the bank's backing storage.
+    pub const fn buffer(&self) -> &Arc<DramBuffer> {
+        &self.buffer
+    }
+}
+
+/// The RAM banks reachable without going through the bus.
+#[derive(Debug, Clone, Default)]
+pub struct HostRam {
+    /// Banks in ascending address order.
+    banks: Vec<RamBank>,
+    /// Whether accesses use the bounds-checked safe path.
+    safe: bool,
+}
+
+impl HostRam {
+    /// Builds the CPU's view of the given `(base, buffer)` RAM banks.
+    ///
+    /// With `safe` set every access goes through the bounds-checked
+    /// `DramBuffer` API; otherwise host pointers are cached for speed.
+    pub fn new(banks: Vec<(u64, Arc<DramBuffer>)>, safe: bool) -> Self {
+        let mut banks: Vec<RamBank> = banks
+            .into_iter()
+            .map(|(start, buffer)| RamBank {
+                start,
+                end: start + buffer.len() as u64,
+                fast: (!safe).then(|| HostPtr(buffer.as_mut_ptr())),
+                buffer,
+            })
+            .collect();
+        banks.sort_by_key(|b| b.start);
+        Self { banks, safe }
+    }
+
+    /// Returns whether accesses use the bounds-checked safe path.
+    pub const fn is_safe(&self) -> bool {
+        self.safe
+    }
+
+    /// Returns the banks in ascending address order.
+    pub fn banks(&self) -> impl Iterator<Item = &RamBank> {
+        self.banks.iter()
+    }
+
+    /// Returns the host memory backing all banks, in bytes (see
+    /// [`DramBuffer::resident_bytes`]).
+    pub fn resident_bytes(&self) -> usize {
+        self.banks.iter().map(|b| b.buffer.resident_bytes()).sum()
+    }
+
+    /// Returns the bank holding all `size` bytes at `paddr`, and the offset
+    /// of `paddr` within it.
+    #[inline]
+    fn locate(&self, paddr: u64, size: u64) -> Option<(&RamBank, usize)> {
+        let end = paddr.saturating_add(size);
+        self.banks
+            .iter()
+            .find(|b| paddr >= b.start && end <= b.end)
+            .map(|b| (b, (paddr - b.start) as usize))
+    }
+
+    /// Returns whether `size` bytes at `paddr` lie wholly inside one bank.
+    #[inline]
+    pub fn contains(&self, paddr: u64, size: u64) -> bool {
+        self.locate(paddr, size).is_some()
+    }
+
+    /// Reads a little-endian value of `size` bytes (1, 2, 4, or 8) at `paddr`,
+    /// zero-extended. Returns `None` if the range is not RAM or `size` is
+    /// not a supported width.
+    #[inline]
+    pub fn read(&self, paddr: u64, size: u64) -> Option<u64> {
+        if !matches!(size, 1 | 2 | 4 | 8) {
+            return None;
+        }
+        let (bank, offset) = self.locate(paddr, size)?;
+        if let Some(HostPtr(base)) = bank.fast {
+            // SAFETY: `locate` checked that `offset + size` lies inside the
+            // bank, whose storage `base` points to.
+            unsafe {
+                let p = base.add(offset);
+                return Some(match size {
+                    1 => u64::from(*p),
+                    2 => u64::from((p as *const u16).read_unaligned()),
+                    4 => u64::from((p as *const u32).read_unaligned()),
+                    _ => (p as *const u64).read_unaligned(),
+                });
+            }
+        }
+        let mut bytes = [0u8; 8];
+        bytes[..size as usize].copy_from_slice(bank.buffer.read_slice(offset, size as usize));
+        Some(u64::from_le_bytes(bytes))
+    }
+
+    /// Writes the low `size` bytes (1, 2, 4, or 8) of `val` at `paddr`.
+    /// Returns `false`, writing nothing, if the range is not RAM or `size`
+    /// is not a supported width.
+    #[inline]
+    pub fn write(&self, paddr: u64, size: u64, val: u64) -> bool {
+        if !matches!(size, 1 | 2 | 4 | 8) {
+            return false;
+        }
+        let Some((bank, offset)) = self.locate(paddr, size) else {
+            return false;
+        };
+        if let Some(HostPtr(base)) = bank.fast {
+            // SAFETY: `locate` checked that `offset + size` lies inside the
+            // bank, whose storage `base` points to.
+            unsafe {
+                let p = base.add(offset);
+                match size {
+                    1 => *p = val as u8,
+                    2 => (p as *mut u16).write_unaligned(val as u16),
+                    4 => (p as *mut u32).write_unaligned(val as u32),
+                    _ => (p as *mut u64).write_unaligned(val),
+                }
+            }
+            return true;
+        }
+        bank.buffer.write_slice(offset, &val.to_le_bytes()[..size as usize]);
+        true
+    }
+
+    /// Copies `buf.len()` bytes at `paddr` into `buf`. Returns `false`,
+    /// leaving `buf` untouched, unless the range lies wholly inside one bank.
+    pub fn read_bytes(&self, paddr: u64, buf: &mut [u8]) -> bool {
+        let Some((bank, offset)) = self.locate(paddr, buf.len() as u64) else {
+            return false;
+        };
+        buf.copy_from_slice(bank.buffer.read_slice(offset, buf.len()));
+        true
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    const BASE: u64 = 0x8000_0000;
+
+    fn ram(safe: bool) -> HostRam {
+        let banks = vec![
+            (BASE, Arc::new(DramBuffer::new(256))),
+            (BASE + 0x1000, Arc::new(DramBuffer::new(256))),
+        ];
+        HostRam::new(banks, safe)
+    }
+
+    /// Runs the same mix of aligned and unaligned accesses on `ram` and
+    /// returns every value read back.
+    fn exercise(ram: &HostRam) -> Vec<Option<u64>> {
+        let stores = [
+            (BASE, 8, 0x0123_4567_89ab_cdef),
+            (BASE + 9, 4, 0xdead_beef),
+            (BASE + 15, 2, 0xa55a),
+            (BASE + 0xff, 1, 0x7f),
+            (BASE + 0x1000 + 3, 8, u64::MAX),
+        ];
+        for (addr, size, val) in stores {
+            assert!(ram.write(addr, size, val));
+        }
+        assert!(!ram.write(BASE + 0xfe, 4, 0), "store crossing the end of a bank");
+        assert!(!ram.write(BASE + 0x800, 8, 0), "store in the hole between banks");
+
+        let mut reads = Vec::new();
+        for addr in [BASE, BASE + 1, BASE + 9, BASE + 14, BASE + 0xf8, BASE + 0x1000] {
+            for size in [1, 2, 4, 8] {
+                reads.push(ram.read(addr, size));
+            }
+        }
+        reads.push(ram.read(BASE + 0xfc, 8));
+        reads.push(ram.read(BASE, 16));
+        reads
+    }
+
+    #[test]
+    fn test_safe_path_matches_fast_path() {
+        let (safe, fast) = (ram(true), ram(false));
+        assert!(safe.is_safe());
+        assert!(!fast.is_safe());
+        assert_eq!(exercise(&safe), exercise(&fast));
+        assert_eq!(safe.read(BASE, 8), Some(0x0123_4567_89ab_cdef));
+        assert_eq!(safe.read(BASE + 9, 4), Some(0xdead_beef));
+        assert_eq!(safe.read(BASE + 0xfc, 8), None);
+        assert_eq!(safe.read(BASE, 16), None);
+
+        let mut buf = [0u8; 4];
+        assert!(safe.read_bytes(BASE + 9, &mut buf));
+        assert_eq!(buf, 0xdead_beef_u32.to_le_bytes());
+        assert!(!safe.read_bytes(BASE + 0xfe, &mut buf));
+    }
+}
@@ -0,0 +1,327 @@
+//! RISC-V Semihosting.
+//!
+//! With `general.semihosting` set, an `ebreak` framed by the magic sequence
+//! `slli x0,x0,0x1f; ebreak; srai x0,x0,7` is a request for a host service
+//! rather than a breakpoint. The operation number is in `a0` and `a1` holds
+//! its argument, usually the address of a block of 64-bit parameters; the
+//! result is returned in `a0` and execution resumes after the `ebreak`.
+//! It provides:
+//! 1. **Console:** `SYS_WRITEC`, `SYS_WRITE0`, and the `:tt` handles newlib
+//!    opens for stdin, stdout and stderr.
+//! 2. **Files:** `SYS_OPEN`, `SYS_READ`, `SYS_WRITE` and `SYS_CLOSE` on host
+//!    files, confined to `general.semihosting_root`.
+//! 3. **Exit and time:** `SYS_EXIT` (and `SYS_EXIT_EXTENDED`) set the exit
+//!    code, and `SYS_CLOCK` derives centiseconds from `mtime`.
+//!
+//! Every other operation, and every failed one, returns -1.
+
+use super::Cpu;
+use crate::common::constants::TIMEBASE_FREQ;
+use crate::isa::abi;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// `slli x0, x0, 0x1f`, the instruction before the semihosting `ebreak`.
+pub const SEMIHOST_ENTRY: u32 = 0x01f0_1013;
+
+/// `ebreak`.
+pub const SEMIHOST_EBREAK: u32 = 0x0010_0073;
+
+/// `srai x0, x0, 7`, the instruction after the semihosting `ebreak`.
+pub const SEMIHOST_EXIT: u32 = 0x4070_5013;
+
+/// Open a file; returns a handle.
+pub const SYS_OPEN: u64 = 0x01;
+/// Close a handle.
+pub const SYS_CLOSE: u64 = 0x02;
+/// Write the byte `a1` points to to the console.
+pub const SYS_WRITEC: u64 = 0x03;
+/// Write the NUL-terminated string `a1` points to to the console.
+pub const SYS_WRITE0: u64 = 0x04;
+/// Write to a handle; returns the number of bytes not written.
+pub const SYS_WRITE: u64 = 0x05;
+/// Read from a handle; returns the number of bytes not read.
+pub const SYS_READ: u64 = 0x06;
+/// Centiseconds since the start of the run.
+pub const SYS_CLOCK: u64 = 0x10;
+/// Terminate the program.
+pub const SYS_EXIT: u64 = 0x18;
+/// Terminate the program (parameter block always used).
+pub const SYS_EXIT_EXTENDED: u64 = 0x20;
+
+/// `SYS_EXIT` reason for a normal application exit; the subcode is the exit code.
+pub const ADP_STOPPED_APPLICATION_EXIT: u64 = 0x2_0026;
+
+/// Longest file name `SYS_OPEN` accepts.
+const MAX_NAME_LEN: u64 = 4096;
+
+/// Longest string `SYS_WRITE0` prints.
+const MAX_STRING_LEN: u64 = 1 << 20;
+
+/// Most bytes one `SYS_READ` or `SYS_WRITE` transfers.
+const MAX_TRANSFER: u64 = 1 << 24;
+
+/// Return value of a failed or unsupported call.
+const FAILED: u64 = u64::MAX;
+
+/// What a semihosting handle refers to.
+#[derive(Debug)]
+enum Handle {
+    /// The `:tt` console opened for reading; always at end of file.
+    Stdin,
+    /// The `:tt` console opened for writing.
+    Stdout,
+    /// The `:tt` console opened for appending.
+    Stderr,
+    /// A host file under the sandbox root.
+    File(File),
+}
+
+/// Host-side state of the semihosting interface: open handles, the sandbox
+/// root, and where console output goes.
+#[derive(Debug)]
+pub struct Semihosting {
+    /// Directory file names are resolved in, or `None` to refuse file opens.
+    root: Option<PathBuf>,
+    /// Open handles; handle `n` is slot `n - 1`.
+    handles: Vec<Option<Handle>>,
+    /// Console output collected here instead of the host's stdout and stderr
+    /// when set.
+    pub capture: Option<Vec<u8>>,
+}
+
+impl Semihosting {
+    /// Creates the interface with no handles open.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - Directory `SYS_OPEN` may open files in, if any.
+    pub fn new(root: Option<&str>) -> Self {
+        Self { root: root.map(PathBuf::from), handles: Vec::new(), capture: None }
+    }
+
+    /// Resolves a guest file name inside the sandbox root. Absolute names and
+    /// names with `..` components are refused.
+    fn resolve(&self, name: &str) -> Option<PathBuf> {
+        let root = self.root.as_ref()?;
+        let path = Path::new(name);
+        let confined = path.components().next().is_some()
+            && path.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+        confined.then(|| root.join(path))
+    }
+
+    /// Opens `name` with ARM-style `fopen` mode `mode` (0-11) and returns the
+    /// new handle.
+    fn open(&mut self, name: &str, mode: u64) -> Option<u64> {
+        let handle = if name == ":tt" {
+            match mode {
+                0..=3 => Handle::Stdin,
+                4..=7 => Handle::Stdout,
+                8..=11 => Handle::Stderr,
+                _ => return None,
+            }
+        } else {
+            let path = self.resolve(name)?;
+            let mut options = OpenOptions::new();
+            let _ = match mode / 2 {
+                0 => options.read(true),
+                1 => options.read(true).write(true),
+                2 => options.write(true).create(true).truncate(true),
+                3 => options.read(true).write(true).create(true).truncate(true),
+                4 => options.append(true).create(true),
+                5 => options.read(true).append(true).create(true),
+                _ => return None,
+            };
+            Handle::File(options.open(path).ok()?)
+        };
+        let slot = self.handles.iter().position(Option::is_none).unwrap_or_else(|| {
+            self.handles.push(None);
+            self.handles.len() - 1
+        });
+        self.handles[slot] = Some(handle);
+        Some(slot as u64 + 1)
+    }
+
+    /// Returns the open handle `handle`.
+    fn handle(&mut self, handle: u64) -> Option<&mut Handle> {
+        let slot = usize::try_from(handle.checked_sub(1)?).ok()?;
+        self.handles.get_mut(slot)?.as_mut()
+    }
+
+    /// Closes `handle`; returns whether it was open.
+    fn close(&mut self, handle: u64) -> bool {
+        self.handle(handle).is_some() && {
+            self.handles[handle as usize - 1] = None;
+            true
+        }
+    }
+
+    /// Writes console output to the capture buffer or the host stream.
+    fn console(&mut self, bytes: &[u8], to_stderr: bool) {
+        if let Some(capture) = &mut self.capture {
+            capture.extend_from_slice(bytes);
+        } else if to_stderr {
+            let _ = std::io::stderr().write_all(bytes);
+        } else {
+            let mut out = std::io::stdout();
+            let _ = out.write_all(bytes);
+            let _ = out.flush();
+        }
+    }
+
+    /// Writes `bytes` to `handle`; returns the number written.
+    fn write(&mut self, handle: u64, bytes: &[u8]) -> Option<usize> {
+        match self.handle(handle)? {
+            Handle::Stdin => None,
+            Handle::Stdout => {
+                self.console(bytes, false);
+                Some(bytes.len())
+            }
+            Handle::Stderr => {
+                self.console(bytes, true);
+                Some(bytes.len())
+            }
+            Handle::File(file) => file.write(bytes).ok(),
+        }
+    }
+
+    /// Reads up to `len` bytes from `handle`.
+    fn read(&mut self, handle: u64, len: u64) -> Option<Vec<u8>> {
+        match self.handle(handle)? {
+            Handle::Stdin => Some(Vec::new()),
+            Handle::Stdout | Handle::Stderr => None,
+            Handle::File(file) => {
+                let mut buf = Vec::new();
+                let _ = file.take(len).read_to_end(&mut buf).ok()?;
+                Some(buf)
+            }
+        }
+    }
+}
+
+impl Cpu {
+    /// Returns true if the `ebreak` at `epc` is framed by the semihosting
+    /// entry and exit instructions.
+    pub fn is_semihosting_call(&mut self, epc: u64) -> bool {
+        self.inst_word_at(epc) == SEMIHOST_EBREAK
+            && self.inst_word_at(epc.wrapping_sub(4)) == SEMIHOST_ENTRY
+            && self.inst_word_at(epc.wrapping_add(4)) == SEMIHOST_EXIT
+    }
+
+    /// Services the semihosting call whose `ebreak` is at `epc`: performs the
+    /// operation in `a0` with argument `a1`, returns the result in `a0`, and
+    /// resumes after the `ebreak`. An exit call sets `exit_code` instead.
+    pub fn semihosting_call(&mut self, epc: u64) {
+        let op = self.regs.read(abi::REG_A0);
+        let arg = self.regs.read(abi::REG_A1);
+        let Some(mut host) = self.semihosting.take() else { return };
+        let result = self.semihost_op(&mut host, op, arg);
+        self.semihosting = Some(host);
+        if let Some(ret) = result {
+            self.regs.write(abi::REG_A0, ret);
+            self.trap_reg_write = Some((abi::REG_A0, ret));
+            self.pc = epc.wrapping_add(4);
+        }
+    }
+
+    /// Performs one operation; returns the value for `a0`, or `None` after an exit.
+    fn semihost_op(&mut self, host: &mut Semihosting, op: u64, arg: u64) -> Option<u64> {
+        let ret = match op {
+            SYS_WRITEC => {
+                let byte = self.guest_read_u8(arg);
+                host.console(&[byte], false);
+                0
+            }
+            SYS_WRITE0 => {
+                let bytes = self.guest_read_cstr(arg, MAX_STRING_LEN);
+                host.console(&bytes, false);
+                0
+            }
+            SYS_OPEN => {
+                let (name, mode, len) =
+                    (self.param(arg, 0), self.param(arg, 1), self.param(arg, 2));
+                if len > MAX_NAME_LEN {
+                    FAILED
+                } else {
+                    let name = self.guest_read_bytes(name, len);
+                    String::from_utf8(name).ok().and_then(|n| host.open(&n, mode)).unwrap_or(FAILED)
+                }
+            }
+            SYS_CLOSE => {
+                if host.close(self.param(arg, 0)) {
+                    0
+                } else {
+                    FAILED
+                }
+            }
+            SYS_WRITE => {
+                let (handle, buf, len) =
+                    (self.param(arg, 0), self.param(arg, 1), self.param(arg, 2));
+                if len > MAX_TRANSFER {
+                    FAILED
+                } else {
+                    let bytes = self.guest_read_bytes(buf, len);
+                    host.write(handle, &bytes).map_or(FAILED, |n| len - n as u64)
+                }
+            }
+            SYS_READ => {
+                let (handle, buf, len) =
+                    (self.param(arg, 0), self.param(arg, 1), self.param(arg, 2));
+                host.read(handle, len.min(MAX_TRANSFER)).map_or(FAILED, |bytes| {
+                    self.guest_write_bytes(buf, &bytes);
+                    len - bytes.len() as u64
+                })
+            }
+            SYS_CLOCK => self.stats.cycles / self.clint_divider.max(1) / (TIMEBASE_FREQ / 100),
+            SYS_EXIT | SYS_EXIT_EXTENDED => {
+                let (reason, subcode) = (self.param(arg, 0), self.param(arg, 1));
+                self.exit_code =
+                    Some(if reason == ADP_STOPPED_APPLICATION_EXIT { subcode } else { 1 });
+                return None;
+            }
+            _ => FAILED,
+        };
+        Some(ret)
+    }
+
+    /// Reads parameter `index` of the block at `block`.
+    fn param(&mut self, block: u64, index: u64) -> u64 {
+        let addr = self.host_paddr(block.wrapping_add(8 * index));
+        if self.bus.bus.is_valid_address(addr) { self.bus.bus.read_u64(addr) } else { 0 }
+    }
+
+    /// Reads the guest byte at virtual address `vaddr`, or 0 if unmapped.
+    pub(super) fn guest_read_u8(&mut self, vaddr: u64) -> u8 {
+        let addr = self.host_paddr(vaddr);
+        if self.bus.bus.is_valid_address(addr) { self.bus.bus.read_u8(addr) } else { 0 }
+    }
+
+    /// Writes the guest byte at virtual address `vaddr`, if mapped.
+    pub(super) fn guest_write_u8(&mut self, vaddr: u64, val: u8) {
+        let addr = self.host_paddr(vaddr);
+        if self.bus.bus.is_valid_address(addr) {
+            self.bus.bus.write_u8(addr, val);
+        }
+    }
+
+    /// Reads `len` guest bytes starting at `vaddr`.
+    pub(super) fn guest_read_bytes(&mut self, vaddr: u64, len: u64) -> Vec<u8> {
+        (0..len).map(|i| self.guest_read_u8(vaddr.wrapping_add(i))).collect()
+    }
+
+    /// Writes `bytes` to the guest starting at `vaddr`, skipping unmapped bytes.
+    pub(super) fn guest_write_bytes(&mut self, vaddr: u64, bytes: &[u8]) {
+        for (i, &b) in bytes.iter().enumerate() {
+            self.guest_write_u8(vaddr.wrapping_add(i as u64), b);
+        }
+    }
+
+    /// Reads a NUL-terminated guest string of at most `max` bytes.
+    pub(super) fn guest_read_cstr(&mut self, vaddr: u64, max: u64) -> Vec<u8> {
+        (0..max)
+            .map(|i| self.guest_read_u8(vaddr.wrapping_add(i)))
+            .take_while(|&b| b != 0)
+            .collect()
+    }
+}
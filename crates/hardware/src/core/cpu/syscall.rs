@@ -0,0 +1,649 @@
+//! Syscall Emulation.
+//!
+//! With `general.syscall_emulation` set, an `ecall` in direct mode is a Linux
+//! system call serviced on the host, so statically linked user programs
+//! (newlib or musl) run without booting a kernel. The call number is in `a7`
+//! and its arguments in `a0`-`a5`; the result, or a negated errno, is
+//! returned in `a0` and execution resumes after the `ecall`. It provides:
+//! 1. **Process:** `exit`, `exit_group`, `getpid`, `uname` and the ID calls,
+//!    and the initial stack with `argc`, `argv`, `envp` and the auxiliary
+//!    vector built from `general.args`.
+//! 2. **Files:** `openat`, `close`, `read`, `write`, `readv`, `writev`,
+//!    `fstat` and `lseek` on the console and on host files confined to
+//!    `general.syscall_root`.
+//! 3. **Memory:** `brk` and anonymous `mmap`/`munmap`. The heap grows up from
+//!    the end of the image; mappings are placed downward from below the stack.
+//! 4. **Time:** `clock_gettime` and `gettimeofday` report simulated time
+//!    derived from `mtime`, starting at 0.
+//!
+//! Every other call returns `-ENOSYS` and is counted in `syscalls_unknown`.
+
+use super::Cpu;
+use crate::common::constants::TIMEBASE_FREQ;
+use crate::isa::abi;
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// `openat(dirfd, path, flags, mode)`.
+pub const SYS_OPENAT: u64 = 56;
+/// `close(fd)`.
+pub const SYS_CLOSE: u64 = 57;
+/// `lseek(fd, offset, whence)`.
+pub const SYS_LSEEK: u64 = 62;
+/// `read(fd, buf, count)`.
+pub const SYS_READ: u64 = 63;
+/// `write(fd, buf, count)`.
+pub const SYS_WRITE: u64 = 64;
+/// `readv(fd, iov, iovcnt)`.
+pub const SYS_READV: u64 = 65;
+/// `writev(fd, iov, iovcnt)`.
+pub const SYS_WRITEV: u64 = 66;
+/// `fstat(fd, statbuf)`.
+pub const SYS_FSTAT: u64 = 80;
+/// `exit(status)`.
+pub const SYS_EXIT: u64 = 93;
+/// `exit_group(status)`.
+pub const SYS_EXIT_GROUP: u64 = 94;
+/// `set_tid_address(tidptr)`.
+pub const SYS_SET_TID_ADDRESS: u64 = 96;
+/// `clock_gettime(clockid, tp)`.
+pub const SYS_CLOCK_GETTIME: u64 = 113;
+/// `uname(buf)`.
+pub const SYS_UNAME: u64 = 160;
+/// `gettimeofday(tv, tz)`.
+pub const SYS_GETTIMEOFDAY: u64 = 169;
+/// `getpid()`.
+pub const SYS_GETPID: u64 = 172;
+/// `getuid()`.
+pub const SYS_GETUID: u64 = 174;
+/// `geteuid()`.
+pub const SYS_GETEUID: u64 = 175;
+/// `getgid()`.
+pub const SYS_GETGID: u64 = 176;
+/// `getegid()`.
+pub const SYS_GETEGID: u64 = 177;
+/// `gettid()`.
+pub const SYS_GETTID: u64 = 178;
+/// `brk(addr)`.
+pub const SYS_BRK: u64 = 214;
+/// `munmap(addr, length)`.
+pub const SYS_MUNMAP: u64 = 215;
+/// `mmap(addr, length, prot, flags, fd, offset)`.
+pub const SYS_MMAP: u64 = 222;
+
+/// Returned (negated) for every call the emulation does not implement.
+pub const ENOSYS: u64 = 38;
+
+const ENOENT: u64 = 2;
+const EIO: u64 = 5;
+const EBADF: u64 = 9;
+const ENOMEM: u64 = 12;
+const EACCES: u64 = 13;
+const EEXIST: u64 = 17;
+const ENODEV: u64 = 19;
+const EISDIR: u64 = 21;
+const EINVAL: u64 = 22;
+const ESPIPE: u64 = 29;
+
+/// `dirfd` meaning "relative to the working directory".
+const AT_FDCWD: u64 = -100_i64 as u64;
+
+const O_ACCMODE: u64 = 0o3;
+const O_CREAT: u64 = 0o100;
+const O_EXCL: u64 = 0o200;
+const O_TRUNC: u64 = 0o1000;
+const O_APPEND: u64 = 0o2000;
+
+const MAP_FIXED: u64 = 0x10;
+const MAP_ANONYMOUS: u64 = 0x20;
+
+const S_IFCHR: u32 = 0o020_000;
+const S_IFDIR: u32 = 0o040_000;
+const S_IFREG: u32 = 0o100_000;
+
+/// Size of the RISC-V Linux `struct stat`.
+const STAT_SIZE: usize = 128;
+
+/// Length of each field of `struct utsname`.
+const UTS_FIELD: usize = 65;
+
+const AT_NULL: u64 = 0;
+const AT_PHDR: u64 = 3;
+const AT_PHENT: u64 = 4;
+const AT_PHNUM: u64 = 5;
+const AT_PAGESZ: u64 = 6;
+const AT_ENTRY: u64 = 9;
+const AT_UID: u64 = 11;
+const AT_EUID: u64 = 12;
+const AT_GID: u64 = 13;
+const AT_EGID: u64 = 14;
+const AT_HWCAP: u64 = 16;
+const AT_CLKTCK: u64 = 17;
+const AT_SECURE: u64 = 23;
+const AT_RANDOM: u64 = 25;
+const AT_EXECFN: u64 = 31;
+
+/// Size of one ELF64 program header.
+const PHDR_SIZE: u64 = 56;
+
+/// Page size reported in the auxiliary vector and used for mappings.
+const PAGE_SIZE: u64 = 4096;
+
+/// Space kept free below the top of the stack before mappings start.
+const STACK_SIZE: u64 = 8 << 20;
+
+/// Process ID reported to the program.
+const PID: u64 = 100;
+
+/// Bytes pointed to by `AT_RANDOM`; fixed so runs are reproducible.
+const AT_RANDOM_BYTES: &[u8; 16] = b"rvsim-at-random\0";
+
+/// Longest path `openat` accepts.
+const MAX_PATH: u64 = 4096;
+
+/// Most bytes one `read` or `write` transfers.
+const MAX_TRANSFER: u64 = 1 << 24;
+
+/// Most entries one `readv` or `writev` accepts.
+const MAX_IOV: u64 = 1024;
+
+/// Result of one call: the value for `a0`, or the errno to negate.
+type SysResult = Result<u64, u64>;
+
+/// What a file descriptor refers to.
+#[derive(Debug)]
+enum Fd {
+    /// The host's standard input.
+    Stdin,
+    /// The host's standard output.
+    Stdout,
+    /// The host's standard error.
+    Stderr,
+    /// A host file under the sandbox root.
+    File(File),
+}
+
+/// Host-side state of the emulated process: file descriptors, the sandbox
+/// root, the program arguments, and the heap and mapping layout.
+#[derive(Debug)]
+pub struct SyscallEmulation {
+    /// Directory paths are resolved in, or `None` to refuse file opens.
+    root: Option<PathBuf>,
+    /// `argv`, `argv[0]` first.
+    args: Vec<String>,
+    /// Open descriptors; descriptor `n` is slot `n`.
+    fds: Vec<Option<Fd>>,
+    /// Address the initial stack is built down from.
+    stack_top: u64,
+    /// Initial program break: the page-aligned end of the image.
+    brk_start: u64,
+    /// Current program break.
+    brk: u64,
+    /// Anonymous mappings, start → page-aligned length.
+    mappings: BTreeMap<u64, u64>,
+    /// Console output collected here instead of the host's stdout and stderr
+    /// when set.
+    pub capture: Option<Vec<u8>>,
+}
+
+impl SyscallEmulation {
+    /// Creates the process state with the console on descriptors 0-2.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - Directory `openat` may open files in, if any.
+    /// * `args` - Program arguments, `argv[0]` first.
+    /// * `stack_top` - Address the initial stack is built down from.
+    pub fn new(root: Option<&str>, args: &[String], stack_top: u64) -> Self {
+        Self {
+            root: root.map(PathBuf::from),
+            args: args.to_vec(),
+            fds: vec![Some(Fd::Stdin), Some(Fd::Stdout), Some(Fd::Stderr)],
+            stack_top,
+            brk_start: 0,
+            brk: 0,
+            mappings: BTreeMap::new(),
+            capture: None,
+        }
+    }
+
+    /// Returns the current program break.
+    pub const fn brk(&self) -> u64 {
+        self.brk
+    }
+
+    /// Resolves a guest path inside the sandbox root. Absolute paths are
+    /// taken relative to the root; paths with `..` components are refused.
+    fn resolve(&self, name: &str) -> Option<PathBuf> {
+        let root = self.root.as_ref()?;
+        let mut path = root.clone();
+        for c in Path::new(name).components() {
+            match c {
+                Component::Normal(part) => path.push(part),
+                Component::RootDir | Component::CurDir => {}
+                Component::ParentDir | Component::Prefix(_) => return None,
+            }
+        }
+        Some(path)
+    }
+
+    /// Opens `name` with Linux `open` flags and returns the new descriptor.
+    fn open(&mut self, name: &str, flags: u64) -> SysResult {
+        let path = self.resolve(name).ok_or(EACCES)?;
+        let mut options = OpenOptions::new();
+        let _ = match flags & O_ACCMODE {
+            0 => options.read(true),
+            1 => options.write(true),
+            2 => options.read(true).write(true),
+            _ => return Err(EINVAL),
+        };
+        let _ = options
+            .append(flags & O_APPEND != 0)
+            .truncate(flags & O_TRUNC != 0)
+            .create(flags & O_CREAT != 0 && flags & O_EXCL == 0)
+            .create_new(flags & O_CREAT != 0 && flags & O_EXCL != 0);
+        let file = options.open(path).map_err(|e| errno(&e))?;
+        let slot = self.fds.iter().position(Option::is_none).unwrap_or_else(|| {
+            self.fds.push(None);
+            self.fds.len() - 1
+        });
+        self.fds[slot] = Some(Fd::File(file));
+        Ok(slot as u64)
+    }
+
+    /// Returns the open descriptor `fd`.
+    fn fd(&mut self, fd: u64) -> Result<&mut Fd, u64> {
+        let slot = usize::try_from(fd).map_err(|_| EBADF)?;
+        self.fds.get_mut(slot).and_then(Option::as_mut).ok_or(EBADF)
+    }
+
+    /// Closes `fd`.
+    fn close(&mut self, fd: u64) -> SysResult {
+        let _ = self.fd(fd)?;
+        self.fds[fd as usize] = None;
+        Ok(0)
+    }
+
+    /// Writes console output to the capture buffer or the host stream.
+    fn console(&mut self, bytes: &[u8], to_stderr: bool) {
+        if let Some(capture) = &mut self.capture {
+            capture.extend_from_slice(bytes);
+        } else if to_stderr {
+            let _ = io::stderr().write_all(bytes);
+        } else {
+            let mut out = io::stdout();
+            let _ = out.write_all(bytes);
+            let _ = out.flush();
+        }
+    }
+
+    /// Writes `bytes` to `fd`; returns the number written.
+    fn write(&mut self, fd: u64, bytes: &[u8]) -> SysResult {
+        match self.fd(fd)? {
+            Fd::Stdin => Err(EBADF),
+            Fd::Stdout => {
+                self.console(bytes, false);
+                Ok(bytes.len() as u64)
+            }
+            Fd::Stderr => {
+                self.console(bytes, true);
+                Ok(bytes.len() as u64)
+            }
+            Fd::File(file) => file.write(bytes).map(|n| n as u64).map_err(|e| errno(&e)),
+        }
+    }
+
+    /// Reads up to `len` bytes from `fd`.
+    fn read(&mut self, fd: u64, len: u64) -> Result<Vec<u8>, u64> {
+        let mut buf = Vec::new();
+        let read = match self.fd(fd)? {
+            Fd::Stdin => io::stdin().take(len).read_to_end(&mut buf),
+            Fd::Stdout | Fd::Stderr => return Err(EBADF),
+            Fd::File(file) => file.take(len).read_to_end(&mut buf),
+        };
+        read.map(|_| buf).map_err(|e| errno(&e))
+    }
+
+    /// Moves the offset of `fd`; returns the new offset.
+    fn lseek(&mut self, fd: u64, offset: u64, whence: u64) -> SysResult {
+        let Fd::File(file) = self.fd(fd)? else { return Err(ESPIPE) };
+        let pos = match whence {
+            0 => SeekFrom::Start(offset),
+            1 => SeekFrom::Current(offset as i64),
+            2 => SeekFrom::End(offset as i64),
+            _ => return Err(EINVAL),
+        };
+        file.seek(pos).map_err(|e| errno(&e))
+    }
+
+    /// Returns the `struct stat` of `fd`.
+    fn stat(&mut self, fd: u64) -> Result<[u8; STAT_SIZE], u64> {
+        let Fd::File(file) = self.fd(fd)? else {
+            return Ok(encode_stat(S_IFCHR | 0o620, 0, 1024, 0));
+        };
+        let meta = file.metadata().map_err(|e| errno(&e))?;
+        let mode = match (meta.is_dir(), meta.permissions().readonly()) {
+            (true, _) => S_IFDIR | 0o755,
+            (false, true) => S_IFREG | 0o444,
+            (false, false) => S_IFREG | 0o644,
+        };
+        let mtime = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs());
+        Ok(encode_stat(mode, meta.len(), PAGE_SIZE, mtime))
+    }
+
+    /// Sets the program break to `addr` if it lies between the end of the
+    /// image and the lowest mapping; returns the break in effect.
+    fn set_brk(&mut self, addr: u64) -> u64 {
+        let limit = self.mappings.keys().next().copied().unwrap_or_else(|| self.mmap_top());
+        if (self.brk_start..=limit).contains(&addr) {
+            self.brk = addr;
+        }
+        self.brk
+    }
+
+    /// Returns the address mappings are placed below.
+    const fn mmap_top(&self) -> u64 {
+        (self.stack_top - STACK_SIZE) & !(PAGE_SIZE - 1)
+    }
+
+    /// Reserves `len` bytes in the highest free gap above the heap.
+    fn map(&mut self, len: u64) -> Option<u64> {
+        let len = len.checked_next_multiple_of(PAGE_SIZE)?;
+        let mut top = self.mmap_top();
+        for (&start, &size) in self.mappings.iter().rev() {
+            if top - (start + size) >= len {
+                break;
+            }
+            top = start;
+        }
+        let addr = top.checked_sub(len).filter(|&a| a >= self.brk.next_multiple_of(PAGE_SIZE))?;
+        let _ = self.mappings.insert(addr, len);
+        Some(addr)
+    }
+
+    /// Releases `[addr, addr + len)`, trimming or splitting mappings it overlaps.
+    fn unmap(&mut self, addr: u64, len: u64) {
+        let end = addr.saturating_add(len.next_multiple_of(PAGE_SIZE));
+        let overlapping: Vec<(u64, u64)> = self
+            .mappings
+            .range(..end)
+            .filter(|&(&start, &size)| start + size > addr)
+            .map(|(&start, &size)| (start, size))
+            .collect();
+        for (start, size) in overlapping {
+            let _ = self.mappings.remove(&start);
+            if start < addr {
+                let _ = self.mappings.insert(start, addr - start);
+            }
+            if start + size > end {
+                let _ = self.mappings.insert(end, start + size - end);
+            }
+        }
+    }
+}
+
+/// Maps a host I/O error to a Linux errno.
+fn errno(e: &io::Error) -> u64 {
+    match e.kind() {
+        ErrorKind::NotFound => ENOENT,
+        ErrorKind::PermissionDenied => EACCES,
+        ErrorKind::AlreadyExists => EEXIST,
+        ErrorKind::IsADirectory => EISDIR,
+        ErrorKind::InvalidInput => EINVAL,
+        _ => EIO,
+    }
+}
+
+/// Lays out a RISC-V Linux `struct stat`.
+fn encode_stat(mode: u32, size: u64, blksize: u64, mtime: u64) -> [u8; STAT_SIZE] {
+    let mut st = [0u8; STAT_SIZE];
+    let mut put = |offset: usize, bytes: &[u8]| {
+        st[offset..offset + bytes.len()].copy_from_slice(bytes);
+    };
+    put(16, &mode.to_le_bytes()); // st_mode
+    put(20, &1u32.to_le_bytes()); // st_nlink
+    put(48, &size.to_le_bytes()); // st_size
+    put(56, &(blksize as u32).to_le_bytes()); // st_blksize
+    put(64, &size.div_ceil(512).to_le_bytes()); // st_blocks
+    for offset in [72, 88, 104] {
+        put(offset, &mtime.to_le_bytes()); // st_atime, st_mtime, st_ctime
+    }
+    st
+}
+
+/// Lays out `struct utsname`.
+fn encode_utsname() -> Vec<u8> {
+    let fields = ["Linux", "rvsim", "6.1.0", "#1 SMP", "riscv64", ""];
+    let mut buf = vec![0u8; fields.len() * UTS_FIELD];
+    for (i, field) in fields.iter().enumerate() {
+        buf[i * UTS_FIELD..i * UTS_FIELD + field.len()].copy_from_slice(field.as_bytes());
+    }
+    buf
+}
+
+impl Cpu {
+    /// Prepares the emulated process: sets the program break to the end of
+    /// the image, builds the initial stack (`argc`, `argv`, an empty `envp`
+    /// and the auxiliary vector) below the stack top, and points `sp` at it
+    /// and the PC at `entry`. Does nothing without syscall emulation.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - Entry point of the program.
+    /// * `image_end` - End of the highest loaded segment.
+    /// * `phdrs` - Address and count of the loaded program headers, if any.
+    pub fn start_process(&mut self, entry: u64, image_end: u64, phdrs: Option<(u64, u64)>) {
+        let Some(mut host) = self.syscall_emulation.take() else { return };
+        host.brk_start = image_end.next_multiple_of(PAGE_SIZE);
+        host.brk = host.brk_start;
+
+        let mut top = host.stack_top;
+        let mut push = |cpu: &mut Self, bytes: &[u8]| {
+            top -= bytes.len() as u64;
+            cpu.guest_write_bytes(top, bytes);
+            top
+        };
+        let random = push(self, AT_RANDOM_BYTES);
+        let argv: Vec<u64> =
+            host.args.iter().map(|arg| push(self, &[arg.as_bytes(), &[0]].concat())).collect();
+
+        let (phdr, phnum) = phdrs.unwrap_or((0, 0));
+        let auxv = [
+            (AT_PHDR, phdr),
+            (AT_PHENT, PHDR_SIZE),
+            (AT_PHNUM, phnum),
+            (AT_PAGESZ, PAGE_SIZE),
+            (AT_ENTRY, entry),
+            (AT_UID, 0),
+            (AT_EUID, 0),
+            (AT_GID, 0),
+            (AT_EGID, 0),
+            (AT_HWCAP, self.csrs.misa & ((1 << 26) - 1)),
+            (AT_CLKTCK, 100),
+            (AT_SECURE, 0),
+            (AT_RANDOM, random),
+            (AT_EXECFN, argv.first().copied().unwrap_or(0)),
+            (AT_NULL, 0),
+        ];
+        let mut words = vec![argv.len() as u64];
+        words.extend(&argv);
+        words.extend([0, 0]); // argv and envp terminators
+        words.extend(auxv.iter().flat_map(|&pair| <[u64; 2]>::from(pair)));
+
+        let sp = (top - 8 * words.len() as u64) & !0xf;
+        let bytes: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+        self.guest_write_bytes(sp, &bytes);
+        self.regs.write(abi::REG_SP, sp);
+        self.regs.write(abi::REG_A0, 0);
+        self.pc = entry;
+        self.syscall_emulation = Some(host);
+    }
+
+    /// Services the system call whose `ecall` is at `epc`: performs the call
+    /// in `a7` with arguments `a0`-`a5`, returns the result in `a0`, and
+    /// resumes after the `ecall`. An exit call sets `exit_code` instead.
+    pub fn syscall(&mut self, epc: u64) {
+        let num = self.regs.read(abi::REG_A7);
+        let args = [abi::REG_A0, abi::REG_A1, abi::REG_A2, abi::REG_A3, abi::REG_A4, abi::REG_A5]
+            .map(|r| self.regs.read(r));
+        let Some(mut host) = self.syscall_emulation.take() else { return };
+        self.stats.syscalls += 1;
+        let result = self.syscall_op(&mut host, num, args);
+        self.syscall_emulation = Some(host);
+        if let Some(result) = result {
+            let ret = result.unwrap_or_else(u64::wrapping_neg);
+            self.regs.write(abi::REG_A0, ret);
+            self.trap_reg_write = Some((abi::REG_A0, ret));
+            self.pc = epc.wrapping_add(4);
+        }
+    }
+
+    /// Performs one call; returns its result, or `None` after an exit.
+    fn syscall_op(
+        &mut self,
+        host: &mut SyscallEmulation,
+        num: u64,
+        a: [u64; 6],
+    ) -> Option<SysResult> {
+        let result = match num {
+            SYS_EXIT | SYS_EXIT_GROUP => {
+                self.exit_code = Some(a[0] & 0xff);
+                return None;
+            }
+            SYS_WRITE => {
+                let bytes = self.guest_read_bytes(a[1], a[2].min(MAX_TRANSFER));
+                host.write(a[0], &bytes)
+            }
+            SYS_READ => host.read(a[0], a[2].min(MAX_TRANSFER)).map(|bytes| {
+                self.guest_write_bytes(a[1], &bytes);
+                bytes.len() as u64
+            }),
+            SYS_WRITEV | SYS_READV => self.vectored(host, num == SYS_WRITEV, a[0], a[1], a[2]),
+            SYS_OPENAT => {
+                let name = self.guest_read_cstr(a[1], MAX_PATH);
+                match String::from_utf8(name) {
+                    Ok(name) if a[0] == AT_FDCWD || name.starts_with('/') => host.open(&name, a[2]),
+                    Ok(_) => Err(EBADF),
+                    Err(_) => Err(EINVAL),
+                }
+            }
+            SYS_CLOSE => host.close(a[0]),
+            SYS_FSTAT => host.stat(a[0]).map(|st| {
+                self.guest_write_bytes(a[1], &st);
+                0
+            }),
+            SYS_LSEEK => host.lseek(a[0], a[1], a[2]),
+            SYS_BRK => {
+                let old = host.brk();
+                let new = host.set_brk(a[0]);
+                if new > old {
+                    self.guest_zero(old, new - old);
+                }
+                Ok(new)
+            }
+            SYS_MMAP => {
+                let (len, flags) = (a[1], a[3]);
+                if len == 0 || flags & MAP_FIXED != 0 {
+                    Err(EINVAL)
+                } else if flags & MAP_ANONYMOUS == 0 {
+                    Err(ENODEV)
+                } else {
+                    host.map(len).ok_or(ENOMEM).inspect(|&addr| {
+                        self.guest_zero(addr, len.next_multiple_of(PAGE_SIZE));
+                    })
+                }
+            }
+            SYS_MUNMAP => {
+                if a[0].is_multiple_of(PAGE_SIZE) {
+                    host.unmap(a[0], a[1]);
+                    Ok(0)
+                } else {
+                    Err(EINVAL)
+                }
+            }
+            SYS_CLOCK_GETTIME => {
+                let ns = self.sim_time_ns();
+                let ts = [ns / 1_000_000_000, ns % 1_000_000_000];
+                self.guest_write_bytes(a[1], &ts.map(u64::to_le_bytes).concat());
+                Ok(0)
+            }
+            SYS_GETTIMEOFDAY => {
+                let ns = self.sim_time_ns();
+                if a[0] != 0 {
+                    let tv = [ns / 1_000_000_000, ns % 1_000_000_000 / 1_000];
+                    self.guest_write_bytes(a[0], &tv.map(u64::to_le_bytes).concat());
+                }
+                Ok(0)
+            }
+            SYS_UNAME => {
+                self.guest_write_bytes(a[0], &encode_utsname());
+                Ok(0)
+            }
+            SYS_GETPID | SYS_GETTID | SYS_SET_TID_ADDRESS => Ok(PID),
+            SYS_GETUID | SYS_GETEUID | SYS_GETGID | SYS_GETEGID => Ok(0),
+            _ => {
+                self.stats.syscalls_unknown += 1;
+                Err(ENOSYS)
+            }
+        };
+        Some(result)
+    }
+
+    /// Performs `writev` (or `readv`) over the `count` `iovec`s at `iov`.
+    fn vectored(
+        &mut self,
+        host: &mut SyscallEmulation,
+        write: bool,
+        fd: u64,
+        iov: u64,
+        count: u64,
+    ) -> SysResult {
+        if count > MAX_IOV {
+            return Err(EINVAL);
+        }
+        let mut total = 0;
+        for i in 0..count {
+            let entry = self.guest_read_bytes(iov.wrapping_add(16 * i), 16);
+            let base = u64::from_le_bytes(entry[..8].try_into().unwrap_or_default());
+            let len = u64::from_le_bytes(entry[8..].try_into().unwrap_or_default());
+            let len = len.min(MAX_TRANSFER);
+            let done = if write {
+                let bytes = self.guest_read_bytes(base, len);
+                host.write(fd, &bytes)
+            } else {
+                host.read(fd, len).map(|bytes| {
+                    self.guest_write_bytes(base, &bytes);
+                    bytes.len() as u64
+                })
+            };
+            match done {
+                Ok(n) => total += n,
+                Err(e) if total == 0 => return Err(e),
+                Err(_) => break,
+            }
+            if done != Ok(len) {
+                break;
+            }
+        }
+        Ok(total)
+    }
+
+    /// Zeroes `len` guest bytes starting at `vaddr`.
+    fn guest_zero(&mut self, vaddr: u64, len: u64) {
+        let addr = self.host_paddr(vaddr);
+        self.bus.bus.load_binary_at(&vec![0; len as usize], addr);
+    }
+
+    /// Returns the simulated time in nanoseconds, from `mtime`.
+    fn sim_time_ns(&self) -> u64 {
+        let ticks = u128::from(self.stats.cycles / self.clint_divider.max(1));
+        (ticks * 1_000_000_000 / u128::from(TIMEBASE_FREQ)) as u64
+    }
+}
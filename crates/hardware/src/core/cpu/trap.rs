@@ -7,16 +7,41 @@
 //! 3. **Context Saving:** Updates CSRs (`mepc`, `mcause`, `mtval`, etc.) and modifies privilege state.
 //! 4. **Return Handling:** Implements `MRET` and `SRET` instructions for returning from trap handlers.
 
-use super::Cpu;
+use super::{Cpu, TRAP_LOG_MAX};
 use crate::common::Trap;
 use crate::common::constants::CAUSE_INTERRUPT_BIT;
 use crate::core::arch::csr;
 use crate::core::arch::mode::PrivilegeMode;
+use crate::core::arch::trap::TrapHandler;
 use crate::isa::abi;
 use crate::isa::privileged::cause::{exception, interrupt};
 use crate::isa::privileged::opcodes as sys_ops;
 use crate::trace_trap;
 
+/// A trap taken by the hart, as kept in [`Cpu::recent_traps`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TrapRecord {
+    /// Cycle at which the trap was taken.
+    pub cycle: u64,
+    /// Cause as written to `mcause`/`scause` (interrupt bit included).
+    pub cause: u64,
+    /// PC of the trapping instruction, or where an interrupt was taken.
+    pub epc: u64,
+    /// Trap value as written to `mtval`/`stval`.
+    pub tval: u64,
+    /// Privilege mode the hart was running in when the trap occurred.
+    pub privilege: PrivilegeMode,
+    /// Privilege mode that handles the trap.
+    pub handler: PrivilegeMode,
+}
+
+impl TrapRecord {
+    /// Returns true if the trap is an interrupt rather than an exception.
+    pub const fn is_interrupt(&self) -> bool {
+        self.cause & CAUSE_INTERRUPT_BIT != 0
+    }
+}
+
 impl Cpu {
     /// Handles a trap (exception or interrupt).
     ///
@@ -26,6 +51,15 @@ impl Cpu {
     /// * `epc` - The Exception Program Counter (PC where the trap occurred).
     pub fn trap(&mut self, cause: &Trap, epc: u64) {
         self.load_reservation = None;
+        self.loop_detector.restart(self.stats.cycles);
+
+        if matches!(cause, Trap::Breakpoint(_))
+            && self.semihosting.is_some()
+            && self.is_semihosting_call(epc)
+        {
+            self.semihosting_call(epc);
+            return;
+        }
 
         if self.direct_mode {
             // In direct mode, ecall is handled here at commit time so that
@@ -37,6 +71,10 @@ impl Cpu {
                     | Trap::EnvironmentCallFromSMode
                     | Trap::EnvironmentCallFromMMode
             ) {
+                if self.syscall_emulation.is_some() {
+                    self.syscall(epc);
+                    return;
+                }
                 let val_a7 = self.regs.read(abi::REG_A7);
                 let val_a0 = self.regs.read(abi::REG_A0);
 
@@ -63,6 +101,15 @@ impl Cpu {
                 return;
             }
             eprintln!("\n[!] Fatal trap in direct mode: {cause:?} at PC {epc:#x}");
+            if let Trap::InstructionAccessFault(pc) = *cause
+                && self.last_invalid_pc_debug != Some(pc)
+            {
+                self.last_invalid_pc_debug = Some(pc);
+                eprint!(
+                    "[!] Jumped to invalid PC {pc:#x}; last retired:\n{}",
+                    self.format_pc_trace()
+                );
+            }
             self.exit_code = Some(1);
             return;
         }
@@ -149,6 +196,18 @@ impl Cpu {
             _ => 0,
         };
 
+        if self.recent_traps.len() == TRAP_LOG_MAX {
+            let _ = self.recent_traps.pop_front();
+        }
+        self.recent_traps.push_back(TrapRecord {
+            cycle: self.stats.cycles,
+            cause: if is_interrupt { CAUSE_INTERRUPT_BIT | code } else { code },
+            epc,
+            tval,
+            privilege: self.privilege,
+            handler: if delegate_to_s { PrivilegeMode::Supervisor } else { PrivilegeMode::Machine },
+        });
+
         if delegate_to_s {
             self.csrs.scause = if is_interrupt { CAUSE_INTERRUPT_BIT | code } else { code };
 
@@ -204,6 +263,41 @@ impl Cpu {
         self.stats.traps_taken += 1;
     }
 
+    /// Returns the interrupt the hart should take now, if any.
+    ///
+    /// An interrupt is a candidate when it is pending in `mip` and enabled in
+    /// `mie`. It targets S-mode if delegated in `mideleg` and M-mode
+    /// otherwise, and is taken when the hart runs below the target privilege,
+    /// or at it with the target's global enable (`mstatus.MIE`/`SIE`) set.
+    /// Interrupts targeting M-mode win over those targeting S-mode; among
+    /// them the order is MEI, MSI, MTI, SEI, SSI, STI.
+    pub fn check_interrupts(&self) -> Option<Trap> {
+        const PRIORITY: [u64; 6] = [
+            csr::MIP_MEIP,
+            csr::MIP_MSIP,
+            csr::MIP_MTIP,
+            csr::MIP_SEIP,
+            csr::MIP_SSIP,
+            csr::MIP_STIP,
+        ];
+
+        let candidates = self.csrs.mip & self.csrs.mie;
+        let takes = |target: PrivilegeMode, global_ie: u64| {
+            self.privilege.to_u8() < target.to_u8()
+                || (self.privilege == target && self.csrs.mstatus & global_ie != 0)
+        };
+        let to_m = candidates & !self.csrs.mideleg;
+        let to_s = candidates & self.csrs.mideleg;
+        let taken = if to_m != 0 && takes(PrivilegeMode::Machine, csr::MSTATUS_MIE) {
+            to_m
+        } else if to_s != 0 && takes(PrivilegeMode::Supervisor, csr::MSTATUS_SIE) {
+            to_s
+        } else {
+            return None;
+        };
+        PRIORITY.into_iter().find(|&bit| taken & bit != 0).map(TrapHandler::irq_to_trap)
+    }
+
     /// Executes the `MRET` instruction (Return from Machine Mode).
     pub(crate) const fn do_mret(&mut self) {
         self.clear_reservation(); // MRET invalidates reservations
@@ -319,4 +413,57 @@ mod tests {
         assert_eq!(cpu.privilege, PrivilegeMode::Supervisor);
         assert_eq!(cpu.csrs.sstatus & csr::MSTATUS_SIE, csr::MSTATUS_SIE);
     }
+
+    #[test]
+    fn test_check_interrupts_priority() {
+        let config = Config::default();
+        let system = System::new(&config, "");
+        let mut cpu = Cpu::new(system, &config);
+        cpu.privilege = PrivilegeMode::User;
+        cpu.csrs.mie = !0;
+        cpu.csrs.mip = csr::MIP_MTIP | csr::MIP_MSIP | csr::MIP_SEIP;
+        assert_eq!(cpu.check_interrupts(), Some(Trap::MachineSoftwareInterrupt));
+
+        // An M-mode timer beats a supervisor external interrupt delegated to S.
+        cpu.csrs.mideleg = csr::MIP_SEIP;
+        cpu.csrs.mip = csr::MIP_MTIP | csr::MIP_SEIP;
+        assert_eq!(cpu.check_interrupts(), Some(Trap::MachineTimerInterrupt));
+
+        // Even when the timer is the lower-priority cause.
+        cpu.csrs.mideleg = csr::MIP_MEIP;
+        cpu.csrs.mip = csr::MIP_MEIP | csr::MIP_MTIP;
+        assert_eq!(cpu.check_interrupts(), Some(Trap::MachineTimerInterrupt));
+    }
+
+    #[test]
+    fn test_check_interrupts_masking() {
+        let config = Config::default();
+        let system = System::new(&config, "");
+        let mut cpu = Cpu::new(system, &config);
+        cpu.csrs.mstatus &= !(csr::MSTATUS_MIE | csr::MSTATUS_SIE);
+        cpu.csrs.mip = csr::MIP_MTIP | csr::MIP_STIP;
+        cpu.csrs.mideleg = csr::MIP_STIP;
+
+        // Not enabled in mie.
+        cpu.privilege = PrivilegeMode::User;
+        assert_eq!(cpu.check_interrupts(), None);
+
+        // M-mode with MIE clear holds the timer; S-targeted never preempts M.
+        cpu.csrs.mie = csr::MIE_MTIE | csr::MIE_STIE;
+        cpu.privilege = PrivilegeMode::Machine;
+        assert_eq!(cpu.check_interrupts(), None);
+        cpu.csrs.mstatus |= csr::MSTATUS_SIE;
+        assert_eq!(cpu.check_interrupts(), None);
+        cpu.csrs.mstatus |= csr::MSTATUS_MIE;
+        assert_eq!(cpu.check_interrupts(), Some(Trap::MachineTimerInterrupt));
+
+        // S-mode: M-targeted interrupts ignore MIE; S-targeted need SIE.
+        cpu.privilege = PrivilegeMode::Supervisor;
+        cpu.csrs.mstatus &= !(csr::MSTATUS_MIE | csr::MSTATUS_SIE);
+        assert_eq!(cpu.check_interrupts(), Some(Trap::MachineTimerInterrupt));
+        cpu.csrs.mip = csr::MIP_STIP;
+        assert_eq!(cpu.check_interrupts(), None);
+        cpu.csrs.mstatus |= csr::MSTATUS_SIE;
+        assert_eq!(cpu.check_interrupts(), Some(Trap::SupervisorTimerInterrupt));
+    }
 }
@@ -3,16 +3,19 @@
 //! This stage performs arithmetic, branch resolution, and system instruction
 //! handling. CSR writes and MRET/SRET are deferred to commit via the ROB.
 
+use crate::common::TranslationResult;
 use crate::common::error::{ExceptionStage, Trap};
 use crate::core::Cpu;
+use crate::core::pipeline::backend::shared::cbo;
+use crate::core::pipeline::frontend::fusion::FusionPattern;
 use crate::core::pipeline::latches::{ExMem1Entry, RenameIssueEntry};
 use crate::core::pipeline::prf::PhysReg;
-use crate::core::pipeline::rob::{BpOutcome, CsrUpdate, Rob};
+use crate::core::pipeline::rob::{BpOutcome, CsrUpdate, Rob, RobTag};
 use crate::core::pipeline::signals::{AluOp, ControlFlow, CsrOp, OpASrc, OpBSrc, SystemOp};
 use crate::core::units::alu::Alu;
 use crate::core::units::bru::BranchPredictor;
+use crate::core::units::bru::ras::RasOp;
 use crate::core::units::fpu::Fpu;
-use crate::isa::abi;
 use crate::isa::privileged::opcodes as sys_ops;
 use crate::isa::rv64i::{funct3, opcodes};
 use crate::{trace_execute, trace_trap};
@@ -81,6 +84,7 @@ pub fn execute_inorder(
             rob_tag  = id.rob_tag.0,
             pc       = %crate::trace::Hex(id.pc),
             inst     = %crate::trace::Hex32(id.inst),
+            asm      = %crate::trace::Disasm(id.inst),
             rd       = id.rd.as_usize(),
             rs1      = id.rs1.as_usize(),
             rv1      = %crate::trace::Hex(id.rv1),
@@ -91,9 +95,11 @@ pub fn execute_inorder(
             "EX: begin"
         );
 
-        let fwd_a = id.rv1;
-        let fwd_b = id.rv2;
-        let fwd_c = id.rv3;
+        // Operands produced by an older instruction of this group are
+        // chained from its result (issue only allows this on a full bypass).
+        let fwd_a = chained_operand(&results, id.rs1_tag).unwrap_or(id.rv1);
+        let fwd_b = chained_operand(&results, id.rs2_tag).unwrap_or(id.rv2);
+        let fwd_c = chained_operand(&results, id.rs3_tag).unwrap_or(id.rv3);
         let store_data = fwd_b;
 
         let op_a = match id.ctrl.a_src {
@@ -108,6 +114,30 @@ pub fn execute_inorder(
         };
         let op_c = fwd_c;
 
+        // Privilege, CSR access and mstatus.FS checks. These are in execute
+        // (not decode) because a preceding CSR write to mstatus may still be
+        // in-flight (deferred to commit) when the instruction is decoded.
+        if cpu.is_illegal_at_execute(&id.ctrl, id.rs1) {
+            rob.fault(id.rob_tag, Trap::IllegalInstruction(id.inst), ExceptionStage::Execute);
+            flush_remaining = true;
+            results.push(ExMem1Entry {
+                rob_tag: id.rob_tag,
+                pc: id.pc,
+                inst: id.inst,
+                inst_size: id.inst_size,
+                rd: id.rd,
+                alu: 0,
+                store_data: 0,
+                ctrl: id.ctrl,
+                trap: None,
+                exception_stage: None,
+                rd_phys: PhysReg::default(),
+                fp_flags: 0,
+                sfence_vma: None,
+            });
+            continue;
+        }
+
         // FENCE.I: flush the pipeline so younger instructions are squashed.
         // The I-cache flush is deferred to COMMIT time (after store drain)
         // to ensure that all prior stores are visible in RAM before the
@@ -135,139 +165,42 @@ pub fn execute_inorder(
             continue;
         }
 
-        // System instructions (FENCE is a NOP at execute — handled at commit only)
-        if !matches!(id.ctrl.system_op, SystemOp::None | SystemOp::Fence) {
-            // MRET: requires M-mode privilege (spec §3.3.2)
-            if id.ctrl.system_op == SystemOp::Mret {
-                if cpu.privilege != crate::core::arch::mode::PrivilegeMode::Machine {
-                    rob.fault(
-                        id.rob_tag,
-                        Trap::IllegalInstruction(id.inst),
-                        ExceptionStage::Execute,
-                    );
-                    flush_remaining = true;
-                    results.push(ExMem1Entry {
-                        rob_tag: id.rob_tag,
-                        pc: id.pc,
-                        inst: id.inst,
-                        inst_size: id.inst_size,
-                        rd: id.rd,
-                        alu: 0,
-                        store_data: 0,
-                        ctrl: id.ctrl,
-                        trap: None,
-                        exception_stage: None,
-                        rd_phys: PhysReg::default(),
-                        fp_flags: 0,
-                        sfence_vma: None,
-                    });
-                    continue;
-                }
-                flush_remaining = true;
-                results.push(ExMem1Entry {
-                    rob_tag: id.rob_tag,
-                    pc: id.pc,
-                    inst: id.inst,
-                    inst_size: id.inst_size,
-                    rd: id.rd,
-                    alu: 0,
-                    store_data: 0,
-                    ctrl: id.ctrl,
-                    trap: None,
-                    exception_stage: None,
-                    rd_phys: PhysReg::default(),
-                    fp_flags: 0,
-                    sfence_vma: None,
-                });
-                continue;
+        // CBO.*: check menvcfg and translate the block now; the cache and
+        // memory update is deferred to commit (after store drain).
+        if let SystemOp::Cbo(op) = id.ctrl.system_op {
+            let TranslationResult { paddr, trap, pte_update, .. } =
+                cbo::translate_block(cpu, op, id.inst, op_a);
+            if let Some(trap) = trap {
+                rob.fault(id.rob_tag, trap, ExceptionStage::Execute);
+            } else if let Some(upd) = pte_update {
+                rob.set_pte_update(id.rob_tag, upd);
             }
+            cpu.pc = id.pc.wrapping_add(id.inst_size.as_u64());
+            cpu.redirect_pending = true;
+            flush_remaining = true;
 
-            // SRET: requires at least S-mode privilege (spec §3.3.2)
-            // In S-mode, SRET is illegal if mstatus.TSR=1
-            if id.ctrl.system_op == SystemOp::Sret {
-                if cpu.privilege == crate::core::arch::mode::PrivilegeMode::User {
-                    rob.fault(
-                        id.rob_tag,
-                        Trap::IllegalInstruction(id.inst),
-                        ExceptionStage::Execute,
-                    );
-                    flush_remaining = true;
-                    results.push(ExMem1Entry {
-                        rob_tag: id.rob_tag,
-                        pc: id.pc,
-                        inst: id.inst,
-                        inst_size: id.inst_size,
-                        rd: id.rd,
-                        alu: 0,
-                        store_data: 0,
-                        ctrl: id.ctrl,
-                        trap: None,
-                        exception_stage: None,
-                        rd_phys: PhysReg::default(),
-                        fp_flags: 0,
-                        sfence_vma: None,
-                    });
-                    continue;
-                }
-                let tsr = (cpu.csrs.mstatus >> 22) & 1;
-                if cpu.privilege == crate::core::arch::mode::PrivilegeMode::Supervisor && tsr != 0 {
-                    rob.fault(
-                        id.rob_tag,
-                        Trap::IllegalInstruction(id.inst),
-                        ExceptionStage::Execute,
-                    );
-                    flush_remaining = true;
-                    results.push(ExMem1Entry {
-                        rob_tag: id.rob_tag,
-                        pc: id.pc,
-                        inst: id.inst,
-                        inst_size: id.inst_size,
-                        rd: id.rd,
-                        alu: 0,
-                        store_data: 0,
-                        ctrl: id.ctrl,
-                        trap: None,
-                        exception_stage: None,
-                        rd_phys: PhysReg::default(),
-                        fp_flags: 0,
-                        sfence_vma: None,
-                    });
-                    continue;
-                }
-
-                flush_remaining = true;
-                results.push(ExMem1Entry {
-                    rob_tag: id.rob_tag,
-                    pc: id.pc,
-                    inst: id.inst,
-                    inst_size: id.inst_size,
-                    rd: id.rd,
-                    alu: 0,
-                    store_data: 0,
-                    ctrl: id.ctrl,
-                    trap: None,
-                    exception_stage: None,
-                    rd_phys: PhysReg::default(),
-                    fp_flags: 0,
-                    sfence_vma: None,
-                });
-                continue;
-            }
+            results.push(ExMem1Entry {
+                rob_tag: id.rob_tag,
+                pc: id.pc,
+                inst: id.inst,
+                inst_size: id.inst_size,
+                rd: id.rd,
+                alu: paddr.val(),
+                store_data: 0,
+                ctrl: id.ctrl,
+                trap: None,
+                exception_stage: None,
+                rd_phys: PhysReg::default(),
+                fp_flags: 0,
+                sfence_vma: None,
+            });
+            continue;
+        }
 
-            // WFI: deferred to commit (like MRET/SRET), but check privilege here.
-            // WFI is illegal in U-mode, or in S-mode when mstatus.TW=1.
-            if id.ctrl.system_op == SystemOp::Wfi {
-                let tw = (cpu.csrs.mstatus >> 21) & 1;
-                if cpu.privilege == crate::core::arch::mode::PrivilegeMode::User
-                    || (cpu.privilege == crate::core::arch::mode::PrivilegeMode::Supervisor
-                        && tw != 0)
-                {
-                    rob.fault(
-                        id.rob_tag,
-                        Trap::IllegalInstruction(id.inst),
-                        ExceptionStage::Execute,
-                    );
-                }
+        // System instructions (FENCE is a NOP at execute — handled at commit only)
+        if !matches!(id.ctrl.system_op, SystemOp::None | SystemOp::Fence) {
+            // MRET/SRET/WFI: deferred to commit.
+            if matches!(id.ctrl.system_op, SystemOp::Mret | SystemOp::Sret | SystemOp::Wfi) {
                 flush_remaining = true;
                 results.push(ExMem1Entry {
                     rob_tag: id.rob_tag,
@@ -289,33 +222,6 @@ pub fn execute_inorder(
 
             // SFENCE.VMA
             if id.ctrl.system_op == SystemOp::SfenceVma {
-                // In S-mode, SFENCE.VMA is illegal if mstatus.TVM=1
-                let tvm = (cpu.csrs.mstatus >> 20) & 1;
-                if cpu.privilege == crate::core::arch::mode::PrivilegeMode::Supervisor && tvm != 0 {
-                    rob.fault(
-                        id.rob_tag,
-                        Trap::IllegalInstruction(id.inst),
-                        ExceptionStage::Execute,
-                    );
-                    flush_remaining = true;
-                    results.push(ExMem1Entry {
-                        rob_tag: id.rob_tag,
-                        pc: id.pc,
-                        inst: id.inst,
-                        inst_size: id.inst_size,
-                        rd: id.rd,
-                        alu: 0,
-                        store_data: 0,
-                        ctrl: id.ctrl,
-                        trap: None,
-                        exception_stage: None,
-                        rd_phys: PhysReg::default(),
-                        fp_flags: 0,
-                        sfence_vma: None,
-                    });
-                    continue;
-                }
-
                 // SFENCE.VMA: Do NOT flush TLBs or clear reservation here.
                 // Preceding PTE-modifying stores may still be in the store
                 // buffer; flushing TLBs now would let in-flight fetches
@@ -386,149 +292,6 @@ pub fn execute_inorder(
 
             // CSR operations: compute old/new but defer write to commit
             if id.ctrl.csr_op != CsrOp::None {
-                // In S-mode, SATP access is illegal if mstatus.TVM=1
-                if id.ctrl.csr_addr == crate::core::arch::csr::SATP
-                    && cpu.privilege == crate::core::arch::mode::PrivilegeMode::Supervisor
-                    && ((cpu.csrs.mstatus >> 20) & 1) != 0
-                {
-                    rob.fault(
-                        id.rob_tag,
-                        Trap::IllegalInstruction(id.inst),
-                        ExceptionStage::Execute,
-                    );
-                    flush_remaining = true;
-                    results.push(ExMem1Entry {
-                        rob_tag: id.rob_tag,
-                        pc: id.pc,
-                        inst: id.inst,
-                        inst_size: id.inst_size,
-                        rd: id.rd,
-                        alu: 0,
-                        store_data: 0,
-                        ctrl: id.ctrl,
-                        trap: None,
-                        exception_stage: None,
-                        rd_phys: PhysReg::default(),
-                        fp_flags: 0,
-                        sfence_vma: None,
-                    });
-                    continue;
-                }
-
-                // Counter-enable check for CYCLE/TIME/INSTRET
-                {
-                    use crate::core::arch::csr as csr_addrs;
-                    use crate::core::arch::mode::PrivilegeMode;
-                    let counter_bit = if id.ctrl.csr_addr == csr_addrs::CYCLE {
-                        Some(0)
-                    } else if id.ctrl.csr_addr == csr_addrs::TIME {
-                        Some(1)
-                    } else if id.ctrl.csr_addr == csr_addrs::INSTRET {
-                        Some(2)
-                    } else {
-                        None
-                    };
-                    if let Some(bit) = counter_bit {
-                        let mask = 1u64 << bit;
-                        let denied = match cpu.privilege {
-                            PrivilegeMode::Supervisor => (cpu.csrs.mcounteren & mask) == 0,
-                            PrivilegeMode::User => {
-                                (cpu.csrs.mcounteren & mask) == 0
-                                    || (cpu.csrs.scounteren & mask) == 0
-                            }
-                            PrivilegeMode::Machine => false,
-                        };
-                        if denied {
-                            rob.fault(
-                                id.rob_tag,
-                                Trap::IllegalInstruction(id.inst),
-                                ExceptionStage::Execute,
-                            );
-                            flush_remaining = true;
-                            results.push(ExMem1Entry {
-                                rob_tag: id.rob_tag,
-                                pc: id.pc,
-                                inst: id.inst,
-                                inst_size: id.inst_size,
-                                rd: id.rd,
-                                alu: 0,
-                                store_data: 0,
-                                ctrl: id.ctrl,
-                                trap: None,
-                                exception_stage: None,
-                                rd_phys: PhysReg::default(),
-                                fp_flags: 0,
-                                sfence_vma: None,
-                            });
-                            continue;
-                        }
-                    }
-                }
-
-                // Privilege check: CSR bits [9:8] encode minimum privilege level.
-                let csr_priv = id.ctrl.csr_addr.privilege_level() as u32;
-                if (cpu.privilege.to_u8() as u32) < csr_priv {
-                    rob.fault(
-                        id.rob_tag,
-                        Trap::IllegalInstruction(id.inst),
-                        ExceptionStage::Execute,
-                    );
-                    flush_remaining = true;
-                    results.push(ExMem1Entry {
-                        rob_tag: id.rob_tag,
-                        pc: id.pc,
-                        inst: id.inst,
-                        inst_size: id.inst_size,
-                        rd: id.rd,
-                        alu: 0,
-                        store_data: 0,
-                        ctrl: id.ctrl,
-                        trap: None,
-                        exception_stage: None,
-                        rd_phys: PhysReg::default(),
-                        fp_flags: 0,
-                        sfence_vma: None,
-                    });
-                    continue;
-                }
-
-                // Read-only check: CSR bits [11:10] == 0b11 means read-only.
-                // CSRRW/CSRRWI always write. CSRRS/CSRRC/CSRRSI/CSRRCI write
-                // only when rs1 (or uimm) != 0.
-                let read_only = id.ctrl.csr_addr.is_read_only();
-                if read_only {
-                    let would_write = match id.ctrl.csr_op {
-                        CsrOp::Rw | CsrOp::Rwi => true,
-                        CsrOp::Rs | CsrOp::Rc => !id.rs1.is_zero(),
-                        CsrOp::Rsi | CsrOp::Rci => (id.rs1.as_u8() & 0x1f) != 0,
-                        CsrOp::None => false,
-                    };
-                    if would_write {
-                        rob.fault(
-                            id.rob_tag,
-                            Trap::IllegalInstruction(id.inst),
-                            ExceptionStage::Execute,
-                        );
-                        flush_remaining = true;
-                        results.push(ExMem1Entry {
-                            rob_tag: id.rob_tag,
-                            pc: id.pc,
-                            inst: id.inst,
-                            inst_size: id.inst_size,
-                            rd: id.rd,
-                            alu: 0,
-                            store_data: 0,
-                            ctrl: id.ctrl,
-                            trap: None,
-                            exception_stage: None,
-                            rd_phys: PhysReg::default(),
-                            fp_flags: 0,
-                            sfence_vma: None,
-                        });
-                        continue;
-                    }
-                }
-
                 // Drain accumulated fp_flags from older ROB entries AND in-flight
                 // pipeline latches into fflags before reading fflags/fcsr/frm,
                 // so the CSR read sees flags from all older FP instructions.
@@ -542,11 +305,13 @@ pub fn execute_inorder(
                         cpu.csrs.fflags |= (acc | inflight_fp_flags | batch_fp_flags) as u64;
                     }
                 }
-                let old = cpu.csr_read(id.ctrl.csr_addr);
-                let src = match id.ctrl.csr_op {
-                    CsrOp::Rwi | CsrOp::Rsi | CsrOp::Rci => id.rs1.as_usize() as u64 & 0x1f,
-                    _ => fwd_a,
+                // CSRRW/CSRRWI with rd=x0 must not read the CSR (spec §2.8).
+                let old = if id.ctrl.csr_op.reads_csr(id.rd) {
+                    cpu.csr_read(id.ctrl.csr_addr)
+                } else {
+                    0
                 };
+                let src = if id.ctrl.csr_op.is_imm() { id.rs1.as_usize() as u64 } else { fwd_a };
                 let new = match id.ctrl.csr_op {
                     CsrOp::Rw | CsrOp::Rwi => src,
                     CsrOp::Rs | CsrOp::Rsi => old | src,
@@ -557,12 +322,7 @@ pub fn execute_inorder(
                 // Only generate a CSR write if the operation actually writes.
                 // CSRRS/CSRRC with rs1=x0 and CSRRSI/CSRRCI with uimm=0 are
                 // pure reads and must not trigger write side effects (spec §2.8).
-                let would_write = match id.ctrl.csr_op {
-                    CsrOp::Rw | CsrOp::Rwi => true,
-                    CsrOp::Rs | CsrOp::Rc => !id.rs1.is_zero(),
-                    CsrOp::Rsi | CsrOp::Rci => (id.rs1.as_u8() & 0x1f) != 0,
-                    CsrOp::None => false,
-                };
+                let would_write = id.ctrl.csr_op.writes_csr(id.rs1);
                 if would_write {
                     rob.set_csr_update(
                         id.rob_tag,
@@ -599,35 +359,6 @@ pub fn execute_inorder(
             }
         }
 
-        // When mstatus.FS == OFF, all FP instructions trap as illegal.
-        // This check is in execute (not decode) because a preceding CSR write
-        // to mstatus may still be in-flight (deferred to commit) when the FP
-        // instruction is decoded, causing a false positive.
-        {
-            let fs = (cpu.csrs.mstatus & crate::core::arch::csr::MSTATUS_FS) >> 13;
-            let is_fp = id.ctrl.fp_reg_write || id.ctrl.rs1_fp || id.ctrl.rs2_fp || id.ctrl.rs3_fp;
-            if fs == 0 && is_fp {
-                rob.fault(id.rob_tag, Trap::IllegalInstruction(id.inst), ExceptionStage::Execute);
-                flush_remaining = true;
-                results.push(ExMem1Entry {
-                    rob_tag: id.rob_tag,
-                    pc: id.pc,
-                    inst: id.inst,
-                    inst_size: id.inst_size,
-                    rd: id.rd,
-                    alu: 0,
-                    store_data: 0,
-                    ctrl: id.ctrl,
-                    trap: None,
-                    exception_stage: None,
-                    rd_phys: PhysReg::default(),
-                    fp_flags: 0,
-                    sfence_vma: None,
-                });
-                continue;
-            }
-        }
-
         // ALU / FPU execution
         let (alu_out, fp_flags) = compute_alu(id.ctrl.alu, op_a, op_b, op_c, id.ctrl.is_rv32);
 
@@ -637,6 +368,12 @@ pub fn execute_inorder(
 
         // Branch resolution
         if id.ctrl.control_flow == ControlFlow::Branch {
+            // A fused compare+branch tests the comparison result against zero.
+            let (op_a, op_b) = if id.ctrl.fusion == Some(FusionPattern::CmpBranch) {
+                (alu_out, 0)
+            } else {
+                (op_a, op_b)
+            };
             let taken = match (id.inst >> FUNCT3_SHIFT) & FUNCT3_MASK {
                 funct3::BEQ => op_a == op_b,
                 funct3::BNE => op_a != op_b,
@@ -672,6 +409,7 @@ pub fn execute_inorder(
                 cpu.stats.speculative_branch_mispredictions += 1;
                 cpu.pc = actual_next_pc;
                 cpu.redirect_pending = true;
+                cpu.mispredict_pending = true;
                 flush_remaining = true;
             } else {
                 cpu.stats.speculative_branch_predictions += 1;
@@ -682,8 +420,6 @@ pub fn execute_inorder(
         if id.ctrl.control_flow == ControlFlow::Jump {
             use crate::common::constants::OPCODE_MASK;
             let is_jalr = (id.inst & OPCODE_MASK) == opcodes::OP_JALR;
-            let rd_link = id.rd == abi::REG_RA || id.rd == abi::REG_T0;
-            let rs1_link = is_jalr && (id.rs1 == abi::REG_RA || id.rs1 == abi::REG_T0);
 
             let actual_target = if is_jalr {
                 (fwd_a.wrapping_add(id.imm as u64)) & JALR_ALIGNMENT_MASK
@@ -705,36 +441,26 @@ pub fn execute_inorder(
             rob.set_bp_target(id.rob_tag, actual_target);
 
             // Update BTB directly — jumps are unconditional, don't train direction predictor.
-            // Skip for calls — on_call already updates the BTB.
-            if !rd_link {
-                cpu.branch_predictor.update_btb(id.pc, actual_target);
-            }
+            cpu.branch_predictor.update_btb(id.pc, actual_target);
 
             if mispredicted {
+                // Undo wrong-path RAS pushes/pops, then redo this jump's own
+                // call/return (fetch may not have applied it, e.g. after a
+                // page-crossing fetch).
                 cpu.branch_predictor.repair_history(&id.ghr_snapshot);
                 cpu.branch_predictor.restore_ras(id.ras_snapshot);
+                let ret_addr = id.pc.wrapping_add(id.inst_size.as_u64());
+                if let Some(op) = RasOp::for_inst(id.inst, ret_addr) {
+                    cpu.branch_predictor.update_ras(op);
+                }
                 cpu.stats.speculative_branch_mispredictions += 1;
                 cpu.pc = actual_target;
                 cpu.redirect_pending = true;
+                cpu.mispredict_pending = true;
                 flush_remaining = true;
             } else {
                 cpu.stats.speculative_branch_predictions += 1;
             }
-
-            // RAS management per RISC-V spec Table 2.1:
-            // Both x1 (ra) and x5 (t0) are link registers.
-            let ret_addr = id.pc.wrapping_add(id.inst_size.as_u64());
-            if rd_link && rs1_link && id.rd != id.rs1 {
-                // Coroutine swap: pop then push
-                cpu.branch_predictor.on_return();
-                cpu.branch_predictor.on_call(id.pc, ret_addr, actual_target);
-            } else if rd_link {
-                // Call (JAL/JALR with rd in {x1, x5})
-                cpu.branch_predictor.on_call(id.pc, ret_addr, actual_target);
-            } else if rs1_link {
-                // Return (JALR with rs1 in {x1, x5}, rd not a link register)
-                cpu.branch_predictor.on_return();
-            }
         }
 
         results.push(ExMem1Entry {
@@ -757,9 +483,27 @@ pub fn execute_inorder(
     (results, flush_remaining)
 }
 
+/// Returns the result of the instruction in `results` tagged `tag`, if any.
+fn chained_operand(results: &[ExMem1Entry], tag: Option<RobTag>) -> Option<u64> {
+    let tag = tag?;
+    results.iter().find(|e| e.rob_tag == tag).map(|e| {
+        if e.ctrl.control_flow == ControlFlow::Jump {
+            e.pc.wrapping_add(e.inst_size.as_u64())
+        } else {
+            e.alu
+        }
+    })
+}
+
 /// Computes the ALU/FPU result and returns `(result, fp_flags)`.
 /// `fp_flags` is non-zero only for floating-point arithmetic operations.
-fn compute_alu(alu_op: AluOp, op_a: u64, op_b: u64, op_c: u64, is_rv32: bool) -> (u64, u8) {
+pub(crate) fn compute_alu(
+    alu_op: AluOp,
+    op_a: u64,
+    op_b: u64,
+    op_c: u64,
+    is_rv32: bool,
+) -> (u64, u8) {
     // FP conversions and moves that need special handling.
     // Int-to-float and float-to-float conversions can raise FP exception
     // flags (INEXACT, OVERFLOW, etc.), so we use the host FPU to detect them.
@@ -5,13 +5,31 @@
 //! - If tag is None → read from architectural register file.
 //! - If tag points to a completed ROB entry → bypass the result.
 //! - If the ROB entry is still in-flight → stall (operand not ready).
+//!
+//! With a full bypass network, a result still in the Memory1/Memory2
+//! latches is forwarded instead of waiting for writeback.
+//!
+//! Within one issue group, a younger instruction that reads an older
+//! member's result waits for a later cycle, unless the bypass is full and
+//! the producer is a single-cycle operation: execute then chains the value
+//! straight from the producer. Loads never chain, so a dependent
+//! instruction always issues at least one cycle behind the load.
+//!
+//! An instruction with ready operands also needs a free functional unit of
+//! its type, and a free issue port of its class; if either is exhausted
+//! this cycle, issue stalls behind it.
 
 use crate::common::RegIdx;
+use crate::config::BypassModel;
 use crate::core::Cpu;
+use crate::core::pipeline::backend::o3::fu_pool::{
+    FuPool, FuType, OpClass, PORT_CLASS_COUNT, PortClass, PortConfig,
+};
 use crate::core::pipeline::latches::RenameIssueEntry;
 use crate::core::pipeline::rob::{Rob, RobState, RobTag};
 use crate::core::pipeline::signals::SystemOp;
 use crate::core::pipeline::store_buffer::StoreBuffer;
+use crate::core::units::lsu::ordering::Fence;
 use crate::trace_issue;
 
 use std::collections::VecDeque;
@@ -21,6 +39,8 @@ use std::collections::VecDeque;
 pub struct InOrderIssueUnit {
     queue: VecDeque<RenameIssueEntry>,
     capacity: usize,
+    bypass: BypassModel,
+    ports: PortConfig,
 }
 
 impl InOrderIssueUnit {
@@ -32,8 +52,13 @@ impl InOrderIssueUnit {
     /// all of these are dispatched at once. If the issue queue is smaller
     /// than the ROB, entries would be silently dropped, leaving ROB slots
     /// permanently stuck in `Issued` state and deadlocking the pipeline.
-    pub fn new(capacity: usize) -> Self {
-        Self { queue: VecDeque::with_capacity(capacity), capacity }
+    pub fn new(capacity: usize, bypass: BypassModel, ports: PortConfig) -> Self {
+        Self { queue: VecDeque::with_capacity(capacity), capacity, bypass, ports }
+    }
+
+    /// Returns the modelled forwarding network.
+    pub const fn bypass(&self) -> BypassModel {
+        self.bypass
     }
 
     /// Accept dispatched instructions from rename.
@@ -55,17 +80,34 @@ impl InOrderIssueUnit {
 
     /// Select instructions to execute this cycle, reading operands via
     /// tags captured at rename time. Returns up to `width` entries with
-    /// operands populated.
+    /// operands populated, each paired with its execution latency.
+    ///
+    /// `forwarded` holds the `(tag, value)` results available on the bypass
+    /// network this cycle; it is ignored without a full bypass. Operands
+    /// chained from an older member of the group are left for execute to
+    /// fill in.
     ///
     /// In-order: if the head-of-queue is blocked, nothing behind it can issue.
+    /// Each issued instruction acquires a unit from `fu_pool` at cycle `now`;
+    /// a head whose unit type is saturated counts as a structural stall, and
+    /// one whose port class has issued its limit this cycle as a port stall.
+    #[allow(clippy::too_many_arguments)]
     pub fn select(
         &mut self,
         width: usize,
         rob: &Rob,
         store_buffer: &StoreBuffer,
-        cpu: &Cpu,
-    ) -> Vec<RenameIssueEntry> {
+        fu_pool: &mut FuPool,
+        forwarded: &[(RobTag, u64)],
+        now: u64,
+        cpu: &mut Cpu,
+    ) -> Vec<(RenameIssueEntry, u64)> {
         let mut selected = Vec::with_capacity(width);
+        let full_bypass = self.bypass == BypassModel::Full;
+        let forwarded = if full_bypass { forwarded } else { &[] };
+        // Older members of this group, and whether each can chain its result.
+        let mut group: Vec<(RobTag, bool)> = Vec::with_capacity(width);
+        let mut ports_used = [0usize; PORT_CLASS_COUNT];
 
         for _ in 0..width {
             let Some(entry) = self.queue.front() else { break };
@@ -73,7 +115,8 @@ impl InOrderIssueUnit {
             // Faulted instructions don't need operands — pass through
             if entry.trap.is_some() {
                 if let Some(e) = self.queue.pop_front() {
-                    selected.push(e);
+                    group.push((e.rob_tag, false));
+                    selected.push((e, 1));
                 }
                 continue;
             }
@@ -81,22 +124,30 @@ impl InOrderIssueUnit {
             // ── Serialization checks (matching O3 issue queue) ──────────
 
             // System/CSR instructions are serializing: wait for all older
-            // instructions to complete before issuing.
-            if entry.ctrl.system_op != SystemOp::None && !rob.all_before_completed(entry.rob_tag) {
+            // instructions to complete, and for any older CSR write to
+            // commit, before issuing.
+            if entry.ctrl.system_op != SystemOp::None
+                && (!rob.all_before_completed(entry.rob_tag)
+                    || rob.has_pending_csr_write_before(entry.rob_tag))
+            {
                 break;
             }
 
             // FENCE: wait for older operations matching pred bits to complete.
             if entry.ctrl.system_op == SystemOp::Fence {
-                let pred_bits = ((entry.inst >> 24) & 0xF) as u8;
-                let pred_r = pred_bits & 0b0010 != 0;
-                let pred_w = pred_bits & 0b0001 != 0;
-                if !rob.fence_pred_satisfied(entry.rob_tag, pred_r, pred_w) {
+                let pred = Fence::decode(entry.inst).pred;
+                if !rob.fence_pred_satisfied(entry.rob_tag, pred.r, pred.w) {
                     break;
                 }
             }
 
-            // Loads/stores: blocked by older in-flight FENCE with matching succ bits.
+            // Release atomic: wait for all older loads and stores to complete.
+            if entry.ctrl.rl && !rob.fence_pred_satisfied(entry.rob_tag, true, true) {
+                break;
+            }
+
+            // Loads/stores: blocked by older in-flight FENCE with matching
+            // succ bits, or by an older acquire atomic.
             if (entry.ctrl.mem_read || entry.ctrl.mem_write)
                 && rob.has_fence_blocking(entry.rob_tag, entry.ctrl.mem_read, entry.ctrl.mem_write)
             {
@@ -109,23 +160,81 @@ impl InOrderIssueUnit {
                 break;
             }
 
+            // ── Same-group dependences ──────────────────────────────────
+
+            let mut sources = vec![
+                (entry.rs1, entry.ctrl.rs1_fp, entry.rs1_tag),
+                (entry.rs2, entry.ctrl.rs2_fp, entry.rs2_tag),
+            ];
+            if entry.ctrl.rs3_fp {
+                sources.push((entry.rs3, true, entry.rs3_tag));
+            }
+            let blocked = sources.iter().any(|&(reg, is_fp, tag)| {
+                (is_fp || !reg.is_zero())
+                    && group.iter().any(|&(t, chains)| Some(t) == tag && !(chains && full_bypass))
+            });
+            if blocked {
+                trace_issue!(cpu.trace;
+                    pc      = %crate::trace::Hex(entry.pc),
+                    "IS: stall — depends on an older instruction in this group"
+                );
+                break;
+            }
+
             // ── Operand readiness ───────────────────────────────────────
 
-            // Try to read all source operands using tags captured at rename
-            let rv1 = read_operand_by_tag(entry.rs1, entry.ctrl.rs1_fp, entry.rs1_tag, rob, cpu);
-            let rv2 = read_operand_by_tag(entry.rs2, entry.ctrl.rs2_fp, entry.rs2_tag, rob, cpu);
-            let rv3 = if entry.ctrl.rs3_fp {
-                read_operand_by_tag(entry.rs3, true, entry.rs3_tag, rob, cpu)
-            } else {
-                Some(0)
+            // Try to read all source operands using tags captured at rename;
+            // an operand chained from this group is filled in by execute.
+            let read = |reg: RegIdx, is_fp: bool, tag: Option<RobTag>| {
+                if tag.is_some_and(|t| group.iter().any(|&(g, _)| g == t)) {
+                    Some(0)
+                } else {
+                    read_operand_by_tag(reg, is_fp, tag, rob, forwarded, cpu)
+                }
             };
+            let rv1 = read(entry.rs1, entry.ctrl.rs1_fp, entry.rs1_tag);
+            let rv2 = read(entry.rs2, entry.ctrl.rs2_fp, entry.rs2_tag);
+            let rv3 =
+                if entry.ctrl.rs3_fp { read(entry.rs3, true, entry.rs3_tag) } else { Some(0) };
 
             if let (Some(v1), Some(v2), Some(v3)) = (rv1, rv2, rv3) {
+                // ── Structural hazard ───────────────────────────────────
+                let op = OpClass::classify(&entry.ctrl);
+                let fu_type = op.fu_type();
+                let port = PortClass::of(fu_type);
+                if ports_used[port as usize] >= self.ports.limit(port) {
+                    cpu.stats.stalls_issue_port += 1;
+                    trace_issue!(cpu.trace;
+                        pc      = %crate::trace::Hex(entry.pc),
+                        port    = port.name(),
+                        "IS: stall — issue ports of this class taken"
+                    );
+                    break;
+                }
+                if !fu_pool.has_free(fu_type, now) {
+                    cpu.stats.stalls_fu_structural += 1;
+                    trace_issue!(cpu.trace;
+                        pc      = %crate::trace::Hex(entry.pc),
+                        fu      = fu_type.name(),
+                        "IS: stall — no free functional unit"
+                    );
+                    break;
+                }
+                let latency = fu_pool.latency_for(op, &entry.ctrl, v1, v2);
+                let _ = fu_pool.acquire_for(op, now, latency);
+                cpu.stats.fu_busy_cycles[fu_type as usize] += fu_pool.occupancy(op, latency);
+                ports_used[port as usize] += 1;
+                cpu.stats.port_issues[port as usize] += 1;
+
                 let Some(mut issued) = self.queue.pop_front() else { break };
+                let chains = latency == 1
+                    && fu_type != FuType::Mem
+                    && issued.ctrl.system_op == SystemOp::None;
+                group.push((issued.rob_tag, chains));
                 issued.rv1 = v1;
                 issued.rv2 = v2;
                 issued.rv3 = v3;
-                selected.push(issued);
+                selected.push((issued, latency));
             } else {
                 // Head of queue blocked — in-order can't skip
                 trace_issue!(cpu.trace;
@@ -173,12 +282,16 @@ impl InOrderIssueUnit {
 
 /// Read a single operand value using the tag captured at rename time.
 ///
+/// A producer that has not written back yet is read from `forwarded` when
+/// its result is on the bypass network.
+///
 /// Returns `Some(value)` if the operand is ready, `None` if stalled.
 fn read_operand_by_tag(
     reg: RegIdx,
     is_fp: bool,
     tag: Option<RobTag>,
     rob: &Rob,
+    forwarded: &[(RobTag, u64)],
     cpu: &Cpu,
 ) -> Option<u64> {
     // x0 is hardwired zero
@@ -196,7 +309,7 @@ fn read_operand_by_tag(
             // In-flight producer — check if ROB entry has completed
             match rob.find_entry(t) {
                 Some(entry) if entry.state == RobState::Completed => entry.result,
-                Some(_) => None, // Not ready — stall
+                Some(_) => forwarded.iter().find(|(f, _)| *f == t).map(|&(_, v)| v),
                 None => {
                     // ROB entry gone (already committed) — value is in register file
                     Some(if is_fp { cpu.regs.read_f(reg) } else { cpu.regs.read(reg) })
@@ -1,21 +1,32 @@
 //! In-order backend: FIFO issue, single execution path.
 //!
 //! This backend implements the simple in-order pipeline with:
-//! - `InOrderIssueUnit`: FIFO pass-through (no reordering)
+//! - `InOrderIssueUnit`: FIFO pass-through (no reordering), gated by a `FuPool`
 //! - `InOrderExecuteUnit`: Single ALU/FPU/BRU execution
+//!
+//! Results of multi-cycle arithmetic (e.g. multiply, divide) wait in
+//! `in_flight` until their unit finishes. Issue stays in order, but younger
+//! independent instructions may complete while an older divide is running.
+//!
+//! On a misprediction the scoreboard is restored from the branch's checkpoint
+//! when one was allocated, and rebuilt from the surviving ROB entries otherwise.
 
 pub mod execute;
 pub mod issue;
 
-use crate::config::Config;
+use crate::config::{BypassModel, Config};
 use crate::core::Cpu;
+use crate::core::pipeline::backend::o3::fu_pool::{FuPool, FuType};
 use crate::core::pipeline::backend::shared::{commit, memory1, memory2, writeback};
+use crate::core::pipeline::checkpoint::CheckpointTable;
 use crate::core::pipeline::engine::ExecutionEngine;
 use crate::core::pipeline::free_list::FreeList;
 use crate::core::pipeline::latches::{ExMem1Entry, Mem1Mem2Entry, Mem2WbEntry, RenameIssueEntry};
+use crate::core::pipeline::pipe_trace::PipeStage;
 use crate::core::pipeline::rename_map::RenameMap;
-use crate::core::pipeline::rob::Rob;
+use crate::core::pipeline::rob::{Rob, RobTag};
 use crate::core::pipeline::scoreboard::Scoreboard;
+use crate::core::pipeline::signals::{ControlFlow, SystemOp};
 use crate::core::pipeline::store_buffer::StoreBuffer;
 use crate::core::units::bru::BranchPredictor;
 
@@ -26,21 +37,10 @@ fn drain_mshr_completions(cpu: &mut Cpu, mem1_mem2: &mut Vec<Mem1Mem2Entry>, now
     if cpu.l1d_mshrs.capacity() == 0 {
         return;
     }
+    cpu.resolve_dram_requests();
     let completed = cpu.l1d_mshrs.drain_completions(now);
     for mshr_entry in completed {
-        let (_penalty, evicted) = cpu.l1_d_cache.install_line_public_tracked(
-            mshr_entry.line_addr,
-            mshr_entry.is_write,
-            0,
-        );
-
-        if cpu.inclusion_policy == crate::config::InclusionPolicy::Exclusive
-            && cpu.l2_cache.enabled
-            && let Some(ev) = evicted
-        {
-            let _ = cpu.l2_cache.install_or_replace(ev.addr, ev.dirty, 0);
-            cpu.stats.exclusive_l1_to_l2_swaps += 1;
-        }
+        cpu.install_l1d_fill(mshr_entry.line_addr, mshr_entry.is_write);
 
         for waiter in mshr_entry.waiters {
             if let Some(mut parked) = waiter.parked_entry {
@@ -64,8 +64,13 @@ pub struct InOrderEngine {
     pub scoreboard: Scoreboard,
     /// FIFO issue unit.
     pub issuer: InOrderIssueUnit,
+    /// Functional unit pool (structural hazards at issue).
+    pub fu_pool: FuPool,
     /// Pipeline width.
     pub width: usize,
+    /// Multi-cycle results still in their functional unit, with the cycle
+    /// at which each enters Memory1.
+    pub in_flight: Vec<(u64, ExMem1Entry)>,
     /// Execute -> Memory1 latch.
     pub execute_mem1: Vec<ExMem1Entry>,
     /// Memory1 -> Memory2 latch.
@@ -74,6 +79,8 @@ pub struct InOrderEngine {
     pub mem2_wb: Vec<Mem2WbEntry>,
     /// Memory1 stall counter (D-TLB / D-cache latency).
     pub mem1_stall: u64,
+    /// Per-branch scoreboard checkpoints for misprediction recovery.
+    pub checkpoints: CheckpointTable,
     /// Current cycle counter (for MSHR completion tracking).
     cycle: u64,
     /// Committed rename map stub (unused; required by shared `commit_stage` signature).
@@ -89,17 +96,50 @@ impl InOrderEngine {
             rob: Rob::new(config.pipeline.rob_size),
             store_buffer: StoreBuffer::new(config.pipeline.store_buffer_size),
             scoreboard: Scoreboard::new(),
-            issuer: InOrderIssueUnit::new(config.pipeline.rob_size),
+            issuer: InOrderIssueUnit::new(
+                config.pipeline.rob_size,
+                config.pipeline.bypass,
+                config.pipeline.ports,
+            ),
+            fu_pool: FuPool::new(&config.pipeline.fu_config, &config.pipeline.latencies),
             width: config.pipeline.width,
+            in_flight: Vec::new(),
             execute_mem1: Vec::with_capacity(config.pipeline.width),
             mem1_mem2: Vec::with_capacity(config.pipeline.width),
             mem2_wb: Vec::with_capacity(config.pipeline.width),
             mem1_stall: 0,
+            checkpoints: CheckpointTable::new(config.pipeline.checkpoint_count),
             cycle: 0,
             committed_rename_map: RenameMap::new(),
             free_list: FreeList::new(0, 0),
         }
     }
+
+    /// Results on the bypass network at issue time: ALU and jump results
+    /// that have left execute, and any result that has left Memory2.
+    fn forwarded_results(&self) -> Vec<(RobTag, u64)> {
+        if self.issuer.bypass() != BypassModel::Full {
+            return Vec::new();
+        }
+        let mem1 = self
+            .mem1_mem2
+            .iter()
+            .filter(|e| e.trap.is_none() && FuType::classify(&e.ctrl) != FuType::Mem)
+            .map(|e| {
+                let val = if e.ctrl.control_flow == ControlFlow::Jump {
+                    e.pc.wrapping_add(e.inst_size.as_u64())
+                } else {
+                    e.alu
+                };
+                (e.rob_tag, val)
+            });
+        let mem2 = self
+            .mem2_wb
+            .iter()
+            .filter(|e| e.trap.is_none())
+            .map(|e| (e.rob_tag, writeback::result_value(e)));
+        mem1.chain(mem2).collect()
+    }
 }
 
 impl ExecutionEngine for InOrderEngine {
@@ -124,7 +164,7 @@ impl ExecutionEngine for InOrderEngine {
             self.width,
             None, // in-order backend: no load queue
             None, // in-order backend: no PRF
-            None, // in-order backend: no checkpoints
+            Some(&mut self.checkpoints),
         );
 
         // Handle trap: flush everything
@@ -132,6 +172,8 @@ impl ExecutionEngine for InOrderEngine {
             self.flush(cpu);
             cpu.redirect_pending = true;
             cpu.trap(&trap, pc);
+            // Operands are read from `cpu.regs`, which already holds the result.
+            cpu.trap_reg_write = None;
             cpu.committed_next_pc = cpu.pc;
             return;
         }
@@ -187,10 +229,32 @@ impl ExecutionEngine for InOrderEngine {
         let (results, needs_flush) = if backpressured {
             (Vec::new(), false)
         } else {
-            let issued = self.issuer.select(self.width, &self.rob, &self.store_buffer, cpu);
-            if issued.is_empty() && !self.issuer.is_empty() {
+            let structural_before = cpu.stats.stalls_fu_structural + cpu.stats.stalls_issue_port;
+            let forwarded = self.forwarded_results();
+            let issued = self.issuer.select(
+                self.width,
+                &self.rob,
+                &self.store_buffer,
+                &mut self.fu_pool,
+                &forwarded,
+                self.cycle,
+                cpu,
+            );
+            let structural_stalled =
+                cpu.stats.stalls_fu_structural + cpu.stats.stalls_issue_port != structural_before;
+            if issued.is_empty() && !self.issuer.is_empty() && !structural_stalled {
                 cpu.stats.stalls_data += 1;
             }
+            let (issued, latencies): (Vec<_>, Vec<_>) = issued
+                .into_iter()
+                .map(|(e, latency)| {
+                    let tag = e.rob_tag;
+                    (e, (tag, latency))
+                })
+                .unzip();
+            for e in &issued {
+                cpu.pipe_event(e.seq, PipeStage::Execute);
+            }
             // Accumulate fp_flags from in-flight pipeline entries that
             // haven't reached writeback/ROB yet, so CSR reads of fflags
             // see flags from all older FP instructions.
@@ -204,8 +268,34 @@ impl ExecutionEngine for InOrderEngine {
             for e in &self.mem2_wb {
                 inflight_fp_flags |= e.fp_flags;
             }
-            execute::execute_inorder(cpu, issued, &mut self.rob, inflight_fp_flags)
+            for (_, e) in &self.in_flight {
+                inflight_fp_flags |= e.fp_flags;
+            }
+            let (mut results, needs_flush) =
+                execute::execute_inorder(cpu, issued, &mut self.rob, inflight_fp_flags);
+            // Hold multi-cycle arithmetic results until their unit finishes.
+            // Memory ops are timed by Memory1. Control flow and system ops
+            // always pass straight through, so the
+            // instruction that requested a flush stays last in execute_mem1.
+            let ready_at = |e: &ExMem1Entry| {
+                let latency = latencies.iter().find(|(t, _)| *t == e.rob_tag).map_or(1, |l| l.1);
+                self.cycle + latency - 1
+            };
+            let held = results.extract_if(.., |e| {
+                e.trap.is_none()
+                    && FuType::classify(&e.ctrl) != FuType::Mem
+                    && e.ctrl.control_flow == ControlFlow::Sequential
+                    && e.ctrl.system_op == SystemOp::None
+                    && ready_at(e) > self.cycle
+            });
+            self.in_flight.extend(held.map(|e| (ready_at(&e), e)));
+            (results, needs_flush)
         };
+        // Multi-cycle results whose unit finishes this cycle leave execute
+        // ahead of the younger results issued this cycle.
+        let now = self.cycle;
+        self.execute_mem1
+            .extend(self.in_flight.extract_if(.., |(ready, _)| *ready <= now).map(|(_, e)| e));
         self.execute_mem1.extend(results);
 
         // If execute detected a misprediction / CSR / MRET / SRET / FENCE.I,
@@ -240,10 +330,23 @@ impl ExecutionEngine for InOrderEngine {
                 // Pre-branch stores may still be in-flight (Ready but not yet
                 // Committed) and must be kept for correct store-to-load forwarding.
                 self.store_buffer.flush_after(keep_tag);
+                // Restore the scoreboard as it was right after the branch renamed.
+                // Without a checkpoint (CSR/FENCE.I/xRET flushes, or checkpoints
+                // disabled), rebuild it from the surviving ROB entries instead
+                // (pre-branch instructions that haven't committed yet still need
+                // their scoreboard entries).
+                if let Some(ckpt) = self.checkpoints.find_by_tag(keep_tag) {
+                    self.scoreboard = ckpt.scoreboard.clone();
+                    cpu.stats.checkpoint_restores += 1;
+                } else {
+                    self.scoreboard.rebuild_from_rob(&self.rob);
+                    cpu.stats.recovery_walk_entries += self.rob.len() as u64;
+                }
+                self.checkpoints.flush_after(keep_tag);
+            } else {
+                self.scoreboard.rebuild_from_rob(&self.rob);
+                cpu.stats.recovery_walk_entries += self.rob.len() as u64;
             }
-            // Rebuild scoreboard from surviving ROB entries (pre-branch instructions
-            // that haven't committed yet still need their scoreboard entries).
-            self.scoreboard.rebuild_from_rob(&self.rob);
         }
 
         // Accept dispatched instructions from rename into the issue queue.
@@ -275,10 +378,12 @@ impl ExecutionEngine for InOrderEngine {
         self.store_buffer.flush_speculative();
         self.scoreboard.flush();
         self.issuer.flush();
+        self.in_flight.clear();
         self.execute_mem1.clear();
         self.mem1_mem2.clear();
         self.mem2_wb.clear();
         self.mem1_stall = 0;
+        self.checkpoints.flush_all();
         // Flush all MSHRs — their parked entries are now invalid
         cpu.l1d_mshrs.flush();
         // Reset speculative GHR to committed state — wrong-path branch
@@ -287,8 +392,8 @@ impl ExecutionEngine for InOrderEngine {
     }
 
     fn read_csr_speculative(&self, cpu: &crate::core::Cpu, addr: crate::common::CsrAddr) -> u64 {
-        // In-order serialization guarantees all older CSR writes have committed
-        // before a newer CSR read issues, so architectural state is correct.
+        // Issue holds CSR instructions until all older CSR writes have
+        // committed, so architectural state is correct.
         cpu.csr_read(addr)
     }
 
@@ -315,6 +420,22 @@ impl ExecutionEngine for InOrderEngine {
     fn scoreboard_mut(&mut self) -> &mut Scoreboard {
         &mut self.scoreboard
     }
+
+    fn issue_queue_len(&self) -> usize {
+        self.issuer.len()
+    }
+
+    fn checkpoint_table(&self) -> &CheckpointTable {
+        &self.checkpoints
+    }
+
+    fn checkpoint_table_mut(&mut self) -> &mut CheckpointTable {
+        &mut self.checkpoints
+    }
+
+    fn checkpoint_count(&self) -> usize {
+        self.checkpoints.capacity()
+    }
 }
 
 #[cfg(test)]
@@ -8,16 +8,18 @@
 //! This is independent from the in-order execute — both call the same
 //! hardware units but are structured differently.
 
-use crate::common::SfenceVmaInfo;
 use crate::common::error::{ExceptionStage, Trap};
+use crate::common::{SfenceVmaInfo, TranslationResult};
 use crate::core::Cpu;
+use crate::core::pipeline::backend::shared::cbo;
+use crate::core::pipeline::frontend::fusion::FusionPattern;
 use crate::core::pipeline::latches::{ExMem1Entry, RenameIssueEntry};
 use crate::core::pipeline::rob::{BpOutcome, CsrUpdate, Rob};
 use crate::core::pipeline::signals::{AluOp, ControlFlow, CsrOp, OpASrc, OpBSrc, SystemOp};
 use crate::core::units::alu::Alu;
 use crate::core::units::bru::BranchPredictor;
+use crate::core::units::bru::ras::RasOp;
 use crate::core::units::fpu::Fpu;
-use crate::isa::abi;
 use crate::isa::privileged::opcodes as sys_ops;
 use crate::isa::rv64i::{funct3, opcodes};
 use crate::trace_branch;
@@ -41,6 +43,7 @@ pub fn execute_one(cpu: &mut Cpu, id: RenameIssueEntry, rob: &mut Rob) -> (ExMem
             rob_tag         = id.rob_tag.0,
             pc              = %crate::trace::Hex(id.pc),
             inst            = %crate::trace::Hex32(id.inst),
+            asm             = %crate::trace::Disasm(id.inst),
             trap            = ?trap,
             stage           = ?id.exception_stage,
             "EX: trap propagated from earlier stage"
@@ -68,6 +71,7 @@ pub fn execute_one(cpu: &mut Cpu, id: RenameIssueEntry, rob: &mut Rob) -> (ExMem
         rob_tag  = id.rob_tag.0,
         pc       = %crate::trace::Hex(id.pc),
         inst     = %crate::trace::Hex32(id.inst),
+        asm      = %crate::trace::Disasm(id.inst),
         rd       = id.rd.as_usize(),
         rd_phys  = id.rd_phys.0,
         rs1      = id.rs1.as_usize(),
@@ -102,6 +106,33 @@ pub fn execute_one(cpu: &mut Cpu, id: RenameIssueEntry, rob: &mut Rob) -> (ExMem
     };
     let op_c = fwd_c;
 
+    // Privilege, CSR access and mstatus.FS checks.
+    if cpu.is_illegal_at_execute(&id.ctrl, id.rs1) {
+        trace_trap!(cpu.trace;
+            event   = "illegal",
+            pc      = %crate::trace::Hex(id.pc),
+            rob_tag = id.rob_tag.0,
+            "EX: IllegalInstruction"
+        );
+        rob.fault(id.rob_tag, Trap::IllegalInstruction(id.inst), ExceptionStage::Execute);
+        let result = ExMem1Entry {
+            rob_tag: id.rob_tag,
+            pc: id.pc,
+            inst: id.inst,
+            inst_size: id.inst_size,
+            rd: id.rd,
+            alu: 0,
+            store_data: 0,
+            ctrl: id.ctrl,
+            trap: None,
+            exception_stage: None,
+            rd_phys: id.rd_phys,
+            fp_flags: 0,
+            sfence_vma: None,
+        };
+        return (result, true);
+    }
+
     // FENCE.I: flush the pipeline so younger instructions are squashed.
     // The I-cache flush is deferred to COMMIT time (after store drain)
     // to ensure that all prior stores are visible in RAM before the
@@ -134,36 +165,43 @@ pub fn execute_one(cpu: &mut Cpu, id: RenameIssueEntry, rob: &mut Rob) -> (ExMem
         return (result, true);
     }
 
+    // CBO.*: check menvcfg and translate the block now; the cache and memory
+    // update is deferred to commit (after store drain), which redirects again
+    // so younger loads observe it. Younger instructions are squashed here.
+    if let SystemOp::Cbo(op) = id.ctrl.system_op {
+        let TranslationResult { paddr, trap, pte_update, .. } =
+            cbo::translate_block(cpu, op, id.inst, op_a);
+        if let Some(trap) = trap {
+            rob.fault(id.rob_tag, trap, ExceptionStage::Execute);
+        } else if let Some(upd) = pte_update {
+            rob.set_pte_update(id.rob_tag, upd);
+        }
+        cpu.pc = id.pc.wrapping_add(id.inst_size.as_u64());
+        cpu.redirect_pending = true;
+
+        let result = ExMem1Entry {
+            rob_tag: id.rob_tag,
+            pc: id.pc,
+            inst: id.inst,
+            inst_size: id.inst_size,
+            rd: id.rd,
+            alu: paddr.val(),
+            store_data: 0,
+            ctrl: id.ctrl,
+            trap: None,
+            exception_stage: None,
+            rd_phys: id.rd_phys,
+            fp_flags: 0,
+            sfence_vma: None,
+        };
+        return (result, true);
+    }
+
     // System instructions (FENCE is a NOP at execute — handled at commit only)
     if !matches!(id.ctrl.system_op, SystemOp::None | SystemOp::Fence) {
         return execute_system(cpu, id, rob, fwd_a, store_data);
     }
 
-    // When mstatus.FS == OFF, all FP instructions trap as illegal.
-    {
-        let fs = (cpu.csrs.mstatus & crate::core::arch::csr::MSTATUS_FS) >> 13;
-        let is_fp = id.ctrl.fp_reg_write || id.ctrl.rs1_fp || id.ctrl.rs2_fp || id.ctrl.rs3_fp;
-        if fs == 0 && is_fp {
-            rob.fault(id.rob_tag, Trap::IllegalInstruction(id.inst), ExceptionStage::Execute);
-            let result = ExMem1Entry {
-                rob_tag: id.rob_tag,
-                pc: id.pc,
-                inst: id.inst,
-                inst_size: id.inst_size,
-                rd: id.rd,
-                alu: 0,
-                store_data: 0,
-                ctrl: id.ctrl,
-                trap: None,
-                exception_stage: None,
-                rd_phys: id.rd_phys,
-                fp_flags: 0,
-                sfence_vma: None,
-            };
-            return (result, true);
-        }
-    }
-
     // ALU / FPU execution
     let (alu_out, fp_flags) = compute_alu(id.ctrl.alu, op_a, op_b, op_c, id.ctrl.is_rv32);
     trace_execute!(cpu.trace;
@@ -180,6 +218,12 @@ pub fn execute_one(cpu: &mut Cpu, id: RenameIssueEntry, rob: &mut Rob) -> (ExMem
 
     // Branch resolution
     if id.ctrl.control_flow == ControlFlow::Branch {
+        // A fused compare+branch tests the comparison result against zero.
+        let (op_a, op_b) = if id.ctrl.fusion == Some(FusionPattern::CmpBranch) {
+            (alu_out, 0)
+        } else {
+            (op_a, op_b)
+        };
         let taken = match (id.inst >> FUNCT3_SHIFT) & FUNCT3_MASK {
             funct3::BEQ => op_a == op_b,
             funct3::BNE => op_a != op_b,
@@ -227,6 +271,7 @@ pub fn execute_one(cpu: &mut Cpu, id: RenameIssueEntry, rob: &mut Rob) -> (ExMem
             cpu.stats.speculative_branch_mispredictions += 1;
             cpu.pc = actual_next_pc;
             cpu.redirect_pending = true;
+            cpu.mispredict_pending = true;
             needs_flush = true;
         } else {
             cpu.stats.speculative_branch_predictions += 1;
@@ -237,8 +282,7 @@ pub fn execute_one(cpu: &mut Cpu, id: RenameIssueEntry, rob: &mut Rob) -> (ExMem
     if id.ctrl.control_flow == ControlFlow::Jump {
         use crate::common::constants::OPCODE_MASK;
         let is_jalr = (id.inst & OPCODE_MASK) == opcodes::OP_JALR;
-        let rd_link = id.rd == abi::REG_RA || id.rd == abi::REG_T0;
-        let rs1_link = is_jalr && (id.rs1 == abi::REG_RA || id.rs1 == abi::REG_T0);
+        let ras_op = RasOp::for_inst(id.inst, id.pc.wrapping_add(id.inst_size.as_u64()));
 
         let actual_target = if is_jalr {
             (fwd_a.wrapping_add(id.imm as u64)) & JALR_ALIGNMENT_MASK
@@ -257,16 +301,17 @@ pub fn execute_one(cpu: &mut Cpu, id: RenameIssueEntry, rob: &mut Rob) -> (ExMem
         rob.set_bp_target(id.rob_tag, actual_target);
 
         // Update BTB directly — jumps are unconditional, don't train direction predictor.
-        // Skip for calls — on_call already updates the BTB.
-        if !rd_link {
-            cpu.branch_predictor.update_btb(id.pc, actual_target);
-        }
+        cpu.branch_predictor.update_btb(id.pc, actual_target);
 
         trace_branch!(cpu.trace;
             event          = "resolve",
             pc             = %crate::trace::Hex(id.pc),
             rob_tag        = id.rob_tag.0,
-            bp_type        = if rs1_link && !rd_link { "JALR/RAS" } else if rd_link { "JAL/call" } else { "JAL/JALR" },
+            bp_type        = match ras_op {
+                Some(RasOp::Pop) => "JALR/RAS",
+                Some(_) => "JAL/call",
+                None => "JAL/JALR",
+            },
             pred_taken     = id.pred_taken,
             pred_target    = %crate::trace::Hex(predicted_target),
             actual_taken   = true,
@@ -275,30 +320,22 @@ pub fn execute_one(cpu: &mut Cpu, id: RenameIssueEntry, rob: &mut Rob) -> (ExMem
             "EX: jump resolved"
         );
         if mispredicted {
+            // Undo wrong-path RAS pushes/pops, then redo this jump's own
+            // call/return (fetch may not have applied it, e.g. after a
+            // page-crossing fetch).
             cpu.branch_predictor.repair_history(&id.ghr_snapshot);
             cpu.branch_predictor.restore_ras(id.ras_snapshot);
+            if let Some(op) = ras_op {
+                cpu.branch_predictor.update_ras(op);
+            }
             cpu.stats.speculative_branch_mispredictions += 1;
             cpu.pc = actual_target;
             cpu.redirect_pending = true;
+            cpu.mispredict_pending = true;
             needs_flush = true;
         } else {
             cpu.stats.speculative_branch_predictions += 1;
         }
-
-        // RAS management per RISC-V spec Table 2.1:
-        // Both x1 (ra) and x5 (t0) are link registers.
-        let ret_addr = id.pc.wrapping_add(id.inst_size.as_u64());
-        if rd_link && rs1_link && id.rd != id.rs1 {
-            // Coroutine swap: pop then push
-            cpu.branch_predictor.on_return();
-            cpu.branch_predictor.on_call(id.pc, ret_addr, actual_target);
-        } else if rd_link {
-            // Call (JAL/JALR with rd in {x1, x5})
-            cpu.branch_predictor.on_call(id.pc, ret_addr, actual_target);
-        } else if rs1_link {
-            // Return (JALR with rs1 in {x1, x5}, rd not a link register)
-            cpu.branch_predictor.on_return();
-        }
     }
 
     let result = ExMem1Entry {
@@ -345,12 +382,8 @@ fn execute_system(
             sfence_vma: None,
         };
 
-    // MRET: requires M-mode privilege (spec §3.3.2)
+    // MRET: privilege restore deferred to commit.
     if id.ctrl.system_op == SystemOp::Mret {
-        if cpu.privilege != crate::core::arch::mode::PrivilegeMode::Machine {
-            rob.fault(id.rob_tag, Trap::IllegalInstruction(id.inst), ExceptionStage::Execute);
-            return (make_result(0, id.ctrl), true);
-        }
         trace_trap!(cpu.trace;
             event       = "return",
             pc          = %crate::trace::Hex(id.pc),
@@ -364,25 +397,8 @@ fn execute_system(
         return (make_result(0, id.ctrl), true);
     }
 
-    // SRET: requires at least S-mode privilege (spec §3.3.2)
+    // SRET: privilege restore deferred to commit.
     if id.ctrl.system_op == SystemOp::Sret {
-        if cpu.privilege == crate::core::arch::mode::PrivilegeMode::User {
-            rob.fault(id.rob_tag, Trap::IllegalInstruction(id.inst), ExceptionStage::Execute);
-            return (make_result(0, id.ctrl), true);
-        }
-        let tsr = (cpu.csrs.mstatus >> 22) & 1;
-        if cpu.privilege == crate::core::arch::mode::PrivilegeMode::Supervisor && tsr != 0 {
-            trace_trap!(cpu.trace;
-                event   = "illegal",
-                pc      = %crate::trace::Hex(id.pc),
-                rob_tag = id.rob_tag.0,
-                insn    = "SRET",
-                reason  = "TSR=1 in S-mode",
-                "EX: SRET -> IllegalInstruction (TSR)"
-            );
-            rob.fault(id.rob_tag, Trap::IllegalInstruction(id.inst), ExceptionStage::Execute);
-            return (make_result(0, id.ctrl), true);
-        }
         trace_trap!(cpu.trace;
             event     = "return",
             pc        = %crate::trace::Hex(id.pc),
@@ -396,21 +412,8 @@ fn execute_system(
         return (make_result(0, id.ctrl), true);
     }
 
-    // WFI: deferred to commit (like MRET/SRET), but check privilege here.
+    // WFI: deferred to commit (like MRET/SRET).
     if id.ctrl.system_op == SystemOp::Wfi {
-        let tw = (cpu.csrs.mstatus >> 21) & 1;
-        if cpu.privilege == crate::core::arch::mode::PrivilegeMode::User
-            || (cpu.privilege == crate::core::arch::mode::PrivilegeMode::Supervisor && tw != 0)
-        {
-            trace_trap!(cpu.trace;
-                event   = "illegal",
-                pc      = %crate::trace::Hex(id.pc),
-                insn    = "WFI",
-                reason  = "U-mode or TW=1",
-                "EX: WFI -> IllegalInstruction"
-            );
-            rob.fault(id.rob_tag, Trap::IllegalInstruction(id.inst), ExceptionStage::Execute);
-        }
         return (make_result(0, id.ctrl), true);
     }
 
@@ -425,29 +428,6 @@ fn execute_system(
     // then flushes TLBs with proper ASID/vaddr granularity and triggers
     // a full pipeline squash.
     if id.ctrl.system_op == SystemOp::SfenceVma {
-        let tvm = (cpu.csrs.mstatus >> 20) & 1;
-        if cpu.privilege == crate::core::arch::mode::PrivilegeMode::Supervisor && tvm != 0 {
-            rob.fault(id.rob_tag, Trap::IllegalInstruction(id.inst), ExceptionStage::Execute);
-            return (
-                ExMem1Entry {
-                    rob_tag: id.rob_tag,
-                    pc: id.pc,
-                    inst: id.inst,
-                    inst_size: id.inst_size,
-                    rd: id.rd,
-                    alu: 0,
-                    store_data,
-                    ctrl: id.ctrl,
-                    trap: None,
-                    exception_stage: None,
-                    rd_phys: id.rd_phys,
-                    fp_flags: 0,
-                    sfence_vma: None,
-                },
-                true,
-            );
-        }
-
         return (
             ExMem1Entry {
                 rob_tag: id.rob_tag,
@@ -513,134 +493,6 @@ fn execute_csr(
     fwd_a: u64,
     store_data: u64,
 ) -> (ExMem1Entry, bool) {
-    // SATP access check in S-mode with TVM=1
-    if id.ctrl.csr_addr == crate::core::arch::csr::SATP
-        && cpu.privilege == crate::core::arch::mode::PrivilegeMode::Supervisor
-        && ((cpu.csrs.mstatus >> 20) & 1) != 0
-    {
-        rob.fault(id.rob_tag, Trap::IllegalInstruction(id.inst), ExceptionStage::Execute);
-        return (
-            ExMem1Entry {
-                rob_tag: id.rob_tag,
-                pc: id.pc,
-                inst: id.inst,
-                inst_size: id.inst_size,
-                rd: id.rd,
-                alu: 0,
-                store_data: 0,
-                ctrl: id.ctrl,
-                trap: None,
-                exception_stage: None,
-                rd_phys: id.rd_phys,
-                fp_flags: 0,
-                sfence_vma: None,
-            },
-            true,
-        );
-    }
-
-    // Counter-enable check for CYCLE/TIME/INSTRET (and their high-word aliases)
-    {
-        use crate::core::arch::csr as csr_addrs;
-        use crate::core::arch::mode::PrivilegeMode;
-        let counter_bit = if id.ctrl.csr_addr == csr_addrs::CYCLE {
-            Some(0) // CY bit
-        } else if id.ctrl.csr_addr == csr_addrs::TIME {
-            Some(1) // TM bit
-        } else if id.ctrl.csr_addr == csr_addrs::INSTRET {
-            Some(2) // IR bit
-        } else {
-            None
-        };
-        if let Some(bit) = counter_bit {
-            let mask = 1u64 << bit;
-            let denied = match cpu.privilege {
-                PrivilegeMode::Supervisor => (cpu.csrs.mcounteren & mask) == 0,
-                PrivilegeMode::User => {
-                    (cpu.csrs.mcounteren & mask) == 0 || (cpu.csrs.scounteren & mask) == 0
-                }
-                PrivilegeMode::Machine => false,
-            };
-            if denied {
-                rob.fault(id.rob_tag, Trap::IllegalInstruction(id.inst), ExceptionStage::Execute);
-                return (
-                    ExMem1Entry {
-                        rob_tag: id.rob_tag,
-                        pc: id.pc,
-                        inst: id.inst,
-                        inst_size: id.inst_size,
-                        rd: id.rd,
-                        alu: 0,
-                        store_data: 0,
-                        ctrl: id.ctrl,
-                        trap: None,
-                        exception_stage: None,
-                        rd_phys: id.rd_phys,
-                        fp_flags: 0,
-                        sfence_vma: None,
-                    },
-                    true,
-                );
-            }
-        }
-    }
-
-    // Privilege check
-    let csr_priv = id.ctrl.csr_addr.privilege_level() as u32;
-    if (cpu.privilege.to_u8() as u32) < csr_priv {
-        rob.fault(id.rob_tag, Trap::IllegalInstruction(id.inst), ExceptionStage::Execute);
-        return (
-            ExMem1Entry {
-                rob_tag: id.rob_tag,
-                pc: id.pc,
-                inst: id.inst,
-                inst_size: id.inst_size,
-                rd: id.rd,
-                alu: 0,
-                store_data: 0,
-                ctrl: id.ctrl,
-                trap: None,
-                exception_stage: None,
-                rd_phys: id.rd_phys,
-                fp_flags: 0,
-                sfence_vma: None,
-            },
-            true,
-        );
-    }
-
-    // Read-only check
-    let read_only = id.ctrl.csr_addr.is_read_only();
-    if read_only {
-        let would_write = match id.ctrl.csr_op {
-            CsrOp::Rw | CsrOp::Rwi => true,
-            CsrOp::Rs | CsrOp::Rc => !id.rs1.is_zero(),
-            CsrOp::Rsi | CsrOp::Rci => (id.rs1.as_u8() & 0x1f) != 0,
-            CsrOp::None => false,
-        };
-        if would_write {
-            rob.fault(id.rob_tag, Trap::IllegalInstruction(id.inst), ExceptionStage::Execute);
-            return (
-                ExMem1Entry {
-                    rob_tag: id.rob_tag,
-                    pc: id.pc,
-                    inst: id.inst,
-                    inst_size: id.inst_size,
-                    rd: id.rd,
-                    alu: 0,
-                    store_data: 0,
-                    ctrl: id.ctrl,
-                    trap: None,
-                    exception_stage: None,
-                    rd_phys: id.rd_phys,
-                    fp_flags: 0,
-                    sfence_vma: None,
-                },
-                true,
-            );
-        }
-    }
-
     // fp_flags are deferred to commit, but a CSR read of fflags/fcsr must
     // see the accumulated flags from all older (completed) instructions.
     // CSR instructions are serializing, so all older entries are complete.
@@ -654,11 +506,9 @@ fn execute_csr(
             cpu.csrs.fflags |= acc as u64;
         }
     }
-    let old = cpu.csr_read(id.ctrl.csr_addr);
-    let src = match id.ctrl.csr_op {
-        CsrOp::Rwi | CsrOp::Rsi | CsrOp::Rci => id.rs1.as_usize() as u64 & 0x1f,
-        _ => fwd_a,
-    };
+    // CSRRW/CSRRWI with rd=x0 must not read the CSR (spec §2.8).
+    let old = if id.ctrl.csr_op.reads_csr(id.rd) { cpu.csr_read(id.ctrl.csr_addr) } else { 0 };
+    let src = if id.ctrl.csr_op.is_imm() { id.rs1.as_usize() as u64 } else { fwd_a };
     let new = match id.ctrl.csr_op {
         CsrOp::Rw | CsrOp::Rwi => src,
         CsrOp::Rs | CsrOp::Rsi => old | src,
@@ -680,12 +530,7 @@ fn execute_csr(
     // Only generate a CSR write if the operation actually writes.
     // CSRRS/CSRRC with rs1=x0 and CSRRSI/CSRRCI with uimm=0 are
     // pure reads and must not trigger write side effects (spec §2.8).
-    let would_write = match id.ctrl.csr_op {
-        CsrOp::Rw | CsrOp::Rwi => true,
-        CsrOp::Rs | CsrOp::Rc => !id.rs1.is_zero(),
-        CsrOp::Rsi | CsrOp::Rci => (id.rs1.as_u8() & 0x1f) != 0,
-        CsrOp::None => false,
-    };
+    let would_write = id.ctrl.csr_op.writes_csr(id.rs1);
     if would_write {
         rob.set_csr_update(
             id.rob_tag,
@@ -700,8 +545,9 @@ fn execute_csr(
     //
     // Pure CSR reads (CSRRS/CSRRC with rs1=x0, CSRRSI/CSRRCI with uimm=0)
     // have no side effects. They are still serializing at issue time
-    // (all_before_completed gate in issue_queue.rs) which guarantees the
-    // read sees the latest committed CSR value, but they do NOT need to
+    // (all_before_completed and has_pending_csr_write_before gates in
+    // issue_queue.rs) which guarantees the read sees the latest CSR value,
+    // but they do NOT need to
     // flush younger instructions.
     let needs_flush = would_write;
     if needs_flush {
@@ -839,6 +685,7 @@ mod tests {
     use crate::config::Config;
     use crate::core::pipeline::signals::ControlSignals;
     use crate::core::units::bru::Ghr;
+    use crate::core::units::bru::ras::RasSnapshot;
     use crate::soc::builder::System;
 
     #[test]
@@ -887,7 +734,8 @@ mod tests {
             pred_taken: false,
             pred_target: 0,
             ghr_snapshot: Ghr::default(),
-            ras_snapshot: 0,
+            ras_snapshot: RasSnapshot::default(),
+            seq: 0,
         };
 
         let (result, flush) = execute_one(&mut cpu, issue, &mut rob);
@@ -942,7 +790,8 @@ mod tests {
             pred_taken: false,
             pred_target: 0,
             ghr_snapshot: Ghr::default(),
-            ras_snapshot: 0,
+            ras_snapshot: RasSnapshot::default(),
+            seq: 0,
         };
 
         let (_result, flush) = execute_one(&mut cpu, issue, &mut rob);
@@ -1000,7 +849,8 @@ mod tests {
             pred_taken: false,
             pred_target: 0,
             ghr_snapshot: Ghr::default(),
-            ras_snapshot: 0,
+            ras_snapshot: RasSnapshot::default(),
+            seq: 0,
         };
 
         let (_result, flush) = execute_one(&mut cpu, issue, &mut rob);
@@ -1062,7 +912,8 @@ mod tests {
             pred_taken: false,
             pred_target: 0,
             ghr_snapshot: Ghr::default(),
-            ras_snapshot: 0,
+            ras_snapshot: RasSnapshot::default(),
+            seq: 0,
         };
 
         let (_result, flush) = execute_one(&mut cpu, issue, &mut rob);
@@ -1123,7 +974,8 @@ mod tests {
             pred_taken: false,
             pred_target: 0, // Predicted NOT taken
             ghr_snapshot: Ghr::default(),
-            ras_snapshot: 0,
+            ras_snapshot: RasSnapshot::default(),
+            seq: 0,
         };
 
         let (_result, flush) = execute_one(&mut cpu, issue, &mut rob);
@@ -1182,7 +1034,8 @@ mod tests {
             pred_taken: true,
             pred_target: 0, // Predicted incorrectly
             ghr_snapshot: Ghr::default(),
-            ras_snapshot: 0,
+            ras_snapshot: RasSnapshot::default(),
+            seq: 0,
         };
 
         let (_result, flush) = execute_one(&mut cpu, issue, &mut rob);
@@ -1,13 +1,20 @@
-//! Functional Unit Pool for the O3 backend.
+//! Functional Unit Pool shared by the in-order and O3 backends.
 //!
 //! Models pipelined and non-pipelined execution units with configurable
 //! latencies. Structural hazards are enforced: an instruction cannot issue
 //! if all units of the required type are busy.
 //!
-//! Default latencies are Skylake-class values matching real hardware.
+//! Default latencies are Skylake-class values matching real hardware. The
+//! integer divider is iterative: its latency depends on the operands (see
+//! [`FuPool::latency_for`]), with the configured latency as the worst case.
+//!
+//! `pipeline.latencies` refines this per [`OpClass`]: each class may set its
+//! own latency and throughput (issue interval) on its unit, so `fdiv` and
+//! `fsqrt` can differ although they share the divide/sqrt unit.
 
 use crate::core::pipeline::signals::{AluOp, ControlFlow, ControlSignals};
 use serde::Deserialize;
+use std::collections::BTreeMap;
 
 /// Identifies which type of functional unit an instruction uses.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -37,6 +44,19 @@ pub enum FuType {
 pub const FU_TYPE_COUNT: usize = 9;
 
 impl FuType {
+    /// Every FU type, in `FuType as usize` order.
+    pub const ALL: [Self; FU_TYPE_COUNT] = [
+        Self::IntAlu,
+        Self::IntMul,
+        Self::IntDiv,
+        Self::FpAdd,
+        Self::FpMul,
+        Self::FpFma,
+        Self::FpDivSqrt,
+        Self::Branch,
+        Self::Mem,
+    ];
+
     /// Human-readable name for stats output.
     pub const fn name(self) -> &'static str {
         match self {
@@ -53,6 +73,99 @@ impl FuType {
     }
 
     /// Classify an instruction's FU type from its control signals.
+    pub fn classify(ctrl: &ControlSignals) -> Self {
+        OpClass::classify(ctrl).fu_type()
+    }
+}
+
+/// Operation class: the granularity at which per-op timing is configured.
+///
+/// Finer than [`FuType`]: divide and square root share a unit but usually
+/// differ in latency, as do FP adds, conversions and the simple FP ops.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[repr(u8)]
+pub enum OpClass {
+    /// Integer add, sub, logic, shift, compare, set-less-than.
+    IntAlu = 0,
+    /// Integer multiply: mul, mulh, mulhsu, mulhu.
+    IntMul = 1,
+    /// Integer divide and remainder.
+    IntDiv = 2,
+    /// FP add and subtract.
+    FpAdd = 3,
+    /// FP multiply.
+    FpMul = 4,
+    /// FP fused multiply-add.
+    FpFma = 5,
+    /// FP divide.
+    FpDiv = 6,
+    /// FP square root.
+    FpSqrt = 7,
+    /// FP conversions and moves between register files.
+    FpCvt = 8,
+    /// FP min/max, sign injection, compares and classify.
+    FpMisc = 9,
+    /// Conditional branches, jal, jalr.
+    Branch = 10,
+    /// Loads, stores and atomics (address calculation).
+    Mem = 11,
+}
+
+/// Number of distinct operation classes.
+pub const OP_CLASS_COUNT: usize = 12;
+
+impl OpClass {
+    /// Every operation class, in `OpClass as usize` order.
+    pub const ALL: [Self; OP_CLASS_COUNT] = [
+        Self::IntAlu,
+        Self::IntMul,
+        Self::IntDiv,
+        Self::FpAdd,
+        Self::FpMul,
+        Self::FpFma,
+        Self::FpDiv,
+        Self::FpSqrt,
+        Self::FpCvt,
+        Self::FpMisc,
+        Self::Branch,
+        Self::Mem,
+    ];
+
+    /// Name used as the key in `pipeline.latencies`.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::IntAlu => "int_alu",
+            Self::IntMul => "int_mul",
+            Self::IntDiv => "int_div",
+            Self::FpAdd => "fp_add",
+            Self::FpMul => "fp_mul",
+            Self::FpFma => "fp_fma",
+            Self::FpDiv => "fp_div",
+            Self::FpSqrt => "fp_sqrt",
+            Self::FpCvt => "fp_cvt",
+            Self::FpMisc => "fp_misc",
+            Self::Branch => "branch",
+            Self::Mem => "mem",
+        }
+    }
+
+    /// The functional unit that executes this class.
+    pub const fn fu_type(self) -> FuType {
+        match self {
+            Self::IntAlu => FuType::IntAlu,
+            Self::IntMul => FuType::IntMul,
+            Self::IntDiv => FuType::IntDiv,
+            Self::FpAdd | Self::FpCvt | Self::FpMisc => FuType::FpAdd,
+            Self::FpMul => FuType::FpMul,
+            Self::FpFma => FuType::FpFma,
+            Self::FpDiv | Self::FpSqrt => FuType::FpDivSqrt,
+            Self::Branch => FuType::Branch,
+            Self::Mem => FuType::Mem,
+        }
+    }
+
+    /// Classify an instruction's operation class from its control signals.
     pub fn classify(ctrl: &ControlSignals) -> Self {
         if ctrl.mem_read
             || ctrl.mem_write
@@ -66,21 +179,12 @@ impl FuType {
         match ctrl.alu {
             AluOp::Mul | AluOp::Mulh | AluOp::Mulhsu | AluOp::Mulhu => Self::IntMul,
             AluOp::Div | AluOp::Divu | AluOp::Rem | AluOp::Remu => Self::IntDiv,
+            AluOp::FAdd | AluOp::FSub => Self::FpAdd,
             AluOp::FMul => Self::FpMul,
-            AluOp::FDiv | AluOp::FSqrt => Self::FpDivSqrt,
+            AluOp::FDiv => Self::FpDiv,
+            AluOp::FSqrt => Self::FpSqrt,
             AluOp::FMAdd | AluOp::FMSub | AluOp::FNMAdd | AluOp::FNMSub => Self::FpFma,
-            AluOp::FAdd
-            | AluOp::FSub
-            | AluOp::FMin
-            | AluOp::FMax
-            | AluOp::FSgnJ
-            | AluOp::FSgnJN
-            | AluOp::FSgnJX
-            | AluOp::FEq
-            | AluOp::FLt
-            | AluOp::FLe
-            | AluOp::FClass
-            | AluOp::FCvtWS
+            AluOp::FCvtWS
             | AluOp::FCvtWUS
             | AluOp::FCvtLS
             | AluOp::FCvtLUS
@@ -91,12 +195,109 @@ impl FuType {
             | AluOp::FCvtSD
             | AluOp::FCvtDS
             | AluOp::FMvToX
-            | AluOp::FMvToF => Self::FpAdd,
+            | AluOp::FMvToF => Self::FpCvt,
+            AluOp::FMin
+            | AluOp::FMax
+            | AluOp::FSgnJ
+            | AluOp::FSgnJN
+            | AluOp::FSgnJX
+            | AluOp::FEq
+            | AluOp::FLt
+            | AluOp::FLe
+            | AluOp::FClass => Self::FpMisc,
             _ => Self::IntAlu,
         }
     }
 }
 
+/// Timing of one operation class: cycles from issue to result, and cycles
+/// before the unit accepts another instruction (1 = fully pipelined, equal
+/// to `latency` = not pipelined at all).
+///
+/// Deserializes from `{"latency": 13, "throughput": 4}` or `[13, 4]`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub struct OpTiming {
+    /// Cycles from issue until dependents may consume the result.
+    pub latency: u64,
+    /// Cycles the unit stays busy before it accepts the next instruction.
+    pub throughput: u64,
+}
+
+/// Per-class timing overrides (`pipeline.latencies`). A class left out takes
+/// its unit's latency from [`FuConfig`] and is pipelined unless the unit is
+/// a divider.
+pub type LatencyTable = BTreeMap<OpClass, OpTiming>;
+
+/// Class of issue port: the group of functional units a port feeds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum PortClass {
+    /// Integer ALU, multiplier and divider.
+    Alu = 0,
+    /// All floating-point units.
+    Fpu = 1,
+    /// Branch/jump unit.
+    Branch = 2,
+    /// Load/store address calculation.
+    Mem = 3,
+}
+
+/// Number of distinct issue port classes.
+pub const PORT_CLASS_COUNT: usize = 4;
+
+impl PortClass {
+    /// Every port class, in `PortClass as usize` order.
+    pub const ALL: [Self; PORT_CLASS_COUNT] = [Self::Alu, Self::Fpu, Self::Branch, Self::Mem];
+
+    /// Human-readable name for stats output.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Alu => "alu",
+            Self::Fpu => "fpu",
+            Self::Branch => "branch",
+            Self::Mem => "mem",
+        }
+    }
+
+    /// The port class that issues to units of `fu_type`.
+    pub const fn of(fu_type: FuType) -> Self {
+        match fu_type {
+            FuType::IntAlu | FuType::IntMul | FuType::IntDiv => Self::Alu,
+            FuType::FpAdd | FuType::FpMul | FuType::FpFma | FuType::FpDivSqrt => Self::Fpu,
+            FuType::Branch => Self::Branch,
+            FuType::Mem => Self::Mem,
+        }
+    }
+}
+
+/// Issue ports per class: how many instructions of each class may issue in
+/// one cycle. 0 leaves the class limited only by the pipeline width.
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct PortConfig {
+    /// Integer ALU ports.
+    pub alu: usize,
+    /// Floating-point ports.
+    pub fpu: usize,
+    /// Branch ports.
+    pub branch: usize,
+    /// Memory ports.
+    pub mem: usize,
+}
+
+impl PortConfig {
+    /// Returns the number of instructions of `class` that may issue per cycle.
+    pub const fn limit(&self, class: PortClass) -> usize {
+        let ports = match class {
+            PortClass::Alu => self.alu,
+            PortClass::Fpu => self.fpu,
+            PortClass::Branch => self.branch,
+            PortClass::Mem => self.mem,
+        };
+        if ports == 0 { usize::MAX } else { ports }
+    }
+}
+
 /// One instance of a functional unit.
 #[derive(Clone, Debug)]
 pub struct FuUnit {
@@ -121,13 +322,26 @@ impl FuUnit {
     /// Acquire the unit for one instruction issued at cycle `now`.
     /// Returns the cycle at which the result will be ready.
     pub const fn acquire(&mut self, now: u64) -> u64 {
-        let complete = now + self.latency;
-        self.busy_until = if self.is_pipelined {
-            now + 1 // pipelined: free next cycle
+        self.acquire_for(now, self.latency)
+    }
+
+    /// Acquire the unit for one instruction that takes `latency` cycles.
+    /// Returns the cycle at which the result will be ready.
+    pub const fn acquire_for(&mut self, now: u64, latency: u64) -> u64 {
+        let occupancy = if self.is_pipelined {
+            1 // pipelined: free next cycle
         } else {
-            complete // non-pipelined: blocked until done
+            latency // non-pipelined: blocked until done
         };
-        complete
+        self.occupy(now, latency, occupancy)
+    }
+
+    /// Acquire the unit for one instruction that takes `latency` cycles and
+    /// keeps the unit busy for `occupancy` of them.
+    /// Returns the cycle at which the result will be ready.
+    pub const fn occupy(&mut self, now: u64, latency: u64, occupancy: u64) -> u64 {
+        self.busy_until = now + occupancy;
+        now + latency
     }
 }
 
@@ -144,8 +358,10 @@ pub struct FuConfig {
     pub int_mul_latency: u64,
     /// Number of integer divider units.
     pub num_int_div: usize,
-    /// Latency of integer divide operations in cycles.
+    /// Worst-case latency of integer divide operations in cycles.
     pub int_div_latency: u64,
+    /// Quotient bits the divider retires per cycle (0 = fixed latency).
+    pub int_div_bits_per_cycle: u64,
     /// Number of floating-point adder units.
     pub num_fp_add: usize,
     /// Latency of floating-point add operations in cycles.
@@ -181,6 +397,7 @@ impl Default for FuConfig {
             int_mul_latency: 3,
             num_int_div: 1,
             int_div_latency: 35,
+            int_div_bits_per_cycle: 2,
             num_fp_add: 2,
             fp_add_latency: 4,
             num_fp_mul: 2,
@@ -201,11 +418,14 @@ impl Default for FuConfig {
 #[derive(Debug)]
 pub struct FuPool {
     units: Vec<FuUnit>,
+    int_div_bits_per_cycle: u64,
+    timings: [Option<OpTiming>; OP_CLASS_COUNT],
 }
 
 impl FuPool {
-    /// Create a new pool from the given config.
-    pub fn new(config: &FuConfig) -> Self {
+    /// Create a new pool from the given unit config and per-class timing
+    /// overrides.
+    pub fn new(config: &FuConfig, latencies: &LatencyTable) -> Self {
         let mut units = Vec::new();
 
         let add = |units: &mut Vec<FuUnit>, fu_type, count, latency, pipelined| {
@@ -230,7 +450,15 @@ impl FuPool {
         add(&mut units, FuType::Branch, config.num_branch, config.branch_latency, true);
         add(&mut units, FuType::Mem, config.num_mem, config.mem_latency, true);
 
-        Self { units }
+        let mut timings = [None; OP_CLASS_COUNT];
+        for (&op, &timing) in latencies {
+            timings[op as usize] = Some(OpTiming {
+                latency: timing.latency.max(1),
+                throughput: timing.throughput.max(1),
+            });
+        }
+
+        Self { units, int_div_bits_per_cycle: config.int_div_bits_per_cycle, timings }
     }
 
     /// Returns true if at least one unit of `fu_type` is free at cycle `now`.
@@ -253,11 +481,81 @@ impl FuPool {
         panic!("acquire called with no free unit of type {fu_type:?}");
     }
 
+    /// Acquire a free unit for an instruction of class `op` issued at cycle
+    /// `now` that takes `latency` cycles (see [`Self::latency_for`]). The unit
+    /// stays busy for [`Self::occupancy`] cycles.
+    /// Returns the cycle at which the result is ready.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no unit for `op` is free (caller must call `has_free` first).
+    pub fn acquire_for(&mut self, op: OpClass, now: u64, latency: u64) -> u64 {
+        let fu_type = op.fu_type();
+        let occupancy = self.occupancy(op, latency);
+        for unit in &mut self.units {
+            if unit.fu_type == fu_type && unit.is_free(now) {
+                return unit.occupy(now, latency, occupancy);
+            }
+        }
+        panic!("acquire called with no free unit of type {fu_type:?}");
+    }
+
+    /// Returns the timing of class `op`: its entry in `pipeline.latencies`,
+    /// or else its unit's latency, fully pipelined unless the unit is not.
+    pub fn timing(&self, op: OpClass) -> OpTiming {
+        self.timings[op as usize].unwrap_or_else(|| {
+            let fu_type = op.fu_type();
+            let latency = self.get_latency(fu_type);
+            OpTiming { latency, throughput: if self.is_pipelined(fu_type) { 1 } else { latency } }
+        })
+    }
+
+    /// Returns the latency of one instruction of class `op`, given its
+    /// control signals and source operands `a` and `b`.
+    ///
+    /// Every class has a fixed latency except integer divide, which retires
+    /// `int_div_bits_per_cycle` quotient bits per cycle after a fixed setup.
+    /// The quotient width is the difference in significant bits between
+    /// dividend and divisor, so a dividend smaller than the divisor (or a zero
+    /// divisor) exits after setup alone. A full 64-bit quotient takes exactly
+    /// the class's configured latency.
+    pub fn latency_for(&self, op: OpClass, ctrl: &ControlSignals, a: u64, b: u64) -> u64 {
+        let max = self.timing(op).latency;
+        let per_cycle = self.int_div_bits_per_cycle;
+        if op != OpClass::IntDiv || per_cycle == 0 {
+            return max;
+        }
+        let signed = matches!(ctrl.alu, AluOp::Div | AluOp::Rem);
+        let (a, b) = if ctrl.is_rv32 {
+            let (a, b) = (a as u32, b as u32);
+            if signed {
+                (u64::from((a as i32).unsigned_abs()), u64::from((b as i32).unsigned_abs()))
+            } else {
+                (u64::from(a), u64::from(b))
+            }
+        } else if signed {
+            ((a as i64).unsigned_abs(), (b as i64).unsigned_abs())
+        } else {
+            (a, b)
+        };
+        let setup = max.saturating_sub(64_u64.div_ceil(per_cycle)).max(1);
+        let quotient_bits =
+            if b == 0 || a < b { 0 } else { u64::from(b.leading_zeros() - a.leading_zeros() + 1) };
+        (setup + quotient_bits.div_ceil(per_cycle)).min(max)
+    }
+
     /// Returns the latency of the first unit of `fu_type`.
     pub fn get_latency(&self, fu_type: FuType) -> u64 {
         self.units.iter().find(|u| u.fu_type == fu_type).map_or(1, |u| u.latency)
     }
 
+    /// Returns how many cycles a unit stays unavailable after accepting one
+    /// instruction of class `op` that takes `latency` cycles: the class's
+    /// throughput, but never longer than the instruction itself runs.
+    pub fn occupancy(&self, op: OpClass, latency: u64) -> u64 {
+        self.timing(op).throughput.min(latency).max(1)
+    }
+
     /// Returns whether the first unit of `fu_type` is pipelined.
     pub fn is_pipelined(&self, fu_type: FuType) -> bool {
         self.units.iter().find(|u| u.fu_type == fu_type).is_none_or(|u| u.is_pipelined)
@@ -265,12 +563,12 @@ impl FuPool {
 }
 
 #[cfg(test)]
-#[allow(unused_results)]
+#[allow(clippy::unwrap_used, unused_results)]
 mod tests {
     use super::*;
 
     fn default_pool() -> FuPool {
-        FuPool::new(&FuConfig::default())
+        FuPool::new(&FuConfig::default(), &LatencyTable::new())
     }
 
     #[test]
@@ -308,6 +606,79 @@ mod tests {
         assert!(!pool.has_free(FuType::FpDivSqrt, 0));
     }
 
+    #[test]
+    fn test_occupancy_pipelined_vs_not() {
+        let pool = default_pool();
+        assert_eq!(pool.occupancy(OpClass::IntMul, 3), 1);
+        assert_eq!(pool.occupancy(OpClass::IntDiv, 12), 12);
+    }
+
+    #[test]
+    fn test_div_latency_depends_on_quotient_bits() {
+        let pool = default_pool();
+        let div = ControlSignals { alu: AluOp::Div, ..Default::default() };
+        let divu = ControlSignals { alu: AluOp::Divu, ..Default::default() };
+        let divuw = ControlSignals { alu: AluOp::Divu, is_rv32: true, ..Default::default() };
+        // Setup is 35 - 64/2 = 3 cycles; quotient bits retire two per cycle.
+        assert_eq!(pool.latency_for(OpClass::IntDiv, &div, 3, 7), 3, "early out: a < b");
+        assert_eq!(pool.latency_for(OpClass::IntDiv, &div, 3, 0), 3, "early out: b == 0");
+        assert_eq!(pool.latency_for(OpClass::IntDiv, &div, 100, 5), 6, "5 quotient bits");
+        assert_eq!(pool.latency_for(OpClass::IntDiv, &div, -100_i64 as u64, 5), 6, "signed");
+        assert_eq!(pool.latency_for(OpClass::IntDiv, &divu, u64::MAX, 1), 35, "worst case");
+        assert_eq!(pool.latency_for(OpClass::IntDiv, &divuw, u64::MAX, 1), 19, "32-bit quotient");
+        assert_eq!(pool.latency_for(OpClass::IntMul, &div, u64::MAX, 1), 3, "fixed latency");
+    }
+
+    #[test]
+    fn test_div_latency_fixed_when_bits_per_cycle_zero() {
+        let pool = FuPool::new(
+            &FuConfig { int_div_bits_per_cycle: 0, ..FuConfig::default() },
+            &LatencyTable::new(),
+        );
+        let div = ControlSignals { alu: AluOp::Div, ..Default::default() };
+        assert_eq!(pool.latency_for(OpClass::IntDiv, &div, 3, 7), 35);
+    }
+
+    #[test]
+    fn test_latency_table_overrides_unit_timing() {
+        let latencies = LatencyTable::from([
+            (OpClass::FpDiv, OpTiming { latency: 13, throughput: 4 }),
+            (OpClass::FpMisc, OpTiming { latency: 1, throughput: 1 }),
+        ]);
+        let mut pool = FuPool::new(&FuConfig::default(), &latencies);
+        let fdiv = ControlSignals { alu: AluOp::FDiv, ..Default::default() };
+        assert_eq!(pool.latency_for(OpClass::FpDiv, &fdiv, 0, 0), 13);
+        assert_eq!(pool.timing(OpClass::FpSqrt), OpTiming { latency: 21, throughput: 21 });
+        assert_eq!(pool.timing(OpClass::FpMisc), OpTiming { latency: 1, throughput: 1 });
+        assert_eq!(pool.timing(OpClass::FpAdd), OpTiming { latency: 4, throughput: 1 });
+
+        // The divider accepts the next divide after the throughput interval.
+        assert_eq!(pool.acquire_for(OpClass::FpDiv, 0, 13), 13);
+        assert!(!pool.has_free(FuType::FpDivSqrt, 3));
+        assert!(pool.has_free(FuType::FpDivSqrt, 4));
+    }
+
+    #[test]
+    fn test_latency_table_deserializes_pairs_and_objects() {
+        let table: LatencyTable = serde_json::from_str(
+            r#"{"fp_div": [13, 4], "fp_sqrt": {"latency": 17, "throughput": 6}}"#,
+        )
+        .unwrap();
+        assert_eq!(table[&OpClass::FpDiv], OpTiming { latency: 13, throughput: 4 });
+        assert_eq!(table[&OpClass::FpSqrt], OpTiming { latency: 17, throughput: 6 });
+        assert!(serde_json::from_str::<LatencyTable>(r#"{"fdiv": [1, 1]}"#).is_err());
+    }
+
+    #[test]
+    fn test_all_matches_discriminants() {
+        for (i, fu) in FuType::ALL.iter().enumerate() {
+            assert_eq!(*fu as usize, i);
+        }
+        for (i, op) in OpClass::ALL.iter().enumerate() {
+            assert_eq!(*op as usize, i);
+        }
+    }
+
     #[test]
     fn test_classify_int_alu() {
         let ctrl = ControlSignals { alu: AluOp::Add, ..Default::default() };
@@ -326,6 +697,18 @@ mod tests {
         assert_eq!(FuType::classify(&ctrl), FuType::FpFma);
     }
 
+    #[test]
+    fn test_classify_splits_fp_div_and_sqrt() {
+        let fdiv = ControlSignals { alu: AluOp::FDiv, ..Default::default() };
+        let fsqrt = ControlSignals { alu: AluOp::FSqrt, ..Default::default() };
+        let fcvt = ControlSignals { alu: AluOp::FCvtDS, ..Default::default() };
+        assert_eq!(OpClass::classify(&fdiv), OpClass::FpDiv);
+        assert_eq!(OpClass::classify(&fsqrt), OpClass::FpSqrt);
+        assert_eq!(OpClass::classify(&fcvt), OpClass::FpCvt);
+        assert_eq!(FuType::classify(&fsqrt), FuType::FpDivSqrt);
+        assert_eq!(FuType::classify(&fcvt), FuType::FpAdd);
+    }
+
     #[test]
     fn test_classify_branch() {
         let ctrl = ControlSignals { control_flow: ControlFlow::Branch, ..Default::default() };
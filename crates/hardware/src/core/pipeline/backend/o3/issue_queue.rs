@@ -10,11 +10,15 @@
 
 use crate::common::RegIdx;
 use crate::core::Cpu;
+use crate::core::pipeline::backend::o3::fu_pool::{
+    FuType, PORT_CLASS_COUNT, PortClass, PortConfig,
+};
 use crate::core::pipeline::latches::RenameIssueEntry;
 use crate::core::pipeline::prf::{PhysReg, PhysRegFile};
 use crate::core::pipeline::rob::{Rob, RobState, RobTag};
 use crate::core::pipeline::signals::SystemOp;
 use crate::core::pipeline::store_buffer::StoreBuffer;
+use crate::core::units::lsu::ordering::Fence;
 use crate::core::units::mdp::MemDepState;
 
 /// Readiness state of a single source operand.
@@ -266,10 +270,14 @@ impl IssueQueue {
     /// dispatch time, rather than re-querying the predictor every cycle.
     ///
     /// System/CSR instructions are serializing: they must not issue until all
-    /// older ROB entries have completed.
+    /// older ROB entries have completed and older CSR writes have committed.
     ///
     /// Memory port limits: at most `load_ports` loads and `store_ports` stores
-    /// are issued per cycle, modeling finite LSU bandwidth.
+    /// are issued per cycle, modeling finite LSU bandwidth. `ports` caps the
+    /// instructions issued per functional unit class; an entry whose class is
+    /// saturated stays in the IQ (counted in `stalls_issue_port`) while
+    /// younger entries of other classes may still issue.
+    #[allow(clippy::too_many_arguments)]
     pub fn select(
         &mut self,
         width: usize,
@@ -277,7 +285,9 @@ impl IssueQueue {
         rob: &Rob,
         load_ports: usize,
         store_ports: usize,
+        ports: PortConfig,
         prf: Option<&PhysRegFile>,
+        cpu: &mut Cpu,
     ) -> Vec<SelectedEntry> {
         // Collect indices of all ready entries
         let mut ready_indices: Vec<usize> = Vec::new();
@@ -310,26 +320,31 @@ impl IssueQueue {
                         continue;
                     }
                     // System/CSR instructions (excluding FENCE) are serializing:
-                    // wait for all older instructions to complete before issuing.
+                    // wait for all older instructions to complete, and for any
+                    // older CSR write to commit, before issuing.
                     // FENCE is excluded here because it has its own granular check
                     // below that only waits for operations matching its pred bits,
                     // rather than draining the entire pipeline.
                     if iq.entry.ctrl.system_op != SystemOp::None
                         && iq.entry.ctrl.system_op != SystemOp::Fence
-                        && !rob.all_before_completed(iq.entry.rob_tag)
+                        && (!rob.all_before_completed(iq.entry.rob_tag)
+                            || rob.has_pending_csr_write_before(iq.entry.rob_tag))
                     {
                         continue;
                     }
                     // FENCE: wait for older operations matching pred bits to complete.
                     if iq.entry.ctrl.system_op == SystemOp::Fence {
-                        let pred_bits = ((iq.entry.inst >> 24) & 0xF) as u8;
-                        let pred_r = pred_bits & 0b0010 != 0;
-                        let pred_w = pred_bits & 0b0001 != 0;
-                        if !rob.fence_pred_satisfied(iq.entry.rob_tag, pred_r, pred_w) {
+                        let pred = Fence::decode(iq.entry.inst).pred;
+                        if !rob.fence_pred_satisfied(iq.entry.rob_tag, pred.r, pred.w) {
                             continue;
                         }
                     }
-                    // Loads/stores: blocked by older in-flight FENCE with matching succ bits.
+                    // Release atomic: wait for all older loads and stores to complete.
+                    if iq.entry.ctrl.rl && !rob.fence_pred_satisfied(iq.entry.rob_tag, true, true) {
+                        continue;
+                    }
+                    // Loads/stores: blocked by older in-flight FENCE with
+                    // matching succ bits, or by an older acquire atomic.
                     if (iq.entry.ctrl.mem_read || iq.entry.ctrl.mem_write)
                         && rob.has_fence_blocking(
                             iq.entry.rob_tag,
@@ -351,6 +366,7 @@ impl IssueQueue {
         let mut result: Vec<SelectedEntry> = Vec::with_capacity(width);
         let mut loads_issued = 0usize;
         let mut stores_issued = 0usize;
+        let mut class_issued = [0usize; PORT_CLASS_COUNT];
         for &idx in &ready_indices {
             if result.len() >= width {
                 break;
@@ -365,6 +381,12 @@ impl IssueQueue {
             if is_store && stores_issued >= store_ports {
                 continue;
             }
+            let port = PortClass::of(FuType::classify(ctrl));
+            let needs_port = slot.entry.trap.is_none();
+            if needs_port && class_issued[port as usize] >= ports.limit(port) {
+                cpu.stats.stalls_issue_port += 1;
+                continue;
+            }
 
             let Some(iq) = self.slots[idx].take() else { continue };
             self.count -= 1;
@@ -374,6 +396,9 @@ impl IssueQueue {
             if is_store {
                 stores_issued += 1;
             }
+            if needs_port {
+                class_issued[port as usize] += 1;
+            }
 
             let mem_dep = iq.mem_dep;
             let mut entry = iq.entry;
@@ -563,7 +588,7 @@ mod tests {
     use crate::core::pipeline::latches::RenameIssueEntry;
     use crate::core::pipeline::prf::PhysReg;
     use crate::core::pipeline::rob::RobTag;
-    use crate::core::pipeline::signals::ControlSignals;
+    use crate::core::pipeline::signals::{AluOp, ControlSignals};
 
     fn make_entry(rob_tag: u32) -> RenameIssueEntry {
         RenameIssueEntry {
@@ -592,10 +617,16 @@ mod tests {
             pred_taken: false,
             pred_target: 0,
             ghr_snapshot: crate::core::units::bru::Ghr::default(),
-            ras_snapshot: 0,
+            ras_snapshot: crate::core::units::bru::ras::RasSnapshot::default(),
+            seq: 0,
         }
     }
 
+    fn test_cpu() -> Cpu {
+        let config = crate::config::Config::default();
+        Cpu::new(crate::soc::builder::System::new(&config, ""), &config)
+    }
+
     fn ready_operand(value: u64) -> OperandState {
         OperandState::ready(PhysReg(0), None, value)
     }
@@ -617,6 +648,7 @@ mod tests {
 
     #[test]
     fn test_dispatch_and_select_ready() {
+        let mut cpu = test_cpu();
         let mut iq = IssueQueue::new(16);
 
         // Manually insert a ready entry
@@ -629,8 +661,16 @@ mod tests {
         });
         iq.count = 1;
 
-        let selected =
-            iq.select(4, &StoreBuffer::new(16), &Rob::new(64), usize::MAX, usize::MAX, None);
+        let selected = iq.select(
+            4,
+            &StoreBuffer::new(16),
+            &Rob::new(64),
+            usize::MAX,
+            usize::MAX,
+            PortConfig::default(),
+            None,
+            &mut cpu,
+        );
         assert_eq!(selected.len(), 1);
         assert_eq!(selected[0].entry.rob_tag.0, 1);
         assert_eq!(selected[0].entry.rv1, 42);
@@ -640,6 +680,7 @@ mod tests {
 
     #[test]
     fn test_wakeup_phys_chain() {
+        let mut cpu = test_cpu();
         let mut iq = IssueQueue::new(16);
         let p5 = PhysReg(5);
 
@@ -655,22 +696,39 @@ mod tests {
         iq.count = 1;
 
         // Not ready yet
-        let selected =
-            iq.select(4, &StoreBuffer::new(16), &Rob::new(64), usize::MAX, usize::MAX, None);
+        let selected = iq.select(
+            4,
+            &StoreBuffer::new(16),
+            &Rob::new(64),
+            usize::MAX,
+            usize::MAX,
+            PortConfig::default(),
+            None,
+            &mut cpu,
+        );
         assert_eq!(selected.len(), 0);
 
         // Wakeup with phys reg 5
         iq.wakeup_phys(p5, 999);
 
         // Now should be selectable
-        let selected =
-            iq.select(4, &StoreBuffer::new(16), &Rob::new(64), usize::MAX, usize::MAX, None);
+        let selected = iq.select(
+            4,
+            &StoreBuffer::new(16),
+            &Rob::new(64),
+            usize::MAX,
+            usize::MAX,
+            PortConfig::default(),
+            None,
+            &mut cpu,
+        );
         assert_eq!(selected.len(), 1);
         assert_eq!(selected[0].entry.rv1, 999);
     }
 
     #[test]
     fn test_wakeup_legacy_chain() {
+        let mut cpu = test_cpu();
         let mut iq = IssueQueue::new(16);
 
         // Entry depends on tag 5
@@ -687,14 +745,23 @@ mod tests {
         // Wakeup with tag 5
         iq.wakeup(RobTag(5), 999);
 
-        let selected =
-            iq.select(4, &StoreBuffer::new(16), &Rob::new(64), usize::MAX, usize::MAX, None);
+        let selected = iq.select(
+            4,
+            &StoreBuffer::new(16),
+            &Rob::new(64),
+            usize::MAX,
+            usize::MAX,
+            PortConfig::default(),
+            None,
+            &mut cpu,
+        );
         assert_eq!(selected.len(), 1);
         assert_eq!(selected[0].entry.rv1, 999);
     }
 
     #[test]
     fn test_oldest_first_select() {
+        let mut cpu = test_cpu();
         let mut iq = IssueQueue::new(16);
 
         // Insert entries with tags 3, 1, 2 in random slot order
@@ -710,16 +777,32 @@ mod tests {
         iq.count = 3;
 
         // Select width=2 should get tags 1 and 2 (oldest first)
-        let selected =
-            iq.select(2, &StoreBuffer::new(16), &Rob::new(64), usize::MAX, usize::MAX, None);
+        let selected = iq.select(
+            2,
+            &StoreBuffer::new(16),
+            &Rob::new(64),
+            usize::MAX,
+            usize::MAX,
+            PortConfig::default(),
+            None,
+            &mut cpu,
+        );
         assert_eq!(selected.len(), 2);
         assert_eq!(selected[0].entry.rob_tag.0, 1);
         assert_eq!(selected[1].entry.rob_tag.0, 2);
         assert_eq!(iq.len(), 1);
 
         // Remaining is tag 3
-        let selected =
-            iq.select(4, &StoreBuffer::new(16), &Rob::new(64), usize::MAX, usize::MAX, None);
+        let selected = iq.select(
+            4,
+            &StoreBuffer::new(16),
+            &Rob::new(64),
+            usize::MAX,
+            usize::MAX,
+            PortConfig::default(),
+            None,
+            &mut cpu,
+        );
         assert_eq!(selected.len(), 1);
         assert_eq!(selected[0].entry.rob_tag.0, 3);
     }
@@ -796,6 +879,7 @@ mod tests {
 
     #[test]
     fn test_port_limits() {
+        let mut cpu = test_cpu();
         let mut iq = IssueQueue::new(16);
 
         // Insert 3 loads (tags 1, 2, 3) and 2 stores (tags 4, 5), all ready
@@ -820,7 +904,16 @@ mod tests {
         iq.count = 5;
 
         // With load_ports=2, store_ports=1, width=4: should get 2 loads + 1 store = 3
-        let selected = iq.select(4, &StoreBuffer::new(16), &Rob::new(64), 2, 1, None);
+        let selected = iq.select(
+            4,
+            &StoreBuffer::new(16),
+            &Rob::new(64),
+            2,
+            1,
+            PortConfig::default(),
+            None,
+            &mut cpu,
+        );
         assert_eq!(selected.len(), 3);
         // Oldest first: tags 1 (load), 2 (load), 4 (store)
         assert_eq!(selected[0].entry.rob_tag.0, 1);
@@ -834,10 +927,54 @@ mod tests {
         assert_eq!(iq.len(), 2);
 
         // Next cycle: should get remaining load + store
-        let selected = iq.select(4, &StoreBuffer::new(16), &Rob::new(64), 2, 1, None);
+        let selected = iq.select(
+            4,
+            &StoreBuffer::new(16),
+            &Rob::new(64),
+            2,
+            1,
+            PortConfig::default(),
+            None,
+            &mut cpu,
+        );
         assert_eq!(selected.len(), 2);
         assert_eq!(selected[0].entry.rob_tag.0, 3);
         assert_eq!(selected[1].entry.rob_tag.0, 5);
         assert!(iq.is_empty());
     }
+
+    #[test]
+    fn test_fpu_port_limit() {
+        let mut cpu = test_cpu();
+        let mut iq = IssueQueue::new(16);
+
+        // Two ready FP adds (tags 1, 2) and one integer op (tag 3).
+        for (slot, tag, alu) in [(0, 1u32, AluOp::FAdd), (1, 2, AluOp::FAdd), (2, 3, AluOp::Add)] {
+            let mut entry = make_entry(tag);
+            entry.ctrl.alu = alu;
+            iq.slots[slot] = Some(IssueQueueEntry {
+                entry,
+                src1: ready_operand(0),
+                src2: ready_operand(0),
+                src3: ready_operand(0),
+                mem_dep: MemDepState::None,
+            });
+        }
+        iq.count = 3;
+
+        let ports = PortConfig { fpu: 1, ..PortConfig::default() };
+        let sb = StoreBuffer::new(16);
+        let rob = Rob::new(64);
+
+        // One FP op per cycle; the integer op issues past the blocked one.
+        let selected = iq.select(4, &sb, &rob, usize::MAX, usize::MAX, ports, None, &mut cpu);
+        let tags: Vec<u32> = selected.iter().map(|s| s.entry.rob_tag.0).collect();
+        assert_eq!(tags, vec![1, 3]);
+        assert_eq!(cpu.stats.stalls_issue_port, 1);
+
+        let selected = iq.select(4, &sb, &rob, usize::MAX, usize::MAX, ports, None, &mut cpu);
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].entry.rob_tag.0, 2);
+        assert!(iq.is_empty());
+    }
 }
@@ -17,16 +17,17 @@ use crate::core::pipeline::engine::ExecutionEngine;
 use crate::core::pipeline::free_list::FreeList;
 use crate::core::pipeline::latches::{ExMem1Entry, Mem1Mem2Entry, Mem2WbEntry, RenameIssueEntry};
 use crate::core::pipeline::load_queue::LoadQueue;
+use crate::core::pipeline::pipe_trace::PipeStage;
 use crate::core::pipeline::prf::PhysRegFile;
 use crate::core::pipeline::rename_map::RenameMap;
 use crate::core::pipeline::rob::Rob;
 use crate::core::pipeline::scoreboard::Scoreboard;
 use crate::core::pipeline::signals::ControlFlow;
-use crate::core::pipeline::store_buffer::StoreBuffer;
+use crate::core::pipeline::store_buffer::{StoreBuffer, width_to_bytes};
 use crate::core::units::bru::BranchPredictor;
 use crate::core::units::mdp::MemDepUnit;
 
-use self::fu_pool::{FuPool, FuType};
+use self::fu_pool::{FuPool, FuType, OpClass, PortClass, PortConfig};
 use self::issue_queue::IssueQueue;
 
 /// A result that has been computed but not yet written back (pending due to latency).
@@ -73,6 +74,8 @@ pub struct O3Engine {
     pub load_ports: usize,
     /// Maximum stores issued per cycle.
     pub store_ports: usize,
+    /// Issue ports per functional unit class.
+    pub ports: PortConfig,
     /// Execute -> Memory1 latch.
     pub execute_mem1: Vec<ExMem1Entry>,
     /// Memory1 -> Memory2 latch.
@@ -115,7 +118,7 @@ impl O3Engine {
         let mut prf = PhysRegFile::new(prf_total);
         prf.mark_arch_ready(num_arch);
 
-        let fu_pool = FuPool::new(&config.pipeline.fu_config);
+        let fu_pool = FuPool::new(&config.pipeline.fu_config, &config.pipeline.latencies);
 
         Self {
             rob: Rob::new(rob_size),
@@ -132,6 +135,7 @@ impl O3Engine {
             width: config.pipeline.width,
             load_ports: config.pipeline.load_ports,
             store_ports: config.pipeline.store_ports,
+            ports: config.pipeline.ports,
             execute_mem1: Vec::with_capacity(config.pipeline.width),
             mem1_mem2: Vec::with_capacity(config.pipeline.width),
             mem2_wb: Vec::with_capacity(config.pipeline.width),
@@ -142,29 +146,37 @@ impl O3Engine {
         }
     }
 
-    /// Copy initial architectural register values into the identity-mapped PRF slots.
+    /// Copy the architectural register values into the committed PRF slots.
     ///
     /// Must be called after the CPU's architectural register file has been initialized
-    /// (e.g., with the stack pointer) but before the first pipeline tick. The rename
-    /// map identity-maps arch reg `i` → PhysReg(i) for GPRs and arch reg `i` →
-    /// PhysReg(32 + i) for FPRs, so we write the CPU's register values into those slots.
+    /// (e.g., with the stack pointer) but before the first pipeline tick, and again
+    /// whenever `cpu.regs` was changed outside the pipeline with nothing in flight
+    /// (reset, checkpoint restore, functional execution). Before the first tick the
+    /// committed rename map is the identity: arch reg `i` → PhysReg(i) for GPRs and
+    /// arch reg `i` → PhysReg(32 + i) for FPRs.
     pub fn sync_arch_regs(&mut self, cpu: &crate::core::Cpu) {
         use crate::common::RegIdx;
-        use crate::core::pipeline::prf::PhysReg;
-        // GPRs: arch reg i → PhysReg(i), skip x0 (hardwired zero)
+        // x0 is hardwired zero.
         for i in 1u8..32 {
-            let val = cpu.regs.read(RegIdx::new(i));
-            if val != 0 {
-                self.prf.write(PhysReg(i as u16), val);
-            }
+            let reg = RegIdx::new(i);
+            self.prf.write(self.committed_rename_map.get(reg, false), cpu.regs.read(reg));
         }
-        // FPRs: arch reg i → PhysReg(32 + i)
         for i in 0u8..32 {
-            let val = cpu.regs.read_f(RegIdx::new(i));
-            if val != 0 {
-                self.prf.write(PhysReg((32 + i) as u16), val);
-            }
+            let reg = RegIdx::new(i);
+            self.prf.write(self.committed_rename_map.get(reg, true), cpu.regs.read_f(reg));
+        }
+    }
+
+    /// Overwrite the committed value of one architectural register.
+    ///
+    /// Writes the physical register the committed rename map currently points
+    /// at, so a harness can poke state between instructions after the pipeline
+    /// has started renaming. Writes to x0 are ignored.
+    pub fn write_arch_reg(&mut self, reg: crate::common::RegIdx, is_fp: bool, val: u64) {
+        if !is_fp && reg.is_zero() {
+            return;
         }
+        self.prf.write(self.committed_rename_map.get(reg, is_fp), val);
     }
 
     /// Compute the squash stall penalty in cycles.
@@ -244,6 +256,9 @@ impl ExecutionEngine for O3Engine {
             self.squash_stall_remaining = self.compute_squash_stall(squashed, 0);
             cpu.redirect_pending = true;
             cpu.trap(&trap, pc);
+            if let Some((reg, val)) = cpu.trap_reg_write.take() {
+                self.write_arch_reg(reg, false, val);
+            }
             cpu.committed_next_pc = cpu.pc;
             return;
         }
@@ -288,23 +303,11 @@ impl ExecutionEngine for O3Engine {
         // Drain completed MSHRs: install cache lines in L1D and resume
         // parked loads/atomics into the mem1→mem2 latch.
         if cpu.l1d_mshrs.capacity() > 0 {
+            cpu.resolve_dram_requests();
             let completed = cpu.l1d_mshrs.drain_completions(now);
             for mshr_entry in completed {
                 // Install the fetched line into L1D (with eviction tracking)
-                let (_penalty, evicted) = cpu.l1_d_cache.install_line_public_tracked(
-                    mshr_entry.line_addr,
-                    mshr_entry.is_write,
-                    0, // write-back penalty already accounted for in miss latency
-                );
-
-                // Exclusive policy: L1D eviction → install evicted line into L2
-                if cpu.inclusion_policy == crate::config::InclusionPolicy::Exclusive
-                    && cpu.l2_cache.enabled
-                    && let Some(ev) = evicted
-                {
-                    let _ = cpu.l2_cache.install_or_replace(ev.addr, ev.dirty, 0);
-                    cpu.stats.exclusive_l1_to_l2_swaps += 1;
-                }
+                cpu.install_l1d_fill(mshr_entry.line_addr, mshr_entry.is_write);
 
                 // Resume parked loads/atomics
                 for waiter in mshr_entry.waiters {
@@ -328,12 +331,21 @@ impl ExecutionEngine for O3Engine {
             Some(&mut self.load_queue),
         );
 
-        // Notify MDP when stores resolve — wake instructions waiting on them.
+        // Notify MDP when stores resolve — wake instructions waiting on them —
+        // and when loads complete, so `WaitFor` predictions can be scored.
         for entry in &self.mem2_wb[wb_before..] {
-            if entry.ctrl.mem_write
-                && let Some(store_tag) = self.mdp.store_resolved(entry.rob_tag)
+            let size = width_to_bytes(entry.ctrl.width) as u64;
+            if entry.ctrl.mem_write {
+                let start = self.store_buffer.find_paddr(entry.rob_tag).map_or(0, |p| p.val());
+                if let Some(store_tag) =
+                    self.mdp.store_resolved(entry.rob_tag, (start, start + size))
+                {
+                    self.issue_queue.wakeup_mem_dep(&[store_tag]);
+                }
+            } else if entry.ctrl.mem_read
+                && let Some(paddr) = self.load_queue.find(entry.rob_tag).and_then(|e| e.paddr)
             {
-                self.issue_queue.wakeup_mem_dep(&[store_tag]);
+                self.mdp.load_executed(entry.rob_tag, (paddr.val(), paddr.val() + size));
             }
         }
 
@@ -413,6 +425,7 @@ impl ExecutionEngine for O3Engine {
 
             self.rebuild_rename_map();
             self.scoreboard.rebuild_from_rob(&self.rob);
+            cpu.stats.recovery_walk_entries += self.rob.len() as u64;
             // Flush stale checkpoints (memory violations use rebuild_rename_map
             // since the violating load is not a branch and has no checkpoint).
             if let Some(keep_tag) = keep_tag {
@@ -488,7 +501,7 @@ impl ExecutionEngine for O3Engine {
                         // Memory ops: push to memory pipeline
                         self.execute_mem1.push(entry);
                     } else if !pr.speculative_written {
-                        // Non-memory, non-pipelined (e.g. IntDiv, FpDivSqrt, system): write PRF + wakeup now
+                        // Non-memory, multi-cycle (e.g. IntMul, IntDiv, FpDivSqrt, system): write PRF + wakeup now
                         let val = if entry.ctrl.control_flow == ControlFlow::Jump {
                             entry.pc.wrapping_add(entry.inst_size.as_u64())
                         } else {
@@ -506,6 +519,7 @@ impl ExecutionEngine for O3Engine {
                         // to prevent speculative CSR state from persisting if
                         // an older instruction traps.
                         self.rob.complete(entry.rob_tag, val);
+                        cpu.pipe_event_tag(entry.rob_tag, PipeStage::Writeback);
                         self.prf.write(entry.rd_phys, val);
                         self.issue_queue.wakeup_phys(entry.rd_phys, val);
                         // ROB Completed, PRF written, wakeup done — skip memory pipeline.
@@ -534,7 +548,9 @@ impl ExecutionEngine for O3Engine {
                 &self.rob,
                 self.load_ports,
                 self.store_ports,
+                self.ports,
                 Some(&self.prf),
+                cpu,
             );
 
             let mut issued_count = 0;
@@ -543,7 +559,8 @@ impl ExecutionEngine for O3Engine {
             for selected in issued {
                 let entry = selected.entry;
                 let mem_dep = selected.mem_dep;
-                let fu_type = FuType::classify(&entry.ctrl);
+                let op = OpClass::classify(&entry.ctrl);
+                let fu_type = op.fu_type();
                 let rob_tag = entry.rob_tag;
                 let is_mem_instr = entry.ctrl.mem_read || entry.ctrl.mem_write;
 
@@ -574,9 +591,13 @@ impl ExecutionEngine for O3Engine {
                     self.mdp.issued(rob_tag);
                 }
 
-                let complete_cycle = self.fu_pool.acquire(fu_type, now);
-                let is_pipelined = self.fu_pool.is_pipelined(fu_type);
+                let latency = self.fu_pool.latency_for(op, &entry.ctrl, entry.rv1, entry.rv2);
+                let complete_cycle = self.fu_pool.acquire_for(op, now, latency);
+                let occupancy = self.fu_pool.occupancy(op, latency);
+                cpu.stats.fu_busy_cycles[fu_type as usize] += occupancy;
+                cpu.stats.port_issues[PortClass::of(fu_type) as usize] += 1;
 
+                cpu.pipe_event(entry.seq, PipeStage::Execute);
                 let (ex_result, flush) = execute::execute_one(cpu, entry, &mut self.rob);
                 issued_count += 1;
 
@@ -584,9 +605,12 @@ impl ExecutionEngine for O3Engine {
                     || ex_result.ctrl.mem_write
                     || ex_result.ctrl.atomic_op != crate::core::pipeline::signals::AtomicOp::None;
 
-                // For pipelined non-memory instructions: speculative wakeup immediately
-                // so dependent instructions can be selected on the very next cycle.
-                let speculative_written = if !is_mem && is_pipelined && ex_result.trap.is_none() {
+                // For single-cycle pipelined non-memory instructions: speculative
+                // wakeup immediately so dependent instructions can be selected on
+                // the very next cycle. Longer-latency results (e.g. IntMul) wait in
+                // pending_results and wake dependents when they complete.
+                let single_cycle = occupancy == 1 && complete_cycle <= now + 1;
+                let speculative_written = if !is_mem && single_cycle && ex_result.trap.is_none() {
                     let val = if ex_result.ctrl.control_flow == ControlFlow::Jump {
                         ex_result.pc.wrapping_add(ex_result.inst_size.as_u64())
                     } else {
@@ -603,6 +627,7 @@ impl ExecutionEngine for O3Engine {
                         self.rob.set_sfence_vma(ex_result.rob_tag, info);
                     }
                     self.rob.complete(ex_result.rob_tag, val);
+                    cpu.pipe_event_tag(ex_result.rob_tag, PipeStage::Writeback);
                     self.prf.write(ex_result.rd_phys, val);
                     self.issue_queue.wakeup_phys(ex_result.rd_phys, val);
                     true
@@ -711,26 +736,22 @@ impl ExecutionEngine for O3Engine {
             // must be rebuilt by forward-walking surviving ROB entries, adding
             // ceil(surviving / width) stall cycles on top of the squash walk.
             let surviving = self.rob.len();
-            if self.checkpoints.capacity() > 0 {
-                if let Some(ckpt) = self.checkpoints.find_by_tag(keep_tag) {
-                    self.rename_map = ckpt.rename_map.clone();
-                    // Checkpoint found: O(1) rename restore, no rebuild penalty.
-                    self.squash_stall_remaining = self.compute_squash_stall(squashed, 0);
-                } else {
-                    self.rebuild_rename_map(); // non-branch flush (CSR/FENCE)
-                    // No checkpoint: must pay rebuild cost for surviving entries.
-                    self.squash_stall_remaining = self.compute_squash_stall(squashed, surviving);
-                    cpu.stats.stalls_rename_rebuild += surviving.div_ceil(self.width.max(1)) as u64;
-                }
-                self.checkpoints.flush_after(keep_tag);
+            if let Some(ckpt) = self.checkpoints.find_by_tag(keep_tag) {
+                self.rename_map = ckpt.rename_map.clone();
+                self.scoreboard = ckpt.scoreboard.clone();
+                // Checkpoint found: O(1) rename restore, no rebuild penalty.
+                self.squash_stall_remaining = self.compute_squash_stall(squashed, 0);
+                cpu.stats.checkpoint_restores += 1;
             } else {
+                // Non-branch flush (CSR/FENCE) or no checkpoint support:
+                // must pay rebuild cost for surviving entries.
                 self.rebuild_rename_map();
-                // No checkpoint support: always pay rebuild cost.
+                self.scoreboard.rebuild_from_rob(&self.rob);
                 self.squash_stall_remaining = self.compute_squash_stall(squashed, surviving);
                 cpu.stats.stalls_rename_rebuild += surviving.div_ceil(self.width.max(1)) as u64;
+                cpu.stats.recovery_walk_entries += surviving as u64;
             }
-            // Scoreboard is still used by in-order; rebuild from remaining ROB entries
-            self.scoreboard.rebuild_from_rob(&self.rob);
+            self.checkpoints.flush_after(keep_tag);
         }
 
         // ── 8. Dispatch from rename into issue queue ───────────────────
@@ -751,6 +772,8 @@ impl ExecutionEngine for O3Engine {
         cpu.stats.mdp_predictions_wait_all = mdp_stats.predictions_wait_all;
         cpu.stats.mdp_predictions_wait_for = mdp_stats.predictions_wait_for;
         cpu.stats.mdp_violations = mdp_stats.violations;
+        cpu.stats.mdp_avoided_violations = mdp_stats.avoided_violations;
+        cpu.stats.mdp_false_dependences = mdp_stats.false_dependences;
     }
 
     fn can_accept(&self) -> usize {
@@ -810,6 +833,8 @@ impl ExecutionEngine for O3Engine {
     }
 
     fn read_csr_speculative(&self, cpu: &crate::core::Cpu, addr: crate::common::CsrAddr) -> u64 {
+        // The issue queue holds CSR instructions until older CSR writes have
+        // committed, so architectural state is current.
         cpu.csr_read(addr)
     }
 
@@ -837,6 +862,10 @@ impl ExecutionEngine for O3Engine {
         &mut self.scoreboard
     }
 
+    fn issue_queue_len(&self) -> usize {
+        self.issue_queue.len()
+    }
+
     fn rename_map(&self) -> &RenameMap {
         &self.rename_map
     }
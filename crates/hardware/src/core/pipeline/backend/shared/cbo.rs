@@ -0,0 +1,82 @@
+//! Cache-Block Operations (Zicbom, Zicboz).
+//!
+//! A `cbo.*` instruction is split across two stages:
+//! 1. **Execute:** checks the `menvcfg` enables for the current privilege,
+//!    then translates the block address. The physical block address is the
+//!    instruction's result and travels to commit in the ROB.
+//! 2. **Commit:** after the store buffer drains, applies the operation to
+//!    L1D, L2 and L3 (and, for `cbo.zero`, to memory), then redirects so
+//!    younger instructions observe the new state.
+//!
+//! Caches hold tags only and data always lives in memory, so a dirty block
+//! has nothing to lose: `cbo.inval` drops the block exactly as `cbo.flush`.
+
+use crate::common::{AccessType, PhysAddr, TranslationResult, Trap, VirtAddr};
+use crate::core::Cpu;
+use crate::core::arch::csr::{MENVCFG_CBCFE, MENVCFG_CBIE, MENVCFG_CBZE};
+use crate::core::arch::mode::PrivilegeMode;
+use crate::core::pipeline::signals::CboOp;
+use crate::core::units::cache::CacheSim;
+use crate::isa::zicbo::opcodes::BLOCK_BYTES;
+
+/// Checks that `op` is enabled at the current privilege and translates the
+/// block containing `vaddr`.
+///
+/// Below M-mode, `cbo.inval` needs a non-zero `menvcfg.CBIE`, `cbo.clean` and
+/// `cbo.flush` need `menvcfg.CBCFE`, and `cbo.zero` needs `menvcfg.CBZE`;
+/// otherwise the instruction is illegal. On success the returned physical
+/// address is aligned to the block.
+pub fn translate_block(cpu: &mut Cpu, op: CboOp, inst: u32, vaddr: u64) -> TranslationResult {
+    if cpu.privilege != PrivilegeMode::Machine {
+        let enabled = match op {
+            CboOp::Inval => cpu.csrs.menvcfg & MENVCFG_CBIE != 0,
+            CboOp::Clean | CboOp::Flush => cpu.csrs.menvcfg & MENVCFG_CBCFE != 0,
+            CboOp::Zero => cpu.csrs.menvcfg & MENVCFG_CBZE != 0,
+        };
+        if !enabled {
+            return TranslationResult::fault(Trap::IllegalInstruction(inst), 0);
+        }
+    }
+    let block = vaddr & !(BLOCK_BYTES - 1);
+    cpu.translate(VirtAddr::new(block), AccessType::Write, BLOCK_BYTES)
+}
+
+/// Applies `op` to the block at physical address `paddr`.
+///
+/// The caller must have drained the store buffer, so every older store is
+/// already in memory and in the caches.
+pub fn commit(cpu: &mut Cpu, op: CboOp, paddr: u64) {
+    if !cpu.is_ram(paddr) {
+        return;
+    }
+    match op {
+        CboOp::Clean => {
+            for cache in [&mut cpu.l1_d_cache, &mut cpu.l2_cache, &mut cpu.l3_cache] {
+                for_each_line(cache, paddr, |c, addr| {
+                    let _ = c.clean_line(addr);
+                });
+            }
+        }
+        CboOp::Flush | CboOp::Inval => {
+            for cache in [&mut cpu.l1_d_cache, &mut cpu.l2_cache, &mut cpu.l3_cache] {
+                for_each_line(cache, paddr, |c, addr| {
+                    let _ = c.invalidate_line(addr);
+                });
+            }
+        }
+        CboOp::Zero => {
+            let _latency = cpu.simulate_memory_access(PhysAddr::new(paddr), AccessType::Write);
+            for offset in (0..BLOCK_BYTES).step_by(8) {
+                cpu.bus.bus.write_u64(PhysAddr::new(paddr + offset), 0);
+            }
+        }
+    }
+}
+
+/// Calls `f` once for every line of `cache` that overlaps the block at `block`.
+fn for_each_line(cache: &mut CacheSim, block: u64, mut f: impl FnMut(&mut CacheSim, u64)) {
+    let step = cache.line_bytes().clamp(1, BLOCK_BYTES as usize);
+    for addr in (block..block + BLOCK_BYTES).step_by(step) {
+        f(cache, addr);
+    }
+}
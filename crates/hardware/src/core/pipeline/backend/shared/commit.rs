@@ -7,16 +7,12 @@
 //! 4. Handle traps/interrupts.
 //! 5. Drain one committed store to memory per cycle.
 
-use crate::common::constants::{
-    DELEG_MEIP_BIT, DELEG_MSIP_BIT, DELEG_MTIP_BIT, DELEG_SEIP_BIT, DELEG_SSIP_BIT, DELEG_STIP_BIT,
-};
 use crate::common::constants::{PAGE_SHIFT, VPN_MASK};
 use crate::common::{Asid, LrScRecord, RegIdx, SfenceVmaInfo, Trap, Vpn};
 use crate::core::Cpu;
 use crate::core::arch::csr;
-use crate::core::arch::mode::PrivilegeMode;
-use crate::core::arch::trap::TrapHandler;
 use crate::core::cpu::PC_TRACE_MAX;
+use crate::core::pipeline::backend::shared::cbo;
 use crate::core::pipeline::checkpoint::CheckpointTable;
 use crate::core::pipeline::free_list::FreeList;
 use crate::core::pipeline::load_queue::LoadQueue;
@@ -24,9 +20,12 @@ use crate::core::pipeline::prf::PhysRegFile;
 use crate::core::pipeline::rename_map::RenameMap;
 use crate::core::pipeline::rob::{Rob, RobState};
 use crate::core::pipeline::scoreboard::Scoreboard;
-use crate::core::pipeline::signals::{AluOp, ControlFlow, MemWidth, SystemOp};
+use crate::core::pipeline::signals::{AluOp, ControlFlow, ControlSignals, MemWidth, SystemOp};
 use crate::core::pipeline::store_buffer::{StoreBuffer, StoreResolution, width_to_bytes};
 use crate::core::units::bru::BranchPredictor;
+use crate::core::units::bru::ras::RasOp;
+use crate::core::units::lsu::ordering::Fence;
+use crate::core::units::lsu::unaligned;
 use crate::trace_branch;
 use crate::trace_commit;
 use crate::trace_csr;
@@ -64,7 +63,7 @@ pub fn commit_stage(
             cpu.committed_next_pc // ROB empty: use last committed PC + size
         };
 
-        let interrupt = check_interrupts(cpu);
+        let interrupt = cpu.check_interrupts();
         if let Some(interrupt_trap) = interrupt {
             cpu.wfi_waiting = false;
             trace_trap!(cpu.trace;
@@ -77,6 +76,11 @@ pub fn commit_stage(
                 priv_mode  = ?cpu.privilege,
                 "CM: interrupt detected — flushing pipeline"
             );
+            let since = cpu.interrupt_pending_since.take().unwrap_or(cpu.stats.cycles);
+            let latency = cpu.stats.cycles - since;
+            cpu.stats.interrupts_taken += 1;
+            cpu.stats.interrupt_latency_cycles += latency;
+            cpu.stats.interrupt_latency_max = cpu.stats.interrupt_latency_max.max(latency);
             trap_event = Some((interrupt_trap, epc));
         } else if cpu.wfi_waiting {
             // WFI active: never fall through to the commit loop.
@@ -114,6 +118,11 @@ pub fn commit_stage(
     for _ in 0..width {
         let Some(head) = rob.peek_head() else { break };
 
+        // Instruction limit of a detailed run: retire nothing past it.
+        if cpu.retire_limit.is_some_and(|limit| cpu.stats.instructions_retired >= limit) {
+            break;
+        }
+
         // Safety guard: a load must not retire while older stores have unresolved
         // addresses. Without this, a bypassed load's LQ entry gets deallocated
         // before memory2 can detect a violation against a later-resolving store.
@@ -134,7 +143,9 @@ pub fn commit_stage(
                 && let Some(ref the_trap) = entry.trap
             {
                 #[cfg(feature = "commit-log")]
-                if let Some(ref mut log) = cpu.commit_log {
+                if cpu.trace_in_region
+                    && let Some(ref mut log) = cpu.commit_log
+                {
                     use crate::common::Trap;
                     use std::io::Write;
                     // Log all faulting instructions except fetch-stage page/access
@@ -171,6 +182,21 @@ pub fn commit_stage(
                 if entry.phys_dst.0 != 0 {
                     free_list.reclaim(entry.phys_dst);
                 }
+                // A semihosting or emulated system call reads its arguments
+                // from memory on the host side, so older stores must be
+                // visible first.
+                let host_call = match the_trap {
+                    Trap::Breakpoint(_) => cpu.semihosting.is_some(),
+                    Trap::EnvironmentCallFromUMode
+                    | Trap::EnvironmentCallFromSMode
+                    | Trap::EnvironmentCallFromMMode => {
+                        cpu.direct_mode && cpu.syscall_emulation.is_some()
+                    }
+                    _ => false,
+                };
+                if host_call {
+                    drain_all_committed(cpu, store_buffer);
+                }
                 trap_event = Some((the_trap.clone(), entry.pc));
             }
             break;
@@ -191,9 +217,36 @@ pub fn commit_stage(
             break;
         }
 
+        // Run-until breakpoint: leave the target as the next instruction to commit.
+        if cpu.break_hit {
+            break;
+        }
+        if let Some(bp) = cpu.break_pc {
+            if head.fused.map_or(head.pc, |f| f.pc) == bp {
+                if cpu.break_skip {
+                    cpu.break_skip = false;
+                } else {
+                    cpu.break_hit = true;
+                    break;
+                }
+            } else if head.pc == bp {
+                // The tail of a pair fused before the breakpoint was armed:
+                // retire the pair, then stop.
+                cpu.break_hit = true;
+            }
+        }
+
         // Completed — retire
         let Some(entry) = rob.commit_head() else { break };
         retired_count += 1;
+        cpu.retired_seq = entry.seq;
+        if let Some(head) = entry.fused {
+            cpu.update_trace_region(head.pc);
+        }
+        cpu.update_trace_region(entry.pc);
+        if let Some(trace) = &mut cpu.pipe_trace {
+            trace.retire(entry.seq, cpu.stats.cycles);
+        }
 
         // Track the next-to-commit PC for accurate interrupt EPC when ROB is empty.
         // For taken branches and jumps, the next PC is the branch target, not pc+4.
@@ -229,7 +282,7 @@ pub fn commit_stage(
         // so we can include the destination register value).
         #[cfg(feature = "commit-log")]
         let commit_log_entry: Option<(u64, u32, bool, usize, u64)> = {
-            if cpu.commit_log.is_some() {
+            if cpu.commit_log.is_some() && cpu.trace_in_region {
                 let has_rd =
                     (entry.ctrl.reg_write && !entry.rd.is_zero()) || entry.ctrl.fp_reg_write;
                 Some((entry.pc, entry.inst, has_rd, entry.rd.as_usize(), entry.result.unwrap_or(0)))
@@ -237,17 +290,47 @@ pub fn commit_stage(
                 None
             }
         };
+        // A fused head retires first, writing the same rd as its tail.
+        #[cfg(feature = "commit-log")]
+        let commit_log_head: Option<(u64, u32, usize, u64)> = match (entry.fused, entry.ctrl.fusion)
+        {
+            (Some(head), Some(pattern)) if cpu.commit_log.is_some() && cpu.trace_in_region => {
+                let head_rd = RegIdx::new(((head.inst >> 7) & 0x1F) as u8);
+                let val = crate::core::pipeline::frontend::fusion::head_result(
+                    head,
+                    pattern,
+                    entry.result.unwrap_or(0),
+                );
+                Some((head.pc, head.inst, head_rd.as_usize(), val))
+            }
+            _ => None,
+        };
 
         // Update PC trace
+        if let Some(head) = entry.fused {
+            cpu.pc_trace.push((head.pc, head.inst));
+        }
         cpu.pc_trace.push((entry.pc, entry.inst));
-        if cpu.pc_trace.len() > PC_TRACE_MAX {
+        while cpu.pc_trace.len() > PC_TRACE_MAX {
             let _ = cpu.pc_trace.remove(0);
         }
 
         // Statistics
+        if entry.fused.is_some() {
+            cpu.stats.instructions_retired += 1;
+            cpu.csrs.count_instret();
+            cpu.stats.fused_pairs += 1;
+            // Every fusion head (lui, auipc, slli, slt/sltu) is an ALU op.
+            cpu.stats.inst_alu += 1;
+        }
         if entry.inst != 0 && entry.inst != 0x13 {
             cpu.stats.instructions_retired += 1;
-            update_instruction_stats(cpu, &entry);
+            cpu.csrs.count_instret();
+            update_instruction_stats(cpu, &entry.ctrl);
+            if cpu.access_trace.is_some() && (entry.ctrl.mem_read || entry.ctrl.mem_write) {
+                let size = unaligned::width_to_bytes(entry.ctrl.width);
+                cpu.trace_access(entry.pc, entry.mem_paddr, size, entry.ctrl.mem_write, false);
+            }
         }
 
         // Apply deferred branch predictor update (only update on committed branches)
@@ -274,6 +357,23 @@ pub fn commit_stage(
             }
         }
 
+        // Mirror committed calls/returns onto the committed RAS, which full
+        // flushes restore.
+        if entry.ctrl.control_flow == ControlFlow::Jump
+            && let Some(op) =
+                RasOp::for_inst(entry.inst, entry.pc.wrapping_add(entry.inst_size.as_u64()))
+        {
+            cpu.branch_predictor.commit_ras(op);
+        }
+
+        // AMOCAS compares against the architectural rd, which the register
+        // write below overwrites with Memory2's placeholder; read the
+        // operands first.
+        let cas_operands = matches!(entry.lr_sc, Some(LrScRecord::Cas { .. })).then(|| {
+            let rs2 = RegIdx::new(((entry.inst >> 20) & 0x1F) as u8);
+            (cas_register_pair(cpu, entry.rd), cas_register_pair(cpu, rs2))
+        });
+
         // Write to register file
         debug_assert!(
             entry.result.is_some() || (!entry.ctrl.reg_write && !entry.ctrl.fp_reg_write),
@@ -282,6 +382,19 @@ pub fn commit_stage(
             entry.pc,
         );
         let val = entry.result.unwrap_or(0);
+        if cpu.hang_threshold.is_some() {
+            let changed = if entry.ctrl.fp_reg_write {
+                cpu.regs.read_f(entry.rd) != val
+            } else {
+                entry.ctrl.reg_write && !entry.rd.is_zero() && cpu.regs.read(entry.rd) != val
+            };
+            let progress =
+                changed || entry.ctrl.mem_write || entry.ctrl.system_op != SystemOp::None;
+            if let Some(head) = entry.fused {
+                cpu.loop_detector.retire(head.pc, false, cpu.stats.cycles);
+            }
+            cpu.loop_detector.retire(entry.pc, progress, cpu.stats.cycles);
+        }
         if entry.ctrl.fp_reg_write {
             cpu.regs.write_f(entry.rd, val);
             scoreboard.clear_if_match(entry.rd, true, entry.tag);
@@ -325,6 +438,13 @@ pub fn commit_stage(
 
         // Write deferred commit log entry (now that rd has been written).
         #[cfg(feature = "commit-log")]
+        if let Some((pc, inst, rd, val)) = commit_log_head
+            && let Some(ref mut log) = cpu.commit_log
+        {
+            use std::io::Write;
+            let _ = writeln!(log, "core   0: 0x{pc:016x} (0x{inst:08x}) x{rd} 0x{val:016x}");
+        }
+        #[cfg(feature = "commit-log")]
         if let Some((pc, inst, has_rd, rd, val)) = commit_log_entry {
             if let Some(ref mut log) = cpu.commit_log {
                 use std::io::Write;
@@ -378,16 +498,17 @@ pub fn commit_stage(
                 deferred = !csr_update.applied,
                 "CM: CSR write applied at commit"
             );
-            // SATP changes address translation: any instructions fetched
-            // between the execute-stage redirect and this commit used the
-            // old page tables. Force a re-flush so the frontend re-fetches
-            // with the new translation context.
+            // SATP changes address translation and MISA the legal
+            // instructions: any instructions fetched between the
+            // execute-stage redirect and this commit were translated or
+            // decoded under the old value. Force a re-flush so the frontend
+            // re-fetches with the new context.
             //
             // We must also reset cpu.pc to the instruction after this CSR,
             // because Fetch1 has been advancing cpu.pc since the execute-stage
             // redirect. Without this, the frontend would restart from the
             // stale (advanced) cpu.pc, skipping instructions.
-            if csr_update.addr == csr::SATP {
+            if csr_update.addr == csr::SATP || csr_update.addr == csr::MISA {
                 cpu.pc = entry.pc.wrapping_add(entry.inst_size.as_u64());
                 cpu.redirect_pending = true;
             }
@@ -430,19 +551,23 @@ pub fn commit_stage(
         // WFI — applied at commit so it is properly ordered with
         // preceding instructions in the same fetch group.
         if entry.ctrl.system_op == SystemOp::Wfi {
+            let next_pc = entry.pc.wrapping_add(entry.inst_size.as_u64());
             if cpu.csrs.mie != 0 || cpu.csrs.mip != 0 {
                 // At least one interrupt source is enabled or pending —
                 // enter the waiting state.  The interrupt check at the
                 // top of commit_instructions will wake us.
                 cpu.wfi_waiting = true;
-                cpu.wfi_pc = entry.pc.wrapping_add(entry.inst_size.as_u64());
-            } else {
-                // Nothing enabled, nothing pending — NOP (advance past WFI
-                // to avoid deadlock, e.g. OpenSBI early boot).
-                cpu.pc = entry.pc.wrapping_add(entry.inst_size.as_u64());
-                cpu.redirect_pending = true;
+                cpu.wfi_pc = next_pc;
             }
-            cpu.committed_next_pc = entry.pc.wrapping_add(entry.inst_size.as_u64());
+            // Otherwise nothing is enabled or pending — NOP (advance past
+            // WFI to avoid deadlock, e.g. OpenSBI early boot).
+            //
+            // Either way, redirect to the next instruction so everything
+            // fetched behind the WFI is flushed; a waiting hart then parks
+            // with an empty backend and refetches from `wfi_pc` on wakeup.
+            cpu.pc = next_pc;
+            cpu.redirect_pending = true;
+            cpu.committed_next_pc = next_pc;
             break;
         }
 
@@ -485,6 +610,35 @@ pub fn commit_stage(
                         break;
                     }
                 }
+                LrScRecord::Cas { paddr } => {
+                    // AMOCAS is performed here, non-speculatively, on the
+                    // architectural register values.  Older committed
+                    // stores are drained first so the comparison sees them.
+                    // The store buffer slot was cancelled in Memory2 and
+                    // rd was written with a placeholder, so fix rd (and the
+                    // odd register of an amocas.q pair) and flush younger
+                    // instructions that may have consumed the placeholder.
+                    drain_all_committed(cpu, store_buffer);
+                    let (expected, swap) = cas_operands.unwrap_or_default();
+                    let (old_lo, old_hi) =
+                        compare_and_swap(cpu, paddr, entry.ctrl.width, expected, swap);
+                    if entry.ctrl.reg_write && !entry.rd.is_zero() {
+                        cpu.regs.write(entry.rd, old_lo);
+                        if let Some(ref mut prf) = prf {
+                            prf.write(entry.phys_dst, old_lo);
+                        }
+                        if matches!(entry.ctrl.width, MemWidth::Quad) {
+                            let rd_hi = RegIdx::new(entry.rd.as_u8() + 1);
+                            cpu.regs.write(rd_hi, old_hi);
+                            if let Some(ref mut prf) = prf {
+                                prf.write(committed_rename_map.get(rd_hi, false), old_hi);
+                            }
+                        }
+                    }
+                    cpu.pc = entry.pc.wrapping_add(entry.inst_size.as_u64());
+                    cpu.redirect_pending = true;
+                    break;
+                }
             }
         }
 
@@ -493,15 +647,14 @@ pub fn commit_stage(
             // Per RISC-V spec Section 8.2: a store to the reservation set
             // between a paired LR and SC must cause the SC to fail.  Clear
             // the reservation when a non-LR/SC store (regular store or AMO)
-            // commits to an address in the reservation granule.
+            // commits to any byte of the reservation granule.
             //
             // SC stores are excluded: they already handle the reservation
             // above via LrScRecord::Sc.
             if entry.lr_sc.is_none()
                 && let Some(paddr) = store_buffer.find_paddr(entry.tag)
-                && cpu.check_reservation(paddr)
             {
-                cpu.clear_reservation();
+                cpu.invalidate_reservation(paddr, width_to_bytes(entry.ctrl.width) as u64);
             }
             store_buffer.mark_committed(entry.tag);
         }
@@ -542,15 +695,23 @@ pub fn commit_stage(
             // Without this break, younger instructions (fetched before the
             // store drain) could commit in the same cycle with stale data.
             break;
+        } else if let SystemOp::Cbo(op) = entry.ctrl.system_op {
+            // CBO.*: older stores must reach the caches and memory before the
+            // block is cleaned, flushed or zeroed.
+            drain_all_committed(cpu, store_buffer);
+            cbo::commit(cpu, op, entry.result.unwrap_or_default());
+            // Younger instructions may have loaded the block before it was
+            // zeroed; re-fetch them behind the operation, as for FENCE.I.
+            cpu.pc = entry.pc.wrapping_add(entry.inst_size.as_u64());
+            cpu.redirect_pending = true;
+            break;
         } else if entry.ctrl.system_op == SystemOp::Fence {
-            let pred_bits = ((entry.inst >> 24) & 0xF) as u8;
-            let pred_w = pred_bits & 0b0001 != 0;
-            let pred_r = pred_bits & 0b0010 != 0;
+            let pred = Fence::decode(entry.inst).pred;
             // FENCE pred,succ:
             // - pred.w: drain store buffer (older stores globally visible)
             // - pred.r: older loads already completed by commit order
             // - Both pred.r and pred.w: full drain + flush WCB
-            if pred_w || pred_r {
+            if pred.w || pred.r {
                 drain_all_committed(cpu, store_buffer);
             }
         }
@@ -578,8 +739,12 @@ pub fn commit_stage(
     }
     cpu.stats.retire_histogram[retired_count.min(3)] += 1;
 
-    // Drain one committed store to memory per cycle
-    drain_one_store(cpu, store_buffer);
+    // Drain up to `store_drain_rate` committed stores to memory
+    for _ in 0..cpu.store_drain_rate {
+        if !drain_one_store(cpu, store_buffer) {
+            break;
+        }
+    }
 
     trap_event
 }
@@ -589,14 +754,16 @@ pub fn commit_stage(
 /// If a Write Combining Buffer (WCB) is configured, stores are first merged
 /// into the WCB. The WCB coalesces stores to the same cache line and only
 /// drains to L1D when an entry is evicted (LRU) or flushed.
-fn drain_one_store(cpu: &mut Cpu, store_buffer: &mut StoreBuffer) {
-    let Some(store) = store_buffer.drain_one() else { return };
+///
+/// Returns `false` if there was no committed store to drain.
+fn drain_one_store(cpu: &mut Cpu, store_buffer: &mut StoreBuffer) -> bool {
+    let Some(store) = store_buffer.drain_one() else { return false };
     let StoreResolution::Committed { paddr, data } = store.resolution else {
         // Cancelled (failed SC) — no write needed, just drain the slot.
-        return;
+        return true;
     };
 
-    let is_ram = paddr.val() >= cpu.ram_start && paddr.val() < cpu.ram_end;
+    let is_ram = cpu.is_ram(paddr.val());
     let width_bytes = width_to_bytes(store.width);
 
     if !cpu.wcb.is_disabled() && is_ram {
@@ -627,6 +794,7 @@ fn drain_one_store(cpu: &mut Cpu, store_buffer: &mut StoreBuffer) {
         via_wcb    = !cpu.wcb.is_disabled(),
         "CM: committed store drained to memory"
     );
+    true
 }
 
 /// Drains **all** committed stores from the store buffer to memory.
@@ -634,10 +802,10 @@ fn drain_one_store(cpu: &mut Cpu, store_buffer: &mut StoreBuffer) {
 /// Called before SATP writes to ensure page table entries set up by
 /// preceding stores are visible in physical memory before the page
 /// table walker consults them. Also flushes the WCB.
-fn drain_all_committed(cpu: &mut Cpu, store_buffer: &mut StoreBuffer) {
+pub(crate) fn drain_all_committed(cpu: &mut Cpu, store_buffer: &mut StoreBuffer) {
     while let Some(store) = store_buffer.drain_one() {
         if let StoreResolution::Committed { paddr, data } = store.resolution {
-            let is_ram = paddr.val() >= cpu.ram_start && paddr.val() < cpu.ram_end;
+            let is_ram = cpu.is_ram(paddr.val());
             if is_ram {
                 let _latency = cpu.simulate_memory_access(paddr, crate::common::AccessType::Write);
             }
@@ -658,104 +826,118 @@ fn flush_wcb(cpu: &mut Cpu) {
     }
 }
 
+/// Performs an `amocas.w/d/q` at `paddr`.
+///
+/// `expected` is the comparison value read from `rd` and `swap` the value
+/// read from `rs2` (see [`cas_register_pair`]).  Memory is written only
+/// when the comparison succeeds.
+///
+/// # Returns
+///
+/// The old memory value as `(low, high)`; `low` is sign-extended for words
+/// and `high` is only meaningful for `amocas.q`.
+pub(crate) fn compare_and_swap(
+    cpu: &mut Cpu,
+    paddr: crate::common::PhysAddr,
+    width: MemWidth,
+    expected: (u64, u64),
+    swap: (u64, u64),
+) -> (u64, u64) {
+    match width {
+        MemWidth::Word => {
+            let old = cpu.bus.bus.read_u32(paddr);
+            if old == expected.0 as u32 {
+                write_store_to_memory(cpu, paddr, swap.0, MemWidth::Word);
+                cpu.invalidate_reservation(paddr, 4);
+            }
+            ((old as i32) as i64 as u64, 0)
+        }
+        MemWidth::Double => {
+            let old = cpu.bus.bus.read_u64(paddr);
+            if old == expected.0 {
+                write_store_to_memory(cpu, paddr, swap.0, MemWidth::Double);
+                cpu.invalidate_reservation(paddr, 8);
+            }
+            (old, 0)
+        }
+        MemWidth::Quad => {
+            let paddr_hi = crate::common::PhysAddr::new(paddr.val() + 8);
+            let old = (cpu.bus.bus.read_u64(paddr), cpu.bus.bus.read_u64(paddr_hi));
+            if old == expected {
+                write_store_to_memory(cpu, paddr, swap.0, MemWidth::Double);
+                write_store_to_memory(cpu, paddr_hi, swap.1, MemWidth::Double);
+                cpu.invalidate_reservation(paddr, 16);
+            }
+            old
+        }
+        MemWidth::Byte | MemWidth::Half | MemWidth::Nop => (0, 0),
+    }
+}
+
+/// Reads an AMOCAS operand as `(base, base + 1)`.
+///
+/// `x0` as a pair base reads as zero for both halves.  The high half is
+/// ignored for `amocas.w` and `amocas.d`.
+pub(crate) const fn cas_register_pair(cpu: &Cpu, base: RegIdx) -> (u64, u64) {
+    if base.is_zero() {
+        (0, 0)
+    } else {
+        (cpu.regs.read(base), cpu.regs.read(RegIdx::new((base.as_u8() + 1) & 0x1F)))
+    }
+}
+
 /// Writes a store's data to the correct memory target (RAM fast-path or bus).
-fn write_store_to_memory(
+pub(crate) fn write_store_to_memory(
     cpu: &mut Cpu,
     paddr: crate::common::PhysAddr,
     data: u64,
     width: MemWidth,
 ) {
     let raw = paddr.val();
+    let size = unaligned::width_to_bytes(width);
+    cpu.store_log.push((paddr, size));
     let in_htif = cpu.htif_range.is_some_and(|(lo, hi)| raw >= lo && raw < hi);
-    let is_ram = !in_htif && raw >= cpu.ram_start && raw < cpu.ram_end;
-    if is_ram {
-        let offset = (raw - cpu.ram_start) as usize;
-        unsafe {
-            match width {
-                MemWidth::Byte => *cpu.ram_ptr.add(offset) = data as u8,
-                MemWidth::Half => {
-                    (cpu.ram_ptr.add(offset) as *mut u16).write_unaligned(data as u16);
-                }
-                MemWidth::Word => {
-                    (cpu.ram_ptr.add(offset) as *mut u32).write_unaligned(data as u32);
-                }
-                MemWidth::Double => (cpu.ram_ptr.add(offset) as *mut u64).write_unaligned(data),
-                MemWidth::Nop => {}
-            }
-        }
+    if !in_htif && cpu.ram.contains(raw, size) {
+        // Quad stores come only from amocas.q, written as two doubles;
+        // `write` ignores them.
+        let _ = cpu.ram.write(raw, size, data);
+        cpu.faults.store(raw, size);
+    } else if unaligned::needs_split(raw, size) {
+        // Emulated misaligned store: write the aligned sub-accesses byte by byte.
+        unaligned::split_store(raw, size, data, |a, b| {
+            cpu.bus.bus.write_u8(crate::common::PhysAddr::new(a), b);
+        });
     } else {
         match width {
             MemWidth::Byte => cpu.bus.bus.write_u8(paddr, data as u8),
             MemWidth::Half => cpu.bus.bus.write_u16(paddr, data as u16),
             MemWidth::Word => cpu.bus.bus.write_u32(paddr, data as u32),
             MemWidth::Double => cpu.bus.bus.write_u64(paddr, data),
-            MemWidth::Nop => {}
+            MemWidth::Quad | MemWidth::Nop => {}
         }
     }
 }
 
-/// Checks for pending interrupts. Returns the trap if one should be taken.
-fn check_interrupts(cpu: &Cpu) -> Option<Trap> {
-    let mip = cpu.csrs.mip;
-    let mie = cpu.csrs.mie;
-    let mstatus = cpu.csrs.mstatus;
-
-    let m_global_ie = (mstatus & csr::MSTATUS_MIE) != 0;
-    let s_global_ie = (mstatus & csr::MSTATUS_SIE) != 0;
-
-    let check = |bit: u64, enable_bit: u64, deleg_bit: u64| -> Option<Trap> {
-        let pending = (mip & bit) != 0;
-        let enabled = (mie & enable_bit) != 0;
-        if !pending || !enabled {
-            return None;
-        }
-
-        let delegated = (cpu.csrs.mideleg & deleg_bit) != 0;
-        let target_priv =
-            if delegated { PrivilegeMode::Supervisor } else { PrivilegeMode::Machine };
-
-        if cpu.privilege.to_u8() < target_priv.to_u8() {
-            return Some(TrapHandler::irq_to_trap(bit));
-        }
-        if cpu.privilege == target_priv {
-            if target_priv == PrivilegeMode::Machine && m_global_ie {
-                return Some(TrapHandler::irq_to_trap(bit));
-            }
-            if target_priv == PrivilegeMode::Supervisor && s_global_ie {
-                return Some(TrapHandler::irq_to_trap(bit));
-            }
-        }
-        None
-    };
-
-    check(csr::MIP_MEIP, csr::MIE_MEIP, 1 << DELEG_MEIP_BIT)
-        .or_else(|| check(csr::MIP_MSIP, csr::MIE_MSIP, 1 << DELEG_MSIP_BIT))
-        .or_else(|| check(csr::MIP_MTIP, csr::MIE_MTIE, 1 << DELEG_MTIP_BIT))
-        .or_else(|| check(csr::MIP_SEIP, csr::MIE_SEIP, 1 << DELEG_SEIP_BIT))
-        .or_else(|| check(csr::MIP_SSIP, csr::MIE_SSIP, 1 << DELEG_SSIP_BIT))
-        .or_else(|| check(csr::MIP_STIP, csr::MIE_STIE, 1 << DELEG_STIP_BIT))
-}
-
-/// Updates instruction statistics based on the committed entry.
-const fn update_instruction_stats(cpu: &mut Cpu, entry: &crate::core::pipeline::rob::RobEntry) {
-    if entry.ctrl.mem_read {
-        if entry.ctrl.fp_reg_write {
+/// Updates instruction statistics for a retired instruction with control signals `ctrl`.
+pub(crate) const fn update_instruction_stats(cpu: &mut Cpu, ctrl: &ControlSignals) {
+    if ctrl.mem_read {
+        if ctrl.fp_reg_write {
             cpu.stats.inst_fp_load += 1;
         } else {
             cpu.stats.inst_load += 1;
         }
-    } else if entry.ctrl.mem_write {
-        if entry.ctrl.rs2_fp {
+    } else if ctrl.mem_write {
+        if ctrl.rs2_fp {
             cpu.stats.inst_fp_store += 1;
         } else {
             cpu.stats.inst_store += 1;
         }
-    } else if matches!(entry.ctrl.control_flow, ControlFlow::Branch | ControlFlow::Jump) {
+    } else if matches!(ctrl.control_flow, ControlFlow::Branch | ControlFlow::Jump) {
         cpu.stats.inst_branch += 1;
-    } else if !matches!(entry.ctrl.system_op, SystemOp::None) {
+    } else if !matches!(ctrl.system_op, SystemOp::None) {
         cpu.stats.inst_system += 1;
     } else {
-        match entry.ctrl.alu {
+        match ctrl.alu {
             AluOp::FAdd
             | AluOp::FSub
             | AluOp::FMul
@@ -798,7 +980,7 @@ const fn update_instruction_stats(cpu: &mut Cpu, entry: &crate::core::pipeline::
 /// * rs1 != 0, rs2 == 0: flush TLB entries matching virtual address in rs1
 /// * rs1 == 0, rs2 != 0: flush non-global TLB entries matching ASID in rs2
 /// * rs1 != 0, rs2 != 0: flush TLB entry matching both vaddr and ASID
-fn sfence_vma_commit(cpu: &mut Cpu, info: &SfenceVmaInfo) {
+pub(crate) fn sfence_vma_commit(cpu: &mut Cpu, info: &SfenceVmaInfo) {
     match (!info.rs1_idx.is_zero(), !info.rs2_idx.is_zero()) {
         (false, false) => {
             cpu.mmu.dtlb.flush();
@@ -834,8 +1016,10 @@ fn sfence_vma_commit(cpu: &mut Cpu, info: &SfenceVmaInfo) {
 mod tests {
     use super::*;
     use crate::common::InstSize;
+    use crate::common::constants::DELEG_SEIP_BIT;
     use crate::config::Config;
     use crate::core::Cpu;
+    use crate::core::arch::mode::PrivilegeMode;
     use crate::soc::builder::System;
 
     #[test]
@@ -844,7 +1028,7 @@ mod tests {
         let system = System::new(&config, "");
         let cpu = Cpu::new(system, &config);
 
-        assert!(check_interrupts(&cpu).is_none());
+        assert!(cpu.check_interrupts().is_none());
     }
 
     #[test]
@@ -858,7 +1042,7 @@ mod tests {
         cpu.csrs.mstatus |= csr::MSTATUS_MIE;
         cpu.privilege = PrivilegeMode::Machine;
 
-        assert_eq!(check_interrupts(&cpu), Some(Trap::MachineExternalInterrupt));
+        assert_eq!(cpu.check_interrupts(), Some(Trap::MachineExternalInterrupt));
     }
 
     #[test]
@@ -873,7 +1057,7 @@ mod tests {
         cpu.csrs.mideleg |= 1 << DELEG_SEIP_BIT;
         cpu.privilege = PrivilegeMode::Supervisor;
 
-        assert_eq!(check_interrupts(&cpu), Some(Trap::SupervisorExternalInterrupt));
+        assert_eq!(cpu.check_interrupts(), Some(Trap::SupervisorExternalInterrupt));
     }
 
     #[test]
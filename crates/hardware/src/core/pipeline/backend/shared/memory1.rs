@@ -7,11 +7,17 @@
 //! are handled non-blocking: loads are parked in an MSHR and the pipeline
 //! continues. When MSHRs are not configured, the original blocking behavior
 //! is preserved (full miss penalty added to `complete_cycle`).
+//!
+//! With `l1_d_ports` set, at most that many loads/stores start an L1D access
+//! per cycle; the rest wait in `input` for the next cycle, as on MSHR-full.
 
+use crate::common::constants::PAGE_SIZE;
 use crate::common::{AccessType, ExceptionStage, PhysAddr, TranslationResult, VirtAddr};
+use crate::config::MisalignedPolicy;
 use crate::core::Cpu;
 use crate::core::pipeline::latches::{ExMem1Entry, Mem1Mem2Entry};
 use crate::core::pipeline::load_queue::LoadQueue;
+use crate::core::pipeline::pipe_trace::PipeStage;
 use crate::core::pipeline::prf::PhysReg;
 use crate::core::pipeline::signals::AtomicOp;
 use crate::core::units::cache::mshr::{CacheResponse, MshrWaiter};
@@ -42,12 +48,14 @@ pub fn memory1_stage(
     let entries = std::mem::take(input);
     let has_mshrs = cpu.l1d_mshrs.capacity() > 0;
     let mut cancelled_wakeups: Vec<PhysReg> = Vec::new();
+    let mut ports_used = 0;
     // Do NOT clear output — memory2 may have pushed stalled entries back
     // into this latch. We append new entries after any stalled ones.
 
     let mut iter = entries.into_iter();
 
     while let Some(ex) = iter.next() {
+        cpu.pipe_event_tag(ex.rob_tag, PipeStage::Memory);
         // Propagate traps
         if let Some(ref trap) = ex.trap {
             trace_trap!(cpu.trace;
@@ -86,6 +94,22 @@ pub fn memory1_stage(
         let needs_translation = ex.ctrl.mem_read || ex.ctrl.mem_write;
 
         if needs_translation {
+            // Structural hazard: every load/store occupies one L1D port.
+            if cpu.l1_d_ports != 0 && ports_used == cpu.l1_d_ports {
+                cpu.stats.stalls_dcache_port += 1;
+                trace_mem!(cpu.trace;
+                    stage   = "M1",
+                    rob_tag = ex.rob_tag.0,
+                    pc      = %crate::trace::Hex(ex.pc),
+                    ports   = cpu.l1_d_ports,
+                    "M1: L1D ports busy — access deferred to next cycle"
+                );
+                input.push(ex);
+                input.extend(iter);
+                return cancelled_wakeups;
+            }
+            ports_used += 1;
+
             let mut per_entry_latency: u64 = 0;
 
             // Check alignment.
@@ -95,56 +119,88 @@ pub fn memory1_stage(
             // implementation supports misaligned regular accesses.
             let size = unaligned::width_to_bytes(ex.ctrl.width);
             let is_atomic = ex.ctrl.atomic_op != AtomicOp::None;
-            if !unaligned::is_aligned(ex.alu, size) {
-                if cpu.misaligned_access_trap || is_atomic {
-                    let trap = if ex.ctrl.mem_write {
-                        unaligned::store_misaligned_trap(ex.alu)
-                    } else {
-                        unaligned::load_misaligned_trap(ex.alu)
-                    };
-                    trace_trap!(cpu.trace;
-                        event     = "misaligned-access",
-                        stage     = "M1",
-                        pc        = %crate::trace::Hex(ex.pc),
-                        rob_tag   = ex.rob_tag.0,
-                        vaddr     = %crate::trace::Hex(ex.alu),
-                        size      = size,
-                        is_write  = ex.ctrl.mem_write,
-                        trap      = ?trap,
-                        "M1: misaligned memory access trap"
-                    );
-                    output.push(Mem1Mem2Entry {
-                        rob_tag: ex.rob_tag,
-                        pc: ex.pc,
-                        inst: ex.inst,
-                        inst_size: ex.inst_size,
-                        rd: ex.rd,
-                        rd_phys: ex.rd_phys,
-                        alu: ex.alu,
-                        vaddr: VirtAddr::new(ex.alu),
-                        paddr: PhysAddr::new(0),
-                        store_data: ex.store_data,
-                        ctrl: ex.ctrl,
-                        trap: Some(trap),
-                        exception_stage: Some(ExceptionStage::Memory),
-                        fp_flags: ex.fp_flags,
-                        complete_cycle: current_cycle,
-                        pte_update: None,
-                        sfence_vma: ex.sfence_vma,
-                    });
-                    input.extend(iter);
-                    return cancelled_wakeups;
-                }
-                let latency_penalty = unaligned::calculate_unaligned_latency(ex.alu, size, 64);
-                per_entry_latency += latency_penalty;
+            if !unaligned::is_aligned(ex.alu, size)
+                && (cpu.misaligned == MisalignedPolicy::Trap || is_atomic)
+            {
+                let trap = if ex.ctrl.mem_write {
+                    unaligned::store_misaligned_trap(ex.alu)
+                } else {
+                    unaligned::load_misaligned_trap(ex.alu)
+                };
+                trace_trap!(cpu.trace;
+                    event     = "misaligned-access",
+                    stage     = "M1",
+                    pc        = %crate::trace::Hex(ex.pc),
+                    rob_tag   = ex.rob_tag.0,
+                    vaddr     = %crate::trace::Hex(ex.alu),
+                    size      = size,
+                    is_write  = ex.ctrl.mem_write,
+                    trap      = ?trap,
+                    "M1: misaligned memory access trap"
+                );
+                output.push(Mem1Mem2Entry {
+                    rob_tag: ex.rob_tag,
+                    pc: ex.pc,
+                    inst: ex.inst,
+                    inst_size: ex.inst_size,
+                    rd: ex.rd,
+                    rd_phys: ex.rd_phys,
+                    alu: ex.alu,
+                    vaddr: VirtAddr::new(ex.alu),
+                    paddr: PhysAddr::new(0),
+                    store_data: ex.store_data,
+                    ctrl: ex.ctrl,
+                    trap: Some(trap),
+                    exception_stage: Some(ExceptionStage::Memory),
+                    fp_flags: ex.fp_flags,
+                    complete_cycle: current_cycle,
+                    pte_update: None,
+                    sfence_vma: ex.sfence_vma,
+                });
+                input.extend(iter);
+                return cancelled_wakeups;
+            }
+
+            // Emulated misaligned accesses that cross an 8-byte boundary are
+            // performed as two aligned sub-accesses; `split_hi` is the second.
+            let (_, split_hi) = unaligned::split_at_granule(ex.alu, size);
+            let line_bytes = cpu.l1_d_cache.line_bytes() as u64;
+            if split_hi.is_some() {
+                per_entry_latency +=
+                    unaligned::calculate_unaligned_latency(ex.alu, size, line_bytes);
             }
 
             let access_type = if ex.ctrl.mem_write { AccessType::Write } else { AccessType::Read };
 
-            let TranslationResult { paddr, cycles, trap: fault, pte_update } =
+            let TranslationResult { paddr, cycles, trap: mut fault, mut pte_update } =
                 cpu.translate(VirtAddr::new(ex.alu), access_type, size);
             per_entry_latency += cycles;
 
+            // A split that crosses a page boundary needs the second page
+            // translated too. The access carries a single physical address,
+            // so if the pages are not physically contiguous (or both need an
+            // A/D update) fall back to the misaligned trap for software.
+            if let Some((hi_vaddr, hi_len)) = split_hi
+                && fault.is_none()
+                && hi_vaddr % PAGE_SIZE == 0
+            {
+                let hi = cpu.translate(VirtAddr::new(hi_vaddr), access_type, hi_len);
+                per_entry_latency += hi.cycles;
+                if hi.trap.is_some() {
+                    fault = hi.trap;
+                } else if hi.paddr.val() != paddr.val().wrapping_add(hi_vaddr - ex.alu)
+                    || (pte_update.is_some() && hi.pte_update.is_some())
+                {
+                    fault = Some(if ex.ctrl.mem_write {
+                        unaligned::store_misaligned_trap(ex.alu)
+                    } else {
+                        unaligned::load_misaligned_trap(ex.alu)
+                    });
+                } else {
+                    pte_update = pte_update.or(hi.pte_update);
+                }
+            }
+
             if let Some(t) = fault {
                 trace_trap!(cpu.trace;
                     event      = "translation-fault",
@@ -182,10 +238,15 @@ pub fn memory1_stage(
 
             // Check that the physical address is backed by a device.
             // Unmapped regions generate access faults for S/U-mode (Linux
-            // device probing depends on this). M-mode firmware (OpenSBI)
-            // probes addresses expecting bus default (0), not faults.
-            if cpu.privilege != crate::core::arch::mode::PrivilegeMode::Machine
-                && !cpu.bus.bus.is_valid_address(paddr)
+            // device probing depends on this), including M-mode accesses
+            // made at S/U through MPRV. M-mode firmware (OpenSBI) probes
+            // addresses expecting bus default (0), not faults. An access
+            // that starts in RAM but runs past the end of its bank faults in
+            // every mode: nothing can service the tail.
+            let past_bank_end = cpu.is_ram(paddr.val()) && !cpu.ram.contains(paddr.val(), size);
+            if past_bank_end
+                || (cpu.data_privilege() != crate::core::arch::mode::PrivilegeMode::Machine
+                    && !cpu.bus.bus.is_valid_address(paddr))
             {
                 let fault = if ex.ctrl.mem_write {
                     crate::common::Trap::StoreAccessFault(ex.alu)
@@ -248,13 +309,26 @@ pub fn memory1_stage(
                 lq.fill_address(ex.rob_tag, VirtAddr::new(ex.alu), paddr);
             }
 
+            // The second half of a split that lands in another cache line is
+            // a second D-cache access, issued after the first. A load parked
+            // in an MSHR also waits for it before completing.
+            let mut split_line_latency = 0;
+            if let Some((hi_vaddr, _)) = split_hi
+                && paddr.val() >= cpu.cache_base
+                && unaligned::crosses_cache_line(ex.alu, size, line_bytes)
+            {
+                let hi_paddr = PhysAddr::new(paddr.val() + (hi_vaddr - ex.alu));
+                split_line_latency = cpu.simulate_memory_access(hi_paddr, access_type);
+                per_entry_latency += split_line_latency;
+            }
+
             // D-cache/bus latency: only cacheable addresses (RAM) go through
             // the cache hierarchy. MMIO addresses (below cache_base) bypass
             // caches entirely — they are uncacheable by nature.
             if paddr.val() >= cpu.cache_base && has_mshrs {
                 // ── Non-blocking path (MSHRs available) ──
                 let is_write = ex.ctrl.mem_write;
-                let l1d_hit = cpu.l1_d_cache.access_check(paddr.val(), is_write);
+                let l1d_hit = cpu.l1d_access_check(paddr.val(), is_write);
 
                 if l1d_hit {
                     cpu.stats.dcache_hits += 1;
@@ -290,8 +364,10 @@ pub fn memory1_stage(
                 } else {
                     // L1D miss — compute miss latency from L2/L3/DRAM
                     cpu.stats.dcache_misses += 1;
+                    let (next_level_latency, dram_request) =
+                        cpu.simulate_l1d_miss_latency(paddr, access_type);
                     let miss_latency =
-                        cpu.l1_d_cache.latency + cpu.simulate_l1d_miss_latency(paddr, access_type);
+                        cpu.l1_d_cache.latency + next_level_latency + split_line_latency;
                     trace_mem!(cpu.trace;
                         stage       = "M1",
                         rob_tag     = ex.rob_tag.0,
@@ -316,6 +392,9 @@ pub fn memory1_stage(
                             current_cycle,
                             waiter,
                         );
+                        if let Some(id) = dram_request {
+                            cpu.l1d_mshrs.await_dram(paddr.val(), &resp, id, current_cycle);
+                        }
                         match resp {
                             CacheResponse::MshrAllocated { .. } => {
                                 cpu.stats.mshr_allocations += 1;
@@ -378,6 +457,9 @@ pub fn memory1_stage(
                             current_cycle,
                             waiter,
                         );
+                        if let Some(id) = dram_request {
+                            cpu.l1d_mshrs.await_dram(paddr.val(), &resp, id, current_cycle);
+                        }
                         match resp {
                             CacheResponse::MshrAllocated { .. } => {
                                 cpu.stats.mshr_allocations += 1;
@@ -725,4 +807,44 @@ mod tests {
         assert_eq!(input[0].rob_tag.0, 2);
         assert_eq!(output.len(), 0);
     }
+
+    #[test]
+    fn test_memory1_port_limit_defers_second_load() {
+        let mut config = Config::default();
+        config.cache.l1_d_ports = 1;
+        let system = System::new(&config, "");
+        let mut cpu = Cpu::new(system, &config);
+
+        let ctrl = ControlSignals { mem_read: true, ..Default::default() };
+        let load = |tag: u32, alu: u64| ExMem1Entry {
+            rob_tag: crate::core::pipeline::rob::RobTag(tag),
+            pc: 0x1000,
+            inst: 0,
+            inst_size: InstSize::Standard,
+            rd: RegIdx::new(1),
+            alu,
+            store_data: 0,
+            ctrl,
+            trap: None,
+            exception_stage: None,
+            rd_phys: PhysReg(tag as u16),
+            fp_flags: 0,
+            sfence_vma: None,
+        };
+
+        let mut input = vec![load(1, 0x8000_0000), load(2, 0x8000_0008)];
+        let mut output = Vec::new();
+
+        memory1_stage(&mut cpu, &mut input, &mut output, 10, None);
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].rob_tag.0, 1);
+        assert_eq!(input.len(), 1);
+        assert_eq!(input[0].rob_tag.0, 2);
+        assert_eq!(cpu.stats.stalls_dcache_port, 1);
+
+        memory1_stage(&mut cpu, &mut input, &mut output, 11, None);
+        assert!(input.is_empty());
+        assert_eq!(output.len(), 2);
+        assert_eq!(output[1].rob_tag.0, 2);
+    }
 }
@@ -4,6 +4,7 @@
 //! For stores: resolve the store buffer entry with paddr + data (NO memory write).
 //! This stage is the same for both in-order and O3 backends.
 
+use crate::common::PhysAddr;
 use crate::common::error::{ExceptionStage, LrScRecord, Trap};
 use crate::core::Cpu;
 use crate::core::pipeline::latches::{Mem1Mem2Entry, Mem2WbEntry};
@@ -11,7 +12,7 @@ use crate::core::pipeline::load_queue::LoadQueue;
 use crate::core::pipeline::rob::{Rob, RobTag};
 use crate::core::pipeline::signals::{AtomicOp, MemWidth};
 use crate::core::pipeline::store_buffer::{ForwardResult, StoreBuffer};
-use crate::core::units::lsu::Lsu;
+use crate::core::units::lsu::{Lsu, unaligned};
 use crate::trace_fwd;
 use crate::trace_mem;
 use crate::trace_trap;
@@ -26,7 +27,7 @@ pub fn memory2_stage(
     input: &mut Vec<Mem1Mem2Entry>,
     output: &mut Vec<Mem2WbEntry>,
     store_buffer: &mut StoreBuffer,
-    _rob: &mut Rob,
+    rob: &mut Rob,
     mut load_queue: Option<&mut LoadQueue>,
 ) -> Option<(RobTag, u64)> {
     let mut violation: Option<(RobTag, u64)> = None;
@@ -77,15 +78,26 @@ pub fn memory2_stage(
         }
 
         let raw_paddr = mem.paddr;
-        let is_ram = raw_paddr.val() >= cpu.ram_start && raw_paddr.val() < cpu.ram_end;
-        let ram_offset = if is_ram { (raw_paddr.val() - cpu.ram_start) as usize } else { 0 };
+        let size = unaligned::width_to_bytes(mem.ctrl.width);
+        let is_ram = cpu.ram.contains(raw_paddr.val(), size);
+        if cpu.access_trace.is_some() {
+            rob.set_mem_paddr(mem.rob_tag, raw_paddr);
+        }
 
         let mut ld: u64 = 0;
-        let trap: Option<Trap> = None;
-        let exception_stage: Option<ExceptionStage> = None;
+        let mut trap: Option<Trap> = None;
+        let mut exception_stage: Option<ExceptionStage> = None;
         let mut lr_sc: Option<LrScRecord> = None;
 
         if mem.ctrl.atomic_op != AtomicOp::None {
+            // Release ordering: every older store must be globally visible
+            // before the atomic performs, so wait for the store buffer to
+            // drain them.
+            if mem.ctrl.rl && store_buffer.has_older_store(mem.rob_tag) {
+                input.push(mem);
+                input.extend(iter);
+                return violation;
+            }
             // Atomic operations
             match mem.ctrl.atomic_op {
                 AtomicOp::Lr => {
@@ -129,6 +141,52 @@ pub fn memory2_stage(
                         }
                     }
                 }
+                AtomicOp::Cas => {
+                    // Compare-and-swap is performed at commit on the
+                    // architectural rd/rs2 values (see LrScRecord::Cas).
+                    // Cancel the store buffer slot so the store is never
+                    // drained; rd gets a placeholder that commit replaces.
+                    store_buffer.cancel(mem.rob_tag);
+                    ld = 0;
+                    lr_sc = Some(LrScRecord::Cas { paddr: raw_paddr });
+                }
+                _ if !is_ram
+                    || cpu
+                        .htif_range
+                        .is_some_and(|(lo, hi)| raw_paddr.val() >= lo && raw_paddr.val() < hi) =>
+                {
+                    // AMO to a device region: device registers are not
+                    // idempotent, so the access must not be performed
+                    // speculatively or split across read and drain.  Wait
+                    // until the AMO is the oldest instruction and all older
+                    // stores have drained, then perform the read-modify-write
+                    // on the bus in one step.  The store buffer slot is
+                    // cancelled so commit does not write the value again.
+                    let at_head = rob.peek_head().is_some_and(|h| h.tag == mem.rob_tag);
+                    if !at_head || store_buffer.has_older_store(mem.rob_tag) {
+                        input.push(mem);
+                        input.extend(iter);
+                        return violation;
+                    }
+                    let size = if matches!(mem.ctrl.width, MemWidth::Double) { 8 } else { 4 };
+                    let (op, width, src) = (mem.ctrl.atomic_op, mem.ctrl.width, mem.store_data);
+                    let old_raw = cpu.bus.bus.read_modify_write(raw_paddr, size, |old| {
+                        let old = if size == 4 { (old as u32 as i32) as i64 as u64 } else { old };
+                        Lsu::atomic_alu(op, old, src, width)
+                    });
+                    store_buffer.cancel(mem.rob_tag);
+                    ld = if size == 4 { (old_raw as u32 as i32) as i64 as u64 } else { old_raw };
+                    trace_mem!(cpu.trace;
+                        stage   = "M2",
+                        rob_tag = mem.rob_tag.0,
+                        pc      = %crate::trace::Hex(mem.pc),
+                        op      = "amo-device",
+                        paddr   = %crate::trace::Hex(raw_paddr.val()),
+                        width   = ?mem.ctrl.width,
+                        old     = %crate::trace::Hex(ld),
+                        "M2: AMO performed as bus read-modify-write"
+                    );
+                }
                 _ => {
                     // AMO: atomic read-modify-write must operate on the
                     // globally-visible value.  Stall until all older stores
@@ -179,6 +237,7 @@ pub fn memory2_stage(
             // Check store buffer for forwarding first
             match store_buffer.forward_load(raw_paddr, mem.ctrl.width, mem.rob_tag) {
                 ForwardResult::Hit(forwarded) => {
+                    cpu.stats.store_forwards += 1;
                     // Apply sign extension for signed loads (LB, LH, LW on RV64).
                     // The store buffer returns raw masked data without sign extension.
                     ld = if mem.ctrl.signed_load {
@@ -219,7 +278,8 @@ pub fn memory2_stage(
                     );
                 }
                 ForwardResult::Stall => {
-                    // Partial overlap — push back current + remaining entries
+                    // Partial coverage — push back current + remaining entries
+                    cpu.stats.store_forward_stalls += 1;
                     trace_fwd!(cpu.trace;
                         event           = "stall",
                         load_pc         = %crate::trace::Hex(mem.pc),
@@ -227,7 +287,7 @@ pub fn memory2_stage(
                         paddr           = %crate::trace::Hex(raw_paddr.val()),
                         width           = ?mem.ctrl.width,
                         partial_overlap = true,
-                        "M2: store-to-load forwarding STALL (partial overlap)"
+                        "M2: store-to-load forwarding STALL (partial coverage)"
                     );
                     input.push(mem);
                     input.extend(iter);
@@ -244,34 +304,35 @@ pub fn memory2_stage(
                         is_ram,
                         "M2: store buffer miss — reading from memory"
                     );
+                    if is_ram
+                        && cpu.faults.is_armed()
+                        && !cpu.faults.check_load(&cpu.ram, &mut cpu.stats, raw_paddr.val(), size)
+                    {
+                        // Uncorrectable ECC error: the load faults instead
+                        // of returning poisoned data.
+                        trap = Some(Trap::LoadAccessFault(mem.vaddr.val()));
+                        exception_stage = Some(ExceptionStage::Memory);
+                    }
                     ld = if is_ram {
-                        unsafe {
-                            match (mem.ctrl.width, mem.ctrl.signed_load) {
-                                (MemWidth::Byte, true) => {
-                                    (*cpu.ram_ptr.add(ram_offset) as i8) as i64 as u64
-                                }
-                                (MemWidth::Half, true) => {
-                                    ((cpu.ram_ptr.add(ram_offset) as *const u16).read_unaligned()
-                                        as i16) as i64 as u64
-                                }
-                                (MemWidth::Word, true) => {
-                                    ((cpu.ram_ptr.add(ram_offset) as *const u32).read_unaligned()
-                                        as i32) as i64 as u64
-                                }
-                                (MemWidth::Byte, false) => *cpu.ram_ptr.add(ram_offset) as u64,
-                                (MemWidth::Half, false) => {
-                                    (cpu.ram_ptr.add(ram_offset) as *const u16).read_unaligned()
-                                        as u64
-                                }
-                                (MemWidth::Word, false) => {
-                                    (cpu.ram_ptr.add(ram_offset) as *const u32).read_unaligned()
-                                        as u64
-                                }
-                                (MemWidth::Double, _) => {
-                                    (cpu.ram_ptr.add(ram_offset) as *const u64).read_unaligned()
-                                }
-                                _ => 0,
+                        let shift = 64 - 8 * size;
+                        cpu.ram.read(raw_paddr.val(), size).map_or(0, |val| {
+                            if mem.ctrl.signed_load {
+                                (((val << shift) as i64) >> shift) as u64
+                            } else {
+                                val
                             }
+                        })
+                    } else if unaligned::needs_split(raw_paddr.val(), size) {
+                        // Emulated misaligned access: combine the aligned
+                        // sub-accesses byte by byte, then sign-extend.
+                        let val = unaligned::split_load(raw_paddr.val(), size, |a| {
+                            cpu.bus.bus.read_u8(PhysAddr::new(a))
+                        });
+                        let shift = 64 - 8 * size;
+                        if mem.ctrl.signed_load {
+                            (((val << shift) as i64) >> shift) as u64
+                        } else {
+                            val
                         }
                     } else {
                         match (mem.ctrl.width, mem.ctrl.signed_load) {
@@ -1,5 +1,6 @@
 //! Shared backend stages used by all backend implementations.
 
+pub mod cbo;
 pub mod commit;
 pub mod memory1;
 pub mod memory2;
@@ -7,16 +7,30 @@
 use crate::common::ExceptionStage;
 use crate::core::Cpu;
 use crate::core::pipeline::latches::Mem2WbEntry;
+use crate::core::pipeline::pipe_trace::PipeStage;
 use crate::core::pipeline::rob::Rob;
 use crate::core::pipeline::signals::ControlFlow;
 use crate::trace_trap;
 use crate::trace_writeback;
 
+/// Returns the value `wb` writes to its destination register: the loaded
+/// data for loads, the link address for jumps, the ALU result otherwise.
+pub const fn result_value(wb: &Mem2WbEntry) -> u64 {
+    if wb.ctrl.mem_read {
+        wb.load_data
+    } else if matches!(wb.ctrl.control_flow, ControlFlow::Jump) {
+        wb.pc.wrapping_add(wb.inst_size.as_u64())
+    } else {
+        wb.alu
+    }
+}
+
 /// Executes the Writeback stage: mark ROB entries Completed.
 pub fn writeback_stage(cpu: &mut Cpu, input: &mut Vec<Mem2WbEntry>, rob: &mut Rob) {
     let entries = std::mem::take(input);
 
     for wb in entries {
+        cpu.pipe_event_tag(wb.rob_tag, PipeStage::Writeback);
         if let Some(ref trap) = wb.trap {
             // Mark as faulted
             rob.fault(
@@ -35,14 +49,7 @@ pub fn writeback_stage(cpu: &mut Cpu, input: &mut Vec<Mem2WbEntry>, rob: &mut Ro
             continue;
         }
 
-        // Compute the result value
-        let val = if wb.ctrl.mem_read {
-            wb.load_data
-        } else if wb.ctrl.control_flow == ControlFlow::Jump {
-            wb.pc.wrapping_add(wb.inst_size.as_u64())
-        } else {
-            wb.alu
-        };
+        let val = result_value(&wb);
 
         if wb.fp_flags != 0 {
             rob.set_fp_flags(wb.rob_tag, wb.fp_flags);
@@ -1,25 +1,28 @@
 //! Checkpoint table for O(1) branch recovery.
 //!
-//! A `CheckpointTable` stores snapshots of the speculative rename map taken
-//! at branch/jump dispatch time. On a misprediction, the rename map is
+//! A `CheckpointTable` stores snapshots of the speculative rename map and
+//! scoreboard taken at branch/jump dispatch time. On a misprediction, both are
 //! restored directly from the checkpoint instead of walking the entire
-//! surviving ROB (`rebuild_rename_map()`), reducing recovery from O(ROB size)
-//! to O(1).
+//! surviving ROB (`rebuild_rename_map()` / `Scoreboard::rebuild_from_rob()`),
+//! reducing recovery from O(ROB size) to O(1).
 
 use super::rename_map::RenameMap;
 use super::rob::RobTag;
+use super::scoreboard::Scoreboard;
 
 /// Index into the checkpoint table.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct CheckpointId(pub u8);
 
-/// A single rename map snapshot associated with a branch/jump.
+/// A single rename map and scoreboard snapshot associated with a branch/jump.
 #[derive(Clone, Debug)]
 pub struct Checkpoint {
     /// ROB tag of the branch/jump that owns this checkpoint.
     pub branch_tag: RobTag,
-    /// Rename map snapshot taken *after* the branch's own rd rename.
+    /// Rename map snapshot taken *after* the branch's own rd rename (O3).
     pub rename_map: RenameMap,
+    /// Scoreboard snapshot taken *after* the branch's own rd marking (in-order).
+    pub scoreboard: Scoreboard,
 }
 
 /// Fixed-size table of checkpoint slots.
@@ -55,12 +58,17 @@ impl CheckpointTable {
         self.slots.len() - self.count
     }
 
-    /// Allocates a checkpoint slot, saving `rename_map` for `branch_tag`.
-    /// Returns `None` if the table is full.
-    pub fn allocate(&mut self, branch_tag: RobTag, rename_map: &RenameMap) -> Option<CheckpointId> {
+    /// Allocates a checkpoint slot, saving `rename_map` and `scoreboard` for
+    /// `branch_tag`. Returns `None` if the table is full.
+    pub fn allocate(
+        &mut self,
+        branch_tag: RobTag,
+        rename_map: RenameMap,
+        scoreboard: Scoreboard,
+    ) -> Option<CheckpointId> {
         for (i, slot) in self.slots.iter_mut().enumerate() {
             if slot.is_none() {
-                *slot = Some(Checkpoint { branch_tag, rename_map: rename_map.clone() });
+                *slot = Some(Checkpoint { branch_tag, rename_map, scoreboard });
                 self.count += 1;
                 return Some(CheckpointId(i as u8));
             }
@@ -123,13 +131,16 @@ mod tests {
         assert!(!table.is_full());
 
         let rm = make_rename_map(100);
+        let mut sb = Scoreboard::new();
+        sb.set_producer(RegIdx::new(5), false, RobTag(9));
         let tag = RobTag(10);
-        let id = table.allocate(tag, &rm).unwrap();
+        let id = table.allocate(tag, rm, sb).unwrap();
         assert_eq!(table.available(), 3);
 
         let ckpt = table.find_by_tag(tag).unwrap();
         assert_eq!(ckpt.branch_tag, tag);
         assert_eq!(ckpt.rename_map.get(RegIdx::new(1), false), PhysReg(100));
+        assert_eq!(ckpt.scoreboard.get_producer(RegIdx::new(5), false), Some(RobTag(9)));
 
         table.free(id);
         assert_eq!(table.available(), 4);
@@ -140,20 +151,20 @@ mod tests {
     fn test_full_table() {
         let mut table = CheckpointTable::new(2);
         let rm = make_rename_map(1);
-        table.allocate(RobTag(1), &rm).unwrap();
-        table.allocate(RobTag(2), &rm).unwrap();
+        table.allocate(RobTag(1), rm.clone(), Scoreboard::new()).unwrap();
+        table.allocate(RobTag(2), rm.clone(), Scoreboard::new()).unwrap();
         assert!(table.is_full());
-        assert!(table.allocate(RobTag(3), &rm).is_none());
+        assert!(table.allocate(RobTag(3), rm, Scoreboard::new()).is_none());
     }
 
     #[test]
     fn test_flush_after() {
         let mut table = CheckpointTable::new(4);
         let rm = make_rename_map(1);
-        table.allocate(RobTag(1), &rm).unwrap();
-        table.allocate(RobTag(2), &rm).unwrap();
-        table.allocate(RobTag(3), &rm).unwrap();
-        table.allocate(RobTag(4), &rm).unwrap();
+        table.allocate(RobTag(1), rm.clone(), Scoreboard::new()).unwrap();
+        table.allocate(RobTag(2), rm.clone(), Scoreboard::new()).unwrap();
+        table.allocate(RobTag(3), rm.clone(), Scoreboard::new()).unwrap();
+        table.allocate(RobTag(4), rm, Scoreboard::new()).unwrap();
         assert!(table.is_full());
 
         // Keep tag 2, flush tags 3 and 4
@@ -169,8 +180,8 @@ mod tests {
     fn test_flush_all() {
         let mut table = CheckpointTable::new(4);
         let rm = make_rename_map(1);
-        table.allocate(RobTag(1), &rm).unwrap();
-        table.allocate(RobTag(2), &rm).unwrap();
+        table.allocate(RobTag(1), rm.clone(), Scoreboard::new()).unwrap();
+        table.allocate(RobTag(2), rm, Scoreboard::new()).unwrap();
         table.flush_all();
         assert_eq!(table.available(), 4);
         assert!(table.find_by_tag(RobTag(1)).is_none());
@@ -182,7 +193,7 @@ mod tests {
         assert!(table.is_full());
         assert_eq!(table.available(), 0);
         let rm = make_rename_map(1);
-        assert!(table.allocate(RobTag(1), &rm).is_none());
+        assert!(table.allocate(RobTag(1), rm, Scoreboard::new()).is_none());
         // flush_after and flush_all should be no-ops
         table.flush_after(RobTag(1));
         table.flush_all();
@@ -6,6 +6,7 @@
 //! 3. **`ExecutionEngine`** — high-level trait covering the entire backend.
 //! 4. **`PipelineDispatch`** — enum dispatch for type-erased pipeline storage.
 
+use crate::core::pipeline::backend::o3::fu_pool::FuType;
 use crate::core::pipeline::checkpoint::CheckpointTable;
 use crate::core::pipeline::free_list::FreeList;
 use crate::core::pipeline::latches::RenameIssueEntry;
@@ -16,6 +17,7 @@ use crate::core::pipeline::rob::Rob;
 use crate::core::pipeline::scoreboard::Scoreboard;
 use crate::core::pipeline::snapshot::PipelineSnapshot;
 use crate::core::pipeline::store_buffer::StoreBuffer;
+use crate::stats::StallCause;
 use serde::Deserialize;
 
 /// Backend type selection.
@@ -42,7 +44,11 @@ pub trait ExecutionEngine {
     /// Flush all speculative state. Committed stores in the store buffer remain.
     fn flush(&mut self, cpu: &mut crate::core::Cpu);
 
-    /// Read a CSR, checking in-flight `CsrUpdate` entries in the ROB.
+    /// Read a CSR as a CSR instruction issuing now would see it.
+    ///
+    /// CSR instructions do not issue while an older `CsrUpdate` is pending in
+    /// the ROB (see [`Rob::has_pending_csr_write_before`]), so no forwarding is
+    /// needed and this is the architectural value.
     fn read_csr_speculative(&self, cpu: &crate::core::Cpu, addr: crate::common::CsrAddr) -> u64;
 
     /// Access the scoreboard (for rename to mark producers, issue to check readiness).
@@ -105,6 +111,11 @@ pub trait ExecutionEngine {
     fn checkpoint_count(&self) -> usize {
         0
     }
+
+    /// Returns the number of instructions waiting in the issue queue.
+    fn issue_queue_len(&self) -> usize {
+        0
+    }
 }
 
 /// The full pipeline combines a frontend and an engine.
@@ -124,7 +135,17 @@ pub struct Pipeline<E: ExecutionEngine> {
 impl<E: ExecutionEngine> Pipeline<E> {
     /// Run one cycle of the entire pipeline.
     pub fn tick(&mut self, cpu: &mut crate::core::Cpu) {
+        if self.is_parked(cpu) {
+            cpu.stats.cycles_wfi += 1;
+            cpu.stats.retire_histogram[0] += 1;
+            if cpu.sample_occupancy {
+                self.sample_occupancy(cpu);
+            }
+            return;
+        }
+
         let pc_before = cpu.pc;
+        let before = StallCounters::read(&cpu.stats);
 
         // Backend always runs (commit/writeback/memory must drain even during stalls)
         self.engine.tick(cpu, &mut self.rename_output);
@@ -142,25 +163,192 @@ impl<E: ExecutionEngine> Pipeline<E> {
         // - pc != pc_before catches commit-stage redirects (MRET/SRET) that
         //   don't go through the execute stage's flush path.
         let needs_frontend_flush = cpu.redirect_pending || cpu.pc != pc_before;
+        let mispredicted = std::mem::take(&mut cpu.mispredict_pending);
         if cpu.redirect_pending {
             cpu.redirect_pending = false;
         }
         if needs_frontend_flush {
-            self.frontend.flush();
+            if mispredicted {
+                self.frontend.flush_mispredict();
+            } else {
+                self.frontend.flush();
+            }
+            self.frontend.refill_seq = Some(cpu.next_seq);
             self.rename_output.clear();
+            self.squash_trace(cpu);
         }
 
         // Frontend runs every cycle (per-stage stalls are handled internally)
         if cpu.exit_code.is_none() && !cpu.wfi_waiting {
             self.frontend.tick(cpu, &mut self.engine, &mut self.rename_output);
         }
+
+        self.attribute_stall(cpu, &before);
+        if cpu.sample_occupancy {
+            self.sample_occupancy(cpu);
+        }
+    }
+
+    /// Returns `true` if the hart is parked in WFI with nothing left to do.
+    ///
+    /// Once a WFI has committed, the backend has drained its older stores and
+    /// misses, and no enabled interrupt is pending, every stage would idle
+    /// until one is, so the cycle is skipped. `mtime` and the interrupt lines
+    /// keep advancing in [`Cpu::pre_tick`](crate::core::Cpu::pre_tick), which
+    /// wakes the hart through the commit-stage interrupt poll.
+    fn is_parked(&self, cpu: &crate::core::Cpu) -> bool {
+        cpu.wfi_waiting
+            && cpu.csrs.mip & cpu.csrs.mie == 0
+            && self.engine.rob().is_empty()
+            && self.engine.store_buffer().is_empty()
+            && cpu.l1d_mshrs.active_count() == 0
+    }
+
+    /// Formats the ROB and store buffer contents, oldest first, for hang
+    /// reports.
+    pub fn format_backend(&self) -> String {
+        use crate::core::pipeline::store_buffer::StoreResolution;
+        use std::fmt::Write;
+
+        let rob = self.engine.rob();
+        let mut out = format!("ROB ({}/{}):\n", rob.len(), rob.capacity());
+        for e in rob.iter_in_order() {
+            let _ = write!(
+                out,
+                "  #{:<4} {:#018x}: {:<28} {:?}",
+                e.tag.0,
+                e.pc,
+                crate::isa::disasm::disassemble(e.inst),
+                e.state
+            );
+            if let Some(trap) = &e.trap {
+                let _ = write!(out, " ({trap:?})");
+            }
+            out.push('\n');
+        }
+        let sb = self.engine.store_buffer();
+        let _ = writeln!(out, "store buffer ({}/{}):", sb.len(), sb.capacity());
+        for e in sb.iter() {
+            let state = match e.resolution {
+                StoreResolution::Pending => "pending".to_owned(),
+                StoreResolution::Ready { paddr, data } => {
+                    format!("ready     [{:#x}] <- {data:#x}", paddr.val())
+                }
+                StoreResolution::Committed { paddr, data } => {
+                    format!("committed [{:#x}] <- {data:#x}", paddr.val())
+                }
+                StoreResolution::Cancelled => "cancelled".to_owned(),
+            };
+            let _ = writeln!(out, "  #{:<4} {:<6} {state}", e.rob_tag.0, format!("{:?}", e.width));
+        }
+        out
+    }
+
+    /// Records this cycle's ROB, store buffer, and issue queue occupancy.
+    fn sample_occupancy(&self, cpu: &mut crate::core::Cpu) {
+        cpu.stats.rob_occupancy.record(self.engine.rob().len());
+        cpu.stats.store_buffer_occupancy.record(self.engine.store_buffer().len());
+        cpu.stats.issue_queue_occupancy.record(self.engine.issue_queue_len());
+    }
+
+    /// Charges this cycle to a [`StallCause`] if nothing retired in it.
+    ///
+    /// With an empty ROB the frontend is to blame: an I-TLB or I-cache miss,
+    /// refilling after a redirect, or otherwise nothing decoded. With a
+    /// non-empty ROB the head is inspected: a memory operation waits on the
+    /// D-cache and a multiply/divide/FP operation on its unit; failing that,
+    /// a full ROB or store buffer blocking dispatch, memory pipeline
+    /// backpressure, or a redirect is charged before the head's operands.
+    fn attribute_stall(&mut self, cpu: &mut crate::core::Cpu, before: &StallCounters) {
+        if self.frontend.refill_seq.is_some_and(|seq| cpu.retired_seq >= seq) {
+            self.frontend.refill_seq = None;
+        }
+        let now = StallCounters::read(&cpu.stats);
+        if now.retire_none == before.retire_none || now.wfi != before.wfi {
+            return;
+        }
+        let refilling = self.frontend.refill_seq.is_some();
+        let rob_empty = now.rob_empty != before.rob_empty;
+        let dispatch_blocked = now.dispatch != before.dispatch;
+        let sb_full = dispatch_blocked && self.engine.store_buffer().free_slots() == 0;
+        let cause = if rob_empty {
+            if sb_full {
+                StallCause::StoreBufferFull
+            } else if self.frontend.fetch1_stall > 0 {
+                StallCause::ITlbMiss
+            } else if self.frontend.fetch2_stall > 0 {
+                StallCause::ICacheMiss
+            } else if refilling {
+                StallCause::BranchRecovery
+            } else {
+                StallCause::DecodeStarvation
+            }
+        } else {
+            let head_fu = self.engine.rob().peek_head().map(|e| FuType::classify(&e.ctrl));
+            match head_fu {
+                Some(FuType::Mem) => StallCause::DCacheMiss,
+                Some(fu) if !matches!(fu, FuType::IntAlu | FuType::Branch) => {
+                    StallCause::LongLatencyFu
+                }
+                _ if dispatch_blocked && self.engine.rob().is_full() => StallCause::RobFull,
+                _ if sb_full => StallCause::StoreBufferFull,
+                _ if now.backpressure != before.backpressure => StallCause::Backpressure,
+                _ if refilling => StallCause::BranchRecovery,
+                _ => StallCause::Scoreboard,
+            }
+        };
+        cpu.stats.stall_cycles[cause as usize] += 1;
     }
 
     /// Flush the entire pipeline.
     pub fn flush(&mut self, cpu: &mut crate::core::Cpu) {
         self.frontend.flush();
+        self.frontend.refill_seq = Some(cpu.next_seq);
         self.rename_output.clear();
         self.engine.flush(cpu);
+        self.squash_trace(cpu);
+    }
+
+    /// Brings the hart to an instruction boundary: flushes the pipeline,
+    /// writes every committed store to memory and points `cpu.pc` at the
+    /// next instruction to commit, so the architectural state is complete.
+    pub fn quiesce(&mut self, cpu: &mut crate::core::Cpu) {
+        self.flush(cpu);
+        crate::core::pipeline::backend::shared::commit::drain_all_committed(
+            cpu,
+            self.engine.store_buffer_mut(),
+        );
+        cpu.pc = cpu.committed_next_pc;
+    }
+
+    /// Marks instructions that no longer have a ROB entry as flushed in the
+    /// pipeline trace.
+    fn squash_trace(&self, cpu: &mut crate::core::Cpu) {
+        if let Some(trace) = &mut cpu.pipe_trace {
+            trace.squash_except(self.engine.rob().iter_all().map(|e| e.seq), cpu.stats.cycles);
+        }
+    }
+}
+
+/// Stall-related counters sampled around a cycle to see what changed in it.
+#[derive(Clone, Copy, Debug)]
+struct StallCounters {
+    retire_none: u64,
+    wfi: u64,
+    rob_empty: u64,
+    dispatch: u64,
+    backpressure: u64,
+}
+
+impl StallCounters {
+    const fn read(stats: &crate::stats::SimStats) -> Self {
+        Self {
+            retire_none: stats.retire_histogram[0],
+            wfi: stats.cycles_wfi,
+            rob_empty: stats.cycles_rob_empty,
+            dispatch: stats.stalls_dispatch,
+            backpressure: stats.stalls_backpressure,
+        }
     }
 }
 
@@ -182,6 +370,14 @@ impl PipelineDispatch {
         }
     }
 
+    /// Formats the ROB and store buffer contents (see [`Pipeline::format_backend`]).
+    pub fn format_backend(&self) -> String {
+        match self {
+            Self::InOrder(p) => p.format_backend(),
+            Self::OutOfOrder(p) => p.format_backend(),
+        }
+    }
+
     /// Flush.
     pub fn flush(&mut self, cpu: &mut crate::core::Cpu) {
         match self {
@@ -190,12 +386,20 @@ impl PipelineDispatch {
         }
     }
 
+    /// Quiesce (see [`Pipeline::quiesce`]).
+    pub fn quiesce(&mut self, cpu: &mut crate::core::Cpu) {
+        match self {
+            Self::InOrder(p) => p.quiesce(cpu),
+            Self::OutOfOrder(p) => p.quiesce(cpu),
+        }
+    }
+
     /// Capture a point-in-time snapshot of all inter-stage latch contents.
     pub fn snapshot(&self, width: usize) -> PipelineSnapshot {
         match self {
             Self::InOrder(p) => PipelineSnapshot {
-                fetch1_fetch2: p.frontend.fetch1_fetch2.clone(),
-                fetch2_decode: p.frontend.fetch2_decode.clone(),
+                fetch1_fetch2: p.frontend.ftq.iter().flatten().cloned().collect(),
+                fetch2_decode: p.frontend.fetch_buffer.clone(),
                 decode_rename: p.frontend.decode_rename.clone(),
                 rename_issue: p.rename_output.clone(),
                 issue_queue: p.engine.issuer.queue_snapshot(),
@@ -208,8 +412,8 @@ impl PipelineDispatch {
                 width,
             },
             Self::OutOfOrder(p) => PipelineSnapshot {
-                fetch1_fetch2: p.frontend.fetch1_fetch2.clone(),
-                fetch2_decode: p.frontend.fetch2_decode.clone(),
+                fetch1_fetch2: p.frontend.ftq.iter().flatten().cloned().collect(),
+                fetch2_decode: p.frontend.fetch_buffer.clone(),
                 decode_rename: p.frontend.decode_rename.clone(),
                 rename_issue: p.rename_output.clone(),
                 issue_queue: p.engine.issue_queue.queue_snapshot(),
@@ -327,7 +531,7 @@ mod tests {
         let system = crate::soc::builder::System::new(&config, "");
         let mut cpu = crate::core::Cpu::new(system, &config);
 
-        let frontend = crate::core::pipeline::frontend::Frontend::new(config.pipeline.width);
+        let frontend = crate::core::pipeline::frontend::Frontend::new(&config);
         let engine = crate::core::pipeline::backend::inorder::InOrderEngine::new(&config);
         let pipeline = Pipeline { frontend, engine, rename_output: Vec::new() };
         let mut dispatch = PipelineDispatch::InOrder(Box::new(pipeline));
@@ -10,9 +10,11 @@
 use crate::common::RegIdx;
 use crate::common::error::{ExceptionStage, Trap};
 use crate::core::Cpu;
+use crate::core::arch::csr::{MISA_EXT_A, MISA_EXT_D, MISA_EXT_F, MISA_EXT_M};
+use crate::core::pipeline::frontend::fusion;
 use crate::core::pipeline::latches::{IdExEntry, IfIdEntry};
 use crate::core::pipeline::signals::{
-    AluOp, AtomicOp, ControlFlow, ControlSignals, CsrOp, MemWidth, OpASrc, OpBSrc, SystemOp,
+    AluOp, AtomicOp, CboOp, ControlFlow, ControlSignals, CsrOp, MemWidth, OpASrc, OpBSrc, SystemOp,
 };
 use crate::isa::decode::decode as instruction_decode;
 use crate::isa::instruction::{Decoded, InstructionBits};
@@ -23,6 +25,8 @@ use crate::isa::rv64d::{funct7 as d_funct7, opcodes as d_opcodes};
 use crate::isa::rv64f::{funct3 as f_funct3, funct7 as f_funct7, opcodes as f_opcodes};
 use crate::isa::rv64i::{funct3 as i_funct3, funct7 as i_funct7, opcodes as i_opcodes};
 use crate::isa::rv64m::{funct3 as m_funct3, opcodes as m_opcodes};
+use crate::isa::zicbo::opcodes as cbo_ops;
+use crate::isa::zicond::opcodes as zicond_opcodes;
 
 /// ADDI x0, x0, 0 instruction encoding (canonical NOP).
 const INSTRUCTION_NOP: u32 = 0x0000_0013;
@@ -43,7 +47,22 @@ const FP_FMT_SINGLE: u32 = 0;
 const FP_FMT_DOUBLE: u32 = 1;
 
 /// Decodes a single instruction into control signals.
-fn decode_instruction(inst: u32, pc: u64, d: &Decoded) -> Result<ControlSignals, Trap> {
+///
+/// Reserved encodings, and instructions from an M/A/F/D extension whose
+/// `misa` bit is clear, return [`Trap::IllegalInstruction`] carrying the
+/// instruction bits. `zacas` enables the Zacas compare-and-swap encodings and
+/// `zicond` the Zicond conditional-zero encodings; when false they decode as
+/// illegal instructions.
+pub(crate) fn decode_instruction(
+    inst: u32,
+    pc: u64,
+    d: &Decoded,
+    misa: u64,
+    zacas: bool,
+    zicond: bool,
+) -> Result<ControlSignals, Trap> {
+    let has = |ext: u64| misa & ext != 0;
+
     let mut c = ControlSignals {
         a_src: OpASrc::Reg1,
         b_src: OpBSrc::Imm,
@@ -65,11 +84,18 @@ fn decode_instruction(inst: u32, pc: u64, d: &Decoded) -> Result<ControlSignals,
             c.control_flow = ControlFlow::Jump;
         }
         i_opcodes::OP_JALR => {
+            if d.funct3 != 0 {
+                return Err(Trap::IllegalInstruction(inst));
+            }
             c.reg_write = true;
             c.control_flow = ControlFlow::Jump;
             c.alu = AluOp::Add;
         }
         i_opcodes::OP_BRANCH => {
+            // funct3 0b010 and 0b011 are reserved.
+            if matches!(d.funct3, 0b010 | 0b011) {
+                return Err(Trap::IllegalInstruction(inst));
+            }
             c.control_flow = ControlFlow::Branch;
             c.b_src = OpBSrc::Reg2;
         }
@@ -105,21 +131,19 @@ fn decode_instruction(inst: u32, pc: u64, d: &Decoded) -> Result<ControlSignals,
         i_opcodes::OP_IMM | i_opcodes::OP_IMM_32 => {
             c.reg_write = true;
             c.is_rv32 = d.opcode == i_opcodes::OP_IMM_32;
-            c.alu = match d.funct3 {
-                i_funct3::ADD_SUB => AluOp::Add,
-                i_funct3::SLT => AluOp::Slt,
-                i_funct3::SLTU => AluOp::Sltu,
-                i_funct3::XOR => AluOp::Xor,
-                i_funct3::OR => AluOp::Or,
-                i_funct3::AND => AluOp::And,
-                i_funct3::SLL => AluOp::Sll,
-                i_funct3::SRL_SRA => {
-                    if (d.funct7 & FUNCT7_ALT_BIT) != 0 {
-                        AluOp::Sra
-                    } else {
-                        AluOp::Srl
-                    }
-                }
+            // RV64 shifts take a 6-bit shamt, so bit 25 belongs to it; the
+            // word shifts take a 5-bit shamt and check the full funct7.
+            let shift_funct = if c.is_rv32 { d.funct7 } else { d.funct7 & !1 };
+            c.alu = match (d.funct3, shift_funct) {
+                (i_funct3::ADD_SUB, _) => AluOp::Add,
+                (i_funct3::SLT, _) if !c.is_rv32 => AluOp::Slt,
+                (i_funct3::SLTU, _) if !c.is_rv32 => AluOp::Sltu,
+                (i_funct3::XOR, _) if !c.is_rv32 => AluOp::Xor,
+                (i_funct3::OR, _) if !c.is_rv32 => AluOp::Or,
+                (i_funct3::AND, _) if !c.is_rv32 => AluOp::And,
+                (i_funct3::SLL, i_funct7::DEFAULT) => AluOp::Sll,
+                (i_funct3::SRL_SRA, i_funct7::DEFAULT) => AluOp::Srl,
+                (i_funct3::SRL_SRA, FUNCT7_ALT_BIT) => AluOp::Sra,
                 _ => return Err(Trap::IllegalInstruction(inst)),
             };
         }
@@ -129,41 +153,68 @@ fn decode_instruction(inst: u32, pc: u64, d: &Decoded) -> Result<ControlSignals,
             c.b_src = OpBSrc::Reg2;
 
             if d.funct7 == m_opcodes::M_EXTENSION {
+                if !has(MISA_EXT_M) {
+                    return Err(Trap::IllegalInstruction(inst));
+                }
                 c.alu = match d.funct3 {
                     m_funct3::MUL => AluOp::Mul,
-                    m_funct3::MULH => AluOp::Mulh,
-                    m_funct3::MULHSU => AluOp::Mulhsu,
-                    m_funct3::MULHU => AluOp::Mulhu,
+                    m_funct3::MULH if !c.is_rv32 => AluOp::Mulh,
+                    m_funct3::MULHSU if !c.is_rv32 => AluOp::Mulhsu,
+                    m_funct3::MULHU if !c.is_rv32 => AluOp::Mulhu,
                     m_funct3::DIV => AluOp::Div,
                     m_funct3::DIVU => AluOp::Divu,
                     m_funct3::REM => AluOp::Rem,
                     m_funct3::REMU => AluOp::Remu,
                     _ => return Err(Trap::IllegalInstruction(inst)),
                 };
+            } else if d.funct7 == zicond_opcodes::ZICOND {
+                if !zicond || c.is_rv32 {
+                    return Err(Trap::IllegalInstruction(inst));
+                }
+                c.alu = match d.funct3 {
+                    zicond_opcodes::CZERO_EQZ => AluOp::CzeroEqz,
+                    zicond_opcodes::CZERO_NEZ => AluOp::CzeroNez,
+                    _ => return Err(Trap::IllegalInstruction(inst)),
+                };
             } else {
                 c.alu = match (d.funct3, d.funct7) {
                     (i_funct3::ADD_SUB, i_funct7::DEFAULT) => AluOp::Add,
                     (i_funct3::ADD_SUB, i_funct7::SUB) => AluOp::Sub,
                     (i_funct3::SLL, i_funct7::DEFAULT) => AluOp::Sll,
-                    (i_funct3::SLT, i_funct7::DEFAULT) => AluOp::Slt,
-                    (i_funct3::SLTU, i_funct7::DEFAULT) => AluOp::Sltu,
-                    (i_funct3::XOR, i_funct7::DEFAULT) => AluOp::Xor,
+                    (i_funct3::SLT, i_funct7::DEFAULT) if !c.is_rv32 => AluOp::Slt,
+                    (i_funct3::SLTU, i_funct7::DEFAULT) if !c.is_rv32 => AluOp::Sltu,
+                    (i_funct3::XOR, i_funct7::DEFAULT) if !c.is_rv32 => AluOp::Xor,
                     (i_funct3::SRL_SRA, i_funct7::DEFAULT) => AluOp::Srl,
                     (i_funct3::SRL_SRA, i_funct7::SRA) => AluOp::Sra,
-                    (i_funct3::OR, i_funct7::DEFAULT) => AluOp::Or,
-                    (i_funct3::AND, i_funct7::DEFAULT) => AluOp::And,
+                    (i_funct3::OR, i_funct7::DEFAULT) if !c.is_rv32 => AluOp::Or,
+                    (i_funct3::AND, i_funct7::DEFAULT) if !c.is_rv32 => AluOp::And,
                     _ => return Err(Trap::IllegalInstruction(inst)),
                 };
             }
         }
         a_opcodes::OP_AMO => {
+            if !has(MISA_EXT_A) {
+                return Err(Trap::IllegalInstruction(inst));
+            }
+            let f5 = d.funct7 >> 2;
+            let is_cas = f5 == a_funct5::AMOCAS;
+            if is_cas && !zacas {
+                return Err(Trap::IllegalInstruction(inst));
+            }
             c.width = match d.funct3 {
                 a_funct3::WIDTH_32 => MemWidth::Word,
                 a_funct3::WIDTH_64 => MemWidth::Double,
+                // amocas.q operates on even/odd register pairs.
+                a_funct3::WIDTH_128
+                    if is_cas
+                        && d.rd.as_usize().is_multiple_of(2)
+                        && d.rs2.as_usize().is_multiple_of(2) =>
+                {
+                    MemWidth::Quad
+                }
                 _ => return Err(Trap::IllegalInstruction(inst)),
             };
 
-            let f5 = d.funct7 >> 2;
             c.atomic_op = match f5 {
                 a_funct5::LR => AtomicOp::Lr,
                 a_funct5::SC => AtomicOp::Sc,
@@ -176,6 +227,7 @@ fn decode_instruction(inst: u32, pc: u64, d: &Decoded) -> Result<ControlSignals,
                 a_funct5::AMOMAX => AtomicOp::Max,
                 a_funct5::AMOMINU => AtomicOp::Minu,
                 a_funct5::AMOMAXU => AtomicOp::Maxu,
+                a_funct5::AMOCAS => AtomicOp::Cas,
                 _ => return Err(Trap::IllegalInstruction(inst)),
             };
 
@@ -185,14 +237,16 @@ fn decode_instruction(inst: u32, pc: u64, d: &Decoded) -> Result<ControlSignals,
             c.mem_read = true;
             c.mem_write = c.atomic_op != AtomicOp::Lr;
             c.reg_write = true;
+            c.aq = d.funct7 & 0b10 != 0;
+            c.rl = d.funct7 & 0b01 != 0;
         }
         f_opcodes::OP_LOAD_FP => {
             c.fp_reg_write = true;
             c.mem_read = true;
             c.alu = AluOp::Add;
             c.width = match d.funct3 {
-                FP_WIDTH_WORD => MemWidth::Word,
-                FP_WIDTH_DOUBLE => MemWidth::Double,
+                FP_WIDTH_WORD if has(MISA_EXT_F) => MemWidth::Word,
+                FP_WIDTH_DOUBLE if has(MISA_EXT_D) => MemWidth::Double,
                 _ => return Err(Trap::IllegalInstruction(inst)),
             };
         }
@@ -203,8 +257,8 @@ fn decode_instruction(inst: u32, pc: u64, d: &Decoded) -> Result<ControlSignals,
             c.b_src = OpBSrc::Imm;
             c.alu = AluOp::Add;
             c.width = match d.funct3 {
-                FP_WIDTH_WORD => MemWidth::Word,
-                FP_WIDTH_DOUBLE => MemWidth::Double,
+                FP_WIDTH_WORD if has(MISA_EXT_F) => MemWidth::Word,
+                FP_WIDTH_DOUBLE if has(MISA_EXT_D) => MemWidth::Double,
                 _ => return Err(Trap::IllegalInstruction(inst)),
             };
         }
@@ -213,7 +267,25 @@ fn decode_instruction(inst: u32, pc: u64, d: &Decoded) -> Result<ControlSignals,
             c.is_rv32 = fmt == FP_FMT_SINGLE;
             let is_double = fmt == FP_FMT_DOUBLE;
 
-            if !c.is_rv32 && !is_double {
+            // FCVT.S.D is a D-extension instruction despite its S format.
+            let needs_d = is_double || d.funct7 == d_funct7::FCVT_S_D;
+            if !(c.is_rv32 || is_double) || !has(MISA_EXT_F) || (needs_d && !has(MISA_EXT_D)) {
+                return Err(Trap::IllegalInstruction(inst));
+            }
+            // Unary operations encode their variant in rs2; other values are reserved.
+            let rs2 = d.rs2.as_u8();
+            let rs2_ok = match d.funct7 {
+                f_funct7::FSQRT
+                | d_funct7::FSQRT_D
+                | f_funct7::FCLASS_MV_X_F
+                | d_funct7::FCLASS_MV_X_D
+                | f_funct7::FMV_F_X
+                | d_funct7::FMV_D_X
+                | f_funct7::FCVT_DS => rs2 == 0,
+                d_funct7::FCVT_S_D => rs2 == 1,
+                _ => true,
+            };
+            if !rs2_ok {
                 return Err(Trap::IllegalInstruction(inst));
             }
 
@@ -259,7 +331,7 @@ fn decode_instruction(inst: u32, pc: u64, d: &Decoded) -> Result<ControlSignals,
                         _ => return Err(Trap::IllegalInstruction(inst)),
                     }
                 }
-                f_funct7::FMV_F_X | d_funct7::FMV_D_X => {
+                f_funct7::FMV_F_X | d_funct7::FMV_D_X if d.funct3 == 0 => {
                     c.rs1_fp = false;
                     c.fp_reg_write = true;
                     c.a_src = OpASrc::Reg1;
@@ -302,6 +374,14 @@ fn decode_instruction(inst: u32, pc: u64, d: &Decoded) -> Result<ControlSignals,
             c.b_src = OpBSrc::Reg2;
             let fmt = d.funct7 & 0x3;
             c.is_rv32 = fmt == FP_FMT_SINGLE;
+            let legal = match fmt {
+                FP_FMT_SINGLE => has(MISA_EXT_F),
+                FP_FMT_DOUBLE => has(MISA_EXT_F) && has(MISA_EXT_D),
+                _ => false,
+            };
+            if !legal {
+                return Err(Trap::IllegalInstruction(inst));
+            }
 
             c.alu = match d.opcode {
                 d_opcodes::OP_FMADD => AluOp::FMAdd,
@@ -323,23 +403,23 @@ fn decode_instruction(inst: u32, pc: u64, d: &Decoded) -> Result<ControlSignals,
                     sys_ops::SRET => c.system_op = SystemOp::Sret,
                     sys_ops::WFI => c.system_op = SystemOp::Wfi,
                     sys_ops::ECALL => c.system_op = SystemOp::System,
+                    // Other funct3 = 0 encodings are reserved.
+                    _ if d.funct3 == 0 => return Err(Trap::IllegalInstruction(inst)),
                     _ => {
-                        if d.funct3 != 0 {
-                            c.system_op = SystemOp::System;
-                            c.csr_addr = inst.csr();
-                            c.a_src = OpASrc::Reg1;
-                            c.b_src = OpBSrc::Zero;
-                            c.csr_op = match d.funct3 {
-                                sys_ops::CSRRW => CsrOp::Rw,
-                                sys_ops::CSRRS => CsrOp::Rs,
-                                sys_ops::CSRRC => CsrOp::Rc,
-                                sys_ops::CSRRWI => CsrOp::Rwi,
-                                sys_ops::CSRRSI => CsrOp::Rsi,
-                                sys_ops::CSRRCI => CsrOp::Rci,
-                                _ => CsrOp::None,
-                            };
-                            c.reg_write = !d.rd.is_zero();
-                        }
+                        c.system_op = SystemOp::System;
+                        c.csr_addr = inst.csr();
+                        c.a_src = OpASrc::Reg1;
+                        c.b_src = OpBSrc::Zero;
+                        c.csr_op = match d.funct3 {
+                            sys_ops::CSRRW => CsrOp::Rw,
+                            sys_ops::CSRRS => CsrOp::Rs,
+                            sys_ops::CSRRC => CsrOp::Rc,
+                            sys_ops::CSRRWI => CsrOp::Rwi,
+                            sys_ops::CSRRSI => CsrOp::Rsi,
+                            sys_ops::CSRRCI => CsrOp::Rci,
+                            _ => return Err(Trap::IllegalInstruction(inst)),
+                        };
+                        c.reg_write = !d.rd.is_zero();
                     }
                 }
             }
@@ -347,6 +427,16 @@ fn decode_instruction(inst: u32, pc: u64, d: &Decoded) -> Result<ControlSignals,
         i_opcodes::OP_MISC_MEM => match d.funct3 {
             i_funct3::FENCE => c.system_op = SystemOp::Fence,
             i_funct3::FENCE_I => c.system_op = SystemOp::FenceI,
+            cbo_ops::CBO if d.rd.is_zero() => {
+                c.system_op = SystemOp::Cbo(match inst >> 20 {
+                    cbo_ops::CBO_INVAL => CboOp::Inval,
+                    cbo_ops::CBO_CLEAN => CboOp::Clean,
+                    cbo_ops::CBO_FLUSH => CboOp::Flush,
+                    cbo_ops::CBO_ZERO => CboOp::Zero,
+                    _ => return Err(Trap::IllegalInstruction(inst)),
+                });
+                c.a_src = OpASrc::Reg1;
+            }
             _ => return Err(Trap::IllegalInstruction(inst)),
         },
         _ => return Err(Trap::IllegalInstruction(inst)),
@@ -357,8 +447,10 @@ fn decode_instruction(inst: u32, pc: u64, d: &Decoded) -> Result<ControlSignals,
 /// Executes the decode stage.
 ///
 /// Consumes Fetch2->Decode entries (`IfIdEntry`) and produces
-/// Decode->Rename entries (`IdExEntry`).
+/// Decode->Rename entries (`IdExEntry`). Adjacent pairs within the bundle
+/// that match an enabled fusion pattern are emitted as a single entry.
 pub fn decode_stage(cpu: &mut Cpu, input: &mut Vec<IfIdEntry>, output: &mut Vec<IdExEntry>) {
+    let bundle_start = output.len();
     let mut consumed_count = 0;
     let mut bundle_writes: Vec<(RegIdx, bool)> = Vec::with_capacity(cpu.pipeline_width);
     let mut broke_on_trap = false;
@@ -371,8 +463,12 @@ pub fn decode_stage(cpu: &mut Cpu, input: &mut Vec<IfIdEntry>, output: &mut Vec<
                 inst_size: if_entry.inst_size,
                 trap: Some(trap.clone()),
                 exception_stage: if_entry.exception_stage,
+                seq: if_entry.seq,
                 ..Default::default()
             });
+            if let Some(trace) = &mut cpu.pipe_trace {
+                trace.decode(if_entry.seq, if_entry.inst, cpu.stats.cycles);
+            }
             consumed_count += 1;
             broke_on_trap = true;
             break;
@@ -381,49 +477,31 @@ pub fn decode_stage(cpu: &mut Cpu, input: &mut Vec<IfIdEntry>, output: &mut Vec<
         let inst = if_entry.inst;
 
         if inst == INSTRUCTION_NOP {
+            // Eliminated at decode: the NOP leaves the pipeline here.
+            if let Some(trace) = &mut cpu.pipe_trace {
+                trace.decode(if_entry.seq, inst, cpu.stats.cycles);
+                trace.retire(if_entry.seq, cpu.stats.cycles);
+            }
             consumed_count += 1;
             continue;
         }
 
         let d = instruction_decode(inst);
 
-        let (ctrl, trap, ex_stage) = match decode_instruction(inst, if_entry.pc, &d) {
-            Ok(c) => (c, None, None),
-            Err(t) => (ControlSignals::default(), Some(t), Some(ExceptionStage::Decode)),
-        };
+        let (ctrl, trap, ex_stage) =
+            match decode_instruction(inst, if_entry.pc, &d, cpu.csrs.misa, cpu.zacas, cpu.zicond) {
+                Ok(c) => (c, None, None),
+                Err(t) => (ControlSignals::default(), Some(t), Some(ExceptionStage::Decode)),
+            };
 
-        // Check for intra-bundle hazards (superscalar, in-order only).
-        // With register renaming (O3 backend), rename resolves all RAW hazards
-        // by mapping source operands to physical registers before updating the
-        // rename map for the destination. Splitting the bundle here would
-        // create unnecessary 1-cycle bubbles.
         let rs3_idx = inst.rs3();
-        if !cpu.has_register_renaming {
-            let hazard = ((!d.rs1.is_zero() || ctrl.rs1_fp)
-                && bundle_writes.contains(&(d.rs1, ctrl.rs1_fp)))
-                || ((!d.rs2.is_zero() || ctrl.rs2_fp)
-                    && bundle_writes.contains(&(d.rs2, ctrl.rs2_fp)))
-                || (ctrl.rs3_fp && bundle_writes.contains(&(rs3_idx, true)));
-
-            if hazard {
-                break;
-            }
-        }
-
-        if ctrl.reg_write && !d.rd.is_zero() {
-            bundle_writes.push((d.rd, false));
-        }
-        if ctrl.fp_reg_write {
-            bundle_writes.push((d.rd, true));
-        }
-
         let rv1 = if ctrl.rs1_fp { cpu.regs.read_f(d.rs1) } else { cpu.regs.read(d.rs1) };
         let rv2 = if ctrl.rs2_fp { cpu.regs.read_f(d.rs2) } else { cpu.regs.read(d.rs2) };
         let rv3 = if ctrl.rs3_fp { cpu.regs.read_f(rs3_idx) } else { 0 };
 
         let has_trap = trap.is_some();
 
-        output.push(IdExEntry {
+        let entry = IdExEntry {
             pc: if_entry.pc,
             inst,
             inst_size: if_entry.inst_size,
@@ -442,7 +520,58 @@ pub fn decode_stage(cpu: &mut Cpu, input: &mut Vec<IfIdEntry>, output: &mut Vec<
             pred_target: if_entry.pred_target,
             ghr_snapshot: if_entry.ghr_snapshot,
             ras_snapshot: if_entry.ras_snapshot,
-        });
+            fused: None,
+            seq: if_entry.seq,
+        };
+
+        // Macro-op fusion with the previous entry of this bundle. The fused
+        // entry's sources are the head's, which already passed the hazard
+        // check, and its destination is already in `bundle_writes`. An armed
+        // breakpoint must stay an instruction boundary, so its instruction
+        // is never a fused tail.
+        if !cpu.fusion.is_empty()
+            && output.len() > bundle_start
+            && cpu.break_pc != Some(entry.pc)
+            && let Some(head) = output.last_mut()
+            && let Some(fused) = fusion::try_fuse(head, &entry, &cpu.fusion)
+        {
+            if let Some(trace) = &mut cpu.pipe_trace {
+                trace.decode(entry.seq, inst, cpu.stats.cycles);
+                trace.fuse(head.seq, entry.seq);
+            }
+            *head = fused;
+            consumed_count += 1;
+            continue;
+        }
+
+        // Check for intra-bundle hazards (superscalar, in-order only).
+        // With register renaming (O3 backend), rename resolves all RAW hazards
+        // by mapping source operands to physical registers before updating the
+        // rename map for the destination. Splitting the bundle here would
+        // create unnecessary 1-cycle bubbles.
+        if !cpu.has_register_renaming {
+            let hazard = ((!d.rs1.is_zero() || ctrl.rs1_fp)
+                && bundle_writes.contains(&(d.rs1, ctrl.rs1_fp)))
+                || ((!d.rs2.is_zero() || ctrl.rs2_fp)
+                    && bundle_writes.contains(&(d.rs2, ctrl.rs2_fp)))
+                || (ctrl.rs3_fp && bundle_writes.contains(&(rs3_idx, true)));
+
+            if hazard {
+                break;
+            }
+        }
+
+        if ctrl.reg_write && !d.rd.is_zero() {
+            bundle_writes.push((d.rd, false));
+        }
+        if ctrl.fp_reg_write {
+            bundle_writes.push((d.rd, true));
+        }
+
+        if let Some(trace) = &mut cpu.pipe_trace {
+            trace.decode(entry.seq, inst, cpu.stats.cycles);
+        }
+        output.push(entry);
 
         consumed_count += 1;
 
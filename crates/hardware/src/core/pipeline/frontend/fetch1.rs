@@ -4,31 +4,32 @@
 //! performs branch prediction to determine the next PC, and initiates
 //! I-TLB lookups for address translation.
 
-// RISC-V instructions may be misaligned (compressed 16-bit instructions); read_unaligned is intentional.
-#![allow(clippy::cast_ptr_alignment)]
-
 use crate::common::InstSize;
 use crate::common::constants::{
-    COMPRESSED_INSTRUCTION_MASK, COMPRESSED_INSTRUCTION_VALUE, OPCODE_MASK, RD_MASK, RD_SHIFT,
-    RS1_MASK, RS1_SHIFT,
-};
-use crate::common::{
-    AccessType, ExceptionStage, PhysAddr, RegIdx, TranslationResult, Trap, VirtAddr,
+    COMPRESSED_INSTRUCTION_MASK, COMPRESSED_INSTRUCTION_VALUE, OPCODE_MASK,
 };
+use crate::common::{AccessType, ExceptionStage, PhysAddr, TranslationResult, Trap, VirtAddr};
 use crate::core::Cpu;
 use crate::core::arch::csr;
 use crate::core::pipeline::latches::Fetch1Fetch2Entry;
+use crate::core::units::bru::ras::{RasOp, RasSnapshot};
 use crate::core::units::bru::{BranchPredictor, Ghr};
-use crate::isa::abi;
 use crate::isa::rv64i::opcodes;
+use crate::isa::rvc::expand::expand;
 use crate::trace_branch;
 use crate::trace_fetch;
 
 /// Executes the Fetch1 stage: PC generation + I-TLB + branch prediction.
 ///
-/// Produces entries in the Fetch1->Fetch2 latch with physical addresses
-/// and prediction information.
-pub fn fetch1_stage(cpu: &mut Cpu, output: &mut Vec<Fetch1Fetch2Entry>, stall_out: &mut u64) {
+/// Produces one fetch block of at most `fetch_width` instructions for the
+/// FTQ: entries with physical addresses and prediction information, ending
+/// at the cache line or the first predicted-taken control transfer.
+pub fn fetch1_stage(
+    cpu: &mut Cpu,
+    fetch_width: usize,
+    output: &mut Vec<Fetch1Fetch2Entry>,
+    stall_out: &mut u64,
+) {
     output.clear();
 
     let mut current_pc = cpu.pc;
@@ -42,7 +43,7 @@ pub fn fetch1_stage(cpu: &mut Cpu, output: &mut Vec<Fetch1Fetch2Entry>, stall_ou
     let line_bytes = cpu.i_cache_line_bytes as u64;
     let line_end = (current_pc | (line_bytes - 1)) + 1; // end of current cache line
 
-    for _ in 0..cpu.pipeline_width {
+    for _ in 0..fetch_width {
         // Stop if fewer than 2 bytes remain in this cache line (minimum instruction size).
         if current_pc + 2 > line_end {
             break;
@@ -81,7 +82,8 @@ pub fn fetch1_stage(cpu: &mut Cpu, output: &mut Vec<Fetch1Fetch2Entry>, stall_ou
                 trap: Some(trap_cause.clone()),
                 exception_stage: Some(ExceptionStage::Fetch),
                 ghr_snapshot: Ghr::default(),
-                ras_snapshot: 0,
+                ras_snapshot: RasSnapshot::default(),
+                seq: cpu.alloc_seq(current_pc),
             });
             break;
         }
@@ -89,22 +91,15 @@ pub fn fetch1_stage(cpu: &mut Cpu, output: &mut Vec<Fetch1Fetch2Entry>, stall_ou
         let phys_addr = paddr.val();
 
         // Read the first half-word to determine instruction type for prediction
-        let half_word = if phys_addr >= cpu.ram_start && phys_addr < cpu.ram_end {
-            let offset = (phys_addr - cpu.ram_start) as usize;
-            unsafe {
-                let ptr = cpu.ram_ptr.add(offset) as *const u16;
-                ptr.read_unaligned()
-            }
-        } else {
-            cpu.bus.bus.read_u16(paddr)
-        };
+        let half_word =
+            cpu.ram.read(phys_addr, 2).map_or_else(|| cpu.bus.bus.read_u16(paddr), |v| v as u16);
 
         let is_compressed =
             (half_word & COMPRESSED_INSTRUCTION_MASK) != COMPRESSED_INSTRUCTION_VALUE;
 
         let step = if is_compressed { InstSize::Compressed } else { InstSize::Standard };
 
-        // Branch prediction (peek at opcode from half_word for 32-bit instructions)
+        // Branch prediction on the (expanded) opcode
         let mut next_pc_calc = current_pc.wrapping_add(step.as_u64());
         let mut pred_taken = false;
         let mut pred_target = 0;
@@ -112,30 +107,8 @@ pub fn fetch1_stage(cpu: &mut Cpu, output: &mut Vec<Fetch1Fetch2Entry>, stall_ou
         let ghr_snapshot = cpu.branch_predictor.snapshot_history();
         let ras_snapshot = cpu.branch_predictor.snapshot_ras();
 
-        if is_compressed {
-            // Compressed branch prediction: detect C.BEQZ / C.BNEZ
-            // Quadrant 1 (bits 1:0 = 01), funct3 = 110 or 111
-            let quadrant = half_word & 0x3;
-            let funct3_c = (half_word >> 13) & 0x7;
-            if quadrant == 0x01 && (funct3_c == 0b110 || funct3_c == 0b111) {
-                let (taken, target) = cpu.branch_predictor.predict_branch(current_pc);
-                cpu.branch_predictor.speculate(current_pc, taken);
-                if taken && let Some(tgt) = target {
-                    next_pc_calc = tgt;
-                    pred_taken = true;
-                    pred_target = tgt;
-                    stop_fetch = true;
-                }
-                trace_branch!(cpu.trace;
-                    event        = "predict",
-                    pc           = %crate::trace::Hex(current_pc),
-                    paddr        = %crate::trace::Hex(phys_addr),
-                    bp_type      = "compressed-branch",
-                    pred_taken   = taken,
-                    pred_target  = %crate::trace::Hex(target.unwrap_or(0)),
-                    "F1: compressed branch prediction"
-                );
-            }
+        let full_inst = if is_compressed {
+            expand(half_word)
         } else {
             // For 32-bit instructions, read full instruction for opcode extraction
             let upper_va = current_pc.wrapping_add(2);
@@ -160,6 +133,7 @@ pub fn fetch1_stage(cpu: &mut Cpu, output: &mut Vec<Fetch1Fetch2Entry>, stall_ou
                         exception_stage: None,
                         ghr_snapshot: Ghr::default(),
                         ras_snapshot,
+                        seq: cpu.alloc_seq(current_pc),
                     });
                     cpu.pc = next_pc_calc;
                     break;
@@ -170,87 +144,82 @@ pub fn fetch1_stage(cpu: &mut Cpu, output: &mut Vec<Fetch1Fetch2Entry>, stall_ou
             };
 
             let upper_raw = upper_phys.val();
-            let upper_half = if upper_raw >= cpu.ram_start && upper_raw < cpu.ram_end {
-                let offset = (upper_raw - cpu.ram_start) as usize;
-                unsafe {
-                    let ptr = cpu.ram_ptr.add(offset) as *const u16;
-                    ptr.read_unaligned()
-                }
-            } else {
-                cpu.bus.bus.read_u16(upper_phys)
-            };
-
-            let full_inst = (upper_half as u32) << 16 | (half_word as u32);
-            let opcode = full_inst & OPCODE_MASK;
-            let rd = RegIdx::new(((full_inst >> RD_SHIFT) & RD_MASK) as u8);
-            let rs1 = RegIdx::new(((full_inst >> RS1_SHIFT) & RS1_MASK) as u8);
+            let upper_half = cpu
+                .ram
+                .read(upper_raw, 2)
+                .map_or_else(|| cpu.bus.bus.read_u16(upper_phys), |v| v as u16);
 
-            if opcode == opcodes::OP_BRANCH {
-                let (taken, target) = cpu.branch_predictor.predict_branch(current_pc);
-                cpu.branch_predictor.speculate(current_pc, taken);
-                if taken && let Some(tgt) = target {
-                    next_pc_calc = tgt;
-                    pred_taken = true;
-                    pred_target = tgt;
-                    stop_fetch = true;
-                }
-                trace_branch!(cpu.trace;
-                    event        = "predict",
-                    pc           = %crate::trace::Hex(current_pc),
-                    paddr        = %crate::trace::Hex(phys_addr),
-                    inst         = %crate::trace::Hex32(full_inst),
-                    bp_type      = "branch",
-                    pred_taken   = taken,
-                    pred_target  = %crate::trace::Hex(target.unwrap_or(0)),
-                    "F1: branch prediction"
-                );
-            } else if opcode == opcodes::OP_JAL {
-                if let Some(tgt) = cpu.branch_predictor.predict_btb(current_pc) {
-                    next_pc_calc = tgt;
-                    pred_taken = true;
-                    pred_target = tgt;
-                    stop_fetch = true;
-                }
-                trace_branch!(cpu.trace;
-                    event       = "predict",
-                    pc          = %crate::trace::Hex(current_pc),
-                    paddr       = %crate::trace::Hex(phys_addr),
-                    inst        = %crate::trace::Hex32(full_inst),
-                    bp_type     = "JAL/BTB",
-                    pred_taken  = pred_taken,
-                    pred_target = %crate::trace::Hex(pred_target),
-                    "F1: JAL prediction"
-                );
-            } else if opcode == opcodes::OP_JALR {
-                // Per RISC-V spec Table 2.1: both x1 and x5 are link registers.
-                let rd_link = rd == abi::REG_RA || rd == abi::REG_T0;
-                let rs1_link = rs1 == abi::REG_RA || rs1 == abi::REG_T0;
-                // Use RAS for returns and coroutine swaps (rs1 is link, but not
-                // a pure self-call where rd == rs1 both link).
-                let use_ras = rs1_link && (!rd_link || rd != rs1);
-                if use_ras {
-                    if let Some(tgt) = cpu.branch_predictor.predict_return() {
-                        next_pc_calc = tgt;
-                        pred_taken = true;
-                        pred_target = tgt;
-                    }
-                } else if let Some(tgt) = cpu.branch_predictor.predict_btb(current_pc) {
+            (upper_half as u32) << 16 | (half_word as u32)
+        };
+        let opcode = full_inst & OPCODE_MASK;
+        let ras_op = RasOp::for_inst(full_inst, current_pc.wrapping_add(step.as_u64()));
+
+        if opcode == opcodes::OP_BRANCH {
+            let (taken, target) = cpu.branch_predictor.predict_branch(current_pc);
+            cpu.branch_predictor.speculate(current_pc, taken);
+            if taken && let Some(tgt) = target {
+                next_pc_calc = tgt;
+                pred_taken = true;
+                pred_target = tgt;
+                stop_fetch = true;
+            }
+            trace_branch!(cpu.trace;
+                event        = "predict",
+                pc           = %crate::trace::Hex(current_pc),
+                paddr        = %crate::trace::Hex(phys_addr),
+                inst         = %crate::trace::Hex32(full_inst),
+                bp_type      = "branch",
+                pred_taken   = taken,
+                pred_target  = %crate::trace::Hex(target.unwrap_or(0)),
+                "F1: branch prediction"
+            );
+        } else if opcode == opcodes::OP_JAL {
+            if let Some(tgt) = cpu.branch_predictor.predict_btb(current_pc) {
+                next_pc_calc = tgt;
+                pred_taken = true;
+                pred_target = tgt;
+                stop_fetch = true;
+            }
+            trace_branch!(cpu.trace;
+                event       = "predict",
+                pc          = %crate::trace::Hex(current_pc),
+                paddr       = %crate::trace::Hex(phys_addr),
+                inst        = %crate::trace::Hex32(full_inst),
+                bp_type     = "JAL/BTB",
+                pred_taken  = pred_taken,
+                pred_target = %crate::trace::Hex(pred_target),
+                "F1: JAL prediction"
+            );
+        } else if opcode == opcodes::OP_JALR {
+            // Use RAS for returns and coroutine swaps.
+            let use_ras = matches!(ras_op, Some(RasOp::Pop | RasOp::PopThenPush(_)));
+            if use_ras {
+                if let Some(tgt) = cpu.branch_predictor.predict_return() {
                     next_pc_calc = tgt;
                     pred_taken = true;
                     pred_target = tgt;
                 }
-                stop_fetch = true;
-                trace_branch!(cpu.trace;
-                    event       = "predict",
-                    pc          = %crate::trace::Hex(current_pc),
-                    paddr       = %crate::trace::Hex(phys_addr),
-                    inst        = %crate::trace::Hex32(full_inst),
-                    bp_type     = if use_ras { "JALR/RAS" } else { "JALR/BTB" },
-                    pred_taken  = pred_taken,
-                    pred_target = %crate::trace::Hex(pred_target),
-                    "F1: JALR prediction"
-                );
+            } else if let Some(tgt) = cpu.branch_predictor.predict_btb(current_pc) {
+                next_pc_calc = tgt;
+                pred_taken = true;
+                pred_target = tgt;
             }
+            stop_fetch = true;
+            trace_branch!(cpu.trace;
+                event       = "predict",
+                pc          = %crate::trace::Hex(current_pc),
+                paddr       = %crate::trace::Hex(phys_addr),
+                inst        = %crate::trace::Hex32(full_inst),
+                bp_type     = if use_ras { "JALR/RAS" } else { "JALR/BTB" },
+                pred_taken  = pred_taken,
+                pred_target = %crate::trace::Hex(pred_target),
+                "F1: JALR prediction"
+            );
+        }
+
+        // Speculative RAS update; `ras_snapshot` undoes it on misprediction.
+        if let Some(op) = ras_op {
+            cpu.branch_predictor.update_ras(op);
         }
 
         trace_fetch!(cpu.trace;
@@ -272,6 +241,7 @@ pub fn fetch1_stage(cpu: &mut Cpu, output: &mut Vec<Fetch1Fetch2Entry>, stall_ou
             exception_stage: None,
             ghr_snapshot,
             ras_snapshot,
+            seq: cpu.alloc_seq(current_pc),
         });
 
         current_pc = next_pc_calc;
@@ -6,8 +6,8 @@
 //!
 //! I-cache timing is modeled per cache line:
 //!
-//! - **Hit:** Instructions are decoded and delivered to `output` (the
-//!   fetch2→decode latch) the same cycle. No stall.
+//! - **Hit:** Instructions are decoded and appended to `output` (the
+//!   fetch buffer) the same cycle. No stall.
 //! - **Miss:** `simulate_memory_access` installs the line and returns
 //!   the miss penalty. Instructions are decoded into `pending` (a
 //!   holding buffer), `stall_out` is set to the penalty, and nothing
@@ -15,22 +15,26 @@
 //!   `pending` → `output`. The I-cache is NOT re-accessed on delivery
 //!   (the line was already installed on the miss), so there is exactly
 //!   one miss stat and zero spurious hit stats per miss event.
-
-// RISC-V instructions may be misaligned (compressed 16-bit instructions); read_unaligned is intentional.
-#![allow(clippy::cast_ptr_alignment)]
+//!
+//! A 32-bit instruction in the last two bytes of a line has its upper half
+//! in the next line (and, at a page end, the next page). Fetching it takes a
+//! second I-cache access, charged as the cache latency plus any miss penalty
+//! of that line, after translating the upper half if it is on another page.
 
 use crate::common::constants::{COMPRESSED_INSTRUCTION_MASK, COMPRESSED_INSTRUCTION_VALUE};
-use crate::common::{AccessType, ExceptionStage, InstSize, Trap, VirtAddr};
+use crate::common::{AccessType, ExceptionStage, InstSize, PhysAddr, Trap, VirtAddr};
 use crate::core::Cpu;
+use crate::core::arch::csr;
 use crate::core::pipeline::latches::{Fetch1Fetch2Entry, IfIdEntry};
 use crate::isa::rvc::expand::expand;
 use crate::{trace_fetch, trace_trap};
 
 /// Executes the Fetch2 stage: I-cache access + RVC expansion.
 ///
-/// Consumes Fetch1→Fetch2 entries and produces Fetch2→Decode entries.
+/// Consumes one fetch block from the FTQ and appends its instructions to
+/// the fetch buffer.
 ///
-/// - On an I-cache **hit**, decoded instructions go into `output`.
+/// - On an I-cache **hit**, decoded instructions are appended to `output`.
 /// - On an I-cache **miss**, decoded instructions go into `pending`
 ///   and `stall_out` is set to the miss penalty. The caller delivers
 ///   `pending` when the stall expires (without re-probing the cache).
@@ -41,13 +45,16 @@ pub fn fetch2_stage(
     pending: &mut Vec<IfIdEntry>,
     stall_out: &mut u64,
 ) {
-    output.clear();
     pending.clear();
 
     if input.is_empty() {
         return;
     }
 
+    // Fetch1 ends a block at the line end, so only its last instruction can
+    // straddle two lines.
+    let split = input.last().and_then(|f1| straddling_upper_half(cpu, f1, stall_out));
+
     // --- I-cache probe (before decoding) ---
     // Probe each unique cache line. On a miss, record the penalty.
     // `simulate_memory_access` installs the line on miss, so this is the
@@ -67,7 +74,14 @@ pub fn fetch2_stage(
             }
             last_line = this_line;
 
-            let penalty = cpu.simulate_memory_access(f1.paddr, AccessType::Fetch);
+            let penalty = fetch_line(cpu, f1.pc, f1.paddr);
+            icache_penalty += penalty;
+        }
+
+        if let Some(SplitFetch { pc, upper: Ok(upper) }) = split {
+            let penalty = cpu.l1_i_cache.latency + fetch_line(cpu, pc, upper);
+            cpu.stats.fetch_line_crossings += 1;
+            cpu.stats.fetch_line_crossing_cycles += penalty;
             icache_penalty += penalty;
         }
     }
@@ -104,6 +118,7 @@ pub fn fetch2_stage(
                 exception_stage: f1.exception_stage,
                 ghr_snapshot: f1.ghr_snapshot,
                 ras_snapshot: f1.ras_snapshot,
+                seq: f1.seq,
             });
             break;
         }
@@ -111,54 +126,38 @@ pub fn fetch2_stage(
         let phys_addr = f1.paddr.val();
 
         // Read the first half-word (functional — raw pointer for data)
-        let half_word = if phys_addr >= cpu.ram_start && phys_addr < cpu.ram_end {
-            let offset = (phys_addr - cpu.ram_start) as usize;
-            unsafe {
-                let ptr = cpu.ram_ptr.add(offset) as *const u16;
-                ptr.read_unaligned()
-            }
-        } else {
-            cpu.bus.bus.read_u16(f1.paddr)
-        };
+        let half_word =
+            cpu.ram.read(phys_addr, 2).map_or_else(|| cpu.bus.bus.read_u16(f1.paddr), |v| v as u16);
 
         let is_compressed =
             (half_word & COMPRESSED_INSTRUCTION_MASK) != COMPRESSED_INSTRUCTION_VALUE;
 
         let (inst, step, inst_trap) = if is_compressed {
             let expanded = expand(half_word);
-            if expanded == 0 {
+            if expanded == 0 || cpu.csrs.misa & csr::MISA_EXT_C == 0 {
                 (0, InstSize::Compressed, Some(Trap::IllegalInstruction(half_word as u32)))
             } else {
                 (expanded, InstSize::Compressed, None)
             }
         } else {
-            let upper_va = f1.pc.wrapping_add(2);
-            let crosses_page = (f1.pc >> 12) != (upper_va >> 12);
-
-            let (upper_phys, upper_fault) = if crosses_page {
-                let result = cpu.translate(VirtAddr::new(upper_va), AccessType::Fetch, 2);
-                *stall_out += result.cycles;
-                (result.paddr, result.trap)
-            } else {
-                (crate::common::PhysAddr::new(phys_addr + 2), None)
+            // An upper half in the same line is also in the same page.
+            let upper = match &split {
+                Some(s) if s.pc == f1.pc => s.upper.clone(),
+                _ => Ok(PhysAddr::new(phys_addr + 2)),
             };
 
-            if let Some(t) = upper_fault {
-                (0, InstSize::Standard, Some(t))
-            } else {
-                let upper_raw = upper_phys.val();
-                let upper_half = if upper_raw >= cpu.ram_start && upper_raw < cpu.ram_end {
-                    let offset = (upper_raw - cpu.ram_start) as usize;
-                    unsafe {
-                        let ptr = cpu.ram_ptr.add(offset) as *const u16;
-                        ptr.read_unaligned()
-                    }
-                } else {
-                    cpu.bus.bus.read_u16(upper_phys)
-                };
-
-                let full_inst = (upper_half as u32) << 16 | (half_word as u32);
-                (full_inst, InstSize::Standard, None)
+            match upper {
+                Err(t) => (0, InstSize::Standard, Some(t)),
+                Ok(upper_phys) => {
+                    let upper_raw = upper_phys.val();
+                    let upper_half = cpu
+                        .ram
+                        .read(upper_raw, 2)
+                        .map_or_else(|| cpu.bus.bus.read_u16(upper_phys), |v| v as u16);
+
+                    let full_inst = (upper_half as u32) << 16 | (half_word as u32);
+                    (full_inst, InstSize::Standard, None)
+                }
             }
         };
 
@@ -180,6 +179,7 @@ pub fn fetch2_stage(
                 exception_stage: Some(ExceptionStage::Fetch),
                 ghr_snapshot: f1.ghr_snapshot,
                 ras_snapshot: f1.ras_snapshot,
+                seq: f1.seq,
             });
             break;
         }
@@ -202,6 +202,59 @@ pub fn fetch2_stage(
             exception_stage: None,
             ghr_snapshot: f1.ghr_snapshot,
             ras_snapshot: f1.ras_snapshot,
+            seq: f1.seq,
         });
     }
 }
+
+/// Probes the I-cache for the line holding `paddr`, fetched for the
+/// instruction at `pc`, and logs a miss to the access trace. Returns the
+/// miss penalty.
+fn fetch_line(cpu: &mut Cpu, pc: u64, paddr: PhysAddr) -> u64 {
+    let misses = cpu.stats.icache_misses;
+    let penalty = cpu.simulate_memory_access(paddr, AccessType::Fetch);
+    if cpu.stats.icache_misses != misses {
+        let line = cpu.i_cache_line_bytes as u64;
+        cpu.trace_access(pc, PhysAddr::new(paddr.val() & !(line - 1)), line, false, true);
+    }
+    penalty
+}
+
+/// The upper half of a 32-bit instruction that straddles an I-cache line.
+struct SplitFetch {
+    /// PC of the instruction.
+    pc: u64,
+    /// Physical address of the upper half, or the fault translating it.
+    upper: Result<PhysAddr, Trap>,
+}
+
+/// Returns the upper half's location if `f1` is a 32-bit instruction that
+/// crosses an I-cache line, translating it (and charging the walk to
+/// `stall_out`) when it also crosses a page.
+fn straddling_upper_half(
+    cpu: &mut Cpu,
+    f1: &Fetch1Fetch2Entry,
+    stall_out: &mut u64,
+) -> Option<SplitFetch> {
+    let line_bytes = cpu.i_cache_line_bytes as u64;
+    if f1.trap.is_some() || (f1.pc & (line_bytes - 1)) != line_bytes - 2 {
+        return None;
+    }
+    let half_word = cpu
+        .ram
+        .read(f1.paddr.val(), 2)
+        .map_or_else(|| cpu.bus.bus.read_u16(f1.paddr), |v| v as u16);
+    if (half_word & COMPRESSED_INSTRUCTION_MASK) != COMPRESSED_INSTRUCTION_VALUE {
+        return None;
+    }
+
+    let upper_va = f1.pc.wrapping_add(2);
+    let upper = if (f1.pc >> 12) == (upper_va >> 12) {
+        Ok(PhysAddr::new(f1.paddr.val() + 2))
+    } else {
+        let result = cpu.translate(VirtAddr::new(upper_va), AccessType::Fetch, 2);
+        *stall_out += result.cycles;
+        result.trap.map_or(Ok(result.paddr), Err)
+    };
+    Some(SplitFetch { pc: f1.pc, upper })
+}
@@ -0,0 +1,164 @@
+//! Macro-op Fusion: combines adjacent dependent instruction pairs at decode.
+//!
+//! Runs on the Fetch2->Decode to Decode->Rename transition. When the
+//! instruction just decoded forms an enabled pattern with the previous entry
+//! of the same decode bundle, the two are replaced by a single fused entry
+//! that occupies one rename slot, one ROB entry and one issue slot, but
+//! retires as two instructions.
+//!
+//! Every fused form is expressed with the ordinary control signals of the
+//! second instruction (the *tail*), so the backend executes it unchanged:
+//! 1. **`LuiAddi`:** `lui rd, hi; addi[w] rd, rd, lo` becomes `addi[w] rd, x0, hi+lo`.
+//! 2. **`AuipcAddi`:** `auipc rd, hi; addi rd, rd, lo` becomes `addi rd, x0, pc+hi+lo`.
+//! 3. **`AuipcJalr`:** `auipc rd, hi; jalr rd, lo(rd)` becomes `jalr rd, pc+hi+lo(x0)`.
+//! 4. **`SlliSrli`:** `slli rd, rs, n; srli rd, rd, n` becomes `andi rd, rs, !0 >> n`.
+//! 5. **`CmpBranch`:** `slt[u] rd, a, b; beqz/bnez rd, off` becomes a branch on
+//!    `slt[u] a, b` that also writes `rd`.
+//!
+//! Only pairs where the head's result is either overwritten by the tail or
+//! still written by the fused op are fused, so no architectural state is lost.
+
+use serde::Deserialize;
+
+use crate::core::pipeline::latches::IdExEntry;
+use crate::core::pipeline::signals::{AluOp, ControlFlow};
+use crate::isa::abi;
+use crate::isa::decode::decode;
+use crate::isa::instruction::InstructionBits;
+use crate::isa::rv64i::{funct3, opcodes};
+
+/// Shift-amount mask for RV64 `slli`/`srli`.
+const SHAMT_MASK: i64 = 0x3F;
+
+/// An instruction pair the decode stage may fuse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub enum FusionPattern {
+    /// `lui` + `addi`/`addiw` building a 32-bit constant (`li`).
+    LuiAddi,
+    /// `auipc` + `addi` forming a PC-relative address (`la`).
+    AuipcAddi,
+    /// `auipc` + `jalr` forming a far call (`call`).
+    AuipcJalr,
+    /// `slli` + `srli` by the same amount (zero-extension).
+    SlliSrli,
+    /// `slt`/`sltu` + `beqz`/`bnez` on the comparison result.
+    CmpBranch,
+}
+
+/// The first instruction of a fused pair, kept so commit can retire it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FusedHead {
+    /// Program counter of the head instruction.
+    pub pc: u64,
+    /// Raw (expanded) encoding of the head instruction.
+    pub inst: u32,
+}
+
+/// Attempts to fuse `head` and `tail` under one of the enabled `patterns`.
+///
+/// Returns the fused entry, which keeps the tail's PC, encoding and branch
+/// prediction metadata, or `None` if the pair does not match.
+pub fn try_fuse(
+    head: &IdExEntry,
+    tail: &IdExEntry,
+    patterns: &[FusionPattern],
+) -> Option<IdExEntry> {
+    if head.trap.is_some()
+        || tail.trap.is_some()
+        || head.fused.is_some()
+        || head.pred_taken
+        || head.pc.wrapping_add(head.inst_size.as_u64()) != tail.pc
+        || head.rd.is_zero()
+    {
+        return None;
+    }
+
+    let pattern = classify(head, tail)?;
+    if !patterns.contains(&pattern) {
+        return None;
+    }
+
+    let mut fused = tail.clone();
+    fused.fused = Some(FusedHead { pc: head.pc, inst: head.inst });
+    fused.ctrl.fusion = Some(pattern);
+    match pattern {
+        FusionPattern::LuiAddi => {
+            fused.rs1 = abi::REG_ZERO;
+            fused.rv1 = 0;
+            fused.imm = head.imm.wrapping_add(tail.imm);
+        }
+        FusionPattern::AuipcAddi | FusionPattern::AuipcJalr => {
+            // The head's result is a constant; fold it into the immediate and
+            // read x0 so the tail has no register dependency left.
+            fused.rs1 = abi::REG_ZERO;
+            fused.rv1 = 0;
+            fused.imm = (head.pc as i64).wrapping_add(head.imm).wrapping_add(tail.imm);
+        }
+        FusionPattern::SlliSrli => {
+            fused.rs1 = head.rs1;
+            fused.rv1 = head.rv1;
+            fused.ctrl.alu = AluOp::And;
+            fused.imm = (u64::MAX >> (tail.imm & SHAMT_MASK)) as i64;
+        }
+        FusionPattern::CmpBranch => {
+            fused.rs1 = head.rs1;
+            fused.rs2 = head.rs2;
+            fused.rv1 = head.rv1;
+            fused.rv2 = head.rv2;
+            fused.rd = head.rd;
+            fused.ctrl.reg_write = true;
+            fused.ctrl.alu = head.ctrl.alu;
+        }
+    }
+    Some(fused)
+}
+
+/// Identifies which pattern, if any, `head` followed by `tail` forms.
+fn classify(head: &IdExEntry, tail: &IdExEntry) -> Option<FusionPattern> {
+    let (h, t) = (head.inst, tail.inst);
+    let rd = head.rd;
+    let chained = tail.rs1 == rd && tail.rd == rd;
+    let is_addi = t.opcode() == opcodes::OP_IMM && t.funct3() == funct3::ADD_SUB;
+    let is_addiw = t.opcode() == opcodes::OP_IMM_32 && t.funct3() == funct3::ADD_SUB;
+
+    match h.opcode() {
+        opcodes::OP_LUI if chained && (is_addi || is_addiw) => Some(FusionPattern::LuiAddi),
+        opcodes::OP_AUIPC if chained && is_addi => Some(FusionPattern::AuipcAddi),
+        opcodes::OP_AUIPC if chained && t.opcode() == opcodes::OP_JALR => {
+            Some(FusionPattern::AuipcJalr)
+        }
+        opcodes::OP_IMM
+            if chained
+                && matches!(head.ctrl.alu, AluOp::Sll)
+                && matches!(tail.ctrl.alu, AluOp::Srl)
+                && t.opcode() == opcodes::OP_IMM
+                && (head.imm & SHAMT_MASK) == (tail.imm & SHAMT_MASK) =>
+        {
+            Some(FusionPattern::SlliSrli)
+        }
+        opcodes::OP_REG
+            if matches!(head.ctrl.alu, AluOp::Slt | AluOp::Sltu)
+                && tail.ctrl.control_flow == ControlFlow::Branch
+                && matches!(t.funct3(), funct3::BEQ | funct3::BNE)
+                && ((tail.rs1 == head.rd && tail.rs2.is_zero())
+                    || (tail.rs2 == head.rd && tail.rs1.is_zero())) =>
+        {
+            Some(FusionPattern::CmpBranch)
+        }
+        _ => None,
+    }
+}
+
+/// Recovers the value the head instruction would have written to `rd`,
+/// given the fused entry's committed result (used for commit logging).
+pub fn head_result(head: FusedHead, pattern: FusionPattern, fused_result: u64) -> u64 {
+    let upper = decode(head.inst).imm as u64;
+    match pattern {
+        FusionPattern::LuiAddi => upper,
+        FusionPattern::AuipcAddi | FusionPattern::AuipcJalr => head.pc.wrapping_add(upper),
+        // (rs << n) >> n << n == rs << n
+        FusionPattern::SlliSrli => fused_result << (decode(head.inst).imm & SHAMT_MASK),
+        FusionPattern::CmpBranch => fused_result,
+    }
+}
@@ -1,54 +1,114 @@
 //! Frontend pipeline stages (shared across all backends).
 //!
 //! The frontend is generic over the execution engine and handles:
-//! Fetch1 -> Fetch2 -> Decode -> Rename
+//! Fetch1 -> FTQ -> Fetch2 -> Fetch Buffer -> Decode -> Rename
+//!
+//! Fetch is decoupled from decode by two queues. Fetch1 walks the predicted
+//! path one fetch block of up to `fetch_width` instructions per cycle and
+//! pushes each block into the fetch target queue (FTQ), so prediction keeps
+//! running ahead across taken branches while Fetch2 waits on the I-cache.
+//! Fetch2 pops one block per cycle into the fetch buffer, which keeps filling
+//! while decode is backpressured; decode takes up to `width` instructions
+//! from its head.
 
 pub mod decode;
 pub mod fetch1;
 pub mod fetch2;
+pub mod fusion;
 pub mod rename;
 
+use crate::config::Config;
 use crate::core::pipeline::engine::ExecutionEngine;
 use crate::core::pipeline::latches::{Fetch1Fetch2Entry, IdExEntry, IfIdEntry, RenameIssueEntry};
+use std::collections::VecDeque;
 use std::marker::PhantomData;
 
 /// The frontend pipeline, generic over the execution engine.
 ///
-/// Same frontend code works with `InOrderEngine` and (future) `O3Engine`.
+/// Same frontend code works with `InOrderEngine` and `O3Engine`.
 #[derive(Debug)]
 pub struct Frontend<E: ExecutionEngine> {
-    /// Fetch1 -> Fetch2 latch.
-    pub fetch1_fetch2: Vec<Fetch1Fetch2Entry>,
-    /// Fetch2 -> Decode latch (reuses `IfIdEntry` for I-cache result).
-    pub fetch2_decode: Vec<IfIdEntry>,
+    /// Fetch target queue: predicted fetch blocks from Fetch1, oldest first.
+    pub ftq: VecDeque<Vec<Fetch1Fetch2Entry>>,
+    /// Fetch buffer between Fetch2 and Decode (reuses `IfIdEntry` for the
+    /// I-cache result), oldest first.
+    pub fetch_buffer: Vec<IfIdEntry>,
     /// Decode -> Rename latch (reuses `IdExEntry` for decoded signals).
     pub decode_rename: Vec<IdExEntry>,
     /// Fetch1 stall counter (I-TLB translation latency).
     pub fetch1_stall: u64,
     /// Fetch2 stall counter (I-cache / page-crossing latency).
     pub fetch2_stall: u64,
+    /// Fetch1 bubble remaining after a branch misprediction.
+    pub mispredict_stall: u64,
+    /// First sequence number fetched after the latest redirect, until an
+    /// instruction from the redirected stream retires.
+    pub refill_seq: Option<u64>,
     /// Holding buffer for decoded instructions waiting on an I-cache miss.
     /// On a miss, fetch2 decodes into here and stalls; when the stall
-    /// expires these are moved to `fetch2_decode` without re-accessing the
+    /// expires these are moved to `fetch_buffer` without re-accessing the
     /// I-cache (the line was already installed on the miss).
     fetch2_pending: Vec<IfIdEntry>,
+    /// Maximum number of fetch blocks held in the FTQ.
+    ftq_size: usize,
+    /// Maximum number of instructions held in the fetch buffer.
+    fetch_buffer_size: usize,
+    /// Pipeline width: the decode bandwidth.
+    width: usize,
+    /// Largest fetch block, in instructions.
+    fetch_width: usize,
+    /// Bubble injected into Fetch1 after each branch misprediction.
+    mispredict_penalty: u64,
     _marker: PhantomData<E>,
 }
 
 impl<E: ExecutionEngine> Frontend<E> {
-    /// Creates a new frontend with the given pipeline width.
-    pub fn new(width: usize) -> Self {
+    /// Creates a new frontend sized from the pipeline configuration.
+    ///
+    /// The FTQ holds at least one block and the fetch buffer at least one
+    /// full fetch block, whatever the configuration asks for. A
+    /// `fetch_width` of 0 fetches `width` instructions per cycle.
+    pub fn new(config: &Config) -> Self {
+        let width = config.pipeline.width.max(1);
+        let fetch_width = match config.pipeline.fetch_width {
+            0 => width,
+            w => w,
+        };
+        let ftq_size = config.pipeline.ftq_size.max(1);
+        let fetch_buffer_size = config.pipeline.fetch_buffer_size.max(fetch_width);
         Self {
-            fetch1_fetch2: Vec::with_capacity(width),
-            fetch2_decode: Vec::with_capacity(width),
+            ftq: VecDeque::with_capacity(ftq_size),
+            fetch_buffer: Vec::with_capacity(fetch_buffer_size),
             decode_rename: Vec::with_capacity(width),
             fetch1_stall: 0,
             fetch2_stall: 0,
-            fetch2_pending: Vec::with_capacity(width),
+            mispredict_stall: 0,
+            refill_seq: None,
+            fetch2_pending: Vec::with_capacity(fetch_width),
+            ftq_size,
+            fetch_buffer_size,
+            width,
+            fetch_width,
+            mispredict_penalty: config.pipeline.mispredict_penalty,
             _marker: PhantomData,
         }
     }
 
+    /// Returns the capacity of the fetch buffer in instructions.
+    pub const fn fetch_buffer_size(&self) -> usize {
+        self.fetch_buffer_size
+    }
+
+    /// Returns the largest fetch block in instructions.
+    pub const fn fetch_width(&self) -> usize {
+        self.fetch_width
+    }
+
+    /// Returns the capacity of the FTQ in fetch blocks.
+    pub const fn ftq_size(&self) -> usize {
+        self.ftq_size
+    }
+
     /// Executes one cycle of all frontend stages (reverse order).
     pub fn tick(
         &mut self,
@@ -59,51 +119,79 @@ impl<E: ExecutionEngine> Frontend<E> {
         // Rename: decode_rename -> engine (ROB alloc)
         rename::rename_stage(cpu, &mut self.decode_rename, engine, rename_output);
 
-        // Decode: fetch2_decode -> decode_rename
+        // Decode: fetch buffer head -> decode_rename
         // Only run decode when rename has consumed the previous output;
         // otherwise decode would keep appending to decode_rename while
         // rename can't drain it (e.g. ROB full), causing unbounded growth
         // and O(n²) behaviour as rename re-scans the growing vec each cycle.
-        if self.decode_rename.is_empty() {
-            decode::decode_stage(cpu, &mut self.fetch2_decode, &mut self.decode_rename);
+        // Decode sees at most `width` instructions; anything it leaves
+        // (intra-bundle hazard) goes back to the head of the buffer.
+        if self.decode_rename.is_empty() && !self.fetch_buffer.is_empty() {
+            let n = self.fetch_buffer.len().min(self.width);
+            let mut window: Vec<IfIdEntry> = self.fetch_buffer.drain(..n).collect();
+            decode::decode_stage(cpu, &mut window, &mut self.decode_rename);
+            let _ = self.fetch_buffer.splice(0..0, window);
         }
 
-        // Fetch2: fetch1_fetch2 -> fetch2_decode (gated by fetch2_stall or backpressure)
+        // Fetch2: FTQ head -> fetch buffer (gated by fetch2_stall, and by
+        // room in the buffer for a whole fetch block)
         if self.fetch2_stall > 0 {
             self.fetch2_stall -= 1;
             // When the stall expires, deliver pending instructions (from an
-            // I-cache miss that already decoded but couldn't deliver).
+            // I-cache miss that already decoded but couldn't deliver). Room
+            // was reserved when the block was fetched.
             if self.fetch2_stall == 0 && !self.fetch2_pending.is_empty() {
-                self.fetch2_decode.append(&mut self.fetch2_pending);
+                self.fetch_buffer.append(&mut self.fetch2_pending);
             }
-        } else if self.fetch2_decode.is_empty() {
+        } else if self.fetch_buffer.len() + self.fetch_width <= self.fetch_buffer_size
+            && let Some(mut block) = self.ftq.pop_front()
+        {
             fetch2::fetch2_stage(
                 cpu,
-                &mut self.fetch1_fetch2,
-                &mut self.fetch2_decode,
+                &mut block,
+                &mut self.fetch_buffer,
                 &mut self.fetch2_pending,
                 &mut self.fetch2_stall,
             );
         }
 
-        // Fetch1: PC gen -> fetch1_fetch2 (gated by fetch1_stall or backpressure)
-        if self.fetch1_stall > 0 {
+        // Fetch1: PC gen + prediction -> FTQ (gated by the misprediction
+        // bubble, fetch1_stall or a full FTQ)
+        if self.mispredict_stall > 0 {
+            self.mispredict_stall -= 1;
+        } else if self.fetch1_stall > 0 {
             self.fetch1_stall -= 1;
-        } else if self.fetch1_fetch2.is_empty() {
-            // Only run F1 when F2 has consumed the previous output;
-            // otherwise F1 would clear the latch and overwrite entries
-            // that F2 still needs to process.
-            fetch1::fetch1_stage(cpu, &mut self.fetch1_fetch2, &mut self.fetch1_stall);
+        } else if self.ftq.len() < self.ftq_size {
+            let mut block = Vec::with_capacity(self.fetch_width);
+            fetch1::fetch1_stage(cpu, self.fetch_width, &mut block, &mut self.fetch1_stall);
+            if !block.is_empty() {
+                self.ftq.push_back(block);
+            }
+        }
+
+        let occupancy = self.fetch_buffer.len() as u64;
+        cpu.stats.fetch_buffer_occupancy += occupancy;
+        cpu.stats.fetch_buffer_peak = cpu.stats.fetch_buffer_peak.max(occupancy);
+        if self.fetch_buffer.len() + self.fetch_width > self.fetch_buffer_size {
+            cpu.stats.fetch_buffer_full_cycles += 1;
         }
     }
 
-    /// Flushes all frontend latches and stall counters.
+    /// Flushes the FTQ, the fetch buffer, all latches and stall counters.
     pub fn flush(&mut self) {
-        self.fetch1_fetch2.clear();
-        self.fetch2_decode.clear();
+        self.ftq.clear();
+        self.fetch_buffer.clear();
         self.fetch2_pending.clear();
         self.decode_rename.clear();
         self.fetch1_stall = 0;
         self.fetch2_stall = 0;
+        self.mispredict_stall = 0;
+    }
+
+    /// Flushes the frontend after a branch misprediction and holds Fetch1
+    /// for the configured penalty before the refill starts.
+    pub fn flush_mispredict(&mut self) {
+        self.flush();
+        self.mispredict_stall = self.mispredict_penalty;
     }
 }
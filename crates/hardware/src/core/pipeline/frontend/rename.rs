@@ -4,6 +4,9 @@
 //! For the O3 backend (`has_prf` = true): uses physical register file, free list,
 //! and rename map to implement full register renaming.
 //!
+//! Both backends allocate a checkpoint for every branch/jump when the engine has
+//! checkpoint slots, and stall dispatch while all slots are in use.
+//!
 //! Source register tags are captured BEFORE the scoreboard is updated for rd,
 //! so that instructions reading their own destination (e.g. ADDI x5, x5, 16)
 //! get the previous producer's tag, not their own.
@@ -12,6 +15,7 @@ use crate::core::Cpu;
 use crate::core::pipeline::engine::ExecutionEngine;
 use crate::core::pipeline::latches::{IdExEntry, RenameIssueEntry};
 use crate::core::pipeline::prf::PhysReg;
+use crate::core::pipeline::rename_map::RenameMap;
 use crate::core::pipeline::signals::ControlFlow;
 use crate::trace_rename;
 
@@ -44,31 +48,32 @@ pub fn rename_stage<E: ExecutionEngine>(
             // (only count once per cycle, on the first rejected entry)
             if input.is_empty() {
                 cpu.stats.stalls_dispatch += 1;
+                if engine.store_buffer().free_slots() == 0 {
+                    cpu.stats.stalls_store_buffer_full += 1;
+                }
             }
             // Put unconsumed entries back
             input.push(id);
             continue;
         }
 
+        // Stall if this is a branch/jump and the checkpoint table is full
+        let is_branch_or_jump =
+            matches!(id.ctrl.control_flow, ControlFlow::Branch | ControlFlow::Jump);
+        let checkpointed = is_branch_or_jump && engine.checkpoint_count() > 0;
+        if checkpointed && engine.checkpoint_table().is_full() {
+            cpu.stats.stalls_checkpoint += 1;
+            // Can't break — remaining entries in the iterator would be
+            // dropped (lost forever, PC already advanced past them).
+            // Set budget=0 so subsequent iterations hit the budget check
+            // and push their entries back into input too.
+            budget = 0;
+            input.push(id);
+            continue;
+        }
+
         if engine.has_prf() {
             // ── O3 backend: full physical register renaming ────────────────
-            // Stall if this is a branch/jump and the checkpoint table is full
-            let is_branch_or_jump =
-                matches!(id.ctrl.control_flow, ControlFlow::Branch | ControlFlow::Jump);
-            if is_branch_or_jump
-                && engine.checkpoint_count() > 0
-                && engine.checkpoint_table().is_full()
-            {
-                cpu.stats.stalls_checkpoint += 1;
-                // Can't break — remaining entries in the iterator would be
-                // dropped (lost forever, PC already advanced past them).
-                // Set budget=0 so subsequent iterations hit the budget check
-                // and push their entries back into input too.
-                budget = 0;
-                input.push(id);
-                continue;
-            }
-
             // Capture source physical regs BEFORE updating rename map for rd
             let rs1_phys = engine.rename_map().get(id.rs1, id.ctrl.rs1_fp);
             let rs2_phys = engine.rename_map().get(id.rs2, id.ctrl.rs2_fp);
@@ -109,6 +114,11 @@ pub fn rename_stage<E: ExecutionEngine>(
                 break;
             };
 
+            if let Some(head) = id.fused {
+                engine.rob_mut().set_fused(rob_tag, head);
+            }
+            engine.rob_mut().set_seq(rob_tag, id.seq);
+
             // Update speculative rename map and mark PRF not-ready
             if needs_dst {
                 engine.rename_map_mut().set(id.rd, id.ctrl.fp_reg_write, rd_phys);
@@ -136,10 +146,12 @@ pub fn rename_stage<E: ExecutionEngine>(
             }
 
             // Allocate checkpoint for branch/jump (snapshot rename map *after* rd rename)
-            if is_branch_or_jump && engine.checkpoint_count() > 0 {
+            if checkpointed {
                 let map_snapshot = engine.rename_map().clone();
+                let sb_snapshot = engine.scoreboard().clone();
 
-                let Some(ckpt_id) = engine.checkpoint_table_mut().allocate(rob_tag, &map_snapshot)
+                let Some(ckpt_id) =
+                    engine.checkpoint_table_mut().allocate(rob_tag, map_snapshot, sb_snapshot)
                 else {
                     unreachable!("checkpoint table full after stall check");
                 };
@@ -179,6 +191,7 @@ pub fn rename_stage<E: ExecutionEngine>(
                 pred_target: id.pred_target,
                 ghr_snapshot: id.ghr_snapshot,
                 ras_snapshot: id.ras_snapshot,
+                seq: id.seq,
             };
 
             trace_rename!(cpu.trace;
@@ -197,6 +210,9 @@ pub fn rename_stage<E: ExecutionEngine>(
                 "RN: O3 rename"
             );
 
+            if let Some(trace) = &mut cpu.pipe_trace {
+                trace.rename(entry.seq, rob_tag, cpu.stats.cycles);
+            }
             rename_output.push(entry);
         } else {
             // ── In-order / legacy backend: scoreboard-based rename ─────────
@@ -215,6 +231,11 @@ pub fn rename_stage<E: ExecutionEngine>(
                 break;
             };
 
+            if let Some(head) = id.fused {
+                engine.rob_mut().set_fused(rob_tag, head);
+            }
+            engine.rob_mut().set_seq(rob_tag, id.seq);
+
             // Capture source register tags BEFORE updating scoreboard for rd.
             let rs1_tag = engine.scoreboard().get_producer(id.rs1, id.ctrl.rs1_fp);
             let rs2_tag = engine.scoreboard().get_producer(id.rs2, id.ctrl.rs2_fp);
@@ -235,6 +256,19 @@ pub fn rename_stage<E: ExecutionEngine>(
                 }
             }
 
+            // Allocate checkpoint for branch/jump (snapshot scoreboard *after* rd marking)
+            if checkpointed {
+                let sb_snapshot = engine.scoreboard().clone();
+
+                let Some(ckpt_id) =
+                    engine.checkpoint_table_mut().allocate(rob_tag, RenameMap::new(), sb_snapshot)
+                else {
+                    unreachable!("checkpoint table full after stall check");
+                };
+
+                engine.rob_mut().set_checkpoint_id(rob_tag, ckpt_id);
+            }
+
             // Create RenameIssueEntry — operand values are 0, read at issue stage
             let entry = RenameIssueEntry {
                 rob_tag,
@@ -263,6 +297,7 @@ pub fn rename_stage<E: ExecutionEngine>(
                 pred_target: id.pred_target,
                 ghr_snapshot: id.ghr_snapshot,
                 ras_snapshot: id.ras_snapshot,
+                seq: id.seq,
             };
 
             trace_rename!(cpu.trace;
@@ -278,6 +313,9 @@ pub fn rename_stage<E: ExecutionEngine>(
                 "RN: in-order rename"
             );
 
+            if let Some(trace) = &mut cpu.pipe_trace {
+                trace.rename(entry.seq, rob_tag, cpu.stats.cycles);
+            }
             rename_output.push(entry);
         }
         budget -= 1;
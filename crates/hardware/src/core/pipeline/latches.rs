@@ -9,10 +9,12 @@
 
 use crate::common::error::{ExceptionStage, LrScRecord, PteUpdate, SfenceVmaInfo, Trap};
 use crate::common::{InstSize, PhysAddr, RegIdx, VirtAddr};
+use crate::core::pipeline::frontend::fusion::FusedHead;
 use crate::core::pipeline::prf::PhysReg;
 use crate::core::pipeline::rob::RobTag;
 use crate::core::pipeline::signals::ControlSignals;
 use crate::core::units::bru::Ghr;
+use crate::core::units::bru::ras::RasSnapshot;
 
 /// Entry in the IF/ID pipeline latch (Fetch to Decode stage).
 ///
@@ -36,8 +38,10 @@ pub struct IfIdEntry {
     pub exception_stage: Option<ExceptionStage>,
     /// GHR snapshot captured at prediction time for speculative history repair.
     pub ghr_snapshot: Ghr,
-    /// RAS pointer snapshot captured at prediction time for speculative recovery.
-    pub ras_snapshot: usize,
+    /// RAS snapshot captured at prediction time for speculative recovery.
+    pub ras_snapshot: RasSnapshot,
+    /// Fetch sequence number, carried through to the ROB for the pipeline trace.
+    pub seq: u64,
 }
 
 /// Entry in the ID/EX pipeline latch (Decode to Execute stage).
@@ -80,8 +84,12 @@ pub struct IdExEntry {
     pub pred_target: u64,
     /// GHR snapshot captured at prediction time for speculative history repair.
     pub ghr_snapshot: Ghr,
-    /// RAS pointer snapshot captured at prediction time for speculative recovery.
-    pub ras_snapshot: usize,
+    /// RAS snapshot captured at prediction time for speculative recovery.
+    pub ras_snapshot: RasSnapshot,
+    /// Head instruction absorbed by macro-op fusion, if any.
+    pub fused: Option<FusedHead>,
+    /// Fetch sequence number, carried through to the ROB for the pipeline trace.
+    pub seq: u64,
 }
 
 /// Entry in the EX/MEM pipeline latch (Execute to Memory stage).
@@ -158,8 +166,10 @@ pub struct Fetch1Fetch2Entry {
     pub exception_stage: Option<ExceptionStage>,
     /// GHR snapshot captured at prediction time for speculative history repair.
     pub ghr_snapshot: Ghr,
-    /// RAS pointer snapshot captured at prediction time for speculative recovery.
-    pub ras_snapshot: usize,
+    /// RAS snapshot captured at prediction time for speculative recovery.
+    pub ras_snapshot: RasSnapshot,
+    /// Fetch sequence number, carried through to the ROB for the pipeline trace.
+    pub seq: u64,
 }
 
 /// Entry from Rename -> Issue (also used as Issue -> Execute input).
@@ -218,8 +228,10 @@ pub struct RenameIssueEntry {
     pub pred_target: u64,
     /// GHR snapshot captured at prediction time for speculative history repair.
     pub ghr_snapshot: Ghr,
-    /// RAS pointer snapshot captured at prediction time for speculative recovery.
-    pub ras_snapshot: usize,
+    /// RAS snapshot captured at prediction time for speculative recovery.
+    pub ras_snapshot: RasSnapshot,
+    /// Fetch sequence number, carried through to the ROB for the pipeline trace.
+    pub seq: u64,
 }
 
 /// Entry from Execute -> Memory1 latch.
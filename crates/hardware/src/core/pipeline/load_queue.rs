@@ -250,6 +250,19 @@ impl LoadQueue {
         self.count = new_count;
     }
 
+    /// Returns the entry with the given ROB tag, if present.
+    pub fn find(&self, rob_tag: RobTag) -> Option<&LoadQueueEntry> {
+        let cap = self.entries.len();
+        let mut idx = self.head;
+        for _ in 0..self.count {
+            if self.entries[idx].valid && self.entries[idx].rob_tag == rob_tag {
+                return Some(&self.entries[idx]);
+            }
+            idx = (idx + 1) % cap;
+        }
+        None
+    }
+
     /// Finds the entry with the given ROB tag.
     fn find_by_tag_mut(&mut self, rob_tag: RobTag) -> Option<&mut LoadQueueEntry> {
         let cap = self.entries.len();
@@ -271,6 +284,7 @@ const fn width_to_bytes(w: MemWidth) -> usize {
         MemWidth::Half => 2,
         MemWidth::Word => 4,
         MemWidth::Double => 8,
+        MemWidth::Quad => 16,
         MemWidth::Nop => 0,
     }
 }
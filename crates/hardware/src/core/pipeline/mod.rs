@@ -8,6 +8,7 @@
 //! 5. **Backend:** Issue, Execute, Memory1, Memory2, Writeback, and Commit stages.
 //! 6. **Latches:** Inter-stage buffers for communication between pipeline stages.
 //! 7. **Signals:** Control signals generated during instruction decoding.
+//! 8. **Occupancy:** Per-cycle ROB, store buffer, and issue queue occupancy histograms.
 
 /// Execution engine traits and pipeline dispatch.
 pub mod engine;
@@ -56,3 +57,9 @@ pub mod backend;
 
 /// Point-in-time pipeline state snapshot.
 pub mod snapshot;
+
+/// Pipeline visualization trace (Konata / O3PipeView).
+pub mod pipe_trace;
+
+/// Per-cycle buffer occupancy histograms.
+pub mod occupancy;
@@ -0,0 +1,100 @@
+//! Per-cycle occupancy sampling for pipeline buffers.
+//!
+//! This module provides [`OccupancyHistogram`], which the pipeline fills in
+//! once per cycle for the ROB, store buffer, and issue queue. It tracks:
+//! 1. **Distribution:** A per-entry histogram of sampled occupancy, from which
+//!    percentiles are derived.
+//! 2. **Summary:** Minimum, average, and maximum occupancy.
+
+/// Number of single-entry histogram buckets. Occupancies at or above this
+/// value are accumulated in the final (overflow) bucket.
+pub const OCCUPANCY_BUCKETS: usize = 1024;
+
+/// Fixed-bucket histogram of a buffer's occupancy, sampled once per cycle.
+#[derive(Clone, Debug)]
+pub struct OccupancyHistogram {
+    /// Cycle count per occupancy (index = entries, last bucket = overflow).
+    histogram: Box<[u64; OCCUPANCY_BUCKETS]>,
+    /// Total samples recorded.
+    samples: u64,
+    /// Sum of all sampled occupancies (for the average).
+    sum: u64,
+    /// Smallest occupancy observed.
+    min: u64,
+    /// Largest occupancy observed.
+    max: u64,
+}
+
+impl Default for OccupancyHistogram {
+    /// Returns an empty histogram.
+    fn default() -> Self {
+        Self {
+            histogram: Box::new([0; OCCUPANCY_BUCKETS]),
+            samples: 0,
+            sum: 0,
+            min: u64::MAX,
+            max: 0,
+        }
+    }
+}
+
+impl OccupancyHistogram {
+    /// Records one cycle's occupancy.
+    pub fn record(&mut self, occupancy: usize) {
+        self.histogram[occupancy.min(OCCUPANCY_BUCKETS - 1)] += 1;
+        let occupancy = occupancy as u64;
+        self.samples += 1;
+        self.sum += occupancy;
+        self.min = self.min.min(occupancy);
+        self.max = self.max.max(occupancy);
+    }
+
+    /// Returns the number of cycles sampled.
+    pub const fn samples(&self) -> u64 {
+        self.samples
+    }
+
+    /// Returns the smallest occupancy observed (0 if nothing was sampled).
+    pub const fn min(&self) -> u64 {
+        if self.samples == 0 { 0 } else { self.min }
+    }
+
+    /// Returns the largest occupancy observed.
+    pub const fn max(&self) -> u64 {
+        self.max
+    }
+
+    /// Returns the average occupancy (0.0 if nothing was sampled).
+    pub fn avg(&self) -> f64 {
+        if self.samples == 0 { 0.0 } else { self.sum as f64 / self.samples as f64 }
+    }
+
+    /// Returns the occupancy at or below which `pct` percent of cycles were
+    /// sampled. Occupancies in the overflow bucket report the maximum.
+    pub fn percentile(&self, pct: f64) -> u64 {
+        if self.samples == 0 {
+            return 0;
+        }
+        let target = ((self.samples as f64 * pct / 100.0).ceil() as u64).clamp(1, self.samples);
+        let mut seen = 0;
+        for (occupancy, &count) in self.histogram.iter().enumerate() {
+            seen += count;
+            if seen >= target {
+                return if occupancy == OCCUPANCY_BUCKETS - 1 {
+                    self.max
+                } else {
+                    occupancy as u64
+                };
+            }
+        }
+        self.max
+    }
+
+    /// Returns the histogram up to the largest occupancy observed
+    /// (index = entries, last bucket = overflow if reached).
+    pub fn histogram(&self) -> &[u64] {
+        let len =
+            if self.samples == 0 { 0 } else { (self.max as usize + 1).min(OCCUPANCY_BUCKETS) };
+        &self.histogram[..len]
+    }
+}
@@ -0,0 +1,367 @@
+//! Pipeline visualization trace (Konata / gem5 O3PipeView).
+//!
+//! Every instruction is tagged with a sequence number at Fetch1. The number
+//! travels through the latches and the ROB, and each stage reports the cycle
+//! at which the instruction entered it. The trace file can then be loaded into
+//! [Konata](https://github.com/shioyadan/Konata) to visualize the pipeline:
+//! 1. **Konata:** Kanata 0004 log in cycle order, each instruction's commands
+//!    written once it has retired or been squashed and nothing in flight can
+//!    come before them.
+//! 2. **O3PipeView:** gem5's `O3PipeView` records, written once an instruction
+//!    has retired or been squashed, in sequence-number order.
+//!
+//! Instructions that are squashed by a redirect are marked as flushed (Konata
+//! retire type 1; O3PipeView retire tick 0).
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::common::SimError;
+use crate::config::PipeTraceFormat;
+use crate::core::pipeline::rob::RobTag;
+use crate::isa::disasm::disassemble;
+
+/// gem5 ticks per simulated cycle in `O3PipeView` output (1 GHz clock).
+const O3_TICKS_PER_CYCLE: u64 = 1000;
+
+/// Pipeline stage reported to the trace, in pipeline order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PipeStage {
+    /// Fetch1/Fetch2: PC generation through the fetch buffer.
+    Fetch,
+    /// Decode.
+    Decode,
+    /// Rename, including time spent waiting in the issue queue.
+    Rename,
+    /// Issued to a functional unit and executing.
+    Execute,
+    /// Memory1/Memory2.
+    Memory,
+    /// Writeback: result written to the ROB.
+    Writeback,
+    /// Commit.
+    Commit,
+}
+
+impl PipeStage {
+    /// Number of stages.
+    const COUNT: usize = 7;
+
+    /// Every stage, in pipeline order.
+    const ALL: [Self; Self::COUNT] = [
+        Self::Fetch,
+        Self::Decode,
+        Self::Rename,
+        Self::Execute,
+        Self::Memory,
+        Self::Writeback,
+        Self::Commit,
+    ];
+
+    /// Konata stage name.
+    const fn konata_name(self) -> &'static str {
+        match self {
+            Self::Fetch => "F",
+            Self::Decode => "Dc",
+            Self::Rename => "Rn",
+            Self::Execute => "Ex",
+            Self::Memory => "Mem",
+            Self::Writeback => "Wb",
+            Self::Commit => "Cm",
+        }
+    }
+}
+
+/// Per-instruction record, live from fetch until retire or squash.
+#[derive(Debug)]
+struct Record {
+    /// Program counter.
+    pc: u64,
+    /// Disassembly, filled in at decode.
+    disasm: String,
+    /// Cycle at which each stage was entered.
+    cycles: [Option<u64>; PipeStage::COUNT],
+    /// Latest stage entered.
+    stage: PipeStage,
+    /// Fused head carried along with this instruction.
+    fused_head: Option<u64>,
+    /// Fused tail that this instruction was absorbed into.
+    absorbed_by: Option<u64>,
+}
+
+/// Pipeline trace writer for one hart.
+#[derive(Debug)]
+pub struct PipeTrace {
+    out: BufWriter<File>,
+    format: PipeTraceFormat,
+    /// In-flight instructions keyed by sequence number.
+    live: HashMap<u64, Record>,
+    /// ROB tag to sequence number, assigned at rename.
+    tags: HashMap<RobTag, u64>,
+    /// Finished `O3PipeView` records waiting for older instructions to finish.
+    done: BTreeMap<u64, String>,
+    /// Finished Konata commands keyed by (cycle, sequence number, order),
+    /// waiting until no in-flight instruction can add an earlier one.
+    pending: BTreeMap<(u64, u64, u8), String>,
+    /// Cycle of the latest command written.
+    cycle: Option<u64>,
+    /// Whether instructions finishing now are written (see [`Self::set_active`]).
+    active: bool,
+    next_id: u64,
+    next_retire_id: u64,
+}
+
+impl PipeTrace {
+    /// Creates a trace writing `format` records to `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SimError::FileRead`] if the file cannot be created.
+    pub fn create(path: &str, format: PipeTraceFormat) -> Result<Self, SimError> {
+        let file = File::create(path)
+            .map_err(|source| SimError::FileRead { path: path.to_owned(), source })?;
+        let mut out = BufWriter::with_capacity(1 << 20, file);
+        if format == PipeTraceFormat::Konata {
+            let _ = writeln!(out, "Kanata\t0004");
+        }
+        Ok(Self {
+            out,
+            format,
+            live: HashMap::new(),
+            tags: HashMap::new(),
+            done: BTreeMap::new(),
+            pending: BTreeMap::new(),
+            cycle: None,
+            active: true,
+            next_id: 0,
+            next_retire_id: 0,
+        })
+    }
+
+    /// Selects whether instructions that retire or are squashed from now on
+    /// appear in the trace. Used for the trace region: an instruction is kept
+    /// if the region was active when it left the pipeline.
+    pub const fn set_active(&mut self, active: bool) {
+        self.active = active;
+    }
+
+    /// Advances the trace to `cycle`, writing the Konata cycle command.
+    fn advance(&mut self, cycle: u64) {
+        match self.cycle {
+            None => {
+                let _ = writeln!(self.out, "C=\t{cycle}");
+            }
+            Some(last) if cycle > last => {
+                let _ = writeln!(self.out, "C\t{}", cycle - last);
+            }
+            Some(_) => return,
+        }
+        self.cycle = Some(cycle);
+    }
+
+    /// Records instruction `seq` being fetched at `pc`.
+    pub fn fetch(&mut self, seq: u64, pc: u64, cycle: u64) {
+        let mut cycles = [None; PipeStage::COUNT];
+        cycles[PipeStage::Fetch as usize] = Some(cycle);
+        let _ = self.live.insert(
+            seq,
+            Record {
+                pc,
+                disasm: String::new(),
+                cycles,
+                stage: PipeStage::Fetch,
+                fused_head: None,
+                absorbed_by: None,
+            },
+        );
+    }
+
+    /// Records instruction `seq` being decoded as `inst`.
+    pub fn decode(&mut self, seq: u64, inst: u32, cycle: u64) {
+        let Some(rec) = self.live.get_mut(&seq) else { return };
+        rec.disasm = disassemble(inst);
+        self.stage(seq, PipeStage::Decode, cycle);
+    }
+
+    /// Records instruction `seq` being renamed into ROB entry `tag`.
+    pub fn rename(&mut self, seq: u64, tag: RobTag, cycle: u64) {
+        let _ = self.tags.insert(tag, seq);
+        self.stage(seq, PipeStage::Rename, cycle);
+    }
+
+    /// Records the instruction in ROB entry `tag` entering `stage`.
+    pub fn stage_tag(&mut self, tag: RobTag, stage: PipeStage, cycle: u64) {
+        if let Some(&seq) = self.tags.get(&tag) {
+            self.stage(seq, stage, cycle);
+        }
+    }
+
+    /// Records instruction `seq` entering `stage`. Stages already passed are
+    /// ignored, so a stage retried after a stall keeps its first cycle.
+    pub fn stage(&mut self, seq: u64, stage: PipeStage, cycle: u64) {
+        let Some(rec) = self.live.get_mut(&seq) else { return };
+        if rec.cycles[stage as usize].is_some() || stage < rec.stage {
+            return;
+        }
+        rec.cycles[stage as usize] = Some(cycle);
+        rec.stage = stage;
+        if let Some(head) = rec.fused_head {
+            self.stage(head, stage, cycle);
+        }
+    }
+
+    /// Records `head` being fused into `tail` at decode. The head follows the
+    /// tail through the rest of the pipeline and retires with it.
+    pub fn fuse(&mut self, head: u64, tail: u64) {
+        if let Some(rec) = self.live.get_mut(&head) {
+            rec.absorbed_by = Some(tail);
+        }
+        if let Some(rec) = self.live.get_mut(&tail) {
+            rec.fused_head = Some(head);
+        }
+    }
+
+    /// Records instruction `seq` retiring, along with its fused head.
+    pub fn retire(&mut self, seq: u64, cycle: u64) {
+        let head = self.live.get(&seq).and_then(|r| r.fused_head);
+        if let Some(head) = head {
+            self.finish(head, cycle, false);
+        }
+        self.finish(seq, cycle, false);
+    }
+
+    /// Marks every in-flight instruction not in `survivors` as flushed.
+    pub fn squash_except(&mut self, survivors: impl Iterator<Item = u64>, cycle: u64) {
+        let survivors: HashSet<u64> = survivors.collect();
+        let mut squashed: Vec<u64> = self
+            .live
+            .iter()
+            .filter(|(seq, rec)| !survivors.contains(&rec.absorbed_by.unwrap_or(**seq)))
+            .map(|(seq, _)| *seq)
+            .collect();
+        squashed.sort_unstable();
+        for seq in squashed {
+            self.finish(seq, cycle, true);
+        }
+    }
+
+    /// Ends instruction `seq`'s lifetime and writes what has become final.
+    fn finish(&mut self, seq: u64, cycle: u64, flushed: bool) {
+        if !flushed {
+            self.stage(seq, PipeStage::Commit, cycle);
+        }
+        let Some(rec) = self.live.remove(&seq) else { return };
+        if self.active {
+            match self.format {
+                PipeTraceFormat::Konata => self.finish_konata(seq, &rec, cycle, flushed),
+                PipeTraceFormat::O3PipeView => {
+                    let _ = self.done.insert(seq, Self::o3pipeview_record(seq, &rec, flushed));
+                }
+            }
+        }
+        self.write_final();
+    }
+
+    /// Queues the Konata commands for a finished instruction.
+    fn finish_konata(&mut self, seq: u64, rec: &Record, cycle: u64, flushed: bool) {
+        let id = self.next_id;
+        self.next_id += 1;
+        let retire_id = if flushed {
+            0
+        } else {
+            self.next_retire_id += 1;
+            self.next_retire_id - 1
+        };
+        let fetched = rec.cycles[PipeStage::Fetch as usize].unwrap_or(cycle);
+        let mut commands = vec![
+            (fetched, format!("I\t{id}\t{seq}\t0")),
+            (fetched, format!("L\t{id}\t0\t{:08x}: ", rec.pc)),
+        ];
+        if let Some(decoded) = rec.cycles[PipeStage::Decode as usize]
+            && !rec.disasm.is_empty()
+        {
+            commands.push((decoded, format!("L\t{id}\t0\t{}", rec.disasm)));
+        }
+        for (i, stage) in PipeStage::ALL.iter().enumerate() {
+            if let Some(c) = rec.cycles[i] {
+                commands.push((c, format!("S\t{id}\t0\t{}", stage.konata_name())));
+            }
+        }
+        commands.push((cycle, format!("R\t{id}\t{retire_id}\t{}", u8::from(flushed))));
+        // Stable sort keeps the per-instruction order within a cycle.
+        commands.sort_by_key(|&(c, _)| c);
+        for (order, (c, text)) in commands.into_iter().enumerate() {
+            let _ = self.pending.insert((c, seq, order as u8), text);
+        }
+    }
+
+    /// Formats the `O3PipeView` record of a finished instruction.
+    fn o3pipeview_record(seq: u64, rec: &Record, flushed: bool) -> String {
+        let tick =
+            |stage: PipeStage| rec.cycles[stage as usize].map_or(0, |c| c * O3_TICKS_PER_CYCLE);
+        let retire = if flushed { 0 } else { tick(PipeStage::Commit) };
+        format!(
+            "O3PipeView:fetch:{}:0x{:08x}:0:{seq}:{}\n\
+             O3PipeView:decode:{}\n\
+             O3PipeView:rename:{}\n\
+             O3PipeView:dispatch:{}\n\
+             O3PipeView:issue:{}\n\
+             O3PipeView:complete:{}\n\
+             O3PipeView:retire:{retire}:store:0\n",
+            tick(PipeStage::Fetch),
+            rec.pc,
+            rec.disasm,
+            tick(PipeStage::Decode),
+            tick(PipeStage::Rename),
+            tick(PipeStage::Rename),
+            tick(PipeStage::Execute),
+            tick(PipeStage::Writeback),
+        )
+    }
+
+    /// Writes the finished output that no in-flight instruction can precede:
+    /// `O3PipeView` records older than every live instruction, and Konata
+    /// commands no later than the earliest live fetch.
+    fn write_final(&mut self) {
+        match self.format {
+            PipeTraceFormat::Konata => {
+                let earliest = self
+                    .live
+                    .values()
+                    .filter_map(|r| r.cycles[PipeStage::Fetch as usize])
+                    .min()
+                    .unwrap_or(u64::MAX);
+                while let Some(entry) = self.pending.first_entry() {
+                    let cycle = entry.key().0;
+                    if cycle > earliest {
+                        break;
+                    }
+                    let text = entry.remove();
+                    self.advance(cycle);
+                    let _ = writeln!(self.out, "{text}");
+                }
+            }
+            PipeTraceFormat::O3PipeView => {
+                let oldest_live = self.live.keys().min().copied().unwrap_or(u64::MAX);
+                while let Some(entry) = self.done.first_entry() {
+                    if *entry.key() > oldest_live {
+                        break;
+                    }
+                    let _ = self.out.write_all(entry.remove().as_bytes());
+                }
+            }
+        }
+    }
+}
+
+impl Drop for PipeTrace {
+    fn drop(&mut self) {
+        let cycle = self.live.values().flat_map(|r| r.cycles).flatten().max().unwrap_or(0);
+        self.squash_except(std::iter::empty(), cycle);
+        for text in std::mem::take(&mut self.done).into_values() {
+            let _ = self.out.write_all(text.as_bytes());
+        }
+        let _ = self.out.flush();
+    }
+}
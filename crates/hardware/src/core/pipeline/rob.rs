@@ -11,11 +11,13 @@
 use std::collections::HashMap;
 
 use crate::common::error::{ExceptionStage, LrScRecord, PteUpdate, SfenceVmaInfo, Trap};
-use crate::common::{CsrAddr, InstSize, RegIdx};
+use crate::common::{CsrAddr, InstSize, PhysAddr, RegIdx};
 use crate::core::pipeline::checkpoint::CheckpointId;
+use crate::core::pipeline::frontend::fusion::FusedHead;
 use crate::core::pipeline::prf::PhysReg;
 use crate::core::pipeline::signals::ControlSignals;
 use crate::core::units::bru::Ghr;
+use crate::core::units::lsu::ordering::Fence;
 
 /// Branch outcome recorded at execute time for deferred predictor update.
 ///
@@ -114,6 +116,8 @@ pub struct RobEntry {
     pub store_data: u64,
     /// Virtual address for loads/stores (ALU output for memory ops).
     pub store_addr: u64,
+    /// Physical address of a load or store, recorded for the access trace.
+    pub mem_paddr: PhysAddr,
     /// Control signals from decode.
     pub ctrl: ControlSignals,
     /// Current lifecycle state.
@@ -150,6 +154,10 @@ pub struct RobEntry {
     pub lr_sc: Option<LrScRecord>,
     /// Checkpoint table slot allocated for this branch/jump (O3 backend).
     pub checkpoint_id: Option<CheckpointId>,
+    /// Head instruction absorbed by macro-op fusion; retires with this entry.
+    pub fused: Option<FusedHead>,
+    /// Fetch sequence number (pipeline trace).
+    pub seq: u64,
 }
 
 /// Reorder Buffer — circular buffer for in-order commit.
@@ -247,6 +255,7 @@ impl Rob {
             result: None,
             store_data: 0,
             store_addr: 0,
+            mem_paddr: PhysAddr::default(),
             ctrl,
             state: RobState::Issued,
             trap: None,
@@ -265,6 +274,8 @@ impl Rob {
             sfence_vma: None,
             lr_sc: None,
             checkpoint_id: None,
+            fused: None,
+            seq: 0,
         };
 
         let _ = self.tag_index.insert(tag, self.tail);
@@ -396,6 +407,20 @@ impl Rob {
         }
     }
 
+    /// Records the head instruction fused into a given entry.
+    pub fn set_fused(&mut self, tag: RobTag, head: FusedHead) {
+        if let Some(entry) = self.find_entry_mut(tag) {
+            entry.fused = Some(head);
+        }
+    }
+
+    /// Records the fetch sequence number of a given entry.
+    pub fn set_seq(&mut self, tag: RobTag, seq: u64) {
+        if let Some(entry) = self.find_entry_mut(tag) {
+            entry.seq = seq;
+        }
+    }
+
     /// Sets the store address and data for a given entry.
     pub fn set_store_info(&mut self, tag: RobTag, addr: u64, data: u64) {
         if let Some(entry) = self.find_entry_mut(tag) {
@@ -404,6 +429,13 @@ impl Rob {
         }
     }
 
+    /// Sets the physical address accessed by a load or store.
+    pub fn set_mem_paddr(&mut self, tag: RobTag, paddr: PhysAddr) {
+        if let Some(entry) = self.find_entry_mut(tag) {
+            entry.mem_paddr = paddr;
+        }
+    }
+
     /// Returns a reference to the head entry (oldest), if the ROB is non-empty.
     pub fn peek_head(&self) -> Option<&RobEntry> {
         if self.count == 0 { None } else { Some(&self.entries[self.head]) }
@@ -679,6 +711,31 @@ impl Rob {
         true // tag not found in ROB (shouldn't happen)
     }
 
+    /// Returns true if an entry older than `tag` holds a CSR write that has
+    /// not yet been applied to architectural state.
+    ///
+    /// CSR writes are deferred to commit, so a younger CSR instruction that
+    /// issued while one is pending would read the stale value. Rather than
+    /// forwarding the pending value (which would have to replicate WARL
+    /// masking and aliases such as `sstatus`/`mstatus`), the issue stage
+    /// holds CSR instructions until this returns false.
+    pub fn has_pending_csr_write_before(&self, tag: RobTag) -> bool {
+        let mut idx = self.head;
+        for _ in 0..self.count {
+            let entry = &self.entries[idx];
+            if entry.valid {
+                if entry.tag == tag {
+                    return false;
+                }
+                if entry.csr_update.as_ref().is_some_and(|u| !u.applied) {
+                    return true;
+                }
+            }
+            idx = (idx + 1) % self.entries.len();
+        }
+        false
+    }
+
     /// Returns true if all older ROB entries matching a FENCE's predecessor
     /// set have completed (Completed or Faulted).
     ///
@@ -712,11 +769,14 @@ impl Rob {
         true
     }
 
-    /// Checks if an older in-flight FENCE in the ROB blocks issuance of an
-    /// instruction with the given `tag`, `is_load`, and `is_store` flags.
+    /// Checks if an older in-flight FENCE or acquire atomic in the ROB blocks
+    /// issuance of an instruction with the given `tag`, `is_load`, and
+    /// `is_store` flags.
     ///
     /// A FENCE with successor bits `succ.r` / `succ.w` prevents younger
     /// loads/stores (respectively) from issuing until the FENCE has committed.
+    /// An atomic with the `aq` bit prevents younger loads and stores from
+    /// issuing until it has performed its memory access.
     /// Returns `true` if the instruction is blocked by an older fence.
     pub fn has_fence_blocking(&self, tag: RobTag, is_load: bool, is_store: bool) -> bool {
         if self.count == 0 || (!is_load && !is_store) {
@@ -730,15 +790,15 @@ impl Rob {
                     return false; // reached our entry — no older fence found
                 }
                 if entry.ctrl.system_op == crate::core::pipeline::signals::SystemOp::Fence {
-                    // Decode FENCE pred/succ from the raw instruction
-                    let succ_bits = ((entry.inst >> 20) & 0xF) as u8;
-                    let succ_r = succ_bits & 0b0010 != 0;
-                    let succ_w = succ_bits & 0b0001 != 0;
-                    let blocked = (is_load && succ_r) || (is_store && succ_w);
+                    let succ = Fence::decode(entry.inst).succ;
+                    let blocked = (is_load && succ.r) || (is_store && succ.w);
                     if blocked {
                         return true;
                     }
                 }
+                if entry.ctrl.aq && entry.state == RobState::Issued {
+                    return true;
+                }
             }
             idx = (idx + 1) % self.entries.len();
         }
@@ -912,6 +972,33 @@ mod tests {
         assert_eq!(csr.new_val, 20);
     }
 
+    #[test]
+    fn test_has_pending_csr_write_before() {
+        let mut rob = Rob::new(4);
+        let writer = alloc(&mut rob, 0x1000, 0, make_ctrl(true, false)).unwrap();
+        let reader = alloc(&mut rob, 0x1004, 5, make_ctrl(true, false)).unwrap();
+        assert!(!rob.has_pending_csr_write_before(reader));
+
+        rob.set_csr_update(
+            writer,
+            CsrUpdate { addr: CsrAddr::from_u32(0x140), old_val: 0, new_val: 1, applied: false },
+        );
+        rob.complete(writer, 0);
+        assert!(rob.has_pending_csr_write_before(reader));
+        assert!(!rob.has_pending_csr_write_before(writer));
+
+        // Eagerly applied writes are already architectural.
+        rob.mark_csr_applied(writer);
+        assert!(!rob.has_pending_csr_write_before(reader));
+
+        rob.set_csr_update(
+            writer,
+            CsrUpdate { addr: CsrAddr::from_u32(0x140), old_val: 0, new_val: 1, applied: false },
+        );
+        let _ = rob.commit_head();
+        assert!(!rob.has_pending_csr_write_before(reader));
+    }
+
     #[test]
     fn test_circular_wraparound() {
         let mut rob = Rob::new(2);
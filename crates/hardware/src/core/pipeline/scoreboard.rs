@@ -11,7 +11,7 @@ use crate::core::pipeline::rob::{Rob, RobTag};
 /// Tag-based scoreboard: maps each architectural register to the ROB tag
 /// of its latest in-flight producer, or None if the value is in the
 /// architectural register file.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Scoreboard {
     /// GPR scoreboard (x0 always None — hardwired zero).
     gpr: [Option<RobTag>; 32],
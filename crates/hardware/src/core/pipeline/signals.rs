@@ -6,7 +6,8 @@
 //! 3. **Memory Control:** Specifies access widths and sign-extension requirements.
 //! 4. **System Control:** Manages privilege transitions and system-level instructions.
 
-use crate::common::CsrAddr;
+use crate::common::{CsrAddr, RegIdx};
+use crate::core::pipeline::frontend::fusion::FusionPattern;
 
 /// ALU operation types for integer and floating-point instructions.
 #[derive(Clone, Copy, Debug, Default)]
@@ -66,6 +67,12 @@ pub enum AluOp {
     /// Integer remainder (unsigned).
     Remu,
 
+    /// Conditional zero if `rs2` is zero (Zicond `czero.eqz`).
+    CzeroEqz,
+
+    /// Conditional zero if `rs2` is non-zero (Zicond `czero.nez`).
+    CzeroNez,
+
     /// Floating-point addition.
     FAdd,
 
@@ -196,6 +203,9 @@ pub enum AtomicOp {
 
     /// Atomic maximum (unsigned).
     Maxu,
+
+    /// Compare-and-swap (Zacas): store `rs2` if memory equals `rd`.
+    Cas,
 }
 
 /// Memory access width for load and store operations.
@@ -216,6 +226,9 @@ pub enum MemWidth {
 
     /// 64-bit double-word access.
     Double,
+
+    /// 128-bit quad-word access (`amocas.q` only).
+    Quad,
 }
 
 /// Source for ALU operand A.
@@ -285,10 +298,29 @@ pub enum SystemOp {
     /// `SFENCE.VMA` — supervisor memory-management fence.
     SfenceVma,
 
+    /// `CBO.*` — cache-block management or zero operation (Zicbom/Zicboz).
+    Cbo(CboOp),
+
     /// Generic system instruction (CSR, ECALL) not covered by a specific variant.
     System,
 }
 
+/// Cache-block operation selected by a `CBO.*` instruction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CboOp {
+    /// `CBO.CLEAN` — write back the block if dirty, keeping it valid.
+    Clean,
+
+    /// `CBO.FLUSH` — write back the block if dirty, then invalidate it.
+    Flush,
+
+    /// `CBO.INVAL` — invalidate the block, discarding any dirty data.
+    Inval,
+
+    /// `CBO.ZERO` — store zeros to the whole block.
+    Zero,
+}
+
 /// CSR (Control and Status Register) operation type.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
 pub enum CsrOp {
@@ -315,6 +347,38 @@ pub enum CsrOp {
     Rci,
 }
 
+impl CsrOp {
+    /// Whether the source operand is the 5-bit `zimm` held in the `rs1` field.
+    pub const fn is_imm(self) -> bool {
+        matches!(self, Self::Rwi | Self::Rsi | Self::Rci)
+    }
+
+    /// Whether the instruction writes the CSR (Zicsr §2.8).
+    ///
+    /// `CSRRW`/`CSRRWI` always write. The set/clear forms write only when
+    /// `rs1` (or `zimm`) is non-zero; otherwise they are pure reads and must
+    /// not raise write faults or cause write side effects.
+    pub const fn writes_csr(self, rs1: RegIdx) -> bool {
+        match self {
+            Self::Rw | Self::Rwi => true,
+            Self::Rs | Self::Rc | Self::Rsi | Self::Rci => !rs1.is_zero(),
+            Self::None => false,
+        }
+    }
+
+    /// Whether the instruction reads the CSR (Zicsr §2.8).
+    ///
+    /// `CSRRW`/`CSRRWI` with `rd = x0` do not read the CSR. The set/clear
+    /// forms always read.
+    pub const fn reads_csr(self, rd: RegIdx) -> bool {
+        match self {
+            Self::Rw | Self::Rwi => !rd.is_zero(),
+            Self::Rs | Self::Rc | Self::Rsi | Self::Rci => true,
+            Self::None => false,
+        }
+    }
+}
+
 /// Control signals for pipeline stage execution.
 ///
 /// Contains all signals generated during instruction decode that control execution
@@ -358,4 +422,12 @@ pub struct ControlSignals {
     pub rs3_fp: bool,
     /// Atomic memory operation type.
     pub atomic_op: AtomicOp,
+    /// Atomic has acquire semantics (`aq`): younger memory operations wait
+    /// until it has performed.
+    pub aq: bool,
+    /// Atomic has release semantics (`rl`): it waits until older memory
+    /// operations have performed and older stores have drained.
+    pub rl: bool,
+    /// Macro-op fusion pattern, if this entry stands for two instructions.
+    pub fusion: Option<FusionPattern>,
 }
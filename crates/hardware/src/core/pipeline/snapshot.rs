@@ -13,13 +13,15 @@ use crate::core::pipeline::latches::{
 ///
 /// Stages in order:
 ///   Fetch1 → Fetch2 → Decode → Rename → Issue → Execute → Mem1 → Mem2 → Writeback → Commit.
-/// Each field is a `Vec` of latch entries; the length is at most `pipeline_width`.
+/// Each field is a `Vec` of latch entries; the length is at most `pipeline_width`,
+/// except for the fetch target queue and fetch buffer, which hold up to their
+/// configured sizes.
 /// An empty vec means the stage is stalled or idle this cycle.
 #[derive(Clone, Debug, Default)]
 pub struct PipelineSnapshot {
-    /// Fetch1 → Fetch2 latch (PC gen / I-TLB).
+    /// Fetch target queue (PC gen / I-TLB), flattened, oldest block first.
     pub fetch1_fetch2: Vec<Fetch1Fetch2Entry>,
-    /// Fetch2 → Decode latch (I-cache access).
+    /// Fetch buffer (I-cache access), oldest first.
     pub fetch2_decode: Vec<IfIdEntry>,
     /// Decode → Rename latch (instruction decode).
     pub decode_rename: Vec<IdExEntry>,
@@ -15,11 +15,11 @@ use crate::core::pipeline::signals::MemWidth;
 /// Result of store-to-load forwarding check.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ForwardResult {
-    /// Store fully covers the load — use the forwarded data.
+    /// Older stores cover every byte of the load — use the forwarded data.
     Hit(u64),
     /// No overlap with any pending store — safe to read from memory.
     Miss,
-    /// Partial overlap — must stall until the store drains to memory.
+    /// Stores cover only part of the load — must stall until they drain to memory.
     Stall,
 }
 
@@ -46,7 +46,8 @@ pub enum StoreResolution {
         /// Data to write.
         data: u64,
     },
-    /// Cancelled (failed SC) — committed no-op, will drain without writing.
+    /// Cancelled (failed SC, or a device AMO already performed on the bus) —
+    /// committed no-op, will drain without writing.
     Cancelled,
 }
 
@@ -145,6 +146,12 @@ impl StoreBuffer {
         false
     }
 
+    /// Iterates over the occupied entries from oldest to youngest.
+    pub fn iter(&self) -> impl Iterator<Item = &StoreBufferEntry> {
+        let cap = self.entries.len();
+        (0..self.count).map(move |i| &self.entries[(self.head + i) % cap]).filter(|e| e.valid)
+    }
+
     /// Returns true if the store buffer is full.
     #[inline]
     pub const fn is_full(&self) -> bool {
@@ -188,7 +195,10 @@ impl StoreBuffer {
     pub fn mark_committed(&mut self, rob_tag: RobTag) {
         if let Some(entry) = self.find_by_tag_mut(rob_tag) {
             debug_assert!(
-                matches!(entry.resolution, StoreResolution::Ready { .. }),
+                matches!(
+                    entry.resolution,
+                    StoreResolution::Ready { .. } | StoreResolution::Cancelled
+                ),
                 "mark_committed on non-Ready entry: rob_tag={} resolution={:?}",
                 rob_tag.0,
                 entry.resolution,
@@ -201,9 +211,12 @@ impl StoreBuffer {
 
     /// Attempts store-to-load forwarding.
     ///
-    /// Returns `Hit(data)` if a pending store fully covers the load,
-    /// `Stall` if a store partially overlaps (must wait for drain),
-    /// or `Miss` if no overlap exists.
+    /// Forwarding is byte-granular: walking from the youngest older store to
+    /// the oldest, each load byte takes its value from the first store that
+    /// writes it. Returns `Hit(data)` if the stores together cover every byte
+    /// of the load, `Stall` if only some bytes are covered (the rest would
+    /// have to come from memory, so the load waits for the stores to drain),
+    /// or `Miss` if no store overlaps the load.
     ///
     /// `load_rob_tag` is the ROB tag of the load instruction. Only stores
     /// older than the load (lower tag) are considered for forwarding. Stores
@@ -217,63 +230,62 @@ impl StoreBuffer {
         let load_size = width_to_bytes(width);
         let load_start = paddr.val();
         let load_end = load_start + load_size as u64;
+        let full: u16 = if load_size >= 16 { u16::MAX } else { (1 << load_size) - 1 };
+
+        // Bit `i` of `covered` is set once load byte `i` has been forwarded.
+        let mut covered: u16 = 0;
+        let mut value: u64 = 0;
 
-        // Search from newest to oldest for the most recent matching store
+        // Search from newest to oldest so the most recent store wins each byte.
         let mut idx = if self.tail == 0 { self.entries.len() - 1 } else { self.tail - 1 };
 
         for _ in 0..self.count {
             let entry = &self.entries[idx];
-            if entry.valid {
-                // Skip stores that are newer than or same age as the load —
-                // they are after the load in program order and must not forward.
-                if !entry.rob_tag.is_older_than(load_rob_tag) {
-                    if idx == 0 {
-                        idx = self.entries.len() - 1;
-                    } else {
-                        idx -= 1;
-                    }
-                    continue;
-                }
-
-                // Older store — check for overlap based on resolution state.
-                match entry.resolution {
-                    StoreResolution::Ready { paddr: store_paddr, data: store_data }
-                    | StoreResolution::Committed { paddr: store_paddr, data: store_data } => {
-                        let store_size = width_to_bytes(entry.width);
-                        let store_start = store_paddr.val();
-                        let store_end = store_start + store_size as u64;
-
-                        // Check for any overlap
-                        if load_start < store_end && load_end > store_start {
-                            // Full overlap: store completely covers the load
-                            if store_start <= load_start && store_end >= load_end {
-                                let offset = (load_start - store_start) as u32;
-                                let shifted = store_data >> (offset * 8);
-                                let mask = if load_size >= 8 {
-                                    u64::MAX
-                                } else {
-                                    (1u64 << (load_size * 8)) - 1
-                                };
-                                return ForwardResult::Hit(shifted & mask);
-                            }
-                            // Partial overlap: must stall
-                            return ForwardResult::Stall;
-                        }
-                    }
-                    // Pending or Cancelled: no resolved address to check.
-                    // (Loads are not issued until all older stores have their
-                    // addresses resolved, so Pending is handled at issue time.)
-                    _ => {}
-                }
-            }
             if idx == 0 {
                 idx = self.entries.len() - 1;
             } else {
                 idx -= 1;
             }
+            // Skip stores that are newer than or same age as the load —
+            // they are after the load in program order and must not forward.
+            if !entry.valid || !entry.rob_tag.is_older_than(load_rob_tag) {
+                continue;
+            }
+
+            // Older store — check for overlap based on resolution state.
+            // Pending or Cancelled entries have no resolved address to check.
+            // (Loads are not issued until all older stores have their
+            // addresses resolved, so Pending is handled at issue time.)
+            let (StoreResolution::Ready { paddr: store_paddr, data: store_data }
+            | StoreResolution::Committed { paddr: store_paddr, data: store_data }) =
+                entry.resolution
+            else {
+                continue;
+            };
+            let store_start = store_paddr.val();
+            let store_end = store_start + width_to_bytes(entry.width) as u64;
+            if load_start >= store_end || load_end <= store_start {
+                continue;
+            }
+            // Store data only carries 8 bytes; wider accesses cannot be merged.
+            if load_size > 8 || store_end - store_start > 8 {
+                return ForwardResult::Stall;
+            }
+
+            for addr in load_start.max(store_start)..load_end.min(store_end) {
+                let bit = 1u16 << (addr - load_start);
+                if covered & bit == 0 {
+                    covered |= bit;
+                    let byte = (store_data >> ((addr - store_start) * 8)) & 0xFF;
+                    value |= byte << ((addr - load_start) * 8);
+                }
+            }
+            if covered == full {
+                return ForwardResult::Hit(value);
+            }
         }
 
-        ForwardResult::Miss
+        if covered == 0 { ForwardResult::Miss } else { ForwardResult::Stall }
     }
 
     /// Checks whether any store buffer entry older than `rob_tag` has an
@@ -296,6 +308,22 @@ impl StoreBuffer {
         false
     }
 
+    /// Returns true if any store older than `rob_tag` is still in the buffer
+    /// (resolved, committed, or not). Used to order device AMOs and release
+    /// atomics after all prior stores have drained.
+    pub fn has_older_store(&self, rob_tag: RobTag) -> bool {
+        let cap = self.entries.len();
+        let mut idx = self.head;
+        for _ in 0..self.count {
+            let entry = &self.entries[idx];
+            if entry.valid && entry.rob_tag.is_older_than(rob_tag) {
+                return true;
+            }
+            idx = (idx + 1) % cap;
+        }
+        false
+    }
+
     /// Checks whether a specific store is unresolved (no address yet).
     ///
     /// Returns `true` if the store is found in the buffer and still has no
@@ -512,6 +540,7 @@ pub(crate) const fn width_to_bytes(w: MemWidth) -> usize {
         MemWidth::Half => 2,
         MemWidth::Word => 4,
         MemWidth::Double => 8,
+        MemWidth::Quad => 16,
         MemWidth::Nop => 0,
     }
 }
@@ -583,6 +612,75 @@ mod tests {
         assert_eq!(result, ForwardResult::Hit(0x78));
     }
 
+    #[test]
+    fn test_forward_byte_from_middle_of_double() {
+        // sd; lb 3(base)
+        let mut sb = StoreBuffer::new(4);
+        sb.allocate(RobTag(1), MemWidth::Double);
+        sb.resolve(
+            RobTag(1),
+            VirtAddr::new(0x1000),
+            PhysAddr::new(0x8000_0000),
+            0x8877_6655_4433_2211,
+        );
+
+        let result = sb.forward_load(PhysAddr::new(0x8000_0003), MemWidth::Byte, RobTag(2));
+        assert_eq!(result, ForwardResult::Hit(0x44));
+        let result = sb.forward_load(PhysAddr::new(0x8000_0006), MemWidth::Half, RobTag(2));
+        assert_eq!(result, ForwardResult::Hit(0x8877));
+    }
+
+    #[test]
+    fn test_forward_partial_coverage_stalls() {
+        // sb; lw — three bytes of the word would have to come from memory.
+        let mut sb = StoreBuffer::new(4);
+        sb.allocate(RobTag(1), MemWidth::Byte);
+        sb.resolve(RobTag(1), VirtAddr::new(0x1001), PhysAddr::new(0x8000_0001), 0xAB);
+
+        let result = sb.forward_load(PhysAddr::new(0x8000_0000), MemWidth::Word, RobTag(2));
+        assert_eq!(result, ForwardResult::Stall);
+        // A load of just the stored byte forwards.
+        let result = sb.forward_load(PhysAddr::new(0x8000_0001), MemWidth::Byte, RobTag(2));
+        assert_eq!(result, ForwardResult::Hit(0xAB));
+    }
+
+    #[test]
+    fn test_forward_merges_multiple_stores() {
+        // sw 0(base); sw 4(base); ld 0(base)
+        let mut sb = StoreBuffer::new(4);
+        sb.allocate(RobTag(1), MemWidth::Word);
+        sb.allocate(RobTag(2), MemWidth::Word);
+        sb.resolve(RobTag(1), VirtAddr::new(0x1000), PhysAddr::new(0x8000_0000), 0x4433_2211);
+        sb.resolve(RobTag(2), VirtAddr::new(0x1004), PhysAddr::new(0x8000_0004), 0x8877_6655);
+
+        let result = sb.forward_load(PhysAddr::new(0x8000_0000), MemWidth::Double, RobTag(3));
+        assert_eq!(result, ForwardResult::Hit(0x8877_6655_4433_2211));
+        let result = sb.forward_load(PhysAddr::new(0x8000_0002), MemWidth::Word, RobTag(3));
+        assert_eq!(result, ForwardResult::Hit(0x6655_4433));
+    }
+
+    #[test]
+    fn test_forward_youngest_store_wins_each_byte() {
+        // sd; sb 2(base); ld — byte 2 comes from the younger sb.
+        let mut sb = StoreBuffer::new(4);
+        sb.allocate(RobTag(1), MemWidth::Double);
+        sb.allocate(RobTag(2), MemWidth::Byte);
+        sb.resolve(
+            RobTag(1),
+            VirtAddr::new(0x1000),
+            PhysAddr::new(0x8000_0000),
+            0x8877_6655_4433_2211,
+        );
+        sb.resolve(RobTag(2), VirtAddr::new(0x1002), PhysAddr::new(0x8000_0002), 0xEE);
+        sb.mark_committed(RobTag(1));
+
+        let result = sb.forward_load(PhysAddr::new(0x8000_0000), MemWidth::Double, RobTag(3));
+        assert_eq!(result, ForwardResult::Hit(0x8877_6655_44EE_2211));
+        // The load older than the sb only sees the sd.
+        let result = sb.forward_load(PhysAddr::new(0x8000_0000), MemWidth::Double, RobTag(2));
+        assert_eq!(result, ForwardResult::Hit(0x8877_6655_4433_2211));
+    }
+
     #[test]
     fn test_flush_speculative() {
         let mut sb = StoreBuffer::new(4);
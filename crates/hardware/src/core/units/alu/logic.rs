@@ -1,7 +1,8 @@
 //! ALU logical and comparison operations.
 //!
-//! Implements bitwise OR, AND, XOR, and set-less-than (signed and unsigned)
-//! for both RV64 and RV32 variants.
+//! Implements bitwise OR, AND, XOR, set-less-than (signed and unsigned)
+//! for both RV64 and RV32 variants, and the Zicond conditional-zero
+//! operations.
 //!
 //! For 32-bit comparisons (`Slt`/`Sltu` with `is32`), only the lower 32 bits
 //! of each operand are considered. The result is always 0 or 1.
@@ -19,8 +20,8 @@ use crate::core::pipeline::signals::AluOp;
 ///
 /// # Returns
 ///
-/// The 64-bit result. Bitwise operations always use the full 64 bits
-/// regardless of `is32`. Returns `0` for non-logic opcodes.
+/// The 64-bit result. Bitwise and conditional-zero operations always use
+/// the full 64 bits regardless of `is32`. Returns `0` for non-logic opcodes.
 pub const fn execute(op: AluOp, a: u64, b: u64, is32: bool) -> u64 {
     match op {
         AluOp::Or => a | b,
@@ -40,6 +41,9 @@ pub const fn execute(op: AluOp, a: u64, b: u64, is32: bool) -> u64 {
                 (a < b) as u64
             }
         }
+        AluOp::CzeroEqz if b == 0 => 0,
+        AluOp::CzeroNez if b != 0 => 0,
+        AluOp::CzeroEqz | AluOp::CzeroNez => a,
         _ => 0,
     }
 }
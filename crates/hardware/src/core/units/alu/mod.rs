@@ -7,13 +7,13 @@
 //!
 //! Operations are organized into submodules by category:
 //! - [`arithmetic`]: Add, Sub, Mul, Mulh, Mulhsu, Mulhu, Div, Divu, Rem, Remu
-//! - [`logic`]:      Or, And, Xor, Slt, Sltu
+//! - [`logic`]:      Or, And, Xor, Slt, Sltu, `CzeroEqz`, `CzeroNez`
 //! - [`shifts`]:     Sll, Srl, Sra
 
 /// Integer arithmetic operations (add, subtract, multiply, divide).
 pub mod arithmetic;
 
-/// Bitwise logical and comparison operations (or, and, xor, slt).
+/// Bitwise logical, comparison and conditional-zero operations (or, and, xor, slt, czero).
 pub mod logic;
 
 /// Shift operations (sll, srl, sra).
@@ -88,10 +88,14 @@ impl Alu {
             | AluOp::Rem
             | AluOp::Remu => arithmetic::execute(op, a, b, is32),
 
-            // Logic / comparisons: or, and, xor, slt, sltu
-            AluOp::Or | AluOp::And | AluOp::Xor | AluOp::Slt | AluOp::Sltu => {
-                logic::execute(op, a, b, is32)
-            }
+            // Logic / comparisons: or, and, xor, slt, sltu, czero.*
+            AluOp::Or
+            | AluOp::And
+            | AluOp::Xor
+            | AluOp::Slt
+            | AluOp::Sltu
+            | AluOp::CzeroEqz
+            | AluOp::CzeroNez => logic::execute(op, a, b, is32),
 
             // Shifts: sll, srl, sra
             AluOp::Sll | AluOp::Srl | AluOp::Sra => shifts::execute(op, a, b, is32),
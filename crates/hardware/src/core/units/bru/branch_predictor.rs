@@ -5,6 +5,9 @@
 //! predicting conditional branches, indirect jumps (via BTB), and function
 //! returns (via RAS).
 
+use crate::core::units::bru::ras::{RasOp, RasSnapshot};
+use serde::{Deserialize, Serialize};
+
 /// Maximum number of u64 words in a GHR. 16 × 64 = 1024 bits.
 /// This is a capacity bound — the effective history length comes from config.
 const GHR_MAX_WORDS: usize = 16;
@@ -19,7 +22,7 @@ const GHR_MAX_WORDS: usize = 16;
 /// Bit 0 is the most recently pushed outcome. Snapshots are captured at
 /// fetch time and carried through pipeline latches so that update and
 /// repair operations use the correct history state.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Ghr {
     /// Bit storage. `bits[0]` bit 0 = position 0 (most recent).
     /// `bits[0]` bit 63 = position 63, `bits[1]` bit 0 = position 64, etc.
@@ -143,14 +146,15 @@ pub trait BranchPredictor {
     /// Predicts the target address for a jump instruction using the BTB.
     fn predict_btb(&self, pc: u64) -> Option<u64>;
 
-    /// Records a function call for return address prediction.
-    fn on_call(&mut self, pc: u64, ret_addr: u64, target: u64);
-
     /// Predicts the return address for a return instruction.
     fn predict_return(&self) -> Option<u64>;
 
-    /// Records a function return for return address prediction.
-    fn on_return(&mut self);
+    /// Speculatively applies a fetched call or return to the RAS.
+    fn update_ras(&mut self, op: RasOp);
+
+    /// Applies a committed call or return to the committed RAS, which
+    /// [`BranchPredictor::repair_to_committed`] restores.
+    fn commit_ras(&mut self, _op: RasOp) {}
 
     /// Speculatively updates the GHR with a predicted branch outcome.
     ///
@@ -173,13 +177,16 @@ pub trait BranchPredictor {
     /// the predictor trains on the correct history state.
     fn repair_history(&mut self, _ghr: &Ghr) {}
 
-    /// Returns a snapshot of the RAS pointer for speculative checkpointing.
-    fn snapshot_ras(&self) -> usize {
-        0
+    /// Returns a snapshot of the RAS pointer and top entry for speculative
+    /// checkpointing.
+    ///
+    /// Called at fetch time before the instruction's own push or pop.
+    fn snapshot_ras(&self) -> RasSnapshot {
+        RasSnapshot::default()
     }
 
-    /// Restores the RAS pointer to a previously captured snapshot.
-    fn restore_ras(&mut self, _ptr: usize) {}
+    /// Restores the RAS to a previously captured snapshot.
+    fn restore_ras(&mut self, _snapshot: RasSnapshot) {}
 
     /// Updates only the BTB with a jump target (no direction training).
     ///
@@ -187,10 +194,11 @@ pub trait BranchPredictor {
     /// learns the target without polluting direction predictor state.
     fn update_btb(&mut self, _pc: u64, _target: u64) {}
 
-    /// Resets speculative GHR to the committed GHR state.
+    /// Resets speculative GHR and RAS to the committed state.
     ///
     /// Called on full pipeline flushes (trap, MRET/SRET, FENCE.I) where the
-    /// speculative history may contain wrong-path branch outcomes.
+    /// speculative history may contain wrong-path branch outcomes and the
+    /// RAS wrong-path calls and returns.
     fn repair_to_committed(&mut self) {}
 }
 
@@ -207,11 +215,10 @@ mod tests {
         fn predict_btb(&self, _pc: u64) -> Option<u64> {
             None
         }
-        fn on_call(&mut self, _pc: u64, _ret_addr: u64, _target: u64) {}
         fn predict_return(&self) -> Option<u64> {
             None
         }
-        fn on_return(&mut self) {}
+        fn update_ras(&mut self, _op: RasOp) {}
     }
 
     #[test]
@@ -220,8 +227,8 @@ mod tests {
         predictor.speculate(0x1000, true);
         assert_eq!(predictor.snapshot_history(), Ghr::default());
         predictor.repair_history(&Ghr::new(42));
-        assert_eq!(predictor.snapshot_ras(), 0);
-        predictor.restore_ras(5);
+        assert_eq!(predictor.snapshot_ras(), RasSnapshot::default());
+        predictor.restore_ras(RasSnapshot::default());
     }
 
     #[test]
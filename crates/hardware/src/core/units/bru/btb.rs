@@ -4,8 +4,10 @@
 //! flow instructions. It allows the fetch stage to predict the target of a
 //! branch or jump before the instruction is decoded.
 
+use serde::{Deserialize, Serialize};
+
 /// An entry in the Branch Target Buffer.
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
 struct BtbEntry {
     /// The tag used to verify if this entry corresponds to the requested PC.
     tag: u64,
@@ -16,7 +18,7 @@ struct BtbEntry {
 }
 
 /// Set-associative Branch Target Buffer.
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Btb {
     /// Flat array of entries: `num_sets * ways` elements.
     table: Vec<BtbEntry>,
@@ -5,13 +5,14 @@
 //! when a new branch outcome is pushed into the GHR.
 
 use crate::core::units::bru::Ghr;
+use serde::{Deserialize, Serialize};
 
 /// A single Circular Shift Register for incremental folded XOR computation.
 ///
 /// Maintains a `fold_width`-bit value that represents the XOR-fold of the
 /// most recent `hist_length` bits of the GHR, updated incrementally in O(1)
 /// per branch outcome push.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct FoldedHistory {
     /// Current folded value (only the low `fold_width` bits are meaningful).
     pub val: u64,
@@ -173,19 +174,43 @@ mod tests {
         // All TAGE and ITTAGE history length / fold width combos.
         let cases = [
             // TAGE-like: table_bits=11, tag_widths 9-10, hist lengths up to 712
-            (5, 11), (5, 10), (5, 9), (5, 8),
-            (15, 11), (15, 10), (15, 9),
-            (44, 11), (44, 10),
-            (130, 11), (130, 10),
-            (247, 11), (247, 10),
-            (375, 11), (375, 10),
-            (512, 11), (512, 10),
-            (712, 11), (712, 10), (712, 9),
+            (5, 11),
+            (5, 10),
+            (5, 9),
+            (5, 8),
+            (15, 11),
+            (15, 10),
+            (15, 9),
+            (44, 11),
+            (44, 10),
+            (130, 11),
+            (130, 10),
+            (247, 11),
+            (247, 10),
+            (375, 11),
+            (375, 10),
+            (512, 11),
+            (512, 10),
+            (712, 11),
+            (712, 10),
+            (712, 9),
             // ITTAGE-like: shorter histories
-            (4, 9), (8, 9), (16, 10), (32, 10),
-            (64, 11), (128, 11), (256, 11), (512, 11),
+            (4, 9),
+            (8, 9),
+            (16, 10),
+            (32, 10),
+            (64, 11),
+            (128, 11),
+            (256, 11),
+            (512, 11),
             // Edge cases
-            (1, 1), (2, 1), (63, 7), (64, 8), (65, 8), (127, 10), (128, 10),
+            (1, 1),
+            (2, 1),
+            (63, 7),
+            (64, 8),
+            (65, 8),
+            (127, 10),
+            (128, 10),
         ];
 
         for &(hist_len, fold_w) in &cases {
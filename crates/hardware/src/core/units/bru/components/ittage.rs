@@ -11,9 +11,10 @@
 use super::tagged_bank::GeoBankSet;
 use crate::config::IttageConfig;
 use crate::core::units::bru::Ghr;
+use serde::{Deserialize, Serialize};
 
 /// An entry in an ITTAGE bank.
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
 struct IttageEntry {
     /// Tag for matching the history/PC hash.
     tag: u16,
@@ -31,7 +32,7 @@ struct IttageEntry {
 ///
 /// Does **not** own a GHR — the caller provides GHR references for
 /// speculate/repair/update to avoid redundant copies.
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Ittage {
     /// Shared CSR/indexing infrastructure (owns its own CSRs for its history lengths).
     banks: GeoBankSet,
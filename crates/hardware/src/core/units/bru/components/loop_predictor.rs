@@ -3,8 +3,10 @@
 //! Detects counted loops and predicts their iteration behavior. When confident
 //! in a loop's trip count, it can override any base predictor's decision.
 
+use serde::{Deserialize, Serialize};
+
 /// An entry in the loop predictor table.
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
 struct LoopEntry {
     /// PC-derived tag for matching.
     tag: u16,
@@ -19,7 +21,7 @@ struct LoopEntry {
 }
 
 /// Standalone loop predictor that can be composed with any base predictor.
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct LoopPredictor {
     /// Loop predictor table.
     table: Vec<LoopEntry>,
@@ -3,8 +3,10 @@
 //! Prevents parameter mix-ups between TAGE and SC by using newtypes
 //! for confidence levels, metadata, and sum values.
 
+use serde::{Deserialize, Serialize};
+
 /// TAGE confidence level, derived from the provider counter.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TageConfLevel {
     /// Counter at max: |2*ctr+1| >= 7 (ctr == 3 or -4 for 3-bit).
     High,
@@ -32,7 +34,7 @@ impl TageConfLevel {
 }
 
 /// Metadata from TAGE passed to the SC for bias indexing and override decisions.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct TageScMeta {
     /// Confidence level derived from the TAGE provider counter.
     pub conf: TageConfLevel,
@@ -49,5 +51,5 @@ pub struct TageScMeta {
 
 /// The SC's sum value -- kept as a distinct type to prevent confusing
 /// it with raw counter values or thresholds.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct ScSum(pub i32);
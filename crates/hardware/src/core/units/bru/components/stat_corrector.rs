@@ -12,6 +12,7 @@ use crate::config::ScConfig;
 use crate::core::units::bru::Ghr;
 
 use super::sc_types::{ScSum, TageConfLevel, TageScMeta};
+use serde::{Deserialize, Serialize};
 
 /// Maximum number of GEHL tables. Covers all realistic configs (Seznec uses 4-6).
 const MAX_SC_TABLES: usize = 8;
@@ -28,7 +29,7 @@ fn clamp_counter(val: i32, bits: usize) -> i8 {
 ///
 /// GEHL counter tables are stored in a single flat `Vec<i8>` indexed as
 /// `[table * table_size + entry]` to eliminate double indirection.
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct StatCorrector {
     // --- GEHL tables ---
     /// Flattened GEHL counter tables: `num_tables * table_size` entries.
@@ -250,14 +251,8 @@ impl StatCorrector {
                     false
                 }
             }
-            TageConfLevel::Medium => {
-                if sum_abs < quarter {
-                    // FirstH chooser zone
-                    self.first_h >= 0
-                } else {
-                    false
-                }
-            }
+            // FirstH chooser zone
+            TageConfLevel::Medium if sum_abs < quarter => self.first_h >= 0,
             _ => false,
         }
     }
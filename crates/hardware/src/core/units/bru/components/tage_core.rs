@@ -8,9 +8,10 @@ use super::sc_types::{TageConfLevel, TageScMeta};
 use super::tagged_bank::GeoBankSet;
 use crate::config::TageConfig;
 use crate::core::units::bru::Ghr;
+use serde::{Deserialize, Serialize};
 
 /// An entry in a TAGE tagged bank.
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
 struct TageEntry {
     tag: u16,
     ctr: i8,
@@ -30,7 +31,7 @@ pub struct TageUpdateResult {
 ///
 /// Provides speculative prediction via `predict()` and commit-time update via
 /// `update()`. CSR management is delegated to the internal `GeoBankSet`.
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct TageCore {
     base: Vec<i8>,
     geo_banks: GeoBankSet,
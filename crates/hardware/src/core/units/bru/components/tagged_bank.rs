@@ -9,6 +9,7 @@
 
 use super::folded_history::FoldedHistory;
 use crate::core::units::bru::Ghr;
+use serde::{Deserialize, Serialize};
 
 /// Maximum number of banks supported. Configs with more banks will panic at init.
 /// 16 covers all realistic TAGE/ITTAGE configurations (Seznec uses 8-12).
@@ -22,7 +23,7 @@ const MAX_BANKS: usize = 16;
 /// All per-bank arrays are fixed-size `[T; MAX_BANKS]` — only the first
 /// `num_banks` elements are meaningful. This eliminates heap indirection on the
 /// hot speculate/predict path.
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct GeoBankSet {
     /// Number of active banks.
     num_banks: usize,
@@ -26,11 +26,13 @@ use self::predictors::{
     gshare::GSharePredictor, perceptron::PerceptronPredictor, sc_l_tage::ScLTagePredictor,
     static_bp::StaticPredictor, tage::TagePredictor, tournament::TournamentPredictor,
 };
+use self::ras::{RasOp, RasSnapshot};
 use crate::config::{BranchPredictor as BpType, Config};
+use serde::{Deserialize, Serialize};
 
 /// Enum wrapper for static dispatch of Branch Predictors.
 /// This avoids vtable lookups in the critical fetch loop.
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub enum BranchPredictorWrapper {
     /// Static (always not-taken) predictor.
     Static(StaticPredictor),
@@ -89,6 +91,29 @@ impl BranchPredictorWrapper {
     }
 }
 
+impl BranchPredictorWrapper {
+    /// Returns the whole predictor (tables, histories, BTB and RAS) for a
+    /// checkpoint.
+    pub fn save_state(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or_default()
+    }
+
+    /// Restores a predictor saved by [`save_state`](Self::save_state).
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the problem if `state` is malformed or holds
+    /// a different kind of predictor.
+    pub fn restore_state(&mut self, state: serde_json::Value) -> Result<(), String> {
+        let saved: Self = serde_json::from_value(state).map_err(|e| e.to_string())?;
+        if std::mem::discriminant(&saved) != std::mem::discriminant(self) {
+            return Err("checkpoint holds a different branch predictor".to_owned());
+        }
+        *self = saved;
+        Ok(())
+    }
+}
+
 impl BranchPredictor for BranchPredictorWrapper {
     #[inline(always)]
     fn predict_branch(&self, pc: u64) -> (bool, Option<u64>) {
@@ -126,18 +151,6 @@ impl BranchPredictor for BranchPredictorWrapper {
         }
     }
 
-    #[inline(always)]
-    fn on_call(&mut self, pc: u64, ret_addr: u64, target: u64) {
-        match self {
-            Self::Static(bp) => bp.on_call(pc, ret_addr, target),
-            Self::GShare(bp) => bp.on_call(pc, ret_addr, target),
-            Self::Tournament(bp) => bp.on_call(pc, ret_addr, target),
-            Self::Tage(bp) => bp.on_call(pc, ret_addr, target),
-            Self::Perceptron(bp) => bp.on_call(pc, ret_addr, target),
-            Self::ScLTage(bp) => bp.on_call(pc, ret_addr, target),
-        }
-    }
-
     #[inline(always)]
     fn predict_return(&self) -> Option<u64> {
         match self {
@@ -151,14 +164,26 @@ impl BranchPredictor for BranchPredictorWrapper {
     }
 
     #[inline(always)]
-    fn on_return(&mut self) {
+    fn update_ras(&mut self, op: RasOp) {
+        match self {
+            Self::Static(bp) => bp.update_ras(op),
+            Self::GShare(bp) => bp.update_ras(op),
+            Self::Tournament(bp) => bp.update_ras(op),
+            Self::Tage(bp) => bp.update_ras(op),
+            Self::Perceptron(bp) => bp.update_ras(op),
+            Self::ScLTage(bp) => bp.update_ras(op),
+        }
+    }
+
+    #[inline(always)]
+    fn commit_ras(&mut self, op: RasOp) {
         match self {
-            Self::Static(bp) => bp.on_return(),
-            Self::GShare(bp) => bp.on_return(),
-            Self::Tournament(bp) => bp.on_return(),
-            Self::Tage(bp) => bp.on_return(),
-            Self::Perceptron(bp) => bp.on_return(),
-            Self::ScLTage(bp) => bp.on_return(),
+            Self::Static(bp) => bp.commit_ras(op),
+            Self::GShare(bp) => bp.commit_ras(op),
+            Self::Tournament(bp) => bp.commit_ras(op),
+            Self::Tage(bp) => bp.commit_ras(op),
+            Self::Perceptron(bp) => bp.commit_ras(op),
+            Self::ScLTage(bp) => bp.commit_ras(op),
         }
     }
 
@@ -199,7 +224,7 @@ impl BranchPredictor for BranchPredictorWrapper {
     }
 
     #[inline(always)]
-    fn snapshot_ras(&self) -> usize {
+    fn snapshot_ras(&self) -> RasSnapshot {
         match self {
             Self::Static(bp) => bp.snapshot_ras(),
             Self::GShare(bp) => bp.snapshot_ras(),
@@ -211,14 +236,14 @@ impl BranchPredictor for BranchPredictorWrapper {
     }
 
     #[inline(always)]
-    fn restore_ras(&mut self, ptr: usize) {
+    fn restore_ras(&mut self, snapshot: RasSnapshot) {
         match self {
-            Self::Static(bp) => bp.restore_ras(ptr),
-            Self::GShare(bp) => bp.restore_ras(ptr),
-            Self::Tournament(bp) => bp.restore_ras(ptr),
-            Self::Tage(bp) => bp.restore_ras(ptr),
-            Self::Perceptron(bp) => bp.restore_ras(ptr),
-            Self::ScLTage(bp) => bp.restore_ras(ptr),
+            Self::Static(bp) => bp.restore_ras(snapshot),
+            Self::GShare(bp) => bp.restore_ras(snapshot),
+            Self::Tournament(bp) => bp.restore_ras(snapshot),
+            Self::Tage(bp) => bp.restore_ras(snapshot),
+            Self::Perceptron(bp) => bp.restore_ras(snapshot),
+            Self::ScLTage(bp) => bp.restore_ras(snapshot),
         }
     }
 
@@ -14,7 +14,12 @@
 //! - **Best Case:** Correlated branches where outcome depends on recent history
 //! - **Worst Case:** Uncorrelated branches or history length too short/long for pattern
 
-use crate::core::units::bru::{BranchPredictor, Ghr, btb::Btb, ras::Ras};
+use crate::core::units::bru::{
+    BranchPredictor, Ghr,
+    btb::Btb,
+    ras::{Ras, RasOp, RasSnapshot},
+};
+use serde::{Deserialize, Serialize};
 
 /// Size of the Pattern History Table (2^12 entries).
 const TABLE_BITS: usize = 12;
@@ -22,7 +27,7 @@ const TABLE_BITS: usize = 12;
 const TABLE_SIZE: usize = 1 << TABLE_BITS;
 
 /// `GShare` Predictor structure.
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct GSharePredictor {
     /// Global History Register storing recent branch outcomes.
     ghr: u64,
@@ -94,19 +99,18 @@ impl BranchPredictor for GSharePredictor {
     }
 
     /// Handles a function call by pushing the return address to the RAS.
-    fn on_call(&mut self, pc: u64, ret_addr: u64, target: u64) {
-        self.ras.push(ret_addr);
-        self.btb.update(pc, target);
-    }
-
     /// Predicts the return address using the RAS.
     fn predict_return(&self) -> Option<u64> {
         self.ras.top()
     }
 
     /// Handles a function return by popping from the RAS.
-    fn on_return(&mut self) {
-        let _ = self.ras.pop();
+    fn update_ras(&mut self, op: RasOp) {
+        self.ras.apply(op);
+    }
+
+    fn commit_ras(&mut self, op: RasOp) {
+        self.ras.commit(op);
     }
 
     fn speculate(&mut self, _pc: u64, taken: bool) {
@@ -121,15 +125,19 @@ impl BranchPredictor for GSharePredictor {
         self.ghr = ghr.val();
     }
 
-    fn snapshot_ras(&self) -> usize {
-        self.ras.snapshot_ptr()
+    fn snapshot_ras(&self) -> RasSnapshot {
+        self.ras.snapshot()
     }
 
-    fn restore_ras(&mut self, ptr: usize) {
-        self.ras.restore_ptr(ptr);
+    fn restore_ras(&mut self, snapshot: RasSnapshot) {
+        self.ras.restore(snapshot);
     }
 
     fn update_btb(&mut self, pc: u64, target: u64) {
         self.btb.update(pc, target);
     }
+
+    fn repair_to_committed(&mut self) {
+        self.ras.restore_committed();
+    }
 }
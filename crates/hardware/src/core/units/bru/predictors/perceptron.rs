@@ -5,7 +5,12 @@
 //! prediction is the dot product of the weights and the history vector.
 
 use crate::config::PerceptronConfig;
-use crate::core::units::bru::{BranchPredictor, Ghr, btb::Btb, ras::Ras};
+use crate::core::units::bru::{
+    BranchPredictor, Ghr,
+    btb::Btb,
+    ras::{Ras, RasOp, RasSnapshot},
+};
+use serde::{Deserialize, Serialize};
 
 /// Coefficient used to calculate the training threshold.
 const THETA_COEFF: f64 = 1.93;
@@ -13,7 +18,7 @@ const THETA_COEFF: f64 = 1.93;
 const THETA_BIAS: f64 = 14.0;
 
 /// Perceptron Predictor structure.
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct PerceptronPredictor {
     /// Global History Register.
     ghr: u64,
@@ -139,19 +144,18 @@ impl BranchPredictor for PerceptronPredictor {
     }
 
     /// Handles a function call by pushing the return address to the RAS.
-    fn on_call(&mut self, pc: u64, ret_addr: u64, target: u64) {
-        self.ras.push(ret_addr);
-        self.btb.update(pc, target);
-    }
-
     /// Predicts the return address using the RAS.
     fn predict_return(&self) -> Option<u64> {
         self.ras.top()
     }
 
     /// Handles a function return by popping from the RAS.
-    fn on_return(&mut self) {
-        let _ = self.ras.pop();
+    fn update_ras(&mut self, op: RasOp) {
+        self.ras.apply(op);
+    }
+
+    fn commit_ras(&mut self, op: RasOp) {
+        self.ras.commit(op);
     }
 
     fn speculate(&mut self, _pc: u64, taken: bool) {
@@ -167,15 +171,19 @@ impl BranchPredictor for PerceptronPredictor {
         self.ghr = ghr.val();
     }
 
-    fn snapshot_ras(&self) -> usize {
-        self.ras.snapshot_ptr()
+    fn snapshot_ras(&self) -> RasSnapshot {
+        self.ras.snapshot()
     }
 
-    fn restore_ras(&mut self, ptr: usize) {
-        self.ras.restore_ptr(ptr);
+    fn restore_ras(&mut self, snapshot: RasSnapshot) {
+        self.ras.restore(snapshot);
     }
 
     fn update_btb(&mut self, pc: u64, target: u64) {
         self.btb.update(pc, target);
     }
+
+    fn repair_to_committed(&mut self) {
+        self.ras.restore_committed();
+    }
 }
@@ -21,22 +21,23 @@ use crate::core::units::bru::{
         ittage::Ittage, loop_predictor::LoopPredictor, sc_types::ScSum,
         stat_corrector::StatCorrector, tage_core::TageCore,
     },
-    ras::Ras,
+    ras::{Ras, RasOp, RasSnapshot},
 };
+use serde::{Deserialize, Serialize};
 
 /// Number of entries in the predict-time SC metadata cache.
 const SC_CACHE_SIZE: usize = 64;
 const SC_CACHE_MASK: usize = SC_CACHE_SIZE - 1;
 
 /// Cached predict-time SC metadata for a single PC.
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
 struct ScCacheEntry {
     pc: u64,
     meta: Option<(crate::core::units::bru::components::sc_types::TageScMeta, ScSum)>,
 }
 
 /// SC-L-TAGE + ITTAGE composed predictor.
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ScLTagePredictor {
     btb: Btb,
     ras: Ras,
@@ -140,17 +141,16 @@ impl BranchPredictor for ScLTagePredictor {
         self.ittage.predict(pc).or_else(|| self.btb.lookup(pc))
     }
 
-    fn on_call(&mut self, pc: u64, ret_addr: u64, target: u64) {
-        self.ras.push(ret_addr);
-        self.btb.update(pc, target);
-    }
-
     fn predict_return(&self) -> Option<u64> {
         self.ras.top()
     }
 
-    fn on_return(&mut self) {
-        let _ = self.ras.pop();
+    fn update_ras(&mut self, op: RasOp) {
+        self.ras.apply(op);
+    }
+
+    fn commit_ras(&mut self, op: RasOp) {
+        self.ras.commit(op);
     }
 
     fn speculate(&mut self, _pc: u64, taken: bool) {
@@ -169,12 +169,12 @@ impl BranchPredictor for ScLTagePredictor {
         self.ittage.repair_history(ghr);
     }
 
-    fn snapshot_ras(&self) -> usize {
-        self.ras.snapshot_ptr()
+    fn snapshot_ras(&self) -> RasSnapshot {
+        self.ras.snapshot()
     }
 
-    fn restore_ras(&mut self, ptr: usize) {
-        self.ras.restore_ptr(ptr);
+    fn restore_ras(&mut self, snapshot: RasSnapshot) {
+        self.ras.restore(snapshot);
     }
 
     fn update_btb(&mut self, pc: u64, target: u64) {
@@ -183,6 +183,7 @@ impl BranchPredictor for ScLTagePredictor {
 
     fn repair_to_committed(&mut self) {
         self.spec_ghr = self.commit_ghr;
+        self.ras.restore_committed();
         self.tage.repair_to_committed_csrs();
         self.ittage.repair_to_committed_csrs();
     }
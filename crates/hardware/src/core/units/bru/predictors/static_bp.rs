@@ -4,10 +4,15 @@
 //! It utilizes a BTB for unconditional jumps and a RAS for function returns, but
 //! assumes all conditional branches will fall through.
 
-use crate::core::units::bru::{BranchPredictor, Ghr, btb::Btb, ras::Ras};
+use crate::core::units::bru::{
+    BranchPredictor, Ghr,
+    btb::Btb,
+    ras::{Ras, RasOp, RasSnapshot},
+};
+use serde::{Deserialize, Serialize};
 
 /// Static Branch Predictor structure.
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct StaticPredictor {
     /// Branch Target Buffer for jump targets.
     btb: Btb,
@@ -51,30 +56,33 @@ impl BranchPredictor for StaticPredictor {
     }
 
     /// Handles a function call by pushing the return address to the RAS.
-    fn on_call(&mut self, pc: u64, ret_addr: u64, target: u64) {
-        self.ras.push(ret_addr);
-        self.btb.update(pc, target);
-    }
-
     /// Predicts the return address using the RAS.
     fn predict_return(&self) -> Option<u64> {
         self.ras.top()
     }
 
     /// Handles a function return by popping from the RAS.
-    fn on_return(&mut self) {
-        let _ = self.ras.pop();
+    fn update_ras(&mut self, op: RasOp) {
+        self.ras.apply(op);
     }
 
-    fn snapshot_ras(&self) -> usize {
-        self.ras.snapshot_ptr()
+    fn commit_ras(&mut self, op: RasOp) {
+        self.ras.commit(op);
     }
 
-    fn restore_ras(&mut self, ptr: usize) {
-        self.ras.restore_ptr(ptr);
+    fn snapshot_ras(&self) -> RasSnapshot {
+        self.ras.snapshot()
+    }
+
+    fn restore_ras(&mut self, snapshot: RasSnapshot) {
+        self.ras.restore(snapshot);
     }
 
     fn update_btb(&mut self, pc: u64, target: u64) {
         self.btb.update(pc, target);
     }
+
+    fn repair_to_committed(&mut self) {
+        self.ras.restore_committed();
+    }
 }
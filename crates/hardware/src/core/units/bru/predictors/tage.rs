@@ -7,11 +7,15 @@
 
 use crate::config::TageConfig;
 use crate::core::units::bru::{
-    BranchPredictor, Ghr, btb::Btb, components::tage_core::TageCore, ras::Ras,
+    BranchPredictor, Ghr,
+    btb::Btb,
+    components::tage_core::TageCore,
+    ras::{Ras, RasOp, RasSnapshot},
 };
+use serde::{Deserialize, Serialize};
 
 /// TAGE Predictor structure.
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct TagePredictor {
     btb: Btb,
     ras: Ras,
@@ -58,17 +62,16 @@ impl BranchPredictor for TagePredictor {
         self.btb.lookup(pc)
     }
 
-    fn on_call(&mut self, pc: u64, ret_addr: u64, target: u64) {
-        self.ras.push(ret_addr);
-        self.btb.update(pc, target);
-    }
-
     fn predict_return(&self) -> Option<u64> {
         self.ras.top()
     }
 
-    fn on_return(&mut self) {
-        let _ = self.ras.pop();
+    fn update_ras(&mut self, op: RasOp) {
+        self.ras.apply(op);
+    }
+
+    fn commit_ras(&mut self, op: RasOp) {
+        self.ras.commit(op);
     }
 
     fn speculate(&mut self, _pc: u64, taken: bool) {
@@ -85,12 +88,12 @@ impl BranchPredictor for TagePredictor {
         self.tage.repair(&self.spec_ghr);
     }
 
-    fn snapshot_ras(&self) -> usize {
-        self.ras.snapshot_ptr()
+    fn snapshot_ras(&self) -> RasSnapshot {
+        self.ras.snapshot()
     }
 
-    fn restore_ras(&mut self, ptr: usize) {
-        self.ras.restore_ptr(ptr);
+    fn restore_ras(&mut self, snapshot: RasSnapshot) {
+        self.ras.restore(snapshot);
     }
 
     fn update_btb(&mut self, pc: u64, target: u64) {
@@ -99,6 +102,7 @@ impl BranchPredictor for TagePredictor {
 
     fn repair_to_committed(&mut self) {
         self.spec_ghr = self.commit_ghr;
+        self.ras.restore_committed();
         self.tage.repair_to_committed_csrs();
     }
 }
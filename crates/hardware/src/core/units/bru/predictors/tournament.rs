@@ -5,10 +5,15 @@
 //! This allows the predictor to adapt to different types of branch behaviors.
 
 use crate::config::TournamentConfig;
-use crate::core::units::bru::{BranchPredictor, Ghr, btb::Btb, ras::Ras};
+use crate::core::units::bru::{
+    BranchPredictor, Ghr,
+    btb::Btb,
+    ras::{Ras, RasOp, RasSnapshot},
+};
+use serde::{Deserialize, Serialize};
 
 /// Tournament Predictor structure.
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct TournamentPredictor {
     /// Branch Target Buffer.
     btb: Btb,
@@ -159,19 +164,18 @@ impl BranchPredictor for TournamentPredictor {
     }
 
     /// Handles a function call by pushing the return address to the RAS.
-    fn on_call(&mut self, pc: u64, ret_addr: u64, target: u64) {
-        self.ras.push(ret_addr);
-        self.btb.update(pc, target);
-    }
-
     /// Predicts the return address using the RAS.
     fn predict_return(&self) -> Option<u64> {
         self.ras.top()
     }
 
     /// Handles a function return by popping from the RAS.
-    fn on_return(&mut self) {
-        let _ = self.ras.pop();
+    fn update_ras(&mut self, op: RasOp) {
+        self.ras.apply(op);
+    }
+
+    fn commit_ras(&mut self, op: RasOp) {
+        self.ras.commit(op);
     }
 
     fn speculate(&mut self, _pc: u64, taken: bool) {
@@ -186,15 +190,19 @@ impl BranchPredictor for TournamentPredictor {
         self.ghr = ghr.val();
     }
 
-    fn snapshot_ras(&self) -> usize {
-        self.ras.snapshot_ptr()
+    fn snapshot_ras(&self) -> RasSnapshot {
+        self.ras.snapshot()
     }
 
-    fn restore_ras(&mut self, ptr: usize) {
-        self.ras.restore_ptr(ptr);
+    fn restore_ras(&mut self, snapshot: RasSnapshot) {
+        self.ras.restore(snapshot);
     }
 
     fn update_btb(&mut self, pc: u64, target: u64) {
         self.btb.update(pc, target);
     }
+
+    fn repair_to_committed(&mut self) {
+        self.ras.restore_committed();
+    }
 }
@@ -3,22 +3,125 @@
 //! The RAS is a specialized predictor for function return addresses. It operates
 //! as a hardware stack that pushes addresses on function calls and pops them
 //! on returns to predict the execution flow.
+//!
+//! Calls and returns update the speculative stack at fetch. Each fetched
+//! instruction carries a [`RasSnapshot`] of the stack pointer and top entry,
+//! so a misprediction can undo wrong-path pushes and pops, including a push
+//! that overwrote the entry a wrong-path return had just popped. Committed
+//! calls and returns are mirrored onto a second stack that full pipeline
+//! flushes (traps, `xRET`) restore.
 
-/// Return Address Stack structure.
-#[derive(Debug)]
-pub struct Ras {
+use crate::common::RegIdx;
+use crate::common::constants::{OPCODE_MASK, RD_MASK, RD_SHIFT, RS1_MASK, RS1_SHIFT};
+use crate::isa::abi;
+use crate::isa::rv64i::opcodes;
+use serde::{Deserialize, Serialize};
+
+/// Return-address-stack action of a jump, per RISC-V spec Table 2.1 (both
+/// `x1` and `x5` are link registers).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RasOp {
+    /// Call: push the return address.
+    Push(u64),
+    /// Return: pop.
+    Pop,
+    /// Coroutine swap: pop, then push the return address.
+    PopThenPush(u64),
+}
+
+impl RasOp {
+    /// Classifies a (decompressed) `JAL`/`JALR` whose return address is
+    /// `ret_addr`. Returns `None` for other instructions and for jumps that
+    /// neither link nor return.
+    pub fn for_inst(inst: u32, ret_addr: u64) -> Option<Self> {
+        let is_link = |r: RegIdx| r == abi::REG_RA || r == abi::REG_T0;
+        let rd = RegIdx::new(((inst >> RD_SHIFT) & RD_MASK) as u8);
+        let rs1 = RegIdx::new(((inst >> RS1_SHIFT) & RS1_MASK) as u8);
+        match inst & OPCODE_MASK {
+            opcodes::OP_JAL => is_link(rd).then_some(Self::Push(ret_addr)),
+            opcodes::OP_JALR => match (is_link(rd), is_link(rs1)) {
+                (false, false) => None,
+                (false, true) => Some(Self::Pop),
+                (true, false) => Some(Self::Push(ret_addr)),
+                (true, true) if rd == rs1 => Some(Self::Push(ret_addr)),
+                (true, true) => Some(Self::PopThenPush(ret_addr)),
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Speculative RAS state captured at fetch, before the instruction's own
+/// push or pop.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RasSnapshot {
+    /// Stack pointer.
+    ptr: usize,
+    /// Entry at the top of the stack (0 if empty).
+    top: u64,
+}
+
+/// One stack of return addresses.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Stack {
     /// The stack storage.
-    stack: Vec<u64>,
+    entries: Vec<u64>,
     /// Current stack pointer index.
     ptr: usize,
-    /// Maximum capacity of the stack.
-    capacity: usize,
+}
+
+impl Stack {
+    fn push(&mut self, addr: u64) {
+        let capacity = self.entries.len();
+        if self.ptr < capacity {
+            self.entries[self.ptr] = addr;
+            self.ptr += 1;
+        } else {
+            self.entries[capacity - 1] = addr;
+        }
+    }
+
+    fn pop(&mut self) -> Option<u64> {
+        if self.ptr == 0 {
+            None
+        } else {
+            self.ptr -= 1;
+            Some(self.entries[self.ptr])
+        }
+    }
+
+    fn top(&self) -> Option<u64> {
+        if self.ptr == 0 { None } else { Some(self.entries[self.ptr - 1]) }
+    }
+
+    fn apply(&mut self, op: RasOp) {
+        match op {
+            RasOp::Push(addr) => self.push(addr),
+            RasOp::Pop => {
+                let _ = self.pop();
+            }
+            RasOp::PopThenPush(addr) => {
+                let _ = self.pop();
+                self.push(addr);
+            }
+        }
+    }
+}
+
+/// Return Address Stack structure.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Ras {
+    /// Stack updated at fetch, possibly by wrong-path instructions.
+    spec: Stack,
+    /// Stack updated only by committed calls and returns.
+    committed: Stack,
 }
 
 impl Ras {
     /// Creates a new Return Address Stack with the specified capacity.
     pub fn new(capacity: usize) -> Self {
-        Self { stack: vec![0; capacity], ptr: 0, capacity }
+        let stack = Stack { entries: vec![0; capacity], ptr: 0 };
+        Self { spec: stack.clone(), committed: stack }
     }
 
     /// Pushes a return address onto the stack.
@@ -30,12 +133,7 @@ impl Ras {
     ///
     /// * `addr` - The return address to push.
     pub fn push(&mut self, addr: u64) {
-        if self.ptr < self.capacity {
-            self.stack[self.ptr] = addr;
-            self.ptr += 1;
-        } else {
-            self.stack[self.capacity - 1] = addr;
-        }
+        self.spec.push(addr);
     }
 
     /// Pops a return address from the stack.
@@ -44,37 +142,52 @@ impl Ras {
     ///
     /// The popped return address, or `None` if the stack is empty.
     pub fn pop(&mut self) -> Option<u64> {
-        if self.ptr == 0 {
-            None
-        } else {
-            self.ptr -= 1;
-            Some(self.stack[self.ptr])
-        }
+        self.spec.pop()
     }
 
     /// Peeks at the top of the stack without removing the entry.
     ///
-    /// Used to predict the target of a return instruction without modifying
-    /// the stack state until the instruction is committed.
+    /// Used to predict the target of a return instruction.
     ///
     /// # Returns
     ///
     /// The return address at the top of the stack, or `None` if empty.
     pub fn top(&self) -> Option<u64> {
-        if self.ptr == 0 { None } else { Some(self.stack[self.ptr - 1]) }
+        self.spec.top()
+    }
+
+    /// Applies a fetched call or return to the speculative stack.
+    pub fn apply(&mut self, op: RasOp) {
+        self.spec.apply(op);
     }
 
-    /// Snapshots the current stack pointer for speculative checkpointing.
+    /// Applies a committed call or return to the committed stack.
+    pub fn commit(&mut self, op: RasOp) {
+        self.committed.apply(op);
+    }
+
+    /// Snapshots the stack pointer and top entry for speculative checkpointing.
     ///
     /// Used at fetch time so the RAS can be restored on misprediction.
-    pub const fn snapshot_ptr(&self) -> usize {
-        self.ptr
+    pub fn snapshot(&self) -> RasSnapshot {
+        RasSnapshot { ptr: self.spec.ptr, top: self.spec.top().unwrap_or(0) }
     }
 
-    /// Restores the stack pointer to a previously captured snapshot.
+    /// Restores the stack pointer and top entry from a snapshot.
     ///
     /// Called on misprediction to undo speculative push/pop operations.
-    pub const fn restore_ptr(&mut self, ptr: usize) {
-        self.ptr = ptr;
+    pub fn restore(&mut self, snapshot: RasSnapshot) {
+        self.spec.ptr = snapshot.ptr.min(self.spec.entries.len());
+        if let Some(top) = self.spec.ptr.checked_sub(1) {
+            self.spec.entries[top] = snapshot.top;
+        }
+    }
+
+    /// Resets the speculative stack to the committed one.
+    ///
+    /// Called on full pipeline flushes, where every in-flight call and return
+    /// is discarded.
+    pub fn restore_committed(&mut self) {
+        self.spec.clone_from(&self.committed);
     }
 }
@@ -0,0 +1,161 @@
+//! Memory Hierarchy Bandwidth Accounting.
+//!
+//! Counts the bytes moved across each link of the cache hierarchy, split by
+//! why they moved: demand fills, prefetch fills and dirty writebacks. A link
+//! is named after the level that serves it: a cache fills from, and writes
+//! its dirty victims back to, the next enabled level below it, so with L2
+//! disabled L1 traffic crosses the [`Link::L3`] link, and the last-level
+//! cache always talks to [`Link::Dram`].
+//!
+//! Besides the totals, the bytes crossing each link are binned into
+//! fixed-length intervals of cycles, giving a utilization timeline.
+
+/// A link between a cache and the level below it, named after the lower level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Link {
+    /// Into L2.
+    L2 = 0,
+    /// Into L3.
+    L3 = 1,
+    /// Into main memory.
+    Dram = 2,
+}
+
+/// Number of links.
+pub const LINK_COUNT: usize = 3;
+
+impl Link {
+    /// Every link, in `Link as usize` order.
+    pub const ALL: [Self; LINK_COUNT] = [Self::L2, Self::L3, Self::Dram];
+
+    /// Returns the short name used in statistics output.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::L2 => "l2",
+            Self::L3 => "l3",
+            Self::Dram => "dram",
+        }
+    }
+}
+
+/// Why a line crossed a link.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Traffic {
+    /// Fill for a demand miss.
+    Demand = 0,
+    /// Fill for a hardware prefetch.
+    Prefetch = 1,
+    /// Dirty victim written back to the lower level.
+    Writeback = 2,
+}
+
+/// Number of traffic kinds.
+pub const TRAFFIC_COUNT: usize = 3;
+
+impl Traffic {
+    /// Every traffic kind, in `Traffic as usize` order.
+    pub const ALL: [Self; TRAFFIC_COUNT] = [Self::Demand, Self::Prefetch, Self::Writeback];
+
+    /// Returns the short name used in statistics output.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Demand => "demand",
+            Self::Prefetch => "prefetch",
+            Self::Writeback => "writeback",
+        }
+    }
+}
+
+/// Bytes moved across each link, in total and per interval.
+#[derive(Clone, Debug)]
+pub struct BandwidthStats {
+    /// Total bytes, indexed by `[link][traffic]`.
+    bytes: [[u64; TRAFFIC_COUNT]; LINK_COUNT],
+    /// Cycles per timeline interval (0 = no timeline).
+    interval: u64,
+    /// Cycle at which the first interval starts.
+    origin: u64,
+    /// Bytes per link in each interval so far.
+    timeline: Vec<[u64; LINK_COUNT]>,
+    /// Core clock frequency in MHz.
+    clock_mhz: u64,
+}
+
+impl Default for BandwidthStats {
+    /// Returns empty statistics with no timeline and a 1 GHz clock.
+    fn default() -> Self {
+        Self::new(0, 1000)
+    }
+}
+
+impl BandwidthStats {
+    /// Creates empty statistics binning traffic into `interval`-cycle
+    /// intervals (0 disables the timeline) on a `clock_mhz` clock.
+    pub const fn new(interval: u64, clock_mhz: u64) -> Self {
+        Self {
+            bytes: [[0; TRAFFIC_COUNT]; LINK_COUNT],
+            interval,
+            origin: 0,
+            timeline: Vec::new(),
+            clock_mhz,
+        }
+    }
+
+    /// Returns empty statistics with the same interval and clock whose
+    /// timeline starts at `cycle`.
+    #[must_use]
+    pub const fn restarted(&self, cycle: u64) -> Self {
+        let mut stats = Self::new(self.interval, self.clock_mhz);
+        stats.origin = cycle;
+        stats
+    }
+
+    /// Records `bytes` crossing `link` for `traffic` during `cycle`.
+    pub fn record(&mut self, link: Link, traffic: Traffic, bytes: u64, cycle: u64) {
+        self.bytes[link as usize][traffic as usize] += bytes;
+        if let Some(idx) = cycle.saturating_sub(self.origin).checked_div(self.interval) {
+            let idx = idx as usize;
+            if self.timeline.len() <= idx {
+                self.timeline.resize(idx + 1, [0; LINK_COUNT]);
+            }
+            self.timeline[idx][link as usize] += bytes;
+        }
+    }
+
+    /// Returns the bytes of `traffic` that crossed `link`.
+    pub const fn bytes(&self, link: Link, traffic: Traffic) -> u64 {
+        self.bytes[link as usize][traffic as usize]
+    }
+
+    /// Returns the bytes of every kind that crossed `link`.
+    pub fn link_bytes(&self, link: Link) -> u64 {
+        self.bytes[link as usize].iter().sum()
+    }
+
+    /// Converts `bytes` moved in `cycles` to GB/s at the configured clock.
+    pub fn gb_per_sec(&self, bytes: u64, cycles: u64) -> f64 {
+        if cycles == 0 {
+            0.0
+        } else {
+            bytes as f64 / cycles as f64 * self.clock_mhz as f64 / 1000.0
+        }
+    }
+
+    /// Returns the cycles per timeline interval (0 = no timeline).
+    pub const fn interval(&self) -> u64 {
+        self.interval
+    }
+
+    /// Returns the cycle at which the first timeline interval starts.
+    pub const fn origin(&self) -> u64 {
+        self.origin
+    }
+
+    /// Returns the bytes per link (indexed by `Link as usize`) in each
+    /// interval, up to the last one with traffic.
+    pub fn timeline(&self) -> &[[u64; LINK_COUNT]] {
+        &self.timeline
+    }
+}
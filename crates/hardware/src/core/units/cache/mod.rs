@@ -11,6 +11,9 @@ pub mod policies;
 /// Miss Status Holding Registers (MSHRs) for non-blocking cache access.
 pub mod mshr;
 
+/// Bytes moved between cache levels and DRAM, by traffic kind and interval.
+pub mod bandwidth;
+
 use self::policies::{
     FifoPolicy, LruPolicy, MruPolicy, PlruPolicy, RandomPolicy, ReplacementPolicy,
 };
@@ -353,34 +356,40 @@ impl CacheSim {
         (hit, penalty, evictions, prefetches)
     }
 
-    /// Installs prefetch targets into this cache, returning any evictions.
+    /// Installs prefetch targets into this cache, returning the number of
+    /// lines installed and any evictions.
     ///
     /// Used after filtering prefetch candidates through a shared prefetch filter.
     pub fn install_prefetches(
         &mut self,
         targets: &[u64],
         next_level_latency: u64,
-    ) -> Vec<EvictedLine> {
+    ) -> (usize, Vec<EvictedLine>) {
+        let mut installed = 0;
         let mut evictions = Vec::new();
         for &target in targets {
             if !self.contains(target) {
                 let (_pen, evicted) = self.install_line_tracked(target, false, next_level_latency);
+                installed += 1;
                 if let Some(ev) = evicted {
                     evictions.push(ev);
                 }
             }
         }
-        evictions
+        (installed, evictions)
     }
 
     /// Non-blocking cache access: checks for hit/miss without installing the line on miss.
     ///
-    /// On hit: updates replacement policy and dirty bit, triggers prefetcher. Returns true.
-    /// On miss: triggers prefetcher but does NOT install the line. Returns false.
-    /// The caller (MSHR) is responsible for installing the line later.
-    pub fn access_check(&mut self, addr: u64, is_write: bool) -> bool {
+    /// On hit: updates replacement policy and dirty bit. On miss: does NOT
+    /// install the line; the caller (MSHR) is responsible for installing it
+    /// later. Either way the prefetcher observes the access and its targets
+    /// are installed.
+    ///
+    /// Returns `(hit, prefetched_lines, prefetch_evictions)`.
+    pub fn access_check(&mut self, addr: u64, is_write: bool) -> (bool, usize, Vec<EvictedLine>) {
         if !self.enabled {
-            return false;
+            return (false, 0, Vec::new());
         }
 
         let set_index = ((addr as usize) / self.line_bytes) % self.num_sets;
@@ -402,13 +411,9 @@ impl CacheSim {
 
         let prefetches =
             self.prefetcher.as_mut().map_or_else(Vec::new, |pref| pref.observe(addr, hit));
-        for target in prefetches {
-            if !self.contains(target) {
-                let _ = self.install_line(target, false, 0);
-            }
-        }
+        let (prefetched, evictions) = self.install_prefetches(&prefetches, 0);
 
-        hit
+        (hit, prefetched, evictions)
     }
 
     /// Install a cache line from outside (e.g. when an MSHR completes).
@@ -459,6 +464,29 @@ impl CacheSim {
         false
     }
 
+    /// Writes back the cache line containing the specified address, keeping it valid.
+    ///
+    /// Used by `cbo.clean`. Returns true if the line was present and dirty.
+    pub fn clean_line(&mut self, addr: u64) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        let set_index = ((addr as usize) / self.line_bytes) % self.num_sets;
+        let tag = addr / (self.line_bytes * self.num_sets) as u64;
+        let base_idx = set_index * self.ways;
+
+        for i in 0..self.ways {
+            let idx = base_idx + i;
+            if self.lines[idx].valid && self.lines[idx].tag == tag {
+                let was_dirty = self.lines[idx].dirty;
+                self.lines[idx].dirty = false;
+                return was_dirty;
+            }
+        }
+        false
+    }
+
     /// Installs a line without evicting the previous one (used for exclusive policy
     /// when swapping an L1 evictee into L2, if there is an invalid way available).
     /// Falls back to normal `install_line` if no free way exists.
@@ -498,6 +526,56 @@ impl CacheSim {
         self.line_bytes
     }
 
+    /// Returns the valid lines as `(index, tag, dirty)` for a checkpoint,
+    /// with the geometry they were taken under.
+    ///
+    /// Replacement-policy and prefetcher state are not saved, so a restored
+    /// cache picks its first victims and prefetches as a cold one would.
+    pub fn save_state(&self) -> serde_json::Value {
+        let lines: Vec<(usize, u64, bool)> = self
+            .lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| line.valid)
+            .map(|(i, line)| (i, line.tag, line.dirty))
+            .collect();
+        serde_json::json!({
+            "sets": self.num_sets,
+            "ways": self.ways,
+            "line_bytes": self.line_bytes,
+            "lines": lines,
+        })
+    }
+
+    /// Restores the lines saved by [`save_state`](Self::save_state),
+    /// invalidating all others.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the problem if `state` is malformed or was
+    /// taken from a cache of a different geometry.
+    pub fn restore_state(&mut self, state: &serde_json::Value) -> Result<(), String> {
+        let field = |name: &str| state.get(name).and_then(serde_json::Value::as_u64);
+        let geometry = (field("sets"), field("ways"), field("line_bytes"));
+        let expected = (self.num_sets as u64, self.ways as u64, self.line_bytes as u64);
+        if geometry != (Some(expected.0), Some(expected.1), Some(expected.2)) {
+            return Err(format!(
+                "cache has {} sets x {} ways x {} bytes, checkpoint has {geometry:?}",
+                expected.0, expected.1, expected.2
+            ));
+        }
+        let lines: Vec<(usize, u64, bool)> =
+            serde_json::from_value(state.get("lines").cloned().unwrap_or_default())
+                .map_err(|e| e.to_string())?;
+        self.lines.fill(CacheLine::default());
+        for (i, tag, dirty) in lines {
+            let line =
+                self.lines.get_mut(i).ok_or_else(|| format!("cache line {i} out of range"))?;
+            *line = CacheLine { tag, valid: true, dirty };
+        }
+        Ok(())
+    }
+
     /// Flushes the cache: writes back all dirty lines and invalidates all entries.
     ///
     /// Returns information about evicted dirty lines so callers can account
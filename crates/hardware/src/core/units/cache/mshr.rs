@@ -4,6 +4,11 @@
 //! in-flight simultaneously, enabling Memory-Level Parallelism (MLP).
 //! When a second miss arrives for the same cache line, it coalesces with
 //! the existing MSHR entry instead of allocating a new one.
+//!
+//! When the memory controller queues requests, a miss that reaches DRAM is
+//! allocated with the latency of the cache levels only and waits on its
+//! DRAM request; the fill completes once the controller reports when that
+//! request finishes.
 
 use crate::core::pipeline::latches::Mem1Mem2Entry;
 use crate::core::pipeline::rob::RobTag;
@@ -42,6 +47,8 @@ pub struct MshrEntry {
     pub valid: bool,
     /// Whether the original access was a write (for `install_line` dirty bit).
     pub is_write: bool,
+    /// Queued DRAM request (id, arrival cycle) the fill still waits on.
+    pub dram_request: Option<(u64, u64)>,
 }
 
 /// Result of attempting an MSHR-aware cache access.
@@ -70,6 +77,9 @@ pub struct MshrFile {
     cap: usize,
     count: usize,
     line_bytes: usize,
+    /// Queued DRAM requests no MSHR waits on any more; their completions
+    /// are collected and dropped.
+    abandoned: Vec<u64>,
 }
 
 impl MshrFile {
@@ -84,10 +94,11 @@ impl MshrFile {
                 waiters: Vec::new(),
                 valid: false,
                 is_write: false,
+                dram_request: None,
             };
             capacity
         ];
-        Self { entries, cap: capacity, count: 0, line_bytes: safe_line }
+        Self { entries, cap: capacity, count: 0, line_bytes: safe_line, abandoned: Vec::new() }
     }
 
     /// Align an address to the cache line boundary.
@@ -145,6 +156,7 @@ impl MshrFile {
                     waiters: vec![waiter],
                     valid: true,
                     is_write,
+                    dram_request: None,
                 };
                 self.count += 1;
                 return CacheResponse::MshrAllocated { complete_cycle };
@@ -154,6 +166,34 @@ impl MshrFile {
         CacheResponse::MshrFull
     }
 
+    /// Makes the MSHR allocated for `addr` wait on queued DRAM request `id`,
+    /// which arrived at `current_cycle`. If `resp` shows no MSHR was
+    /// allocated for this miss, the request is abandoned instead.
+    pub fn await_dram(&mut self, addr: u64, resp: &CacheResponse, id: u64, current_cycle: u64) {
+        let line_addr = self.line_align(addr);
+        match (resp, self.find_line(line_addr)) {
+            (CacheResponse::MshrAllocated { .. }, Some(idx)) => {
+                self.entries[idx].dram_request = Some((id, current_cycle));
+            }
+            _ => self.abandoned.push(id),
+        }
+    }
+
+    /// Collects DRAM completions through `take_completion`, which returns
+    /// the completion cycle of a queued request once it is known. A waiting
+    /// MSHR's fill is delayed by its request's latency from arrival.
+    pub fn resolve_dram(&mut self, mut take_completion: impl FnMut(u64) -> Option<u64>) {
+        for entry in &mut self.entries {
+            if let Some((id, arrival)) = entry.dram_request
+                && let Some(done) = take_completion(id)
+            {
+                entry.complete_cycle += done.saturating_sub(arrival);
+                entry.dram_request = None;
+            }
+        }
+        self.abandoned.retain(|&id| take_completion(id).is_none());
+    }
+
     /// Check for completed MSHRs and return them.
     ///
     /// Completed entries are freed. The caller is responsible for
@@ -161,7 +201,8 @@ impl MshrFile {
     pub fn drain_completions(&mut self, current_cycle: u64) -> Vec<MshrEntry> {
         let mut completed = Vec::new();
         for entry in &mut self.entries {
-            if entry.valid && entry.complete_cycle <= current_cycle {
+            if entry.valid && entry.dram_request.is_none() && entry.complete_cycle <= current_cycle
+            {
                 let mut taken = MshrEntry {
                     line_addr: 0,
                     state: MshrState::Complete,
@@ -169,6 +210,7 @@ impl MshrFile {
                     waiters: Vec::new(),
                     valid: false,
                     is_write: false,
+                    dram_request: None,
                 };
                 std::mem::swap(entry, &mut taken);
                 taken.state = MshrState::Complete;
@@ -196,6 +238,11 @@ impl MshrFile {
     /// Flush all MSHRs.
     pub fn flush(&mut self) {
         for entry in &mut self.entries {
+            if entry.valid
+                && let Some((id, _)) = entry.dram_request.take()
+            {
+                self.abandoned.push(id);
+            }
             entry.valid = false;
             entry.waiters.clear();
         }
@@ -415,4 +462,43 @@ mod tests {
         assert_eq!(completed[0].waiters.len(), 0);
         assert_eq!(completed[0].line_addr, 0x1000);
     }
+
+    #[test]
+    fn test_dram_request_delays_completion_until_resolved() {
+        let mut mf = MshrFile::new(4, 64);
+
+        // Cache levels take 20 cycles; the DRAM request is still queued.
+        let resp = mf.request(0x1000, false, 20, 10, make_waiter(1));
+        mf.await_dram(0x1000, &resp, 7, 10);
+        assert!(mf.drain_completions(100).is_empty());
+
+        // Not issued yet: nothing changes.
+        mf.resolve_dram(|_| None);
+        assert!(mf.drain_completions(100).is_empty());
+
+        // DRAM finished at cycle 60, 50 cycles after arrival.
+        mf.resolve_dram(|id| (id == 7).then_some(60));
+        assert!(mf.drain_completions(79).is_empty());
+        assert_eq!(mf.drain_completions(80).len(), 1);
+    }
+
+    #[test]
+    fn test_dram_request_abandoned_on_coalesce_and_flush() {
+        let mut mf = MshrFile::new(4, 64);
+        let resp = mf.request(0x1000, false, 20, 10, make_waiter(1));
+        mf.await_dram(0x1000, &resp, 1, 10);
+        let resp = mf.request(0x1000, false, 20, 11, make_waiter(2));
+        mf.await_dram(0x1000, &resp, 2, 11);
+        mf.flush();
+
+        // Both requests are still collected once the controller issues them.
+        let mut taken = Vec::new();
+        mf.resolve_dram(|id| {
+            taken.push(id);
+            Some(50)
+        });
+        taken.sort_unstable();
+        assert_eq!(taken, [1, 2]);
+        mf.resolve_dram(|_| panic!("no request left"));
+    }
 }
@@ -3,6 +3,13 @@
 //! This module implements RISC-V memory ordering semantics for
 //! FENCE instructions, including the predecessor/successor ordering
 //! sets (I/O/R/W) and TSO fence variants.
+//!
+//! The pipeline decodes every FENCE with [`Fence::decode`]: issue holds the
+//! fence until older operations in its predecessor set complete, younger
+//! loads/stores in its successor set wait for it to commit, and commit
+//! drains the store buffer when the predecessor set includes memory.
+//! FENCE.I additionally drains the store buffer and invalidates the L1
+//! I-cache at commit.
 
 /// Predecessor/Successor ordering bits for FENCE instructions.
 ///
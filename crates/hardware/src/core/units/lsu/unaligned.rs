@@ -4,6 +4,7 @@
 //! splitting them into multiple aligned byte reads/writes when the access
 //! is not naturally aligned. It also provides:
 //! - Alignment checking utilities
+//! - Splitting at access-granule boundaries
 //! - Cache line crossing detection
 //! - Latency calculation for unaligned accesses
 //! - Support for byte-granular split access
@@ -27,6 +28,46 @@ pub const fn is_aligned(addr: u64, size: u64) -> bool {
     (addr & (size - 1)) == 0
 }
 
+/// Width in bytes of the aligned window the LSU reads or writes in a single
+/// access. Misaligned accesses that cross a window boundary are split.
+pub const ACCESS_GRANULE: u64 = 8;
+
+/// Checks whether a misaligned access crosses an [`ACCESS_GRANULE`]
+/// boundary and so must be split into two aligned sub-accesses.
+///
+/// # Arguments
+///
+/// * `addr` - The byte address of the access.
+/// * `size` - The access width in bytes.
+///
+/// # Returns
+///
+/// `true` if the access is misaligned and spans two granules.
+pub const fn needs_split(addr: u64, size: u64) -> bool {
+    !is_aligned(addr, size) && (addr % ACCESS_GRANULE) + size > ACCESS_GRANULE
+}
+
+/// Splits an access into the aligned sub-accesses that cover it.
+///
+/// # Arguments
+///
+/// * `addr` - The byte address of the access.
+/// * `size` - The access width in bytes.
+///
+/// # Returns
+///
+/// The `(addr, len)` of the first sub-access and, if the access
+/// [needs a split](needs_split), of the second one starting at the next
+/// granule boundary.
+pub const fn split_at_granule(addr: u64, size: u64) -> ((u64, u64), Option<(u64, u64)>) {
+    if !needs_split(addr, size) {
+        return ((addr, size), None);
+    }
+    let boundary = (addr | (ACCESS_GRANULE - 1)).wrapping_add(1);
+    let low = boundary - addr;
+    ((addr, low), Some((boundary, size - low)))
+}
+
 /// Checks whether an unaligned access crosses a cache line boundary.
 ///
 /// An access crosses a cache line boundary if it begins in one cache line
@@ -114,7 +155,7 @@ pub const fn store_misaligned_trap(addr: u64) -> Trap {
 ///
 /// # Returns
 ///
-/// The size in bytes (0 for Nop, 1/2/4/8 for actual operations)
+/// The size in bytes (0 for Nop, 1/2/4/8/16 for actual operations)
 pub const fn width_to_bytes(width: crate::core::pipeline::signals::MemWidth) -> u64 {
     use crate::core::pipeline::signals::MemWidth;
     match width {
@@ -123,6 +164,7 @@ pub const fn width_to_bytes(width: crate::core::pipeline::signals::MemWidth) ->
         MemWidth::Half => 2,
         MemWidth::Word => 4,
         MemWidth::Double => 8,
+        MemWidth::Quad => 16,
     }
 }
 
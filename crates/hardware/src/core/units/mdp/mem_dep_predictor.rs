@@ -17,6 +17,12 @@ pub struct MdpStats {
     pub predictions_wait_for: u64,
     /// Number of violations (calls to `violation`).
     pub violations: u64,
+    /// `WaitFor` loads whose address overlapped the predicted store
+    /// (a violation the prediction avoided).
+    pub avoided_violations: u64,
+    /// `WaitFor` loads whose address did not overlap the predicted store
+    /// (the load was delayed needlessly).
+    pub false_dependences: u64,
 }
 
 /// Raw prediction from the predictor (SSIT/LFST lookup result).
@@ -38,6 +38,11 @@ pub struct MemDepUnit {
     predictor: PredictorKind,
     /// In-flight dependency records: `waiter_rob_tag.0` → barrier it waits on.
     deps: HashMap<u32, DepRecord>,
+    /// Issued loads that waited on a barrier: `load_rob_tag.0` → barrier.
+    /// Kept until the load's address is known so the prediction can be scored.
+    waited: HashMap<u32, RobTag>,
+    /// Byte range `[start, end)` written by each barrier store that woke a waiter.
+    barrier_ranges: HashMap<u32, (u64, u64)>,
     /// Aggregated statistics (`bypass`/`wait_all`/`wait_for`/violations).
     stats: MdpStats,
 }
@@ -69,7 +74,13 @@ impl MemDepUnit {
                 &config.pipeline.store_set,
             ))),
         };
-        Self { predictor, deps: HashMap::new(), stats: MdpStats::default() }
+        Self {
+            predictor,
+            deps: HashMap::new(),
+            waited: HashMap::new(),
+            barrier_ranges: HashMap::new(),
+            stats: MdpStats::default(),
+        }
     }
 
     /// Called at dispatch for every instruction.
@@ -133,10 +144,11 @@ impl MemDepUnit {
 
     /// Called when a store resolves its address (in memory2).
     ///
+    /// `range` is the `[start, end)` physical byte range the store writes.
     /// Marks all deps waiting on this store as resolved.
     /// Returns the store's `RobTag` so the IQ can wake entries whose
     /// `WaitFor(barrier)` matches it.
-    pub fn store_resolved(&mut self, store_rob_tag: RobTag) -> Option<RobTag> {
+    pub fn store_resolved(&mut self, store_rob_tag: RobTag, range: (u64, u64)) -> Option<RobTag> {
         let mut any_woken = false;
         for dep in self.deps.values_mut() {
             if dep.barrier == store_rob_tag && !dep.resolved {
@@ -144,14 +156,45 @@ impl MemDepUnit {
                 any_woken = true;
             }
         }
-        if any_woken { Some(store_rob_tag) } else { None }
+        if any_woken {
+            let _ = self.barrier_ranges.insert(store_rob_tag.0, range);
+            Some(store_rob_tag)
+        } else {
+            None
+        }
     }
 
     /// Called when an instruction is issued from the IQ.
     ///
-    /// Removes its dependency record (cleanup).
+    /// Removes its dependency record (cleanup). A load that waited on a
+    /// barrier is remembered until [`load_executed`](Self::load_executed).
     pub fn issued(&mut self, rob_tag: RobTag) {
-        let _ = self.deps.remove(&rob_tag.0);
+        if let Some(dep) = self.deps.remove(&rob_tag.0) {
+            let _ = self.waited.insert(rob_tag.0, dep.barrier);
+        }
+    }
+
+    /// Called when a load has read memory, with the `[start, end)` physical
+    /// byte range it accessed.
+    ///
+    /// Scores a `WaitFor` prediction: overlapping the barrier store means the
+    /// wait avoided a violation, otherwise it was a false dependence.
+    pub fn load_executed(&mut self, load_rob_tag: RobTag, range: (u64, u64)) {
+        let Some(barrier) = self.waited.remove(&load_rob_tag.0) else {
+            return;
+        };
+        if let Some(&(start, end)) = self.barrier_ranges.get(&barrier.0) {
+            if range.0 < end && range.1 > start {
+                self.stats.avoided_violations += 1;
+            } else {
+                self.stats.false_dependences += 1;
+            }
+        }
+        if !self.waited.values().any(|&b| b == barrier)
+            && !self.deps.values().any(|d| d.barrier == barrier)
+        {
+            let _ = self.barrier_ranges.remove(&barrier.0);
+        }
     }
 
     /// Train on violation detection.
@@ -165,6 +208,8 @@ impl MemDepUnit {
     /// Full flush — clear all dep records and LFST.
     pub fn flush(&mut self) {
         self.deps.clear();
+        self.waited.clear();
+        self.barrier_ranges.clear();
         if let PredictorKind::StoreSet(predictor) = &mut self.predictor {
             predictor.flush();
         }
@@ -176,6 +221,8 @@ impl MemDepUnit {
     /// contains surviving entries when we walk it for LFST rebuild.
     pub fn flush_after(&mut self, keep_tag: RobTag, rob: &Rob) {
         self.deps.retain(|&tag, _| RobTag(tag).is_older_or_eq(keep_tag));
+        self.waited.retain(|&tag, _| RobTag(tag).is_older_or_eq(keep_tag));
+        self.barrier_ranges.retain(|&tag, _| RobTag(tag).is_older_or_eq(keep_tag));
         if let PredictorKind::StoreSet(predictor) = &mut self.predictor {
             predictor.flush_after(keep_tag);
             // Rebuild LFST from surviving stores in program order.
@@ -285,10 +332,33 @@ mod tests {
         let _ = mdu.dispatch(load_pc, l1, true, false);
 
         // Resolve store — should wake the load.
-        let woken = mdu.store_resolved(s1);
+        let woken = mdu.store_resolved(s1, (0x100, 0x108));
         assert_eq!(woken, Some(s1));
     }
 
+    #[test]
+    fn test_load_executed_scores_prediction() {
+        let config = store_set_config();
+        let mut mdu = MemDepUnit::new(&config);
+        let load_pc = 0x1000;
+        let store_pc = 0x2000;
+        mdu.violation(load_pc, store_pc);
+
+        for (load_range, avoided, false_deps) in [((0x104, 0x108), 1, 0), ((0x200, 0x208), 1, 1)] {
+            let s = RobTag(5);
+            let l = RobTag(10);
+            let _ = mdu.dispatch(store_pc, s, false, true);
+            let _ = mdu.dispatch(load_pc, l, true, false);
+            let _ = mdu.store_resolved(s, (0x100, 0x108));
+            mdu.issued(l);
+            mdu.load_executed(l, load_range);
+            assert_eq!(mdu.stats().avoided_violations, avoided);
+            assert_eq!(mdu.stats().false_dependences, false_deps);
+            assert!(mdu.waited.is_empty() && mdu.barrier_ranges.is_empty());
+            mdu.flush();
+        }
+    }
+
     #[test]
     fn test_issued_cleans_up() {
         let config = store_set_config();
@@ -101,7 +101,7 @@ impl PmpEntry {
 /// Maintains the PMP configuration and address registers and provides
 /// a `check` method that determines whether an access at a given
 /// physical address is permitted.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Pmp {
     /// PMP entries (up to `PMP_COUNT`).
     entries: Vec<PmpEntry>,
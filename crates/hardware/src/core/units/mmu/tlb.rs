@@ -7,6 +7,7 @@
 //! before invoking the hardware page table walker.
 
 use crate::common::{Asid, Ppn, Vpn};
+use serde::{Deserialize, Serialize};
 
 /// Translation data and permission bits returned on a TLB hit.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -27,7 +28,7 @@ pub struct TlbHit {
 }
 
 /// A single entry in the TLB.
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
 #[allow(clippy::struct_excessive_bools)]
 struct TlbEntry {
     /// Virtual Page Number (Tag).
@@ -141,6 +142,30 @@ impl Tlb {
         }
     }
 
+    /// Returns the entries for a checkpoint.
+    pub fn save_state(&self) -> serde_json::Value {
+        serde_json::to_value(&self.entries).unwrap_or_default()
+    }
+
+    /// Restores the entries saved by [`save_state`](Self::save_state).
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the problem if `state` is malformed or was
+    /// taken from a TLB of a different size.
+    pub fn restore_state(&mut self, state: serde_json::Value) -> Result<(), String> {
+        let entries: Vec<TlbEntry> = serde_json::from_value(state).map_err(|e| e.to_string())?;
+        if entries.len() != self.entries.len() {
+            return Err(format!(
+                "TLB has {} entries, checkpoint has {}",
+                self.entries.len(),
+                entries.len()
+            ));
+        }
+        self.entries = entries;
+        Ok(())
+    }
+
     /// Flushes all entries from the TLB.
     ///
     /// Called when SFENCE.VMA has rs1=x0 and rs2=x0.
@@ -192,7 +217,7 @@ impl Tlb {
 
 /// Shared L2 TLB sitting between the per-access-type L1 TLBs and the
 /// hardware page table walker. 4-way set-associative with LRU replacement.
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct L2Tlb {
     /// Flat array of entries: `sets * ways` elements, laid out
     /// `[set0_way0, set0_way1, …, set0_wayN, set1_way0, …]`.
@@ -282,6 +307,33 @@ impl L2Tlb {
         self.touch_lru(set, victim);
     }
 
+    /// Returns the entries and LRU ages for a checkpoint.
+    pub fn save_state(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or_default()
+    }
+
+    /// Restores the entries and LRU ages saved by
+    /// [`save_state`](Self::save_state), keeping the configured latency.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the problem if `state` is malformed or was
+    /// taken from a TLB of a different geometry.
+    pub fn restore_state(&mut self, state: serde_json::Value) -> Result<(), String> {
+        let saved: Self = serde_json::from_value(state).map_err(|e| e.to_string())?;
+        if (saved.entries.len(), saved.ways) != (self.entries.len(), self.ways) {
+            return Err(format!(
+                "L2 TLB has {} entries in {} ways, checkpoint has {} in {}",
+                self.entries.len(),
+                self.ways,
+                saved.entries.len(),
+                saved.ways
+            ));
+        }
+        *self = Self { latency: self.latency, ..saved };
+        Ok(())
+    }
+
     /// Flushes all entries.
     pub fn flush(&mut self) {
         for e in &mut self.entries {
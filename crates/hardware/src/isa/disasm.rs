@@ -3,6 +3,13 @@
 //! Converts a 32-bit instruction encoding into a human-readable mnemonic
 //! string for debug tracing, logging, and test diagnostics.
 //!
+//! The default [`Syntax::Canonical`] output matches `objdump`: common
+//! pseudo-instructions (`nop`, `mv`, `li`, `ret`, `j`, `jr`, `beqz`/`bnez`,
+//! `not`, `neg`, `seqz`/`snez`, `sext.w`, `csrr`/`csrw`/`csrs`/`csrc`), ABI
+//! register names, and symbolic
+//! CSR names.  [`Syntax::Numeric`] keeps the base instruction, `xN`/`fN`
+//! registers, and hexadecimal CSR numbers for tooling that parses the text.
+//!
 //! # Supported Extensions
 //!
 //! - RV64I (base integer)
@@ -10,17 +17,21 @@
 //! - RV64A (atomic)
 //! - RV64F (single-precision float)
 //! - RV64D (double-precision float)
+//! - Zicbom/Zicboz (CBO.CLEAN, CBO.FLUSH, CBO.INVAL, CBO.ZERO)
+//! - Zicond (CZERO.EQZ, CZERO.NEZ)
 //! - Privileged (ECALL, EBREAK, xRET, CSR, FENCE, WFI)
 //!
 //! # Usage
 //!
 //! ```ignore
 //! use rvsim_core::isa::disasm::disassemble;
-//! let text = disassemble(0x00A00513); // ADDI x10, x0, 10
-//! assert_eq!(text, "addi x10, x0, 10");
+//! use rvsim_core::isa::disasm::{Syntax, disassemble, disassemble_with};
+//! assert_eq!(disassemble(0x00A00513), "li a0, 10");
+//! assert_eq!(disassemble_with(0x00A00513, Syntax::Numeric), "addi x10, x0, 10");
 //! ```
 
-use crate::common::RegIdx;
+use crate::common::{CsrAddr, RegIdx};
+use crate::core::arch::csr;
 use crate::isa::instruction::InstructionBits;
 use crate::isa::privileged::opcodes as sys_op;
 use crate::isa::rv64a::{funct5 as a_f5, opcodes as a_op};
@@ -29,6 +40,8 @@ use crate::isa::rv64f::{funct3 as f_f3, funct7 as f_f7, opcodes as f_op};
 use crate::isa::rv64i::{funct3 as i_f3, funct7 as i_f7, opcodes as i_op};
 use crate::isa::rv64m::{funct3 as m_f3, opcodes as m_op};
 use crate::isa::rvc;
+use crate::isa::zicbo::opcodes as cbo_op;
+use crate::isa::zicond::opcodes as zicond_op;
 
 /// ABI register names for x0–x31.
 const REG_NAMES: [&str; 32] = [
@@ -44,27 +57,65 @@ const FREG_NAMES: [&str; 32] = [
     "fs10", "fs11", "ft8", "ft9", "ft10", "ft11",
 ];
 
-/// Returns the ABI name for an integer register index.
-#[inline]
-fn xreg(idx: RegIdx) -> &'static str {
-    REG_NAMES.get(idx.as_usize()).copied().unwrap_or("x??")
+/// Output style for [`disassemble_with`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Syntax {
+    /// Pseudo-instructions, ABI register names, and symbolic CSR names.
+    #[default]
+    Canonical,
+    /// Base instructions only, `xN`/`fN` registers, and hexadecimal CSRs.
+    Numeric,
 }
 
-/// Returns the ABI name for a floating-point register index.
-#[inline]
-fn freg(idx: RegIdx) -> &'static str {
-    FREG_NAMES.get(idx.as_usize()).copied().unwrap_or("f??")
+impl Syntax {
+    /// Returns the name of an integer register.
+    fn x(self, idx: RegIdx) -> String {
+        match self {
+            Self::Canonical => REG_NAMES.get(idx.as_usize()).copied().unwrap_or("x??").to_string(),
+            Self::Numeric => format!("x{}", idx.as_u8()),
+        }
+    }
+
+    /// Returns the name of a floating-point register.
+    fn f(self, idx: RegIdx) -> String {
+        match self {
+            Self::Canonical => FREG_NAMES.get(idx.as_usize()).copied().unwrap_or("f??").to_string(),
+            Self::Numeric => format!("f{}", idx.as_u8()),
+        }
+    }
+
+    /// Returns the name of a CSR, falling back to its hexadecimal address.
+    fn csr(self, addr: CsrAddr) -> String {
+        match (self, csr::csr_name(addr)) {
+            (Self::Canonical, Some(name)) => name.to_string(),
+            _ => format!("{:#05x}", addr.as_u32()),
+        }
+    }
+
+    const fn pseudo(self) -> bool {
+        matches!(self, Self::Canonical)
+    }
 }
 
 /// Disassembles a 32-bit RISC-V instruction into a human-readable string.
 ///
 /// Returns a mnemonic like `"add a0, a1, a2"` or `"unknown"` for
-/// unrecognised encodings.
+/// unrecognised encodings, using [`Syntax::Canonical`].
 ///
 /// # Arguments
 ///
 /// * `inst` - The raw 32-bit instruction encoding.
 pub fn disassemble(inst: u32) -> String {
+    disassemble_with(inst, Syntax::Canonical)
+}
+
+/// Disassembles a 32-bit RISC-V instruction in the given [`Syntax`].
+///
+/// # Arguments
+///
+/// * `inst` - The raw 32-bit instruction encoding.
+/// * `syn` - Output style.
+pub fn disassemble_with(inst: u32, syn: Syntax) -> String {
     // Compressed instructions: expand to 32-bit equivalent first.
     if inst & 0x3 != 0x3 {
         let c_inst = inst as u16;
@@ -72,7 +123,7 @@ pub fn disassemble(inst: u32) -> String {
         if expanded == 0 {
             return format!("unknown ({inst:#010x})");
         }
-        return disassemble(expanded);
+        return disassemble_with(expanded, syn);
     }
 
     let opcode = inst.opcode();
@@ -95,12 +146,12 @@ pub fn disassemble(inst: u32) -> String {
 
     match opcode {
         // ── R-type register-register ──────────────────────
-        i_op::OP_REG => disasm_op_reg(rd, rs1, rs2, f3, f7, false),
-        i_op::OP_REG_32 => disasm_op_reg(rd, rs1, rs2, f3, f7, true),
+        i_op::OP_REG => disasm_op_reg(syn, rd, rs1, rs2, f3, f7, false),
+        i_op::OP_REG_32 => disasm_op_reg(syn, rd, rs1, rs2, f3, f7, true),
 
         // ── I-type immediate arithmetic ───────────────────
-        i_op::OP_IMM => disasm_op_imm(rd, rs1, f3, imm_i, false),
-        i_op::OP_IMM_32 => disasm_op_imm(rd, rs1, f3, imm_i, true),
+        i_op::OP_IMM => disasm_op_imm(syn, rd, rs1, f3, imm_i, false),
+        i_op::OP_IMM_32 => disasm_op_imm(syn, rd, rs1, f3, imm_i, true),
 
         // ── Loads ─────────────────────────────────────────
         i_op::OP_LOAD => {
@@ -114,11 +165,11 @@ pub fn disassemble(inst: u32) -> String {
                 i_f3::LWU => "lwu",
                 _ => "l??",
             };
-            format!("{mn} {}, {imm_i}({})", xreg(rd), xreg(rs1))
+            format!("{mn} {}, {imm_i}({})", syn.x(rd), syn.x(rs1))
         }
         f_op::OP_LOAD_FP => {
             let mn = if f3 == i_f3::LW { "flw" } else { "fld" };
-            format!("{mn} {}, {imm_i}({})", freg(rd), xreg(rs1))
+            format!("{mn} {}, {imm_i}({})", syn.f(rd), syn.x(rs1))
         }
 
         // ── Stores ────────────────────────────────────────
@@ -130,11 +181,11 @@ pub fn disassemble(inst: u32) -> String {
                 i_f3::SD => "sd",
                 _ => "s??",
             };
-            format!("{mn} {}, {imm_s}({})", xreg(rs2), xreg(rs1))
+            format!("{mn} {}, {imm_s}({})", syn.x(rs2), syn.x(rs1))
         }
         f_op::OP_STORE_FP => {
             let mn = if f3 == i_f3::SW { "fsw" } else { "fsd" };
-            format!("{mn} {}, {imm_s}({})", freg(rs2), xreg(rs1))
+            format!("{mn} {}, {imm_s}({})", syn.f(rs2), syn.x(rs1))
         }
 
         // ── Branches ──────────────────────────────────────
@@ -157,17 +208,20 @@ pub fn disassemble(inst: u32) -> String {
                 let v = (bit12 << 12) | (bit11 << 11) | (bits10_5 << 5) | (bits4_1 << 1);
                 ((v as i32) << 19 >> 19) as i64
             };
-            format!("{mn} {}, {}, {imm_b}", xreg(rs1), xreg(rs2))
+            if syn.pseudo() && matches!(f3, i_f3::BEQ | i_f3::BNE) && rs2.is_zero() {
+                return format!("{mn}z {}, {imm_b}", syn.x(rs1));
+            }
+            format!("{mn} {}, {}, {imm_b}", syn.x(rs1), syn.x(rs2))
         }
 
         // ── U-type ────────────────────────────────────────
         i_op::OP_LUI => {
             let imm = ((inst & 0xFFFFF000) as i32) as i64;
-            format!("lui {}, {:#x}", xreg(rd), (imm >> 12) & 0xFFFFF)
+            format!("lui {}, {:#x}", syn.x(rd), (imm >> 12) & 0xFFFFF)
         }
         i_op::OP_AUIPC => {
             let imm = ((inst & 0xFFFFF000) as i32) as i64;
-            format!("auipc {}, {:#x}", xreg(rd), (imm >> 12) & 0xFFFFF)
+            format!("auipc {}, {:#x}", syn.x(rd), (imm >> 12) & 0xFFFFF)
         }
 
         // ── JAL ───────────────────────────────────────────
@@ -178,55 +232,103 @@ pub fn disassemble(inst: u32) -> String {
             let bit20 = (inst >> 31) & 1;
             let v = (bit20 << 20) | (bits19_12 << 12) | (bit11 << 11) | (bits10_1 << 1);
             let imm = ((v as i32) << 11 >> 11) as i64;
-            format!("jal {}, {imm}", xreg(rd))
+            if syn.pseudo() && rd.is_zero() {
+                return format!("j {imm}");
+            }
+            format!("jal {}, {imm}", syn.x(rd))
         }
 
         // ── JALR ──────────────────────────────────────────
         i_op::OP_JALR => {
-            format!("jalr {}, {imm_i}({})", xreg(rd), xreg(rs1))
+            if syn.pseudo() && rd.is_zero() && imm_i == 0 {
+                if rs1 == RegIdx::new(1) {
+                    return "ret".to_string();
+                }
+                return format!("jr {}", syn.x(rs1));
+            }
+            format!("jalr {}, {imm_i}({})", syn.x(rd), syn.x(rs1))
         }
 
         // ── Floating-point arithmetic ─────────────────────
-        f_op::OP_FP => disasm_op_fp(inst, rd, rs1, rs2, f3, f7),
+        f_op::OP_FP => disasm_op_fp(syn, inst, rd, rs1, rs2, f3, f7),
 
         // ── FMA ───────────────────────────────────────────
         f_op::OP_FMADD => {
             let p = fp_precision(f7);
-            format!("fmadd.{p} {}, {}, {}, {}", freg(rd), freg(rs1), freg(rs2), freg(inst.rs3()))
+            format!(
+                "fmadd.{p} {}, {}, {}, {}",
+                syn.f(rd),
+                syn.f(rs1),
+                syn.f(rs2),
+                syn.f(inst.rs3())
+            )
         }
         f_op::OP_FMSUB => {
             let p = fp_precision(f7);
-            format!("fmsub.{p} {}, {}, {}, {}", freg(rd), freg(rs1), freg(rs2), freg(inst.rs3()))
+            format!(
+                "fmsub.{p} {}, {}, {}, {}",
+                syn.f(rd),
+                syn.f(rs1),
+                syn.f(rs2),
+                syn.f(inst.rs3())
+            )
         }
         f_op::OP_FNMSUB => {
             let p = fp_precision(f7);
-            format!("fnmsub.{p} {}, {}, {}, {}", freg(rd), freg(rs1), freg(rs2), freg(inst.rs3()))
+            format!(
+                "fnmsub.{p} {}, {}, {}, {}",
+                syn.f(rd),
+                syn.f(rs1),
+                syn.f(rs2),
+                syn.f(inst.rs3())
+            )
         }
         f_op::OP_FNMADD => {
             let p = fp_precision(f7);
-            format!("fnmadd.{p} {}, {}, {}, {}", freg(rd), freg(rs1), freg(rs2), freg(inst.rs3()))
+            format!(
+                "fnmadd.{p} {}, {}, {}, {}",
+                syn.f(rd),
+                syn.f(rs1),
+                syn.f(rs2),
+                syn.f(inst.rs3())
+            )
         }
 
         // ── Atomic ────────────────────────────────────────
-        a_op::OP_AMO => disasm_amo(rd, rs1, rs2, f3, f7),
+        a_op::OP_AMO => disasm_amo(syn, rd, rs1, rs2, f3, f7),
 
         // ── FENCE / System ────────────────────────────────
-        i_op::OP_MISC_MEM => {
-            if f3 == i_f3::FENCE_I {
-                "fence.i".to_string()
-            } else {
-                "fence".to_string()
+        i_op::OP_MISC_MEM => match f3 {
+            i_f3::FENCE_I => "fence.i".to_string(),
+            cbo_op::CBO => {
+                let mn = match inst >> 20 {
+                    cbo_op::CBO_INVAL => "cbo.inval",
+                    cbo_op::CBO_CLEAN => "cbo.clean",
+                    cbo_op::CBO_FLUSH => "cbo.flush",
+                    cbo_op::CBO_ZERO => "cbo.zero",
+                    _ => return format!("unknown ({inst:#010x})"),
+                };
+                format!("{mn} ({})", syn.x(rs1))
             }
-        }
+            _ => "fence".to_string(),
+        },
 
-        sys_op::OP_SYSTEM => disasm_system(inst, rd, rs1, f3),
+        sys_op::OP_SYSTEM => disasm_system(syn, inst, rd, rs1, f3),
 
         _ => format!("unknown ({inst:#010x})"),
     }
 }
 
 /// Disassemble `OP_REG` / `OP_REG_32` (R-type register-register).
-fn disasm_op_reg(rd: RegIdx, rs1: RegIdx, rs2: RegIdx, f3: u32, f7: u32, is_w: bool) -> String {
+fn disasm_op_reg(
+    syn: Syntax,
+    rd: RegIdx,
+    rs1: RegIdx,
+    rs2: RegIdx,
+    f3: u32,
+    f7: u32,
+    is_w: bool,
+) -> String {
     let suffix = if is_w { "w" } else { "" };
 
     // M-extension
@@ -242,7 +344,26 @@ fn disasm_op_reg(rd: RegIdx, rs1: RegIdx, rs2: RegIdx, f3: u32, f7: u32, is_w: b
             m_f3::REMU => "remu",
             _ => "m??",
         };
-        return format!("{mn}{suffix} {}, {}, {}", xreg(rd), xreg(rs1), xreg(rs2));
+        return format!("{mn}{suffix} {}, {}, {}", syn.x(rd), syn.x(rs1), syn.x(rs2));
+    }
+
+    // Zicond
+    if f7 == zicond_op::ZICOND && !is_w {
+        let mn = match f3 {
+            zicond_op::CZERO_EQZ => "czero.eqz",
+            zicond_op::CZERO_NEZ => "czero.nez",
+            _ => "czero.??",
+        };
+        return format!("{mn} {}, {}, {}", syn.x(rd), syn.x(rs1), syn.x(rs2));
+    }
+
+    if syn.pseudo() && !is_w && rs1.is_zero() {
+        if (f3, f7) == (i_f3::ADD_SUB, i_f7::SUB) {
+            return format!("neg {}, {}", syn.x(rd), syn.x(rs2));
+        }
+        if (f3, f7) == (i_f3::SLTU, i_f7::DEFAULT) {
+            return format!("snez {}, {}", syn.x(rd), syn.x(rs2));
+        }
     }
 
     let mn = match (f3, f7) {
@@ -258,13 +379,37 @@ fn disasm_op_reg(rd: RegIdx, rs1: RegIdx, rs2: RegIdx, f3: u32, f7: u32, is_w: b
         (i_f3::AND, _) => "and",
         _ => "r??",
     };
-    format!("{mn}{suffix} {}, {}, {}", xreg(rd), xreg(rs1), xreg(rs2))
+    format!("{mn}{suffix} {}, {}, {}", syn.x(rd), syn.x(rs1), syn.x(rs2))
 }
 
 /// Disassemble `OP_IMM` / `OP_IMM_32` (I-type immediate arithmetic).
-fn disasm_op_imm(rd: RegIdx, rs1: RegIdx, f3: u32, imm: i64, is_w: bool) -> String {
+fn disasm_op_imm(syn: Syntax, rd: RegIdx, rs1: RegIdx, f3: u32, imm: i64, is_w: bool) -> String {
     let suffix = if is_w { "w" } else { "" };
     let shamt = imm & 0x3F;
+
+    if syn.pseudo() {
+        match (f3, is_w) {
+            (i_f3::ADD_SUB, false) if rd.is_zero() && rs1.is_zero() && imm == 0 => {
+                return "nop".to_string();
+            }
+            (i_f3::ADD_SUB, false) if rs1.is_zero() => {
+                return format!("li {}, {imm}", syn.x(rd));
+            }
+            (i_f3::ADD_SUB, false) if imm == 0 => {
+                return format!("mv {}, {}", syn.x(rd), syn.x(rs1));
+            }
+            (i_f3::ADD_SUB, true) if imm == 0 => {
+                return format!("sext.w {}, {}", syn.x(rd), syn.x(rs1));
+            }
+            (i_f3::XOR, false) if imm == -1 => {
+                return format!("not {}, {}", syn.x(rd), syn.x(rs1));
+            }
+            (i_f3::SLTU, false) if imm == 1 => {
+                return format!("seqz {}, {}", syn.x(rd), syn.x(rs1));
+            }
+            _ => {}
+        }
+    }
     let mn = match f3 {
         i_f3::ADD_SUB => "addi",
         i_f3::SLT => "slti",
@@ -272,28 +417,44 @@ fn disasm_op_imm(rd: RegIdx, rs1: RegIdx, f3: u32, imm: i64, is_w: bool) -> Stri
         i_f3::XOR => "xori",
         i_f3::OR => "ori",
         i_f3::AND => "andi",
-        i_f3::SLL => return format!("slli{suffix} {}, {}, {shamt}", xreg(rd), xreg(rs1)),
+        i_f3::SLL => return format!("slli{suffix} {}, {}, {shamt}", syn.x(rd), syn.x(rs1)),
         i_f3::SRL_SRA => {
             let mn = if (imm >> 10) & 1 != 0 { "srai" } else { "srli" };
-            return format!("{mn}{suffix} {}, {}, {shamt}", xreg(rd), xreg(rs1));
+            return format!("{mn}{suffix} {}, {}, {shamt}", syn.x(rd), syn.x(rs1));
         }
         _ => "i??",
     };
-    format!("{mn}{suffix} {}, {}, {imm}", xreg(rd), xreg(rs1))
+    format!("{mn}{suffix} {}, {}, {imm}", syn.x(rd), syn.x(rs1))
 }
 
 /// Disassemble `OP_FP` (floating-point arithmetic).
-fn disasm_op_fp(inst: u32, rd: RegIdx, rs1: RegIdx, rs2: RegIdx, f3: u32, f7: u32) -> String {
+fn disasm_op_fp(
+    syn: Syntax,
+    inst: u32,
+    rd: RegIdx,
+    rs1: RegIdx,
+    rs2: RegIdx,
+    f3: u32,
+    f7: u32,
+) -> String {
     // Determine precision from format bits (bits 26:25 of funct7)
     let is_double = (f7 & 1) != 0;
     let p = if is_double { "d" } else { "s" };
 
     match f7 {
-        f_f7::FADD | d_f7::FADD_D => format!("fadd.{p} {}, {}, {}", freg(rd), freg(rs1), freg(rs2)),
-        f_f7::FSUB | d_f7::FSUB_D => format!("fsub.{p} {}, {}, {}", freg(rd), freg(rs1), freg(rs2)),
-        f_f7::FMUL | d_f7::FMUL_D => format!("fmul.{p} {}, {}, {}", freg(rd), freg(rs1), freg(rs2)),
-        f_f7::FDIV | d_f7::FDIV_D => format!("fdiv.{p} {}, {}, {}", freg(rd), freg(rs1), freg(rs2)),
-        f_f7::FSQRT | d_f7::FSQRT_D => format!("fsqrt.{p} {}, {}", freg(rd), freg(rs1)),
+        f_f7::FADD | d_f7::FADD_D => {
+            format!("fadd.{p} {}, {}, {}", syn.f(rd), syn.f(rs1), syn.f(rs2))
+        }
+        f_f7::FSUB | d_f7::FSUB_D => {
+            format!("fsub.{p} {}, {}, {}", syn.f(rd), syn.f(rs1), syn.f(rs2))
+        }
+        f_f7::FMUL | d_f7::FMUL_D => {
+            format!("fmul.{p} {}, {}, {}", syn.f(rd), syn.f(rs1), syn.f(rs2))
+        }
+        f_f7::FDIV | d_f7::FDIV_D => {
+            format!("fdiv.{p} {}, {}, {}", syn.f(rd), syn.f(rs1), syn.f(rs2))
+        }
+        f_f7::FSQRT | d_f7::FSQRT_D => format!("fsqrt.{p} {}, {}", syn.f(rd), syn.f(rs1)),
         f_f7::FSGNJ | d_f7::FSGNJ_D => {
             let mn = match f3 {
                 f_f3::FSGNJ => "fsgnj",
@@ -301,11 +462,11 @@ fn disasm_op_fp(inst: u32, rd: RegIdx, rs1: RegIdx, rs2: RegIdx, f3: u32, f7: u3
                 f_f3::FSGNJX => "fsgnjx",
                 _ => "fsgnj?",
             };
-            format!("{mn}.{p} {}, {}, {}", freg(rd), freg(rs1), freg(rs2))
+            format!("{mn}.{p} {}, {}, {}", syn.f(rd), syn.f(rs1), syn.f(rs2))
         }
         f_f7::FMIN_MAX | d_f7::FMIN_MAX_D => {
             let mn = if f3 == f_f3::FMIN { "fmin" } else { "fmax" };
-            format!("{mn}.{p} {}, {}, {}", freg(rd), freg(rs1), freg(rs2))
+            format!("{mn}.{p} {}, {}, {}", syn.f(rd), syn.f(rs1), syn.f(rs2))
         }
         f_f7::FCMP | d_f7::FCMP_D => {
             let mn = match f3 {
@@ -314,13 +475,13 @@ fn disasm_op_fp(inst: u32, rd: RegIdx, rs1: RegIdx, rs2: RegIdx, f3: u32, f7: u3
                 f_f3::FLE => "fle",
                 _ => "fcmp?",
             };
-            format!("{mn}.{p} {}, {}, {}", xreg(rd), freg(rs1), freg(rs2))
+            format!("{mn}.{p} {}, {}, {}", syn.x(rd), syn.f(rs1), syn.f(rs2))
         }
         f_f7::FCLASS_MV_X_F | d_f7::FCLASS_MV_X_D => {
             if f3 == f_f3::FCLASS {
-                format!("fclass.{p} {}, {}", xreg(rd), freg(rs1))
+                format!("fclass.{p} {}, {}", syn.x(rd), syn.f(rs1))
             } else {
-                format!("fmv.x.{} {}, {}", if is_double { "d" } else { "w" }, xreg(rd), freg(rs1))
+                format!("fmv.x.{} {}, {}", if is_double { "d" } else { "w" }, syn.x(rd), syn.f(rs1))
             }
         }
         f_f7::FCVT_W_F | d_f7::FCVT_W_D => {
@@ -330,7 +491,7 @@ fn disasm_op_fp(inst: u32, rd: RegIdx, rs1: RegIdx, rs2: RegIdx, f3: u32, f7: u3
                 2 => "l",
                 _ => "lu",
             };
-            format!("fcvt.{variant}.{p} {}, {}", xreg(rd), freg(rs1))
+            format!("fcvt.{variant}.{p} {}, {}", syn.x(rd), syn.f(rs1))
         }
         f_f7::FCVT_F_W | d_f7::FCVT_D_W => {
             let variant = match rs2.as_u8() {
@@ -339,13 +500,13 @@ fn disasm_op_fp(inst: u32, rd: RegIdx, rs1: RegIdx, rs2: RegIdx, f3: u32, f7: u3
                 2 => "l",
                 _ => "lu",
             };
-            format!("fcvt.{p}.{variant} {}, {}", freg(rd), xreg(rs1))
+            format!("fcvt.{p}.{variant} {}, {}", syn.f(rd), syn.x(rs1))
         }
         f_f7::FMV_F_X | d_f7::FMV_D_X => {
-            format!("fmv.{}.x {}, {}", if is_double { "d" } else { "w" }, freg(rd), xreg(rs1))
+            format!("fmv.{}.x {}, {}", if is_double { "d" } else { "w" }, syn.f(rd), syn.x(rs1))
         }
-        f_f7::FCVT_DS => format!("fcvt.d.s {}, {}", freg(rd), freg(rs1)),
-        d_f7::FCVT_S_D => format!("fcvt.s.d {}, {}", freg(rd), freg(rs1)),
+        f_f7::FCVT_DS => format!("fcvt.d.s {}, {}", syn.f(rd), syn.f(rs1)),
+        d_f7::FCVT_S_D => format!("fcvt.s.d {}, {}", syn.f(rd), syn.f(rs1)),
         _ => {
             let _ = inst;
             format!("fp?? (funct7={f7:#04x})")
@@ -354,8 +515,12 @@ fn disasm_op_fp(inst: u32, rd: RegIdx, rs1: RegIdx, rs2: RegIdx, f3: u32, f7: u3
 }
 
 /// Disassemble AMO instruction.
-fn disasm_amo(rd: RegIdx, rs1: RegIdx, rs2: RegIdx, f3: u32, f7: u32) -> String {
-    let suffix = if f3 == 0b011 { ".d" } else { ".w" };
+fn disasm_amo(syn: Syntax, rd: RegIdx, rs1: RegIdx, rs2: RegIdx, f3: u32, f7: u32) -> String {
+    let suffix = match f3 {
+        0b011 => ".d",
+        0b100 => ".q",
+        _ => ".w",
+    };
     let funct5 = f7 >> 2;
     let aq = (f7 >> 1) & 1 != 0;
     let rl = f7 & 1 != 0;
@@ -366,7 +531,7 @@ fn disasm_amo(rd: RegIdx, rs1: RegIdx, rs2: RegIdx, f3: u32, f7: u32) -> String
         (false, false) => "",
     };
     let mn = match funct5 {
-        a_f5::LR => return format!("lr{suffix}{ordering} {}, ({})", xreg(rd), xreg(rs1)),
+        a_f5::LR => return format!("lr{suffix}{ordering} {}, ({})", syn.x(rd), syn.x(rs1)),
         a_f5::SC => "sc",
         a_f5::AMOSWAP => "amoswap",
         a_f5::AMOADD => "amoadd",
@@ -377,13 +542,14 @@ fn disasm_amo(rd: RegIdx, rs1: RegIdx, rs2: RegIdx, f3: u32, f7: u32) -> String
         a_f5::AMOMAX => "amomax",
         a_f5::AMOMINU => "amominu",
         a_f5::AMOMAXU => "amomaxu",
+        a_f5::AMOCAS => "amocas",
         _ => "amo??",
     };
-    format!("{mn}{suffix}{ordering} {}, {}, ({})", xreg(rd), xreg(rs2), xreg(rs1))
+    format!("{mn}{suffix}{ordering} {}, {}, ({})", syn.x(rd), syn.x(rs2), syn.x(rs1))
 }
 
 /// Disassemble system instructions.
-fn disasm_system(inst: u32, rd: RegIdx, rs1: RegIdx, f3: u32) -> String {
+fn disasm_system(syn: Syntax, inst: u32, rd: RegIdx, rs1: RegIdx, f3: u32) -> String {
     // Fixed-encoding system instructions
     match inst {
         sys_op::ECALL => return "ecall".to_string(),
@@ -395,21 +561,33 @@ fn disasm_system(inst: u32, rd: RegIdx, rs1: RegIdx, f3: u32) -> String {
     }
 
     if (inst & 0xFE007FFF) == sys_op::SFENCE_VMA {
-        return format!("sfence.vma {}, {}", xreg(rs1), xreg(inst.rs2()));
+        return format!("sfence.vma {}, {}", syn.x(rs1), syn.x(inst.rs2()));
     }
 
     // CSR instructions
-    let csr = inst.csr();
-    let mn = match f3 {
-        sys_op::CSRRW => "csrrw",
-        sys_op::CSRRS => "csrrs",
-        sys_op::CSRRC => "csrrc",
-        sys_op::CSRRWI => return format!("csrrwi {}, {csr:#05x}, {}", xreg(rd), rs1.as_u8()),
-        sys_op::CSRRSI => return format!("csrrsi {}, {csr:#05x}, {}", xreg(rd), rs1.as_u8()),
-        sys_op::CSRRCI => return format!("csrrci {}, {csr:#05x}, {}", xreg(rd), rs1.as_u8()),
+    let csr = syn.csr(inst.csr());
+    let (mn, is_imm) = match f3 {
+        sys_op::CSRRW => ("csrrw", false),
+        sys_op::CSRRS => ("csrrs", false),
+        sys_op::CSRRC => ("csrrc", false),
+        sys_op::CSRRWI => ("csrrwi", true),
+        sys_op::CSRRSI => ("csrrsi", true),
+        sys_op::CSRRCI => ("csrrci", true),
         _ => return format!("system?? ({inst:#010x})"),
     };
-    format!("{mn} {}, {csr:#05x}, {}", xreg(rd), xreg(rs1))
+    let src = if is_imm { rs1.as_u8().to_string() } else { syn.x(rs1) };
+
+    if syn.pseudo() {
+        if f3 == sys_op::CSRRS && rs1.is_zero() {
+            return format!("csrr {}, {csr}", syn.x(rd));
+        }
+        if rd.is_zero() {
+            // csrrw/csrrs/csrrc x0 → csrw/csrs/csrc (and the immediate forms).
+            let short = mn.replacen("csrr", "csr", 1);
+            return format!("{short} {csr}, {src}");
+        }
+    }
+    format!("{mn} {}, {csr}, {src}", syn.x(rd))
 }
 
 /// Determine FMA precision suffix from the format field (bits 26:25).
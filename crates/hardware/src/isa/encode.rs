@@ -0,0 +1,204 @@
+//! RISC-V Instruction Encoder.
+//!
+//! The inverse of [`decode`](crate::isa::decode::decode): builds 32-bit
+//! instruction encodings from structured fields for every base format
+//! (R, R4, I, S, B, U, J). Immediates are taken as the sign-extended values
+//! `decode` produces and truncated to the bits the format holds; branch and
+//! jump offsets drop bit 0 and U-type immediates their low 12 bits. Used to
+//! generate valid instructions for tests and fuzzing.
+
+use crate::common::RegIdx;
+use crate::isa::instruction::{
+    Decoded, FUNCT3_MASK, FUNCT7_MASK, OPCODE_MASK, RD_MASK, RS1_MASK, RS2_MASK,
+};
+use crate::isa::rv64f::opcodes as fp_opcodes;
+use crate::isa::rv64i::opcodes;
+
+/// Bit position of the destination register field.
+const RD_SHIFT: u32 = 7;
+
+/// Bit position of the funct3 field.
+const FUNCT3_SHIFT: u32 = 12;
+
+/// Bit position of the first source register field.
+const RS1_SHIFT: u32 = 15;
+
+/// Bit position of the second source register field.
+const RS2_SHIFT: u32 = 20;
+
+/// Bit position of the funct7 field.
+const FUNCT7_SHIFT: u32 = 25;
+
+/// Bit position of the third source register field (R4-type).
+const RS3_SHIFT: u32 = 27;
+
+/// Bit position of the I-type immediate field.
+const I_IMM_SHIFT: u32 = 20;
+
+/// Bit mask for the R4-type format field (2 bits: `fmt`).
+const FMT_MASK: u32 = 0x3;
+
+/// Bit mask for a 12-bit immediate (I- and S-type).
+const IMM12_MASK: u32 = 0xFFF;
+
+/// Bit mask for the U-type immediate field (bits 12-31).
+const U_IMM_MASK: u32 = 0xFFFF_F000;
+
+/// Instruction format, which determines where the immediate bits live.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// `funct7 | rs2 | rs1 | funct3 | rd | opcode`.
+    R,
+    /// `rs3 | fmt | rs2 | rs1 | funct3 | rd | opcode` (fused multiply-add).
+    R4,
+    /// `imm[11:0] | rs1 | funct3 | rd | opcode`.
+    I,
+    /// `imm[11:5] | rs2 | rs1 | funct3 | imm[4:0] | opcode`.
+    S,
+    /// `imm[12|10:5] | rs2 | rs1 | funct3 | imm[4:1|11] | opcode`.
+    B,
+    /// `imm[31:12] | rd | opcode`.
+    U,
+    /// `imm[20|10:1|11|19:12] | rd | opcode`.
+    J,
+}
+
+/// Returns the format `decode` uses for `opcode`.
+///
+/// Opcodes whose immediate `decode` leaves at zero (SYSTEM, MISC-MEM) are
+/// reported as R-type, whose fields cover every bit of the encoding.
+pub const fn format(opcode: u32) -> Format {
+    match opcode {
+        opcodes::OP_IMM
+        | opcodes::OP_LOAD
+        | opcodes::OP_JALR
+        | opcodes::OP_IMM_32
+        | fp_opcodes::OP_LOAD_FP => Format::I,
+        opcodes::OP_STORE | fp_opcodes::OP_STORE_FP => Format::S,
+        opcodes::OP_BRANCH => Format::B,
+        opcodes::OP_LUI | opcodes::OP_AUIPC => Format::U,
+        opcodes::OP_JAL => Format::J,
+        fp_opcodes::OP_FMADD
+        | fp_opcodes::OP_FMSUB
+        | fp_opcodes::OP_FNMSUB
+        | fp_opcodes::OP_FNMADD => Format::R4,
+        // OP_REG, OP_REG_32, OP_FP, OP_AMO, OP_SYSTEM, OP_MISC_MEM
+        _ => Format::R,
+    }
+}
+
+/// Places the opcode, `rd`, `funct3` and `rs1` fields shared by most formats.
+const fn base(opcode: u32, rd: RegIdx, funct3: u32, rs1: RegIdx) -> u32 {
+    ((rs1.as_u8() as u32 & RS1_MASK) << RS1_SHIFT)
+        | ((funct3 & FUNCT3_MASK) << FUNCT3_SHIFT)
+        | ((rd.as_u8() as u32 & RD_MASK) << RD_SHIFT)
+        | (opcode & OPCODE_MASK)
+}
+
+/// Places the `rs2` field.
+const fn rs2_field(rs2: RegIdx) -> u32 {
+    (rs2.as_u8() as u32 & RS2_MASK) << RS2_SHIFT
+}
+
+/// Encodes an R-type instruction.
+///
+/// Used for register-register ALU, multiply/divide, floating-point and
+/// atomic instructions.
+pub const fn r_type(
+    opcode: u32,
+    rd: RegIdx,
+    funct3: u32,
+    rs1: RegIdx,
+    rs2: RegIdx,
+    funct7: u32,
+) -> u32 {
+    ((funct7 & FUNCT7_MASK) << FUNCT7_SHIFT) | rs2_field(rs2) | base(opcode, rd, funct3, rs1)
+}
+
+/// Encodes an R4-type instruction.
+///
+/// Used for fused multiply-add; `fmt` selects single (0) or double (1)
+/// precision and `funct3` holds the rounding mode.
+pub const fn r4_type(
+    opcode: u32,
+    rd: RegIdx,
+    funct3: u32,
+    rs1: RegIdx,
+    rs2: RegIdx,
+    rs3: RegIdx,
+    fmt: u32,
+) -> u32 {
+    ((rs3.as_u8() as u32 & RS1_MASK) << RS3_SHIFT)
+        | ((fmt & FMT_MASK) << FUNCT7_SHIFT)
+        | rs2_field(rs2)
+        | base(opcode, rd, funct3, rs1)
+}
+
+/// Encodes an I-type instruction with the low 12 bits of `imm`.
+///
+/// Used for loads, JALR, immediate arithmetic and immediate shifts (whose
+/// `funct6`/`funct7` is part of the immediate).
+pub const fn i_type(opcode: u32, rd: RegIdx, funct3: u32, rs1: RegIdx, imm: i64) -> u32 {
+    ((imm as u32 & IMM12_MASK) << I_IMM_SHIFT) | base(opcode, rd, funct3, rs1)
+}
+
+/// Encodes an S-type instruction with the low 12 bits of `imm`.
+///
+/// Used for stores.
+pub const fn s_type(opcode: u32, funct3: u32, rs1: RegIdx, rs2: RegIdx, imm: i64) -> u32 {
+    let imm = imm as u32;
+    (((imm >> 5) & 0x7F) << FUNCT7_SHIFT)
+        | rs2_field(rs2)
+        | ((imm & 0x1F) << RD_SHIFT)
+        | base(opcode, RegIdx::new(0), funct3, rs1)
+}
+
+/// Encodes a B-type instruction with bits 12:1 of the offset `imm`.
+///
+/// Used for conditional branches.
+pub const fn b_type(opcode: u32, funct3: u32, rs1: RegIdx, rs2: RegIdx, imm: i64) -> u32 {
+    let imm = imm as u32;
+    (((imm >> 12) & 0x1) << 31)
+        | (((imm >> 5) & 0x3F) << 25)
+        | rs2_field(rs2)
+        | (((imm >> 1) & 0xF) << 8)
+        | (((imm >> 11) & 0x1) << 7)
+        | base(opcode, RegIdx::new(0), funct3, rs1)
+}
+
+/// Encodes a U-type instruction with bits 31:12 of `imm`.
+///
+/// Used for LUI and AUIPC; `imm` is the value placed in the register before
+/// any addition, as `decode` reports it.
+pub const fn u_type(opcode: u32, rd: RegIdx, imm: i64) -> u32 {
+    (imm as u32 & U_IMM_MASK) | ((rd.as_u8() as u32 & RD_MASK) << RD_SHIFT) | (opcode & OPCODE_MASK)
+}
+
+/// Encodes a J-type instruction with bits 20:1 of the offset `imm`.
+///
+/// Used for JAL.
+pub const fn j_type(opcode: u32, rd: RegIdx, imm: i64) -> u32 {
+    let imm = imm as u32;
+    (((imm >> 20) & 0x1) << 31)
+        | (((imm >> 1) & 0x3FF) << 21)
+        | (((imm >> 11) & 0x1) << 20)
+        | (((imm >> 12) & 0xFF) << 12)
+        | ((rd.as_u8() as u32 & RD_MASK) << RD_SHIFT)
+        | (opcode & OPCODE_MASK)
+}
+
+/// Encodes the fields of `d` in the format of its opcode, ignoring `raw`.
+///
+/// Only the fields that format holds are used, so
+/// `encode(&decode(inst)) == inst` for every instruction.
+pub const fn encode(d: &Decoded) -> u32 {
+    match format(d.opcode) {
+        // An R4 funct7 holds rs3 and fmt.
+        Format::R | Format::R4 => r_type(d.opcode, d.rd, d.funct3, d.rs1, d.rs2, d.funct7),
+        Format::I => i_type(d.opcode, d.rd, d.funct3, d.rs1, d.imm),
+        Format::S => s_type(d.opcode, d.funct3, d.rs1, d.rs2, d.imm),
+        Format::B => b_type(d.opcode, d.funct3, d.rs1, d.rs2, d.imm),
+        Format::U => u_type(d.opcode, d.rd, d.imm),
+        Format::J => j_type(d.opcode, d.rd, d.imm),
+    }
+}
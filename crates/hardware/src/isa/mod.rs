@@ -11,6 +11,8 @@
 //! * `rv64f`: Standard Extension for Single-Precision Floating-Point.
 //! * `rv64d`: Standard Extension for Double-Precision Floating-Point.
 //! * `rvc`: Standard Extension for Compressed Instructions.
+//! * `zicbo`: Cache-Block Management and Zero Extensions (Zicbom, Zicboz).
+//! * `zicond`: Integer Conditional Operations Extension (Zicond).
 //! * `privileged`: Privileged Architecture (CSRs, Traps).
 
 /// Application Binary Interface (ABI) register name mappings.
@@ -19,6 +21,9 @@ pub mod abi;
 /// Instruction decoding logic for all RISC-V instruction formats.
 pub mod decode;
 
+/// Instruction encoding from structured fields, the inverse of `decode`.
+pub mod encode;
+
 /// Instruction disassembler for debug tracing and diagnostics.
 pub mod disasm;
 
@@ -45,3 +50,9 @@ pub mod rv64m;
 
 /// Compressed instruction extension (16-bit instruction encoding).
 pub mod rvc;
+
+/// Cache-block management and zero extensions (CBO instructions).
+pub mod zicbo;
+
+/// Integer conditional operations extension (`czero.eqz`, `czero.nez`).
+pub mod zicond;
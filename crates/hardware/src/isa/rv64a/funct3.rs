@@ -9,6 +9,9 @@ pub const WIDTH_32: u32 = 0b010;
 /// Operation Width: 64-bit (Double).
 pub const WIDTH_64: u32 = 0b011;
 
+/// Operation Width: 128-bit (Quad, `amocas.q` only).
+pub const WIDTH_128: u32 = 0b100;
+
 /// Ordering: Acquire.
 pub const AQ: u32 = 1 << 1;
 
@@ -34,3 +34,6 @@ pub const AMOMINU: u32 = 0b11000;
 
 /// Atomic Maximum (Unsigned).
 pub const AMOMAXU: u32 = 0b11100;
+
+/// Atomic Compare-and-Swap (Zacas).
+pub const AMOCAS: u32 = 0b00101;
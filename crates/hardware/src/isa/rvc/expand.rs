@@ -9,6 +9,11 @@ use crate::isa::rv64f::opcodes as fp_opcodes;
 use crate::isa::rv64i::{funct3, funct7, opcodes};
 
 /// Expands a 16-bit RVC instruction into its 32-bit equivalent.
+///
+/// Reserved encodings (e.g. `c.addi4spn` with `nzuimm = 0`, `c.jr x0`, the
+/// unused `c.subw`/`c.addw` slots) and quadrant 3 return `0`, which fetch
+/// reports as an illegal instruction.  HINT encodings expand to their base
+/// instruction with `rd = x0`.
 pub const fn expand(inst: u16) -> u32 {
     let op = inst & 0x3;
     let funct3 = (inst >> 13) & 0x7;
@@ -302,10 +307,8 @@ pub const fn expand(inst: u16) -> u32 {
         QUADRANT_2 => match funct3 {
             q2::C_SLLI => {
                 let imm = ((inst >> 2) & 0x1F) | ((inst >> 12) & 1) << 5;
+                // rd=0 is a HINT and expands to SLLI x0, x0, shamt.
                 let rd = ((inst >> 7) & 0x1F) as u32;
-                if rd == 0 {
-                    return 0; // C.SLLI with rd=0 is reserved
-                }
                 (imm as u32) << 20 | (rd << 15) | (funct3::SLL << 12) | (rd << 7) | opcodes::OP_IMM
             }
             q2::C_FLDSP => {
@@ -0,0 +1,14 @@
+//! RISC-V Cache-Block Operation Extensions (Zicbom, Zicboz).
+//!
+//! Zicbom adds `cbo.clean`, `cbo.flush` and `cbo.inval`, which write back
+//! and/or invalidate the cache block containing the address in `rs1`.
+//! Zicboz adds `cbo.zero`, which zeroes that block. All four share the
+//! `OP_MISC_MEM` opcode with `FENCE`, with `funct3 = CBO`, `rd = x0`, and the
+//! operation selected by the 12-bit immediate field.
+//!
+//! # Structure
+//!
+//! - `opcodes`: `funct3` selector, operation immediates and the block size.
+
+/// Cache-block operation encodings and block size.
+pub mod opcodes;
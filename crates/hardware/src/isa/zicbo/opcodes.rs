@@ -0,0 +1,21 @@
+//! RISC-V Cache-Block Operation (Zicbom/Zicboz) Opcodes.
+//!
+//! The CBO instructions use the `OP_MISC_MEM` opcode with `funct3 = CBO`.
+//! The operation is encoded in `inst[31:20]`.
+
+/// `funct3` selecting a cache-block operation under `OP_MISC_MEM`.
+pub const CBO: u32 = 0b010;
+
+/// `cbo.inval`: invalidate the block without writing it back.
+pub const CBO_INVAL: u32 = 0x000;
+/// `cbo.clean`: write back the block if dirty and keep it valid.
+pub const CBO_CLEAN: u32 = 0x001;
+/// `cbo.flush`: write back the block if dirty, then invalidate it.
+pub const CBO_FLUSH: u32 = 0x002;
+/// `cbo.zero`: store zeros to every byte of the block.
+pub const CBO_ZERO: u32 = 0x004;
+
+/// Cache-block size in bytes for both Zicbom and Zicboz.
+///
+/// Reported to software as `riscv,cbom-block-size` / `riscv,cboz-block-size`.
+pub const BLOCK_BYTES: u64 = 64;
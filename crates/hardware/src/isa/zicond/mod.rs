@@ -0,0 +1,12 @@
+//! RISC-V Integer Conditional Operations Extension (Zicond).
+//!
+//! Zicond adds `czero.eqz` and `czero.nez`, which write `rs1` to `rd` or zero
+//! it depending on whether `rs2` is zero. They share the `OP_REG` opcode with
+//! base integer arithmetic and are distinguished by `funct7 = ZICOND`.
+//!
+//! # Structure
+//!
+//! - `opcodes`: `funct7` selector and `funct3` operation codes.
+
+/// Conditional-zero encodings.
+pub mod opcodes;
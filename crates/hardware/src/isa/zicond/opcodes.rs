@@ -0,0 +1,12 @@
+//! RISC-V Integer Conditional Operations (Zicond) Opcodes.
+//!
+//! The Zicond instructions use the `OP_REG` opcode with `funct7 = ZICOND`.
+//! The operation is encoded in `funct3`.
+
+/// Zicond selector in the `funct7` field.
+pub const ZICOND: u32 = 0b0000111;
+
+/// `czero.eqz`: `rd = (rs2 == 0) ? 0 : rs1`.
+pub const CZERO_EQZ: u32 = 0b101;
+/// `czero.nez`: `rd = (rs2 != 0) ? 0 : rs1`.
+pub const CZERO_NEZ: u32 = 0b111;
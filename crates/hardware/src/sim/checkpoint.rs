@@ -0,0 +1,457 @@
+//! Simulator checkpoints.
+//!
+//! [`Simulator::save_checkpoint`] writes the whole simulated machine to a
+//! file that [`Simulator::load_checkpoint`] restores into a simulator built
+//! from the same configuration and image. The file is a little-endian `u64`
+//! header length, a JSON header, and the RAM pages the header lists:
+//!
+//! 1. **Harts:** PC, privilege mode, registers, CSRs, PMP entries, the LR/SC
+//!    reservation and WFI state of every hart, and the boot state a reboot
+//!    returns to.
+//! 2. **Devices:** the state each device exposes through
+//!    [`Device::save_state`](crate::soc::traits::Device::save_state), keyed
+//!    by name and base address (UART FIFOs, CLINT timers, PLIC, `VirtIO`
+//!    queues and the disk sectors written since the image was loaded).
+//! 3. **RAM:** every bank in 4 KiB pages; all-zero pages are left out.
+//! 4. **Microarchitecture (optional):** cache lines, TLB entries and the
+//!    branch predictor of every hart. Without it a restored hart starts with
+//!    cold caches and TLBs.
+//!
+//! In-flight instructions are not saved: saving first flushes every pipeline
+//! and writes committed stores to memory, as a breakpoint hit does. Host-side
+//! state is not saved either: files opened through semihosting or syscall
+//! emulation, TAP connections, queued memory-controller requests and UART
+//! output not yet collected.
+
+use crate::common::{PhysAddr, RegIdx, RegisterFile, SimError};
+use crate::core::Cpu;
+use crate::core::arch::csr::Csrs;
+use crate::core::arch::mode::PrivilegeMode;
+use crate::core::pipeline::engine::PipelineDispatch;
+use crate::core::units::mmu::pmp::{PMP_COUNT, Pmp};
+use crate::sim::simulator::{BootState, Simulator};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Identifies a checkpoint file.
+const MAGIC: &str = "rvsim-checkpoint";
+
+/// Format version written and read by this simulator. Version 1 held only
+/// hart 0 and the first RAM bank.
+pub const VERSION: u64 = 2;
+
+/// Granularity at which RAM is saved.
+const PAGE_SIZE: usize = 4096;
+
+/// Number of integer and floating-point registers.
+const NUM_REGS: u8 = 32;
+
+/// State a hart restarts from: what [`BootState`] holds.
+#[derive(Serialize, Deserialize)]
+struct ArchState {
+    pc: u64,
+    privilege: u8,
+    gpr: Vec<u64>,
+    /// Raw bits, since NaN payloads have no JSON form.
+    fpr: Vec<u64>,
+    csrs: Csrs,
+    /// `(cfg, addr)` of each PMP entry.
+    pmp: Vec<(u8, u64)>,
+}
+
+impl ArchState {
+    fn new(pc: u64, privilege: PrivilegeMode, regs: &RegisterFile, csrs: &Csrs, pmp: &Pmp) -> Self {
+        Self {
+            pc,
+            privilege: privilege.to_u8(),
+            gpr: (0..NUM_REGS).map(|i| regs.read(RegIdx::new(i))).collect(),
+            fpr: (0..NUM_REGS).map(|i| regs.read_f(RegIdx::new(i))).collect(),
+            csrs: csrs.clone(),
+            pmp: (0..PMP_COUNT).map(|i| (pmp.get_cfg(i), pmp.get_addr(i))).collect(),
+        }
+    }
+
+    fn regs(&self) -> RegisterFile {
+        let mut regs = RegisterFile::new();
+        for (i, (&x, &f)) in (0..NUM_REGS).zip(self.gpr.iter().zip(&self.fpr)) {
+            regs.write(RegIdx::new(i), x);
+            regs.write_f(RegIdx::new(i), f);
+        }
+        regs
+    }
+
+    /// Rebuilds the PMP, writing each address before its configuration
+    /// since locked entries ignore writes.
+    fn pmp(&self) -> Pmp {
+        let mut pmp = Pmp::new();
+        for (i, &(cfg, addr)) in self.pmp.iter().enumerate() {
+            pmp.set_addr(i, addr);
+            pmp.set_cfg(i, cfg);
+        }
+        pmp
+    }
+
+    fn boot_state(&self) -> BootState {
+        BootState {
+            pc: self.pc,
+            privilege: PrivilegeMode::from_u8(self.privilege),
+            regs: self.regs(),
+            csrs: self.csrs.clone(),
+            pmp: self.pmp(),
+        }
+    }
+}
+
+/// Architectural state of one hart.
+#[derive(Serialize, Deserialize)]
+struct HartState {
+    #[serde(flatten)]
+    arch: ArchState,
+    reservation: Option<u64>,
+    direct_mode: bool,
+    trace: bool,
+    /// PC to resume at if the hart is waiting in a WFI.
+    wfi: Option<u64>,
+    sw_seip: bool,
+}
+
+/// Saved state of one device.
+#[derive(Serialize, Deserialize)]
+struct DeviceState {
+    name: String,
+    base: u64,
+    state: Value,
+}
+
+/// Layout of one RAM bank and the indices of its saved pages.
+#[derive(Serialize, Deserialize)]
+struct RamBankState {
+    start: u64,
+    size: u64,
+    pages: Vec<u64>,
+}
+
+/// Caches, TLBs and branch predictor of one hart.
+#[derive(Serialize, Deserialize)]
+struct MicroarchState {
+    l1i: Value,
+    l1d: Value,
+    l2: Value,
+    l3: Value,
+    itlb: Value,
+    dtlb: Value,
+    l2_tlb: Value,
+    branch_predictor: Value,
+}
+
+/// The JSON header of a checkpoint file.
+#[derive(Serialize, Deserialize)]
+struct Header {
+    magic: String,
+    version: u64,
+    harts: Vec<HartState>,
+    /// Boot state of each hart; empty if the simulator had not ticked.
+    boot: Vec<ArchState>,
+    boots: u64,
+    devices: Vec<DeviceState>,
+    ram: Vec<RamBankState>,
+    microarch: Option<Vec<MicroarchState>>,
+}
+
+impl HartState {
+    fn capture(cpu: &Cpu) -> Self {
+        Self {
+            arch: ArchState::new(cpu.pc, cpu.privilege, &cpu.regs, &cpu.csrs, &cpu.pmp),
+            reservation: cpu.load_reservation.map(|addr| addr.val()),
+            direct_mode: cpu.direct_mode,
+            trace: cpu.trace_requested,
+            wfi: cpu.wfi_waiting.then_some(cpu.wfi_pc),
+            sw_seip: cpu.sw_seip,
+        }
+    }
+
+    /// Flushes `pipeline`, resets `cpu` and restores it to this state.
+    fn restore(&self, cpu: &mut Cpu, pipeline: &mut PipelineDispatch) {
+        pipeline.flush(cpu);
+        cpu.reset();
+        cpu.pc = self.arch.pc;
+        cpu.committed_next_pc = self.arch.pc;
+        cpu.privilege = PrivilegeMode::from_u8(self.arch.privilege);
+        cpu.regs = self.arch.regs();
+        cpu.csrs = self.arch.csrs.clone();
+        cpu.pmp = self.arch.pmp();
+        cpu.load_reservation = self.reservation.map(PhysAddr::new);
+        cpu.direct_mode = self.direct_mode;
+        cpu.set_trace(self.trace);
+        if let Some(pc) = self.wfi {
+            cpu.wfi_waiting = true;
+            cpu.wfi_pc = pc;
+        }
+        cpu.sw_seip = self.sw_seip;
+        cpu.exit_code = None;
+        if let PipelineDispatch::OutOfOrder(p) = pipeline {
+            p.engine.sync_arch_regs(cpu);
+        }
+    }
+}
+
+impl MicroarchState {
+    fn capture(cpu: &Cpu) -> Self {
+        Self {
+            l1i: cpu.l1_i_cache.save_state(),
+            l1d: cpu.l1_d_cache.save_state(),
+            l2: cpu.l2_cache.save_state(),
+            l3: cpu.l3_cache.save_state(),
+            itlb: cpu.mmu.itlb.save_state(),
+            dtlb: cpu.mmu.dtlb.save_state(),
+            l2_tlb: cpu.mmu.l2_tlb.save_state(),
+            branch_predictor: cpu.branch_predictor.save_state(),
+        }
+    }
+
+    fn restore(self, cpu: &mut Cpu) -> Result<(), String> {
+        cpu.l1_i_cache.restore_state(&self.l1i)?;
+        cpu.l1_d_cache.restore_state(&self.l1d)?;
+        cpu.l2_cache.restore_state(&self.l2)?;
+        cpu.l3_cache.restore_state(&self.l3)?;
+        cpu.mmu.itlb.restore_state(self.itlb)?;
+        cpu.mmu.dtlb.restore_state(self.dtlb)?;
+        cpu.mmu.l2_tlb.restore_state(self.l2_tlb)?;
+        cpu.branch_predictor.restore_state(self.branch_predictor)
+    }
+}
+
+/// Invalidates the caches and TLBs of a hart restored without
+/// microarchitectural state.
+fn invalidate_microarch(cpu: &mut Cpu) {
+    let _ = cpu.l1_i_cache.invalidate_all();
+    let _ = cpu.l1_d_cache.invalidate_all();
+    let _ = cpu.l2_cache.invalidate_all();
+    let _ = cpu.l3_cache.invalidate_all();
+    cpu.mmu.itlb.flush();
+    cpu.mmu.dtlb.flush();
+    cpu.mmu.l2_tlb.flush();
+}
+
+/// Returns the `(offset, length)` of each page of a bank of `size` bytes.
+fn pages(size: u64) -> impl Iterator<Item = (usize, usize)> {
+    let size = size as usize;
+    (0..size).step_by(PAGE_SIZE).map(move |offset| (offset, PAGE_SIZE.min(size - offset)))
+}
+
+impl Simulator {
+    /// Saves the simulator to a checkpoint file at `path` (see the module
+    /// documentation for what it holds). With `microarch` set, cache, TLB
+    /// and branch predictor contents are saved too.
+    ///
+    /// Every pipeline is flushed first, with committed stores written to
+    /// memory, so the run continues from the next instruction to commit just
+    /// as after a breakpoint hit.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SimError::Checkpoint`] if the file cannot be written.
+    pub fn save_checkpoint(
+        &mut self,
+        path: impl AsRef<Path>,
+        microarch: bool,
+    ) -> Result<(), SimError> {
+        let path = path.as_ref();
+        let fail =
+            |reason: String| SimError::Checkpoint { path: path.display().to_string(), reason };
+
+        // Before the first tick the PC set by the loader has not reached
+        // `committed_next_pc` yet, and nothing is in flight.
+        if !self.boot_states.is_empty() {
+            for (cpu, pipeline) in self.harts_mut() {
+                pipeline.quiesce(cpu);
+            }
+        }
+        let cpus: Vec<&Cpu> =
+            std::iter::once(&self.cpu).chain(self.secondary_harts.iter().map(|h| &h.cpu)).collect();
+        let banks: Vec<_> = self.cpu.ram.banks().cloned().collect();
+        let header = Header {
+            magic: MAGIC.to_owned(),
+            version: VERSION,
+            harts: cpus.iter().map(|cpu| HartState::capture(cpu)).collect(),
+            boot: self
+                .boot_states
+                .iter()
+                .map(|b| ArchState::new(b.pc, b.privilege, &b.regs, &b.csrs, &b.pmp))
+                .collect(),
+            boots: self.boots,
+            devices: self
+                .cpu
+                .bus
+                .bus
+                .devices()
+                .filter_map(|dev| {
+                    Some(DeviceState {
+                        name: dev.name().to_owned(),
+                        base: dev.address_range().0,
+                        state: dev.save_state()?,
+                    })
+                })
+                .collect(),
+            ram: banks
+                .iter()
+                .map(|bank| {
+                    let size = bank.end() - bank.start();
+                    RamBankState {
+                        start: bank.start(),
+                        size,
+                        pages: pages(size)
+                            .filter(|&(offset, len)| {
+                                bank.buffer().read_slice(offset, len).iter().any(|&b| b != 0)
+                            })
+                            .map(|(offset, _)| (offset / PAGE_SIZE) as u64)
+                            .collect(),
+                    }
+                })
+                .collect(),
+            microarch: microarch
+                .then(|| cpus.iter().map(|cpu| MicroarchState::capture(cpu)).collect()),
+        };
+
+        let json = serde_json::to_vec(&header).map_err(|e| fail(e.to_string()))?;
+        let file = File::create(path).map_err(|e| fail(e.to_string()))?;
+        let mut w = BufWriter::new(file);
+        w.write_all(&(json.len() as u64).to_le_bytes()).map_err(|e| fail(e.to_string()))?;
+        w.write_all(&json).map_err(|e| fail(e.to_string()))?;
+        for (bank, saved) in banks.iter().zip(&header.ram) {
+            for &page in &saved.pages {
+                let offset = page as usize * PAGE_SIZE;
+                let len = PAGE_SIZE.min(saved.size as usize - offset);
+                w.write_all(bank.buffer().read_slice(offset, len))
+                    .map_err(|e| fail(e.to_string()))?;
+            }
+        }
+        w.flush().map_err(|e| fail(e.to_string()))
+    }
+
+    /// Restores a checkpoint written by [`Self::save_checkpoint`].
+    ///
+    /// The simulator must have been built from the same configuration and
+    /// image as the one that saved it: the harts, RAM banks and devices are
+    /// checked against the checkpoint. RAM pages the checkpoint leaves out
+    /// are zeroed. Statistics restart from zero, keeping the cycle clock.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SimError::Checkpoint`] if the file cannot be read, is not a
+    /// version [`VERSION`] checkpoint, or does not match this simulator. A
+    /// mismatch found in a device or microarchitectural state can leave the
+    /// simulator partly restored.
+    pub fn load_checkpoint(&mut self, path: impl AsRef<Path>) -> Result<(), SimError> {
+        let path = path.as_ref();
+        let fail =
+            |reason: String| SimError::Checkpoint { path: path.display().to_string(), reason };
+
+        let file = File::open(path).map_err(|e| fail(e.to_string()))?;
+        let file_len = file.metadata().map_err(|e| fail(e.to_string()))?.len();
+        let mut r = BufReader::new(file);
+        let mut len = [0u8; 8];
+        r.read_exact(&mut len).map_err(|e| fail(e.to_string()))?;
+        let len = u64::from_le_bytes(len);
+        if len > file_len.saturating_sub(8) {
+            return Err(fail("not an rvsim checkpoint".to_owned()));
+        }
+        let mut json = vec![0u8; len as usize];
+        r.read_exact(&mut json).map_err(|e| fail(e.to_string()))?;
+        let header: Value = serde_json::from_slice(&json)
+            .map_err(|_| fail("not an rvsim checkpoint".to_owned()))?;
+        if header.get("magic").and_then(Value::as_str) != Some(MAGIC) {
+            return Err(fail("not an rvsim checkpoint".to_owned()));
+        }
+        let version = header.get("version").and_then(Value::as_u64).unwrap_or(0);
+        if version != VERSION {
+            return Err(fail(format!("unsupported version {version} (expected {VERSION})")));
+        }
+        let header: Header = serde_json::from_value(header).map_err(|e| fail(e.to_string()))?;
+
+        let num_harts = self.num_harts();
+        if header.harts.len() != num_harts {
+            return Err(fail(format!(
+                "simulator has {num_harts} harts, checkpoint has {}",
+                header.harts.len()
+            )));
+        }
+        if !header.boot.is_empty() && header.boot.len() != num_harts {
+            return Err(fail(format!("checkpoint has {} boot states", header.boot.len())));
+        }
+        if header.microarch.as_ref().is_some_and(|m| m.len() != num_harts) {
+            return Err(fail("microarchitectural state does not cover every hart".to_owned()));
+        }
+        let banks: Vec<_> = self.cpu.ram.banks().cloned().collect();
+        let layout: Vec<_> = banks.iter().map(|b| (b.start(), b.end() - b.start())).collect();
+        let saved: Vec<_> = header.ram.iter().map(|b| (b.start, b.size)).collect();
+        if layout != saved {
+            return Err(fail(format!(
+                "RAM banks (start, size) differ: simulator has {layout:x?}, checkpoint has {saved:x?}"
+            )));
+        }
+        for saved in &header.devices {
+            if !self
+                .cpu
+                .bus
+                .bus
+                .devices()
+                .any(|d| d.name() == saved.name && d.address_range().0 == saved.base)
+            {
+                return Err(fail(format!(
+                    "no {} device at {:#x} to restore",
+                    saved.name, saved.base
+                )));
+            }
+        }
+
+        let mut page = vec![0u8; PAGE_SIZE];
+        for (bank, saved) in banks.iter().zip(&header.ram) {
+            let mut listed = saved.pages.iter().peekable();
+            for (offset, len) in pages(saved.size) {
+                let buffer = bank.buffer();
+                if listed.next_if(|&&p| p as usize * PAGE_SIZE == offset).is_some() {
+                    r.read_exact(&mut page[..len]).map_err(|e| fail(e.to_string()))?;
+                    buffer.write_slice(offset, &page[..len]);
+                } else if buffer.read_slice(offset, len).iter().any(|&b| b != 0) {
+                    buffer.write_slice(offset, &vec![0; len]);
+                }
+            }
+            if let Some(p) = listed.next() {
+                return Err(fail(format!(
+                    "page {p} of the bank at {:#x} out of order",
+                    saved.start
+                )));
+            }
+        }
+
+        for saved in header.devices {
+            let dev = self
+                .cpu
+                .bus
+                .bus
+                .devices_mut()
+                .find(|d| d.name() == saved.name && d.address_range().0 == saved.base);
+            if let Some(dev) = dev {
+                dev.restore_state(saved.state).map_err(|e| fail(format!("{}: {e}", saved.name)))?;
+            }
+        }
+
+        let mut microarch = header.microarch.map(Vec::into_iter);
+        for ((cpu, pipeline), hart) in self.harts_mut().zip(&header.harts) {
+            hart.restore(cpu, pipeline);
+            match microarch.as_mut().and_then(Iterator::next) {
+                Some(state) => state.restore(cpu).map_err(fail)?,
+                None => invalidate_microarch(cpu),
+            }
+            cpu.stats.reset_for_boot();
+        }
+        self.boot_states = header.boot.iter().map(ArchState::boot_state).collect();
+        self.boots = header.boots;
+        self.pending_reboot = false;
+        Ok(())
+    }
+}
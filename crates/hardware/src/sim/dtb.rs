@@ -4,7 +4,11 @@
 //! This allows the simulator to provide a DTB to OpenSBI/Linux without
 //! requiring an external `dtc` compilation step.
 
+use crate::common::IrqId;
+use crate::common::constants::TIMEBASE_FREQ;
 use crate::config::Config;
+use crate::isa::zicbo::opcodes::BLOCK_BYTES;
+use crate::soc::interconnect::Bus;
 
 // FDT constants
 const FDT_MAGIC: u32 = 0xd00dfeed;
@@ -158,34 +162,55 @@ impl FdtBuilder {
     }
 }
 
+/// Phandle of the PLIC node.
+const PLIC_PHANDLE: u32 = 1;
+/// Phandle of the `SysCon` node (referenced by poweroff/reboot).
+const SYSCON_PHANDLE: u32 = 2;
+/// Phandle of hart 0's local interrupt controller; hart `h` uses `+ h`.
+const CPU_INTC_PHANDLE_BASE: u32 = 0x10;
+
+/// Encodes an `interrupts-extended` list of `(phandle, cause)` pairs.
+fn interrupts_extended(pairs: impl Iterator<Item = (u32, u32)>) -> Vec<u8> {
+    let mut data = Vec::new();
+    for (phandle, cause) in pairs {
+        data.extend_from_slice(&phandle.to_be_bytes());
+        data.extend_from_slice(&cause.to_be_bytes());
+    }
+    data
+}
+
+/// Emits a PLIC-routed interrupt for a device node.
+fn prop_plic_irq(b: &mut FdtBuilder, irq: Option<IrqId>) {
+    if let Some(irq) = irq {
+        b.prop_u32("interrupt-parent", PLIC_PHANDLE);
+        b.prop_bytes("interrupts", &irq.val().to_be_bytes());
+    }
+}
+
 /// Generates a DTB binary matching the simulator's `SoC` layout.
 ///
-/// The generated DTB includes:
-/// - Memory region at `ram_base` with `ram_size`
-/// - CLINT at `clint_base`
-/// - PLIC at 0x0c000000
-/// - UART at `uart_base`
-/// - `VirtIO` block device at `disk_base`
-/// - CPU with rv64imafdc ISA and SV39 MMU
-pub fn generate_dtb(config: &Config) -> Vec<u8> {
-    let ram_base = config.system.ram_base;
-    let ram_size = config.memory.ram_size as u64;
-    let uart_base = config.system.uart_base;
-    let disk_base = config.system.disk_base;
-    let clint_base = config.system.clint_base;
-    let syscon_base = config.system.syscon_base;
-    let rtc_base: u64 = 0x10_1000;
-    let plic_base: u64 = 0x0c00_0000;
-    let timebase_freq: u32 = 10_000_000;
-
+/// Node addresses, sizes, and PLIC interrupt numbers are taken from the
+/// devices registered on `bus`, so the tree always describes the system the
+/// simulator actually built.  The generated DTB includes:
+/// - One CPU per configured hart with `rv64imafdc_zicbom_zicboz` ISA and SV39 MMU
+/// - Memory node for the `DRAM` device
+/// - CLINT and PLIC with per-hart `interrupts-extended`
+/// - UARTs (`UART0` as `stdout-path`), `VirtIO` block and network devices,
+///   Goldfish RTC, and `SysCon` with poweroff/reboot sub-nodes, when present
+///   on the bus
+pub fn generate_dtb(config: &Config, bus: &Bus) -> Vec<u8> {
+    let num_harts = config.system.num_harts.max(1) as u32;
+    let timebase_freq = TIMEBASE_FREQ as u32;
+    let intc = |hart: u32| CPU_INTC_PHANDLE_BASE + hart;
+
+    let uart_base = bus
+        .devices()
+        .find(|d| d.name() == "UART0")
+        .map_or(config.system.uart_base, |d| d.address_range().0);
     let bootargs =
         format!("root=/dev/vda rw console=ttyS0 earlycon=uart8250,mmio,{uart_base:#x} rootwait");
     let stdout_path = format!("/soc/uart@{uart_base:x}");
 
-    // Phandle values (arbitrary unique IDs)
-    let cpu0_intc_phandle: u32 = 1;
-    let plic_phandle: u32 = 2;
-
     let mut b = FdtBuilder::new();
 
     // Root node
@@ -207,30 +232,32 @@ pub fn generate_dtb(config: &Config) -> Vec<u8> {
     b.prop_u32("#size-cells", 0);
     b.prop_u32("timebase-frequency", timebase_freq);
 
-    // /cpus/cpu@0
-    b.begin_node("cpu@0");
-    b.prop_string("device_type", "cpu");
-    b.prop_reg_1_0(0);
-    b.prop_string("status", "okay");
-    b.prop_string("compatible", "riscv");
-    b.prop_string("riscv,isa", "rv64imafdc");
-    b.prop_string("mmu-type", "riscv,sv39");
-
-    // /cpus/cpu@0/interrupt-controller
-    b.begin_node("interrupt-controller");
-    b.prop_u32("#interrupt-cells", 1);
-    b.prop_empty("interrupt-controller");
-    b.prop_string("compatible", "riscv,cpu-intc");
-    b.prop_u32("phandle", cpu0_intc_phandle);
-    b.end_node(); // interrupt-controller
-
-    b.end_node(); // cpu@0
+    for hart in 0..num_harts {
+        b.begin_node(&format!("cpu@{hart}"));
+        b.prop_string("device_type", "cpu");
+        b.prop_reg_1_0(hart);
+        b.prop_string("status", "okay");
+        b.prop_string("compatible", "riscv");
+        b.prop_string("riscv,isa", "rv64imafdc_zicbom_zicboz");
+        b.prop_u32("riscv,cbom-block-size", BLOCK_BYTES as u32);
+        b.prop_u32("riscv,cboz-block-size", BLOCK_BYTES as u32);
+        b.prop_string("mmu-type", "riscv,sv39");
+
+        b.begin_node("interrupt-controller");
+        b.prop_u32("#interrupt-cells", 1);
+        b.prop_empty("interrupt-controller");
+        b.prop_string("compatible", "riscv,cpu-intc");
+        b.prop_u32("phandle", intc(hart));
+        b.end_node(); // interrupt-controller
+
+        b.end_node(); // cpu@N
+    }
     b.end_node(); // cpus
 
-    // /memory
-    {
-        let node_name = format!("memory@{ram_base:x}");
-        b.begin_node(&node_name);
+    // /memory, one node per bank
+    for ram in bus.devices().filter(|d| d.name() == "DRAM") {
+        let (ram_base, ram_size) = ram.address_range();
+        b.begin_node(&format!("memory@{ram_base:x}"));
         b.prop_string("device_type", "memory");
         b.prop_reg_2_2(ram_base, ram_size);
         b.end_node();
@@ -243,108 +270,91 @@ pub fn generate_dtb(config: &Config) -> Vec<u8> {
     b.prop_string("compatible", "simple-bus");
     b.prop_empty("ranges");
 
-    // /soc/clint
-    {
-        let node_name = format!("clint@{clint_base:x}");
-        b.begin_node(&node_name);
-        b.prop_string("compatible", "riscv,clint0");
-        b.prop_reg_2_2(clint_base, 0x10000);
-        // interrupts-extended: <&cpu0_intc 3>, <&cpu0_intc 7>
-        // (3 = M-mode software interrupt, 7 = M-mode timer interrupt)
-        let mut ie = Vec::with_capacity(16);
-        ie.extend_from_slice(&cpu0_intc_phandle.to_be_bytes());
-        ie.extend_from_slice(&3u32.to_be_bytes());
-        ie.extend_from_slice(&cpu0_intc_phandle.to_be_bytes());
-        ie.extend_from_slice(&7u32.to_be_bytes());
-        b.prop_bytes("interrupts-extended", &ie);
-        b.end_node();
-    }
-
-    // /soc/uart
-    {
-        let node_name = format!("uart@{uart_base:x}");
-        b.begin_node(&node_name);
-        b.prop_string("compatible", "ns16550a");
-        b.prop_reg_2_2(uart_base, 0x100);
-        b.prop_u32("clock-frequency", 10_000_000);
-        b.prop_u32("interrupt-parent", plic_phandle);
-        b.prop_bytes("interrupts", &10u32.to_be_bytes());
-        b.prop_string("status", "okay");
-        b.end_node();
-    }
-
-    // /soc/virtio_mmio
-    {
-        let node_name = format!("virtio_mmio@{disk_base:x}");
-        b.begin_node(&node_name);
-        b.prop_string("compatible", "virtio,mmio");
-        b.prop_reg_2_2(disk_base, 0x1000);
-        b.prop_u32("interrupt-parent", plic_phandle);
-        b.prop_bytes("interrupts", &1u32.to_be_bytes());
-        b.end_node();
-    }
-
-    // /soc/plic
-    {
-        let node_name = format!("interrupt-controller@{plic_base:x}");
-        b.begin_node(&node_name);
-        b.prop_string("compatible", "riscv,plic0");
-        b.prop_reg_2_2(plic_base, 0x4000000);
-        b.prop_u32("#interrupt-cells", 1);
-        b.prop_empty("interrupt-controller");
-        // interrupts-extended: <&cpu0_intc 11>, <&cpu0_intc 9>
-        // (11 = M-mode external interrupt, 9 = S-mode external interrupt)
-        let mut ie = Vec::with_capacity(16);
-        ie.extend_from_slice(&cpu0_intc_phandle.to_be_bytes());
-        ie.extend_from_slice(&11u32.to_be_bytes());
-        ie.extend_from_slice(&cpu0_intc_phandle.to_be_bytes());
-        ie.extend_from_slice(&9u32.to_be_bytes());
-        b.prop_bytes("interrupts-extended", &ie);
-        b.prop_u32("riscv,ndev", 0x35);
-        b.prop_u32("phandle", plic_phandle);
-        b.end_node();
-    }
-
-    // /soc/syscon (with poweroff and reboot sub-nodes)
-    let syscon_phandle: u32 = 3;
-    {
-        let node_name = format!("syscon@{syscon_base:x}");
-        b.begin_node(&node_name);
-        b.prop_string("compatible", "syscon");
-        b.prop_reg_2_2(syscon_base, 0x1000);
-        b.prop_u32("phandle", syscon_phandle);
-        b.end_node();
-    }
-
-    // /soc/poweroff — writes 0x5555 to syscon offset 0x00
-    {
-        b.begin_node("poweroff");
-        b.prop_string("compatible", "syscon-poweroff");
-        b.prop_u32("regmap", syscon_phandle);
-        b.prop_u32("offset", 0x00);
-        b.prop_u32("value", 0x5555);
-        b.end_node();
-    }
-
-    // /soc/reboot — writes 0x7777 to syscon offset 0x00
-    {
-        b.begin_node("reboot");
-        b.prop_string("compatible", "syscon-reboot");
-        b.prop_u32("regmap", syscon_phandle);
-        b.prop_u32("offset", 0x00);
-        b.prop_u32("value", 0x7777);
-        b.end_node();
-    }
-
-    // /soc/rtc (Goldfish RTC)
-    {
-        let node_name = format!("rtc@{rtc_base:x}");
-        b.begin_node(&node_name);
-        b.prop_string("compatible", "google,goldfish-rtc");
-        b.prop_reg_2_2(rtc_base, 0x1000);
-        b.prop_u32("interrupt-parent", plic_phandle);
-        b.prop_bytes("interrupts", &11u32.to_be_bytes());
-        b.end_node();
+    for dev in bus.devices() {
+        let (base, size) = dev.address_range();
+        match dev.name() {
+            "CLINT" => {
+                b.begin_node(&format!("clint@{base:x}"));
+                b.prop_string("compatible", "riscv,clint0");
+                b.prop_reg_2_2(base, size);
+                // 3 = M-mode software interrupt, 7 = M-mode timer interrupt
+                let ie =
+                    interrupts_extended((0..num_harts).flat_map(|h| [(intc(h), 3), (intc(h), 7)]));
+                b.prop_bytes("interrupts-extended", &ie);
+                b.end_node();
+            }
+            "PLIC" => {
+                b.begin_node(&format!("interrupt-controller@{base:x}"));
+                b.prop_string("compatible", "riscv,plic0");
+                b.prop_reg_2_2(base, size);
+                b.prop_u32("#interrupt-cells", 1);
+                b.prop_empty("interrupt-controller");
+                // 11 = M-mode external interrupt, 9 = S-mode external interrupt;
+                // the order matches the PLIC's per-hart context numbering.
+                let ie =
+                    interrupts_extended((0..num_harts).flat_map(|h| [(intc(h), 11), (intc(h), 9)]));
+                b.prop_bytes("interrupts-extended", &ie);
+                b.prop_u32("riscv,ndev", 0x35);
+                b.prop_u32("phandle", PLIC_PHANDLE);
+                b.end_node();
+            }
+            "UART0" | "UART1" | "UART2" | "UART3" => {
+                b.begin_node(&format!("uart@{base:x}"));
+                b.prop_string("compatible", "ns16550a");
+                b.prop_reg_2_2(base, size);
+                b.prop_u32("clock-frequency", 10_000_000);
+                prop_plic_irq(&mut b, dev.get_irq_id());
+                b.prop_string("status", "okay");
+                b.end_node();
+            }
+            "VirtIO-Blk" | "VirtIO-Net" | "VirtIO-Rng" => {
+                b.begin_node(&format!("virtio_mmio@{base:x}"));
+                b.prop_string("compatible", "virtio,mmio");
+                b.prop_reg_2_2(base, size);
+                prop_plic_irq(&mut b, dev.get_irq_id());
+                b.end_node();
+            }
+            "GoldfishRTC" => {
+                b.begin_node(&format!("rtc@{base:x}"));
+                b.prop_string("compatible", "google,goldfish-rtc");
+                b.prop_reg_2_2(base, size);
+                prop_plic_irq(&mut b, dev.get_irq_id());
+                b.end_node();
+            }
+            "DMA" => {
+                b.begin_node(&format!("dma@{base:x}"));
+                b.prop_string("compatible", "rvsim,dma");
+                b.prop_reg_2_2(base, size);
+                prop_plic_irq(&mut b, dev.get_irq_id());
+                b.end_node();
+            }
+            "SysCon" => {
+                b.begin_node(&format!("syscon@{base:x}"));
+                b.prop_string("compatible", "syscon");
+                b.prop_reg_2_2(base, size);
+                b.prop_u32("phandle", SYSCON_PHANDLE);
+                b.end_node();
+
+                // Writes 0x5555 to syscon offset 0x00
+                b.begin_node("poweroff");
+                b.prop_string("compatible", "syscon-poweroff");
+                b.prop_u32("regmap", SYSCON_PHANDLE);
+                b.prop_u32("offset", 0x00);
+                b.prop_u32("value", 0x5555);
+                b.end_node();
+
+                // Writes 0x7777 to syscon offset 0x00
+                b.begin_node("reboot");
+                b.prop_string("compatible", "syscon-reboot");
+                b.prop_u32("regmap", SYSCON_PHANDLE);
+                b.prop_u32("offset", 0x00);
+                b.prop_u32("value", 0x7777);
+                b.end_node();
+            }
+            // RAM is described by /memory; HTIF is discovered via the ELF
+            // `tohost` symbol rather than the device tree.
+            _ => {}
+        }
     }
 
     b.end_node(); // soc
@@ -0,0 +1,136 @@
+//! Fast-forwarding and switching between functional and detailed simulation.
+//!
+//! Sampled simulation (SimPoint-style) skips to a region of interest with a
+//! fast functional model and simulates only that region in detail. Here the
+//! functional model is [`Cpu::step_functional`]: one instruction per cycle,
+//! no pipeline, with the TLBs and optionally the caches kept warm. It
+//! produces the same architectural state the pipeline would, so detailed
+//! simulation can take over at any instruction boundary.
+//!
+//! A run either fast-forwards from the configuration
+//! (`general.fast_forward_insts` / `general.switch_pc`, taken by
+//! [`Simulator::tick`]) or is driven explicitly with
+//! [`Simulator::run_functional`] and [`Simulator::run_detailed`].
+
+use super::simulator::Simulator;
+use crate::common::SimError;
+use crate::config::GeneralConfig;
+use crate::core::Cpu;
+use crate::core::pipeline::engine::PipelineDispatch;
+
+/// Fast-forward requested by the configuration, ended by whichever switch
+/// point hart 0 reaches first.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct FastForward {
+    /// Instructions to retire before switching; 0 for no limit.
+    insts: u64,
+    /// PC to switch at.
+    switch_pc: Option<u64>,
+}
+
+impl FastForward {
+    /// Returns the fast-forward `config` asks for, if any.
+    pub(super) const fn from_config(config: &GeneralConfig) -> Option<Self> {
+        if config.fast_forward_insts == 0 && config.switch_pc.is_none() {
+            return None;
+        }
+        Some(Self { insts: config.fast_forward_insts, switch_pc: config.switch_pc })
+    }
+
+    /// Returns `true` once hart 0 (`cpu`) is at a switch point.
+    pub(super) fn reached(&self, cpu: &Cpu) -> bool {
+        (self.insts != 0 && cpu.stats.instructions_retired >= self.insts)
+            || self.switch_pc == Some(cpu.pc)
+            || cpu.exit_code.is_some()
+    }
+}
+
+impl Simulator {
+    /// Executes `n` instructions on hart 0 functionally, without the
+    /// pipeline, and returns how many retired.
+    ///
+    /// The other harts advance one instruction per cycle alongside. Stops
+    /// early if a hart exits or the guest reboots. In-flight instructions
+    /// of an earlier detailed run are drained first, and afterwards the
+    /// pipelines are empty and start at the next instruction, so
+    /// [`Self::run_detailed`] or [`Self::tick`] continue in detail. Any
+    /// fast-forward pending from the configuration is dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns the watchdog and reboot limit errors of [`Self::tick`].
+    pub fn run_functional(&mut self, n: u64) -> Result<u64, SimError> {
+        if !self.boot_states.is_empty() {
+            for (cpu, pipeline) in self.harts_mut() {
+                pipeline.quiesce(cpu);
+            }
+        }
+        self.capture_boot_states();
+        self.fast_forward = None;
+        let start = self.cpu.stats.instructions_retired;
+        let boots = self.boots;
+        let mut result = Ok(());
+        while self.cpu.stats.instructions_retired.saturating_sub(start) < n
+            && !self.stopped(boots)
+            && result.is_ok()
+        {
+            result = self.tick_harts(true);
+        }
+        self.enter_detailed();
+        result.map(|()| self.cpu.stats.instructions_retired.saturating_sub(start))
+    }
+
+    /// Simulates hart 0 in detail until `n` more instructions have retired
+    /// and returns how many did.
+    ///
+    /// Ends any fast-forward pending from the configuration first. Stops
+    /// early if a hart exits or the guest reboots. Afterwards the pipelines
+    /// are drained, so the architectural state is exactly that after the
+    /// last retired instruction.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`Self::tick`].
+    pub fn run_detailed(&mut self, n: u64) -> Result<u64, SimError> {
+        if self.fast_forward.take().is_some() {
+            self.enter_detailed();
+        }
+        let start = self.cpu.stats.instructions_retired;
+        let boots = self.boots;
+        self.cpu.retire_limit = Some(start.saturating_add(n));
+        let mut result = Ok(());
+        while self.cpu.stats.instructions_retired.saturating_sub(start) < n
+            && !self.stopped(boots)
+            && result.is_ok()
+        {
+            result = self.tick();
+        }
+        self.cpu.retire_limit = None;
+        if !self.boot_states.is_empty() {
+            for (cpu, pipeline) in self.harts_mut() {
+                pipeline.quiesce(cpu);
+            }
+        }
+        result.map(|()| self.cpu.stats.instructions_retired.saturating_sub(start))
+    }
+
+    /// Returns `true` if a hart has exited or the guest rebooted since boot
+    /// `boots`.
+    fn stopped(&self, boots: u64) -> bool {
+        self.boots != boots
+            || self.cpu.exit_code.is_some()
+            || self.secondary_harts.iter().any(|h| h.cpu.exit_code.is_some())
+    }
+
+    /// Points every pipeline at its hart's next instruction, so detailed
+    /// simulation resumes where functional execution stopped.
+    pub(super) fn enter_detailed(&mut self) {
+        for (cpu, pipeline) in self.harts_mut() {
+            pipeline.flush(cpu);
+            cpu.committed_next_pc = cpu.pc;
+            if let PipelineDispatch::OutOfOrder(p) = pipeline {
+                p.engine.sync_arch_regs(cpu);
+            }
+        }
+    }
+}
@@ -12,8 +12,11 @@ use crate::core::arch::csr;
 use crate::core::arch::mode::PrivilegeMode;
 use crate::isa::abi;
 use crate::isa::privileged::opcodes as sys_ops;
+use crate::sim::symbols::SymbolTable;
 use crate::soc::interconnect::Bus;
-use object::{Object, ObjectSymbol};
+use object::elf;
+use object::read::elf::{ElfFile64, FileHeader, ProgramHeader};
+use object::{Endianness, FileKind, Object};
 use std::fs;
 
 /// Loads a binary file from disk into a byte vector.
@@ -63,8 +66,8 @@ pub fn setup_kernel_load(
         let dtb_data = load_binary(&path)?;
         cpu.bus.load_binary_at(&dtb_data, PhysAddr::new(dtb_addr));
     } else {
-        // Generate DTB from SoC config when no external DTB is provided.
-        let dtb_data = crate::sim::dtb::generate_dtb(config);
+        // Generate DTB from the bus's devices when no external DTB is provided.
+        let dtb_data = crate::sim::dtb::generate_dtb(config, &cpu.bus.bus);
         cpu.bus.load_binary_at(&dtb_data, PhysAddr::new(dtb_addr));
     }
 
@@ -147,51 +150,97 @@ pub struct ElfLoadResult {
     pub entry: u64,
     /// Address of the `tohost` symbol, if present.
     pub tohost_addr: Option<u64>,
+    /// Named symbols of the image.
+    pub symbols: SymbolTable,
+    /// End of the highest loaded segment (including its BSS).
+    pub image_end: u64,
+    /// Address and count of the program headers, if a segment loads them.
+    pub phdrs: Option<(u64, u64)>,
 }
 
 /// Attempts to load an ELF file into memory via the bus.
 ///
-/// If the file starts with the ELF magic (`\x7fELF`), parses the ELF,
-/// loads all `PT_LOAD` segments, and extracts the `tohost` symbol address.
-/// Returns `None` if the data is not a valid ELF.
-pub fn try_load_elf(data: &[u8], bus: &mut Bus) -> Option<ElfLoadResult> {
-    if data.len() < 4 || &data[..4] != b"\x7fELF" {
-        return None;
+/// If the file starts with the ELF magic (`\x7fELF`), checks that it is a
+/// 64-bit RISC-V image, copies every `PT_LOAD` segment to its physical
+/// address (`p_paddr`), zero-fills the rest of the segment up to `p_memsz`
+/// (the BSS), and collects the symbol table.
+///
+/// # Errors
+///
+/// Returns `Ok(None)` if `data` is not an ELF file, and
+/// [`SimError::InvalidElf`] if it is one that cannot be loaded: malformed,
+/// 32-bit, built for another architecture, or with a segment outside the
+/// memory map.
+pub fn try_load_elf(data: &[u8], bus: &mut Bus) -> Result<Option<ElfLoadResult>, SimError> {
+    if !data.starts_with(b"\x7fELF") {
+        return Ok(None);
     }
+    let invalid = |reason: String| SimError::InvalidElf { reason };
 
-    let file = object::File::parse(data).ok()?;
-    let entry = file.entry();
+    match FileKind::parse(data) {
+        Ok(FileKind::Elf64) => {}
+        Ok(FileKind::Elf32) => return Err(invalid("32-bit ELF; expected ELF64 for RV64".into())),
+        Ok(kind) => return Err(invalid(format!("unsupported file kind {kind:?}"))),
+        Err(e) => return Err(invalid(e.to_string())),
+    }
+    let file = ElfFile64::<Endianness>::parse(data).map_err(|e| invalid(e.to_string()))?;
+    let endian = file.endian();
+    let machine = file.elf_header().e_machine(endian);
+    if machine != elf::EM_RISCV {
+        return Err(invalid(format!(
+            "built for {:?} (e_machine {machine}); expected RISC-V (e_machine {})",
+            file.architecture(),
+            elf::EM_RISCV
+        )));
+    }
 
-    // Load ELF segments into memory
-    for segment in file.segments() {
-        use object::ObjectSegment;
-        // We only care about loadable segments (PT_LOAD flags)
-        let p_memsz = segment.size();
-        if p_memsz == 0 {
+    let phoff = file.elf_header().e_phoff(endian);
+    let phnum = file.elf_program_headers().len() as u64;
+    let mut image_end = 0;
+    let mut phdrs = None;
+    for phdr in file.elf_program_headers() {
+        if phdr.p_type(endian) != elf::PT_LOAD {
             continue;
         }
-        let paddr = segment.address();
-        if let Ok(seg_data) = segment.data() {
-            if !seg_data.is_empty() {
-                bus.load_binary_at(seg_data, PhysAddr::new(paddr));
-            }
-            // Zero-fill the BSS gap (p_memsz > p_filesz)
-            let p_filesz = seg_data.len() as u64;
-            if p_memsz > p_filesz {
-                let bss_start = paddr + p_filesz;
-                let bss_size = (p_memsz - p_filesz) as usize;
-                bus.load_binary_at(&vec![0u8; bss_size], PhysAddr::new(bss_start));
-            }
-        } else if p_memsz > 0 {
-            // No file data but memsz > 0: zero-fill the entire region
-            bus.load_binary_at(&vec![0u8; p_memsz as usize], PhysAddr::new(paddr));
+        let paddr = phdr.p_paddr(endian);
+        let memsz = phdr.p_memsz(endian);
+        if memsz == 0 {
+            continue;
+        }
+        let offset = phdr.p_offset(endian);
+        if phoff >= offset && phoff - offset < phdr.p_filesz(endian) {
+            phdrs = Some((paddr + (phoff - offset), phnum));
+        }
+        let bytes = phdr.data(endian, data).map_err(|()| {
+            invalid(format!("segment at {paddr:#x} runs past the end of the file"))
+        })?;
+        let filesz = bytes.len() as u64;
+        if filesz > memsz {
+            return Err(invalid(format!("segment at {paddr:#x} has p_filesz > p_memsz")));
+        }
+        let mapped = paddr.checked_add(memsz).is_some_and(|end| {
+            bus.is_valid_address(PhysAddr::new(paddr))
+                && bus.is_valid_address(PhysAddr::new(end - 1))
+        });
+        if !mapped {
+            return Err(invalid(format!(
+                "segment at {paddr:#x} ({memsz:#x} bytes) is outside the memory map"
+            )));
+        }
+        image_end = image_end.max(paddr + memsz);
+        bus.load_binary_at(bytes, PhysAddr::new(paddr));
+        // Zero-fill the BSS gap (p_memsz > p_filesz)
+        if memsz > filesz {
+            bus.load_binary_at(
+                &vec![0u8; (memsz - filesz) as usize],
+                PhysAddr::new(paddr + filesz),
+            );
         }
     }
 
-    // Find tohost symbol
-    let tohost_addr = file.symbols().find(|s| s.name() == Ok("tohost")).map(|s| s.address());
-
-    Some(ElfLoadResult { entry, tohost_addr })
+    let symbols = SymbolTable::from_elf(&file);
+    let tohost_addr = symbols.lookup("tohost");
+    Ok(Some(ElfLoadResult { entry: file.entry(), tohost_addr, symbols, image_end, phdrs }))
 }
 
 #[cfg(test)]
@@ -205,7 +254,7 @@ mod tests {
     fn test_try_load_elf_invalid() {
         let mut bus = Bus::new(8, 0);
         let data = b"NOT AN ELF FILE";
-        let result = try_load_elf(data, &mut bus);
+        let result = try_load_elf(data, &mut bus).unwrap();
         assert!(result.is_none());
     }
 
@@ -213,7 +262,7 @@ mod tests {
     fn test_try_load_elf_too_short() {
         let mut bus = Bus::new(8, 0);
         let data = b"EL";
-        let result = try_load_elf(data, &mut bus);
+        let result = try_load_elf(data, &mut bus).unwrap();
         assert!(result.is_none());
     }
 
@@ -4,6 +4,9 @@
 //! the initial system state, and the `Simulator` struct that owns
 //! both the CPU and the pipeline.
 
+pub mod checkpoint;
 pub mod dtb;
+pub mod functional;
 pub mod loader;
 pub mod simulator;
+pub mod symbols;
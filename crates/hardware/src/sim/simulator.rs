@@ -2,45 +2,227 @@
 //!
 //! This avoids the borrow-splitting hack where the pipeline was stored as
 //! `Option<PipelineDispatch>` inside `Cpu` and temporarily `take()`-en each tick.
+//!
+//! Multi-hart systems keep hart 0 in `cpu`/`pipeline` and the remaining harts
+//! in `secondary_harts`. All harts are ticked round-robin each cycle and share
+//! one `System`: it lives in hart 0's `Cpu` and is swapped into a secondary
+//! hart for the duration of that hart's tick.
+//!
+//! Each hart's state at the first tick is kept as its boot state. A reboot
+//! command written to the `SysCon` device restores it on every hart and
+//! flushes the pipelines; memory is left as it is. The reboot is also
+//! reported through [`Simulator::take_system_event`], so a run loop that
+//! loaded a kernel can load it again before the next boot runs.
+//!
+//! With `general.fast_forward_insts` or `general.switch_pc` set, the first
+//! ticks execute instructions functionally (see [`crate::sim::functional`])
+//! and detailed simulation starts at the switch point.
 
-use crate::common::SimError;
-use crate::config::Config;
+use crate::common::{RegIdx, RegisterFile, SimError};
+use crate::config::{Config, RebootStats};
 use crate::core::Cpu;
+use crate::core::arch::csr::Csrs;
+use crate::core::arch::mode::PrivilegeMode;
 use crate::core::pipeline::backend::inorder::InOrderEngine;
 use crate::core::pipeline::backend::o3::O3Engine;
 use crate::core::pipeline::engine::{BackendType, Pipeline, PipelineDispatch};
 use crate::core::pipeline::frontend::Frontend;
+use crate::core::units::mmu::pmp::Pmp;
+use crate::sim::functional::FastForward;
+use crate::sim::symbols::SymbolTable;
 use crate::soc::System;
+use crate::soc::devices::SysconEvent;
+use crate::soc::mmio_trace::MmioTraceFile;
+
+/// A secondary hart: its own architectural state and pipeline.
+///
+/// Between ticks `cpu.bus` is a detached placeholder; RAM is shared with
+/// hart 0 through `cpu.ram`.
+#[derive(Debug)]
+pub struct Hart {
+    /// CPU architectural state of this hart.
+    pub cpu: Cpu,
+    /// Pipeline implementation of this hart.
+    pub pipeline: PipelineDispatch,
+}
+
+/// Power event requested by the guest, returned by
+/// [`Simulator::take_system_event`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SystemEvent {
+    /// The simulation finished with this exit code (power off, failure,
+    /// HTIF or an exit syscall).
+    Exit(u64),
+    /// The harts were restarted from their boot state; the payload is the
+    /// number of the boot that is starting (2 for the first reboot).
+    Reboot(u64),
+}
+
+/// Architectural state a hart starts from, restored on reboot.
+#[derive(Clone, Debug)]
+pub(super) struct BootState {
+    /// Entry point.
+    pub(super) pc: u64,
+    /// Privilege mode at entry.
+    pub(super) privilege: PrivilegeMode,
+    /// Registers at entry (e.g. hart ID and device tree pointer).
+    pub(super) regs: RegisterFile,
+    /// CSRs at entry.
+    pub(super) csrs: Csrs,
+    /// PMP entries at entry.
+    pub(super) pmp: Pmp,
+}
+
+impl BootState {
+    /// Captures the current state of `cpu`.
+    fn capture(cpu: &Cpu) -> Self {
+        Self {
+            pc: cpu.pc,
+            privilege: cpu.privilege,
+            regs: cpu.regs.clone(),
+            csrs: cpu.csrs.clone(),
+            pmp: cpu.pmp.clone(),
+        }
+    }
+
+    /// Flushes `pipeline`, resets `cpu` and restores it to this state.
+    fn restore(&self, cpu: &mut Cpu, pipeline: &mut PipelineDispatch) {
+        pipeline.flush(cpu);
+        cpu.reset();
+        cpu.pc = self.pc;
+        cpu.committed_next_pc = self.pc;
+        cpu.privilege = self.privilege;
+        cpu.regs = self.regs.clone();
+        cpu.csrs = self.csrs.clone();
+        cpu.pmp = self.pmp.clone();
+        if let PipelineDispatch::OutOfOrder(p) = pipeline {
+            p.engine.sync_arch_regs(cpu);
+        }
+    }
+}
 
 /// Top-level simulator: CPU architectural state + pipeline.
 #[derive(Debug)]
 pub struct Simulator {
-    /// CPU architectural state (registers, caches, MMU, bus, stats).
+    /// CPU architectural state of hart 0 (registers, caches, MMU, bus, stats).
     pub cpu: Cpu,
-    /// Pipeline implementation (frontend + backend engine).
+    /// Pipeline implementation of hart 0 (frontend + backend engine).
     pub pipeline: PipelineDispatch,
+    /// Harts 1..`num_harts`, ticked after hart 0 each cycle.
+    pub secondary_harts: Vec<Hart>,
+    /// Symbols of the loaded ELF image (empty for other images).
+    pub symbols: SymbolTable,
+    /// Boot state of each hart, captured on the first tick.
+    pub(super) boot_states: Vec<BootState>,
+    /// Boots so far, counting the first.
+    pub(super) boots: u64,
+    /// Reboot not yet taken by [`Self::take_system_event`].
+    pub(super) pending_reboot: bool,
+    /// Reboots allowed before [`SimError::RebootLimitExceeded`].
+    max_reboots: Option<u64>,
+    /// Whether statistics are reset on reboot.
+    reboot_stats: RebootStats,
+    /// Fast-forward still to run before detailed simulation, if any.
+    pub(super) fast_forward: Option<FastForward>,
+    /// Whether functional execution warms the caches.
+    pub(super) warm_caches: bool,
 }
 
-unsafe impl Send for Simulator {}
+// SAFETY: the simulator is only ever driven from one thread at a time; the
+// `Cell`-based predictor caches are never shared across threads. RAM access
+// is already thread-safe through `HostRam`.
 unsafe impl Sync for Simulator {}
 
 impl Simulator {
     /// Creates a new simulator with the given system and configuration.
+    ///
+    /// `config.system.num_harts - 1` secondary harts are created alongside
+    /// hart 0. They adopt hart 0's start PC and mode on the first tick.
+    /// When `config.general.pipe_trace` is set, hart 0 writes a pipeline
+    /// visualization trace to that file, and when `config.general.mmio_trace`
+    /// is set, the bus logs every device access to that file. When
+    /// `config.general.access_trace` is set, hart 0 logs its physical memory
+    /// accesses to that file.
     pub fn new(system: System, config: &Config) -> Self {
-        let cpu = Cpu::new(system, config);
-        let pipeline = match config.pipeline.backend {
+        let exit_request = system.exit_request.clone();
+        let mut cpu = Cpu::new(system, config);
+        if let Some(path) = &config.general.pipe_trace
+            && let Err(e) = cpu.open_pipe_trace(path, config.general.pipe_trace_format)
+        {
+            eprintln!("warning: pipeline trace disabled: {e}");
+        }
+        if let Some(path) = &config.general.access_trace
+            && let Err(e) = cpu.open_access_trace(
+                path,
+                config.general.access_trace_format,
+                config.general.access_trace_sample,
+            )
+        {
+            eprintln!("warning: access trace disabled: {e}");
+        }
+        if let Some(path) = &config.general.mmio_trace {
+            match MmioTraceFile::create(path) {
+                Ok(trace) => cpu.bus.bus.set_mmio_trace(Some(Box::new(trace))),
+                Err(e) => eprintln!("warning: MMIO trace disabled: {e}"),
+            }
+        }
+        let secondary_harts = (1..config.system.num_harts.max(1))
+            .map(|hart_id| {
+                let mut hart_cpu = Cpu::new(System::detached(exit_request.clone()), config);
+                hart_cpu.hart_id = hart_id;
+                hart_cpu.ram.clone_from(&cpu.ram);
+                Hart { cpu: hart_cpu, pipeline: Self::build_pipeline(config) }
+            })
+            .collect();
+        Self {
+            cpu,
+            pipeline: Self::build_pipeline(config),
+            secondary_harts,
+            symbols: SymbolTable::default(),
+            boot_states: Vec::new(),
+            boots: 1,
+            pending_reboot: false,
+            max_reboots: config.general.max_reboots,
+            reboot_stats: config.general.reboot_stats,
+            fast_forward: FastForward::from_config(&config.general),
+            warm_caches: config.general.fast_forward_warm,
+        }
+    }
+
+    /// Builds the pipeline selected by `config.pipeline.backend`.
+    fn build_pipeline(config: &Config) -> PipelineDispatch {
+        match config.pipeline.backend {
             BackendType::InOrder => PipelineDispatch::InOrder(Box::new(Pipeline {
-                frontend: Frontend::new(config.pipeline.width),
+                frontend: Frontend::new(config),
                 engine: InOrderEngine::new(config),
                 rename_output: Vec::with_capacity(config.pipeline.width),
             })),
             BackendType::OutOfOrder => PipelineDispatch::OutOfOrder(Box::new(Pipeline {
-                frontend: Frontend::new(config.pipeline.width),
+                frontend: Frontend::new(config),
                 engine: O3Engine::new(config),
                 rename_output: Vec::with_capacity(config.pipeline.width),
             })),
-        };
-        Self { cpu, pipeline }
+        }
+    }
+
+    /// Returns every hart's CPU and pipeline, hart 0 first.
+    pub(super) fn harts_mut(&mut self) -> impl Iterator<Item = (&mut Cpu, &mut PipelineDispatch)> {
+        std::iter::once((&mut self.cpu, &mut self.pipeline))
+            .chain(self.secondary_harts.iter_mut().map(|h| (&mut h.cpu, &mut h.pipeline)))
+    }
+
+    /// Returns the number of harts in the system.
+    pub const fn num_harts(&self) -> usize {
+        self.secondary_harts.len() + 1
+    }
+
+    /// Returns the architectural state of hart `hart_id`, if it exists.
+    pub fn hart(&self, hart_id: usize) -> Option<&Cpu> {
+        if hart_id == 0 {
+            Some(&self.cpu)
+        } else {
+            self.secondary_harts.get(hart_id - 1).map(|h| &h.cpu)
+        }
     }
 
     /// Synchronize the architectural register file into the O3 PRF.
@@ -53,26 +235,318 @@ impl Simulator {
         }
     }
 
+    /// Writes integer register `reg` of the primary hart from outside the pipeline.
+    ///
+    /// Updates both the architectural file and, for the O3 backend, the
+    /// committed physical register. Writes to x0 are ignored.
+    pub fn write_reg(&mut self, reg: RegIdx, val: u64) {
+        self.cpu.regs.write(reg, val);
+        if let PipelineDispatch::OutOfOrder(ref mut p) = self.pipeline {
+            p.engine.write_arch_reg(reg, false, val);
+        }
+    }
+
+    /// Writes floating-point register `reg` (raw bits) of the primary hart from
+    /// outside the pipeline. See [`Self::write_reg`].
+    pub fn write_freg(&mut self, reg: RegIdx, val: u64) {
+        self.cpu.regs.write_f(reg, val);
+        if let PipelineDispatch::OutOfOrder(ref mut p) = self.pipeline {
+            p.engine.write_arch_reg(reg, true, val);
+        }
+    }
+
+    /// Arms (or with `None`, disarms) a breakpoint on the primary hart.
+    ///
+    /// Commit stops just before the instruction at `pc` retires; poll
+    /// [`Self::take_breakpoint_hit`] after each tick. If `pc` is already the
+    /// next instruction to commit it retires once first, so re-arming the
+    /// same breakpoint after a hit makes progress.
+    pub fn set_breakpoint(&mut self, pc: Option<u64>) {
+        self.cpu.break_pc = pc;
+        self.cpu.break_skip = pc == Some(self.cpu.committed_next_pc);
+        self.cpu.break_hit = false;
+    }
+
+    /// Returns `true` if the armed breakpoint was reached, then disarms it.
+    ///
+    /// On a hit every older instruction has retired and the pipeline is
+    /// flushed, so `cpu.pc` is the breakpoint address and fetch resumes there.
+    /// The one exception is a breakpoint on the second instruction of a
+    /// macro-op pair fused before it was armed: the pair retires as a unit,
+    /// so the run stops just after it.
+    pub fn take_breakpoint_hit(&mut self) -> bool {
+        if !self.cpu.break_hit {
+            return false;
+        }
+        self.set_breakpoint(None);
+        self.pipeline.flush(&mut self.cpu);
+        self.cpu.pc = self.cpu.committed_next_pc;
+        true
+    }
+
     /// Advances the simulator by one clock cycle.
     ///
+    /// A reboot written to the `SysCon` device during the cycle takes effect
+    /// at its end (see [`Self::reboot`]).
+    ///
     /// # Errors
     ///
-    /// Returns [`SimError::HangDetected`] if the PC has not advanced for too many
-    /// consecutive cycles (and is not stuck in a WFI spin-wait).
+    /// Returns [`SimError::HangDetected`] if the PC has not changed for
+    /// `general.hang_threshold` consecutive cycles outside WFI,
+    /// [`SimError::LivelockDetected`] if the committed instructions have
+    /// looped over a few PCs without progress for as long, and
+    /// [`SimError::CycleLimitExceeded`] once `general.max_cycles` have been
+    /// simulated. All carry a diagnostic dump of the hart.
+    ///
+    /// Returns [`SimError::RebootLimitExceeded`] if the guest asks for more
+    /// than `general.max_reboots` reboots; the harts are left as they were.
     ///
     /// Returns [`SimError::KernelPanic`] if the guest OS panic sentinel fires.
     pub fn tick(&mut self) -> Result<(), SimError> {
-        let prev_priv = self.cpu.privilege;
-        let skip = self.cpu.pre_tick()?;
+        self.capture_boot_states();
+        if let Some(ff) = self.fast_forward {
+            if !ff.reached(&self.cpu) {
+                return self.tick_harts(true);
+            }
+            self.fast_forward = None;
+            self.enter_detailed();
+        }
+        self.tick_harts(false)
+    }
+
+    /// Captures every hart's boot state, once, before the first cycle.
+    pub(super) fn capture_boot_states(&mut self) {
+        if self.boot_states.is_empty() {
+            self.release_secondary_harts();
+            self.boot_states = std::iter::once(&self.cpu)
+                .chain(self.secondary_harts.iter().map(|h| &h.cpu))
+                .map(BootState::capture)
+                .collect();
+        }
+    }
+
+    /// Advances every hart by one cycle, through its pipeline or, with
+    /// `functional` set, by executing an instruction to completion.
+    pub(super) fn tick_harts(&mut self, functional: bool) -> Result<(), SimError> {
+        let warm = self.warm_caches;
+        Self::tick_hart(&mut self.cpu, &mut self.pipeline, functional, warm)?;
+        self.broadcast_stores(0);
+        for i in 0..self.secondary_harts.len() {
+            let hart = &mut self.secondary_harts[i];
+            hart.cpu.htif_range = self.cpu.htif_range;
+            std::mem::swap(&mut self.cpu.bus, &mut hart.cpu.bus);
+            let result = Self::tick_hart(&mut hart.cpu, &mut hart.pipeline, functional, warm);
+            std::mem::swap(&mut self.cpu.bus, &mut hart.cpu.bus);
+            result?;
+            self.broadcast_stores(i + 1);
+        }
+        if self.cpu.bus.bus.take_syscon_event() == Some(SysconEvent::Reboot) {
+            if let Some(limit) = self.max_reboots
+                && self.boots > limit
+            {
+                return Err(SimError::RebootLimitExceeded { reboots: self.boots, limit });
+            }
+            self.reboot();
+        }
+        Ok(())
+    }
+
+    /// Restarts every hart from the state it had on the first tick.
+    ///
+    /// In-flight instructions are discarded; PC, privilege mode, registers
+    /// and CSRs are restored and TLBs and LR/SC reservations cleared.
+    /// Memory and caches are kept; statistics are kept or zeroed as
+    /// `general.reboot_stats` selects. The reboot is reported by the next
+    /// [`Self::take_system_event`]. Does nothing before the first tick.
+    pub fn reboot(&mut self) {
+        if self.boot_states.is_empty() {
+            return;
+        }
+        let reset_stats = self.reboot_stats == RebootStats::Reset;
+        let harts = std::iter::once((&mut self.cpu, &mut self.pipeline))
+            .chain(self.secondary_harts.iter_mut().map(|h| (&mut h.cpu, &mut h.pipeline)));
+        for ((cpu, pipeline), boot) in harts.zip(&self.boot_states) {
+            boot.restore(cpu, pipeline);
+            if reset_stats {
+                cpu.stats.reset_for_boot();
+            }
+        }
+        self.boots += 1;
+        self.pending_reboot = true;
+    }
+
+    /// Resets every hart to its power-on state (see [`Cpu::reset`]), after
+    /// discarding in-flight instructions. Unlike [`Self::reboot`], the harts
+    /// restart from the reset vector with power-on CSRs rather than from the
+    /// state captured on the first tick, and no system event is raised.
+    pub fn reset(&mut self) {
+        let harts = std::iter::once((&mut self.cpu, &mut self.pipeline))
+            .chain(self.secondary_harts.iter_mut().map(|h| (&mut h.cpu, &mut h.pipeline)));
+        for (cpu, pipeline) in harts {
+            pipeline.flush(cpu);
+            cpu.reset();
+            if let PipelineDispatch::OutOfOrder(p) = pipeline {
+                p.engine.sync_arch_regs(cpu);
+            }
+        }
+    }
+
+    /// Returns the number of boots so far, counting the first.
+    pub const fn boots(&self) -> u64 {
+        self.boots
+    }
+
+    /// Invalidates the other harts' LR/SC reservations against the memory
+    /// writes hart `src` made this cycle.
+    fn broadcast_stores(&mut self, src: usize) {
+        if self.secondary_harts.is_empty() {
+            return;
+        }
+        let log = if src == 0 {
+            std::mem::take(&mut self.cpu.store_log)
+        } else {
+            std::mem::take(&mut self.secondary_harts[src - 1].cpu.store_log)
+        };
+        for (hart_id, cpu) in std::iter::once(&mut self.cpu)
+            .chain(self.secondary_harts.iter_mut().map(|h| &mut h.cpu))
+            .enumerate()
+        {
+            if hart_id != src && cpu.load_reservation.is_some() {
+                for &(addr, size) in &log {
+                    cpu.invalidate_reservation(addr, size);
+                }
+            }
+        }
+        let owner = if src == 0 { &mut self.cpu } else { &mut self.secondary_harts[src - 1].cpu };
+        owner.store_log = log;
+    }
+
+    /// Advances a single hart by one clock cycle, functionally (warming the
+    /// caches if `warm` is set) or through its pipeline.
+    fn tick_hart(
+        cpu: &mut Cpu,
+        pipeline: &mut PipelineDispatch,
+        functional: bool,
+        warm: bool,
+    ) -> Result<(), SimError> {
+        let prev_priv = cpu.privilege;
+        let skip = cpu.pre_tick()?;
+        Self::check_watchdog(cpu, pipeline)?;
         if !skip {
-            self.pipeline.tick(&mut self.cpu);
+            if functional {
+                let _ = cpu.step_functional(warm);
+            } else {
+                pipeline.tick(cpu);
+            }
+        }
+        cpu.post_tick(prev_priv);
+        Ok(())
+    }
+
+    /// Fails with a hang report once `cpu` trips `general.hang_threshold`,
+    /// at one PC or in a loop without progress, or `general.max_cycles`.
+    fn check_watchdog(cpu: &mut Cpu, pipeline: &PipelineDispatch) -> Result<(), SimError> {
+        if cpu.hang_threshold.is_some_and(|limit| cpu.same_pc_count >= limit) {
+            return Err(SimError::HangDetected {
+                pc: cpu.pc,
+                inst: cpu.inst_word_at(cpu.pc),
+                cycle_count: cpu.same_pc_count,
+                report: Self::hang_report(cpu, pipeline),
+            });
+        }
+        let in_loop = cpu.loop_detector.cycles_in_loop(cpu.stats.cycles);
+        if cpu.hang_threshold.is_some_and(|limit| in_loop >= limit) {
+            return Err(SimError::LivelockDetected {
+                pcs: cpu.loop_detector.loop_pcs(),
+                cycle_count: in_loop,
+                report: format!(
+                    "loop:\n{}{}",
+                    Self::format_loop(cpu),
+                    Self::hang_report(cpu, pipeline)
+                ),
+            });
+        }
+        if cpu.max_cycles.is_some_and(|limit| cpu.stats.cycles >= limit) {
+            return Err(SimError::CycleLimitExceeded {
+                cycles: cpu.stats.cycles,
+                report: Self::hang_report(cpu, pipeline),
+            });
         }
-        self.cpu.post_tick(prev_priv);
         Ok(())
     }
 
-    /// Retrieves the exit code if the simulation has finished.
-    pub const fn take_exit(&mut self) -> Option<u64> {
-        self.cpu.take_exit()
+    /// Lists the PCs of the loop `cpu`'s livelock detector is tracking, with
+    /// the instruction at each, disassembled.
+    fn format_loop(cpu: &mut Cpu) -> String {
+        use std::fmt::Write;
+
+        let mut out = String::new();
+        for pc in cpu.loop_detector.loop_pcs() {
+            let inst = cpu.inst_word_at(pc);
+            let _ = writeln!(out, "  {pc:#018x}: {}", crate::isa::disasm::disassemble(inst));
+        }
+        out
+    }
+
+    /// Formats the diagnostic dump attached to a watchdog error: privilege
+    /// mode, pending interrupts, the last retired instructions and the ROB
+    /// and store buffer contents.
+    fn hang_report(cpu: &Cpu, pipeline: &PipelineDispatch) -> String {
+        format!(
+            "hart {} in {} mode at cycle {}\n\
+             interrupts: mip {:#x} mie {:#x} pending+enabled {:#x} mstatus {:#x}\n\
+             last {} retired:\n{}{}",
+            cpu.hart_id,
+            cpu.privilege,
+            cpu.stats.cycles,
+            cpu.csrs.mip,
+            cpu.csrs.mie,
+            cpu.csrs.mip & cpu.csrs.mie,
+            cpu.csrs.mstatus,
+            cpu.pc_trace.len(),
+            cpu.format_pc_trace(),
+            pipeline.format_backend()
+        )
+    }
+
+    /// Starts the secondary harts from hart 0's boot state.
+    ///
+    /// Loaders only configure hart 0, so before the first cycle the other
+    /// harts copy its PC, privilege and addressing mode. Following the SBI
+    /// boot convention, `a0` holds the hart ID and `a1` is copied from hart 0
+    /// (the device tree pointer when booting a kernel).
+    fn release_secondary_harts(&mut self) {
+        use crate::isa::abi;
+
+        for hart in &mut self.secondary_harts {
+            let cpu = &mut hart.cpu;
+            cpu.pc = self.cpu.pc;
+            cpu.committed_next_pc = self.cpu.pc;
+            cpu.privilege = self.cpu.privilege;
+            cpu.direct_mode = self.cpu.direct_mode;
+            cpu.regs.write(abi::REG_A0, cpu.hart_id as u64);
+            cpu.regs.write(abi::REG_A1, self.cpu.regs.read(abi::REG_A1));
+            if let PipelineDispatch::OutOfOrder(ref mut p) = hart.pipeline {
+                p.engine.sync_arch_regs(cpu);
+            }
+        }
+    }
+
+    /// Retrieves the exit code if the simulation has finished on any hart.
+    pub fn take_exit(&mut self) -> Option<u64> {
+        self.cpu
+            .take_exit()
+            .or_else(|| self.secondary_harts.iter_mut().find_map(|h| h.cpu.take_exit()))
+    }
+
+    /// Returns and clears the power event raised since the last call: an
+    /// exit (see [`Self::take_exit`]) or a reboot that has already restarted
+    /// the harts. Run loops that loaded a kernel reload it on
+    /// [`SystemEvent::Reboot`] before ticking again.
+    pub fn take_system_event(&mut self) -> Option<SystemEvent> {
+        if let Some(code) = self.take_exit() {
+            return Some(SystemEvent::Exit(code));
+        }
+        std::mem::take(&mut self.pending_reboot).then_some(SystemEvent::Reboot(self.boots))
     }
 }
@@ -0,0 +1,91 @@
+//! ELF Symbol Table.
+//!
+//! Keeps the named symbols of a loaded ELF image so that addresses can be
+//! looked up by name (e.g. `tohost`) and PCs mapped back to the function or
+//! object that contains them.
+
+use std::collections::HashMap;
+
+use object::{Object, ObjectSymbol, SymbolKind};
+
+/// One named symbol.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Symbol {
+    /// Symbol name.
+    pub name: String,
+    /// Symbol value (its address).
+    pub addr: u64,
+    /// Size in bytes (0 if unknown).
+    pub size: u64,
+}
+
+/// Named symbols of a loaded ELF image, sorted by address.
+#[derive(Clone, Debug, Default)]
+pub struct SymbolTable {
+    /// Symbols in ascending address order.
+    symbols: Vec<Symbol>,
+    /// Name → index into `symbols` (the first symbol of that name).
+    by_name: HashMap<String, usize>,
+}
+
+impl SymbolTable {
+    /// Collects the defined, named symbols of `file`, skipping section and
+    /// file symbols.
+    pub fn from_elf<'data>(file: &impl Object<'data>) -> Self {
+        let symbols = file
+            .symbols()
+            .filter(|s| {
+                !s.is_undefined() && !matches!(s.kind(), SymbolKind::Section | SymbolKind::File)
+            })
+            .filter_map(|s| {
+                let name = s.name().ok().filter(|n| !n.is_empty())?;
+                Some(Symbol { name: name.to_owned(), addr: s.address(), size: s.size() })
+            })
+            .collect();
+        Self::new(symbols)
+    }
+
+    /// Builds a table from `symbols`, in any order.
+    pub fn new(mut symbols: Vec<Symbol>) -> Self {
+        symbols.sort_by(|a, b| a.addr.cmp(&b.addr).then_with(|| a.name.cmp(&b.name)));
+        let mut by_name = HashMap::with_capacity(symbols.len());
+        for (i, sym) in symbols.iter().enumerate() {
+            let _ = by_name.entry(sym.name.clone()).or_insert(i);
+        }
+        Self { symbols, by_name }
+    }
+
+    /// Returns the address of the symbol called `name`.
+    pub fn lookup(&self, name: &str) -> Option<u64> {
+        self.by_name.get(name).map(|&i| self.symbols[i].addr)
+    }
+
+    /// Returns the symbol containing `addr` and the offset of `addr` into it.
+    ///
+    /// A sized symbol contains the addresses it covers; a symbol of size 0
+    /// (e.g. an assembly label) is taken to extend up to the next symbol.
+    pub fn symbolize(&self, addr: u64) -> Option<(&Symbol, u64)> {
+        let end = self.symbols.partition_point(|s| s.addr <= addr);
+        let sym =
+            self.symbols[..end].iter().rev().find(|s| s.size == 0 || addr - s.addr < s.size)?;
+        if sym.size == 0 && self.symbols[..end].last().is_some_and(|last| last.addr > sym.addr) {
+            return None;
+        }
+        Some((sym, addr - sym.addr))
+    }
+
+    /// Returns the symbols in ascending address order.
+    pub fn iter(&self) -> impl Iterator<Item = &Symbol> {
+        self.symbols.iter()
+    }
+
+    /// Returns the number of symbols.
+    pub const fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// Returns `true` if the table has no symbols.
+    pub const fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+}
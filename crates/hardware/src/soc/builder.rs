@@ -2,21 +2,32 @@
 //!
 //! This module builds the complete SoC from configuration. It performs:
 //! 1. **Bus setup:** Creates the interconnect with configured width and latency.
-//! 2. **Device registration:** Instantiates RAM, UART, VirtIO disk, CLINT, PLIC, SysCon, and RTC.
+//! 2. **Device registration:** Instantiates RAM (one device per configured bank) and the MMIO
+//!    devices in the configured device list (by default UART, VirtIO disk, VirtIO entropy
+//!    source, CLINT, PLIC, SysCon, RTC, and when configured the VirtIO network device and DMA
+//!    controller), rejecting overlapping address ranges.
 //! 3. **Memory controller:** Selects simple or DRAM controller based on config.
 //! 4. **Binary loading:** Optionally loads a disk image from path and kernel via `load_binary_at`.
 
-use crate::config::{Config, MemoryController as MemControllerType};
-use crate::soc::devices::{Clint, GoldfishRtc, Htif, Plic, SysCon, Uart, VirtioBlock};
+use crate::config::{
+    Config, DeviceKind, MemoryController as MemControllerType, MemoryRegion, RamBacking,
+};
+use crate::soc::devices::{
+    Clint, Device, DmaController, GoldfishRtc, Htif, Plic, SysCon, Uart, VirtioBlock, VirtioNet,
+    VirtioRng,
+};
 use crate::soc::interconnect::Bus;
 use crate::soc::memory::Memory;
 use crate::soc::memory::buffer::DramBuffer;
 use crate::soc::memory::controller::{
     DramConfig, DramController, MemoryController, SimpleController,
 };
+use crate::soc::memory::traffic::MemoryTraffic;
 use std::fs;
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::AtomicU64;
+use std::sync::{Arc, Mutex, PoisonError, Weak};
+use std::time::SystemTime;
 
 /// Top-level system instance containing the bus, memory controller, and exit flag.
 ///
@@ -29,6 +40,8 @@ pub struct System {
     pub mem_controller: Box<dyn MemoryController + Send + Sync>,
     /// Atomic exit code: when not `u64::MAX`, simulation should stop and use this as exit code.
     pub exit_request: Arc<AtomicU64>,
+    /// Ids of DMA requests waiting in the memory controller's queue.
+    pub dma_requests: Vec<u64>,
 }
 
 impl std::fmt::Debug for System {
@@ -43,80 +56,149 @@ impl std::fmt::Debug for System {
 impl System {
     /// Builds a new system from configuration and optional disk image path.
     ///
-    /// Creates the bus, RAM, UART, `VirtIO` disk (loading `disk_path` if non-empty), CLINT, PLIC,
-    /// `SysCon`, and Goldfish RTC. The memory controller is chosen from `config.memory.controller`.
+    /// See [`System::try_new`]; this panics where that returns an error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the memory map or device list is invalid, or if the network
+    /// device's TAP interface cannot be opened.
+    pub fn new(config: &Config, disk_path: &str) -> Self {
+        Self::try_new(config, disk_path).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Builds a new system from configuration and optional disk image path.
+    ///
+    /// Creates the bus with one RAM device per bank, then the MMIO devices listed by
+    /// [`Config::devices`] (by default UART, `VirtIO` disk and entropy device, CLINT, PLIC,
+    /// `SysCon` and Goldfish RTC, plus the `VirtIO` network device and DMA controller when
+    /// configured), and an HTIF device when `config.system.tohost_addr` is set. The `VirtIO`
+    /// disk loads `disk_path` if non-empty. The memory controller is chosen from
+    /// `config.memory.controller`.
     ///
     /// # Arguments
     ///
     /// * `config` - Simulator configuration (system, memory, etc.).
     /// * `disk_path` - Path to disk image file; if non-empty and readable, data is loaded into `VirtIO`.
     ///
-    /// # Returns
+    /// # Errors
     ///
-    /// A fully constructed `System` ready for simulation.
-    pub fn new(config: &Config, disk_path: &str) -> Self {
+    /// Returns a description of the problem if the memory map fails
+    /// [`Config::validate_memory_map`], the device list fails
+    /// [`Config::validate_devices`], the RAM file cannot be mapped, two devices'
+    /// address ranges overlap, or the network device's TAP interface cannot be
+    /// opened.
+    pub fn try_new(config: &Config, disk_path: &str) -> Result<Self, String> {
+        config.validate_memory_map().map_err(|e| format!("invalid memory map: {e}"))?;
+        config.validate_devices().map_err(|e| format!("invalid device list: {e}"))?;
         let mut bus = Bus::new(config.system.bus_width, config.system.bus_latency);
         let exit_request = Arc::new(AtomicU64::new(u64::MAX));
 
-        let ram_base = config.system.ram_base;
-        let ram_size = config.memory.ram_size;
-        let ram_buffer = Arc::new(DramBuffer::new(ram_size));
-        let mem = Memory::new(ram_buffer.clone(), ram_base);
-
-        let uart_base = config.system.uart_base;
-        let uart = Uart::new(uart_base, config.system.uart_to_stderr, config.system.uart_quiet);
-
-        let clint_addr = config.system.clint_base;
-        let clint = Clint::new(clint_addr, config.system.clint_divider);
-
-        let plic_addr = 0x0c00_0000;
-        let plic = Plic::new(plic_addr);
+        // One RAM device per bank; DMA-capable devices work in the boot bank
+        // (the one holding `ram_base`).
+        let regions = config.ram_regions();
+        let buffers = ram_buffers(config, &regions)?;
+        let boot_bank = regions
+            .iter()
+            .position(|r| (r.base..r.end()).contains(&config.system.ram_base))
+            .unwrap_or(0);
+        let ram_base = regions[boot_bank].base;
+        let ram_buffer = buffers[boot_bank].clone();
+        bus.set_boot_address(config.system.ram_base);
 
-        let disk_base = config.system.disk_base;
-        let mut disk = VirtioBlock::new(disk_base, ram_base, ram_buffer);
-        if !disk_path.is_empty()
-            && let Ok(disk_data) = fs::read(disk_path)
-            && !disk_data.is_empty()
-        {
-            disk.load(disk_data);
+        for (region, buffer) in regions.iter().zip(buffers) {
+            bus.add_device(Box::new(Memory::new(buffer, region.base)));
         }
 
-        let syscon_addr = config.system.syscon_base;
-        let syscon = SysCon::new(syscon_addr, exit_request.clone());
-
-        let rtc = GoldfishRtc::new(0x101000);
-
-        bus.add_device(Box::new(mem));
-        bus.add_device(Box::new(uart));
-        bus.add_device(Box::new(disk));
-        bus.add_device(Box::new(clint));
-        bus.add_device(Box::new(plic));
-        bus.add_device(Box::new(syscon));
-        bus.add_device(Box::new(rtc));
+        let mut uarts = 0;
+        for dev in config.devices() {
+            let base = dev.base;
+            let irq = dev.irq().unwrap_or_default();
+            let device: Box<dyn Device + Send + Sync> = match dev.kind {
+                DeviceKind::Uart { to_stderr, quiet } => {
+                    let mut uart = Uart::with_index(base, uarts, to_stderr, quiet);
+                    uart.set_irq(irq);
+                    uarts += 1;
+                    Box::new(uart)
+                }
+                DeviceKind::VirtioBlock { write_cache_sectors, write_latency, copy_on_write } => {
+                    let mut disk = VirtioBlock::new(base, ram_base, ram_buffer.clone());
+                    disk.set_irq(irq);
+                    disk.set_write_cache(write_cache_sectors, write_latency);
+                    if copy_on_write {
+                        disk.set_copy_on_write(true);
+                        if let Some(image) = shared_disk_image(disk_path) {
+                            disk.load_shared(image);
+                        }
+                    } else if !disk_path.is_empty()
+                        && let Ok(disk_data) = fs::read(disk_path)
+                        && !disk_data.is_empty()
+                    {
+                        disk.load(disk_data);
+                    }
+                    Box::new(disk)
+                }
+                DeviceKind::VirtioNet { backend } => {
+                    let mut net = VirtioNet::new(base, ram_base, ram_buffer.clone(), &backend)
+                        .map_err(|e| format!("cannot open network backend {backend:?}: {e}"))?;
+                    net.set_irq(irq);
+                    Box::new(net)
+                }
+                DeviceKind::VirtioRng { seed } => {
+                    let mut rng = VirtioRng::new(base, ram_base, ram_buffer.clone(), seed);
+                    rng.set_irq(irq);
+                    Box::new(rng)
+                }
+                DeviceKind::Clint => Box::new(Clint::with_harts(
+                    base,
+                    config.system.clint_divider,
+                    config.system.num_harts,
+                )),
+                DeviceKind::Plic => Box::new(Plic::with_harts(base, config.system.num_harts)),
+                DeviceKind::SysCon => Box::new(SysCon::new(base, exit_request.clone())),
+                DeviceKind::GoldfishRtc => {
+                    let mut rtc = GoldfishRtc::new(base);
+                    rtc.set_irq(irq);
+                    Box::new(rtc)
+                }
+                DeviceKind::Dma { bytes_per_cycle } => {
+                    let mut dma =
+                        DmaController::new(base, ram_base, ram_buffer.clone(), bytes_per_cycle);
+                    dma.set_irq(irq);
+                    Box::new(dma)
+                }
+            };
+            bus.try_add_device(device)?;
+        }
 
         if config.system.tohost_addr != 0 {
-            let htif = Htif::new(config.system.tohost_addr, exit_request.clone());
-            bus.add_device(Box::new(htif));
+            let htif = new_htif(&mut bus, config.system.tohost_addr, &exit_request, config);
+            bus.try_add_device(Box::new(htif))?;
         }
 
         let mem_controller: Box<dyn MemoryController + Send + Sync> = match config.memory.controller
         {
             MemControllerType::Dram => Box::new(DramController::new(DramConfig {
                 t_cas: config.memory.t_cas,
+                t_rcd: config.memory.t_rcd,
                 t_ras: config.memory.t_ras,
                 t_pre: config.memory.t_pre,
                 t_rrd: config.memory.t_rrd,
+                num_channels: config.memory.num_channels.max(1),
                 num_banks: config.memory.num_banks,
+                num_bank_groups: config.memory.num_bank_groups,
                 row_size_bytes: config.memory.row_size_bytes,
                 t_refi: config.memory.t_refi,
                 t_rfc: config.memory.t_rfc,
+                scheduler: config.memory.scheduler,
+                starvation_limit: config.memory.starvation_limit,
+                write_drain_threshold: config.memory.write_drain_threshold,
             })),
             MemControllerType::Simple => {
                 Box::new(SimpleController::new(config.memory.row_miss_latency))
             }
         };
 
-        Self { bus, mem_controller, exit_request }
+        Ok(Self { bus, mem_controller, exit_request, dma_requests: Vec::new() })
     }
 
     /// Loads a binary into memory at the given physical address.
@@ -129,6 +211,19 @@ impl System {
         self.bus.load_binary_at(data, addr);
     }
 
+    /// Creates a detached system with an empty bus that shares `exit_request`.
+    ///
+    /// Secondary harts hold one of these while idle; the simulator swaps in
+    /// the real system for the duration of each hart's tick.
+    pub(crate) fn detached(exit_request: Arc<AtomicU64>) -> Self {
+        Self {
+            bus: Bus::new(8, 0),
+            mem_controller: Box::new(SimpleController::new(0)),
+            exit_request,
+            dma_requests: Vec::new(),
+        }
+    }
+
     /// Advances all devices by one tick; returns (`timer_irq`, `msip`, `meip`, `seip`).
     ///
     /// # Returns
@@ -138,6 +233,57 @@ impl System {
         self.bus.tick()
     }
 
+    /// Passes the DMA controller's memory traffic from this cycle to the memory
+    /// controller, so its progress waits on DRAM latency and contends with
+    /// the CPU's accesses.
+    ///
+    /// # Arguments
+    ///
+    /// * `cycle` - Current cycle.
+    /// * `traffic` - Traffic counters charged with the DMA requests.
+    pub fn service_dma(&mut self, cycle: u64, traffic: &mut MemoryTraffic) {
+        let Some(dma) = self.bus.dma_mut() else { return };
+        if dma.memory_requests().is_empty() {
+            return;
+        }
+        let mut latency = 0;
+        let queued = self.dma_requests.len();
+        for (i, &(addr, bytes)) in dma.memory_requests().iter().enumerate() {
+            // Each chunk issues its read, then its write.
+            let is_write = i % 2 == 1;
+            match self.mem_controller.enqueue(addr, bytes, is_write, cycle) {
+                Some(id) => self.dma_requests.push(id),
+                None => {
+                    latency = latency.max(self.mem_controller.request(addr, bytes, cycle, traffic));
+                }
+            }
+        }
+        dma.hold_requests(self.dma_requests.len() - queued);
+        dma.complete_requests(latency);
+    }
+
+    /// Advances the memory controller's request scheduler by one cycle and
+    /// hands the DMA controller the completion times of its queued requests.
+    ///
+    /// # Arguments
+    ///
+    /// * `cycle` - Current cycle.
+    /// * `traffic` - Traffic counters charged with the issued requests.
+    pub fn tick_memory(&mut self, cycle: u64, traffic: &mut MemoryTraffic) {
+        self.mem_controller.tick(cycle, traffic);
+        if self.dma_requests.is_empty() {
+            return;
+        }
+        let Some(dma) = self.bus.dma_mut() else { return };
+        let controller = &mut self.mem_controller;
+        self.dma_requests.retain(|&id| {
+            controller.take_completion(id).is_none_or(|done| {
+                dma.release_request(done.saturating_sub(cycle));
+                false
+            })
+        });
+    }
+
     /// Returns the requested exit code if a device has requested shutdown.
     ///
     /// # Returns
@@ -160,9 +306,83 @@ impl System {
     /// Registers an HTIF device at the given tohost address.
     ///
     /// Called after ELF loading discovers a `tohost` symbol. The device shares
-    /// the same `exit_request` atomic so the simulation loop picks up the exit.
-    pub fn add_htif(&mut self, tohost_addr: u64) {
-        let htif = Htif::new(tohost_addr, self.exit_request.clone());
+    /// the same `exit_request` atomic so the simulation loop picks up the exit,
+    /// and its console follows the UART settings in `config`.
+    pub fn add_htif(&mut self, tohost_addr: u64, config: &Config) {
+        let htif = new_htif(&mut self.bus, tohost_addr, &self.exit_request, config);
         self.bus.add_device(Box::new(htif));
     }
 }
+
+/// Alignment of each bank's offset within a RAM file; a multiple of every
+/// common host page size, as `mmap` requires.
+const RAM_FILE_BANK_ALIGN: u64 = 64 * 1024;
+
+/// Allocates the storage for each RAM bank with `config.memory.ram_backing`.
+///
+/// A RAM file holds the banks in address-map order, each at the next
+/// [`RAM_FILE_BANK_ALIGN`]-aligned offset.
+fn ram_buffers(config: &Config, regions: &[MemoryRegion]) -> Result<Vec<Arc<DramBuffer>>, String> {
+    match (config.memory.ram_backing, &config.memory.ram_file) {
+        (RamBacking::Anonymous, _) => {
+            Ok(regions.iter().map(|r| Arc::new(DramBuffer::new(r.size))).collect())
+        }
+        (RamBacking::Heap, _) => {
+            Ok(regions.iter().map(|r| Arc::new(DramBuffer::heap(r.size))).collect())
+        }
+        (RamBacking::File, Some(path)) => {
+            let mut offset = 0;
+            regions
+                .iter()
+                .map(|r| {
+                    let buffer = DramBuffer::from_file(Path::new(path), offset, r.size)
+                        .map_err(|e| format!("cannot map RAM file {path}: {e}"))?;
+                    offset = (offset + r.size as u64).next_multiple_of(RAM_FILE_BANK_ALIGN);
+                    Ok(Arc::new(buffer))
+                })
+                .collect()
+        }
+        (RamBacking::File, None) => Err("ram_backing File needs a ram_file".to_string()),
+    }
+}
+
+/// Creates the HTIF device at `tohost_addr`, with access to the bus's RAM
+/// banks and its console output routed like the UART's.
+fn new_htif(
+    bus: &mut Bus,
+    tohost_addr: u64,
+    exit_request: &Arc<AtomicU64>,
+    config: &Config,
+) -> Htif {
+    let mut htif = Htif::new(tohost_addr, exit_request.clone());
+    htif.set_ram(bus.ram_buffers());
+    htif.set_console(config.system.uart_to_stderr, config.system.uart_quiet);
+    htif
+}
+
+/// Reads a disk image for copy-on-write use, sharing one copy between every
+/// system in the process that opens the same unmodified file.
+///
+/// Returns `None` if `path` is empty, unreadable or an empty file.
+fn shared_disk_image(path: &str) -> Option<Arc<Vec<u8>>> {
+    type Key = (PathBuf, u64, Option<SystemTime>);
+    static IMAGES: Mutex<Vec<(Key, Weak<Vec<u8>>)>> = Mutex::new(Vec::new());
+
+    if path.is_empty() {
+        return None;
+    }
+    let canonical = fs::canonicalize(path).ok()?;
+    let meta = fs::metadata(&canonical).ok()?;
+    let key = (canonical, meta.len(), meta.modified().ok());
+
+    let mut images = IMAGES.lock().unwrap_or_else(PoisonError::into_inner);
+    images.retain(|(_, image)| image.strong_count() > 0);
+    if let Some(image) = images.iter().find(|(k, _)| *k == key).and_then(|(_, w)| w.upgrade()) {
+        return Some(image);
+    }
+    let data = fs::read(&key.0).ok().filter(|d| !d.is_empty())?;
+    let image = Arc::new(data);
+    images.push((key, Arc::downgrade(&image)));
+    drop(images);
+    Some(image)
+}
@@ -6,11 +6,16 @@
 //!
 //! # Memory Map
 //!
-//! * `0x0000`: MSIP (Machine Software Interrupt Pending)
-//! * `0x4000`: MTIMECMP (Machine Time Compare)
-//! * `0xBFF8`: MTIME (Machine Time)
+//! * `0x0000`: MSIP (Machine Software Interrupt Pending), one 32-bit word per hart
+//! * `0x4000`: MTIMECMP (Machine Time Compare), one 64-bit register per hart
+//! * `0xBFF8`: MTIME (Machine Time), shared by all harts
+//!
+//! Writing `msip[h]` is how harts send inter-processor interrupts: bit 0 of
+//! the register is mirrored into hart `h`'s `mip.MSIP` every cycle, so
+//! writing 1 raises a machine software interrupt and writing 0 clears it.
 
 use crate::soc::devices::Device;
+use serde::{Deserialize, Serialize};
 
 /// Offset for the Machine Software Interrupt Pending register.
 const MSIP_OFFSET: u64 = 0x0000;
@@ -26,29 +31,50 @@ pub struct Clint {
     base_addr: u64,
     /// Current machine time counter.
     mtime: u64,
-    /// Machine time compare register.
-    mtimecmp: u64,
-    /// Machine software interrupt pending register.
-    msip: u32,
+    /// Machine time compare register, indexed by hart.
+    mtimecmp: Vec<u64>,
+    /// Machine software interrupt pending register, indexed by hart.
+    msip: Vec<u32>,
     /// Divider to scale CPU cycles to timer ticks.
     divider: u64,
     /// Internal counter for the divider.
     counter: u64,
 }
 
+/// CLINT registers saved in a checkpoint.
+#[derive(Serialize, Deserialize)]
+struct ClintState {
+    mtime: u64,
+    mtimecmp: Vec<u64>,
+    msip: Vec<u32>,
+    counter: u64,
+}
+
 impl Clint {
-    /// Creates a new CLINT device.
+    /// Creates a new single-hart CLINT device.
     ///
     /// # Arguments
     ///
     /// * `base_addr` - The base physical address.
     /// * `divider` - The ratio of CPU cycles to timer ticks (e.g., 10 means timer increments every 10 cycles).
-    pub const fn new(base_addr: u64, divider: u64) -> Self {
+    pub fn new(base_addr: u64, divider: u64) -> Self {
+        Self::with_harts(base_addr, divider, 1)
+    }
+
+    /// Creates a CLINT with one MSIP and MTIMECMP register per hart.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_addr` - The base physical address.
+    /// * `divider` - The ratio of CPU cycles to timer ticks.
+    /// * `num_harts` - Number of harts served (at least 1).
+    pub fn with_harts(base_addr: u64, divider: u64, num_harts: usize) -> Self {
+        let num_harts = num_harts.max(1);
         Self {
             base_addr,
             mtime: 0,
-            mtimecmp: u64::MAX,
-            msip: 0,
+            mtimecmp: vec![u64::MAX; num_harts],
+            msip: vec![0; num_harts],
             divider: if divider == 0 { 1 } else { divider },
             counter: 0,
         }
@@ -56,9 +82,67 @@ impl Clint {
 }
 
 impl Clint {
-    /// Returns `true` if the machine software interrupt pending bit is set.
-    pub const fn msip_pending(&self) -> bool {
-        (self.msip & 1) != 0
+    /// Returns `true` if hart 0's machine software interrupt pending bit is set.
+    pub fn msip_pending(&self) -> bool {
+        self.hart_msip(0)
+    }
+
+    /// Returns `true` if the machine software interrupt pending bit of `hart` is set.
+    pub fn hart_msip(&self, hart: usize) -> bool {
+        self.msip.get(hart).is_some_and(|&v| (v & 1) != 0)
+    }
+
+    /// Returns `true` if the machine timer interrupt of `hart` is pending
+    /// (`mtime >= mtimecmp[hart]`).
+    pub fn hart_mtip(&self, hart: usize) -> bool {
+        self.mtimecmp.get(hart).is_some_and(|&cmp| self.mtime >= cmp)
+    }
+
+    /// Returns the number of harts served.
+    pub const fn num_harts(&self) -> usize {
+        self.msip.len()
+    }
+
+    /// Returns the current machine time.
+    pub const fn mtime(&self) -> u64 {
+        self.mtime
+    }
+
+    /// Returns `mtimecmp` of `hart`, or `None` if the hart does not exist.
+    pub fn mtimecmp(&self, hart: usize) -> Option<u64> {
+        self.mtimecmp.get(hart).copied()
+    }
+
+    /// Sets `mtimecmp` of `hart`; ignored if the hart does not exist.
+    pub fn set_mtimecmp(&mut self, hart: usize, val: u64) {
+        if let Some(cmp) = self.mtimecmp.get_mut(hart) {
+            *cmp = val;
+        }
+    }
+
+    /// Sets or clears `msip` of `hart`; ignored if the hart does not exist.
+    pub fn set_msip(&mut self, hart: usize, pending: bool) {
+        if let Some(msip) = self.msip.get_mut(hart) {
+            *msip = u32::from(pending);
+        }
+    }
+
+    /// Maps an offset in the MSIP bank to a hart index.
+    fn msip_hart(&self, offset: u64) -> Option<usize> {
+        let hart = ((offset - MSIP_OFFSET) / 4) as usize;
+        (offset < MTIMECMP_OFFSET && offset.is_multiple_of(4) && hart < self.msip.len())
+            .then_some(hart)
+    }
+
+    /// Maps an offset in the MTIMECMP bank to a hart index and the word
+    /// within the register (0 = low, 1 = high).
+    fn mtimecmp_hart(&self, offset: u64) -> Option<(usize, u64)> {
+        if !(MTIMECMP_OFFSET..MTIME_OFFSET).contains(&offset) || !offset.is_multiple_of(4) {
+            return None;
+        }
+        let rel = offset - MTIMECMP_OFFSET;
+        let hart = (rel / 8) as usize;
+        (hart < self.mtimecmp.len()).then_some((hart, (rel / 4) & 1))
     }
 }
 
@@ -91,10 +175,13 @@ impl Device for Clint {
     ///
     /// Handles reads to MSIP, and the lower/upper halves of MTIME and MTIMECMP.
     fn read_u32(&mut self, offset: u64) -> u32 {
+        if let Some(hart) = self.msip_hart(offset) {
+            return self.msip[hart];
+        }
+        if let Some((hart, word)) = self.mtimecmp_hart(offset) {
+            return (self.mtimecmp[hart] >> (word * 32)) as u32;
+        }
         match offset {
-            MSIP_OFFSET => self.msip,
-            MTIMECMP_OFFSET => self.mtimecmp as u32,
-            val if val == MTIMECMP_OFFSET + 4 => (self.mtimecmp >> 32) as u32,
             MTIME_OFFSET => self.mtime as u32,
             val if val == MTIME_OFFSET + 4 => (self.mtime >> 32) as u32,
             _ => 0,
@@ -103,12 +190,13 @@ impl Device for Clint {
 
     /// Reads a double-word (64-bit) from the device.
     fn read_u64(&mut self, offset: u64) -> u64 {
-        match offset {
-            MSIP_OFFSET => self.msip as u64,
-            MTIMECMP_OFFSET => self.mtimecmp,
-            MTIME_OFFSET => self.mtime,
-            _ => 0,
+        if let Some(hart) = self.msip_hart(offset) {
+            return self.msip[hart] as u64;
         }
+        if let Some((hart, 0)) = self.mtimecmp_hart(offset) {
+            return self.mtimecmp[hart];
+        }
+        if offset == MTIME_OFFSET { self.mtime } else { 0 }
     }
 
     /// Writes a byte (unimplemented).
@@ -120,14 +208,20 @@ impl Device for Clint {
     ///
     /// Handles writes to MSIP, and the lower/upper halves of MTIME and MTIMECMP.
     fn write_u32(&mut self, offset: u64, val: u32) {
+        if let Some(hart) = self.msip_hart(offset) {
+            self.msip[hart] = val & 1;
+            return;
+        }
+        if let Some((hart, word)) = self.mtimecmp_hart(offset) {
+            let cmp = &mut self.mtimecmp[hart];
+            *cmp = if word == 0 {
+                (*cmp & 0xFFFF_FFFF_0000_0000) | (val as u64)
+            } else {
+                (*cmp & 0x0000_0000_FFFF_FFFF) | ((val as u64) << 32)
+            };
+            return;
+        }
         match offset {
-            MSIP_OFFSET => self.msip = val & 1,
-            MTIMECMP_OFFSET => {
-                self.mtimecmp = (self.mtimecmp & 0xFFFF_FFFF_0000_0000) | (val as u64);
-            }
-            o if o == MTIMECMP_OFFSET + 4 => {
-                self.mtimecmp = (self.mtimecmp & 0x0000_0000_FFFF_FFFF) | ((val as u64) << 32);
-            }
             MTIME_OFFSET => self.mtime = (self.mtime & 0xFFFF_FFFF_0000_0000) | (val as u64),
             o if o == MTIME_OFFSET + 4 => {
                 self.mtime = (self.mtime & 0x0000_0000_FFFF_FFFF) | ((val as u64) << 32);
@@ -138,18 +232,20 @@ impl Device for Clint {
 
     /// Writes a double-word (64-bit) to the device.
     fn write_u64(&mut self, offset: u64, val: u64) {
-        match offset {
-            MSIP_OFFSET => self.msip = (val as u32) & 1,
-            MTIMECMP_OFFSET => self.mtimecmp = val,
-            MTIME_OFFSET => self.mtime = val,
-            _ => {}
+        if let Some(hart) = self.msip_hart(offset) {
+            self.msip[hart] = (val as u32) & 1;
+        } else if let Some((hart, 0)) = self.mtimecmp_hart(offset) {
+            self.mtimecmp[hart] = val;
+        } else if offset == MTIME_OFFSET {
+            self.mtime = val;
         }
     }
 
     /// Advances the device state by one cycle.
     ///
     /// Increments the `mtime` counter based on the configured divider.
-    /// Returns `true` if the machine timer interrupt is pending (`mtime >= mtimecmp`).
+    /// Returns `true` if hart 0's machine timer interrupt is pending
+    /// (`mtime >= mtimecmp[0]`); other harts are queried with [`Clint::hart_mtip`].
     fn tick(&mut self) -> bool {
         self.counter += 1;
         if self.counter >= self.divider {
@@ -157,7 +253,35 @@ impl Device for Clint {
             self.counter = 0;
         }
 
-        self.mtime >= self.mtimecmp
+        self.hart_mtip(0)
+    }
+
+    /// Saves `mtime`, the per-hart `mtimecmp` and `msip` registers and the divider count.
+    fn save_state(&self) -> Option<serde_json::Value> {
+        let state = ClintState {
+            mtime: self.mtime,
+            mtimecmp: self.mtimecmp.clone(),
+            msip: self.msip.clone(),
+            counter: self.counter,
+        };
+        serde_json::to_value(state).ok()
+    }
+
+    /// Restores the registers saved by `save_state`; the hart count must match.
+    fn restore_state(&mut self, state: serde_json::Value) -> Result<(), String> {
+        let state: ClintState = serde_json::from_value(state).map_err(|e| e.to_string())?;
+        if state.mtimecmp.len() != self.mtimecmp.len() || state.msip.len() != self.msip.len() {
+            return Err(format!(
+                "CLINT has {} harts, checkpoint has {}",
+                self.mtimecmp.len(),
+                state.mtimecmp.len()
+            ));
+        }
+        self.mtime = state.mtime;
+        self.mtimecmp = state.mtimecmp;
+        self.msip = state.msip;
+        self.counter = state.counter;
+        Ok(())
     }
 
     fn as_clint_mut(&mut self) -> Option<&mut Clint> {
@@ -0,0 +1,368 @@
+//! Memory-to-Memory DMA Controller.
+//!
+//! A single-channel copy engine. The driver programs source, destination and
+//! length, then writes START to the control register. It provides:
+//! 1. **Transfer:** Each cycle up to `bytes_per_cycle` bytes are copied inside
+//!    the RAM bank through the shared DRAM buffer, bypassing the caches.
+//! 2. **Timing:** Every chunk's read and write are handed to the memory
+//!    controller (see [`System::service_dma`](crate::soc::System::service_dma)); the
+//!    transfer completes once the slowest of them has, so DMA contends with
+//!    the CPU for DRAM banks.
+//! 3. **Completion:** STATUS reports BUSY, DONE and ERROR; with IRQ enabled in
+//!    CTRL, DONE or ERROR raises PLIC source 4 until cleared.
+//!
+//! # Registers
+//!
+//! Every register is 64 bits wide and may be accessed as two 32-bit halves.
+//!
+//! | Offset | Name | Access |
+//! |--------|------|--------|
+//! | `0x00` | SRC | Source physical address (ignored while busy) |
+//! | `0x08` | DST | Destination physical address (ignored while busy) |
+//! | `0x10` | LEN | Length in bytes (ignored while busy) |
+//! | `0x18` | CTRL | Bit 0 START (write-only), bit 1 IRQ enable |
+//! | `0x20` | STATUS | Bit 0 BUSY, bit 1 DONE, bit 2 ERROR; write 1 to clear DONE/ERROR |
+//! | `0x28` | COPIED | Bytes copied by the current or last transfer (read-only) |
+
+use crate::common::IrqId;
+use crate::soc::devices::Device;
+use crate::soc::memory::buffer::DramBuffer;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Source address register offset.
+const REG_SRC: u64 = 0x00;
+
+/// Destination address register offset.
+const REG_DST: u64 = 0x08;
+
+/// Transfer length register offset.
+const REG_LEN: u64 = 0x10;
+
+/// Control register offset.
+const REG_CTRL: u64 = 0x18;
+
+/// Status register offset.
+const REG_STATUS: u64 = 0x20;
+
+/// Bytes-copied register offset.
+const REG_COPIED: u64 = 0x28;
+
+/// CTRL: start a transfer.
+pub const CTRL_START: u64 = 1 << 0;
+
+/// CTRL: raise the interrupt on completion or error.
+pub const CTRL_IRQ_ENABLE: u64 = 1 << 1;
+
+/// STATUS: a transfer is in progress.
+pub const STATUS_BUSY: u64 = 1 << 0;
+
+/// STATUS: the last transfer completed.
+pub const STATUS_DONE: u64 = 1 << 1;
+
+/// STATUS: the last transfer was rejected (range outside RAM).
+pub const STATUS_ERROR: u64 = 1 << 2;
+
+/// Default PLIC source driven by the controller.
+const DMA_IRQ: u32 = 4;
+
+/// Memory-to-memory DMA controller.
+#[derive(Debug)]
+pub struct DmaController {
+    /// Base physical address of the register block.
+    base_addr: u64,
+    /// PLIC source the device raises.
+    irq: u32,
+    /// Base physical address of the RAM bank the controller copies within.
+    ram_base: u64,
+    /// Shared reference to that RAM bank.
+    ram: Arc<DramBuffer>,
+    /// Bytes moved per cycle.
+    bytes_per_cycle: u64,
+
+    src: u64,
+    dst: u64,
+    len: u64,
+    irq_enable: bool,
+    /// STATUS bits.
+    status: u64,
+    /// Bytes copied by the current transfer.
+    copied: u64,
+    /// Cycles until the memory controller finishes the requests issued so far.
+    outstanding: u64,
+    /// Requests waiting in the memory controller's queue.
+    queued: usize,
+
+    /// (address, bytes) of this cycle's reads and writes, awaiting memory timing.
+    memory_requests: Vec<(u64, u64)>,
+    /// DMA writes (address, length) not yet reported to the bus.
+    dma_writes: Vec<(u64, u64)>,
+}
+
+/// Registers and transfer progress saved in a checkpoint.
+#[derive(Serialize, Deserialize)]
+struct DmaState {
+    src: u64,
+    dst: u64,
+    len: u64,
+    irq_enable: bool,
+    status: u64,
+    copied: u64,
+    outstanding: u64,
+}
+
+impl DmaController {
+    /// Creates an idle DMA controller.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_addr` - MMIO base address.
+    /// * `ram_base` - Base address of the RAM bank in `ram`.
+    /// * `ram` - Shared DRAM buffer the transfers read and write.
+    /// * `bytes_per_cycle` - Bytes moved per cycle (at least 1).
+    pub fn new(base_addr: u64, ram_base: u64, ram: Arc<DramBuffer>, bytes_per_cycle: u64) -> Self {
+        Self {
+            base_addr,
+            irq: DMA_IRQ,
+            ram_base,
+            ram,
+            bytes_per_cycle: bytes_per_cycle.max(1),
+            src: 0,
+            dst: 0,
+            len: 0,
+            irq_enable: false,
+            status: 0,
+            copied: 0,
+            outstanding: 0,
+            queued: 0,
+            memory_requests: Vec::new(),
+            dma_writes: Vec::new(),
+        }
+    }
+
+    /// Sets the PLIC source the device raises.
+    pub const fn set_irq(&mut self, irq: u32) {
+        self.irq = irq;
+    }
+
+    /// Returns the STATUS register.
+    pub const fn status(&self) -> u64 {
+        self.status
+    }
+
+    /// Returns whether a transfer is in progress.
+    pub const fn busy(&self) -> bool {
+        self.status & STATUS_BUSY != 0
+    }
+
+    /// Returns the bytes copied by the current or last transfer.
+    pub const fn copied(&self) -> u64 {
+        self.copied
+    }
+
+    /// Returns the (source, destination, length) registers.
+    pub const fn transfer(&self) -> (u64, u64, u64) {
+        (self.src, self.dst, self.len)
+    }
+
+    /// Returns the reads and writes issued this cycle, as (address, bytes).
+    pub fn memory_requests(&self) -> &[(u64, u64)] {
+        &self.memory_requests
+    }
+
+    /// Clears this cycle's requests once the memory controller took `latency`
+    /// cycles to serve them.
+    pub fn complete_requests(&mut self, latency: u64) {
+        self.memory_requests.clear();
+        self.outstanding = self.outstanding.max(latency);
+    }
+
+    /// Notes that `count` of this cycle's requests went to the memory
+    /// controller's queue; the transfer cannot finish until each one is
+    /// released with [`release_request`](Self::release_request).
+    pub const fn hold_requests(&mut self, count: usize) {
+        self.queued += count;
+    }
+
+    /// Releases a queued request that the memory controller will complete
+    /// in `latency` cycles.
+    pub fn release_request(&mut self, latency: u64) {
+        self.queued = self.queued.saturating_sub(1);
+        self.outstanding = self.outstanding.max(latency);
+    }
+
+    /// Converts a guest physical address range to an offset into RAM, or
+    /// `None` if any part of it lies outside.
+    fn ram_offset(&self, addr: u64, len: u64) -> Option<usize> {
+        let offset = usize::try_from(addr.checked_sub(self.ram_base)?).ok()?;
+        (offset.checked_add(usize::try_from(len).ok()?)? <= self.ram.len()).then_some(offset)
+    }
+
+    /// Starts the programmed transfer, or flags ERROR if either range is outside RAM.
+    fn start(&mut self) {
+        self.status &= !(STATUS_DONE | STATUS_ERROR);
+        self.copied = 0;
+        if self.ram_offset(self.src, self.len).is_none()
+            || self.ram_offset(self.dst, self.len).is_none()
+        {
+            self.status |= STATUS_ERROR;
+        } else {
+            self.status |= STATUS_BUSY;
+        }
+    }
+
+    /// Copies the next chunk and records its memory traffic.
+    fn step(&mut self) {
+        let n = self.bytes_per_cycle.min(self.len - self.copied);
+        let src = self.src + self.copied;
+        let dst = self.dst + self.copied;
+        if let (Some(from), Some(to)) = (self.ram_offset(src, n), self.ram_offset(dst, n)) {
+            let chunk = self.ram.read_slice(from, n as usize).to_vec();
+            self.ram.write_slice(to, &chunk);
+            self.dma_writes.push((dst, n));
+            self.memory_requests.push((src, n));
+            self.memory_requests.push((dst, n));
+        }
+        self.copied += n;
+    }
+
+    const fn read_reg(&self, reg: u64) -> u64 {
+        match reg {
+            REG_SRC => self.src,
+            REG_DST => self.dst,
+            REG_LEN => self.len,
+            REG_CTRL if self.irq_enable => CTRL_IRQ_ENABLE,
+            REG_STATUS => self.status,
+            REG_COPIED => self.copied,
+            _ => 0,
+        }
+    }
+
+    /// Writes `val` to the bits of register `reg` selected by `mask`.
+    fn write_reg(&mut self, reg: u64, val: u64, mask: u64) {
+        let merge = |old: u64| (old & !mask) | (val & mask);
+        let busy = self.busy();
+        match reg {
+            REG_SRC if !busy => self.src = merge(self.src),
+            REG_DST if !busy => self.dst = merge(self.dst),
+            REG_LEN if !busy => self.len = merge(self.len),
+            REG_CTRL => {
+                let val = val & mask;
+                if mask & CTRL_IRQ_ENABLE != 0 {
+                    self.irq_enable = val & CTRL_IRQ_ENABLE != 0;
+                }
+                if val & CTRL_START != 0 && !busy {
+                    self.start();
+                }
+            }
+            REG_STATUS => self.status &= !(val & mask & (STATUS_DONE | STATUS_ERROR)),
+            _ => {}
+        }
+    }
+}
+
+impl Device for DmaController {
+    /// Returns the device name.
+    fn name(&self) -> &'static str {
+        "DMA"
+    }
+
+    /// Returns the address range (Base, Size).
+    fn address_range(&self) -> (u64, u64) {
+        (self.base_addr, 0x1000)
+    }
+
+    /// Reads a register half (delegates to `read_u64`).
+    fn read_u32(&mut self, offset: u64) -> u32 {
+        (self.read_u64(offset & !7) >> ((offset & 4) * 8)) as u32
+    }
+
+    /// Reads a register.
+    fn read_u64(&mut self, offset: u64) -> u64 {
+        self.read_reg(offset & !7)
+    }
+
+    /// Writes a register half.
+    fn write_u32(&mut self, offset: u64, val: u32) {
+        let shift = (offset & 4) * 8;
+        self.write_reg(offset & !7, u64::from(val) << shift, 0xffff_ffff << shift);
+    }
+
+    /// Writes a register.
+    fn write_u64(&mut self, offset: u64, val: u64) {
+        self.write_reg(offset & !7, val, u64::MAX);
+    }
+
+    /// Reads a byte (delegates to `read_u32`).
+    fn read_u8(&mut self, offset: u64) -> u8 {
+        (self.read_u32(offset & !3) >> ((offset & 3) * 8)) as u8
+    }
+    /// Reads a half-word (delegates to `read_u32`).
+    fn read_u16(&mut self, offset: u64) -> u16 {
+        (self.read_u32(offset & !3) >> ((offset & 3) * 8)) as u16
+    }
+    /// Writes a byte (delegates to `write_u32`).
+    fn write_u8(&mut self, offset: u64, val: u8) {
+        self.write_u32(offset & !3, val as u32);
+    }
+    /// Writes a half-word (delegates to `write_u32`).
+    fn write_u16(&mut self, offset: u64, val: u16) {
+        self.write_u32(offset & !3, val as u32);
+    }
+
+    /// Advances the transfer by one cycle; returns true if the interrupt is raised.
+    fn tick(&mut self) -> bool {
+        if self.busy() {
+            self.outstanding = self.outstanding.saturating_sub(1);
+            if self.copied < self.len {
+                self.step();
+            } else if self.outstanding == 0 && self.queued == 0 {
+                self.status = (self.status & !STATUS_BUSY) | STATUS_DONE;
+            }
+        }
+        self.irq_enable && self.status & (STATUS_DONE | STATUS_ERROR) != 0
+    }
+
+    /// Returns the Interrupt Request (IRQ) ID associated with this device.
+    fn get_irq_id(&self) -> Option<IrqId> {
+        Some(IrqId::new(self.irq))
+    }
+
+    /// Reports the destination writes made since the last call.
+    fn take_dma_writes(&mut self, out: &mut Vec<(u64, u64)>) {
+        out.append(&mut self.dma_writes);
+    }
+
+    /// Saves the registers and the progress of the current transfer. Requests
+    /// waiting in the memory controller's queue count as served.
+    fn save_state(&self) -> Option<serde_json::Value> {
+        let state = DmaState {
+            src: self.src,
+            dst: self.dst,
+            len: self.len,
+            irq_enable: self.irq_enable,
+            status: self.status,
+            copied: self.copied,
+            outstanding: self.outstanding,
+        };
+        serde_json::to_value(state).ok()
+    }
+
+    /// Restores the state saved by `save_state`.
+    fn restore_state(&mut self, state: serde_json::Value) -> Result<(), String> {
+        let state: DmaState = serde_json::from_value(state).map_err(|e| e.to_string())?;
+        self.src = state.src;
+        self.dst = state.dst;
+        self.len = state.len;
+        self.irq_enable = state.irq_enable;
+        self.status = state.status;
+        self.copied = state.copied;
+        self.outstanding = state.outstanding;
+        self.queued = 0;
+        Ok(())
+    }
+
+    fn as_dma_mut(&mut self) -> Option<&mut DmaController> {
+        Some(self)
+    }
+}
@@ -1,35 +1,121 @@
 //! Goldfish Real-Time Clock (RTC).
 //!
 //! A virtual RTC device commonly used in Android emulators (QEMU).
-//! It provides the current system time in nanoseconds.
+//! It provides the current system time in nanoseconds and a one-shot alarm
+//! that raises the RTC interrupt when the time reaches the programmed value.
 //!
 //! # Memory Map
 //!
-//! * `0x00`: Time (Low 32 bits)
-//! * `0x04`: Time (High 32 bits)
+//! * `0x00`: Time (Low 32 bits; reading latches the high word, writing sets the time)
+//! * `0x04`: Time (High 32 bits; latched value on read, staged on write)
+//! * `0x08`: Alarm (Low 32 bits; writing arms the alarm)
+//! * `0x0C`: Alarm (High 32 bits; staged on write)
+//! * `0x10`: IRQ enable
+//! * `0x14`: Clear alarm (write disarms)
+//! * `0x18`: Alarm status (1 while armed)
+//! * `0x1C`: Clear interrupt (write acknowledges)
 
 use crate::common::IrqId;
 use crate::soc::devices::Device;
+use serde::{Deserialize, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// Time, low 32 bits.
+const REG_TIME_LOW: u64 = 0x00;
+/// Time, high 32 bits.
+const REG_TIME_HIGH: u64 = 0x04;
+/// Alarm, low 32 bits.
+const REG_ALARM_LOW: u64 = 0x08;
+/// Alarm, high 32 bits.
+const REG_ALARM_HIGH: u64 = 0x0C;
+/// Interrupt enable.
+const REG_IRQ_ENABLED: u64 = 0x10;
+/// Disarm the alarm.
+const REG_CLEAR_ALARM: u64 = 0x14;
+/// Alarm armed status.
+const REG_ALARM_STATUS: u64 = 0x18;
+/// Acknowledge the interrupt.
+const REG_CLEAR_INTERRUPT: u64 = 0x1C;
+
+/// Default PLIC source driven by the alarm.
+const RTC_IRQ: u32 = 11;
+
 /// Goldfish RTC device structure.
 #[derive(Debug)]
 pub struct GoldfishRtc {
     /// Base physical address of the device.
     base_addr: u64,
+    /// PLIC source the device raises.
+    irq: u32,
+    /// Offset added to the host clock (set by guest writes to the time registers).
+    time_offset: u64,
+    /// High word of the time latched by the last read of the low word.
+    time_high_latch: u32,
+    /// High word of the time staged by a write, applied by the low-word write.
+    time_high_write: u32,
+    /// Programmed alarm time in nanoseconds.
+    alarm: u64,
+    /// High word of the alarm staged by a write, applied by the low-word write.
+    alarm_high_write: u32,
+    /// Whether the alarm is armed.
+    alarm_armed: bool,
+    /// Whether the alarm interrupt is enabled.
+    irq_enabled: bool,
+    /// Whether the alarm has fired and not been acknowledged.
+    irq_pending: bool,
+}
+
+/// RTC registers saved in a checkpoint.
+#[derive(Serialize, Deserialize)]
+#[allow(clippy::struct_excessive_bools)]
+struct RtcState {
+    time_offset: u64,
+    time_high_latch: u32,
+    time_high_write: u32,
+    alarm: u64,
+    alarm_high_write: u32,
+    alarm_armed: bool,
+    irq_enabled: bool,
+    irq_pending: bool,
 }
 
 impl GoldfishRtc {
     /// Creates a new Goldfish RTC device.
     pub const fn new(base_addr: u64) -> Self {
-        Self { base_addr }
+        Self {
+            base_addr,
+            irq: RTC_IRQ,
+            time_offset: 0,
+            time_high_latch: 0,
+            time_high_write: 0,
+            alarm: 0,
+            alarm_high_write: 0,
+            alarm_armed: false,
+            irq_enabled: false,
+            irq_pending: false,
+        }
+    }
+
+    /// Sets the PLIC source the device raises.
+    pub const fn set_irq(&mut self, irq: u32) {
+        self.irq = irq;
     }
 
     /// Retrieves the current system time in nanoseconds.
     #[allow(clippy::unused_self)]
-    fn get_time_ns(&self) -> u64 {
+    fn get_host_time_ns(&self) -> u64 {
         SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos() as u64
     }
+
+    /// Retrieves the current guest time in nanoseconds.
+    fn get_time_ns(&self) -> u64 {
+        self.get_host_time_ns().wrapping_add(self.time_offset)
+    }
+
+    /// Sets the guest time to `ns` nanoseconds.
+    fn set_time_ns(&mut self, ns: u64) {
+        self.time_offset = ns.wrapping_sub(self.get_host_time_ns());
+    }
 }
 
 impl Device for GoldfishRtc {
@@ -54,23 +140,31 @@ impl Device for GoldfishRtc {
 
     /// Reads a word (32-bit) from the device.
     ///
-    /// Returns the lower or upper 32 bits of the current nanosecond timestamp.
+    /// Reading the low word of the time latches the high word, so a
+    /// low-then-high read sequence returns a consistent timestamp.
     fn read_u32(&mut self, offset: u64) -> u32 {
-        let time = self.get_time_ns();
         match offset {
-            0x00 => time as u32,
-            0x04 => (time >> 32) as u32,
+            REG_TIME_LOW => {
+                let time = self.get_time_ns();
+                self.time_high_latch = (time >> 32) as u32;
+                time as u32
+            }
+            REG_TIME_HIGH => self.time_high_latch,
+            REG_ALARM_LOW => self.alarm as u32,
+            REG_ALARM_HIGH => (self.alarm >> 32) as u32,
+            REG_IRQ_ENABLED => u32::from(self.irq_enabled),
+            REG_ALARM_STATUS => u32::from(self.alarm_armed),
             _ => 0,
         }
     }
 
     /// Reads a double-word (64-bit) from the device.
     ///
-    /// Returns the full 64-bit nanosecond timestamp.
+    /// Returns the full 64-bit nanosecond timestamp or alarm.
     fn read_u64(&mut self, offset: u64) -> u64 {
-        let time = self.get_time_ns();
         match offset {
-            0x00 => time,
+            REG_TIME_LOW => self.get_time_ns(),
+            REG_ALARM_LOW => self.alarm,
             _ => 0,
         }
     }
@@ -79,13 +173,87 @@ impl Device for GoldfishRtc {
     fn write_u8(&mut self, _offset: u64, _val: u8) {}
     /// Writes a half-word (unimplemented).
     fn write_u16(&mut self, _offset: u64, _val: u16) {}
-    /// Writes a word (unimplemented).
-    fn write_u32(&mut self, _offset: u64, _val: u32) {}
-    /// Writes a double-word (unimplemented).
-    fn write_u64(&mut self, _offset: u64, _val: u64) {}
+
+    /// Writes a word (32-bit) to the device.
+    ///
+    /// High words are staged and take effect with the following low-word
+    /// write, which sets the time or arms the alarm.
+    fn write_u32(&mut self, offset: u64, val: u32) {
+        match offset {
+            REG_TIME_LOW => {
+                self.set_time_ns((u64::from(self.time_high_write) << 32) | u64::from(val));
+            }
+            REG_TIME_HIGH => self.time_high_write = val,
+            REG_ALARM_LOW => {
+                self.alarm = (u64::from(self.alarm_high_write) << 32) | u64::from(val);
+                self.alarm_armed = true;
+            }
+            REG_ALARM_HIGH => self.alarm_high_write = val,
+            REG_IRQ_ENABLED => self.irq_enabled = val & 1 != 0,
+            REG_CLEAR_ALARM => self.alarm_armed = false,
+            REG_CLEAR_INTERRUPT => self.irq_pending = false,
+            _ => {}
+        }
+    }
+
+    /// Writes a double-word (64-bit) to the device.
+    ///
+    /// Sets the full time or alarm in one access; other registers take the
+    /// low 32 bits.
+    fn write_u64(&mut self, offset: u64, val: u64) {
+        match offset {
+            REG_TIME_LOW => self.set_time_ns(val),
+            REG_ALARM_LOW => {
+                self.alarm = val;
+                self.alarm_armed = true;
+            }
+            _ => self.write_u32(offset, val as u32),
+        }
+    }
+
+    /// Fires the alarm once the time reaches it.
+    ///
+    /// Returns `true` while a fired alarm is unacknowledged and the interrupt
+    /// is enabled.
+    fn tick(&mut self) -> bool {
+        if self.alarm_armed && self.get_time_ns() >= self.alarm {
+            self.alarm_armed = false;
+            self.irq_pending = true;
+        }
+        self.irq_enabled && self.irq_pending
+    }
 
     /// Returns the Interrupt Request (IRQ) ID associated with this device.
     fn get_irq_id(&self) -> Option<IrqId> {
-        Some(IrqId::new(11))
+        Some(IrqId::new(self.irq))
+    }
+
+    /// Saves the offset from the host clock, the latched words and the alarm.
+    fn save_state(&self) -> Option<serde_json::Value> {
+        let state = RtcState {
+            time_offset: self.time_offset,
+            time_high_latch: self.time_high_latch,
+            time_high_write: self.time_high_write,
+            alarm: self.alarm,
+            alarm_high_write: self.alarm_high_write,
+            alarm_armed: self.alarm_armed,
+            irq_enabled: self.irq_enabled,
+            irq_pending: self.irq_pending,
+        };
+        serde_json::to_value(state).ok()
+    }
+
+    /// Restores the registers saved by `save_state`.
+    fn restore_state(&mut self, state: serde_json::Value) -> Result<(), String> {
+        let state: RtcState = serde_json::from_value(state).map_err(|e| e.to_string())?;
+        self.time_offset = state.time_offset;
+        self.time_high_latch = state.time_high_latch;
+        self.time_high_write = state.time_high_write;
+        self.alarm = state.alarm;
+        self.alarm_high_write = state.alarm_high_write;
+        self.alarm_armed = state.alarm_armed;
+        self.irq_enabled = state.irq_enabled;
+        self.irq_pending = state.irq_pending;
+        Ok(())
     }
 }
@@ -1,54 +1,306 @@
 //! Host-Target Interface (HTIF) device.
 //!
 //! Implements the HTIF tohost/fromhost protocol used by riscv-tests and other
-//! bare-metal test suites. The test program writes a result value to the
-//! `tohost` memory-mapped address:
+//! bare-metal programs. The program writes a command to the `tohost`
+//! memory-mapped address, laid out as `device << 56 | cmd << 48 | payload`:
 //!
-//! * `1` — test passed (exit code 0).
-//! * Odd and not 1 — test failed; the failing test number is `value >> 1`.
+//! * Device 0, command 0 — with bit 0 of the payload set, exit: a payload of
+//!   `1` is a pass (exit code 0), any other is a failure whose test number is
+//!   `payload >> 1`. With bit 0 clear, the payload is the address of a
+//!   syscall block (`magic_mem`) of eight 64-bit words: the syscall number
+//!   and its arguments. The result is written back to the first word.
+//!   `write` (64) to file descriptor 1 or 2 prints the buffer to the console
+//!   and `exit` (93) exits; other syscalls return `-ENOSYS`.
+//! * Device 1, command 1 — console putchar of the low payload byte.
+//! * Device 1, command 0 — console getchar, answered with `0x100 | byte` once
+//!   a byte of input is available.
 //! * `0` — ignored (tests poll-write zero before writing the real value).
 //!
-//! This device occupies a single 8-byte slot on the bus at the address of the
-//! `tohost` ELF symbol. It shares the same `exit_request` atomic as SysCon so
-//! the simulation loop picks up the exit without any extra plumbing.
+//! Each command except exit and getchar is answered at once by a response
+//! in `fromhost` (the command with a payload of `1` for syscalls, `0` for
+//! putchar). Responses wait until the program has cleared `fromhost` by
+//! writing zero, and `tohost` reads back as zero once a command is taken.
+//!
+//! This device occupies the 16 bytes at the address of the `tohost` ELF
+//! symbol: `tohost` and then `fromhost`. Stores there go through the bus to
+//! the device, but loads are served from RAM like any other, so the device
+//! mirrors both registers into the RAM behind them. Console output goes to
+//! the same host stream as the UART's and input comes from the host's stdin.
+//! It shares the same `exit_request` atomic as SysCon so the simulation loop
+//! picks up the exit without any extra plumbing.
 
 use crate::soc::devices::Device;
-use std::sync::Arc;
+use crate::soc::devices::uart::{OUTPUT_CAPTURE_LIMIT, host_stdin};
+use crate::soc::memory::buffer::DramBuffer;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::{self, Write};
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
+
+/// Device number of the syscall proxy (and exit).
+pub const DEV_SYSCALL: u64 = 0;
+/// Device number of the console.
+pub const DEV_CONSOLE: u64 = 1;
+/// Console command: read a character.
+pub const CMD_GETCHAR: u64 = 0;
+/// Console command: write a character.
+pub const CMD_PUTCHAR: u64 = 1;
+/// Proxied syscall number of `write`.
+pub const SYS_WRITE: u64 = 64;
+/// Proxied syscall number of `exit`.
+pub const SYS_EXIT: u64 = 93;
+
+/// Mask of the payload field of a command.
+const PAYLOAD_MASK: u64 = (1 << 48) - 1;
+/// Words in a syscall block: the syscall number and up to seven arguments.
+const SYSCALL_WORDS: usize = 8;
+/// `EFAULT`, returned for a buffer outside RAM.
+const EFAULT: u64 = 14;
+/// `ENOSYS`, returned for an unsupported syscall.
+const ENOSYS: u64 = 38;
+
+/// Builds an HTIF command (or response) word.
+pub const fn command(device: u64, cmd: u64, payload: u64) -> u64 {
+    device << 56 | (cmd & 0xff) << 48 | (payload & PAYLOAD_MASK)
+}
 
 /// HTIF device: intercepts writes to the `tohost` address.
 #[derive(Debug)]
 pub struct Htif {
     base_addr: u64,
     exit_signal: Arc<AtomicU64>,
+    /// RAM banks (base, buffer) holding syscall blocks, buffers and the
+    /// registers' RAM mirror.
+    ram: Vec<(u64, Arc<DramBuffer>)>,
+    /// Current `fromhost` value.
+    fromhost: u64,
+    /// Responses waiting for `fromhost` to be cleared, oldest first.
+    responses: VecDeque<u64>,
+    /// Getchar commands not yet answered.
+    pending_reads: usize,
+    /// Host bytes (stdin or injected) waiting for a getchar.
+    host_input: VecDeque<u8>,
+    /// Channel receiver for stdin. Wrapped in Mutex for Sync.
+    rx_receiver: Mutex<Receiver<u8>>,
+    /// Console bytes not yet collected by [`Htif::take_output`].
+    output: VecDeque<u8>,
+    /// When true, console output goes to stderr.
+    to_stderr: bool,
+    /// When true, console output is not printed.
+    quiet: bool,
+}
+
+/// HTIF mailbox state saved in a checkpoint.
+#[derive(Serialize, Deserialize)]
+struct HtifState {
+    fromhost: u64,
+    responses: Vec<u64>,
+    pending_reads: usize,
+    #[serde(with = "crate::common::hex")]
+    host_input: Vec<u8>,
 }
 
 impl Htif {
     /// Creates a new HTIF device at `base_addr` using the shared exit signal.
-    pub const fn new(base_addr: u64, exit_signal: Arc<AtomicU64>) -> Self {
-        Self { base_addr, exit_signal }
+    ///
+    /// Until [`Htif::set_ram`] is called, syscall blocks cannot be read and
+    /// a syscall command exits with the raw `tohost` value.
+    pub fn new(base_addr: u64, exit_signal: Arc<AtomicU64>) -> Self {
+        Self {
+            base_addr,
+            exit_signal,
+            ram: Vec::new(),
+            fromhost: 0,
+            responses: VecDeque::new(),
+            pending_reads: 0,
+            host_input: VecDeque::new(),
+            rx_receiver: Mutex::new(host_stdin()),
+            output: VecDeque::new(),
+            to_stderr: false,
+            quiet: false,
+        }
+    }
+
+    /// Gives the device the RAM banks, as (base, buffer), that the syscall
+    /// proxy reads and the registers are mirrored into.
+    pub fn set_ram(&mut self, ram: Vec<(u64, Arc<DramBuffer>)>) {
+        self.ram = ram;
+    }
+
+    /// Sends console output to stderr instead of stdout, or nowhere when
+    /// `quiet`, as for the UART.
+    pub const fn set_console(&mut self, to_stderr: bool, quiet: bool) {
+        self.to_stderr = to_stderr;
+        self.quiet = quiet;
+    }
+
+    /// Queues `bytes` as console input, ahead of anything read later from stdin.
+    pub fn push_input(&mut self, bytes: &[u8]) {
+        self.host_input.extend(bytes);
     }
 
-    fn handle_tohost(&self, val: u64) {
+    /// Removes and returns the console output since the last call.
+    ///
+    /// Bytes are kept whether or not output is printed or suppressed; at most
+    /// the last 1 MiB is retained.
+    pub fn take_output(&mut self) -> Vec<u8> {
+        self.output.drain(..).collect()
+    }
+
+    /// Returns the current `fromhost` value.
+    pub const fn fromhost(&self) -> u64 {
+        self.fromhost
+    }
+
+    fn handle_tohost(&mut self, val: u64) {
         if val == 0 {
             return;
         }
-        if val == 1 {
-            // Pass
-            self.exit_signal.store(0, Ordering::Relaxed);
-        } else if val & 1 != 0 {
-            // Fail — test number is val >> 1
-            let test_num = val >> 1;
-            eprintln!("[HTIF] FAIL: test case {test_num} (tohost={val:#x})");
-            self.exit_signal.store(test_num, Ordering::Relaxed);
-        } else {
-            // Even non-zero values are device commands in full HTIF.
-            // For riscv-tests we only care about the above cases, but store
-            // the raw value so the simulation exits rather than spinning.
-            eprintln!("[HTIF] Unhandled tohost value: {val:#x}");
+        self.mirror(0, 0);
+        let device = val >> 56;
+        let cmd = (val >> 48) & 0xff;
+        let payload = val & PAYLOAD_MASK;
+        match (device, cmd) {
+            (DEV_SYSCALL, 0) if payload & 1 != 0 => {
+                if payload == 1 {
+                    // Pass
+                    self.exit_signal.store(0, Ordering::Relaxed);
+                } else {
+                    // Fail — test number is payload >> 1
+                    let test_num = payload >> 1;
+                    eprintln!("[HTIF] FAIL: test case {test_num} (tohost={val:#x})");
+                    self.exit_signal.store(test_num, Ordering::Relaxed);
+                }
+            }
+            (DEV_SYSCALL, 0) => self.syscall(val, payload),
+            (DEV_CONSOLE, CMD_PUTCHAR) => {
+                self.putchar(payload as u8);
+                self.respond(command(DEV_CONSOLE, CMD_PUTCHAR, 0));
+            }
+            (DEV_CONSOLE, CMD_GETCHAR) => {
+                self.pending_reads += 1;
+                self.poll_input();
+            }
+            _ => {
+                // Store the raw value so the simulation exits rather than
+                // spinning on a response that never comes.
+                eprintln!("[HTIF] Unhandled tohost value: {val:#x}");
+                self.exit_signal.store(val, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Runs the proxied syscall whose block is at `addr`.
+    fn syscall(&mut self, val: u64, addr: u64) {
+        let Some(block) = self.read_ram(addr, 8 * SYSCALL_WORDS as u64) else {
+            eprintln!("[HTIF] Syscall block outside RAM: tohost={val:#x}");
             self.exit_signal.store(val, Ordering::Relaxed);
+            return;
+        };
+        let word =
+            |i: usize| u64::from_le_bytes(block[8 * i..8 * i + 8].try_into().unwrap_or_default());
+        let (num, fd, buf, len) = (word(0), word(1), word(2), word(3));
+        let result = match num {
+            SYS_WRITE if fd == 1 || fd == 2 => {
+                self.read_ram(buf, len).map_or(EFAULT.wrapping_neg(), |bytes| {
+                    for b in bytes {
+                        self.putchar(b);
+                    }
+                    len
+                })
+            }
+            SYS_EXIT => {
+                self.exit_signal.store(fd, Ordering::Relaxed);
+                return;
+            }
+            _ => ENOSYS.wrapping_neg(),
+        };
+        self.write_ram(addr, &result.to_le_bytes());
+        self.respond(command(DEV_SYSCALL, 0, 1));
+    }
+
+    /// Writes one console byte to the host.
+    fn putchar(&mut self, byte: u8) {
+        if self.output.len() == OUTPUT_CAPTURE_LIMIT {
+            let _ = self.output.pop_front();
+        }
+        self.output.push_back(byte);
+        if !self.quiet {
+            if self.to_stderr {
+                eprint!("{}", byte as char);
+                let _ = io::stderr().flush();
+            } else {
+                print!("{}", byte as char);
+                let _ = io::stdout().flush();
+            }
+        }
+    }
+
+    /// Queues a response and delivers it if `fromhost` is free.
+    fn respond(&mut self, response: u64) {
+        self.responses.push_back(response);
+        self.deliver();
+    }
+
+    /// Moves the oldest response into `fromhost` once the program cleared it.
+    fn deliver(&mut self) {
+        if self.fromhost == 0
+            && let Some(response) = self.responses.pop_front()
+        {
+            self.set_fromhost(response);
+        }
+    }
+
+    /// Answers waiting getchar commands from the host input.
+    fn poll_input(&mut self) {
+        if let Ok(rx) = self.rx_receiver.lock() {
+            self.host_input.extend(rx.try_iter());
+        }
+        while self.pending_reads > 0
+            && let Some(byte) = self.host_input.pop_front()
+        {
+            self.pending_reads -= 1;
+            self.respond(command(DEV_CONSOLE, CMD_GETCHAR, 0x100 | u64::from(byte)));
+        }
+    }
+
+    fn set_fromhost(&mut self, val: u64) {
+        self.fromhost = val;
+        self.mirror(8, val);
+    }
+
+    /// Copies a register value into the RAM behind it, where loads read it.
+    fn mirror(&self, offset: u64, val: u64) {
+        self.write_ram(self.base_addr + offset, &val.to_le_bytes());
+    }
+
+    /// Returns the RAM bank holding `len` bytes at `addr`, with the offset into it.
+    fn ram_range(&self, addr: u64, len: u64) -> Option<(&DramBuffer, usize)> {
+        self.ram.iter().find_map(|(base, buf)| {
+            let offset = usize::try_from(addr.checked_sub(*base)?).ok()?;
+            let end = offset.checked_add(usize::try_from(len).ok()?)?;
+            (end <= buf.len()).then_some((buf.as_ref(), offset))
+        })
+    }
+
+    fn read_ram(&self, addr: u64, len: u64) -> Option<Vec<u8>> {
+        self.ram_range(addr, len).map(|(buf, offset)| buf.read_slice(offset, len as usize).to_vec())
+    }
+
+    fn write_ram(&self, addr: u64, bytes: &[u8]) {
+        if let Some((buf, offset)) = self.ram_range(addr, bytes.len() as u64) {
+            buf.write_slice(offset, bytes);
         }
     }
+
+    /// Reads `size` bytes of the registers at `offset`; `tohost` reads as zero.
+    const fn read(&self, offset: u64, size: u64) -> u64 {
+        let regs = (self.fromhost as u128) << 64;
+        let val = (regs >> (8 * (offset & 15))) as u64;
+        if size == 8 { val } else { val & ((1 << (8 * size)) - 1) }
+    }
 }
 
 impl Device for Htif {
@@ -61,31 +313,69 @@ impl Device for Htif {
         (self.base_addr, 16)
     }
 
-    fn read_u8(&mut self, _offset: u64) -> u8 {
-        0
+    fn read_u8(&mut self, offset: u64) -> u8 {
+        self.read(offset, 1) as u8
     }
-    fn read_u16(&mut self, _offset: u64) -> u16 {
-        0
+    fn read_u16(&mut self, offset: u64) -> u16 {
+        self.read(offset, 2) as u16
     }
-    fn read_u32(&mut self, _offset: u64) -> u32 {
-        0
+    fn read_u32(&mut self, offset: u64) -> u32 {
+        self.read(offset, 4) as u32
     }
-    fn read_u64(&mut self, _offset: u64) -> u64 {
-        0
+    fn read_u64(&mut self, offset: u64) -> u64 {
+        self.read(offset, 8)
     }
 
     fn write_u8(&mut self, _offset: u64, _val: u8) {}
     fn write_u16(&mut self, _offset: u64, _val: u16) {}
 
     fn write_u32(&mut self, offset: u64, val: u32) {
-        if offset == 0 {
-            self.handle_tohost(val as u64);
+        match offset {
+            0 => self.handle_tohost(val as u64),
+            8 => self.set_fromhost(self.fromhost & !0xffff_ffff | val as u64),
+            12 => self.set_fromhost(self.fromhost & 0xffff_ffff | (val as u64) << 32),
+            _ => {}
         }
+        self.deliver();
     }
 
     fn write_u64(&mut self, offset: u64, val: u64) {
-        if offset == 0 {
-            self.handle_tohost(val);
+        match offset {
+            0 => self.handle_tohost(val),
+            8 => self.set_fromhost(val),
+            _ => {}
+        }
+        self.deliver();
+    }
+
+    fn tick(&mut self) -> bool {
+        if self.pending_reads > 0 {
+            self.poll_input();
         }
+        self.deliver();
+        false
+    }
+
+    fn save_state(&self) -> Option<serde_json::Value> {
+        let state = HtifState {
+            fromhost: self.fromhost,
+            responses: self.responses.iter().copied().collect(),
+            pending_reads: self.pending_reads,
+            host_input: self.host_input.iter().copied().collect(),
+        };
+        serde_json::to_value(state).ok()
+    }
+
+    fn restore_state(&mut self, state: serde_json::Value) -> Result<(), String> {
+        let state: HtifState = serde_json::from_value(state).map_err(|e| e.to_string())?;
+        self.fromhost = state.fromhost;
+        self.responses = state.responses.into();
+        self.pending_reads = state.pending_reads;
+        self.host_input = state.host_input.into();
+        Ok(())
+    }
+
+    fn as_htif_mut(&mut self) -> Option<&mut Htif> {
+        Some(self)
     }
 }
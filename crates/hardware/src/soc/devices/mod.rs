@@ -2,11 +2,14 @@
 //!
 //! This module contains implementations of various hardware devices
 //! found in the SoC, such as timers (CLINT), interrupt controllers (PLIC),
-//! serial ports (UART), and block devices (VirtIO).
+//! serial ports (UART), and block and network devices (VirtIO).
 
 /// Core Local Interruptor (timer and software interrupt controller).
 pub mod clint;
 
+/// Memory-to-memory DMA controller with completion interrupt.
+pub mod dma;
+
 /// Goldfish RTC (Real-Time Clock) device.
 pub mod goldfish_rtc;
 
@@ -25,12 +28,21 @@ pub mod uart;
 /// VirtIO block device (disk emulation).
 pub mod virtio_disk;
 
+/// VirtIO network device (Ethernet frames via a host backend).
+pub mod virtio_net;
+
+/// VirtIO entropy device (seeded pseudo-random source).
+pub mod virtio_rng;
+
 pub use clint::Clint;
+pub use dma::DmaController;
 pub use goldfish_rtc::GoldfishRtc;
 pub use htif::Htif;
 pub use plic::Plic;
-pub use syscon::SysCon;
-pub use uart::Uart;
+pub use syscon::{SysCon, SysconEvent};
+pub use uart::{SerialLink, Uart};
 pub use virtio_disk::VirtioBlock;
+pub use virtio_net::VirtioNet;
+pub use virtio_rng::VirtioRng;
 
 pub use crate::soc::traits::Device;
@@ -9,8 +9,17 @@
 //! * `0x001000`: Interrupt Pending Bits
 //! * `0x002000`: Interrupt Enables
 //! * `0x200000`: Priority Thresholds and Claim/Complete Registers
+//!
+//! # Claim/Complete
+//!
+//! Each context is offered the highest-priority source that is pending,
+//! enabled for it and above its threshold. Reading the claim register takes
+//! that source: its pending bit clears and its gateway stays closed, so a
+//! still-asserted line cannot re-pend it, until the ID is written back to the
+//! complete register.
 
 use crate::soc::devices::Device;
+use serde::{Deserialize, Serialize};
 
 /// Base offset for PLIC priority registers (one per interrupt source).
 const PLIC_PRIORITY_BASE: u64 = 0x000000;
@@ -24,134 +33,212 @@ const PLIC_ENABLE_BASE: u64 = 0x002000;
 /// Base offset for PLIC context-specific registers (threshold, claim/complete).
 const PLIC_CONTEXT_BASE: u64 = 0x200000;
 
-/// Number of interrupt contexts (M-mode + S-mode per HART).
-const NUM_CONTEXTS: usize = 2;
+/// Number of interrupt contexts per HART (M-mode + S-mode).
+const CONTEXTS_PER_HART: usize = 2;
 
 /// Number of 32-bit enable words per context (covers 1024 interrupt sources).
 const ENABLE_WORDS_PER_CONTEXT: usize = 32;
 
+/// Number of 32-bit words in the pending and claimed bitmaps (1024 sources).
+const SOURCE_WORDS: usize = 32;
+
+/// Number of interrupt sources, including the reserved source 0.
+const NUM_SOURCES: usize = SOURCE_WORDS * 32;
+
+/// Number of device interrupt lines sampled by [`Plic::update_irqs`].
+const IRQ_LINES: usize = 64;
+
 /// PLIC device structure.
 #[derive(Debug)]
 pub struct Plic {
     /// Base physical address of the device.
     base_addr: u64,
-    /// Interrupt source priorities (1-1023).
+    /// Interrupt source priorities (1-1023); source 0 is hardwired to 0.
     priorities: Vec<u32>,
     /// Pending interrupt bits (bitmap).
     pending: Vec<u32>,
+    /// Gateway state: sources claimed by a context and not yet completed.
+    /// A claimed source forwards no new request until it is completed.
+    claimed: Vec<u32>,
     /// Interrupt enable bits per context: enables[ctx][word].
     enables: Vec<Vec<u32>>,
     /// Priority thresholds per context.
     thresholds: Vec<u32>,
-    /// Claim/Complete registers per context.
+    /// Highest-priority claimable source per context as of the last
+    /// [`Plic::check_interrupts`] (0 = none); drives MEIP/SEIP.
+    claims: Vec<u32>,
+    /// Number of contexts (`2 * num_harts`): context `2h` is hart `h`'s
+    /// M-mode target and `2h + 1` its S-mode target.
+    num_contexts: usize,
+}
+
+/// PLIC registers and gateway state saved in a checkpoint.
+#[derive(Serialize, Deserialize)]
+struct PlicState {
+    priorities: Vec<u32>,
+    pending: Vec<u32>,
+    claimed: Vec<u32>,
+    enables: Vec<Vec<u32>>,
+    thresholds: Vec<u32>,
     claims: Vec<u32>,
 }
 
 impl Plic {
-    /// Creates a new PLIC device.
+    /// Creates a new single-hart PLIC device.
     pub fn new(base_addr: u64) -> Self {
+        Self::with_harts(base_addr, 1)
+    }
+
+    /// Creates a PLIC with M-mode and S-mode contexts for `num_harts` harts.
+    pub fn with_harts(base_addr: u64, num_harts: usize) -> Self {
+        let num_contexts = CONTEXTS_PER_HART * num_harts.max(1);
         Self {
             base_addr,
-            priorities: vec![0; 1024],
-            pending: vec![0; 32],
-            enables: vec![vec![0u32; ENABLE_WORDS_PER_CONTEXT]; NUM_CONTEXTS],
-            thresholds: vec![0; NUM_CONTEXTS],
-            claims: vec![0; NUM_CONTEXTS],
+            priorities: vec![0; NUM_SOURCES],
+            pending: vec![0; SOURCE_WORDS],
+            claimed: vec![0; SOURCE_WORDS],
+            enables: vec![vec![0u32; ENABLE_WORDS_PER_CONTEXT]; num_contexts],
+            thresholds: vec![0; num_contexts],
+            claims: vec![0; num_contexts],
+            num_contexts,
         }
     }
 
     /// Updates the pending status of interrupts based on external signals.
     ///
+    /// Sources are level-triggered: a source's pending bit follows its line,
+    /// except that the gateway holds it clear from claim until completion.
+    ///
     /// # Arguments
     ///
     /// * `mask` - A 64-bit mask where set bits indicate active interrupt lines.
     pub fn update_irqs(&mut self, mask: u64) {
-        self.pending[0] = (mask & 0xFFFFFFFF) as u32;
-        self.pending[1] = (mask >> 32) as u32;
+        let mask = mask & !1; // source 0 does not exist
+        for word in 0..IRQ_LINES / 32 {
+            let level = (mask >> (word * 32)) as u32;
+            self.pending[word] = level & !self.claimed[word];
+        }
     }
 
-    /// Checks for pending interrupts that exceed the priority threshold.
+    /// Checks for pending interrupts that exceed the priority threshold,
+    /// refreshing the claim register of every context.
     ///
     /// # Returns
     ///
     /// A tuple `(meip, seip)` indicating if a Machine External Interrupt
-    /// or Supervisor External Interrupt is pending.
+    /// or Supervisor External Interrupt is pending for hart 0.
     pub fn check_interrupts(&mut self) -> (bool, bool) {
-        let mut meip = false;
-        let mut seip = false;
-
-        if self.has_qualified_irq(0) {
-            meip = true;
-            self.claims[0] = self.calc_max_id(0);
-        } else {
-            self.claims[0] = 0;
+        for ctx in 0..self.num_contexts {
+            self.claims[ctx] = self.calc_max_id(ctx);
         }
+        self.hart_interrupts(0)
+    }
 
-        if self.has_qualified_irq(1) {
-            seip = true;
-            self.claims[1] = self.calc_max_id(1);
-        } else {
-            self.claims[1] = 0;
-        }
+    /// Returns `(meip, seip)` for `hart` as of the last [`Plic::check_interrupts`].
+    pub fn hart_interrupts(&self, hart: usize) -> (bool, bool) {
+        let m_ctx = hart * CONTEXTS_PER_HART;
+        let s_ctx = m_ctx + 1;
+        (
+            self.claims.get(m_ctx).is_some_and(|&id| id != 0),
+            self.claims.get(s_ctx).is_some_and(|&id| id != 0),
+        )
+    }
 
-        (meip, seip)
+    /// Returns the number of interrupt contexts (two per hart).
+    pub const fn num_contexts(&self) -> usize {
+        self.num_contexts
     }
 
-    /// Determines if a context has any pending interrupt above its threshold.
-    fn has_qualified_irq(&self, ctx: usize) -> bool {
-        let threshold = self.thresholds[ctx];
-        let num_words = std::cmp::min(self.pending.len(), self.enables[ctx].len());
+    /// Returns the priority of `source` (0 for out-of-range sources).
+    pub fn priority(&self, source: u32) -> u32 {
+        self.priorities.get(source as usize).copied().unwrap_or(0)
+    }
 
-        for word in 0..num_words {
-            let active = self.pending[word] & self.enables[ctx][word];
-            if active == 0 {
-                continue;
-            }
-            for bit in 0..32 {
-                let irq_id = word * 32 + bit;
-                if irq_id == 0 {
-                    continue; // IRQ 0 is reserved
-                }
-                if (active & (1 << bit)) != 0
-                    && irq_id < self.priorities.len()
-                    && self.priorities[irq_id] > threshold
-                {
-                    return true;
-                }
-            }
-        }
-        false
+    /// Returns the priority threshold of `ctx` (0 for out-of-range contexts).
+    pub fn threshold(&self, ctx: usize) -> u32 {
+        self.thresholds.get(ctx).copied().unwrap_or(0)
     }
 
-    /// Calculates the ID of the highest priority pending interrupt for a context.
+    /// Returns the IDs of all pending sources, in ascending order.
+    pub fn pending_sources(&self) -> Vec<u32> {
+        bitmap_ids(&self.pending)
+    }
+
+    /// Returns the IDs of all claimed but not yet completed sources.
+    pub fn claimed_sources(&self) -> Vec<u32> {
+        bitmap_ids(&self.claimed)
+    }
+
+    /// Returns the IDs of the sources enabled for `ctx`.
+    pub fn enabled_sources(&self, ctx: usize) -> Vec<u32> {
+        self.enables.get(ctx).map_or_else(Vec::new, |words| bitmap_ids(words))
+    }
+
+    /// Returns whether `source` is enabled for `ctx`.
+    fn is_enabled(&self, ctx: usize, source: u32) -> bool {
+        let (word, bit) = (source as usize / 32, source % 32);
+        self.enables[ctx].get(word).is_some_and(|w| w & (1 << bit) != 0)
+    }
+
+    /// Calculates the ID of the highest priority pending interrupt for a
+    /// context whose priority exceeds the context's threshold (0 = none).
+    /// Ties go to the lowest ID.
     fn calc_max_id(&self, ctx: usize) -> u32 {
         let threshold = self.thresholds[ctx];
-        let num_words = std::cmp::min(self.pending.len(), self.enables[ctx].len());
-
-        let mut max_prio = 0;
+        let mut max_prio = threshold;
         let mut max_id = 0;
 
-        for word in 0..num_words {
-            let active = self.pending[word] & self.enables[ctx][word];
-            if active == 0 {
-                continue;
-            }
-            for bit in 0..32 {
+        for (word, (&pending, &enabled)) in self.pending.iter().zip(&self.enables[ctx]).enumerate()
+        {
+            let mut active = pending & enabled;
+            while active != 0 {
+                let bit = active.trailing_zeros() as usize;
+                active &= active - 1;
                 let irq_id = word * 32 + bit;
-                if irq_id == 0 {
-                    continue;
-                }
-                if (active & (1 << bit)) != 0 && irq_id < self.priorities.len() {
-                    let prio = self.priorities[irq_id];
-                    if prio > max_prio && prio > threshold {
-                        max_prio = prio;
-                        max_id = irq_id as u32;
-                    }
+                let prio = self.priorities[irq_id];
+                if prio > max_prio {
+                    max_prio = prio;
+                    max_id = irq_id as u32;
                 }
             }
         }
         max_id
     }
+
+    /// Claims the best interrupt for `ctx`: clears its pending bit and closes
+    /// its gateway until completion. Returns 0 if nothing qualifies.
+    fn claim(&mut self, ctx: usize) -> u32 {
+        let irq_id = self.calc_max_id(ctx);
+        if irq_id != 0 {
+            let (word, bit) = (irq_id as usize / 32, 1u32 << (irq_id % 32));
+            self.pending[word] &= !bit;
+            self.claimed[word] |= bit;
+            for c in 0..self.num_contexts {
+                self.claims[c] = self.calc_max_id(c);
+            }
+        }
+        irq_id
+    }
+
+    /// Completes `irq_id` for `ctx`, reopening its gateway. Per spec, a
+    /// completion for a source not enabled for the context is ignored.
+    fn complete(&mut self, ctx: usize, irq_id: u32) {
+        if irq_id == 0 || irq_id as usize >= NUM_SOURCES || !self.is_enabled(ctx, irq_id) {
+            return;
+        }
+        self.claimed[irq_id as usize / 32] &= !(1u32 << (irq_id % 32));
+    }
+}
+
+/// Returns the indices of the set bits of a little-endian word bitmap.
+fn bitmap_ids(words: &[u32]) -> Vec<u32> {
+    words
+        .iter()
+        .enumerate()
+        .flat_map(|(w, &bits)| {
+            (0..32).filter(move |b| bits & (1 << b) != 0).map(move |b| (w * 32 + b) as u32)
+        })
+        .collect()
 }
 
 impl Device for Plic {
@@ -183,29 +270,18 @@ impl Device for Plic {
             let rel = (offset - PLIC_ENABLE_BASE) as usize;
             let ctx = rel / 0x80;
             let word_idx = (rel % 0x80) / 4;
-            if ctx < NUM_CONTEXTS && word_idx < ENABLE_WORDS_PER_CONTEXT {
+            if ctx < self.num_contexts && word_idx < ENABLE_WORDS_PER_CONTEXT {
                 return self.enables[ctx][word_idx];
             }
         } else if offset >= PLIC_CONTEXT_BASE {
             let ctx = (offset - PLIC_CONTEXT_BASE) as usize / 0x1000;
             let reg = offset & 0xFFF;
-            if ctx < 2 {
+            if ctx < self.num_contexts {
                 if reg == 0 {
                     return self.thresholds[ctx];
                 }
                 if reg == 4 {
-                    // Claim read: return the highest-priority pending IRQ
-                    // for this context. Per spec, claiming an interrupt
-                    // atomically clears its pending bit.
-                    let irq_id = self.claims[ctx];
-                    if irq_id > 0 && (irq_id as usize) < 1024 {
-                        let idx = irq_id as usize / 32;
-                        let bit = 1u32 << (irq_id % 32);
-                        if idx < self.pending.len() {
-                            self.pending[idx] &= !bit;
-                        }
-                    }
-                    return irq_id;
+                    return self.claim(ctx);
                 }
             }
         }
@@ -219,34 +295,27 @@ impl Device for Plic {
         #[allow(clippy::absurd_extreme_comparisons)]
         if (PLIC_PRIORITY_BASE..PLIC_PENDING_BASE).contains(&offset) {
             let idx = (offset - PLIC_PRIORITY_BASE) as usize / 4;
-            if idx < self.priorities.len() {
+            // Source 0 is reserved: its priority is hardwired to zero.
+            if idx != 0 && idx < self.priorities.len() {
                 self.priorities[idx] = val;
             }
         } else if (PLIC_ENABLE_BASE..PLIC_CONTEXT_BASE).contains(&offset) {
             let rel = (offset - PLIC_ENABLE_BASE) as usize;
             let ctx = rel / 0x80;
             let word_idx = (rel % 0x80) / 4;
-            if ctx < NUM_CONTEXTS && word_idx < ENABLE_WORDS_PER_CONTEXT {
-                self.enables[ctx][word_idx] = val;
+            if ctx < self.num_contexts && word_idx < ENABLE_WORDS_PER_CONTEXT {
+                // Bit 0 (the reserved source 0) is hardwired to zero.
+                self.enables[ctx][word_idx] = if word_idx == 0 { val & !1 } else { val };
             }
         } else if offset >= PLIC_CONTEXT_BASE {
             let ctx = (offset - PLIC_CONTEXT_BASE) as usize / 0x1000;
             let reg = offset & 0xFFF;
-            if ctx < 2 {
+            if ctx < self.num_contexts {
                 if reg == 0 {
                     self.thresholds[ctx] = val;
                 }
                 if reg == 4 {
-                    // Completion write: clear pending bit for the completed IRQ
-                    let irq_id = val;
-                    if irq_id > 0 && (irq_id as usize) < 1024 {
-                        let idx = irq_id as usize / 32;
-                        let bit = 1u32 << (irq_id % 32);
-                        if idx < self.pending.len() {
-                            self.pending[idx] &= !bit;
-                        }
-                    }
-                    self.claims[ctx] = 0;
+                    self.complete(ctx, val);
                 }
             }
         }
@@ -297,6 +366,42 @@ impl Device for Plic {
     }
 
     /// Returns a mutable reference to the PLIC if this device is one.
+    /// Saves priorities, pending and claimed sources, enables, thresholds and claims.
+    fn save_state(&self) -> Option<serde_json::Value> {
+        let state = PlicState {
+            priorities: self.priorities.clone(),
+            pending: self.pending.clone(),
+            claimed: self.claimed.clone(),
+            enables: self.enables.clone(),
+            thresholds: self.thresholds.clone(),
+            claims: self.claims.clone(),
+        };
+        serde_json::to_value(state).ok()
+    }
+
+    /// Restores the state saved by `save_state`; the context and source counts must match.
+    fn restore_state(&mut self, state: serde_json::Value) -> Result<(), String> {
+        let state: PlicState = serde_json::from_value(state).map_err(|e| e.to_string())?;
+        if state.thresholds.len() != self.num_contexts
+            || state.priorities.len() != self.priorities.len()
+        {
+            return Err(format!(
+                "PLIC has {} contexts and {} sources, checkpoint has {} and {}",
+                self.num_contexts,
+                self.priorities.len(),
+                state.thresholds.len(),
+                state.priorities.len()
+            ));
+        }
+        self.priorities = state.priorities;
+        self.pending = state.pending;
+        self.claimed = state.claimed;
+        self.enables = state.enables;
+        self.thresholds = state.thresholds;
+        self.claims = state.claims;
+        Ok(())
+    }
+
     fn as_plic_mut(&mut self) -> Option<&mut Plic> {
         Some(self)
     }
@@ -4,17 +4,33 @@
 //! It is primarily used by the kernel or test environment to gracefully exit
 //! the simulation or trigger a reset.
 //!
+//! Power off and failure set the exit code directly; every command is also
+//! recorded as a [`SysconEvent`] which the simulator polls each cycle, acting
+//! on [`SysconEvent::Reboot`] by restarting the harts from their boot state.
+//!
 //! # Registers
 //!
-//! * `0x00`: Command Register (Write Only)
-//!   * `0x5555`: Power Off
+//! * `0x00`: Command Register (Write Only), laid out like the `SiFive` test
+//!   finisher: bits 15:0 select the command, bits 31:16 carry an exit code.
+//!   * `0x5555`: Power Off (exit code 0)
 //!   * `0x7777`: Reset
-//!   * `0x3333`: Failure/Panic
+//!   * `0x3333`: Failure/Panic (exit code from bits 31:16, 1 if zero)
 
 use crate::soc::devices::Device;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
 
+/// Command received by the `SysCon` device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SysconEvent {
+    /// Power off (`0x5555`); exits with code 0.
+    Poweroff,
+    /// Reboot (`0x7777`); restarts the guest from its boot entry point.
+    Reboot,
+    /// Failure (`0x3333`); exits with the code in bits 31:16, or 1.
+    Failure,
+}
+
 /// `SysCon` device structure.
 #[derive(Debug)]
 pub struct SysCon {
@@ -22,6 +38,8 @@ pub struct SysCon {
     base_addr: u64,
     /// Shared atomic flag to signal the simulation loop to exit.
     exit_signal: Arc<AtomicU64>,
+    /// Last command received and not yet taken by the simulator.
+    event: Option<SysconEvent>,
 }
 
 impl SysCon {
@@ -32,7 +50,12 @@ impl SysCon {
     /// * `base_addr` - The base physical address.
     /// * `exit_signal` - Shared atomic for signaling exit codes.
     pub const fn new(base_addr: u64, exit_signal: Arc<AtomicU64>) -> Self {
-        Self { base_addr, exit_signal }
+        Self { base_addr, exit_signal, event: None }
+    }
+
+    /// Returns and clears the last command received.
+    pub const fn take_event(&mut self) -> Option<SysconEvent> {
+        self.event.take()
     }
 }
 
@@ -74,18 +97,21 @@ impl Device for SysCon {
     /// Interprets specific magic values to trigger system events.
     fn write_u32(&mut self, offset: u64, val: u32) {
         if offset == 0 {
-            match val {
+            match val & 0xFFFF {
                 0x5555 => {
                     println!("[SysCon] Poweroff signal received.");
                     self.exit_signal.store(0, Ordering::Relaxed);
+                    self.event = Some(SysconEvent::Poweroff);
                 }
                 0x7777 => {
-                    println!("[SysCon] Reset signal received (Simulated as Exit).");
-                    self.exit_signal.store(0, Ordering::Relaxed);
+                    println!("[SysCon] Reset signal received.");
+                    self.event = Some(SysconEvent::Reboot);
                 }
                 0x3333 => {
-                    println!("[SysCon] Failure signal received.");
-                    self.exit_signal.store(1, Ordering::Relaxed);
+                    let code = u64::from(val >> 16).max(1);
+                    println!("[SysCon] Failure signal received (code {code}).");
+                    self.exit_signal.store(code, Ordering::Relaxed);
+                    self.event = Some(SysconEvent::Failure);
                 }
                 _ => {}
             }
@@ -96,4 +122,9 @@ impl Device for SysCon {
     fn write_u64(&mut self, offset: u64, val: u64) {
         self.write_u32(offset, val as u32);
     }
+
+    /// Returns a mutable reference to the system controller.
+    fn as_syscon_mut(&mut self) -> Option<&mut SysCon> {
+        Some(self)
+    }
 }
@@ -1,15 +1,30 @@
 //! Universal Asynchronous Receiver-Transmitter (UART).
 //!
 //! Implements a 16550-compatible UART device for serial communication.
-//! Handles standard registers (RBR, THR, IER, IIR, LCR, LSR) and integrates
-//! with stdin/stdout for console I/O.
+//! Handles the standard registers (RBR, THR, IER, IIR, FCR, LCR, MCR, LSR)
+//! and integrates with stdin/stdout for console I/O. It provides:
+//! 1. **FIFOs:** A 16-byte receive FIFO with a programmable trigger level and
+//!    character timeout, enabled through FCR. Transmitted bytes leave at once,
+//!    so the transmit FIFO never fills and THRE is raised after every write.
+//! 2. **Interrupts:** IIR reports the highest-priority source: receiver line
+//!    status, then received data (or character timeout), then THR empty.
+//! 3. **Host I/O:** Bytes from stdin or [`Uart::push_input`] wait in a host
+//!    queue until the receive FIFO has room; transmitted bytes are printed
+//!    and kept for [`Uart::take_output`].
+//! 4. **Loopback:** With MCR loopback set, transmitted bytes are received
+//!    back; a byte arriving at a full FIFO sets the overrun error.
+//! 5. **Serial links:** A [`SerialLink`] end attached with [`Uart::connect`]
+//!    carries transmitted bytes to another UART, possibly in another
+//!    simulator, where they are received after a latency in that UART's ticks.
 
 use crate::common::IrqId;
+use crate::config::MAX_UARTS;
 use crate::soc::devices::Device;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::io::{self, Read, Write};
-use std::sync::Mutex;
-use std::sync::mpsc::{Receiver, channel};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
 use std::thread;
 
 /// Receiver Buffer Register (Read) / Divisor Latch Low (DLAB=1).
@@ -20,8 +35,8 @@ const REG_THR: u64 = 0;
 const REG_IER: u64 = 1;
 /// Interrupt Identity Register (Read).
 const REG_IIR: u64 = 2;
-/// FIFO Control Register (Write) — acknowledged but not implemented.
-const _REG_FCR: u64 = 2;
+/// FIFO Control Register (Write).
+const REG_FCR: u64 = 2;
 /// Line Control Register.
 const REG_LCR: u64 = 3;
 /// Modem Control Register.
@@ -42,12 +57,24 @@ const IIR_THRE: u8 = 0x02;
 /// Interrupt Identity Register: Receiver Data Available interrupt.
 const IIR_RDA: u8 = 0x04;
 
-/// Interrupt Identity Register: Interrupt ID mask (bits 7:6).
-const IIR_ID_MASK: u8 = 0xC0;
+/// Interrupt Identity Register: Receiver Line Status interrupt.
+const IIR_RLS: u8 = 0x06;
+
+/// Interrupt Identity Register: Character Timeout interrupt (FIFO mode).
+const IIR_CTI: u8 = 0x0C;
+
+/// Interrupt Identity Register: FIFOs enabled (bits 7:6).
+const IIR_FIFO_ENABLED: u8 = 0xC0;
 
 /// Line Status Register: Data ready bit (receiver has data).
 const LSR_DATA_READY: u8 = 0x01;
 
+/// Line Status Register: Overrun error (a byte arrived at a full receiver).
+const LSR_OVERRUN: u8 = 0x02;
+
+/// Line Status Register: Error bits that raise a receiver line status interrupt.
+const LSR_ERRORS: u8 = 0x1E;
+
 /// Line Status Register: Transmitter Holding Register Empty.
 const LSR_THRE: u8 = 0x20;
 
@@ -66,47 +93,208 @@ const IER_RDA: u8 = 0x01;
 /// Interrupt Enable Register: Transmitter Holding Register Empty interrupt enable.
 const IER_THRE: u8 = 0x02;
 
+/// Interrupt Enable Register: Receiver Line Status interrupt enable.
+const IER_RLS: u8 = 0x04;
+
+/// FIFO Control Register: Enable both FIFOs.
+const FCR_ENABLE: u8 = 0x01;
+
+/// FIFO Control Register: Clear the receive FIFO (self-clearing).
+const FCR_CLEAR_RX: u8 = 0x02;
+
+/// FIFO Control Register: Clear the transmit FIFO (self-clearing; the FIFO is always empty).
+const FCR_CLEAR_TX: u8 = 0x04;
+
+/// FIFO Control Register: Receive trigger level field (bits 7:6).
+const FCR_TRIGGER_SHIFT: u8 = 6;
+
+/// Modem Control Register: Loopback mode.
+const MCR_LOOPBACK: u8 = 0x10;
+
+/// Depth of the receive FIFO in bytes.
+const FIFO_DEPTH: usize = 16;
+
+/// Receive FIFO trigger levels selected by FCR bits 7:6.
+const RX_TRIGGER_LEVELS: [usize; 4] = [1, 4, 8, 14];
+
+/// Ticks without receive activity before a character timeout is signalled.
+const RX_TIMEOUT_TICKS: u32 = 256;
+
+/// Maximum transmitted bytes kept for [`Uart::take_output`]; older bytes are dropped.
+pub(crate) const OUTPUT_CAPTURE_LIMIT: usize = 1 << 20;
+
+/// Default PLIC source driven by the UART.
+const UART_IRQ: u32 = 10;
+
+/// Device names of the UARTs, by index; `UART0` is the console.
+const UART_NAMES: [&str; MAX_UARTS] = ["UART0", "UART1", "UART2", "UART3"];
+
+/// One end of a host-side serial link between two UARTs.
+///
+/// Ends are made in pairs by [`SerialLink::pair`] and attached with
+/// [`Uart::connect`]; each carries the bytes its UART transmits to the other.
+/// A byte is delivered `latency` ticks of the receiving UART after the
+/// receiver first sees it, so each side's own stepping advances delivery and
+/// the two simulators may run on separate threads.
+#[derive(Debug)]
+pub struct SerialLink {
+    /// Bytes transmitted at this end and not yet seen by the other.
+    tx: Arc<Mutex<VecDeque<u8>>>,
+    /// Bytes transmitted at the other end and not yet seen by this one.
+    rx: Arc<Mutex<VecDeque<u8>>>,
+    /// Receiver ticks from a byte being seen to its delivery.
+    latency: u64,
+    /// Seen bytes with the tick at which they are delivered.
+    in_flight: VecDeque<(u64, u8)>,
+    /// Ticks of the UART holding this end.
+    ticks: u64,
+}
+
+impl SerialLink {
+    /// Creates the two connected ends of a link with the given latency in cycles.
+    pub fn pair(latency: u64) -> (Self, Self) {
+        let a = Arc::new(Mutex::new(VecDeque::new()));
+        let b = Arc::new(Mutex::new(VecDeque::new()));
+        let end = |tx, rx| Self { tx, rx, latency, in_flight: VecDeque::new(), ticks: 0 };
+        (end(Arc::clone(&a), Arc::clone(&b)), end(b, a))
+    }
+
+    /// Returns the delivery latency in receiver ticks.
+    pub const fn latency(&self) -> u64 {
+        self.latency
+    }
+
+    /// Sends a byte to the other end.
+    fn send(&self, byte: u8) {
+        self.tx.lock().unwrap_or_else(PoisonError::into_inner).push_back(byte);
+    }
+
+    /// Advances one tick and appends the bytes now due to `out`.
+    fn tick(&mut self, out: &mut VecDeque<u8>) {
+        let deliver_at = self.ticks + self.latency;
+        self.in_flight.extend(
+            self.rx
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .drain(..)
+                .map(|b| (deliver_at, b)),
+        );
+        while let Some(&(at, byte)) = self.in_flight.front()
+            && at <= self.ticks
+        {
+            out.push_back(byte);
+            let _ = self.in_flight.pop_front();
+        }
+        self.ticks += 1;
+    }
+}
+
+/// Returns a receiver for the bytes read from the host's stdin.
+///
+/// A single background thread reads stdin for the whole process and sends
+/// every byte to every receiver, so the console UART and the HTIF console
+/// (and the consoles of several simulators) see the same input.
+pub fn host_stdin() -> Receiver<u8> {
+    static SUBSCRIBERS: OnceLock<Mutex<Vec<Sender<u8>>>> = OnceLock::new();
+    let mut spawn = false;
+    let subscribers = SUBSCRIBERS.get_or_init(|| {
+        spawn = true;
+        Mutex::new(Vec::new())
+    });
+    let (tx, rx) = channel();
+    subscribers.lock().unwrap_or_else(PoisonError::into_inner).push(tx);
+    if spawn {
+        let _ = thread::spawn(move || {
+            let mut buffer = [0u8; 1];
+            let stdin = io::stdin();
+            let mut handle = stdin.lock();
+            while handle.read_exact(&mut buffer).is_ok() {
+                subscribers
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .retain(|tx| tx.send(buffer[0]).is_ok());
+            }
+        });
+    }
+    rx
+}
+
 /// UART device structure.
 ///
-/// Simulates a 16550 UART. It spawns a background thread to capture `stdin`
-/// for input and writes output directly to `stdout`.
+/// Simulates a 16550 UART. The console (`UART0`) takes its input from the
+/// host's `stdin` (see [`host_stdin`]); every UART writes output directly to `stdout`.
 #[derive(Debug)]
 #[allow(clippy::struct_excessive_bools)]
 pub struct Uart {
     /// Base physical address of the device.
     base_addr: u64,
-    /// Queue for received bytes (from stdin).
-    rx_queue: VecDeque<u8>,
+    /// PLIC source the device raises.
+    irq: u32,
+    /// Position among the system's UARTs; selects the device name.
+    index: usize,
+    /// Receive FIFO (a single holding byte when FIFOs are disabled).
+    rx_fifo: VecDeque<u8>,
+    /// Host bytes (stdin or injected) waiting for room in the receive FIFO.
+    host_input: VecDeque<u8>,
+    /// Transmitted bytes not yet collected by [`Uart::take_output`].
+    output: VecDeque<u8>,
     /// Channel receiver for stdin thread. Wrapped in Mutex for Sync.
     rx_receiver: Mutex<Receiver<u8>>,
     /// Interrupt Enable Register.
     ier: u8,
+    /// FIFO Control Register (enable and trigger bits).
+    fcr: u8,
     /// Line Control Register.
     lcr: u8,
     /// Modem Control Register.
     mcr: u8,
+    /// Line status error bits, cleared when LSR is read.
+    lsr_errors: u8,
     /// Scratch Register.
     scr: u8,
     /// Divisor Latch (Baud Rate).
     div: u16,
     /// Internal tick counter for polling stdin.
     tick_count: u8,
+    /// Ticks since a byte last entered or left the receive FIFO.
+    rx_idle: u32,
     /// Transmitter Holding Register Empty Interrupt Pending.
     thre_ip: bool,
     /// When true, output goes to stderr (for visibility when run from Python).
     to_stderr: bool,
     /// When true, all output is suppressed (for scripting / benchmarks).
     quiet: bool,
+    /// Link to another UART, if connected.
+    link: Option<SerialLink>,
     /// State machine index for panic detection.
     panic_match_state: usize,
     /// Flag indicating if a kernel panic string was detected.
     panic_detected: bool,
 }
 
+/// UART registers and FIFOs saved in a checkpoint.
+#[derive(Serialize, Deserialize)]
+struct UartState {
+    #[serde(with = "crate::common::hex")]
+    rx_fifo: Vec<u8>,
+    #[serde(with = "crate::common::hex")]
+    host_input: Vec<u8>,
+    ier: u8,
+    fcr: u8,
+    lcr: u8,
+    mcr: u8,
+    lsr_errors: u8,
+    scr: u8,
+    div: u16,
+    tick_count: u8,
+    rx_idle: u32,
+    thre_ip: bool,
+}
+
 impl Uart {
-    /// Creates a new UART device.
+    /// Creates the console UART (`UART0`).
     ///
-    /// Spawns a background thread to read from stdin.
+    /// Receives the bytes read from the host's stdin.
     ///
     /// # Arguments
     ///
@@ -114,50 +302,147 @@ impl Uart {
     /// * `to_stderr` - When true, write output to stderr instead of stdout (for Python API).
     /// * `quiet` - When true, all output is discarded (for scripting / benchmarks).
     pub fn new(base_addr: u64, to_stderr: bool, quiet: bool) -> Self {
-        let (tx, rx) = channel();
+        Self::with_index(base_addr, 0, to_stderr, quiet)
+    }
 
-        let _ = thread::spawn(move || {
-            let mut buffer = [0u8; 1];
-            let stdin = io::stdin();
-            let mut handle = stdin.lock();
-            while handle.read_exact(&mut buffer).is_ok() {
-                let _ = tx.send(buffer[0]);
-            }
-        });
+    /// Creates the UART at position `index` among the system's UARTs, named
+    /// `UART<index>`.
+    ///
+    /// Only the console (index 0) reads stdin; the others receive input
+    /// through [`Uart::push_input`] alone.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not below [`MAX_UARTS`].
+    pub fn with_index(base_addr: u64, index: usize, to_stderr: bool, quiet: bool) -> Self {
+        assert!(index < MAX_UARTS, "UART index {index} out of range");
+        let rx = if index == 0 { host_stdin() } else { channel().1 };
 
         Self {
             base_addr,
-            rx_queue: VecDeque::new(),
+            irq: UART_IRQ,
+            index,
+            rx_fifo: VecDeque::new(),
+            host_input: VecDeque::new(),
+            output: VecDeque::new(),
             rx_receiver: Mutex::new(rx),
             ier: 0,
+            fcr: 0,
             lcr: 0,
             mcr: 0,
+            lsr_errors: 0,
             scr: 0,
             div: 0,
             tick_count: 0,
+            rx_idle: 0,
             thre_ip: true,
             to_stderr,
             quiet,
+            link: None,
             panic_match_state: 0,
             panic_detected: false,
         }
     }
 
-    /// Polls the stdin receiver and populates the RX queue.
+    /// Sets the PLIC source the device raises.
+    pub const fn set_irq(&mut self, irq: u32) {
+        self.irq = irq;
+    }
+
+    /// Returns the position among the system's UARTs (0 for the console).
+    pub const fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Queues bytes for the guest to receive, as if typed on the console.
+    ///
+    /// Bytes move into the receive FIFO as it has room, so none are lost.
+    pub fn push_input(&mut self, bytes: &[u8]) {
+        self.host_input.extend(bytes);
+        self.fill_rx_fifo();
+    }
+
+    /// Attaches one end of a serial link, replacing any previous link.
+    ///
+    /// Transmitted bytes are sent over the link (as well as captured and
+    /// printed as usual), and bytes from the other end join the host input.
+    /// In loopback mode nothing is sent, as on real hardware.
+    pub fn connect(&mut self, link: SerialLink) {
+        self.link = Some(link);
+    }
+
+    /// Detaches the serial link, dropping bytes still in flight to this UART.
+    pub fn disconnect(&mut self) {
+        self.link = None;
+    }
+
+    /// Returns whether a serial link is attached.
+    pub const fn is_connected(&self) -> bool {
+        self.link.is_some()
+    }
+
+    /// Removes and returns the bytes the guest has transmitted since the last call.
+    ///
+    /// Bytes are kept whether or not output is printed or suppressed; at most
+    /// the last 1 MiB is retained.
+    pub fn take_output(&mut self) -> Vec<u8> {
+        self.output.drain(..).collect()
+    }
+
+    /// Polls the stdin receiver and queues its bytes for the receive FIFO.
     fn check_stdin(&mut self) {
         if let Ok(rx) = self.rx_receiver.lock() {
             while let Ok(byte) = rx.try_recv() {
-                self.rx_queue.push_back(byte);
+                self.host_input.push_back(byte);
             }
         }
     }
 
+    /// Returns whether the FIFOs are enabled (16550 mode).
+    const fn fifo_enabled(&self) -> bool {
+        (self.fcr & FCR_ENABLE) != 0
+    }
+
+    /// Returns the receive capacity: the FIFO depth, or one holding byte.
+    const fn rx_capacity(&self) -> usize {
+        if self.fifo_enabled() { FIFO_DEPTH } else { 1 }
+    }
+
+    /// Returns the receive FIFO level that raises a data-available interrupt.
+    const fn rx_trigger(&self) -> usize {
+        if self.fifo_enabled() {
+            RX_TRIGGER_LEVELS[(self.fcr >> FCR_TRIGGER_SHIFT) as usize]
+        } else {
+            1
+        }
+    }
+
+    /// Moves waiting host bytes into the receive FIFO while it has room.
+    fn fill_rx_fifo(&mut self) {
+        while self.rx_fifo.len() < self.rx_capacity() {
+            let Some(byte) = self.host_input.pop_front() else {
+                break;
+            };
+            self.rx_fifo.push_back(byte);
+            self.rx_idle = 0;
+        }
+    }
+
     /// Calculates the Interrupt Identity Register (IIR) value.
     ///
     /// Determines the highest priority pending interrupt.
     fn update_interrupts(&self) -> u8 {
-        if (self.ier & IER_RDA) != 0 && !self.rx_queue.is_empty() {
-            return IIR_RDA;
+        if (self.ier & IER_RLS) != 0 && (self.lsr_errors & LSR_ERRORS) != 0 {
+            return IIR_RLS;
+        }
+
+        if (self.ier & IER_RDA) != 0 && !self.rx_fifo.is_empty() {
+            if self.rx_fifo.len() >= self.rx_trigger() {
+                return IIR_RDA;
+            }
+            if self.rx_idle >= RX_TIMEOUT_TICKS {
+                return IIR_CTI;
+            }
         }
 
         if (self.ier & IER_THRE) != 0 && self.thre_ip {
@@ -206,7 +491,10 @@ impl Uart {
         if self.dlab_set() {
             (self.div & 0xFF) as u8
         } else {
-            self.rx_queue.pop_front().unwrap_or(0)
+            let byte = self.rx_fifo.pop_front().unwrap_or(0);
+            self.rx_idle = 0;
+            self.fill_rx_fifo();
+            byte
         }
     }
 
@@ -225,17 +513,19 @@ impl Uart {
         if iir == IIR_THRE {
             self.thre_ip = false;
         }
-        IIR_ID_MASK | iir
+        if self.fifo_enabled() { IIR_FIFO_ENABLED | iir } else { iir }
     }
 
     /// Reads Line Status Register (LSR).
     ///
-    /// Indicates if data is ready or if the transmitter is empty.
-    fn read_lsr(&self) -> u8 {
-        let mut lsr = LSR_DEFAULT;
-        if !self.rx_queue.is_empty() {
+    /// Indicates if data is ready or if the transmitter is empty. Reading
+    /// clears the error bits.
+    fn read_lsr(&mut self) -> u8 {
+        let mut lsr = LSR_DEFAULT | self.lsr_errors;
+        if !self.rx_fifo.is_empty() {
             lsr |= LSR_DATA_READY;
         }
+        self.lsr_errors = 0;
         lsr
     }
 
@@ -246,10 +536,31 @@ impl Uart {
         if self.dlab_set() {
             self.div = (self.div & 0xFF00) | (val as u16);
         } else {
+            self.thre_ip = true;
+
+            if (self.mcr & MCR_LOOPBACK) != 0 {
+                if self.rx_fifo.len() < self.rx_capacity() {
+                    self.rx_fifo.push_back(val);
+                    self.rx_idle = 0;
+                } else {
+                    self.lsr_errors |= LSR_OVERRUN;
+                }
+                return;
+            }
+
+            if let Some(link) = &self.link {
+                link.send(val);
+            }
+
             if self.check_char_for_panic(val) {
                 return;
             }
 
+            if self.output.len() == OUTPUT_CAPTURE_LIMIT {
+                let _ = self.output.pop_front();
+            }
+            self.output.push_back(val);
+
             // Real hardware: each byte is shifted out on the wire as soon
             // as the transmit shift register finishes. There is no software
             // buffering — the character is visible immediately. We match
@@ -263,9 +574,19 @@ impl Uart {
                     let _ = io::stdout().flush();
                 }
             }
+        }
+    }
 
-            self.thre_ip = true;
+    /// Writes FIFO Control Register (FCR).
+    ///
+    /// Toggling the enable bit clears both FIFOs; the clear bits self-clear.
+    fn write_fcr(&mut self, val: u8) {
+        if (val ^ self.fcr) & FCR_ENABLE != 0 || (val & FCR_CLEAR_RX) != 0 {
+            self.rx_fifo.clear();
+            self.rx_idle = 0;
         }
+        self.fcr = val & !(FCR_CLEAR_RX | FCR_CLEAR_TX);
+        self.fill_rx_fifo();
     }
 
     /// Writes Interrupt Enable Register (IER) or Divisor Latch High (DLM).
@@ -286,7 +607,7 @@ impl Uart {
 impl Device for Uart {
     /// Returns the device name.
     fn name(&self) -> &'static str {
-        "UART0"
+        UART_NAMES[self.index]
     }
     /// Returns the address range (Base, Size).
     fn address_range(&self) -> (u64, u64) {
@@ -325,6 +646,7 @@ impl Device for Uart {
         match offset {
             REG_THR => self.write_thr_or_dll(val),
             REG_IER => self.write_ier_or_dlm(val),
+            REG_FCR => self.write_fcr(val),
             REG_LCR => self.lcr = val,
             REG_MCR => self.mcr = val,
             REG_SCR => self.scr = val,
@@ -347,12 +669,23 @@ impl Device for Uart {
 
     /// Advances the device state.
     ///
-    /// Polls stdin periodically and returns true if an interrupt is pending.
+    /// Polls stdin periodically and the serial link every tick, refills the
+    /// receive FIFO, advances the character timeout, and returns true if an
+    /// interrupt is pending.
     fn tick(&mut self) -> bool {
         self.tick_count = self.tick_count.wrapping_add(1);
         if self.tick_count == 0 {
             self.check_stdin();
         }
+        if let Some(link) = &mut self.link {
+            link.tick(&mut self.host_input);
+        }
+        if !self.host_input.is_empty() {
+            self.fill_rx_fifo();
+        }
+        if !self.rx_fifo.is_empty() {
+            self.rx_idle = self.rx_idle.saturating_add(1);
+        }
 
         let iir = self.update_interrupts();
         (iir & IIR_NO_INTERRUPT) == 0
@@ -360,10 +693,47 @@ impl Device for Uart {
 
     /// Returns the Interrupt Request (IRQ) ID associated with this device.
     fn get_irq_id(&self) -> Option<IrqId> {
-        Some(IrqId::new(10))
+        Some(IrqId::new(self.irq))
     }
 
     /// Returns a mutable reference to the UART if this device is one.
+    /// Saves the registers, the receive FIFO and host input not yet received.
+    fn save_state(&self) -> Option<serde_json::Value> {
+        let state = UartState {
+            rx_fifo: self.rx_fifo.iter().copied().collect(),
+            host_input: self.host_input.iter().copied().collect(),
+            ier: self.ier,
+            fcr: self.fcr,
+            lcr: self.lcr,
+            mcr: self.mcr,
+            lsr_errors: self.lsr_errors,
+            scr: self.scr,
+            div: self.div,
+            tick_count: self.tick_count,
+            rx_idle: self.rx_idle,
+            thre_ip: self.thre_ip,
+        };
+        serde_json::to_value(state).ok()
+    }
+
+    /// Restores the registers and FIFOs saved by `save_state`.
+    fn restore_state(&mut self, state: serde_json::Value) -> Result<(), String> {
+        let state: UartState = serde_json::from_value(state).map_err(|e| e.to_string())?;
+        self.rx_fifo = state.rx_fifo.into();
+        self.host_input = state.host_input.into();
+        self.ier = state.ier;
+        self.fcr = state.fcr;
+        self.lcr = state.lcr;
+        self.mcr = state.mcr;
+        self.lsr_errors = state.lsr_errors;
+        self.scr = state.scr;
+        self.div = state.div;
+        self.tick_count = state.tick_count;
+        self.rx_idle = state.rx_idle;
+        self.thre_ip = state.thre_ip;
+        Ok(())
+    }
+
     fn as_uart_mut(&mut self) -> Option<&mut Uart> {
         Some(self)
     }
@@ -2,10 +2,29 @@
 //!
 //! Implements a `VirtIO` block device over Memory-Mapped I/O (MMIO) for disk access.
 //! Supports the legacy `VirtIO` interface required by the Linux kernel.
+//!
+//! Writes go straight to the disk image unless a write cache is configured with
+//! [`VirtioBlock::set_write_cache`]. Cached sectors are acknowledged at once and
+//! written back, oldest first, when the cache is full or on `VIRTIO_BLK_T_FLUSH`.
+//! Every sector written to the image costs `write_latency` cycles, during which
+//! the request's completion (used ring entry and interrupt) is held back.
+//!
+//! The image may be shared with other devices (see [`VirtioBlock::load_shared`]);
+//! the first write to a shared image gives this device its own copy. In
+//! copy-on-write mode ([`VirtioBlock::set_copy_on_write`]) the image is never
+//! modified: written sectors go to a per-device overlay, which
+//! [`VirtioBlock::export_overlay`] saves as a sparse diff file. Otherwise the
+//! sectors written since the image was loaded are tracked, so a checkpoint can
+//! save them without saving the whole image.
 
 use crate::common::IrqId;
 use crate::soc::devices::Device;
 use crate::soc::memory::buffer::DramBuffer;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::io::{self, Write};
+use std::path::Path;
 use std::sync::Arc;
 
 /// `VirtIO` MMIO magic value register offset.
@@ -116,6 +135,61 @@ const VRING_DESC_F_WRITE: u16 = 2;
 /// Disk sector size in bytes (512 bytes per sector).
 const SECTOR_SIZE: u64 = 512;
 
+/// Request type: read sectors into the guest buffers.
+const VIRTIO_BLK_T_IN: u32 = 0;
+
+/// Request type: write the guest buffers to sectors.
+const VIRTIO_BLK_T_OUT: u32 = 1;
+
+/// Request type: make every completed write durable.
+const VIRTIO_BLK_T_FLUSH: u32 = 4;
+
+/// Request status: success.
+const VIRTIO_BLK_S_OK: u8 = 0;
+
+/// Request status: request type not supported.
+const VIRTIO_BLK_S_UNSUPP: u8 = 2;
+
+/// Feature bit (low word): the device supports `VIRTIO_BLK_T_FLUSH`.
+pub const VIRTIO_BLK_F_FLUSH: u32 = 1 << 9;
+
+/// Magic bytes opening an overlay diff file written by [`VirtioBlock::export_overlay`].
+pub const OVERLAY_MAGIC: &[u8; 8] = b"RVSIMCOW";
+
+/// Default PLIC source driven by the device.
+const BLK_IRQ: u32 = 1;
+
+/// Write cache activity counts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DiskWriteStats {
+    /// Sectors written into the write cache (acknowledged before reaching the image).
+    pub cached_sectors: u64,
+    /// Sectors written directly to the image (no write cache).
+    pub write_through_sectors: u64,
+    /// Cached sectors written back to the image.
+    pub flushed_sectors: u64,
+    /// `VIRTIO_BLK_T_FLUSH` requests completed.
+    pub flush_requests: u64,
+}
+
+impl DiskWriteStats {
+    /// Adds the counts in `other` to these.
+    pub const fn add(&mut self, other: &Self) {
+        self.cached_sectors += other.cached_sectors;
+        self.write_through_sectors += other.write_through_sectors;
+        self.flushed_sectors += other.flushed_sectors;
+        self.flush_requests += other.flush_requests;
+    }
+
+    /// Returns whether every count is zero.
+    pub const fn is_empty(&self) -> bool {
+        self.cached_sectors == 0
+            && self.write_through_sectors == 0
+            && self.flushed_sectors == 0
+            && self.flush_requests == 0
+    }
+}
+
 /// `VirtIO` Block device structure.
 ///
 /// Implements a memory-mapped block device compliant with the `VirtIO` specification.
@@ -125,10 +199,12 @@ const SECTOR_SIZE: u64 = 512;
 pub struct VirtioBlock {
     /// Base physical address of the device MMIO region.
     base_addr: u64,
+    /// PLIC source the device raises.
+    irq: u32,
     /// Base physical address of system RAM.
     ram_base: u64,
-    /// Disk image data.
-    disk_image: Vec<u8>,
+    /// Disk image data, possibly shared with other devices.
+    disk_image: Arc<Vec<u8>>,
     /// Shared reference to system RAM for DMA.
     ram: Arc<DramBuffer>,
 
@@ -163,6 +239,63 @@ pub struct VirtioBlock {
     device_features_sel: u32,
     /// Driver features selection.
     driver_features_sel: u32,
+
+    /// DMA writes (address, length) not yet reported to the bus.
+    dma_writes: Vec<(u64, u64)>,
+
+    /// Write cache capacity in sectors (0 = write-through).
+    write_cache_sectors: usize,
+    /// Cycles to write one sector to the image.
+    write_latency: u64,
+    /// Cached sector contents not yet written to the image, by sector number.
+    write_cache: BTreeMap<u64, Vec<u8>>,
+    /// Cached sector numbers, oldest first.
+    write_cache_order: VecDeque<u64>,
+    /// Cycles until the queued completions are published.
+    busy_cycles: u64,
+    /// (head descriptor, bytes written) of requests waiting for `busy_cycles`.
+    completions: Vec<(u16, u32)>,
+    /// Write cache activity since the last [`VirtioBlock::take_write_stats`].
+    write_stats: DiskWriteStats,
+    /// Sectors written since the image was loaded, by sector number, when in
+    /// copy-on-write mode.
+    overlay: Option<BTreeMap<u64, Vec<u8>>>,
+    /// Sectors written to the image since it was loaded, when not in
+    /// copy-on-write mode.
+    written: BTreeSet<u64>,
+}
+
+/// One disk sector, hex-encoded in a checkpoint.
+#[derive(Serialize, Deserialize)]
+struct SectorState {
+    sector: u64,
+    #[serde(with = "crate::common::hex")]
+    data: Vec<u8>,
+}
+
+/// Device registers, queue position, pending completions and disk changes
+/// saved in a checkpoint.
+#[derive(Serialize, Deserialize)]
+struct DiskState {
+    image_len: usize,
+    status: u32,
+    queue_num: u32,
+    queue_ready: u32,
+    queue_notify: u32,
+    queue_desc: (u32, u32),
+    queue_avail: (u32, u32),
+    queue_used: (u32, u32),
+    interrupt_status: u32,
+    last_avail_idx: u16,
+    device_features_sel: u32,
+    driver_features_sel: u32,
+    busy_cycles: u64,
+    completions: Vec<(u16, u32)>,
+    /// Sectors changed from the loaded image: the overlay, or the written
+    /// sectors of a write-through image.
+    changed: Vec<SectorState>,
+    /// Write cache contents, oldest first.
+    write_cache: Vec<SectorState>,
 }
 
 unsafe impl Send for VirtioBlock {}
@@ -176,11 +309,12 @@ impl VirtioBlock {
     /// * `base_addr` - MMIO base address.
     /// * `ram_base` - System RAM base address.
     /// * `ram` - Shared DRAM buffer for DMA access.
-    pub const fn new(base_addr: u64, ram_base: u64, ram: Arc<DramBuffer>) -> Self {
+    pub fn new(base_addr: u64, ram_base: u64, ram: Arc<DramBuffer>) -> Self {
         Self {
             base_addr,
+            irq: BLK_IRQ,
             ram_base,
-            disk_image: Vec::new(),
+            disk_image: Arc::new(Vec::new()),
             ram,
             status: 0,
             queue_num: 0,
@@ -196,7 +330,140 @@ impl VirtioBlock {
             last_avail_idx: 0,
             device_features_sel: 0,
             driver_features_sel: 0,
+            dma_writes: Vec::new(),
+            write_cache_sectors: 0,
+            write_latency: 0,
+            write_cache: BTreeMap::new(),
+            write_cache_order: VecDeque::new(),
+            busy_cycles: 0,
+            completions: Vec::new(),
+            write_stats: DiskWriteStats {
+                cached_sectors: 0,
+                write_through_sectors: 0,
+                flushed_sectors: 0,
+                flush_requests: 0,
+            },
+            overlay: None,
+            written: BTreeSet::new(),
+        }
+    }
+
+    /// Sets the PLIC source the device raises.
+    pub const fn set_irq(&mut self, irq: u32) {
+        self.irq = irq;
+    }
+
+    /// Configures the write cache and the cost of writing to the image.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity_sectors` - Sectors the cache holds before writing back the
+    ///   oldest; 0 writes every sector straight to the image.
+    /// * `write_latency` - Cycles to write one sector to the image.
+    pub const fn set_write_cache(&mut self, capacity_sectors: usize, write_latency: u64) {
+        self.write_cache_sectors = capacity_sectors;
+        self.write_latency = write_latency;
+    }
+
+    /// Enables or disables copy-on-write mode, discarding any overlay.
+    ///
+    /// In copy-on-write mode the loaded image is left untouched and sectors
+    /// written to it are kept in an overlay instead.
+    pub fn set_copy_on_write(&mut self, enabled: bool) {
+        self.overlay = enabled.then(BTreeMap::new);
+    }
+
+    /// Returns whether copy-on-write mode is enabled.
+    pub const fn copy_on_write(&self) -> bool {
+        self.overlay.is_some()
+    }
+
+    /// Returns the number of sectors held in the copy-on-write overlay.
+    pub fn overlay_sectors(&self) -> usize {
+        self.overlay.as_ref().map_or(0, BTreeMap::len)
+    }
+
+    /// Returns the image as loaded, without the overlay or write cache.
+    pub const fn base_image(&self) -> &Arc<Vec<u8>> {
+        &self.disk_image
+    }
+
+    /// Returns the disk contents, excluding sectors still held in the write cache.
+    pub fn image(&self) -> Cow<'_, [u8]> {
+        match &self.overlay {
+            Some(overlay) if !overlay.is_empty() => {
+                let mut data = self.disk_image.to_vec();
+                Self::apply_sectors(overlay, 0, &mut data);
+                Cow::Owned(data)
+            }
+            _ => Cow::Borrowed(&self.disk_image),
+        }
+    }
+
+    /// Returns the contents of `sector` as the guest would read it, or `None`
+    /// past the end of the disk.
+    pub fn read_sector(&self, sector: u64) -> Option<Vec<u8>> {
+        let start = usize::try_from(sector.checked_mul(SECTOR_SIZE)?).ok()?;
+        let end = (start.checked_add(SECTOR_SIZE as usize)?).min(self.disk_image.len());
+        (start < end).then(|| self.read_disk(start, end - start))
+    }
+
+    /// Replaces the contents of `sector` from the host, as an immediate write
+    /// to the image (or overlay) that takes no time and is not counted.
+    ///
+    /// Returns false, writing nothing, if `sector` is past the end of the disk
+    /// or `data` is not exactly its size.
+    pub fn write_sector(&mut self, sector: u64, data: &[u8]) -> bool {
+        if self.read_sector(sector).is_none_or(|old| old.len() != data.len()) {
+            return false;
         }
+        if self.write_cache.remove(&sector).is_some() {
+            self.write_cache_order.retain(|&s| s != sector);
+        }
+        self.write_image((sector * SECTOR_SIZE) as usize, data);
+        true
+    }
+
+    /// Writes the copy-on-write overlay to `path` as a sparse diff.
+    ///
+    /// The file holds [`OVERLAY_MAGIC`], the sector size and the sector count
+    /// as little-endian `u64`s, then for each overlay sector in ascending
+    /// order its number (`u64`) and contents, zero-padded to a full sector.
+    /// Sectors still in the write cache are not included.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error creating or writing the file.
+    pub fn export_overlay(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let empty = BTreeMap::new();
+        let overlay = self.overlay.as_ref().unwrap_or(&empty);
+        let mut out = io::BufWriter::new(std::fs::File::create(path)?);
+        out.write_all(OVERLAY_MAGIC)?;
+        out.write_all(&SECTOR_SIZE.to_le_bytes())?;
+        out.write_all(&(overlay.len() as u64).to_le_bytes())?;
+        for (sector, contents) in overlay {
+            let mut padded = contents.clone();
+            padded.resize(SECTOR_SIZE as usize, 0);
+            out.write_all(&sector.to_le_bytes())?;
+            out.write_all(&padded)?;
+        }
+        out.flush()
+    }
+
+    /// Returns the number of sectors waiting in the write cache.
+    pub fn dirty_sectors(&self) -> usize {
+        self.write_cache.len()
+    }
+
+    /// Returns and clears the write cache activity counts.
+    pub fn take_write_stats(&mut self) -> DiskWriteStats {
+        std::mem::take(&mut self.write_stats)
+    }
+
+    /// Returns whether any write cache activity was counted since the last
+    /// [`VirtioBlock::take_write_stats`].
+    pub const fn has_write_stats(&self) -> bool {
+        !self.write_stats.is_empty()
     }
 
     /// Loads a disk image into the device.
@@ -205,7 +472,20 @@ impl VirtioBlock {
     ///
     /// * `data` - The raw bytes of the disk image.
     pub fn load(&mut self, data: Vec<u8>) {
-        self.disk_image = data;
+        self.load_shared(Arc::new(data));
+    }
+
+    /// Loads a disk image that other devices may share, discarding any overlay.
+    ///
+    /// # Arguments
+    ///
+    /// * `image` - The raw bytes of the disk image.
+    pub fn load_shared(&mut self, image: Arc<Vec<u8>>) {
+        self.disk_image = image;
+        if let Some(overlay) = &mut self.overlay {
+            overlay.clear();
+        }
+        self.written.clear();
     }
 
     /// Performs a Direct Memory Access (DMA) read from system RAM.
@@ -257,7 +537,7 @@ impl VirtioBlock {
     ///
     /// * `addr` - Physical address to write to.
     /// * `data` - Bytes to write.
-    fn dma_write(&self, addr: u64, data: &[u8]) {
+    fn dma_write(&mut self, addr: u64, data: &[u8]) {
         if addr < self.ram_base {
             println!("[VirtIO] DMA Write Out of Bounds (Low): 0x{addr:x}");
             return;
@@ -274,6 +554,7 @@ impl VirtioBlock {
         }
 
         self.ram.write_slice(offset, data);
+        self.dma_writes.push((addr, data.len() as u64));
     }
 
     /// Processes the `VirtQueue`.
@@ -287,7 +568,6 @@ impl VirtioBlock {
 
         let desc_addr = ((self.queue_desc_high as u64) << 32) | (self.queue_desc_low as u64);
         let avail_addr = ((self.queue_avail_high as u64) << 32) | (self.queue_avail_low as u64);
-        let used_addr = ((self.queue_used_high as u64) << 32) | (self.queue_used_low as u64);
 
         let avail_idx = self.dma_read_u16(avail_addr + 2);
 
@@ -331,7 +611,8 @@ impl VirtioBlock {
             }
 
             let mut len_written = 0;
-            if descriptors.len() >= 3 {
+            let mut latency = 0;
+            if descriptors.len() >= 2 {
                 let (h_addr, _, _) = descriptors[0];
                 let header = self.dma_read(h_addr, 16);
                 let type_val = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
@@ -339,61 +620,178 @@ impl VirtioBlock {
                     header[8], header[9], header[10], header[11], header[12], header[13],
                     header[14], header[15],
                 ]);
-                let is_write = type_val == 1;
-                let is_flush = type_val == 4;
 
                 let (s_addr, _, _) = descriptors[descriptors.len() - 1];
+                let data_descs = &descriptors[1..descriptors.len() - 1];
 
                 let sector_offset = (sector * SECTOR_SIZE) as usize;
                 let mut current_offset = 0;
+                let mut status = VIRTIO_BLK_S_OK;
 
-                if is_flush {
-                    // FLUSH: no data transfer, just acknowledge
-                } else if is_write {
-                    let mut current_disk_offset = sector_offset;
-
-                    for (d_addr, d_len, _) in &descriptors[1..descriptors.len() - 1] {
-                        let data = self.dma_read(*d_addr, *d_len as usize);
-                        if current_disk_offset + data.len() <= self.disk_image.len() {
-                            self.disk_image[current_disk_offset..current_disk_offset + data.len()]
-                                .copy_from_slice(&data);
+                match type_val {
+                    VIRTIO_BLK_T_FLUSH => {
+                        while !self.write_cache_order.is_empty() {
+                            latency += self.write_back_oldest();
                         }
-                        current_disk_offset += *d_len as usize;
-                        len_written += *d_len;
+                        self.write_stats.flush_requests += 1;
                     }
-                } else {
-                    for (d_addr, d_len, d_flags) in &descriptors[1..descriptors.len() - 1] {
-                        if (d_flags & VRING_DESC_F_WRITE) != 0
-                            && sector_offset + current_offset < self.disk_image.len()
-                        {
-                            let available =
-                                self.disk_image.len() - (sector_offset + current_offset);
-                            let copy_len = std::cmp::min(*d_len as usize, available);
-                            self.dma_write(
-                                *d_addr,
-                                &self.disk_image[sector_offset + current_offset
-                                    ..sector_offset + current_offset + copy_len],
-                            );
-                            len_written += copy_len as u32;
+                    VIRTIO_BLK_T_OUT => {
+                        let mut data = Vec::new();
+                        for (d_addr, d_len, _) in data_descs {
+                            data.extend(self.dma_read(*d_addr, *d_len as usize));
+                        }
+                        latency += self.write_disk(sector_offset, &data);
+                        len_written = data.len() as u32;
+                    }
+                    VIRTIO_BLK_T_IN => {
+                        for (d_addr, d_len, d_flags) in data_descs {
+                            if (d_flags & VRING_DESC_F_WRITE) != 0
+                                && sector_offset + current_offset < self.disk_image.len()
+                            {
+                                let available =
+                                    self.disk_image.len() - (sector_offset + current_offset);
+                                let copy_len = std::cmp::min(*d_len as usize, available);
+                                let start = sector_offset + current_offset;
+                                let sector = self.read_disk(start, copy_len);
+                                self.dma_write(*d_addr, &sector);
+                                len_written += copy_len as u32;
+                            }
+                            current_offset += *d_len as usize;
                         }
-                        current_offset += *d_len as usize;
                     }
+                    _ => status = VIRTIO_BLK_S_UNSUPP,
                 }
 
-                self.dma_write(s_addr, &[0]);
+                self.dma_write(s_addr, &[status]);
             }
 
-            let used_idx_addr = used_addr + 2;
-            let current_used = self.dma_read_u16(used_idx_addr);
-            let used_elem = used_addr + 4 + (current_used as u64 % self.queue_num as u64) * 8;
-
-            self.dma_write(used_elem, &u32::from(head_idx).to_le_bytes());
-            self.dma_write(used_elem + 4, &len_written.to_le_bytes());
-            self.dma_write(used_idx_addr, &current_used.wrapping_add(1).to_le_bytes());
+            self.busy_cycles += latency;
+            if self.busy_cycles == 0 {
+                self.publish(head_idx, len_written);
+            } else {
+                self.completions.push((head_idx, len_written));
+            }
 
             self.last_avail_idx = self.last_avail_idx.wrapping_add(1);
         }
-        self.interrupt_status |= 1;
+        if self.completions.is_empty() {
+            self.interrupt_status |= 1;
+        }
+    }
+
+    /// Adds a used ring entry for the request headed by `head_idx`.
+    fn publish(&mut self, head_idx: u16, len_written: u32) {
+        let used_addr = ((self.queue_used_high as u64) << 32) | (self.queue_used_low as u64);
+        let used_idx_addr = used_addr + 2;
+        let current_used = self.dma_read_u16(used_idx_addr);
+        let used_elem = used_addr + 4 + (current_used as u64 % self.queue_num as u64) * 8;
+
+        self.dma_write(used_elem, &u32::from(head_idx).to_le_bytes());
+        self.dma_write(used_elem + 4, &len_written.to_le_bytes());
+        self.dma_write(used_idx_addr, &current_used.wrapping_add(1).to_le_bytes());
+    }
+
+    /// Copies the parts of `sectors` that fall within `data`, which holds the
+    /// disk bytes starting at `offset`.
+    fn apply_sectors(sectors: &BTreeMap<u64, Vec<u8>>, offset: usize, data: &mut [u8]) {
+        let len = data.len();
+        let first = offset as u64 / SECTOR_SIZE;
+        let last = (offset + len).div_ceil(SECTOR_SIZE as usize) as u64;
+        for (&sector, contents) in sectors.range(first..last) {
+            let start = (sector * SECTOR_SIZE) as usize;
+            for (i, &byte) in contents.iter().enumerate() {
+                if (offset..offset + len).contains(&(start + i)) {
+                    data[start + i - offset] = byte;
+                }
+            }
+        }
+    }
+
+    /// Reads `len` bytes of the disk at byte `offset`, as updated by the
+    /// overlay and any cached sectors.
+    fn read_disk(&self, offset: usize, len: usize) -> Vec<u8> {
+        let mut data = self.disk_image[offset..offset + len].to_vec();
+        if let Some(overlay) = &self.overlay {
+            Self::apply_sectors(overlay, offset, &mut data);
+        }
+        Self::apply_sectors(&self.write_cache, offset, &mut data);
+        data
+    }
+
+    /// Stores `data` at byte `offset` of the image, or in the overlay in
+    /// copy-on-write mode. The range must lie within the image.
+    fn write_image(&mut self, offset: usize, data: &[u8]) {
+        let sector_size = SECTOR_SIZE as usize;
+        let end = offset + data.len();
+        if self.overlay.is_none() {
+            Arc::make_mut(&mut self.disk_image)[offset..end].copy_from_slice(data);
+            self.written.extend((offset / sector_size) as u64..end.div_ceil(sector_size) as u64);
+            return;
+        }
+        for sector in offset / sector_size..end.div_ceil(sector_size) {
+            let start = sector * sector_size;
+            let sector_end = (start + sector_size).min(self.disk_image.len());
+            let mut contents = self.disk_image[start..sector_end].to_vec();
+            if let Some(overlay) = &self.overlay {
+                Self::apply_sectors(overlay, start, &mut contents);
+            }
+            let from = offset.max(start);
+            let to = end.min(sector_end);
+            contents[from - start..to - start].copy_from_slice(&data[from - offset..to - offset]);
+            if let Some(overlay) = &mut self.overlay {
+                let _ = overlay.insert(sector as u64, contents);
+            }
+        }
+    }
+
+    /// Writes `data` at byte `offset` of the disk, through the write cache if one
+    /// is configured. Bytes beyond the end of the image are dropped.
+    ///
+    /// Returns the cycles spent writing sectors to the image.
+    fn write_disk(&mut self, offset: usize, data: &[u8]) -> u64 {
+        let end = (offset + data.len()).min(self.disk_image.len());
+        if offset >= end {
+            return 0;
+        }
+        let sector_size = SECTOR_SIZE as usize;
+        let sectors = (offset / sector_size) as u64..end.div_ceil(sector_size) as u64;
+
+        if self.write_cache_sectors == 0 {
+            self.write_image(offset, &data[..end - offset]);
+            let n = sectors.end - sectors.start;
+            self.write_stats.write_through_sectors += n;
+            return n * self.write_latency;
+        }
+
+        let mut latency = 0;
+        for sector in sectors {
+            let start = sector as usize * sector_size;
+            let sector_end = (start + sector_size).min(self.disk_image.len());
+            let mut contents = self.read_disk(start, sector_end - start);
+            let from = offset.max(start);
+            let to = end.min(sector_end);
+            contents[from - start..to - start].copy_from_slice(&data[from - offset..to - offset]);
+            if self.write_cache.insert(sector, contents).is_none() {
+                self.write_cache_order.push_back(sector);
+            }
+            self.write_stats.cached_sectors += 1;
+            while self.write_cache.len() > self.write_cache_sectors {
+                latency += self.write_back_oldest();
+            }
+        }
+        latency
+    }
+
+    /// Writes the oldest cached sector to the image.
+    ///
+    /// Returns the cycles spent.
+    fn write_back_oldest(&mut self) -> u64 {
+        let Some(sector) = self.write_cache_order.pop_front() else { return 0 };
+        if let Some(contents) = self.write_cache.remove(&sector) {
+            self.write_image((sector * SECTOR_SIZE) as usize, &contents);
+        }
+        self.write_stats.flushed_sectors += 1;
+        self.write_latency
     }
 }
 
@@ -417,13 +815,11 @@ impl Device for VirtioBlock {
             REG_VERSION => VIRTIO_VERSION_VALUE,
             REG_DEVICE_ID => VIRTIO_MMIO_DEVICE_ID_VALUE,
             REG_VENDOR_ID => VIRTIO_MMIO_VENDOR_ID_VALUE,
-            REG_DEVICE_FEATURES => {
-                if self.device_features_sel == 1 {
-                    1
-                } else {
-                    0
-                }
-            }
+            REG_DEVICE_FEATURES => match self.device_features_sel {
+                0 => VIRTIO_BLK_F_FLUSH,
+                1 => 1,
+                _ => 0,
+            },
             REG_QUEUE_NUM_MAX => QUEUE_NUM_MAX_VALUE,
             REG_QUEUE_READY => self.queue_ready,
             REG_INTERRUPT_STATUS => self.interrupt_status,
@@ -493,15 +889,121 @@ impl Device for VirtioBlock {
         self.write_u32(offset, val as u32);
     }
 
-    /// Advances the device state.
+    /// Advances the device state, publishing held-back completions once the
+    /// image writes they wait for are done.
     ///
     /// Returns true if an interrupt is pending.
     fn tick(&mut self) -> bool {
+        if self.busy_cycles > 0 {
+            self.busy_cycles -= 1;
+            if self.busy_cycles == 0 {
+                for (head_idx, len_written) in std::mem::take(&mut self.completions) {
+                    self.publish(head_idx, len_written);
+                }
+                self.interrupt_status |= 1;
+            }
+        }
         (self.interrupt_status & 1) != 0
     }
 
     /// Returns the Interrupt Request (IRQ) ID associated with this device.
     fn get_irq_id(&self) -> Option<IrqId> {
-        Some(IrqId::new(1))
+        Some(IrqId::new(self.irq))
+    }
+
+    /// Reports the used-ring and data-buffer writes made while processing requests.
+    fn take_dma_writes(&mut self, out: &mut Vec<(u64, u64)>) {
+        out.append(&mut self.dma_writes);
+    }
+
+    /// Saves the registers, the queue position, completions held back by
+    /// image writes, the write cache and the sectors changed from the loaded
+    /// image (the overlay in copy-on-write mode).
+    fn save_state(&self) -> Option<serde_json::Value> {
+        let sector = |sector: u64, data: &[u8]| SectorState { sector, data: data.to_vec() };
+        let changed = self.overlay.as_ref().map_or_else(
+            || {
+                let size = SECTOR_SIZE as usize;
+                let len = self.disk_image.len();
+                self.written
+                    .iter()
+                    .map(|&s| {
+                        let start = s as usize * size;
+                        sector(s, &self.disk_image[start..(start + size).min(len)])
+                    })
+                    .collect()
+            },
+            |overlay| overlay.iter().map(|(&s, data)| sector(s, data)).collect(),
+        );
+        let state = DiskState {
+            image_len: self.disk_image.len(),
+            status: self.status,
+            queue_num: self.queue_num,
+            queue_ready: self.queue_ready,
+            queue_notify: self.queue_notify,
+            queue_desc: (self.queue_desc_low, self.queue_desc_high),
+            queue_avail: (self.queue_avail_low, self.queue_avail_high),
+            queue_used: (self.queue_used_low, self.queue_used_high),
+            interrupt_status: self.interrupt_status,
+            last_avail_idx: self.last_avail_idx,
+            device_features_sel: self.device_features_sel,
+            driver_features_sel: self.driver_features_sel,
+            busy_cycles: self.busy_cycles,
+            completions: self.completions.clone(),
+            changed,
+            write_cache: self
+                .write_cache_order
+                .iter()
+                .filter_map(|s| self.write_cache.get(s).map(|data| sector(*s, data)))
+                .collect(),
+        };
+        serde_json::to_value(state).ok()
+    }
+
+    /// Restores the state saved by `save_state` onto the same disk image as
+    /// loaded, writing the changed sectors back into it (or its overlay).
+    fn restore_state(&mut self, state: serde_json::Value) -> Result<(), String> {
+        let state: DiskState = serde_json::from_value(state).map_err(|e| e.to_string())?;
+        if state.image_len != self.disk_image.len() {
+            return Err(format!(
+                "disk image is {} bytes, checkpoint was taken with {}",
+                self.disk_image.len(),
+                state.image_len
+            ));
+        }
+        self.status = state.status;
+        self.queue_num = state.queue_num;
+        self.queue_ready = state.queue_ready;
+        self.queue_notify = state.queue_notify;
+        (self.queue_desc_low, self.queue_desc_high) = state.queue_desc;
+        (self.queue_avail_low, self.queue_avail_high) = state.queue_avail;
+        (self.queue_used_low, self.queue_used_high) = state.queue_used;
+        self.interrupt_status = state.interrupt_status;
+        self.last_avail_idx = state.last_avail_idx;
+        self.device_features_sel = state.device_features_sel;
+        self.driver_features_sel = state.driver_features_sel;
+        self.busy_cycles = state.busy_cycles;
+        self.completions = state.completions;
+        if let Some(overlay) = &mut self.overlay {
+            overlay.clear();
+        }
+        for s in state.changed {
+            let start = s.sector as usize * SECTOR_SIZE as usize;
+            if start + s.data.len() > self.disk_image.len() {
+                return Err(format!("sector {} is past the end of the disk", s.sector));
+            }
+            self.write_image(start, &s.data);
+        }
+        self.write_cache.clear();
+        self.write_cache_order.clear();
+        for s in state.write_cache {
+            self.write_cache_order.push_back(s.sector);
+            let _ = self.write_cache.insert(s.sector, s.data);
+        }
+        Ok(())
+    }
+
+    fn as_virtio_blk_mut(&mut self) -> Option<&mut VirtioBlock> {
+        Some(self)
     }
 }
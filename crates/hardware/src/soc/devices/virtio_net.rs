@@ -0,0 +1,683 @@
+//! VirtIO Network Device (MMIO).
+//!
+//! Implements a `VirtIO` network device over Memory-Mapped I/O (MMIO). The
+//! device exposes a receive queue (0) and a transmit queue (1); every frame
+//! is preceded in guest memory by a 12-byte `virtio_net_hdr`. Frames are
+//! exchanged with a host-side backend:
+//! 1. **Capture:** Transmitted frames are queued for the host to collect and
+//!    only host-injected frames are received.
+//! 2. **Loopback:** Transmitted frames are received back by the guest.
+//! 3. **TAP:** Frames are exchanged with a host TAP interface (Linux only).
+//!
+//! Host-injected frames (see [`VirtioNet::inject_frame`]) are received under
+//! every backend.
+
+use crate::common::IrqId;
+use crate::config::NetBackend;
+use crate::soc::devices::Device;
+use crate::soc::memory::buffer::DramBuffer;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+
+/// `VirtIO` MMIO magic value register offset.
+const REG_MAGIC: u64 = 0x00;
+
+/// `VirtIO` MMIO version register offset.
+const REG_VERSION: u64 = 0x04;
+
+/// `VirtIO` MMIO device ID register offset.
+const REG_DEVICE_ID: u64 = 0x08;
+
+/// `VirtIO` MMIO vendor ID register offset.
+const REG_VENDOR_ID: u64 = 0x0c;
+
+/// `VirtIO` MMIO device features register offset.
+const REG_DEVICE_FEATURES: u64 = 0x10;
+
+/// `VirtIO` MMIO device features select register offset.
+const REG_DEVICE_FEATURES_SEL: u64 = 0x14;
+
+/// `VirtIO` MMIO driver features select register offset.
+const REG_DRIVER_FEATURES_SEL: u64 = 0x24;
+
+/// `VirtIO` MMIO queue select register offset.
+const REG_QUEUE_SEL: u64 = 0x30;
+
+/// `VirtIO` MMIO queue maximum size register offset.
+const REG_QUEUE_NUM_MAX: u64 = 0x34;
+
+/// `VirtIO` MMIO queue size register offset.
+const REG_QUEUE_NUM: u64 = 0x38;
+
+/// `VirtIO` MMIO queue ready register offset.
+const REG_QUEUE_READY: u64 = 0x44;
+
+/// `VirtIO` MMIO queue notify register offset.
+const REG_QUEUE_NOTIFY: u64 = 0x50;
+
+/// `VirtIO` MMIO interrupt status register offset.
+const REG_INTERRUPT_STATUS: u64 = 0x60;
+
+/// `VirtIO` MMIO interrupt acknowledge register offset.
+const REG_INTERRUPT_ACK: u64 = 0x64;
+
+/// `VirtIO` MMIO device status register offset.
+const REG_STATUS: u64 = 0x70;
+
+/// `VirtIO` MMIO queue descriptor table address (low 32 bits) register offset.
+const REG_QUEUE_DESC_LOW: u64 = 0x80;
+
+/// `VirtIO` MMIO queue descriptor table address (high 32 bits) register offset.
+const REG_QUEUE_DESC_HIGH: u64 = 0x84;
+
+/// `VirtIO` MMIO queue available ring address (low 32 bits) register offset.
+const REG_QUEUE_AVAIL_LOW: u64 = 0x90;
+
+/// `VirtIO` MMIO queue available ring address (high 32 bits) register offset.
+const REG_QUEUE_AVAIL_HIGH: u64 = 0x94;
+
+/// `VirtIO` MMIO queue used ring address (low 32 bits) register offset.
+const REG_QUEUE_USED_LOW: u64 = 0xa0;
+
+/// `VirtIO` MMIO queue used ring address (high 32 bits) register offset.
+const REG_QUEUE_USED_HIGH: u64 = 0xa4;
+
+/// `VirtIO` MMIO configuration space base offset.
+const REG_CONFIG_BASE: u64 = 0x100;
+
+/// `VirtIO` MMIO magic value ("virt" in ASCII: 0x74726976).
+const VIRTIO_MMIO_MAGIC_VALUE: u32 = 0x74726976;
+
+/// `VirtIO` MMIO vendor ID value (QEMU vendor: 0x554d4551).
+const VIRTIO_MMIO_VENDOR_ID_VALUE: u32 = 0x554d4551;
+
+/// `VirtIO` MMIO device ID for network device (1).
+const VIRTIO_MMIO_DEVICE_ID_VALUE: u32 = 1;
+
+/// `VirtIO` specification version (2).
+const VIRTIO_VERSION_VALUE: u32 = 2;
+
+/// Feature bit 5 (word 0): the device reports its MAC address in config space.
+const VIRTIO_NET_F_MAC: u32 = 1 << 5;
+
+/// Feature bit 32 (bit 0 of word 1): `VIRTIO_F_VERSION_1`.
+const VIRTIO_F_VERSION_1: u32 = 1;
+
+/// Maximum queue size supported by this device (16 entries).
+const QUEUE_NUM_MAX_VALUE: u32 = 16;
+
+/// Index of the receive queue.
+const RX_QUEUE: usize = 0;
+
+/// Index of the transmit queue.
+const TX_QUEUE: usize = 1;
+
+/// Size of a virtqueue descriptor in bytes (16 bytes).
+const DESC_SIZE: u64 = 16;
+
+/// Virtqueue descriptor flag: indicates chained descriptors (more descriptors follow).
+const VRING_DESC_F_NEXT: u16 = 1;
+
+/// Virtqueue descriptor flag: indicates write-only descriptor (device writes to memory).
+const VRING_DESC_F_WRITE: u16 = 2;
+
+/// Size of `virtio_net_hdr` with `VIRTIO_F_VERSION_1` (includes `num_buffers`).
+pub const NET_HDR_SIZE: usize = 12;
+
+/// Default MAC address (QEMU's locally administered 52:54:00 prefix).
+pub const DEFAULT_MAC: [u8; 6] = [0x52, 0x54, 0x00, 0x12, 0x34, 0x56];
+
+/// Maximum frames held for the guest before further frames are dropped.
+const RX_BACKLOG_LIMIT: usize = 256;
+
+/// Cycles between polls of a TAP interface for incoming frames.
+const TAP_POLL_INTERVAL: u32 = 1024;
+
+/// Default PLIC source driven by the device.
+const NET_IRQ: u32 = 2;
+
+/// A descriptor chain as (addr, len, flags) triples.
+type Chain = Vec<(u64, u32, u16)>;
+
+/// State of one virtqueue as programmed by the driver.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+struct Virtqueue {
+    /// Configured queue size.
+    num: u32,
+    /// Queue ready bit.
+    ready: u32,
+    /// Descriptor table address.
+    desc: u64,
+    /// Available ring address.
+    avail: u64,
+    /// Used ring address.
+    used: u64,
+    /// Last processed available index.
+    last_avail_idx: u16,
+}
+
+/// An Ethernet frame, hex-encoded in a checkpoint.
+#[derive(Serialize, Deserialize)]
+struct Frame(#[serde(with = "crate::common::hex")] Vec<u8>);
+
+/// Device registers, queue positions and undelivered frames saved in a checkpoint.
+#[derive(Serialize, Deserialize)]
+struct NetState {
+    status: u32,
+    device_features_sel: u32,
+    driver_features_sel: u32,
+    queue_sel: usize,
+    queues: [Virtqueue; 2],
+    interrupt_status: u32,
+    rx_pending: Vec<Frame>,
+}
+
+/// Host side of the device.
+#[derive(Debug)]
+enum Backend {
+    /// Transmitted frames are kept for [`VirtioNet::take_tx_frames`].
+    Capture,
+    /// Transmitted frames are queued for reception.
+    Loopback,
+    /// Frames are exchanged with a host TAP interface.
+    Tap(TapInterface),
+}
+
+/// A host TAP interface opened in non-blocking mode.
+#[derive(Debug)]
+struct TapInterface {
+    /// The `/dev/net/tun` handle bound to the interface.
+    file: std::fs::File,
+    /// Cycles until the next poll for incoming frames.
+    countdown: u32,
+}
+
+impl TapInterface {
+    /// Opens `/dev/net/tun` and attaches it to the TAP interface `name`.
+    #[cfg(target_os = "linux")]
+    fn open(name: &str) -> io::Result<Self> {
+        use std::os::unix::fs::OpenOptionsExt;
+        use std::os::unix::io::AsRawFd;
+
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open("/dev/net/tun")?;
+
+        // struct ifreq: a 16-byte interface name followed by a union whose
+        // first member is the `short` flags word.
+        let mut ifreq = [0u8; 40];
+        let bytes = name.as_bytes();
+        if bytes.len() >= libc::IFNAMSIZ {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "interface name too long"));
+        }
+        ifreq[..bytes.len()].copy_from_slice(bytes);
+        let flags = (libc::IFF_TAP | libc::IFF_NO_PI) as libc::c_short;
+        ifreq[libc::IFNAMSIZ..libc::IFNAMSIZ + 2].copy_from_slice(&flags.to_ne_bytes());
+
+        // SAFETY: `ifreq` is a live buffer at least as large as `struct ifreq`.
+        let ret = unsafe { libc::ioctl(file.as_raw_fd(), libc::TUNSETIFF, ifreq.as_mut_ptr()) };
+        if ret < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { file, countdown: 0 })
+    }
+
+    /// TAP interfaces are only supported on Linux.
+    #[cfg(not(target_os = "linux"))]
+    fn open(_name: &str) -> io::Result<Self> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "TAP backend requires Linux"))
+    }
+
+    /// Reads every frame currently waiting on the interface.
+    fn poll(&mut self, out: &mut VecDeque<Vec<u8>>) {
+        let mut buf = vec![0u8; 65536];
+        while out.len() < RX_BACKLOG_LIMIT {
+            match self.file.read(&mut buf) {
+                Ok(n) if n > 0 => out.push_back(buf[..n].to_vec()),
+                _ => break,
+            }
+        }
+    }
+
+    /// Writes one frame to the interface; frames the host cannot accept are dropped.
+    fn send(&mut self, frame: &[u8]) {
+        let _ = self.file.write(frame);
+    }
+}
+
+/// `VirtIO` Network device structure.
+///
+/// Implements a memory-mapped network device compliant with the `VirtIO`
+/// specification. Frame buffers are read and written in guest RAM through a
+/// shared DRAM buffer.
+#[derive(Debug)]
+pub struct VirtioNet {
+    /// Base physical address of the device MMIO region.
+    base_addr: u64,
+    /// PLIC source the device raises.
+    irq: u32,
+    /// Base physical address of system RAM.
+    ram_base: u64,
+    /// Shared reference to system RAM for DMA.
+    ram: Arc<DramBuffer>,
+    /// MAC address reported in configuration space.
+    mac: [u8; 6],
+    /// Host side of the device.
+    backend: Backend,
+
+    /// Device status register.
+    status: u32,
+    /// Device features selection.
+    device_features_sel: u32,
+    /// Driver features selection.
+    driver_features_sel: u32,
+    /// Queue selected for configuration.
+    queue_sel: usize,
+    /// Receive and transmit queues.
+    queues: [Virtqueue; 2],
+    /// Interrupt status register.
+    interrupt_status: u32,
+
+    /// Frames waiting for a receive buffer.
+    rx_pending: VecDeque<Vec<u8>>,
+    /// Frames transmitted by the guest (capture backend only).
+    tx_frames: Vec<Vec<u8>>,
+
+    /// DMA writes (address, length) not yet reported to the bus.
+    dma_writes: Vec<(u64, u64)>,
+}
+
+unsafe impl Send for VirtioNet {}
+unsafe impl Sync for VirtioNet {}
+
+impl VirtioNet {
+    /// Creates a new `VirtIO` Network device.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_addr` - MMIO base address.
+    /// * `ram_base` - System RAM base address.
+    /// * `ram` - Shared DRAM buffer for DMA access.
+    /// * `backend` - Host side of the device.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a TAP interface cannot be opened.
+    pub fn new(
+        base_addr: u64,
+        ram_base: u64,
+        ram: Arc<DramBuffer>,
+        backend: &NetBackend,
+    ) -> io::Result<Self> {
+        let backend = match backend {
+            NetBackend::Capture => Backend::Capture,
+            NetBackend::Loopback => Backend::Loopback,
+            NetBackend::Tap(name) => Backend::Tap(TapInterface::open(name)?),
+        };
+        Ok(Self {
+            base_addr,
+            irq: NET_IRQ,
+            ram_base,
+            ram,
+            mac: DEFAULT_MAC,
+            backend,
+            status: 0,
+            device_features_sel: 0,
+            driver_features_sel: 0,
+            queue_sel: 0,
+            queues: [Virtqueue::default(); 2],
+            interrupt_status: 0,
+            rx_pending: VecDeque::new(),
+            tx_frames: Vec::new(),
+            dma_writes: Vec::new(),
+        })
+    }
+
+    /// Sets the PLIC source the device raises.
+    pub const fn set_irq(&mut self, irq: u32) {
+        self.irq = irq;
+    }
+
+    /// Returns the MAC address reported to the guest.
+    pub const fn mac(&self) -> [u8; 6] {
+        self.mac
+    }
+
+    /// Queues an Ethernet frame for delivery to the guest.
+    ///
+    /// The frame is written into the next receive buffer the driver posts.
+    /// Frames beyond the backlog limit are dropped.
+    pub fn inject_frame(&mut self, frame: Vec<u8>) {
+        if self.rx_pending.len() < RX_BACKLOG_LIMIT {
+            self.rx_pending.push_back(frame);
+        }
+    }
+
+    /// Returns the number of frames waiting for a receive buffer.
+    pub fn rx_pending(&self) -> usize {
+        self.rx_pending.len()
+    }
+
+    /// Removes and returns the frames transmitted by the guest, oldest first.
+    ///
+    /// Only the capture backend records frames; the others forward them.
+    pub fn take_tx_frames(&mut self) -> Vec<Vec<u8>> {
+        std::mem::take(&mut self.tx_frames)
+    }
+
+    /// Resets the device to its power-on state (driver wrote 0 to Status).
+    fn reset(&mut self) {
+        self.status = 0;
+        self.device_features_sel = 0;
+        self.driver_features_sel = 0;
+        self.queue_sel = 0;
+        self.queues = [Virtqueue::default(); 2];
+        self.interrupt_status = 0;
+    }
+
+    /// Reads `len` bytes of guest RAM at `addr`; out-of-range reads return zeros.
+    fn dma_read(&self, addr: u64, len: usize) -> Vec<u8> {
+        if addr < self.ram_base {
+            return vec![0; len];
+        }
+        let offset = (addr - self.ram_base) as usize;
+        if offset.checked_add(len).is_none_or(|end| end > self.ram.len()) {
+            return vec![0; len];
+        }
+        self.ram.read_slice(offset, len).to_vec()
+    }
+
+    fn dma_read_u16(&self, addr: u64) -> u16 {
+        let b = self.dma_read(addr, 2);
+        u16::from_le_bytes([b[0], b[1]])
+    }
+
+    fn dma_read_u32(&self, addr: u64) -> u32 {
+        let b = self.dma_read(addr, 4);
+        u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+    }
+
+    fn dma_read_u64(&self, addr: u64) -> u64 {
+        let b = self.dma_read(addr, 8);
+        u64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]])
+    }
+
+    /// Writes `data` to guest RAM at `addr`; out-of-range writes are dropped.
+    fn dma_write(&mut self, addr: u64, data: &[u8]) {
+        if addr < self.ram_base {
+            return;
+        }
+        let offset = (addr - self.ram_base) as usize;
+        if offset.checked_add(data.len()).is_none_or(|end| end > self.ram.len()) {
+            return;
+        }
+        self.ram.write_slice(offset, data);
+        self.dma_writes.push((addr, data.len() as u64));
+    }
+
+    /// Pops the next available descriptor chain of queue `q`.
+    ///
+    /// Returns the head index and the chain's (addr, len, flags) triples, or
+    /// `None` if the driver has not posted a new chain.
+    fn pop_chain(&mut self, q: usize) -> Option<(u16, Chain)> {
+        let queue = self.queues[q];
+        if queue.num == 0 || queue.ready == 0 {
+            return None;
+        }
+        let avail_idx = self.dma_read_u16(queue.avail + 2);
+        if queue.last_avail_idx == avail_idx {
+            return None;
+        }
+        let ring_offset = 4 + (u64::from(queue.last_avail_idx) % u64::from(queue.num)) * 2;
+        let head = self.dma_read_u16(queue.avail + ring_offset);
+        self.queues[q].last_avail_idx = queue.last_avail_idx.wrapping_add(1);
+
+        let mut chain = Vec::new();
+        let mut idx = head;
+        // A chain never has more descriptors than the queue; the bound stops
+        // a malformed (cyclic) chain.
+        while u32::from(idx) < queue.num && chain.len() < queue.num as usize {
+            let desc = queue.desc + u64::from(idx) * DESC_SIZE;
+            let addr = self.dma_read_u64(desc);
+            let len = self.dma_read_u32(desc + 8);
+            let flags = self.dma_read_u16(desc + 12);
+            chain.push((addr, len, flags));
+            if flags & VRING_DESC_F_NEXT == 0 {
+                break;
+            }
+            idx = self.dma_read_u16(desc + 14);
+        }
+        Some((head, chain))
+    }
+
+    /// Returns a chain to the driver through the used ring of queue `q`.
+    fn push_used(&mut self, q: usize, head: u16, len: u32) {
+        let queue = self.queues[q];
+        let used_idx_addr = queue.used + 2;
+        let current_used = self.dma_read_u16(used_idx_addr);
+        let elem = queue.used + 4 + (u64::from(current_used) % u64::from(queue.num)) * 8;
+        self.dma_write(elem, &u32::from(head).to_le_bytes());
+        self.dma_write(elem + 4, &len.to_le_bytes());
+        self.dma_write(used_idx_addr, &current_used.wrapping_add(1).to_le_bytes());
+        self.interrupt_status |= 1;
+    }
+
+    /// Sends every frame the driver has posted on the transmit queue.
+    fn process_tx(&mut self) {
+        while let Some((head, chain)) = self.pop_chain(TX_QUEUE) {
+            let mut packet = Vec::new();
+            for &(addr, len, flags) in &chain {
+                if flags & VRING_DESC_F_WRITE == 0 {
+                    packet.extend_from_slice(&self.dma_read(addr, len as usize));
+                }
+            }
+            if packet.len() > NET_HDR_SIZE {
+                let frame = packet.split_off(NET_HDR_SIZE);
+                match &mut self.backend {
+                    Backend::Capture => self.tx_frames.push(frame),
+                    Backend::Loopback => self.inject_frame(frame),
+                    Backend::Tap(tap) => tap.send(&frame),
+                }
+            }
+            self.push_used(TX_QUEUE, head, 0);
+        }
+    }
+
+    /// Copies pending frames into the receive buffers the driver has posted.
+    fn process_rx(&mut self) {
+        while !self.rx_pending.is_empty() {
+            let Some((head, chain)) = self.pop_chain(RX_QUEUE) else {
+                break;
+            };
+            let Some(frame) = self.rx_pending.pop_front() else {
+                break;
+            };
+            let mut packet = vec![0u8; NET_HDR_SIZE];
+            // num_buffers = 1: the frame occupies a single chain.
+            packet[10] = 1;
+            packet.extend_from_slice(&frame);
+
+            let mut written = 0;
+            for &(addr, len, flags) in &chain {
+                if written == packet.len() {
+                    break;
+                }
+                if flags & VRING_DESC_F_WRITE != 0 {
+                    let n = (len as usize).min(packet.len() - written);
+                    let data = packet[written..written + n].to_vec();
+                    self.dma_write(addr, &data);
+                    written += n;
+                }
+            }
+            self.push_used(RX_QUEUE, head, written as u32);
+        }
+    }
+}
+
+impl Device for VirtioNet {
+    /// Returns the device name.
+    fn name(&self) -> &'static str {
+        "VirtIO-Net"
+    }
+
+    /// Returns the address range (Base, Size).
+    fn address_range(&self) -> (u64, u64) {
+        (self.base_addr, 0x1000)
+    }
+
+    /// Reads a word (32-bit) from the device.
+    ///
+    /// Handles reads from `VirtIO` MMIO registers and the MAC address in
+    /// configuration space.
+    fn read_u32(&mut self, offset: u64) -> u32 {
+        let queue = &self.queues[self.queue_sel];
+        match offset {
+            REG_MAGIC => VIRTIO_MMIO_MAGIC_VALUE,
+            REG_VERSION => VIRTIO_VERSION_VALUE,
+            REG_DEVICE_ID => VIRTIO_MMIO_DEVICE_ID_VALUE,
+            REG_VENDOR_ID => VIRTIO_MMIO_VENDOR_ID_VALUE,
+            REG_DEVICE_FEATURES => match self.device_features_sel {
+                0 => VIRTIO_NET_F_MAC,
+                1 => VIRTIO_F_VERSION_1,
+                _ => 0,
+            },
+            REG_QUEUE_NUM_MAX => QUEUE_NUM_MAX_VALUE,
+            REG_QUEUE_READY => queue.ready,
+            REG_INTERRUPT_STATUS => self.interrupt_status,
+            REG_STATUS => self.status,
+            _ if (REG_CONFIG_BASE..REG_CONFIG_BASE + 8).contains(&offset) => {
+                let mut config = [0u8; 8];
+                config[..6].copy_from_slice(&self.mac);
+                let i = (offset - REG_CONFIG_BASE) as usize & !3;
+                u32::from_le_bytes([config[i], config[i + 1], config[i + 2], config[i + 3]])
+            }
+            _ => 0,
+        }
+    }
+
+    /// Writes a word (32-bit) to the device.
+    ///
+    /// Handles writes to `VirtIO` MMIO registers (Status, Queue configuration, Notify).
+    fn write_u32(&mut self, offset: u64, val: u32) {
+        let q = self.queue_sel;
+        let set_low = |reg: &mut u64| *reg = (*reg & !0xffff_ffff) | u64::from(val);
+        let set_high = |reg: &mut u64| *reg = (*reg & 0xffff_ffff) | (u64::from(val) << 32);
+        match offset {
+            REG_DEVICE_FEATURES_SEL => self.device_features_sel = val,
+            REG_DRIVER_FEATURES_SEL => self.driver_features_sel = val,
+            REG_QUEUE_SEL if (val as usize) < self.queues.len() => self.queue_sel = val as usize,
+            REG_QUEUE_NUM => self.queues[q].num = val.min(QUEUE_NUM_MAX_VALUE),
+            REG_QUEUE_READY => self.queues[q].ready = val,
+            REG_QUEUE_NOTIFY => match val as usize {
+                RX_QUEUE => self.process_rx(),
+                TX_QUEUE => self.process_tx(),
+                _ => {}
+            },
+            REG_INTERRUPT_ACK => self.interrupt_status &= !val,
+            REG_STATUS => {
+                if val == 0 {
+                    self.reset();
+                } else {
+                    self.status = val;
+                }
+            }
+            REG_QUEUE_DESC_LOW => set_low(&mut self.queues[q].desc),
+            REG_QUEUE_DESC_HIGH => set_high(&mut self.queues[q].desc),
+            REG_QUEUE_AVAIL_LOW => set_low(&mut self.queues[q].avail),
+            REG_QUEUE_AVAIL_HIGH => set_high(&mut self.queues[q].avail),
+            REG_QUEUE_USED_LOW => set_low(&mut self.queues[q].used),
+            REG_QUEUE_USED_HIGH => set_high(&mut self.queues[q].used),
+            _ => {}
+        }
+    }
+
+    /// Reads a byte (delegates to `read_u32`).
+    fn read_u8(&mut self, offset: u64) -> u8 {
+        (self.read_u32(offset & !3) >> ((offset & 3) * 8)) as u8
+    }
+    /// Reads a half-word (delegates to `read_u32`).
+    fn read_u16(&mut self, offset: u64) -> u16 {
+        (self.read_u32(offset & !3) >> ((offset & 3) * 8)) as u16
+    }
+    /// Reads a double-word (delegates to `read_u32`).
+    fn read_u64(&mut self, offset: u64) -> u64 {
+        self.read_u32(offset) as u64
+    }
+    /// Writes a byte (delegates to `write_u32`).
+    fn write_u8(&mut self, offset: u64, val: u8) {
+        self.write_u32(offset & !3, val as u32);
+    }
+    /// Writes a half-word (delegates to `write_u32`).
+    fn write_u16(&mut self, offset: u64, val: u16) {
+        self.write_u32(offset & !3, val as u32);
+    }
+    /// Writes a double-word (delegates to `write_u32`).
+    fn write_u64(&mut self, offset: u64, val: u64) {
+        self.write_u32(offset, val as u32);
+    }
+
+    /// Advances the device state.
+    ///
+    /// Polls the TAP interface, delivers pending frames into posted receive
+    /// buffers, and returns true if an interrupt is pending.
+    fn tick(&mut self) -> bool {
+        if let Backend::Tap(tap) = &mut self.backend {
+            if tap.countdown == 0 {
+                tap.countdown = TAP_POLL_INTERVAL;
+                tap.poll(&mut self.rx_pending);
+            }
+            tap.countdown -= 1;
+        }
+        if !self.rx_pending.is_empty() {
+            self.process_rx();
+        }
+        (self.interrupt_status & 1) != 0
+    }
+
+    /// Returns the Interrupt Request (IRQ) ID associated with this device.
+    fn get_irq_id(&self) -> Option<IrqId> {
+        Some(IrqId::new(self.irq))
+    }
+
+    /// Reports the used-ring and receive-buffer writes made since the last call.
+    fn take_dma_writes(&mut self, out: &mut Vec<(u64, u64)>) {
+        out.append(&mut self.dma_writes);
+    }
+
+    /// Saves the registers, both queue positions and the frames waiting for a
+    /// receive buffer. Frames already transmitted to the host are not kept.
+    fn save_state(&self) -> Option<serde_json::Value> {
+        let state = NetState {
+            status: self.status,
+            device_features_sel: self.device_features_sel,
+            driver_features_sel: self.driver_features_sel,
+            queue_sel: self.queue_sel,
+            queues: self.queues,
+            interrupt_status: self.interrupt_status,
+            rx_pending: self.rx_pending.iter().map(|f| Frame(f.clone())).collect(),
+        };
+        serde_json::to_value(state).ok()
+    }
+
+    /// Restores the state saved by `save_state`.
+    fn restore_state(&mut self, state: serde_json::Value) -> Result<(), String> {
+        let state: NetState = serde_json::from_value(state).map_err(|e| e.to_string())?;
+        self.status = state.status;
+        self.device_features_sel = state.device_features_sel;
+        self.driver_features_sel = state.driver_features_sel;
+        self.queue_sel = state.queue_sel;
+        self.queues = state.queues;
+        self.interrupt_status = state.interrupt_status;
+        self.rx_pending = state.rx_pending.into_iter().map(|f| f.0).collect();
+        Ok(())
+    }
+
+    /// Returns a mutable reference to the network device.
+    fn as_virtio_net_mut(&mut self) -> Option<&mut VirtioNet> {
+        Some(self)
+    }
+}
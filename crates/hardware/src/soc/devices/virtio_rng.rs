@@ -0,0 +1,441 @@
+//! VirtIO Entropy Device (MMIO).
+//!
+//! Implements a `VirtIO` entropy source over Memory-Mapped I/O (MMIO). The
+//! device exposes a single request queue (0); every device-writable buffer
+//! the driver posts is filled with bytes from a seeded pseudo-random
+//! generator. The generator is deterministic, so two runs with the same
+//! `system.rng_seed` hand the guest identical "entropy".
+
+use crate::common::IrqId;
+use crate::common::rng::SplitMix64;
+use crate::soc::devices::Device;
+use crate::soc::memory::buffer::DramBuffer;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// `VirtIO` MMIO magic value register offset.
+const REG_MAGIC: u64 = 0x00;
+
+/// `VirtIO` MMIO version register offset.
+const REG_VERSION: u64 = 0x04;
+
+/// `VirtIO` MMIO device ID register offset.
+const REG_DEVICE_ID: u64 = 0x08;
+
+/// `VirtIO` MMIO vendor ID register offset.
+const REG_VENDOR_ID: u64 = 0x0c;
+
+/// `VirtIO` MMIO device features register offset.
+const REG_DEVICE_FEATURES: u64 = 0x10;
+
+/// `VirtIO` MMIO device features select register offset.
+const REG_DEVICE_FEATURES_SEL: u64 = 0x14;
+
+/// `VirtIO` MMIO driver features select register offset.
+const REG_DRIVER_FEATURES_SEL: u64 = 0x24;
+
+/// `VirtIO` MMIO queue maximum size register offset.
+const REG_QUEUE_NUM_MAX: u64 = 0x34;
+
+/// `VirtIO` MMIO queue size register offset.
+const REG_QUEUE_NUM: u64 = 0x38;
+
+/// `VirtIO` MMIO queue ready register offset.
+const REG_QUEUE_READY: u64 = 0x44;
+
+/// `VirtIO` MMIO queue notify register offset.
+const REG_QUEUE_NOTIFY: u64 = 0x50;
+
+/// `VirtIO` MMIO interrupt status register offset.
+const REG_INTERRUPT_STATUS: u64 = 0x60;
+
+/// `VirtIO` MMIO interrupt acknowledge register offset.
+const REG_INTERRUPT_ACK: u64 = 0x64;
+
+/// `VirtIO` MMIO device status register offset.
+const REG_STATUS: u64 = 0x70;
+
+/// `VirtIO` MMIO queue descriptor table address (low 32 bits) register offset.
+const REG_QUEUE_DESC_LOW: u64 = 0x80;
+
+/// `VirtIO` MMIO queue descriptor table address (high 32 bits) register offset.
+const REG_QUEUE_DESC_HIGH: u64 = 0x84;
+
+/// `VirtIO` MMIO queue available ring address (low 32 bits) register offset.
+const REG_QUEUE_AVAIL_LOW: u64 = 0x90;
+
+/// `VirtIO` MMIO queue available ring address (high 32 bits) register offset.
+const REG_QUEUE_AVAIL_HIGH: u64 = 0x94;
+
+/// `VirtIO` MMIO queue used ring address (low 32 bits) register offset.
+const REG_QUEUE_USED_LOW: u64 = 0xa0;
+
+/// `VirtIO` MMIO queue used ring address (high 32 bits) register offset.
+const REG_QUEUE_USED_HIGH: u64 = 0xa4;
+
+/// `VirtIO` MMIO magic value ("virt" in ASCII: 0x74726976).
+const VIRTIO_MMIO_MAGIC_VALUE: u32 = 0x74726976;
+
+/// `VirtIO` MMIO vendor ID value (QEMU vendor: 0x554d4551).
+const VIRTIO_MMIO_VENDOR_ID_VALUE: u32 = 0x554d4551;
+
+/// `VirtIO` MMIO device ID for entropy device (4).
+const VIRTIO_MMIO_DEVICE_ID_VALUE: u32 = 4;
+
+/// `VirtIO` specification version (2).
+const VIRTIO_VERSION_VALUE: u32 = 2;
+
+/// Feature bit 32 (bit 0 of word 1): `VIRTIO_F_VERSION_1`.
+const VIRTIO_F_VERSION_1: u32 = 1;
+
+/// Maximum queue size supported by this device (16 entries).
+const QUEUE_NUM_MAX_VALUE: u32 = 16;
+
+/// Size of a virtqueue descriptor in bytes (16 bytes).
+const DESC_SIZE: u64 = 16;
+
+/// Virtqueue descriptor flag: indicates chained descriptors (more descriptors follow).
+const VRING_DESC_F_NEXT: u16 = 1;
+
+/// Virtqueue descriptor flag: indicates write-only descriptor (device writes to memory).
+const VRING_DESC_F_WRITE: u16 = 2;
+
+/// Default PLIC source driven by the device.
+const RNG_IRQ: u32 = 3;
+
+/// `VirtIO` Entropy device structure.
+///
+/// Implements a memory-mapped entropy device compliant with the `VirtIO`
+/// specification. Request buffers are filled in guest RAM through a shared
+/// DRAM buffer.
+#[derive(Debug)]
+pub struct VirtioRng {
+    /// Base physical address of the device MMIO region.
+    base_addr: u64,
+    /// PLIC source the device raises.
+    irq: u32,
+    /// Base physical address of system RAM.
+    ram_base: u64,
+    /// Shared reference to system RAM for DMA.
+    ram: Arc<DramBuffer>,
+    /// Generator producing the bytes handed to the guest.
+    rng: SplitMix64,
+
+    /// Device status register.
+    status: u32,
+    /// Device features selection.
+    device_features_sel: u32,
+    /// Driver features selection.
+    driver_features_sel: u32,
+    /// Queue size.
+    queue_num: u32,
+    /// Queue ready bit.
+    queue_ready: u32,
+    /// Descriptor table address.
+    queue_desc: u64,
+    /// Available ring address.
+    queue_avail: u64,
+    /// Used ring address.
+    queue_used: u64,
+    /// Interrupt status register.
+    interrupt_status: u32,
+    /// Last processed available index.
+    last_avail_idx: u16,
+
+    /// DMA writes (address, length) not yet reported to the bus.
+    dma_writes: Vec<(u64, u64)>,
+}
+
+/// Device registers, queue position and generator saved in a checkpoint.
+#[derive(Serialize, Deserialize)]
+struct RngState {
+    rng: SplitMix64,
+    status: u32,
+    device_features_sel: u32,
+    driver_features_sel: u32,
+    queue_num: u32,
+    queue_ready: u32,
+    queue_desc: u64,
+    queue_avail: u64,
+    queue_used: u64,
+    interrupt_status: u32,
+    last_avail_idx: u16,
+}
+
+impl VirtioRng {
+    /// Creates a new `VirtIO` Entropy device.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_addr` - MMIO base address.
+    /// * `ram_base` - System RAM base address.
+    /// * `ram` - Shared DRAM buffer for DMA access.
+    /// * `seed` - Seed of the generator producing the guest's entropy.
+    pub const fn new(base_addr: u64, ram_base: u64, ram: Arc<DramBuffer>, seed: u64) -> Self {
+        Self {
+            base_addr,
+            irq: RNG_IRQ,
+            ram_base,
+            ram,
+            rng: SplitMix64::new(seed),
+            status: 0,
+            device_features_sel: 0,
+            driver_features_sel: 0,
+            queue_num: 0,
+            queue_ready: 0,
+            queue_desc: 0,
+            queue_avail: 0,
+            queue_used: 0,
+            interrupt_status: 0,
+            last_avail_idx: 0,
+            dma_writes: Vec::new(),
+        }
+    }
+
+    /// Sets the PLIC source the device raises.
+    pub const fn set_irq(&mut self, irq: u32) {
+        self.irq = irq;
+    }
+
+    /// Resets the device to its power-on state (driver wrote 0 to Status).
+    ///
+    /// The generator keeps its state, so a re-probed driver sees fresh bytes.
+    const fn reset(&mut self) {
+        self.status = 0;
+        self.device_features_sel = 0;
+        self.driver_features_sel = 0;
+        self.queue_num = 0;
+        self.queue_ready = 0;
+        self.queue_desc = 0;
+        self.queue_avail = 0;
+        self.queue_used = 0;
+        self.interrupt_status = 0;
+        self.last_avail_idx = 0;
+    }
+
+    /// Converts a guest physical address range to an offset into RAM, or
+    /// `None` if any part of it lies outside.
+    fn ram_offset(&self, addr: u64, len: usize) -> Option<usize> {
+        let offset = usize::try_from(addr.checked_sub(self.ram_base)?).ok()?;
+        (offset.checked_add(len)? <= self.ram.len()).then_some(offset)
+    }
+
+    fn dma_read_u16(&self, addr: u64) -> u16 {
+        self.ram_offset(addr, 2).map_or(0, |o| {
+            let b = self.ram.read_slice(o, 2);
+            u16::from_le_bytes([b[0], b[1]])
+        })
+    }
+
+    fn dma_read_u32(&self, addr: u64) -> u32 {
+        self.ram_offset(addr, 4).map_or(0, |o| {
+            let b = self.ram.read_slice(o, 4);
+            u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+        })
+    }
+
+    fn dma_read_u64(&self, addr: u64) -> u64 {
+        self.ram_offset(addr, 8).map_or(0, |o| {
+            let b = self.ram.read_slice(o, 8);
+            u64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]])
+        })
+    }
+
+    /// Writes `data` to guest RAM at `addr`; out-of-range writes are dropped.
+    fn dma_write(&mut self, addr: u64, data: &[u8]) {
+        if let Some(offset) = self.ram_offset(addr, data.len()) {
+            self.ram.write_slice(offset, data);
+            self.dma_writes.push((addr, data.len() as u64));
+        }
+    }
+
+    /// Fills every request the driver has posted and returns it through the
+    /// used ring.
+    fn process_queue(&mut self) {
+        if self.queue_num == 0 || self.queue_ready == 0 {
+            return;
+        }
+        let avail_idx = self.dma_read_u16(self.queue_avail + 2);
+        while self.last_avail_idx != avail_idx {
+            let ring_offset = 4 + (u64::from(self.last_avail_idx) % u64::from(self.queue_num)) * 2;
+            let head = self.dma_read_u16(self.queue_avail + ring_offset);
+            self.last_avail_idx = self.last_avail_idx.wrapping_add(1);
+
+            let mut written = 0u32;
+            let mut idx = head;
+            // A chain never has more descriptors than the queue; the bound
+            // stops a malformed (cyclic) chain.
+            for _ in 0..self.queue_num {
+                if u32::from(idx) >= self.queue_num {
+                    break;
+                }
+                let desc = self.queue_desc + u64::from(idx) * DESC_SIZE;
+                let addr = self.dma_read_u64(desc);
+                let len = self.dma_read_u32(desc + 8);
+                let flags = self.dma_read_u16(desc + 12);
+                if flags & VRING_DESC_F_WRITE != 0 && self.ram_offset(addr, len as usize).is_some()
+                {
+                    let mut data = vec![0u8; len as usize];
+                    self.rng.fill(&mut data);
+                    self.dma_write(addr, &data);
+                    written = written.wrapping_add(len);
+                }
+                if flags & VRING_DESC_F_NEXT == 0 {
+                    break;
+                }
+                idx = self.dma_read_u16(desc + 14);
+            }
+
+            let used_idx_addr = self.queue_used + 2;
+            let current_used = self.dma_read_u16(used_idx_addr);
+            let elem =
+                self.queue_used + 4 + (u64::from(current_used) % u64::from(self.queue_num)) * 8;
+            self.dma_write(elem, &u32::from(head).to_le_bytes());
+            self.dma_write(elem + 4, &written.to_le_bytes());
+            self.dma_write(used_idx_addr, &current_used.wrapping_add(1).to_le_bytes());
+            self.interrupt_status |= 1;
+        }
+    }
+}
+
+impl Device for VirtioRng {
+    /// Returns the device name.
+    fn name(&self) -> &'static str {
+        "VirtIO-Rng"
+    }
+
+    /// Returns the address range (Base, Size).
+    fn address_range(&self) -> (u64, u64) {
+        (self.base_addr, 0x1000)
+    }
+
+    /// Reads a word (32-bit) from the device.
+    ///
+    /// Handles reads from `VirtIO` MMIO registers. The device has no
+    /// configuration space.
+    fn read_u32(&mut self, offset: u64) -> u32 {
+        match offset {
+            REG_MAGIC => VIRTIO_MMIO_MAGIC_VALUE,
+            REG_VERSION => VIRTIO_VERSION_VALUE,
+            REG_DEVICE_ID => VIRTIO_MMIO_DEVICE_ID_VALUE,
+            REG_VENDOR_ID => VIRTIO_MMIO_VENDOR_ID_VALUE,
+            REG_DEVICE_FEATURES => match self.device_features_sel {
+                1 => VIRTIO_F_VERSION_1,
+                _ => 0,
+            },
+            REG_QUEUE_NUM_MAX => QUEUE_NUM_MAX_VALUE,
+            REG_QUEUE_READY => self.queue_ready,
+            REG_INTERRUPT_STATUS => self.interrupt_status,
+            REG_STATUS => self.status,
+            _ => 0,
+        }
+    }
+
+    /// Writes a word (32-bit) to the device.
+    ///
+    /// Handles writes to `VirtIO` MMIO registers (Status, Queue configuration, Notify).
+    fn write_u32(&mut self, offset: u64, val: u32) {
+        let set_low = |reg: &mut u64| *reg = (*reg & !0xffff_ffff) | u64::from(val);
+        let set_high = |reg: &mut u64| *reg = (*reg & 0xffff_ffff) | (u64::from(val) << 32);
+        match offset {
+            REG_DEVICE_FEATURES_SEL => self.device_features_sel = val,
+            REG_DRIVER_FEATURES_SEL => self.driver_features_sel = val,
+            REG_QUEUE_NUM => self.queue_num = val.min(QUEUE_NUM_MAX_VALUE),
+            REG_QUEUE_READY => self.queue_ready = val,
+            REG_QUEUE_NOTIFY => self.process_queue(),
+            REG_INTERRUPT_ACK => self.interrupt_status &= !val,
+            REG_STATUS => {
+                if val == 0 {
+                    self.reset();
+                } else {
+                    self.status = val;
+                }
+            }
+            REG_QUEUE_DESC_LOW => set_low(&mut self.queue_desc),
+            REG_QUEUE_DESC_HIGH => set_high(&mut self.queue_desc),
+            REG_QUEUE_AVAIL_LOW => set_low(&mut self.queue_avail),
+            REG_QUEUE_AVAIL_HIGH => set_high(&mut self.queue_avail),
+            REG_QUEUE_USED_LOW => set_low(&mut self.queue_used),
+            REG_QUEUE_USED_HIGH => set_high(&mut self.queue_used),
+            _ => {}
+        }
+    }
+
+    /// Reads a byte (delegates to `read_u32`).
+    fn read_u8(&mut self, offset: u64) -> u8 {
+        (self.read_u32(offset & !3) >> ((offset & 3) * 8)) as u8
+    }
+    /// Reads a half-word (delegates to `read_u32`).
+    fn read_u16(&mut self, offset: u64) -> u16 {
+        (self.read_u32(offset & !3) >> ((offset & 3) * 8)) as u16
+    }
+    /// Reads a double-word (delegates to `read_u32`).
+    fn read_u64(&mut self, offset: u64) -> u64 {
+        self.read_u32(offset) as u64
+    }
+    /// Writes a byte (delegates to `write_u32`).
+    fn write_u8(&mut self, offset: u64, val: u8) {
+        self.write_u32(offset & !3, val as u32);
+    }
+    /// Writes a half-word (delegates to `write_u32`).
+    fn write_u16(&mut self, offset: u64, val: u16) {
+        self.write_u32(offset & !3, val as u32);
+    }
+    /// Writes a double-word (delegates to `write_u32`).
+    fn write_u64(&mut self, offset: u64, val: u64) {
+        self.write_u32(offset, val as u32);
+    }
+
+    /// Advances the device state.
+    ///
+    /// Requests are served on notify; returns true if an interrupt is pending.
+    fn tick(&mut self) -> bool {
+        (self.interrupt_status & 1) != 0
+    }
+
+    /// Returns the Interrupt Request (IRQ) ID associated with this device.
+    fn get_irq_id(&self) -> Option<IrqId> {
+        Some(IrqId::new(self.irq))
+    }
+
+    /// Reports the used-ring and request-buffer writes made since the last call.
+    fn take_dma_writes(&mut self, out: &mut Vec<(u64, u64)>) {
+        out.append(&mut self.dma_writes);
+    }
+
+    /// Saves the registers, the queue position and the generator, so the
+    /// restored device hands out the same bytes.
+    fn save_state(&self) -> Option<serde_json::Value> {
+        let state = RngState {
+            rng: self.rng.clone(),
+            status: self.status,
+            device_features_sel: self.device_features_sel,
+            driver_features_sel: self.driver_features_sel,
+            queue_num: self.queue_num,
+            queue_ready: self.queue_ready,
+            queue_desc: self.queue_desc,
+            queue_avail: self.queue_avail,
+            queue_used: self.queue_used,
+            interrupt_status: self.interrupt_status,
+            last_avail_idx: self.last_avail_idx,
+        };
+        serde_json::to_value(state).ok()
+    }
+
+    /// Restores the state saved by `save_state`.
+    fn restore_state(&mut self, state: serde_json::Value) -> Result<(), String> {
+        let state: RngState = serde_json::from_value(state).map_err(|e| e.to_string())?;
+        self.rng = state.rng;
+        self.status = state.status;
+        self.device_features_sel = state.device_features_sel;
+        self.driver_features_sel = state.driver_features_sel;
+        self.queue_num = state.queue_num;
+        self.queue_ready = state.queue_ready;
+        self.queue_desc = state.queue_desc;
+        self.queue_avail = state.queue_avail;
+        self.queue_used = state.queue_used;
+        self.interrupt_status = state.interrupt_status;
+        self.last_avail_idx = state.last_avail_idx;
+        Ok(())
+    }
+}
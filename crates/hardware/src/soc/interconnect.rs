@@ -1,13 +1,22 @@
 //! System interconnect (bus) for memory and MMIO access.
 //!
 //! This module implements the bus that routes physical address accesses to devices. It provides:
-//! 1. **Device registration:** Devices are added by address range and sorted for lookup.
+//! 1. **Device registration:** Devices are added by address range, rejected if they overlap
+//!    another device, and sorted for lookup.
 //! 2. **Access routing:** Read/write by address with last-device hint for throughput.
-//! 3. **Tick and IRQ:** Each device is ticked; PLIC aggregates IRQs for timer and external.
-//! 4. **Load and RAM pointer:** Binary loading and raw RAM pointer for CPU DMA-style access.
+//! 3. **Tick and IRQ:** Each device is ticked and its DMA writes collected; PLIC aggregates IRQs
+//!    for timer and external.
+//! 4. **Load and RAM access:** Binary loading, and the RAM banks' buffers for direct CPU access.
+//! 5. **MMIO monitoring:** Accesses to devices other than RAM are counted per device and,
+//!    when a trace sink is set, reported to it.
 
-use super::devices::Device;
+use super::devices::{
+    Clint, Device, DmaController, Htif, SysCon, SysconEvent, Uart, VirtioBlock, VirtioNet,
+};
+use super::memory::buffer::DramBuffer;
+use super::mmio_trace::{MmioAccess, MmioStats, MmioTraceSink};
 use crate::common::PhysAddr;
+use std::sync::Arc;
 
 /// System bus connecting CPU and devices; routes accesses by physical address.
 ///
@@ -21,10 +30,24 @@ pub struct Bus {
     /// Base latency in cycles per transaction.
     pub latency_cycles: u64,
     last_device_idx: usize,
+    /// Address the boot RAM bank must cover (see [`Bus::set_boot_address`]).
+    boot_address: Option<u64>,
     ram_idx: Option<usize>,
     uart_idx: Option<usize>,
     htif_idx: Option<usize>,
     clint_idx: Option<usize>,
+    syscon_idx: Option<usize>,
+    disk_idx: Option<usize>,
+    /// DMA writes (address, length) collected by the last [`Bus::tick`].
+    dma_writes: Vec<(u64, u64)>,
+    /// Receiver of every MMIO access, if tracing is enabled.
+    mmio_trace: Option<Box<dyn MmioTraceSink + Send + Sync>>,
+    /// MMIO accesses counted since the last [`Bus::take_mmio_stats`].
+    mmio_stats: MmioStats,
+    /// Cycle stamped on traced accesses.
+    access_cycle: u64,
+    /// Hart stamped on traced accesses.
+    access_hart: usize,
 }
 
 impl std::fmt::Debug for Bus {
@@ -37,7 +60,10 @@ impl std::fmt::Debug for Bus {
             .field("uart_idx", &self.uart_idx)
             .field("htif_idx", &self.htif_idx)
             .field("clint_idx", &self.clint_idx)
+            .field("syscon_idx", &self.syscon_idx)
+            .field("disk_idx", &self.disk_idx)
             .field("num_devices", &self.devices.len())
+            .field("mmio_trace", &self.mmio_trace.is_some())
             .finish_non_exhaustive()
     }
 }
@@ -59,26 +85,141 @@ impl Bus {
             width_bytes,
             latency_cycles,
             last_device_idx: 0,
+            boot_address: None,
             ram_idx: None,
             uart_idx: None,
             htif_idx: None,
             clint_idx: None,
+            syscon_idx: None,
+            disk_idx: None,
+            dma_writes: Vec::new(),
+            mmio_trace: None,
+            mmio_stats: MmioStats::default(),
+            access_cycle: 0,
+            access_hart: 0,
         }
     }
 
+    /// Makes the RAM bank covering `addr` the boot bank: the one
+    /// [`Bus::get_ram_info`] returns and the bus checks first. Without a boot
+    /// address, or when no bank covers it, the lowest bank is used.
+    pub fn set_boot_address(&mut self, addr: u64) {
+        self.boot_address = Some(addr);
+        self.ram_idx = self.boot_ram_idx();
+    }
+
+    /// Returns the index of the boot RAM bank.
+    fn boot_ram_idx(&self) -> Option<usize> {
+        let mut banks =
+            self.devices.iter().enumerate().filter(|(_, d)| d.name() == "DRAM").peekable();
+        let lowest = banks.peek().map(|&(i, _)| i);
+        self.boot_address
+            .and_then(|addr| {
+                banks.find(|(_, d)| {
+                    let (base, size) = d.address_range();
+                    addr >= base && addr - base < size
+                })
+            })
+            .map_or(lowest, |(i, _)| Some(i))
+    }
+
     /// Registers a device on the bus; devices are sorted by base address for lookup.
     ///
     /// # Arguments
     ///
     /// * `dev` - The device to add (must implement `Device` and be `Send + Sync`).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the device's range overlaps a registered device (see [`Bus::try_add_device`]).
     pub fn add_device(&mut self, dev: Box<dyn Device + Send + Sync>) {
+        if let Err(e) = self.try_add_device(dev) {
+            panic!("{e}");
+        }
+    }
+
+    /// Registers a device on the bus unless its address range overlaps a registered device.
+    ///
+    /// The HTIF is the one exception: it is routed ahead of RAM, so it may sit inside a RAM bank.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the clash if the range wraps the address space or overlaps
+    /// another device; the bus is left unchanged.
+    pub fn try_add_device(&mut self, dev: Box<dyn Device + Send + Sync>) -> Result<(), String> {
+        let (base, size) = dev.address_range();
+        let end = base
+            .checked_add(size)
+            .ok_or_else(|| format!("{} at {base:#x} wraps the address space", dev.name()))?;
+        let shadows_ram = |a: &dyn Device, b: &dyn Device| a.name() == "HTIF" && b.name() == "DRAM";
+        for other in &self.devices {
+            let (other_base, other_size) = other.address_range();
+            let other_end = other_base.saturating_add(other_size);
+            if base < other_end
+                && other_base < end
+                && !shadows_ram(dev.as_ref(), other.as_ref())
+                && !shadows_ram(other.as_ref(), dev.as_ref())
+            {
+                return Err(format!(
+                    "{} at {base:#x}..{end:#x} overlaps {} at {other_base:#x}..{other_end:#x}",
+                    dev.name(),
+                    other.name()
+                ));
+            }
+        }
+
         self.devices.push(dev);
         self.devices.sort_by_key(|d| d.address_range().0);
-        self.ram_idx = self.devices.iter().position(|d| d.name() == "DRAM");
+        self.ram_idx = self.boot_ram_idx();
         self.uart_idx = self.devices.iter().position(|d| d.name() == "UART0");
         self.htif_idx = self.devices.iter().position(|d| d.name() == "HTIF");
         self.clint_idx = self.devices.iter().position(|d| d.name() == "CLINT");
+        self.disk_idx = self.devices.iter().position(|d| d.name() == "VirtIO-Blk");
+        self.syscon_idx = self.devices.iter().position(|d| d.name() == "SysCon");
         self.last_device_idx = 0;
+        Ok(())
+    }
+
+    /// Returns the registered devices in ascending base-address order.
+    pub fn devices(&self) -> impl Iterator<Item = &(dyn Device + Send + Sync)> {
+        self.devices.iter().map(AsRef::as_ref)
+    }
+
+    /// Returns the registered devices mutably, in ascending base-address order.
+    pub fn devices_mut(
+        &mut self,
+    ) -> impl Iterator<Item = &mut (dyn Device + Send + Sync + 'static)> {
+        self.devices.iter_mut().map(AsMut::as_mut)
+    }
+
+    /// Sets (or with `None` removes) the receiver of the MMIO trace.
+    ///
+    /// Every read and write that reaches a device other than RAM is reported to it.
+    pub fn set_mmio_trace(&mut self, sink: Option<Box<dyn MmioTraceSink + Send + Sync>>) {
+        self.mmio_trace = sink;
+    }
+
+    /// Returns whether an MMIO trace receiver is set.
+    pub const fn mmio_trace_enabled(&self) -> bool {
+        self.mmio_trace.is_some()
+    }
+
+    /// Sets the cycle and hart stamped on the MMIO accesses that follow.
+    ///
+    /// Each hart calls this before it ticks, since the bus is shared between harts.
+    pub const fn set_access_context(&mut self, cycle: u64, hart: usize) {
+        self.access_cycle = cycle;
+        self.access_hart = hart;
+    }
+
+    /// Returns and clears the per-device MMIO access counts.
+    pub fn take_mmio_stats(&mut self) -> MmioStats {
+        std::mem::take(&mut self.mmio_stats)
+    }
+
+    /// Returns whether any MMIO access was counted since the last [`Bus::take_mmio_stats`].
+    pub fn has_mmio_stats(&self) -> bool {
+        !self.mmio_stats.is_empty()
     }
 
     /// Returns the number of cycles to transfer the given number of bytes on this bus.
@@ -142,40 +283,59 @@ impl Bus {
         false
     }
 
-    /// Advances all devices by one tick and updates PLIC; returns IRQ flags.
+    /// Advances all devices by one tick and updates PLIC; returns hart 0's IRQ flags.
     ///
     /// # Returns
     ///
     /// (`timer_irq`, `msip`, `meip`, `seip`) for machine timer, machine software,
     /// machine external, and supervisor external interrupts.
     pub fn tick(&mut self) -> (bool, bool, bool, bool) {
-        let mut timer_irq = false;
         let mut active_irqs = 0u64;
+        self.dma_writes.clear();
 
         for i in 0..self.devices.len() {
             let dev = &mut self.devices[i];
-            if dev.tick() {
-                if let Some(id) = dev.get_irq_id()
-                    && id.val() < 64
-                {
-                    active_irqs |= 1 << id.val();
-                }
-                if dev.name() == "CLINT" {
-                    timer_irq = true;
-                }
+            dev.take_dma_writes(&mut self.dma_writes);
+            if dev.tick()
+                && let Some(id) = dev.get_irq_id()
+                && id.val() < 64
+            {
+                active_irqs |= 1 << id.val();
             }
         }
 
-        // Query MSIP separately — it is independent of the timer comparison.
-        let msip = self
-            .clint_idx
-            .and_then(|idx| self.devices[idx].as_clint_mut())
-            .is_some_and(|clint| clint.msip_pending());
-
-        let (meip, seip) = self.find_plic().map_or((false, false), |plic| {
+        if let Some(plic) = self.plic_mut() {
             plic.update_irqs(active_irqs);
-            plic.check_interrupts()
-        });
+            let _ = plic.check_interrupts();
+        }
+
+        self.hart_irqs(0)
+    }
+
+    /// Returns the (`physical_address`, `length`) of every device DMA write to RAM
+    /// collected by the last [`Bus::tick`].
+    ///
+    /// Harts check these against their LR/SC reservation, since DMA bypasses
+    /// the store path.
+    pub fn dma_writes(&self) -> &[(u64, u64)] {
+        &self.dma_writes
+    }
+
+    /// Returns the interrupt lines routed to `hart` as of the last [`Bus::tick`].
+    ///
+    /// Devices are ticked once per cycle (by hart 0); the other harts sample
+    /// their own CLINT and PLIC outputs through this method.
+    ///
+    /// # Returns
+    ///
+    /// (`timer_irq`, `msip`, `meip`, `seip`) for the given hart.
+    pub fn hart_irqs(&mut self, hart: usize) -> (bool, bool, bool, bool) {
+        let (timer_irq, msip) = self
+            .clint_mut()
+            .map_or((false, false), |clint| (clint.hart_mtip(hart), clint.hart_msip(hart)));
+
+        let (meip, seip) =
+            self.plic_mut().map_or((false, false), |plic| plic.hart_interrupts(hart));
 
         (timer_irq, msip, meip, seip)
     }
@@ -195,9 +355,18 @@ impl Bus {
         false
     }
 
-    /// Returns a raw pointer and (base, end) for the RAM region if present.
+    /// Returns and clears the last command written to the system controller, if any.
+    pub fn take_syscon_event(&mut self) -> Option<SysconEvent> {
+        self.syscon_idx
+            .and_then(|idx| self.devices[idx].as_syscon_mut())
+            .and_then(SysCon::take_event)
+    }
+
+    /// Returns a raw pointer and (base, end) for the boot RAM bank if present.
     ///
     /// Used by the CPU or loader for direct memory access (e.g., instruction fetch, DMA).
+    /// With several banks this is the one covering the boot address (see
+    /// [`Bus::set_boot_address`]).
     ///
     /// # Returns
     ///
@@ -212,7 +381,53 @@ impl Bus {
         None
     }
 
-    fn find_plic(&mut self) -> Option<&mut crate::soc::devices::Plic> {
+    /// Returns the base address and backing buffer of every RAM bank, in
+    /// ascending address order.
+    pub fn ram_buffers(&mut self) -> Vec<(u64, Arc<DramBuffer>)> {
+        self.devices
+            .iter_mut()
+            .filter_map(|d| d.as_memory_mut())
+            .map(|mem| (mem.address_range().0, Arc::clone(mem.buffer())))
+            .collect()
+    }
+
+    /// Returns the CLINT, if one is registered.
+    pub fn clint_mut(&mut self) -> Option<&mut Clint> {
+        self.clint_idx.and_then(|idx| self.devices.get_mut(idx)).and_then(|d| d.as_clint_mut())
+    }
+
+    /// Returns the UART, if one is registered.
+    pub fn uart_mut(&mut self) -> Option<&mut Uart> {
+        self.uart_idx.and_then(|idx| self.devices.get_mut(idx)).and_then(|d| d.as_uart_mut())
+    }
+
+    /// Returns the UART at position `index` (named `UART<index>`), if one is registered.
+    pub fn nth_uart_mut(&mut self, index: usize) -> Option<&mut Uart> {
+        self.devices.iter_mut().filter_map(|d| d.as_uart_mut()).find(|u| u.index() == index)
+    }
+
+    /// Returns the `VirtIO` block device, if one is registered.
+    pub fn virtio_blk_mut(&mut self) -> Option<&mut VirtioBlock> {
+        self.disk_idx.and_then(|idx| self.devices.get_mut(idx)).and_then(|d| d.as_virtio_blk_mut())
+    }
+
+    /// Returns the `VirtIO` network device, if one is registered.
+    pub fn virtio_net_mut(&mut self) -> Option<&mut VirtioNet> {
+        self.devices.iter_mut().find_map(|d| d.as_virtio_net_mut())
+    }
+
+    /// Returns the DMA controller, if one is registered.
+    pub fn dma_mut(&mut self) -> Option<&mut DmaController> {
+        self.devices.iter_mut().find_map(|d| d.as_dma_mut())
+    }
+
+    /// Returns the HTIF device, if one is registered.
+    pub fn htif_mut(&mut self) -> Option<&mut Htif> {
+        self.htif_idx.and_then(|idx| self.devices.get_mut(idx)).and_then(|d| d.as_htif_mut())
+    }
+
+    /// Returns the PLIC, if one is registered.
+    pub fn plic_mut(&mut self) -> Option<&mut crate::soc::devices::Plic> {
         for dev in &mut self.devices {
             if let Some(plic) = dev.as_plic_mut() {
                 return Some(plic);
@@ -261,44 +476,112 @@ impl Bus {
         None
     }
 
+    /// Counts an access to the device last returned by `find_device` and reports it to the
+    /// MMIO trace; RAM accesses are ignored.
+    fn record_access(&mut self, paddr: PhysAddr, size: u64, value: u64, is_write: bool) {
+        let device = self.devices[self.last_device_idx].name();
+        if device == "DRAM" {
+            return;
+        }
+        self.mmio_stats.record(device, size, is_write);
+        if let Some(sink) = &mut self.mmio_trace {
+            sink.record(&MmioAccess {
+                cycle: self.access_cycle,
+                hart: self.access_hart,
+                paddr: paddr.val(),
+                device,
+                size,
+                value,
+                is_write,
+            });
+        }
+    }
+
+    /// Reads `size` bytes through `read`; returns 0 if no device claims the address.
+    fn read_sized(
+        &mut self,
+        paddr: PhysAddr,
+        size: u64,
+        read: impl FnOnce(&mut dyn Device, u64) -> u64,
+    ) -> u64 {
+        let Some((dev, offset)) = self.find_device(paddr) else { return 0 };
+        let val = read(dev.as_mut(), offset);
+        self.record_access(paddr, size, val, false);
+        val
+    }
+
+    /// Writes `size` bytes through `write`; no-op if no device claims the address.
+    fn write_sized(
+        &mut self,
+        paddr: PhysAddr,
+        size: u64,
+        val: u64,
+        write: impl FnOnce(&mut dyn Device, u64),
+    ) {
+        let Some((dev, offset)) = self.find_device(paddr) else { return };
+        write(dev.as_mut(), offset);
+        self.record_access(paddr, size, val, true);
+    }
+
     /// Reads one byte at the given physical address; returns 0 if no device claims the address.
     pub fn read_u8(&mut self, paddr: PhysAddr) -> u8 {
-        if let Some((dev, offset)) = self.find_device(paddr) { dev.read_u8(offset) } else { 0 }
+        self.read_sized(paddr, 1, |dev, offset| dev.read_u8(offset).into()) as u8
     }
     /// Reads two bytes (little-endian) at the given physical address; returns 0 if unclaimed.
     pub fn read_u16(&mut self, paddr: PhysAddr) -> u16 {
-        if let Some((dev, offset)) = self.find_device(paddr) { dev.read_u16(offset) } else { 0 }
+        self.read_sized(paddr, 2, |dev, offset| dev.read_u16(offset).into()) as u16
     }
     /// Reads four bytes (little-endian) at the given physical address; returns 0 if unclaimed.
     pub fn read_u32(&mut self, paddr: PhysAddr) -> u32 {
-        if let Some((dev, offset)) = self.find_device(paddr) { dev.read_u32(offset) } else { 0 }
+        self.read_sized(paddr, 4, |dev, offset| dev.read_u32(offset).into()) as u32
     }
     /// Reads eight bytes (little-endian) at the given physical address; returns 0 if unclaimed.
     pub fn read_u64(&mut self, paddr: PhysAddr) -> u64 {
-        if let Some((dev, offset)) = self.find_device(paddr) { dev.read_u64(offset) } else { 0 }
+        self.read_sized(paddr, 8, |dev, offset| dev.read_u64(offset))
     }
     /// Writes one byte at the given physical address; no-op if no device claims it.
     pub fn write_u8(&mut self, paddr: PhysAddr, val: u8) {
-        if let Some((dev, offset)) = self.find_device(paddr) {
-            dev.write_u8(offset, val);
-        }
+        self.write_sized(paddr, 1, val.into(), |dev, offset| dev.write_u8(offset, val));
     }
     /// Writes two bytes (little-endian) at the given physical address; no-op if unclaimed.
     pub fn write_u16(&mut self, paddr: PhysAddr, val: u16) {
-        if let Some((dev, offset)) = self.find_device(paddr) {
-            dev.write_u16(offset, val);
-        }
+        self.write_sized(paddr, 2, val.into(), |dev, offset| dev.write_u16(offset, val));
     }
     /// Writes four bytes (little-endian) at the given physical address; no-op if unclaimed.
     pub fn write_u32(&mut self, paddr: PhysAddr, val: u32) {
-        if let Some((dev, offset)) = self.find_device(paddr) {
-            dev.write_u32(offset, val);
-        }
+        self.write_sized(paddr, 4, val.into(), |dev, offset| dev.write_u32(offset, val));
     }
     /// Writes eight bytes (little-endian) at the given physical address; no-op if unclaimed.
     pub fn write_u64(&mut self, paddr: PhysAddr, val: u64) {
-        if let Some((dev, offset)) = self.find_device(paddr) {
-            dev.write_u64(offset, val);
-        }
+        self.write_sized(paddr, 8, val, |dev, offset| dev.write_u64(offset, val));
+    }
+
+    /// Performs an atomic read-modify-write of `size` bytes (4 or 8) at the
+    /// given physical address, as required for AMOs to device regions.
+    ///
+    /// `op` receives the old value (zero-extended) and returns the value to
+    /// write back; the device sees exactly one read followed by one write.
+    /// Returns the old value, or 0 (with no write) if no device claims it.
+    pub fn read_modify_write(
+        &mut self,
+        paddr: PhysAddr,
+        size: u64,
+        op: impl FnOnce(u64) -> u64,
+    ) -> u64 {
+        let Some((dev, offset)) = self.find_device(paddr) else { return 0 };
+        let (old, new) = if size == 8 {
+            let old = dev.read_u64(offset);
+            let new = op(old);
+            dev.write_u64(offset, new);
+            (old, new)
+        } else {
+            let old = dev.read_u32(offset) as u64;
+            let new = op(old) as u32;
+            dev.write_u32(offset, new);
+            (old, new.into())
+        };
+        self.record_access(paddr, size, old, false);
+        self.record_access(paddr, size, new, true);
+        old
     }
 }
@@ -1,22 +1,39 @@
 //! DRAM Buffer Implementation.
 //!
 //! This module provides a safe wrapper around raw memory allocation for the system RAM.
-//! It supports lazy allocation via `mmap` on Unix systems to optimize host memory usage
-//! and startup time. It provides interior mutability to allow shared access between
-//! the CPU (via the Memory device) and DMA-capable devices (like VirtIO).
+//! The storage is one of three backings:
+//! 1. **Anonymous mmap (default on Unix):** Demand-paged; the OS only allocates a page when
+//!    the guest first touches it, so a large, mostly unused RAM costs little host memory.
+//! 2. **File-backed mmap:** Pages live in the host page cache backed by a file, so they can
+//!    be written back and evicted under memory pressure, and survive the run.
+//! 3. **Heap:** A zeroed heap allocation, always used under Miri and on non-Unix hosts.
+//!
+//! It provides interior mutability to allow shared access between the CPU (via the Memory
+//! device) and DMA-capable devices (like VirtIO).
 
+use std::fs::OpenOptions;
+use std::io;
 use std::ops::{Index, IndexMut};
+use std::path::Path;
 use std::slice;
 
+/// How a [`DramBuffer`]'s storage was allocated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Storage {
+    /// An `mmap` mapping (anonymous or file-backed).
+    Mmap,
+    /// A leaked boxed slice.
+    Heap,
+}
+
 /// A simplified wrapper around a raw memory buffer.
 ///
-/// On Unix systems, this uses `mmap` to allocate anonymous memory, which allows
-/// for lazy allocation (pages are only allocated by the OS when accessed).
-/// This significantly improves startup time and memory pressure for large RAM sizes.
+/// The buffer's address never changes once created, whatever the backing, so
+/// raw pointers taken with [`Self::as_mut_ptr`] stay valid for its lifetime.
 pub struct DramBuffer {
     ptr: *mut u8,
     size: usize,
-    is_mmap: bool,
+    storage: Storage,
 }
 
 unsafe impl Send for DramBuffer {}
@@ -26,7 +43,7 @@ impl std::fmt::Debug for DramBuffer {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("DramBuffer")
             .field("size", &self.size)
-            .field("is_mmap", &self.is_mmap)
+            .field("storage", &self.storage)
             .finish_non_exhaustive()
     }
 }
@@ -34,7 +51,8 @@ impl std::fmt::Debug for DramBuffer {
 impl DramBuffer {
     /// Creates a new DRAM buffer of the specified size.
     ///
-    /// On Unix, uses `mmap` for lazy allocation; on other platforms, allocates a `Vec`.
+    /// On Unix, uses an anonymous demand-paged `mmap`; on other platforms and
+    /// under Miri, allocates on the heap.
     ///
     /// # Arguments
     ///
@@ -48,32 +66,119 @@ impl DramBuffer {
     ///
     /// Panics if `mmap` fails on Unix.
     pub fn new(size: usize) -> Self {
-        #[cfg(unix)]
+        #[cfg(all(unix, not(miri)))]
+        {
+            // No swap reservation: the point is to commit only touched pages.
+            let flags = libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_NORESERVE;
+            let buffer = Self::map(size, flags, -1, 0);
+            buffer.unwrap_or_else(|e| panic!("Failed to mmap DRAM buffer of size {size}: {e}"))
+        }
+
+        // Miri cannot interpret `mmap`; fall back to a heap allocation.
+        #[cfg(any(not(unix), miri))]
+        {
+            Self::heap(size)
+        }
+    }
+
+    /// Creates a new zeroed DRAM buffer of the specified size on the heap.
+    ///
+    /// The whole buffer counts as resident.
+    pub fn heap(size: usize) -> Self {
+        let ptr = Box::into_raw(vec![0u8; size].into_boxed_slice()).cast::<u8>();
+        Self { ptr, size, storage: Storage::Heap }
+    }
+
+    /// Creates a DRAM buffer of the specified size mapped from `path` at
+    /// byte `offset`, which must be a multiple of the host page size.
+    ///
+    /// The file is created if missing and extended (with zeros) if shorter
+    /// than `offset + size`. Guest writes go to the file through the host
+    /// page cache.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened, extended or mapped,
+    /// or on a host without `mmap` (non-Unix, Miri).
+    pub fn from_file(path: &Path, offset: u64, size: usize) -> io::Result<Self> {
+        let file =
+            OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+        let end = offset + size as u64;
+        if file.metadata()?.len() < end {
+            file.set_len(end)?;
+        }
+
+        #[cfg(all(unix, not(miri)))]
         {
-            use std::ptr;
-            let ptr = unsafe {
-                libc::mmap(
-                    ptr::null_mut(),
-                    size,
-                    libc::PROT_READ | libc::PROT_WRITE,
-                    libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
-                    -1,
-                    0,
-                )
-            };
-
-            assert!(ptr != libc::MAP_FAILED, "Failed to mmap DRAM buffer of size {size}");
-
-            Self { ptr: ptr as *mut u8, size, is_mmap: true }
+            use std::os::fd::AsRawFd;
+            let offset = libc::off_t::try_from(offset).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidInput, "file offset too large")
+            })?;
+            // The mapping keeps the file referenced; `file` may be closed.
+            Self::map(size, libc::MAP_SHARED, file.as_raw_fd(), offset)
         }
 
-        #[cfg(not(unix))]
+        #[cfg(any(not(unix), miri))]
         {
-            let mut vec = vec![0u8; size];
-            let ptr = vec.as_mut_ptr();
-            std::mem::forget(vec);
-            Self { ptr, size, is_mmap: false }
+            Err(io::Error::new(io::ErrorKind::Unsupported, "file-backed RAM requires mmap"))
+        }
+    }
+
+    /// Maps `size` bytes read-write with the given `mmap` flags.
+    #[cfg(all(unix, not(miri)))]
+    fn map(
+        size: usize,
+        flags: libc::c_int,
+        fd: libc::c_int,
+        offset: libc::off_t,
+    ) -> io::Result<Self> {
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                size,
+                libc::PROT_READ | libc::PROT_WRITE,
+                flags,
+                fd,
+                offset,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { ptr: ptr.cast(), size, storage: Storage::Mmap })
+    }
+
+    /// Returns the number of bytes of the buffer backed by host memory.
+    ///
+    /// For an `mmap` backing this counts the pages currently resident (those
+    /// the guest or a loader has touched and the host has not evicted); a
+    /// heap buffer is wholly resident.
+    pub fn resident_bytes(&self) -> usize {
+        match self.storage {
+            Storage::Heap => self.size,
+            Storage::Mmap => self.resident_mapped_bytes(),
+        }
+    }
+
+    /// Counts the resident pages of an `mmap` backing with `mincore`.
+    #[cfg(all(unix, not(miri)))]
+    fn resident_mapped_bytes(&self) -> usize {
+        let page = usize::try_from(unsafe { libc::sysconf(libc::_SC_PAGESIZE) }).unwrap_or(4096);
+        let pages = self.size.div_ceil(page);
+        let mut residency = vec![0u8; pages];
+        let ok =
+            unsafe { libc::mincore(self.ptr.cast(), self.size, residency.as_mut_ptr().cast()) };
+        if ok != 0 {
+            return self.size;
         }
+        let resident = residency.iter().filter(|&&p| p & 1 != 0).count();
+        (resident * page).min(self.size)
+    }
+
+    /// Unreachable: `mmap` backings only exist on Unix outside Miri.
+    #[cfg(any(not(unix), miri))]
+    const fn resident_mapped_bytes(&self) -> usize {
+        self.size
     }
 
     /// Returns the size of the buffer in bytes.
@@ -145,19 +250,20 @@ impl DramBuffer {
 impl Drop for DramBuffer {
     /// Deallocates the DRAM buffer.
     ///
-    /// On Unix systems, unmaps the mmap'd memory. On other systems,
-    /// reconstructs the Vec to trigger its destructor.
+    /// Unmaps an `mmap` backing (flushing nothing explicitly: the kernel
+    /// writes dirty file-backed pages back on its own), or reconstructs the
+    /// boxed slice of a heap backing to free it.
     fn drop(&mut self) {
-        if self.is_mmap {
-            #[cfg(unix)]
-            unsafe {
-                let _ = libc::munmap(self.ptr as *mut _, self.size);
-            }
-        } else {
-            #[cfg(not(unix))]
-            unsafe {
-                let _ = Vec::from_raw_parts(self.ptr, self.size, self.size);
+        match self.storage {
+            Storage::Mmap => {
+                #[cfg(all(unix, not(miri)))]
+                unsafe {
+                    let _ = libc::munmap(self.ptr.cast(), self.size);
+                }
             }
+            Storage::Heap => unsafe {
+                drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(self.ptr, self.size)));
+            },
         }
     }
 }
@@ -2,11 +2,21 @@
 //!
 //! This module provides:
 //! 1. **SimpleController:** Fixed latency per access (no row-buffer modeling).
-//! 2. **DramController:** Multi-bank, row-buffer-aware latency with CAS, RAS,
-//!    precharge, tRRD, and periodic refresh for realistic DRAM timing.
+//! 2. **DramController:** Row-buffer-aware latency over interleaved channels
+//!    and banks: a row hit pays tCAS, a miss on a closed bank tRCD + tCAS and
+//!    a conflict with another open row tRP + tRCD + tCAS, with tRAS, tRRD
+//!    and periodic refresh for realistic DRAM timing. Row-buffer outcomes
+//!    are counted per channel and bank group.
+//! 3. **Request queue:** Optionally, the DRAM controller queues requests and
+//!    issues at most one per channel per cycle, in arrival order (FCFS) or
+//!    with row hits first (FR-FCFS), favouring reads until enough writes
+//!    have queued. Callers poll for each request's completion cycle.
 //!
 //! Controllers are `Send + Sync` for use with the Python bindings and multi-threaded simulation.
 
+use super::traffic::{MemoryTraffic, RowOutcome};
+use crate::config::DramScheduler;
+
 /// Trait for memory controller implementations that report access latency in cycles.
 ///
 /// Implementors must be `Send + Sync` for thread-safe use with the bus and Python bindings.
@@ -23,6 +33,61 @@ pub trait MemoryController: Send + Sync {
     ///
     /// Latency in simulation cycles.
     fn access_latency(&mut self, addr: u64, current_cycle: u64) -> u64;
+
+    /// Services a request of `bytes` bytes and records its completion latency
+    /// and size into `traffic`.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - Physical address being accessed.
+    /// * `bytes` - Size of the transfer in bytes (typically one cache line).
+    /// * `current_cycle` - The current simulation cycle.
+    /// * `traffic` - Latency histogram and bandwidth record to update.
+    ///
+    /// # Returns
+    ///
+    /// Latency in simulation cycles.
+    fn request(
+        &mut self,
+        addr: u64,
+        bytes: u64,
+        current_cycle: u64,
+        traffic: &mut MemoryTraffic,
+    ) -> u64 {
+        let latency = self.access_latency(addr, current_cycle);
+        traffic.record(current_cycle, latency, bytes);
+        latency
+    }
+
+    /// Queues a request for the scheduler and returns its id, or `None` if
+    /// the controller has no request queue and the caller should use
+    /// [`request`](Self::request) instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - Physical address being accessed.
+    /// * `bytes` - Size of the transfer in bytes.
+    /// * `is_write` - Whether the request writes memory.
+    /// * `current_cycle` - The cycle at which the request arrives.
+    fn enqueue(
+        &mut self,
+        _addr: u64,
+        _bytes: u64,
+        _is_write: bool,
+        _current_cycle: u64,
+    ) -> Option<u64> {
+        None
+    }
+
+    /// Advances the request scheduler by one cycle, issuing queued requests
+    /// and recording them into `traffic`.
+    fn tick(&mut self, _current_cycle: u64, _traffic: &mut MemoryTraffic) {}
+
+    /// Returns and forgets the completion cycle of queued request `id` once
+    /// it has been issued; `None` while it is still waiting.
+    fn take_completion(&mut self, _id: u64) -> Option<u64> {
+        None
+    }
 }
 
 /// Fixed-latency memory controller; every access takes the same number of cycles.
@@ -51,50 +116,101 @@ struct BankState {
     open_row: Option<u64>,
     /// Cycle at which this bank becomes available (after activation or refresh).
     busy_until: u64,
+    /// Cycle of the bank's last activation; its row stays open for at least
+    /// tRAS before it can be precharged.
+    activated_at: u64,
+}
+
+/// Per-channel state: its banks and tRRD tracking.
+#[derive(Debug)]
+struct Channel {
+    banks: Vec<BankState>,
+    /// Cycle of the last bank activation (for tRRD enforcement).
+    /// `None` means no activation has occurred yet.
+    last_activate_cycle: Option<u64>,
 }
 
 /// Configuration parameters for constructing a [`DramController`].
 #[derive(Clone, Copy, Debug)]
 pub struct DramConfig {
-    /// Column access strobe latency (cycles).
+    /// Column access strobe latency, tCAS (cycles).
     pub t_cas: u64,
-    /// Row access strobe latency (cycles).
+    /// Activate to column command delay, tRCD (cycles).
+    pub t_rcd: u64,
+    /// Minimum time a row stays open after activation, tRAS (cycles).
     pub t_ras: u64,
-    /// Precharge latency (cycles).
+    /// Precharge latency, tRP (cycles).
     pub t_pre: u64,
     /// Row-to-row delay for different-bank activations (cycles).
     pub t_rrd: u64,
-    /// Number of independent DRAM banks.
+    /// Number of independent channels, each with its own banks.
+    pub num_channels: usize,
+    /// Number of independent DRAM banks per channel.
     pub num_banks: usize,
+    /// Number of bank groups the banks of a channel are divided into, for
+    /// row-buffer statistics.
+    pub num_bank_groups: usize,
     /// Size of a DRAM row (page) in bytes. Must be a power of two.
     pub row_size_bytes: usize,
     /// Refresh interval in cycles (0 disables refresh).
     pub t_refi: u64,
     /// Refresh cycle time in cycles.
     pub t_rfc: u64,
+    /// Request scheduling policy.
+    pub scheduler: DramScheduler,
+    /// Cycles after which a queued request is no longer bypassed by row
+    /// hits (0 = never).
+    pub starvation_limit: u64,
+    /// Queued writes at which writes are drained ahead of reads (0 = no
+    /// read priority).
+    pub write_drain_threshold: usize,
+}
+
+/// A request waiting in the DRAM controller's queue.
+#[derive(Debug)]
+struct QueuedRequest {
+    id: u64,
+    addr: u64,
+    bytes: u64,
+    is_write: bool,
+    arrival: u64,
+    channel: usize,
+    bank: usize,
+    row: u64,
 }
 
-/// DRAM controller with multi-bank row buffers, tRRD, and refresh modeling.
+/// DRAM controller with multi-channel, multi-bank row buffers, tRRD, and
+/// refresh modeling.
 ///
-/// Each bank independently tracks its open row and busy state. Refresh
-/// periodically marks all banks as unavailable for `t_rfc` cycles.
+/// Consecutive rows are interleaved across channels first, then across the
+/// banks of a channel. Each bank independently tracks its open row and busy
+/// state. Refresh periodically marks all banks as unavailable for `t_rfc`
+/// cycles.
 #[derive(Debug)]
 pub struct DramController {
-    banks: Vec<BankState>,
+    channels: Vec<Channel>,
     num_banks: usize,
+    num_bank_groups: usize,
     t_cas: u64,
+    t_rcd: u64,
     t_ras: u64,
     t_pre: u64,
     t_rrd: u64,
     t_refi: u64,
     t_rfc: u64,
-    row_mask: u64,
     row_shift: u32,
-    /// Cycle of the last bank activation (for tRRD enforcement).
-    /// `None` means no activation has occurred yet.
-    last_activate_cycle: Option<u64>,
     /// Next cycle at which an auto-refresh fires.
     next_refresh_cycle: u64,
+    scheduler: DramScheduler,
+    starvation_limit: u64,
+    write_drain_threshold: usize,
+    /// Queued requests in arrival order.
+    queue: Vec<QueuedRequest>,
+    /// Issued requests not yet collected, as (id, completion cycle).
+    completed: Vec<(u64, u64)>,
+    next_id: u64,
+    /// Whether writes are being drained ahead of reads.
+    draining_writes: bool,
 }
 
 impl DramController {
@@ -105,44 +221,60 @@ impl DramController {
             "row_size_bytes must be a power of two"
         );
         debug_assert!(cfg.num_banks > 0, "num_banks must be > 0");
+        debug_assert!(cfg.num_channels > 0, "num_channels must be > 0");
 
-        let row_shift = cfg.row_size_bytes.trailing_zeros();
-        let row_mask = !(cfg.row_size_bytes as u64 - 1);
-
-        let mut banks = Vec::with_capacity(cfg.num_banks);
-        for _ in 0..cfg.num_banks {
-            banks.push(BankState { open_row: None, busy_until: 0 });
-        }
+        let channels = (0..cfg.num_channels)
+            .map(|_| Channel {
+                banks: (0..cfg.num_banks)
+                    .map(|_| BankState { open_row: None, busy_until: 0, activated_at: 0 })
+                    .collect(),
+                last_activate_cycle: None,
+            })
+            .collect();
 
         Self {
-            banks,
+            channels,
             num_banks: cfg.num_banks,
+            num_bank_groups: cfg.num_bank_groups.clamp(1, cfg.num_banks),
             t_cas: cfg.t_cas,
+            t_rcd: cfg.t_rcd,
             t_ras: cfg.t_ras,
             t_pre: cfg.t_pre,
             t_rrd: cfg.t_rrd,
             t_refi: cfg.t_refi,
             t_rfc: cfg.t_rfc,
-            row_mask,
-            row_shift,
-            last_activate_cycle: None,
+            row_shift: cfg.row_size_bytes.trailing_zeros(),
             next_refresh_cycle: if cfg.t_refi > 0 { cfg.t_refi } else { u64::MAX },
+            scheduler: cfg.scheduler,
+            starvation_limit: cfg.starvation_limit,
+            write_drain_threshold: cfg.write_drain_threshold,
+            queue: Vec::new(),
+            completed: Vec::new(),
+            next_id: 0,
+            draining_writes: false,
         }
     }
 
-    /// Determines the bank index for a given address.
+    /// Maps an address to its (channel, bank, row).
     ///
-    /// Bank selection uses the bits just above the row-offset bits:
-    /// `bank = (addr >> row_shift) % num_banks`
+    /// The bits just above the row offset select the channel, the next ones
+    /// the bank; the rest are the row:
+    /// `channel = (addr >> row_shift) % num_channels`,
+    /// `bank = (addr >> row_shift) / num_channels % num_banks`.
     #[inline]
-    const fn bank_index(&self, addr: u64) -> usize {
-        ((addr >> self.row_shift) as usize) % self.num_banks
+    const fn decode(&self, addr: u64) -> (usize, usize, u64) {
+        let page = addr >> self.row_shift;
+        let channels = self.channels.len() as u64;
+        let channel = (page % channels) as usize;
+        let bank = ((page / channels) % self.num_banks as u64) as usize;
+        let row = page / channels / self.num_banks as u64;
+        (channel, bank, row)
     }
 
-    /// Returns the row address for a given physical address.
+    /// Returns the bank group of `bank`; banks are assigned to groups in turn.
     #[inline]
-    const fn row_addr(&self, addr: u64) -> u64 {
-        addr & self.row_mask
+    const fn bank_group(&self, bank: usize) -> usize {
+        bank % self.num_bank_groups
     }
 
     /// Handles refresh: if `current_cycle` has reached or passed the next
@@ -158,7 +290,7 @@ impl DramController {
         while effective_cycle >= self.next_refresh_cycle {
             let refresh_end = self.next_refresh_cycle + self.t_rfc;
             // All banks become unavailable until refresh completes.
-            for bank in &mut self.banks {
+            for bank in self.channels.iter_mut().flat_map(|c| &mut c.banks) {
                 if bank.busy_until < refresh_end {
                     bank.busy_until = refresh_end;
                 }
@@ -174,55 +306,167 @@ impl DramController {
         effective_cycle
     }
 
-    /// Enforces tRRD spacing and performs a row activation. Returns the
-    /// ready cycle after activation constraints are applied.
-    const fn activate(&mut self, mut ready_cycle: u64) -> u64 {
-        if let Some(last_act) = self.last_activate_cycle {
+    /// Enforces tRRD spacing within `channel` and performs a row activation.
+    /// Returns the ready cycle after activation constraints are applied.
+    fn activate(&mut self, channel: usize, mut ready_cycle: u64) -> u64 {
+        let channel = &mut self.channels[channel];
+        if let Some(last_act) = channel.last_activate_cycle {
             let earliest_activate = last_act + self.t_rrd;
             if ready_cycle < earliest_activate {
                 ready_cycle = earliest_activate;
             }
         }
-        self.last_activate_cycle = Some(ready_cycle);
+        channel.last_activate_cycle = Some(ready_cycle);
         ready_cycle
     }
-}
 
-impl MemoryController for DramController {
-    fn access_latency(&mut self, addr: u64, current_cycle: u64) -> u64 {
+    /// Picks the queued request to issue on `channel` at `cycle`, returning
+    /// its index in the queue.
+    ///
+    /// Reads go first unless writes are being drained; the other kind is
+    /// only considered when none of the preferred kind is queued. Under FCFS
+    /// only the oldest request is a candidate, and only once its bank is
+    /// free. Under FR-FCFS the oldest row hit to a free bank goes first,
+    /// then the oldest request to a free bank, unless the oldest request has
+    /// waited `starvation_limit` cycles, in which case it is served next.
+    fn pick(&self, channel: usize, cycle: u64) -> Option<usize> {
+        let mut candidates: Vec<usize> =
+            (0..self.queue.len()).filter(|&i| self.queue[i].channel == channel).collect();
+        let preferred = |i: &usize| self.queue[*i].is_write == self.draining_writes;
+        if self.write_drain_threshold > 0 && candidates.iter().any(preferred) {
+            candidates.retain(preferred);
+        }
+        let bank = |i: usize| &self.channels[channel].banks[self.queue[i].bank];
+
+        let &oldest = candidates.first()?;
+        let starving = self.starvation_limit > 0
+            && cycle.saturating_sub(self.queue[oldest].arrival) >= self.starvation_limit;
+        if self.scheduler != DramScheduler::FrFcfs || starving {
+            return (bank(oldest).busy_until <= cycle).then_some(oldest);
+        }
+        candidates.retain(|&i| bank(i).busy_until <= cycle);
+        let row_hit = |i: &&usize| bank(**i).open_row == Some(self.queue[**i].row);
+        candidates.iter().find(row_hit).or_else(|| candidates.first()).copied()
+    }
+
+    /// Performs an access and returns its latency, the (channel, bank group)
+    /// it went to, and its row-buffer outcome.
+    fn access(&mut self, addr: u64, current_cycle: u64) -> (u64, (usize, usize), RowOutcome) {
         // 1. Handle any pending refresh.
         let mut ready_cycle = self.handle_refresh(current_cycle);
 
-        let bank_idx = self.bank_index(addr);
-        let row = self.row_addr(addr);
+        let (ch, bank_idx, row) = self.decode(addr);
+        let group = (ch, self.bank_group(bank_idx));
+        let bank = &self.channels[ch].banks[bank_idx];
 
         // 2. Wait for the target bank to be free.
-        if ready_cycle < self.banks[bank_idx].busy_until {
-            ready_cycle = self.banks[bank_idx].busy_until;
+        if ready_cycle < bank.busy_until {
+            ready_cycle = bank.busy_until;
         }
 
-        // 3. Determine row hit / miss / cold-start latency.
-        match self.banks[bank_idx].open_row {
+        // 3. Determine row hit / miss / conflict latency.
+        let outcome = match bank.open_row {
             Some(open_row) if open_row == row => {
                 // Row hit — just CAS (but must wait for bank to be free).
-                self.banks[bank_idx].busy_until = ready_cycle + self.t_cas;
-                (ready_cycle - current_cycle) + self.t_cas
+                self.channels[ch].banks[bank_idx].busy_until = ready_cycle + self.t_cas;
+                return ((ready_cycle - current_cycle) + self.t_cas, group, RowOutcome::Hit);
             }
             Some(_) => {
-                // Row miss — precharge + tRRD wait + activate + CAS.
-                ready_cycle += self.t_pre;
-                ready_cycle = self.activate(ready_cycle);
-                self.banks[bank_idx].open_row = Some(row);
-                self.banks[bank_idx].busy_until = ready_cycle + self.t_ras;
-                (ready_cycle - current_cycle) + self.t_ras + self.t_cas
+                // Row conflict — the open row must have been open for tRAS,
+                // then precharge + tRRD wait + activate + CAS.
+                ready_cycle = ready_cycle.max(bank.activated_at + self.t_ras) + self.t_pre;
+                RowOutcome::Conflict
             }
-            None => {
-                // Cold start — tRRD wait + activate + CAS.
-                ready_cycle = self.activate(ready_cycle);
-                self.banks[bank_idx].open_row = Some(row);
-                self.banks[bank_idx].busy_until = ready_cycle + self.t_ras;
-                (ready_cycle - current_cycle) + self.t_ras + self.t_cas
+            // Row miss (bank closed) — tRRD wait + activate + CAS.
+            None => RowOutcome::Miss,
+        };
+        ready_cycle = self.activate(ch, ready_cycle);
+        let bank = &mut self.channels[ch].banks[bank_idx];
+        bank.open_row = Some(row);
+        bank.activated_at = ready_cycle;
+        bank.busy_until = ready_cycle + self.t_rcd;
+        ((ready_cycle - current_cycle) + self.t_rcd + self.t_cas, group, outcome)
+    }
+}
+
+impl MemoryController for DramController {
+    fn access_latency(&mut self, addr: u64, current_cycle: u64) -> u64 {
+        self.access(addr, current_cycle).0
+    }
+
+    fn request(
+        &mut self,
+        addr: u64,
+        bytes: u64,
+        current_cycle: u64,
+        traffic: &mut MemoryTraffic,
+    ) -> u64 {
+        let (latency, (channel, bank_group), outcome) = self.access(addr, current_cycle);
+        traffic.record(current_cycle, latency, bytes);
+        traffic.record_row(channel, bank_group, outcome);
+        latency
+    }
+
+    fn enqueue(
+        &mut self,
+        addr: u64,
+        bytes: u64,
+        is_write: bool,
+        current_cycle: u64,
+    ) -> Option<u64> {
+        if self.scheduler == DramScheduler::Immediate {
+            return None;
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        let (channel, bank, row) = self.decode(addr);
+        self.queue.push(QueuedRequest {
+            id,
+            addr,
+            bytes,
+            is_write,
+            arrival: current_cycle,
+            channel,
+            bank,
+            row,
+        });
+        Some(id)
+    }
+
+    fn tick(&mut self, current_cycle: u64, traffic: &mut MemoryTraffic) {
+        if self.scheduler == DramScheduler::Immediate {
+            return;
+        }
+        traffic.record_queue_occupancy(self.queue.len());
+        if self.queue.is_empty() {
+            return;
+        }
+        let _ = self.handle_refresh(current_cycle);
+
+        let writes = self.queue.iter().filter(|r| r.is_write).count();
+        if self.write_drain_threshold > 0 && writes >= self.write_drain_threshold {
+            self.draining_writes = true;
+        } else if writes == 0 {
+            self.draining_writes = false;
+        }
+
+        for channel in 0..self.channels.len() {
+            let Some(idx) = self.pick(channel, current_cycle) else { continue };
+            let req = self.queue.remove(idx);
+            let (latency, (ch, bank_group), outcome) = self.access(req.addr, current_cycle);
+            let done = current_cycle + latency;
+            let total = done - req.arrival;
+            traffic.record(req.arrival, total, req.bytes);
+            traffic.record_row(ch, bank_group, outcome);
+            if !req.is_write {
+                traffic.record_read(total);
             }
+            self.completed.push((req.id, done));
         }
     }
+
+    fn take_completion(&mut self, id: u64) -> Option<u64> {
+        let idx = self.completed.iter().position(|&(c, _)| c == id)?;
+        Some(self.completed.swap_remove(idx).1)
+    }
 }
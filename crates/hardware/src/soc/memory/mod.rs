@@ -4,6 +4,7 @@
 //! 1. **Buffer:** Backing storage (e.g., `DramBuffer`) for RAM contents.
 //! 2. **Memory:** Device implementation that maps the buffer at a physical base address.
 //! 3. **Controller:** Latency modeling (simple or DRAM row-buffer) for timing simulation.
+//! 4. **Traffic:** Latency histogram and bandwidth recording for controller requests.
 
 /// DRAM buffer implementation (e.g., mmap or `Vec`) for raw byte storage.
 pub mod buffer;
@@ -11,6 +12,9 @@ pub mod buffer;
 /// Memory controller implementations for access latency modeling.
 pub mod controller;
 
+/// Memory request latency histogram and bandwidth tracking.
+pub mod traffic;
+
 use self::buffer::DramBuffer;
 use crate::soc::devices::Device;
 use std::sync::Arc;
@@ -49,6 +53,11 @@ impl Memory {
         }
     }
 
+    /// Returns the shared backing buffer.
+    pub const fn buffer(&self) -> &Arc<DramBuffer> {
+        &self.buffer
+    }
+
     /// Returns a raw mutable pointer to the underlying memory buffer.
     ///
     /// Required for devices like `VirtIO` that perform direct memory access (DMA)
@@ -0,0 +1,283 @@
+//! Memory traffic recording for latency and bandwidth analysis.
+//!
+//! This module provides [`MemoryTraffic`], which the memory controller fills in
+//! as requests complete. It tracks:
+//! 1. **Latency distribution:** A per-cycle histogram of request completion latency,
+//!    from which the average and tail (p99) latency are derived.
+//! 2. **Bandwidth:** Bytes transferred per fixed-size cycle window, keeping the
+//!    peak window to report sustained bandwidth bursts.
+//! 3. **Row buffer:** DRAM row hits, misses and conflicts per channel and bank
+//!    group, for controllers that model row buffers.
+//! 4. **Request queue:** Queue occupancy and a separate read latency
+//!    histogram, for controllers that schedule requests from a queue.
+
+/// Number of single-cycle histogram buckets. Latencies at or above this value
+/// are accumulated in the final (overflow) bucket.
+pub const LATENCY_BUCKETS: usize = 1024;
+
+/// Width of a bandwidth accounting window, in cycles.
+pub const BANDWIDTH_WINDOW_CYCLES: u64 = 1000;
+
+/// Outcome of a DRAM access at the bank's row buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RowOutcome {
+    /// The row was already open.
+    Hit,
+    /// The bank had no open row.
+    Miss,
+    /// Another row was open and had to be closed first.
+    Conflict,
+}
+
+/// Row-buffer outcome counts for one bank group of one channel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RowBufferStats {
+    /// Channel index.
+    pub channel: usize,
+    /// Bank group index within the channel.
+    pub bank_group: usize,
+    /// Accesses that found their row open.
+    pub hits: u64,
+    /// Accesses to a bank with no open row.
+    pub misses: u64,
+    /// Accesses that found another row open.
+    pub conflicts: u64,
+}
+
+impl RowBufferStats {
+    /// Returns the number of accesses counted.
+    pub const fn accesses(&self) -> u64 {
+        self.hits + self.misses + self.conflicts
+    }
+
+    /// Returns the fraction of accesses that were row hits (0.0 if none).
+    pub fn hit_rate(&self) -> f64 {
+        let accesses = self.accesses();
+        if accesses == 0 { 0.0 } else { self.hits as f64 / accesses as f64 }
+    }
+}
+
+/// Latency histogram and bandwidth tracker for memory controller requests.
+#[derive(Clone, Debug)]
+pub struct MemoryTraffic {
+    /// Request count per completion latency (index = cycles, last bucket = overflow).
+    histogram: Box<[u64; LATENCY_BUCKETS]>,
+    /// Total requests recorded.
+    requests: u64,
+    /// Total bytes transferred.
+    bytes: u64,
+    /// Sum of all request latencies (for the average).
+    latency_sum: u64,
+    /// Largest latency observed.
+    max_latency: u64,
+    /// Index of the bandwidth window currently being filled.
+    window_index: u64,
+    /// Bytes completed in the current window.
+    window_bytes: u64,
+    /// Largest number of bytes completed in any single window.
+    peak_window_bytes: u64,
+    /// Row-buffer outcomes per (channel, bank group), in that order.
+    row_buffer: Vec<RowBufferStats>,
+    /// Queued read count per latency from arrival (last bucket = overflow).
+    read_histogram: Box<[u64; LATENCY_BUCKETS]>,
+    /// Total queued reads recorded.
+    reads: u64,
+    /// Sum of queued read latencies.
+    read_latency_sum: u64,
+    /// Largest queued read latency observed.
+    max_read_latency: u64,
+    /// Sum of the request queue length over sampled cycles.
+    queue_occupancy_sum: u64,
+    /// Cycles on which the request queue length was sampled.
+    queue_samples: u64,
+}
+
+impl Default for MemoryTraffic {
+    /// Returns an empty traffic record.
+    fn default() -> Self {
+        Self {
+            histogram: Box::new([0; LATENCY_BUCKETS]),
+            requests: 0,
+            bytes: 0,
+            latency_sum: 0,
+            max_latency: 0,
+            window_index: 0,
+            window_bytes: 0,
+            peak_window_bytes: 0,
+            row_buffer: Vec::new(),
+            read_histogram: Box::new([0; LATENCY_BUCKETS]),
+            reads: 0,
+            read_latency_sum: 0,
+            max_read_latency: 0,
+            queue_occupancy_sum: 0,
+            queue_samples: 0,
+        }
+    }
+}
+
+impl MemoryTraffic {
+    /// Records a completed request.
+    ///
+    /// # Arguments
+    ///
+    /// * `issue_cycle` - Cycle at which the request was issued to the controller.
+    /// * `latency` - Cycles until the request completed.
+    /// * `bytes` - Number of bytes transferred by the request.
+    pub fn record(&mut self, issue_cycle: u64, latency: u64, bytes: u64) {
+        let bucket = (latency as usize).min(LATENCY_BUCKETS - 1);
+        self.histogram[bucket] += 1;
+        self.requests += 1;
+        self.bytes += bytes;
+        self.latency_sum += latency;
+        self.max_latency = self.max_latency.max(latency);
+
+        // Bytes are attributed to the window in which the request completes.
+        // Completions that land in an already-closed window (reordered by the
+        // controller) are charged to the current window.
+        let window = issue_cycle.saturating_add(latency) / BANDWIDTH_WINDOW_CYCLES;
+        if window > self.window_index {
+            self.window_index = window;
+            self.window_bytes = 0;
+        }
+        self.window_bytes += bytes;
+        self.peak_window_bytes = self.peak_window_bytes.max(self.window_bytes);
+    }
+
+    /// Records the row-buffer outcome of an access to `bank_group` of `channel`.
+    pub fn record_row(&mut self, channel: usize, bank_group: usize, outcome: RowOutcome) {
+        let key = (channel, bank_group);
+        let idx = match self.row_buffer.binary_search_by_key(&key, |s| (s.channel, s.bank_group)) {
+            Ok(idx) => idx,
+            Err(idx) => {
+                self.row_buffer
+                    .insert(idx, RowBufferStats { channel, bank_group, ..Default::default() });
+                idx
+            }
+        };
+        let stats = &mut self.row_buffer[idx];
+        match outcome {
+            RowOutcome::Hit => stats.hits += 1,
+            RowOutcome::Miss => stats.misses += 1,
+            RowOutcome::Conflict => stats.conflicts += 1,
+        }
+    }
+
+    /// Records the latency, from arrival to completion, of a queued read.
+    pub fn record_read(&mut self, latency: u64) {
+        let bucket = (latency as usize).min(LATENCY_BUCKETS - 1);
+        self.read_histogram[bucket] += 1;
+        self.reads += 1;
+        self.read_latency_sum += latency;
+        self.max_read_latency = self.max_read_latency.max(latency);
+    }
+
+    /// Samples the request queue length for one cycle.
+    pub const fn record_queue_occupancy(&mut self, len: usize) {
+        self.queue_occupancy_sum += len as u64;
+        self.queue_samples += 1;
+    }
+
+    /// Returns the row-buffer outcomes of every (channel, bank group) accessed
+    /// so far, in that order. Empty for controllers without row buffers.
+    pub fn row_buffer(&self) -> &[RowBufferStats] {
+        &self.row_buffer
+    }
+
+    /// Returns the number of requests recorded.
+    pub const fn requests(&self) -> u64 {
+        self.requests
+    }
+
+    /// Returns the total number of bytes transferred.
+    pub const fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Returns the largest latency observed, in cycles.
+    pub const fn max_latency(&self) -> u64 {
+        self.max_latency
+    }
+
+    /// Returns the average request latency in cycles (0.0 if no requests).
+    pub fn avg_latency(&self) -> f64 {
+        if self.requests == 0 { 0.0 } else { self.latency_sum as f64 / self.requests as f64 }
+    }
+
+    /// Returns the latency (in cycles) at or below which `pct` percent of
+    /// requests completed. Latencies in the overflow bucket report the
+    /// maximum observed latency.
+    pub fn percentile_latency(&self, pct: f64) -> u64 {
+        percentile(&self.histogram[..], self.requests, self.max_latency, pct)
+    }
+
+    /// Returns the 99th-percentile request latency in cycles.
+    pub fn p99_latency(&self) -> u64 {
+        self.percentile_latency(99.0)
+    }
+
+    /// Returns the peak bandwidth in bytes per cycle, measured over
+    /// [`BANDWIDTH_WINDOW_CYCLES`]-cycle windows.
+    pub fn peak_bandwidth(&self) -> f64 {
+        self.peak_window_bytes as f64 / BANDWIDTH_WINDOW_CYCLES as f64
+    }
+
+    /// Returns the latency histogram (index = latency in cycles, last bucket = overflow).
+    pub fn histogram(&self) -> &[u64] {
+        &self.histogram[..]
+    }
+
+    /// Returns the number of queued reads recorded.
+    pub const fn reads(&self) -> u64 {
+        self.reads
+    }
+
+    /// Returns the average queued read latency in cycles (0.0 if none).
+    pub fn avg_read_latency(&self) -> f64 {
+        if self.reads == 0 { 0.0 } else { self.read_latency_sum as f64 / self.reads as f64 }
+    }
+
+    /// Returns the latency (in cycles) at or below which `pct` percent of
+    /// queued reads completed.
+    pub fn percentile_read_latency(&self, pct: f64) -> u64 {
+        percentile(&self.read_histogram[..], self.reads, self.max_read_latency, pct)
+    }
+
+    /// Returns the queued read latency histogram (index = latency in cycles,
+    /// last bucket = overflow).
+    pub fn read_histogram(&self) -> &[u64] {
+        &self.read_histogram[..]
+    }
+
+    /// Returns whether the controller queued requests, i.e. whether queue
+    /// occupancy was sampled.
+    pub const fn is_queued(&self) -> bool {
+        self.queue_samples > 0
+    }
+
+    /// Returns the average request queue length over sampled cycles (0.0 if
+    /// the controller has no queue).
+    pub fn avg_queue_occupancy(&self) -> f64 {
+        if self.queue_samples == 0 {
+            0.0
+        } else {
+            self.queue_occupancy_sum as f64 / self.queue_samples as f64
+        }
+    }
+}
+
+/// Returns the latency at or below which `pct` percent of the `total`
+/// samples in `histogram` lie. The overflow bucket reports `max`.
+fn percentile(histogram: &[u64], total: u64, max: u64, pct: f64) -> u64 {
+    if total == 0 {
+        return 0;
+    }
+    let target = ((total as f64 * pct / 100.0).ceil() as u64).clamp(1, total);
+    let mut seen = 0;
+    for (latency, &count) in histogram.iter().enumerate() {
+        seen += count;
+        if seen >= target {
+            return if latency == LATENCY_BUCKETS - 1 { max } else { latency as u64 };
+        }
+    }
+    max
+}
@@ -0,0 +1,156 @@
+//! MMIO access trace and per-device access counts.
+//!
+//! The bus reports every access that reaches a device other than RAM. It provides:
+//! 1. **Trace:** An optional [`MmioTraceSink`] receives each access (cycle, hart, address,
+//!    device, size, value); [`MmioTraceFile`] writes them as text lines.
+//! 2. **Counts:** [`MmioStats`] accumulates reads, writes, and bytes per device name.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::common::SimError;
+
+/// One device access seen by the bus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MmioAccess<'a> {
+    /// Cycle of the hart that made the access.
+    pub cycle: u64,
+    /// Hart that made the access.
+    pub hart: usize,
+    /// Physical address.
+    pub paddr: u64,
+    /// Name of the device that claimed the address.
+    pub device: &'a str,
+    /// Access size in bytes.
+    pub size: u64,
+    /// Value read or written, zero-extended.
+    pub value: u64,
+    /// `true` for a write, `false` for a read.
+    pub is_write: bool,
+}
+
+/// Receiver of the bus MMIO trace.
+pub trait MmioTraceSink {
+    /// Called once for every device access, in the order the bus sees them.
+    fn record(&mut self, access: &MmioAccess<'_>);
+}
+
+/// MMIO trace written to a text file, one access per line:
+/// `<cycle> <hart> <R|W> <address> <device> <size> <value>`.
+#[derive(Debug)]
+pub struct MmioTraceFile {
+    out: BufWriter<File>,
+}
+
+impl MmioTraceFile {
+    /// Creates (truncating) the trace file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SimError::FileRead`] if the file cannot be created.
+    pub fn create(path: &str) -> Result<Self, SimError> {
+        let file = File::create(path)
+            .map_err(|source| SimError::FileRead { path: path.to_owned(), source })?;
+        Ok(Self { out: BufWriter::new(file) })
+    }
+}
+
+impl MmioTraceSink for MmioTraceFile {
+    fn record(&mut self, a: &MmioAccess<'_>) {
+        let _ = writeln!(
+            self.out,
+            "{} {} {} {:#x} {} {} {:#x}",
+            a.cycle,
+            a.hart,
+            if a.is_write { 'W' } else { 'R' },
+            a.paddr,
+            a.device,
+            a.size,
+            a.value
+        );
+    }
+}
+
+impl Drop for MmioTraceFile {
+    fn drop(&mut self) {
+        let _ = self.out.flush();
+    }
+}
+
+/// Access counts for one device.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DeviceAccessStats {
+    /// Read accesses.
+    pub reads: u64,
+    /// Write accesses.
+    pub writes: u64,
+    /// Bytes read.
+    pub bytes_read: u64,
+    /// Bytes written.
+    pub bytes_written: u64,
+}
+
+impl DeviceAccessStats {
+    /// Adds the counts in `other` to these.
+    pub const fn add(&mut self, other: &Self) {
+        self.reads += other.reads;
+        self.writes += other.writes;
+        self.bytes_read += other.bytes_read;
+        self.bytes_written += other.bytes_written;
+    }
+}
+
+/// Per-device access counts, keyed by device name.
+#[derive(Clone, Debug, Default)]
+pub struct MmioStats {
+    devices: BTreeMap<String, DeviceAccessStats>,
+}
+
+impl MmioStats {
+    /// Counts one access of `size` bytes to `device`.
+    pub fn record(&mut self, device: &str, size: u64, is_write: bool) {
+        if !self.devices.contains_key(device) {
+            let _ = self.devices.insert(device.to_owned(), DeviceAccessStats::default());
+        }
+        let Some(entry) = self.devices.get_mut(device) else { return };
+        if is_write {
+            entry.writes += 1;
+            entry.bytes_written += size;
+        } else {
+            entry.reads += 1;
+            entry.bytes_read += size;
+        }
+    }
+
+    /// Adds every count in `other` to this record.
+    pub fn merge(&mut self, other: &Self) {
+        for (name, s) in &other.devices {
+            self.devices.entry(name.clone()).or_default().add(s);
+        }
+    }
+
+    /// Returns the counts for `device`, if it was accessed.
+    pub fn get(&self, device: &str) -> Option<&DeviceAccessStats> {
+        self.devices.get(device)
+    }
+
+    /// Iterates over the accessed devices in name order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &DeviceAccessStats)> {
+        self.devices.iter().map(|(name, s)| (name.as_str(), s))
+    }
+
+    /// Returns whether no access has been counted.
+    pub fn is_empty(&self) -> bool {
+        self.devices.is_empty()
+    }
+
+    /// Returns the counts summed over every device.
+    pub fn total(&self) -> DeviceAccessStats {
+        let mut total = DeviceAccessStats::default();
+        for s in self.devices.values() {
+            total.add(s);
+        }
+        total
+    }
+}
@@ -16,6 +16,9 @@ pub mod interconnect;
 /// Memory controller implementations.
 pub mod memory;
 
+/// MMIO access trace and per-device access counts.
+pub mod mmio_trace;
+
 /// Device trait definitions for MMIO access.
 pub mod traits;
 
@@ -3,13 +3,14 @@
 //! This module defines the `Device` trait implemented by all bus-attached components. It provides:
 //! 1. **Identification:** `name` and `address_range` for bus routing.
 //! 2. **Access:** Byte, half, word, and doubleword read/write at device-relative offsets.
-//! 3. **Lifecycle:** Optional `tick` and IRQ reporting for timer and interrupt devices.
-//! 4. **Downcasting:** Optional casts to `Plic`, `Uart`, or `Memory` for device-specific access.
+//! 3. **Lifecycle:** Optional `tick`, IRQ and DMA-write reporting for timer, interrupt and DMA devices.
+//! 4. **Checkpointing:** Optional `save_state`/`restore_state` for devices with guest-visible state.
+//! 5. **Downcasting:** Optional casts to `Plic`, `Uart`, `SysCon`, `VirtioNet`, `VirtioBlock`, `DmaController`, `Htif`, or `Memory` for device-specific access.
 //!
 //! All implementors must be `Send + Sync` for use with the Python bindings and multi-threaded simulation.
 
 use crate::common::IrqId;
-use crate::soc::devices::{Clint, Plic, Uart};
+use crate::soc::devices::{Clint, DmaController, Htif, Plic, SysCon, Uart, VirtioBlock, VirtioNet};
 use crate::soc::memory::Memory;
 
 /// Trait for memory-mapped I/O devices attached to the system bus.
@@ -53,6 +54,26 @@ pub trait Device: Send + Sync {
     fn get_irq_id(&self) -> Option<IrqId> {
         None
     }
+    /// Moves the (`physical_address`, `length`) of every DMA write to RAM made since the
+    /// last call into `out` (default: the device does no DMA).
+    fn take_dma_writes(&mut self, _out: &mut Vec<(u64, u64)>) {}
+
+    /// Returns the device's state for a checkpoint (default: `None`, nothing to save).
+    ///
+    /// Covers what the guest can observe (registers, FIFOs, queue positions), not host-side
+    /// connections such as stdin or a TAP interface.
+    fn save_state(&self) -> Option<serde_json::Value> {
+        None
+    }
+    /// Restores state returned by [`Device::save_state`] on a device built from the same
+    /// configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the mismatch if `state` does not fit this device.
+    fn restore_state(&mut self, _state: serde_json::Value) -> Result<(), String> {
+        Ok(())
+    }
 
     /// Returns a mutable reference as `Clint` if this device is the CLINT; otherwise `None`.
     fn as_clint_mut(&mut self) -> Option<&mut Clint> {
@@ -66,6 +87,26 @@ pub trait Device: Send + Sync {
     fn as_uart_mut(&mut self) -> Option<&mut Uart> {
         None
     }
+    /// Returns a mutable reference as `SysCon` if this device is the system controller; otherwise `None`.
+    fn as_syscon_mut(&mut self) -> Option<&mut SysCon> {
+        None
+    }
+    /// Returns a mutable reference as `VirtioNet` if this device is the network device; otherwise `None`.
+    fn as_virtio_net_mut(&mut self) -> Option<&mut VirtioNet> {
+        None
+    }
+    /// Returns a mutable reference as `VirtioBlock` if this device is the block device; otherwise `None`.
+    fn as_virtio_blk_mut(&mut self) -> Option<&mut VirtioBlock> {
+        None
+    }
+    /// Returns a mutable reference as `DmaController` if this device is the DMA engine; otherwise `None`.
+    fn as_dma_mut(&mut self) -> Option<&mut DmaController> {
+        None
+    }
+    /// Returns a mutable reference as `Htif` if this device is the HTIF; otherwise `None`.
+    fn as_htif_mut(&mut self) -> Option<&mut Htif> {
+        None
+    }
     /// Returns a mutable reference as `Memory` if this device is RAM; otherwise `None`.
     fn as_memory_mut(&mut self) -> Option<&mut Memory> {
         None
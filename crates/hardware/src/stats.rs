@@ -4,13 +4,89 @@
 //! 1. **Cycle and IPC:** Total cycles, retired instructions, and derived metrics (CPI, MIPS).
 //! 2. **Instruction mix:** Counts by category (ALU, load, store, branch, system, FP).
 //! 3. **Branch prediction:** Lookups, mispredictions, and accuracy.
-//! 4. **Stalls:** Memory, control, and data hazard stall counts.
+//! 4. **Stalls:** Memory, control, and data hazard stall counts, plus the cause
+//!    charged for every cycle in which nothing retired.
 //! 5. **Cache hierarchy:** Hit/miss counts for L1-I, L1-D, L2, and L3.
+//! 6. **Main memory:** Controller request latency distribution and peak bandwidth,
+//!    plus the bytes moved between cache levels and DRAM.
+//! 7. **Occupancy:** Per-cycle ROB, store buffer, and issue queue occupancy histograms.
+//! 8. **MMIO:** Reads, writes, and bytes per device, for accesses other than RAM.
 
-use crate::core::pipeline::backend::o3::fu_pool::FU_TYPE_COUNT;
+use crate::core::pipeline::backend::o3::fu_pool::{
+    FU_TYPE_COUNT, FuType, PORT_CLASS_COUNT, PortClass,
+};
+use crate::core::pipeline::occupancy::OccupancyHistogram;
+use crate::core::units::cache::bandwidth::{BandwidthStats, Link, Traffic};
+use crate::soc::devices::virtio_disk::DiskWriteStats;
+use crate::soc::memory::traffic::MemoryTraffic;
+use crate::soc::mmio_trace::MmioStats;
 use std::io::IsTerminal;
 use std::time::Instant;
 
+/// Cause charged for a cycle in which no instruction retired.
+///
+/// Cycles with an empty ROB are blamed on the frontend; cycles with a
+/// non-empty ROB are blamed on whatever is holding the ROB head.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum StallCause {
+    /// Fetch waiting on an L1-I miss.
+    ICacheMiss = 0,
+    /// Fetch waiting on an I-TLB miss (page table walk).
+    ITlbMiss = 1,
+    /// Refilling the pipeline after a misprediction or other redirect.
+    BranchRecovery = 2,
+    /// ROB empty with no fetch stall: decode delivered nothing to rename.
+    DecodeStarvation = 3,
+    /// Dispatch blocked by a full ROB.
+    RobFull = 4,
+    /// Dispatch blocked by a full store buffer.
+    StoreBufferFull = 5,
+    /// ROB head waiting on its operands (RAW hazard).
+    Scoreboard = 6,
+    /// ROB head is a memory operation still waiting on the D-cache.
+    DCacheMiss = 7,
+    /// ROB head is executing on a multiply, divide or FP unit.
+    LongLatencyFu = 8,
+    /// Memory pipeline could not accept the next operation.
+    Backpressure = 9,
+}
+
+/// Number of distinct stall causes.
+pub const STALL_CAUSE_COUNT: usize = 10;
+
+impl StallCause {
+    /// Every stall cause, in `StallCause as usize` order.
+    pub const ALL: [Self; STALL_CAUSE_COUNT] = [
+        Self::ICacheMiss,
+        Self::ITlbMiss,
+        Self::BranchRecovery,
+        Self::DecodeStarvation,
+        Self::RobFull,
+        Self::StoreBufferFull,
+        Self::Scoreboard,
+        Self::DCacheMiss,
+        Self::LongLatencyFu,
+        Self::Backpressure,
+    ];
+
+    /// Human-readable name for stats output.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::ICacheMiss => "icache_miss",
+            Self::ITlbMiss => "itlb_miss",
+            Self::BranchRecovery => "branch_recovery",
+            Self::DecodeStarvation => "decode_starvation",
+            Self::RobFull => "rob_full",
+            Self::StoreBufferFull => "store_buffer_full",
+            Self::Scoreboard => "scoreboard",
+            Self::DCacheMiss => "dcache_miss",
+            Self::LongLatencyFu => "long_latency_fu",
+            Self::Backpressure => "backpressure",
+        }
+    }
+}
+
 /// Simulation statistics structure tracking all performance metrics.
 ///
 /// Collects detailed statistics about instruction execution, cache behavior,
@@ -20,8 +96,14 @@ pub struct SimStats {
     start_time: Instant,
     /// Total simulator cycles elapsed.
     pub cycles: u64,
+    /// Value of `cycles` when a reboot last reset the statistics (see
+    /// `general.reboot_stats`). `cycles` itself keeps running, as it is also
+    /// the machine clock.
+    pub boot_cycle: u64,
     /// Number of instructions committed (retired).
     pub instructions_retired: u64,
+    /// Number of macro-op fused pairs retired (each counts as two instructions).
+    pub fused_pairs: u64,
 
     /// Count of integer load instructions retired.
     pub inst_load: u64,
@@ -67,6 +149,10 @@ pub struct SimStats {
     /// Cycles where the ROB was empty at commit (pipeline draining/refilling after flush).
     pub cycles_rob_empty: u64,
 
+    /// Cycles in which nothing retired (outside WFI), charged to one cause each.
+    /// Indexed by `StallCause as usize`.
+    pub stall_cycles: [u64; STALL_CAUSE_COUNT],
+
     /// Stall cycles due to memory (cache/memory not ready).
     pub stalls_mem: u64,
     /// Stall cycles due to control hazards (branch resolution, flush).
@@ -76,6 +162,16 @@ pub struct SimStats {
 
     /// Number of traps (exceptions or interrupts) taken.
     pub traps_taken: u64,
+    /// Number of interrupts taken at the commit boundary.
+    pub interrupts_taken: u64,
+    /// Total cycles from an enabled interrupt becoming pending to its vector.
+    pub interrupt_latency_cycles: u64,
+    /// Longest single interrupt latency in cycles.
+    pub interrupt_latency_max: u64,
+    /// System calls serviced by syscall emulation.
+    pub syscalls: u64,
+    /// System calls syscall emulation does not implement (answered `-ENOSYS`).
+    pub syscalls_unknown: u64,
 
     /// L1 instruction cache hit count.
     pub icache_hits: u64,
@@ -98,9 +194,32 @@ pub struct SimStats {
     /// Indexed by `FuType as usize` (see `fu_pool::FU_TYPE_COUNT`).
     pub fu_utilization: [u64; FU_TYPE_COUNT],
 
+    /// Busy cycles per FU type: cycles a unit spent unable to accept a new
+    /// instruction (one per issue if pipelined, the full latency if not).
+    /// Indexed by `FuType as usize`.
+    pub fu_busy_cycles: [u64; FU_TYPE_COUNT],
+
     /// Stall cycles where a ready IQ entry could not issue (no free FU).
     pub stalls_fu_structural: u64,
 
+    /// Instructions issued per port class.
+    /// Indexed by `PortClass as usize` (see `fu_pool::PORT_CLASS_COUNT`).
+    pub port_issues: [u64; PORT_CLASS_COUNT],
+    /// Ready instructions held back because their class's issue ports were taken.
+    pub stalls_issue_port: u64,
+
+    /// Fetch buffer occupancy summed over every frontend cycle (divide by
+    /// `cycles` for the average).
+    pub fetch_buffer_occupancy: u64,
+    /// Largest fetch buffer occupancy seen.
+    pub fetch_buffer_peak: u64,
+    /// Frontend cycles with no room in the fetch buffer for another fetch block.
+    pub fetch_buffer_full_cycles: u64,
+    /// 32-bit instructions fetched across an I-cache line boundary.
+    pub fetch_line_crossings: u64,
+    /// Fetch stall cycles charged for the second I-cache access of line-crossing instructions.
+    pub fetch_line_crossing_cycles: u64,
+
     /// Total ROB entries squashed due to branch mispredictions / ordering violations.
     pub misprediction_penalty: u64,
 
@@ -109,6 +228,10 @@ pub struct SimStats {
 
     /// Number of memory ordering violations detected (load queue).
     pub mem_ordering_violations: u64,
+    /// Loads satisfied by store-to-load forwarding from the store buffer.
+    pub store_forwards: u64,
+    /// Load replays caused by a partially overlapping older store.
+    pub store_forward_stalls: u64,
 
     /// Number of pipeline flushes (mispredictions + violations + CSR/FENCE redirects).
     pub pipeline_flushes: u64,
@@ -119,6 +242,8 @@ pub struct SimStats {
     pub mshr_coalesces: u64,
     /// Stalls due to all MSHRs being full.
     pub stalls_mshr_full: u64,
+    /// Memory accesses deferred a cycle because every L1D port was taken.
+    pub stalls_dcache_port: u64,
     /// Load replays due to speculative wakeup on L1D miss.
     pub load_replays: u64,
 
@@ -145,9 +270,19 @@ pub struct SimStats {
     /// (ROB/SB/LQ/IQ/PRF full).
     pub stalls_dispatch: u64,
 
+    /// Dispatch stall cycles where the store buffer had no free entry.
+    pub stalls_store_buffer_full: u64,
+
     /// Stall cycles where a branch/jump could not dispatch because the checkpoint table was full.
     pub stalls_checkpoint: u64,
 
+    /// Flushes recovered by restoring a branch checkpoint instead of walking the ROB.
+    pub checkpoint_restores: u64,
+
+    /// Surviving ROB entries walked to rebuild the rename map / scoreboard after
+    /// flushes that had no checkpoint.
+    pub recovery_walk_entries: u64,
+
     /// Stall cycles where rename could not dispatch because the rename map was being rebuilt
     /// by walking the ROB (no checkpoint available for this flush).
     pub stalls_rename_rebuild: u64,
@@ -170,10 +305,44 @@ pub struct SimStats {
     pub mdp_predictions_wait_for: u64,
     /// MDP: violations (calls to update).
     pub mdp_violations: u64,
+    /// MDP: `WaitFor` loads that overlapped the predicted store.
+    pub mdp_avoided_violations: u64,
+    /// MDP: `WaitFor` loads that did not overlap the predicted store.
+    pub mdp_false_dependences: u64,
 
     /// Retirement histogram: how many instructions were retired per cycle.
     /// Index 0 = cycles with 0 retires, 1 = 1 retire, 2 = 2 retires, 3 = 3+ retires.
     pub retire_histogram: [u64; 4],
+
+    /// Memory controller traffic: request latency histogram and bandwidth windows.
+    pub mem_traffic: MemoryTraffic,
+
+    /// Bytes moved between cache levels and DRAM, by link, kind and interval.
+    pub bandwidth: BandwidthStats,
+
+    /// Host memory backing guest RAM, in bytes, as of the last snapshot
+    /// (see `DramBuffer::resident_bytes`); 0 if never sampled.
+    pub ram_resident_bytes: u64,
+
+    /// Bits flipped in RAM by fault injection.
+    pub faults_injected: u64,
+    /// Single-bit RAM errors corrected by ECC on a load.
+    pub ecc_corrected: u64,
+    /// Loads that found an uncorrectable RAM error.
+    pub ecc_uncorrected: u64,
+
+    /// Device accesses made by this hart, per device (RAM excluded).
+    pub mmio: MmioStats,
+
+    /// `VirtIO` block device write cache activity observed by this hart.
+    pub disk: DiskWriteStats,
+
+    /// ROB occupancy sampled each cycle (empty when `general.sample_occupancy` is off).
+    pub rob_occupancy: OccupancyHistogram,
+    /// Store buffer occupancy sampled each cycle.
+    pub store_buffer_occupancy: OccupancyHistogram,
+    /// Issue queue occupancy sampled each cycle.
+    pub issue_queue_occupancy: OccupancyHistogram,
 }
 
 impl Default for SimStats {
@@ -182,7 +351,9 @@ impl Default for SimStats {
         Self {
             start_time: Instant::now(),
             cycles: 0,
+            boot_cycle: 0,
             instructions_retired: 0,
+            fused_pairs: 0,
             inst_load: 0,
             inst_store: 0,
             inst_branch: 0,
@@ -202,10 +373,16 @@ impl Default for SimStats {
             cycles_machine: 0,
             cycles_wfi: 0,
             cycles_rob_empty: 0,
+            stall_cycles: [0; STALL_CAUSE_COUNT],
             stalls_mem: 0,
             stalls_control: 0,
             stalls_data: 0,
             traps_taken: 0,
+            interrupts_taken: 0,
+            interrupt_latency_cycles: 0,
+            interrupt_latency_max: 0,
+            syscalls: 0,
+            syscalls_unknown: 0,
             icache_hits: 0,
             icache_misses: 0,
             dcache_hits: 0,
@@ -215,14 +392,25 @@ impl Default for SimStats {
             l3_hits: 0,
             l3_misses: 0,
             fu_utilization: [0; FU_TYPE_COUNT],
+            fu_busy_cycles: [0; FU_TYPE_COUNT],
             stalls_fu_structural: 0,
+            port_issues: [0; PORT_CLASS_COUNT],
+            stalls_issue_port: 0,
+            fetch_buffer_occupancy: 0,
+            fetch_buffer_peak: 0,
+            fetch_buffer_full_cycles: 0,
+            fetch_line_crossings: 0,
+            fetch_line_crossing_cycles: 0,
             misprediction_penalty: 0,
             stalls_backpressure: 0,
             mem_ordering_violations: 0,
+            store_forwards: 0,
+            store_forward_stalls: 0,
             pipeline_flushes: 0,
             mshr_allocations: 0,
             mshr_coalesces: 0,
             stalls_mshr_full: 0,
+            stalls_dcache_port: 0,
             load_replays: 0,
             inclusion_back_invalidations: 0,
             exclusive_l1_to_l2_swaps: 0,
@@ -233,7 +421,10 @@ impl Default for SimStats {
             pf_dedup_l2: 0,
             pf_dedup_l3: 0,
             stalls_dispatch: 0,
+            stalls_store_buffer_full: 0,
             stalls_checkpoint: 0,
+            checkpoint_restores: 0,
+            recovery_walk_entries: 0,
             stalls_rename_rebuild: 0,
             stalls_squash: 0,
             flushes_branch: 0,
@@ -242,22 +433,70 @@ impl Default for SimStats {
             mdp_predictions_wait_all: 0,
             mdp_predictions_wait_for: 0,
             mdp_violations: 0,
+            mdp_avoided_violations: 0,
+            mdp_false_dependences: 0,
             retire_histogram: [0; 4],
+            mem_traffic: MemoryTraffic::default(),
+            bandwidth: BandwidthStats::default(),
+            ram_resident_bytes: 0,
+            faults_injected: 0,
+            ecc_corrected: 0,
+            ecc_uncorrected: 0,
+            mmio: MmioStats::default(),
+            disk: DiskWriteStats::default(),
+            rob_occupancy: OccupancyHistogram::default(),
+            store_buffer_occupancy: OccupancyHistogram::default(),
+            issue_queue_occupancy: OccupancyHistogram::default(),
         }
     }
 }
 
 /// Section names for selective stats output.
 ///
-/// Valid section identifiers: `"summary"`, `"core"`, `"instruction_mix"`, `"branch"`, `"memory"`.
-/// Pass an empty slice to `print_sections` to print all sections.
-pub const STATS_SECTIONS: &[&str] = &["summary", "core", "instruction_mix", "branch", "memory"];
+/// Valid section identifiers: `"summary"`, `"core"`, `"instruction_mix"`, `"branch"`,
+/// `"occupancy"`, `"memory"`, `"mmio"`. Pass an empty slice to `print_sections` to print all
+/// sections.
+pub const STATS_SECTIONS: &[&str] =
+    &["summary", "core", "instruction_mix", "branch", "occupancy", "memory", "mmio"];
 
 impl SimStats {
+    /// Returns the cycles these statistics cover: every cycle, or those since
+    /// the last reboot when `general.reboot_stats` is `Reset`.
+    pub const fn measured_cycles(&self) -> u64 {
+        self.cycles - self.boot_cycle
+    }
+
+    /// Returns the percentage of load dependence predictions that were right.
+    ///
+    /// A prediction is wrong when a load allowed past a store turned out to
+    /// alias it (`mdp_violations`) or a load held for a store turned out not
+    /// to (`mdp_false_dependences`). Returns 0 before any prediction.
+    pub fn mdp_accuracy_pct(&self) -> f64 {
+        let total = self.mdp_predictions_bypass
+            + self.mdp_predictions_wait_all
+            + self.mdp_predictions_wait_for;
+        if total == 0 {
+            return 0.0;
+        }
+        let wrong = (self.mdp_violations + self.mdp_false_dependences).min(total);
+        100.0 * ((total - wrong) as f64 / total as f64)
+    }
+
+    /// Zeroes every statistic for a new boot, keeping the cycle clock.
+    pub fn reset_for_boot(&mut self) {
+        *self = Self {
+            cycles: self.cycles,
+            boot_cycle: self.cycles,
+            bandwidth: self.bandwidth.restarted(self.cycles),
+            ..Self::default()
+        };
+    }
+
     /// Prints only the requested statistics sections to stdout.
     ///
     /// Each element of `sections` should be one of `"summary"`, `"core"`, `"instruction_mix"`,
-    /// `"branch"`, or `"memory"`. Pass an empty slice to print all sections (same as `print()`).
+    /// `"branch"`, `"occupancy"`, `"memory"`, or `"mmio"`. Pass an empty slice to print all
+    /// sections (same as `print()`).
     ///
     /// # Arguments
     ///
@@ -279,7 +518,7 @@ impl SimStats {
         let want = |s: &str| sections.is_empty() || sections.iter().any(|x| x == s);
         let duration = self.start_time.elapsed();
         let seconds = duration.as_secs_f64();
-        let cyc = if self.cycles == 0 { 1 } else { self.cycles };
+        let cyc = self.measured_cycles().max(1);
         let instr = if self.instructions_retired == 0 { 1 } else { self.instructions_retired };
 
         let rule =
@@ -290,7 +529,7 @@ impl SimStats {
             let ipc = self.instructions_retired as f64 / cyc as f64;
             let cpi = cyc as f64 / instr as f64;
             let mips = (self.instructions_retired as f64 / seconds) / 1_000_000.0;
-            let khz = (self.cycles as f64 / seconds) / 1000.0;
+            let khz = (self.measured_cycles() as f64 / seconds) / 1000.0;
             let active_cycles = cyc.saturating_sub(self.cycles_wfi);
             let active_cyc = if active_cycles == 0 { 1 } else { active_cycles };
             let active_ipc = self.instructions_retired as f64 / active_cyc as f64;
@@ -298,9 +537,12 @@ impl SimStats {
             println!("{bold}RISC-V SYSTEM SIMULATION STATISTICS{rst}");
             println!("{rule}");
             println!("host_seconds             {seconds:.4} s");
-            println!("sim_cycles               {}", self.cycles);
+            println!("sim_cycles               {}", self.measured_cycles());
             println!("sim_freq                 {khz:.2} kHz");
             println!("sim_insts                {}", self.instructions_retired);
+            if self.fused_pairs > 0 {
+                println!("sim_fused_pairs          {}", self.fused_pairs);
+            }
             println!("sim_ipc                  {ipc:.4}");
             if self.cycles_wfi > 0 {
                 println!("sim_ipc_active           {active_ipc:.4}");
@@ -342,6 +584,17 @@ impl SimStats {
                     (self.cycles_wfi as f64 / cyc as f64) * 100.0
                 );
             }
+            for cause in StallCause::ALL {
+                let stalled = self.stall_cycles[cause as usize];
+                if stalled > 0 {
+                    println!(
+                        "  {:<22} {} ({:.2}%)",
+                        format!("stall.{}", cause.name()),
+                        stalled,
+                        (stalled as f64 / cyc as f64) * 100.0
+                    );
+                }
+            }
             if rh_total > 0 {
                 let pct = |v: u64| (v as f64 / rh_total as f64) * 100.0;
                 println!(
@@ -369,6 +622,23 @@ impl SimStats {
             }
             println!("{sep}");
 
+            println!("{bold}FRONTEND{rst}");
+            println!(
+                "  fetch_buffer.avg       {:.2}",
+                self.fetch_buffer_occupancy as f64 / cyc as f64
+            );
+            println!("  fetch_buffer.peak      {}", self.fetch_buffer_peak);
+            println!(
+                "  fetch_buffer.full      {} ({:.2}%)",
+                self.fetch_buffer_full_cycles,
+                (self.fetch_buffer_full_cycles as f64 / cyc as f64) * 100.0
+            );
+            println!(
+                "  fetch.line_crossings   {} ({} cycles)",
+                self.fetch_line_crossings, self.fetch_line_crossing_cycles
+            );
+            println!("{sep}");
+
             println!("{bold}PRIVILEGE BREAKDOWN{rst}");
             println!(
                 "  cycles.user            {} ({:.2}%)",
@@ -387,6 +657,24 @@ impl SimStats {
             );
             println!("{sep}");
 
+            if self.interrupts_taken > 0 {
+                println!("{bold}INTERRUPTS{rst}");
+                println!("  interrupts.taken       {}", self.interrupts_taken);
+                println!(
+                    "  interrupts.latency_avg {:.2}",
+                    self.interrupt_latency_cycles as f64 / self.interrupts_taken as f64
+                );
+                println!("  interrupts.latency_max {}", self.interrupt_latency_max);
+                println!("{sep}");
+            }
+
+            if self.syscalls > 0 {
+                println!("{bold}SYSCALLS{rst}");
+                println!("  syscalls.total         {}", self.syscalls);
+                println!("  syscalls.unknown       {}", self.syscalls_unknown);
+                println!("{sep}");
+            }
+
             println!("{bold}PIPELINE STALLS{rst}");
             // Memory stalls: blocking-mode stalls + MSHR-full stalls (non-blocking mode).
             // With MSHRs enabled, cache miss latency is hidden by the non-blocking cache;
@@ -424,6 +712,13 @@ impl SimStats {
                     (self.stalls_dispatch as f64 / cyc as f64) * 100.0
                 );
             }
+            if self.stalls_store_buffer_full > 0 {
+                println!(
+                    "  stalls.store_buffer    {} ({:.2}%)",
+                    self.stalls_store_buffer_full,
+                    (self.stalls_store_buffer_full as f64 / cyc as f64) * 100.0
+                );
+            }
             if self.stalls_checkpoint > 0 {
                 println!(
                     "  stalls.checkpoint      {} ({:.2}%)",
@@ -445,6 +740,35 @@ impl SimStats {
                     (self.stalls_rename_rebuild as f64 / cyc as f64) * 100.0
                 );
             }
+            for fu in FuType::ALL {
+                let busy = self.fu_busy_cycles[fu as usize];
+                if busy > 0 {
+                    println!(
+                        "  {:<22} {} ({:.2}%)",
+                        format!("fu.{}.busy", fu.name()),
+                        busy,
+                        (busy as f64 / cyc as f64) * 100.0
+                    );
+                }
+            }
+            for port in PortClass::ALL {
+                let issued = self.port_issues[port as usize];
+                if issued > 0 {
+                    println!(
+                        "  {:<22} {} ({:.2}/cycle)",
+                        format!("port.{}.issued", port.name()),
+                        issued,
+                        issued as f64 / cyc as f64
+                    );
+                }
+            }
+            if self.stalls_issue_port > 0 {
+                println!(
+                    "  stalls.issue_port      {} ({:.2}%)",
+                    self.stalls_issue_port,
+                    (self.stalls_issue_port as f64 / cyc as f64) * 100.0
+                );
+            }
             println!("{sep}");
         }
         if want("instruction_mix") {
@@ -538,6 +862,16 @@ impl SimStats {
             }
             println!("  flush.mem_violations   {}", self.mem_ordering_violations);
             println!("  flush.squashed_insns   {}", self.misprediction_penalty);
+            if self.checkpoint_restores > 0 || self.recovery_walk_entries > 0 {
+                println!("  flush.ckpt_restores    {}", self.checkpoint_restores);
+                println!("  flush.rebuild_walked   {}", self.recovery_walk_entries);
+            }
+            if self.store_forwards > 0 || self.store_forward_stalls > 0 {
+                println!("{sep}");
+                println!("{bold}LOAD/STORE QUEUE{rst}");
+                println!("  lsq.store_forwards     {}", self.store_forwards);
+                println!("  lsq.forward_stalls     {}", self.store_forward_stalls);
+            }
             let mdp_total = self.mdp_predictions_bypass
                 + self.mdp_predictions_wait_all
                 + self.mdp_predictions_wait_for;
@@ -549,6 +883,29 @@ impl SimStats {
                 println!("  mdp.wait_all           {}", self.mdp_predictions_wait_all);
                 println!("  mdp.wait_for           {}", self.mdp_predictions_wait_for);
                 println!("  mdp.violations         {}", self.mdp_violations);
+                println!("  mdp.avoided            {}", self.mdp_avoided_violations);
+                println!("  mdp.false_deps         {}", self.mdp_false_dependences);
+                println!("  mdp.accuracy           {:.2}%", self.mdp_accuracy_pct());
+            }
+            println!("{sep}");
+        }
+        if want("occupancy") && self.rob_occupancy.samples() > 0 {
+            println!("{bold}OCCUPANCY{rst}");
+            for (name, h) in [
+                ("rob", &self.rob_occupancy),
+                ("store_buffer", &self.store_buffer_occupancy),
+                ("issue_queue", &self.issue_queue_occupancy),
+            ] {
+                println!(
+                    "  {:<22} min: {} | avg: {:.2} | p50: {} | p90: {} | p99: {} | max: {}",
+                    name,
+                    h.min(),
+                    h.avg(),
+                    h.percentile(50.0),
+                    h.percentile(90.0),
+                    h.percentile(99.0),
+                    h.max()
+                );
             }
             println!("{sep}");
         }
@@ -576,6 +933,9 @@ impl SimStats {
                 );
                 println!("  load.replays           {}", self.load_replays);
             }
+            if self.stalls_dcache_port > 0 {
+                println!("  l1d.port_stalls        {}", self.stalls_dcache_port);
+            }
             if self.inclusion_back_invalidations > 0 {
                 println!("  incl.back_invalidate   {}", self.inclusion_back_invalidations);
             }
@@ -600,6 +960,84 @@ impl SimStats {
                     );
                 }
             }
+            let mt = &self.mem_traffic;
+            if mt.requests() > 0 {
+                println!("  dram.requests          {} | bytes: {}", mt.requests(), mt.bytes());
+                println!(
+                    "  dram.latency           avg: {:.2} | p99: {} | max: {}",
+                    mt.avg_latency(),
+                    mt.p99_latency(),
+                    mt.max_latency()
+                );
+                println!("  dram.peak_bandwidth    {:.2} B/cycle", mt.peak_bandwidth());
+                if mt.is_queued() {
+                    println!("  dram.queue_occupancy   avg: {:.2}", mt.avg_queue_occupancy());
+                    println!(
+                        "  dram.read_latency      avg: {:.2} | p99: {} | reads: {}",
+                        mt.avg_read_latency(),
+                        mt.percentile_read_latency(99.0),
+                        mt.reads()
+                    );
+                }
+                for rb in mt.row_buffer() {
+                    println!(
+                        "  dram.ch{}.bg{}.row_hits  {:.2}% | hits: {} | misses: {} | conflicts: {}",
+                        rb.channel,
+                        rb.bank_group,
+                        rb.hit_rate() * 100.0,
+                        rb.hits,
+                        rb.misses,
+                        rb.conflicts
+                    );
+                }
+            }
+            if self.ram_resident_bytes > 0 {
+                println!(
+                    "  ram.resident           {:.2} MiB",
+                    self.ram_resident_bytes as f64 / (1024.0 * 1024.0)
+                );
+            }
+            if self.faults_injected > 0 {
+                println!(
+                    "  ram.faults             {} | corrected: {} | uncorrected: {}",
+                    self.faults_injected, self.ecc_corrected, self.ecc_uncorrected
+                );
+            }
+            let bw = &self.bandwidth;
+            for link in Link::ALL {
+                let total = bw.link_bytes(link);
+                if total == 0 {
+                    continue;
+                }
+                let [demand, prefetch, writeback] =
+                    Traffic::ALL.map(|traffic| bw.bytes(link, traffic));
+                println!(
+                    "  bw.{:<19} {:.3} GB/s | demand: {demand} B | prefetch: {prefetch} B | writeback: {writeback} B",
+                    link.name(),
+                    bw.gb_per_sec(total, self.measured_cycles())
+                );
+            }
+        }
+        if want("mmio") && (!self.mmio.is_empty() || !self.disk.is_empty()) {
+            println!("{sep}");
+            println!("{bold}MMIO{rst}");
+            for (name, d) in self.mmio.iter() {
+                println!(
+                    "  {name:<22} reads: {} ({} B) | writes: {} ({} B)",
+                    d.reads, d.bytes_read, d.writes, d.bytes_written
+                );
+            }
+            let disk = &self.disk;
+            if !disk.is_empty() {
+                println!(
+                    "  disk.write_sectors     cached: {} | write-through: {}",
+                    disk.cached_sectors, disk.write_through_sectors
+                );
+                println!(
+                    "  disk.flushes           {} | sectors written back: {}",
+                    disk.flush_requests, disk.flushed_sectors
+                );
+            }
         }
         println!("{rule}");
     }
@@ -262,3 +262,14 @@ impl std::fmt::Display for Hex32 {
         write!(f, "{:#010x}", self.0)
     }
 }
+
+/// Wraps an instruction word so it formats as its disassembly in tracing
+/// fields.  Disassembly only happens when the event is actually recorded.
+#[derive(Debug)]
+pub struct Disasm(pub u32);
+
+impl std::fmt::Display for Disasm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&crate::isa::disasm::disassemble(self.0))
+    }
+}
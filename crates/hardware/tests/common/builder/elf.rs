@@ -0,0 +1,196 @@
+//! Minimal ELF64 image builder for loader tests.
+
+/// `EM_RISCV`.
+pub const EM_RISCV: u16 = 243;
+
+/// `EM_X86_64`.
+pub const EM_X86_64: u16 = 62;
+
+/// `STT_OBJECT`.
+pub const STT_OBJECT: u8 = 1;
+
+/// `STT_FUNC`.
+pub const STT_FUNC: u8 = 2;
+
+const EHDR_SIZE: usize = 64;
+const PHDR_SIZE: usize = 56;
+const SHDR_SIZE: usize = 64;
+const SYM_SIZE: usize = 24;
+const SHN_ABS: u16 = 0xfff1;
+
+struct Segment {
+    paddr: u64,
+    vaddr: u64,
+    data: Vec<u8>,
+    memsz: u64,
+}
+
+struct Sym {
+    name: String,
+    value: u64,
+    size: u64,
+    kind: u8,
+}
+
+/// Builds a little-endian ELF64 executable with `PT_LOAD` segments and an
+/// optional symbol table.
+pub struct ElfBuilder {
+    machine: u16,
+    class32: bool,
+    entry: u64,
+    headers: Option<u64>,
+    segments: Vec<Segment>,
+    symbols: Vec<Sym>,
+}
+
+impl ElfBuilder {
+    /// Starts a RISC-V image entering at `entry`.
+    pub fn new(entry: u64) -> Self {
+        Self {
+            machine: EM_RISCV,
+            class32: false,
+            entry,
+            headers: None,
+            segments: Vec::new(),
+            symbols: Vec::new(),
+        }
+    }
+
+    /// Sets `e_machine`.
+    pub fn machine(mut self, machine: u16) -> Self {
+        self.machine = machine;
+        self
+    }
+
+    /// Marks the image as ELFCLASS32 (the rest stays 64-bit layout).
+    pub fn class32(mut self) -> Self {
+        self.class32 = true;
+        self
+    }
+
+    /// Adds a segment loaded at `paddr` with `data`, zero-filled to `memsz`.
+    pub fn segment(self, paddr: u64, data: &[u8], memsz: u64) -> Self {
+        self.segment_at(paddr, paddr, data, memsz)
+    }
+
+    /// Adds a segment linked at `vaddr` and loaded at `paddr`.
+    pub fn segment_at(mut self, vaddr: u64, paddr: u64, data: &[u8], memsz: u64) -> Self {
+        self.segments.push(Segment { paddr, vaddr, data: data.to_vec(), memsz });
+        self
+    }
+
+    /// Adds a segment loading the ELF and program headers at `paddr`, as
+    /// linkers do for the first segment.
+    pub const fn headers_at(mut self, paddr: u64) -> Self {
+        self.headers = Some(paddr);
+        self
+    }
+
+    /// Adds a global absolute symbol.
+    pub fn symbol(mut self, name: &str, value: u64, size: u64, kind: u8) -> Self {
+        self.symbols.push(Sym { name: name.to_owned(), value, size, kind });
+        self
+    }
+
+    /// Returns the image bytes.
+    pub fn build(self) -> Vec<u8> {
+        let phoff = EHDR_SIZE;
+        let phnum = self.segments.len() + usize::from(self.headers.is_some());
+        let mut data_off = phoff + PHDR_SIZE * phnum;
+        let mut out = vec![0u8; data_off];
+
+        let mut phdrs = Vec::new();
+        for seg in &self.segments {
+            phdrs.push((data_off, seg));
+            out.extend_from_slice(&seg.data);
+            data_off += seg.data.len();
+        }
+
+        // .strtab, .symtab, .shstrtab
+        let mut strtab = vec![0u8];
+        let mut symtab = vec![0u8; SYM_SIZE];
+        for sym in &self.symbols {
+            let name = strtab.len() as u32;
+            strtab.extend_from_slice(sym.name.as_bytes());
+            strtab.push(0);
+            symtab.extend_from_slice(&name.to_le_bytes());
+            symtab.push((1 << 4) | sym.kind); // STB_GLOBAL
+            symtab.push(0);
+            symtab.extend_from_slice(&SHN_ABS.to_le_bytes());
+            symtab.extend_from_slice(&sym.value.to_le_bytes());
+            symtab.extend_from_slice(&sym.size.to_le_bytes());
+        }
+        let shstrtab = b"\0.symtab\0.strtab\0.shstrtab\0".to_vec();
+        out.resize(out.len().next_multiple_of(8), 0);
+        let symtab_off = out.len();
+        out.extend_from_slice(&symtab);
+        let strtab_off = out.len();
+        out.extend_from_slice(&strtab);
+        let shstrtab_off = out.len();
+        out.extend_from_slice(&shstrtab);
+        out.resize(out.len().next_multiple_of(8), 0);
+        let shoff = out.len();
+
+        // Section headers: null, .symtab, .strtab, .shstrtab
+        let mut shdr =
+            |name: u32, kind: u32, off: usize, size: usize, link: u32, info: u32, ent: u64| {
+                out.extend_from_slice(&name.to_le_bytes());
+                out.extend_from_slice(&kind.to_le_bytes());
+                out.extend_from_slice(&0u64.to_le_bytes());
+                out.extend_from_slice(&0u64.to_le_bytes());
+                out.extend_from_slice(&(off as u64).to_le_bytes());
+                out.extend_from_slice(&(size as u64).to_le_bytes());
+                out.extend_from_slice(&link.to_le_bytes());
+                out.extend_from_slice(&info.to_le_bytes());
+                out.extend_from_slice(&1u64.to_le_bytes());
+                out.extend_from_slice(&ent.to_le_bytes());
+            };
+        shdr(0, 0, 0, 0, 0, 0, 0);
+        shdr(1, 2, symtab_off, symtab.len(), 2, 1, SYM_SIZE as u64);
+        shdr(9, 3, strtab_off, strtab.len(), 0, 0, 0);
+        shdr(17, 3, shstrtab_off, shstrtab.len(), 0, 0, 0);
+
+        // ELF header
+        let mut ehdr = Vec::with_capacity(EHDR_SIZE);
+        ehdr.extend_from_slice(b"\x7fELF");
+        ehdr.push(if self.class32 { 1 } else { 2 });
+        ehdr.extend_from_slice(&[1, 1, 0]);
+        ehdr.resize(16, 0);
+        ehdr.extend_from_slice(&2u16.to_le_bytes()); // ET_EXEC
+        ehdr.extend_from_slice(&self.machine.to_le_bytes());
+        ehdr.extend_from_slice(&1u32.to_le_bytes());
+        ehdr.extend_from_slice(&self.entry.to_le_bytes());
+        ehdr.extend_from_slice(&(phoff as u64).to_le_bytes());
+        ehdr.extend_from_slice(&(shoff as u64).to_le_bytes());
+        ehdr.extend_from_slice(&0u32.to_le_bytes());
+        ehdr.extend_from_slice(&(EHDR_SIZE as u16).to_le_bytes());
+        ehdr.extend_from_slice(&(PHDR_SIZE as u16).to_le_bytes());
+        ehdr.extend_from_slice(&(phnum as u16).to_le_bytes());
+        ehdr.extend_from_slice(&(SHDR_SIZE as u16).to_le_bytes());
+        ehdr.extend_from_slice(&4u16.to_le_bytes());
+        ehdr.extend_from_slice(&3u16.to_le_bytes());
+        out[..EHDR_SIZE].copy_from_slice(&ehdr);
+
+        // Program headers
+        let header_size = (phoff + PHDR_SIZE * phnum) as u64;
+        let headers = self.headers.map(|paddr| (0, paddr, paddr, header_size, header_size));
+        let loads = phdrs
+            .iter()
+            .map(|&(off, seg)| (off, seg.vaddr, seg.paddr, seg.data.len() as u64, seg.memsz));
+        for (i, (off, vaddr, paddr, filesz, memsz)) in headers.into_iter().chain(loads).enumerate()
+        {
+            let mut ph = Vec::with_capacity(PHDR_SIZE);
+            ph.extend_from_slice(&1u32.to_le_bytes()); // PT_LOAD
+            ph.extend_from_slice(&7u32.to_le_bytes()); // RWX
+            ph.extend_from_slice(&(off as u64).to_le_bytes());
+            ph.extend_from_slice(&vaddr.to_le_bytes());
+            ph.extend_from_slice(&paddr.to_le_bytes());
+            ph.extend_from_slice(&filesz.to_le_bytes());
+            ph.extend_from_slice(&memsz.to_le_bytes());
+            ph.extend_from_slice(&8u64.to_le_bytes());
+            let at = phoff + i * PHDR_SIZE;
+            out[at..at + PHDR_SIZE].copy_from_slice(&ph);
+        }
+        out
+    }
+}
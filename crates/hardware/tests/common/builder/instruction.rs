@@ -1,3 +1,5 @@
+use rvsim_core::isa::privileged::opcodes::OP_SYSTEM;
+use rvsim_core::isa::rv64a::opcodes::OP_AMO;
 use rvsim_core::isa::rv64i::opcodes::*;
 
 pub struct InstructionBuilder {
@@ -273,6 +275,35 @@ impl InstructionBuilder {
         self
     }
 
+    // Shifts by immediate: SLLI, SRLI, SRAI (6-bit `shamt` on RV64)
+
+    pub fn slli(mut self, rd: u32, rs1: u32, shamt: u32) -> Self {
+        self.opcode = OP_IMM;
+        self.rd = rd;
+        self.rs1 = rs1;
+        self.funct3 = 0b001;
+        self.imm = (shamt & 0x3F) as i32;
+        self
+    }
+
+    pub fn srli(mut self, rd: u32, rs1: u32, shamt: u32) -> Self {
+        self.opcode = OP_IMM;
+        self.rd = rd;
+        self.rs1 = rs1;
+        self.funct3 = 0b101;
+        self.imm = (shamt & 0x3F) as i32;
+        self
+    }
+
+    pub fn srai(mut self, rd: u32, rs1: u32, shamt: u32) -> Self {
+        self.opcode = OP_IMM;
+        self.rd = rd;
+        self.rs1 = rs1;
+        self.funct3 = 0b101;
+        self.imm = 0x400 | (shamt & 0x3F) as i32;
+        self
+    }
+
     // RV64I: LD, SD, ADDIW, ADDW, SUBW
 
     pub fn ld(mut self, rd: u32, rs1: u32, imm: i32) -> Self {
@@ -293,6 +324,24 @@ impl InstructionBuilder {
         self
     }
 
+    pub fn lb(mut self, rd: u32, rs1: u32, imm: i32) -> Self {
+        self.opcode = OP_LOAD;
+        self.rd = rd;
+        self.rs1 = rs1;
+        self.funct3 = 0b000;
+        self.imm = imm;
+        self
+    }
+
+    pub fn sb(mut self, rs1: u32, rs2: u32, imm: i32) -> Self {
+        self.opcode = OP_STORE;
+        self.rs1 = rs1;
+        self.rs2 = rs2;
+        self.funct3 = 0b000;
+        self.imm = imm;
+        self
+    }
+
     pub fn addiw(mut self, rd: u32, rs1: u32, imm: i32) -> Self {
         self.opcode = OP_IMM_32;
         self.rd = rd;
@@ -322,6 +371,292 @@ impl InstructionBuilder {
         self
     }
 
+    // M extension: MUL, MULH, MULHSU, MULHU, DIV, DIVU, REM, REMU
+
+    pub fn mul(mut self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.opcode = OP_REG;
+        self.rd = rd;
+        self.rs1 = rs1;
+        self.rs2 = rs2;
+        self.funct3 = 0b000;
+        self.funct7 = 0b0000001;
+        self
+    }
+
+    pub fn mulh(mut self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.opcode = OP_REG;
+        self.rd = rd;
+        self.rs1 = rs1;
+        self.rs2 = rs2;
+        self.funct3 = 0b001;
+        self.funct7 = 0b0000001;
+        self
+    }
+
+    pub fn mulhsu(mut self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.opcode = OP_REG;
+        self.rd = rd;
+        self.rs1 = rs1;
+        self.rs2 = rs2;
+        self.funct3 = 0b010;
+        self.funct7 = 0b0000001;
+        self
+    }
+
+    pub fn mulhu(mut self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.opcode = OP_REG;
+        self.rd = rd;
+        self.rs1 = rs1;
+        self.rs2 = rs2;
+        self.funct3 = 0b011;
+        self.funct7 = 0b0000001;
+        self
+    }
+
+    pub fn div(mut self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.opcode = OP_REG;
+        self.rd = rd;
+        self.rs1 = rs1;
+        self.rs2 = rs2;
+        self.funct3 = 0b100;
+        self.funct7 = 0b0000001;
+        self
+    }
+
+    pub fn divu(mut self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.opcode = OP_REG;
+        self.rd = rd;
+        self.rs1 = rs1;
+        self.rs2 = rs2;
+        self.funct3 = 0b101;
+        self.funct7 = 0b0000001;
+        self
+    }
+
+    pub fn rem(mut self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.opcode = OP_REG;
+        self.rd = rd;
+        self.rs1 = rs1;
+        self.rs2 = rs2;
+        self.funct3 = 0b110;
+        self.funct7 = 0b0000001;
+        self
+    }
+
+    pub fn remu(mut self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.opcode = OP_REG;
+        self.rd = rd;
+        self.rs1 = rs1;
+        self.rs2 = rs2;
+        self.funct3 = 0b111;
+        self.funct7 = 0b0000001;
+        self
+    }
+
+    // A extension: LR/SC, the AMOs and Zacas AMOCAS. Each takes its width
+    // from the suffix; chain `aqrl` to set the ordering bits.
+
+    fn atomic(mut self, funct5: u32, width: u32, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.opcode = OP_AMO;
+        self.rd = rd;
+        self.rs1 = rs1;
+        self.rs2 = rs2;
+        self.funct3 = width;
+        self.funct7 = funct5 << 2;
+        self
+    }
+
+    /// Sets the acquire and release bits of an atomic.
+    pub fn aqrl(mut self, aq: bool, rl: bool) -> Self {
+        self.funct7 = (self.funct7 & !0b11) | (u32::from(aq) << 1) | u32::from(rl);
+        self
+    }
+
+    pub fn lr_w(self, rd: u32, rs1: u32) -> Self {
+        self.atomic(0b00010, 0b010, rd, rs1, 0)
+    }
+
+    pub fn lr_d(self, rd: u32, rs1: u32) -> Self {
+        self.atomic(0b00010, 0b011, rd, rs1, 0)
+    }
+
+    pub fn sc_w(self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.atomic(0b00011, 0b010, rd, rs1, rs2)
+    }
+
+    pub fn sc_d(self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.atomic(0b00011, 0b011, rd, rs1, rs2)
+    }
+
+    pub fn amoswap_w(self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.atomic(0b00001, 0b010, rd, rs1, rs2)
+    }
+
+    pub fn amoswap_d(self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.atomic(0b00001, 0b011, rd, rs1, rs2)
+    }
+
+    pub fn amoadd_w(self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.atomic(0b00000, 0b010, rd, rs1, rs2)
+    }
+
+    pub fn amoadd_d(self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.atomic(0b00000, 0b011, rd, rs1, rs2)
+    }
+
+    pub fn amoxor_w(self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.atomic(0b00100, 0b010, rd, rs1, rs2)
+    }
+
+    pub fn amoxor_d(self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.atomic(0b00100, 0b011, rd, rs1, rs2)
+    }
+
+    pub fn amoand_w(self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.atomic(0b01100, 0b010, rd, rs1, rs2)
+    }
+
+    pub fn amoand_d(self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.atomic(0b01100, 0b011, rd, rs1, rs2)
+    }
+
+    pub fn amoor_w(self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.atomic(0b01000, 0b010, rd, rs1, rs2)
+    }
+
+    pub fn amoor_d(self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.atomic(0b01000, 0b011, rd, rs1, rs2)
+    }
+
+    pub fn amomin_w(self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.atomic(0b10000, 0b010, rd, rs1, rs2)
+    }
+
+    pub fn amomin_d(self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.atomic(0b10000, 0b011, rd, rs1, rs2)
+    }
+
+    pub fn amomax_w(self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.atomic(0b10100, 0b010, rd, rs1, rs2)
+    }
+
+    pub fn amomax_d(self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.atomic(0b10100, 0b011, rd, rs1, rs2)
+    }
+
+    pub fn amominu_w(self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.atomic(0b11000, 0b010, rd, rs1, rs2)
+    }
+
+    pub fn amominu_d(self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.atomic(0b11000, 0b011, rd, rs1, rs2)
+    }
+
+    pub fn amomaxu_w(self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.atomic(0b11100, 0b010, rd, rs1, rs2)
+    }
+
+    pub fn amomaxu_d(self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.atomic(0b11100, 0b011, rd, rs1, rs2)
+    }
+
+    pub fn amocas_w(self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.atomic(0b00101, 0b010, rd, rs1, rs2)
+    }
+
+    pub fn amocas_d(self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.atomic(0b00101, 0b011, rd, rs1, rs2)
+    }
+
+    pub fn amocas_q(self, rd: u32, rs1: u32, rs2: u32) -> Self {
+        self.atomic(0b00101, 0b100, rd, rs1, rs2)
+    }
+
+    // System: ECALL, EBREAK
+
+    pub fn ecall(mut self) -> Self {
+        self.opcode = OP_SYSTEM;
+        self.imm = 0;
+        self
+    }
+
+    pub fn ebreak(mut self) -> Self {
+        self.opcode = OP_SYSTEM;
+        self.imm = 1;
+        self
+    }
+
+    // Zicsr: CSRRW, CSRRS, CSRRC and the immediate forms, which take `zimm` in place of `rs1`
+
+    pub fn csrrw(mut self, rd: u32, csr: u32, rs1: u32) -> Self {
+        self.opcode = OP_SYSTEM;
+        self.rd = rd;
+        self.rs1 = rs1;
+        self.funct3 = 0b001;
+        self.imm = csr as i32;
+        self
+    }
+
+    pub fn csrrs(mut self, rd: u32, csr: u32, rs1: u32) -> Self {
+        self.opcode = OP_SYSTEM;
+        self.rd = rd;
+        self.rs1 = rs1;
+        self.funct3 = 0b010;
+        self.imm = csr as i32;
+        self
+    }
+
+    pub fn csrrc(mut self, rd: u32, csr: u32, rs1: u32) -> Self {
+        self.opcode = OP_SYSTEM;
+        self.rd = rd;
+        self.rs1 = rs1;
+        self.funct3 = 0b011;
+        self.imm = csr as i32;
+        self
+    }
+
+    pub fn csrrwi(mut self, rd: u32, csr: u32, zimm: u32) -> Self {
+        self.opcode = OP_SYSTEM;
+        self.rd = rd;
+        self.rs1 = zimm;
+        self.funct3 = 0b101;
+        self.imm = csr as i32;
+        self
+    }
+
+    pub fn csrrsi(mut self, rd: u32, csr: u32, zimm: u32) -> Self {
+        self.opcode = OP_SYSTEM;
+        self.rd = rd;
+        self.rs1 = zimm;
+        self.funct3 = 0b110;
+        self.imm = csr as i32;
+        self
+    }
+
+    pub fn csrrci(mut self, rd: u32, csr: u32, zimm: u32) -> Self {
+        self.opcode = OP_SYSTEM;
+        self.rd = rd;
+        self.rs1 = zimm;
+        self.funct3 = 0b111;
+        self.imm = csr as i32;
+        self
+    }
+
+    /// CSRR is CSRRS rd, csr, x0
+    pub fn csrr(self, rd: u32, csr: u32) -> Self {
+        self.csrrs(rd, csr, 0)
+    }
+
+    /// CSRW is CSRRW x0, csr, rs1
+    pub fn csrw(self, csr: u32, rs1: u32) -> Self {
+        self.csrrw(0, csr, rs1)
+    }
+
+    /// CSRS is CSRRS x0, csr, rs1
+    pub fn csrs(self, csr: u32, rs1: u32) -> Self {
+        self.csrrs(0, csr, rs1)
+    }
+
     // Branch variants: BNE, BLT, BGE, BLTU, BGEU
 
     pub fn bne(mut self, rs1: u32, rs2: u32, imm: i32) -> Self {
@@ -383,11 +718,11 @@ impl InstructionBuilder {
         let funct7 = (self.funct7 & 0x7F) << 25;
 
         match opcode {
-            OP_REG | OP_REG_32 => {
+            OP_REG | OP_REG_32 | OP_AMO => {
                 // R-type: funct7 | rs2 | rs1 | funct3 | rd | opcode
                 funct7 | rs2 | rs1 | funct3 | rd | opcode
             }
-            OP_IMM | OP_IMM_32 | OP_LOAD | OP_JALR => {
+            OP_IMM | OP_IMM_32 | OP_LOAD | OP_JALR | OP_SYSTEM => {
                 // I-type: imm[11:0] | rs1 | funct3 | rd | opcode
                 let imm_val = (self.imm as u32) & 0xFFF;
                 (imm_val << 20) | rs1 | funct3 | rd | opcode
@@ -3,6 +3,7 @@ use rvsim_core::Simulator;
 use rvsim_core::common::{PhysAddr, RegIdx};
 use rvsim_core::config::Config;
 use rvsim_core::core::Cpu;
+use rvsim_core::core::pipeline::engine::BackendType;
 use rvsim_core::soc::System;
 use rvsim_core::soc::interconnect::Bus;
 use std::sync::Arc;
@@ -29,6 +30,7 @@ impl TestContext {
             bus,
             mem_controller: Box::new(MockMemoryController::new(1)),
             exit_request: Arc::new(AtomicU64::new(u64::MAX)),
+            dma_requests: Vec::new(),
         };
 
         let mut sim = Simulator::new(system, &config);
@@ -44,6 +46,20 @@ impl TestContext {
         Self { sim }
     }
 
+    /// Creates a context on the full system `config` describes (RAM, devices
+    /// and cache hierarchy), as the simulator itself builds it.
+    pub fn with_config(config: &Config) -> Self {
+        let _ = env_logger::builder().is_test(true).try_init();
+        Self { sim: Simulator::new(System::new(config, ""), config) }
+    }
+
+    /// Creates a context on the default system with the given pipeline backend.
+    pub fn with_backend(backend: BackendType) -> Self {
+        let mut config = Config::default();
+        config.pipeline.backend = backend;
+        Self::with_config(&config)
+    }
+
     /// Convenience accessor for the CPU.
     pub fn cpu(&self) -> &Cpu {
         &self.sim.cpu
@@ -92,4 +108,41 @@ impl TestContext {
             }
         }
     }
+
+    /// Run until the program exits and return the simulator.
+    ///
+    /// Panics if it has not exited within `max_cycles` cycles or a tick fails.
+    pub fn run_to_exit(self, max_cycles: u64) -> Simulator {
+        self.run_to_exit_with(max_cycles, |_| {})
+    }
+
+    /// Like [`Self::run_to_exit`], calling `on_tick` after every cycle.
+    pub fn run_to_exit_with(
+        mut self,
+        max_cycles: u64,
+        mut on_tick: impl FnMut(&Simulator),
+    ) -> Simulator {
+        for _ in 0..max_cycles {
+            self.sim.tick().unwrap();
+            on_tick(&self.sim);
+            if self.sim.cpu.exit_code.is_some() {
+                return self.sim;
+            }
+        }
+        panic!("program did not exit within {max_cycles} cycles");
+    }
+
+    /// Run until the program exits and return its exit code, taking it so
+    /// the simulator can be run on.
+    ///
+    /// Panics if it has not exited within `max_cycles` cycles or a tick fails.
+    pub fn run_to_exit_code(&mut self, max_cycles: u64) -> u64 {
+        for _ in 0..max_cycles {
+            self.sim.tick().unwrap();
+            if let Some(code) = self.sim.take_exit() {
+                return code;
+            }
+        }
+        panic!("program did not exit within {max_cycles} cycles");
+    }
 }
@@ -0,0 +1,272 @@
+//! # riscv-tests `p` Environment
+//!
+//! Builds ELF images laid out like the physical-memory (`p`) environment of
+//! `riscv-tests`, so bundled tests run through the same harness as the real
+//! suite. `_start` mirrors `RVTEST_CODE_BEGIN`: it clears `TESTNUM` (`gp`),
+//! installs the trap vector, opens PMP entry 0 over all memory and `mret`s
+//! into the test body in the requested mode. The body is a list of cases in
+//! the style of `test_macros.h`; the first mismatch jumps to `RVTEST_FAIL`,
+//! which reports `(TESTNUM << 1) | 1`, and falling off the end reaches
+//! `RVTEST_PASS`, which reports 1. Both end in an `ecall` whose trap writes
+//! `TESTNUM` to `tohost`. Any other trap fails the test with
+//! `TESTNUM | 1337` unless a handler is installed.
+
+use crate::common::builder::elf::{ElfBuilder, STT_FUNC, STT_OBJECT};
+use crate::common::builder::instruction::InstructionBuilder;
+use rvsim_core::core::arch::mode::PrivilegeMode;
+use rvsim_core::isa::rv64i::opcodes::{OP_AUIPC, OP_IMM, OP_LOAD};
+
+/// `TESTNUM`.
+pub const GP: u32 = 3;
+
+/// Register holding a case's expected value while it is compared.
+const EXPECTED: u32 = 7;
+
+/// Scratch register used to address the constant pool; cases may not check it.
+const SCRATCH: u32 = 31;
+
+const T0: u32 = 5;
+const T4: u32 = 29;
+const T5: u32 = 30;
+const A0: u32 = 10;
+const A7: u32 = 17;
+
+/// Offset of `tohost` from the start of the image.
+pub const TOHOST: u64 = 0x1_0000;
+
+/// Offset of the test data area (`tdat`) from the start of the image.
+pub const TDAT: u64 = 0x1_1000;
+
+/// Offset of the pool of 64-bit constants loaded by cases.
+const POOL: u64 = 0x1_2000;
+
+/// Number of words in `_start`.
+const RESET_WORDS: usize = 18;
+
+/// Number of words in `trap_vector`, up to and including `write_tohost`.
+const TRAP_WORDS: usize = 13;
+
+const MRET: u32 = 0x3020_0073;
+const FENCE: u32 = 0x0ff0_000f;
+
+const CSR_MSTATUS: u32 = 0x300;
+const CSR_MTVEC: u32 = 0x305;
+const CSR_MEPC: u32 = 0x341;
+const CSR_MCAUSE: u32 = 0x342;
+const CSR_PMPCFG0: u32 = 0x3a0;
+const CSR_PMPADDR0: u32 = 0x3b0;
+
+/// Encodes `csrrw` (`funct3` 1), `csrrs` (2) or `csrrc` (3).
+pub const fn csr(funct3: u32, rd: u32, csr: u32, rs1: u32) -> u32 {
+    (csr << 20) | (rs1 << 15) | (funct3 << 12) | (rd << 7) | 0x73
+}
+
+/// Splits a PC-relative offset into `auipc` and low 12-bit immediates.
+const fn pcrel(offset: i64) -> (i32, i32) {
+    let hi = (offset + 0x800) >> 12;
+    (hi as i32, (offset - (hi << 12)) as i32)
+}
+
+/// Encodes `la rd, target` placed at `at`.
+fn la(rd: u32, at: u64, target: u64) -> [u32; 2] {
+    let (hi, lo) = pcrel(target.wrapping_sub(at) as i64);
+    let b = InstructionBuilder::new;
+    [b().auipc(rd, hi).build(), b().addi(rd, rd, lo).build()]
+}
+
+/// Encodes `jal x0` from `at` to `target`.
+fn jump(at: u64, target: u64) -> u32 {
+    InstructionBuilder::new().jal(0, target.wrapping_sub(at) as i32).build()
+}
+
+/// A test program in the `p` environment.
+pub struct TestProgram {
+    base: u64,
+    mode: PrivilegeMode,
+    handler: Vec<u32>,
+    body: Vec<u32>,
+    /// Body indices of `jal x0, fail` placeholders.
+    fail_jumps: Vec<usize>,
+    /// Body indices of `auipc; ld` placeholders with their register and
+    /// pool slot.
+    loads: Vec<(usize, u32, usize)>,
+    pool: Vec<u64>,
+    tdat: Vec<u8>,
+}
+
+impl TestProgram {
+    /// Starts a program linked at `base` whose body runs in U-mode, as the
+    /// `rv64u*` suites do.
+    pub const fn new(base: u64) -> Self {
+        Self {
+            base,
+            mode: PrivilegeMode::User,
+            handler: Vec::new(),
+            body: Vec::new(),
+            fail_jumps: Vec::new(),
+            loads: Vec::new(),
+            pool: Vec::new(),
+            tdat: Vec::new(),
+        }
+    }
+
+    /// Runs the body in `mode` instead.
+    pub const fn mode(&mut self, mode: PrivilegeMode) -> &mut Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Installs a machine-mode handler for traps other than `ecall`,
+    /// entered with the trap CSRs set and returning with `mret`. The trap
+    /// vector clobbers `t4` and `t5` before entering it.
+    pub fn handler(&mut self, code: &[u32]) -> &mut Self {
+        self.handler = code.to_vec();
+        self
+    }
+
+    /// Sets the initial contents of the test data area.
+    pub fn data(&mut self, bytes: &[u8]) -> &mut Self {
+        self.tdat = bytes.to_vec();
+        self
+    }
+
+    /// Address of the test data area.
+    pub const fn tdat(&self) -> u64 {
+        self.base + TDAT
+    }
+
+    /// Appends test case `num`: loads each `inputs` register with its value,
+    /// runs `code`, then fails the test unless each `checks` register holds
+    /// its value. `code` must leave `gp` alone; `t2` and `t6` are used for
+    /// the comparisons and cannot be checked.
+    pub fn case(
+        &mut self,
+        num: u32,
+        inputs: &[(u32, u64)],
+        code: &[u32],
+        checks: &[(u32, u64)],
+    ) -> &mut Self {
+        let b = InstructionBuilder::new;
+        self.body.push(b().addi(GP, 0, num as i32).build());
+        for &(reg, value) in inputs {
+            self.li(reg, value);
+        }
+        self.body.extend_from_slice(code);
+        for &(reg, value) in checks {
+            assert!(reg != SCRATCH && reg != EXPECTED, "x{reg} cannot be checked");
+            self.li(EXPECTED, value);
+            self.body.push(b().beq(reg, EXPECTED, 8).build());
+            self.fail_jumps.push(self.body.len());
+            self.body.push(0);
+        }
+        self
+    }
+
+    /// Appends a load of `value` from the constant pool into `reg`.
+    fn li(&mut self, reg: u32, value: u64) {
+        self.loads.push((self.body.len(), reg, self.pool.len()));
+        self.pool.push(value);
+        self.body.extend([0, 0]);
+    }
+
+    /// Returns the ELF image.
+    pub fn build(&self) -> Vec<u8> {
+        let b = InstructionBuilder::new;
+        let base = self.base;
+        let trap_vector = base + 4 * RESET_WORDS as u64;
+        let handler = trap_vector + 4 * TRAP_WORDS as u64;
+        let body = handler + 4 * self.handler.len() as u64;
+        let pass = body + 4 * self.body.len() as u64;
+        let fail = pass + 20;
+        let tohost = base + TOHOST;
+
+        let mut text = Vec::new();
+        let at = |text: &Vec<u32>| base + 4 * text.len() as u64;
+
+        // _start (RVTEST_CODE_BEGIN)
+        text.push(b().addi(GP, 0, 0).build());
+        text.extend(la(T0, at(&text), trap_vector));
+        text.push(csr(1, 0, CSR_MTVEC, T0));
+        text.push(b().addi(T0, 0, -1).build());
+        text.push(csr(1, 0, CSR_PMPADDR0, T0));
+        text.push(b().addi(T0, 0, 0x1f).build()); // NAPOT, RWX
+        text.push(csr(1, 0, CSR_PMPCFG0, T0));
+        text.push(b().lui(T0, 2).build());
+        text.push(b().addi(T0, T0, -0x800).build()); // MSTATUS_MPP
+        text.push(csr(3, 0, CSR_MSTATUS, T0));
+        text.push(b().addi(T0, 0, i32::from(self.mode.to_u8())).build());
+        text.push(b().opcode(OP_IMM).funct3(1).rd(T0).rs1(T0).imm(11).build());
+        text.push(csr(2, 0, CSR_MSTATUS, T0));
+        text.extend(la(T0, at(&text), body));
+        text.push(csr(1, 0, CSR_MEPC, T0));
+        text.push(MRET);
+        assert_eq!(text.len(), RESET_WORDS);
+
+        // trap_vector: ecalls report TESTNUM, anything else goes to the
+        // handler or fails.
+        text.push(csr(2, T5, CSR_MCAUSE, 0));
+        for (i, cause) in [8, 9, 11].into_iter().enumerate() {
+            text.push(b().addi(T4, 0, cause).build());
+            text.push(b().beq(T5, T4, 4 * (7 - 2 * i as i32)).build());
+        }
+        if self.handler.is_empty() {
+            text.push(b().opcode(OP_IMM).build());
+        } else {
+            text.push(jump(at(&text), handler));
+        }
+        text.push(b().ori(GP, GP, 1337).build()); // TESTNUM | 1337
+        // write_tohost
+        text.extend(la(T5, at(&text), tohost));
+        text.push(b().sd(T5, GP, 0).build());
+        text.push(b().jal(0, -12).build());
+        assert_eq!(text.len(), RESET_WORDS + TRAP_WORDS);
+
+        text.extend_from_slice(&self.handler);
+
+        let mut code = self.body.clone();
+        for &(i, reg, slot) in &self.loads {
+            let (hi, lo) =
+                pcrel((base + POOL + 8 * slot as u64).wrapping_sub(body + 4 * i as u64) as i64);
+            code[i] = b().opcode(OP_AUIPC).rd(SCRATCH).imm(hi).build();
+            code[i + 1] = b().opcode(OP_LOAD).funct3(3).rd(reg).rs1(SCRATCH).imm(lo).build();
+        }
+        for &i in &self.fail_jumps {
+            code[i] = jump(body + 4 * i as u64, fail);
+        }
+        text.extend(code);
+
+        // RVTEST_PASS
+        text.extend([
+            FENCE,
+            b().addi(GP, 0, 1).build(),
+            b().addi(A7, 0, 93).build(),
+            b().addi(A0, 0, 0).build(),
+            b().ecall().build(),
+        ]);
+        // RVTEST_FAIL
+        text.extend([
+            FENCE,
+            b().beq(GP, 0, 0).build(),
+            b().add(GP, GP, GP).build(),
+            b().ori(GP, GP, 1).build(),
+            b().addi(A7, 0, 93).build(),
+            b().addi(A0, GP, 0).build(),
+            b().ecall().build(),
+        ]);
+        assert!((4 * text.len() as u64) < TOHOST, "test program too large");
+
+        let text: Vec<u8> = text.iter().flat_map(|w| w.to_le_bytes()).collect();
+        let pool: Vec<u8> = self.pool.iter().flat_map(|v| v.to_le_bytes()).collect();
+        ElfBuilder::new(base)
+            .segment(base, &text, text.len() as u64)
+            .segment(tohost, &[], 16)
+            .segment(base + TDAT, &self.tdat, self.tdat.len() as u64)
+            .segment(base + POOL, &pool, pool.len() as u64)
+            .symbol("_start", base, 4 * RESET_WORDS as u64, STT_FUNC)
+            .symbol("trap_vector", trap_vector, 4 * TRAP_WORDS as u64, STT_FUNC)
+            .symbol("fail", fail, 28, STT_FUNC)
+            .symbol("tohost", tohost, 8, STT_OBJECT)
+            .symbol("fromhost", tohost + 8, 8, STT_OBJECT)
+            .build()
+    }
+}
@@ -0,0 +1,13 @@
+//! # Compliance Tests
+//!
+//! Runs `riscv-tests` ISA images through the simulator and checks the
+//! verdict each reports over HTIF.
+
+/// `p` environment for building test images.
+pub mod env;
+
+/// Harness that runs test images and directories of them.
+pub mod riscv_tests;
+
+/// Bundled subset of the suite.
+pub mod suites;
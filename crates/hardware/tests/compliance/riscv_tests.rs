@@ -0,0 +1,225 @@
+//! # riscv-tests Harness
+//!
+//! Runs `riscv-tests` ISA images (`rv64ui-p-add`, `rv64um-p-div`, ...) to
+//! completion and reads their verdict from HTIF: `tohost` = 1 is a pass and
+//! `(n << 1) | 1` is a failure of test case `n`, which the HTIF device turns
+//! into exit codes 0 and `n`. ELF images are loaded by their segments with
+//! HTIF at their `tohost` symbol; raw `.bin` images are loaded at the start
+//! of RAM with `tohost` at the suite's usual offset.
+//!
+//! The bundled suites run through this harness on every `cargo test`. Point
+//! `RVSIM_RISCV_TESTS` at a directory of built images (by default
+//! `software/riscv-tests/isa`) to run the real suite as well; it is skipped
+//! when the directory does not exist.
+
+use super::env::{TestProgram, csr};
+use crate::common::builder::elf::ElfBuilder;
+use crate::common::builder::instruction::InstructionBuilder;
+use rvsim_core::Simulator;
+use rvsim_core::common::PhysAddr;
+use rvsim_core::config::Config;
+use rvsim_core::core::arch::mode::PrivilegeMode;
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::sim::loader;
+use rvsim_core::soc::System;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Cycles a test may run before it is reported as timed out.
+pub const CYCLE_LIMIT: u64 = 500_000;
+
+/// Offset of `tohost` from the start of a raw `.bin` image (`.tohost` in
+/// the suite's linker script).
+pub const BIN_TOHOST: u64 = 0x1000;
+
+/// Environment variable naming a directory of images to run.
+pub const DIR_VAR: &str = "RVSIM_RISCV_TESTS";
+
+/// Verdict of one test image.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// Wrote 1 to `tohost`.
+    Pass,
+    /// Failed test case `n`.
+    Fail(u64),
+    /// Did not report within the cycle limit.
+    Timeout,
+    /// Could not be loaded, or the simulator stopped with an error.
+    Error(String),
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pass => write!(f, "PASS"),
+            Self::Fail(n) => write!(f, "FAIL (test {n})"),
+            Self::Timeout => write!(f, "TIMEOUT"),
+            Self::Error(e) => write!(f, "ERROR: {e}"),
+        }
+    }
+}
+
+/// Runs `image` on `backend` for at most `max_cycles`.
+pub fn run_image(image: &[u8], backend: BackendType, max_cycles: u64) -> Outcome {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    let base = config.system.ram_base;
+    let mut system = System::new(&config, "");
+    let (entry, tohost) = match loader::try_load_elf(image, &mut system.bus) {
+        Ok(Some(elf)) => match elf.tohost_addr {
+            Some(tohost) => (elf.entry, tohost),
+            None => return Outcome::Error("no `tohost` symbol".to_owned()),
+        },
+        Ok(None) => {
+            system.bus.load_binary_at(image, PhysAddr::new(base));
+            (base, base + BIN_TOHOST)
+        }
+        Err(e) => return Outcome::Error(e.to_string()),
+    };
+    system.add_htif(tohost, &config);
+
+    let mut sim = Simulator::new(system, &config);
+    sim.cpu.pc = entry;
+    sim.cpu.direct_mode = false;
+    sim.cpu.privilege = PrivilegeMode::Machine;
+    for _ in 0..max_cycles {
+        if let Err(e) = sim.tick() {
+            return Outcome::Error(e.to_string());
+        }
+        if let Some(code) = sim.take_exit() {
+            return if code == 0 { Outcome::Pass } else { Outcome::Fail(code) };
+        }
+    }
+    Outcome::Timeout
+}
+
+/// Returns whether `name` is a test image: an `rv64*` file that is not a
+/// disassembly or hex dump.
+pub fn is_test_image(name: &str) -> bool {
+    name.starts_with("rv64")
+        && Path::new(name).extension().is_none_or(|ext| ext == "bin" || ext == "elf")
+}
+
+/// Runs every test image in `dir` on `backend`, in name order.
+pub fn run_dir(
+    dir: &Path,
+    backend: BackendType,
+    max_cycles: u64,
+) -> io::Result<Vec<(String, Outcome)>> {
+    let mut names = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        if is_test_image(&name) {
+            names.push(name);
+        }
+    }
+    names.sort();
+    names
+        .into_iter()
+        .map(|name| {
+            let image = fs::read(dir.join(&name))?;
+            let outcome = run_image(&image, backend, max_cycles);
+            Ok((name, outcome))
+        })
+        .collect()
+}
+
+/// Formats one line per test and a summary.
+pub fn report(results: &[(String, Outcome)]) -> String {
+    let lines: String =
+        results.iter().map(|(name, outcome)| format!("{outcome:<8} {name}\n")).collect();
+    let passed = results.iter().filter(|(_, o)| *o == Outcome::Pass).count();
+    format!("{lines}{passed}/{} passed\n", results.len())
+}
+
+#[test]
+fn riscv_tests_directory() {
+    let dir = std::env::var_os(DIR_VAR).map_or_else(
+        || Path::new(env!("CARGO_MANIFEST_DIR")).join("../../software/riscv-tests/isa"),
+        PathBuf::from,
+    );
+    if !dir.is_dir() {
+        eprintln!("skipping riscv-tests: {} not found (set {DIR_VAR})", dir.display());
+        return;
+    }
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let results = run_dir(&dir, backend, CYCLE_LIMIT).unwrap();
+        println!("{backend:?}:\n{}", report(&results));
+        let failed: Vec<_> = results.iter().filter(|(_, o)| *o != Outcome::Pass).collect();
+        assert!(failed.is_empty(), "{backend:?}: {failed:?}");
+    }
+}
+
+/// Runs `image`, linked at the start of RAM, on both backends.
+fn outcomes(image: impl Fn(u64) -> Vec<u8>, max_cycles: u64) -> Vec<Outcome> {
+    let image = image(Config::default().system.ram_base);
+    [BackendType::InOrder, BackendType::OutOfOrder]
+        .into_iter()
+        .map(|backend| run_image(&image, backend, max_cycles))
+        .collect()
+}
+
+#[test]
+fn failing_case_reports_its_number() {
+    let image = |base| {
+        let mut p = TestProgram::new(base);
+        p.case(2, &[(1, 5)], &[], &[(1, 5)]);
+        p.case(3, &[(1, 5)], &[], &[(1, 6)]);
+        p.case(4, &[], &[], &[]);
+        p.build()
+    };
+    assert_eq!(outcomes(image, CYCLE_LIMIT), [Outcome::Fail(3), Outcome::Fail(3)]);
+}
+
+#[test]
+fn unexpected_trap_fails_the_current_case() {
+    let image = |base| {
+        let mut p = TestProgram::new(base);
+        p.case(2, &[], &[], &[]);
+        p.case(4, &[], &[csr(2, 14, 0x300, 0)], &[]);
+        p.build()
+    };
+    let fail = Outcome::Fail((4 | 0x539) >> 1);
+    assert_eq!(outcomes(image, CYCLE_LIMIT), [fail.clone(), fail]);
+}
+
+#[test]
+fn raw_binary_reports_through_tohost_at_its_usual_offset() {
+    let b = InstructionBuilder::new;
+    let words = [
+        b().auipc(5, 1).build(),
+        b().addi(6, 0, 1).build(),
+        b().sd(5, 6, 0).build(),
+        b().jal(0, 0).build(),
+    ];
+    let image: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+    assert_eq!(outcomes(|_| image.clone(), CYCLE_LIMIT), [Outcome::Pass, Outcome::Pass]);
+
+    let spin = InstructionBuilder::new().jal(0, 0).build().to_le_bytes().to_vec();
+    assert_eq!(outcomes(|_| spin.clone(), 1000), [Outcome::Timeout, Outcome::Timeout]);
+}
+
+#[test]
+fn elf_without_tohost_is_an_error() {
+    let image = |base| ElfBuilder::new(base).segment(base, &[0x6f, 0, 0, 0], 4).build();
+    for outcome in outcomes(image, CYCLE_LIMIT) {
+        assert!(matches!(&outcome, Outcome::Error(e) if e.contains("tohost")), "{outcome}");
+    }
+}
+
+#[test]
+fn directories_are_filtered_to_test_images() {
+    let dir = tempfile::tempdir().unwrap();
+    let base = Config::default().system.ram_base;
+    let pass = TestProgram::new(base).build();
+    for name in ["rv64ui-p-b", "rv64ui-p-a", "rv64ui-p-a.dump", "rv64ui-p-c.bin", "Makefile"] {
+        fs::write(dir.path().join(name), &pass).unwrap();
+    }
+    let results = run_dir(dir.path(), BackendType::InOrder, CYCLE_LIMIT).unwrap();
+    let names: Vec<_> = results.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["rv64ui-p-a", "rv64ui-p-b", "rv64ui-p-c.bin"]);
+    assert!(results.iter().all(|(_, o)| *o == Outcome::Pass));
+    assert!(report(&results).ends_with("3/3 passed\n"));
+}
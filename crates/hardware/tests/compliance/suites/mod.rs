@@ -0,0 +1,188 @@
+//! # Bundled riscv-tests Suites
+//!
+//! A subset of `rv64ui`, `rv64um` and `rv64mi`/`rv64si` rebuilt in the `p`
+//! environment, so the compliance harness runs on every `cargo test`
+//! without a RISC-V toolchain. Each image is named like its counterpart in
+//! the real suite. Expected values come from a reference model of each
+//! instruction applied to operands in the spirit of `riscv-tests`: zero,
+//! one, the 12-, 32- and 64-bit sign boundaries, all-ones and mixed bit
+//! patterns. A suite writes its images to a directory and runs it through
+//! [`run_dir`](super::riscv_tests::run_dir) on both backends.
+
+/// Base integer instructions (`rv64ui`).
+pub mod rv64i;
+
+/// Multiply and divide (`rv64um`).
+pub mod rv64m;
+
+/// Machine- and supervisor-mode traps and CSRs (`rv64mi`, `rv64si`).
+pub mod privilege;
+
+use super::env::TestProgram;
+use super::riscv_tests::{CYCLE_LIMIT, Outcome, report, run_dir};
+use crate::common::builder::instruction::InstructionBuilder;
+use rvsim_core::config::Config;
+use rvsim_core::core::pipeline::engine::BackendType;
+
+/// A named image generator, taking the address the image is linked at.
+pub type TestImage = (&'static str, fn(u64) -> Vec<u8>);
+
+/// Register-register and register-immediate operand pairs.
+pub const OPERANDS: &[(u64, u64)] = &[
+    (0, 0),
+    (1, 1),
+    (3, 7),
+    (0, 0xffff_ffff_ffff_8000),
+    (0xffff_ffff_8000_0000, 0),
+    (0xffff_ffff_8000_0000, 0xffff_ffff_ffff_8000),
+    (0, 0x7fff),
+    (0x7fff_ffff, 0),
+    (0x7fff_ffff, 0x7fff),
+    (0xffff_ffff_8000_0000, 0x7fff),
+    (0x7fff_ffff, 0xffff_ffff_ffff_8000),
+    (0, u64::MAX),
+    (u64::MAX, 1),
+    (u64::MAX, u64::MAX),
+    (1, 0x7fff_ffff),
+    (0xffff_ffff_8000_0000, u64::MAX),
+    (0x8000_0000_0000_0000, u64::MAX),
+    (0x8000_0000_0000_0000, 0),
+    (0x7fff_ffff_ffff_ffff, 1),
+    (0xff00_ff00_ff00_ff00, 0x0f0f_0f0f_0f0f_0f0f),
+    (0x0123_4567_89ab_cdef, 0xfedc_ba98_7654_3210),
+    (20, 6),
+    (0xffff_ffff_ffff_ffec, 6),
+    (20, 0xffff_ffff_ffff_fffa),
+];
+
+/// Shifted values, paired with every shift amount.
+pub const SHIFTED: &[u64] =
+    &[1, u64::MAX, 0x2121_2121_2121_2121, 0x8000_0000_0000_0000, 0xffff_ffff_8000_0000];
+
+/// Shift amounts, including ones with bits above the shift width set.
+pub const SHAMTS: &[u64] = &[0, 1, 7, 14, 20, 31, 32, 33, 63, 0xffff_ffff_ffff_ffc0, u64::MAX];
+
+/// Immediates used for register-immediate forms.
+pub const IMMS: &[i32] = &[0, 1, 3, -1, 0x7ff, -0x800, 0x555, -0x556];
+
+/// Sign-extends the low 32 bits of `x`.
+pub const fn sext32(x: u64) -> u64 {
+    x as u32 as i32 as i64 as u64
+}
+
+/// An instruction's fixed encoding fields. For immediate shifts, `funct7`
+/// holds the bits above the shift amount.
+#[derive(Clone, Copy, Debug)]
+pub struct Op {
+    opcode: u32,
+    funct3: u32,
+    funct7: u32,
+}
+
+impl Op {
+    pub const fn new(opcode: u32, funct3: u32, funct7: u32) -> Self {
+        Self { opcode, funct3, funct7 }
+    }
+
+    fn builder(self) -> InstructionBuilder {
+        InstructionBuilder::new().opcode(self.opcode).funct3(self.funct3).funct7(self.funct7)
+    }
+
+    /// R-type `op rd, rs1, rs2`.
+    pub fn r(self, rd: u32, rs1: u32, rs2: u32) -> u32 {
+        self.builder().rd(rd).rs1(rs1).rs2(rs2).build()
+    }
+
+    /// I-type `op rd, rs1, imm`; loads are `op rd, imm(rs1)`.
+    pub fn i(self, rd: u32, rs1: u32, imm: i32) -> u32 {
+        self.builder().rd(rd).rs1(rs1).imm(((self.funct7 << 5) as i32) | imm).build()
+    }
+
+    /// S-type `op rs2, imm(rs1)`.
+    pub fn s(self, rs1: u32, rs2: u32, imm: i32) -> u32 {
+        self.builder().rs1(rs1).rs2(rs2).imm(imm).build()
+    }
+
+    /// B-type `op rs1, rs2, imm`.
+    pub fn b(self, rs1: u32, rs2: u32, imm: i32) -> u32 {
+        self.builder().rs1(rs1).rs2(rs2).imm(imm).build()
+    }
+}
+
+/// `TEST_RR_OP` over every operand pair, followed by the
+/// `TEST_RR_SRC1_EQ_DEST`, `TEST_RR_SRC2_EQ_DEST`, `TEST_RR_SRC12_EQ_DEST`
+/// and `TEST_RR_ZERODEST` variants.
+pub fn rr_image(base: u64, op: Op, model: fn(u64, u64) -> u64, pairs: &[(u64, u64)]) -> Vec<u8> {
+    let mut p = TestProgram::new(base);
+    let mut num = 2;
+    for &(a, b) in pairs {
+        p.case(num, &[(1, a), (2, b)], &[op.r(14, 1, 2)], &[(14, model(a, b))]);
+        num += 1;
+    }
+    let (a, b) = pairs[2];
+    p.case(num, &[(1, a), (2, b)], &[op.r(1, 1, 2)], &[(1, model(a, b))]);
+    p.case(num + 1, &[(1, a), (2, b)], &[op.r(2, 1, 2)], &[(2, model(a, b))]);
+    p.case(num + 2, &[(1, a)], &[op.r(1, 1, 1)], &[(1, model(a, a))]);
+    p.case(num + 3, &[(1, a), (2, b)], &[op.r(0, 1, 2)], &[(0, 0)]);
+    p.build()
+}
+
+/// `TEST_RR_OP` over [`OPERANDS`].
+pub fn rr(base: u64, op: Op, model: fn(u64, u64) -> u64) -> Vec<u8> {
+    rr_image(base, op, model, OPERANDS)
+}
+
+/// `TEST_RR_OP` for a shift, over every value and shift amount.
+pub fn rr_shift(base: u64, op: Op, model: fn(u64, u64) -> u64) -> Vec<u8> {
+    let pairs: Vec<_> = SHIFTED.iter().flat_map(|&a| SHAMTS.iter().map(move |&b| (a, b))).collect();
+    rr_image(base, op, model, &pairs)
+}
+
+/// `TEST_IMM_OP` over every value and immediate, followed by the
+/// `TEST_IMM_SRC1_EQ_DEST` and `TEST_IMM_ZERODEST` variants.
+pub fn imm_image(base: u64, op: Op, model: fn(u64, u64) -> u64, pairs: &[(u64, i32)]) -> Vec<u8> {
+    let mut p = TestProgram::new(base);
+    let mut num = 2;
+    for &(a, imm) in pairs {
+        p.case(num, &[(1, a)], &[op.i(14, 1, imm)], &[(14, model(a, imm as i64 as u64))]);
+        num += 1;
+    }
+    let (a, imm) = pairs[2];
+    p.case(num, &[(1, a)], &[op.i(1, 1, imm)], &[(1, model(a, imm as i64 as u64))]);
+    p.case(num + 1, &[(1, a)], &[op.i(0, 1, imm)], &[(0, 0)]);
+    p.build()
+}
+
+/// `TEST_IMM_OP` over the first operand of [`OPERANDS`] and [`IMMS`].
+pub fn imm(base: u64, op: Op, model: fn(u64, u64) -> u64) -> Vec<u8> {
+    let pairs: Vec<_> =
+        OPERANDS.iter().flat_map(|&(a, _)| IMMS.iter().map(move |&imm| (a, imm))).collect();
+    imm_image(base, op, model, &pairs)
+}
+
+/// `TEST_IMM_OP` for an immediate shift of up to `max` bits.
+pub fn imm_shift(base: u64, op: Op, model: fn(u64, u64) -> u64, max: i32) -> Vec<u8> {
+    let amounts = [0, 1, 7, 14, 20, 31, 32, 33, 63];
+    let pairs: Vec<_> = SHIFTED
+        .iter()
+        .flat_map(|&a| amounts.into_iter().filter(move |&s| s <= max).map(move |s| (a, s)))
+        .collect();
+    imm_image(base, op, model, &pairs)
+}
+
+/// Writes `tests` to a directory, runs it through the harness on both
+/// backends and asserts that every image passes.
+pub fn run_suite(tests: &[TestImage]) {
+    let base = Config::default().system.ram_base;
+    let dir = tempfile::tempdir().unwrap();
+    for (name, image) in tests {
+        std::fs::write(dir.path().join(name), image(base)).unwrap();
+    }
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let results = run_dir(dir.path(), backend, CYCLE_LIMIT).unwrap();
+        println!("{backend:?}:\n{}", report(&results));
+        assert_eq!(results.len(), tests.len());
+        let failed: Vec<_> = results.iter().filter(|(_, o)| *o != Outcome::Pass).collect();
+        assert!(failed.is_empty(), "{backend:?}: {failed:?}");
+    }
+}
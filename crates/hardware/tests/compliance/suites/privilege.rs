@@ -0,0 +1,120 @@
+//! # rv64mi / rv64si
+//!
+//! CSR read-modify-write instructions in register and immediate forms,
+//! `misa` and `mhartid`, and traps taken to M-mode from lower privilege
+//! levels: machine CSRs, `mret` and illegal encodings raise illegal
+//! instruction exceptions and `ebreak` a breakpoint, each with `mepc` at
+//! the faulting instruction.
+
+use super::{TestImage, run_suite};
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::compliance::env::{TestProgram, csr};
+use rvsim_core::core::arch::mode::PrivilegeMode;
+use rvsim_core::isa::rv64i::opcodes::OP_IMM;
+
+const CSR_SSCRATCH: u32 = 0x140;
+const CSR_MSTATUS: u32 = 0x300;
+const CSR_MISA: u32 = 0x301;
+const CSR_MSCRATCH: u32 = 0x340;
+const CSR_MEPC: u32 = 0x341;
+const CSR_MCAUSE: u32 = 0x342;
+const CSR_MHARTID: u32 = 0xf14;
+
+const MRET: u32 = 0x3020_0073;
+
+/// Cause recorded by the trap handler.
+const CAUSE: u32 = 9;
+/// `mepc` recorded by the trap handler.
+const EPC: u32 = 10;
+
+const V: u64 = 0x0123_4567_89ab_cdef;
+
+fn srli(rd: u32, rs1: u32, shamt: i32) -> u32 {
+    InstructionBuilder::new().opcode(OP_IMM).funct3(5).rd(rd).rs1(rs1).imm(shamt).build()
+}
+
+/// Records `mcause` and `mepc` and resumes after the trapping instruction.
+fn skip_handler() -> [u32; 5] {
+    let b = InstructionBuilder::new;
+    [
+        csr(2, CAUSE, CSR_MCAUSE, 0),
+        csr(2, EPC, CSR_MEPC, 0),
+        b().addi(30, EPC, 4).build(),
+        csr(1, 0, CSR_MEPC, 30),
+        MRET,
+    ]
+}
+
+/// Case `num`: `inst` traps with `cause` and `mepc` pointing at it.
+fn traps(p: &mut TestProgram, num: u32, inst: u32, cause: u64) {
+    let b = InstructionBuilder::new;
+    let code = [
+        b().addi(CAUSE, 0, 0).build(),
+        b().auipc(11, 0).build(),
+        inst,
+        b().sub(12, EPC, 11).build(),
+    ];
+    p.case(num, &[], &code, &[(CAUSE, cause), (12, 4)]);
+}
+
+fn mi_csr(base: u64) -> Vec<u8> {
+    let mut p = TestProgram::new(base);
+    p.mode(PrivilegeMode::Machine);
+    let read = csr(2, 15, CSR_MSCRATCH, 0);
+    p.case(2, &[(1, V)], &[csr(1, 0, CSR_MSCRATCH, 1), csr(2, 14, CSR_MSCRATCH, 0)], &[(14, V)]);
+    p.case(3, &[(2, 0xf0)], &[csr(2, 14, CSR_MSCRATCH, 2), read], &[(14, V), (15, V | 0xf0)]);
+    p.case(
+        4,
+        &[(2, 0xff)],
+        &[csr(3, 14, CSR_MSCRATCH, 2), read],
+        &[(14, V | 0xf0), (15, (V | 0xf0) & !0xff)],
+    );
+    p.case(5, &[], &[csr(5, 14, CSR_MSCRATCH, 0x1f), read], &[(14, V & !0xff), (15, 0x1f)]);
+    p.case(
+        6,
+        &[],
+        &[csr(6, 0, CSR_MSCRATCH, 0x10), csr(7, 14, CSR_MSCRATCH, 3), read],
+        &[(14, 0x1f), (15, 0x1c)],
+    );
+    // misa: MXL = 2 (RV64), I and M present.
+    let misa = [
+        csr(2, 14, CSR_MISA, 0),
+        srli(15, 14, 8),
+        InstructionBuilder::new().andi(15, 15, 1).build(),
+        srli(16, 14, 12),
+        InstructionBuilder::new().andi(16, 16, 1).build(),
+        srli(14, 14, 62),
+    ];
+    p.case(7, &[], &misa, &[(14, 2), (15, 1), (16, 1)]);
+    p.case(8, &[(14, V)], &[csr(2, 14, CSR_MHARTID, 0)], &[(14, 0)]);
+    p.build()
+}
+
+fn mi_illegal(base: u64) -> Vec<u8> {
+    let mut p = TestProgram::new(base);
+    p.handler(&skip_handler());
+    traps(&mut p, 2, csr(2, 14, CSR_MSTATUS, 0), 2);
+    traps(&mut p, 3, MRET, 2);
+    traps(&mut p, 4, 0, 2);
+    traps(&mut p, 5, InstructionBuilder::new().ebreak().build(), 3);
+    p.build()
+}
+
+fn si_csr(base: u64) -> Vec<u8> {
+    let mut p = TestProgram::new(base);
+    p.mode(PrivilegeMode::Supervisor).handler(&skip_handler());
+    p.case(2, &[(1, V)], &[csr(1, 0, CSR_SSCRATCH, 1), csr(2, 14, CSR_SSCRATCH, 0)], &[(14, V)]);
+    traps(&mut p, 3, csr(2, 14, CSR_MSCRATCH, 0), 2);
+    traps(&mut p, 4, csr(2, 14, CSR_MHARTID, 0), 2);
+    traps(&mut p, 5, MRET, 2);
+    p.build()
+}
+
+/// The bundled `rv64mi` and `rv64si` images.
+pub const TESTS: &[TestImage] =
+    &[("rv64mi-p-csr", mi_csr), ("rv64mi-p-illegal", mi_illegal), ("rv64si-p-csr", si_csr)];
+
+#[test]
+fn rv64mi_rv64si() {
+    run_suite(TESTS);
+}
@@ -0,0 +1,423 @@
+//! # rv64ui
+//!
+//! Arithmetic, logic, shifts and compares in register and immediate forms
+//! including the 32-bit `*w` variants, `lui`/`auipc`, every branch
+//! condition taken and not taken, `jal`/`jalr` links, and loads and stores
+//! of each width at positive and negative offsets.
+
+use super::{OPERANDS, Op, TestImage, imm, imm_shift, rr, rr_shift, run_suite, sext32};
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::compliance::env::TestProgram;
+use rvsim_core::isa::rv64i::opcodes::{
+    OP_BRANCH, OP_IMM, OP_IMM_32, OP_LOAD, OP_REG, OP_REG_32, OP_STORE,
+};
+
+const ADD: Op = Op::new(OP_REG, 0, 0);
+const SUB: Op = Op::new(OP_REG, 0, 0x20);
+const SLL: Op = Op::new(OP_REG, 1, 0);
+const SLT: Op = Op::new(OP_REG, 2, 0);
+const SLTU: Op = Op::new(OP_REG, 3, 0);
+const XOR: Op = Op::new(OP_REG, 4, 0);
+const SRL: Op = Op::new(OP_REG, 5, 0);
+const SRA: Op = Op::new(OP_REG, 5, 0x20);
+const OR: Op = Op::new(OP_REG, 6, 0);
+const AND: Op = Op::new(OP_REG, 7, 0);
+const ADDW: Op = Op::new(OP_REG_32, 0, 0);
+const SUBW: Op = Op::new(OP_REG_32, 0, 0x20);
+const SLLW: Op = Op::new(OP_REG_32, 1, 0);
+const SRLW: Op = Op::new(OP_REG_32, 5, 0);
+const SRAW: Op = Op::new(OP_REG_32, 5, 0x20);
+
+const ADDI: Op = Op::new(OP_IMM, 0, 0);
+const SLLI: Op = Op::new(OP_IMM, 1, 0);
+const SLTI: Op = Op::new(OP_IMM, 2, 0);
+const SLTIU: Op = Op::new(OP_IMM, 3, 0);
+const XORI: Op = Op::new(OP_IMM, 4, 0);
+const SRLI: Op = Op::new(OP_IMM, 5, 0);
+const SRAI: Op = Op::new(OP_IMM, 5, 0x20);
+const ORI: Op = Op::new(OP_IMM, 6, 0);
+const ANDI: Op = Op::new(OP_IMM, 7, 0);
+const ADDIW: Op = Op::new(OP_IMM_32, 0, 0);
+const SLLIW: Op = Op::new(OP_IMM_32, 1, 0);
+const SRLIW: Op = Op::new(OP_IMM_32, 5, 0);
+const SRAIW: Op = Op::new(OP_IMM_32, 5, 0x20);
+
+const BEQ: Op = Op::new(OP_BRANCH, 0, 0);
+const BNE: Op = Op::new(OP_BRANCH, 1, 0);
+const BLT: Op = Op::new(OP_BRANCH, 4, 0);
+const BGE: Op = Op::new(OP_BRANCH, 5, 0);
+const BLTU: Op = Op::new(OP_BRANCH, 6, 0);
+const BGEU: Op = Op::new(OP_BRANCH, 7, 0);
+
+const LB: Op = Op::new(OP_LOAD, 0, 0);
+const LH: Op = Op::new(OP_LOAD, 1, 0);
+const LW: Op = Op::new(OP_LOAD, 2, 0);
+const LD: Op = Op::new(OP_LOAD, 3, 0);
+const LBU: Op = Op::new(OP_LOAD, 4, 0);
+const LHU: Op = Op::new(OP_LOAD, 5, 0);
+const LWU: Op = Op::new(OP_LOAD, 6, 0);
+const SB: Op = Op::new(OP_STORE, 0, 0);
+const SH: Op = Op::new(OP_STORE, 1, 0);
+const SW: Op = Op::new(OP_STORE, 2, 0);
+const SD: Op = Op::new(OP_STORE, 3, 0);
+
+/// `tdat` for the load tests, as in `ld.S`.
+const LOAD_DATA: [u64; 4] =
+    [0x00ff_00ff_00ff_00ff, 0xff00_ff00_ff00_ff00, 0x0ff0_0ff0_0ff0_0ff0, 0xf00f_f00f_f00f_f00f];
+
+const fn lt(a: u64, b: u64) -> u64 {
+    ((a as i64) < (b as i64)) as u64
+}
+
+/// `TEST_BR2_OP_TAKEN` / `TEST_BR2_OP_NOTTAKEN` over [`OPERANDS`]: `x14`
+/// stays 0 only if the branch skips the instruction after it.
+fn branch(base: u64, op: Op, taken: fn(u64, u64) -> bool) -> Vec<u8> {
+    let b = InstructionBuilder::new;
+    let mut p = TestProgram::new(base);
+    for (num, &(x, y)) in (2..).zip(OPERANDS.iter().chain(&[(5, 5), (5, 6)])) {
+        let code = [b().addi(14, 0, 0).build(), op.b(1, 2, 8), b().addi(14, 0, 1).build()];
+        p.case(num, &[(1, x), (2, y)], &code, &[(14, u64::from(!taken(x, y)))]);
+    }
+    p.build()
+}
+
+/// `TEST_LD_OP` at every aligned offset of [`LOAD_DATA`], from its start
+/// and from its end with a negative offset.
+fn load(base: u64, op: Op, width: usize, signed: bool) -> Vec<u8> {
+    let bytes: Vec<u8> = LOAD_DATA.iter().flat_map(|d| d.to_le_bytes()).collect();
+    let mut p = TestProgram::new(base);
+    p.data(&bytes);
+    let tdat = p.tdat();
+    let len = bytes.len() as i32;
+    let mut num = 2;
+    for off in (0..bytes.len()).step_by(width) {
+        let mut raw = [0u8; 8];
+        raw[..width].copy_from_slice(&bytes[off..off + width]);
+        let shift = 64 - 8 * width as u32;
+        let value = u64::from_le_bytes(raw) << shift;
+        let value = if signed { ((value as i64) >> shift) as u64 } else { value >> shift };
+        let off = off as i32;
+        p.case(num, &[(1, tdat)], &[op.i(14, 1, off)], &[(14, value)]);
+        p.case(num + 1, &[(1, tdat + len as u64)], &[op.i(14, 1, off - len)], &[(14, value)]);
+        num += 2;
+    }
+    p.build()
+}
+
+/// `TEST_ST_OP` at every aligned offset of a 32-byte area, read back with
+/// the sign-extending load of the same width.
+fn store(base: u64, op: Op, reload: Op, width: usize) -> Vec<u8> {
+    let mut p = TestProgram::new(base);
+    p.data(&[0; 32]);
+    let tdat = p.tdat();
+    for (num, off) in (2..).zip((0..32).step_by(width)) {
+        let value = 0xaabb_ccdd_e00f_f00f_u64.rotate_left(8 * off as u32);
+        let shift = 64 - 8 * width as u32;
+        let expected = (((value << shift) as i64) >> shift) as u64;
+        let (addr, imm) = if num % 4 == 0 { (tdat + 32, off - 32) } else { (tdat, off) };
+        let code = [op.s(1, 2, imm), reload.i(14, 1, imm)];
+        p.case(num, &[(1, addr), (2, value)], &code, &[(14, expected)]);
+    }
+    p.build()
+}
+
+fn add(base: u64) -> Vec<u8> {
+    rr(base, ADD, u64::wrapping_add)
+}
+
+fn addi(base: u64) -> Vec<u8> {
+    imm(base, ADDI, u64::wrapping_add)
+}
+
+fn addiw(base: u64) -> Vec<u8> {
+    imm(base, ADDIW, |a, b| sext32(a.wrapping_add(b)))
+}
+
+fn addw(base: u64) -> Vec<u8> {
+    rr(base, ADDW, |a, b| sext32(a.wrapping_add(b)))
+}
+
+fn and(base: u64) -> Vec<u8> {
+    rr(base, AND, |a, b| a & b)
+}
+
+fn andi(base: u64) -> Vec<u8> {
+    imm(base, ANDI, |a, b| a & b)
+}
+
+fn auipc(base: u64) -> Vec<u8> {
+    let b = InstructionBuilder::new;
+    let mut p = TestProgram::new(base);
+    for (num, imm) in [(2, 1), (3, -1), (4, 0x7ffff), (5, -0x80000)] {
+        let code =
+            [b().auipc(14, imm).build(), b().auipc(15, 0).build(), b().sub(14, 14, 15).build()];
+        p.case(num, &[], &code, &[(14, ((i64::from(imm) << 12) - 4) as u64)]);
+    }
+    p.build()
+}
+
+fn beq(base: u64) -> Vec<u8> {
+    branch(base, BEQ, |a, b| a == b)
+}
+
+fn bge(base: u64) -> Vec<u8> {
+    branch(base, BGE, |a, b| a as i64 >= b as i64)
+}
+
+fn bgeu(base: u64) -> Vec<u8> {
+    branch(base, BGEU, |a, b| a >= b)
+}
+
+fn blt(base: u64) -> Vec<u8> {
+    branch(base, BLT, |a, b| (a as i64) < b as i64)
+}
+
+fn bltu(base: u64) -> Vec<u8> {
+    branch(base, BLTU, |a, b| a < b)
+}
+
+fn bne(base: u64) -> Vec<u8> {
+    branch(base, BNE, |a, b| a != b)
+}
+
+/// Jumps over two instructions and checks the link is the return address.
+fn jal(base: u64) -> Vec<u8> {
+    let b = InstructionBuilder::new;
+    let mut p = TestProgram::new(base);
+    let code = [
+        b().addi(14, 0, 0).build(),
+        b().auipc(2, 0).build(),
+        b().jal(1, 12).build(),
+        b().addi(14, 0, 1).build(),
+        b().addi(14, 0, 2).build(),
+        b().sub(15, 1, 2).build(),
+    ];
+    p.case(2, &[], &code, &[(14, 0), (15, 8)]);
+    let back = [
+        b().jal(0, 12).build(),
+        b().addi(14, 0, 3).build(),
+        b().jal(0, 8).build(),
+        b().jal(0, -8).build(),
+    ];
+    p.case(3, &[], &back, &[(14, 3)]);
+    p.build()
+}
+
+/// Jumps through a register, with bit 0 of the target ignored.
+fn jalr(base: u64) -> Vec<u8> {
+    let b = InstructionBuilder::new;
+    let mut p = TestProgram::new(base);
+    for (num, imm) in [(2, 16), (3, 17)] {
+        let code = [
+            b().addi(14, 0, 0).build(),
+            b().auipc(2, 0).build(),
+            b().jalr(1, 2, imm).build(),
+            b().addi(14, 0, 1).build(),
+            b().addi(14, 0, 2).build(),
+            b().sub(15, 1, 2).build(),
+        ];
+        p.case(num, &[], &code, &[(14, 0), (15, 8)]);
+    }
+    p.build()
+}
+
+fn lb(base: u64) -> Vec<u8> {
+    load(base, LB, 1, true)
+}
+
+fn lbu(base: u64) -> Vec<u8> {
+    load(base, LBU, 1, false)
+}
+
+fn ld(base: u64) -> Vec<u8> {
+    load(base, LD, 8, true)
+}
+
+fn lh(base: u64) -> Vec<u8> {
+    load(base, LH, 2, true)
+}
+
+fn lhu(base: u64) -> Vec<u8> {
+    load(base, LHU, 2, false)
+}
+
+fn lui(base: u64) -> Vec<u8> {
+    let b = InstructionBuilder::new;
+    let mut p = TestProgram::new(base);
+    for (num, imm) in [(2, 0), (3, 0xfffff), (4, 0x7ffff), (5, 0x80000), (6, 0x12345)] {
+        let expected = sext32(imm << 12);
+        p.case(num, &[], &[b().lui(14, imm as i32).build()], &[(14, expected)]);
+    }
+    p.case(7, &[], &[b().lui(0, 0x80000).build()], &[(0, 0)]);
+    p.build()
+}
+
+fn lw(base: u64) -> Vec<u8> {
+    load(base, LW, 4, true)
+}
+
+fn lwu(base: u64) -> Vec<u8> {
+    load(base, LWU, 4, false)
+}
+
+fn or(base: u64) -> Vec<u8> {
+    rr(base, OR, |a, b| a | b)
+}
+
+fn ori(base: u64) -> Vec<u8> {
+    imm(base, ORI, |a, b| a | b)
+}
+
+fn sb(base: u64) -> Vec<u8> {
+    store(base, SB, LB, 1)
+}
+
+fn sd(base: u64) -> Vec<u8> {
+    store(base, SD, LD, 8)
+}
+
+fn sh(base: u64) -> Vec<u8> {
+    store(base, SH, LH, 2)
+}
+
+fn sll(base: u64) -> Vec<u8> {
+    rr_shift(base, SLL, |a, b| a << (b & 63))
+}
+
+fn slli(base: u64) -> Vec<u8> {
+    imm_shift(base, SLLI, |a, b| a << b, 63)
+}
+
+fn slliw(base: u64) -> Vec<u8> {
+    imm_shift(base, SLLIW, |a, b| sext32(a << b), 31)
+}
+
+fn sllw(base: u64) -> Vec<u8> {
+    rr_shift(base, SLLW, |a, b| sext32(a << (b & 31)))
+}
+
+fn slt(base: u64) -> Vec<u8> {
+    rr(base, SLT, lt)
+}
+
+fn slti(base: u64) -> Vec<u8> {
+    imm(base, SLTI, lt)
+}
+
+fn sltiu(base: u64) -> Vec<u8> {
+    imm(base, SLTIU, |a, b| u64::from(a < b))
+}
+
+fn sltu(base: u64) -> Vec<u8> {
+    rr(base, SLTU, |a, b| u64::from(a < b))
+}
+
+fn sra(base: u64) -> Vec<u8> {
+    rr_shift(base, SRA, |a, b| ((a as i64) >> (b & 63)) as u64)
+}
+
+fn srai(base: u64) -> Vec<u8> {
+    imm_shift(base, SRAI, |a, b| ((a as i64) >> b) as u64, 63)
+}
+
+fn sraiw(base: u64) -> Vec<u8> {
+    imm_shift(base, SRAIW, |a, b| ((a as i32) >> b) as i64 as u64, 31)
+}
+
+fn sraw(base: u64) -> Vec<u8> {
+    rr_shift(base, SRAW, |a, b| ((a as i32) >> (b & 31)) as i64 as u64)
+}
+
+fn srl(base: u64) -> Vec<u8> {
+    rr_shift(base, SRL, |a, b| a >> (b & 63))
+}
+
+fn srli(base: u64) -> Vec<u8> {
+    imm_shift(base, SRLI, |a, b| a >> b, 63)
+}
+
+fn srliw(base: u64) -> Vec<u8> {
+    imm_shift(base, SRLIW, |a, b| sext32(u64::from(a as u32 >> b)), 31)
+}
+
+fn srlw(base: u64) -> Vec<u8> {
+    rr_shift(base, SRLW, |a, b| sext32(u64::from(a as u32 >> (b & 31))))
+}
+
+fn sub(base: u64) -> Vec<u8> {
+    rr(base, SUB, u64::wrapping_sub)
+}
+
+fn subw(base: u64) -> Vec<u8> {
+    rr(base, SUBW, |a, b| sext32(a.wrapping_sub(b)))
+}
+
+fn sw(base: u64) -> Vec<u8> {
+    store(base, SW, LW, 4)
+}
+
+fn xor(base: u64) -> Vec<u8> {
+    rr(base, XOR, |a, b| a ^ b)
+}
+
+fn xori(base: u64) -> Vec<u8> {
+    imm(base, XORI, |a, b| a ^ b)
+}
+
+/// The bundled `rv64ui` images.
+pub const TESTS: &[TestImage] = &[
+    ("rv64ui-p-add", add),
+    ("rv64ui-p-addi", addi),
+    ("rv64ui-p-addiw", addiw),
+    ("rv64ui-p-addw", addw),
+    ("rv64ui-p-and", and),
+    ("rv64ui-p-andi", andi),
+    ("rv64ui-p-auipc", auipc),
+    ("rv64ui-p-beq", beq),
+    ("rv64ui-p-bge", bge),
+    ("rv64ui-p-bgeu", bgeu),
+    ("rv64ui-p-blt", blt),
+    ("rv64ui-p-bltu", bltu),
+    ("rv64ui-p-bne", bne),
+    ("rv64ui-p-jal", jal),
+    ("rv64ui-p-jalr", jalr),
+    ("rv64ui-p-lb", lb),
+    ("rv64ui-p-lbu", lbu),
+    ("rv64ui-p-ld", ld),
+    ("rv64ui-p-lh", lh),
+    ("rv64ui-p-lhu", lhu),
+    ("rv64ui-p-lui", lui),
+    ("rv64ui-p-lw", lw),
+    ("rv64ui-p-lwu", lwu),
+    ("rv64ui-p-or", or),
+    ("rv64ui-p-ori", ori),
+    ("rv64ui-p-sb", sb),
+    ("rv64ui-p-sd", sd),
+    ("rv64ui-p-sh", sh),
+    ("rv64ui-p-sll", sll),
+    ("rv64ui-p-slli", slli),
+    ("rv64ui-p-slliw", slliw),
+    ("rv64ui-p-sllw", sllw),
+    ("rv64ui-p-slt", slt),
+    ("rv64ui-p-slti", slti),
+    ("rv64ui-p-sltiu", sltiu),
+    ("rv64ui-p-sltu", sltu),
+    ("rv64ui-p-sra", sra),
+    ("rv64ui-p-srai", srai),
+    ("rv64ui-p-sraiw", sraiw),
+    ("rv64ui-p-sraw", sraw),
+    ("rv64ui-p-srl", srl),
+    ("rv64ui-p-srli", srli),
+    ("rv64ui-p-srliw", srliw),
+    ("rv64ui-p-srlw", srlw),
+    ("rv64ui-p-sub", sub),
+    ("rv64ui-p-subw", subw),
+    ("rv64ui-p-sw", sw),
+    ("rv64ui-p-xor", xor),
+    ("rv64ui-p-xori", xori),
+];
+
+#[test]
+fn rv64ui() {
+    run_suite(TESTS);
+}
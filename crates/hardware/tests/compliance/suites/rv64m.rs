@@ -0,0 +1,114 @@
+//! # rv64um
+//!
+//! Multiplies, including the high halves for every signedness, and divides
+//! and remainders, including division by zero and the signed overflow
+//! case, in 64-bit and `*w` forms.
+
+use super::{OPERANDS, Op, TestImage, rr, rr_image, run_suite, sext32};
+use rvsim_core::isa::rv64i::opcodes::{OP_REG, OP_REG_32};
+
+const MUL: Op = Op::new(OP_REG, 0, 1);
+const MULH: Op = Op::new(OP_REG, 1, 1);
+const MULHSU: Op = Op::new(OP_REG, 2, 1);
+const MULHU: Op = Op::new(OP_REG, 3, 1);
+const DIV: Op = Op::new(OP_REG, 4, 1);
+const DIVU: Op = Op::new(OP_REG, 5, 1);
+const REM: Op = Op::new(OP_REG, 6, 1);
+const REMU: Op = Op::new(OP_REG, 7, 1);
+const MULW: Op = Op::new(OP_REG_32, 0, 1);
+const DIVW: Op = Op::new(OP_REG_32, 4, 1);
+const DIVUW: Op = Op::new(OP_REG_32, 5, 1);
+const REMW: Op = Op::new(OP_REG_32, 6, 1);
+const REMUW: Op = Op::new(OP_REG_32, 7, 1);
+
+/// Operand pairs for the `*w` divides: [`OPERANDS`] plus the 32-bit signed
+/// overflow and dividends whose upper halves must be ignored.
+fn word_operands() -> Vec<(u64, u64)> {
+    let extra = [(0x8000_0000, u64::MAX), (0x1_0000_0014, 6), (0x14, 0x1_0000_0000)];
+    OPERANDS.iter().copied().chain(extra).collect()
+}
+
+fn div(base: u64) -> Vec<u8> {
+    rr(base, DIV, |a, b| if b == 0 { u64::MAX } else { (a as i64).wrapping_div(b as i64) as u64 })
+}
+
+fn divu(base: u64) -> Vec<u8> {
+    rr(base, DIVU, |a, b| a.checked_div(b).unwrap_or(u64::MAX))
+}
+
+fn divuw(base: u64) -> Vec<u8> {
+    let model = |a: u64, b: u64| {
+        (a as u32).checked_div(b as u32).map_or(u64::MAX, |q| sext32(u64::from(q)))
+    };
+    rr_image(base, DIVUW, model, &word_operands())
+}
+
+fn divw(base: u64) -> Vec<u8> {
+    let model = |a: u64, b: u64| {
+        if b as u32 == 0 { u64::MAX } else { i64::from((a as i32).wrapping_div(b as i32)) as u64 }
+    };
+    rr_image(base, DIVW, model, &word_operands())
+}
+
+fn mul(base: u64) -> Vec<u8> {
+    rr(base, MUL, u64::wrapping_mul)
+}
+
+fn mulh(base: u64) -> Vec<u8> {
+    rr(base, MULH, |a, b| ((i128::from(a as i64) * i128::from(b as i64)) >> 64) as u64)
+}
+
+fn mulhsu(base: u64) -> Vec<u8> {
+    rr(base, MULHSU, |a, b| ((i128::from(a as i64) * i128::from(b)) >> 64) as u64)
+}
+
+fn mulhu(base: u64) -> Vec<u8> {
+    rr(base, MULHU, |a, b| ((u128::from(a) * u128::from(b)) >> 64) as u64)
+}
+
+fn mulw(base: u64) -> Vec<u8> {
+    rr(base, MULW, |a, b| sext32(a.wrapping_mul(b)))
+}
+
+fn rem(base: u64) -> Vec<u8> {
+    rr(base, REM, |a, b| if b == 0 { a } else { (a as i64).wrapping_rem(b as i64) as u64 })
+}
+
+fn remu(base: u64) -> Vec<u8> {
+    rr(base, REMU, |a, b| a.checked_rem(b).unwrap_or(a))
+}
+
+fn remuw(base: u64) -> Vec<u8> {
+    let model =
+        |a: u64, b: u64| sext32(u64::from((a as u32).checked_rem(b as u32).unwrap_or(a as u32)));
+    rr_image(base, REMUW, model, &word_operands())
+}
+
+fn remw(base: u64) -> Vec<u8> {
+    let model = |a: u64, b: u64| {
+        if b as u32 == 0 { sext32(a) } else { i64::from((a as i32).wrapping_rem(b as i32)) as u64 }
+    };
+    rr_image(base, REMW, model, &word_operands())
+}
+
+/// The bundled `rv64um` images.
+pub const TESTS: &[TestImage] = &[
+    ("rv64um-p-div", div),
+    ("rv64um-p-divu", divu),
+    ("rv64um-p-divuw", divuw),
+    ("rv64um-p-divw", divw),
+    ("rv64um-p-mul", mul),
+    ("rv64um-p-mulh", mulh),
+    ("rv64um-p-mulhsu", mulhsu),
+    ("rv64um-p-mulhu", mulhu),
+    ("rv64um-p-mulw", mulw),
+    ("rv64um-p-rem", rem),
+    ("rv64um-p-remu", remu),
+    ("rv64um-p-remuw", remuw),
+    ("rv64um-p-remw", remw),
+];
+
+#[test]
+fn rv64um() {
+    run_suite(TESTS);
+}
@@ -49,4 +49,7 @@ pub mod unit;
 pub mod integration;
 
 // pub mod fuzz;
-// pub mod compliance;
+
+/// ISA compliance tests: a harness for `riscv-tests` images and a bundled
+/// subset of the suite that runs without a RISC-V toolchain.
+pub mod compliance;
@@ -4,6 +4,7 @@
 //! defaults, and validation.
 
 use rvsim_core::config::*;
+use rvsim_core::core::pipeline::frontend::fusion::FusionPattern;
 
 #[test]
 fn test_config_default() {
@@ -36,6 +37,21 @@ fn test_system_config_defaults() {
     assert_eq!(system.bus_latency, 4);
     assert_eq!(system.clint_divider, 10);
     assert!(!system.uart_to_stderr);
+    assert_eq!(system.net_base, 0x9000_1000);
+    assert_eq!(system.net, None);
+    assert_eq!(system.rng_base, 0x9000_2000);
+    assert_eq!(system.rng_seed, 0);
+}
+
+#[test]
+fn test_system_net_backends() {
+    let parse = |net: &str| -> SystemConfig {
+        serde_json::from_str(&format!(r#"{{"net": {net}}}"#)).unwrap()
+    };
+    assert_eq!(parse(r#""Capture""#).net, Some(NetBackend::Capture));
+    assert_eq!(parse(r#""Loopback""#).net, Some(NetBackend::Loopback));
+    assert_eq!(parse(r#"{"Tap": "tap0"}"#).net, Some(NetBackend::Tap("tap0".into())));
+    assert_eq!(parse("null").net, None);
 }
 
 #[test]
@@ -81,6 +97,17 @@ fn test_pipeline_config_defaults() {
     assert_eq!(pipeline.btb_size, 256);
     assert_eq!(pipeline.ras_size, 8);
     assert_eq!(pipeline.misa_override, None);
+    assert!(pipeline.fusion.is_empty());
+}
+
+#[test]
+fn test_pipeline_fusion_patterns() {
+    let pipeline: PipelineConfig =
+        serde_json::from_str(r#"{"fusion": ["LuiAddi", "AuipcJalr", "CmpBranch"]}"#).unwrap();
+    assert_eq!(
+        pipeline.fusion,
+        [FusionPattern::LuiAddi, FusionPattern::AuipcJalr, FusionPattern::CmpBranch]
+    );
 }
 
 #[test]
@@ -115,6 +142,20 @@ fn test_memory_controller_enum() {
     assert_ne!(MemoryController::Simple, MemoryController::Dram);
 }
 
+#[test]
+fn test_dram_scheduler_names() {
+    assert_eq!(DramScheduler::default(), DramScheduler::Immediate);
+    for (name, expected) in [
+        ("Immediate", DramScheduler::Immediate),
+        ("FCFS", DramScheduler::Fcfs),
+        ("FR-FCFS", DramScheduler::FrFcfs),
+        ("FrFcfs", DramScheduler::FrFcfs),
+    ] {
+        let parsed: DramScheduler = serde_json::from_str(&format!("\"{name}\"")).unwrap();
+        assert_eq!(parsed, expected, "{name}");
+    }
+}
+
 #[test]
 fn test_replacement_policy_enum() {
     assert_eq!(ReplacementPolicy::default(), ReplacementPolicy::Lru);
@@ -1067,3 +1108,105 @@ fn test_custom_dram_timings() {
     assert_eq!(config.memory.row_miss_latency, 200);
     assert_eq!(config.memory.tlb_size, 64);
 }
+
+#[test]
+fn test_memory_regions_default_to_single_bank() {
+    let config = Config::default();
+    assert_eq!(
+        config.ram_regions(),
+        vec![MemoryRegion { base: config.system.ram_base, size: config.memory.ram_size }]
+    );
+    assert!(config.validate_memory_map().is_ok());
+    assert!(!config.memory.safe_ram);
+}
+
+#[test]
+fn test_memory_regions_validation() {
+    let mut config = Config::default();
+    let base = config.system.ram_base;
+    config.memory.regions = vec![
+        MemoryRegion { base: base + 0x10_0000, size: 0x10_0000 },
+        MemoryRegion { base, size: 0x8_0000 },
+    ];
+    assert!(config.validate_memory_map().is_ok());
+    assert_eq!(config.ram_regions()[0].base, base);
+
+    config.memory.regions[1].size = 0x20_0000;
+    assert!(config.validate_memory_map().unwrap_err().contains("overlap"));
+
+    config.memory.regions[1].size = 0;
+    assert!(config.validate_memory_map().unwrap_err().contains("empty"));
+
+    config.memory.regions = vec![MemoryRegion { base: base + 0x10_0000, size: 0x10_0000 }];
+    assert!(config.validate_memory_map().unwrap_err().contains("ram_base"));
+}
+
+#[test]
+fn test_devices_default_to_standard_set() {
+    let mut config = Config::default();
+    let types: Vec<_> = config.devices().iter().map(|d| d.kind.name()).collect();
+    assert_eq!(
+        types,
+        ["Uart", "VirtioBlock", "VirtioRng", "Clint", "Plic", "SysCon", "GoldfishRtc"]
+    );
+    assert_eq!(config.devices()[0].base, config.system.uart_base);
+    assert_eq!(config.devices()[0].irq(), Some(10));
+    assert!(config.validate_devices().is_ok());
+
+    config.system.dma = true;
+    config.system.net = Some(NetBackend::Capture);
+    let types: Vec<_> = config.devices().iter().map(|d| d.kind.name()).collect();
+    assert_eq!(types[7..], ["Dma", "VirtioNet"]);
+}
+
+#[test]
+fn test_json_device_list() {
+    let system: SystemConfig = serde_json::from_str(
+        r#"{"devices": [
+            {"type": "Uart", "base": 268435456},
+            {"type": "Uart", "base": 268439552, "irq": 12, "quiet": true},
+            {"type": "VirtioNet", "base": 2415923200, "backend": "Loopback"},
+            {"type": "Clint", "base": 33554432}
+        ]}"#,
+    )
+    .unwrap();
+    let config = Config { system, ..Config::default() };
+    let devices = config.devices();
+    assert_eq!(devices.len(), 4);
+    assert_eq!(devices[1].kind, DeviceKind::Uart { to_stderr: false, quiet: true });
+    assert_eq!((devices[1].base, devices[1].irq()), (0x1000_1000, Some(12)));
+    assert_eq!(devices[2].kind, DeviceKind::VirtioNet { backend: NetBackend::Loopback });
+    assert_eq!(devices[3].irq(), None);
+    assert!(config.validate_devices().is_ok());
+
+    let unknown =
+        serde_json::from_str::<SystemConfig>(r#"{"devices": [{"type": "Gpu", "base": 0}]}"#);
+    assert!(unknown.is_err());
+}
+
+#[test]
+fn test_device_list_validation() {
+    let uart = |base: u64, irq: Option<u32>| DeviceConfig {
+        kind: DeviceKind::Uart { to_stderr: false, quiet: false },
+        base,
+        irq,
+    };
+    let mut config = Config::default();
+
+    config.system.devices = vec![uart(0x1000_0000, None), uart(0x1000_1000, None)];
+    let err = config.validate_devices().unwrap_err();
+    assert!(err.contains("both use interrupt 10"), "{err}");
+
+    config.system.devices[1].irq = Some(64);
+    assert!(config.validate_devices().unwrap_err().contains("1 to 63"));
+
+    config.system.devices =
+        (0..5).map(|i| uart(0x1000_0000 + i * 0x1000, Some(10 + i as u32))).collect();
+    assert!(config.validate_devices().unwrap_err().contains("at most 4"));
+
+    config.system.devices = vec![
+        DeviceConfig { kind: DeviceKind::Plic, base: 0x0c00_0000, irq: None },
+        DeviceConfig { kind: DeviceKind::Plic, base: 0x0d00_0000, irq: None },
+    ];
+    assert!(config.validate_devices().unwrap_err().contains("more than one Plic"));
+}
@@ -0,0 +1,141 @@
+//! Physical Memory Access Trace Tests.
+//!
+//! Runs a short load/store program on both backends with the access trace on
+//! and reads the file back:
+//!   - The record counts match the committed load and store counters and the
+//!     I-cache miss counter, in both the binary and the CSV format.
+//!   - Loads and stores carry the PC and physical address of the access.
+//!   - Sampling keeps one record in N.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use rvsim_core::Simulator;
+use rvsim_core::common::{PhysAddr, RegIdx};
+use rvsim_core::config::{AccessTraceFormat, Config};
+use rvsim_core::core::cpu::access_trace::{AccessRecord, read_access_trace};
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::soc::System;
+
+/// Offset from the start of RAM of the data the program accesses.
+const DATA: u64 = 0x2000;
+
+/// Instructions in the program before the final `j .`.
+const BODY: u64 = 6;
+
+/// Returns a trace path unique to this test process and `name`.
+fn trace_path(name: &str) -> String {
+    std::env::temp_dir()
+        .join(format!("rvsim-access-trace-{}-{name}", std::process::id()))
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Runs the load/store program on `backend` with the access trace written
+/// to `path` and the I-cache on, returning the simulator with the trace closed.
+fn run(backend: BackendType, path: &str, format: AccessTraceFormat, sample: u64) -> Simulator {
+    let b = InstructionBuilder::new;
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.cache.l1_i.enabled = true;
+    config.general.access_trace = Some(path.to_owned());
+    config.general.access_trace_format = format;
+    config.general.access_trace_sample = sample;
+    let base = config.system.ram_base;
+
+    let program = [
+        b().ld(7, 10, 0).build(),
+        b().sd(10, 7, 8).build(),
+        b().lw(8, 10, 8).build(),
+        b().addi(9, 8, 1).build(),
+        b().sw(10, 9, 16).build(),
+        b().ld(11, 10, 16).build(),
+        b().jal(0, 0).build(),
+    ];
+    let mut sim = Simulator::new(System::new(&config, ""), &config);
+    for (i, &inst) in program.iter().enumerate() {
+        sim.cpu.bus.bus.write_u32(PhysAddr::new(base + 4 * i as u64), inst);
+    }
+    sim.cpu.bus.bus.write_u64(PhysAddr::new(base + DATA), 0x1234);
+    sim.cpu.pc = base;
+    sim.write_reg(RegIdx::new(10), base + DATA);
+
+    for _ in 0..10_000 {
+        sim.tick().unwrap();
+        if sim.cpu.stats.instructions_retired >= BODY {
+            break;
+        }
+    }
+    assert!(sim.cpu.stats.instructions_retired >= BODY, "{backend:?}: program did not finish");
+    sim.cpu.access_trace = None;
+    sim
+}
+
+/// Returns the (loads, stores, fetches) in `records`.
+fn counts(records: &[AccessRecord]) -> (u64, u64, u64) {
+    let fetches = records.iter().filter(|r| r.is_fetch).count() as u64;
+    let stores = records.iter().filter(|r| r.is_write).count() as u64;
+    (records.len() as u64 - fetches - stores, stores, fetches)
+}
+
+#[test]
+fn record_counts_match_stats() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        for format in [AccessTraceFormat::Binary, AccessTraceFormat::Csv] {
+            let path = trace_path(&format!("counts-{backend:?}-{format:?}"));
+            let sim = run(backend, &path, format, 1);
+            let records = read_access_trace(&path).unwrap();
+            let _ = std::fs::remove_file(&path);
+
+            let stats = &sim.cpu.stats;
+            assert_eq!(
+                counts(&records),
+                (stats.inst_load, stats.inst_store, stats.icache_misses),
+                "{backend:?} {format:?}"
+            );
+            assert_eq!((stats.inst_load, stats.inst_store), (3, 2), "{backend:?} {format:?}");
+            assert!(stats.icache_misses > 0, "{backend:?} {format:?}");
+        }
+    }
+}
+
+#[test]
+fn loads_and_stores_carry_pc_and_paddr() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let path = trace_path(&format!("addrs-{backend:?}"));
+        let _ = run(backend, &path, AccessTraceFormat::Binary, 1);
+        let records = read_access_trace(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        let base = Config::default().system.ram_base;
+        let data: Vec<_> = records
+            .iter()
+            .filter(|r| !r.is_fetch)
+            .map(|r| (r.pc - base, r.paddr - base, r.size, r.is_write))
+            .collect();
+        assert_eq!(
+            data,
+            [
+                (0, DATA, 8, false),
+                (4, DATA + 8, 8, true),
+                (8, DATA + 8, 4, false),
+                (16, DATA + 16, 4, true),
+                (20, DATA + 16, 8, false),
+            ],
+            "{backend:?}"
+        );
+        assert!(records.windows(2).all(|w| w[0].cycle <= w[1].cycle), "{backend:?}");
+    }
+}
+
+#[test]
+fn sampling_keeps_one_record_in_n() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let path = trace_path(&format!("sample-{backend:?}"));
+        let sim = run(backend, &path, AccessTraceFormat::Binary, 2);
+        let records = read_access_trace(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        let stats = &sim.cpu.stats;
+        let total = stats.inst_load + stats.inst_store + stats.icache_misses;
+        assert_eq!(records.len() as u64, total / 2, "{backend:?}");
+    }
+}
@@ -0,0 +1,184 @@
+//! RAM Fault Injection Tests.
+//!
+//! Runs machine-mode loads on both backends over RAM words with injected
+//! bit flips and verifies the ECC model:
+//!   - With SEC-DED, a single flipped bit is corrected on the load, scrubbed
+//!     from RAM and counted; a double flip raises a load access fault.
+//!   - A store over the bad bytes clears the error.
+//!   - Without ECC a scheduled flip reaches the loaded value.
+//!   - Random flips are drawn per load and, one at a time, always corrected.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use rvsim_core::Simulator;
+use rvsim_core::common::{PhysAddr, RegIdx};
+use rvsim_core::config::{Config, Ecc, InjectedFault};
+use rvsim_core::core::arch::mode::PrivilegeMode;
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::isa::privileged::cause::exception;
+use rvsim_core::soc::System;
+
+/// Offset from the start of RAM of the data word loaded.
+const DATA: u64 = 0x2000;
+/// Value stored at `DATA`.
+const VALUE: u64 = 0x0123_4567_89ab_cdef;
+
+/// Loads `program` at the start of RAM with a `j .` trap handler, stores
+/// `VALUE` at `DATA`, points `a0` at it and starts in M-mode.
+fn setup(config: &Config, program: &[u32]) -> Simulator {
+    let b = InstructionBuilder::new;
+    let base = config.system.ram_base;
+    let handler = base + 0x100;
+
+    let mut sim = Simulator::new(System::new(config, ""), config);
+    let bus = &mut sim.cpu.bus.bus;
+    for (i, &inst) in program.iter().enumerate() {
+        bus.write_u32(PhysAddr::new(base + 4 * i as u64), inst);
+    }
+    bus.write_u32(PhysAddr::new(handler), b().jal(0, 0).build());
+    bus.write_u64(PhysAddr::new(base + DATA), VALUE);
+    sim.cpu.direct_mode = false;
+    sim.cpu.csrs.mtvec = handler;
+    sim.cpu.privilege = PrivilegeMode::Machine;
+    sim.cpu.pc = base;
+    sim.write_reg(RegIdx::new(10), base + DATA);
+    sim
+}
+
+/// Config for `backend` with SEC-DED ECC on.
+fn secded(backend: BackendType) -> Config {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.memory.fault_injection.ecc = Ecc::SecDed;
+    config
+}
+
+/// Ticks until `retired` instructions retire or a trap is taken, panicking
+/// after 10 000 cycles.
+fn run(sim: &mut Simulator, backend: BackendType, retired: u64) {
+    for _ in 0..10_000 {
+        sim.tick().unwrap();
+        if sim.cpu.stats.instructions_retired >= retired || !sim.cpu.recent_traps.is_empty() {
+            return;
+        }
+    }
+    panic!("{backend:?}: program did not finish");
+}
+
+#[test]
+fn single_bit_flip_is_corrected_and_scrubbed() {
+    let b = InstructionBuilder::new;
+    let program = [b().ld(7, 10, 0).build(), b().jal(0, 0).build()];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut sim = setup(&secded(backend), &program);
+        let addr = sim.cpu.pc + DATA;
+        let cpu = &mut sim.cpu;
+        assert!(cpu.faults.inject(&cpu.ram, &mut cpu.stats, addr + 3, 5));
+        assert_eq!(cpu.bus.bus.read_u64(PhysAddr::new(addr)), VALUE ^ (1 << 29));
+        run(&mut sim, backend, 1);
+
+        assert!(sim.cpu.recent_traps.is_empty(), "{backend:?}");
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(7)), VALUE, "{backend:?}");
+        assert_eq!(sim.cpu.bus.bus.read_u64(PhysAddr::new(addr)), VALUE, "{backend:?}: scrubbed");
+        assert_eq!(sim.cpu.stats.faults_injected, 1, "{backend:?}");
+        assert_eq!(sim.cpu.stats.ecc_corrected, 1, "{backend:?}");
+        assert_eq!(sim.cpu.stats.ecc_uncorrected, 0, "{backend:?}");
+        assert_eq!(sim.cpu.faults.flipped_bits(), 0, "{backend:?}");
+    }
+}
+
+#[test]
+fn double_bit_flip_raises_load_access_fault() {
+    let b = InstructionBuilder::new;
+    let program = [b().lw(7, 10, 4).build(), b().jal(0, 0).build()];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut sim = setup(&secded(backend), &program);
+        let base = sim.cpu.pc;
+        let cpu = &mut sim.cpu;
+        // Both flips land in the word, outside the bytes the load reads.
+        assert!(cpu.faults.inject(&cpu.ram, &mut cpu.stats, base + DATA, 0));
+        assert!(cpu.faults.inject(&cpu.ram, &mut cpu.stats, base + DATA + 1, 7));
+        run(&mut sim, backend, 1);
+
+        let t = sim.cpu.recent_traps.back().expect("load should fault");
+        assert_eq!(t.cause, exception::LOAD_ACCESS_FAULT, "{backend:?}");
+        assert_eq!(t.tval, base + DATA + 4, "{backend:?}");
+        assert_eq!(t.epc, base, "{backend:?}");
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(7)), 0, "{backend:?}");
+        assert!(sim.cpu.stats.ecc_uncorrected >= 1, "{backend:?}");
+        assert_eq!(sim.cpu.stats.ecc_corrected, 0, "{backend:?}");
+        assert_eq!(sim.cpu.faults.flipped_bits(), 2, "{backend:?}: word stays poisoned");
+    }
+}
+
+#[test]
+fn store_over_bad_bytes_clears_the_error() {
+    let b = InstructionBuilder::new;
+    let program = [b().sd(10, 0, 0).build(), b().ld(7, 10, 0).build(), b().jal(0, 0).build()];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut sim = setup(&secded(backend), &program);
+        let addr = sim.cpu.pc + DATA;
+        let cpu = &mut sim.cpu;
+        assert!(cpu.faults.inject(&cpu.ram, &mut cpu.stats, addr, 0));
+        assert!(cpu.faults.inject(&cpu.ram, &mut cpu.stats, addr + 7, 0));
+        run(&mut sim, backend, 2);
+
+        assert!(sim.cpu.recent_traps.is_empty(), "{backend:?}");
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(7)), 0, "{backend:?}");
+        assert_eq!(sim.cpu.stats.ecc_uncorrected, 0, "{backend:?}");
+        assert_eq!(sim.cpu.faults.flipped_bits(), 0, "{backend:?}");
+    }
+}
+
+#[test]
+fn scheduled_flip_reaches_the_load_without_ecc() {
+    let b = InstructionBuilder::new;
+    let program = [b().ld(7, 10, 0).build(), b().jal(0, 0).build()];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut config = Config::default();
+        config.pipeline.backend = backend;
+        let addr = config.system.ram_base + DATA;
+        config.memory.fault_injection.faults =
+            vec![InjectedFault { addr: addr + 7, bit: 7, cycle: 1 }];
+        let mut sim = setup(&config, &program);
+        run(&mut sim, backend, 1);
+
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(7)), VALUE ^ (1 << 63), "{backend:?}");
+        assert_eq!(sim.cpu.stats.faults_injected, 1, "{backend:?}");
+        assert_eq!(sim.cpu.stats.ecc_corrected, 0, "{backend:?}");
+    }
+}
+
+#[test]
+fn random_flips_are_corrected_one_at_a_time() {
+    let b = InstructionBuilder::new;
+    let program = [
+        b().ld(7, 10, 0).build(),
+        b().ld(8, 10, 0).build(),
+        b().ld(9, 10, 0).build(),
+        b().jal(0, 0).build(),
+    ];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut config = secded(backend);
+        config.memory.fault_injection.rate = 1.0;
+        config.memory.fault_injection.seed = 7;
+        let mut sim = setup(&config, &program);
+        run(&mut sim, backend, 3);
+
+        assert!(sim.cpu.recent_traps.is_empty(), "{backend:?}");
+        for rd in 7..=9 {
+            assert_eq!(sim.cpu.regs.read(RegIdx::new(rd)), VALUE, "{backend:?}: x{rd}");
+        }
+        assert!(sim.cpu.stats.faults_injected >= 3, "{backend:?}");
+        assert_eq!(sim.cpu.stats.ecc_corrected, sim.cpu.stats.faults_injected, "{backend:?}");
+    }
+}
+
+#[test]
+fn fault_outside_ram_is_rejected() {
+    let mut config = Config::default();
+    config.memory.fault_injection.faults = vec![InjectedFault { addr: 0x10, bit: 0, cycle: 0 }];
+    assert!(System::try_new(&config, "").is_err());
+    config.memory.fault_injection.faults =
+        vec![InjectedFault { addr: config.system.ram_base, bit: 8, cycle: 0 }];
+    assert!(System::try_new(&config, "").is_err());
+}
@@ -0,0 +1,81 @@
+//! Interrupt Masking Tests.
+//!
+//! A timer interrupt raised while `mstatus.MIE` is clear stays pending in
+//! `mip` without being taken. Once the program sets `mstatus.MIE` the
+//! interrupt is taken at the next commit boundary, with `mepc` pointing at
+//! the instruction after the `csrs`.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use rvsim_core::Simulator;
+use rvsim_core::common::{PhysAddr, RegIdx};
+use rvsim_core::config::Config;
+use rvsim_core::core::arch::csr;
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::soc::System;
+
+/// `csrs mstatus, x9`.
+const CSRS_MSTATUS_X9: u32 = 0x3004_A073;
+
+/// `mret`.
+const MRET: u32 = 0x3020_0073;
+
+/// Offset of the trap handler from the start of RAM.
+const HANDLER_OFFSET: u64 = 0x800;
+
+/// Offset of `mtimecmp` for hart 0 within the CLINT.
+const MTIMECMP_OFFSET: u64 = 0x4000;
+
+/// Iterations of the counting loop run with the interrupt masked.
+const ITERATIONS: i32 = 200;
+
+#[test]
+fn masked_timer_interrupt_fires_once_unmasked() {
+    let b = InstructionBuilder::new;
+    let program = [
+        (0x00, b().addi(5, 5, 1).build()),
+        (0x04, b().bne(5, 6, -4).build()),
+        (0x08, CSRS_MSTATUS_X9),
+        (0x0C, b().addi(7, 0, 42).build()),
+        (0x10, b().jal(0, 0).build()),
+        (HANDLER_OFFSET, b().addi(28, 28, 1).build()),
+        (HANDLER_OFFSET + 4, b().addi(11, 0, -1).build()),
+        (HANDLER_OFFSET + 8, b().sd(10, 11, 0).build()),
+        (HANDLER_OFFSET + 12, MRET),
+    ];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut config = Config::default();
+        config.pipeline.backend = backend;
+        config.system.clint_divider = 1;
+        let base = config.system.ram_base;
+
+        let mut sim = Simulator::new(System::new(&config, ""), &config);
+        for (offset, inst) in program {
+            sim.cpu.bus.bus.write_u32(PhysAddr::new(base + offset), inst);
+        }
+        let mtimecmp = config.system.clint_base + MTIMECMP_OFFSET;
+        sim.cpu.bus.bus.write_u64(PhysAddr::new(mtimecmp), 0);
+        sim.cpu.pc = base;
+        sim.cpu.direct_mode = false;
+        sim.cpu.csrs.mtvec = base + HANDLER_OFFSET;
+        sim.cpu.csrs.mie = csr::MIE_MTIE;
+        sim.write_reg(RegIdx::new(6), ITERATIONS as u64);
+        sim.write_reg(RegIdx::new(9), csr::MSTATUS_MIE);
+        sim.write_reg(RegIdx::new(10), mtimecmp);
+
+        let mut held = false;
+        for _ in 0..5_000 {
+            sim.tick().unwrap();
+            if sim.cpu.csrs.mstatus & csr::MSTATUS_MIE == 0
+                && sim.cpu.regs.read(RegIdx::new(28)) == 0
+            {
+                held |= sim.cpu.csrs.mip & csr::MIP_MTIP != 0;
+            }
+        }
+        assert!(held, "{backend:?}: timer interrupt never pending while masked");
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(5)), ITERATIONS as u64, "{backend:?}");
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(28)), 1, "{backend:?}");
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(7)), 42, "{backend:?}");
+        assert_eq!(sim.cpu.csrs.mcause, (1 << 63) | 7, "{backend:?}");
+        assert_eq!(sim.cpu.csrs.mepc, base + 0x0C, "{backend:?}");
+    }
+}
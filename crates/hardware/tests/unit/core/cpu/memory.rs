@@ -2,9 +2,12 @@
 //!
 //! Tests for address translation, cache simulation, and memory access.
 
-use rvsim_core::common::{AccessType, VirtAddr};
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::common::{AccessType, PhysAddr, RegIdx, VirtAddr};
 use rvsim_core::config::Config;
 use rvsim_core::core::Cpu;
+use rvsim_core::core::pipeline::engine::BackendType;
 
 fn create_test_cpu() -> Cpu {
     let config = Config::default();
@@ -444,3 +447,86 @@ fn test_cache_disabled_no_stats_update() {
     assert_eq!(cpu.stats.icache_hits, initial_icache_hits);
     assert_eq!(cpu.stats.dcache_hits, initial_dcache_hits);
 }
+
+#[test]
+fn test_dram_traffic_recorded_for_uncached_stream() {
+    let mut config = Config::default();
+    config.memory.controller = rvsim_core::config::MemoryController::Simple;
+    config.memory.row_miss_latency = 20;
+    let system = rvsim_core::soc::System::new(&config, "");
+    let mut cpu = Cpu::new(system, &config);
+    cpu.l1_i_cache.enabled = false;
+    cpu.l1_d_cache.enabled = false;
+    cpu.l2_cache.enabled = false;
+    cpu.l3_cache.enabled = false;
+
+    for i in 0..16u64 {
+        let paddr = rvsim_core::common::PhysAddr::new(0x8000_0000 + i * 64);
+        let _ = cpu.simulate_memory_access(paddr, AccessType::Read);
+    }
+
+    let traffic = &cpu.stats.mem_traffic;
+    assert_eq!(traffic.requests(), 16);
+    assert_eq!(traffic.bytes(), 16 * 64);
+    assert!((traffic.avg_latency() - 20.0).abs() < f64::EPSILON);
+    assert_eq!(traffic.p99_latency(), 20);
+}
+
+#[test]
+fn test_dram_traffic_skipped_on_cache_hit() {
+    let mut cpu = create_test_cpu();
+    cpu.l1_d_cache.enabled = true;
+    let paddr = rvsim_core::common::PhysAddr::new(0x8000_0000u64);
+    let _ = cpu.simulate_memory_access(paddr, AccessType::Read);
+    let after_miss = cpu.stats.mem_traffic.requests();
+    let _ = cpu.simulate_memory_access(paddr, AccessType::Read);
+    assert_eq!(cpu.stats.mem_traffic.requests(), after_miss);
+}
+
+/// Runs a load/store program and returns the loaded registers and stored words.
+fn run_load_store(backend: BackendType, safe_ram: bool) -> (Vec<u64>, Vec<u64>) {
+    let b = InstructionBuilder::new;
+    let program = [
+        b().sd(5, 6, 0).build(),
+        b().sw(5, 7, 8).build(),
+        b().sb(5, 6, 13).build(),
+        b().ld(18, 5, 0).build(),
+        b().lw(19, 5, 8).build(),
+        b().lb(20, 5, 13).build(),
+        b().lw(21, 5, 4).build(),
+        b().addi(17, 0, 93).build(),
+        b().ecall().build(),
+    ];
+
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.memory.safe_ram = safe_ram;
+    let base = config.system.ram_base;
+    let data = base + 0x2000;
+
+    let mut ctx = TestContext::with_config(&config).load_program(base, &program);
+    assert_eq!(ctx.sim.cpu.ram.is_safe(), safe_ram);
+    ctx.sim.write_reg(RegIdx::new(5), data);
+    ctx.sim.write_reg(RegIdx::new(6), 0xfedc_ba98_7654_32f0);
+    ctx.sim.write_reg(RegIdx::new(7), 0x8000_0001);
+    let mut sim = ctx.run_to_exit(10_000);
+    assert_eq!(sim.cpu.exit_code, Some(0), "{backend:?} safe_ram={safe_ram}");
+
+    let regs = (18..=21).map(|r| sim.cpu.regs.read(RegIdx::new(r))).collect();
+    let mem =
+        [0, 8].iter().map(|off| sim.cpu.bus.bus.read_u64(PhysAddr::new(data + off))).collect();
+    (regs, mem)
+}
+
+#[test]
+fn test_safe_ram_matches_fast_path() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let fast = run_load_store(backend, false);
+        let safe = run_load_store(backend, true);
+        assert_eq!(safe, fast, "{backend:?}");
+        assert_eq!(safe.0[0], 0xfedc_ba98_7654_32f0, "{backend:?}: ld");
+        assert_eq!(safe.0[1], 0xffff_ffff_8000_0001, "{backend:?}: lw sign-extends");
+        assert_eq!(safe.0[2], 0xffff_ffff_ffff_fff0, "{backend:?}: lb sign-extends");
+        assert_eq!(safe.1[1], 0x0000_f000_8000_0001, "{backend:?}: sb lands in memory");
+    }
+}
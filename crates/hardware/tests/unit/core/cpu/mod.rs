@@ -9,5 +9,17 @@ pub mod trap_handling;
 /// Tests for CPU execution and pipeline coordination.
 pub mod execution;
 
+/// Tests for interrupt masking and delivery.
+pub mod interrupts;
+
 /// Tests for memory access and cache simulation.
 pub mod memory;
+
+/// Tests for RAM fault injection and ECC.
+pub mod fault_injection;
+
+/// Tests for the physical memory access trace.
+pub mod access_trace;
+
+/// Tests for the recent-trap log.
+pub mod trap_log;
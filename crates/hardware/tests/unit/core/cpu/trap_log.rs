@@ -0,0 +1,118 @@
+//! # Trap Log Tests
+//!
+//! Verifies that `Cpu::recent_traps` records each trap taken with its cause,
+//! EPC, trap value and privilege modes, including a load page fault raised
+//! by a running program, and that it keeps only the most recent entries.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use rvsim_core::Simulator;
+use rvsim_core::common::constants::CAUSE_INTERRUPT_BIT;
+use rvsim_core::common::{PhysAddr, RegIdx, Trap};
+use rvsim_core::config::Config;
+use rvsim_core::core::Cpu;
+use rvsim_core::core::arch::csr;
+use rvsim_core::core::arch::mode::PrivilegeMode;
+use rvsim_core::core::cpu::TRAP_LOG_MAX;
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::isa::privileged::cause::{exception, interrupt};
+use rvsim_core::soc::System;
+
+/// Virtual address with no mapping in the test page table.
+const UNMAPPED: u64 = 0x4000_0000;
+
+fn create_test_cpu() -> Cpu {
+    let config = Config::default();
+    let mut cpu = Cpu::new(System::new(&config, ""), &config);
+    cpu.direct_mode = false;
+    cpu
+}
+
+#[test]
+fn load_page_fault_is_logged_with_faulting_address() {
+    let b = InstructionBuilder::new;
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut config = Config::default();
+        config.pipeline.backend = backend;
+        let base = config.system.ram_base;
+        let root = base + 0x10_000;
+        let handler = base + 0x100;
+
+        let mut sim = Simulator::new(System::new(&config, ""), &config);
+        let bus = &mut sim.cpu.bus.bus;
+        bus.write_u32(PhysAddr::new(base), b().ld(5, 6, 0).build());
+        bus.write_u32(PhysAddr::new(handler), b().jal(0, 0).build());
+        // Identity-map the gigapage holding RAM (V, R, W, X, A, D); the rest
+        // of the root table is empty.
+        let vpn2 = (base >> 30) & 0x1ff;
+        bus.write_u64(PhysAddr::new(root + 8 * vpn2), ((base >> 12) << 10) | 0xcf);
+
+        // One NAPOT region covering the whole address space, RWX.
+        sim.cpu.pmp.set_addr(0, u64::MAX >> 10);
+        sim.cpu.pmp.set_cfg(0, 0x1F);
+        sim.cpu.direct_mode = false;
+        sim.cpu.csrs.mtvec = handler;
+        sim.cpu.csrs.satp = (csr::SATP_MODE_SV39 << csr::SATP_MODE_SHIFT) | (root >> 12);
+        sim.cpu.privilege = PrivilegeMode::Supervisor;
+        sim.cpu.pc = base;
+        sim.write_reg(RegIdx::new(6), UNMAPPED);
+
+        for _ in 0..10_000 {
+            sim.tick().unwrap();
+            if !sim.cpu.recent_traps.is_empty() {
+                break;
+            }
+        }
+
+        let traps: Vec<_> = sim.cpu.recent_traps.iter().collect();
+        assert_eq!(traps.len(), 1, "{backend:?}: {traps:?}");
+        let t = traps[0];
+        assert_eq!(t.cause, exception::LOAD_PAGE_FAULT, "{backend:?}");
+        assert!(!t.is_interrupt());
+        assert_eq!(t.tval, UNMAPPED, "{backend:?}");
+        assert_eq!(t.epc, base, "{backend:?}");
+        assert_eq!(t.privilege, PrivilegeMode::Supervisor);
+        assert_eq!(t.handler, PrivilegeMode::Machine);
+        assert_eq!(sim.cpu.csrs.mtval, UNMAPPED);
+    }
+}
+
+#[test]
+fn interrupt_cause_includes_interrupt_bit() {
+    let mut cpu = create_test_cpu();
+    cpu.csrs.mideleg = 1 << (interrupt::SUPERVISOR_TIMER & !CAUSE_INTERRUPT_BIT);
+    cpu.privilege = PrivilegeMode::User;
+
+    cpu.trap(&Trap::SupervisorTimerInterrupt, 0x8000_1000);
+
+    let t = cpu.recent_traps.back().unwrap();
+    assert_eq!(t.cause, interrupt::SUPERVISOR_TIMER);
+    assert!(t.is_interrupt());
+    assert_eq!(t.epc, 0x8000_1000);
+    assert_eq!(t.tval, 0);
+    assert_eq!(t.privilege, PrivilegeMode::User);
+    assert_eq!(t.handler, PrivilegeMode::Supervisor);
+}
+
+#[test]
+fn log_keeps_only_the_most_recent_traps() {
+    let mut cpu = create_test_cpu();
+    for i in 0..TRAP_LOG_MAX as u64 + 10 {
+        cpu.trap(&Trap::StorePageFault(i), i * 4);
+    }
+
+    assert_eq!(cpu.recent_traps.len(), TRAP_LOG_MAX);
+    assert_eq!(cpu.recent_traps.front().unwrap().tval, 10);
+    assert_eq!(cpu.recent_traps.back().unwrap().tval, TRAP_LOG_MAX as u64 + 9);
+}
+
+#[test]
+fn direct_mode_syscalls_are_not_logged() {
+    let mut cpu = create_test_cpu();
+    cpu.direct_mode = true;
+    cpu.regs.write(RegIdx::new(17), 93);
+
+    cpu.trap(&Trap::EnvironmentCallFromMMode, cpu.pc);
+
+    assert_eq!(cpu.exit_code, Some(0));
+    assert!(cpu.recent_traps.is_empty());
+}
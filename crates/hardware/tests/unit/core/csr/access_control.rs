@@ -29,15 +29,26 @@ fn csr_default_all_zero() {
 fn csr_write_and_read_mstatus() {
     let mut csrs = Csrs::default();
     csrs.write(csr::MSTATUS, 0x0000_0000_000A_0000);
-    assert_eq!(csrs.read(csr::MSTATUS), 0x0000_0000_000A_0000);
+    // UXL/SXL are hardwired to RV64 whatever is written.
+    assert_eq!(csrs.read(csr::MSTATUS), 0x0000_0000_000A_0000 | csr::MSTATUS_DEFAULT_RV64);
 }
 
-/// Verifies that the `misa` (Machine ISA) register can be written to and read back correctly.
+/// Verifies that `misa` writes change only the writable extension bits.
 #[test]
 fn csr_write_and_read_misa() {
-    let mut csrs = Csrs::default();
-    csrs.write(csr::MISA, csr::MISA_DEFAULT_RV64IMAFDC);
-    assert_eq!(csrs.read(csr::MISA), csr::MISA_DEFAULT_RV64IMAFDC);
+    let mut csrs = Csrs {
+        misa: csr::MISA_DEFAULT_RV64IMAFDC,
+        misa_writable: csr::MISA_TOGGLEABLE,
+        ..Default::default()
+    };
+    csrs.write(csr::MISA, csr::MISA_DEFAULT_RV64IMAFDC & !csr::MISA_EXT_M);
+    assert_eq!(csrs.read(csr::MISA), csr::MISA_DEFAULT_RV64IMAFDC & !csr::MISA_EXT_M);
+    // XLEN and I are not writable.
+    csrs.write(csr::MISA, 0);
+    assert_eq!(
+        csrs.read(csr::MISA),
+        csr::MISA_XLEN_64 | csr::MISA_EXT_I | csr::MISA_EXT_S | csr::MISA_EXT_U
+    );
 }
 
 /// Verifies that the `mie` (Machine Interrupt Enable) register can be written to and read back correctly.
@@ -171,5 +182,6 @@ fn csr_clone() {
     let mut csrs = Csrs::default();
     csrs.write(csr::MSTATUS, 0xABCD);
     let cloned = csrs.clone();
-    assert_eq!(cloned.read(csr::MSTATUS), 0xABCD);
+    let expected = (0xABCD & csr::MSTATUS_WRITABLE) | csr::MSTATUS_DEFAULT_RV64;
+    assert_eq!(cloned.read(csr::MSTATUS), expected);
 }
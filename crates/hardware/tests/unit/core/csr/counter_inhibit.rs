@@ -0,0 +1,79 @@
+//! # Counter-Inhibit Tests
+//!
+//! Runs programs on both pipeline backends that set `mcountinhibit` and
+//! check that `IR` freezes `minstret` while `mcycle` keeps counting, that
+//! `CY` freezes `mcycle` while `minstret` keeps counting, and that the
+//! unimplemented `TM` bit reads as zero.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::Simulator;
+use rvsim_core::common::RegIdx;
+use rvsim_core::config::Config;
+use rvsim_core::core::arch::csr;
+use rvsim_core::core::pipeline::engine::BackendType;
+
+/// `addi x5, x5, 1`, repeated `n` times.
+fn work(n: usize) -> impl Iterator<Item = u32> {
+    std::iter::repeat_n(InstructionBuilder::new().addi(5, 5, 1).build(), n)
+}
+
+/// Runs `program` followed by an exit on `backend`, returning the simulator.
+fn run(backend: BackendType, program: &[u32]) -> Simulator {
+    let b = InstructionBuilder::new;
+    let exit = [b().addi(17, 0, 93).build(), b().ecall().build()];
+    let program: Vec<u32> = program.iter().chain(&exit).copied().collect();
+    TestContext::with_backend(backend)
+        .load_program(Config::default().system.ram_base, &program)
+        .run_to_exit(10_000)
+}
+
+fn reg(sim: &Simulator, r: u32) -> u64 {
+    sim.cpu.regs.read(RegIdx::new(r as u8))
+}
+
+#[test]
+fn inhibited_minstret_stops_while_mcycle_advances() {
+    let b = InstructionBuilder::new;
+    let (mcycle, minstret) = (csr::MCYCLE.as_u32(), csr::MINSTRET.as_u32());
+    let inhibit = csr::MCOUNTINHIBIT.as_u32();
+    let mut program = vec![
+        b().csrrwi(0, inhibit, csr::MCOUNTINHIBIT_IR as u32).build(),
+        b().csrrs(10, minstret, 0).build(),
+        b().csrrs(11, mcycle, 0).build(),
+    ];
+    program.extend(work(20));
+    program.extend([b().csrrs(12, minstret, 0).build(), b().csrrs(13, mcycle, 0).build()]);
+
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let sim = run(backend, &program);
+        assert_eq!(reg(&sim, 5), 20, "{backend:?}");
+        assert_eq!(reg(&sim, 12), reg(&sim, 10), "{backend:?}: minstret advanced");
+        assert!(reg(&sim, 13) > reg(&sim, 11), "{backend:?}: mcycle stopped");
+        assert!(sim.cpu.stats.instructions_retired > 20, "{backend:?}: stats still count");
+    }
+}
+
+#[test]
+fn inhibited_mcycle_stops_while_minstret_advances() {
+    let b = InstructionBuilder::new;
+    let (mcycle, minstret) = (csr::MCYCLE.as_u32(), csr::MINSTRET.as_u32());
+    let inhibit = csr::MCOUNTINHIBIT.as_u32();
+    // Also try to set TM (bit 1), which is not implemented.
+    let mut program = vec![
+        b().csrrwi(0, inhibit, 0b11).build(),
+        b().csrrs(14, inhibit, 0).build(),
+        b().csrrs(10, minstret, 0).build(),
+        b().csrrs(11, mcycle, 0).build(),
+    ];
+    program.extend(work(20));
+    program.extend([b().csrrs(12, minstret, 0).build(), b().csrrs(13, mcycle, 0).build()]);
+
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let sim = run(backend, &program);
+        assert_eq!(reg(&sim, 14), csr::MCOUNTINHIBIT_CY, "{backend:?}");
+        assert_eq!(reg(&sim, 13), reg(&sim, 11), "{backend:?}: mcycle advanced");
+        assert!(reg(&sim, 12) >= reg(&sim, 10) + 20, "{backend:?}: minstret stopped");
+        assert!(sim.cpu.stats.cycles > reg(&sim, 13), "{backend:?}");
+    }
+}
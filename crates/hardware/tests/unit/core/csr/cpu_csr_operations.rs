@@ -155,6 +155,59 @@ fn test_csr_write_sstatus_masks_properly() {
     assert_eq!(mstatus & mask, sstatus_value);
 }
 
+#[test]
+fn test_csr_mstatus_all_ones_clears_reserved_bits() {
+    let mut cpu = create_test_cpu();
+
+    cpu.csr_write(csr::MSTATUS, u64::MAX);
+
+    // Every WPRI and hardwired field reads back as its fixed value: UXL/SXL
+    // stay RV64, and SD reflects FS = Dirty.
+    let expected = csr::MSTATUS_WRITABLE | csr::MSTATUS_DEFAULT_RV64 | csr::MSTATUS_SD;
+    assert_eq!(cpu.csr_read(csr::MSTATUS), expected);
+    let sstatus_expected = (expected & csr::SSTATUS_VISIBLE) | csr::MSTATUS_SD;
+    assert_eq!(cpu.csr_read(csr::SSTATUS), sstatus_expected);
+
+    cpu.csr_write(csr::MSTATUS, 0);
+    assert_eq!(cpu.csr_read(csr::MSTATUS), csr::MSTATUS_DEFAULT_RV64);
+}
+
+#[test]
+fn test_csr_mstatus_mpp_is_legalized() {
+    let mut cpu = create_test_cpu();
+
+    for (mpp, legal) in [(0, 0), (1, 1), (2, 0), (3, 3)] {
+        cpu.csr_write(csr::MSTATUS, csr::MSTATUS_MIE | (mpp << csr::MSTATUS_MPP_SHIFT));
+        let mstatus = cpu.csr_read(csr::MSTATUS);
+        assert_eq!((mstatus >> csr::MSTATUS_MPP_SHIFT) & csr::MSTATUS_MPP_MASK, legal, "MPP={mpp}");
+        assert_ne!(mstatus & csr::MSTATUS_MIE, 0, "MPP={mpp}: other fields are kept");
+    }
+}
+
+#[test]
+fn test_csr_sstatus_write_touches_only_supervisor_fields() {
+    let mut cpu = create_test_cpu();
+    let machine_fields = csr::MSTATUS_MIE
+        | csr::MSTATUS_MPIE
+        | csr::MSTATUS_MPP
+        | csr::MSTATUS_MPRV
+        | csr::MSTATUS_TVM
+        | csr::MSTATUS_TW
+        | csr::MSTATUS_TSR;
+    cpu.csr_write(csr::MSTATUS, machine_fields);
+
+    cpu.csr_write(csr::SSTATUS, u64::MAX);
+    let mstatus = cpu.csr_read(csr::MSTATUS);
+    assert_eq!(
+        mstatus,
+        machine_fields | csr::SSTATUS_WRITABLE | csr::MSTATUS_DEFAULT_RV64 | csr::MSTATUS_SD
+    );
+
+    cpu.csr_write(csr::SSTATUS, 0);
+    assert_eq!(cpu.csr_read(csr::MSTATUS), machine_fields | csr::MSTATUS_DEFAULT_RV64);
+    assert_eq!(cpu.csr_read(csr::SSTATUS), csr::MSTATUS_DEFAULT_RV64 & csr::MSTATUS_UXL);
+}
+
 #[test]
 fn test_csr_sie_delegation() {
     let mut cpu = create_test_cpu();
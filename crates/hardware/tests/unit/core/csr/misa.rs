@@ -0,0 +1,169 @@
+//! # Writable `misa` Tests
+//!
+//! Runs machine-mode programs on both pipeline backends that toggle
+//! extensions through `misa` and check that:
+//!   - Clearing M makes a following `mul` trap as illegal, and setting it
+//!     again brings `mul` back.
+//!   - Clearing C makes compressed instructions illegal, but the write is
+//!     suppressed when the next instruction is not 4-byte aligned.
+//!   - Extensions the hart was not configured with cannot be turned on.
+
+use rvsim_core::Simulator;
+use rvsim_core::common::{PhysAddr, RegIdx};
+use rvsim_core::config::Config;
+use rvsim_core::core::arch::csr;
+use rvsim_core::core::arch::mode::PrivilegeMode;
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::isa::privileged::cause::exception;
+use rvsim_core::soc::System;
+
+const CSRRS: u32 = 0b010;
+const CSRRC: u32 = 0b011;
+
+/// `c.nop`.
+const C_NOP: u16 = 0x0001;
+/// `jal x0, 0` (spin).
+const SPIN: u32 = 0x0000_006f;
+/// `mul x7, x5, x6`.
+const MUL: u32 = (1 << 25) | (6 << 20) | (5 << 15) | (7 << 7) | 0x33;
+
+/// Encodes a SYSTEM CSR instruction on `misa`.
+const fn misa_inst(funct3: u32, rd: u32, rs1: u32) -> u32 {
+    (csr::MISA.as_u32() << 20) | (rs1 << 15) | (funct3 << 12) | (rd << 7) | 0x73
+}
+
+/// `addi rd, x0, imm`.
+const fn li(rd: u32, imm: u32) -> u32 {
+    (imm << 20) | (rd << 7) | 0x13
+}
+
+/// `lui rd, imm`.
+const fn lui(rd: u32, imm: u32) -> u32 {
+    (imm << 12) | (rd << 7) | 0x37
+}
+
+/// An instruction placed in RAM: 32-bit or compressed.
+enum Inst {
+    Word(u32),
+    Half(u16),
+}
+
+/// Lays out `program` from the start of RAM, with traps vectored to a spin
+/// loop, and runs it in M-mode on `backend` until `retired` instructions
+/// retire or a trap is taken.
+fn run(config: &Config, backend: BackendType, program: &[Inst], retired: u64) -> Simulator {
+    let mut config = config.clone();
+    config.pipeline.backend = backend;
+    let base = config.system.ram_base;
+    let handler = base + 0x100;
+    let mut sim = Simulator::new(System::new(&config, ""), &config);
+    let mut addr = base;
+    for inst in program {
+        match *inst {
+            Inst::Word(w) => {
+                sim.cpu.bus.bus.write_u32(PhysAddr::new(addr), w);
+                addr += 4;
+            }
+            Inst::Half(h) => {
+                sim.cpu.bus.bus.write_u16(PhysAddr::new(addr), h);
+                addr += 2;
+            }
+        }
+    }
+    sim.cpu.bus.bus.write_u32(PhysAddr::new(handler), SPIN);
+    sim.cpu.direct_mode = false;
+    sim.cpu.csrs.mtvec = handler;
+    sim.cpu.privilege = PrivilegeMode::Machine;
+    sim.cpu.pc = base;
+    sim.write_reg(RegIdx::new(5), 3);
+    sim.write_reg(RegIdx::new(6), 4);
+    for _ in 0..10_000 {
+        sim.tick().unwrap();
+        if sim.cpu.stats.instructions_retired >= retired || !sim.cpu.recent_traps.is_empty() {
+            return sim;
+        }
+    }
+    panic!("{backend:?}: program did not finish");
+}
+
+#[test]
+fn clearing_m_makes_mul_illegal() {
+    use Inst::Word;
+    let program = [Word(lui(5, 1)), Word(misa_inst(CSRRC, 0, 5)), Word(MUL), Word(SPIN)];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let sim = run(&Config::default(), backend, &program, 3);
+        let base = sim.cpu.csrs.mtvec - 0x100;
+
+        assert_eq!(sim.cpu.csrs.misa & csr::MISA_EXT_M, 0, "{backend:?}");
+        let t = sim.cpu.recent_traps.back().expect("mul should trap");
+        assert_eq!(t.cause, exception::ILLEGAL_INSTRUCTION, "{backend:?}");
+        assert_eq!(t.epc, base + 8, "{backend:?}");
+        assert_eq!(t.tval, u64::from(MUL), "{backend:?}");
+    }
+}
+
+#[test]
+fn setting_m_again_restores_mul() {
+    use Inst::Word;
+    let program = [
+        Word(lui(10, 1)),
+        Word(misa_inst(CSRRC, 0, 10)),
+        Word(misa_inst(CSRRS, 0, 10)),
+        Word(MUL),
+        Word(SPIN),
+    ];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let sim = run(&Config::default(), backend, &program, 4);
+
+        assert!(sim.cpu.recent_traps.is_empty(), "{backend:?}");
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(7)), 12, "{backend:?}");
+        assert_eq!(sim.cpu.csrs.misa, csr::MISA_DEFAULT_RV64IMAFDC, "{backend:?}");
+    }
+}
+
+#[test]
+fn clearing_c_makes_compressed_instructions_illegal() {
+    use Inst::{Half, Word};
+    let c = csr::MISA_EXT_C as u32;
+    let program = [Word(li(5, c)), Word(misa_inst(CSRRC, 0, 5)), Half(C_NOP), Half(C_NOP)];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let sim = run(&Config::default(), backend, &program, 3);
+        let base = sim.cpu.csrs.mtvec - 0x100;
+
+        assert_eq!(sim.cpu.csrs.misa & csr::MISA_EXT_C, 0, "{backend:?}");
+        let t = sim.cpu.recent_traps.back().expect("c.nop should trap");
+        assert_eq!(t.cause, exception::ILLEGAL_INSTRUCTION, "{backend:?}");
+        assert_eq!(t.epc, base + 8, "{backend:?}");
+    }
+}
+
+#[test]
+fn clearing_c_is_suppressed_before_a_misaligned_instruction() {
+    use Inst::{Half, Word};
+    let c = csr::MISA_EXT_C as u32;
+    // The CSR write sits at offset 6, so the next instruction is at 10.
+    let program =
+        [Word(li(5, c)), Half(C_NOP), Word(misa_inst(CSRRC, 0, 5)), Half(C_NOP), Word(SPIN)];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let sim = run(&Config::default(), backend, &program, 5);
+
+        assert!(sim.cpu.recent_traps.is_empty(), "{backend:?}");
+        assert_eq!(sim.cpu.csrs.misa, csr::MISA_DEFAULT_RV64IMAFDC, "{backend:?}");
+    }
+}
+
+#[test]
+fn unconfigured_extension_cannot_be_enabled() {
+    use Inst::Word;
+    let mut config = Config::default();
+    let without_m = csr::MISA_DEFAULT_RV64IMAFDC & !csr::MISA_EXT_M;
+    config.pipeline.misa_override = Some(format!("{without_m:x}"));
+    let program = [Word(lui(5, 1)), Word(misa_inst(CSRRS, 10, 5)), Word(MUL), Word(SPIN)];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let sim = run(&config, backend, &program, 3);
+
+        assert_eq!(sim.cpu.csrs.misa, without_m, "{backend:?}");
+        let t = sim.cpu.recent_traps.back().expect("mul should trap");
+        assert_eq!(t.cause, exception::ILLEGAL_INSTRUCTION, "{backend:?}");
+    }
+}
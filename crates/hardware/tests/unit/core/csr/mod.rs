@@ -27,3 +27,21 @@ pub mod trap_setup;
 /// This module verifies the CPU-level CSR operations including side effects
 /// like TLB flushes, interrupt inhibition, and MSTATUS/SSTATUS synchronization.
 pub mod cpu_csr_operations;
+
+/// Pipeline tests for the Zicsr instructions.
+///
+/// This module verifies read/write suppression for `rd=x0`, `rs1=x0` and
+/// `zimm=0`, and the 5-bit immediate operand of `CSRRWI`/`CSRRSI`/`CSRRCI`.
+pub mod zicsr;
+
+/// Pipeline tests for `mcountinhibit`.
+///
+/// This module verifies that the `CY` and `IR` bits independently freeze
+/// `mcycle` and `minstret` while the simulator's own statistics keep counting.
+pub mod counter_inhibit;
+
+/// Pipeline tests for writes to `misa`.
+///
+/// This module verifies that the toggleable extension bits can be cleared
+/// and set again, and that decode follows the live value.
+pub mod misa;
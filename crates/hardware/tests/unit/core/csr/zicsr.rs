@@ -0,0 +1,175 @@
+//! # Zicsr Instruction Tests
+//!
+//! Runs CSR instructions through both pipeline backends and checks the
+//! read/write suppression rules of Zicsr §2.8: set/clear forms with `rs1=x0`
+//! or `zimm=0` are pure reads, `CSRRW`/`CSRRWI` always write, and the
+//! immediate forms take their operand from the 5-bit `zimm` field rather
+//! than a register. A CSR read issued right behind a write to the same CSR
+//! must observe the new value even though the write is deferred to commit.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::Simulator;
+use rvsim_core::common::RegIdx;
+use rvsim_core::config::Config;
+use rvsim_core::core::arch::csr;
+use rvsim_core::core::pipeline::engine::BackendType;
+
+const A0: u32 = 10;
+const A1: u32 = 11;
+const A2: u32 = 12;
+const A3: u32 = 13;
+const A5: u32 = 15;
+
+/// `li a0, code; li a7, 93; ecall`
+fn exit(code: u32) -> [u32; 3] {
+    let b = InstructionBuilder::new;
+    [b().addi(A0, 0, code as i32).build(), b().addi(17, 0, 93).build(), b().ecall().build()]
+}
+
+/// Runs `program` to exit on `backend` after `setup`, returning the simulator.
+fn run(backend: BackendType, program: &[u32], setup: impl FnOnce(&mut Simulator)) -> Simulator {
+    run_with(backend, Config::default().pipeline.width, program, setup)
+}
+
+/// Like [`run`], with a pipeline of `width`.
+fn run_with(
+    backend: BackendType,
+    width: usize,
+    program: &[u32],
+    setup: impl FnOnce(&mut Simulator),
+) -> Simulator {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.pipeline.width = width;
+    let base = config.system.ram_base;
+    let mut ctx = TestContext::with_config(&config).load_program(base, program);
+    setup(&mut ctx.sim);
+    ctx.run_to_exit(10_000)
+}
+
+fn reg(sim: &Simulator, r: u32) -> u64 {
+    sim.cpu.regs.read(RegIdx::new(r as u8))
+}
+
+#[test]
+fn set_clear_with_zero_source_reads_read_only_csr() {
+    let b = InstructionBuilder::new;
+    let mhartid = csr::MHARTID.as_u32();
+    let program = [
+        b().csrrs(A1, mhartid, 0).build(),
+        b().csrrc(A2, mhartid, 0).build(),
+        b().csrrsi(A3, mhartid, 0).build(),
+        b().csrrci(0, mhartid, 0).build(),
+        exit(7)[0],
+        exit(7)[1],
+        exit(7)[2],
+    ];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let sim = run(backend, &program, |s| {
+            s.write_reg(RegIdx::new(A1 as u8), 0xAA);
+            s.write_reg(RegIdx::new(A2 as u8), 0xAA);
+            s.write_reg(RegIdx::new(A3 as u8), 0xAA);
+        });
+        assert_eq!(sim.cpu.exit_code, Some(7), "{backend:?}: pure reads must not trap");
+        assert_eq!(reg(&sim, A1), 0, "{backend:?}");
+        assert_eq!(reg(&sim, A2), 0, "{backend:?}");
+        assert_eq!(reg(&sim, A3), 0, "{backend:?}");
+    }
+}
+
+#[test]
+fn writing_forms_trap_on_read_only_csr() {
+    let b = InstructionBuilder::new;
+    let mhartid = csr::MHARTID.as_u32();
+    let cases = [
+        // csrrw always writes, even with rd=x0.
+        b().csrrw(0, mhartid, A0).build(),
+        // A non-zero rs1 index writes even when its value is zero.
+        b().csrrs(A1, mhartid, A5).build(),
+        b().csrrc(A1, mhartid, A5).build(),
+        b().csrrwi(A1, mhartid, 0).build(),
+        b().csrrsi(A1, mhartid, 1).build(),
+        b().csrrci(0, mhartid, 2).build(),
+    ];
+    for inst in cases {
+        let program = [inst, exit(7)[0], exit(7)[1], exit(7)[2]];
+        for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+            let sim = run(backend, &program, |_| {});
+            assert_eq!(sim.cpu.exit_code, Some(1), "{backend:?} {inst:#010x}: expected trap");
+        }
+    }
+}
+
+#[test]
+fn immediate_forms_use_zimm() {
+    let b = InstructionBuilder::new;
+    let mscratch = csr::MSCRATCH.as_u32();
+    let program = [
+        b().csrrwi(A1, mscratch, 31).build(),
+        b().csrrci(A2, mscratch, 0b10101).build(),
+        b().csrrsi(A3, mscratch, 0b00001).build(),
+        exit(0)[0],
+        exit(0)[1],
+        exit(0)[2],
+    ];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        // Poison the registers the zimm fields alias so a register read shows up.
+        let sim = run(backend, &program, |s| {
+            for r in [1_u8, 21, 31] {
+                s.write_reg(RegIdx::new(r), u64::MAX);
+            }
+            s.cpu.csrs.mscratch = 0x100;
+        });
+        assert_eq!(reg(&sim, A1), 0x100, "{backend:?}");
+        assert_eq!(reg(&sim, A2), 31, "{backend:?}");
+        assert_eq!(reg(&sim, A3), 0b01010, "{backend:?}");
+        assert_eq!(sim.cpu.csrs.mscratch, 0b01011, "{backend:?}");
+    }
+}
+
+#[test]
+fn csrrw_with_rd_x0_only_writes() {
+    let b = InstructionBuilder::new;
+    let mscratch = csr::MSCRATCH.as_u32();
+    let program = [
+        b().csrrw(0, mscratch, A5).build(),
+        b().csrrs(A1, mscratch, 0).build(),
+        b().csrrwi(0, mscratch, 3).build(),
+        b().csrrs(A2, mscratch, 0).build(),
+        exit(0)[0],
+        exit(0)[1],
+        exit(0)[2],
+    ];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let sim = run(backend, &program, |s| {
+            s.write_reg(RegIdx::new(A5 as u8), 0xDEAD_BEEF);
+            s.cpu.csrs.mscratch = 0x1234;
+        });
+        assert_eq!(reg(&sim, 0), 0, "{backend:?}");
+        assert_eq!(reg(&sim, A1), 0xDEAD_BEEF, "{backend:?}");
+        assert_eq!(reg(&sim, A2), 3, "{backend:?}");
+    }
+}
+
+#[test]
+fn csr_read_sees_older_write_in_same_group() {
+    let b = InstructionBuilder::new;
+    let sscratch = csr::SSCRATCH.as_u32();
+    let program = [
+        b().csrrw(0, sscratch, A5).build(),
+        b().csrrs(A1, sscratch, 0).build(),
+        exit(0)[0],
+        exit(0)[1],
+        exit(0)[2],
+    ];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        for width in [1, 2, 4] {
+            let sim = run_with(backend, width, &program, |s| {
+                s.write_reg(RegIdx::new(A5 as u8), 0x5EED);
+                s.cpu.csrs.sscratch = 0x1234;
+            });
+            assert_eq!(reg(&sim, A1), 0x5EED, "{backend:?} width={width}");
+        }
+    }
+}
@@ -0,0 +1,161 @@
+//! Cache-Block Operation (Zicbom/Zicboz) Tests.
+//!
+//! Runs `cbo.*` instructions through both backends with the data caches
+//! enabled and checks the effect on memory and on L1D/L2: `cbo.zero` clears
+//! exactly one 64-byte block, `cbo.flush`/`cbo.inval` drop the line, and
+//! `cbo.clean` keeps it but clears its dirty bit. Below M-mode each
+//! operation is gated by its `menvcfg` enable.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::Simulator;
+use rvsim_core::common::{PhysAddr, RegIdx};
+use rvsim_core::config::Config;
+use rvsim_core::core::arch::csr;
+use rvsim_core::core::arch::mode::PrivilegeMode;
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::isa::disasm::disassemble;
+
+const DATA_OFFSET: u64 = 0x1000;
+const BLOCK: u64 = 64;
+const FILL: u64 = 0xA5A5_A5A5_A5A5_A5A5;
+
+const CBO_INVAL: u32 = 0;
+const CBO_CLEAN: u32 = 1;
+const CBO_FLUSH: u32 = 2;
+const CBO_ZERO: u32 = 4;
+
+/// Encodes `cbo.<op> (rs1)`.
+fn cbo(op: u32, rs1: u32) -> u32 {
+    (op << 20) | (rs1 << 15) | (0b010 << 12) | 0x0F
+}
+
+/// Runs `program` then exits, with `x14` pointing `x14_offset` bytes into the
+/// data area and the surrounding 256 bytes filled with [`FILL`].
+fn run(
+    backend: BackendType,
+    program: &[u32],
+    x14_offset: u64,
+    setup: impl FnOnce(&mut Simulator),
+) -> Simulator {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.pipeline.width = 4;
+    config.cache.l1_d.enabled = true;
+    config.cache.l2.enabled = true;
+    let base = config.system.ram_base;
+    let data = base + DATA_OFFSET;
+
+    let b = InstructionBuilder::new;
+    let mut program = program.to_vec();
+    program.extend([b().addi(10, 0, 0).build(), b().addi(17, 0, 93).build(), b().ecall().build()]);
+    let mut ctx = TestContext::with_config(&config).load_program(base, &program);
+    for offset in (0..4 * BLOCK).step_by(8) {
+        ctx.sim.cpu.bus.bus.write_u64(PhysAddr::new(data + offset), FILL);
+    }
+    ctx.sim.write_reg(RegIdx::new(14), data + x14_offset);
+    ctx.sim.write_reg(RegIdx::new(15), 0x1234);
+    setup(&mut ctx.sim);
+    ctx.run_to_exit(10_000)
+}
+
+fn data_addr(offset: u64) -> u64 {
+    Config::default().system.ram_base + DATA_OFFSET + offset
+}
+
+#[test]
+fn cbo_zero_clears_one_block() {
+    let b = InstructionBuilder::new;
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        // x14 points into the middle of the second block; the load after
+        // cbo.zero must observe the zeroed data.
+        let program = [cbo(CBO_ZERO, 14), b().ld(16, 14, -5).build()];
+        let mut sim = run(backend, &program, BLOCK + 0x15, |s| {
+            s.write_reg(RegIdx::new(16), 0xFFFF);
+        });
+        assert_eq!(sim.cpu.exit_code, Some(0), "{backend:?}");
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(16)), 0, "{backend:?}");
+        for offset in (0..4 * BLOCK).step_by(8) {
+            let val = sim.cpu.bus.bus.read_u64(PhysAddr::new(data_addr(offset)));
+            let expected = if (BLOCK..2 * BLOCK).contains(&offset) { 0 } else { FILL };
+            assert_eq!(val, expected, "{backend:?} offset {offset:#x}");
+        }
+    }
+}
+
+#[test]
+fn cbo_flush_evicts_dirty_line() {
+    let b = InstructionBuilder::new;
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let store = b().sd(14, 15, 0).build();
+        let kept = run(backend, &[store], 0, |_| {});
+        assert!(kept.cpu.l1_d_cache.contains(data_addr(0)), "{backend:?}: store allocates");
+
+        let mut sim = run(backend, &[store, cbo(CBO_FLUSH, 14)], 8, |_| {});
+        assert!(!sim.cpu.l1_d_cache.contains(data_addr(0)), "{backend:?}");
+        assert!(!sim.cpu.l2_cache.contains(data_addr(0)), "{backend:?}");
+        let val = sim.cpu.bus.bus.read_u64(PhysAddr::new(data_addr(8)));
+        assert_eq!(val, 0x1234, "{backend:?}: flushed data reaches memory");
+    }
+}
+
+#[test]
+fn cbo_inval_drops_line() {
+    let b = InstructionBuilder::new;
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let sim = run(backend, &[b().ld(16, 14, 0).build(), cbo(CBO_INVAL, 14)], 0, |_| {});
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(16)), FILL);
+        assert!(!sim.cpu.l1_d_cache.contains(data_addr(0)), "{backend:?}");
+    }
+}
+
+#[test]
+fn cbo_clean_keeps_line_but_clears_dirty() {
+    let b = InstructionBuilder::new;
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut sim = run(backend, &[b().sd(14, 15, 0).build(), cbo(CBO_CLEAN, 14)], 0, |_| {});
+        assert!(sim.cpu.l1_d_cache.contains(data_addr(0)), "{backend:?}");
+        assert!(!sim.cpu.l1_d_cache.clean_line(data_addr(0)), "{backend:?}: line is clean");
+    }
+}
+
+#[test]
+fn cbo_below_machine_mode_requires_menvcfg_enable() {
+    let cases = [
+        (CBO_ZERO, csr::MENVCFG_CBZE),
+        (CBO_FLUSH, csr::MENVCFG_CBCFE),
+        (CBO_CLEAN, csr::MENVCFG_CBCFE),
+        (CBO_INVAL, csr::MENVCFG_CBIE),
+    ];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        for (op, enable) in cases {
+            let denied = run(backend, &[cbo(op, 14)], 0, |s| {
+                s.cpu.privilege = PrivilegeMode::Supervisor;
+                s.cpu.csrs.menvcfg = !enable;
+            });
+            assert_eq!(denied.cpu.exit_code, Some(1), "{backend:?} op {op}: illegal instruction");
+
+            let allowed = run(backend, &[cbo(op, 14)], 0, |s| {
+                s.cpu.privilege = PrivilegeMode::Supervisor;
+                s.cpu.csrs.menvcfg = enable;
+            });
+            assert_eq!(allowed.cpu.exit_code, Some(0), "{backend:?} op {op}");
+        }
+    }
+}
+
+#[test]
+fn cbo_with_nonzero_rd_is_illegal() {
+    let inst = cbo(CBO_ZERO, 14) | (5 << 7);
+    let mut sim = run(BackendType::InOrder, &[inst], 0, |_| {});
+    assert_eq!(sim.cpu.exit_code, Some(1));
+    assert_eq!(sim.cpu.bus.bus.read_u64(PhysAddr::new(data_addr(0))), FILL);
+}
+
+#[test]
+fn cbo_disassembles() {
+    assert_eq!(disassemble(cbo(CBO_ZERO, 10)), "cbo.zero (a0)");
+    assert_eq!(disassemble(cbo(CBO_FLUSH, 11)), "cbo.flush (a1)");
+    assert_eq!(disassemble(cbo(CBO_CLEAN, 12)), "cbo.clean (a2)");
+    assert_eq!(disassemble(cbo(CBO_INVAL, 13)), "cbo.inval (a3)");
+}
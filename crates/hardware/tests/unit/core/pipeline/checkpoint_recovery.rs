@@ -0,0 +1,70 @@
+//! Checkpoint Recovery Tests.
+//!
+//! Runs a branchy loop behind a worst-case divide with a 256-entry ROB, so
+//! every misprediction leaves older instructions in flight. With checkpoints
+//! each recovery restores the branch's snapshot; without them every recovery
+//! walks the surviving ROB entries. Both must compute the same result.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::Simulator;
+use rvsim_core::common::RegIdx;
+use rvsim_core::config::Config;
+use rvsim_core::core::pipeline::engine::BackendType;
+
+/// Runs 200 iterations of a loop whose inner branch alternates direction,
+/// with `checkpoint_count` checkpoint slots.
+fn run(backend: BackendType, checkpoint_count: usize) -> Simulator {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.pipeline.width = 2;
+    config.pipeline.rob_size = 256;
+    config.pipeline.prf_gpr_size = 32 + 256;
+    config.pipeline.prf_fpr_size = 32 + 256;
+    config.pipeline.checkpoint_count = checkpoint_count;
+    let base = config.system.ram_base;
+
+    let b = InstructionBuilder::new;
+    let program = [
+        b().addi(8, 0, 200).build(),
+        // loop: u64::MAX / 1 is the divider's worst case.
+        b().divu(5, 14, 15).build(),
+        b().andi(9, 8, 1).build(),
+        b().beq(9, 0, 8).build(),
+        b().addi(10, 10, 1).build(),
+        b().addi(11, 11, 2).build(),
+        b().addi(8, 8, -1).build(),
+        b().bne(8, 0, -24).build(),
+        b().addi(17, 0, 93).build(),
+        b().ecall().build(),
+    ];
+    let mut ctx = TestContext::with_config(&config).load_program(base, &program);
+    ctx.sim.write_reg(RegIdx::new(14), u64::MAX);
+    ctx.sim.write_reg(RegIdx::new(15), 1);
+    ctx.run_to_exit(1_000_000)
+}
+
+#[test]
+fn checkpoints_replace_rob_walks_on_misprediction() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let walked = run(backend, 0);
+        let restored = run(backend, 32);
+        let (w, r) = (&walked.cpu.stats, &restored.cpu.stats);
+
+        for sim in [&walked, &restored] {
+            assert_eq!(sim.cpu.regs.read(RegIdx::new(10)), 100, "{backend:?}");
+            assert_eq!(sim.cpu.regs.read(RegIdx::new(11)), 400, "{backend:?}");
+        }
+        assert_eq!(w.instructions_retired, r.instructions_retired, "{backend:?}");
+
+        assert_eq!(w.checkpoint_restores, 0, "{backend:?}");
+        assert!(w.recovery_walk_entries >= w.flushes_branch, "{backend:?}");
+        assert!(r.checkpoint_restores >= 100, "{backend:?}");
+        assert!(
+            r.recovery_walk_entries * 10 < w.recovery_walk_entries,
+            "{backend:?}: {} vs {} entries walked",
+            r.recovery_walk_entries,
+            w.recovery_walk_entries
+        );
+    }
+}
@@ -0,0 +1,156 @@
+//! Multiply/Divide Latency Tests.
+//!
+//! Compares cycle counts of small programs on both backends. Chains of
+//! dependent multiplies and divides pay the unit's latency at every link
+//! (fixed for the pipelined multiplier, operand-dependent for the divider),
+//! while independent adds issued behind a divide finish while it is still
+//! running. An `fdiv` holds back a dependent `fadd` for the latency set in
+//! `pipeline.latencies`, and back-to-back divides issue one throughput
+//! interval apart.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::Simulator;
+use rvsim_core::common::RegIdx;
+use rvsim_core::config::Config;
+use rvsim_core::core::pipeline::backend::o3::fu_pool::{OpClass, OpTiming};
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::isa::encode::r_type;
+
+/// Encodes a double-precision OP-FP instruction with dynamic rounding.
+fn fp_d(funct7: u32, rd: u8, rs1: u8, rs2: u8) -> u32 {
+    r_type(0x53, RegIdx::new(rd), 0b111, RegIdx::new(rs1), RegIdx::new(rs2), funct7)
+}
+
+fn fadd_d(rd: u8, rs1: u8, rs2: u8) -> u32 {
+    fp_d(0b000_0001, rd, rs1, rs2)
+}
+
+fn fdiv_d(rd: u8, rs1: u8, rs2: u8) -> u32 {
+    fp_d(0b000_1101, rd, rs1, rs2)
+}
+
+/// Runs `program` then exits on `backend`, returning the simulator.
+fn run(backend: BackendType, program: &[u32]) -> Simulator {
+    run_with(backend, program, |_| {})
+}
+
+/// Like [`run`], with `configure` applied to the default config first.
+fn run_with(
+    backend: BackendType,
+    program: &[u32],
+    configure: impl FnOnce(&mut Config),
+) -> Simulator {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.pipeline.width = 4;
+    configure(&mut config);
+    let base = config.system.ram_base;
+
+    let b = InstructionBuilder::new;
+    let mut program = program.to_vec();
+    program.extend([b().addi(17, 0, 93).build(), b().ecall().build()]);
+    let mut ctx = TestContext::with_config(&config).load_program(base, &program);
+    for (r, v) in [(10, 0), (12, 100_000), (13, 5), (14, u64::MAX), (15, 1)] {
+        ctx.sim.write_reg(RegIdx::new(r), v);
+    }
+    ctx.sim.write_freg(RegIdx::new(1), 10.0_f64.to_bits());
+    ctx.sim.write_freg(RegIdx::new(2), 4.0_f64.to_bits());
+    ctx.run_to_exit(10_000)
+}
+
+#[test]
+fn dependent_multiply_chain_pays_pipelined_latency() {
+    let b = InstructionBuilder::new;
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let muls = run(
+            backend,
+            &[b().mul(5, 13, 13).build(), b().mul(5, 5, 13).build(), b().mul(5, 5, 13).build()],
+        );
+        let adds = run(
+            backend,
+            &[b().add(5, 13, 13).build(), b().add(5, 5, 13).build(), b().add(5, 5, 13).build()],
+        );
+        assert_eq!(muls.cpu.regs.read(RegIdx::new(5)), 625, "{backend:?}");
+        // The default multiplier takes 3 cycles: two more than an add per link.
+        assert_eq!(muls.cpu.stats.cycles - adds.cpu.stats.cycles, 3 * 2, "{backend:?}");
+    }
+}
+
+#[test]
+fn dependent_divide_chain_pays_each_latency() {
+    let b = InstructionBuilder::new;
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        // 100000 / 5 / 5 / 5: quotients of 15, 13 and 10 bits take 3 + ceil(bits / 2)
+        // cycles on the default radix-4 divider, i.e. 11, 10 and 8 cycles.
+        let divs = run(
+            backend,
+            &[b().div(5, 12, 13).build(), b().div(5, 5, 13).build(), b().div(5, 5, 13).build()],
+        );
+        let adds = run(
+            backend,
+            &[b().add(5, 12, 13).build(), b().add(5, 5, 13).build(), b().add(5, 5, 13).build()],
+        );
+        assert_eq!(divs.cpu.regs.read(RegIdx::new(5)), 800, "{backend:?}");
+        let extra = divs.cpu.stats.cycles - adds.cpu.stats.cycles;
+        assert_eq!(extra, (11 - 1) + (10 - 1) + (8 - 1), "{backend:?}");
+    }
+}
+
+#[test]
+fn independent_add_completes_alongside_divide() {
+    let b = InstructionBuilder::new;
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        // u64::MAX / 1 is the divider's worst case: 35 cycles.
+        let alone = run(backend, &[b().divu(5, 14, 15).build()]);
+        let adds: Vec<u32> = (6..10).map(|rd| b().addi(rd, 10, 1).build()).collect();
+        let with_adds = run(backend, &[&[b().divu(5, 14, 15).build()], adds.as_slice()].concat());
+        assert_eq!(with_adds.cpu.regs.read(RegIdx::new(5)), u64::MAX, "{backend:?}");
+        assert_eq!(with_adds.cpu.regs.read(RegIdx::new(9)), 1, "{backend:?}");
+        // The adds execute under the divide and retire in the cycle after it.
+        let extra = with_adds.cpu.stats.cycles - alone.cpu.stats.cycles;
+        assert!(extra <= 1, "{backend:?}: adds added {extra} cycles");
+    }
+}
+
+/// Sets the timing of FP divides.
+fn fdiv_timing(latency: u64, throughput: u64) -> impl FnOnce(&mut Config) {
+    move |c: &mut Config| {
+        let _ = c.pipeline.latencies.insert(OpClass::FpDiv, OpTiming { latency, throughput });
+    }
+}
+
+#[test]
+fn dependent_fadd_waits_for_configured_fdiv_latency() {
+    // fdiv.d f3, f1, f2 feeding fadd.d f4, f3, f1, against the same chain
+    // with an fadd in place of the divide (4 cycles on the default FP adder).
+    let chain = [fdiv_d(3, 1, 2), fadd_d(4, 3, 1)];
+    let baseline = [fadd_d(3, 1, 2), fadd_d(4, 3, 1)];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let adds = run(backend, &baseline);
+        for latency in [13, 30] {
+            let sim = run_with(backend, &chain, fdiv_timing(latency, latency));
+            assert_eq!(sim.cpu.regs.read_f(RegIdx::new(4)), 12.5_f64.to_bits(), "{backend:?}");
+            let extra = sim.cpu.stats.cycles - adds.cpu.stats.cycles;
+            assert_eq!(extra, latency - 4, "{backend:?}: fdiv latency {latency}");
+        }
+        // Without an entry the divide takes the unit's 21 cycles.
+        let default = run(backend, &chain);
+        assert_eq!(default.cpu.stats.cycles - adds.cpu.stats.cycles, 21 - 4, "{backend:?}");
+    }
+}
+
+#[test]
+fn back_to_back_fdivs_issue_one_throughput_apart() {
+    // Two independent divides on the single FP divide/sqrt unit.
+    let one = [fdiv_d(3, 1, 2)];
+    let two = [fdiv_d(3, 1, 2), fdiv_d(4, 2, 1)];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        for throughput in [1, 4, 13] {
+            let single = run_with(backend, &one, fdiv_timing(13, throughput));
+            let pair = run_with(backend, &two, fdiv_timing(13, throughput));
+            let extra = pair.cpu.stats.cycles - single.cpu.stats.cycles;
+            assert_eq!(extra, throughput, "{backend:?}: throughput {throughput}");
+        }
+    }
+}
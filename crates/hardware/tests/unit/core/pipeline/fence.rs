@@ -0,0 +1,123 @@
+//! FENCE and FENCE.I Tests.
+//!
+//! A FENCE whose predecessor set includes memory must drain the store buffer
+//! when it commits, rather than letting older stores trickle out afterwards.
+//!
+//! A store patches the instruction immediately after a `fence.i`. By the
+//! time the store commits that instruction has already been fetched, so the
+//! fence must drain the store buffer, invalidate the L1 I-cache and re-fetch
+//! behind itself for the new encoding to execute. The same holds JIT-style,
+//! for a routine that already ran (and sits in the I-cache) before it is
+//! patched and called again.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::Simulator;
+use rvsim_core::common::{PhysAddr, RegIdx};
+use rvsim_core::config::Config;
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::soc::System;
+
+/// `fence.i`.
+const FENCE_I: u32 = 0x0000_100F;
+
+/// `fence rw, rw`.
+const FENCE_RW_RW: u32 = 0x0330_000F;
+
+/// Runs the self-modifying program on `backend` and returns the simulator
+/// once it exits.
+fn run(backend: BackendType, icache: bool) -> Simulator {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.pipeline.width = 2;
+    config.cache.l1_i.enabled = icache;
+    let base = config.system.ram_base;
+
+    let b = InstructionBuilder::new;
+    let program = [
+        b().sw(10, 6, 8).build(),
+        FENCE_I,
+        // Patched to `addi x7, x0, 42` by the store above.
+        b().addi(7, 0, 1).build(),
+        b().addi(17, 0, 93).build(),
+        b().ecall().build(),
+    ];
+    let mut ctx = TestContext::with_config(&config).load_program(base, &program);
+    ctx.sim.write_reg(RegIdx::new(6), u64::from(b().addi(7, 0, 42).build()));
+    ctx.sim.write_reg(RegIdx::new(10), base);
+    ctx.run_to_exit(10_000)
+}
+
+#[test]
+fn fence_i_executes_patched_instruction() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        for icache in [false, true] {
+            let sim = run(backend, icache);
+            assert_eq!(sim.cpu.regs.read(RegIdx::new(7)), 42, "{backend:?} icache={icache}");
+        }
+    }
+}
+
+#[test]
+fn fence_i_executes_patched_routine_after_jump() {
+    let b = InstructionBuilder::new;
+    let program = [
+        (0x00, b().jal(1, 0x48).build()),
+        // Patch the routine's first instruction to `addi x7, x0, 42`.
+        (0x04, b().sw(10, 6, 0x48).build()),
+        (0x08, FENCE_I),
+        (0x0C, b().jal(1, 0x3C).build()),
+        (0x10, b().addi(17, 0, 93).build()),
+        (0x14, b().ecall().build()),
+        // Routine: returns 1 in x7 until patched.
+        (0x48, b().addi(7, 0, 1).build()),
+        (0x4C, b().jalr(0, 1, 0).build()),
+    ];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        for icache in [false, true] {
+            let mut config = Config::default();
+            config.pipeline.backend = backend;
+            config.pipeline.width = 2;
+            config.cache.l1_i.enabled = icache;
+            let base = config.system.ram_base;
+
+            let mut ctx = TestContext::with_config(&config);
+            for (offset, inst) in program {
+                ctx.sim.cpu.bus.bus.write_u32(PhysAddr::new(base + offset), inst);
+            }
+            ctx.sim.cpu.pc = base;
+            ctx.sim.write_reg(RegIdx::new(6), u64::from(b().addi(7, 0, 42).build()));
+            ctx.sim.write_reg(RegIdx::new(10), base);
+            let sim = ctx.run_to_exit(10_000);
+            assert_eq!(sim.cpu.regs.read(RegIdx::new(7)), 42, "{backend:?} icache={icache}");
+        }
+    }
+}
+
+#[test]
+fn fence_drains_store_buffer_at_commit() {
+    let b = InstructionBuilder::new;
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut config = Config::default();
+        config.pipeline.backend = backend;
+        config.pipeline.width = 4;
+        let base = config.system.ram_base;
+
+        let mut program: Vec<u32> = (0..4).map(|i| b().sw(10, 6, 0x100 + 8 * i).build()).collect();
+        program.extend([FENCE_RW_RW, b().jal(0, 0).build()]);
+        let mut sim = Simulator::new(System::new(&config, ""), &config);
+        for (i, inst) in program.into_iter().enumerate() {
+            sim.cpu.bus.bus.write_u32(PhysAddr::new(base + 4 * i as u64), inst);
+        }
+        sim.cpu.pc = base;
+        sim.write_reg(RegIdx::new(6), 0x1234_5678);
+        sim.write_reg(RegIdx::new(10), base);
+        while sim.cpu.stats.instructions_retired < 5 {
+            sim.tick().unwrap();
+        }
+        for i in 0..4 {
+            let addr = PhysAddr::new(base + 0x100 + 8 * i);
+            assert_eq!(sim.cpu.bus.bus.read_u32(addr), 0x1234_5678, "{backend:?} store {i}");
+        }
+    }
+}
@@ -0,0 +1,211 @@
+//! Decoupled Fetch Tests — Fetch Target Queue and Fetch Buffer.
+//!
+//! Drives the frontend on its own, in front of an in-order engine whose
+//! rename bandwidth is switched off on alternate cycles. Fetch must keep
+//! filling the fetch buffer while decode is stalled, the FTQ must keep
+//! accepting predicted blocks while the I-cache misses, and a flush must
+//! empty both queues. A fetch width above the issue width builds up the
+//! buffer even without a stall, while fetch blocks still end at the line.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use rvsim_core::common::{CsrAddr, PhysAddr};
+use rvsim_core::config::Config;
+use rvsim_core::core::Cpu;
+use rvsim_core::core::pipeline::backend::inorder::InOrderEngine;
+use rvsim_core::core::pipeline::engine::ExecutionEngine;
+use rvsim_core::core::pipeline::frontend::Frontend;
+use rvsim_core::core::pipeline::latches::RenameIssueEntry;
+use rvsim_core::core::pipeline::rob::Rob;
+use rvsim_core::core::pipeline::scoreboard::Scoreboard;
+use rvsim_core::core::pipeline::store_buffer::StoreBuffer;
+use rvsim_core::soc::System;
+
+/// An in-order engine whose rename bandwidth is gated by `open`.
+struct Throttled {
+    inner: InOrderEngine,
+    open: bool,
+}
+
+impl ExecutionEngine for Throttled {
+    fn tick(&mut self, cpu: &mut Cpu, rename_output: &mut Vec<RenameIssueEntry>) {
+        self.inner.tick(cpu, rename_output);
+    }
+    fn can_accept(&self) -> usize {
+        if self.open { self.inner.can_accept() } else { 0 }
+    }
+    fn flush(&mut self, cpu: &mut Cpu) {
+        self.inner.flush(cpu);
+    }
+    fn read_csr_speculative(&self, cpu: &Cpu, addr: CsrAddr) -> u64 {
+        self.inner.read_csr_speculative(cpu, addr)
+    }
+    fn scoreboard(&self) -> &Scoreboard {
+        self.inner.scoreboard()
+    }
+    fn scoreboard_mut(&mut self) -> &mut Scoreboard {
+        self.inner.scoreboard_mut()
+    }
+    fn rob(&self) -> &Rob {
+        self.inner.rob()
+    }
+    fn rob_mut(&mut self) -> &mut Rob {
+        self.inner.rob_mut()
+    }
+    fn store_buffer(&self) -> &StoreBuffer {
+        self.inner.store_buffer()
+    }
+    fn store_buffer_mut(&mut self) -> &mut StoreBuffer {
+        self.inner.store_buffer_mut()
+    }
+}
+
+/// Builds a width-4 CPU running a long run of independent `addi`s.
+fn setup(icache: bool) -> (Cpu, Frontend<Throttled>, Throttled) {
+    let mut config = Config::default();
+    config.pipeline.width = 4;
+    config.cache.l1_i.enabled = icache;
+    setup_with(&config)
+}
+
+/// Like [`setup`], with the given configuration.
+fn setup_with(config: &Config) -> (Cpu, Frontend<Throttled>, Throttled) {
+    let base = config.system.ram_base;
+
+    let mut cpu = Cpu::new(System::new(config, ""), config);
+    for i in 0..256 {
+        let inst = InstructionBuilder::new().addi(5 + (i % 8), 0, i as i32).build();
+        cpu.bus.bus.write_u32(PhysAddr::new(base + 4 * u64::from(i)), inst);
+    }
+    cpu.pc = base;
+    let engine = Throttled { inner: InOrderEngine::new(config), open: true };
+    (cpu, Frontend::new(config), engine)
+}
+
+#[test]
+fn fetch_fills_buffer_while_decode_stalls() {
+    let (mut cpu, mut frontend, mut engine) = setup(false);
+    let mut renamed = Vec::new();
+    for cycle in 0..16 {
+        engine.open = cycle % 2 == 0;
+        let mut out = Vec::new();
+        frontend.tick(&mut cpu, &mut engine, &mut out);
+        renamed.extend(out.iter().map(|e| e.pc));
+    }
+
+    // Decode drains 4 instructions every other cycle while fetch delivers 4
+    // every cycle, so the buffer fills up to its capacity.
+    assert_eq!(frontend.fetch_buffer.len(), frontend.fetch_buffer_size());
+    assert_eq!(cpu.stats.fetch_buffer_peak, 16);
+    assert!(cpu.stats.fetch_buffer_full_cycles > 0);
+    assert!(cpu.stats.fetch_buffer_occupancy > 0);
+
+    // Everything renamed so far is the program in order.
+    let base = Config::default().system.ram_base;
+    assert!(!renamed.is_empty());
+    for (i, pc) in renamed.iter().enumerate() {
+        assert_eq!(*pc, base + 4 * i as u64);
+    }
+    // The buffer head follows the bundle still waiting in the rename latch.
+    let next = renamed.len() + frontend.decode_rename.len();
+    assert_eq!(frontend.fetch_buffer[0].pc, base + 4 * next as u64);
+}
+
+#[test]
+fn buffer_of_one_block_does_not_run_ahead() {
+    let mut config = Config::default();
+    config.pipeline.width = 4;
+    config.pipeline.fetch_buffer_size = 1;
+    let frontend = Frontend::<InOrderEngine>::new(&config);
+    // The buffer always holds at least one full fetch block.
+    assert_eq!(frontend.fetch_buffer_size(), 4);
+
+    let (mut cpu, _, mut engine) = setup(false);
+    let mut frontend = Frontend::new(&config);
+    for cycle in 0..16 {
+        engine.open = cycle % 2 == 0;
+        frontend.tick(&mut cpu, &mut engine, &mut Vec::new());
+        assert!(frontend.fetch_buffer.len() <= 4);
+    }
+    assert_eq!(cpu.stats.fetch_buffer_peak, 4);
+}
+
+#[test]
+fn ftq_runs_ahead_during_icache_miss() {
+    let (mut cpu, mut frontend, mut engine) = setup(true);
+    frontend.tick(&mut cpu, &mut engine, &mut Vec::new());
+    frontend.tick(&mut cpu, &mut engine, &mut Vec::new());
+    assert!(frontend.fetch2_stall > 0, "first line misses in the I-cache");
+    for _ in 0..frontend.ftq_size() {
+        frontend.tick(&mut cpu, &mut engine, &mut Vec::new());
+    }
+    assert!(frontend.fetch2_stall > 0);
+    assert_eq!(frontend.ftq.len(), frontend.ftq_size(), "prediction keeps running");
+    assert!(frontend.fetch_buffer.is_empty());
+}
+
+#[test]
+fn flush_empties_ftq_and_fetch_buffer() {
+    let (mut cpu, mut frontend, mut engine) = setup(false);
+    engine.open = false;
+    for _ in 0..8 {
+        frontend.tick(&mut cpu, &mut engine, &mut Vec::new());
+    }
+    assert!(!frontend.ftq.is_empty());
+    assert!(!frontend.fetch_buffer.is_empty());
+
+    frontend.flush();
+    assert!(frontend.ftq.is_empty());
+    assert!(frontend.fetch_buffer.is_empty());
+    assert!(frontend.decode_rename.is_empty());
+}
+
+/// Width-2 issue behind a width-4 fetch.
+fn wide_fetch_config() -> Config {
+    let mut config = Config::default();
+    config.pipeline.width = 2;
+    config.pipeline.fetch_width = 4;
+    config.cache.l1_i.enabled = false;
+    config
+}
+
+#[test]
+fn wide_fetch_builds_up_buffer_under_stall() {
+    let (mut cpu, mut frontend, mut engine) = setup_with(&wide_fetch_config());
+    assert_eq!(frontend.fetch_width(), 4);
+
+    // Rename stalled: fetch keeps delivering 4-instruction blocks.
+    engine.open = false;
+    for _ in 0..3 {
+        frontend.tick(&mut cpu, &mut engine, &mut Vec::new());
+    }
+    assert!(!frontend.fetch_buffer.is_empty());
+    assert!(frontend.ftq.iter().all(|block| block.len() == 4));
+    assert!(frontend.decode_rename.len() <= 2, "decode is still 2 wide");
+
+    // Even with rename open, fetching 4 and draining 2 fills the buffer.
+    engine.open = true;
+    for _ in 0..16 {
+        frontend.tick(&mut cpu, &mut engine, &mut Vec::new());
+        assert!(frontend.decode_rename.len() <= 2);
+    }
+    assert_eq!(cpu.stats.fetch_buffer_peak, frontend.fetch_buffer_size() as u64);
+    assert!(cpu.stats.fetch_buffer_full_cycles > 0);
+}
+
+#[test]
+fn wide_fetch_block_ends_at_cache_line() {
+    let mut config = wide_fetch_config();
+    config.pipeline.fetch_width = 32;
+    let (mut cpu, mut frontend, mut engine) = setup_with(&config);
+    // Start two instructions before the end of the first I-cache line.
+    let line = cpu.i_cache_line_bytes as u64;
+    cpu.pc += line - 8;
+    engine.open = false;
+    frontend.tick(&mut cpu, &mut engine, &mut Vec::new());
+    assert_eq!(frontend.ftq[0].len(), 2, "first block stops at the line end");
+
+    // The next block covers the whole following line, and no more.
+    frontend.tick(&mut cpu, &mut engine, &mut Vec::new());
+    assert_eq!(frontend.fetch_buffer.len(), 2);
+    assert_eq!(frontend.ftq[0].len(), line as usize / 4);
+}
@@ -0,0 +1,217 @@
+//! Macro-op Fusion Tests.
+//!
+//! Runs small programs with and without decode-stage fusion on both backends
+//! and checks that fused pairs produce the same architectural results, retire
+//! as two instructions each (also in the per-class counts), and improve IPC on
+//! a constant-heavy loop.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::Simulator;
+use rvsim_core::common::RegIdx;
+use rvsim_core::config::Config;
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::core::pipeline::frontend::fusion::FusionPattern;
+
+const ALL_PATTERNS: [FusionPattern; 5] = [
+    FusionPattern::LuiAddi,
+    FusionPattern::AuipcAddi,
+    FusionPattern::AuipcJalr,
+    FusionPattern::SlliSrli,
+    FusionPattern::CmpBranch,
+];
+
+const A0: u32 = 10;
+const A1: u32 = 11;
+const A2: u32 = 12;
+const A3: u32 = 13;
+const A7: u32 = 17;
+const S0: u32 = 8;
+
+/// Start of RAM, where every program is loaded.
+const BASE: u64 = 0x8000_0000;
+
+fn b() -> InstructionBuilder {
+    InstructionBuilder::new()
+}
+
+/// Loads `program` at `BASE` on a 4-wide `backend` with `fusion` enabled.
+fn context(backend: BackendType, fusion: &[FusionPattern], program: &[u32]) -> TestContext {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.pipeline.width = 4;
+    config.pipeline.fusion = fusion.to_vec();
+    TestContext::with_config(&config).load_program(BASE, program)
+}
+
+/// Runs `program` until it exits.
+fn run(backend: BackendType, fusion: &[FusionPattern], program: &[u32]) -> Simulator {
+    context(backend, fusion, program).run_to_exit(100_000)
+}
+
+fn reg(sim: &Simulator, r: u32) -> u64 {
+    sim.cpu.regs.read(RegIdx::new(r as u8))
+}
+
+/// Runs `program` with every pattern enabled and with fusion off, on both
+/// backends, and checks `check` against each result.
+fn run_both(program: &[u32], expected_fused: u64, check: impl Fn(&Simulator)) {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let off = run(backend, &[], program);
+        let on = run(backend, &ALL_PATTERNS, program);
+        check(&off);
+        check(&on);
+        assert_eq!(off.cpu.stats.fused_pairs, 0, "{backend:?}");
+        assert_eq!(on.cpu.stats.fused_pairs, expected_fused, "{backend:?}");
+        assert_eq!(
+            on.cpu.stats.instructions_retired, off.cpu.stats.instructions_retired,
+            "{backend:?}: a fused pair must retire as two instructions"
+        );
+        assert_eq!(on.cpu.stats.inst_alu, off.cpu.stats.inst_alu, "{backend:?}: ALU count");
+    }
+}
+
+#[test]
+fn lui_addi_builds_constant() {
+    let mut program = vec![b().lui(A0, 0x12345).build(), b().addi(A0, A0, -0x123).build()];
+    program.extend([b().addi(A7, 0, 93).build(), b().ecall().build()]);
+    run_both(&program, 1, |sim| assert_eq!(reg(sim, A0), 0x1234_5000 - 0x123));
+}
+
+#[test]
+fn lui_addiw_sign_extends() {
+    let mut program = vec![b().lui(A0, 0x7FFFF).build(), b().addiw(A0, A0, 0x7FF).build()];
+    program.extend([b().addi(A7, 0, 93).build(), b().ecall().build()]);
+    run_both(&program, 1, |sim| assert_eq!(reg(sim, A0), 0x7FFF_F7FF));
+
+    let mut program = vec![b().lui(A0, 0x80000).build(), b().addiw(A0, A0, 1).build()];
+    program.extend([b().addi(A7, 0, 93).build(), b().ecall().build()]);
+    run_both(&program, 1, |sim| assert_eq!(reg(sim, A0), 0xFFFF_FFFF_8000_0001));
+}
+
+#[test]
+fn auipc_addi_forms_pc_relative_address() {
+    let mut program = vec![b().auipc(A1, 1).build(), b().addi(A1, A1, 0x10).build()];
+    program.extend([b().addi(A7, 0, 93).build(), b().ecall().build()]);
+    run_both(&program, 1, |sim| assert_eq!(reg(sim, A1), BASE + 0x1010));
+}
+
+#[test]
+fn auipc_jalr_calls_and_links() {
+    // 0x00: auipc ra, 0; jalr ra, 16(ra) -> 0x10
+    // 0x08: li a7, 93; ecall  (return address is 0x08)
+    // 0x10: addi a0, x0, 7; jalr x0, 0(ra)
+    let ra = 1;
+    let mut program = vec![b().auipc(ra, 0).build(), b().jalr(ra, ra, 16).build()];
+    program.extend([b().addi(A7, 0, 93).build(), b().ecall().build()]);
+    program.extend([b().addi(A0, 0, 7).build(), b().jalr(0, ra, 0).build()]);
+    run_both(&program, 1, |sim| {
+        assert_eq!(reg(sim, A0), 7);
+        assert_eq!(reg(sim, ra), BASE + 8);
+    });
+}
+
+#[test]
+fn slli_srli_zero_extends() {
+    let program = [
+        b().addi(A2, 0, -2).build(),
+        b().nop().build(),
+        b().nop().build(),
+        b().nop().build(),
+        b().slli(A2, A2, 32).build(),
+        b().srli(A2, A2, 32).build(),
+        b().addi(A7, 0, 93).build(),
+        b().ecall().build(),
+    ];
+    run_both(&program, 1, |sim| assert_eq!(reg(sim, A2), 0xFFFF_FFFE));
+}
+
+#[test]
+fn mismatched_shift_amounts_are_not_fused() {
+    let program = [
+        b().addi(A2, 0, -1).build(),
+        b().slli(A2, A2, 32).build(),
+        b().srli(A2, A2, 16).build(),
+        b().addi(A7, 0, 93).build(),
+        b().ecall().build(),
+    ];
+    run_both(&program, 0, |sim| assert_eq!(reg(sim, A2), 0xFFFF_FFFF_0000));
+}
+
+#[test]
+fn compare_branch_writes_result_and_branches() {
+    // slt a3, a0, a1; bnez a3, +8; li a2, 1 (skipped when taken); exit
+    for (a0, a1, taken) in [(1_u64, 2_u64, true), (2, 1, false), (u64::MAX, 0, true)] {
+        let program = [
+            b().slt(A3, A0, A1).build(),
+            b().bne(A3, 0, 8).build(),
+            b().addi(A2, 0, 1).build(),
+            b().addi(A7, 0, 93).build(),
+            b().ecall().build(),
+        ];
+        for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+            for fusion in [&[][..], &ALL_PATTERNS[..]] {
+                let mut ctx = context(backend, fusion, &program);
+                ctx.sim.write_reg(RegIdx::new(A0 as u8), a0);
+                ctx.sim.write_reg(RegIdx::new(A1 as u8), a1);
+                let sim = ctx.run_to_exit(100_000);
+                assert_eq!(reg(&sim, A3), u64::from(taken), "{backend:?} slt result");
+                assert_eq!(reg(&sim, A2), u64::from(!taken), "{backend:?} fallthrough executed");
+                assert_eq!(sim.cpu.stats.fused_pairs, if fusion.is_empty() { 0 } else { 1 });
+            }
+        }
+    }
+}
+
+#[test]
+fn disabled_patterns_are_not_fused() {
+    let mut program = vec![b().lui(A0, 0x12345).build(), b().addi(A0, A0, 1).build()];
+    program.extend([b().addi(A7, 0, 93).build(), b().ecall().build()]);
+    let sim = run(BackendType::InOrder, &[FusionPattern::AuipcAddi], &program);
+    assert_eq!(sim.cpu.stats.fused_pairs, 0);
+    assert_eq!(reg(&sim, A0), 0x1234_5001);
+}
+
+#[test]
+fn fused_head_appears_in_pc_trace() {
+    let mut program = vec![b().lui(A0, 1).build(), b().addi(A0, A0, 1).build()];
+    program.extend([b().addi(A7, 0, 93).build(), b().ecall().build()]);
+    let sim = run(BackendType::InOrder, &ALL_PATTERNS, &program);
+    let pcs: Vec<u64> = sim.cpu.pc_trace.iter().map(|&(pc, _)| pc - BASE).collect();
+    assert!(pcs.starts_with(&[0, 4]), "{pcs:?}");
+}
+
+/// A loop materialising four 32-bit constants per iteration.
+fn li_loop(iterations: i32) -> Vec<u32> {
+    let mut program = vec![b().addi(S0, 0, iterations).build()];
+    for (i, rd) in [A0, A1, A2, A3].into_iter().enumerate() {
+        program.push(b().lui(rd, 0x12345 + i as i32).build());
+        program.push(b().addi(rd, rd, 0x678).build());
+    }
+    program.push(b().addi(S0, S0, -1).build());
+    program.push(b().bne(S0, 0, -36).build());
+    program.extend([b().addi(A7, 0, 93).build(), b().ecall().build()]);
+    program
+}
+
+#[test]
+fn li_heavy_loop_ipc_improves() {
+    let program = li_loop(200);
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let off = run(backend, &[], &program);
+        let on = run(backend, &[FusionPattern::LuiAddi], &program);
+        assert_eq!(reg(&on, A3), 0x1234_8678);
+        assert_eq!(on.cpu.stats.instructions_retired, off.cpu.stats.instructions_retired);
+        assert!(on.cpu.stats.fused_pairs >= 200, "{backend:?}");
+
+        let ipc = |sim: &Simulator| {
+            sim.cpu.stats.instructions_retired as f64 / sim.cpu.stats.cycles as f64
+        };
+        assert!(
+            ipc(&on) > ipc(&off),
+            "{backend:?}: fused IPC {:.3} should beat unfused {:.3}",
+            ipc(&on),
+            ipc(&off)
+        );
+    }
+}
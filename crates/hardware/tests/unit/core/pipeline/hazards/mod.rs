@@ -1 +1,3 @@
 pub mod control_hazards;
+pub mod data_hazards;
+pub mod structural_hazards;
@@ -0,0 +1,127 @@
+//! Illegal Instruction Tests.
+//!
+//! Runs reserved encodings and instructions from disabled extensions through
+//! the full pipeline. Each must raise an illegal-instruction exception at
+//! its own PC with `mtval` holding the instruction bits, and must not write
+//! its destination register.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::common::PhysAddr;
+use rvsim_core::core::arch::csr::{MISA_EXT_F, MISA_EXT_M, MSTATUS_FS, MSTATUS_FS_INIT};
+use rvsim_core::isa::privileged::cause::exception;
+
+const BASE_ADDR: u64 = 0x8000_0000;
+const MEM_SIZE: usize = 0x2000;
+const HANDLER: u64 = BASE_ADDR + 0x800;
+
+/// Marker preset in x7, the destination of every test instruction.
+const MARKER: u64 = 0xAAAA;
+
+/// Encodes an R-type instruction.
+fn r_type(opcode: u32, funct3: u32, funct7: u32, rd: u32, rs1: u32, rs2: u32) -> u32 {
+    (funct7 << 25) | (rs2 << 20) | (rs1 << 15) | (funct3 << 12) | (rd << 7) | opcode
+}
+
+/// Runs `inst` after `setup` and then spins, with traps vectored to a spin
+/// loop at `HANDLER`.
+fn run(inst: u32, setup: impl FnOnce(&mut TestContext)) -> TestContext {
+    let b = InstructionBuilder::new;
+    let mut code = vec![inst];
+    code.extend([b().nop().build(); 6]);
+    code.push(b().jal(0, 0).build());
+    let mut tc = TestContext::new().with_memory(MEM_SIZE, BASE_ADDR).load_program(BASE_ADDR, &code);
+    tc.sim
+        .cpu
+        .bus
+        .bus
+        .write_u32(PhysAddr::new(HANDLER), InstructionBuilder::new().jal(0, 0).build());
+    tc.cpu_mut().direct_mode = false;
+    tc.cpu_mut().csrs.mtvec = HANDLER;
+    tc.set_reg(5, 3);
+    tc.set_reg(6, 4);
+    tc.set_reg(7, MARKER);
+    setup(&mut tc);
+    tc.run(100);
+    tc
+}
+
+/// Turns the FPU on (`mstatus.FS` = Initial).
+fn fs_on(tc: &mut TestContext) {
+    let mstatus = &mut tc.cpu_mut().csrs.mstatus;
+    *mstatus = (*mstatus & !MSTATUS_FS) | MSTATUS_FS_INIT;
+}
+
+/// Asserts that `inst` trapped as illegal with `mtval` = `inst`.
+fn assert_illegal(tc: &TestContext, inst: u32) {
+    let csrs = &tc.cpu().csrs;
+    assert_eq!(csrs.mcause, exception::ILLEGAL_INSTRUCTION, "{inst:#010x}");
+    assert_eq!(csrs.mtval, u64::from(inst), "{inst:#010x}");
+    assert_eq!(csrs.mepc, BASE_ADDR, "{inst:#010x}");
+    assert_eq!(tc.get_reg(7), MARKER, "{inst:#010x}");
+}
+
+#[test]
+fn reserved_op_encoding_traps_with_mtval() {
+    // OP with funct7 = 0b0000010 is not assigned to any extension.
+    let inst = r_type(0x33, 0b000, 0b000_0010, 7, 5, 6);
+    assert_illegal(&run(inst, |_| {}), inst);
+}
+
+#[test]
+fn reserved_funct3_encodings_trap_with_mtval() {
+    let b = InstructionBuilder::new;
+    let reserved = [
+        // slt has no OP-32 form.
+        r_type(0x3B, 0b010, 0, 7, 5, 6),
+        // mulh has no OP-32 form.
+        r_type(0x3B, 0b001, 1, 7, 5, 6),
+        // slti has no OP-IMM-32 form.
+        b().opcode(0x1B).rd(7).rs1(5).funct3(0b010).imm(1).build(),
+        // slli with a non-zero funct6.
+        b().opcode(0x13).rd(7).rs1(5).funct3(0b001).imm(0x041).build(),
+        // Branch funct3 0b010.
+        b().opcode(0x63).rs1(5).rs2(6).funct3(0b010).build(),
+        // jalr with funct3 != 0.
+        b().opcode(0x67).rd(7).rs1(5).funct3(0b001).build(),
+        // SYSTEM funct3 0b100.
+        (5 << 15) | (0b100 << 12) | (7 << 7) | 0x73,
+        // SYSTEM funct3 0 that is not ecall/ebreak/xret/wfi/sfence.vma.
+        0x0030_0073,
+        // fsqrt.d with rs2 != 0.
+        r_type(0x53, 0b000, 0b010_1101, 7, 5, 1),
+    ];
+    for inst in reserved {
+        assert_illegal(&run(inst, |_| {}), inst);
+    }
+}
+
+#[test]
+fn fp_instruction_with_fs_off_traps_with_mtval() {
+    // fadd.d f7, f5, f6 (dynamic rounding).
+    let inst = r_type(0x53, 0b111, 0b000_0001, 7, 5, 6);
+    let tc = run(inst, |tc| tc.cpu_mut().csrs.mstatus &= !MSTATUS_FS);
+    assert_illegal(&tc, inst);
+
+    // With FS on the same instruction executes.
+    let tc = run(inst, fs_on);
+    assert_ne!(tc.cpu().csrs.mcause, exception::ILLEGAL_INSTRUCTION);
+}
+
+#[test]
+fn disabled_extension_traps_with_mtval() {
+    // mul x7, x5, x6 without M.
+    let mul = r_type(0x33, 0b000, 1, 7, 5, 6);
+    let tc = run(mul, |tc| tc.cpu_mut().csrs.misa &= !MISA_EXT_M);
+    assert_illegal(&tc, mul);
+    assert_eq!(run(mul, |_| {}).get_reg(7), 12);
+
+    // fmv.x.w x7, f5 without F.
+    let fmv = r_type(0x53, 0b000, 0b111_0000, 7, 5, 0);
+    let tc = run(fmv, |tc| {
+        fs_on(tc);
+        tc.cpu_mut().csrs.misa &= !MISA_EXT_F;
+    });
+    assert_illegal(&tc, fmv);
+    assert_eq!(run(fmv, fs_on).get_reg(7), 0);
+}
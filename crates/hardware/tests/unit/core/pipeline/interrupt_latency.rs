@@ -0,0 +1,97 @@
+//! Interrupt Boundary and Latency Tests.
+//!
+//! A CLINT timer interrupt lands on a tight loop at a range of cycles. The
+//! interrupt is polled at the commit boundary, so `mepc` must name the first
+//! uncommitted instruction: every loop instruction before it has retired and
+//! none after it has. The cycles from `mip & mie` going nonzero to the vector
+//! are counted in the interrupt latency stats and must stay small.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use rvsim_core::Simulator;
+use rvsim_core::common::{PhysAddr, RegIdx};
+use rvsim_core::config::Config;
+use rvsim_core::core::arch::csr;
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::soc::System;
+
+/// Offset of the trap handler (a spin loop) from the start of RAM.
+const HANDLER_OFFSET: u64 = 0x800;
+
+/// Offset of `mtimecmp` for hart 0 within the CLINT.
+const MTIMECMP_OFFSET: u64 = 0x4000;
+
+/// `mcause` of a machine timer interrupt.
+const MCAUSE_MTI: u64 = (1 << 63) | 7;
+
+/// Upper bound on cycles from the timer becoming pending to its vector.
+const MAX_LATENCY: u64 = 16;
+
+/// Runs the loop `x5 += 1; x6 += 2` on `backend` with the timer due at
+/// `mtimecmp`, and returns the simulator once the interrupt is taken.
+fn run(backend: BackendType, mtimecmp: u64) -> Simulator {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.system.clint_divider = 1;
+    let base = config.system.ram_base;
+
+    let b = InstructionBuilder::new;
+    let program = [
+        (0x00, b().addi(5, 5, 1).build()),
+        (0x04, b().addi(6, 6, 2).build()),
+        (0x08, b().jal(0, -8).build()),
+        (HANDLER_OFFSET, b().jal(0, 0).build()),
+    ];
+    let mut sim = Simulator::new(System::new(&config, ""), &config);
+    for (offset, inst) in program {
+        sim.cpu.bus.bus.write_u32(PhysAddr::new(base + offset), inst);
+    }
+    sim.cpu.bus.bus.write_u64(PhysAddr::new(config.system.clint_base + MTIMECMP_OFFSET), mtimecmp);
+    sim.cpu.pc = base;
+    sim.cpu.direct_mode = false;
+    sim.cpu.csrs.mtvec = base + HANDLER_OFFSET;
+    sim.cpu.csrs.mie = csr::MIE_MTIE;
+    sim.cpu.csrs.mstatus |= csr::MSTATUS_MIE;
+    for _ in 0..10_000 {
+        sim.tick().unwrap();
+        if sim.cpu.stats.interrupts_taken > 0 {
+            return sim;
+        }
+    }
+    panic!("timer interrupt was not taken");
+}
+
+#[test]
+fn timer_interrupt_mepc_is_first_uncommitted_instruction() {
+    let base = Config::default().system.ram_base;
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        for mtimecmp in 200..212 {
+            let sim = run(backend, mtimecmp);
+            let x5 = sim.cpu.regs.read(RegIdx::new(5));
+            let x6 = sim.cpu.regs.read(RegIdx::new(6));
+            let ctx = format!("{backend:?} mtimecmp={mtimecmp}");
+            assert_eq!(sim.cpu.csrs.mcause, MCAUSE_MTI, "{ctx}");
+            assert!(x5 > 0, "{ctx}");
+            match sim.cpu.csrs.mepc - base {
+                // Between the two adds: the first has retired, the second not.
+                0x04 => assert_eq!(x6, 2 * (x5 - 1), "{ctx}"),
+                0x00 | 0x08 => assert_eq!(x6, 2 * x5, "{ctx}"),
+                other => panic!("{ctx}: mepc at offset {other:#x} is outside the loop"),
+            }
+        }
+    }
+}
+
+#[test]
+fn timer_interrupt_latency_is_bounded() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let sim = run(backend, 200);
+        let stats = &sim.cpu.stats;
+        assert_eq!(stats.interrupts_taken, 1, "{backend:?}");
+        assert_eq!(stats.interrupt_latency_cycles, stats.interrupt_latency_max, "{backend:?}");
+        assert!(
+            stats.interrupt_latency_max <= MAX_LATENCY,
+            "{backend:?}: {}",
+            stats.interrupt_latency_max
+        );
+    }
+}
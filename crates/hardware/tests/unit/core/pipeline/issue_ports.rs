@@ -0,0 +1,63 @@
+//! Typed Issue Port Tests.
+//!
+//! Two independent `fadd.d`s fetched together on a 2-wide core, with two FP
+//! adders. With `pipeline.ports.fpu = 1` the second waits for the port and
+//! issues the next cycle; with unlimited ports both issue at once.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::config::Config;
+use rvsim_core::core::pipeline::backend::o3::fu_pool::PortClass;
+use rvsim_core::core::pipeline::engine::BackendType;
+
+/// `fadd.d f1, f2, f3`.
+const FADD_D_F1: u32 = 0x0231_00D3;
+/// `fadd.d f4, f5, f6`.
+const FADD_D_F4: u32 = 0x0262_8253;
+
+/// Runs the two FP adds, returning the cycles on which each was issued and
+/// the issue-port stall count.
+fn run(backend: BackendType, fpu_ports: usize) -> (Vec<u64>, u64) {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.pipeline.width = 2;
+    config.pipeline.ports.fpu = fpu_ports;
+    let base = config.system.ram_base;
+
+    let b = InstructionBuilder::new;
+    let program = [FADD_D_F1, FADD_D_F4, b().addi(17, 0, 93).build(), b().ecall().build()];
+
+    let fpu = PortClass::Fpu as usize;
+    let mut issued = 0;
+    let mut issue_cycles = Vec::new();
+    let sim = TestContext::with_config(&config).load_program(base, &program).run_to_exit_with(
+        10_000,
+        |sim| {
+            let total = sim.cpu.stats.port_issues[fpu];
+            issue_cycles
+                .extend(std::iter::repeat_n(sim.cpu.stats.cycles, (total - issued) as usize));
+            issued = total;
+        },
+    );
+    (issue_cycles, sim.cpu.stats.stalls_issue_port)
+}
+
+#[test]
+fn single_fpu_port_issues_fp_ops_in_consecutive_cycles() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let (cycles, stalls) = run(backend, 1);
+        assert_eq!(cycles.len(), 2, "{backend:?}: {cycles:?}");
+        assert_eq!(cycles[1], cycles[0] + 1, "{backend:?}: {cycles:?}");
+        assert!(stalls > 0, "{backend:?}: second fadd never waited for the port");
+    }
+}
+
+#[test]
+fn unlimited_fpu_ports_issue_fp_ops_together() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let (cycles, stalls) = run(backend, 0);
+        assert_eq!(cycles.len(), 2, "{backend:?}: {cycles:?}");
+        assert_eq!(cycles[0], cycles[1], "{backend:?}: {cycles:?}");
+        assert_eq!(stalls, 0, "{backend:?}");
+    }
+}
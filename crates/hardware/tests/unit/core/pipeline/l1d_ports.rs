@@ -0,0 +1,56 @@
+//! L1D Port Contention Tests.
+//!
+//! Pairs of independent loads on a 2-wide core. With `l1_d_ports = 1` the
+//! second load of each pair must wait a cycle for the port, which shows up
+//! as `stalls_dcache_port` and extra cycles; with unlimited ports it does not.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::config::Config;
+use rvsim_core::core::pipeline::engine::BackendType;
+
+const PAIRS: usize = 16;
+
+/// Runs `PAIRS` load pairs and returns `(cycles, port stalls)`.
+fn run(backend: BackendType, ports: usize) -> (u64, u64) {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.pipeline.width = 2;
+    config.cache.l1_d_ports = ports;
+    let base = config.system.ram_base;
+
+    let b = InstructionBuilder::new;
+    let mut program = vec![
+        b().auipc(10, 0).build(),
+        b().addi(10, 10, 0x400).build(), // x10 = data
+    ];
+    for _ in 0..PAIRS {
+        program.push(b().lw(5, 10, 0).build());
+        program.push(b().lw(6, 10, 8).build());
+    }
+    program.extend([b().addi(17, 0, 93).build(), b().ecall().build()]);
+
+    let sim = TestContext::with_config(&config).load_program(base, &program).run_to_exit(100_000);
+    (sim.cpu.stats.cycles, sim.cpu.stats.stalls_dcache_port)
+}
+
+#[test]
+fn single_port_serializes_paired_loads() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let (free_cycles, free_stalls) = run(backend, 0);
+        let (one_port_cycles, one_port_stalls) = run(backend, 1);
+        assert_eq!(free_stalls, 0, "{backend:?}: unlimited ports stalled");
+        assert!(one_port_stalls > 0, "{backend:?}: loads never contended for the port");
+        assert!(
+            one_port_cycles > free_cycles,
+            "{backend:?}: {one_port_cycles} cycles with one port vs {free_cycles} unlimited"
+        );
+    }
+}
+
+#[test]
+fn two_ports_admit_a_pair_per_cycle() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        assert_eq!(run(backend, 2), run(backend, 0), "{backend:?}");
+    }
+}
@@ -0,0 +1,68 @@
+//! I-Cache Line-Crossing Fetch Tests.
+//!
+//! A compressed `c.nop` shifts `addi x5, x0, 7` to the last two bytes of the
+//! first I-cache line, so its upper half sits in the next line. Fetch2 must
+//! take a second I-cache access for it and charge that in the stats; with two
+//! `c.nop`s the same instruction starts the next line and no crossing occurs.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::Simulator;
+use rvsim_core::common::{PhysAddr, RegIdx};
+use rvsim_core::config::Config;
+use rvsim_core::core::pipeline::engine::BackendType;
+
+const C_NOP: u16 = 0x0001;
+
+/// Runs the program with `c_nops` compressed NOPs ending at the line end
+/// followed by the `addi`, returning the finished simulator.
+fn run(backend: BackendType, c_nops: u64) -> Simulator {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.cache.l1_i.enabled = true;
+    let base = config.system.ram_base;
+    let line = config.cache.l1_i.line_bytes as u64;
+
+    let b = InstructionBuilder::new;
+    let mut ctx = TestContext::with_config(&config);
+    let bus = &mut ctx.sim.cpu.bus.bus;
+    let mut addr = base;
+    while addr < base + line - 4 {
+        bus.write_u32(PhysAddr::new(addr), b().addi(0, 0, 0).build());
+        addr += 4;
+    }
+    for _ in 0..c_nops {
+        bus.write_u16(PhysAddr::new(addr), C_NOP);
+        addr += 2;
+    }
+    for inst in [b().addi(5, 0, 7).build(), b().addi(17, 0, 93).build(), b().ecall().build()] {
+        bus.write_u32(PhysAddr::new(addr), inst);
+        addr += 4;
+    }
+    ctx.sim.cpu.pc = base;
+    ctx.run_to_exit(10_000)
+}
+
+#[test]
+fn instruction_across_line_boundary_pays_second_access() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let sim = run(backend, 1);
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(5)), 7, "{backend:?}");
+        assert_eq!(sim.cpu.stats.fetch_line_crossings, 1, "{backend:?}");
+        assert!(
+            sim.cpu.stats.fetch_line_crossing_cycles >= sim.cpu.l1_i_cache.latency,
+            "{backend:?}: {} cycles",
+            sim.cpu.stats.fetch_line_crossing_cycles
+        );
+    }
+}
+
+#[test]
+fn line_aligned_instruction_has_no_crossing_penalty() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let sim = run(backend, 2);
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(5)), 7, "{backend:?}");
+        assert_eq!(sim.cpu.stats.fetch_line_crossings, 0, "{backend:?}");
+        assert_eq!(sim.cpu.stats.fetch_line_crossing_cycles, 0, "{backend:?}");
+    }
+}
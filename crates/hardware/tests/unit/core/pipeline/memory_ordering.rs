@@ -0,0 +1,230 @@
+//! Speculative Load / Memory-Ordering Violation Tests.
+//!
+//! With the store-set predictor, the O3 backend lets a load issue before an
+//! older store whose address is still unknown. These directed tests delay a
+//! store's address behind a divide and check that a load to the same address
+//! is squashed and re-executed with the stored value, while a load to a
+//! different address runs ahead without a squash. Once trained, the
+//! predictor holds the aliasing load back instead of squashing it again,
+//! while a neighbouring load that never aliased keeps bypassing the store.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::Simulator;
+use rvsim_core::common::{PhysAddr, RegIdx};
+use rvsim_core::config::{Config, MemDepPredictor};
+use rvsim_core::core::pipeline::engine::BackendType;
+
+const DATA_OFFSET: u64 = 0x1000;
+const OLD: u64 = 0x1111_1111_1111_1111;
+const NEW: u64 = 0x2222_2222_2222_2222;
+
+/// `x13 = x14 + (x11 / x12)` computed slowly, then `sd x15, 0(x13)` followed
+/// by `ld x16, load_offset(x14)`, then exit.
+///
+/// The divide yields 0, so the store goes to `x14` and the load overlaps it
+/// exactly when `load_offset == 0`.
+fn run(load_offset: i32) -> Simulator {
+    let mut config = Config::default();
+    config.pipeline.backend = BackendType::OutOfOrder;
+    config.pipeline.width = 4;
+    config.pipeline.mem_dep_predictor = MemDepPredictor::StoreSet;
+    let base = config.system.ram_base;
+    let data = base + DATA_OFFSET;
+
+    let program = [
+        InstructionBuilder::new().div(10, 11, 12).build(),
+        InstructionBuilder::new().add(13, 10, 14).build(),
+        InstructionBuilder::new().sd(13, 15, 0).build(),
+        InstructionBuilder::new().ld(16, 14, load_offset).build(),
+        InstructionBuilder::new().addi(17, 0, 93).build(),
+        InstructionBuilder::new().ecall().build(),
+    ];
+    let mut ctx = TestContext::with_config(&config).load_program(base, &program);
+    ctx.sim.cpu.bus.bus.write_u64(PhysAddr::new(data), OLD);
+    ctx.sim.cpu.bus.bus.write_u64(PhysAddr::new(data + 8), OLD);
+    ctx.sim.write_reg(RegIdx::new(11), 0);
+    ctx.sim.write_reg(RegIdx::new(12), 1);
+    ctx.sim.write_reg(RegIdx::new(14), data);
+    ctx.sim.write_reg(RegIdx::new(15), NEW);
+
+    ctx.run_to_exit(10_000)
+}
+
+#[test]
+fn late_store_address_conflict_squashes_load() {
+    let sim = run(0);
+    assert_eq!(sim.cpu.regs.read(RegIdx::new(16)), NEW, "load must see the older store");
+    assert_eq!(sim.cpu.stats.mem_ordering_violations, 1);
+    assert_eq!(sim.cpu.stats.mdp_violations, 1, "store-set predictor is trained");
+    assert!(sim.cpu.stats.misprediction_penalty > 0, "younger instructions squashed");
+}
+
+#[test]
+fn independent_load_runs_ahead_without_squash() {
+    let mut sim = run(8);
+    assert_eq!(sim.cpu.regs.read(RegIdx::new(16)), OLD);
+    assert_eq!(sim.cpu.stats.mem_ordering_violations, 0);
+    assert_eq!(sim.cpu.stats.store_forwards, 0);
+    let data = sim.cpu.bus.bus.read_u64(PhysAddr::new(0x8000_0000 + DATA_OFFSET));
+    assert_eq!(data, NEW);
+}
+
+/// Runs the late-address store and aliasing load of [`run`] in a loop of
+/// `iterations`, under the store-set predictor. With `independent_load`, each
+/// iteration also loads `x18` from the doubleword after the stored one.
+fn run_loop(iterations: i32, independent_load: bool) -> Simulator {
+    let mut config = Config::default();
+    config.pipeline.backend = BackendType::OutOfOrder;
+    config.pipeline.width = 4;
+    config.pipeline.mem_dep_predictor = MemDepPredictor::StoreSet;
+    let base = config.system.ram_base;
+
+    let b = InstructionBuilder::new;
+    let mut ctx = TestContext::with_config(&config);
+    let mut body = vec![
+        InstructionBuilder::new().div(10, 11, 12).build(),
+        b().add(13, 10, 14).build(),
+        b().sd(13, 15, 0).build(),
+        b().ld(16, 14, 0).build(),
+    ];
+    if independent_load {
+        body.push(b().ld(18, 14, 8).build());
+    }
+    body.extend([b().addi(15, 15, 1).build(), b().addi(8, 8, -1).build()]);
+    let back = -4 * body.len() as i32;
+    let program = std::iter::once(b().addi(8, 0, iterations).build()).chain(body).chain([
+        b().bne(8, 0, back).build(),
+        b().addi(17, 0, 93).build(),
+        b().ecall().build(),
+    ]);
+    for (i, inst) in program.enumerate() {
+        ctx.sim.cpu.bus.bus.write_u32(PhysAddr::new(base + 4 * i as u64), inst);
+    }
+    ctx.sim.cpu.pc = base;
+    ctx.sim.write_reg(RegIdx::new(11), 0);
+    ctx.sim.write_reg(RegIdx::new(12), 1);
+    ctx.sim.cpu.bus.bus.write_u64(PhysAddr::new(base + DATA_OFFSET + 8), OLD);
+    ctx.sim.write_reg(RegIdx::new(14), base + DATA_OFFSET);
+    ctx.sim.write_reg(RegIdx::new(15), NEW);
+    ctx.run_to_exit(100_000)
+}
+
+#[test]
+fn store_set_stops_squashing_recurring_alias() {
+    let sim = run_loop(20, false);
+    let stats = &sim.cpu.stats;
+    assert_eq!(sim.cpu.regs.read(RegIdx::new(16)), NEW + 19);
+    assert_eq!(stats.mem_ordering_violations, 1, "only the first iteration may squash");
+    assert!(stats.mdp_predictions_wait_for >= 19, "wait_for {}", stats.mdp_predictions_wait_for);
+    assert!(stats.mdp_avoided_violations >= 19, "avoided {}", stats.mdp_avoided_violations);
+    assert_eq!(stats.mdp_false_dependences, 0);
+}
+
+#[test]
+fn store_set_holds_dependent_load_while_independent_load_bypasses() {
+    let sim = run_loop(20, true);
+    let stats = &sim.cpu.stats;
+    assert_eq!(sim.cpu.regs.read(RegIdx::new(16)), NEW + 19);
+    assert_eq!(sim.cpu.regs.read(RegIdx::new(18)), OLD);
+    assert_eq!(stats.mem_ordering_violations, 1, "only the first iteration may squash");
+    assert!(stats.mdp_avoided_violations >= 19, "avoided {}", stats.mdp_avoided_violations);
+    assert!(stats.mdp_predictions_bypass >= 20, "bypass {}", stats.mdp_predictions_bypass);
+    assert_eq!(stats.mdp_false_dependences, 0, "the independent load never waits");
+    assert!(stats.mdp_accuracy_pct() > 95.0, "accuracy {:.2}%", stats.mdp_accuracy_pct());
+}
+
+/// Runs `stores` then `load` behind a full-width divide, returning the
+/// simulator after exit. On the O3 backend the divide keeps the stores from
+/// committing, so they are still buffered when the load executes.
+fn run_forward(backend: BackendType, stores: &[u32], load: u32) -> Simulator {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.pipeline.width = 4;
+    let base = config.system.ram_base;
+    let data = base + DATA_OFFSET;
+
+    let mut program = vec![InstructionBuilder::new().div(20, 11, 12).build()];
+    program.extend_from_slice(stores);
+    program.extend([
+        load,
+        InstructionBuilder::new().addi(17, 0, 93).build(),
+        InstructionBuilder::new().ecall().build(),
+    ]);
+    let mut ctx = TestContext::with_config(&config).load_program(base, &program);
+    ctx.sim.cpu.bus.bus.write_u64(PhysAddr::new(data), OLD);
+    ctx.sim.write_reg(RegIdx::new(11), i64::MAX as u64);
+    ctx.sim.write_reg(RegIdx::new(12), 1);
+    ctx.sim.write_reg(RegIdx::new(14), data);
+    ctx.sim.write_reg(RegIdx::new(15), 0x8877_6655_4433_2211);
+    ctx.sim.write_reg(RegIdx::new(18), 0xEEEE_EEEE_EEEE_EEEE);
+    ctx.run_to_exit(10_000)
+}
+
+#[test]
+fn byte_load_forwards_from_middle_of_double_store() {
+    let b = InstructionBuilder::new;
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let sim = run_forward(backend, &[b().sd(14, 15, 0).build()], b().lb(16, 14, 3).build());
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(16)), 0x44, "{backend:?}");
+        if backend == BackendType::OutOfOrder {
+            assert_eq!(sim.cpu.stats.store_forwards, 1);
+            assert_eq!(sim.cpu.stats.store_forward_stalls, 0);
+        }
+    }
+}
+
+#[test]
+fn word_load_merges_two_buffered_stores() {
+    let b = InstructionBuilder::new;
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        // sd 0(x14); sb x18, 5(x14); lw 4(x14)
+        let stores = [b().sd(14, 15, 0).build(), b().sb(14, 18, 5).build()];
+        let sim = run_forward(backend, &stores, b().lw(16, 14, 4).build());
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(16)), 0xFFFF_FFFF_8877_EE55, "{backend:?}");
+        if backend == BackendType::OutOfOrder {
+            assert_eq!(sim.cpu.stats.store_forwards, 1);
+        }
+    }
+}
+
+#[test]
+fn partially_covered_load_stalls_and_reads_merged_memory() {
+    let b = InstructionBuilder::new;
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        // sb x18, 1(x14); lw 0(x14) — three bytes must come from memory.
+        let sim = run_forward(backend, &[b().sb(14, 18, 1).build()], b().lw(16, 14, 0).build());
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(16)), 0x1111_EE11, "{backend:?}");
+        assert_eq!(sim.cpu.stats.store_forwards, 0, "{backend:?}");
+        if backend == BackendType::OutOfOrder {
+            assert!(sim.cpu.stats.store_forward_stalls > 0);
+        }
+    }
+}
+
+#[test]
+fn resolved_store_forwards_to_load() {
+    let mut config = Config::default();
+    config.pipeline.backend = BackendType::OutOfOrder;
+    config.pipeline.width = 4;
+    let base = config.system.ram_base;
+    let data = base + DATA_OFFSET;
+
+    // div x20, x11, x12 keeps the store from committing before the load runs.
+    // sd x15, 0(x14); lw x16, 4(x14); exit
+    let program = [
+        InstructionBuilder::new().div(20, 11, 12).build(),
+        InstructionBuilder::new().sd(14, 15, 0).build(),
+        InstructionBuilder::new().lw(16, 14, 4).build(),
+        InstructionBuilder::new().addi(17, 0, 93).build(),
+        InstructionBuilder::new().ecall().build(),
+    ];
+    let mut ctx = TestContext::with_config(&config).load_program(base, &program);
+    ctx.sim.write_reg(RegIdx::new(11), i64::MAX as u64);
+    ctx.sim.write_reg(RegIdx::new(12), 1);
+    ctx.sim.write_reg(RegIdx::new(14), data);
+    ctx.sim.write_reg(RegIdx::new(15), 0x8765_4321_0000_0000);
+    let sim = ctx.run_to_exit(10_000);
+    assert_eq!(sim.cpu.regs.read(RegIdx::new(16)), 0xFFFF_FFFF_8765_4321, "lw sign-extends");
+    assert_eq!(sim.cpu.stats.store_forwards, 1);
+}
@@ -0,0 +1,49 @@
+//! Misprediction Penalty Tests.
+//!
+//! Runs one forward branch that a cold predictor gets wrong (taken) or right
+//! (not taken), with and without `mispredict_penalty`. Only the mispredicted
+//! branch may pay the extra bubble, and it must pay all of it.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::config::Config;
+use rvsim_core::core::pipeline::engine::BackendType;
+
+const PENALTY: u64 = 20;
+
+/// Runs `branch` followed by a skippable `addi` and an exit, returning
+/// `(cycles, speculative mispredictions)`.
+fn run(backend: BackendType, branch: u32, penalty: u64) -> (u64, u64) {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.pipeline.mispredict_penalty = penalty;
+    let base = config.system.ram_base;
+
+    let b = InstructionBuilder::new;
+    let program =
+        [branch, b().addi(5, 5, 1).build(), b().addi(17, 0, 93).build(), b().ecall().build()];
+    let sim = TestContext::with_config(&config).load_program(base, &program).run_to_exit(100_000);
+    let stats = &sim.cpu.stats;
+    (stats.cycles, stats.speculative_branch_mispredictions)
+}
+
+#[test]
+fn mispredicted_branch_pays_configured_penalty() {
+    let b = InstructionBuilder::new;
+    let taken = b().beq(0, 0, 8).build();
+    let not_taken = b().bne(0, 0, 8).build();
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let (miss_base, misses) = run(backend, taken, 0);
+        let (miss_slow, _) = run(backend, taken, PENALTY);
+        assert!(misses >= 1, "{backend:?}: taken branch was predicted");
+        assert!(
+            miss_slow >= miss_base + PENALTY,
+            "{backend:?}: {miss_slow} cycles with penalty vs {miss_base} without"
+        );
+
+        let (hit_base, hits_missed) = run(backend, not_taken, 0);
+        let (hit_slow, _) = run(backend, not_taken, PENALTY);
+        assert_eq!(hits_missed, 0, "{backend:?}: not-taken branch mispredicted");
+        assert_eq!(hit_slow, hit_base, "{backend:?}: correct prediction paid the penalty");
+    }
+}
@@ -1 +1,20 @@
+pub mod cbo;
+pub mod checkpoint_recovery;
+pub mod execute_latency;
+pub mod fence;
+pub mod fetch_buffer;
+pub mod fusion;
 pub mod hazards;
+pub mod illegal_instruction;
+pub mod interrupt_latency;
+pub mod issue_ports;
+pub mod l1d_ports;
+pub mod line_crossing;
+pub mod memory_ordering;
+pub mod mispredict_penalty;
+pub mod occupancy;
+pub mod pipe_trace;
+pub mod ras_recovery;
+pub mod stall_attribution;
+pub mod store_drain;
+pub mod wfi;
@@ -0,0 +1,67 @@
+//! Occupancy Histogram Tests.
+//!
+//! A stream of loads through a cold, non-blocking D-cache, each followed by independent
+//! ALU work, keeps the ROB head waiting on memory while dispatch fills the
+//! ROB behind it, so the sampled ROB occupancy must skew toward full.
+//! With `general.sample_occupancy` off nothing is recorded.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::Simulator;
+use rvsim_core::common::RegIdx;
+use rvsim_core::config::Config;
+use rvsim_core::core::pipeline::engine::BackendType;
+
+/// Offset of the load data from the start of RAM.
+const DATA_OFFSET: u64 = 0x10_0000;
+
+/// Runs 64 cold-miss loads, each followed by 32 independent adds, then exits.
+fn run(backend: BackendType, sample: bool) -> (Simulator, usize) {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.pipeline.width = 2;
+    config.cache.l1_d.enabled = true;
+    config.cache.l1_d.mshr_count = 4;
+    config.cache.l2.enabled = true;
+    config.general.sample_occupancy = sample;
+    let base = config.system.ram_base;
+    let rob_size = config.pipeline.rob_size;
+
+    let b = InstructionBuilder::new;
+    let mut program = Vec::new();
+    for i in 0..64 {
+        program.push(b().ld(6, 7, 64 * i).build());
+        program.extend((0..32).map(|j| b().addi(8 + (j % 8), 0, j as i32).build()));
+    }
+    program.extend([b().addi(17, 0, 93).build(), b().ecall().build()]);
+
+    let mut ctx = TestContext::with_config(&config).load_program(base, &program);
+    ctx.sim.write_reg(RegIdx::new(7), base + DATA_OFFSET);
+    let sim = ctx.run_to_exit(1_000_000);
+    (sim, rob_size)
+}
+
+#[test]
+fn dcache_miss_stall_skews_rob_occupancy_toward_full() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let (sim, rob_size) = run(backend, true);
+        let rob = &sim.cpu.stats.rob_occupancy;
+        assert_eq!(rob.samples(), sim.cpu.stats.cycles, "{backend:?}");
+        assert_eq!(rob.max(), rob_size as u64, "{backend:?}");
+        assert!(rob.percentile(50.0) >= rob_size as u64 * 3 / 4, "{backend:?}: {rob:?}");
+        assert_eq!(rob.histogram().iter().sum::<u64>(), rob.samples(), "{backend:?}");
+        assert!(sim.cpu.stats.issue_queue_occupancy.max() > 0, "{backend:?}");
+    }
+}
+
+#[test]
+fn disabled_sampling_records_nothing() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let (sim, _) = run(backend, false);
+        let stats = &sim.cpu.stats;
+        assert_eq!(stats.rob_occupancy.samples(), 0, "{backend:?}");
+        assert_eq!(stats.store_buffer_occupancy.samples(), 0, "{backend:?}");
+        assert_eq!(stats.issue_queue_occupancy.samples(), 0, "{backend:?}");
+        assert!(stats.rob_occupancy.histogram().is_empty(), "{backend:?}");
+    }
+}
@@ -0,0 +1,123 @@
+//! Pipeline Visualization Trace Tests.
+//!
+//! Runs a ~1k-instruction loop with an alternating branch on both backends
+//! with `general.pipe_trace` set, then parses the trace file. Every fetched
+//! instruction must retire or be marked as flushed exactly once, retired
+//! instructions must pass through the stages in order, and the branch
+//! mispredictions must leave squashed instructions in the trace.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::config::{Config, PipeTraceFormat};
+use rvsim_core::core::pipeline::engine::BackendType;
+use std::collections::HashMap;
+
+/// Runs 200 iterations of a 5- to 8-instruction loop with `pipe_trace` written in
+/// `format`, returning the trace text and the retired instruction count.
+fn run(backend: BackendType, format: PipeTraceFormat) -> (String, u64) {
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.pipeline.width = 2;
+    config.general.pipe_trace = Some(file.path().to_str().unwrap().to_owned());
+    config.general.pipe_trace_format = format;
+    let base = config.system.ram_base;
+
+    let b = InstructionBuilder::new;
+    let program = [
+        b().addi(8, 0, 200).build(),
+        // loop:
+        b().andi(9, 8, 1).build(),
+        b().beq(9, 0, 8).build(),
+        b().addi(10, 10, 1).build(),
+        b().addi(11, 11, 2).build(),
+        b().add(12, 10, 11).build(),
+        b().addi(8, 8, -1).build(),
+        b().bne(8, 0, -24).build(),
+        b().addi(17, 0, 93).build(),
+        b().ecall().build(),
+    ];
+    let sim = TestContext::with_config(&config).load_program(base, &program).run_to_exit(100_000);
+    let retired = sim.cpu.stats.instructions_retired;
+    // Dropping the simulator closes the trace, squashing what is left in flight.
+    drop(sim);
+    (std::fs::read_to_string(file.path()).unwrap(), retired)
+}
+
+#[test]
+fn konata_trace_covers_every_instruction() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let (text, retired) = run(backend, PipeTraceFormat::Konata);
+        let mut lines = text.lines();
+        assert_eq!(lines.next(), Some("Kanata\t0004"), "{backend:?}");
+        assert!(lines.next().unwrap().starts_with("C=\t"), "{backend:?}");
+
+        let mut stages: HashMap<u64, Vec<String>> = HashMap::new();
+        let mut ends: HashMap<u64, u8> = HashMap::new();
+        for line in lines {
+            let f: Vec<&str> = line.split('\t').collect();
+            match f[0] {
+                "C" => assert!(f[1].parse::<u64>().unwrap() > 0, "{backend:?}: {line}"),
+                "I" => assert!(stages.insert(f[1].parse().unwrap(), Vec::new()).is_none()),
+                "S" => stages.get_mut(&f[1].parse().unwrap()).unwrap().push(f[3].to_owned()),
+                "R" => assert!(ends.insert(f[1].parse().unwrap(), f[3].parse().unwrap()).is_none()),
+                "L" => {}
+                _ => panic!("{backend:?}: unexpected line {line}"),
+            }
+        }
+
+        assert_eq!(stages.len(), ends.len(), "{backend:?}: every instruction ends once");
+        let done = ends.values().filter(|&&t| t == 0).count() as u64;
+        let flushed = ends.values().filter(|&&t| t == 1).count();
+        assert_eq!(done, retired, "{backend:?}");
+        assert!(done > 1000, "{backend:?}: {done} retired");
+        assert!(flushed > 0, "{backend:?}: mispredictions squash instructions");
+
+        let order = ["F", "Dc", "Rn", "Ex", "Mem", "Wb", "Cm"];
+        for (id, s) in &stages {
+            let pos: Vec<usize> =
+                s.iter().map(|n| order.iter().position(|o| o == n).unwrap()).collect();
+            assert!(pos.is_sorted_by(|a, b| a < b), "{backend:?}: {id} stages {s:?}");
+            if ends[id] == 0 {
+                for stage in ["F", "Dc", "Rn", "Ex", "Cm"] {
+                    assert!(s.iter().any(|n| n == stage), "{backend:?}: {id} skipped {stage}");
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn o3pipeview_trace_is_ordered_by_sequence_number() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let (text, retired) = run(backend, PipeTraceFormat::O3PipeView);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len() % 7, 0, "{backend:?}");
+
+        let mut last_seq = None;
+        let (mut done, mut squashed) = (0, 0);
+        for record in lines.chunks(7) {
+            let ticks: Vec<u64> =
+                record.iter().map(|l| l.split(':').nth(2).unwrap().parse().unwrap()).collect();
+            let kinds: Vec<&str> = record.iter().map(|l| l.split(':').nth(1).unwrap()).collect();
+            assert_eq!(
+                kinds,
+                ["fetch", "decode", "rename", "dispatch", "issue", "complete", "retire"],
+                "{backend:?}"
+            );
+            let seq: u64 = record[0].split(':').nth(5).unwrap().parse().unwrap();
+            assert!(last_seq < Some(seq), "{backend:?}: seq {seq} out of order");
+            last_seq = Some(seq);
+            assert!(ticks[0] > 0 && ticks[0].is_multiple_of(1000), "{backend:?}");
+
+            if ticks[6] == 0 {
+                squashed += 1;
+            } else {
+                done += 1;
+                assert!(ticks.is_sorted(), "{backend:?}: {record:?}");
+            }
+        }
+        assert_eq!(done, retired, "{backend:?}");
+        assert!(squashed > 0, "{backend:?}");
+    }
+}
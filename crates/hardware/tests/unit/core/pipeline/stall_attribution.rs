@@ -0,0 +1,112 @@
+//! Stall Attribution Tests.
+//!
+//! Each microbenchmark is built to lose most of its non-retiring cycles to
+//! one cause: straight-line code through a cold I-cache, a chain of
+//! worst-case divides, a stream of loads through a cold D-cache, and a loop
+//! whose backward branch the static predictor always gets wrong. On both
+//! backends the matching `stall_cycles` bucket must be the largest.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::Simulator;
+use rvsim_core::common::RegIdx;
+use rvsim_core::config::Config;
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::stats::StallCause;
+
+/// Offset of the load data from the start of RAM.
+const DATA_OFFSET: u64 = 0x10_0000;
+
+/// Runs `program` then exits on `backend` with `configure` applied, with x7
+/// pointing at the data region, x14 = `u64::MAX` and x15 = 1.
+fn run(backend: BackendType, configure: impl FnOnce(&mut Config), program: &[u32]) -> Simulator {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.pipeline.width = 2;
+    configure(&mut config);
+    let base = config.system.ram_base;
+
+    let b = InstructionBuilder::new;
+    let mut program = program.to_vec();
+    program.extend([b().addi(17, 0, 93).build(), b().ecall().build()]);
+    let mut ctx = TestContext::with_config(&config).load_program(base, &program);
+    for (r, v) in [(7, base + DATA_OFFSET), (14, u64::MAX), (15, 1)] {
+        ctx.sim.write_reg(RegIdx::new(r), v);
+    }
+    ctx.run_to_exit(1_000_000)
+}
+
+/// Asserts that `expected` is the largest stall bucket.
+fn assert_dominant(sim: &Simulator, expected: StallCause, backend: BackendType) {
+    let stalls = &sim.cpu.stats.stall_cycles;
+    let top = StallCause::ALL.into_iter().max_by_key(|&c| stalls[c as usize]).unwrap();
+    assert!(stalls[expected as usize] > 0, "{backend:?}: no {} cycles", expected.name());
+    assert_eq!(top, expected, "{backend:?}: {stalls:?}");
+}
+
+#[test]
+fn cold_icache_is_charged_to_icache_miss() {
+    let b = InstructionBuilder::new;
+    let program: Vec<u32> = (0..2048).map(|i| b().addi(5 + (i % 8), 0, i as i32).build()).collect();
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let sim = run(backend, |c| c.cache.l1_i.enabled = true, &program);
+        assert_dominant(&sim, StallCause::ICacheMiss, backend);
+    }
+}
+
+#[test]
+fn divide_chain_is_charged_to_long_latency_fu() {
+    // divu x5, x5, x15: u64::MAX / 1 is the divider's worst case.
+    let divu = (1 << 25) | (15 << 20) | (5 << 15) | (0b101 << 12) | (5 << 7) | 0x33;
+    let b = InstructionBuilder::new;
+    let mut program = vec![b().addi(5, 14, 0).build()];
+    program.extend([divu; 32]);
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let sim = run(backend, |_| {}, &program);
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(5)), u64::MAX, "{backend:?}");
+        assert_dominant(&sim, StallCause::LongLatencyFu, backend);
+    }
+}
+
+#[test]
+fn cold_dcache_is_charged_to_dcache_miss() {
+    // Eight blocks of 30 loads, one per cache line, with no branches.
+    let b = InstructionBuilder::new;
+    let mut program = Vec::new();
+    for _ in 0..8 {
+        program.extend((0..30).map(|i| b().ld(6, 7, 64 * i).build()));
+        program.push(b().addi(7, 7, 64 * 30).build());
+    }
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let sim = run(
+            backend,
+            |c| {
+                c.cache.l1_d.enabled = true;
+                c.cache.l2.enabled = true;
+            },
+            &program,
+        );
+        assert!(sim.cpu.stats.dcache_misses >= 240, "{backend:?}");
+        assert_dominant(&sim, StallCause::DCacheMiss, backend);
+    }
+}
+
+#[test]
+fn mispredicted_loop_is_charged_to_branch_recovery() {
+    // The default static predictor predicts the backward branch not-taken,
+    // so every iteration but the last redirects fetch.
+    let b = InstructionBuilder::new;
+    let program = [
+        b().addi(8, 0, 300).build(),
+        // loop:
+        b().addi(10, 10, 1).build(),
+        b().addi(11, 11, 2).build(),
+        b().addi(8, 8, -1).build(),
+        b().bne(8, 0, -12).build(),
+    ];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let sim = run(backend, |_| {}, &program);
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(10)), 300, "{backend:?}");
+        assert_dominant(&sim, StallCause::BranchRecovery, backend);
+    }
+}
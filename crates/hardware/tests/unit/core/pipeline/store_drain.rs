@@ -0,0 +1,98 @@
+//! Store Buffer Drain Tests.
+//!
+//! A memset-style run of back-to-back stores at width 4 retires faster than
+//! a single store port can drain the store buffer, so with one port the
+//! buffer fills and stalls dispatch. Each additional store port drains one
+//! more committed store per cycle, unless `store_buffer_drain_rate` sets
+//! the drain rate on its own.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::Simulator;
+use rvsim_core::common::{PhysAddr, RegIdx};
+use rvsim_core::config::Config;
+use rvsim_core::core::pipeline::engine::BackendType;
+
+/// Offset of the memset target from the start of RAM.
+const DATA_OFFSET: u64 = 0x10_0000;
+
+/// Number of `sd` instructions in the memset.
+const STORES: u64 = 1024;
+
+/// Runs the memset on `backend` with `store_ports` and the given store
+/// buffer drain rate, and returns the simulator once it exits.
+fn run(backend: BackendType, store_ports: usize, drain_rate: usize) -> Simulator {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.pipeline.width = 4;
+    config.pipeline.store_ports = store_ports;
+    config.pipeline.store_buffer_drain_rate = drain_rate;
+    config.cache.l1_d.enabled = true;
+    config.cache.l1_d.mshr_count = 8;
+    let base = config.system.ram_base;
+
+    let b = InstructionBuilder::new;
+    let mut program = Vec::new();
+    for _ in 0..STORES / 128 {
+        program.extend((0..128).map(|i| b().sd(10, 0, 8 * i).build()));
+        program.push(b().addi(10, 10, 8 * 128).build());
+    }
+    program.extend([b().addi(17, 0, 93).build(), b().ecall().build()]);
+
+    let mut ctx = TestContext::with_config(&config).load_program(base, &program);
+    for offset in (0..8 * STORES).step_by(8) {
+        ctx.sim.cpu.bus.bus.write_u64(PhysAddr::new(base + DATA_OFFSET + offset), u64::MAX);
+    }
+    ctx.sim.write_reg(RegIdx::new(10), base + DATA_OFFSET);
+    ctx.run_to_exit(100_000)
+}
+
+/// Returns stores retired per cycle.
+fn stores_per_cycle(sim: &Simulator) -> f64 {
+    STORES as f64 / sim.cpu.stats.cycles as f64
+}
+
+#[test]
+fn two_store_ports_double_memset_throughput() {
+    // The in-order backend's Memory1 blocks for each access's latency, so
+    // only the out-of-order backend can issue stores fast enough to saturate
+    // two ports.
+    let one = run(BackendType::OutOfOrder, 1, 0);
+    let two = run(BackendType::OutOfOrder, 2, 0);
+    assert!(stores_per_cycle(&one) <= 1.0, "{}", stores_per_cycle(&one));
+    assert!(stores_per_cycle(&two) > 1.8, "{}", stores_per_cycle(&two));
+    assert!(
+        two.cpu.stats.stalls_store_buffer_full * 2 < one.cpu.stats.stalls_store_buffer_full,
+        "{} vs {}",
+        two.cpu.stats.stalls_store_buffer_full,
+        one.cpu.stats.stalls_store_buffer_full
+    );
+}
+
+#[test]
+fn multi_port_drain_writes_every_store() {
+    let base = Config::default().system.ram_base + DATA_OFFSET;
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut sim = run(backend, 2, 0);
+        for offset in (0..8 * STORES).step_by(8) {
+            let addr = PhysAddr::new(base + offset);
+            assert_eq!(sim.cpu.bus.bus.read_u64(addr), 0, "{backend:?} {offset:#x}");
+        }
+    }
+}
+
+#[test]
+fn slow_drain_rate_fills_store_buffer_and_stalls_dispatch() {
+    // Two store ports issue two stores a cycle, but only one drains.
+    let slow = run(BackendType::OutOfOrder, 2, 1);
+    let fast = run(BackendType::OutOfOrder, 2, 2);
+    assert!(slow.cpu.stats.stalls_store_buffer_full > 0);
+    assert!(slow.cpu.stats.stalls_store_buffer_full <= slow.cpu.stats.stalls_dispatch);
+    assert!(stores_per_cycle(&slow) <= 1.0, "{}", stores_per_cycle(&slow));
+    assert!(
+        fast.cpu.stats.stalls_store_buffer_full * 2 < slow.cpu.stats.stalls_store_buffer_full,
+        "{} vs {}",
+        fast.cpu.stats.stalls_store_buffer_full,
+        slow.cpu.stats.stalls_store_buffer_full
+    );
+}
@@ -0,0 +1,103 @@
+//! WFI Wakeup Tests.
+//!
+//! A hart executing `wfi` with the CLINT timer compare in the future parks:
+//! nothing after the `wfi` retires while `mtime` advances towards
+//! `mtimecmp`. It resumes in the cycle MTIP is raised, either by taking the
+//! timer interrupt (`mstatus.MIE` set) or by continuing after the `wfi`
+//! (`mstatus.MIE` clear).
+
+use crate::common::builder::instruction::InstructionBuilder;
+use rvsim_core::Simulator;
+use rvsim_core::common::{PhysAddr, RegIdx};
+use rvsim_core::config::Config;
+use rvsim_core::core::arch::csr;
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::soc::System;
+
+/// `wfi`.
+const WFI: u32 = 0x1050_0073;
+
+/// `mret`.
+const MRET: u32 = 0x3020_0073;
+
+/// Offset of the trap handler from the start of RAM.
+const HANDLER_OFFSET: u64 = 0x800;
+
+/// Offset of `mtimecmp` for hart 0 within the CLINT.
+const MTIMECMP_OFFSET: u64 = 0x4000;
+
+/// Timer compare value; `mtime` advances once per cycle.
+const MTIMECMP: u64 = 500;
+
+/// Runs `wfi` then `x7 = 42` and spins on `backend`, with machine interrupts
+/// globally enabled if `mie`. The handler counts interrupts in x28 and
+/// disarms the timer. Returns the simulator and the cycle MTIP was first
+/// raised.
+fn run(backend: BackendType, mie: bool) -> (Simulator, u64) {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.system.clint_divider = 1;
+    let base = config.system.ram_base;
+
+    let b = InstructionBuilder::new;
+    let program = [
+        (0x00, WFI),
+        (0x04, b().addi(7, 0, 42).build()),
+        (0x08, b().jal(0, 0).build()),
+        (HANDLER_OFFSET, b().addi(28, 28, 1).build()),
+        (HANDLER_OFFSET + 4, b().addi(11, 0, -1).build()),
+        (HANDLER_OFFSET + 8, b().sd(10, 11, 0).build()),
+        (HANDLER_OFFSET + 12, MRET),
+    ];
+    let mut sim = Simulator::new(System::new(&config, ""), &config);
+    for (offset, inst) in program {
+        sim.cpu.bus.bus.write_u32(PhysAddr::new(base + offset), inst);
+    }
+    let mtimecmp = config.system.clint_base + MTIMECMP_OFFSET;
+    sim.cpu.bus.bus.write_u64(PhysAddr::new(mtimecmp), MTIMECMP);
+    sim.cpu.pc = base;
+    sim.cpu.direct_mode = false;
+    sim.cpu.csrs.mtvec = base + HANDLER_OFFSET;
+    sim.cpu.csrs.mie = csr::MIE_MTIE;
+    if mie {
+        sim.cpu.csrs.mstatus |= csr::MSTATUS_MIE;
+    }
+    sim.write_reg(RegIdx::new(10), mtimecmp);
+
+    let mut parked = false;
+    let mut raised_at = None;
+    for _ in 0..2_000 {
+        sim.tick().unwrap();
+        if raised_at.is_none() && sim.cpu.csrs.mip & csr::MIP_MTIP != 0 {
+            assert!(!sim.cpu.wfi_waiting, "{backend:?}: still waiting with MTIP raised");
+            raised_at = Some(sim.cpu.stats.cycles);
+        } else if raised_at.is_none() {
+            parked |= sim.cpu.wfi_waiting;
+            assert_eq!(sim.cpu.stats.instructions_retired, u64::from(parked), "{backend:?}");
+        }
+    }
+    assert!(parked, "{backend:?}: never parked in WFI");
+    (sim, raised_at.unwrap())
+}
+
+#[test]
+fn wfi_resumes_when_timer_interrupt_is_taken() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let (sim, raised_at) = run(backend, true);
+        assert!(raised_at >= MTIMECMP, "{backend:?}: woke at cycle {raised_at}");
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(28)), 1, "{backend:?}");
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(7)), 42, "{backend:?}");
+        assert_eq!(sim.cpu.csrs.mcause, (1 << 63) | 7, "{backend:?}");
+        assert_eq!(sim.cpu.csrs.mepc, Config::default().system.ram_base + 4, "{backend:?}");
+    }
+}
+
+#[test]
+fn wfi_resumes_on_pending_timer_with_interrupts_disabled() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let (sim, raised_at) = run(backend, false);
+        assert!(raised_at >= MTIMECMP, "{backend:?}: woke at cycle {raised_at}");
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(28)), 0, "{backend:?}");
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(7)), 42, "{backend:?}");
+    }
+}
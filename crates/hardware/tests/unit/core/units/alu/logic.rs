@@ -450,6 +450,54 @@ fn slt_vs_sltu_neg1_distinction() {
     assert_eq!(alu(AluOp::Sltu, NEG1, ZERO, false), 0);
 }
 
+// ═════════════════════════════════════════════════════════════════════════════
+//  CZERO.EQZ / CZERO.NEZ (Zicond)
+// ═════════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn czero_eqz_zero_condition_clears() {
+    assert_eq!(alu(AluOp::CzeroEqz, 0xDEAD_BEEF_CAFE_BABE, ZERO, false), 0);
+    assert_eq!(alu(AluOp::CzeroEqz, NEG1, ZERO, false), 0);
+}
+
+#[test]
+fn czero_eqz_nonzero_condition_passes_rs1() {
+    for cond in [ONE, NEG1, I64_MIN, HIGH_BYTE, 1 << 32] {
+        assert_eq!(alu(AluOp::CzeroEqz, 0xDEAD_BEEF_CAFE_BABE, cond, false), 0xDEAD_BEEF_CAFE_BABE);
+    }
+}
+
+#[test]
+fn czero_nez_zero_condition_passes_rs1() {
+    assert_eq!(alu(AluOp::CzeroNez, 0xDEAD_BEEF_CAFE_BABE, ZERO, false), 0xDEAD_BEEF_CAFE_BABE);
+    assert_eq!(alu(AluOp::CzeroNez, I64_MAX, ZERO, false), I64_MAX);
+}
+
+#[test]
+fn czero_nez_nonzero_condition_clears() {
+    for cond in [ONE, NEG1, I64_MIN, HIGH_BYTE, 1 << 32] {
+        assert_eq!(alu(AluOp::CzeroNez, 0xDEAD_BEEF_CAFE_BABE, cond, false), 0);
+    }
+}
+
+#[test]
+fn czero_tests_full_condition_register() {
+    // Only the upper word is set: the condition is still non-zero.
+    assert_eq!(alu(AluOp::CzeroEqz, ALTERNATING_5, 1 << 63, false), ALTERNATING_5);
+    assert_eq!(alu(AluOp::CzeroNez, ALTERNATING_5, 1 << 63, false), 0);
+}
+
+#[test]
+fn czero_pair_selects_between_values() {
+    // (cond ? a : b) == czero.eqz(a, cond) | czero.nez(b, cond)
+    for cond in [ZERO, ONE, NEG1] {
+        let picked = alu(AluOp::CzeroEqz, ALTERNATING_A, cond, false)
+            | alu(AluOp::CzeroNez, LOW_BYTE, cond, false);
+        let expected = if cond != 0 { ALTERNATING_A } else { LOW_BYTE };
+        assert_eq!(picked, expected, "cond={cond:#x}");
+    }
+}
+
 // ═════════════════════════════════════════════════════════════════════════════
 //  Bitwise operations: every bit position
 // ═════════════════════════════════════════════════════════════════════════════
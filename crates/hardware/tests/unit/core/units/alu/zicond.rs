@@ -0,0 +1,92 @@
+//! Zicond Conditional-Zero Tests.
+//!
+//! Runs `czero.eqz` / `czero.nez` through the full pipeline with zero and
+//! non-zero condition operands, and checks that the encodings are illegal
+//! when Zicond is disabled.
+//!
+//! Reference: RISC-V Zicond extension, version 1.0.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::common::PhysAddr;
+use rvsim_core::isa::disasm::disassemble;
+use rvsim_core::isa::privileged::cause::exception;
+
+const BASE_ADDR: u64 = 0x8000_0000;
+const MEM_SIZE: usize = 0x2000;
+const HANDLER: u64 = BASE_ADDR + 0x800;
+
+const CZERO_EQZ: u32 = 0b101;
+const CZERO_NEZ: u32 = 0b111;
+
+const VALUE: u64 = 0x1234_5678_9ABC_DEF0;
+
+/// Encodes `czero.{eqz,nez} rd, rs1, rs2`.
+fn czero(funct3: u32, rd: u32, rs1: u32, rs2: u32) -> u32 {
+    (0b0000111 << 25) | (rs2 << 20) | (rs1 << 15) | (funct3 << 12) | (rd << 7) | 0x33
+}
+
+/// Runs `program` with x5 = `VALUE`, x6 = `cond` and x7 preset to a marker,
+/// and traps vectored to a spin loop at `HANDLER`.
+fn run(program: &[u32], cond: u64, zicond: bool) -> TestContext {
+    let nop = InstructionBuilder::new().nop().build();
+    let mut code = program.to_vec();
+    code.extend([nop; 6]);
+    let mut tc = TestContext::new().with_memory(MEM_SIZE, BASE_ADDR).load_program(BASE_ADDR, &code);
+    tc.sim
+        .cpu
+        .bus
+        .bus
+        .write_u32(PhysAddr::new(HANDLER), InstructionBuilder::new().jal(0, 0).build());
+    tc.cpu_mut().direct_mode = false;
+    tc.cpu_mut().csrs.mtvec = HANDLER;
+    tc.cpu_mut().zicond = zicond;
+    tc.set_reg(5, VALUE);
+    tc.set_reg(6, cond);
+    tc.set_reg(7, 0xAAAA);
+    tc.run(100);
+    tc
+}
+
+#[test]
+fn czero_eqz_executes() {
+    assert_eq!(run(&[czero(CZERO_EQZ, 7, 5, 6)], 0, true).get_reg(7), 0);
+    assert_eq!(run(&[czero(CZERO_EQZ, 7, 5, 6)], 1, true).get_reg(7), VALUE);
+    assert_eq!(run(&[czero(CZERO_EQZ, 7, 5, 6)], 1 << 40, true).get_reg(7), VALUE);
+}
+
+#[test]
+fn czero_nez_executes() {
+    assert_eq!(run(&[czero(CZERO_NEZ, 7, 5, 6)], 0, true).get_reg(7), VALUE);
+    assert_eq!(run(&[czero(CZERO_NEZ, 7, 5, 6)], 1, true).get_reg(7), 0);
+    assert_eq!(run(&[czero(CZERO_NEZ, 7, 5, 6)], u64::MAX, true).get_reg(7), 0);
+}
+
+#[test]
+fn czero_result_forwards_to_dependent() {
+    // x8 = x7 + x5 uses the czero result in the next instruction.
+    let add = InstructionBuilder::new().add(8, 7, 5).build();
+    assert_eq!(run(&[czero(CZERO_EQZ, 7, 5, 6), add], 3, true).get_reg(8), VALUE * 2);
+    assert_eq!(run(&[czero(CZERO_NEZ, 7, 5, 6), add], 3, true).get_reg(8), VALUE);
+}
+
+#[test]
+fn czero_is_illegal_when_zicond_disabled() {
+    for funct3 in [CZERO_EQZ, CZERO_NEZ] {
+        let tc = run(&[czero(funct3, 7, 5, 6)], 1, false);
+        assert_eq!(tc.cpu().csrs.mcause, exception::ILLEGAL_INSTRUCTION);
+        assert_eq!(tc.get_reg(7), 0xAAAA);
+    }
+}
+
+#[test]
+fn czero_reserved_funct3_is_illegal() {
+    let tc = run(&[czero(0b000, 7, 5, 6)], 1, true);
+    assert_eq!(tc.cpu().csrs.mcause, exception::ILLEGAL_INSTRUCTION);
+}
+
+#[test]
+fn czero_disassembles() {
+    assert_eq!(disassemble(czero(CZERO_EQZ, 10, 11, 12)), "czero.eqz a0, a1, a2");
+    assert_eq!(disassemble(czero(CZERO_NEZ, 10, 11, 12)), "czero.nez a0, a1, a2");
+}
@@ -13,6 +13,7 @@ use rvsim_core::core::units::bru::predictors::perceptron::PerceptronPredictor;
 use rvsim_core::core::units::bru::predictors::static_bp::StaticPredictor;
 use rvsim_core::core::units::bru::predictors::tage::TagePredictor;
 use rvsim_core::core::units::bru::predictors::tournament::TournamentPredictor;
+use rvsim_core::core::units::bru::ras::RasOp;
 use rvsim_core::core::units::bru::{BranchPredictor, Ghr};
 
 // ══════════════════════════════════════════════════════════
@@ -352,40 +353,38 @@ fn all_predictors_use_btb() {
 // 7. RAS Integration (all predictors)
 // ══════════════════════════════════════════════════════════
 
-/// All predictors correctly push/pop the RAS via on_call/on_return/predict_return.
+/// All predictors correctly push/pop the RAS via update_ras/predict_return.
 #[test]
 fn all_predictors_use_ras() {
-    let call_pc = 0x1000;
     let ret_addr = 0x1004;
-    let call_target = 0x2000;
 
     let mut static_bp = StaticPredictor::new(64, 4, 8);
-    static_bp.on_call(call_pc, ret_addr, call_target);
+    static_bp.update_ras(RasOp::Push(ret_addr));
     assert_eq!(static_bp.predict_return(), Some(ret_addr));
-    static_bp.on_return();
+    static_bp.update_ras(RasOp::Pop);
     assert_eq!(static_bp.predict_return(), None);
 
     let mut gshare = GSharePredictor::new(64, 4, 8);
-    gshare.on_call(call_pc, ret_addr, call_target);
+    gshare.update_ras(RasOp::Push(ret_addr));
     assert_eq!(gshare.predict_return(), Some(ret_addr));
-    gshare.on_return();
+    gshare.update_ras(RasOp::Pop);
     assert_eq!(gshare.predict_return(), None);
 
     let mut perceptron = default_perceptron();
-    perceptron.on_call(call_pc, ret_addr, call_target);
+    perceptron.update_ras(RasOp::Push(ret_addr));
     assert_eq!(perceptron.predict_return(), Some(ret_addr));
-    perceptron.on_return();
+    perceptron.update_ras(RasOp::Pop);
     assert_eq!(perceptron.predict_return(), None);
 
     let mut tage = default_tage();
-    tage.on_call(call_pc, ret_addr, call_target);
+    tage.update_ras(RasOp::Push(ret_addr));
     assert_eq!(tage.predict_return(), Some(ret_addr));
-    tage.on_return();
+    tage.update_ras(RasOp::Pop);
     assert_eq!(tage.predict_return(), None);
 
     let mut tournament = default_tournament();
-    tournament.on_call(call_pc, ret_addr, call_target);
+    tournament.update_ras(RasOp::Push(ret_addr));
     assert_eq!(tournament.predict_return(), Some(ret_addr));
-    tournament.on_return();
+    tournament.update_ras(RasOp::Pop);
     assert_eq!(tournament.predict_return(), None);
 }
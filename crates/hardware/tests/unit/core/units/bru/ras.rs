@@ -1,9 +1,10 @@
 //! Return Address Stack (RAS) Tests.
 //!
 //! Verifies push/pop/top semantics, overflow behaviour, underflow safety,
-//! and correct LIFO ordering for return address prediction.
+//! correct LIFO ordering for return address prediction, and snapshot
+//! recovery of the speculative stack.
 
-use rvsim_core::core::units::bru::ras::Ras;
+use rvsim_core::core::units::bru::ras::{Ras, RasOp};
 
 // ══════════════════════════════════════════════════════════
 // 1. Basic push/pop
@@ -175,3 +176,73 @@ fn push_max_address() {
     ras.push(u64::MAX);
     assert_eq!(ras.pop(), Some(u64::MAX));
 }
+
+// ══════════════════════════════════════════════════════════
+// 7. Speculative recovery
+// ══════════════════════════════════════════════════════════
+
+#[test]
+fn restore_undoes_wrong_path_pop_then_push() {
+    let mut ras = Ras::new(8);
+    ras.push(0x1004);
+    ras.push(0x2008);
+    let snap = ras.snapshot();
+
+    // Wrong path: a return pops 0x2008, then a call overwrites its slot.
+    ras.apply(RasOp::Pop);
+    ras.apply(RasOp::Push(0xDEAD));
+    assert_eq!(ras.top(), Some(0xDEAD));
+
+    ras.restore(snap);
+    assert_eq!(ras.pop(), Some(0x2008), "Top entry must be restored, not just the pointer");
+    assert_eq!(ras.pop(), Some(0x1004));
+}
+
+#[test]
+fn restore_undoes_wrong_path_pushes() {
+    let mut ras = Ras::new(8);
+    ras.push(0x1004);
+    let snap = ras.snapshot();
+    ras.apply(RasOp::Push(0xA));
+    ras.apply(RasOp::Push(0xB));
+    ras.restore(snap);
+    assert_eq!(ras.pop(), Some(0x1004));
+    assert_eq!(ras.pop(), None);
+}
+
+#[test]
+fn restore_committed_discards_speculative_state() {
+    let mut ras = Ras::new(8);
+    ras.apply(RasOp::Push(0x1004));
+    ras.commit(RasOp::Push(0x1004));
+    ras.apply(RasOp::Push(0x2008));
+    ras.apply(RasOp::PopThenPush(0x300C));
+
+    ras.restore_committed();
+    assert_eq!(ras.pop(), Some(0x1004));
+    assert_eq!(ras.pop(), None);
+}
+
+// ══════════════════════════════════════════════════════════
+// 8. Call/return classification (Table 2.1)
+// ══════════════════════════════════════════════════════════
+
+#[test]
+fn ras_op_classification() {
+    let ret = 0x1004;
+    // jal ra, 0x10 / jal x0, 0x10
+    assert_eq!(RasOp::for_inst(0x0100_00EF, ret), Some(RasOp::Push(ret)));
+    assert_eq!(RasOp::for_inst(0x0100_006F, ret), None);
+    // jalr t0, 0(a0) — call through t0
+    assert_eq!(RasOp::for_inst(0x0005_02E7, ret), Some(RasOp::Push(ret)));
+    // jalr x0, 0(ra) — ret
+    assert_eq!(RasOp::for_inst(0x0000_8067, ret), Some(RasOp::Pop));
+    // jalr ra, 0(ra) — rd == rs1 is a push, not a swap
+    assert_eq!(RasOp::for_inst(0x0000_80E7, ret), Some(RasOp::Push(ret)));
+    // jalr ra, 0(t0) — coroutine swap
+    assert_eq!(RasOp::for_inst(0x0002_80E7, ret), Some(RasOp::PopThenPush(ret)));
+    // jalr x0, 0(a0) — indirect jump
+    assert_eq!(RasOp::for_inst(0x0005_0067, ret), None);
+    // addi ra, ra, 0 — not a jump
+    assert_eq!(RasOp::for_inst(0x0000_8093, ret), None);
+}
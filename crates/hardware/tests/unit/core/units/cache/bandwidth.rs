@@ -0,0 +1,77 @@
+//! Bandwidth Accounting Tests.
+//!
+//! Streams stores through a small L1D and L2 (L3 disabled) on both backends
+//! and checks the bytes accounted on each link: every L1D miss fills a line
+//! from L2, every L2 miss fills one from DRAM, and nearly every line written
+//! is eventually evicted dirty and written back to DRAM, so the DRAM
+//! writeback traffic approaches one line per L2 miss.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::common::RegIdx;
+use rvsim_core::config::{Config, Prefetcher};
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::core::units::cache::bandwidth::{Link, Traffic};
+
+/// Offset from the start of RAM of the buffer written.
+const DATA: u64 = 0x10_0000;
+/// Bytes written, 16 times the L2 capacity.
+const STREAM: u64 = 256 * 1024;
+const LINE: u64 = 64;
+
+fn streaming_config(backend: BackendType) -> Config {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.general.bandwidth_interval = 1000;
+    for (cache, size) in [(&mut config.cache.l1_d, 4 * 1024), (&mut config.cache.l2, 16 * 1024)] {
+        cache.enabled = true;
+        cache.size_bytes = size;
+        cache.line_bytes = LINE as usize;
+        cache.ways = 4;
+        cache.prefetcher = Prefetcher::None;
+    }
+    config.cache.l3.enabled = false;
+    config
+}
+
+#[test]
+fn streaming_stores_write_back_a_line_per_l2_miss() {
+    let b = InstructionBuilder::new;
+    // x10 = cursor, x11 = end; one `sd` per double word.
+    let program = [
+        b().sd(10, 10, 0).build(),
+        b().addi(10, 10, 8).build(),
+        b().bne(10, 11, -8).build(),
+        b().addi(17, 0, 93).build(),
+        b().ecall().build(),
+    ];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let config = streaming_config(backend);
+        let base = config.system.ram_base;
+        let mut ctx = TestContext::with_config(&config).load_program(base, &program);
+        ctx.sim.write_reg(RegIdx::new(10), base + DATA);
+        ctx.sim.write_reg(RegIdx::new(11), base + DATA + STREAM);
+        let sim = ctx.run_to_exit(2_000_000);
+
+        let stats = &sim.cpu.stats;
+        let bw = &stats.bandwidth;
+        assert!(stats.l2_misses >= STREAM / LINE, "{backend:?}: {} L2 misses", stats.l2_misses);
+        assert_eq!(bw.bytes(Link::L2, Traffic::Demand), stats.dcache_misses * LINE, "{backend:?}");
+        assert_eq!(bw.bytes(Link::Dram, Traffic::Demand), stats.l2_misses * LINE, "{backend:?}");
+        assert_eq!(bw.link_bytes(Link::L3), 0, "{backend:?}: L3 is disabled");
+
+        let written = bw.bytes(Link::Dram, Traffic::Writeback);
+        let missed = stats.l2_misses * LINE;
+        assert!(written <= missed, "{backend:?}: {written} B written back, {missed} B missed");
+        assert!(
+            written * 10 >= missed * 9,
+            "{backend:?}: {written} B written back, {missed} B missed"
+        );
+
+        let timeline = bw.timeline();
+        assert!(timeline.len() > 1, "{backend:?}");
+        let dram: u64 = timeline.iter().map(|bin| bin[Link::Dram as usize]).sum();
+        assert_eq!(dram, bw.link_bytes(Link::Dram), "{backend:?}");
+        assert!(bw.gb_per_sec(dram, stats.cycles) > 0.0, "{backend:?}");
+    }
+}
@@ -1,2 +1,3 @@
+pub mod bandwidth;
 pub mod cache_sim;
 pub mod policies;
@@ -0,0 +1,106 @@
+//! Floating-Point Context Status (`mstatus.FS`) Tests.
+//!
+//! With FS = Off every FP instruction and FP CSR access raises an
+//! illegal-instruction exception, so a kernel can switch FP context lazily.
+//! Otherwise any instruction that writes an FP register or `fflags` moves
+//! FS to Dirty (and sets `mstatus.SD`), while FP stores leave it unchanged.
+//!
+//! Reference: RISC-V Privileged ISA, §3.1.6.6 "Extension Context Status".
+
+use crate::common::builder::instruction::InstructionBuilder;
+use rvsim_core::Simulator;
+use rvsim_core::common::{PhysAddr, RegIdx};
+use rvsim_core::config::Config;
+use rvsim_core::core::arch::csr::{
+    self, MSTATUS_FS, MSTATUS_FS_CLEAN, MSTATUS_FS_DIRTY, MSTATUS_FS_INIT, MSTATUS_FS_OFF,
+    MSTATUS_SD,
+};
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::isa::privileged::cause::exception;
+use rvsim_core::soc::System;
+
+/// Offset of the trap handler (a spin loop) from the start of RAM.
+const HANDLER_OFFSET: u64 = 0x800;
+
+/// `fadd.s f7, f5, f6` (dynamic rounding).
+const FADD_S: u32 = (6 << 20) | (5 << 15) | (0b111 << 12) | (7 << 7) | 0x53;
+
+/// `fsw f5, 0(x10)`.
+const FSW: u32 = (5 << 20) | (10 << 15) | (0b010 << 12) | 0x27;
+
+/// `csrrs x7, fcsr, x0` (`frcsr x7`).
+const FRCSR: u32 = (0x003 << 20) | (0b010 << 12) | (7 << 7) | 0x73;
+
+/// Runs `inst` followed by a spin loop on `backend` with `mstatus.FS` = `fs`,
+/// returning the simulator once the pipeline has settled.
+fn run(backend: BackendType, inst: u32, fs: u64) -> Simulator {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    let base = config.system.ram_base;
+
+    let b = InstructionBuilder::new;
+    let mut sim = Simulator::new(System::new(&config, ""), &config);
+    for (i, word) in [inst, b().jal(0, 0).build()].into_iter().enumerate() {
+        sim.cpu.bus.bus.write_u32(PhysAddr::new(base + 4 * i as u64), word);
+    }
+    sim.cpu.bus.bus.write_u32(PhysAddr::new(base + HANDLER_OFFSET), b().jal(0, 0).build());
+    sim.cpu.pc = base;
+    sim.cpu.direct_mode = false;
+    sim.cpu.csrs.mtvec = base + HANDLER_OFFSET;
+    sim.cpu.csrs.mstatus = (sim.cpu.csrs.mstatus & !MSTATUS_FS) | fs;
+    sim.write_reg(RegIdx::new(7), 0xAAAA);
+    sim.write_reg(RegIdx::new(10), base + 0x1000);
+    for _ in 0..100 {
+        sim.tick().unwrap();
+    }
+    sim
+}
+
+/// Returns `mstatus.FS` as read by software.
+fn fs(sim: &Simulator) -> u64 {
+    sim.cpu.csr_read(csr::MSTATUS) & MSTATUS_FS
+}
+
+#[test]
+fn fadd_with_fs_off_traps() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let sim = run(backend, FADD_S, MSTATUS_FS_OFF);
+        assert_eq!(sim.cpu.csrs.mcause, exception::ILLEGAL_INSTRUCTION, "{backend:?}");
+        assert_eq!(sim.cpu.csrs.mtval, u64::from(FADD_S), "{backend:?}");
+        assert_eq!(fs(&sim), MSTATUS_FS_OFF, "{backend:?}");
+    }
+}
+
+#[test]
+fn fp_csr_access_with_fs_off_traps() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let sim = run(backend, FRCSR, MSTATUS_FS_OFF);
+        assert_eq!(sim.cpu.csrs.mcause, exception::ILLEGAL_INSTRUCTION, "{backend:?}");
+        assert_eq!(sim.cpu.csrs.mtval, u64::from(FRCSR), "{backend:?}");
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(7)), 0xAAAA, "{backend:?}");
+
+        let sim = run(backend, FRCSR, MSTATUS_FS_INIT);
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(7)), 0, "{backend:?}");
+    }
+}
+
+#[test]
+fn fp_register_write_sets_fs_dirty() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        for initial in [MSTATUS_FS_INIT, MSTATUS_FS_CLEAN] {
+            let sim = run(backend, FADD_S, initial);
+            assert_ne!(sim.cpu.csrs.mcause, exception::ILLEGAL_INSTRUCTION, "{backend:?}");
+            assert_eq!(fs(&sim), MSTATUS_FS_DIRTY, "{backend:?}");
+            assert_ne!(sim.cpu.csr_read(csr::MSTATUS) & MSTATUS_SD, 0, "{backend:?}");
+        }
+    }
+}
+
+#[test]
+fn fp_store_leaves_fs_clean() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let sim = run(backend, FSW, MSTATUS_FS_CLEAN);
+        assert_ne!(sim.cpu.csrs.mcause, exception::ILLEGAL_INSTRUCTION, "{backend:?}");
+        assert_eq!(fs(&sim), MSTATUS_FS_CLEAN, "{backend:?}");
+    }
+}
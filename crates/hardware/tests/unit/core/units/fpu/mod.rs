@@ -1,3 +1,4 @@
 pub mod exception_flags;
+pub mod fs_state;
 pub mod nan_handling;
 pub mod rounding_modes;
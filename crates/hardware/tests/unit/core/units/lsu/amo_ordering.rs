@@ -0,0 +1,137 @@
+//! AMO Width and Ordering Tests.
+//!
+//! Runs A-extension instructions through the full pipeline on both backends
+//! and verifies:
+//!   - `.w` AMOs operate on the low 32 bits, sign-extend the loaded value
+//!     into `rd`, and leave the upper word of memory untouched; `.d` AMOs
+//!     operate on all 64 bits.
+//!   - An AMO with `rl` set performs only after every older store has
+//!     drained from the store buffer.
+//!   - An AMO with `aq` set is not overtaken by a younger load.
+//!
+//! Reference: RISC-V ISA Spec Volume I, Section 8.4 (AMOs).
+
+use crate::common::builder::instruction::InstructionBuilder;
+use rvsim_core::Simulator;
+use rvsim_core::common::{PhysAddr, RegIdx};
+use rvsim_core::config::Config;
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::soc::System;
+
+fn b() -> InstructionBuilder {
+    InstructionBuilder::new()
+}
+
+/// Offset from the start of RAM of the data area, held in `x5`.
+const DATA: u64 = 0x1000;
+
+/// Loads `program` followed by `j .`, points `x5` at the data area and
+/// `x6` at `rs2`, and returns the simulator. Two store ports issue stores
+/// faster than the store buffer drains them.
+fn setup(backend: BackendType, program: &[u32], rs2: u64) -> Simulator {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.pipeline.width = 4;
+    config.pipeline.store_ports = 2;
+    config.pipeline.store_buffer_drain_rate = 1;
+    let base = config.system.ram_base;
+    let mut sim = Simulator::new(System::new(&config, ""), &config);
+    let jump = b().jal(0, 0).build();
+    for (i, inst) in program.iter().chain([&jump]).enumerate() {
+        sim.cpu.bus.bus.write_u32(PhysAddr::new(base + 4 * i as u64), *inst);
+    }
+    sim.cpu.pc = base;
+    sim.write_reg(RegIdx::new(5), base + DATA);
+    sim.write_reg(RegIdx::new(6), rs2);
+    sim
+}
+
+/// Runs a single AMO on a memory double word holding `mem` and returns
+/// (`rd`, memory double word afterwards).
+fn run_amo(backend: BackendType, inst: u32, mem: u64, rs2: u64) -> (u64, u64) {
+    let mut sim = setup(backend, &[inst], rs2);
+    let data = PhysAddr::new(sim.cpu.regs.read(RegIdx::new(5)));
+    sim.cpu.bus.bus.write_u64(data, mem);
+    while sim.cpu.stats.instructions_retired < 1 {
+        sim.tick().unwrap();
+    }
+    (sim.cpu.regs.read(RegIdx::new(7)), sim.cpu.bus.bus.read_u64(data))
+}
+
+#[test]
+fn amomax_w_with_negative_operands() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let inst = b().amomax_w(7, 5, 6).build();
+        // max(-5, -3) = -3. The upper bits of rs2 are ignored and the upper
+        // word of memory is left alone.
+        let (rd, mem) = run_amo(backend, inst, 0xAAAA_AAAA_FFFF_FFFB, 0x1234_5678_FFFF_FFFD);
+        assert_eq!(rd, -5i64 as u64, "{backend:?}: rd is the sign-extended old word");
+        assert_eq!(mem, 0xAAAA_AAAA_FFFF_FFFD, "{backend:?}");
+
+        // max(-1, -100) keeps -1.
+        let (rd, mem) = run_amo(backend, inst, 0xFFFF_FFFF, -100i64 as u64);
+        assert_eq!(rd, u64::MAX, "{backend:?}");
+        assert_eq!(mem, 0xFFFF_FFFF, "{backend:?}");
+
+        // Unsigned: 0xFFFF_FFFB is larger than 5.
+        let inst = b().amomaxu_w(7, 5, 6).build();
+        let (rd, mem) = run_amo(backend, inst, 0xFFFF_FFFB, 5);
+        assert_eq!(rd, -5i64 as u64, "{backend:?}: amomaxu.w still sign-extends rd");
+        assert_eq!(mem, 0xFFFF_FFFB, "{backend:?}");
+    }
+}
+
+#[test]
+fn amoadd_w_and_d_widths() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        // .w wraps within the low word and does not carry into the upper one.
+        let inst = b().amoadd_w(7, 5, 6).build();
+        let (rd, mem) = run_amo(backend, inst, 0x0000_0001_FFFF_FFFF, 1);
+        assert_eq!(rd, u64::MAX, "{backend:?}");
+        assert_eq!(mem, 0x0000_0001_0000_0000, "{backend:?}");
+
+        // .d carries across the whole double word and returns it unextended.
+        let inst = b().amoadd_d(7, 5, 6).build();
+        let (rd, mem) = run_amo(backend, inst, 0x0000_0001_FFFF_FFFF, 1);
+        assert_eq!(rd, 0x0000_0001_FFFF_FFFF, "{backend:?}");
+        assert_eq!(mem, 0x0000_0002_0000_0000, "{backend:?}");
+    }
+}
+
+/// Number of stores ahead of the release AMO.
+const STORES: u64 = 32;
+
+#[test]
+fn amo_rl_performs_after_prior_stores_drain() {
+    let mut program: Vec<u32> =
+        (0..STORES).map(|i| b().sd(5, 6, 0x100 + 8 * i as i32).build()).collect();
+    program.push(b().amoadd_w(7, 5, 6).aqrl(false, true).build());
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut sim = setup(backend, &program, 0x55);
+        let data = sim.cpu.regs.read(RegIdx::new(5));
+        while sim.cpu.stats.instructions_retired <= STORES {
+            sim.tick().unwrap();
+        }
+        // The AMO has just retired; every older store must already be in
+        // memory rather than waiting in the store buffer.
+        for i in 0..STORES {
+            let addr = PhysAddr::new(data + 0x100 + 8 * i);
+            assert_eq!(sim.cpu.bus.bus.read_u64(addr), 0x55, "{backend:?} store {i}");
+        }
+    }
+}
+
+#[test]
+fn amo_aq_orders_younger_load() {
+    let program = [b().amoadd_w(7, 5, 6).aqrl(true, false).build(), b().lw(8, 5, 0).build()];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut sim = setup(backend, &program, 3);
+        let data = PhysAddr::new(sim.cpu.regs.read(RegIdx::new(5)));
+        sim.cpu.bus.bus.write_u32(data, 4);
+        while sim.cpu.stats.instructions_retired < 2 {
+            sim.tick().unwrap();
+        }
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(7)), 4, "{backend:?}");
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(8)), 7, "{backend:?}: load sees the AMO");
+    }
+}
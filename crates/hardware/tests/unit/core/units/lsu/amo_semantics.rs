@@ -0,0 +1,116 @@
+//! AMO Alignment and Device-Region Tests.
+//!
+//! Runs A-extension instructions through the full pipeline and verifies:
+//!   - Misaligned AMOs raise Store/AMO address-misaligned with the faulting
+//!     address in `mtval`, even though regular misaligned accesses are emulated.
+//!   - Misaligned LR raises load address-misaligned.
+//!   - AMOs to device (MMIO) regions are performed as a single bus
+//!     read-modify-write and return the old device value.
+//!
+//! Reference: RISC-V ISA Spec Volume I, Section 8.4 (AMOs).
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::Simulator;
+use rvsim_core::common::{PhysAddr, RegIdx};
+use rvsim_core::config::Config;
+use rvsim_core::isa::privileged::cause::exception;
+use rvsim_core::soc::System;
+
+const BASE_ADDR: u64 = 0x8000_0000;
+const MEM_SIZE: usize = 0x2000;
+const HANDLER: u64 = BASE_ADDR + 0x800;
+
+/// Runs a single atomic at `addr` with traps vectored to `HANDLER`.
+fn run_trapping(inst: u32, addr: u64) -> TestContext {
+    let nop = InstructionBuilder::new().nop().build();
+    let mut tc = TestContext::new()
+        .with_memory(MEM_SIZE, BASE_ADDR)
+        .load_program(BASE_ADDR, &[inst, nop, nop, nop, nop]);
+    // Handler: spin (`j .`) so the first trap's CSRs are preserved.
+    tc.sim
+        .cpu
+        .bus
+        .bus
+        .write_u32(PhysAddr::new(HANDLER), InstructionBuilder::new().jal(0, 0).build());
+    tc.cpu_mut().direct_mode = false;
+    tc.cpu_mut().csrs.mtvec = HANDLER;
+    tc.set_reg(5, addr);
+    tc.set_reg(6, 1);
+    tc.run(100);
+    tc
+}
+
+#[test]
+fn amoadd_w_misaligned_raises_store_amo_misaligned() {
+    let addr = BASE_ADDR + 0x1002;
+    let tc = run_trapping(InstructionBuilder::new().amoadd_w(8, 5, 6).build(), addr);
+    let csrs = &tc.cpu().csrs;
+    assert_eq!(csrs.mcause, exception::STORE_ADDRESS_MISALIGNED);
+    assert_eq!(csrs.mtval, addr, "mtval must hold the misaligned address");
+    assert_eq!(csrs.mepc, BASE_ADDR);
+}
+
+#[test]
+fn amoadd_w_misaligned_does_not_write_memory() {
+    let addr = BASE_ADDR + 0x1002;
+    let mut tc = run_trapping(InstructionBuilder::new().amoadd_w(8, 5, 6).build(), addr);
+    assert_eq!(tc.sim.cpu.bus.bus.read_u32(PhysAddr::new(BASE_ADDR + 0x1000)), 0);
+    assert_eq!(tc.sim.cpu.bus.bus.read_u32(PhysAddr::new(BASE_ADDR + 0x1004)), 0);
+}
+
+#[test]
+fn lr_w_misaligned_raises_load_misaligned() {
+    let addr = BASE_ADDR + 0x1002;
+    let tc = run_trapping(InstructionBuilder::new().lr_w(8, 5).build(), addr);
+    assert_eq!(tc.cpu().csrs.mcause, exception::LOAD_ADDRESS_MISALIGNED);
+    assert_eq!(tc.cpu().csrs.mtval, addr);
+}
+
+/// Builds a full system (DRAM + CLINT) running `program` from the RAM base.
+fn system_with_program(program: &[u32]) -> (Simulator, Config) {
+    let config = Config::default();
+    let system = System::new(&config, "");
+    let mut sim = Simulator::new(system, &config);
+    for (i, inst) in program.iter().enumerate() {
+        sim.cpu.bus.bus.write_u32(PhysAddr::new(config.system.ram_base + i as u64 * 4), *inst);
+    }
+    sim.cpu.pc = config.system.ram_base;
+    (sim, config)
+}
+
+#[test]
+fn amoadd_w_to_clint_mtimecmp_is_read_modify_write() {
+    let nop = InstructionBuilder::new().nop().build();
+    let inst = InstructionBuilder::new().amoadd_w(8, 5, 6).build();
+    let (mut sim, config) = system_with_program(&[inst, nop, nop, nop, nop]);
+    let mtimecmp = PhysAddr::new(config.system.clint_base + 0x4000);
+    sim.cpu.bus.bus.write_u64(mtimecmp, 100);
+    sim.cpu.regs.write(RegIdx::new(5), mtimecmp.val());
+    sim.cpu.regs.write(RegIdx::new(6), 23);
+
+    for _ in 0..100 {
+        sim.tick().unwrap();
+    }
+
+    assert_eq!(sim.cpu.regs.read(RegIdx::new(8)), 100, "rd must receive the old device value");
+    assert_eq!(sim.cpu.bus.bus.read_u64(mtimecmp), 123, "device must hold old + rs2");
+}
+
+#[test]
+fn amoor_w_to_clint_msip_sets_bit_once() {
+    let nop = InstructionBuilder::new().nop().build();
+    let inst = InstructionBuilder::new().amoor_w(8, 5, 6).build();
+    let (mut sim, config) = system_with_program(&[inst, nop, nop, nop, nop]);
+    let msip = PhysAddr::new(config.system.clint_base);
+    sim.cpu.regs.write(RegIdx::new(5), msip.val());
+    sim.cpu.regs.write(RegIdx::new(6), 1);
+    sim.cpu.regs.write(RegIdx::new(8), 0xDEAD);
+
+    for _ in 0..100 {
+        sim.tick().unwrap();
+    }
+
+    assert_eq!(sim.cpu.regs.read(RegIdx::new(8)), 0);
+    assert_eq!(sim.cpu.bus.bus.read_u32(msip), 1);
+}
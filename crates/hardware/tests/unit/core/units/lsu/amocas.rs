@@ -0,0 +1,242 @@
+//! Zacas Compare-and-Swap Tests.
+//!
+//! Runs `amocas.w/d/q` through the full pipeline and verifies:
+//!   - On a match, memory receives the swap value from `rs2` (or the
+//!     `rs2`/`rs2+1` pair for `amocas.q`).
+//!   - On a mismatch, memory is unchanged.
+//!   - In both cases `rd` (and `rd+1` for `amocas.q`) receives the old value,
+//!     and younger instructions observe it.
+//!   - The encodings are illegal when Zacas is disabled, and `amocas.q`
+//!     with an odd register pair base is illegal.
+//!
+//! Reference: RISC-V Zacas extension, version 1.0.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::Simulator;
+use rvsim_core::common::{PhysAddr, RegIdx};
+use rvsim_core::config::Config;
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::isa::privileged::cause::exception;
+use rvsim_core::soc::System;
+
+const BASE_ADDR: u64 = 0x8000_0000;
+const MEM_SIZE: usize = 0x2000;
+const DATA_ADDR: u64 = BASE_ADDR + 0x1000;
+const HANDLER: u64 = BASE_ADDR + 0x800;
+
+fn b() -> InstructionBuilder {
+    InstructionBuilder::new()
+}
+
+/// Runs `program` with Zacas enabled, x5 = `DATA_ADDR`, and traps vectored
+/// to a spin loop at `HANDLER`.
+fn run(program: &[u32], regs: &[(usize, u64)], mem: &[(u64, u64)], zacas: bool) -> TestContext {
+    let nop = b().nop().build();
+    let mut code = program.to_vec();
+    code.extend([nop; 6]);
+    let mut tc = TestContext::new().with_memory(MEM_SIZE, BASE_ADDR).load_program(BASE_ADDR, &code);
+    tc.sim.cpu.bus.bus.write_u32(PhysAddr::new(HANDLER), b().jal(0, 0).build());
+    tc.cpu_mut().direct_mode = false;
+    tc.cpu_mut().csrs.mtvec = HANDLER;
+    tc.cpu_mut().zacas = zacas;
+    tc.set_reg(5, DATA_ADDR);
+    for &(reg, val) in regs {
+        tc.set_reg(reg, val);
+    }
+    for &(addr, val) in mem {
+        tc.sim.cpu.bus.bus.write_u64(PhysAddr::new(addr), val);
+    }
+    tc.run(100);
+    tc
+}
+
+fn read_u64(tc: &mut TestContext, addr: u64) -> u64 {
+    tc.sim.cpu.bus.bus.read_u64(PhysAddr::new(addr))
+}
+
+// ══════════════════════════════════════════════════════════
+// 1. amocas.w / amocas.d
+// ══════════════════════════════════════════════════════════
+
+#[test]
+fn amocas_w_success_swaps() {
+    let mut tc = run(&[b().amocas_w(8, 5, 6).build()], &[(8, 5), (6, 9)], &[(DATA_ADDR, 5)], true);
+    assert_eq!(tc.get_reg(8), 5);
+    assert_eq!(read_u64(&mut tc, DATA_ADDR), 9);
+}
+
+#[test]
+fn amocas_w_failure_leaves_memory() {
+    let mut tc = run(&[b().amocas_w(8, 5, 6).build()], &[(8, 7), (6, 9)], &[(DATA_ADDR, 5)], true);
+    assert_eq!(tc.get_reg(8), 5, "rd receives the old value on failure");
+    assert_eq!(read_u64(&mut tc, DATA_ADDR), 5);
+}
+
+#[test]
+fn amocas_w_compares_low_word_and_sign_extends() {
+    let mut tc = run(
+        &[b().amocas_w(8, 5, 6).build()],
+        &[(8, 0xFFFF_FFFF), (6, 1)],
+        &[(DATA_ADDR, 0x1234_5678_FFFF_FFFF)],
+        true,
+    );
+    assert_eq!(tc.get_reg(8), u64::MAX);
+    assert_eq!(read_u64(&mut tc, DATA_ADDR), 0x1234_5678_0000_0001, "upper word untouched");
+}
+
+#[test]
+fn amocas_d_success_swaps() {
+    let old = 0xDEAD_BEEF_0000_0001;
+    let mut tc =
+        run(&[b().amocas_d(8, 5, 6).build()], &[(8, old), (6, 42)], &[(DATA_ADDR, old)], true);
+    assert_eq!(tc.get_reg(8), old);
+    assert_eq!(read_u64(&mut tc, DATA_ADDR), 42);
+}
+
+#[test]
+fn amocas_d_failure_leaves_memory() {
+    let old = 0xDEAD_BEEF_0000_0001;
+    let mut tc =
+        run(&[b().amocas_d(8, 5, 6).build()], &[(8, 0), (6, 42)], &[(DATA_ADDR, old)], true);
+    assert_eq!(tc.get_reg(8), old);
+    assert_eq!(read_u64(&mut tc, DATA_ADDR), old);
+}
+
+#[test]
+fn amocas_result_forwards_to_younger_instruction() {
+    let tc = run(
+        &[b().amocas_d(8, 5, 6).build(), b().addi(9, 8, 1).build()],
+        &[(8, 0), (6, 42)],
+        &[(DATA_ADDR, 100)],
+        true,
+    );
+    assert_eq!(tc.get_reg(9), 101, "younger reader must see the old memory value");
+}
+
+#[test]
+fn amocas_rd_equals_rs2() {
+    // Comparison and swap values are both read before rd is written.
+    let mut tc = run(&[b().amocas_d(8, 5, 8).build()], &[(8, 7)], &[(DATA_ADDR, 7)], true);
+    assert_eq!(tc.get_reg(8), 7);
+    assert_eq!(read_u64(&mut tc, DATA_ADDR), 7);
+}
+
+// ══════════════════════════════════════════════════════════
+// 2. amocas.q register pairs
+// ══════════════════════════════════════════════════════════
+
+#[test]
+fn amocas_q_success_swaps_both_halves() {
+    let mut tc = run(
+        &[b().amocas_q(10, 5, 12).build()],
+        &[(10, 0x1111), (11, 0x2222), (12, 0x3333), (13, 0x4444)],
+        &[(DATA_ADDR, 0x1111), (DATA_ADDR + 8, 0x2222)],
+        true,
+    );
+    assert_eq!(tc.get_reg(10), 0x1111);
+    assert_eq!(tc.get_reg(11), 0x2222);
+    assert_eq!(read_u64(&mut tc, DATA_ADDR), 0x3333);
+    assert_eq!(read_u64(&mut tc, DATA_ADDR + 8), 0x4444);
+}
+
+#[test]
+fn amocas_q_high_half_mismatch_fails() {
+    let mut tc = run(
+        &[b().amocas_q(10, 5, 12).build()],
+        &[(10, 0x1111), (11, 0x9999), (12, 0x3333), (13, 0x4444)],
+        &[(DATA_ADDR, 0x1111), (DATA_ADDR + 8, 0x2222)],
+        true,
+    );
+    assert_eq!(tc.get_reg(10), 0x1111);
+    assert_eq!(tc.get_reg(11), 0x2222, "odd register receives the old high half");
+    assert_eq!(read_u64(&mut tc, DATA_ADDR), 0x1111);
+    assert_eq!(read_u64(&mut tc, DATA_ADDR + 8), 0x2222);
+}
+
+#[test]
+fn amocas_q_high_result_forwards_to_younger_instruction() {
+    let tc = run(
+        &[b().amocas_q(10, 5, 12).build(), b().addi(9, 11, 1).build()],
+        &[(11, 0x7777)],
+        &[(DATA_ADDR, 1), (DATA_ADDR + 8, 0x50)],
+        true,
+    );
+    assert_eq!(tc.get_reg(9), 0x51);
+}
+
+#[test]
+fn amocas_q_x0_pair_reads_as_zero() {
+    // rs2 = x0 swaps in zero for both halves; rd = x0 compares against zero
+    // and discards the result.
+    let mut tc = run(
+        &[b().amocas_q(0, 5, 0).build()],
+        &[(1, 0xFFFF)],
+        &[(DATA_ADDR, 0), (DATA_ADDR + 8, 0)],
+        true,
+    );
+    assert_eq!(tc.get_reg(1), 0xFFFF, "x1 is not written when rd = x0");
+    assert_eq!(read_u64(&mut tc, DATA_ADDR), 0);
+}
+
+#[test]
+fn amocas_q_odd_register_pair_is_illegal() {
+    let inst = b().amocas_q(11, 5, 12).build();
+    let tc = run(&[inst], &[], &[], true);
+    assert_eq!(tc.cpu().csrs.mcause, exception::ILLEGAL_INSTRUCTION);
+    assert_eq!(tc.cpu().csrs.mepc, BASE_ADDR);
+
+    let inst = b().amocas_q(10, 5, 13).build();
+    let tc = run(&[inst], &[], &[], true);
+    assert_eq!(tc.cpu().csrs.mcause, exception::ILLEGAL_INSTRUCTION);
+}
+
+#[test]
+fn amocas_q_requires_16_byte_alignment() {
+    let mut tc = run(&[b().amocas_q(10, 5, 12).build()], &[(5, DATA_ADDR + 8)], &[], true);
+    assert_eq!(tc.cpu().csrs.mcause, exception::STORE_ADDRESS_MISALIGNED);
+    assert_eq!(tc.cpu().csrs.mtval, DATA_ADDR + 8);
+    assert_eq!(read_u64(&mut tc, DATA_ADDR + 8), 0);
+}
+
+// ══════════════════════════════════════════════════════════
+// 3. Configuration gate and O3 backend
+// ══════════════════════════════════════════════════════════
+
+#[test]
+fn amocas_is_illegal_when_zacas_disabled() {
+    for inst in [b().amocas_w(8, 5, 6), b().amocas_d(8, 5, 6), b().amocas_q(8, 5, 6)] {
+        let mut tc = run(&[inst.build()], &[(8, 5), (6, 9)], &[(DATA_ADDR, 5)], false);
+        assert_eq!(tc.cpu().csrs.mcause, exception::ILLEGAL_INSTRUCTION);
+        assert_eq!(read_u64(&mut tc, DATA_ADDR), 5);
+    }
+}
+
+#[test]
+fn amocas_q_on_o3_backend() {
+    let mut config = Config::default();
+    config.pipeline.backend = BackendType::OutOfOrder;
+    config.pipeline.zacas = true;
+    let mut sim = Simulator::new(System::new(&config, ""), &config);
+    let data = config.system.ram_base + 0x1000;
+    let program =
+        [b().amocas_q(10, 5, 12).build(), b().add(9, 10, 11).build(), b().jal(0, 0).build()];
+    for (i, inst) in program.iter().enumerate() {
+        sim.cpu.bus.bus.write_u32(PhysAddr::new(config.system.ram_base + i as u64 * 4), *inst);
+    }
+    sim.cpu.bus.bus.write_u64(PhysAddr::new(data), 3);
+    sim.cpu.bus.bus.write_u64(PhysAddr::new(data + 8), 4);
+    sim.cpu.pc = config.system.ram_base;
+    for (reg, val) in [(5, data), (10, 3), (11, 4), (12, 30), (13, 40)] {
+        sim.cpu.regs.write(RegIdx::new(reg), val);
+    }
+    sim.sync_arch_regs();
+
+    for _ in 0..300 {
+        sim.tick().unwrap();
+    }
+
+    assert_eq!(sim.cpu.regs.read(RegIdx::new(9)), 7, "younger add sees both old halves");
+    assert_eq!(sim.cpu.bus.bus.read_u64(PhysAddr::new(data)), 30);
+    assert_eq!(sim.cpu.bus.bus.read_u64(PhysAddr::new(data + 8)), 40);
+}
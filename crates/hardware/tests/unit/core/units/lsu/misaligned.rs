@@ -0,0 +1,122 @@
+//! Misaligned Access Policy Tests.
+//!
+//! Runs a 4-byte load straddling a cache line boundary through the full
+//! pipeline under both `MisalignedPolicy` settings. `Trap` must raise a
+//! load-address-misaligned exception with `mtval` = address and leave the
+//! destination untouched; `Emulate` must return the combined value of both
+//! halves and cost more cycles than the same load aligned.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::Simulator;
+use rvsim_core::common::{PhysAddr, RegIdx};
+use rvsim_core::config::{Config, MisalignedPolicy};
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::isa::privileged::cause::exception;
+
+/// Offset of the trap handler (a spin loop) from the start of RAM.
+const HANDLER_OFFSET: u64 = 0x800;
+
+/// Offset of the data from the start of RAM (64-byte aligned).
+const DATA_OFFSET: u64 = 0x1000;
+
+/// Marker preset in x7, the destination of every load.
+const MARKER: u64 = 0xAAAA;
+
+/// Runs `program` then exits on `backend` under `policy`, with x10 pointing
+/// at `DATA_OFFSET + offset` and byte `i` of the data region holding `i`.
+/// Under `Trap` the exception vectors to a spin loop; under `Emulate` the
+/// program exits through the direct-mode ecall. Returns the simulator once it
+/// exits or has spun in the handler.
+fn run(backend: BackendType, policy: MisalignedPolicy, offset: u64, program: &[u32]) -> Simulator {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.memory.misaligned = policy;
+    config.cache.l1_d.enabled = true;
+    // Non-blocking D-cache, so load latency holds the ROB head on both backends.
+    config.cache.l1_d.mshr_count = 4;
+    let base = config.system.ram_base;
+
+    let b = InstructionBuilder::new;
+    let mut program = program.to_vec();
+    program.extend([b().addi(17, 0, 93).build(), b().ecall().build()]);
+    let mut ctx = TestContext::with_config(&config).load_program(base, &program);
+    ctx.sim.cpu.bus.bus.write_u32(PhysAddr::new(base + HANDLER_OFFSET), b().jal(0, 0).build());
+    for i in 0..128 {
+        ctx.sim.cpu.bus.bus.write_u8(PhysAddr::new(base + DATA_OFFSET + i), i as u8);
+    }
+    ctx.sim.cpu.direct_mode = policy == MisalignedPolicy::Emulate;
+    ctx.sim.cpu.csrs.mtvec = base + HANDLER_OFFSET;
+    ctx.sim.write_reg(RegIdx::new(7), MARKER);
+    ctx.sim.write_reg(RegIdx::new(10), base + DATA_OFFSET + offset);
+    ctx.run(10_000);
+    ctx.sim
+}
+
+/// Returns the physical address `offset` bytes into the data region.
+fn data_addr(offset: u64) -> u64 {
+    Config::default().system.ram_base + DATA_OFFSET + offset
+}
+
+#[test]
+fn line_crossing_load_traps_under_trap_policy() {
+    let lw = InstructionBuilder::new().lw(7, 10, 0).build();
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let sim = run(backend, MisalignedPolicy::Trap, 62, &[lw]);
+        let addr = data_addr(62);
+        assert!(sim.cpu.exit_code.is_none(), "{backend:?}");
+        assert_eq!(sim.cpu.csrs.mcause, exception::LOAD_ADDRESS_MISALIGNED, "{backend:?}");
+        assert_eq!(sim.cpu.csrs.mtval, addr, "{backend:?}");
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(7)), MARKER, "{backend:?}");
+    }
+}
+
+#[test]
+fn line_crossing_store_traps_under_trap_policy() {
+    let sw = InstructionBuilder::new().sw(10, 7, 0).build();
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut sim = run(backend, MisalignedPolicy::Trap, 62, &[sw]);
+        let addr = data_addr(62);
+        assert_eq!(sim.cpu.csrs.mcause, exception::STORE_ADDRESS_MISALIGNED, "{backend:?}");
+        assert_eq!(sim.cpu.csrs.mtval, addr, "{backend:?}");
+        assert_eq!(sim.cpu.bus.bus.read_u32(PhysAddr::new(addr)), 0x4140_3F3E, "{backend:?}");
+    }
+}
+
+#[test]
+fn line_crossing_load_is_emulated_under_emulate_policy() {
+    let lw = InstructionBuilder::new().lw(7, 10, 0).build();
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let aligned = run(backend, MisalignedPolicy::Emulate, 64, &[lw]);
+        let split = run(backend, MisalignedPolicy::Emulate, 62, &[lw]);
+        assert!(split.cpu.exit_code.is_some(), "{backend:?}");
+        // Bytes 0x3E, 0x3F from the first line and 0x40, 0x41 from the second.
+        assert_eq!(split.cpu.regs.read(RegIdx::new(7)), 0x4140_3F3E, "{backend:?}");
+        assert!(
+            split.cpu.stats.cycles > aligned.cpu.stats.cycles,
+            "{backend:?}: split {} vs aligned {}",
+            split.cpu.stats.cycles,
+            aligned.cpu.stats.cycles
+        );
+    }
+}
+
+#[test]
+fn line_crossing_store_round_trips_under_emulate_policy() {
+    let b = InstructionBuilder::new;
+    let program = [
+        // x11 = 0xFFFF_FFFF_8765_4321.
+        b().lui(11, 0x87654).build(),
+        b().addi(11, 11, 0x321).build(),
+        b().sw(10, 11, 0).build(),
+        b().lw(7, 10, 0).build(),
+    ];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut sim = run(backend, MisalignedPolicy::Emulate, 62, &program);
+        assert!(sim.cpu.exit_code.is_some(), "{backend:?}");
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(7)), 0xFFFF_FFFF_8765_4321, "{backend:?}");
+        let line = data_addr(64);
+        assert_eq!(sim.cpu.bus.bus.read_u16(PhysAddr::new(line - 2)), 0x4321, "{backend:?}");
+        assert_eq!(sim.cpu.bus.bus.read_u16(PhysAddr::new(line)), 0x8765, "{backend:?}");
+    }
+}
@@ -1,3 +1,8 @@
+pub mod amo_ordering;
+pub mod amo_semantics;
+pub mod amocas;
 pub mod atomic;
+pub mod misaligned;
 pub mod ordering;
+pub mod reservation;
 pub mod unaligned;
@@ -1,7 +1,7 @@
 //! Unaligned memory access Unit Tests.
 //!
 //! Verifies alignment checks, trap generation, split loads, split stores,
-//! cache line crossing detection, and latency calculations.
+//! granule splitting, cache line crossing detection, and latency calculations.
 
 use rvsim_core::common::error::Trap;
 use rvsim_core::core::units::lsu::unaligned;
@@ -185,7 +185,33 @@ fn split_store_then_load_round_trip() {
 }
 
 // ══════════════════════════════════════════════════════════
-// 5. Cache line crossing detection
+// 5. Granule splitting
+// ══════════════════════════════════════════════════════════
+
+#[test]
+fn misaligned_access_within_granule_is_not_split() {
+    assert!(!unaligned::needs_split(0x1001, 2));
+    assert!(!unaligned::needs_split(0x1002, 4));
+    assert_eq!(unaligned::split_at_granule(0x1002, 4), ((0x1002, 4), None));
+}
+
+#[test]
+fn aligned_access_is_never_split() {
+    assert!(!unaligned::needs_split(0x1000, 8));
+    // amocas.q: a naturally aligned 16-byte access spans two granules.
+    assert!(!unaligned::needs_split(0x1000, 16));
+}
+
+#[test]
+fn access_crossing_granule_splits_at_boundary() {
+    assert!(unaligned::needs_split(0x103E, 4));
+    assert_eq!(unaligned::split_at_granule(0x103E, 4), ((0x103E, 2), Some((0x1040, 2))));
+    assert_eq!(unaligned::split_at_granule(0x1007, 2), ((0x1007, 1), Some((0x1008, 1))));
+    assert_eq!(unaligned::split_at_granule(0x1001, 8), ((0x1001, 7), Some((0x1008, 1))));
+}
+
+// ══════════════════════════════════════════════════════════
+// 6. Cache line crossing detection
 // ══════════════════════════════════════════════════════════
 
 #[test]
@@ -241,7 +267,7 @@ fn different_cache_line_sizes() {
 }
 
 // ══════════════════════════════════════════════════════════
-// 6. Latency calculation for unaligned accesses
+// 7. Latency calculation for unaligned accesses
 // ══════════════════════════════════════════════════════════
 
 #[test]
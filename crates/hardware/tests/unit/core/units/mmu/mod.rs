@@ -1,3 +1,4 @@
+pub mod mprv;
 pub mod pmp;
 pub mod ptw;
 pub mod tlb;
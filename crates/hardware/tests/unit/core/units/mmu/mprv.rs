@@ -0,0 +1,152 @@
+//! MPRV (Modify Privilege) Tests.
+//!
+//! Runs machine-mode code with Sv39 enabled on both backends and verifies
+//! that, while `mstatus.MPRV` is set, loads are translated and checked as
+//! though running in `mstatus.MPP`:
+//!   - With MPP = U, a load through a user page translates, while a load
+//!     from a supervisor-only page raises a load page fault. Instruction
+//!     fetches keep using M-mode and run from the supervisor-only page.
+//!   - A load that translates to an unbacked physical address raises a
+//!     load access fault instead of reading the bus default as M-mode would.
+//!   - With MPRV clear, M-mode loads bypass translation.
+//!
+//! Reference: RISC-V Privileged Spec, Section 3.1.6.3 (Memory Privilege in
+//! mstatus Register).
+
+use crate::common::builder::instruction::InstructionBuilder;
+use rvsim_core::Simulator;
+use rvsim_core::common::{PhysAddr, RegIdx};
+use rvsim_core::config::Config;
+use rvsim_core::core::arch::csr;
+use rvsim_core::core::arch::mode::PrivilegeMode;
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::isa::privileged::cause::exception;
+use rvsim_core::soc::System;
+
+/// Offset from the start of RAM of the data word loaded.
+const DATA: u64 = 0x2000;
+/// Value stored at `DATA`.
+const VALUE: u64 = 0x0123_4567_89ab_cdef;
+/// Virtual gigapage aliasing RAM through a user (U=1) mapping.
+const USER_ALIAS: u64 = 0x4000_0000;
+/// Virtual gigapage mapped (U=1) to a physical address with no device.
+const UNBACKED_VA: u64 = 0xC000_0000;
+const UNBACKED_PA: u64 = 0x20_0000_0000;
+
+/// Leaf PTE bits: V, R, W, X, A, D.
+const PTE_RWX: u64 = 0xcf;
+/// `PTE_RWX` plus U.
+const PTE_URWX: u64 = 0xdf;
+
+/// Loads `program` at the start of RAM with a `j .` trap handler, maps the
+/// RAM gigapage identity (supervisor-only), `USER_ALIAS` onto RAM and
+/// `UNBACKED_VA` onto `UNBACKED_PA` (both user), and starts in M-mode with
+/// Sv39 on and `mstatus` holding `mstatus`.
+fn setup(backend: BackendType, program: &[u32], mstatus: u64) -> Simulator {
+    let b = InstructionBuilder::new;
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    let base = config.system.ram_base;
+    let root = base + 0x10_000;
+    let handler = base + 0x100;
+
+    let mut sim = Simulator::new(System::new(&config, ""), &config);
+    let bus = &mut sim.cpu.bus.bus;
+    for (i, &inst) in program.iter().enumerate() {
+        bus.write_u32(PhysAddr::new(base + 4 * i as u64), inst);
+    }
+    bus.write_u32(PhysAddr::new(handler), b().jal(0, 0).build());
+    bus.write_u64(PhysAddr::new(base + DATA), VALUE);
+    for (va, pa, bits) in
+        [(base, base, PTE_RWX), (USER_ALIAS, base, PTE_URWX), (UNBACKED_VA, UNBACKED_PA, PTE_URWX)]
+    {
+        let vpn2 = (va >> 30) & 0x1ff;
+        bus.write_u64(PhysAddr::new(root + 8 * vpn2), ((pa >> 12) << 10) | bits);
+    }
+
+    // One NAPOT region covering the whole address space, RWX.
+    sim.cpu.pmp.set_addr(0, u64::MAX >> 10);
+    sim.cpu.pmp.set_cfg(0, 0x1F);
+    sim.cpu.direct_mode = false;
+    sim.cpu.csrs.mtvec = handler;
+    sim.cpu.csrs.satp = (csr::SATP_MODE_SV39 << csr::SATP_MODE_SHIFT) | (root >> 12);
+    sim.cpu.csrs.mstatus = mstatus;
+    sim.cpu.privilege = PrivilegeMode::Machine;
+    sim.cpu.pc = base;
+    sim
+}
+
+/// `mstatus` with MPRV set and MPP = `mpp`.
+const fn mprv(mpp: PrivilegeMode) -> u64 {
+    csr::MSTATUS_MPRV | ((mpp as u64) << csr::MSTATUS_MPP_SHIFT)
+}
+
+/// Ticks until the first trap, panicking after 10 000 cycles.
+fn run_to_trap(sim: &mut Simulator, backend: BackendType) {
+    for _ in 0..10_000 {
+        sim.tick().unwrap();
+        if !sim.cpu.recent_traps.is_empty() {
+            return;
+        }
+    }
+    panic!("{backend:?}: no trap taken");
+}
+
+#[test]
+fn mprv_with_mpp_user_enforces_user_page_permissions() {
+    let b = InstructionBuilder::new;
+    let program = [b().ld(7, 10, 0).build(), b().ld(8, 11, 0).build()];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut sim = setup(backend, &program, mprv(PrivilegeMode::User));
+        let base = sim.cpu.pc;
+        sim.write_reg(RegIdx::new(10), USER_ALIAS + DATA);
+        sim.write_reg(RegIdx::new(11), base + DATA);
+        run_to_trap(&mut sim, backend);
+
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(7)), VALUE, "{backend:?}: user alias loads");
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(8)), 0, "{backend:?}");
+        let t = sim.cpu.recent_traps.back().unwrap();
+        assert_eq!(t.cause, exception::LOAD_PAGE_FAULT, "{backend:?}");
+        assert_eq!(t.tval, base + DATA, "{backend:?}");
+        assert_eq!(t.epc, base + 4, "{backend:?}");
+        assert_eq!(t.privilege, PrivilegeMode::Machine, "{backend:?}");
+    }
+}
+
+#[test]
+fn mprv_load_from_unbacked_address_raises_access_fault() {
+    let b = InstructionBuilder::new;
+    let program = [b().ld(7, 10, 0).build()];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut sim = setup(backend, &program, mprv(PrivilegeMode::User));
+        let base = sim.cpu.pc;
+        sim.write_reg(RegIdx::new(10), UNBACKED_VA);
+        run_to_trap(&mut sim, backend);
+
+        let t = sim.cpu.recent_traps.back().unwrap();
+        assert_eq!(t.cause, exception::LOAD_ACCESS_FAULT, "{backend:?}");
+        assert_eq!(t.tval, UNBACKED_VA, "{backend:?}");
+        assert_eq!(t.epc, base, "{backend:?}");
+    }
+}
+
+#[test]
+fn machine_loads_bypass_translation_without_mprv() {
+    let b = InstructionBuilder::new;
+    let program = [b().ld(7, 10, 0).build(), b().jal(0, 0).build()];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        // MPP = U alone changes nothing.
+        let mpp_user = (PrivilegeMode::User as u64) << csr::MSTATUS_MPP_SHIFT;
+        let mut sim = setup(backend, &program, mpp_user);
+        let base = sim.cpu.pc;
+        sim.write_reg(RegIdx::new(10), base + DATA);
+        for _ in 0..10_000 {
+            sim.tick().unwrap();
+            if sim.cpu.stats.instructions_retired >= 2 {
+                break;
+            }
+        }
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(7)), VALUE, "{backend:?}");
+        assert!(sim.cpu.recent_traps.is_empty(), "{backend:?}");
+    }
+}
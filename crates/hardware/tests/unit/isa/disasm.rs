@@ -4,7 +4,7 @@
 //! encodings to human-readable mnemonics for RV64I, RV64M, RV64A,
 //! RV64F/D, and privileged instructions.
 
-use rvsim_core::isa::disasm::disassemble;
+use rvsim_core::isa::disasm::{Syntax, disassemble, disassemble_with};
 
 // ══════════════════════════════════════════════════════════
 // 1. RV64I: Register-Register (R-type)
@@ -34,8 +34,8 @@ fn disasm_sub() {
 
 #[test]
 fn disasm_addi() {
-    // ADDI x10, x0, 10 → 0x00A00513
-    let text = disassemble(0x00A0_0513);
+    // ADDI x10, x0, 10 → 0x00A00513 (li a0, 10)
+    let text = disassemble_with(0x00A0_0513, Syntax::Numeric);
     assert!(text.starts_with("addi "), "Expected 'addi', got '{}'", text);
     assert!(text.contains("10"), "Expected immediate 10 in '{}'", text);
 }
@@ -44,9 +44,10 @@ fn disasm_addi() {
 fn disasm_addi_negative() {
     // ADDI x10, x0, -1 → imm=0xFFF
     let inst: u32 = 0xFFF0_0513;
-    let text = disassemble(inst);
+    let text = disassemble_with(inst, Syntax::Numeric);
     assert!(text.starts_with("addi "), "Expected 'addi', got '{}'", text);
     assert!(text.contains("-1"), "Expected immediate -1 in '{}'", text);
+    assert_eq!(disassemble(inst), "li a0, -1");
 }
 
 #[test]
@@ -110,7 +111,7 @@ fn disasm_beq() {
 fn disasm_bne() {
     // BNE x10, x0, offset
     let inst: u32 = 0x0005_1063;
-    let text = disassemble(inst);
+    let text = disassemble_with(inst, Syntax::Numeric);
     assert!(text.starts_with("bne "), "Expected 'bne', got '{}'", text);
 }
 
@@ -151,8 +152,9 @@ fn disasm_jal() {
 fn disasm_jalr() {
     // JALR x0, 0(x1) — a.k.a. "ret"
     let inst: u32 = 0x0000_8067;
-    let text = disassemble(inst);
+    let text = disassemble_with(inst, Syntax::Numeric);
     assert!(text.starts_with("jalr "), "Expected 'jalr', got '{}'", text);
+    assert_eq!(disassemble(inst), "ret");
 }
 
 // ══════════════════════════════════════════════════════════
@@ -219,6 +221,8 @@ fn disasm_csrrw() {
     let inst: u32 = 0x3005_9573;
     let text = disassemble(inst);
     assert!(text.starts_with("csrrw "), "Expected 'csrrw', got '{}'", text);
+    assert!(text.contains("mstatus"), "Expected CSR mstatus in '{}'", text);
+    let text = disassemble_with(inst, Syntax::Numeric);
     assert!(text.contains("0x300"), "Expected CSR 0x300 in '{}'", text);
 }
 
@@ -230,6 +234,32 @@ fn disasm_csrrs() {
     assert!(text.starts_with("csrrs "), "Expected 'csrrs', got '{}'", text);
 }
 
+/// Encodes `csrr a0, csr`.
+fn csrr_a0(csr: u32) -> u32 {
+    (csr << 20) | (0b010 << 12) | (10 << 7) | 0x73
+}
+
+#[test]
+fn disasm_csr_mcountinhibit() {
+    assert_eq!(disassemble(csrr_a0(0x320)), "csrr a0, mcountinhibit");
+}
+
+#[test]
+fn disasm_csr_pmpcfg() {
+    for i in 0..16 {
+        assert_eq!(disassemble(csrr_a0(0x3A0 + i)), format!("csrr a0, pmpcfg{i}"));
+    }
+}
+
+#[test]
+fn disasm_csr_pmpaddr() {
+    for i in 0..64 {
+        assert_eq!(disassemble(csrr_a0(0x3B0 + i)), format!("csrr a0, pmpaddr{i}"));
+    }
+    // Just past the PMP registers.
+    assert_eq!(disassemble(csrr_a0(0x3F0)), "csrr a0, 0x3f0");
+}
+
 // ══════════════════════════════════════════════════════════
 // 10. M-extension
 // ══════════════════════════════════════════════════════════
@@ -279,3 +309,60 @@ fn disasm_unknown() {
     let text = disassemble(0x0000_0000);
     assert!(text.contains("unknown"), "Expected 'unknown' for all-zeroes, got '{}'", text);
 }
+
+// ══════════════════════════════════════════════════════════
+// 13. Golden output: pseudo-instructions and symbolic names
+// ══════════════════════════════════════════════════════════
+
+/// `(encoding, canonical, numeric)` for a representative instruction mix.
+const GOLDEN: &[(u32, &str, &str)] = &[
+    (0x0000_0013, "nop", "addi x0, x0, 0"),
+    (0x0005_8513, "mv a0, a1", "addi x10, x11, 0"),
+    (0x02A0_0293, "li t0, 42", "addi x5, x0, 42"),
+    (0xFF01_0113, "addi sp, sp, -16", "addi x2, x2, -16"),
+    (0x0000_8067, "ret", "jalr x0, 0(x1)"),
+    (0x0000_8367, "jalr t1, 0(ra)", "jalr x6, 0(x1)"),
+    (0x0005_0067, "jr a0", "jalr x0, 0(x10)"),
+    (0x0085_0067, "jalr zero, 8(a0)", "jalr x0, 8(x10)"),
+    (0x0100_006F, "j 16", "jal x0, 16"),
+    (0x0100_00EF, "jal ra, 16", "jal x1, 16"),
+    (0x0005_0463, "beqz a0, 8", "beq x10, x0, 8"),
+    (0x00B5_0463, "beq a0, a1, 8", "beq x10, x11, 8"),
+    (0x0005_1463, "bnez a0, 8", "bne x10, x0, 8"),
+    (0x00B5_1463, "bne a0, a1, 8", "bne x10, x11, 8"),
+    (0xFFF5_4513, "not a0, a0", "xori x10, x10, -1"),
+    (0x40B0_0533, "neg a0, a1", "sub x10, x0, x11"),
+    (0x0015_B513, "seqz a0, a1", "sltiu x10, x11, 1"),
+    (0x0025_B513, "sltiu a0, a1, 2", "sltiu x10, x11, 2"),
+    (0x00B0_3533, "snez a0, a1", "sltu x10, x0, x11"),
+    (0x00B6_3533, "sltu a0, a2, a1", "sltu x10, x12, x11"),
+    (0x0005_851B, "sext.w a0, a1", "addiw x10, x11, 0"),
+    (0x3000_2573, "csrr a0, mstatus", "csrrs x10, 0x300, x0"),
+    (0x1052_9073, "csrw stvec, t0", "csrrw x0, 0x105, x5"),
+    (0x1805_1073, "csrw satp, a0", "csrrw x0, 0x180, x10"),
+    (0x3004_6073, "csrsi mstatus, 8", "csrrsi x0, 0x300, 8"),
+    (0x3000_B073, "csrc mstatus, ra", "csrrc x0, 0x300, x1"),
+    (0x3415_9573, "csrrw a0, mepc, a1", "csrrw x10, 0x341, x11"),
+    (0x7C00_2573, "csrr a0, 0x7c0", "csrrs x10, 0x7c0, x0"),
+    (0x0081_3503, "ld a0, 8(sp)", "ld x10, 8(x2)"),
+    (0x8082, "ret", "jalr x0, 0(x1)"),
+    (0x0001, "nop", "addi x0, x0, 0"),
+];
+
+#[test]
+fn disasm_golden_canonical() {
+    for &(inst, canonical, _) in GOLDEN {
+        assert_eq!(disassemble(inst), canonical, "canonical form of {inst:#010x}");
+    }
+}
+
+#[test]
+fn disasm_golden_numeric() {
+    for &(inst, _, numeric) in GOLDEN {
+        assert_eq!(
+            disassemble_with(inst, Syntax::Numeric),
+            numeric,
+            "numeric form of {inst:#010x}"
+        );
+    }
+}
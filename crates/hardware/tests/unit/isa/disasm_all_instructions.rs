@@ -20,7 +20,7 @@ fn test_rv64i_arithmetic() {
 
 #[test]
 fn test_rv64i_immediate() {
-    assert!(disassemble(0x00A00513).starts_with("addi"));
+    assert!(disassemble(0x00A00513).starts_with("li"));
     assert!(disassemble(0x00A52513).starts_with("slti"));
     assert!(disassemble(0x00A53513).starts_with("sltiu"));
     assert!(disassemble(0x00A54513).starts_with("xori"));
@@ -78,7 +78,7 @@ fn test_rv64i_jumps() {
     let jal = disassemble(0x008000EF);
     assert!(jal.starts_with("jal"));
 
-    let jalr = disassemble(0x00058067);
+    let jalr = disassemble(0x000580E7);
     assert!(jalr.starts_with("jalr"));
 }
 
@@ -248,12 +248,10 @@ fn test_privileged_fence() {
 #[test]
 fn test_pseudo_instructions() {
     // NOP (addi x0, x0, 0)
-    let nop = disassemble(0x00000013);
-    assert!(nop.contains("addi") && nop.contains("zero"));
+    assert_eq!(disassemble(0x00000013), "nop");
 
     // MV (addi rd, rs, 0)
-    let mv = disassemble(0x00058513);
-    assert!(mv.contains("addi"));
+    assert_eq!(disassemble(0x00058513), "mv a0, a1");
 }
 
 #[test]
@@ -272,10 +270,10 @@ fn test_large_immediates() {
 fn test_all_integer_registers() {
     // Test disassembly includes all register names
     for i in 0..32 {
-        let inst = 0x00050513 | ((i as u32) << 7); // addi xi, a0, 0
+        let inst = 0x00050513 | ((i as u32) << 7); // addi xi, a0, 0 (mv xi, a0)
         let result = disassemble(inst);
         assert!(!result.is_empty());
-        assert!(result.contains("addi"));
+        assert!(result.contains("mv"));
     }
 }
 
@@ -0,0 +1,312 @@
+//! Encode → Decode Round-Trip Properties.
+//!
+//! Generates random valid RV64I/M/A/F/D instructions from structured
+//! fields, encodes them with `isa::encode` and checks that `decode()`
+//! extracts the same fields, that re-encoding the decoded fields gives the
+//! same bits, and that the disassembler recognises the instruction. Also
+//! checks that any 32-bit word survives decode → encode unchanged.
+
+use proptest::prelude::*;
+use rvsim_core::common::RegIdx;
+use rvsim_core::isa::decode::decode;
+use rvsim_core::isa::disasm::disassemble;
+use rvsim_core::isa::encode::{self, Format};
+use rvsim_core::isa::instruction::InstructionBits;
+use rvsim_core::isa::privileged::opcodes as sys_op;
+use rvsim_core::isa::rv64a::funct5 as a_f5;
+use rvsim_core::isa::rv64a::opcodes as a_op;
+use rvsim_core::isa::rv64d::funct7 as d_f7;
+use rvsim_core::isa::rv64f::funct7 as f_f7;
+use rvsim_core::isa::rv64f::opcodes as f_op;
+use rvsim_core::isa::rv64i::funct3 as i_f3;
+use rvsim_core::isa::rv64i::funct7 as i_f7;
+use rvsim_core::isa::rv64i::opcodes as i_op;
+use rvsim_core::isa::rv64m::opcodes as m_op;
+
+/// How an instruction's immediate (or immediate-like fields) is chosen.
+#[derive(Clone, Copy, Debug)]
+enum Imm {
+    /// No immediate.
+    None,
+    /// Any 12-bit signed value.
+    Any12,
+    /// A shift: fixed upper bits above a shift amount of `bits` bits.
+    Shift { upper: i64, bits: u32 },
+    /// A 13-bit even branch offset.
+    Branch,
+    /// A 32-bit value with the low 12 bits clear.
+    Upper,
+    /// A 21-bit even jump offset.
+    Jump,
+}
+
+/// One instruction (or family sharing an encoding shape).
+#[derive(Clone, Copy, Debug)]
+struct Spec {
+    name: &'static str,
+    format: Format,
+    opcode: u32,
+    /// Valid funct3 values (rounding modes for FP arithmetic).
+    funct3: &'static [u32],
+    funct7: u32,
+    /// Low funct7 bits that may take any value (`aq`/`rl`).
+    funct7_free: u32,
+    /// Fixed `rs2` for instructions that use it as an opcode extension.
+    rs2: Option<u8>,
+    imm: Imm,
+}
+
+const ANY_RM: &[u32] = &[0, 1, 2, 3, 4, 7];
+
+const fn spec(name: &'static str, format: Format, opcode: u32, funct3: &'static [u32]) -> Spec {
+    Spec { name, format, opcode, funct3, funct7: 0, funct7_free: 0, rs2: None, imm: Imm::None }
+}
+
+const fn r(name: &'static str, opcode: u32, funct3: &'static [u32], funct7: u32) -> Spec {
+    Spec { funct7, ..spec(name, Format::R, opcode, funct3) }
+}
+
+const fn fp(name: &'static str, funct3: &'static [u32], funct7: u32, rs2: Option<u8>) -> Spec {
+    Spec { rs2, ..r(name, f_op::OP_FP, funct3, funct7) }
+}
+
+const fn i(name: &'static str, opcode: u32, funct3: &'static [u32], imm: Imm) -> Spec {
+    Spec { imm, ..spec(name, Format::I, opcode, funct3) }
+}
+
+const fn amo(name: &'static str, funct3: u32, funct5: u32, rs2: Option<u8>) -> Spec {
+    let funct3: &'static [u32] = if funct3 == 2 { &[2] } else { &[3] };
+    Spec { funct7: funct5 << 2, funct7_free: 0b11, rs2, ..r(name, a_op::OP_AMO, funct3, 0) }
+}
+
+fn specs() -> Vec<Spec> {
+    use Format::{B, J, R4, S, U};
+    let mut v = vec![
+        // RV64I
+        r("add", i_op::OP_REG, &[i_f3::ADD_SUB], i_f7::DEFAULT),
+        r("sub", i_op::OP_REG, &[i_f3::ADD_SUB], i_f7::SUB),
+        r("sll", i_op::OP_REG, &[i_f3::SLL], i_f7::DEFAULT),
+        r("slt", i_op::OP_REG, &[i_f3::SLT, i_f3::SLTU], i_f7::DEFAULT),
+        r("xor/or/and", i_op::OP_REG, &[i_f3::XOR, i_f3::OR, i_f3::AND], i_f7::DEFAULT),
+        r("srl", i_op::OP_REG, &[i_f3::SRL_SRA], i_f7::DEFAULT),
+        r("sra", i_op::OP_REG, &[i_f3::SRL_SRA], i_f7::SRA),
+        r("addw/sllw/srlw", i_op::OP_REG_32, &[0, 1, 5], i_f7::DEFAULT),
+        r("subw/sraw", i_op::OP_REG_32, &[0, 5], i_f7::SUB),
+        i("alu-imm", i_op::OP_IMM, &[0, 2, 3, 4, 6, 7], Imm::Any12),
+        i("slli/srli", i_op::OP_IMM, &[1, 5], Imm::Shift { upper: 0, bits: 6 }),
+        i("srai", i_op::OP_IMM, &[5], Imm::Shift { upper: 0x10, bits: 6 }),
+        i("addiw", i_op::OP_IMM_32, &[0], Imm::Any12),
+        i("slliw/srliw", i_op::OP_IMM_32, &[1, 5], Imm::Shift { upper: 0, bits: 5 }),
+        i("sraiw", i_op::OP_IMM_32, &[5], Imm::Shift { upper: 0x20, bits: 5 }),
+        i("load", i_op::OP_LOAD, &[0, 1, 2, 3, 4, 5, 6], Imm::Any12),
+        i("jalr", i_op::OP_JALR, &[0], Imm::Any12),
+        Spec { imm: Imm::Any12, ..spec("store", S, i_op::OP_STORE, &[0, 1, 2, 3]) },
+        Spec { imm: Imm::Branch, ..spec("branch", B, i_op::OP_BRANCH, &[0, 1, 4, 5, 6, 7]) },
+        Spec { imm: Imm::Upper, ..spec("lui", U, i_op::OP_LUI, &[]) },
+        Spec { imm: Imm::Upper, ..spec("auipc", U, i_op::OP_AUIPC, &[]) },
+        Spec { imm: Imm::Jump, ..spec("jal", J, i_op::OP_JAL, &[]) },
+        // RV64M
+        r("mul/div", i_op::OP_REG, &[0, 1, 2, 3, 4, 5, 6, 7], m_op::M_EXTENSION),
+        r("mulw/divw", i_op::OP_REG_32, &[0, 4, 5, 6, 7], m_op::M_EXTENSION),
+        // RV64A
+        amo("lr.w", 2, a_f5::LR, Some(0)),
+        amo("lr.d", 3, a_f5::LR, Some(0)),
+        amo("sc.w", 2, a_f5::SC, None),
+        amo("sc.d", 3, a_f5::SC, None),
+    ];
+    for f5 in [
+        a_f5::AMOSWAP,
+        a_f5::AMOADD,
+        a_f5::AMOXOR,
+        a_f5::AMOAND,
+        a_f5::AMOOR,
+        a_f5::AMOMIN,
+        a_f5::AMOMAX,
+        a_f5::AMOMINU,
+        a_f5::AMOMAXU,
+    ] {
+        v.push(amo("amo.w", 2, f5, None));
+        v.push(amo("amo.d", 3, f5, None));
+    }
+    // RV64F / RV64D
+    v.extend([
+        i("flw/fld", f_op::OP_LOAD_FP, &[2, 3], Imm::Any12),
+        Spec { imm: Imm::Any12, ..spec("fsw/fsd", S, f_op::OP_STORE_FP, &[2, 3]) },
+        fp("fadd.s", ANY_RM, f_f7::FADD, None),
+        fp("fsub.s", ANY_RM, f_f7::FSUB, None),
+        fp("fmul.s", ANY_RM, f_f7::FMUL, None),
+        fp("fdiv.s", ANY_RM, f_f7::FDIV, None),
+        fp("fsqrt.s", ANY_RM, f_f7::FSQRT, Some(0)),
+        fp("fsgnj.s", &[0, 1, 2], f_f7::FSGNJ, None),
+        fp("fmin/fmax.s", &[0, 1], f_f7::FMIN_MAX, None),
+        fp("fcmp.s", &[0, 1, 2], f_f7::FCMP, None),
+        fp("fmv.x.w", &[0], f_f7::FCLASS_MV_X_F, Some(0)),
+        fp("fclass.s", &[1], f_f7::FCLASS_MV_X_F, Some(0)),
+        fp("fmv.w.x", &[0], f_f7::FMV_F_X, Some(0)),
+        fp("fcvt.s.d", ANY_RM, d_f7::FCVT_S_D, Some(1)),
+        fp("fadd.d", ANY_RM, d_f7::FADD_D, None),
+        fp("fsub.d", ANY_RM, d_f7::FSUB_D, None),
+        fp("fmul.d", ANY_RM, d_f7::FMUL_D, None),
+        fp("fdiv.d", ANY_RM, d_f7::FDIV_D, None),
+        fp("fsqrt.d", ANY_RM, d_f7::FSQRT_D, Some(0)),
+        fp("fsgnj.d", &[0, 1, 2], d_f7::FSGNJ_D, None),
+        fp("fmin/fmax.d", &[0, 1], d_f7::FMIN_MAX_D, None),
+        fp("fcmp.d", &[0, 1, 2], d_f7::FCMP_D, None),
+        fp("fmv.x.d", &[0], d_f7::FCLASS_MV_X_D, Some(0)),
+        fp("fclass.d", &[1], d_f7::FCLASS_MV_X_D, Some(0)),
+        fp("fmv.d.x", &[0], d_f7::FMV_D_X, Some(0)),
+        fp("fcvt.d.s", ANY_RM, f_f7::FCVT_DS, Some(0)),
+    ]);
+    // Conversions to and from w, wu, l and lu.
+    for rs2 in 0..4 {
+        v.push(fp("fcvt.w.s", ANY_RM, f_f7::FCVT_W_F, Some(rs2)));
+        v.push(fp("fcvt.s.w", ANY_RM, f_f7::FCVT_F_W, Some(rs2)));
+        v.push(fp("fcvt.w.d", ANY_RM, d_f7::FCVT_W_D, Some(rs2)));
+        v.push(fp("fcvt.d.w", ANY_RM, d_f7::FCVT_D_W, Some(rs2)));
+    }
+    for opcode in [f_op::OP_FMADD, f_op::OP_FMSUB, f_op::OP_FNMSUB, f_op::OP_FNMADD] {
+        v.push(Spec { funct7_free: 1, ..spec("fmadd", R4, opcode, ANY_RM) });
+    }
+    v
+}
+
+/// Random raw material turned into an instance of a [`Spec`].
+#[derive(Clone, Debug)]
+struct Draw {
+    spec: usize,
+    regs: [u8; 4],
+    funct3: usize,
+    funct7: u32,
+    imm: i64,
+}
+
+fn draw() -> impl Strategy<Value = Draw> {
+    (any::<usize>(), any::<[u8; 4]>(), any::<usize>(), any::<u32>(), any::<i64>())
+        .prop_map(|(spec, regs, funct3, funct7, imm)| Draw { spec, regs, funct3, funct7, imm })
+}
+
+/// A concrete instruction with the fields `decode` should report.
+#[derive(Debug)]
+struct Inst {
+    spec: Spec,
+    rd: u8,
+    rs1: u8,
+    rs2: u8,
+    rs3: u8,
+    funct3: u32,
+    funct7: u32,
+    imm: i64,
+}
+
+fn instantiate(specs: &[Spec], d: &Draw) -> Inst {
+    let spec = specs[d.spec % specs.len()];
+    let [rd, rs1, rs2, rs3] = d.regs.map(|r| r & 0x1F);
+    let funct3 = if spec.funct3.is_empty() { 0 } else { spec.funct3[d.funct3 % spec.funct3.len()] };
+    let imm = match spec.imm {
+        Imm::None => 0,
+        Imm::Any12 => (d.imm << 52) >> 52,
+        Imm::Shift { upper, bits } => (upper << bits) | (d.imm & ((1 << bits) - 1)),
+        Imm::Branch => ((d.imm << 51) >> 51) & !1,
+        Imm::Upper => i64::from(d.imm as i32) & !0xFFF,
+        Imm::Jump => ((d.imm << 43) >> 43) & !1,
+    };
+    Inst {
+        spec,
+        rd,
+        rs1,
+        rs2: spec.rs2.unwrap_or(rs2),
+        rs3,
+        funct3,
+        funct7: spec.funct7 | (d.funct7 & spec.funct7_free),
+        imm,
+    }
+}
+
+fn encode_inst(x: &Inst) -> u32 {
+    let (op, f3, f7) = (x.spec.opcode, x.funct3, x.funct7);
+    let (rd, rs1, rs2, rs3) =
+        (RegIdx::new(x.rd), RegIdx::new(x.rs1), RegIdx::new(x.rs2), RegIdx::new(x.rs3));
+    match x.spec.format {
+        Format::R => encode::r_type(op, rd, f3, rs1, rs2, f7),
+        Format::R4 => encode::r4_type(op, rd, f3, rs1, rs2, rs3, f7),
+        Format::I => encode::i_type(op, rd, f3, rs1, x.imm),
+        Format::S => encode::s_type(op, f3, rs1, rs2, x.imm),
+        Format::B => encode::b_type(op, f3, rs1, rs2, x.imm),
+        Format::U => encode::u_type(op, rd, x.imm),
+        Format::J => encode::j_type(op, rd, x.imm),
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(4096))]
+
+    #[test]
+    fn valid_instructions_round_trip(d in draw()) {
+        let specs = specs();
+        let x = instantiate(&specs, &d);
+        let raw = encode_inst(&x);
+        let dec = decode(raw);
+        let name = x.spec.name;
+
+        prop_assert_eq!(encode::format(dec.opcode), x.spec.format, "{}", name);
+        prop_assert_eq!(dec.opcode, x.spec.opcode, "{}", name);
+        prop_assert_eq!(dec.raw, raw);
+        match x.spec.format {
+            Format::R | Format::R4 => {
+                prop_assert_eq!(dec.rd, RegIdx::new(x.rd), "{} rd", name);
+                prop_assert_eq!(dec.rs1, RegIdx::new(x.rs1), "{} rs1", name);
+                prop_assert_eq!(dec.rs2, RegIdx::new(x.rs2), "{} rs2", name);
+                prop_assert_eq!(dec.funct3, x.funct3, "{} funct3", name);
+                prop_assert_eq!(dec.imm, 0, "{} imm", name);
+                if x.spec.format == Format::R4 {
+                    prop_assert_eq!(raw.rs3(), RegIdx::new(x.rs3), "{} rs3", name);
+                    prop_assert_eq!(dec.funct7, (u32::from(x.rs3) << 2) | x.funct7, "{} funct7", name);
+                } else {
+                    prop_assert_eq!(dec.funct7, x.funct7, "{} funct7", name);
+                }
+            }
+            Format::I => {
+                prop_assert_eq!(dec.rd, RegIdx::new(x.rd), "{} rd", name);
+                prop_assert_eq!(dec.rs1, RegIdx::new(x.rs1), "{} rs1", name);
+                prop_assert_eq!(dec.funct3, x.funct3, "{} funct3", name);
+                prop_assert_eq!(dec.imm, x.imm, "{} imm", name);
+            }
+            Format::S | Format::B => {
+                prop_assert_eq!(dec.rs1, RegIdx::new(x.rs1), "{} rs1", name);
+                prop_assert_eq!(dec.rs2, RegIdx::new(x.rs2), "{} rs2", name);
+                prop_assert_eq!(dec.funct3, x.funct3, "{} funct3", name);
+                prop_assert_eq!(dec.imm, x.imm, "{} imm", name);
+            }
+            Format::U | Format::J => {
+                prop_assert_eq!(dec.rd, RegIdx::new(x.rd), "{} rd", name);
+                prop_assert_eq!(dec.imm, x.imm, "{} imm", name);
+            }
+        }
+        prop_assert_eq!(encode::encode(&dec), raw, "{} re-encode", name);
+        let text = disassemble(raw);
+        prop_assert!(!text.starts_with("unknown"), "{}: {:#010x} -> {}", name, raw, text);
+    }
+
+    #[test]
+    fn any_word_survives_decode_and_encode(raw in any::<u32>()) {
+        prop_assert_eq!(encode::encode(&decode(raw)), raw);
+    }
+
+    #[test]
+    fn system_words_round_trip(csr in 0u32..0x1000, rs1 in 0u8..32, funct3 in 0u32..8, rd in 0u8..32) {
+        let raw = (csr << 20) | (u32::from(rs1) << 15) | (funct3 << 12) | (u32::from(rd) << 7) | sys_op::OP_SYSTEM;
+        let dec = decode(raw);
+        prop_assert_eq!(encode::format(dec.opcode), Format::R);
+        prop_assert_eq!(encode::encode(&dec), raw);
+        prop_assert_eq!(raw.csr().as_u32(), csr);
+    }
+}
+
+#[test]
+fn immediates_keep_only_the_bits_their_format_holds() {
+    let x1 = RegIdx::new(1);
+    assert_eq!(decode(encode::i_type(i_op::OP_IMM, x1, 0, x1, 0x1801)).imm, -0x7ff);
+    assert_eq!(decode(encode::b_type(i_op::OP_BRANCH, 0, x1, x1, 0x7)).imm, 0x6);
+    assert_eq!(decode(encode::u_type(i_op::OP_LUI, x1, 0x1234_5fff)).imm, 0x1234_5000);
+    assert_eq!(decode(encode::j_type(i_op::OP_JAL, x1, -0x10_0001)).imm, 0xf_fffe);
+}
@@ -10,6 +10,13 @@
 /// supported RV64GC instruction formats.
 pub mod decode_properties;
 
+/// Encode → decode round-trip property tests.
+///
+/// This module generates random valid RV64I/M/A/F/D instructions with the
+/// encoder and checks that the decoder extracts the fields they were built
+/// from.
+pub mod encode_roundtrip;
+
 /// Instruction disassembler unit tests.
 ///
 /// This module verifies that the disassembler correctly converts raw instruction
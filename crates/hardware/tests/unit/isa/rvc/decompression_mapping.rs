@@ -358,11 +358,14 @@ fn rvc_c_slli() {
 }
 
 #[test]
-fn rvc_c_slli_rd0_illegal() {
-    // C.SLLI with rd=0 is reserved
+fn rvc_c_slli_rd0_hint() {
+    // C.SLLI with rd=0 is a HINT → SLLI x0, x0, shamt
     let cinst: u16 = 0b0000_0000_0001_0010;
-    let expanded = expand(cinst);
-    assert_eq!(expanded, 0);
+    let d = expand_and_decode(cinst);
+    assert_eq!(d.opcode, i_op::OP_IMM);
+    assert_eq!(d.funct3, i_f3::SLL);
+    assert_eq!(d.rd, RegIdx::new(0));
+    assert_eq!(d.rs1, RegIdx::new(0));
 }
 
 #[test]
@@ -0,0 +1,327 @@
+//! Exhaustive RVC Expansion Tests.
+//!
+//! Expands every 16-bit pattern in quadrants 0–2 and compares the result
+//! against a reference model written directly from the RV64C opcode
+//! listing. Immediates are described with the spec's own bit-scatter
+//! notation (e.g. `"5:4|9:6|2|3"`) so the reference shares no field
+//! extraction code with the expander.
+//!
+//! Reserved encodings must expand to `0`, which fetch turns into an
+//! illegal-instruction trap. HINT encodings (e.g. `c.addi x0, imm`) expand
+//! to their base instruction, which writes `x0` and has no effect.
+//!
+//! Reference: RISC-V ISA Spec Volume I, Chapter 16 (RVC), Tables 16.5–16.7.
+
+use rvsim_core::isa::privileged::opcodes as sys_op;
+use rvsim_core::isa::rv64f::opcodes as f_op;
+use rvsim_core::isa::rv64i::opcodes as i_op;
+use rvsim_core::isa::rvc::expand::expand;
+
+// ──────────────────────────────────────────────────────────
+// Spec-notation helpers
+// ──────────────────────────────────────────────────────────
+
+/// Extracts `c[hi:lo]`.
+fn bits(c: u16, hi: u32, lo: u32) -> u32 {
+    (u32::from(c) >> lo) & ((1 << (hi - lo + 1)) - 1)
+}
+
+/// Scatters instruction bits `c[hi..]` into an immediate using the spec's
+/// notation: `"5:4|9:6|2|3"` at `hi = 12` means `c[12:11]` → `imm[5:4]`,
+/// `c[10:7]` → `imm[9:6]`, `c[6]` → `imm[2]`, `c[5]` → `imm[3]`.
+fn scatter(c: u16, hi: u32, spec: &str) -> u32 {
+    let mut pos = hi;
+    let mut imm = 0;
+    for field in spec.split('|') {
+        let (top, bottom) = field.split_once(':').unwrap_or((field, field));
+        let (top, bottom) = (top.parse::<u32>().unwrap(), bottom.parse::<u32>().unwrap());
+        for imm_bit in (bottom..=top).rev() {
+            imm |= ((u32::from(c) >> pos) & 1) << imm_bit;
+            pos = pos.wrapping_sub(1);
+        }
+    }
+    imm
+}
+
+/// Sign-extends the low `width` bits of `v`.
+fn sext(v: u32, width: u32) -> i32 {
+    ((v << (32 - width)) as i32) >> (32 - width)
+}
+
+/// Compressed register `rd'`/`rs1'`/`rs2'` field starting at bit `lo`.
+fn creg(c: u16, lo: u32) -> u32 {
+    8 + bits(c, lo + 2, lo)
+}
+
+// ──────────────────────────────────────────────────────────
+// 32-bit encoders
+// ──────────────────────────────────────────────────────────
+
+fn i_type(imm: i32, rs1: u32, f3: u32, rd: u32, op: u32) -> u32 {
+    ((imm as u32 & 0xFFF) << 20) | (rs1 << 15) | (f3 << 12) | (rd << 7) | op
+}
+
+fn s_type(imm: i32, rs2: u32, rs1: u32, f3: u32, op: u32) -> u32 {
+    let imm = imm as u32;
+    ((imm >> 5 & 0x7F) << 25) | (rs2 << 20) | (rs1 << 15) | (f3 << 12) | ((imm & 0x1F) << 7) | op
+}
+
+fn r_type(f7: u32, rs2: u32, rs1: u32, f3: u32, rd: u32, op: u32) -> u32 {
+    (f7 << 25) | (rs2 << 20) | (rs1 << 15) | (f3 << 12) | (rd << 7) | op
+}
+
+fn b_type(imm: i32, rs1: u32, f3: u32) -> u32 {
+    let imm = imm as u32;
+    ((imm >> 12 & 1) << 31)
+        | ((imm >> 5 & 0x3F) << 25)
+        | (rs1 << 15)
+        | (f3 << 12)
+        | ((imm >> 1 & 0xF) << 8)
+        | ((imm >> 11 & 1) << 7)
+        | i_op::OP_BRANCH
+}
+
+fn j_type(imm: i32, rd: u32) -> u32 {
+    let imm = imm as u32;
+    ((imm >> 20 & 1) << 31)
+        | ((imm >> 1 & 0x3FF) << 21)
+        | ((imm >> 11 & 1) << 20)
+        | ((imm >> 12 & 0xFF) << 12)
+        | (rd << 7)
+        | i_op::OP_JAL
+}
+
+// ──────────────────────────────────────────────────────────
+// Reference model
+// ──────────────────────────────────────────────────────────
+
+/// The RV64C expansion of `c`, or `None` if the encoding is reserved.
+fn reference(c: u16) -> Option<u32> {
+    let funct3 = bits(c, 15, 13);
+    let rd = bits(c, 11, 7);
+    let rs2 = bits(c, 6, 2);
+    let imm6 = sext(scatter(c, 12, "5") | scatter(c, 6, "4:0"), 6);
+    let shamt = scatter(c, 12, "5") | scatter(c, 6, "4:0");
+
+    let expanded = match (bits(c, 1, 0), funct3) {
+        // ── Quadrant 0 ──
+        (0b00, 0b000) => {
+            let nzuimm = scatter(c, 12, "5:4|9:6|2|3");
+            if nzuimm == 0 {
+                return None;
+            }
+            i_type(nzuimm as i32, 2, 0b000, creg(c, 2), i_op::OP_IMM)
+        }
+        (0b00, 0b001) => {
+            let uimm = scatter(c, 12, "5:3") | scatter(c, 6, "7:6");
+            i_type(uimm as i32, creg(c, 7), 0b011, creg(c, 2), f_op::OP_LOAD_FP)
+        }
+        (0b00, 0b010) => {
+            let uimm = scatter(c, 12, "5:3") | scatter(c, 6, "2|6");
+            i_type(uimm as i32, creg(c, 7), 0b010, creg(c, 2), i_op::OP_LOAD)
+        }
+        (0b00, 0b011) => {
+            let uimm = scatter(c, 12, "5:3") | scatter(c, 6, "7:6");
+            i_type(uimm as i32, creg(c, 7), 0b011, creg(c, 2), i_op::OP_LOAD)
+        }
+        (0b00, 0b101) => {
+            let uimm = scatter(c, 12, "5:3") | scatter(c, 6, "7:6");
+            s_type(uimm as i32, creg(c, 2), creg(c, 7), 0b011, f_op::OP_STORE_FP)
+        }
+        (0b00, 0b110) => {
+            let uimm = scatter(c, 12, "5:3") | scatter(c, 6, "2|6");
+            s_type(uimm as i32, creg(c, 2), creg(c, 7), 0b010, i_op::OP_STORE)
+        }
+        (0b00, 0b111) => {
+            let uimm = scatter(c, 12, "5:3") | scatter(c, 6, "7:6");
+            s_type(uimm as i32, creg(c, 2), creg(c, 7), 0b011, i_op::OP_STORE)
+        }
+
+        // ── Quadrant 1 ──
+        (0b01, 0b000) => i_type(imm6, rd, 0b000, rd, i_op::OP_IMM),
+        (0b01, 0b001) => {
+            if rd == 0 {
+                return None;
+            }
+            i_type(imm6, rd, 0b000, rd, i_op::OP_IMM_32)
+        }
+        (0b01, 0b010) => i_type(imm6, 0, 0b000, rd, i_op::OP_IMM),
+        (0b01, 0b011) if rd == 2 => {
+            let nzimm = sext(scatter(c, 12, "9") | scatter(c, 6, "4|6|8:7|5"), 10);
+            if nzimm == 0 {
+                return None;
+            }
+            i_type(nzimm, 2, 0b000, 2, i_op::OP_IMM)
+        }
+        (0b01, 0b011) => {
+            let nzimm = sext(scatter(c, 12, "17") | scatter(c, 6, "16:12"), 18);
+            if nzimm == 0 {
+                return None;
+            }
+            (nzimm as u32 & 0xFFFF_F000) | (rd << 7) | i_op::OP_LUI
+        }
+        (0b01, 0b100) => {
+            let rd = creg(c, 7);
+            let rs2 = creg(c, 2);
+            match (bits(c, 11, 10), bits(c, 12, 12), bits(c, 6, 5)) {
+                (0b00, _, _) => i_type(shamt as i32, rd, 0b101, rd, i_op::OP_IMM),
+                (0b01, _, _) => i_type((0x400 | shamt) as i32, rd, 0b101, rd, i_op::OP_IMM),
+                (0b10, _, _) => i_type(imm6, rd, 0b111, rd, i_op::OP_IMM),
+                (0b11, 0, 0b00) => r_type(0x20, rs2, rd, 0b000, rd, i_op::OP_REG),
+                (0b11, 0, 0b01) => r_type(0, rs2, rd, 0b100, rd, i_op::OP_REG),
+                (0b11, 0, 0b10) => r_type(0, rs2, rd, 0b110, rd, i_op::OP_REG),
+                (0b11, 0, 0b11) => r_type(0, rs2, rd, 0b111, rd, i_op::OP_REG),
+                (0b11, 1, 0b00) => r_type(0x20, rs2, rd, 0b000, rd, i_op::OP_REG_32),
+                (0b11, 1, 0b01) => r_type(0, rs2, rd, 0b000, rd, i_op::OP_REG_32),
+                _ => return None,
+            }
+        }
+        (0b01, 0b101) => j_type(sext(scatter(c, 12, "11|4|9:8|10|6|7|3:1|5"), 12), 0),
+        (0b01, 0b110 | 0b111) => {
+            let offset = sext(scatter(c, 12, "8|4:3") | scatter(c, 6, "7:6|2:1|5"), 9);
+            b_type(offset, creg(c, 7), funct3 - 0b110)
+        }
+
+        // ── Quadrant 2 ──
+        (0b10, 0b000) => i_type(shamt as i32, rd, 0b001, rd, i_op::OP_IMM),
+        (0b10, 0b001) => {
+            let uimm = scatter(c, 12, "5") | scatter(c, 6, "4:3|8:6");
+            i_type(uimm as i32, 2, 0b011, rd, f_op::OP_LOAD_FP)
+        }
+        (0b10, 0b010) => {
+            if rd == 0 {
+                return None;
+            }
+            let uimm = scatter(c, 12, "5") | scatter(c, 6, "4:2|7:6");
+            i_type(uimm as i32, 2, 0b010, rd, i_op::OP_LOAD)
+        }
+        (0b10, 0b011) => {
+            if rd == 0 {
+                return None;
+            }
+            let uimm = scatter(c, 12, "5") | scatter(c, 6, "4:3|8:6");
+            i_type(uimm as i32, 2, 0b011, rd, i_op::OP_LOAD)
+        }
+        (0b10, 0b100) => match (bits(c, 12, 12), rd, rs2) {
+            (0, 0, 0) => return None,
+            (0, _, 0) => i_type(0, rd, 0b000, 0, i_op::OP_JALR),
+            (0, _, _) => r_type(0, rs2, 0, 0b000, rd, i_op::OP_REG),
+            (1, 0, 0) => sys_op::EBREAK,
+            (1, _, 0) => i_type(0, rd, 0b000, 1, i_op::OP_JALR),
+            _ => r_type(0, rs2, rd, 0b000, rd, i_op::OP_REG),
+        },
+        (0b10, 0b101) => {
+            let uimm = scatter(c, 12, "5:3|8:6");
+            s_type(uimm as i32, rs2, 2, 0b011, f_op::OP_STORE_FP)
+        }
+        (0b10, 0b110) => {
+            let uimm = scatter(c, 12, "5:2|7:6");
+            s_type(uimm as i32, rs2, 2, 0b010, i_op::OP_STORE)
+        }
+        (0b10, 0b111) => {
+            let uimm = scatter(c, 12, "5:3|8:6");
+            s_type(uimm as i32, rs2, 2, 0b011, i_op::OP_STORE)
+        }
+        // Quadrant 0 funct3=100 is reserved; quadrant 3 is not compressed.
+        _ => return None,
+    };
+    Some(expanded)
+}
+
+// ══════════════════════════════════════════════════════════
+// Exhaustive comparison
+// ══════════════════════════════════════════════════════════
+
+#[test]
+fn rvc_every_encoding_matches_reference() {
+    let mut mismatches = Vec::new();
+    for c in 0..=u16::MAX {
+        if c & 0x3 == 0x3 {
+            continue;
+        }
+        let expected = reference(c).unwrap_or(0);
+        let actual = expand(c);
+        if actual != expected {
+            mismatches
+                .push(format!("{c:#06x}: expanded {actual:#010x}, expected {expected:#010x}"));
+        }
+    }
+    assert!(
+        mismatches.is_empty(),
+        "{} mismatching encodings, first few:\n{}",
+        mismatches.len(),
+        mismatches.iter().take(16).cloned().collect::<Vec<_>>().join("\n")
+    );
+}
+
+#[test]
+fn rvc_reserved_encodings_are_illegal() {
+    // Spot-check the reserved rows of the opcode map by name.
+    let reserved: &[(u16, &str)] = &[
+        (0x0000, "all-zero (c.addi4spn nzuimm=0)"),
+        (0x0010, "c.addi4spn nzuimm=0, rd'=x12"),
+        (0x8000, "quadrant 0 funct3=100"),
+        (0x2001, "c.addiw rd=x0"),
+        (0x6101, "c.addi16sp nzimm=0"),
+        (0x6081, "c.lui nzimm=0"),
+        (0x9C41, "c.subw-space funct=10"),
+        (0x9C61, "c.subw-space funct=11"),
+        (0x4002, "c.lwsp rd=x0"),
+        (0x6002, "c.ldsp rd=x0"),
+        (0x8002, "c.jr rs1=x0"),
+    ];
+    for &(c, what) in reserved {
+        assert_eq!(expand(c), 0, "{what} ({c:#06x}) must be reserved");
+    }
+}
+
+#[test]
+fn rvc_hint_encodings_expand_to_base_instruction() {
+    // Spot-check the HINT rows of the opcode map by name: each writes x0.
+    let add_x0_a0 = r_type(0, 10, 0, 0b000, 0, i_op::OP_REG);
+    let hints: &[(u16, u32, &str)] = &[
+        (0x0005, i_type(1, 0, 0b000, 0, i_op::OP_IMM), "c.nop nzimm=1"),
+        (0x4015, i_type(5, 0, 0b000, 0, i_op::OP_IMM), "c.li x0, 5"),
+        (0x802A, add_x0_a0, "c.mv x0, a0"),
+        (0x902A, add_x0_a0, "c.add x0, a0"),
+        (0x000E, i_type(3, 0, 0b001, 0, i_op::OP_IMM), "c.slli x0, 3"),
+        (0x100E, i_type(35, 0, 0b001, 0, i_op::OP_IMM), "c.slli x0, 35"),
+    ];
+    for &(c, expected, what) in hints {
+        assert_eq!(expand(c), expected, "{what} ({c:#06x}) must expand as a HINT");
+    }
+}
+
+#[test]
+fn rvc_fp_stack_accesses_expand() {
+    // c.fldsp f8, 8(sp): uimm[4:3]=01 in c[6:5], rd=8
+    assert_eq!(expand(0x2422), i_type(8, 2, 0b011, 8, f_op::OP_LOAD_FP));
+    // c.fsdsp f9, 72(sp): uimm[5:3]=001 in c[12:10], uimm[8:6]=001 in c[9:7]
+    assert_eq!(expand(0xA4A6), s_type(72, 9, 2, 0b011, f_op::OP_STORE_FP));
+}
+
+#[test]
+fn rvc_fp_stack_accesses_scale_offsets() {
+    // c.fldsp f1, 32(sp): uimm[5] in c[12]
+    assert_eq!(expand(0x3082), i_type(32, 2, 0b011, 1, f_op::OP_LOAD_FP));
+    // c.fldsp f31, 504(sp): largest offset, every uimm bit set
+    assert_eq!(expand(0x3FFE), i_type(504, 2, 0b011, 31, f_op::OP_LOAD_FP));
+    // c.fsdsp f0, 0(sp)
+    assert_eq!(expand(0xA002), s_type(0, 0, 2, 0b011, f_op::OP_STORE_FP));
+    // c.fsdsp f31, 504(sp)
+    assert_eq!(expand(0xBFFE), s_type(504, 31, 2, 0b011, f_op::OP_STORE_FP));
+}
+
+#[test]
+fn rvc_fp_register_accesses_expand() {
+    // c.fld f8, 0(s0): rd'=0, rs1'=0
+    assert_eq!(expand(0x2000), i_type(0, 8, 0b011, 8, f_op::OP_LOAD_FP));
+    // c.fld f9, 16(a0): uimm[5:3]=010 in c[12:10]
+    assert_eq!(expand(0x2904), i_type(16, 10, 0b011, 9, f_op::OP_LOAD_FP));
+    // c.fld f15, 248(a5): largest offset, uimm[7:6]=11 in c[6:5]
+    assert_eq!(expand(0x3FFC), i_type(248, 15, 0b011, 15, f_op::OP_LOAD_FP));
+    // c.fsd f10, 8(s1)
+    assert_eq!(expand(0xA488), s_type(8, 10, 9, 0b011, f_op::OP_STORE_FP));
+    // c.fsd f15, 200(s0): uimm[5:3]=001, uimm[7:6]=11
+    assert_eq!(expand(0xA47C), s_type(200, 15, 8, 0b011, f_op::OP_STORE_FP));
+}
@@ -1 +1,2 @@
 pub mod decompression_mapping;
+pub mod exhaustive;
@@ -0,0 +1,128 @@
+//! Commit Breakpoint Tests.
+//!
+//! Arms a breakpoint with `Simulator::set_breakpoint` and checks that commit
+//! stops with exactly the older instructions retired, on both backends, also
+//! when the target is either half of a macro-op fusion pair.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use rvsim_core::Simulator;
+use rvsim_core::common::{PhysAddr, RegIdx};
+use rvsim_core::config::Config;
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::core::pipeline::frontend::fusion::FusionPattern;
+use rvsim_core::soc::System;
+
+/// `li x5, 1; li x6, 2; li x7, 3; li x8, 4; j .`
+fn boot(backend: BackendType) -> Simulator {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.pipeline.width = 4;
+    let base = config.system.ram_base;
+    let mut sim = Simulator::new(System::new(&config, ""), &config);
+    let program = [
+        InstructionBuilder::new().addi(5, 0, 1).build(),
+        InstructionBuilder::new().addi(6, 0, 2).build(),
+        InstructionBuilder::new().addi(7, 0, 3).build(),
+        InstructionBuilder::new().addi(8, 0, 4).build(),
+        InstructionBuilder::new().jal(0, 0).build(),
+    ];
+    for (i, inst) in program.into_iter().enumerate() {
+        sim.cpu.bus.bus.write_u32(PhysAddr::new(base + 4 * i as u64), inst);
+    }
+    sim.cpu.pc = base;
+    sim
+}
+
+/// `li x6, 2; lui x5, 0x12345; addi x5, x5, 0x678; li x7, 3; j .`, with
+/// `lui`+`addi` fusion on.
+fn boot_fused(backend: BackendType) -> Simulator {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.pipeline.width = 4;
+    config.pipeline.fusion = vec![FusionPattern::LuiAddi];
+    let base = config.system.ram_base;
+    let mut sim = Simulator::new(System::new(&config, ""), &config);
+    let program = [
+        InstructionBuilder::new().addi(6, 0, 2).build(),
+        InstructionBuilder::new().lui(5, 0x12345).build(),
+        InstructionBuilder::new().addi(5, 5, 0x678).build(),
+        InstructionBuilder::new().addi(7, 0, 3).build(),
+        InstructionBuilder::new().jal(0, 0).build(),
+    ];
+    for (i, inst) in program.into_iter().enumerate() {
+        sim.cpu.bus.bus.write_u32(PhysAddr::new(base + 4 * i as u64), inst);
+    }
+    sim.cpu.pc = base;
+    sim
+}
+
+/// Ticks until the breakpoint is hit, returning whether it was within `cycles`.
+fn run_to_breakpoint(sim: &mut Simulator, cycles: u64) -> bool {
+    for _ in 0..cycles {
+        sim.tick().unwrap();
+        if sim.take_breakpoint_hit() {
+            return true;
+        }
+    }
+    false
+}
+
+fn regs(sim: &Simulator) -> [u64; 4] {
+    [5, 6, 7, 8].map(|r| sim.cpu.regs.read(RegIdx::new(r)))
+}
+
+#[test]
+fn breakpoint_stops_before_target_commits() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut sim = boot(backend);
+        let target = sim.cpu.pc + 8;
+        sim.set_breakpoint(Some(target));
+        assert!(run_to_breakpoint(&mut sim, 200), "{backend:?}");
+        assert_eq!(sim.cpu.pc, target, "{backend:?}");
+        assert_eq!(sim.cpu.committed_next_pc, target, "{backend:?}");
+        assert_eq!(regs(&sim), [1, 2, 0, 0], "{backend:?}");
+        assert_eq!(sim.cpu.break_pc, None, "hit disarms the breakpoint");
+    }
+}
+
+#[test]
+fn rearming_at_current_pc_makes_progress() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut sim = boot(backend);
+        let target = sim.cpu.pc + 8;
+        sim.set_breakpoint(Some(target));
+        assert!(run_to_breakpoint(&mut sim, 200), "{backend:?}");
+
+        sim.set_breakpoint(Some(target));
+        assert!(!run_to_breakpoint(&mut sim, 200), "{backend:?}: target retires once");
+        assert_eq!(regs(&sim), [1, 2, 3, 4], "{backend:?}");
+    }
+}
+
+#[test]
+fn breakpoint_on_fused_pair_stops_at_either_half() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let x5_x6 = |sim: &Simulator| [5, 6].map(|r| sim.cpu.regs.read(RegIdx::new(r)));
+
+        let mut sim = boot_fused(backend);
+        let head = sim.cpu.pc + 4;
+        sim.set_breakpoint(Some(head));
+        assert!(run_to_breakpoint(&mut sim, 200), "{backend:?}: head");
+        assert_eq!(sim.cpu.pc, head, "{backend:?}: head");
+        assert_eq!(x5_x6(&sim), [0, 2], "{backend:?}: head");
+        assert!(!run_to_breakpoint(&mut sim, 200));
+        assert_eq!(sim.cpu.stats.fused_pairs, 1, "{backend:?}: pair fuses after the hit");
+
+        // The tail of an armed breakpoint is not fused, so the run stops
+        // between the two halves.
+        let mut sim = boot_fused(backend);
+        let tail = sim.cpu.pc + 8;
+        sim.set_breakpoint(Some(tail));
+        assert!(run_to_breakpoint(&mut sim, 200), "{backend:?}: tail");
+        assert_eq!(sim.cpu.pc, tail, "{backend:?}: tail");
+        assert_eq!(x5_x6(&sim), [0x1234_5000, 2], "{backend:?}: tail");
+        assert_eq!(sim.cpu.stats.fused_pairs, 0, "{backend:?}: tail");
+        assert!(!run_to_breakpoint(&mut sim, 200));
+        assert_eq!(x5_x6(&sim), [0x1234_5678, 2], "{backend:?}: tail");
+    }
+}
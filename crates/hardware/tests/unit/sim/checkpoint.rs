@@ -0,0 +1,200 @@
+//! Checkpoint Tests.
+//!
+//! Stops a load/store benchmark partway, saves a checkpoint, restores it
+//! into a freshly built simulator and checks that the run ends with the same
+//! exit code and instruction count as an uninterrupted one, with and without
+//! microarchitectural state. Device state survives the round trip, and
+//! checkpoints that are not rvsim checkpoints, are of another version, or
+//! were taken with other RAM banks are rejected.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::Simulator;
+use rvsim_core::common::{PhysAddr, RegIdx, SimError};
+use rvsim_core::config::Config;
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::sim::checkpoint::VERSION;
+use std::path::Path;
+
+/// Offset of the benchmark's array from the start of RAM.
+const ARRAY_OFFSET: u64 = 0x1000;
+
+/// Elements the benchmark writes and sums.
+const ELEMENTS: i32 = 64;
+
+/// Cycle budget for a whole run.
+const MAX_CYCLES: u64 = 1_000_000;
+
+/// Builds the benchmark: fill an array with a running checksum while reading
+/// each element back, sum the array, and exit with the low bits of the sum.
+fn benchmark() -> Vec<u32> {
+    let b = InstructionBuilder::new;
+    vec![
+        b().addi(7, 0, ELEMENTS).build(),
+        b().addi(6, 0, 1).build(),
+        b().addi(5, 10, 0).build(),
+        // Fill loop.
+        b().add(6, 6, 6).build(),
+        b().xor(6, 6, 7).build(),
+        b().sd(5, 6, 0).build(),
+        b().ld(28, 5, 0).build(),
+        b().add(6, 6, 28).build(),
+        b().addi(5, 5, 8).build(),
+        b().addi(7, 7, -1).build(),
+        b().bne(7, 0, -28).build(),
+        // Sum loop.
+        b().addi(5, 10, 0).build(),
+        b().addi(7, 0, ELEMENTS).build(),
+        b().ld(28, 5, 0).build(),
+        b().add(6, 6, 28).build(),
+        b().addi(5, 5, 8).build(),
+        b().addi(7, 7, -1).build(),
+        b().bne(7, 0, -16).build(),
+        b().andi(10, 6, 0x7ff).build(),
+        b().addi(17, 0, 93).build(),
+        b().ecall().build(),
+    ]
+}
+
+/// Builds a simulator with the benchmark at the start of RAM and x10
+/// pointing at its array.
+fn boot(backend: BackendType) -> TestContext {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    let base = config.system.ram_base;
+    let mut ctx = TestContext::with_config(&config).load_program(base, &benchmark());
+    ctx.sim.write_reg(RegIdx::new(10), base + ARRAY_OFFSET);
+    ctx
+}
+
+/// Runs until the program exits and returns its exit code and `minstret`.
+fn finish(ctx: &mut TestContext) -> (u64, u64) {
+    let code = ctx.run_to_exit_code(MAX_CYCLES);
+    (code, ctx.sim.cpu.csrs.minstret)
+}
+
+/// Runs `cycles` cycles, checking that the program has not exited.
+fn run_for(sim: &mut Simulator, cycles: u64) {
+    for _ in 0..cycles {
+        sim.tick().unwrap();
+        assert!(sim.cpu.exit_code.is_none(), "exited before the checkpoint");
+    }
+}
+
+/// Rewrites the checkpoint header at `path` with `edit`.
+fn edit_header(path: &Path, edit: impl FnOnce(&mut serde_json::Value)) {
+    let bytes = std::fs::read(path).unwrap();
+    let len = u64::from_le_bytes(bytes[..8].try_into().unwrap()) as usize;
+    let mut header: serde_json::Value = serde_json::from_slice(&bytes[8..8 + len]).unwrap();
+    edit(&mut header);
+    let json = serde_json::to_vec(&header).unwrap();
+    let mut out = (json.len() as u64).to_le_bytes().to_vec();
+    out.extend(json);
+    out.extend(&bytes[8 + len..]);
+    std::fs::write(path, out).unwrap();
+}
+
+#[test]
+fn restored_run_matches_uninterrupted_run() {
+    let dir = tempfile::tempdir().unwrap();
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut reference = boot(backend);
+        let expected = finish(&mut reference);
+        for microarch in [false, true] {
+            let path = dir.path().join(format!("{backend:?}-{microarch}.ckpt"));
+            let mut ctx = boot(backend);
+            run_for(&mut ctx.sim, reference.sim.cpu.stats.cycles / 2);
+            ctx.sim.save_checkpoint(&path, microarch).unwrap();
+
+            let mut restored = boot(backend);
+            restored.sim.load_checkpoint(&path).unwrap();
+            let bp = |sim: &Simulator| sim.cpu.branch_predictor.save_state();
+            assert_eq!(bp(&restored.sim) == bp(&ctx.sim), microarch, "{backend:?}: predictor");
+            assert_eq!(finish(&mut restored), expected, "{backend:?} microarch={microarch}");
+
+            // Saving flushes the pipeline but does not disturb the run.
+            assert_eq!(finish(&mut ctx), expected, "{backend:?}: saving hart");
+        }
+    }
+}
+
+#[test]
+fn checkpoint_before_first_tick_restores_entry_state() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("entry.ckpt");
+    let expected = finish(&mut boot(BackendType::InOrder));
+    boot(BackendType::InOrder).sim.save_checkpoint(&path, false).unwrap();
+
+    let config = Config::default();
+    let mut ctx = TestContext::with_config(&config);
+    ctx.sim.load_checkpoint(&path).unwrap();
+    assert_eq!(ctx.sim.cpu.pc, config.system.ram_base);
+    assert_eq!(finish(&mut ctx), expected);
+}
+
+#[test]
+fn zero_pages_are_skipped_and_cleared() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("ram.ckpt");
+    let config = Config::default();
+    let base = config.system.ram_base;
+    boot(BackendType::InOrder).sim.save_checkpoint(&path, false).unwrap();
+    let size = std::fs::metadata(&path).unwrap().len();
+    assert!(size < 64 * 1024, "checkpoint of a nearly empty RAM is {size} bytes");
+
+    let mut sim = boot(BackendType::InOrder).sim;
+    let scratch = PhysAddr::new(base + 0x10_0000);
+    sim.cpu.bus.bus.write_u64(scratch, 0xdead_beef);
+    sim.load_checkpoint(&path).unwrap();
+    assert_eq!(sim.cpu.bus.bus.read_u64(scratch), 0);
+    assert_eq!(sim.cpu.bus.bus.read_u32(PhysAddr::new(base)), benchmark()[0]);
+}
+
+#[test]
+fn device_state_survives_restore() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("devices.ckpt");
+    let config = Config::default();
+    let mut sim = boot(BackendType::InOrder).sim;
+    sim.cpu.bus.bus.clint_mut().unwrap().set_mtimecmp(0, 0x1234_5678);
+    sim.cpu.bus.bus.uart_mut().unwrap().push_input(b"abc");
+    run_for(&mut sim, 10);
+    sim.save_checkpoint(&path, false).unwrap();
+
+    let mut restored = boot(BackendType::InOrder).sim;
+    restored.load_checkpoint(&path).unwrap();
+    assert_eq!(restored.cpu.bus.bus.clint_mut().unwrap().mtimecmp(0), Some(0x1234_5678));
+    let rbr = PhysAddr::new(config.system.uart_base);
+    let input: Vec<u8> = (0..3).map(|_| restored.cpu.bus.bus.read_u8(rbr)).collect();
+    assert_eq!(input, b"abc");
+}
+
+#[test]
+fn mismatched_checkpoints_are_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("bad.ckpt");
+    let mut sim = boot(BackendType::InOrder).sim;
+    let check = |sim: &mut Simulator, needle: &str| match sim.load_checkpoint(&path) {
+        Err(SimError::Checkpoint { reason, .. }) => {
+            assert!(reason.contains(needle), "{reason}");
+        }
+        other => panic!("expected a checkpoint error, got {other:?}"),
+    };
+
+    std::fs::write(&path, b"\x02\0\0\0\0\0\0\0{}").unwrap();
+    check(&mut sim, "not an rvsim checkpoint");
+    std::fs::write(&path, b"not a checkpoint").unwrap();
+    check(&mut sim, "not an rvsim checkpoint");
+
+    sim.save_checkpoint(&path, false).unwrap();
+    edit_header(&path, |h| h["version"] = (VERSION + 1).into());
+    check(&mut sim, "unsupported version");
+
+    sim.save_checkpoint(&path, false).unwrap();
+    edit_header(&path, |h| h["ram"][0]["size"] = 4096.into());
+    check(&mut sim, "RAM banks");
+
+    sim.save_checkpoint(&path, false).unwrap();
+    edit_header(&path, |h| h["devices"][0]["base"] = 0.into());
+    check(&mut sim, "to restore");
+}
@@ -0,0 +1,244 @@
+//! Generated Device Tree Tests.
+//!
+//! Parses the flattened device tree produced by `generate_dtb` and checks it
+//! against the devices actually registered on the system bus.
+
+use std::collections::HashMap;
+
+use rvsim_core::common::PhysAddr;
+use rvsim_core::config::{Config, DeviceConfig, DeviceKind, MemoryRegion, NetBackend};
+use rvsim_core::core::Cpu;
+use rvsim_core::isa::abi;
+use rvsim_core::sim::dtb::generate_dtb;
+use rvsim_core::sim::loader;
+use rvsim_core::soc::System;
+
+const FDT_MAGIC: u32 = 0xd00d_feed;
+const FDT_BEGIN_NODE: u32 = 1;
+const FDT_END_NODE: u32 = 2;
+const FDT_PROP: u32 = 3;
+const FDT_NOP: u32 = 4;
+const FDT_END: u32 = 9;
+
+/// Node path (e.g. `/soc/uart@10000000`) → property name → raw value.
+type Tree = HashMap<String, HashMap<String, Vec<u8>>>;
+
+fn be32(blob: &[u8], off: usize) -> u32 {
+    u32::from_be_bytes(blob[off..off + 4].try_into().unwrap())
+}
+
+fn cstr(blob: &[u8], off: usize) -> String {
+    let end = blob[off..].iter().position(|&b| b == 0).unwrap();
+    String::from_utf8(blob[off..off + end].to_vec()).unwrap()
+}
+
+/// Minimal FDT parser: validates the header and flattens the structure block.
+fn parse(blob: &[u8]) -> Tree {
+    assert_eq!(be32(blob, 0), FDT_MAGIC, "bad FDT magic");
+    assert_eq!(be32(blob, 4) as usize, blob.len(), "totalsize must match blob length");
+    let struct_off = be32(blob, 8) as usize;
+    let strings_off = be32(blob, 12) as usize;
+
+    let mut tree = Tree::new();
+    let mut path: Vec<String> = Vec::new();
+    let mut off = struct_off;
+    loop {
+        let token = be32(blob, off);
+        off += 4;
+        match token {
+            FDT_BEGIN_NODE => {
+                let name = cstr(blob, off);
+                off = (off + name.len() + 1).next_multiple_of(4);
+                path.push(name);
+                let _ = tree.entry(format!("/{}", path[1..].join("/"))).or_default();
+            }
+            FDT_END_NODE => {
+                let _ = path.pop().unwrap();
+            }
+            FDT_PROP => {
+                let len = be32(blob, off) as usize;
+                let name = cstr(blob, strings_off + be32(blob, off + 4) as usize);
+                let value = blob[off + 8..off + 8 + len].to_vec();
+                off = (off + 8 + len).next_multiple_of(4);
+                let _ =
+                    tree.get_mut(&format!("/{}", path[1..].join("/"))).unwrap().insert(name, value);
+            }
+            FDT_NOP => {}
+            FDT_END => break,
+            other => panic!("unexpected FDT token {other}"),
+        }
+    }
+    assert!(path.is_empty(), "unbalanced FDT nodes");
+    tree
+}
+
+/// Decodes a `reg` property with `#address-cells = 2` and `#size-cells = 2`.
+fn reg_2_2(value: &[u8]) -> (u64, u64) {
+    let cell = |i: usize| u64::from(be32(value, i * 4));
+    ((cell(0) << 32) | cell(1), (cell(2) << 32) | cell(3))
+}
+
+fn device_range(system: &System, name: &str) -> (u64, u64) {
+    system.bus.devices().find(|d| d.name() == name).unwrap().address_range()
+}
+
+#[test]
+fn generated_dtb_parses() {
+    let config = Config::default();
+    let system = System::new(&config, "");
+    let tree = parse(&generate_dtb(&config, &system.bus));
+    assert!(tree.contains_key("/chosen"));
+    assert!(tree.contains_key("/cpus/cpu@0/interrupt-controller"));
+    assert!(tree.contains_key("/soc"));
+}
+
+#[test]
+fn uart_node_matches_uart_device() {
+    let mut config = Config::default();
+    config.system.uart_base = 0x1000_2000;
+    let system = System::new(&config, "");
+    let tree = parse(&generate_dtb(&config, &system.bus));
+
+    let (base, size) = device_range(&system, "UART0");
+    assert_eq!(base, 0x1000_2000);
+    let node = &tree[&format!("/soc/uart@{base:x}")];
+    assert_eq!(reg_2_2(&node["reg"]), (base, size));
+    assert_eq!(be32(&node["interrupts"], 0), 10);
+
+    let stdout = cstr(&tree["/chosen"]["stdout-path"], 0);
+    assert_eq!(stdout, format!("/soc/uart@{base:x}"));
+}
+
+#[test]
+fn every_mmio_node_matches_its_device() {
+    let config = Config::default();
+    let system = System::new(&config, "");
+    let tree = parse(&generate_dtb(&config, &system.bus));
+
+    let expected = [
+        ("CLINT", "clint"),
+        ("PLIC", "interrupt-controller"),
+        ("VirtIO-Blk", "virtio_mmio"),
+        ("SysCon", "syscon"),
+        ("GoldfishRTC", "rtc"),
+    ];
+    for (device, node) in expected {
+        let (base, size) = device_range(&system, device);
+        let path = format!("/soc/{node}@{base:x}");
+        let props = tree.get(&path).unwrap_or_else(|| panic!("missing {path}"));
+        assert_eq!(reg_2_2(&props["reg"]), (base, size), "{path} reg");
+    }
+
+    let (ram_base, ram_size) = device_range(&system, "DRAM");
+    let memory = &tree[&format!("/memory@{ram_base:x}")];
+    assert_eq!(reg_2_2(&memory["reg"]), (ram_base, ram_size));
+}
+
+#[test]
+fn every_ram_bank_has_a_memory_node() {
+    let mut config = Config::default();
+    let high = config.system.ram_base;
+    let low = high - 0x4000_0000;
+    config.memory.regions = vec![
+        MemoryRegion { base: low, size: 0x10_0000 },
+        MemoryRegion { base: high, size: 0x20_0000 },
+    ];
+    let system = System::new(&config, "");
+    let tree = parse(&generate_dtb(&config, &system.bus));
+
+    assert_eq!(tree.keys().filter(|path| path.starts_with("/memory@")).count(), 2);
+    assert_eq!(reg_2_2(&tree[&format!("/memory@{low:x}")]["reg"]), (low, 0x10_0000));
+    assert_eq!(reg_2_2(&tree[&format!("/memory@{high:x}")]["reg"]), (high, 0x20_0000));
+}
+
+#[test]
+fn net_node_present_only_when_configured() {
+    let mut config = Config::default();
+    let system = System::new(&config, "");
+    let tree = parse(&generate_dtb(&config, &system.bus));
+    assert!(!tree.contains_key(&format!("/soc/virtio_mmio@{:x}", config.system.net_base)));
+
+    config.system.net = Some(NetBackend::Capture);
+    let system = System::new(&config, "");
+    let tree = parse(&generate_dtb(&config, &system.bus));
+    let (base, size) = device_range(&system, "VirtIO-Net");
+    let node = &tree[&format!("/soc/virtio_mmio@{base:x}")];
+    assert_eq!(cstr(&node["compatible"], 0), "virtio,mmio");
+    assert_eq!(reg_2_2(&node["reg"]), (base, size));
+    assert_eq!(be32(&node["interrupts"], 0), 2);
+}
+
+#[test]
+fn rng_node_present() {
+    let config = Config::default();
+    let system = System::new(&config, "");
+    let tree = parse(&generate_dtb(&config, &system.bus));
+    let (base, size) = device_range(&system, "VirtIO-Rng");
+    assert_eq!(base, config.system.rng_base);
+    let node = &tree[&format!("/soc/virtio_mmio@{base:x}")];
+    assert_eq!(cstr(&node["compatible"], 0), "virtio,mmio");
+    assert_eq!(reg_2_2(&node["reg"]), (base, size));
+    assert_eq!(be32(&node["interrupts"], 0), 3);
+}
+
+#[test]
+fn one_cpu_node_per_hart() {
+    let mut config = Config::default();
+    config.system.num_harts = 2;
+    let system = System::new(&config, "");
+    let tree = parse(&generate_dtb(&config, &system.bus));
+
+    assert_eq!(be32(&tree["/cpus/cpu@1"]["reg"], 0), 1);
+    assert!(!tree.contains_key("/cpus/cpu@2"));
+
+    let (clint_base, _) = device_range(&system, "CLINT");
+    let clint = &tree[&format!("/soc/clint@{clint_base:x}")];
+    // Two (phandle, cause) pairs per hart.
+    assert_eq!(clint["interrupts-extended"].len(), 2 * 2 * 8);
+    let hart1_intc = be32(&tree["/cpus/cpu@1/interrupt-controller"]["phandle"], 0);
+    assert_eq!(be32(&clint["interrupts-extended"], 16), hart1_intc);
+}
+
+#[test]
+fn kernel_load_passes_generated_dtb_in_a1() {
+    let config = Config::default();
+    let mut cpu = Cpu::new(System::new(&config, ""), &config);
+    loader::setup_kernel_load(&mut cpu, &config, "", None, None).unwrap();
+
+    assert_eq!(cpu.regs.read(abi::REG_A0), 0, "a0 = boot hart id");
+    let dtb_addr = cpu.regs.read(abi::REG_A1);
+    let expected = generate_dtb(&config, &cpu.bus.bus);
+    let loaded: Vec<u8> = (0..expected.len() as u64)
+        .map(|i| cpu.bus.bus.read_u8(PhysAddr::new(dtb_addr + i)))
+        .collect();
+    assert_eq!(loaded, expected);
+}
+
+#[test]
+fn dtb_lists_every_uart() {
+    let mut config = Config::default();
+    config.system.devices = vec![
+        DeviceConfig {
+            kind: DeviceKind::Uart { to_stderr: false, quiet: true },
+            base: 0x1000_0000,
+            irq: None,
+        },
+        DeviceConfig {
+            kind: DeviceKind::Uart { to_stderr: false, quiet: true },
+            base: 0x1000_1000,
+            irq: Some(12),
+        },
+        DeviceConfig { kind: DeviceKind::Plic, base: 0x0c00_0000, irq: None },
+    ];
+    let system = System::new(&config, "");
+    let tree = parse(&generate_dtb(&config, &system.bus));
+
+    for (name, irq) in [("UART0", 10), ("UART1", 12)] {
+        let (base, size) = device_range(&system, name);
+        let node = &tree[&format!("/soc/uart@{base:x}")];
+        assert_eq!(cstr(&node["compatible"], 0), "ns16550a");
+        assert_eq!(reg_2_2(&node["reg"]), (base, size));
+        assert_eq!(be32(&node["interrupts"], 0), irq);
+    }
+    assert_eq!(cstr(&tree["/chosen"]["stdout-path"], 0), "/soc/uart@10000000");
+}
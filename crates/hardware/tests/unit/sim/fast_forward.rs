@@ -0,0 +1,163 @@
+//! Fast-Forward Tests.
+//!
+//! Runs a loop of ALU, multiply/divide, memory and call/return instructions
+//! functionally for a while and then in detail, and checks that the
+//! architectural state matches a run that was detailed throughout. Also
+//! checks the configured switch points.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use rvsim_core::Simulator;
+use rvsim_core::common::{PhysAddr, RegIdx};
+use rvsim_core::config::Config;
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::soc::System;
+
+/// Offset of the workload's array from the start of RAM.
+const ARRAY_OFFSET: u64 = 0x1000;
+
+/// Byte offset of the subroutine the loop calls.
+const SUBROUTINE_OFFSET: u64 = 4 * 17;
+
+/// Builds the workload: an endless loop mixing a running hash, a
+/// read-modify-write of a 64-entry array indexed by the counter, a division
+/// and a call to a subroutine.
+fn workload() -> Vec<u32> {
+    let b = InstructionBuilder::new;
+    vec![
+        b().addi(7, 7, 1).build(),
+        b().mul(8, 7, 7).build(),
+        b().xor(9, 9, 8).build(),
+        b().srl(11, 9, 7).build(),
+        b().add(9, 9, 11).build(),
+        b().andi(12, 7, 0x1f8).build(),
+        b().add(13, 10, 12).build(),
+        b().ld(14, 13, 0).build(),
+        b().add(14, 14, 9).build(),
+        b().sd(13, 14, 0).build(),
+        b().divu(15, 9, 7).build(),
+        b().rem(16, 9, 7).build(),
+        b().add(18, 15, 16).build(),
+        b().bge(18, 0, 8).build(),
+        b().sub(18, 0, 18).build(),
+        b().jal(1, (SUBROUTINE_OFFSET - 4 * 15) as i32).build(),
+        b().jal(0, -4 * 16).build(),
+        // Subroutine.
+        b().addi(17, 17, 3).build(),
+        b().xor(17, 17, 18).build(),
+        b().jalr(0, 1, 0).build(),
+    ]
+}
+
+/// Builds a simulator with the workload at the start of RAM and x10
+/// pointing at its array.
+fn boot(config: &Config) -> Simulator {
+    let base = config.system.ram_base;
+    let mut sim = Simulator::new(System::new(config, ""), config);
+    for (i, &inst) in workload().iter().enumerate() {
+        sim.cpu.bus.bus.write_u32(PhysAddr::new(base + 4 * i as u64), inst);
+    }
+    sim.cpu.pc = base;
+    sim.write_reg(RegIdx::new(10), base + ARRAY_OFFSET);
+    sim
+}
+
+/// Returns the integer registers, PC and `minstret` of hart 0.
+fn arch_state(sim: &Simulator) -> (Vec<u64>, u64, u64) {
+    let regs = (0..32).map(|i| sim.cpu.regs.read(RegIdx::new(i))).collect();
+    (regs, sim.cpu.pc, sim.cpu.csrs.minstret)
+}
+
+/// Returns a configuration for `backend` with the defaults otherwise.
+fn config_for(backend: BackendType) -> Config {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config
+}
+
+#[test]
+fn fast_forward_then_detailed_matches_detailed_run() {
+    let config = config_for(BackendType::InOrder);
+    let mut reference = boot(&config);
+    assert_eq!(reference.run_detailed(1_100_000).unwrap(), 1_100_000);
+
+    let mut sim = boot(&config);
+    assert_eq!(sim.run_functional(1_000_000).unwrap(), 1_000_000);
+    assert_eq!(sim.run_detailed(100_000).unwrap(), 100_000);
+    assert_eq!(arch_state(&sim), arch_state(&reference));
+}
+
+#[test]
+fn switching_back_and_forth_matches_detailed_run() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let config = config_for(backend);
+        let mut reference = boot(&config);
+        assert_eq!(reference.run_detailed(40_000).unwrap(), 40_000);
+
+        let mut sim = boot(&config);
+        for _ in 0..4 {
+            assert_eq!(sim.run_detailed(3_000).unwrap(), 3_000, "{backend:?}");
+            assert_eq!(sim.run_functional(7_000).unwrap(), 7_000, "{backend:?}");
+        }
+        assert_eq!(arch_state(&sim), arch_state(&reference), "{backend:?}");
+        let array = |sim: &mut Simulator| {
+            let base = config.system.ram_base + ARRAY_OFFSET;
+            (0..64)
+                .map(|i| sim.cpu.bus.bus.read_u64(PhysAddr::new(base + 8 * i)))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(array(&mut sim), array(&mut reference), "{backend:?}: memory");
+    }
+}
+
+#[test]
+fn warm_fast_forward_fills_caches_only_when_asked() {
+    for warm in [false, true] {
+        let mut config = config_for(BackendType::InOrder);
+        config.cache.l1_i.enabled = true;
+        config.general.fast_forward_warm = warm;
+        config.general.fast_forward_insts = 1_000;
+        let mut sim = boot(&config);
+        while sim.cpu.stats.instructions_retired < 1_000 {
+            sim.tick().unwrap();
+        }
+        assert_eq!(sim.cpu.stats.cycles, sim.cpu.stats.instructions_retired);
+        let accesses = sim.cpu.stats.icache_hits + sim.cpu.stats.icache_misses;
+        assert_eq!(accesses != 0, warm);
+    }
+}
+
+#[test]
+fn configured_fast_forward_matches_detailed_run() {
+    let mut config = config_for(BackendType::OutOfOrder);
+    let mut reference = boot(&config);
+    assert_eq!(reference.run_detailed(20_000).unwrap(), 20_000);
+
+    config.general.fast_forward_insts = 15_000;
+    let mut sim = boot(&config);
+    while sim.cpu.stats.instructions_retired < 15_000 {
+        sim.tick().unwrap();
+    }
+    // One instruction per cycle until the switch.
+    assert_eq!(sim.cpu.stats.cycles, 15_000);
+    assert_eq!(sim.run_detailed(5_000).unwrap(), 5_000);
+    assert_eq!(arch_state(&sim), arch_state(&reference));
+}
+
+#[test]
+fn switch_pc_ends_fast_forward_at_that_pc() {
+    let mut config = config_for(BackendType::InOrder);
+    let subroutine = config.system.ram_base + SUBROUTINE_OFFSET;
+    config.general.fast_forward_insts = 1_000_000;
+    config.general.switch_pc = Some(subroutine);
+    let mut sim = boot(&config);
+    while sim.cpu.pc != subroutine {
+        sim.tick().unwrap();
+    }
+    // The loop body before the first call, with its branch taken.
+    assert_eq!(sim.cpu.stats.instructions_retired, 15);
+    assert_eq!(sim.cpu.regs.read(RegIdx::new(1)), config.system.ram_base + 4 * 16);
+
+    // The next tick runs the pipeline, which takes cycles to fill.
+    sim.tick().unwrap();
+    assert_eq!(sim.cpu.stats.instructions_retired, 15);
+}
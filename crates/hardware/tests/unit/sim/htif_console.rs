@@ -0,0 +1,132 @@
+//! HTIF Console Tests.
+//!
+//! Runs a bare-metal program that talks to the HTIF device the way
+//! benchmarks built against it do, on both backends: console putchar and
+//! getchar, a proxied `write` syscall printing a buffer from memory, and
+//! finally a pass written to `tohost`. Each command waits for its
+//! `fromhost` response and clears it. Also checks that a failing exit
+//! payload and a proxied `exit` syscall end the run with their code.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::common::{PhysAddr, RegIdx};
+use rvsim_core::config::Config;
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::soc::devices::htif::{
+    CMD_GETCHAR, CMD_PUTCHAR, DEV_CONSOLE, DEV_SYSCALL, SYS_EXIT, SYS_WRITE, command,
+};
+
+/// Offset from the start of RAM of `tohost`, held in `s0`.
+const TOHOST: u64 = 0x1000;
+/// Offset from the start of RAM of the data area, held in `s1`.
+const DATA: u64 = 0x2000;
+/// Offset in the data area of the syscall block.
+const MAGIC_MEM: u64 = 0x100;
+/// Offset in the data area of the buffer written by the syscall.
+const BUF: u64 = 0x200;
+
+const S0: u32 = 8;
+const S1: u32 = 9;
+const S2: u32 = 18;
+const T1: u32 = 6;
+const T2: u32 = 7;
+
+/// Appends code that sends the command at `offset` in the data area, waits
+/// for the response and clears it, leaving the response in `t2`.
+fn send(program: &mut Vec<u32>, offset: u64) {
+    let b = InstructionBuilder::new;
+    program.extend([
+        b().ld(T1, S1, offset as i32).build(),
+        b().sd(S0, T1, 0).build(),
+        b().ld(T2, S0, 8).build(),
+        b().beq(T2, 0, -4).build(),
+        b().sd(S0, 0, 8).build(),
+    ]);
+}
+
+#[test]
+fn console_and_syscall_write_reach_the_host() {
+    let b = InstructionBuilder::new;
+    let text = b"syscall write\n";
+    let mut program = Vec::new();
+    send(&mut program, 0x00);
+    send(&mut program, 0x08);
+    send(&mut program, 0x10);
+    program.push(b().addi(S2, T2, 0).build());
+    send(&mut program, 0x18);
+    program.extend([b().addi(T1, 0, 1).build(), b().sd(S0, T1, 0).build(), b().jal(0, 0).build()]);
+
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut config = Config::default();
+        config.pipeline.backend = backend;
+        let base = config.system.ram_base;
+        config.system.tohost_addr = base + TOHOST;
+        let mut ctx = TestContext::with_config(&config).load_program(base, &program);
+        let sim = &mut ctx.sim;
+        let data = base + DATA;
+        let words = [
+            command(DEV_CONSOLE, CMD_PUTCHAR, u64::from(b'h')),
+            command(DEV_CONSOLE, CMD_PUTCHAR, u64::from(b'i')),
+            command(DEV_CONSOLE, CMD_GETCHAR, 0),
+            command(DEV_SYSCALL, 0, data + MAGIC_MEM),
+        ];
+        for (i, w) in words.iter().enumerate() {
+            sim.cpu.bus.bus.write_u64(PhysAddr::new(data + 8 * i as u64), *w);
+        }
+        let block = [SYS_WRITE, 1, data + BUF, text.len() as u64];
+        for (i, w) in block.iter().enumerate() {
+            sim.cpu.bus.bus.write_u64(PhysAddr::new(data + MAGIC_MEM + 8 * i as u64), *w);
+        }
+        for (i, &c) in text.iter().enumerate() {
+            sim.cpu.bus.bus.write_u8(PhysAddr::new(data + BUF + i as u64), c);
+        }
+        sim.write_reg(RegIdx::new(S0 as u8), base + TOHOST);
+        sim.write_reg(RegIdx::new(S1 as u8), data);
+        sim.cpu.bus.bus.htif_mut().unwrap().push_input(b"x");
+
+        assert_eq!(ctx.run_to_exit_code(100_000), 0, "{backend:?}");
+        let sim = &mut ctx.sim;
+
+        let htif = sim.cpu.bus.bus.htif_mut().unwrap();
+        assert_eq!(htif.take_output(), b"hisyscall write\n", "{backend:?}");
+        assert_eq!(
+            sim.cpu.regs.read(RegIdx::new(S2 as u8)),
+            command(DEV_CONSOLE, CMD_GETCHAR, 0x100 | u64::from(b'x')),
+            "{backend:?}"
+        );
+        let result = sim.cpu.bus.bus.read_u64(PhysAddr::new(data + MAGIC_MEM));
+        assert_eq!(result, text.len() as u64, "{backend:?}");
+    }
+}
+
+#[test]
+fn exit_payload_and_exit_syscall_end_the_run_with_their_code() {
+    let b = InstructionBuilder::new;
+    let program = [b().ld(T1, S1, 0).build(), b().sd(S0, T1, 0).build(), b().jal(0, 0).build()];
+    let data = Config::default().system.ram_base + DATA;
+    // (word stored to tohost, syscall block, expected exit code)
+    let cases = [
+        (command(DEV_SYSCALL, 0, (42 << 1) | 1), None, 42),
+        (command(DEV_SYSCALL, 0, data + MAGIC_MEM), Some([SYS_EXIT, 7]), 7),
+    ];
+
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        for (word, block, expected) in cases {
+            let mut config = Config::default();
+            config.pipeline.backend = backend;
+            let base = config.system.ram_base;
+            config.system.tohost_addr = base + TOHOST;
+            let mut ctx = TestContext::with_config(&config).load_program(base, &program);
+            let bus = &mut ctx.sim.cpu.bus.bus;
+            bus.write_u64(PhysAddr::new(data), word);
+            for (i, w) in block.iter().flatten().enumerate() {
+                bus.write_u64(PhysAddr::new(data + MAGIC_MEM + 8 * i as u64), *w);
+            }
+            ctx.sim.write_reg(RegIdx::new(S0 as u8), base + TOHOST);
+            ctx.sim.write_reg(RegIdx::new(S1 as u8), data);
+
+            let code = ctx.run_to_exit_code(10_000);
+            assert_eq!(code, expected, "{backend:?}: tohost={word:#x}");
+        }
+    }
+}
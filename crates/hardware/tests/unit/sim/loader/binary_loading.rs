@@ -182,7 +182,7 @@ fn test_setup_kernel_load_different_ram_bases() {
     config1.system.ram_base = 0x80000000;
 
     let mut config2 = Config::default();
-    config2.system.ram_base = 0x90000000;
+    config2.system.ram_base = 0xA0000000;
 
     let system1 = rvsim_core::soc::System::new(&config1, "");
     let mut cpu1 = Cpu::new(system1, &config1);
@@ -194,7 +194,7 @@ fn test_setup_kernel_load_different_ram_bases() {
 
     // PC should match the respective RAM bases
     assert_eq!(cpu1.pc, 0x80000000);
-    assert_eq!(cpu2.pc, 0x90000000);
+    assert_eq!(cpu2.pc, 0xA0000000);
 }
 
 #[test]
@@ -0,0 +1,93 @@
+//! # ELF Segment Loading Tests
+//!
+//! Loads hand-built ELF64 images through `loader::try_load_elf` and checks
+//! that every `PT_LOAD` segment lands at its physical address with its BSS
+//! zero-filled, that non-ELF data is left to the caller, and that images
+//! built for another architecture, 32-bit images and segments outside the
+//! memory map are rejected with a clear error.
+
+use crate::common::builder::elf::{EM_X86_64, ElfBuilder};
+use rvsim_core::common::{PhysAddr, SimError};
+use rvsim_core::config::Config;
+use rvsim_core::sim::loader;
+use rvsim_core::soc::System;
+
+/// Offset of the data segment from the start of RAM.
+const DATA: u64 = 0x1_0000;
+
+fn system() -> (System, u64) {
+    let config = Config::default();
+    (System::new(&config, ""), config.system.ram_base)
+}
+
+fn read_bytes(system: &mut System, addr: u64, len: usize) -> Vec<u8> {
+    (0..len as u64).map(|i| system.bus.read_u8(PhysAddr::new(addr + i))).collect()
+}
+
+fn load_err(image: &[u8]) -> String {
+    let (mut system, _) = system();
+    match loader::try_load_elf(image, &mut system.bus) {
+        Err(e @ SimError::InvalidElf { .. }) => e.to_string(),
+        other => panic!("expected InvalidElf, got {other:?}"),
+    }
+}
+
+#[test]
+fn text_and_data_segments_load_with_zeroed_bss() {
+    let (mut system, base) = system();
+    let text: Vec<u8> = (0..64).collect();
+    let data = b"initialized data";
+    // Garbage where the BSS goes, to check it is cleared.
+    system.bus.load_binary_at(&[0xAA; 64], PhysAddr::new(base + DATA + data.len() as u64));
+
+    let image = ElfBuilder::new(base)
+        .segment(base, &text, text.len() as u64)
+        .segment(base + DATA, data, data.len() as u64 + 48)
+        .build();
+    let result = loader::try_load_elf(&image, &mut system.bus).unwrap().unwrap();
+
+    assert_eq!(result.entry, base);
+    assert_eq!(read_bytes(&mut system, base, text.len()), text);
+    assert_eq!(read_bytes(&mut system, base + DATA, data.len()), data);
+    let bss = read_bytes(&mut system, base + DATA + data.len() as u64, 48);
+    assert!(bss.iter().all(|&b| b == 0), "BSS not zero-filled: {bss:?}");
+    assert_eq!(system.bus.read_u8(PhysAddr::new(base + DATA + data.len() as u64 + 48)), 0xAA);
+}
+
+#[test]
+fn segments_load_at_physical_not_virtual_address() {
+    let (mut system, base) = system();
+    let image = ElfBuilder::new(0xffff_ffff_8000_0000)
+        .segment_at(0xffff_ffff_8000_0000, base + DATA, b"high-half", 9)
+        .build();
+    let _ = loader::try_load_elf(&image, &mut system.bus).unwrap().unwrap();
+    assert_eq!(read_bytes(&mut system, base + DATA, 9), b"high-half");
+}
+
+#[test]
+fn non_elf_data_is_not_loaded() {
+    let (mut system, _) = system();
+    assert!(loader::try_load_elf(b"\x13\x00\x00\x00", &mut system.bus).unwrap().is_none());
+}
+
+#[test]
+fn wrong_architecture_is_rejected() {
+    let image = ElfBuilder::new(0).machine(EM_X86_64).build();
+    let err = load_err(&image);
+    assert!(err.contains("expected RISC-V"), "{err}");
+    assert!(err.contains("X86_64"), "{err}");
+}
+
+#[test]
+fn elf32_is_rejected() {
+    let err = load_err(&ElfBuilder::new(0).class32().build());
+    assert!(err.contains("32-bit"), "{err}");
+}
+
+#[test]
+fn segment_outside_memory_map_is_rejected() {
+    let (_, base) = system();
+    let image = ElfBuilder::new(base).segment(0x4000_0000, &[0x13, 0, 0, 0], 4).build();
+    let err = load_err(&image);
+    assert!(err.contains("0x40000000") && err.contains("outside the memory map"), "{err}");
+}
@@ -0,0 +1,116 @@
+//! # ELF Entry Point and Symbol Tests
+//!
+//! Runs a hand-built ELF with separate text and data segments from its entry
+//! point on both backends, and checks the symbol table kept on the
+//! simulator: lookup by name, mapping addresses back to symbols, and the
+//! `tohost` address used for HTIF.
+
+use crate::common::builder::elf::{ElfBuilder, STT_FUNC, STT_OBJECT};
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::Simulator;
+use rvsim_core::common::RegIdx;
+use rvsim_core::config::Config;
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::sim::loader;
+use rvsim_core::sim::symbols::{Symbol, SymbolTable};
+use rvsim_core::soc::System;
+
+/// Offset of the entry point from the start of RAM.
+const ENTRY: u64 = 0x100;
+
+/// Offset of the data segment from the start of RAM, `auipc`-reachable from
+/// the entry point.
+const DATA: u64 = ENTRY + 0x2000;
+
+/// Value stored in the data segment.
+const VALUE: u64 = 0x0123_4567_89ab_cdef;
+
+/// Builds an image whose `_start` loads `value` from `.data` into a0 and
+/// exits with it. The first 8 bytes of the text segment sit before the entry
+/// point and would trap if executed.
+fn image(base: u64) -> Vec<u8> {
+    let b = InstructionBuilder::new;
+    let text: Vec<u8> = [
+        0,
+        0,
+        b().auipc(5, 2).build(),
+        b().ld(10, 5, 0).build(),
+        b().addi(17, 0, 93).build(),
+        b().ecall().build(),
+    ]
+    .iter()
+    .flat_map(|w: &u32| w.to_le_bytes())
+    .collect();
+    ElfBuilder::new(base + ENTRY)
+        .segment(base + ENTRY - 8, &text, text.len() as u64)
+        .segment(base + DATA, &VALUE.to_le_bytes(), 0x100)
+        .symbol("_start", base + ENTRY, 16, STT_FUNC)
+        .symbol("value", base + DATA, 8, STT_OBJECT)
+        .symbol("tohost", base + DATA + 0x40, 8, STT_OBJECT)
+        .build()
+}
+
+/// Loads `image` the way a front end does: segments into the system, then
+/// the entry point and symbols into the simulator.
+fn load(config: &Config, image: &[u8]) -> Simulator {
+    let mut system = System::new(config, "");
+    let result = loader::try_load_elf(image, &mut system.bus).unwrap().unwrap();
+    let mut sim = Simulator::new(system, config);
+    sim.cpu.pc = result.entry;
+    sim.symbols = result.symbols;
+    sim
+}
+
+#[test]
+fn program_runs_from_entry_point_and_reads_data_segment() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut config = Config::default();
+        config.pipeline.backend = backend;
+        let base = config.system.ram_base;
+        let sim = load(&config, &image(base));
+        assert_eq!(sim.cpu.pc, base + ENTRY, "{backend:?}");
+
+        let sim = TestContext { sim }.run_to_exit(10_000);
+        assert_eq!(sim.cpu.exit_code, Some(VALUE), "{backend:?}");
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(10)), VALUE, "{backend:?}");
+    }
+}
+
+#[test]
+fn symbols_are_kept_for_lookup() {
+    let config = Config::default();
+    let base = config.system.ram_base;
+    let sim = load(&config, &image(base));
+
+    assert_eq!(sim.symbols.len(), 3);
+    assert_eq!(sim.symbols.lookup("_start"), Some(base + ENTRY));
+    assert_eq!(sim.symbols.lookup("value"), Some(base + DATA));
+    assert_eq!(sim.symbols.lookup("missing"), None);
+
+    let (sym, offset) = sim.symbols.symbolize(base + ENTRY + 12).unwrap();
+    assert_eq!((sym.name.as_str(), offset), ("_start", 12));
+    assert!(sim.symbols.symbolize(base + ENTRY + 16).is_none(), "past the end of _start");
+    assert!(sim.symbols.symbolize(base).is_none(), "before any symbol");
+}
+
+#[test]
+fn tohost_symbol_is_reported() {
+    let config = Config::default();
+    let base = config.system.ram_base;
+    let mut system = System::new(&config, "");
+    let result = loader::try_load_elf(&image(base), &mut system.bus).unwrap().unwrap();
+    assert_eq!(result.tohost_addr, Some(base + DATA + 0x40));
+}
+
+#[test]
+fn unsized_symbols_extend_to_the_next_symbol() {
+    let sym = |name: &str, addr, size| Symbol { name: name.to_owned(), addr, size };
+    let table =
+        SymbolTable::new(vec![sym("b", 0x200, 0), sym("a", 0x100, 0), sym("c", 0x300, 0x10)]);
+
+    assert_eq!(table.iter().map(|s| s.addr).collect::<Vec<_>>(), [0x100, 0x200, 0x300]);
+    assert_eq!(table.symbolize(0x1ff).map(|(s, off)| (s.name.as_str(), off)), Some(("a", 0xff)));
+    assert_eq!(table.symbolize(0x250).map(|(s, off)| (s.name.as_str(), off)), Some(("b", 0x50)));
+    assert!(table.symbolize(0x310).is_none());
+}
@@ -4,3 +4,9 @@
 
 /// Tests for binary loading from disk and memory initialization.
 pub mod binary_loading;
+
+/// Tests for ELF segment loading and image validation.
+pub mod elf_segments;
+
+/// Tests for ELF entry points and the symbol table.
+pub mod entry_point;
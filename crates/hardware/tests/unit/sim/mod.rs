@@ -3,5 +3,41 @@
 //! This module contains unit tests for simulation-related functionality,
 //! including binary loading and system initialization.
 
+/// Tests for commit breakpoints.
+pub mod breakpoint;
+
+/// Tests for simulator checkpoints.
+pub mod checkpoint;
+
+/// Tests for device tree generation.
+pub mod dtb;
+
+/// Tests for fast-forwarding and switching to detailed simulation.
+pub mod fast_forward;
+
+/// Tests for the HTIF console and syscall proxy.
+pub mod htif_console;
+
 /// Tests for binary loader and kernel setup.
 pub mod loader;
+
+/// Tests for `SysCon` reboot handling.
+pub mod reboot;
+
+/// Tests for hart reset.
+pub mod reset;
+
+/// Tests for semihosting calls.
+pub mod semihosting;
+
+/// Tests for syscall emulation of static user programs.
+pub mod syscall_emulation;
+
+/// Tests for multi-hart (SMP) simulation.
+pub mod smp;
+
+/// Tests for the trace region filter.
+pub mod trace_region;
+
+/// Tests for the hang watchdog.
+pub mod watchdog;
@@ -0,0 +1,214 @@
+//! SysCon Reboot Tests.
+//!
+//! Writes the reboot command to the `SysCon` device and checks that the
+//! simulator restarts every hart from its boot state with an empty pipeline,
+//! while memory survives the reboot. Power off still exits. Reboots are
+//! reported as system events, bounded by `general.max_reboots`, and reset
+//! the statistics when `general.reboot_stats` asks for it.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::Simulator;
+use rvsim_core::common::{PhysAddr, RegIdx, SimError};
+use rvsim_core::config::{Config, RebootStats};
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::sim::simulator::SystemEvent;
+use rvsim_core::soc::System;
+
+/// `SysCon` reboot command.
+const REBOOT: u64 = 0x7777;
+
+/// `SysCon` power off command.
+const POWEROFF: u64 = 0x5555;
+
+/// Offset of the boot counter from the start of RAM.
+const COUNTER_OFFSET: i32 = 0x100;
+
+/// Loads `program` at the start of RAM with x6 = `SysCon` base, x7 =
+/// `command` and x10 = RAM base.
+fn boot(backend: BackendType, program: &[u32], command: u64) -> Simulator {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    boot_with(config, program, command)
+}
+
+/// Like [`boot`], with the given configuration.
+fn boot_with(mut config: Config, program: &[u32], command: u64) -> Simulator {
+    config.pipeline.width = 4;
+    let base = config.system.ram_base;
+    let mut sim = Simulator::new(System::new(&config, ""), &config);
+    for (i, &inst) in program.iter().enumerate() {
+        sim.cpu.bus.bus.write_u32(PhysAddr::new(base + 4 * i as u64), inst);
+    }
+    sim.cpu.pc = base;
+    sim.write_reg(RegIdx::new(6), config.system.syscon_base);
+    sim.write_reg(RegIdx::new(7), command);
+    sim.write_reg(RegIdx::new(10), base);
+    sim
+}
+
+#[test]
+fn reboot_resets_pc_and_clears_pipeline() {
+    let b = InstructionBuilder::new;
+    let mut program = vec![b().sw(6, 7, 0).build()];
+    program.extend((0..8).map(|_| b().addi(8, 8, 1).build()));
+    program.push(b().jal(0, 0).build());
+    let base = Config::default().system.ram_base;
+
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut sim = boot(backend, &program, REBOOT);
+        let mut left_entry = false;
+        let mut rebooted = false;
+        for _ in 0..1_000 {
+            sim.tick().unwrap();
+            left_entry |= sim.cpu.pc != base;
+            if left_entry && sim.cpu.pc == base {
+                rebooted = true;
+                break;
+            }
+        }
+        assert!(rebooted, "{backend:?}: no reboot");
+        assert!(sim.cpu.exit_code.is_none(), "{backend:?}: reboot must not exit");
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(8)), 0, "{backend:?}: registers restored");
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(7)), REBOOT, "{backend:?}: boot registers kept");
+
+        let snapshot = sim.pipeline.snapshot(4);
+        assert!(snapshot.fetch1_fetch2.is_empty(), "{backend:?}");
+        assert!(snapshot.fetch2_decode.is_empty(), "{backend:?}");
+        assert!(snapshot.decode_rename.is_empty(), "{backend:?}");
+        assert!(snapshot.rename_issue.is_empty(), "{backend:?}");
+        assert!(snapshot.issue_queue.is_empty(), "{backend:?}");
+        assert!(snapshot.execute_mem1.is_empty(), "{backend:?}");
+        assert!(snapshot.mem1_mem2.is_empty(), "{backend:?}");
+        assert!(snapshot.mem2_wb.is_empty(), "{backend:?}");
+    }
+}
+
+#[test]
+fn reboot_reruns_program_with_memory_intact() {
+    let b = InstructionBuilder::new;
+    let program = [
+        // Count boots in memory; reboot on the first, exit on the second.
+        b().lw(5, 10, COUNTER_OFFSET).build(),
+        b().addi(5, 5, 1).build(),
+        b().sw(10, 5, COUNTER_OFFSET).build(),
+        b().addi(8, 0, 2).build(),
+        b().beq(5, 8, 12).build(),
+        b().sw(6, 7, 0).build(),
+        b().jal(0, 0).build(),
+        b().addi(10, 0, 0).build(),
+        b().addi(17, 0, 93).build(),
+        b().ecall().build(),
+    ];
+    let base = Config::default().system.ram_base;
+
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut sim = TestContext { sim: boot(backend, &program, REBOOT) }.run_to_exit(2_000);
+        assert_eq!(sim.cpu.exit_code, Some(0), "{backend:?}");
+        let counter = PhysAddr::new(base + COUNTER_OFFSET as u64);
+        assert_eq!(sim.cpu.bus.bus.read_u32(counter), 2, "{backend:?}");
+    }
+}
+
+#[test]
+fn poweroff_still_exits() {
+    let b = InstructionBuilder::new;
+    let program = [b().sw(6, 7, 0).build(), b().jal(0, 0).build()];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let sim = TestContext { sim: boot(backend, &program, POWEROFF) }.run_to_exit(1_000);
+        assert_eq!(sim.cpu.exit_code, Some(0), "{backend:?}");
+    }
+}
+
+/// Counts boots in memory and reboots until the third boot, which powers
+/// off. Expects x9 = `SysCon` power off command.
+fn reboot_twice_program() -> Vec<u32> {
+    let b = InstructionBuilder::new;
+    vec![
+        b().lw(5, 10, COUNTER_OFFSET).build(),
+        b().addi(5, 5, 1).build(),
+        b().sw(10, 5, COUNTER_OFFSET).build(),
+        b().addi(8, 0, 3).build(),
+        b().bne(5, 8, 8).build(),
+        b().addi(7, 9, 0).build(), // third boot: power off instead
+        b().sw(6, 7, 0).build(),
+        b().jal(0, 0).build(),
+    ]
+}
+
+/// Runs [`reboot_twice_program`] to completion, returning the simulator and
+/// the system events it raised.
+fn run_reboot_twice(config: Config) -> (Simulator, Vec<SystemEvent>) {
+    let mut sim = boot_with(config, &reboot_twice_program(), REBOOT);
+    sim.write_reg(RegIdx::new(9), POWEROFF);
+    let mut events = Vec::new();
+    for _ in 0..5_000 {
+        sim.tick().unwrap();
+        if let Some(event) = sim.take_system_event() {
+            events.push(event);
+            if matches!(event, SystemEvent::Exit(_)) {
+                return (sim, events);
+            }
+        }
+    }
+    panic!("program did not exit; events so far: {events:?}");
+}
+
+#[test]
+fn two_reboots_then_poweroff_boots_three_times() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut config = Config::default();
+        config.pipeline.backend = backend;
+        let (mut sim, events) = run_reboot_twice(config);
+        assert_eq!(
+            events,
+            [SystemEvent::Reboot(2), SystemEvent::Reboot(3), SystemEvent::Exit(0)],
+            "{backend:?}"
+        );
+        assert_eq!(sim.boots(), 3, "{backend:?}");
+        let base = Config::default().system.ram_base;
+        let counter = PhysAddr::new(base + COUNTER_OFFSET as u64);
+        assert_eq!(sim.cpu.bus.bus.read_u32(counter), 3, "{backend:?}");
+    }
+}
+
+#[test]
+fn reboot_past_limit_fails() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut config = Config::default();
+        config.pipeline.backend = backend;
+        config.general.max_reboots = Some(1);
+        let mut sim = boot_with(config, &reboot_twice_program(), REBOOT);
+        sim.write_reg(RegIdx::new(9), POWEROFF);
+        let err = (0..5_000).find_map(|_| sim.tick().err()).expect("reboot limit never hit");
+        assert!(
+            matches!(err, SimError::RebootLimitExceeded { reboots: 2, limit: 1 }),
+            "{backend:?}: {err}"
+        );
+        assert_eq!(sim.boots(), 2, "{backend:?}");
+    }
+}
+
+#[test]
+fn reset_stats_count_only_the_last_boot() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut config = Config::default();
+        config.pipeline.backend = backend;
+        let (accumulated, _) = run_reboot_twice(config.clone());
+        config.general.reboot_stats = RebootStats::Reset;
+        let (reset, _) = run_reboot_twice(config);
+
+        let (acc, last) = (&accumulated.cpu.stats, &reset.cpu.stats);
+        assert_eq!(acc.boot_cycle, 0, "{backend:?}");
+        assert!(last.boot_cycle > 0, "{backend:?}");
+        // The clock keeps running through the reset; only the counters restart.
+        assert_eq!(last.cycles, acc.cycles, "{backend:?}");
+        assert!(last.measured_cycles() < acc.measured_cycles(), "{backend:?}");
+        assert!(
+            last.instructions_retired < acc.instructions_retired,
+            "{backend:?}: {} retired in the last boot vs {} in all three",
+            last.instructions_retired,
+            acc.instructions_retired
+        );
+    }
+}
@@ -0,0 +1,95 @@
+//! Hart Reset Tests.
+//!
+//! Mutates architectural state mid-run and checks that `Simulator::reset`
+//! (and `Cpu::reset` under it) restores the RISC-V reset state:
+//!   - PC at the reset vector (`general.start_pc`), privilege M, and an
+//!     empty pipeline.
+//!   - CSRs at their power-on values: `mstatus` interrupts and MPRV off,
+//!     `mcause` 0, every configured `misa` extension back, PMP off.
+//!   - Execution restarts from the reset vector.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use rvsim_core::Simulator;
+use rvsim_core::common::{PhysAddr, RegIdx};
+use rvsim_core::config::Config;
+use rvsim_core::core::arch::csr::{MISA_EXT_C, MSTATUS_MIE, MSTATUS_MPRV};
+use rvsim_core::core::arch::mode::PrivilegeMode;
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::soc::System;
+
+/// Offset of the reset vector from the start of RAM.
+const VECTOR: u64 = 0x40;
+
+/// Builds a simulator whose reset vector runs an `addi x8, x8, 1` loop.
+fn setup(backend: BackendType) -> Simulator {
+    let b = InstructionBuilder::new;
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.general.start_pc = config.system.ram_base + VECTOR;
+    let vector = config.general.start_pc;
+
+    let mut sim = Simulator::new(System::new(&config, ""), &config);
+    let bus = &mut sim.cpu.bus.bus;
+    bus.write_u32(PhysAddr::new(vector), b().addi(8, 8, 1).build());
+    bus.write_u32(PhysAddr::new(vector + 4), b().jal(0, -4).build());
+    sim
+}
+
+#[test]
+fn reset_restores_pc_privilege_and_csrs() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut sim = setup(backend);
+        let fresh = sim.cpu.csrs.clone();
+        for _ in 0..50 {
+            sim.tick().unwrap();
+        }
+        assert!(sim.cpu.regs.read(RegIdx::new(8)) > 0, "{backend:?}: loop ran");
+
+        let cpu = &mut sim.cpu;
+        cpu.privilege = PrivilegeMode::User;
+        cpu.csrs.mstatus |= MSTATUS_MIE | MSTATUS_MPRV;
+        cpu.csrs.mcause = 5;
+        cpu.csrs.mepc = 0x1234;
+        cpu.csrs.mtvec = 0x8000_1000;
+        cpu.csrs.write_misa(fresh.misa & !MISA_EXT_C);
+        cpu.pmp.set_cfg(0, 0x9f);
+        cpu.pc = 0xdead_0000;
+        sim.reset();
+
+        let cpu = &sim.cpu;
+        assert_eq!(cpu.pc, cpu.reset_vector, "{backend:?}");
+        assert_eq!(cpu.reset_vector, Config::default().system.ram_base + VECTOR, "{backend:?}");
+        assert_eq!(cpu.privilege, PrivilegeMode::Machine, "{backend:?}");
+        assert_eq!(cpu.csrs.mstatus, fresh.mstatus, "{backend:?}");
+        assert_eq!(cpu.csrs.mstatus & (MSTATUS_MIE | MSTATUS_MPRV), 0, "{backend:?}");
+        assert_eq!(cpu.csrs.mcause, 0, "{backend:?}");
+        assert_eq!(cpu.csrs.mepc, 0, "{backend:?}");
+        assert_eq!(cpu.csrs.mtvec, 0, "{backend:?}");
+        assert_eq!(cpu.csrs.misa, fresh.misa, "{backend:?}");
+        assert!(cpu.pmp.entries().iter().all(|e| e.cfg == 0), "{backend:?}");
+
+        let snapshot = sim.pipeline.snapshot(4);
+        assert!(snapshot.fetch1_fetch2.is_empty(), "{backend:?}");
+        assert!(snapshot.issue_queue.is_empty(), "{backend:?}");
+    }
+}
+
+#[test]
+fn execution_restarts_from_reset_vector() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut sim = setup(backend);
+        for _ in 0..20 {
+            sim.tick().unwrap();
+        }
+        sim.reset();
+        sim.write_reg(RegIdx::new(8), 0);
+        let retired = sim.cpu.stats.instructions_retired;
+        for _ in 0..1_000 {
+            sim.tick().unwrap();
+            if sim.cpu.stats.instructions_retired > retired {
+                break;
+            }
+        }
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(8)), 1, "{backend:?}: first instruction re-ran");
+    }
+}
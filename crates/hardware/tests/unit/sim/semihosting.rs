@@ -0,0 +1,159 @@
+//! Semihosting Tests.
+//!
+//! Runs bare-metal programs that issue RISC-V semihosting calls with
+//! `general.semihosting` set, on both backends: console output through
+//! `SYS_WRITE0` and `SYS_WRITEC` with the exit code from `SYS_EXIT`, file I/O
+//! confined to the configured root, and -1 for unsupported calls. A plain
+//! `ebreak` still traps.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::Simulator;
+use rvsim_core::common::{PhysAddr, RegIdx};
+use rvsim_core::config::Config;
+use rvsim_core::core::cpu::semihosting::{
+    ADP_STOPPED_APPLICATION_EXIT, SEMIHOST_EBREAK, SEMIHOST_ENTRY, SEMIHOST_EXIT, SYS_CLOCK,
+    SYS_CLOSE, SYS_EXIT, SYS_OPEN, SYS_READ, SYS_WRITE, SYS_WRITE0, SYS_WRITEC,
+};
+use rvsim_core::core::pipeline::engine::BackendType;
+use std::path::Path;
+
+/// Offset from the start of RAM of the data area, held in `s0`.
+const DATA: u64 = 0x800;
+
+/// `s0`, the data pointer.
+const S0: u32 = 8;
+
+/// Registers the results of successive calls are saved in.
+const SAVED: [u32; 8] = [9, 18, 19, 20, 21, 22, 23, 24];
+
+/// Appends a semihosting call of `op` with `a1 = s0 + arg`, then saves `a0`
+/// in `save`, if given.
+fn call(program: &mut Vec<u32>, op: u64, arg: i32, save: Option<u32>) {
+    let b = InstructionBuilder::new;
+    program.push(b().addi(10, 0, op as i32).build());
+    program.push(b().addi(11, S0, arg).build());
+    program.extend([SEMIHOST_ENTRY, SEMIHOST_EBREAK, SEMIHOST_EXIT]);
+    if let Some(rd) = save {
+        program.push(b().addi(rd, 10, 0).build());
+    }
+}
+
+/// Boots `program` on `backend` with semihosting enabled and files rooted at
+/// `root`, with console output captured.
+fn boot(backend: BackendType, program: &[u32], root: Option<&Path>) -> TestContext {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.general.semihosting = true;
+    config.general.semihosting_root = root.map(|r| r.to_str().unwrap().to_owned());
+    let base = config.system.ram_base;
+    let mut ctx = TestContext::with_config(&config).load_program(base, program);
+    ctx.sim.write_reg(RegIdx::new(S0 as u8), base + DATA);
+    ctx.sim.cpu.semihosting.as_mut().unwrap().capture = Some(Vec::new());
+    ctx
+}
+
+/// Writes `bytes` at `offset` into the data area.
+fn poke(sim: &mut Simulator, offset: u64, bytes: &[u8]) {
+    let addr = sim.cpu.regs.read(RegIdx::new(S0 as u8)) + offset;
+    for (i, &b) in bytes.iter().enumerate() {
+        sim.cpu.bus.bus.write_u8(PhysAddr::new(addr + i as u64), b);
+    }
+}
+
+/// Writes a parameter block of 64-bit words at `offset` into the data area.
+fn poke_block(sim: &mut Simulator, offset: u64, words: &[u64]) {
+    let bytes: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+    poke(sim, offset, &bytes);
+}
+
+#[test]
+fn write0_prints_and_exit_sets_code() {
+    let mut program = Vec::new();
+    call(&mut program, SYS_WRITE0, 0, None);
+    call(&mut program, SYS_EXIT, 0x40, None);
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut ctx = boot(backend, &program, None);
+        poke(&mut ctx.sim, 0, b"hello, semihosting\n\0");
+        poke_block(&mut ctx.sim, 0x40, &[ADP_STOPPED_APPLICATION_EXIT, 42]);
+
+        assert_eq!(ctx.run_to_exit_code(100_000), 42, "{backend:?}");
+        let out = ctx.sim.cpu.semihosting.as_mut().unwrap().capture.take().unwrap();
+        assert_eq!(out, b"hello, semihosting\n", "{backend:?}");
+    }
+}
+
+#[test]
+fn files_are_confined_to_the_root() {
+    let root = tempfile::tempdir().unwrap();
+    let mut program = Vec::new();
+    call(&mut program, SYS_OPEN, 0x100, Some(SAVED[0]));
+    call(&mut program, SYS_WRITE, 0x120, Some(SAVED[1]));
+    call(&mut program, SYS_CLOSE, 0x140, Some(SAVED[2]));
+    call(&mut program, SYS_OPEN, 0x160, Some(SAVED[3]));
+    call(&mut program, SYS_CLOCK, 0, Some(SAVED[4]));
+    call(&mut program, 0x30, 0, Some(SAVED[5]));
+    call(&mut program, SYS_OPEN, 0x180, Some(SAVED[6]));
+    call(&mut program, SYS_READ, 0x1a0, Some(SAVED[7]));
+    call(&mut program, SYS_EXIT, 0x1c0, None);
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut ctx = boot(backend, &program, Some(root.path()));
+        let data = ctx.sim.cpu.regs.read(RegIdx::new(S0 as u8));
+        poke(&mut ctx.sim, 0, b"out.txt\0");
+        poke(&mut ctx.sim, 0x10, b"../escape\0");
+        poke(&mut ctx.sim, 0x20, b"payload");
+        poke_block(&mut ctx.sim, 0x100, &[data, 4, 7]);
+        poke_block(&mut ctx.sim, 0x120, &[1, data + 0x20, 7]);
+        poke_block(&mut ctx.sim, 0x140, &[1]);
+        poke_block(&mut ctx.sim, 0x160, &[data + 0x10, 4, 9]);
+        poke_block(&mut ctx.sim, 0x180, &[data, 0, 7]);
+        poke_block(&mut ctx.sim, 0x1a0, &[1, data + 0x200, 16]);
+        poke_block(&mut ctx.sim, 0x1c0, &[ADP_STOPPED_APPLICATION_EXIT, 0]);
+
+        assert_eq!(ctx.run_to_exit_code(100_000), 0, "{backend:?}");
+        let saved = SAVED.map(|r| ctx.sim.cpu.regs.read(RegIdx::new(r as u8)));
+        let [open, write, close, escape, clock, unsupported, reopen, read] = saved;
+        assert_eq!((open, write, close), (1, 0, 0), "{backend:?}");
+        assert_eq!(escape, u64::MAX, "{backend:?}: open outside the root");
+        assert_ne!(clock, u64::MAX, "{backend:?}");
+        assert_eq!(unsupported, u64::MAX, "{backend:?}");
+        assert_eq!((reopen, read), (1, 16 - 7), "{backend:?}");
+        let back: Vec<u8> =
+            (0..7).map(|i| ctx.sim.cpu.bus.bus.read_u8(PhysAddr::new(data + 0x200 + i))).collect();
+        assert_eq!(back, b"payload", "{backend:?}");
+        assert_eq!(std::fs::read(root.path().join("out.txt")).unwrap(), b"payload");
+        assert!(!root.path().parent().unwrap().join("escape").exists());
+    }
+}
+
+#[test]
+fn writec_prints_and_abnormal_exit_fails() {
+    /// `ADP_Stopped_RunTimeErrorUnknown`: any reason other than an
+    /// application exit.
+    const RUNTIME_ERROR: u64 = 0x2_0023;
+
+    let mut program = Vec::new();
+    call(&mut program, SYS_WRITEC, 0, None);
+    call(&mut program, SYS_WRITEC, 1, None);
+    call(&mut program, SYS_EXIT, 0x40, None);
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut ctx = boot(backend, &program, None);
+        poke(&mut ctx.sim, 0, b"ok");
+        poke_block(&mut ctx.sim, 0x40, &[RUNTIME_ERROR, 42]);
+
+        assert_eq!(ctx.run_to_exit_code(100_000), 1, "{backend:?}");
+        let out = ctx.sim.cpu.semihosting.as_mut().unwrap().capture.take().unwrap();
+        assert_eq!(out, b"ok", "{backend:?}");
+    }
+}
+
+#[test]
+fn plain_ebreak_still_traps() {
+    let program = [InstructionBuilder::new().addi(10, 0, 0x18).build(), SEMIHOST_EBREAK];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut ctx = boot(backend, &program, None);
+        // Direct mode turns the breakpoint trap into a failing exit.
+        assert_eq!(ctx.run_to_exit_code(100_000), 1, "{backend:?}");
+        assert_eq!(ctx.sim.cpu.semihosting.as_mut().unwrap().capture.take().unwrap(), b"");
+    }
+}
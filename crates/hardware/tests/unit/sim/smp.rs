@@ -0,0 +1,265 @@
+//! Multi-hart (SMP) Tests.
+//!
+//! Boots several harts on one shared system and verifies that each hart runs
+//! with its own register state, reads its own index from `mhartid`, and
+//! shares RAM with the others. A store from one hart to a granule another
+//! hart has reserved must make that hart's SC fail.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use rvsim_core::Simulator;
+use rvsim_core::common::{PhysAddr, RegIdx};
+use rvsim_core::config::Config;
+use rvsim_core::core::arch::csr::MIP_MSIP;
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::soc::System;
+
+/// Offset from the `auipc` result at which each hart stores its marker.
+const RESULT_OFFSET: i32 = 0x100;
+
+/// Each hart stores `mhartid + 1` to `base + 16 + RESULT_OFFSET + 8 * mhartid`, then spins.
+fn hartid_program() -> Vec<u32> {
+    vec![
+        InstructionBuilder::new().csrr(5, 0xF14).build(),
+        InstructionBuilder::new().add(6, 5, 5).build(),
+        InstructionBuilder::new().add(6, 6, 6).build(),
+        InstructionBuilder::new().add(6, 6, 6).build(),
+        InstructionBuilder::new().auipc(7, 0).build(),
+        InstructionBuilder::new().add(7, 7, 6).build(),
+        InstructionBuilder::new().addi(5, 5, 1).build(),
+        InstructionBuilder::new().sd(7, 5, RESULT_OFFSET).build(),
+        InstructionBuilder::new().jal(0, 0).build(),
+    ]
+}
+
+fn boot(num_harts: usize) -> (Simulator, Config) {
+    let mut config = Config::default();
+    config.system.num_harts = num_harts;
+    let system = System::new(&config, "");
+    let mut sim = Simulator::new(system, &config);
+    for (i, inst) in hartid_program().iter().enumerate() {
+        sim.cpu.bus.bus.write_u32(PhysAddr::new(config.system.ram_base + i as u64 * 4), *inst);
+    }
+    sim.cpu.pc = config.system.ram_base;
+    (sim, config)
+}
+
+fn marker_addr(config: &Config, hart: u64) -> PhysAddr {
+    PhysAddr::new(config.system.ram_base + 16 + RESULT_OFFSET as u64 + 8 * hart)
+}
+
+#[test]
+fn two_harts_each_store_their_mhartid() {
+    let (mut sim, config) = boot(2);
+    assert_eq!(sim.num_harts(), 2);
+
+    for _ in 0..500 {
+        sim.tick().unwrap();
+    }
+
+    assert_eq!(sim.cpu.bus.bus.read_u64(marker_addr(&config, 0)), 1, "hart 0 must run");
+    assert_eq!(sim.cpu.bus.bus.read_u64(marker_addr(&config, 1)), 2, "hart 1 must run");
+}
+
+#[test]
+fn secondary_hart_has_independent_state() {
+    let (mut sim, _config) = boot(2);
+    for _ in 0..500 {
+        sim.tick().unwrap();
+    }
+
+    let hart1 = sim.hart(1).unwrap();
+    assert_eq!(hart1.hart_id, 1);
+    assert_eq!(hart1.regs.read(RegIdx::new(5)), 2);
+    assert_eq!(sim.cpu.regs.read(RegIdx::new(5)), 1);
+    assert!(hart1.stats.instructions_retired > 0);
+    assert!(sim.hart(2).is_none());
+}
+
+#[test]
+fn four_harts_all_run() {
+    let (mut sim, config) = boot(4);
+    for _ in 0..1000 {
+        sim.tick().unwrap();
+    }
+
+    for hart in 0..4 {
+        assert_eq!(
+            sim.cpu.bus.bus.read_u64(marker_addr(&config, hart)),
+            hart + 1,
+            "hart {hart} must store its mhartid"
+        );
+    }
+}
+
+#[test]
+fn single_hart_is_the_default() {
+    let (mut sim, config) = boot(1);
+    for _ in 0..500 {
+        sim.tick().unwrap();
+    }
+    assert_eq!(sim.num_harts(), 1);
+    assert_eq!(sim.cpu.bus.bus.read_u64(marker_addr(&config, 0)), 1);
+    assert_eq!(sim.cpu.bus.bus.read_u64(marker_addr(&config, 1)), 0);
+}
+
+// ══════════════════════════════════════════════════════════
+// Inter-hart software interrupts (CLINT msip)
+// ══════════════════════════════════════════════════════════
+
+const MRET: u32 = 0x3020_0073;
+
+/// Hart 0 sends an IPI to hart 1 by writing `msip[1] = 1`; hart 1 enables
+/// MSIE, takes the software interrupt, records `mcause` in x28, counts the
+/// interrupt in x29 and clears its own `msip` before returning.
+fn ipi_program(clint_base: u64) -> Vec<(u64, u32)> {
+    let b = InstructionBuilder::new;
+    let clint_hi = (clint_base >> 12) as i32;
+    vec![
+        (0x00, b().csrr(5, 0xF14).build()),
+        (0x04, b().bne(5, 0, 0x3C).build()),
+        // hart 0: msip[1] = 1, then spin
+        (0x08, b().lui(6, clint_hi).build()),
+        (0x0C, b().addi(7, 0, 1).build()),
+        (0x10, b().sw(6, 7, 4).build()),
+        (0x14, b().jal(0, 0).build()),
+        // hart 1: mtvec = handler, mie.MSIE = 1, mstatus.MIE = 1, then spin
+        (0x40, b().auipc(6, 0).build()),
+        (0x44, b().addi(6, 6, 0x40).build()),
+        (0x48, b().csrw(0x305, 6).build()),
+        (0x4C, b().addi(7, 0, 8).build()),
+        (0x50, b().csrs(0x304, 7).build()),
+        (0x54, b().csrs(0x300, 7).build()),
+        (0x58, b().jal(0, 0).build()),
+        // hart 1 handler
+        (0x80, b().csrr(28, 0x342).build()),
+        (0x84, b().lui(6, clint_hi).build()),
+        (0x88, b().sw(6, 0, 4).build()),
+        (0x8C, b().addi(29, 29, 1).build()),
+        (0x90, MRET),
+    ]
+}
+
+fn boot_ipi(backend: BackendType) -> (Simulator, Config) {
+    let mut config = Config::default();
+    config.system.num_harts = 2;
+    config.pipeline.backend = backend;
+    let system = System::new(&config, "");
+    let mut sim = Simulator::new(system, &config);
+    for (offset, inst) in ipi_program(config.system.clint_base) {
+        sim.cpu.bus.bus.write_u32(PhysAddr::new(config.system.ram_base + offset), inst);
+    }
+    sim.cpu.pc = config.system.ram_base;
+    sim.cpu.direct_mode = false;
+    (sim, config)
+}
+
+fn assert_ipi_delivered(backend: BackendType) {
+    let (mut sim, config) = boot_ipi(backend);
+    for _ in 0..1000 {
+        sim.tick().unwrap();
+    }
+
+    let hart1 = sim.hart(1).unwrap();
+    assert_eq!(hart1.regs.read(RegIdx::new(28)), (1 << 63) | 3, "machine software interrupt");
+    assert_eq!(hart1.regs.read(RegIdx::new(29)), 1, "interrupt taken exactly once");
+    assert_eq!(hart1.csrs.mip & MIP_MSIP, 0);
+    assert_eq!(sim.cpu.bus.bus.read_u32(PhysAddr::new(config.system.clint_base + 4)), 0);
+}
+
+#[test]
+fn msip_write_interrupts_target_hart() {
+    assert_ipi_delivered(BackendType::InOrder);
+}
+
+#[test]
+fn msip_write_interrupts_target_hart_o3() {
+    assert_ipi_delivered(BackendType::OutOfOrder);
+}
+
+#[test]
+fn msip_write_does_not_interrupt_other_harts() {
+    let (mut sim, _config) = boot_ipi(BackendType::InOrder);
+    for _ in 0..1000 {
+        sim.tick().unwrap();
+    }
+
+    assert_eq!(sim.cpu.csrs.mip & MIP_MSIP, 0);
+    assert_eq!(sim.cpu.regs.read(RegIdx::new(29)), 0);
+}
+
+#[test]
+fn msip_set_is_visible_in_target_mip() {
+    let (mut sim, config) = boot_ipi(BackendType::InOrder);
+    // Hart 1 has not enabled interrupts yet, so the pending bit stays observable.
+    sim.cpu.bus.bus.write_u32(PhysAddr::new(config.system.clint_base + 4), 1);
+    sim.tick().unwrap();
+    assert_ne!(sim.hart(1).unwrap().csrs.mip & MIP_MSIP, 0);
+    assert_eq!(sim.cpu.csrs.mip & MIP_MSIP, 0);
+
+    sim.cpu.bus.bus.write_u32(PhysAddr::new(config.system.clint_base + 4), 0);
+    sim.tick().unwrap();
+    assert_eq!(sim.hart(1).unwrap().csrs.mip & MIP_MSIP, 0);
+}
+
+// ══════════════════════════════════════════════════════════
+// Cross-hart LR/SC reservations
+// ══════════════════════════════════════════════════════════
+
+/// Offset of the reserved word from the start of RAM.
+const RESERVED_OFFSET: u64 = 0x1000;
+
+/// Hart 0 takes a reservation on `a1`, waits, then exits with the `sc.w`
+/// result (0 = success). Meanwhile hart 1 waits a shorter time and stores to
+/// `a1 + store_off`. Returns the exit code.
+fn run_remote_store(backend: BackendType, store_off: i32) -> u64 {
+    let b = InstructionBuilder::new;
+    let program = [
+        (0x00, b().bne(10, 0, 0x40).build()),
+        // hart 0: lr, spin 400 iterations, sc, exit with the sc result
+        (0x04, b().lr_w(8, 11).build()),
+        (0x08, b().addi(12, 0, 400).build()),
+        (0x0C, b().addi(12, 12, -1).build()),
+        (0x10, b().bne(12, 0, -4).build()),
+        (0x14, b().sc_w(10, 11, 11).build()),
+        (0x18, b().addi(17, 0, 93).build()),
+        (0x1C, b().ecall().build()),
+        // hart 1: spin 100 iterations, store, then spin
+        (0x40, b().addi(12, 0, 100).build()),
+        (0x44, b().addi(12, 12, -1).build()),
+        (0x48, b().bne(12, 0, -4).build()),
+        (0x4C, b().sw(11, 12, store_off).build()),
+        (0x50, b().jal(0, 0).build()),
+    ];
+
+    let mut config = Config::default();
+    config.system.num_harts = 2;
+    config.pipeline.backend = backend;
+    let base = config.system.ram_base;
+    let mut sim = Simulator::new(System::new(&config, ""), &config);
+    for (offset, inst) in program {
+        sim.cpu.bus.bus.write_u32(PhysAddr::new(base + offset), inst);
+    }
+    sim.cpu.pc = base;
+    sim.write_reg(RegIdx::new(11), base + RESERVED_OFFSET);
+    for _ in 0..20_000 {
+        sim.tick().unwrap();
+        if let Some(code) = sim.cpu.exit_code {
+            return code;
+        }
+    }
+    panic!("hart 0 did not exit");
+}
+
+#[test]
+fn remote_store_to_reserved_granule_fails_sc() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        assert_eq!(run_remote_store(backend, 16), 1, "{backend:?}");
+    }
+}
+
+#[test]
+fn remote_store_elsewhere_keeps_reservation() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        assert_eq!(run_remote_store(backend, 128), 0, "{backend:?}");
+    }
+}
@@ -0,0 +1,278 @@
+//! Syscall Emulation Tests.
+//!
+//! Runs hand-assembled static user programs, loaded from ELF images with
+//! `general.syscall_emulation` set, on both backends:
+//!   - A hello-world writing to stdout and exiting with its `argc`.
+//!   - A program reading a file from the sandbox root and echoing it.
+//!   - `brk`, anonymous `mmap`/`munmap`, `uname`, `clock_gettime`, and an
+//!     unknown call answered with `-ENOSYS` and counted.
+//!   - Paths outside the root, missing files and bad descriptors failing
+//!     with the Linux errno.
+//!
+//! Also checks the initial stack: `argc`, `argv`, `envp` and the auxiliary
+//! vector.
+
+use crate::common::builder::elf::ElfBuilder;
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::Simulator;
+use rvsim_core::common::{PhysAddr, RegIdx};
+use rvsim_core::config::Config;
+use rvsim_core::core::cpu::syscall::{
+    ENOSYS, SYS_BRK, SYS_CLOCK_GETTIME, SYS_CLOSE, SYS_EXIT, SYS_EXIT_GROUP, SYS_FSTAT, SYS_GETPID,
+    SYS_MMAP, SYS_MUNMAP, SYS_OPENAT, SYS_READ, SYS_UNAME, SYS_WRITE,
+};
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::sim::loader;
+use rvsim_core::soc::System;
+use std::path::Path;
+
+/// Offset from the start of RAM of the text segment (the entry point).
+const TEXT: u64 = 0x1000;
+/// Offset from the start of RAM of the data segment, held in `s0`.
+const DATA: u64 = TEXT + 0x2000;
+/// Size of the data segment including its BSS.
+const DATA_SIZE: u64 = 0x1000;
+
+/// `s0`, the data pointer.
+const S0: u32 = 8;
+/// Registers the results of successive calls are saved in.
+const SAVED: [u32; 8] = [9, 18, 19, 20, 21, 22, 23, 24];
+
+/// `dirfd` for paths relative to the working directory.
+const AT_FDCWD: i32 = -100;
+
+/// Where a system call argument comes from.
+#[derive(Clone, Copy)]
+enum Arg {
+    /// An immediate.
+    Imm(i32),
+    /// The address `s0 + offset` in the data segment.
+    Data(i32),
+    /// A register plus an offset.
+    Reg(u32, i32),
+}
+
+/// Appends a call of `num` with `args` in `a0`.., then saves `a0` in `save`,
+/// if given.
+fn call(program: &mut Vec<u32>, num: u64, args: &[Arg], save: Option<u32>) {
+    let b = InstructionBuilder::new;
+    for (i, &arg) in args.iter().enumerate() {
+        let rd = 10 + i as u32;
+        program.push(match arg {
+            Arg::Imm(imm) => b().addi(rd, 0, imm).build(),
+            Arg::Data(offset) => b().addi(rd, S0, offset).build(),
+            Arg::Reg(rs, offset) => b().addi(rd, rs, offset).build(),
+        });
+    }
+    program.push(b().addi(17, 0, num as i32).build());
+    program.push(b().ecall().build());
+    if let Some(rd) = save {
+        program.push(b().addi(rd, 10, 0).build());
+    }
+}
+
+/// Starts a program: `auipc s0` to point at the data segment.
+fn program() -> Vec<u32> {
+    vec![InstructionBuilder::new().auipc(S0, ((DATA - TEXT) >> 12) as i32).build()]
+}
+
+/// Loads an ELF of `text` and `data` on `backend` with syscall emulation,
+/// `args` and files rooted at `root`, with console output captured.
+fn boot(
+    backend: BackendType,
+    text: &[u32],
+    data: &[u8],
+    args: &[&str],
+    root: Option<&Path>,
+) -> TestContext {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.general.syscall_emulation = true;
+    config.general.syscall_root = root.map(|r| r.to_str().unwrap().to_owned());
+    config.general.args = args.iter().map(|&a| a.to_owned()).collect();
+    let base = config.system.ram_base;
+    let text: Vec<u8> = text.iter().flat_map(|w| w.to_le_bytes()).collect();
+    let image = ElfBuilder::new(base + TEXT)
+        .headers_at(base)
+        .segment(base + TEXT, &text, text.len() as u64)
+        .segment(base + DATA, data, DATA_SIZE)
+        .build();
+
+    let mut system = System::new(&config, "");
+    let elf = loader::try_load_elf(&image, &mut system.bus).unwrap().unwrap();
+    let mut sim = Simulator::new(system, &config);
+    sim.cpu.start_process(elf.entry, elf.image_end, elf.phdrs);
+    sim.sync_arch_regs();
+    sim.cpu.syscall_emulation.as_mut().unwrap().capture = Some(Vec::new());
+    TestContext { sim }
+}
+
+/// Returns the console output captured so far.
+fn output(sim: &mut Simulator) -> Vec<u8> {
+    sim.cpu.syscall_emulation.as_mut().unwrap().capture.take().unwrap()
+}
+
+/// Returns the saved call results.
+fn saved(sim: &Simulator) -> [u64; 8] {
+    SAVED.map(|r| sim.cpu.regs.read(RegIdx::new(r as u8)))
+}
+
+fn read_u64(sim: &mut Simulator, addr: u64) -> u64 {
+    sim.cpu.bus.bus.read_u64(PhysAddr::new(addr))
+}
+
+fn read_bytes(sim: &mut Simulator, addr: u64, len: u64) -> Vec<u8> {
+    (0..len).map(|i| sim.cpu.bus.bus.read_u8(PhysAddr::new(addr + i))).collect()
+}
+
+#[test]
+fn hello_world_writes_stdout_and_exits_with_argc() {
+    let b = InstructionBuilder::new;
+    let text = b"hello, world\n";
+    let mut p = program();
+    call(&mut p, SYS_WRITE, &[Arg::Imm(1), Arg::Data(0), Arg::Imm(text.len() as i32)], None);
+    p.push(b().ld(10, 2, 0).build()); // a0 = argc
+    call(&mut p, SYS_EXIT_GROUP, &[], None);
+
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut ctx = boot(backend, &p, text, &["hello", "a", "b"], None);
+        assert_eq!(ctx.run_to_exit_code(100_000), 3, "{backend:?}");
+        assert_eq!(output(&mut ctx.sim), text, "{backend:?}");
+        assert_eq!(ctx.sim.cpu.stats.syscalls, 2, "{backend:?}");
+    }
+}
+
+#[test]
+fn file_is_read_from_the_root_and_echoed() {
+    let root = tempfile::tempdir().unwrap();
+    let contents = b"line one\nline two\n";
+    std::fs::write(root.path().join("input.txt"), contents).unwrap();
+
+    let [fd, fstat, n, close, ..] = SAVED;
+    let mut p = program();
+    call(&mut p, SYS_OPENAT, &[Arg::Imm(AT_FDCWD), Arg::Data(0), Arg::Imm(0)], Some(fd));
+    call(&mut p, SYS_FSTAT, &[Arg::Reg(fd, 0), Arg::Data(0x100)], Some(fstat));
+    call(&mut p, SYS_READ, &[Arg::Reg(fd, 0), Arg::Data(0x200), Arg::Imm(0x100)], Some(n));
+    call(&mut p, SYS_WRITE, &[Arg::Imm(1), Arg::Data(0x200), Arg::Reg(n, 0)], None);
+    call(&mut p, SYS_CLOSE, &[Arg::Reg(fd, 0)], Some(close));
+    call(&mut p, SYS_EXIT, &[Arg::Reg(n, 0)], None);
+
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut ctx = boot(backend, &p, b"input.txt\0", &["cat"], Some(root.path()));
+        assert_eq!(ctx.run_to_exit_code(100_000), contents.len() as u64, "{backend:?}");
+        assert_eq!(output(&mut ctx.sim), contents, "{backend:?}");
+        let [fd, fstat, _, close, ..] = saved(&ctx.sim);
+        assert_eq!((fd, fstat, close), (3, 0, 0), "{backend:?}");
+        let data = Config::default().system.ram_base + DATA;
+        assert_eq!(read_u64(&mut ctx.sim, data + 0x100 + 48), contents.len() as u64, "st_size");
+    }
+}
+
+#[test]
+fn heap_mappings_and_process_info() {
+    let b = InstructionBuilder::new;
+    let [brk0, brk1, map, unmap, unknown, pid, clock, uname] = SAVED;
+    let mut p = program();
+    call(&mut p, SYS_BRK, &[Arg::Imm(0)], Some(brk0));
+    call(&mut p, SYS_BRK, &[Arg::Reg(brk0, 0x7f0)], Some(brk1));
+    let anon =
+        [Arg::Imm(0), Arg::Imm(2000), Arg::Imm(3), Arg::Imm(0x22), Arg::Imm(-1), Arg::Imm(0)];
+    call(&mut p, SYS_MMAP, &anon, Some(map));
+    p.push(b().sd(map, brk1, 0).build());
+    call(&mut p, SYS_MUNMAP, &[Arg::Reg(map, 0), Arg::Imm(2000)], Some(unmap));
+    call(&mut p, 999, &[], Some(unknown));
+    call(&mut p, SYS_GETPID, &[], Some(pid));
+    call(&mut p, SYS_CLOCK_GETTIME, &[Arg::Imm(1), Arg::Data(0x100)], Some(clock));
+    call(&mut p, SYS_UNAME, &[Arg::Data(0x200)], Some(uname));
+    call(&mut p, SYS_EXIT, &[Arg::Imm(0)], None);
+
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut ctx = boot(backend, &p, &[], &["mem"], None);
+        assert_eq!(ctx.run_to_exit_code(100_000), 0, "{backend:?}");
+        let [brk0, brk1, map, unmap, unknown, pid, clock, uname] = saved(&ctx.sim);
+        let data = Config::default().system.ram_base + DATA;
+
+        assert_eq!(brk0, data + DATA_SIZE, "{backend:?}: break starts at the end of the image");
+        assert_eq!(brk1, brk0 + 0x7f0, "{backend:?}");
+        assert_eq!(map % 4096, 0, "{backend:?}");
+        assert!(map > brk1 && map < ctx.sim.cpu.regs.read(RegIdx::new(2)), "{backend:?}: {map:#x}");
+        assert_eq!(read_u64(&mut ctx.sim, map), brk1, "{backend:?}: mapping is writable");
+        assert_eq!((unmap, pid, clock, uname), (0, 100, 0, 0), "{backend:?}");
+        assert_eq!(unknown, ENOSYS.wrapping_neg(), "{backend:?}");
+        assert_eq!(ctx.sim.cpu.stats.syscalls_unknown, 1, "{backend:?}");
+        assert!(read_u64(&mut ctx.sim, data + 0x108) < 1_000_000_000, "{backend:?}: tv_nsec");
+        assert_eq!(read_bytes(&mut ctx.sim, data + 0x200, 6), b"Linux\0", "{backend:?}");
+        assert_eq!(read_bytes(&mut ctx.sim, data + 0x200 + 4 * 65, 8), b"riscv64\0", "{backend:?}");
+    }
+}
+
+#[test]
+fn file_errors_use_linux_errno() {
+    let root = tempfile::tempdir().unwrap();
+    std::fs::create_dir(root.path().join("sub")).unwrap();
+    std::fs::write(root.path().join("sub/f"), b"x").unwrap();
+
+    let [escape, missing, absolute, bad_fd, dirfd, ..] = SAVED;
+    let mut p = program();
+    call(&mut p, SYS_OPENAT, &[Arg::Imm(AT_FDCWD), Arg::Data(0), Arg::Imm(0)], Some(escape));
+    call(&mut p, SYS_OPENAT, &[Arg::Imm(AT_FDCWD), Arg::Data(0x10), Arg::Imm(0)], Some(missing));
+    call(&mut p, SYS_OPENAT, &[Arg::Imm(3), Arg::Data(0x20), Arg::Imm(0)], Some(absolute));
+    call(&mut p, SYS_READ, &[Arg::Imm(99), Arg::Data(0x100), Arg::Imm(1)], Some(bad_fd));
+    call(&mut p, SYS_OPENAT, &[Arg::Imm(3), Arg::Data(0x30), Arg::Imm(0)], Some(dirfd));
+    call(&mut p, SYS_EXIT, &[Arg::Imm(0)], None);
+
+    let mut data = [0u8; 0x40];
+    data[..10].copy_from_slice(b"../escape\0");
+    data[0x10..0x18].copy_from_slice(b"missing\0");
+    data[0x20..0x27].copy_from_slice(b"/sub/f\0");
+    data[0x30..0x36].copy_from_slice(b"sub/f\0");
+
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut ctx = boot(backend, &p, &data, &["errs"], Some(root.path()));
+        assert_eq!(ctx.run_to_exit_code(100_000), 0, "{backend:?}");
+        let [escape, missing, absolute, bad_fd, dirfd, ..] = saved(&ctx.sim);
+        let neg = |errno: u64| errno.wrapping_neg();
+        assert_eq!(escape, neg(13), "{backend:?}: EACCES outside the root");
+        assert_eq!(missing, neg(2), "{backend:?}: ENOENT");
+        assert_eq!(absolute, 3, "{backend:?}: absolute paths resolve in the root");
+        assert_eq!(bad_fd, neg(9), "{backend:?}: EBADF");
+        assert_eq!(dirfd, neg(9), "{backend:?}: relative to a directory descriptor");
+    }
+}
+
+#[test]
+fn initial_stack_holds_args_and_auxv() {
+    let mut p = program();
+    call(&mut p, SYS_EXIT, &[Arg::Imm(0)], None);
+    let sim = &mut boot(BackendType::InOrder, &p, &[], &["prog", "--flag"], None).sim;
+    let sp = sim.cpu.regs.read(RegIdx::new(2));
+    let entry = Config::default().system.ram_base + TEXT;
+
+    assert_eq!(sp % 16, 0);
+    assert_eq!(sim.cpu.pc, entry);
+    assert_eq!(read_u64(sim, sp), 2, "argc");
+    let (argv0, argv1) = (read_u64(sim, sp + 8), read_u64(sim, sp + 16));
+    assert_eq!(read_bytes(sim, argv0, 5), b"prog\0");
+    assert_eq!(read_bytes(sim, argv1, 7), b"--flag\0");
+    assert_eq!(read_u64(sim, sp + 24), 0, "argv terminator");
+    assert_eq!(read_u64(sim, sp + 32), 0, "empty envp");
+
+    let mut auxv = Vec::new();
+    let mut at = sp + 40;
+    loop {
+        let (key, val) = (read_u64(sim, at), read_u64(sim, at + 8));
+        if key == 0 {
+            break;
+        }
+        auxv.push((key, val));
+        at += 16;
+    }
+    let get = |key| auxv.iter().find(|&&(k, _)| k == key).map(|&(_, v)| v);
+    assert_eq!(get(6), Some(4096), "AT_PAGESZ");
+    assert_eq!(get(9), Some(entry), "AT_ENTRY");
+    assert_eq!(get(5), Some(3), "AT_PHNUM");
+    assert_eq!(get(3), Some(entry - TEXT + 64), "AT_PHDR: headers follow the ELF header");
+    let random = get(25).unwrap();
+    assert!(random > argv0 && random < sim.cpu.bus.bus.get_ram_info().unwrap().2, "AT_RANDOM");
+}
@@ -0,0 +1,100 @@
+//! Trace Region Tests.
+//!
+//! Runs a straight-line program with `general.trace_start_pc` and
+//! `general.trace_stop_pc` set on both backends and checks that the commit
+//! trace and the pipeline trace only cover the instructions from the start PC
+//! up to, but not including, the stop PC.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::config::{Config, PipeTraceFormat};
+use rvsim_core::core::pipeline::engine::BackendType;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+/// Collects formatted tracing output.
+#[derive(Clone, Default)]
+struct Sink(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// `li x5..x9, 1..5`, then exit. The region is the second and third `li`.
+fn run(backend: BackendType, pipe_trace: Option<(&str, PipeTraceFormat)>) -> u64 {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.general.trace_instructions = true;
+    let base = config.system.ram_base;
+    config.general.trace_start_pc = Some(base + 4);
+    config.general.trace_stop_pc = Some(base + 12);
+    if let Some((path, format)) = pipe_trace {
+        config.general.pipe_trace = Some(path.to_owned());
+        config.general.pipe_trace_format = format;
+    }
+
+    let b = InstructionBuilder::new;
+    let mut program: Vec<u32> = (0..5).map(|i| b().addi(5 + i, 0, i as i32 + 1).build()).collect();
+    program.extend([b().addi(17, 0, 93).build(), b().ecall().build()]);
+    TestContext::with_config(&config).load_program(base, &program).run_to_exit_code(10_000);
+    base
+}
+
+#[test]
+fn commit_trace_covers_only_the_region() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let sink = Sink::default();
+        let writer = sink.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::new("rvsim::commit=trace"))
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+        let base = tracing::subscriber::with_default(subscriber, || run(backend, None));
+
+        let text = String::from_utf8(sink.0.lock().unwrap().clone()).unwrap();
+        let pcs: Vec<u64> = text
+            .lines()
+            .filter(|l| l.contains("instruction retired"))
+            .map(|l| {
+                let pc = l.split("pc=0x").nth(1).unwrap().split_whitespace().next().unwrap();
+                u64::from_str_radix(pc, 16).unwrap()
+            })
+            .collect();
+        assert_eq!(pcs, [base + 4, base + 8], "{backend:?}:\n{text}");
+    }
+}
+
+#[test]
+fn pipe_trace_covers_only_the_region() {
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let path = file.path().to_str().unwrap();
+
+        let base = run(backend, Some((path, PipeTraceFormat::O3PipeView)));
+        let text = std::fs::read_to_string(path).unwrap();
+        let pcs: Vec<u64> = text
+            .lines()
+            .filter_map(|l| l.strip_prefix("O3PipeView:fetch:"))
+            .map(|l| u64::from_str_radix(&l.split(':').nth(1).unwrap()[2..], 16).unwrap())
+            .collect();
+        assert_eq!(pcs, [base + 4, base + 8], "{backend:?}:\n{text}");
+
+        let _ = run(backend, Some((path, PipeTraceFormat::Konata)));
+        let text = std::fs::read_to_string(path).unwrap();
+        let labels: Vec<&str> = text
+            .lines()
+            .filter(|l| l.starts_with("L\t") && l.ends_with(": "))
+            .map(|l| l.split('\t').nth(3).unwrap())
+            .collect();
+        assert_eq!(labels, ["80000004: ", "80000008: "], "{backend:?}:\n{text}");
+        assert_eq!(text.lines().filter(|l| l.starts_with("R\t")).count(), 2, "{backend:?}");
+    }
+}
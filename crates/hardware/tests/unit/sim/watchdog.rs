@@ -0,0 +1,158 @@
+//! Watchdog Tests.
+//!
+//! An infinite `j .` loop trips `general.hang_threshold` after exactly that
+//! many cycles at one PC, and `general.max_cycles` fails the run once the
+//! budget is spent. Both return a hang error carrying the diagnostic dump.
+//! A hart parked in WFI is waiting, not hung, and never trips the watchdog.
+//! A two-instruction spin on a flag that never changes trips the livelock
+//! detector, which reports the loop; a countdown loop makes progress and does
+//! not.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use rvsim_core::Simulator;
+use rvsim_core::common::{PhysAddr, RegIdx, SimError};
+use rvsim_core::config::Config;
+use rvsim_core::core::arch::csr;
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::isa::disasm::disassemble;
+use rvsim_core::soc::System;
+
+/// `wfi`.
+const WFI: u32 = 0x1050_0073;
+
+/// Same-PC threshold used by the tests.
+const THRESHOLD: u64 = 200;
+
+/// Loads `program` at the start of RAM on `backend` with the watchdog set
+/// from `hang_threshold` and `max_cycles`.
+fn boot(
+    backend: BackendType,
+    program: &[u32],
+    hang_threshold: Option<u64>,
+    max_cycles: Option<u64>,
+) -> Simulator {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.general.hang_threshold = hang_threshold;
+    config.general.max_cycles = max_cycles;
+    let base = config.system.ram_base;
+    let mut sim = Simulator::new(System::new(&config, ""), &config);
+    for (i, &inst) in program.iter().enumerate() {
+        sim.cpu.bus.bus.write_u32(PhysAddr::new(base + 4 * i as u64), inst);
+    }
+    sim.cpu.pc = base;
+    sim
+}
+
+/// Ticks until `tick()` fails or `cycles` elapse; returns the error, if any.
+fn run(sim: &mut Simulator, cycles: u64) -> Option<SimError> {
+    (0..cycles).find_map(|_| sim.tick().err())
+}
+
+#[test]
+fn jump_to_self_trips_hang_threshold_with_dump() {
+    let b = InstructionBuilder::new;
+    let spin = b().jal(0, 0).build();
+    let base = Config::default().system.ram_base;
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let program = [b().addi(5, 0, 1).build(), spin];
+        let mut sim = boot(backend, &program, Some(THRESHOLD), None);
+        let err = run(&mut sim, 10_000).expect("watchdog did not fire");
+        assert!(err.is_hang(), "{backend:?}: {err}");
+        let SimError::HangDetected { pc, inst, cycle_count, report } = err else {
+            panic!("{backend:?}: unexpected error {err}");
+        };
+        assert_eq!(pc, base + 4, "{backend:?}");
+        assert_eq!(inst, spin, "{backend:?}");
+        assert_eq!(cycle_count, THRESHOLD, "{backend:?}");
+        assert!(sim.cpu.stats.cycles < 2 * THRESHOLD, "{backend:?}: {}", sim.cpu.stats.cycles);
+        assert!(report.contains("Machine mode"), "{backend:?}:\n{report}");
+        assert!(report.contains("pending+enabled 0x0"), "{backend:?}:\n{report}");
+        assert!(report.contains(&disassemble(spin)), "{backend:?}:\n{report}");
+        assert!(report.contains("ROB ("), "{backend:?}:\n{report}");
+        assert!(report.contains("store buffer ("), "{backend:?}:\n{report}");
+    }
+}
+
+#[test]
+fn max_cycles_trips_with_dump() {
+    let spin = InstructionBuilder::new().jal(0, 0).build();
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut sim = boot(backend, &[spin], None, Some(500));
+        let err = run(&mut sim, 10_000).expect("watchdog did not fire");
+        let SimError::CycleLimitExceeded { cycles, report } = err else {
+            panic!("{backend:?}: unexpected error {err}");
+        };
+        assert_eq!(cycles, 500, "{backend:?}");
+        assert!(report.contains("at cycle 500"), "{backend:?}:\n{report}");
+    }
+}
+
+#[test]
+fn watchdog_is_off_by_default() {
+    let spin = InstructionBuilder::new().jal(0, 0).build();
+    let mut sim = boot(BackendType::InOrder, &[spin], None, None);
+    assert!(run(&mut sim, 10_000).is_none());
+}
+
+#[test]
+fn wfi_does_not_trip_hang_threshold() {
+    let b = InstructionBuilder::new;
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut sim = boot(backend, &[WFI, b().jal(0, 0).build()], Some(THRESHOLD), None);
+        sim.cpu.csrs.mie = csr::MIE_MTIE;
+        assert!(run(&mut sim, 20 * THRESHOLD).is_none(), "{backend:?}");
+        assert!(sim.cpu.wfi_waiting, "{backend:?}");
+    }
+}
+
+#[test]
+fn spin_on_flag_trips_livelock_detector_with_loop() {
+    let b = InstructionBuilder::new;
+    // s0 = base + 0x1000; 1: ld t0, 0(s0); beq t0, zero, 1b
+    let poll = b().ld(5, 8, 0).build();
+    let branch = b().beq(5, 0, -4).build();
+    let program = [b().addi(6, 0, 1).build(), poll, branch];
+    let base = Config::default().system.ram_base;
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut sim = boot(backend, &program, Some(THRESHOLD), None);
+        sim.write_reg(RegIdx::new(8), base + 0x1000);
+        let err = run(&mut sim, 10_000).expect("watchdog did not fire");
+        assert!(err.is_hang(), "{backend:?}: {err}");
+        let SimError::LivelockDetected { pcs, cycle_count, report } = err else {
+            panic!("{backend:?}: unexpected error {err}");
+        };
+        assert_eq!(pcs, [base + 4, base + 8], "{backend:?}");
+        assert_eq!(cycle_count, THRESHOLD, "{backend:?}");
+        let listing = format!(
+            "loop:\n  {:#018x}: {}\n  {:#018x}: {}\n",
+            base + 4,
+            disassemble(poll),
+            base + 8,
+            disassemble(branch)
+        );
+        assert!(report.starts_with(&listing), "{backend:?}:\n{report}");
+        assert!(report.contains("Machine mode"), "{backend:?}:\n{report}");
+    }
+}
+
+#[test]
+fn countdown_loop_is_progress() {
+    let b = InstructionBuilder::new;
+    // t0 = 2000; 1: addi t0, t0, -1; bne t0, zero, 1b; exit
+    let program = [
+        b().addi(5, 0, 2000).build(),
+        b().addi(5, 5, -1).build(),
+        b().bne(5, 0, -4).build(),
+        b().addi(17, 0, 93).build(),
+        b().ecall().build(),
+    ];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut sim = boot(backend, &program, Some(THRESHOLD), None);
+        let exit = (0..100_000).find_map(|_| {
+            sim.tick().unwrap();
+            sim.take_exit()
+        });
+        assert!(exit.is_some(), "{backend:?}");
+    }
+}
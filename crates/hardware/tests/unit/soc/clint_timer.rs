@@ -131,3 +131,76 @@ fn clint_unrecognized_offset_returns_zero() {
     assert_eq!(clint.read_u64(0x1000), 0);
     assert_eq!(clint.read_u32(0x1000), 0);
 }
+
+#[test]
+fn clint_per_hart_msip_registers() {
+    let mut clint = Clint::with_harts(0, 1, 2);
+    clint.write_u32(0x4, 1);
+    assert!(!clint.hart_msip(0));
+    assert!(clint.hart_msip(1));
+    assert_eq!(clint.read_u32(0x0), 0);
+    assert_eq!(clint.read_u32(0x4), 1);
+}
+
+#[test]
+fn clint_per_hart_mtimecmp_registers() {
+    let mut clint = Clint::with_harts(0, 1, 2);
+    clint.write_u64(0x4000 + 8, 3);
+    assert_eq!(clint.read_u64(0x4000), u64::MAX);
+    assert_eq!(clint.read_u64(0x4008), 3);
+
+    for _ in 0..3 {
+        clint.tick();
+    }
+    assert!(!clint.hart_mtip(0));
+    assert!(clint.hart_mtip(1));
+}
+
+#[test]
+fn clint_registers_beyond_last_hart_are_ignored() {
+    let mut clint = Clint::with_harts(0, 1, 2);
+    clint.write_u32(0x8, 1);
+    clint.write_u64(0x4010, 0);
+    assert_eq!(clint.read_u32(0x8), 0);
+    assert_eq!(clint.read_u64(0x4010), 0);
+    assert!(!clint.hart_msip(2));
+    assert!(!clint.hart_mtip(2));
+}
+
+#[test]
+fn clint_hart3_msip_decodes_at_0x000c() {
+    let mut clint = Clint::with_harts(0, 1, 4);
+    clint.write_u32(0x000C, 1);
+    assert_eq!(clint.read_u32(0x000C), 1);
+    assert_eq!(clint.read_u64(0x000C), 1);
+    assert!(clint.hart_msip(3));
+    for hart in 0..3 {
+        assert!(!clint.hart_msip(hart), "msip[3] write leaked into hart {hart}");
+    }
+
+    clint.set_msip(3, false);
+    assert_eq!(clint.read_u32(0x000C), 0);
+}
+
+#[test]
+fn clint_hart3_mtimecmp_decodes_at_0x4018() {
+    let mut clint = Clint::with_harts(0, 1, 4);
+    clint.write_u32(0x4018, 0x0000_0005);
+    clint.write_u32(0x401C, 0x0000_0000);
+    assert_eq!(clint.mtimecmp(3), Some(5));
+    assert_eq!(clint.read_u64(0x4018), 5);
+    assert_eq!(clint.read_u32(0x401C), 0);
+    assert_eq!(clint.read_u64(0x4010), u64::MAX, "hart 2 untouched");
+    assert_eq!(clint.mtimecmp(4), None);
+
+    for _ in 0..5 {
+        clint.tick();
+    }
+    assert!(clint.hart_mtip(3));
+    assert!((0..3).all(|hart| !clint.hart_mtip(hart)));
+
+    clint.set_mtimecmp(3, 0x1_0000_0000);
+    assert_eq!(clint.read_u32(0x4018), 0);
+    assert_eq!(clint.read_u32(0x401C), 1);
+    assert!(!clint.hart_mtip(3));
+}
@@ -0,0 +1,209 @@
+//! DMA Controller Tests.
+//!
+//! Verifies the register interface (copy, completion status and interrupt,
+//! write-1-to-clear, busy-time writes ignored, out-of-RAM ranges rejected),
+//! that memory latency and queued memory requests hold back completion, and
+//! that a guest can program a 4 KiB copy, park in `wfi`, and wake on the
+//! PLIC interrupt with the destination populated.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::common::{PhysAddr, RegIdx};
+use rvsim_core::config::Config;
+use rvsim_core::core::arch::csr;
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::soc::devices::dma::{
+    CTRL_IRQ_ENABLE, CTRL_START, STATUS_BUSY, STATUS_DONE, STATUS_ERROR,
+};
+use rvsim_core::soc::devices::{Device, DmaController};
+use rvsim_core::soc::memory::buffer::DramBuffer;
+use std::sync::Arc;
+
+const RAM_BASE: u64 = 0x8000_0000;
+const DMA_BASE: u64 = 0x9000_3000;
+
+const SRC: u64 = 0x00;
+const DST: u64 = 0x08;
+const LEN: u64 = 0x10;
+const CTRL: u64 = 0x18;
+const STATUS: u64 = 0x20;
+const COPIED: u64 = 0x28;
+
+/// `wfi`.
+const WFI: u32 = 0x1050_0073;
+
+/// PLIC source of the DMA controller.
+const DMA_IRQ: u64 = 4;
+
+/// Doubleword stored at `offset` of the guest copy's source.
+const fn pattern(offset: u64) -> u64 {
+    0xA5A5_0000_0000_0000 | offset
+}
+
+fn make_dma(bytes_per_cycle: u64) -> (DmaController, Arc<DramBuffer>) {
+    let ram = Arc::new(DramBuffer::new(0x1000));
+    for i in 0..64 {
+        ram.write_slice(i, &[i as u8 + 1]);
+    }
+    (DmaController::new(DMA_BASE, RAM_BASE, Arc::clone(&ram), bytes_per_cycle), ram)
+}
+
+fn program(dma: &mut DmaController, src: u64, dst: u64, len: u64, ctrl: u64) {
+    dma.write_u64(SRC, src);
+    dma.write_u64(DST, dst);
+    dma.write_u64(LEN, len);
+    dma.write_u64(CTRL, ctrl);
+}
+
+#[test]
+fn copy_completes_and_raises_interrupt() {
+    let (mut dma, ram) = make_dma(8);
+    program(&mut dma, RAM_BASE, RAM_BASE + 0x800, 64, CTRL_START | CTRL_IRQ_ENABLE);
+    assert_eq!(dma.read_u64(STATUS), STATUS_BUSY);
+
+    let mut cycles = 0;
+    while !dma.tick() {
+        assert!(dma.memory_requests().len() <= 2);
+        dma.complete_requests(0);
+        cycles += 1;
+        assert!(cycles < 100, "transfer never completed");
+    }
+    assert_eq!(cycles, 8, "64 bytes at 8 bytes per cycle");
+    assert_eq!(dma.read_u64(STATUS), STATUS_DONE);
+    assert_eq!(dma.read_u64(COPIED), 64);
+    assert_eq!(ram.read_slice(0x800, 64), ram.read_slice(0, 64));
+
+    let mut writes = Vec::new();
+    dma.take_dma_writes(&mut writes);
+    assert_eq!(writes.len(), 8);
+    assert_eq!(writes[0], (RAM_BASE + 0x800, 8));
+
+    dma.write_u32(STATUS, STATUS_DONE as u32);
+    assert_eq!(dma.read_u64(STATUS), 0);
+    assert!(!dma.tick(), "clearing DONE drops the interrupt");
+}
+
+#[test]
+fn registers_are_frozen_while_busy() {
+    let (mut dma, _ram) = make_dma(1);
+    program(&mut dma, RAM_BASE, RAM_BASE + 0x800, 16, CTRL_START);
+    dma.write_u64(LEN, 4096);
+    dma.write_u32(SRC + 4, 0xdead);
+    assert_eq!(dma.transfer(), (RAM_BASE, RAM_BASE + 0x800, 16));
+
+    for _ in 0..17 {
+        assert!(!dma.tick(), "IRQ disabled");
+    }
+    assert_eq!(dma.read_u64(STATUS), STATUS_DONE);
+    assert_eq!(dma.read_u32(SRC + 4), (RAM_BASE >> 32) as u32);
+}
+
+#[test]
+fn range_outside_ram_sets_error() {
+    let (mut dma, _ram) = make_dma(8);
+    program(&mut dma, RAM_BASE + 0xF00, RAM_BASE, 0x200, CTRL_START | CTRL_IRQ_ENABLE);
+    assert_eq!(dma.read_u64(STATUS), STATUS_ERROR);
+    assert!(dma.tick(), "ERROR raises the interrupt");
+    assert_eq!(dma.read_u64(COPIED), 0);
+}
+
+#[test]
+fn memory_latency_delays_completion() {
+    let (mut dma, _ram) = make_dma(64);
+    program(&mut dma, RAM_BASE, RAM_BASE + 0x800, 64, CTRL_START);
+    let _ = dma.tick();
+    assert_eq!(dma.memory_requests(), &[(RAM_BASE, 64), (RAM_BASE + 0x800, 64)]);
+    dma.complete_requests(30);
+
+    let mut cycles = 1;
+    while dma.busy() {
+        let _ = dma.tick();
+        cycles += 1;
+        assert!(cycles < 100, "transfer never completed");
+    }
+    assert_eq!(cycles, 31, "completion waits for the slowest request");
+}
+
+#[test]
+fn queued_requests_hold_completion_until_released() {
+    let (mut dma, _ram) = make_dma(64);
+    program(&mut dma, RAM_BASE, RAM_BASE + 0x800, 64, CTRL_START);
+    let _ = dma.tick();
+    dma.hold_requests(2);
+    for _ in 0..50 {
+        let _ = dma.tick();
+    }
+    assert!(dma.busy(), "still waiting on the memory controller");
+
+    dma.release_request(0);
+    dma.release_request(10);
+    let mut cycles = 0;
+    while dma.busy() {
+        let _ = dma.tick();
+        cycles += 1;
+        assert!(cycles < 100, "transfer never completed");
+    }
+    assert_eq!(cycles, 10);
+}
+
+#[test]
+fn guest_copy_wakes_from_wfi_with_destination_populated() {
+    const SIZE: u64 = 4096;
+    let b = InstructionBuilder::new;
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut config = Config::default();
+        config.pipeline.backend = backend;
+        config.system.dma = true;
+        let base = config.system.ram_base;
+        let (src, dst) = (base + 0x10_000, base + 0x20_000);
+
+        // x10 = DMA base, x11 = SRC, x12 = DST, x13 = LEN, x14 = CTRL.
+        let program = [
+            b().sd(10, 11, SRC as i32).build(),
+            b().sd(10, 12, DST as i32).build(),
+            b().sd(10, 13, LEN as i32).build(),
+            b().sd(10, 14, CTRL as i32).build(),
+            WFI,
+            b().ld(15, 10, STATUS as i32).build(),
+            b().add(16, 12, 13).build(),
+            b().ld(16, 16, -8).build(),
+            b().addi(17, 0, 93).build(),
+            b().ecall().build(),
+        ];
+        let mut ctx = TestContext::with_config(&config).load_program(base, &program);
+        let bus = &mut ctx.sim.cpu.bus.bus;
+        for i in (0..SIZE).step_by(8) {
+            bus.write_u64(PhysAddr::new(src + i), pattern(i));
+        }
+        // PLIC: give source 4 a priority and enable it for hart 0's M-mode context.
+        let plic = 0x0c00_0000;
+        bus.write_u32(PhysAddr::new(plic + 4 * DMA_IRQ), 1);
+        bus.write_u32(PhysAddr::new(plic + 0x2000), 1 << DMA_IRQ);
+        ctx.sim.cpu.csrs.mie = csr::MIE_MEIP;
+        ctx.sim.write_reg(RegIdx::new(10), config.system.dma_base);
+        ctx.sim.write_reg(RegIdx::new(11), src);
+        ctx.sim.write_reg(RegIdx::new(12), dst);
+        ctx.sim.write_reg(RegIdx::new(13), SIZE);
+        ctx.sim.write_reg(RegIdx::new(14), CTRL_START | CTRL_IRQ_ENABLE);
+
+        let mut parked_cycles = 0;
+        let mut sim = ctx.run_to_exit_with(50_000, |sim| {
+            parked_cycles += u64::from(sim.cpu.wfi_waiting);
+        });
+        assert!(
+            parked_cycles >= SIZE / config.system.dma_bytes_per_cycle,
+            "{backend:?}: parked for only {parked_cycles} cycles"
+        );
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(15)), STATUS_DONE, "{backend:?}");
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(16)), pattern(SIZE - 8));
+        for i in (0..SIZE).step_by(8) {
+            let got = sim.cpu.bus.bus.read_u64(PhysAddr::new(dst + i));
+            assert_eq!(got, pattern(i), "{backend:?}: offset {i:#x}");
+        }
+        let dma_requests: u64 = sim.cpu.stats.mem_traffic.histogram().iter().sum();
+        assert!(
+            dma_requests >= 2 * SIZE / config.system.dma_bytes_per_cycle,
+            "{backend:?}: DMA traffic missing from the memory controller ({dma_requests})"
+        );
+    }
+}
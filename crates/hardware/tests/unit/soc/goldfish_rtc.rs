@@ -1,6 +1,7 @@
 //! Goldfish RTC unit tests.
 //!
-//! Verifies basic device identification for the Goldfish real-time clock.
+//! Verifies device identification, guest time setting and the one-shot alarm
+//! of the Goldfish real-time clock.
 
 use rvsim_core::soc::devices::Device;
 use rvsim_core::soc::devices::goldfish_rtc::GoldfishRtc;
@@ -31,3 +32,77 @@ fn goldfish_rtc_read_time_low_nonzero() {
     let time_ns = ((_time_high as u64) << 32) | (time_low as u64);
     assert!(time_ns > 0, "Time since epoch should be > 0");
 }
+
+// ══════════════════════════════════════════════════════════
+// Alarm
+// ══════════════════════════════════════════════════════════
+
+/// Guest time the tests start from (well ahead of the host clock).
+const T0: u64 = 0x4000_0000_0000_0000;
+
+/// One second in nanoseconds.
+const SECOND: u64 = 1_000_000_000;
+
+/// Sets the guest time through the high/low time registers.
+fn set_time(rtc: &mut GoldfishRtc, ns: u64) {
+    rtc.write_u32(0x04, (ns >> 32) as u32);
+    rtc.write_u32(0x00, ns as u32);
+}
+
+/// Programs the alarm through the high/low alarm registers.
+fn set_alarm(rtc: &mut GoldfishRtc, ns: u64) {
+    rtc.write_u32(0x0C, (ns >> 32) as u32);
+    rtc.write_u32(0x08, ns as u32);
+}
+
+#[test]
+fn goldfish_rtc_time_write_sets_guest_time() {
+    let mut rtc = GoldfishRtc::new(0);
+    set_time(&mut rtc, T0);
+    let low = rtc.read_u32(0x00);
+    let high = rtc.read_u32(0x04);
+    let time = (u64::from(high) << 32) | u64::from(low);
+    assert!((T0..T0 + SECOND).contains(&time), "{time:#x}");
+}
+
+#[test]
+fn goldfish_rtc_alarm_fires_once() {
+    let mut rtc = GoldfishRtc::new(0);
+    set_time(&mut rtc, T0);
+    rtc.write_u32(0x10, 1);
+    set_alarm(&mut rtc, T0 + 10 * SECOND);
+    assert_eq!(rtc.read_u32(0x18), 1, "alarm armed");
+    assert!(!rtc.tick(), "alarm must not fire early");
+
+    // Advance time past the alarm.
+    set_time(&mut rtc, T0 + 11 * SECOND);
+    assert!(rtc.tick(), "alarm must fire once time >= alarm");
+    assert_eq!(rtc.read_u32(0x18), 0, "fired alarm is disarmed");
+    assert!(rtc.tick(), "interrupt stays raised until acknowledged");
+
+    rtc.write_u32(0x1C, 1);
+    assert!(!rtc.tick(), "acknowledge clears the interrupt");
+    assert!(!rtc.tick(), "one-shot alarm does not fire again");
+}
+
+#[test]
+fn goldfish_rtc_alarm_respects_irq_enable() {
+    let mut rtc = GoldfishRtc::new(0);
+    set_time(&mut rtc, T0);
+    set_alarm(&mut rtc, T0);
+    assert!(!rtc.tick(), "disabled interrupt is not raised");
+    rtc.write_u32(0x10, 1);
+    assert!(rtc.tick(), "pending alarm is raised once enabled");
+}
+
+#[test]
+fn goldfish_rtc_clear_alarm_disarms() {
+    let mut rtc = GoldfishRtc::new(0);
+    set_time(&mut rtc, T0);
+    rtc.write_u32(0x10, 1);
+    set_alarm(&mut rtc, T0 + SECOND);
+    rtc.write_u32(0x14, 1);
+    assert_eq!(rtc.read_u32(0x18), 0);
+    set_time(&mut rtc, T0 + 2 * SECOND);
+    assert!(!rtc.tick(), "disarmed alarm must not fire");
+}
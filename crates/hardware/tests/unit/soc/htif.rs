@@ -1,3 +1,4 @@
+use rvsim_core::soc::devices::htif::{CMD_GETCHAR, CMD_PUTCHAR, DEV_CONSOLE, command};
 use rvsim_core::soc::devices::{Device, Htif};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
@@ -101,3 +102,46 @@ fn test_htif_write_u32() {
     htif.write_u32(0, 1);
     assert_eq!(exit_signal.load(Ordering::Relaxed), 0);
 }
+
+#[test]
+fn test_htif_putchar_responses_wait_for_fromhost_clear() {
+    let exit_signal = Arc::new(AtomicU64::new(0xff));
+    let mut htif = Htif::new(0x1000, exit_signal.clone());
+    let response = command(DEV_CONSOLE, CMD_PUTCHAR, 0);
+
+    htif.write_u64(0, command(DEV_CONSOLE, CMD_PUTCHAR, u64::from(b'a')));
+    htif.write_u64(0, command(DEV_CONSOLE, CMD_PUTCHAR, u64::from(b'b')));
+    assert_eq!(htif.take_output(), b"ab");
+    assert_eq!(htif.read_u64(8), response);
+    assert_eq!(htif.read_u64(0), 0);
+
+    htif.write_u64(8, 0);
+    assert_eq!(htif.fromhost(), response);
+    htif.write_u64(8, 0);
+    assert_eq!(htif.fromhost(), 0);
+    assert_eq!(exit_signal.load(Ordering::Relaxed), 0xff);
+}
+
+#[test]
+fn test_htif_getchar_waits_for_input() {
+    let exit_signal = Arc::new(AtomicU64::new(0xff));
+    let mut htif = Htif::new(0x1000, exit_signal);
+
+    htif.write_u64(0, command(DEV_CONSOLE, CMD_GETCHAR, 0));
+    assert!(!htif.tick());
+    assert_eq!(htif.fromhost(), 0);
+
+    htif.push_input(b"q");
+    htif.tick();
+    assert_eq!(htif.fromhost(), command(DEV_CONSOLE, CMD_GETCHAR, 0x100 | u64::from(b'q')));
+}
+
+#[test]
+fn test_htif_unknown_device_stored_raw() {
+    let exit_signal = Arc::new(AtomicU64::new(0xff));
+    let mut htif = Htif::new(0x1000, exit_signal.clone());
+
+    let val = command(7, 0, 1);
+    htif.write_u64(0, val);
+    assert_eq!(exit_signal.load(Ordering::Relaxed), val);
+}
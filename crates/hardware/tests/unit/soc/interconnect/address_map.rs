@@ -4,10 +4,12 @@
 //! transit time calculation, and valid address checks.
 
 use rvsim_core::common::PhysAddr;
+use rvsim_core::soc::devices::{Htif, Uart};
 use rvsim_core::soc::interconnect::Bus;
 use rvsim_core::soc::memory::Memory;
 use rvsim_core::soc::memory::buffer::DramBuffer;
 use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
 
 fn make_bus_with_ram(size: usize, base: u64) -> Bus {
     let mut bus = Bus::new(8, 1);
@@ -157,6 +159,34 @@ fn multiple_devices_routed_correctly() {
     assert_eq!(bus.read_u32(PhysAddr::new(0x2000)), 0xBBBB);
 }
 
+#[test]
+fn overlapping_device_is_rejected() {
+    let mut bus = make_bus_with_ram(4096, 0x8000_0000);
+    let err = bus.try_add_device(Box::new(Uart::new(0x8000_0800, false, true))).unwrap_err();
+    assert!(err.contains("overlaps DRAM"), "{err}");
+    assert_eq!(bus.devices().count(), 1, "rejected device is not registered");
+
+    let buf = Arc::new(DramBuffer::new(4096));
+    assert!(bus.try_add_device(Box::new(Memory::new(buf, 0x7FFF_F800))).is_err());
+    let buf = Arc::new(DramBuffer::new(4096));
+    assert!(bus.try_add_device(Box::new(Memory::new(buf, 0x8000_1000))).is_ok(), "adjacent");
+}
+
+#[test]
+#[should_panic(expected = "overlaps")]
+fn add_device_panics_on_overlap() {
+    let mut bus = make_bus_with_ram(4096, 0x8000_0000);
+    bus.add_device(Box::new(Uart::new(0x8000_0000, false, true)));
+}
+
+#[test]
+fn htif_may_sit_inside_ram() {
+    let mut bus = make_bus_with_ram(4096, 0x8000_0000);
+    let exit = Arc::new(AtomicU64::new(u64::MAX));
+    bus.try_add_device(Box::new(Htif::new(0x8000_0100, exit))).unwrap();
+    assert_eq!(bus.devices().count(), 2);
+}
+
 // ══════════════════════════════════════════════════════════
 // 7. RAM info
 // ══════════════════════════════════════════════════════════
@@ -171,6 +201,20 @@ fn get_ram_info_returns_some() {
     assert_eq!(end, 0x8000_0000 + 4096);
 }
 
+#[test]
+fn get_ram_info_returns_bank_covering_boot_address() {
+    let mut bus = make_bus_with_ram(4096, 0x4000_0000);
+    bus.add_device(Box::new(Memory::new(Arc::new(DramBuffer::new(4096)), 0x8000_0000)));
+    assert_eq!(bus.get_ram_info().map(|(_, base, _)| base), Some(0x4000_0000));
+
+    bus.set_boot_address(0x8000_0010);
+    let (_ptr, base, end) = bus.get_ram_info().unwrap();
+    assert_eq!((base, end), (0x8000_0000, 0x8000_1000));
+
+    bus.set_boot_address(0x9000_0000);
+    assert_eq!(bus.get_ram_info().map(|(_, base, _)| base), Some(0x4000_0000));
+}
+
 #[test]
 fn get_ram_info_none_when_no_ram() {
     let mut bus = Bus::new(8, 0);
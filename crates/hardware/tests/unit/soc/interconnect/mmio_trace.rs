@@ -0,0 +1,141 @@
+//! MMIO Trace Tests.
+//!
+//! Verifies that the bus reports device accesses to the MMIO trace and counts
+//! them per device, that RAM accesses are never traced or counted, and that a
+//! guest's UART writes reach the `general.mmio_trace` file and the hart's
+//! statistics stamped with its cycle and hart.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::common::PhysAddr;
+use rvsim_core::config::Config;
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::soc::devices::Uart;
+use rvsim_core::soc::interconnect::Bus;
+use rvsim_core::soc::memory::Memory;
+use rvsim_core::soc::memory::buffer::DramBuffer;
+use rvsim_core::soc::mmio_trace::{DeviceAccessStats, MmioAccess, MmioTraceSink};
+use std::sync::{Arc, Mutex};
+
+const RAM_BASE: u64 = 0x8000_0000;
+const UART_BASE: u64 = 0x1000_0000;
+
+/// Owned copy of an [`MmioAccess`].
+type Record = (u64, usize, u64, String, u64, u64, bool);
+
+/// Sink collecting every access into a shared list.
+struct Recorder(Arc<Mutex<Vec<Record>>>);
+
+impl MmioTraceSink for Recorder {
+    fn record(&mut self, a: &MmioAccess<'_>) {
+        self.0.lock().unwrap().push((
+            a.cycle,
+            a.hart,
+            a.paddr,
+            a.device.to_owned(),
+            a.size,
+            a.value,
+            a.is_write,
+        ));
+    }
+}
+
+fn make_bus() -> (Bus, Arc<Mutex<Vec<Record>>>) {
+    let mut bus = Bus::new(8, 1);
+    bus.add_device(Box::new(Memory::new(Arc::new(DramBuffer::new(4096)), RAM_BASE)));
+    bus.add_device(Box::new(Uart::new(UART_BASE, false, true)));
+    let log = Arc::new(Mutex::new(Vec::new()));
+    bus.set_mmio_trace(Some(Box::new(Recorder(Arc::clone(&log)))));
+    (bus, log)
+}
+
+#[test]
+fn uart_accesses_are_traced_and_ram_accesses_are_not() {
+    let (mut bus, log) = make_bus();
+    bus.set_access_context(42, 1);
+    bus.write_u64(PhysAddr::new(RAM_BASE + 8), 0xDEAD_BEEF);
+    bus.write_u8(PhysAddr::new(UART_BASE), b'A');
+    let _ = bus.read_u64(PhysAddr::new(RAM_BASE + 8));
+    let lsr = bus.read_u8(PhysAddr::new(UART_BASE + 5));
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        vec![
+            (42, 1, UART_BASE, "UART0".to_owned(), 1, u64::from(b'A'), true),
+            (42, 1, UART_BASE + 5, "UART0".to_owned(), 1, u64::from(lsr), false),
+        ]
+    );
+}
+
+#[test]
+fn device_accesses_are_counted_per_device() {
+    let (mut bus, _log) = make_bus();
+    bus.set_mmio_trace(None);
+    assert!(!bus.mmio_trace_enabled());
+    for &b in b"hi\n" {
+        bus.write_u8(PhysAddr::new(UART_BASE), b);
+    }
+    let _ = bus.read_u8(PhysAddr::new(UART_BASE + 5));
+    bus.write_u32(PhysAddr::new(RAM_BASE), 1);
+    assert!(bus.has_mmio_stats());
+
+    let stats = bus.take_mmio_stats();
+    assert_eq!(
+        stats.get("UART0"),
+        Some(&DeviceAccessStats { reads: 1, writes: 3, bytes_read: 1, bytes_written: 3 })
+    );
+    assert!(stats.get("DRAM").is_none(), "RAM accesses are not counted");
+    assert!(!bus.has_mmio_stats(), "taking the counts clears them");
+}
+
+#[test]
+fn guest_uart_writes_reach_trace_file_and_stats() {
+    let b = InstructionBuilder::new;
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let mut config = Config::default();
+        config.pipeline.backend = backend;
+        config.system.uart_quiet = true;
+        config.general.mmio_trace = Some(file.path().to_str().unwrap().to_owned());
+        let base = config.system.ram_base;
+
+        let program = [
+            b().lui(5, (UART_BASE >> 12) as i32).build(),
+            b().addi(6, 0, i32::from(b'H')).build(),
+            b().sb(5, 6, 0).build(),
+            b().auipc(7, 0).build(),
+            b().sw(7, 6, 0x100).build(),
+            b().addi(6, 0, i32::from(b'i')).build(),
+            b().sb(5, 6, 0).build(),
+            b().addi(17, 0, 93).build(),
+            b().ecall().build(),
+        ];
+        let sim =
+            TestContext::with_config(&config).load_program(base, &program).run_to_exit(10_000);
+
+        let uart = sim.cpu.stats.mmio.get("UART0").copied().unwrap_or_default();
+        assert_eq!(uart.writes, 2, "{backend:?}");
+        assert_eq!(uart.bytes_written, 2, "{backend:?}");
+        assert!(sim.cpu.stats.mmio.get("DRAM").is_none(), "{backend:?}");
+        let cycles = sim.cpu.stats.cycles;
+        drop(sim);
+
+        let text = std::fs::read_to_string(file.path()).unwrap();
+        let writes: Vec<Vec<&str>> = text
+            .lines()
+            .map(|l| l.split(' ').collect::<Vec<_>>())
+            .filter(|f| f[2] == "W")
+            .collect();
+        assert_eq!(writes.len(), 2, "{backend:?}: {text}");
+        for (fields, ch) in writes.iter().zip([b'H', b'i']) {
+            assert_eq!(fields[1], "0", "{backend:?}: hart");
+            assert_eq!(fields[3], format!("{UART_BASE:#x}"), "{backend:?}");
+            assert_eq!(fields[4], "UART0", "{backend:?}");
+            assert_eq!(fields[5], "1", "{backend:?}");
+            assert_eq!(fields[6], format!("{ch:#x}"), "{backend:?}");
+            let cycle: u64 = fields[0].parse().unwrap();
+            assert!(cycle > 0 && cycle <= cycles, "{backend:?}: cycle {cycle}");
+        }
+        assert!(!text.contains("DRAM"), "{backend:?}: {text}");
+    }
+}
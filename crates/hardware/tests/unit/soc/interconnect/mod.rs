@@ -1,2 +1,3 @@
 pub mod address_map;
 pub mod arbitration;
+pub mod mmio_trace;
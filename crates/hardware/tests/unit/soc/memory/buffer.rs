@@ -1,7 +1,8 @@
 //! DRAM Buffer Unit Tests.
 //!
 //! Verifies allocation, read/write at byte and slice level,
-//! indexing, and boundary checks.
+//! indexing, boundary checks, and the anonymous, file-backed and heap
+//! backings with their resident memory.
 
 use rvsim_core::soc::memory::buffer::DramBuffer;
 
@@ -133,3 +134,71 @@ fn buffer_overwrite_slice() {
     buf.write_slice(0, &[5, 6, 7, 8]);
     assert_eq!(buf.read_slice(0, 4), &[5, 6, 7, 8]);
 }
+
+// ══════════════════════════════════════════════════════════
+// 8. Backings and residency
+// ══════════════════════════════════════════════════════════
+
+/// A sparse buffer much larger than the pages the test touches.
+const SPARSE_SIZE: usize = 4 << 30;
+/// Far-apart offsets touched in the sparse buffer.
+const SPARSE_OFFSETS: [usize; 2] = [0x1000, SPARSE_SIZE - 0x2000];
+
+#[test]
+fn anonymous_sparse_buffer_stays_mostly_non_resident() {
+    let buf = DramBuffer::new(SPARSE_SIZE);
+    for (i, &off) in SPARSE_OFFSETS.iter().enumerate() {
+        buf.write_slice(off, &[0xA5, i as u8]);
+    }
+    for (i, &off) in SPARSE_OFFSETS.iter().enumerate() {
+        assert_eq!(buf.read_slice(off, 2), &[0xA5, i as u8]);
+    }
+    assert_eq!(buf.read_u8(SPARSE_SIZE / 2), 0);
+    let resident = buf.resident_bytes();
+    assert!(resident >= 2 * 4096 || cfg!(miri), "touched pages are resident: {resident}");
+    assert!(resident < 16 << 20 || cfg!(miri), "4 GiB buffer holds {resident} resident bytes");
+}
+
+#[cfg(all(unix, not(miri)))]
+#[test]
+fn file_backed_sparse_buffer_writes_through_to_the_file() {
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let buf = DramBuffer::from_file(file.path(), 0, SPARSE_SIZE).unwrap();
+    assert_eq!(buf.len(), SPARSE_SIZE);
+    for (i, &off) in SPARSE_OFFSETS.iter().enumerate() {
+        buf.write_slice(off, &[0x5A, i as u8]);
+    }
+    for (i, &off) in SPARSE_OFFSETS.iter().enumerate() {
+        assert_eq!(buf.read_slice(off, 2), &[0x5A, i as u8]);
+    }
+    let resident = buf.resident_bytes();
+    assert!(resident < 16 << 20, "4 GiB mapping holds {resident} resident bytes");
+    drop(buf);
+
+    let f = std::fs::File::open(file.path()).unwrap();
+    assert_eq!(f.metadata().unwrap().len(), SPARSE_SIZE as u64);
+    let mut bytes = [0u8; 2];
+    std::os::unix::fs::FileExt::read_exact_at(&f, &mut bytes, SPARSE_OFFSETS[1] as u64).unwrap();
+    assert_eq!(bytes, [0x5A, 1]);
+}
+
+#[cfg(all(unix, not(miri)))]
+#[test]
+fn file_backed_buffer_maps_existing_contents_at_offset() {
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let mut data = vec![0u8; 0x2_0000];
+    data[0x1_0010] = 0x77;
+    std::fs::write(file.path(), &data).unwrap();
+    let buf = DramBuffer::from_file(file.path(), 0x1_0000, 0x1000).unwrap();
+    assert_eq!(buf.read_u8(0x10), 0x77);
+    // The file is not truncated to the mapped window.
+    assert_eq!(std::fs::metadata(file.path()).unwrap().len(), 0x2_0000);
+}
+
+#[test]
+fn heap_buffer_is_wholly_resident() {
+    let buf = DramBuffer::heap(8192);
+    buf.write_u8(8191, 0x42);
+    assert_eq!(buf.read_u8(8191), 0x42);
+    assert_eq!(buf.resident_bytes(), 8192);
+}
@@ -3,22 +3,30 @@
 //! Verifies SimpleController (fixed latency) and DramController
 //! (multi-bank, row-buffer-aware, refresh-capable DRAM timing).
 
+use rvsim_core::config::DramScheduler;
 use rvsim_core::soc::memory::controller::{
     DramConfig, DramController, MemoryController, SimpleController,
 };
+use rvsim_core::soc::memory::traffic::{BANDWIDTH_WINDOW_CYCLES, MemoryTraffic, RowBufferStats};
 
 /// Helper: create a DramController with refresh disabled for simpler timing tests.
-/// 8 banks, 2048-byte rows, t_cas=5, t_ras=10, t_pre=8, t_rrd=4.
-fn dram_no_refresh(t_cas: u64, t_ras: u64, t_pre: u64) -> DramController {
+/// 1 channel, 8 banks, 2048-byte rows, t_cas=5, t_rcd=t_ras=10, t_pre=8, t_rrd=4.
+fn dram_no_refresh(t_cas: u64, t_rcd: u64, t_pre: u64) -> DramController {
     DramController::new(DramConfig {
         t_cas,
-        t_ras,
+        t_rcd,
+        t_ras: t_rcd,
         t_pre,
         t_rrd: 4,
+        num_channels: 1,
         num_banks: 8,
+        num_bank_groups: 1,
         row_size_bytes: 2048,
         t_refi: 0,
         t_rfc: 0,
+        scheduler: DramScheduler::Immediate,
+        starvation_limit: 0,
+        write_drain_threshold: 0,
     })
 }
 
@@ -69,7 +77,7 @@ fn simple_controller_address_independent() {
 #[test]
 fn dram_cold_start_latency() {
     let mut ctrl = dram_default();
-    // First access ever: t_ras + t_cas = 10 + 5 = 15
+    // First access ever: t_rcd + t_cas = 10 + 5 = 15
     assert_eq!(ctrl.access_latency(addr(0, 0), 0), 15);
 }
 
@@ -105,7 +113,7 @@ fn dram_row_buffer_miss_same_bank() {
     let mut ctrl = dram_default();
     // Open row 0 in bank 0.
     ctrl.access_latency(addr(0, 0), 0);
-    // Access a different row in the SAME bank → pre + ras + cas = 8 + 10 + 5 = 23
+    // Access a different row in the SAME bank → pre + rcd + cas = 8 + 10 + 5 = 23
     assert_eq!(ctrl.access_latency(addr(0, 1), 100), 23);
 }
 
@@ -169,9 +177,9 @@ fn dram_row_boundary_exact() {
 #[test]
 fn dram_low_latency() {
     let mut ctrl = dram_no_refresh(1, 2, 1);
-    assert_eq!(ctrl.access_latency(addr(0, 0), 0), 3); // cold: ras+cas
+    assert_eq!(ctrl.access_latency(addr(0, 0), 0), 3); // cold: rcd+cas
     assert_eq!(ctrl.access_latency(addr(0, 0), 10), 1); // hit: cas
-    assert_eq!(ctrl.access_latency(addr(0, 1), 100), 4); // miss: pre+ras+cas
+    assert_eq!(ctrl.access_latency(addr(0, 1), 100), 4); // conflict: pre+rcd+cas
 }
 
 #[test]
@@ -191,13 +199,19 @@ fn dram_custom_row_size_4k() {
     // 4 KiB rows, 4 banks, no refresh.
     let mut ctrl = DramController::new(DramConfig {
         t_cas: 5,
+        t_rcd: 10,
         t_ras: 10,
         t_pre: 8,
         t_rrd: 4,
+        num_channels: 1,
         num_banks: 4,
+        num_bank_groups: 1,
         row_size_bytes: 4096,
         t_refi: 0,
         t_rfc: 0,
+        scheduler: DramScheduler::Immediate,
+        starvation_limit: 0,
+        write_drain_threshold: 0,
     });
     // row_shift = 12, bank = (addr >> 12) % 4
     // bank 0, row 0: [0x0000, 0x0FFF]
@@ -216,13 +230,19 @@ fn dram_refresh_causes_latency_spike() {
     // t_refi=100, t_rfc=20 for easy testing. 2 banks.
     let mut ctrl = DramController::new(DramConfig {
         t_cas: 5,
+        t_rcd: 10,
         t_ras: 10,
         t_pre: 8,
         t_rrd: 4,
+        num_channels: 1,
         num_banks: 2,
+        num_bank_groups: 1,
         row_size_bytes: 2048,
         t_refi: 100,
         t_rfc: 20,
+        scheduler: DramScheduler::Immediate,
+        starvation_limit: 0,
+        write_drain_threshold: 0,
     });
 
     // Open a row before refresh.
@@ -233,7 +253,7 @@ fn dram_refresh_causes_latency_spike() {
     // At cycle 100, refresh fires. All banks busy until cycle 120.
     // Access at cycle 100 should pay refresh wait + cold start (rows are closed).
     let lat = ctrl.access_latency(addr(0, 0), 100);
-    // After refresh: effective_cycle=120, then cold start = ras+cas = 15
+    // After refresh: effective_cycle=120, then cold start = rcd+cas = 15
     // Total = (120 - 100) + 10 + 5 = 35
     assert!(lat > 5, "refresh should add extra latency, got {}", lat);
 }
@@ -243,13 +263,19 @@ fn dram_refresh_periodic() {
     // Verify refresh fires periodically.
     let mut ctrl = DramController::new(DramConfig {
         t_cas: 5,
+        t_rcd: 10,
         t_ras: 10,
         t_pre: 8,
         t_rrd: 4,
+        num_channels: 1,
         num_banks: 2,
+        num_bank_groups: 1,
         row_size_bytes: 2048,
         t_refi: 100,
         t_rfc: 20,
+        scheduler: DramScheduler::Immediate,
+        starvation_limit: 0,
+        write_drain_threshold: 0,
     });
 
     // Before first refresh: row hit.
@@ -274,13 +300,19 @@ fn dram_no_refresh_when_disabled() {
     // t_refi=0 disables refresh.
     let mut ctrl = DramController::new(DramConfig {
         t_cas: 5,
+        t_rcd: 10,
         t_ras: 10,
         t_pre: 8,
         t_rrd: 4,
+        num_channels: 1,
         num_banks: 2,
+        num_bank_groups: 1,
         row_size_bytes: 2048,
         t_refi: 0,
         t_rfc: 350,
+        scheduler: DramScheduler::Immediate,
+        starvation_limit: 0,
+        write_drain_threshold: 0,
     });
 
     ctrl.access_latency(addr(0, 0), 0);
@@ -298,11 +330,11 @@ fn dram_trrd_back_to_back_activations() {
     let mut ctrl = dram_default();
     // First activation at cycle 0 → bank 0.
     let lat1 = ctrl.access_latency(addr(0, 0), 0);
-    assert_eq!(lat1, 15); // cold start: ras+cas
+    assert_eq!(lat1, 15); // cold start: rcd+cas
 
     // Immediate second activation at cycle 0 to bank 1.
     // tRRD forces waiting: earliest activate = 0 + 4 = 4.
-    // latency = (4 - 0) + ras + cas = 4 + 10 + 5 = 19
+    // latency = (4 - 0) + rcd + cas = 4 + 10 + 5 = 19
     let lat2 = ctrl.access_latency(addr(1, 0), 0);
     assert_eq!(lat2, 19, "tRRD should delay the second bank activation");
 }
@@ -317,3 +349,276 @@ fn dram_trrd_not_applied_to_row_hits() {
     assert_eq!(ctrl.access_latency(addr(0, 0) + 8, 200), 5);
     assert_eq!(ctrl.access_latency(addr(1, 0) + 8, 200), 5);
 }
+
+// ══════════════════════════════════════════════════════════
+// 11. Row-buffer timing: tRCD vs tRAS, channels, bank groups
+// ══════════════════════════════════════════════════════════
+
+/// Helper: distinct timings (t_cas=5, t_rcd=12, t_ras=20, t_pre=9) so every
+/// term is visible in the latencies; refresh disabled.
+fn dram_timed(num_channels: usize, num_bank_groups: usize) -> DramController {
+    DramController::new(DramConfig {
+        t_cas: 5,
+        t_rcd: 12,
+        t_ras: 20,
+        t_pre: 9,
+        t_rrd: 4,
+        num_channels,
+        num_banks: 8,
+        num_bank_groups,
+        row_size_bytes: 2048,
+        t_refi: 0,
+        t_rfc: 0,
+        scheduler: DramScheduler::Immediate,
+        starvation_limit: 0,
+        write_drain_threshold: 0,
+    })
+}
+
+#[test]
+fn dram_sequential_accesses_in_one_row_pay_only_cas() {
+    let mut ctrl = dram_timed(1, 1);
+    // Cold: t_rcd + t_cas.
+    assert_eq!(ctrl.access_latency(0, 0), 17);
+    for line in 1..32 {
+        assert_eq!(ctrl.access_latency(line * 64, line * 20), 5, "line {line}");
+    }
+}
+
+#[test]
+fn dram_ping_pong_between_rows_of_one_bank_pays_precharge_and_activate() {
+    let mut ctrl = dram_timed(1, 1);
+    ctrl.access_latency(addr(0, 0), 0);
+    for i in 1..10 {
+        let row = i % 2;
+        // Conflict: t_pre + t_rcd + t_cas = 9 + 12 + 5.
+        assert_eq!(ctrl.access_latency(addr(0, row), i * 100), 26, "access {i}");
+    }
+}
+
+#[test]
+fn dram_conflict_waits_for_tras() {
+    let mut ctrl = dram_timed(1, 1);
+    ctrl.access_latency(addr(0, 0), 0);
+    // The row opened at cycle 0 may not close before t_ras = 20:
+    // precharge 20..29, then t_rcd + t_cas → done at 46.
+    assert_eq!(ctrl.access_latency(addr(0, 1), 18), 46 - 18);
+}
+
+#[test]
+fn dram_channels_interleave_consecutive_rows() {
+    let mut ctrl = dram_timed(2, 1);
+    // Rows 0 and 1 go to different channels, so tRRD does not apply and
+    // both stay open.
+    assert_eq!(ctrl.access_latency(0, 0), 17);
+    assert_eq!(ctrl.access_latency(2048, 0), 17);
+    assert_eq!(ctrl.access_latency(64, 100), 5);
+    assert_eq!(ctrl.access_latency(2048 + 64, 100), 5);
+}
+
+#[test]
+fn request_records_row_buffer_outcomes_per_bank_group() {
+    let mut ctrl = dram_timed(1, 2);
+    let mut traffic = MemoryTraffic::default();
+    // Bank 0 is in group 0, bank 1 in group 1.
+    for (a, cycle) in
+        [(addr(0, 0), 0), (addr(0, 0) + 64, 100), (addr(1, 0), 200), (addr(0, 1), 300)]
+    {
+        let _ = ctrl.request(a, 64, cycle, &mut traffic);
+    }
+    assert_eq!(
+        traffic.row_buffer(),
+        [
+            RowBufferStats { channel: 0, bank_group: 0, hits: 1, misses: 1, conflicts: 1 },
+            RowBufferStats { channel: 0, bank_group: 1, hits: 0, misses: 1, conflicts: 0 },
+        ]
+    );
+    assert!((traffic.row_buffer()[0].hit_rate() - 1.0 / 3.0).abs() < f64::EPSILON);
+}
+
+// ══════════════════════════════════════════════════════════
+// 12. Request queue: FR-FCFS, starvation, read priority
+// ══════════════════════════════════════════════════════════
+
+/// Helper: `dram_timed(1, 1)` timings with a request queue.
+fn dram_queued(
+    scheduler: DramScheduler,
+    starvation_limit: u64,
+    write_drain_threshold: usize,
+) -> DramController {
+    DramController::new(DramConfig {
+        t_cas: 5,
+        t_rcd: 12,
+        t_ras: 20,
+        t_pre: 9,
+        t_rrd: 4,
+        num_channels: 1,
+        num_banks: 8,
+        num_bank_groups: 1,
+        row_size_bytes: 2048,
+        t_refi: 0,
+        t_rfc: 0,
+        scheduler,
+        starvation_limit,
+        write_drain_threshold,
+    })
+}
+
+/// Enqueues each `(cycle, addr, is_write)` request at its cycle, ticking the
+/// controller every cycle until all complete, and returns their completion
+/// cycles in request order.
+fn run_queue(
+    ctrl: &mut DramController,
+    traffic: &mut MemoryTraffic,
+    requests: &[(u64, u64, bool)],
+) -> Vec<u64> {
+    let mut ids = Vec::new();
+    let mut done = vec![None; requests.len()];
+    for cycle in 0..10_000 {
+        for &(at, a, is_write) in requests {
+            if at == cycle {
+                ids.push(ctrl.enqueue(a, 64, is_write, cycle).unwrap());
+            }
+        }
+        ctrl.tick(cycle, traffic);
+        for (i, &id) in ids.iter().enumerate() {
+            if done[i].is_none() {
+                done[i] = ctrl.take_completion(id);
+            }
+        }
+        if done.iter().all(Option::is_some) {
+            break;
+        }
+    }
+    done.into_iter().map(Option::unwrap).collect()
+}
+
+#[test]
+fn immediate_scheduler_does_not_queue() {
+    let mut ctrl = dram_default();
+    assert_eq!(ctrl.enqueue(0, 64, false, 0), None);
+}
+
+#[test]
+fn fr_fcfs_row_hit_bypasses_older_row_miss() {
+    // Row 0 of bank 0 opens first; a conflicting request then arrives one
+    // cycle before a hit to the open row.
+    let requests = [(0, addr(0, 0), false), (1, addr(0, 1), false), (2, addr(0, 0) + 64, false)];
+
+    let mut ctrl = dram_queued(DramScheduler::FrFcfs, 0, 0);
+    let done = run_queue(&mut ctrl, &mut MemoryTraffic::default(), &requests);
+    assert_eq!(done[0], 17);
+    assert!(done[2] < done[1], "the hit issues ahead of the older miss");
+
+    let mut ctrl = dram_queued(DramScheduler::Fcfs, 0, 0);
+    let done = run_queue(&mut ctrl, &mut MemoryTraffic::default(), &requests);
+    // The miss waits for tRAS, precharges 20..29, then t_rcd + t_cas.
+    assert_eq!(done[1], 46);
+    assert!(done[1] < done[2], "FCFS serves requests in arrival order");
+}
+
+#[test]
+fn fr_fcfs_starvation_limit_bounds_bypassing() {
+    // A conflicting request followed by a steady stream of row hits.
+    let mut requests = vec![(0, addr(0, 0), false), (1, addr(0, 1), false)];
+    requests.extend((0..40).map(|i| (2 + i, addr(0, 0) + 64 * (i % 32), false)));
+
+    let mut ctrl = dram_queued(DramScheduler::FrFcfs, 0, 0);
+    let done = run_queue(&mut ctrl, &mut MemoryTraffic::default(), &requests);
+    assert!(done[1] > done[2..].iter().copied().max().unwrap(), "miss starves behind hits");
+
+    let mut ctrl = dram_queued(DramScheduler::FrFcfs, 50, 0);
+    let done = run_queue(&mut ctrl, &mut MemoryTraffic::default(), &requests);
+    assert!(done[1] < done[2..].iter().copied().max().unwrap());
+    assert!(done[1] <= 1 + 50 + 5 + 26, "miss issues once it has waited 50 cycles");
+}
+
+#[test]
+fn reads_bypass_writes_until_drain_threshold() {
+    // Three different banks so every request is ready at once.
+    let requests = [(0, addr(1, 0), true), (0, addr(2, 0), true), (0, addr(3, 0), false)];
+
+    let mut ctrl = dram_queued(DramScheduler::FrFcfs, 0, 4);
+    let done = run_queue(&mut ctrl, &mut MemoryTraffic::default(), &requests);
+    assert!(done[2] < done[0] && done[2] < done[1], "read issues first");
+
+    let mut ctrl = dram_queued(DramScheduler::FrFcfs, 0, 2);
+    let done = run_queue(&mut ctrl, &mut MemoryTraffic::default(), &requests);
+    assert!(done[0] < done[2] && done[1] < done[2], "writes drain once two are queued");
+}
+
+#[test]
+fn queue_records_occupancy_and_read_latency() {
+    let mut ctrl = dram_queued(DramScheduler::FrFcfs, 0, 0);
+    let mut traffic = MemoryTraffic::default();
+    let requests = [(0, addr(0, 0), false), (0, addr(1, 0), false), (0, addr(2, 0), true)];
+    let done = run_queue(&mut ctrl, &mut traffic, &requests);
+    // One issue per cycle, activations spaced by tRRD.
+    assert_eq!(done, [17, 21, 25]);
+    assert_eq!(traffic.requests(), 3);
+    assert_eq!(traffic.reads(), 2);
+    assert!((traffic.avg_read_latency() - 19.0).abs() < f64::EPSILON);
+    assert_eq!(traffic.percentile_read_latency(100.0), 21);
+    // Occupancy sampled at cycles 0, 1, 2: 3, 2, 1.
+    assert!((traffic.avg_queue_occupancy() - 2.0).abs() < f64::EPSILON);
+}
+
+// ══════════════════════════════════════════════════════════
+// 13. Traffic recording: latency histogram and bandwidth
+// ══════════════════════════════════════════════════════════
+
+#[test]
+fn request_records_latency_and_bytes() {
+    let mut ctrl = dram_default();
+    let mut traffic = MemoryTraffic::default();
+    // cold (15) + hit (5) + hit (5) + row miss (23)
+    let lats = [
+        ctrl.request(addr(0, 0), 64, 0, &mut traffic),
+        ctrl.request(addr(0, 0) + 64, 64, 100, &mut traffic),
+        ctrl.request(addr(0, 0) + 128, 64, 200, &mut traffic),
+        ctrl.request(addr(0, 1), 64, 300, &mut traffic),
+    ];
+    assert_eq!(lats, [15, 5, 5, 23]);
+    assert_eq!(traffic.requests(), 4);
+    assert_eq!(traffic.bytes(), 256);
+    assert!((traffic.avg_latency() - 12.0).abs() < f64::EPSILON);
+    assert_eq!(traffic.max_latency(), 23);
+    assert_eq!(traffic.histogram()[5], 2);
+}
+
+#[test]
+fn traffic_p99_latency_reports_tail() {
+    let mut ctrl = SimpleController::new(10);
+    let mut traffic = MemoryTraffic::default();
+    for i in 0..99 {
+        let _ = ctrl.request(i * 64, 64, i, &mut traffic);
+    }
+    traffic.record(200, 400, 64);
+    assert_eq!(traffic.p99_latency(), 10);
+    assert_eq!(traffic.percentile_latency(100.0), 400);
+}
+
+#[test]
+fn traffic_peak_bandwidth_uses_busiest_window() {
+    let mut traffic = MemoryTraffic::default();
+    // Window 0: 2 lines; window 1: 4 lines; window 2: 1 line.
+    for _ in 0..2 {
+        traffic.record(10, 10, 64);
+    }
+    for _ in 0..4 {
+        traffic.record(BANDWIDTH_WINDOW_CYCLES + 10, 10, 64);
+    }
+    traffic.record(2 * BANDWIDTH_WINDOW_CYCLES + 10, 10, 64);
+    let expected = (4 * 64) as f64 / BANDWIDTH_WINDOW_CYCLES as f64;
+    assert!((traffic.peak_bandwidth() - expected).abs() < f64::EPSILON);
+    assert_eq!(traffic.bytes(), 7 * 64);
+}
+
+#[test]
+fn traffic_empty_reports_zero() {
+    let traffic = MemoryTraffic::default();
+    assert_eq!(traffic.requests(), 0);
+    assert_eq!(traffic.p99_latency(), 0);
+    assert!(traffic.avg_latency().abs() < f64::EPSILON);
+    assert!(traffic.peak_bandwidth().abs() < f64::EPSILON);
+}
@@ -1,2 +1,4 @@
 pub mod buffer;
 pub mod controller;
+pub mod regions;
+pub mod request_queue;
@@ -0,0 +1,226 @@
+//! Multiple RAM Bank Tests.
+//!
+//! Maps two RAM banks with an unmapped hole between them. Loads and stores
+//! to either bank go through the RAM fast path, while a supervisor load from
+//! the hole raises a load access fault (machine-mode accesses to unmapped
+//! addresses read as zero, as for any other unbacked address). A store that
+//! runs past the end of a bank raises a store access fault in every mode and
+//! leaves the bank untouched. With file-backed RAM both banks are windows of
+//! one file, and fast-path stores land in it.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::Simulator;
+use rvsim_core::common::{PhysAddr, RegIdx};
+use rvsim_core::config::{Config, MemoryRegion, MisalignedPolicy, RamBacking};
+use rvsim_core::core::arch::mode::PrivilegeMode;
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::isa::privileged::cause::exception;
+use rvsim_core::soc::System;
+
+/// Size of each bank.
+const BANK_SIZE: usize = 0x10_0000;
+
+/// Offset of the second bank from the first; the hole lies between them.
+const BANK1_OFFSET: u64 = 0x20_0000;
+
+/// Offset of the trap handler from the start of the first bank.
+const HANDLER_OFFSET: u64 = 0x800;
+
+fn two_bank_config(backend: BackendType) -> Config {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    let base = config.system.ram_base;
+    config.memory.regions = vec![
+        MemoryRegion { base, size: BANK_SIZE },
+        MemoryRegion { base: base + BANK1_OFFSET, size: BANK_SIZE },
+    ];
+    config
+}
+
+#[test]
+fn accesses_in_both_banks_succeed() {
+    let b = InstructionBuilder::new;
+    let program = [
+        b().sd(12, 6, 0x100).build(),
+        b().sd(13, 6, 0x100).build(),
+        b().ld(7, 12, 0x100).build(),
+        b().ld(8, 13, 0x100).build(),
+        b().addi(17, 0, 93).build(),
+        b().ecall().build(),
+    ];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let config = two_bank_config(backend);
+        let base = config.system.ram_base;
+        let mut ctx = TestContext::with_config(&config).load_program(base, &program);
+        ctx.sim.write_reg(RegIdx::new(6), 0x1234_5678_9abc_def0);
+        ctx.sim.write_reg(RegIdx::new(12), base);
+        ctx.sim.write_reg(RegIdx::new(13), base + BANK1_OFFSET);
+        let mut sim = ctx.run_to_exit(10_000);
+        assert_eq!(sim.cpu.exit_code, Some(0), "{backend:?}");
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(7)), 0x1234_5678_9abc_def0, "{backend:?}");
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(8)), 0x1234_5678_9abc_def0, "{backend:?}");
+        let bank1 = PhysAddr::new(base + BANK1_OFFSET + 0x100);
+        assert_eq!(sim.cpu.bus.bus.read_u64(bank1), 0x1234_5678_9abc_def0, "{backend:?}");
+    }
+}
+
+#[test]
+fn file_backed_banks_store_into_the_ram_file() {
+    let b = InstructionBuilder::new;
+    let program = [
+        b().sd(12, 6, 0x100).build(),
+        b().sd(13, 6, 0x100).build(),
+        b().ld(7, 13, 0x100).build(),
+        b().addi(17, 0, 93).build(),
+        b().ecall().build(),
+    ];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let mut config = two_bank_config(backend);
+        config.memory.ram_backing = RamBacking::File;
+        config.memory.ram_file = Some(file.path().to_string_lossy().into_owned());
+        let base = config.system.ram_base;
+        let mut ctx = TestContext::with_config(&config).load_program(base, &program);
+        ctx.sim.write_reg(RegIdx::new(6), 0x1234_5678_9abc_def0);
+        ctx.sim.write_reg(RegIdx::new(12), base);
+        ctx.sim.write_reg(RegIdx::new(13), base + BANK1_OFFSET);
+        let sim = ctx.run_to_exit(10_000);
+        assert_eq!(sim.cpu.exit_code, Some(0), "{backend:?}");
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(7)), 0x1234_5678_9abc_def0, "{backend:?}");
+        assert!(sim.cpu.ram.resident_bytes() > 0, "{backend:?}");
+        drop(sim);
+
+        // The second bank follows the first in the file.
+        let data = std::fs::read(file.path()).unwrap();
+        assert_eq!(data.len(), 2 * BANK_SIZE, "{backend:?}");
+        for offset in [0x100, BANK_SIZE + 0x100] {
+            let word = u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+            assert_eq!(word, 0x1234_5678_9abc_def0, "{backend:?}: file offset {offset:#x}");
+        }
+    }
+}
+
+#[test]
+fn boot_bank_is_the_one_holding_ram_base() {
+    let mut config = Config::default();
+    let base = config.system.ram_base;
+    config.memory.regions = vec![
+        MemoryRegion { base: base - BANK1_OFFSET, size: BANK_SIZE },
+        MemoryRegion { base, size: BANK_SIZE },
+    ];
+    let mut system = System::new(&config, "");
+    let (_ptr, start, end) = system.bus.get_ram_info().unwrap();
+    assert_eq!((start, end), (base, base + BANK_SIZE as u64));
+}
+
+#[test]
+fn file_backing_without_a_file_is_rejected() {
+    let mut config = Config::default();
+    config.memory.ram_backing = RamBacking::File;
+    let err = System::try_new(&config, "").unwrap_err();
+    assert!(err.contains("ram_file"), "{err}");
+}
+
+#[test]
+fn access_in_gap_raises_access_fault() {
+    let b = InstructionBuilder::new;
+    let program = [
+        (0x00, b().ld(7, 10, 0).build()),
+        (0x04, b().jal(0, 0).build()),
+        (HANDLER_OFFSET, b().jal(0, 0).build()),
+    ];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let config = two_bank_config(backend);
+        let base = config.system.ram_base;
+        let gap = base + BANK_SIZE as u64 + 0x40;
+        let mut sim = Simulator::new(System::new(&config, ""), &config);
+        for (offset, inst) in program {
+            sim.cpu.bus.bus.write_u32(PhysAddr::new(base + offset), inst);
+        }
+        sim.cpu.pc = base;
+        sim.cpu.direct_mode = false;
+        sim.cpu.privilege = PrivilegeMode::Supervisor;
+        // One NAPOT region covering the whole address space, RWX.
+        sim.cpu.pmp.set_addr(0, u64::MAX >> 10);
+        sim.cpu.pmp.set_cfg(0, 0x1F);
+        sim.cpu.csrs.mtvec = base + HANDLER_OFFSET;
+        sim.write_reg(RegIdx::new(10), gap);
+        for _ in 0..1_000 {
+            sim.tick().unwrap();
+        }
+        assert_eq!(sim.cpu.csrs.mcause, exception::LOAD_ACCESS_FAULT, "{backend:?}");
+        assert_eq!(sim.cpu.csrs.mepc, base, "{backend:?}");
+        assert_eq!(sim.cpu.csrs.mtval, gap, "{backend:?}");
+    }
+}
+
+#[test]
+fn store_past_bank_end_raises_access_fault() {
+    let b = InstructionBuilder::new;
+    let program = [
+        (0x00, b().sw(11, 6, 0).build()),
+        (0x04, b().jal(0, 0).build()),
+        (HANDLER_OFFSET, b().jal(0, 0).build()),
+    ];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut config = two_bank_config(backend);
+        config.memory.misaligned = MisalignedPolicy::Emulate;
+        let base = config.system.ram_base;
+        let bank_end = base + BANK_SIZE as u64;
+        // Machine mode: the word starts in the bank and ends two bytes past it.
+        let straddle = bank_end - 2;
+        let mut sim = Simulator::new(System::new(&config, ""), &config);
+        for (offset, inst) in program {
+            sim.cpu.bus.bus.write_u32(PhysAddr::new(base + offset), inst);
+        }
+        sim.cpu.pc = base;
+        sim.cpu.direct_mode = false;
+        sim.cpu.csrs.mtvec = base + HANDLER_OFFSET;
+        sim.write_reg(RegIdx::new(6), 0xdead_beef);
+        sim.write_reg(RegIdx::new(11), straddle);
+        for _ in 0..1_000 {
+            sim.tick().unwrap();
+        }
+        assert_eq!(sim.cpu.csrs.mcause, exception::STORE_ACCESS_FAULT, "{backend:?}");
+        assert_eq!(sim.cpu.csrs.mepc, base, "{backend:?}");
+        assert_eq!(sim.cpu.csrs.mtval, straddle, "{backend:?}");
+        assert_eq!(sim.cpu.bus.bus.read_u16(PhysAddr::new(straddle)), 0, "{backend:?}");
+    }
+}
+
+#[test]
+fn supervisor_store_just_past_ram_end_raises_access_fault() {
+    let b = InstructionBuilder::new;
+    let program = [
+        (0x00, b().sd(11, 6, 0).build()),
+        (0x04, b().jal(0, 0).build()),
+        (HANDLER_OFFSET, b().jal(0, 0).build()),
+    ];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let config = two_bank_config(backend);
+        let base = config.system.ram_base;
+        let ram_end = base + BANK1_OFFSET + BANK_SIZE as u64;
+        let mut sim = Simulator::new(System::new(&config, ""), &config);
+        for (offset, inst) in program {
+            sim.cpu.bus.bus.write_u32(PhysAddr::new(base + offset), inst);
+        }
+        sim.cpu.pc = base;
+        sim.cpu.direct_mode = false;
+        sim.cpu.privilege = PrivilegeMode::Supervisor;
+        // One NAPOT region covering the whole address space, RWX.
+        sim.cpu.pmp.set_addr(0, u64::MAX >> 10);
+        sim.cpu.pmp.set_cfg(0, 0x1F);
+        sim.cpu.csrs.mtvec = base + HANDLER_OFFSET;
+        sim.write_reg(RegIdx::new(6), u64::MAX);
+        sim.write_reg(RegIdx::new(11), ram_end);
+        for _ in 0..1_000 {
+            sim.tick().unwrap();
+        }
+        assert_eq!(sim.cpu.csrs.mcause, exception::STORE_ACCESS_FAULT, "{backend:?}");
+        assert_eq!(sim.cpu.csrs.mepc, base, "{backend:?}");
+        assert_eq!(sim.cpu.csrs.mtval, ram_end, "{backend:?}");
+        let last = PhysAddr::new(ram_end - 8);
+        assert_eq!(sim.cpu.bus.bus.read_u64(last), 0, "{backend:?}");
+    }
+}
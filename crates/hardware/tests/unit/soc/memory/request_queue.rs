@@ -0,0 +1,104 @@
+//! DRAM Request Queue Tests.
+//!
+//! Runs programs on both backends with the DRAM controller queueing
+//! requests under FR-FCFS: L1D misses wait in their MSHRs until the
+//! controller issues them, and a DMA copy only completes once every queued
+//! request it made has been served.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::common::{PhysAddr, RegIdx};
+use rvsim_core::config::{Config, DramScheduler, MemoryController};
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::soc::devices::dma::{CTRL_START, STATUS_DONE};
+
+/// Offset from the start of RAM of the data area.
+const DATA: u64 = 0x10_0000;
+/// Number of double words summed, one per 4 KiB so each lands in a new row.
+const LOADS: u64 = 64;
+const STRIDE: u64 = 0x1000;
+
+fn queued_config(backend: BackendType) -> Config {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.memory.controller = MemoryController::Dram;
+    config.memory.scheduler = DramScheduler::FrFcfs;
+    config.cache.l1_d.enabled = true;
+    config.cache.l1_d.mshr_count = 8;
+    config
+}
+
+#[test]
+fn strided_loads_complete_through_the_queue() {
+    let b = InstructionBuilder::new;
+    // x10 = cursor, x11 = sum, x12 = remaining.
+    let program = [
+        b().ld(13, 10, 0).build(),
+        b().add(11, 11, 13).build(),
+        b().lui(14, 1).build(),
+        b().add(10, 10, 14).build(),
+        b().addi(12, 12, -1).build(),
+        b().bne(12, 0, -20).build(),
+        b().addi(17, 0, 93).build(),
+        b().ecall().build(),
+    ];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let config = queued_config(backend);
+        let base = config.system.ram_base;
+        let mut ctx = TestContext::with_config(&config).load_program(base, &program);
+        for i in 0..LOADS {
+            ctx.sim.cpu.bus.bus.write_u64(PhysAddr::new(base + DATA + i * STRIDE), i + 1);
+        }
+        ctx.sim.write_reg(RegIdx::new(10), base + DATA);
+        ctx.sim.write_reg(RegIdx::new(12), LOADS);
+        let sim = ctx.run_to_exit(200_000);
+
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(11)), LOADS * (LOADS + 1) / 2, "{backend:?}");
+        let traffic = &sim.cpu.stats.mem_traffic;
+        assert!(traffic.reads() >= LOADS, "{backend:?}: {} reads", traffic.reads());
+        assert!(traffic.avg_read_latency() > 0.0, "{backend:?}");
+        assert!(traffic.avg_queue_occupancy() > 0.0, "{backend:?}");
+        assert_eq!(sim.cpu.l1d_mshrs.active_count(), 0, "{backend:?}");
+    }
+}
+
+#[test]
+fn dma_copy_completes_through_the_queue() {
+    const SIZE: u64 = 1024;
+    let b = InstructionBuilder::new;
+    // x10 = DMA base, x11 = SRC, x12 = DST, x13 = LEN, x14 = CTRL; poll
+    // STATUS until DONE.
+    let program = [
+        b().sd(10, 11, 0x00).build(),
+        b().sd(10, 12, 0x08).build(),
+        b().sd(10, 13, 0x10).build(),
+        b().sd(10, 14, 0x18).build(),
+        b().ld(15, 10, 0x20).build(),
+        b().andi(15, 15, STATUS_DONE as i32).build(),
+        b().beq(15, 0, -8).build(),
+        b().addi(17, 0, 93).build(),
+        b().ecall().build(),
+    ];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut config = queued_config(backend);
+        config.system.dma = true;
+        let base = config.system.ram_base;
+        let (src, dst) = (base + DATA, base + DATA + 0x1_0000);
+        let mut ctx = TestContext::with_config(&config).load_program(base, &program);
+        for i in (0..SIZE).step_by(8) {
+            ctx.sim.cpu.bus.bus.write_u64(PhysAddr::new(src + i), !i);
+        }
+        ctx.sim.write_reg(RegIdx::new(10), config.system.dma_base);
+        ctx.sim.write_reg(RegIdx::new(11), src);
+        ctx.sim.write_reg(RegIdx::new(12), dst);
+        ctx.sim.write_reg(RegIdx::new(13), SIZE);
+        ctx.sim.write_reg(RegIdx::new(14), CTRL_START);
+        let mut sim = ctx.run_to_exit(50_000);
+
+        for i in (0..SIZE).step_by(8) {
+            let got = sim.cpu.bus.bus.read_u64(PhysAddr::new(dst + i));
+            assert_eq!(got, !i, "{backend:?}: offset {i:#x}");
+        }
+        assert!(sim.cpu.bus.dma_requests.is_empty(), "{backend:?}");
+    }
+}
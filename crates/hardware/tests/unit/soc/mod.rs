@@ -1,5 +1,7 @@
 pub mod clint_timer;
+pub mod device_list;
 pub mod devices;
+pub mod dma;
 pub mod goldfish_rtc;
 pub mod htif;
 pub mod interconnect;
@@ -47,3 +47,17 @@ fn plic_tick_no_interrupt() {
     plic.update_irqs(0);
     assert!(!plic.tick());
 }
+
+#[test]
+fn plic_routes_contexts_per_hart() {
+    let mut plic = Plic::with_harts(0, 2);
+    plic.write_u32(12, 4);
+    // Enable source 3 for hart 1's S-mode context (context 3)
+    plic.write_u32(0x2000 + 3 * 0x80, 1 << 3);
+
+    plic.update_irqs(1 << 3);
+    assert_eq!(plic.check_interrupts(), (false, false), "Hart 0 has nothing enabled");
+    assert_eq!(plic.hart_interrupts(1), (false, true));
+
+    assert_eq!(plic.read_u32(0x200004 + 3 * 0x1000), 3);
+}
@@ -0,0 +1,158 @@
+//! PLIC gateway and arbitration tests.
+//!
+//! Two sources assert at once with different priorities: claims must come
+//! out highest priority first, a claimed source must stay masked until it is
+//! completed, and both M- and S-mode external interrupts must reach `mip`.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use rvsim_core::Simulator;
+use rvsim_core::common::PhysAddr;
+use rvsim_core::config::Config;
+use rvsim_core::core::arch::csr::{MIP_MEIP, MIP_SEIP};
+use rvsim_core::soc::System;
+use rvsim_core::soc::devices::Device;
+use rvsim_core::soc::devices::plic::Plic;
+
+const LOW: u32 = 3;
+const HIGH: u32 = 10;
+const ENABLE: u64 = 0x2000;
+const CONTEXT: u64 = 0x20_0000;
+const CLAIM: u64 = CONTEXT + 4;
+
+/// A PLIC with `LOW` at priority 2 and `HIGH` at priority 5, both enabled
+/// for context 0 and both lines asserted.
+fn two_sources() -> Plic {
+    let mut plic = Plic::new(0);
+    plic.write_u32(4 * u64::from(LOW), 2);
+    plic.write_u32(4 * u64::from(HIGH), 5);
+    plic.write_u32(ENABLE, (1 << LOW) | (1 << HIGH));
+    plic.update_irqs((1 << LOW) | (1 << HIGH));
+    plic
+}
+
+#[test]
+fn claims_highest_priority_first() {
+    let mut plic = two_sources();
+    assert_eq!(plic.check_interrupts(), (true, false));
+    assert_eq!(plic.pending_sources(), vec![LOW, HIGH]);
+
+    assert_eq!(plic.read_u32(CLAIM), HIGH);
+    assert_eq!(plic.pending_sources(), vec![LOW]);
+    assert_eq!(plic.claimed_sources(), vec![HIGH]);
+    assert_eq!(plic.check_interrupts(), (true, false), "lower source still pending");
+
+    assert_eq!(plic.read_u32(CLAIM), LOW);
+    assert_eq!(plic.read_u32(CLAIM), 0, "nothing left to claim");
+    assert_eq!(plic.claimed_sources(), vec![LOW, HIGH]);
+    assert_eq!(plic.check_interrupts(), (false, false));
+}
+
+#[test]
+fn claimed_source_is_masked_until_complete() {
+    let mut plic = two_sources();
+    assert_eq!(plic.read_u32(CLAIM), HIGH);
+
+    // The line is still high, but the gateway holds the request back.
+    plic.update_irqs((1 << LOW) | (1 << HIGH));
+    assert_eq!(plic.read_u32(CLAIM), LOW);
+    plic.update_irqs((1 << LOW) | (1 << HIGH));
+    assert_eq!(plic.read_u32(CLAIM), 0);
+
+    plic.write_u32(CLAIM, HIGH);
+    assert_eq!(plic.claimed_sources(), vec![LOW]);
+    plic.update_irqs((1 << LOW) | (1 << HIGH));
+    assert_eq!(plic.pending_sources(), vec![HIGH], "re-pended after completion");
+    assert_eq!(plic.read_u32(CLAIM), HIGH);
+}
+
+#[test]
+fn threshold_hides_lower_priority_source() {
+    let mut plic = two_sources();
+    plic.write_u32(CONTEXT, 2);
+    assert_eq!(plic.threshold(0), 2);
+    assert_eq!(plic.read_u32(CLAIM), HIGH);
+    assert_eq!(plic.read_u32(CLAIM), 0, "priority 2 is not above threshold 2");
+    assert_eq!(plic.check_interrupts(), (false, false));
+    assert_eq!(plic.pending_sources(), vec![LOW]);
+
+    plic.write_u32(CONTEXT, 1);
+    assert_eq!(plic.check_interrupts(), (true, false));
+    assert_eq!(plic.read_u32(CLAIM), LOW);
+}
+
+#[test]
+fn completion_for_disabled_source_is_ignored() {
+    let mut plic = two_sources();
+    assert_eq!(plic.read_u32(CLAIM), HIGH);
+    plic.write_u32(ENABLE, 1 << LOW);
+    plic.write_u32(CLAIM, HIGH);
+    assert_eq!(plic.claimed_sources(), vec![HIGH], "HIGH is not enabled for context 0");
+
+    plic.write_u32(ENABLE, (1 << LOW) | (1 << HIGH));
+    plic.write_u32(CLAIM, HIGH);
+    assert!(plic.claimed_sources().is_empty());
+}
+
+#[test]
+fn equal_priorities_go_to_lowest_id() {
+    let mut plic = two_sources();
+    plic.write_u32(4 * u64::from(HIGH), 2);
+    assert_eq!(plic.read_u32(CLAIM), LOW);
+}
+
+#[test]
+fn reserved_source_zero_cannot_be_enabled() {
+    let mut plic = Plic::new(0);
+    plic.write_u32(ENABLE, u32::MAX);
+    assert_eq!(plic.read_u32(ENABLE), u32::MAX - 1);
+    assert_eq!(plic.enabled_sources(0).first(), Some(&1));
+    assert_eq!(plic.priority(0), 0);
+}
+
+#[test]
+fn machine_and_supervisor_contexts_claim_independently() {
+    let mut plic = two_sources();
+    // HIGH stays on the M-mode context; LOW moves to the S-mode context.
+    plic.write_u32(ENABLE, 1 << HIGH);
+    plic.write_u32(ENABLE + 0x80, 1 << LOW);
+    assert_eq!(plic.enabled_sources(1), vec![LOW]);
+    assert_eq!(plic.check_interrupts(), (true, true));
+
+    assert_eq!(plic.read_u32(CLAIM + 0x1000), LOW);
+    assert_eq!(plic.check_interrupts(), (true, false));
+    assert_eq!(plic.read_u32(CLAIM), HIGH);
+    assert_eq!(plic.check_interrupts(), (false, false));
+}
+
+#[test]
+fn external_interrupts_reach_mip() {
+    let mut config = Config::default();
+    config.system.uart_quiet = true;
+    let base = config.system.ram_base;
+    let uart = config.system.uart_base;
+    let plic = 0x0c00_0000;
+    let mut sim = Simulator::new(System::new(&config, ""), &config);
+    let bus = &mut sim.cpu.bus.bus;
+    bus.write_u32(PhysAddr::new(base), InstructionBuilder::new().jal(0, 0).build());
+
+    // Enable the UART (source 10) for both the M- and S-mode contexts.
+    bus.write_u32(PhysAddr::new(plic + 4 * u64::from(HIGH)), 1);
+    bus.write_u32(PhysAddr::new(plic + ENABLE), 1 << HIGH);
+    bus.write_u32(PhysAddr::new(plic + ENABLE + 0x80), 1 << HIGH);
+    bus.write_u8(PhysAddr::new(uart + 1), 0x01); // IER: received data available
+    bus.uart_mut().unwrap().push_input(b"x");
+    sim.cpu.pc = base;
+
+    for _ in 0..10 {
+        sim.tick().unwrap();
+    }
+    assert_ne!(sim.cpu.csrs.mip & MIP_MEIP, 0);
+    assert_ne!(sim.cpu.csrs.mip & MIP_SEIP, 0);
+
+    // Claiming from the M-mode context closes the gateway for both.
+    let bus = &mut sim.cpu.bus.bus;
+    assert_eq!(bus.read_u32(PhysAddr::new(plic + CLAIM)), HIGH);
+    assert_eq!(bus.plic_mut().unwrap().claimed_sources(), vec![HIGH]);
+    sim.tick().unwrap();
+    assert_eq!(sim.cpu.csrs.mip & (MIP_MEIP | MIP_SEIP), 0);
+}
@@ -1,2 +1,3 @@
 pub mod claiming;
+pub mod gateway;
 pub mod priority_logic;
@@ -39,9 +39,9 @@ fn plic_set_and_read_priority() {
 #[test]
 fn plic_priority_source_zero_reserved() {
     let mut plic = Plic::new(0);
-    // Source 0 priority at offset 0 — exists but is reserved (no interrupt 0)
+    // Source 0 priority at offset 0 — reserved (no interrupt 0), hardwired to 0
     plic.write_u32(0, 5);
-    assert_eq!(plic.read_u32(0), 5);
+    assert_eq!(plic.read_u32(0), 0);
 }
 
 // ══════════════════════════════════════════════════════════
@@ -5,7 +5,7 @@
 //! registers.
 
 use rvsim_core::soc::devices::Device;
-use rvsim_core::soc::devices::syscon::SysCon;
+use rvsim_core::soc::devices::syscon::{SysCon, SysconEvent};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
 
@@ -98,8 +98,25 @@ fn test_syscon_reset_signal() {
     // Write reset magic value (0x7777) to offset 0
     syscon.write_u32(0, 0x7777);
 
-    // Exit signal should be set to 0 (simulated as exit)
-    assert_eq!(exit_signal.load(Ordering::Relaxed), 0);
+    // Reboot does not exit; it is surfaced as an event for the simulator
+    assert_eq!(exit_signal.load(Ordering::Relaxed), u64::MAX);
+    assert_eq!(syscon.take_event(), Some(SysconEvent::Reboot));
+    assert_eq!(syscon.take_event(), None);
+}
+
+#[test]
+fn test_syscon_poweroff_and_failure_events() {
+    let (mut syscon, _) = create_test_syscon();
+    assert_eq!(syscon.take_event(), None);
+
+    syscon.write_u32(0, 0x5555);
+    assert_eq!(syscon.take_event(), Some(SysconEvent::Poweroff));
+
+    syscon.write_u32(0, 0x3333);
+    assert_eq!(syscon.take_event(), Some(SysconEvent::Failure));
+
+    syscon.write_u32(0, 0x1234);
+    assert_eq!(syscon.take_event(), None);
 }
 
 #[test]
@@ -176,7 +193,7 @@ fn test_syscon_all_magic_values() {
     let exit_signal = Arc::new(AtomicU64::new(u64::MAX));
     let mut syscon = SysCon::new(base_addr, exit_signal.clone());
     syscon.write_u32(0, 0x7777);
-    assert_eq!(exit_signal.load(Ordering::Relaxed), 0);
+    assert_eq!(exit_signal.load(Ordering::Relaxed), u64::MAX);
 
     // Test failure
     let exit_signal = Arc::new(AtomicU64::new(u64::MAX));
@@ -184,3 +201,20 @@ fn test_syscon_all_magic_values() {
     syscon.write_u32(0, 0x3333);
     assert_eq!(exit_signal.load(Ordering::Relaxed), 1);
 }
+
+#[test]
+fn test_syscon_failure_carries_exit_code() {
+    let (mut syscon, exit_signal) = create_test_syscon();
+
+    // SiFive test finisher layout: exit code in bits 31:16
+    syscon.write_u32(0, (42 << 16) | 0x3333);
+    assert_eq!(exit_signal.load(Ordering::Relaxed), 42);
+    assert_eq!(syscon.take_event(), Some(SysconEvent::Failure));
+
+    // Upper bits are ignored for power off and reset
+    let (mut syscon, exit_signal) = create_test_syscon();
+    syscon.write_u32(0, (7 << 16) | 0x5555);
+    assert_eq!(exit_signal.load(Ordering::Relaxed), 0);
+    syscon.write_u32(0, (7 << 16) | 0x7777);
+    assert_eq!(syscon.take_event(), Some(SysconEvent::Reboot));
+}
@@ -0,0 +1,131 @@
+//! UART FIFO and Interrupt Tests.
+//!
+//! Tests IIR priority ordering, the receive FIFO trigger level and character
+//! timeout, loopback overrun, and host input/output, including a guest
+//! program that echoes a line typed on the console.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::common::RegIdx;
+use rvsim_core::config::Config;
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::soc::devices::Device;
+use rvsim_core::soc::devices::uart::Uart;
+
+const RBR: u64 = 0;
+const IER: u64 = 1;
+const IIR: u64 = 2;
+const FCR: u64 = 2;
+const MCR: u64 = 4;
+const LSR: u64 = 5;
+
+/// Interrupt ID field of IIR (bits 3:0).
+fn iir_id(uart: &mut Uart) -> u8 {
+    uart.read_u8(IIR) & 0x0F
+}
+
+#[test]
+fn iir_reports_sources_in_priority_order() {
+    let mut uart = Uart::new(0, true, true);
+    uart.write_u8(FCR, 0x07); // enable and clear FIFOs, trigger level 1
+    uart.write_u8(MCR, 0x10); // loopback
+    uart.write_u8(IER, 0x07); // RLS, THRE, RDA
+
+    // Seventeen bytes into a sixteen-byte FIFO: the last one overruns.
+    for b in 0..17 {
+        uart.write_u8(0, b);
+    }
+    assert!(uart.tick());
+    assert_eq!(iir_id(&mut uart), 0x06, "line status first");
+    assert_ne!(uart.read_u8(LSR) & 0x02, 0, "overrun reported");
+    assert_eq!(iir_id(&mut uart), 0x04, "then received data");
+
+    for _ in 0..16 {
+        let _ = uart.read_u8(RBR);
+    }
+    assert_eq!(uart.read_u8(LSR) & 0x01, 0, "FIFO drained");
+    assert_eq!(iir_id(&mut uart), 0x02, "then THR empty");
+    assert_eq!(iir_id(&mut uart), 0x01, "reading IIR clears THR empty");
+    assert!(!uart.tick());
+}
+
+#[test]
+fn iir_fifo_bits_follow_fcr() {
+    let mut uart = Uart::new(0, true, true);
+    assert_eq!(uart.read_u8(IIR) & 0xC0, 0, "FIFOs disabled at reset");
+    uart.write_u8(FCR, 0x01);
+    assert_eq!(uart.read_u8(IIR) & 0xC0, 0xC0);
+}
+
+#[test]
+fn rx_trigger_level_and_character_timeout() {
+    let mut uart = Uart::new(0, true, true);
+    uart.write_u8(FCR, 0x81); // FIFOs on, trigger level 8
+    uart.write_u8(IER, 0x01);
+
+    uart.push_input(b"abc");
+    assert_eq!(iir_id(&mut uart), 0x01, "below trigger level");
+    let mut ticks = 0;
+    while !uart.tick() {
+        ticks += 1;
+        assert!(ticks < 10_000, "character timeout never fired");
+    }
+    assert_eq!(iir_id(&mut uart), 0x0C, "character timeout");
+    assert_eq!(uart.read_u8(RBR), b'a');
+    assert_eq!(iir_id(&mut uart), 0x01, "reading restarts the timeout");
+
+    uart.push_input(b"defghi");
+    assert_eq!(iir_id(&mut uart), 0x04, "trigger level reached");
+}
+
+#[test]
+fn host_input_waits_for_fifo_room() {
+    let mut uart = Uart::new(0, true, true);
+    uart.write_u8(FCR, 0x01);
+    let input: Vec<u8> = (0..40).collect();
+    uart.push_input(&input);
+    let mut received = Vec::new();
+    while uart.read_u8(LSR) & 0x01 != 0 {
+        received.push(uart.read_u8(RBR));
+    }
+    assert_eq!(received, input, "no byte lost to a full FIFO");
+}
+
+#[test]
+fn transmitted_bytes_are_captured() {
+    let mut uart = Uart::new(0, true, true);
+    for &b in b"hi\n" {
+        uart.write_u8(0, b);
+    }
+    assert_ne!(uart.read_u8(LSR) & 0x60, 0, "transmitter empty");
+    assert_eq!(uart.take_output(), b"hi\n");
+    assert!(uart.take_output().is_empty());
+}
+
+#[test]
+fn guest_echoes_console_input() {
+    let b = InstructionBuilder::new;
+    let program = [
+        b().lb(6, 5, LSR as i32).build(),
+        b().andi(6, 6, 1).build(),
+        b().beq(6, 0, -8).build(),
+        b().lb(8, 5, RBR as i32).build(),
+        b().sb(5, 8, 0).build(),
+        b().bne(8, 7, -20).build(),
+        b().addi(17, 0, 93).build(),
+        b().ecall().build(),
+    ];
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut config = Config::default();
+        config.pipeline.backend = backend;
+        config.system.uart_quiet = true;
+        let base = config.system.ram_base;
+        let mut ctx = TestContext::with_config(&config).load_program(base, &program);
+        ctx.sim.cpu.bus.bus.uart_mut().unwrap().push_input(b"ls\n");
+        ctx.sim.write_reg(RegIdx::new(5), config.system.uart_base);
+        ctx.sim.write_reg(RegIdx::new(7), u64::from(b'\n'));
+        let mut sim = ctx.run_to_exit(10_000);
+        assert_eq!(sim.cpu.exit_code, Some(0), "{backend:?}");
+        assert_eq!(sim.cpu.bus.bus.uart_mut().unwrap().take_output(), b"ls\n", "{backend:?}");
+    }
+}
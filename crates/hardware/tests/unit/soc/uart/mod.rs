@@ -1,2 +1,4 @@
 pub mod comprehensive;
 pub mod fifo_watermarks;
+pub mod interrupts;
+pub mod serial_link;
@@ -0,0 +1,128 @@
+//! UART Serial Link Tests.
+//!
+//! Tests that bytes transmitted by one UART arrive at a linked UART after the
+//! configured latency in the receiver's ticks, raising its receive interrupt,
+//! and that two simulators running bare-metal programs can ping-pong a byte
+//! over the link.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use rvsim_core::Simulator;
+use rvsim_core::common::{PhysAddr, RegIdx};
+use rvsim_core::config::Config;
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::soc::System;
+use rvsim_core::soc::devices::Device;
+use rvsim_core::soc::devices::uart::{SerialLink, Uart};
+
+const RBR: u64 = 0;
+const IER: u64 = 1;
+const IIR: u64 = 2;
+const MCR: u64 = 4;
+const LSR: u64 = 5;
+
+fn linked_pair(latency: u64) -> (Uart, Uart) {
+    let (a_end, b_end) = SerialLink::pair(latency);
+    let mut a = Uart::new(0, true, true);
+    let mut b = Uart::new(0, true, true);
+    a.connect(a_end);
+    b.connect(b_end);
+    (a, b)
+}
+
+#[test]
+fn byte_arrives_after_latency_in_receiver_ticks() {
+    let (mut a, mut b) = linked_pair(5);
+    b.write_u8(IER, 0x01);
+    a.write_u8(0, b'x');
+    assert_eq!(a.take_output(), b"x", "sent bytes are still captured");
+
+    for tick in 0..5 {
+        assert!(!b.tick(), "delivered early at tick {tick}");
+        assert_eq!(b.read_u8(LSR) & 0x01, 0);
+    }
+    assert!(b.tick(), "receive interrupt raised");
+    assert_eq!(b.read_u8(IIR) & 0x0F, 0x04);
+    assert_eq!(b.read_u8(RBR), b'x');
+    assert_eq!(a.read_u8(LSR) & 0x01, 0, "nothing echoed back to the sender");
+}
+
+#[test]
+fn loopback_and_disconnected_uarts_send_nothing() {
+    let (mut a, mut b) = linked_pair(0);
+    a.write_u8(MCR, 0x10);
+    a.write_u8(0, b'l');
+    a.write_u8(MCR, 0);
+    let _ = b.tick();
+    assert_eq!(b.read_u8(LSR) & 0x01, 0, "loopback byte stayed local");
+
+    a.disconnect();
+    assert!(!a.is_connected());
+    a.write_u8(0, b'd');
+    let _ = b.tick();
+    assert_eq!(b.read_u8(LSR) & 0x01, 0);
+}
+
+/// Builds a system running `program` with x5 holding the UART base.
+fn sim_running(backend: BackendType, program: &[u32]) -> Simulator {
+    let mut config = Config::default();
+    config.pipeline.backend = backend;
+    config.system.uart_quiet = true;
+    let base = config.system.ram_base;
+    let mut sim = Simulator::new(System::new(&config, ""), &config);
+    for (i, &inst) in program.iter().enumerate() {
+        sim.cpu.bus.bus.write_u32(PhysAddr::new(base + 4 * i as u64), inst);
+    }
+    sim.cpu.pc = base;
+    sim.write_reg(RegIdx::new(5), config.system.uart_base);
+    sim
+}
+
+#[test]
+fn two_systems_ping_pong_a_byte() {
+    const LATENCY: u64 = 50;
+    let b = InstructionBuilder::new;
+    let wait_for_byte =
+        [b().lb(6, 5, LSR as i32).build(), b().andi(6, 6, 1).build(), b().beq(6, 0, -8).build()];
+    let exit = [b().addi(17, 0, 93).build(), b().ecall().build()];
+    // Sends 'P', then exits with the byte that comes back.
+    let ping: Vec<u32> = [b().addi(8, 0, i32::from(b'P')).build(), b().sb(5, 8, 0).build()]
+        .into_iter()
+        .chain(wait_for_byte)
+        .chain([b().lb(10, 5, RBR as i32).build()])
+        .chain(exit)
+        .collect();
+    // Returns every received byte plus one, then exits with 0.
+    let pong: Vec<u32> = wait_for_byte
+        .into_iter()
+        .chain([
+            b().lb(8, 5, RBR as i32).build(),
+            b().addi(8, 8, 1).build(),
+            b().sb(5, 8, 0).build(),
+            b().addi(10, 0, 0).build(),
+        ])
+        .chain(exit)
+        .collect();
+
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut a = sim_running(backend, &ping);
+        let mut b = sim_running(backend, &pong);
+        let (a_end, b_end) = SerialLink::pair(LATENCY);
+        a.cpu.bus.bus.uart_mut().unwrap().connect(a_end);
+        b.cpu.bus.bus.uart_mut().unwrap().connect(b_end);
+
+        for _ in 0..20_000 {
+            for sim in [&mut a, &mut b] {
+                if sim.cpu.exit_code.is_none() {
+                    sim.tick().unwrap();
+                }
+            }
+            if a.cpu.exit_code.is_some() && b.cpu.exit_code.is_some() {
+                break;
+            }
+        }
+        assert_eq!(b.cpu.exit_code, Some(0), "{backend:?}");
+        assert_eq!(a.cpu.exit_code, Some(u64::from(b'Q')), "{backend:?}");
+        assert!(a.cpu.stats.cycles > 2 * LATENCY, "{backend:?}: round trip took the latency twice");
+        assert_eq!(b.cpu.bus.bus.uart_mut().unwrap().take_output(), b"Q", "{backend:?}");
+    }
+}
@@ -415,6 +415,6 @@ fn virtio_device_features_lower_bits() {
     let mut vio = make_virtio();
     vio.write_u32(0x14, 0); // Select lower 32 bits
     let features = vio.read_u32(0x10);
-    // Lower 32 bits should be 0 for this device
-    assert_eq!(features, 0);
+    // Lower 32 bits advertise only VIRTIO_BLK_F_FLUSH (bit 9)
+    assert_eq!(features, 1 << 9);
 }
@@ -0,0 +1,192 @@
+//! VirtIO Block Copy-on-Write Overlay Tests.
+//!
+//! Verifies that systems built from the same disk file in copy-on-write mode
+//! share one base image, each read back their own writes, and leave both the
+//! shared image and the file untouched; that cached writes are written back
+//! into the overlay; that the overlay exports as a sparse diff; and that a
+//! write-through device copies a shared image before modifying it.
+
+use rvsim_core::common::PhysAddr;
+use rvsim_core::config::Config;
+use rvsim_core::soc::System;
+use rvsim_core::soc::devices::virtio_disk::{OVERLAY_MAGIC, VirtioBlock};
+use std::io::Write;
+use std::sync::Arc;
+use tempfile::NamedTempFile;
+
+/// Guest physical addresses of the queue structures and request buffers.
+const DESC: u64 = 0x8010_0000;
+const AVAIL: u64 = 0x8010_0100;
+const USED: u64 = 0x8010_0200;
+const HEADER: u64 = 0x8010_0300;
+const STATUS: u64 = 0x8010_0400;
+const DATA: u64 = 0x8010_1000;
+
+const SECTOR: usize = 512;
+const SECTORS: usize = 16;
+
+const T_IN: u32 = 0;
+const T_OUT: u32 = 1;
+const T_FLUSH: u32 = 4;
+
+/// A system with the disk's request queue configured, plus the next available slot.
+struct Guest {
+    sys: System,
+    disk_base: u64,
+    next_slot: u16,
+}
+
+impl Guest {
+    fn new(config: &Config, disk_path: &str) -> Self {
+        let mut sys = System::new(config, disk_path);
+        let disk_base = config.system.disk_base;
+        sys.bus.write_u32(PhysAddr::new(disk_base + 0x38), 16);
+        sys.bus.write_u32(PhysAddr::new(disk_base + 0x80), DESC as u32);
+        sys.bus.write_u32(PhysAddr::new(disk_base + 0x90), AVAIL as u32);
+        sys.bus.write_u32(PhysAddr::new(disk_base + 0xa0), USED as u32);
+        sys.bus.write_u32(PhysAddr::new(disk_base + 0x44), 1);
+        Self { sys, disk_base, next_slot: 0 }
+    }
+
+    fn disk(&mut self) -> &mut VirtioBlock {
+        self.sys.bus.virtio_blk_mut().unwrap()
+    }
+
+    fn write_desc(&mut self, idx: u64, addr: u64, len: u32, flags: u16, next: u16) {
+        let desc = DESC + 16 * idx;
+        self.sys.bus.write_u64(PhysAddr::new(desc), addr);
+        self.sys.bus.write_u32(PhysAddr::new(desc + 8), len);
+        self.sys.bus.write_u32(PhysAddr::new(desc + 12), u32::from(flags) | u32::from(next) << 16);
+    }
+
+    /// Submits a request of `kind` for `sector` with `data_len` bytes at `DATA`
+    /// (none for FLUSH) and notifies the device.
+    fn submit(&mut self, kind: u32, sector: u64, data_len: u32) {
+        self.sys.bus.write_u32(PhysAddr::new(HEADER), kind);
+        self.sys.bus.write_u64(PhysAddr::new(HEADER + 8), sector);
+        self.sys.bus.write_u8(PhysAddr::new(STATUS), 0xFF);
+
+        self.write_desc(0, HEADER, 16, 1, 1);
+        let status_idx = if data_len == 0 {
+            1
+        } else {
+            let flags = if kind == T_IN { 1 | 2 } else { 1 };
+            self.write_desc(1, DATA, data_len, flags, 2);
+            2
+        };
+        self.write_desc(status_idx, STATUS, 1, 2, 0);
+
+        let slot = AVAIL + 4 + 2 * u64::from(self.next_slot);
+        let word = self.sys.bus.read_u32(PhysAddr::new(slot & !3));
+        let shift = (slot & 2) * 8;
+        self.sys.bus.write_u32(PhysAddr::new(slot & !3), word & !(0xFFFF << shift));
+        self.next_slot += 1;
+        self.sys.bus.write_u32(PhysAddr::new(AVAIL), u32::from(self.next_slot) << 16);
+        self.sys.bus.write_u32(PhysAddr::new(self.disk_base + 0x50), 0);
+        assert_eq!(self.sys.bus.read_u8(PhysAddr::new(STATUS)), 0, "request failed");
+    }
+
+    /// Writes `data` starting at `sector`.
+    fn write(&mut self, sector: u64, data: &[u8]) {
+        for (i, &b) in data.iter().enumerate() {
+            self.sys.bus.write_u8(PhysAddr::new(DATA + i as u64), b);
+        }
+        self.submit(T_OUT, sector, data.len() as u32);
+    }
+
+    /// Reads sector `sector`.
+    fn read(&mut self, sector: u64) -> Vec<u8> {
+        self.submit(T_IN, sector, SECTOR as u32);
+        (0..SECTOR as u64).map(|i| self.sys.bus.read_u8(PhysAddr::new(DATA + i))).collect()
+    }
+}
+
+fn disk_file() -> NamedTempFile {
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(&[0xEE; SECTOR * SECTORS]).unwrap();
+    file.flush().unwrap();
+    file
+}
+
+fn cow_config() -> Config {
+    let mut config = Config::default();
+    config.system.disk_copy_on_write = true;
+    config
+}
+
+#[test]
+fn systems_sharing_a_base_image_keep_their_own_writes() {
+    let file = disk_file();
+    let path = file.path().to_str().unwrap();
+    let config = cow_config();
+    let mut a = Guest::new(&config, path);
+    let mut b = Guest::new(&config, path);
+    assert!(Arc::ptr_eq(a.disk().base_image(), b.disk().base_image()), "base image not shared");
+
+    a.write(0, &[0xAA; SECTOR]);
+    b.write(0, &[0xBB; SECTOR]);
+
+    assert!(a.read(0).iter().all(|&x| x == 0xAA));
+    assert!(b.read(0).iter().all(|&x| x == 0xBB));
+    assert!(a.read(1).iter().all(|&x| x == 0xEE));
+    assert_eq!(a.disk().overlay_sectors(), 1);
+    assert!(a.disk().image()[..SECTOR].iter().all(|&x| x == 0xAA));
+    assert!(a.disk().base_image().iter().all(|&x| x == 0xEE), "base image modified");
+    assert!(std::fs::read(path).unwrap().iter().all(|&x| x == 0xEE), "file modified");
+}
+
+#[test]
+fn cached_writes_are_written_back_into_the_overlay() {
+    let file = disk_file();
+    let mut config = cow_config();
+    config.system.disk_write_cache_sectors = 4;
+    let mut g = Guest::new(&config, file.path().to_str().unwrap());
+
+    g.write(2, &[0x11; SECTOR]);
+    assert_eq!((g.disk().dirty_sectors(), g.disk().overlay_sectors()), (1, 0));
+    g.submit(T_FLUSH, 0, 0);
+    assert_eq!((g.disk().dirty_sectors(), g.disk().overlay_sectors()), (0, 1));
+    assert!(g.read(2).iter().all(|&x| x == 0x11));
+    assert!(g.disk().base_image().iter().all(|&x| x == 0xEE));
+}
+
+#[test]
+fn overlay_exports_as_sparse_diff() {
+    let file = disk_file();
+    let mut g = Guest::new(&cow_config(), file.path().to_str().unwrap());
+    g.write(7, &[0x77; SECTOR]);
+    g.write(3, &[0x33; 100]);
+
+    let out = NamedTempFile::new().unwrap();
+    g.disk().export_overlay(out.path()).unwrap();
+    let diff = std::fs::read(out.path()).unwrap();
+
+    let u64_at = |at: usize| u64::from_le_bytes(diff[at..at + 8].try_into().unwrap());
+    assert_eq!(&diff[..8], OVERLAY_MAGIC);
+    assert_eq!(u64_at(8), SECTOR as u64);
+    assert_eq!(u64_at(16), 2);
+    assert_eq!(diff.len(), 24 + 2 * (8 + SECTOR));
+    let record = |i: usize| &diff[24 + i * (8 + SECTOR)..24 + (i + 1) * (8 + SECTOR)];
+    assert_eq!(record(0)[..8], 3u64.to_le_bytes());
+    assert!(record(0)[8..108].iter().all(|&x| x == 0x33));
+    assert!(record(0)[108..].iter().all(|&x| x == 0xEE), "rest of sector from base");
+    assert_eq!(record(1)[..8], 7u64.to_le_bytes());
+    assert!(record(1)[8..].iter().all(|&x| x == 0x77));
+}
+
+#[test]
+fn write_through_copies_a_shared_image_before_writing() {
+    let file = disk_file();
+    let path = file.path().to_str().unwrap();
+    let mut a = Guest::new(&cow_config(), path);
+    let mut b = Guest::new(&Config::default(), "");
+    let shared = Arc::clone(a.disk().base_image());
+    b.disk().load_shared(Arc::clone(&shared));
+
+    b.write(0, &[0xBB; SECTOR]);
+
+    assert!(b.read(0).iter().all(|&x| x == 0xBB));
+    assert!(!Arc::ptr_eq(b.disk().base_image(), &shared));
+    assert!(shared.iter().all(|&x| x == 0xEE));
+    assert!(a.read(0).iter().all(|&x| x == 0xEE));
+}
@@ -0,0 +1,211 @@
+//! VirtIO Block Write Cache and FLUSH Tests.
+//!
+//! Verifies that `VIRTIO_BLK_F_FLUSH` is advertised, that cached writes are
+//! acknowledged without reaching the image while reads still see them, that a
+//! FLUSH request returns OK and writes every cached sector back, that a full
+//! cache writes back its oldest sector, and that image writes hold back the
+//! request's completion by the configured latency.
+
+use rvsim_core::soc::devices::Device;
+use rvsim_core::soc::devices::virtio_disk::{DiskWriteStats, VIRTIO_BLK_F_FLUSH, VirtioBlock};
+use rvsim_core::soc::memory::buffer::DramBuffer;
+use std::sync::Arc;
+
+const RAM_BASE: u64 = 0x8000_0000;
+
+/// RAM offsets of the queue structures and request buffers.
+const DESC: usize = 0x1000;
+const AVAIL: usize = 0x1100;
+const USED: usize = 0x1200;
+const HEADER: usize = 0x1300;
+const STATUS: usize = 0x1400;
+const DATA: usize = 0x2000;
+
+const SECTOR: usize = 512;
+const SECTORS: usize = 16;
+
+const T_IN: u32 = 0;
+const T_OUT: u32 = 1;
+const T_FLUSH: u32 = 4;
+const T_GET_ID: u32 = 8;
+
+/// Disk and RAM with the request queue configured, plus the next available slot.
+struct Harness {
+    disk: VirtioBlock,
+    ram: Arc<DramBuffer>,
+    next_slot: u16,
+}
+
+impl Harness {
+    fn new(cache_sectors: usize, write_latency: u64) -> Self {
+        let ram = Arc::new(DramBuffer::new(0x10000));
+        let mut disk = VirtioBlock::new(0x9000_0000, RAM_BASE, Arc::clone(&ram));
+        disk.load(vec![0xEE; SECTOR * SECTORS]);
+        disk.set_write_cache(cache_sectors, write_latency);
+        disk.write_u32(0x38, 16);
+        disk.write_u32(0x80, (RAM_BASE + DESC as u64) as u32);
+        disk.write_u32(0x90, (RAM_BASE + AVAIL as u64) as u32);
+        disk.write_u32(0xa0, (RAM_BASE + USED as u64) as u32);
+        disk.write_u32(0x44, 1);
+        Self { disk, ram, next_slot: 0 }
+    }
+
+    fn write_desc(&self, idx: usize, offset: usize, len: u32, flags: u16, next: u16) {
+        let desc = DESC + 16 * idx;
+        self.ram.write_slice(desc, &(RAM_BASE + offset as u64).to_le_bytes());
+        self.ram.write_slice(desc + 8, &len.to_le_bytes());
+        self.ram.write_slice(desc + 12, &flags.to_le_bytes());
+        self.ram.write_slice(desc + 14, &next.to_le_bytes());
+    }
+
+    /// Submits a request of `kind` for `sector` with `data_len` bytes at `DATA`
+    /// (none for FLUSH) and notifies the device.
+    fn submit(&mut self, kind: u32, sector: u64, data_len: u32) {
+        let mut header = [0u8; 16];
+        header[..4].copy_from_slice(&kind.to_le_bytes());
+        header[8..].copy_from_slice(&sector.to_le_bytes());
+        self.ram.write_slice(HEADER, &header);
+        self.ram.write_slice(STATUS, &[0xFF]);
+
+        self.write_desc(0, HEADER, 16, 1, 1);
+        let status_idx = if data_len == 0 {
+            1
+        } else {
+            let flags = if kind == T_IN { 1 | 2 } else { 1 };
+            self.write_desc(1, DATA, data_len, flags, 2);
+            2
+        };
+        self.write_desc(status_idx, STATUS, 1, 2, 0);
+
+        self.ram.write_slice(AVAIL + 4 + 2 * self.next_slot as usize, &0u16.to_le_bytes());
+        self.next_slot += 1;
+        self.ram.write_slice(AVAIL + 2, &self.next_slot.to_le_bytes());
+        self.disk.write_u32(0x50, 0);
+    }
+
+    /// Writes `fill` to `count` sectors starting at `sector`.
+    fn write_sectors(&mut self, sector: u64, count: usize, fill: u8) {
+        self.ram.write_slice(DATA, &vec![fill; count * SECTOR]);
+        self.submit(T_OUT, sector, (count * SECTOR) as u32);
+    }
+
+    fn status(&self) -> u8 {
+        self.ram.read_slice(STATUS, 1)[0]
+    }
+
+    fn used_idx(&self) -> u16 {
+        let b = self.ram.read_slice(USED + 2, 2);
+        u16::from_le_bytes([b[0], b[1]])
+    }
+
+    fn image_sector(&self, sector: usize) -> Vec<u8> {
+        self.disk.image()[sector * SECTOR..(sector + 1) * SECTOR].to_vec()
+    }
+}
+
+#[test]
+fn flush_feature_is_advertised() {
+    let mut h = Harness::new(0, 0);
+    h.disk.write_u32(0x14, 0);
+    assert_ne!(h.disk.read_u32(0x10) & VIRTIO_BLK_F_FLUSH, 0);
+}
+
+#[test]
+fn write_through_reaches_image_immediately() {
+    let mut h = Harness::new(0, 0);
+    h.write_sectors(2, 1, 0x11);
+    assert_eq!(h.status(), 0);
+    assert_eq!(h.used_idx(), 1);
+    assert!(h.image_sector(2).iter().all(|&b| b == 0x11));
+    assert_eq!(
+        h.disk.take_write_stats(),
+        DiskWriteStats { write_through_sectors: 1, ..DiskWriteStats::default() }
+    );
+}
+
+#[test]
+fn flush_request_returns_ok_and_persists_cached_sectors() {
+    let mut h = Harness::new(8, 0);
+    h.write_sectors(3, 2, 0x5A);
+    assert_eq!(h.status(), 0, "cached write acknowledged");
+    assert_eq!(h.used_idx(), 1);
+    assert_eq!(h.disk.dirty_sectors(), 2);
+    assert!(h.image_sector(3).iter().all(|&b| b == 0xEE), "image untouched until flush");
+
+    h.submit(T_IN, 3, (2 * SECTOR) as u32);
+    assert!(
+        h.ram.read_slice(DATA, 2 * SECTOR).iter().all(|&b| b == 0x5A),
+        "reads see cached sectors"
+    );
+
+    h.submit(T_FLUSH, 0, 0);
+    assert_eq!(h.status(), 0, "FLUSH returns VIRTIO_BLK_S_OK");
+    assert_eq!(h.used_idx(), 3);
+    assert_eq!(h.disk.dirty_sectors(), 0);
+    assert!(h.image_sector(3).iter().all(|&b| b == 0x5A));
+    assert!(h.image_sector(4).iter().all(|&b| b == 0x5A));
+    assert!(h.image_sector(5).iter().all(|&b| b == 0xEE));
+    assert_eq!(
+        h.disk.take_write_stats(),
+        DiskWriteStats {
+            cached_sectors: 2,
+            write_through_sectors: 0,
+            flushed_sectors: 2,
+            flush_requests: 1
+        }
+    );
+    assert!(!h.disk.has_write_stats());
+}
+
+#[test]
+fn full_cache_writes_back_oldest_sector() {
+    let mut h = Harness::new(2, 0);
+    h.write_sectors(7, 1, 0x01);
+    h.write_sectors(1, 1, 0x02);
+    h.write_sectors(7, 1, 0x03);
+    assert_eq!(h.disk.dirty_sectors(), 2, "rewriting a cached sector needs no space");
+    h.write_sectors(4, 1, 0x04);
+
+    assert_eq!(h.disk.dirty_sectors(), 2);
+    assert!(h.image_sector(7).iter().all(|&b| b == 0x03), "oldest sector written back");
+    assert!(h.image_sector(1).iter().all(|&b| b == 0xEE));
+    assert_eq!(h.disk.take_write_stats().flushed_sectors, 1);
+}
+
+#[test]
+fn image_writes_hold_back_completion() {
+    let mut h = Harness::new(0, 10);
+    h.write_sectors(0, 2, 0x33);
+    assert_eq!(h.status(), 0);
+    assert_eq!(h.used_idx(), 0, "completion waits for the image writes");
+    for _ in 0..19 {
+        assert!(!h.disk.tick());
+    }
+    assert_eq!(h.used_idx(), 0);
+    assert!(h.disk.tick(), "interrupt raised when both sectors are written");
+    assert_eq!(h.used_idx(), 1);
+}
+
+#[test]
+fn cached_write_completes_at_once_and_flush_pays_latency() {
+    let mut h = Harness::new(4, 10);
+    h.write_sectors(0, 1, 0x44);
+    assert_eq!(h.used_idx(), 1, "cached write acknowledged without latency");
+
+    h.submit(T_FLUSH, 0, 0);
+    assert_eq!(h.used_idx(), 1);
+    for _ in 0..9 {
+        let _ = h.disk.tick();
+    }
+    assert_eq!(h.used_idx(), 1);
+    let _ = h.disk.tick();
+    assert_eq!(h.used_idx(), 2);
+}
+
+#[test]
+fn unsupported_request_type_returns_unsupp() {
+    let mut h = Harness::new(0, 0);
+    h.submit(T_GET_ID, 0, 20);
+    assert_eq!(h.status(), 2);
+    assert_eq!(h.used_idx(), 1);
+}
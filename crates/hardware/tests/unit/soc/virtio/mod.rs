@@ -1,2 +1,6 @@
 pub mod disk_operations;
+pub mod disk_overlay;
+pub mod disk_write_cache;
+pub mod net;
 pub mod queue_descriptors;
+pub mod rng;
@@ -0,0 +1,176 @@
+//! VirtIO Network Device Tests.
+//!
+//! Checks the device identity registers, delivery of host-injected frames
+//! into posted receive buffers, the loopback backend, and a guest program
+//! transmitting a frame that the host collects from the capture backend.
+
+use crate::common::builder::instruction::InstructionBuilder;
+use crate::common::harness::TestContext;
+use rvsim_core::common::{PhysAddr, RegIdx};
+use rvsim_core::config::{Config, NetBackend};
+use rvsim_core::core::pipeline::engine::BackendType;
+use rvsim_core::soc::devices::Device;
+use rvsim_core::soc::devices::virtio_net::{DEFAULT_MAC, NET_HDR_SIZE, VirtioNet};
+use rvsim_core::soc::memory::buffer::DramBuffer;
+use std::sync::Arc;
+
+const RAM_BASE: u64 = 0x8000_0000;
+
+/// Offsets from the start of RAM of the virtqueue structures and buffers.
+const DESC: u64 = 0x1000;
+const AVAIL: u64 = 0x1100;
+const USED: u64 = 0x1200;
+const BUFFER: u64 = 0x1300;
+
+/// An Ethernet broadcast frame: destination, source, EtherType, payload.
+fn test_frame() -> Vec<u8> {
+    let mut frame = vec![0xff; 6];
+    frame.extend_from_slice(&DEFAULT_MAC);
+    frame.extend_from_slice(&[0x88, 0xb5]);
+    frame.extend_from_slice(b"hello, network");
+    frame
+}
+
+fn make_net(backend: &NetBackend) -> (VirtioNet, Arc<DramBuffer>) {
+    let ram = Arc::new(DramBuffer::new(0x10000));
+    let net = VirtioNet::new(0x9000_1000, RAM_BASE, Arc::clone(&ram), backend).unwrap();
+    (net, ram)
+}
+
+/// Programs queue `q` with the structures at `DESC`/`AVAIL`/`USED` + `0x400 * q`.
+fn setup_queue(net: &mut VirtioNet, q: u32) {
+    let off = 0x400 * u64::from(q);
+    net.write_u32(0x30, q);
+    net.write_u32(0x38, 16);
+    net.write_u32(0x80, (RAM_BASE + DESC + off) as u32);
+    net.write_u32(0x90, (RAM_BASE + AVAIL + off) as u32);
+    net.write_u32(0xa0, (RAM_BASE + USED + off) as u32);
+    net.write_u32(0x44, 1);
+}
+
+/// Posts a single-descriptor chain of `len` bytes at `BUFFER` + `0x400 * q`.
+fn post_buffer(ram: &DramBuffer, q: u32, len: u32, write: bool) {
+    let off = 0x400 * q as usize;
+    let desc = DESC as usize + off;
+    ram.write_slice(desc, &(RAM_BASE + BUFFER + off as u64).to_le_bytes());
+    ram.write_slice(desc + 8, &len.to_le_bytes());
+    ram.write_slice(desc + 12, &(if write { 2u16 } else { 0 }).to_le_bytes());
+    let avail = AVAIL as usize + off;
+    ram.write_slice(avail + 4, &0u16.to_le_bytes());
+    ram.write_slice(avail + 2, &1u16.to_le_bytes());
+}
+
+fn used_entry(ram: &DramBuffer, q: u32) -> (u16, u32) {
+    let used = USED as usize + 0x400 * q as usize;
+    let idx = ram.read_slice(used + 2, 2);
+    let len = ram.read_slice(used + 8, 4);
+    (u16::from_le_bytes([idx[0], idx[1]]), u32::from_le_bytes([len[0], len[1], len[2], len[3]]))
+}
+
+#[test]
+fn identity_and_mac_in_config_space() {
+    let (mut net, _) = make_net(&NetBackend::Capture);
+    assert_eq!(net.read_u32(0x00), 0x7472_6976);
+    assert_eq!(net.read_u32(0x08), 1);
+    net.write_u32(0x14, 0);
+    assert_ne!(net.read_u32(0x10) & (1 << 5), 0, "VIRTIO_NET_F_MAC offered");
+    let mac: Vec<u8> = (0..6).map(|i| net.read_u8(0x100 + i)).collect();
+    assert_eq!(mac, DEFAULT_MAC);
+}
+
+#[test]
+fn injected_frame_fills_posted_rx_buffer() {
+    let (mut net, ram) = make_net(&NetBackend::Capture);
+    let frame = test_frame();
+    net.inject_frame(frame.clone());
+    assert!(!net.tick(), "no interrupt before a buffer is posted");
+    assert_eq!(net.rx_pending(), 1);
+
+    setup_queue(&mut net, 0);
+    post_buffer(&ram, 0, 1526, true);
+    assert!(net.tick(), "frame delivery raises the interrupt");
+    assert_eq!(net.rx_pending(), 0);
+
+    let total = NET_HDR_SIZE + frame.len();
+    assert_eq!(used_entry(&ram, 0), (1, total as u32));
+    let data = ram.read_slice(BUFFER as usize, total);
+    assert_eq!(data[10], 1, "num_buffers");
+    assert_eq!(&data[NET_HDR_SIZE..], frame.as_slice());
+
+    net.write_u32(0x64, 1);
+    assert!(!net.tick(), "acknowledged interrupt is cleared");
+}
+
+#[test]
+fn loopback_backend_receives_transmitted_frame() {
+    let (mut net, ram) = make_net(&NetBackend::Loopback);
+    setup_queue(&mut net, 0);
+    setup_queue(&mut net, 1);
+    post_buffer(&ram, 0, 1526, true);
+
+    let frame = test_frame();
+    let mut packet = vec![0u8; NET_HDR_SIZE];
+    packet.extend_from_slice(&frame);
+    ram.write_slice(BUFFER as usize + 0x400, &packet);
+    post_buffer(&ram, 1, packet.len() as u32, false);
+    net.write_u32(0x50, 1);
+
+    assert_eq!(used_entry(&ram, 1).0, 1, "TX chain returned");
+    assert!(net.take_tx_frames().is_empty(), "loopback does not capture");
+    assert!(net.tick());
+    assert_eq!(&ram.read_slice(BUFFER as usize + NET_HDR_SIZE, frame.len()), &frame.as_slice());
+}
+
+#[test]
+fn guest_transmits_frame_to_capture_backend() {
+    let b = InstructionBuilder::new;
+    let program = [
+        b().sw(5, 6, 0x30).build(),
+        b().sw(5, 7, 0x38).build(),
+        b().sw(5, 8, 0x80).build(),
+        b().sw(5, 9, 0x90).build(),
+        b().sw(5, 18, 0xa0).build(),
+        b().sw(5, 6, 0x44).build(),
+        b().sw(5, 6, 0x50).build(),
+        b().lw(19, 5, 0x60).build(),
+        b().addi(17, 0, 93).build(),
+        b().ecall().build(),
+    ];
+    let frame = test_frame();
+    let mut packet = vec![0u8; NET_HDR_SIZE];
+    packet.extend_from_slice(&frame);
+
+    for backend in [BackendType::InOrder, BackendType::OutOfOrder] {
+        let mut config = Config::default();
+        config.pipeline.backend = backend;
+        config.system.net = Some(NetBackend::Capture);
+        let base = config.system.ram_base;
+        let net_base = config.system.net_base;
+
+        let mut ctx = TestContext::with_config(&config);
+        let bus = &mut ctx.sim.cpu.bus.bus;
+        for (i, inst) in program.into_iter().enumerate() {
+            bus.write_u32(PhysAddr::new(base + 4 * i as u64), inst);
+        }
+        bus.load_binary_at(&packet, PhysAddr::new(base + BUFFER));
+        bus.write_u64(PhysAddr::new(base + DESC), base + BUFFER);
+        bus.write_u32(PhysAddr::new(base + DESC + 8), packet.len() as u32);
+        bus.write_u16(PhysAddr::new(base + AVAIL + 2), 1);
+
+        ctx.sim.cpu.pc = base;
+        ctx.sim.write_reg(RegIdx::new(5), net_base);
+        ctx.sim.write_reg(RegIdx::new(6), 1);
+        ctx.sim.write_reg(RegIdx::new(7), 16);
+        ctx.sim.write_reg(RegIdx::new(8), base + DESC);
+        ctx.sim.write_reg(RegIdx::new(9), base + AVAIL);
+        ctx.sim.write_reg(RegIdx::new(18), base + USED);
+        let mut sim = ctx.run_to_exit(10_000);
+        assert_eq!(sim.cpu.exit_code, Some(0), "{backend:?}");
+        assert_eq!(sim.cpu.regs.read(RegIdx::new(19)), 1, "{backend:?}: interrupt status");
+        let used_idx = sim.cpu.bus.bus.read_u16(PhysAddr::new(base + USED + 2));
+        assert_eq!(used_idx, 1, "{backend:?}");
+
+        let net = sim.cpu.bus.bus.virtio_net_mut().unwrap();
+        assert_eq!(net.take_tx_frames(), vec![frame.clone()], "{backend:?}");
+    }
+}
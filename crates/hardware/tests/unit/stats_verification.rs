@@ -153,5 +153,7 @@ fn stats_sections_constant_available() {
     assert!(STATS_SECTIONS.contains(&"instruction_mix"));
     assert!(STATS_SECTIONS.contains(&"branch"));
     assert!(STATS_SECTIONS.contains(&"memory"));
-    assert_eq!(STATS_SECTIONS.len(), 5);
+    assert!(STATS_SECTIONS.contains(&"occupancy"));
+    assert!(STATS_SECTIONS.contains(&"mmio"));
+    assert_eq!(STATS_SECTIONS.len(), 7);
 }
@@ -15,7 +15,7 @@ from importlib.metadata import version as _metadata_version
 from .config import Config
 from .experiment import Environment, Result
 from .isa import Disassemble, csr, reg
-from .objects import Cpu, Instruction, Simulator
+from .objects import Cpu, HangError, Instruction, RunStatus, Simulator
 from .pipeline import PipelineSnapshot
 from .stats import Stats, Table
 from .sweep import Sweep, SweepResults
@@ -75,6 +75,8 @@ __all__ = [
     "Cpu",
     "Simulator",
     "Instruction",
+    "RunStatus",
+    "HangError",
     "PipelineSnapshot",
     "Environment",
     "Result",
@@ -8,6 +8,7 @@ Usage::
 """
 
 import argparse
+import contextlib
 import os
 import pathlib
 import runpy
@@ -37,9 +38,57 @@ def _detect_mode(filepath: str) -> str:
         return "script"
     if ext == ".elf":
         return "binary"
+    try:
+        with open(filepath, "rb") as f:
+            if f.read(4) == b"\x7fELF":
+                return "binary"
+    except OSError:
+        pass
     return "kernel"
 
 
+@contextlib.contextmanager
+def _raw_stdin(enabled: bool):
+    """Put the terminal in raw mode so keystrokes reach the guest UART.
+
+    Output post-processing and signal keys stay on, so newlines render
+    normally and Ctrl-C still stops the simulator.
+    """
+    if not enabled or not sys.stdin.isatty():
+        yield
+        return
+    try:
+        import termios
+        import tty
+    except ImportError:  # not a POSIX terminal
+        yield
+        return
+    fd = sys.stdin.fileno()
+    saved = termios.tcgetattr(fd)
+    tty.setraw(fd)
+    attrs = termios.tcgetattr(fd)
+    attrs[1] |= termios.OPOST
+    attrs[3] |= termios.ISIG
+    termios.tcsetattr(fd, termios.TCSANOW, attrs)
+    try:
+        yield
+    finally:
+        termios.tcsetattr(fd, termios.TCSADRAIN, saved)
+
+
+def _restore(cpu, path) -> None:
+    """Restore ``cpu`` from the checkpoint at ``path``, if one was given."""
+    if path is None:
+        return
+    from ._cli import error
+
+    try:
+        cpu.restore(path)
+    except RuntimeError as e:
+        print(error(str(e)), file=sys.stderr)
+        sys.exit(1)
+
+
 def _find_bundled_binaries():
     pkg_dir = pathlib.Path(__file__).resolve().parent
     repo_root = pkg_dir.parent
@@ -145,6 +194,13 @@ def _print_help() -> None:
         "Python config file  [dim](must export config or get_config)[/dim]",
     )
     opt_table.add_row("--json [cyan]FILE[/cyan]", "write stats as JSON to FILE")
+    opt_table.add_row(
+        "--checkpoint [cyan]FILE[/cyan]",
+        "save a checkpoint to FILE when the run stops  [dim](e.g. at --limit)[/dim]",
+    )
+    opt_table.add_row(
+        "--restore [cyan]FILE[/cyan]", "resume from a checkpoint saved by --checkpoint"
+    )
     console.print(Padding(opt_table, (0, 2)))
     console.print()
 
@@ -158,6 +214,13 @@ def _print_help() -> None:
     ex_table.add_row("rvsim mandelbrot.elf --limit 5M", "stop after 5 million cycles")
     ex_table.add_row("rvsim mandelbrot.elf --quiet", "suppress all output")
     ex_table.add_row("rvsim mandelbrot.elf --json out.json", "save stats to JSON")
+    ex_table.add_row(
+        "rvsim mandelbrot.elf --limit 5M --checkpoint m.ckpt",
+        "stop after 5M cycles and save the machine",
+    )
+    ex_table.add_row(
+        "rvsim mandelbrot.elf --restore m.ckpt", "resume the saved run to completion"
+    )
     ex_table.add_row(
         "rvsim qsort.elf --config p550.py", "run with a custom pipeline config"
     )
@@ -213,6 +276,10 @@ def main() -> None:
             "  rvsim mandelbrot.elf --no-stats     run without printing stats\n"
             "  rvsim mandelbrot.elf --quiet        suppress all output including program stdout\n"
             "  rvsim mandelbrot.elf --json out.json  save stats to JSON\n"
+            "  rvsim mandelbrot.elf --limit 5M --checkpoint m.ckpt\n"
+            "                                      stop after 5M cycles, save the machine\n"
+            "  rvsim mandelbrot.elf --restore m.ckpt  resume the saved run\n"
+            "  rvsim --se wc.elf input.txt        run a Linux user program with arguments\n"
             "  rvsim experiment.py                 run a Python script via the rvsim API\n"
             "  rvsim list                          list bundled programs and benchmarks\n"
         ),
@@ -258,6 +325,33 @@ def main() -> None:
         default=None,
         help="write stats as JSON to FILE",
     )
+    parser.add_argument(
+        "--checkpoint",
+        metavar="FILE",
+        default=None,
+        help="save a checkpoint to FILE when the run stops without exiting "
+        "(e.g. at --limit)",
+    )
+    parser.add_argument(
+        "--checkpoint-microarch",
+        action="store_true",
+        default=False,
+        help="include cache, TLB and branch predictor contents in the checkpoint",
+    )
+    parser.add_argument(
+        "--restore",
+        metavar="FILE",
+        default=None,
+        help="resume from a checkpoint; the program and config must match the "
+        "run that saved it",
+    )
+    parser.add_argument(
+        "--se",
+        action="store_true",
+        default=False,
+        help="syscall emulation: run a static Linux user binary, passing it the "
+        "arguments that follow and the current directory as its file root",
+    )
     parser.add_argument("positional_args", nargs="*", help=argparse.SUPPRESS)
 
     args, remaining = parser.parse_known_args()
@@ -270,7 +364,11 @@ def main() -> None:
     mode = _detect_mode(target)
     extra_args = args.positional_args[1:] + remaining
 
-    if mode != "script" and extra_args:
+    if args.se and mode != "binary":
+        parser.error("--se needs an ELF binary")
+    if args.checkpoint_microarch and not args.checkpoint:
+        parser.error("--checkpoint-microarch needs --checkpoint")
+    if mode != "script" and extra_args and not args.se:
         parser.error(f"unrecognized arguments: {' '.join(extra_args)}")
 
     # ── Execute ───────────────────────────────────────────────────────────────
@@ -280,8 +378,9 @@ def main() -> None:
         runpy.run_path(target, run_name="__main__")
         return
 
+    from ._cli import error
     from .config import Config
-    from .objects import Simulator
+    from .objects import HangError, Simulator
 
     if args.config:
         sim_tmp = Simulator().config(args.config)
@@ -296,6 +395,10 @@ def main() -> None:
         cfg.uart_to_stderr = True
     elif mode == "kernel":
         cfg.uart_to_stderr = True
+    if args.se:
+        cfg.syscall_emulation = True
+        cfg.syscall_root = os.getcwd()
+        cfg.args = [target] + extra_args
 
     sim = Simulator().config(cfg)
 
@@ -315,17 +418,35 @@ def main() -> None:
             cpu = sim.build()
         finally:
             sys.stderr = _real_stderr
+        _restore(cpu, args.restore)
         print_stats = not args.quiet and not args.no_stats
-        exit_code = run_watch(
-            cpu,
-            limit=args.limit,
-            binary=os.path.basename(target),
-            print_stats=print_stats,
-        )
+        try:
+            exit_code = run_watch(
+                cpu,
+                limit=args.limit,
+                binary=os.path.basename(target),
+                print_stats=print_stats,
+            )
+        except HangError as e:
+            print(error(str(e)), file=sys.stderr)
+            sys.exit(1)
     else:
         stats_sections = None if (args.quiet or args.no_stats) else []
         cpu = sim.build()
-        exit_code = cpu.run(limit=args.limit, stats_sections=stats_sections)
+        _restore(cpu, args.restore)
+        try:
+            with _raw_stdin(mode == "kernel"):
+                exit_code = cpu.run(limit=args.limit, stats_sections=stats_sections)
+        except HangError as e:
+            print(error(str(e)), file=sys.stderr)
+            sys.exit(1)
+
+    if args.checkpoint and exit_code is None:
+        try:
+            cpu.checkpoint(args.checkpoint, microarch=args.checkpoint_microarch)
+        except RuntimeError as e:
+            print(error(str(e)), file=sys.stderr)
+            sys.exit(1)
 
     if args.json and exit_code is not None:
         import json
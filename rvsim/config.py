@@ -8,7 +8,7 @@ dict that the Rust backend expects.
 
 from __future__ import annotations
 
-from typing import Any, Dict, Optional
+from typing import Any, Dict, Mapping, Optional, Sequence, Tuple
 
 __all__ = ["Config"]
 
@@ -56,6 +56,16 @@ class Config:
         btb_size: int = 4096,
         btb_ways: int = 4,
         ras_size: int = 32,
+        ftq_size: int = 4,
+        fetch_buffer_size: int = 16,
+        fetch_width: int = 0,
+        mispredict_penalty: int = 0,
+        zacas: bool = False,
+        zicond: bool = False,
+        fusion: Sequence[str] = (),
+        ports: Optional[Mapping[str, int]] = None,
+        latencies: Optional[Mapping[str, Tuple[int, int]]] = None,
+        store_buffer_drain_rate: int = 0,
         # Caches (None = disabled)
         l1i=Cache("32KB", ways=4, latency=1, prefetcher=Prefetcher.NextLine(degree=1)),
         l1d=Cache(
@@ -68,30 +78,78 @@ class Config:
         l3: Optional[Cache] = None,
         inclusion_policy: Any = Cache.NINE(),
         wcb_entries: int = 0,
+        l1d_ports: int = 0,
         # Memory
         ram_size="256MB",
+        ram_regions: Optional[Sequence[Tuple[int, Any]]] = None,
+        safe_ram: bool = False,
+        ram_backing: str = "Anonymous",
+        ram_file: Optional[str] = None,
         memory_controller=None,
         tlb_size: int = 32,
         l2_tlb_size: int = 512,
         l2_tlb_ways: int = 4,
         l2_tlb_latency: int = 4,
         software_ad_bits: bool = True,
-        misaligned_access_trap: bool = False,
+        misaligned: str = "Emulate",
+        ecc: str = "None",
+        fault_rate: float = 0.0,
+        fault_seed: int = 0,
+        faults: Optional[Sequence[Tuple[int, int, int]]] = None,
         # General
         trace: bool = False,
+        trace_start_pc: Optional[int] = None,
+        trace_stop_pc: Optional[int] = None,
         initial_sp: Optional[int] = None,
+        initial_privilege: Optional[str] = None,
+        initial_satp: Optional[int] = None,
+        initial_mstatus: Optional[int] = None,
+        initial_csrs: Optional[Mapping[int, int]] = None,
+        pipe_trace: Optional[str] = None,
+        pipe_trace_format: str = "Konata",
+        mmio_trace: Optional[str] = None,
+        access_trace: Optional[str] = None,
+        access_trace_format: str = "Binary",
+        access_trace_sample: int = 1,
+        sample_occupancy: bool = True,
+        clock_mhz: int = 1000,
+        bandwidth_interval: int = 10_000,
+        hang_threshold: Optional[int] = None,
+        max_cycles: Optional[int] = None,
+        max_reboots: Optional[int] = None,
+        reboot_stats: str = "Accumulate",
+        semihosting: bool = False,
+        semihosting_root: Optional[str] = None,
+        syscall_emulation: bool = False,
+        syscall_root: Optional[str] = None,
+        args: Optional[Sequence[str]] = None,
+        fast_forward_insts: int = 0,
+        switch_pc: Optional[int] = None,
+        fast_forward_warm: bool = False,
         # System (advanced)
         ram_base: int = 0x8000_0000,
         uart_base: int = 0x1000_0000,
         disk_base: int = 0x9000_0000,
+        disk_write_cache_sectors: int = 0,
+        disk_write_latency: int = 0,
+        disk_copy_on_write: bool = False,
+        net: Optional[str] = None,
+        net_base: int = 0x9000_1000,
+        rng_base: int = 0x9000_2000,
+        rng_seed: int = 0,
+        dma: bool = False,
+        dma_base: int = 0x9000_3000,
+        dma_bytes_per_cycle: int = 8,
         clint_base: int = 0x0200_0000,
         syscon_base: int = 0x0010_0000,
         kernel_offset: int = 0x0020_0000,
         bus_width: int = 8,
         bus_latency: int = 4,
         clint_divider: int = 10,
+        num_harts: int = 1,
         uart_to_stderr: bool = False,
         uart_quiet: bool = False,
+        devices: Optional[Sequence[Dict[str, Any]]] = None,
     ):
         # Pipeline
         self.width = width
@@ -101,6 +159,27 @@ class Config:
         self.btb_size = btb_size
         self.btb_ways = btb_ways
         self.ras_size = ras_size
+        self.ftq_size = ftq_size
+        self.fetch_buffer_size = fetch_buffer_size
+        self.fetch_width = fetch_width
+        self.mispredict_penalty = mispredict_penalty
+        self.zacas = zacas
+        self.zicond = zicond
+        self.fusion = tuple(fusion)
+        self.ports = dict(ports or {})
+        unknown = set(self.ports) - set(_PORT_CLASSES)
+        if unknown:
+            raise ValueError(
+                f"unknown issue port class(es) {sorted(unknown)}; "
+                f"expected one of {list(_PORT_CLASSES)}"
+            )
+        self.latencies = {k: tuple(v) for k, v in (latencies or {}).items()}
+        unknown = set(self.latencies) - set(_OP_CLASSES)
+        if unknown:
+            raise ValueError(
+                f"unknown op class(es) {sorted(unknown)}; expected one of {list(_OP_CLASSES)}"
+            )
+        self.store_buffer_drain_rate = store_buffer_drain_rate
 
         # Caches
         self.l1i = l1i
@@ -109,9 +188,18 @@ class Config:
         self.l3 = l3
         self.inclusion_policy = inclusion_policy
         self.wcb_entries = wcb_entries
+        self.l1d_ports = l1d_ports
 
         # Memory
         self.ram_size = _parse_size(ram_size)
+        self.ram_regions = (
+            tuple((base, _parse_size(size)) for base, size in ram_regions)
+            if ram_regions is not None
+            else None
+        )
+        self.safe_ram = safe_ram
+        self.ram_backing = ram_backing
+        self.ram_file = ram_file
         self.memory_controller = (
             memory_controller
             if memory_controller is not None
@@ -122,24 +210,67 @@ class Config:
         self.l2_tlb_ways = l2_tlb_ways
         self.l2_tlb_latency = l2_tlb_latency
         self.software_ad_bits = software_ad_bits
-        self.misaligned_access_trap = misaligned_access_trap
+        self.misaligned = misaligned
+        self.ecc = ecc
+        self.fault_rate = fault_rate
+        self.fault_seed = fault_seed
+        self.faults = tuple(faults) if faults is not None else None
 
         # General
         self.trace = trace
+        self.trace_start_pc = trace_start_pc
+        self.trace_stop_pc = trace_stop_pc
         self.initial_sp = initial_sp
+        self.initial_privilege = initial_privilege
+        self.initial_satp = initial_satp
+        self.initial_mstatus = initial_mstatus
+        self.initial_csrs = dict(initial_csrs) if initial_csrs is not None else None
+        self.pipe_trace = pipe_trace
+        self.pipe_trace_format = pipe_trace_format
+        self.mmio_trace = mmio_trace
+        self.access_trace = access_trace
+        self.access_trace_format = access_trace_format
+        self.access_trace_sample = access_trace_sample
+        self.sample_occupancy = sample_occupancy
+        self.clock_mhz = clock_mhz
+        self.bandwidth_interval = bandwidth_interval
+        self.hang_threshold = hang_threshold
+        self.max_cycles = max_cycles
+        self.max_reboots = max_reboots
+        self.reboot_stats = reboot_stats
+        self.semihosting = semihosting
+        self.semihosting_root = semihosting_root
+        self.syscall_emulation = syscall_emulation
+        self.syscall_root = syscall_root
+        self.args = list(args) if args is not None else []
+        self.fast_forward_insts = fast_forward_insts
+        self.switch_pc = switch_pc
+        self.fast_forward_warm = fast_forward_warm
 
         # System
         self.ram_base = ram_base
         self.uart_base = uart_base
         self.disk_base = disk_base
+        self.disk_write_cache_sectors = disk_write_cache_sectors
+        self.disk_write_latency = disk_write_latency
+        self.disk_copy_on_write = disk_copy_on_write
+        self.net = net
+        self.net_base = net_base
+        self.rng_base = rng_base
+        self.rng_seed = rng_seed
+        self.dma = dma
+        self.dma_base = dma_base
+        self.dma_bytes_per_cycle = dma_bytes_per_cycle
         self.clint_base = clint_base
         self.syscon_base = syscon_base
         self.kernel_offset = kernel_offset
         self.bus_width = bus_width
         self.bus_latency = bus_latency
         self.clint_divider = clint_divider
+        self.num_harts = num_harts
         self.uart_to_stderr = uart_to_stderr
         self.uart_quiet = uart_quiet
+        self.devices = tuple(dict(d) for d in devices) if devices is not None else None
 
     def to_dict(self) -> Dict[str, Any]:
         """Produce the nested dict expected by the Rust backend."""
@@ -162,33 +293,91 @@ class Config:
             btb_size=self.btb_size,
             btb_ways=self.btb_ways,
             ras_size=self.ras_size,
+            ftq_size=self.ftq_size,
+            fetch_buffer_size=self.fetch_buffer_size,
+            fetch_width=self.fetch_width,
+            mispredict_penalty=self.mispredict_penalty,
+            zacas=self.zacas,
+            zicond=self.zicond,
+            fusion=self.fusion,
+            ports=self.ports,
+            latencies=self.latencies,
+            store_buffer_drain_rate=self.store_buffer_drain_rate,
             l1i=self.l1i,
             l1d=self.l1d,
             l2=self.l2,
             l3=self.l3,
             inclusion_policy=self.inclusion_policy,
             wcb_entries=self.wcb_entries,
+            l1d_ports=self.l1d_ports,
             ram_size=self.ram_size,
+            ram_regions=self.ram_regions,
+            safe_ram=self.safe_ram,
+            ram_backing=self.ram_backing,
+            ram_file=self.ram_file,
             memory_controller=self.memory_controller,
             tlb_size=self.tlb_size,
             l2_tlb_size=self.l2_tlb_size,
             l2_tlb_ways=self.l2_tlb_ways,
             l2_tlb_latency=self.l2_tlb_latency,
             software_ad_bits=self.software_ad_bits,
-            misaligned_access_trap=self.misaligned_access_trap,
+            misaligned=self.misaligned,
+            ecc=self.ecc,
+            fault_rate=self.fault_rate,
+            fault_seed=self.fault_seed,
+            faults=self.faults,
             trace=self.trace,
+            trace_start_pc=self.trace_start_pc,
+            trace_stop_pc=self.trace_stop_pc,
             initial_sp=self.initial_sp,
+            initial_privilege=self.initial_privilege,
+            initial_satp=self.initial_satp,
+            initial_mstatus=self.initial_mstatus,
+            initial_csrs=self.initial_csrs,
+            pipe_trace=self.pipe_trace,
+            pipe_trace_format=self.pipe_trace_format,
+            mmio_trace=self.mmio_trace,
+            access_trace=self.access_trace,
+            access_trace_format=self.access_trace_format,
+            access_trace_sample=self.access_trace_sample,
+            sample_occupancy=self.sample_occupancy,
+            clock_mhz=self.clock_mhz,
+            bandwidth_interval=self.bandwidth_interval,
+            hang_threshold=self.hang_threshold,
+            max_cycles=self.max_cycles,
+            max_reboots=self.max_reboots,
+            reboot_stats=self.reboot_stats,
+            semihosting=self.semihosting,
+            semihosting_root=self.semihosting_root,
+            syscall_emulation=self.syscall_emulation,
+            syscall_root=self.syscall_root,
+            args=self.args,
+            fast_forward_insts=self.fast_forward_insts,
+            switch_pc=self.switch_pc,
+            fast_forward_warm=self.fast_forward_warm,
             ram_base=self.ram_base,
             uart_base=self.uart_base,
             disk_base=self.disk_base,
+            disk_write_cache_sectors=self.disk_write_cache_sectors,
+            disk_write_latency=self.disk_write_latency,
+            disk_copy_on_write=self.disk_copy_on_write,
+            net=self.net,
+            net_base=self.net_base,
+            rng_base=self.rng_base,
+            rng_seed=self.rng_seed,
+            dma=self.dma,
+            dma_base=self.dma_base,
+            dma_bytes_per_cycle=self.dma_bytes_per_cycle,
             clint_base=self.clint_base,
             syscon_base=self.syscon_base,
             kernel_offset=self.kernel_offset,
             bus_width=self.bus_width,
             bus_latency=self.bus_latency,
             clint_divider=self.clint_divider,
+            num_harts=self.num_harts,
             uart_to_stderr=self.uart_to_stderr,
             uart_quiet=self.uart_quiet,
+            devices=self.devices,
         )
         unknown = set(kwargs) - set(fields)
         if unknown:
@@ -213,6 +402,43 @@ class Config:
         return f"Config({', '.join(parts)})"
 
 
+def _net_backend(net: Optional[str]) -> Any:
+    """Map ``"Capture"``, ``"Loopback"`` or ``"Tap:<ifname>"`` to the Rust enum form."""
+    if net is None:
+        return None
+    kind, _, ifname = net.partition(":")
+    if kind == "Tap":
+        if not ifname:
+            raise ValueError('net="Tap" needs an interface name, e.g. "Tap:tap0"')
+        return {"Tap": ifname}
+    if kind not in ("Capture", "Loopback") or ifname:
+        raise ValueError(
+            f'unknown net backend {net!r}: expected "Capture", "Loopback" or "Tap:<ifname>"'
+        )
+    return kind
+
+
+_PRIVILEGE_NAMES = {"M": "Machine", "S": "Supervisor", "U": "User"}
+
+
+def _privilege_name(mode: str) -> str:
+    """Map ``"M"``/``"S"``/``"U"`` or a full mode name to the Rust enum name."""
+    name = _PRIVILEGE_NAMES.get(mode, mode)
+    if name not in _PRIVILEGE_NAMES.values():
+        raise ValueError(
+            f"unknown privilege {mode!r}: expected \"M\", \"S\", \"U\" or a full mode name"
+        )
+    return name
+
+
+def _device(dev: Dict[str, Any]) -> Dict[str, Any]:
+    """Copy a ``devices`` entry, mapping a ``VirtioNet`` backend string as for ``net``."""
+    out = dict(dev)
+    if isinstance(out.get("backend"), str):
+        out["backend"] = _net_backend(out["backend"])
+    return out
+
+
 def _config_to_dict(config) -> Dict[str, Any]:
     """Normalize config to a dict for the Rust backend. Accepts Config or plain dict."""
     if hasattr(config, "to_dict") and callable(getattr(config, "to_dict")):
@@ -417,6 +643,7 @@ def _fu_config_to_dict(fc: Fu) -> dict:
         "int_mul_latency": 3,
         "num_int_div": 0,
         "int_div_latency": 35,
+        "int_div_bits_per_cycle": 2,
         "num_fp_add": 0,
         "fp_add_latency": 4,
         "num_fp_mul": 0,
@@ -440,6 +667,7 @@ def _fu_config_to_dict(fc: Fu) -> dict:
         elif isinstance(u, Fu.IntDiv):
             d["num_int_div"] = u.count
             d["int_div_latency"] = u.latency
+            d["int_div_bits_per_cycle"] = u.bits_per_cycle
         elif isinstance(u, Fu.FpAdd):
             d["num_fp_add"] = u.count
             d["fp_add_latency"] = u.latency
@@ -488,7 +716,9 @@ def _backend_to_pipeline_fields(be) -> dict:
         "store_ports": 1,
         "prf_gpr_size": 64,
         "prf_fpr_size": 64,
-        "fu_config": _fu_config_to_dict(Fu()),
+        "fu_config": _fu_config_to_dict(be.fu_config),
+        "checkpoint_count": be.checkpoint_count,
+        "bypass": be.bypass,
     }
 
 
@@ -510,6 +740,23 @@ def _cache_to_dict(c: Cache) -> Dict[str, Any]:
     return d
 
 
+_PORT_CLASSES = ("alu", "fpu", "branch", "mem")
+
+_OP_CLASSES = (
+    "int_alu",
+    "int_mul",
+    "int_div",
+    "fp_add",
+    "fp_mul",
+    "fp_fma",
+    "fp_div",
+    "fp_sqrt",
+    "fp_cvt",
+    "fp_misc",
+    "branch",
+    "mem",
+)
+
 _TAGE_DEFAULTS = {
     "num_banks": 8,
     "table_size": 2048,
@@ -538,37 +785,98 @@ def _config_to_dict_impl(cfg: Config) -> Dict[str, Any]:
         "trace_instructions": cfg.trace,
         "start_pc": _START_PC_DEFAULT,
         "direct_mode": True,
+        "sample_occupancy": cfg.sample_occupancy,
+        "clock_mhz": cfg.clock_mhz,
+        "bandwidth_interval": cfg.bandwidth_interval,
     }
+    if cfg.trace_start_pc is not None:
+        general["trace_start_pc"] = cfg.trace_start_pc
+    if cfg.trace_stop_pc is not None:
+        general["trace_stop_pc"] = cfg.trace_stop_pc
     if cfg.initial_sp is not None:
         general["initial_sp"] = cfg.initial_sp
+    if cfg.pipe_trace is not None:
+        general["pipe_trace"] = cfg.pipe_trace
+        general["pipe_trace_format"] = cfg.pipe_trace_format
+    if cfg.mmio_trace is not None:
+        general["mmio_trace"] = cfg.mmio_trace
+    if cfg.access_trace is not None:
+        general["access_trace"] = cfg.access_trace
+        general["access_trace_format"] = cfg.access_trace_format
+        general["access_trace_sample"] = cfg.access_trace_sample
+    if cfg.hang_threshold is not None:
+        general["hang_threshold"] = cfg.hang_threshold
+    if cfg.max_cycles is not None:
+        general["max_cycles"] = cfg.max_cycles
+    if cfg.max_reboots is not None:
+        general["max_reboots"] = cfg.max_reboots
+    general["reboot_stats"] = cfg.reboot_stats
+    general["semihosting"] = cfg.semihosting
+    if cfg.semihosting_root is not None:
+        general["semihosting_root"] = cfg.semihosting_root
+    general["syscall_emulation"] = cfg.syscall_emulation
+    if cfg.syscall_root is not None:
+        general["syscall_root"] = cfg.syscall_root
+    general["args"] = list(cfg.args)
+    general["fast_forward_insts"] = cfg.fast_forward_insts
+    if cfg.switch_pc is not None:
+        general["switch_pc"] = cfg.switch_pc
+    general["fast_forward_warm"] = cfg.fast_forward_warm
 
     # System
     system = {
         "ram_base": cfg.ram_base,
         "uart_base": cfg.uart_base,
         "disk_base": cfg.disk_base,
+        "disk_write_cache_sectors": cfg.disk_write_cache_sectors,
+        "disk_write_latency": cfg.disk_write_latency,
+        "disk_copy_on_write": cfg.disk_copy_on_write,
+        "net_base": cfg.net_base,
+        "net": _net_backend(cfg.net),
+        "rng_base": cfg.rng_base,
+        "rng_seed": cfg.rng_seed,
+        "dma": cfg.dma,
+        "dma_base": cfg.dma_base,
+        "dma_bytes_per_cycle": cfg.dma_bytes_per_cycle,
         "clint_base": cfg.clint_base,
         "syscon_base": cfg.syscon_base,
         "kernel_offset": cfg.kernel_offset,
         "bus_width": cfg.bus_width,
         "bus_latency": cfg.bus_latency,
         "clint_divider": cfg.clint_divider,
+        "num_harts": cfg.num_harts,
         "uart_to_stderr": cfg.uart_to_stderr,
         "uart_quiet": cfg.uart_quiet,
         "tohost_addr": 0,
+        "devices": [_device(d) for d in cfg.devices or ()],
     }
 
     # Memory — merge controller-specific params
     mc = cfg.memory_controller
     memory: Dict[str, Any] = {
         "ram_size": cfg.ram_size,
+        "regions": [
+            {"base": base, "size": size} for base, size in cfg.ram_regions or ()
+        ],
+        "safe_ram": cfg.safe_ram,
+        "ram_backing": cfg.ram_backing,
+        "ram_file": cfg.ram_file,
         "controller": _mc_name(mc),
         "tlb_size": cfg.tlb_size,
         "l2_tlb_size": cfg.l2_tlb_size,
         "l2_tlb_ways": cfg.l2_tlb_ways,
         "l2_tlb_latency": cfg.l2_tlb_latency,
         "software_ad_bits": cfg.software_ad_bits,
-        "misaligned_access_trap": cfg.misaligned_access_trap,
+        "misaligned": cfg.misaligned,
+        "fault_injection": {
+            "rate": cfg.fault_rate,
+            "seed": cfg.fault_seed,
+            "faults": [
+                {"addr": addr, "bit": bit, "cycle": cycle}
+                for addr, bit, cycle in cfg.faults or ()
+            ],
+            "ecc": cfg.ecc,
+        },
     }
     # Always emit DRAM timing keys (Rust expects them)
     if isinstance(mc, MemoryController.DRAM):
@@ -576,6 +884,13 @@ def _config_to_dict_impl(cfg: Config) -> Dict[str, Any]:
         memory["t_ras"] = mc.t_ras
         memory["t_pre"] = mc.t_pre
         memory["row_miss_latency"] = mc.row_miss_latency
+        memory["t_rcd"] = mc.t_rcd
+        memory["num_channels"] = mc.num_channels
+        memory["num_banks"] = mc.num_banks
+        memory["num_bank_groups"] = mc.num_bank_groups
+        memory["scheduler"] = mc.scheduler
+        memory["starvation_limit"] = mc.starvation_limit
+        memory["write_drain_threshold"] = mc.write_drain_threshold
     else:
         memory["t_cas"] = 14
         memory["t_ras"] = 14
@@ -598,6 +913,7 @@ def _config_to_dict_impl(cfg: Config) -> Dict[str, Any]:
         ),
         "inclusion_policy": _inclusion_policy_name(cfg.inclusion_policy),
         "wcb_entries": cfg.wcb_entries,
+        "l1_d_ports": cfg.l1d_ports,
     }
 
     # Pipeline — always emit all BP sub-configs with defaults
@@ -634,6 +950,16 @@ def _config_to_dict_impl(cfg: Config) -> Dict[str, Any]:
         "btb_size": cfg.btb_size,
         "btb_ways": cfg.btb_ways,
         "ras_size": cfg.ras_size,
+        "ftq_size": cfg.ftq_size,
+        "fetch_buffer_size": cfg.fetch_buffer_size,
+        "fetch_width": cfg.fetch_width,
+        "mispredict_penalty": cfg.mispredict_penalty,
+        "zacas": cfg.zacas,
+        "zicond": cfg.zicond,
+        "fusion": list(cfg.fusion),
+        "ports": dict(cfg.ports),
+        "latencies": {k: list(v) for k, v in cfg.latencies.items()},
+        "store_buffer_drain_rate": cfg.store_buffer_drain_rate,
         "backend": _backend_name(cfg.backend),
         "tage": tage_dict,
         "perceptron": perceptron_dict,
@@ -645,10 +971,20 @@ def _config_to_dict_impl(cfg: Config) -> Dict[str, Any]:
         **_backend_to_pipeline_fields(cfg.backend),
     }
 
+    # Initial hart state
+    initial_state: Dict[str, Any] = {"csrs": dict(cfg.initial_csrs or {})}
+    if cfg.initial_privilege is not None:
+        initial_state["privilege"] = _privilege_name(cfg.initial_privilege)
+    if cfg.initial_satp is not None:
+        initial_state["satp"] = cfg.initial_satp
+    if cfg.initial_mstatus is not None:
+        initial_state["mstatus"] = cfg.initial_mstatus
+
     return {
         "general": general,
         "system": system,
         "memory": memory,
         "cache": cache,
         "pipeline": pipeline,
+        "initial_state": initial_state,
     }
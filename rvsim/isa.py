@@ -24,6 +24,7 @@ class Disassemble:
         Disassemble().binary("qsort.bin").at(0x80000024, count=10).print()
         Disassemble().bytes(data).print()
         Disassemble().inst(0x00a00513)  # single instruction -> str
+        Disassemble().numeric().inst(0x00a00513)  # "addi x10, x0, 10"
     """
 
     def __init__(self):
@@ -31,6 +32,7 @@ class Disassemble:
         self._base: int = 0x8000_0000
         self._offset: int = 0
         self._count: Optional[int] = None
+        self._numeric: bool = False
 
     def binary(self, path: str) -> "Disassemble":
         with open(path, "rb") as f:
@@ -54,8 +56,13 @@ class Disassemble:
         self._count = n
         return self
 
+    def numeric(self, enabled: bool = True) -> "Disassemble":
+        """Print raw mnemonics, xN/fN registers and hex CSR addresses."""
+        self._numeric = enabled
+        return self
+
     def inst(self, raw: int) -> str:
-        return disassemble(raw)
+        return disassemble(raw, self._numeric)
 
     def decode(self) -> List[Tuple[int, int, str]]:
         if self._data is None:
@@ -71,13 +78,13 @@ class Disassemble:
             half = struct.unpack_from("<H", self._data, off)[0]
             if half & 0x3 != 0x3:
                 # 16-bit compressed instruction
-                asm = disassemble(half)
+                asm = disassemble(half, self._numeric)
                 result.append((self._base + off, half, asm))
                 off += 2
             elif off + 3 < end:
                 # 32-bit instruction
                 inst = struct.unpack_from("<I", self._data, off)[0]
-                asm = disassemble(inst)
+                asm = disassemble(inst, self._numeric)
                 result.append((self._base + off, inst, asm))
                 off += 4
             else:
@@ -5,6 +5,9 @@ Provides:
 - Cpu: Native Rust CPU class with .pc, .regs[i], .csrs[name], .mem32[addr], .stats, .run()
 - Simulator: Fluent API (config/kernel/disk/binary/run).
 - Instruction: Returned by cpu.step() with pc, raw, asm, cycles.
+- RunStatus: Returned by cpu.run_until(), cpu.run_for(), cpu.run_functional()
+  and cpu.run_detailed().
+- HangError: Raised when the watchdog (hang_threshold / max_cycles) fires.
 """
 
 from __future__ import annotations
@@ -14,10 +17,10 @@ import os
 import sys
 from typing import Optional
 
-__all__ = ["Cpu", "Simulator", "Instruction"]
+__all__ = ["Cpu", "Simulator", "Instruction", "RunStatus", "HangError"]
 
 from ._cli import info, warn, error
-from ._core import Cpu, Instruction
+from ._core import Cpu, HangError, Instruction, RunStatus
 from .config import Config, _config_to_dict
 
 _UNSET = object()
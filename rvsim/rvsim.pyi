@@ -1,6 +1,6 @@
 """Type stubs for rvsim."""
 
-from typing import Any, Dict, List, Optional, Union
+from typing import Any, Callable, Dict, List, Mapping, Optional, Sequence, Tuple, Union
 
 # ── pipeline.py ───────────────────────────────────────────────────────────────
 
@@ -101,12 +101,26 @@ class MemoryController:
         t_ras: int
         t_pre: int
         row_miss_latency: int
+        t_rcd: int
+        num_channels: int
+        num_banks: int
+        num_bank_groups: int
+        scheduler: str
+        starvation_limit: int
+        write_drain_threshold: int
         def __init__(
             self,
             t_cas: int = 14,
             t_ras: int = 14,
             t_pre: int = 14,
             row_miss_latency: int = 120,
+            t_rcd: int = 14,
+            num_channels: int = 1,
+            num_banks: int = 8,
+            num_bank_groups: int = 1,
+            scheduler: str = "Immediate",
+            starvation_limit: int = 200,
+            write_drain_threshold: int = 8,
         ) -> None: ...
 
 class Fu:
@@ -123,7 +137,8 @@ class Fu:
     class IntDiv:
         count: int
         latency: int
-        def __init__(self, count: int = 1, latency: int = 35) -> None: ...
+        bits_per_cycle: int
+        def __init__(self, count: int = 1, latency: int = 35, bits_per_cycle: int = 2) -> None: ...
 
     class FpAdd:
         count: int
@@ -159,7 +174,10 @@ class Fu:
     def __init__(self, units: Optional[List[Any]] = None) -> None: ...
 
 class Backend:
-    class InOrder: ...
+    class InOrder:
+        fu_config: Fu
+        checkpoint_count: int
+        def __init__(self, fu_config: Optional[Fu] = None, checkpoint_count: int = 0) -> None: ...
 
     class OutOfOrder:
         rob_size: int
@@ -211,26 +229,85 @@ class Config:
     backend: Any
     btb_size: int
     ras_size: int
+    ftq_size: int
+    fetch_buffer_size: int
+    fetch_width: int
+    mispredict_penalty: int
+    zacas: bool
+    zicond: bool
+    fusion: Tuple[str, ...]
+    ports: Dict[str, int]
+    latencies: Dict[str, Tuple[int, int]]
+    store_buffer_drain_rate: int
     l1i: Optional[Cache]
     l1d: Optional[Cache]
     l2: Optional[Cache]
     l3: Optional[Cache]
+    l1d_ports: int
     ram_size: int
+    ram_regions: Optional[Tuple[Tuple[int, int], ...]]
+    safe_ram: bool
+    ram_backing: str
+    ram_file: Optional[str]
     memory_controller: Any
     tlb_size: int
+    misaligned: str
+    ecc: str
+    fault_rate: float
+    fault_seed: int
+    faults: Optional[Tuple[Tuple[int, int, int], ...]]
     trace: bool
+    trace_start_pc: Optional[int]
+    trace_stop_pc: Optional[int]
     initial_sp: Optional[int]
+    initial_privilege: Optional[str]
+    initial_satp: Optional[int]
+    initial_mstatus: Optional[int]
+    initial_csrs: Optional[Dict[int, int]]
+    pipe_trace: Optional[str]
+    pipe_trace_format: str
+    mmio_trace: Optional[str]
+    access_trace: Optional[str]
+    access_trace_format: str
+    access_trace_sample: int
+    sample_occupancy: bool
+    clock_mhz: int
+    bandwidth_interval: int
+    hang_threshold: Optional[int]
+    max_cycles: Optional[int]
+    max_reboots: Optional[int]
+    reboot_stats: str
+    semihosting: bool
+    semihosting_root: Optional[str]
+    syscall_emulation: bool
+    syscall_root: Optional[str]
+    args: List[str]
+    fast_forward_insts: int
+    switch_pc: Optional[int]
+    fast_forward_warm: bool
     ram_base: int
     uart_base: int
     disk_base: int
+    disk_write_cache_sectors: int
+    disk_write_latency: int
+    disk_copy_on_write: bool
+    net: Optional[str]
+    net_base: int
+    rng_base: int
+    rng_seed: int
+    dma: bool
+    dma_base: int
+    dma_bytes_per_cycle: int
     clint_base: int
     syscon_base: int
     kernel_offset: int
     bus_width: int
     bus_latency: int
     clint_divider: int
+    num_harts: int
     uart_to_stderr: bool
     uart_quiet: bool
+    devices: Optional[Tuple[Dict[str, Any], ...]]
     def __init__(
         self,
         width: int = 1,
@@ -238,26 +315,85 @@ class Config:
         backend: Any = None,
         btb_size: int = 4096,
         ras_size: int = 32,
+        ftq_size: int = 4,
+        fetch_buffer_size: int = 16,
+        fetch_width: int = 0,
+        mispredict_penalty: int = 0,
+        zacas: bool = False,
+        zicond: bool = False,
+        fusion: Sequence[str] = (),
+        ports: Optional[Mapping[str, int]] = None,
+        latencies: Optional[Mapping[str, Tuple[int, int]]] = None,
+        store_buffer_drain_rate: int = 0,
         l1i: Optional[Cache] = None,
         l1d: Optional[Cache] = None,
         l2: Optional[Cache] = None,
         l3: Optional[Cache] = None,
+        l1d_ports: int = 0,
         ram_size: str | int = "256MB",
+        ram_regions: Optional[Sequence[Tuple[int, str | int]]] = None,
+        safe_ram: bool = False,
+        ram_backing: str = "Anonymous",
+        ram_file: Optional[str] = None,
         memory_controller: Any = None,
         tlb_size: int = 32,
+        misaligned: str = "Emulate",
+        ecc: str = "None",
+        fault_rate: float = 0.0,
+        fault_seed: int = 0,
+        faults: Optional[Sequence[Tuple[int, int, int]]] = None,
         trace: bool = False,
+        trace_start_pc: Optional[int] = None,
+        trace_stop_pc: Optional[int] = None,
         initial_sp: Optional[int] = None,
+        initial_privilege: Optional[str] = None,
+        initial_satp: Optional[int] = None,
+        initial_mstatus: Optional[int] = None,
+        initial_csrs: Optional[Mapping[int, int]] = None,
+        pipe_trace: Optional[str] = None,
+        pipe_trace_format: str = "Konata",
+        mmio_trace: Optional[str] = None,
+        access_trace: Optional[str] = None,
+        access_trace_format: str = "Binary",
+        access_trace_sample: int = 1,
+        sample_occupancy: bool = True,
+        clock_mhz: int = 1000,
+        bandwidth_interval: int = 10_000,
+        hang_threshold: Optional[int] = None,
+        max_cycles: Optional[int] = None,
+        max_reboots: Optional[int] = None,
+        reboot_stats: str = "Accumulate",
+        semihosting: bool = False,
+        semihosting_root: Optional[str] = None,
+        syscall_emulation: bool = False,
+        syscall_root: Optional[str] = None,
+        args: Optional[Sequence[str]] = None,
+        fast_forward_insts: int = 0,
+        switch_pc: Optional[int] = None,
+        fast_forward_warm: bool = False,
         ram_base: int = 0x8000_0000,
         uart_base: int = 0x1000_0000,
         disk_base: int = 0x9000_0000,
+        disk_write_cache_sectors: int = 0,
+        disk_write_latency: int = 0,
+        disk_copy_on_write: bool = False,
+        net: Optional[str] = None,
+        net_base: int = 0x9000_1000,
+        rng_base: int = 0x9000_2000,
+        rng_seed: int = 0,
+        dma: bool = False,
+        dma_base: int = 0x9000_3000,
+        dma_bytes_per_cycle: int = 8,
         clint_base: int = 0x0200_0000,
         syscon_base: int = 0x0010_0000,
         kernel_offset: int = 0x0020_0000,
         bus_width: int = 8,
         bus_latency: int = 4,
         clint_divider: int = 10,
+        num_harts: int = 1,
         uart_to_stderr: bool = False,
         uart_quiet: bool = False,
+        devices: Optional[Sequence[Dict[str, Any]]] = None,
     ) -> None: ...
     def to_dict(self) -> Dict[str, Any]: ...
     def replace(self, **kwargs: Any) -> Config: ...
@@ -268,6 +404,8 @@ class Stats(dict):
     def __init__(self, data: Dict[str, Any]) -> None: ...
     def query(self, pattern: str) -> Stats: ...
     def compare(self, other: Stats) -> None: ...
+    def stall_breakdown(self) -> Dict[str, int]: ...
+    def cache_stats(self) -> Dict[str, Dict[str, float]]: ...
     @staticmethod
     def tabulate(rows: Dict[str, Stats], *, title: str = "") -> Table: ...
 
@@ -284,6 +422,18 @@ class Instruction:
     cycles: int
     def __init__(self, pc: int, raw: int, asm: str, cycles: int) -> None: ...
 
+class HangError(RuntimeError):
+    """The watchdog fired: the guest hung or ran past ``max_cycles``."""
+
+class RunStatus:
+    PcReached: RunStatus
+    Trap: RunStatus
+    Exited: RunStatus
+    CycleLimit: RunStatus
+    Halted: RunStatus
+    Condition: RunStatus
+    InstLimit: RunStatus
+
 class Cpu:
     def __init__(
         self,
@@ -299,6 +449,8 @@ class Cpu:
     @pc.setter
     def pc(self, value: int) -> None: ...
     @property
+    def boots(self) -> int: ...
+    @property
     def privilege(self) -> str: ...
     @property
     def trace(self) -> bool: ...
@@ -306,6 +458,17 @@ class Cpu:
     def trace(self, value: bool) -> None: ...
     @property
     def stats(self) -> Dict[str, Any]: ...
+    def stall_breakdown(self) -> Dict[str, int]: ...
+    def occupancy(self) -> Dict[str, List[int]]: ...
+    def mmio_accesses(self) -> Dict[str, Dict[str, int]]: ...
+    def cache_stats(self) -> Dict[str, Dict[str, float]]: ...
+    def bandwidth_timeline(self) -> List[Dict[str, float]]: ...
+    def recent_traps(self) -> List[Dict[str, Any]]: ...
+    def cache_access(self, addr: int, is_write: bool, level: str) -> tuple[bool, int]: ...
+    def cache_contains(self, addr: int, level: str) -> bool: ...
+    def inject_fault(self, addr: int, bit: int) -> None: ...
+    def reset(self) -> None: ...
+    def symbol(self, name: str) -> Optional[int]: ...
     @property
     def regs(self) -> Registers: ...
     @property
@@ -315,6 +478,20 @@ class Cpu:
     @property
     def mem64(self) -> Memory: ...
     @property
+    def net(self) -> Optional[VirtioNet]: ...
+    @property
+    def disk(self) -> Optional[VirtioBlock]: ...
+    @property
+    def uart(self) -> Optional[Uart]: ...
+    @property
+    def uarts(self) -> list[Uart]: ...
+    @property
+    def plic(self) -> Optional[Plic]: ...
+    @property
+    def clint(self) -> Optional[Clint]: ...
+    @property
+    def dma(self) -> Optional[Dma]: ...
+    @property
     def pc_trace(self) -> list[tuple[int, int]]: ...
     def step(self, max_cycles: int = 100_000) -> Optional[Instruction]: ...
     def run(
@@ -326,16 +503,35 @@ class Cpu:
     def sample(self, every: int, limit: Optional[int] = None) -> list[dict]: ...
     def run_until(
         self,
-        predicate: Any = None,
+        predicate: Optional[Callable[[Cpu], bool]] = None,
         *,
         pc: Optional[int] = None,
         privilege: Optional[str] = None,
         limit: Optional[int] = None,
+        stop_on_trap: bool = False,
         chunk: int = 10_000,
-    ) -> Optional[int]: ...
-    def tick(self) -> None: ...
+    ) -> RunStatus: ...
+    def run_for(self, cycles: int) -> RunStatus: ...
+    def run_functional(self, n: int) -> RunStatus: ...
+    def run_detailed(self, n: int) -> RunStatus: ...
+    @property
+    def exit_code(self) -> Optional[int]: ...
+    def tick(self) -> bool: ...
+    def set_step_callback(self, callback: Optional[Callable[[int], Any]]) -> None: ...
+    def add_device(
+        self, base: int, size: int, handlers: Any, irq: Optional[int] = None
+    ) -> CallbackDevice: ...
+    def set_mmio_trace(self, target: Union[str, Callable[..., Any], None]) -> None: ...
+    def read_reg(self, idx: int) -> int: ...
+    def write_reg(self, idx: int, value: int) -> None: ...
+    def read_freg(self, idx: int) -> int: ...
+    def write_freg(self, idx: int, value: int) -> None: ...
+    def read_csr(self, addr: int) -> int: ...
+    def write_csr(self, addr: int, value: int) -> None: ...
+    def disasm(self, addr: int) -> str: ...
     def pipeline_snapshot(self) -> PipelineSnapshot: ...
-    def save(self, path: str) -> None: ...
+    def checkpoint(self, path: str, microarch: bool = False) -> None: ...
+    def save(self, path: str, microarch: bool = False) -> None: ...
     def restore(self, path: str) -> None: ...
 
 class Registers:
@@ -347,6 +543,85 @@ class Csrs:
 
 class Memory:
     def __getitem__(self, addr: int) -> int: ...
+    def read(self, addr: int, len: int) -> bytes: ...
+    def write(self, addr: int, data: bytes) -> None: ...
+
+class VirtioNet:
+    @property
+    def mac(self) -> str: ...
+    @property
+    def rx_pending(self) -> int: ...
+    def inject(self, frame: bytes) -> None: ...
+    def take_tx(self) -> list[bytes]: ...
+
+class VirtioBlock:
+    @property
+    def copy_on_write(self) -> bool: ...
+    @property
+    def overlay_sectors(self) -> int: ...
+    @property
+    def dirty_sectors(self) -> int: ...
+    def read_sector(self, sector: int) -> bytes: ...
+    def write_sector(self, sector: int, data: bytes) -> None: ...
+    def export_overlay(self, path: str) -> None: ...
+
+class Uart:
+    @property
+    def index(self) -> int: ...
+    def write_input(self, data: bytes) -> None: ...
+    def read_output(self) -> bytes: ...
+    @property
+    def connected(self) -> bool: ...
+    def connect(self, other: Uart, latency: int = 0) -> None: ...
+    def disconnect(self) -> None: ...
+
+class Plic:
+    @property
+    def num_contexts(self) -> int: ...
+    @property
+    def pending(self) -> list[int]: ...
+    @property
+    def claimed(self) -> list[int]: ...
+    def enabled(self, context: int) -> list[int]: ...
+    def priority(self, source: int) -> int: ...
+    def threshold(self, context: int) -> int: ...
+
+class Clint:
+    @property
+    def num_harts(self) -> int: ...
+    @property
+    def mtime(self) -> int: ...
+    def mtimecmp(self, hart: int) -> int: ...
+    def set_mtimecmp(self, hart: int, value: int) -> None: ...
+    def msip(self, hart: int) -> bool: ...
+    def set_msip(self, hart: int, pending: bool) -> None: ...
+    def mip(self, hart: int) -> int: ...
+
+class Dma:
+    @property
+    def status(self) -> int: ...
+    @property
+    def busy(self) -> bool: ...
+    @property
+    def done(self) -> bool: ...
+    @property
+    def error(self) -> bool: ...
+    @property
+    def bytes_done(self) -> int: ...
+    @property
+    def transfer(self) -> tuple[int, int, int]: ...
+
+class CallbackDevice:
+    @property
+    def base(self) -> int: ...
+    @property
+    def size(self) -> int: ...
+    @property
+    def irq(self) -> Optional[int]: ...
+    @property
+    def irq_pending(self) -> bool: ...
+    def raise_irq(self) -> None: ...
+    def lower_irq(self) -> None: ...
 
 class Simulator:
     def __init__(self) -> None: ...
@@ -52,6 +52,24 @@ class Stats(dict):
 
         return Stats(matches)
 
+    def stall_breakdown(self) -> Dict[str, int]:
+        """Non-retiring cycles charged to each stall cause, keyed by cause name."""
+        return {k[len("stall_") :]: v for k, v in self.items() if k.startswith("stall_")}
+
+    def cache_stats(self) -> Dict[str, Dict[str, float]]:
+        """Hits, misses and hit rate per cache level (``l1i``, ``l1d``, ``l2``, ``l3``)."""
+        levels = {}
+        for level, prefix in (("l1i", "icache"), ("l1d", "dcache"), ("l2", "l2"), ("l3", "l3")):
+            hits = self.get(f"{prefix}_hits", 0)
+            misses = self.get(f"{prefix}_misses", 0)
+            total = hits + misses
+            levels[level] = {
+                "hits": hits,
+                "misses": misses,
+                "hit_rate": hits / total if total else 0.0,
+            }
+        return levels
+
     @staticmethod
     def tabulate(rows: Dict[str, Stats], *, title: str = "") -> Table:
         """Build a comparison table from labeled :class:`Stats` objects.
@@ -167,7 +185,12 @@ def _geometric_mean(values: Sequence[float]) -> float:
     return math.exp(sum(logs) / len(logs))
 
 
-_RATE_METRICS = {"ipc", "branch_accuracy_pct", "speculative_branch_accuracy_pct"}
+_RATE_METRICS = {
+    "ipc",
+    "branch_accuracy_pct",
+    "speculative_branch_accuracy_pct",
+    "mdp_accuracy_pct",
+}
 _COUNT_METRICS = {
     "cycles",
     "instructions_retired",
@@ -176,9 +199,31 @@ _COUNT_METRICS = {
     "stalls_data",
     "stalls_fu_structural",
     "stalls_backpressure",
+    "stall_icache_miss",
+    "stall_itlb_miss",
+    "stall_branch_recovery",
+    "stall_decode_starvation",
+    "stall_rob_full",
+    "stall_store_buffer_full",
+    "stall_scoreboard",
+    "stall_dcache_miss",
+    "stall_long_latency_fu",
+    "stall_backpressure",
+    "fetch_buffer_full_cycles",
+    "fetch_line_crossings",
+    "fetch_line_crossing_cycles",
+    "mmio_reads",
+    "mmio_writes",
+    "mmio_bytes",
+    "disk_cached_sectors",
+    "disk_write_through_sectors",
+    "disk_flushed_sectors",
+    "disk_flushes",
     "misprediction_penalty",
     "pipeline_flushes",
     "mem_ordering_violations",
+    "store_forwards",
+    "store_forward_stalls",
     "icache_hits",
     "icache_misses",
     "dcache_hits",
@@ -194,6 +239,11 @@ _COUNT_METRICS = {
     "speculative_branch_predictions",
     "speculative_branch_mispredictions",
     "traps_taken",
+    "interrupts_taken",
+    "interrupt_latency_cycles",
+    "interrupt_latency_max",
+    "syscalls",
+    "syscalls_unknown",
     "inst_load",
     "inst_store",
     "inst_branch",
@@ -333,16 +333,34 @@ class MemoryController:
             t_ras: int = 14,
             t_pre: int = 14,
             row_miss_latency: int = 120,
+            t_rcd: int = 14,
+            num_channels: int = 1,
+            num_banks: int = 8,
+            num_bank_groups: int = 1,
+            scheduler: str = "Immediate",
+            starvation_limit: int = 200,
+            write_drain_threshold: int = 8,
         ):
             self.t_cas = t_cas
             self.t_ras = t_ras
             self.t_pre = t_pre
             self.row_miss_latency = row_miss_latency
+            self.t_rcd = t_rcd
+            self.num_channels = num_channels
+            self.num_banks = num_banks
+            self.num_bank_groups = num_bank_groups
+            self.scheduler = scheduler
+            self.starvation_limit = starvation_limit
+            self.write_drain_threshold = write_drain_threshold
 
         def __repr__(self) -> str:
             return (
                 f"MemoryController.DRAM(t_cas={self.t_cas}, t_ras={self.t_ras}, "
-                f"t_pre={self.t_pre}, row_miss_latency={self.row_miss_latency})"
+                f"t_pre={self.t_pre}, row_miss_latency={self.row_miss_latency}, "
+                f"t_rcd={self.t_rcd}, num_channels={self.num_channels}, "
+                f"num_banks={self.num_banks}, num_bank_groups={self.num_bank_groups}, "
+                f"scheduler={self.scheduler!r}, starvation_limit={self.starvation_limit}, "
+                f"write_drain_threshold={self.write_drain_threshold})"
             )
 
 
@@ -350,7 +368,7 @@ class MemoryController:
 
 
 class Fu:
-    """Functional unit pool configuration for the O3 backend.
+    """Functional unit pool configuration, shared by both backends.
 
     Instantiate ``Fu`` with a list of unit descriptors (the inner classes).
     Any FU type omitted will be absent from the pool, so include every type
@@ -390,14 +408,24 @@ class Fu:
             return f"Fu.IntMul(count={self.count}, latency={self.latency})"
 
     class IntDiv:
-        """Integer divider: div, divu, rem, remu. Non-pipelined."""
+        """Integer divider: div, divu, rem, remu. Non-pipelined.
+
+        The divider is iterative: after a fixed setup it retires
+        ``bits_per_cycle`` quotient bits per cycle, so small quotients finish
+        early. ``latency`` is the worst case (a 64-bit quotient). Set
+        ``bits_per_cycle=0`` for a fixed latency.
+        """
 
-        def __init__(self, count: int = 1, latency: int = 35):
+        def __init__(self, count: int = 1, latency: int = 35, bits_per_cycle: int = 2):
             self.count = count
             self.latency = latency
+            self.bits_per_cycle = bits_per_cycle
 
         def __repr__(self) -> str:
-            return f"Fu.IntDiv(count={self.count}, latency={self.latency})"
+            return (
+                f"Fu.IntDiv(count={self.count}, latency={self.latency}, "
+                f"bits_per_cycle={self.bits_per_cycle})"
+            )
 
     class FpAdd:
         """FP adder: fadd, fsub, fmin, fmax, fcmp, fcvt."""
@@ -490,8 +518,15 @@ class Backend:
     """Namespace for pipeline backend configurations."""
 
     class InOrder:
+        def __init__(
+            self, fu_config=None, checkpoint_count: int = 0, bypass: str = "None"
+        ):
+            self.fu_config = fu_config if fu_config is not None else Fu()
+            self.checkpoint_count = checkpoint_count
+            self.bypass = bypass
+
         def __repr__(self) -> str:
-            return "Backend.InOrder()"
+            return f"Backend.InOrder(bypass={self.bypass!r})"
 
     class OutOfOrder:
         def __init__(
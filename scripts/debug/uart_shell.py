@@ -0,0 +1,94 @@
+#!/usr/bin/env python3
+"""
+Boot Linux, log in on the serial console and run ``ls``, driving the UART
+entirely from Python through ``cpu.uart``.
+
+Uses the artifacts built by scripts/setup/boot_linux.py. Exits non-zero if
+the shell never echoes the command back.
+
+Usage:
+    rvsim scripts/debug/uart_shell.py
+    rvsim scripts/debug/uart_shell.py --command "cat /proc/cpuinfo"
+"""
+
+import argparse
+import os
+import sys
+
+from rvsim import Config, Simulator
+
+CHUNK = 5_000_000
+
+
+def repo_root():
+    return os.path.dirname(os.path.dirname(os.path.dirname(os.path.abspath(__file__))))
+
+
+class Console:
+    """Accumulates guest output and waits for expected text."""
+
+    def __init__(self, cpu, budget):
+        self.cpu = cpu
+        self.budget = budget
+        self.buf = b""
+
+    def expect(self, needle: bytes) -> bool:
+        while needle not in self.buf:
+            if self.budget <= 0:
+                return False
+            self.cpu.run_for(CHUNK)
+            self.budget -= CHUNK
+            out = self.cpu.uart.read_output()
+            sys.stdout.write(out.decode(errors="replace"))
+            sys.stdout.flush()
+            self.buf += out
+        # Keep only what follows the match so the next expect starts fresh.
+        self.buf = self.buf.split(needle, 1)[1]
+        return True
+
+    def send(self, data: bytes) -> None:
+        self.cpu.uart.write_input(data)
+
+
+def main() -> int:
+    ap = argparse.ArgumentParser(description=__doc__.strip().splitlines()[0])
+    ap.add_argument("--command", default="ls", help="shell command to run")
+    ap.add_argument(
+        "--max-cycles", type=int, default=2_000_000_000, help="total cycle budget"
+    )
+    args = ap.parse_args()
+
+    out_dir = os.path.join(repo_root(), "software", "linux", "output")
+    image = os.path.join(out_dir, "Image")
+    disk = os.path.join(out_dir, "disk.img")
+    if not os.path.isfile(image):
+        print("Error: Image not found at", image, "(run scripts/setup/boot_linux.py)")
+        return 1
+
+    sim = Simulator().config(Config(uart_quiet=True)).kernel(image)
+    if os.path.isfile(disk):
+        sim = sim.disk(disk)
+    console = Console(sim.build(), args.max_cycles)
+
+    if not console.expect(b"login: "):
+        print("\n[uart_shell] no login prompt")
+        return 1
+    console.send(b"root\n")
+    if not console.expect(b"# "):
+        print("\n[uart_shell] no shell prompt")
+        return 1
+
+    command = args.command.encode()
+    console.send(command + b"\n")
+    if not console.expect(command + b"\r\n"):
+        print("\n[uart_shell] command was not echoed")
+        return 1
+    if not console.expect(b"# "):
+        print("\n[uart_shell] command did not finish")
+        return 1
+    print(f"\n[uart_shell] '{args.command}' echoed and completed")
+    return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())